@@ -0,0 +1,390 @@
+//! Runner settings that can change while agents keep running.
+//!
+//! `--config <PATH>` names a JSON file of rate limits, token budgets, payload
+//! capture and per-agent tool allowlists. Agents are held to it from boot, and
+//! the runner re-reads it on SIGHUP or an `admin.reloadConfig` request. Each
+//! reload is validated in full before any of it is applied, so a bad edit
+//! leaves the running version in place. Every applied change bumps the version
+//! and is recorded as a `ConfigChanged` provenance event.
+//!
+//! ```json
+//! {
+//!   "max_payload_chars": 2048,
+//!   "rate_limits": {
+//!     "models": { "gpt-4o": { "per_minute": 60, "burst": 5 } },
+//!     "default_tool": { "per_second": 10 },
+//!     "agent": { "per_minute": 600 }
+//!   },
+//!   "budgets": { "task": { "total_tokens": 50000 } },
+//!   "tool_allowlists": { "voidship": ["search"] }
+//! }
+//! ```
+//!
+//! Agents missing from `tool_allowlists` may call every tool their manifest
+//! declares; an allowlist can only narrow that set.
+
+use baml_rt_core::canonical::digest_hex;
+use baml_rt_core::{BamlRtError, Result, context};
+use baml_rt_interceptor::{
+    BudgetLimit, BudgetObserver, PayloadCapture, RateLimit, RateLimitInterceptor, Reloadable,
+    UsageBudget,
+};
+use baml_rt_provenance::{ProvEvent, ProvenanceWriter};
+use baml_rt_quickjs::BamlRuntimeManager;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::info;
+
+/// JSON-RPC methods that reload the runner config rather than reach an agent.
+pub(crate) const RELOAD_METHODS: [&str; 2] = ["admin.reloadConfig", "admin/reloadConfig"];
+
+/// One token bucket: exactly one of `per_second` and `per_minute`, plus an
+/// optional burst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RateSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    per_second: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    per_minute: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    burst: Option<u32>,
+}
+
+impl RateSpec {
+    fn limit(&self, key: &str) -> Result<RateLimit> {
+        let limit = match (self.per_second, self.per_minute) {
+            (Some(calls), None) if calls > 0 => RateLimit::per_second(calls),
+            (None, Some(calls)) if calls > 0 => RateLimit::per_minute(calls),
+            (Some(_), None) | (None, Some(_)) => {
+                return Err(invalid(key, "rate must be at least one call"));
+            }
+            _ => return Err(invalid(key, "set exactly one of per_second and per_minute")),
+        };
+        Ok(match self.burst {
+            Some(burst) => limit.with_burst(burst),
+            None => limit,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RateLimitSpecs {
+    models: BTreeMap<String, RateSpec>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    default_model: Option<RateSpec>,
+    tools: BTreeMap<String, RateSpec>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    default_tool: Option<RateSpec>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    agent: Option<RateSpec>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct BudgetSpec {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prompt_tokens: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    completion_tokens: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total_tokens: Option<u64>,
+}
+
+impl BudgetSpec {
+    fn limit(&self) -> BudgetLimit {
+        BudgetLimit {
+            prompt_tokens: self.prompt_tokens,
+            completion_tokens: self.completion_tokens,
+            total_tokens: self.total_tokens,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct BudgetSpecs {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    task: Option<BudgetSpec>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    context: Option<BudgetSpec>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    agent: Option<BudgetSpec>,
+}
+
+/// The reloadable part of the runner config, as read from `--config`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct DynamicConfig {
+    /// Overrides the profile's payload limit; unset restores it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_payload_chars: Option<usize>,
+    rate_limits: RateLimitSpecs,
+    budgets: BudgetSpecs,
+    tool_allowlists: BTreeMap<String, Vec<String>>,
+}
+
+impl DynamicConfig {
+    fn read(path: &Path) -> Result<Self> {
+        let raw = std::fs::read_to_string(path).map_err(|err| {
+            BamlRtError::InvalidArgument(format!("failed to read {}: {err}", path.display()))
+        })?;
+        serde_json::from_str(&raw).map_err(|err| {
+            BamlRtError::InvalidArgument(format!("invalid config {}: {err}", path.display()))
+        })
+    }
+
+    fn rate_limiter(&self) -> Result<RateLimitInterceptor> {
+        let specs = &self.rate_limits;
+        let mut limiter = RateLimitInterceptor::new();
+        for (model, spec) in &specs.models {
+            limiter = limiter.with_model_limit(model, spec.limit(&format!("rate_limits.models.{model}"))?);
+        }
+        if let Some(spec) = &specs.default_model {
+            limiter = limiter.with_default_model_limit(spec.limit("rate_limits.default_model")?);
+        }
+        for (tool, spec) in &specs.tools {
+            limiter = limiter.with_tool_limit(tool, spec.limit(&format!("rate_limits.tools.{tool}"))?);
+        }
+        if let Some(spec) = &specs.default_tool {
+            limiter = limiter.with_default_tool_limit(spec.limit("rate_limits.default_tool")?);
+        }
+        if let Some(spec) = &specs.agent {
+            limiter = limiter.with_agent_limit(spec.limit("rate_limits.agent")?);
+        }
+        Ok(limiter)
+    }
+
+    fn budget(&self) -> UsageBudget {
+        let specs = &self.budgets;
+        let mut budget = UsageBudget::new();
+        if let Some(spec) = &specs.task {
+            budget = budget.with_task_limit(spec.limit());
+        }
+        if let Some(spec) = &specs.context {
+            budget = budget.with_context_limit(spec.limit());
+        }
+        if let Some(spec) = &specs.agent {
+            budget = budget.with_agent_limit(spec.limit());
+        }
+        budget
+    }
+
+    fn to_value(&self) -> Value {
+        serde_json::to_value(self).unwrap_or(Value::Null)
+    }
+
+    /// Top-level keys whose values differ from `previous`.
+    fn changed_keys(&self, previous: &Self) -> Vec<String> {
+        let (current, previous) = (self.to_value(), previous.to_value());
+        let keys: BTreeSet<&String> = [&current, &previous]
+            .into_iter()
+            .filter_map(Value::as_object)
+            .flat_map(|object| object.keys())
+            .collect();
+        keys.into_iter()
+            .filter(|key| current.get(key.as_str()) != previous.get(key.as_str()))
+            .cloned()
+            .collect()
+    }
+}
+
+fn invalid(key: &str, reason: &str) -> BamlRtError {
+    BamlRtError::InvalidArgument(format!("config key {key}: {reason}"))
+}
+
+/// What a reload applied.
+#[derive(Debug, Clone)]
+pub(crate) struct ReloadOutcome {
+    pub(crate) version: u64,
+    pub(crate) digest: String,
+    /// Empty when the file matched the running version and nothing was applied.
+    pub(crate) changed: Vec<String>,
+}
+
+impl ReloadOutcome {
+    pub(crate) fn to_json(&self) -> Value {
+        json!({
+            "version": self.version,
+            "digest": self.digest,
+            "changed": self.changed,
+        })
+    }
+}
+
+struct AppliedConfig {
+    version: u64,
+    digest: String,
+    config: DynamicConfig,
+}
+
+/// An agent whose tool allowlist follows the config.
+struct ReloadTarget {
+    name: String,
+    runtime: Arc<Mutex<BamlRuntimeManager>>,
+    manifest_tools: HashSet<String>,
+}
+
+/// Applies `--config` to every loaded agent, at startup and on each reload.
+pub(crate) struct ConfigReloader {
+    path: PathBuf,
+    /// Payload limit from the provenance profile and flags, restored when the
+    /// config leaves `max_payload_chars` unset.
+    base_max_payload_chars: usize,
+    rate_limiter: Reloadable<RateLimitInterceptor>,
+    budget: Reloadable<UsageBudget>,
+    budget_observer: Option<Arc<dyn BudgetObserver>>,
+    provenance_writer: Option<Arc<dyn ProvenanceWriter>>,
+    agents: std::sync::Mutex<Vec<ReloadTarget>>,
+    /// Also serializes reloads, so two triggers cannot interleave their changes.
+    applied: Mutex<Option<AppliedConfig>>,
+}
+
+impl ConfigReloader {
+    /// Read the config at `path` and build its limits, so agents registered
+    /// before the first [`ConfigReloader::reload`] are already held to them.
+    pub(crate) fn open(
+        path: PathBuf,
+        base_max_payload_chars: usize,
+        provenance_writer: Option<Arc<dyn ProvenanceWriter>>,
+        budget_observer: Option<Arc<dyn BudgetObserver>>,
+    ) -> Result<Self> {
+        let config = DynamicConfig::read(&path)?;
+        let rate_limiter = config.rate_limiter()?;
+        let mut budget = config.budget();
+        if let Some(observer) = &budget_observer {
+            budget = budget.with_observer(observer.clone());
+        }
+        Ok(Self {
+            path,
+            base_max_payload_chars,
+            rate_limiter: Reloadable::new(rate_limiter),
+            budget: Reloadable::new(budget),
+            budget_observer,
+            provenance_writer,
+            agents: std::sync::Mutex::new(Vec::new()),
+            applied: Mutex::new(None),
+        })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Enforce the configured limits on `manager`'s calls.
+    pub(crate) async fn register(&self, manager: &BamlRuntimeManager) {
+        manager.register_llm_interceptor(self.rate_limiter.clone()).await;
+        manager.register_tool_interceptor(self.rate_limiter.clone()).await;
+        manager.register_llm_interceptor(self.budget.clone()).await;
+    }
+
+    /// Let the config narrow `name`'s tools to a subset of `manifest_tools`.
+    pub(crate) fn add_agent(
+        &self,
+        name: impl Into<String>,
+        runtime: Arc<Mutex<BamlRuntimeManager>>,
+        manifest_tools: HashSet<String>,
+    ) {
+        self.agents.lock().unwrap().push(ReloadTarget {
+            name: name.into(),
+            runtime,
+            manifest_tools,
+        });
+    }
+
+    /// Re-read the config file and apply it if it changed.
+    ///
+    /// `trigger` names what asked for the reload in the `ConfigChanged` event,
+    /// e.g. `"startup"`, `"sighup"` or `"admin"`.
+    pub(crate) async fn reload(&self, trigger: &str) -> Result<ReloadOutcome> {
+        let mut applied = self.applied.lock().await;
+        let config = DynamicConfig::read(&self.path)?;
+        let rate_limiter = config.rate_limiter()?;
+        let allowlists = self.allowlists(&config)?;
+        let digest = digest_hex(&config.to_value());
+        if let Some(current) = applied.as_ref()
+            && current.digest == digest
+        {
+            return Ok(ReloadOutcome { version: current.version, digest, changed: Vec::new() });
+        }
+
+        let changed = match applied.as_ref() {
+            Some(current) => config.changed_keys(&current.config),
+            None => config.changed_keys(&DynamicConfig::default()),
+        };
+        for (runtime, tools) in allowlists {
+            runtime.lock().await.set_tool_allowlist(tools).await?;
+        }
+        self.rate_limiter.replace(rate_limiter.sharing_buckets_with(&self.rate_limiter.current()));
+        let mut budget = config.budget().sharing_ledger_with(&self.budget.current());
+        if let Some(observer) = &self.budget_observer {
+            budget = budget.with_observer(observer.clone());
+        }
+        self.budget.replace(budget);
+        PayloadCapture::global().set_max_payload_chars(
+            config.max_payload_chars.unwrap_or(self.base_max_payload_chars),
+        );
+
+        let previous_digest = applied.as_ref().map(|current| current.digest.clone());
+        let version = applied.as_ref().map_or(1, |current| current.version + 1);
+        info!(
+            version,
+            digest = %digest,
+            changed = ?changed,
+            trigger,
+            path = %self.path.display(),
+            "Runner config applied"
+        );
+        if let Some(writer) = &self.provenance_writer {
+            let event = ProvEvent::config_changed(
+                context::generate_context_id(),
+                version,
+                digest.clone(),
+                previous_digest,
+                changed.clone(),
+                trigger.to_string(),
+            );
+            writer.add_event_with_logging(event, "config change").await;
+        }
+        *applied = Some(AppliedConfig { version, digest: digest.clone(), config });
+        Ok(ReloadOutcome { version, digest, changed })
+    }
+
+    /// The tools each agent may call under `config`, checked against its manifest.
+    fn allowlists(
+        &self,
+        config: &DynamicConfig,
+    ) -> Result<Vec<(Arc<Mutex<BamlRuntimeManager>>, HashSet<String>)>> {
+        let agents = self.agents.lock().unwrap();
+        if let Some(unknown) =
+            config.tool_allowlists.keys().find(|name| !agents.iter().any(|agent| &agent.name == *name))
+        {
+            return Err(invalid(
+                &format!("tool_allowlists.{unknown}"),
+                "no agent with this name is loaded",
+            ));
+        }
+        agents
+            .iter()
+            .map(|agent| {
+                let Some(tools) = config.tool_allowlists.get(&agent.name) else {
+                    return Ok((agent.runtime.clone(), agent.manifest_tools.clone()));
+                };
+                if let Some(undeclared) =
+                    tools.iter().find(|tool| !agent.manifest_tools.contains(*tool))
+                {
+                    return Err(invalid(
+                        &format!("tool_allowlists.{}", agent.name),
+                        &format!("tool '{undeclared}' is not declared in the agent manifest"),
+                    ));
+                }
+                Ok((agent.runtime.clone(), tools.iter().cloned().collect()))
+            })
+            .collect()
+    }
+}
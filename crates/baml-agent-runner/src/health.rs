@@ -0,0 +1,140 @@
+//! `runtime.health`: the runner's state, for orchestrators' health checks.
+//!
+//! The runner answers it itself, also while draining, and the HTTP transport
+//! serves it as `GET /health` and `GET /ready`. Per agent the report says
+//! whether its BAML schema is loaded, whether its entry point evaluated, whether
+//! every allowlisted tool is registered, and how much QuickJS heap it uses.
+//! Checks that need a runtime busy for longer than [`PROBE_TIMEOUT`] are
+//! reported as unknown (`null`) instead of holding up the report.
+//!
+//! The runner is healthy when no agent fails those checks and the last
+//! background check of the provenance store did not fail. It is ready when it
+//! is healthy, has agents loaded and is not draining.
+
+use crate::{AgentRunner, BootedAgent};
+use baml_rt_provenance::HealthStatus;
+use baml_rt_quickjs::HeapStats;
+use serde::Serialize;
+use std::time::Duration;
+
+/// How long a report waits for an agent's BAML or JS runtime before reporting
+/// the checks that need it as unknown.
+const PROBE_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ProvenanceStatus {
+    /// The runner was started without a provenance store.
+    Disabled,
+    /// No background check has completed yet.
+    Unknown,
+    Healthy,
+    Unhealthy,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct ProvenanceHealth {
+    status: ProvenanceStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct AgentHealth {
+    agent: String,
+    version: String,
+    healthy: bool,
+    /// `None` when the agent's BAML runtime stayed busy for all of [`PROBE_TIMEOUT`].
+    schema_loaded: Option<bool>,
+    js_evaluated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    js_error: Option<String>,
+    /// `None` when the agent's BAML runtime stayed busy for all of [`PROBE_TIMEOUT`].
+    tools_validated: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools_error: Option<String>,
+    /// `None` when the agent's JS stayed busy for all of [`PROBE_TIMEOUT`].
+    heap: Option<HeapStats>,
+}
+
+/// What `runtime.health` answers with.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct HealthReport {
+    pub(crate) healthy: bool,
+    pub(crate) ready: bool,
+    draining: bool,
+    in_flight: usize,
+    provenance: ProvenanceHealth,
+    agents: Vec<AgentHealth>,
+}
+
+pub(crate) async fn report(runner: &AgentRunner) -> HealthReport {
+    let mut names: Vec<&String> = runner.agents.keys().collect();
+    names.sort();
+    let mut agents = Vec::with_capacity(names.len());
+    for name in names {
+        agents.push(check_agent(name, &runner.agents[name]).await);
+    }
+
+    let provenance = match &runner.provenance_health {
+        None => ProvenanceHealth { status: ProvenanceStatus::Disabled, detail: None },
+        Some(monitor) => match monitor.status().await {
+            HealthStatus::Unknown => {
+                ProvenanceHealth { status: ProvenanceStatus::Unknown, detail: None }
+            }
+            HealthStatus::Healthy => {
+                ProvenanceHealth { status: ProvenanceStatus::Healthy, detail: None }
+            }
+            HealthStatus::Unhealthy(reason) => {
+                ProvenanceHealth { status: ProvenanceStatus::Unhealthy, detail: Some(reason) }
+            }
+        },
+    };
+
+    let draining = runner.shutdown_controller.is_draining();
+    let healthy = provenance.status != ProvenanceStatus::Unhealthy
+        && agents.iter().all(|agent| agent.healthy);
+    HealthReport {
+        healthy,
+        ready: healthy && !draining && !agents.is_empty(),
+        draining,
+        in_flight: runner.shutdown_controller.in_flight(),
+        provenance,
+        agents,
+    }
+}
+
+async fn check_agent(name: &str, booted: &BootedAgent) -> AgentHealth {
+    let runtime = booted.agent.runtime();
+    let probed = tokio::time::timeout(PROBE_TIMEOUT, async {
+        let manager = runtime.lock().await;
+        let tools = manager.validate_tool_allowlist_registered().await;
+        (manager.is_schema_loaded(), tools.err().map(|err| err.to_string()))
+    })
+    .await;
+    let (schema_loaded, tools_validated, tools_error) = match probed {
+        Ok((schema_loaded, tools_error)) => {
+            (Some(schema_loaded), Some(tools_error.is_none()), tools_error)
+        }
+        Err(_) => (None, None, None),
+    };
+    let bridge = booted.agent.bridge();
+    let heap = tokio::time::timeout(PROBE_TIMEOUT, bridge.lock())
+        .await
+        .ok()
+        .map(|bridge| bridge.heap_stats());
+
+    let js_evaluated = booted.entry_point_error.is_none();
+    AgentHealth {
+        agent: name.to_string(),
+        version: booted.manifest.version.clone(),
+        // A check that could not run for a busy runtime does not fail the agent.
+        healthy: schema_loaded != Some(false) && js_evaluated && tools_validated != Some(false),
+        schema_loaded,
+        js_evaluated,
+        js_error: booted.entry_point_error.clone(),
+        tools_validated,
+        tools_error,
+        heap,
+    }
+}
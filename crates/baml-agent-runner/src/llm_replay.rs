@@ -0,0 +1,125 @@
+//! `--replay-llm-calls`: re-run recorded LLM calls against the loaded agents.
+//!
+//! Reads a provenance event log (every segment of a rotated JSONL log, or one
+//! exported event file), invokes each recorded BAML function again with its
+//! recorded arguments on the agent that defines it, optionally against another
+//! client, and prints which outputs changed. The exit status says whether any
+//! replay failed or answered differently, so a prompt change can be checked in
+//! CI against yesterday's traffic.
+//!
+//! With `--replay-tenant`, the events are read through an
+//! [`AccessControlledReader`] on the tenant's behalf, so only calls recorded in
+//! that tenant's contexts are replayed.
+//!
+//! Each replay runs in a fresh context under its own message id, so its own
+//! provenance is recorded apart from the calls it replays.
+
+use crate::AgentRunner;
+use anyhow::Context;
+use async_trait::async_trait;
+use baml_rt_core::context;
+use baml_rt_core::ids::{AgentId, ExternalId, MessageId};
+use baml_rt_core::{BamlRtError, Result};
+use baml_rt_provenance::jsonl_store::log_segments;
+use baml_rt_provenance::{
+    AccessControlledReader, ContextOwnership, InMemoryProvenanceStore, LlmReplayOptions,
+    LlmReplayReport, LlmReplayTarget, ProvEvent, ProvenanceWriter, decode_event_log,
+    replay_llm_calls,
+};
+use baml_rt_quickjs::BamlRuntimeManager;
+use serde_json::Value;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// The BAML runtimes of every booted agent.
+struct AgentRuntimes {
+    runtimes: Vec<(AgentId, Arc<Mutex<BamlRuntimeManager>>)>,
+}
+
+#[async_trait]
+impl LlmReplayTarget for AgentRuntimes {
+    async fn invoke(&self, function_name: &str, args: Value, client: Option<&str>) -> Result<Value> {
+        for (agent_id, runtime) in &self.runtimes {
+            if runtime.lock().await.get_function_signature(function_name).is_none() {
+                continue;
+            }
+            let message_id = MessageId::from_external(ExternalId::new(format!(
+                "replay-{}",
+                uuid::Uuid::new_v4()
+            )));
+            let runtime = runtime.clone();
+            return context::with_agent_id(agent_id.clone(), async move {
+                context::with_context_id(context::generate_context_id(), async move {
+                    context::with_message_id(message_id, async move {
+                        runtime
+                            .lock()
+                            .await
+                            .invoke_function_with_client(function_name, args, client)
+                            .await
+                    })
+                    .await?
+                })
+                .await?
+            })
+            .await;
+        }
+        Err(BamlRtError::FunctionNotFound(function_name.to_string()))
+    }
+}
+
+/// Every event recorded at `path`: the segments of a JSONL log, oldest first,
+/// or the single file if it was not written by the JSONL store.
+async fn load_events(path: &Path) -> anyhow::Result<Vec<ProvEvent>> {
+    let segments =
+        log_segments(path).with_context(|| format!("Failed to list {}", path.display()))?;
+    if segments.is_empty() {
+        anyhow::bail!("No provenance log at {}", path.display());
+    }
+    let mut events = Vec::new();
+    for segment in segments {
+        let bytes = tokio::fs::read(&segment)
+            .await
+            .with_context(|| format!("Failed to read {}", segment.display()))?;
+        events.extend(
+            decode_event_log(&bytes)
+                .with_context(|| format!("Failed to decode {}", segment.display()))?,
+        );
+    }
+    Ok(events)
+}
+
+/// The events of `events` `tenant` may read: those recorded in its contexts.
+pub(crate) async fn tenant_events(
+    events: Vec<ProvEvent>,
+    tenant: &str,
+) -> anyhow::Result<Vec<ProvEvent>> {
+    let store = Arc::new(InMemoryProvenanceStore::new());
+    for event in &events {
+        store.add_event(event.clone()).await.context("Invalid provenance event")?;
+    }
+    let ownership = Arc::new(ContextOwnership::new());
+    ownership.grant_recorded_tenants(&events);
+    let reader = AccessControlledReader::new(store, ownership);
+    Ok(reader.for_principal(tenant).visible_events(events))
+}
+
+pub(crate) async fn run(
+    runner: &AgentRunner,
+    path: &Path,
+    options: &LlmReplayOptions,
+    tenant: Option<&str>,
+) -> anyhow::Result<LlmReplayReport> {
+    let mut events = load_events(path).await?;
+    if let Some(tenant) = tenant {
+        events = tenant_events(events, tenant).await?;
+    }
+    let target = AgentRuntimes {
+        runtimes: runner
+            .agents
+            .values()
+            .map(|booted| (booted.agent.agent_id().clone(), booted.agent.runtime()))
+            .collect(),
+    };
+    Ok(replay_llm_calls(&events, &target, options).await)
+}
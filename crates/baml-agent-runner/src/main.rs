@@ -4,39 +4,90 @@
 //! Each agent package is a tar.gz containing BAML schemas, compiled TypeScript,
 //! and metadata.
 
-use baml_rt_a2a::{A2aAgent, A2aRequestHandler, a2a};
+mod dynamic_config;
+mod health;
+mod llm_replay;
+mod mcp_server;
+mod package_verify;
+mod self_test;
+mod shutdown;
+
+use baml_rt_a2a::{
+    A2aAgent, A2aHttpServer, A2aRequestHandler, A2aWebSocketServer, AgentDirectory,
+    TaskTimeoutConfig, WebhookConfig, DELEGATE_TOOL, HEALTH_METHOD, a2a, response,
+};
+use baml_rt_a2a::a2a_store::TaskUpdateEvent;
+use baml_rt_a2a::response::ResponseFormatter;
+use baml_rt_a2a::chunk_stream::ChunkStream;
 use baml_rt_a2a::a2a_types::{
     JSONRPCId, JSONRPCRequest, Message, MessageRole, Part, SendMessageConfiguration,
-    SendMessageRequest, ROLE_USER,
+    SendMessageRequest, DEFAULT_MAX_MESSAGE_BYTES, ROLE_USER,
 };
 use baml_rt_core::ids::{AgentId, DerivedId, ExternalId, TaskId};
 use baml_rt_a2a::a2a_types::A2aMessageId;
-use baml_rt_core::{BamlRtError, ContextId, Result};
+use baml_rt_core::{BamlRtError, ContextId, ErrorContext, Result, ResultExt};
 use baml_rt_core::context;
-use baml_rt_provenance::{AgentType, ProvEvent, ToolIndexConfig, index_tools};
-use baml_rt_observability::{spans, tracing_setup};
+use baml_rt_core::manifest::{AgentManifest, BundleRequirement};
+use baml_rt_interceptor::{
+    ApprovalInterceptor, BudgetObserver, OperatorCredentials, PayloadCapture, PendingApprovalQueue,
+    PolicyInterceptor,
+};
+use baml_rt_provenance::{
+    AgentType, FalkorDbToolIndexer, FileToolIndexer, LlmReplayOptions, NoopToolIndexer, ProvEvent,
+    ToolIndexConfig, ToolIndexSource, ToolIndexer, detect_drift,
+};
+use baml_rt_observability::{spans, tracing_setup, PrometheusExporter};
 use baml_rt_provenance::{
-    FalkorDbProvenanceConfig, FalkorDbProvenanceWriter, InMemoryProvenanceStore, ProvenanceWriter,
+    CompositeProvenanceWriter, FailureMode, FalkorDbProvenanceConfig, FalkorDbProvenanceWriter,
+    InMemoryProvenanceStore, JsonlProvenanceConfig, JsonlProvenanceWriter,
+    ProvenanceHealthMonitor, ProvenanceProfile, ProvenanceSettings, ProvenanceSnapshotter,
+    ProvenanceWriter, REDACTION_KEY_ENV, RedactionKey, RedactionPolicy, SnapshotConfig,
+    SqliteProvenanceConfig, SqliteProvenanceWriter, ToolUsageAggregator, ToolUsageReporter,
+    wait_until_healthy,
 };
-use baml_rt_quickjs::BamlRuntimeManager;
+use baml_rt_provenance::ProvenanceInterceptor;
+use baml_rt_quickjs::{BamlRuntimeManager, SchemaReload, WorkTracker};
+use baml_rt_tools::{FsBundle, FsSandbox};
+#[cfg(feature = "profiling")]
+use baml_rt_tools::ToolProfiler;
+use dynamic_config::{ConfigReloader, RELOAD_METHODS};
+use mcp_server::McpToolServer;
+use package_verify::{TrustStore, Verification, VerifyPolicy, verify_package};
+use self_test::SmokeInvocation;
+use shutdown::{ShutdownController, spawn_signal_handler};
 use anyhow::Context;
-use clap::{Parser, ValueEnum};
+use async_trait::async_trait;
+use clap::{ArgAction, Parser, ValueEnum};
+use futures_util::stream::{self, LocalBoxStream, StreamExt};
 use serde_json::Value;
 use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
-use tokio::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::{broadcast, Mutex};
 use tracing::{error, info, warn};
 
-/// Agent package metadata
-#[derive(Debug, Clone)]
-struct AgentManifest {
-    version: String,
-    name: String,
-    entry_point: String,
-    signature: String,
+/// How long shutdown waits for in-flight requests unless `--shutdown-timeout-secs` says otherwise.
+const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Env var holding the HMAC key push notifications are signed with.
+const PUSH_SIGNING_SECRET_ENV: &str = "BAML_PUSH_SIGNING_SECRET";
+
+/// JSON-RPC methods answered with the runner's [`health::HealthReport`].
+const HEALTH_METHODS: [&str; 2] = [HEALTH_METHOD, "runtime/health"];
+
+/// JSON-RPC methods that reload an agent's BAML schema rather than reach it.
+const SCHEMA_RELOAD_METHODS: [&str; 2] = ["admin.reloadSchema", "admin/reloadSchema"];
+
+/// Tools whose calls wait for an operator, shared by every booted agent.
+#[derive(Clone)]
+struct ApprovalSettings {
+    queue: PendingApprovalQueue,
+    /// Tool names, or `bundle/*`.
     tools: Vec<String>,
+    timeout: Duration,
 }
 
 /// Inert agent package - just holds package data
@@ -46,23 +97,25 @@ struct AgentPackage {
     entry_point: String,
     signature: String,
     tools: Vec<String>,
+    required_bundles: Vec<BundleRequirement>,
+    task_timeout: Option<Duration>,
+    max_concurrent_tasks: Option<usize>,
     extract_dir: PathBuf,
     baml_src: PathBuf,
+    /// Served from `agent/getCard`.
+    manifest: AgentManifest,
 }
 
 impl AgentPackage {
     /// Load an agent package from a tar.gz file (inert - does not boot the agent)
-    async fn load_from_file(package_path: &Path) -> Result<Self> {
+    async fn load_from_file(package_path: &Path, policy: &VerifyPolicy) -> Result<Self> {
         let span = spans::load_agent_package(package_path);
         let _guard = span.enter();
 
-        // Create temporary extraction directory
-        let epoch_secs = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)?
-            .as_secs();
-        let extract_dir = std::env::temp_dir().join(format!("baml-agent-{}", epoch_secs));
-        std::fs::create_dir_all(&extract_dir)
-            .map_err(BamlRtError::Io)?;
+        // A fresh directory per package, so packages never share or reuse one.
+        let extract_dir =
+            std::env::temp_dir().join(format!("baml-agent-{}", uuid::Uuid::new_v4()));
+        create_private_dir(&extract_dir).map_err(BamlRtError::Io)?;
 
         {
             let extract_span = spans::extract_package(&extract_dir);
@@ -79,57 +132,14 @@ impl AgentPackage {
                 .map_err(BamlRtError::Io)?;
         }
 
-        // Load manifest
-        let manifest_path = extract_dir.join("manifest.json");
-        let manifest_content = std::fs::read_to_string(&manifest_path)
-            .map_err(BamlRtError::Io)?;
-        let manifest_json: Value = serde_json::from_str(&manifest_content)
-            .map_err(BamlRtError::Json)?;
-
-        let tools = manifest_json
-            .get("tools")
-            .and_then(|v| v.as_array())
-            .ok_or_else(|| BamlRtError::InvalidArgument(
-                "manifest.json missing 'tools' field".to_string()
-            ))?
-            .iter()
-            .filter_map(|v| v.as_str().map(|s| s.to_string()))
-            .collect::<Vec<String>>();
-
-        let manifest = AgentManifest {
-            version: manifest_json
-                .get("version")
-                .and_then(|v| v.as_str())
-                .ok_or_else(|| BamlRtError::InvalidArgument(
-                    "manifest.json missing 'version' field".to_string()
-                ))?
-                .to_string(),
-            name: manifest_json
-                .get("name")
-                .and_then(|v| v.as_str())
-                .ok_or_else(|| BamlRtError::InvalidArgument(
-                    "manifest.json missing 'name' field".to_string()
-                ))?
-                .to_string(),
-            entry_point: manifest_json
-                .get("entry_point")
-                .and_then(|v| v.as_str())
-                .unwrap_or("dist/index.js")
-                .to_string(),
-            signature: manifest_json
-                .get("signature")
-                .and_then(|v| v.as_str())
-                .ok_or_else(|| BamlRtError::InvalidArgument(
-                    "manifest.json missing 'signature' field".to_string()
-                ))?
-                .to_string(),
-            tools,
-        };
+        let manifest = AgentManifest::load(&extract_dir)?;
+        let signature = manifest.require_signature()?.to_string();
 
         info!(
             name = manifest.name,
             version = manifest.version,
             entry_point = manifest.entry_point,
+            manifest_version = manifest.manifest_version,
             "Agent manifest loaded"
         );
 
@@ -141,14 +151,33 @@ impl AgentPackage {
             ));
         }
 
+        match verify_package(&extract_dir, &manifest, policy)? {
+            Verification::Signed { key_id } => {
+                info!(name = manifest.name, key_id, "Package signature verified");
+            }
+            Verification::DigestOnly => {
+                info!(name = manifest.name, "Package digest verified; package is not signed");
+            }
+            Verification::Unverified { key_id } => warn!(
+                name = manifest.name,
+                key_id,
+                "Package is signed but no trust store is configured; signature not checked"
+            ),
+            Verification::Unsigned => info!(name = manifest.name, "Package is unsigned"),
+        }
+
         Ok(Self {
-            name: manifest.name,
-            version: manifest.version,
-            entry_point: manifest.entry_point,
-            signature: manifest.signature,
-            tools: manifest.tools,
+            name: manifest.name.clone(),
+            version: manifest.version.clone(),
+            entry_point: manifest.entry_point.clone(),
+            signature,
+            tools: manifest.tools.clone(),
+            required_bundles: manifest.required_bundles.clone(),
+            task_timeout: manifest.task_timeout_secs.map(Duration::from_secs),
+            max_concurrent_tasks: manifest.max_concurrent_tasks,
             extract_dir,
             baml_src,
+            manifest,
         })
     }
 
@@ -157,11 +186,20 @@ impl AgentPackage {
     /// This creates the runtime, loads BAML schema, creates QuickJS bridge,
     /// loads JavaScript code, and returns a configured A2aAgent.
     /// The agent_id is generated internally by A2aAgent.
+    #[allow(clippy::too_many_arguments)]
     async fn boot(
         &self,
         provenance_writer: Option<Arc<dyn ProvenanceWriter>>,
-        tool_index: Option<ToolIndexConfig>,
-    ) -> Result<(A2aAgent, AgentId)> {
+        tool_indexer: Arc<dyn ToolIndexer>,
+        default_task_timeout: Option<Duration>,
+        config_reloader: Option<&ConfigReloader>,
+        tool_usage: Option<ToolUsageAggregator>,
+        approvals: Option<&ApprovalSettings>,
+        operators: &OperatorCredentials,
+        directory: &AgentDirectory,
+        push_notifications: Option<&WebhookConfig>,
+        default_max_concurrent_tasks: Option<usize>,
+    ) -> Result<(A2aAgent, AgentId, Option<String>)> {
         let span = spans::load_agent_package(&self.extract_dir);
         let _guard = span.enter();
 
@@ -181,21 +219,85 @@ impl AgentPackage {
         runtime_manager
             .set_tool_allowlist(self.tools.iter().cloned().collect::<HashSet<_>>())
             .await?;
+        // File tools only ever see the agent's own working directory.
+        let sandbox = FsSandbox::for_extract_dir(&self.extract_dir)?;
+        if let Some(fs_bundle) = FsBundle::declared_in(sandbox, &self.tools) {
+            info!(
+                agent = self.name,
+                root = %fs_bundle.sandbox().root().display(),
+                "File tools confined to the agent sandbox"
+            );
+            runtime_manager.tool_registry().lock().await.register_bundle(fs_bundle)?;
+        }
+        if !self.manifest.policies.is_empty() {
+            let mut policy = PolicyInterceptor::new(self.manifest.policies.clone())?;
+            if let Some(writer) = provenance_writer.clone() {
+                policy = policy.with_observer(Arc::new(ProvenanceInterceptor::new(writer)));
+            }
+            info!(
+                agent = self.name,
+                policies = self.manifest.policies.len(),
+                "Manifest policies enforced on tool and LLM calls"
+            );
+            runtime_manager.register_llm_interceptor(policy.clone()).await;
+            runtime_manager.register_tool_interceptor(policy).await;
+        }
+        if let Some(approvals) = approvals {
+            let mut interceptor =
+                ApprovalInterceptor::new(approvals.queue.clone(), approvals.tools.iter().cloned())
+                    .with_timeout(approvals.timeout);
+            if let Some(writer) = provenance_writer.clone() {
+                interceptor =
+                    interceptor.with_observer(Arc::new(ProvenanceInterceptor::new(writer)));
+            }
+            runtime_manager.register_tool_interceptor(interceptor).await;
+        }
+        if let Some(reloader) = config_reloader {
+            reloader.register(&runtime_manager).await;
+        }
+        if let Some(tool_usage) = tool_usage {
+            runtime_manager.register_tool_interceptor(tool_usage).await;
+        }
 
         // Build A2aAgent - it will generate agent_id internally and create QuickJS bridge
         let runtime_manager_arc = Arc::new(Mutex::new(runtime_manager));
         let mut agent_builder = A2aAgent::builder()
             .with_runtime_handle(runtime_manager_arc.clone())
+            .with_manifest(self.manifest.clone())
             .with_baml_helpers(true); // Register BAML functions
         
         if let Some(writer) = provenance_writer.clone() {
             agent_builder = agent_builder.with_provenance_writer(writer);
         }
+        if let Some(timeout) = self.task_timeout.or(default_task_timeout) {
+            agent_builder = agent_builder.with_task_timeout(TaskTimeoutConfig::new(timeout));
+        }
+        if let Some(approvals) = approvals {
+            agent_builder = agent_builder.with_approval_queue(approvals.queue.clone());
+        }
+        agent_builder = agent_builder.with_operators(operators.clone());
+        if self.delegates() {
+            agent_builder = agent_builder.with_agent_directory(directory.clone());
+        }
+        if let Some(push_notifications) = push_notifications {
+            agent_builder = agent_builder.with_push_notifications(push_notifications.clone());
+        }
+        if let Some(limit) = self.max_concurrent_tasks.or(default_max_concurrent_tasks) {
+            agent_builder = agent_builder.with_max_concurrent_tasks(limit);
+        }
 
         let agent = agent_builder.build().await?;
+
+        // Refuse to boot against a runner that lacks the tool bundles the agent needs.
+        runtime_manager_arc
+            .lock()
+            .await
+            .check_bundle_requirements(&self.required_bundles)
+            .await?;
         
         // Load and evaluate agent JavaScript code
         let entry_point_path = self.extract_dir.join(&self.entry_point);
+        let mut entry_point_error = None;
         if entry_point_path.exists() {
             let eval_span = spans::evaluate_agent_code(&self.entry_point);
             let _eval_guard = eval_span.enter();
@@ -212,8 +314,10 @@ impl AgentPackage {
                 Err(e) => {
                     tracing::warn!(
                         error = %e,
-                        "Agent code execution returned an error (may be expected)"
+                        "Agent code execution returned an error (may be expected); \
+                         initialization that must succeed belongs in onBoot"
                     );
+                    entry_point_error = Some(e.to_string());
                 }
             }
 
@@ -223,18 +327,47 @@ impl AgentPackage {
                 entry_point = self.entry_point,
                 "Agent entry point not found, skipping JavaScript initialization"
             );
+            entry_point_error = Some(format!("entry point {} not found", self.entry_point));
+        }
+
+        if agent.lifecycle().on_boot(&self.name, &self.version).await? {
+            info!(agent = self.name, "Agent onBoot hook completed");
         }
 
-        if let Some(index_config) = tool_index {
+        {
             let manager = runtime_manager_arc.lock().await;
             let tools = manager.export_tool_metadata().await;
-            if let Err(err) = index_tools(&index_config, &tools).await {
-                warn!(error = %err, "Failed to index tool metadata in FalkorDB");
+            let source = ToolIndexSource::new(self.name.clone(), self.signature.clone());
+            match tool_indexer.indexed(&source.package).await {
+                Ok(indexed) => {
+                    let drift = detect_drift(&source, &tools, &indexed);
+                    if !drift.is_clean() {
+                        info!(
+                            agent = self.name,
+                            missing = ?drift.missing,
+                            stale = ?drift.stale,
+                            changed = ?drift.changed,
+                            "Tool index drift detected; re-indexing"
+                        );
+                    }
+                }
+                Err(err) => warn!(error = %err, "Failed to read indexed tool metadata"),
+            }
+            if let Err(err) = tool_indexer.index(&source, &tools).await {
+                warn!(error = %err, "Failed to index tool metadata");
             } else {
-                info!("Tool metadata indexed in FalkorDB");
+                info!(tools = tools.len(), "Tool metadata indexed");
             }
         }
 
+        if let Some(reloader) = config_reloader {
+            reloader.add_agent(
+                self.name.clone(),
+                runtime_manager_arc.clone(),
+                self.tools.iter().cloned().collect(),
+            );
+        }
+
         // Get agent_id from the agent (generated during A2aAgent::build())
         let agent_id = agent.agent_id().clone();
 
@@ -261,18 +394,30 @@ impl AgentPackage {
             }
         }
 
-        Ok((agent, agent_id))
+        Ok((agent, agent_id, entry_point_error))
     }
 
     /// Get the agent name
     fn name(&self) -> &str {
         &self.name
     }
+
+    /// Whether the agent asks other agents for help through `a2a/delegate`
+    fn delegates(&self) -> bool {
+        self.tools.iter().any(|tool| tool == DELEGATE_TOOL)
+    }
 }
 
 /// Booted agent - holds the running A2aAgent
 struct BootedAgent {
     agent: A2aAgent,
+    /// What the agent's runtime has in progress, waited on at shutdown.
+    work: WorkTracker,
+    manifest: AgentManifest,
+    /// Where `admin.reloadSchema` reloads from.
+    baml_src: PathBuf,
+    /// Why the entry point did not evaluate at boot, reported by `runtime.health`.
+    entry_point_error: Option<String>,
 }
 
 impl BootedAgent {
@@ -282,8 +427,8 @@ impl BootedAgent {
         js_bridge.invoke_js_function(function_name, args).await
     }
 
-    async fn handle_a2a(&self, request: Value) -> Result<Vec<Value>> {
-        self.agent.handle_a2a(request).await
+    fn handle_a2a_stream(&self, request: Value) -> ChunkStream<'_> {
+        self.agent.handle_a2a_stream(request)
     }
 }
 
@@ -291,37 +436,132 @@ impl BootedAgent {
 struct AgentRunner {
     agents: HashMap<String, BootedAgent>,
     provenance_writer: Option<Arc<dyn ProvenanceWriter>>,
-    tool_index: Option<ToolIndexConfig>,
+    tool_indexer: Arc<dyn ToolIndexer>,
+    /// Applies to agents whose manifest does not set `task_timeout_secs`.
+    default_task_timeout: Option<Duration>,
+    /// Set when the runner was started with `--config`.
+    config_reloader: Option<Arc<ConfigReloader>>,
+    package_policy: VerifyPolicy,
+    /// Counts every booted agent's tool calls for periodic usage summaries.
+    tool_usage: Option<ToolUsageAggregator>,
+    /// Set by `--require-approval`.
+    approvals: Option<ApprovalSettings>,
+    /// Set by `--operators`: who may call the admin methods.
+    operators: OperatorCredentials,
+    /// Every booted agent, for the ones that delegate with `a2a/delegate`.
+    directory: AgentDirectory,
+    /// Set by `--push-notifications`.
+    push_notifications: Option<WebhookConfig>,
+    /// Applies to agents whose manifest does not set `max_concurrent_tasks`.
+    default_max_concurrent_tasks: Option<usize>,
+    shutdown_controller: ShutdownController,
+    /// How long [`Self::shutdown`] waits for requests and work in flight.
+    shutdown_timeout: Duration,
+    /// Background checks of the provenance store, reported by `runtime.health`.
+    provenance_health: Option<ProvenanceHealthMonitor>,
 }
 
 impl AgentRunner {
     fn new(
         provenance_writer: Option<Arc<dyn ProvenanceWriter>>,
-        tool_index: Option<ToolIndexConfig>,
+        tool_indexer: Arc<dyn ToolIndexer>,
+        default_task_timeout: Option<Duration>,
+        config_reloader: Option<Arc<ConfigReloader>>,
+        package_policy: VerifyPolicy,
     ) -> Self {
         Self {
             agents: HashMap::new(),
             provenance_writer,
-            tool_index,
+            tool_indexer,
+            default_task_timeout,
+            config_reloader,
+            package_policy,
+            tool_usage: None,
+            approvals: None,
+            operators: OperatorCredentials::new(),
+            directory: AgentDirectory::new(),
+            push_notifications: None,
+            default_max_concurrent_tasks: None,
+            shutdown_controller: ShutdownController::new(),
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+            provenance_health: None,
         }
     }
 
-    /// Load and boot an agent package
-    async fn load_agent(&mut self, package_path: &Path) -> Result<()> {
-        let package = AgentPackage::load_from_file(package_path).await?;
+    fn with_tool_usage(mut self, tool_usage: ToolUsageAggregator) -> Self {
+        self.tool_usage = Some(tool_usage);
+        self
+    }
+
+    fn with_approvals(mut self, approvals: ApprovalSettings) -> Self {
+        self.approvals = Some(approvals);
+        self
+    }
+
+    fn with_operators(mut self, operators: OperatorCredentials) -> Self {
+        self.operators = operators;
+        self
+    }
+
+    fn with_push_notifications(mut self, config: WebhookConfig) -> Self {
+        self.push_notifications = Some(config);
+        self
+    }
+
+    fn with_max_concurrent_tasks(mut self, limit: usize) -> Self {
+        self.default_max_concurrent_tasks = Some(limit);
+        self
+    }
+
+    fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    fn with_provenance_health(mut self, monitor: ProvenanceHealthMonitor) -> Self {
+        self.provenance_health = Some(monitor);
+        self
+    }
+
+    /// Load and boot an agent package, returning the agent's name
+    async fn load_agent(&mut self, package_path: &Path) -> Result<String> {
+        let package = AgentPackage::load_from_file(package_path, &self.package_policy).await?;
         let name = package.name().to_string();
         // Boot the package into a running agent
-        let (agent, _agent_id) = package
-            .boot(self.provenance_writer.clone(), self.tool_index.clone())
+        let (agent, _agent_id, entry_point_error) = package
+            .boot(
+                self.provenance_writer.clone(),
+                self.tool_indexer.clone(),
+                self.default_task_timeout,
+                self.config_reloader.as_deref(),
+                self.tool_usage.clone(),
+                self.approvals.as_ref(),
+                &self.operators,
+                &self.directory,
+                self.push_notifications.as_ref(),
+                self.default_max_concurrent_tasks,
+            )
             .await?;
         
+        let work = agent.runtime().lock().await.work_tracker();
         let booted = BootedAgent {
             agent,
+            work,
+            manifest: package.manifest.clone(),
+            baml_src: package.baml_src.clone(),
+            entry_point_error,
         };
         
         info!(agent = name, "Agent loaded and booted successfully");
+        self.directory.insert(booted.agent.card().clone(), Arc::new(booted.agent.clone()));
+        // Agents already booted describe the newcomer in their delegate tool too.
+        for (other, delegator) in &self.agents {
+            if other != &name && delegator.manifest.tools.iter().any(|tool| tool == DELEGATE_TOOL) {
+                delegator.agent.register_delegate_tool(self.directory.clone()).await?;
+            }
+        }
         self.agents.insert(name.clone(), booted);
-        Ok(())
+        Ok(name)
     }
 
     /// Execute a function in a specific agent
@@ -339,7 +579,12 @@ impl AgentRunner {
                 format!("Agent '{}' not found", agent_name)
             ))?;
         
-        agent.invoke_function(function_name, args).await
+        agent
+            .invoke_function(function_name, args)
+            .await
+            .with_error_context(|| {
+                ErrorContext::operation("invoke").with_agent(agent_name).with_function(function_name)
+            })
     }
 
     /// List all loaded agents
@@ -347,71 +592,322 @@ impl AgentRunner {
         self.agents.keys().cloned().collect()
     }
 
-    async fn run_a2a_stdio(&self) -> Result<()> {
+    /// Stop taking requests, wait (up to the shutdown timeout) for the ones in
+    /// flight and the work they started, cancel the tasks still unfinished and
+    /// run every agent's `onShutdown` hook. Hook failures are logged so one
+    /// agent cannot prevent the others from shutting down.
+    async fn shutdown(&self, reason: &str) {
+        self.shutdown_controller.begin();
+        let work: Vec<_> = self.agents.values().map(|booted| booted.work.clone()).collect();
+        if !self.shutdown_controller.drain(&work, self.shutdown_timeout).await {
+            warn!(
+                in_flight = self.shutdown_controller.in_flight(),
+                timeout_secs = self.shutdown_timeout.as_secs(),
+                "Work still running at the shutdown deadline"
+            );
+        }
+        for (name, booted) in &self.agents {
+            let canceled = booted.agent.cancel_unfinished_tasks().await;
+            if canceled > 0 {
+                info!(agent = name, canceled, reason, "Unfinished tasks canceled at shutdown");
+            }
+        }
+        for (name, booted) in &self.agents {
+            if let Err(err) = booted.agent.lifecycle().on_shutdown(reason).await {
+                warn!(agent = name, error = %err, "Agent onShutdown hook failed");
+            }
+        }
+    }
+
+    /// Answer A2A requests on stdin until it closes, `concurrency` at a time.
+    ///
+    /// With a concurrency above one, responses of different requests are
+    /// interleaved line by line and matched to their requests by JSON-RPC id.
+    /// Requests naming the same task still run one after the other, in the
+    /// order they were read.
+    async fn run_a2a_stdio(&self, concurrency: usize) -> Result<()> {
+        use futures_util::stream::FuturesUnordered;
         use tokio::io::{self, AsyncBufReadExt, AsyncWriteExt};
+        use tokio::sync::{mpsc, oneshot, Semaphore};
 
         let stdin = io::stdin();
         let mut lines = io::BufReader::new(stdin).lines();
         let mut stdout = io::stdout();
 
-        while let Some(line) = lines.next_line().await? {
-            let line = line.trim();
-            if line.is_empty() {
-                continue;
+        if concurrency <= 1 {
+            loop {
+                let line = tokio::select! {
+                    line = lines.next_line() => line?,
+                    () = self.shutdown_controller.draining() => break,
+                };
+                let Some(line) = line else {
+                    break;
+                };
+                let request_value = match stdio_request(&line) {
+                    Some(Ok(request_value)) => request_value,
+                    Some(Err(response)) => {
+                        write_stdio_response(&mut stdout, &response).await?;
+                        continue;
+                    }
+                    None => continue,
+                };
+                // Stream chunks are written as the agent produces them.
+                let mut responses = self.route_a2a_stream(request_value);
+                while let Some(response) = responses.next().await {
+                    write_stdio_response(&mut stdout, &response).await?;
+                }
             }
+            return Ok(());
+        }
 
-            let mut request_value: Value = match serde_json::from_str::<Value>(line) {
-                Ok(value) if value.is_object() => value,
-                Ok(_) => wrap_plaintext_message(line),
-                Err(_) => wrap_plaintext_message(line),
-            };
+        let permits = Semaphore::new(concurrency);
+        let (response_tx, mut response_rx) = mpsc::unbounded_channel::<Value>();
+        let mut in_flight = FuturesUnordered::new();
+        // The last request read for each task, which the next one waits for.
+        let mut task_tails: HashMap<String, (u64, oneshot::Receiver<()>)> = HashMap::new();
+        let mut next_seq = 0u64;
+        let mut reading = true;
+
+        while reading || !in_flight.is_empty() {
+            tokio::select! {
+                line = lines.next_line(), if reading => {
+                    let Some(line) = line? else {
+                        reading = false;
+                        continue;
+                    };
+                    let request_value = match stdio_request(&line) {
+                        Some(Ok(request_value)) => request_value,
+                        Some(Err(response)) => {
+                            write_stdio_response(&mut stdout, &response).await?;
+                            continue;
+                        }
+                        None => continue,
+                    };
+                    let seq = next_seq;
+                    next_seq += 1;
+                    let task_id = request_task_id(&request_value);
+                    let (done_tx, done_rx) = oneshot::channel::<()>();
+                    let previous = task_id
+                        .clone()
+                        .and_then(|task_id| task_tails.insert(task_id, (seq, done_rx)))
+                        .map(|(_, previous)| previous);
+                    let response_tx = response_tx.clone();
+                    let permits = &permits;
+                    in_flight.push(async move {
+                        if let Some(previous) = previous {
+                            // Resolves when the previous request finishes, sent or not.
+                            let _ = previous.await;
+                        }
+                        let _permit = permits.acquire().await;
+                        let mut responses = self.route_a2a_stream(request_value);
+                        while let Some(response) = responses.next().await {
+                            let _ = response_tx.send(response);
+                        }
+                        let _ = done_tx.send(());
+                        (task_id, seq)
+                    });
+                }
+                Some((task_id, seq)) = in_flight.next(), if !in_flight.is_empty() => {
+                    if let Some(task_id) = task_id
+                        && task_tails.get(&task_id).is_some_and(|(last, _)| *last == seq)
+                    {
+                        task_tails.remove(&task_id);
+                    }
+                }
+                Some(response) = response_rx.recv() => {
+                    write_stdio_response(&mut stdout, &response).await?;
+                }
+                () = self.shutdown_controller.draining(), if reading => reading = false,
+            }
+        }
+        while let Ok(response) = response_rx.try_recv() {
+            write_stdio_response(&mut stdout, &response).await?;
+        }
+
+        Ok(())
+    }
 
-            let request_id = a2a::extract_jsonrpc_id(&request_value);
-            let (agent_name, prepared_request) = match self.prepare_a2a_request(&mut request_value) {
-                Ok(result) => result,
-                Err(err) => {
-                    let response = map_a2a_error(request_id, err);
-                    let serialized = serde_json::to_string(&response)
-                        .unwrap_or_else(|_| "{\"error\":\"serialization failed\"}".to_string());
-                    stdout.write_all(serialized.as_bytes()).await?;
-                    stdout.write_all(b"\n").await?;
-                    stdout.flush().await?;
-                    continue;
+    /// Merge the task updates of every loaded agent into one channel.
+    fn subscribe_task_updates(&self) -> broadcast::Receiver<TaskUpdateEvent> {
+        let (update_tx, update_rx) = broadcast::channel(TASK_UPDATE_BUFFER);
+        for booted in self.agents.values() {
+            let mut updates = booted.agent.subscribe_task_updates();
+            let update_tx = update_tx.clone();
+            tokio::spawn(async move {
+                loop {
+                    match updates.recv().await {
+                        // No receivers only means no WebSocket client is connected yet.
+                        Ok(update) => {
+                            let _ = update_tx.send(update);
+                        }
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    }
                 }
-            };
+            });
+        }
+        update_rx
+    }
 
-            let agent = match self.agents.get(&agent_name) {
-                Some(agent) => agent,
-                None => {
-                    let response = a2a::error_response(
-                        request_id,
-                        -32601,
-                        "Agent not found",
-                        Some(Value::String(agent_name)),
-                    );
-                    let serialized = serde_json::to_string(&response)
-                        .unwrap_or_else(|_| "{\"error\":\"serialization failed\"}".to_string());
-                    stdout.write_all(serialized.as_bytes()).await?;
-                    stdout.write_all(b"\n").await?;
-                    stdout.flush().await?;
-                    continue;
+    /// Route one JSON-RPC request to its agent, returning the responses to send back.
+    async fn route_a2a(&self, request_value: Value) -> Vec<Value> {
+        self.route_a2a_stream(request_value).collect().await
+    }
+
+    /// Route one JSON-RPC request to its agent, yielding each response as soon
+    /// as it is ready.
+    fn route_a2a_stream(&self, request_value: Value) -> LocalBoxStream<'_, Value> {
+        let request_id = a2a::extract_jsonrpc_id(&request_value);
+        // Answered while draining too, so orchestrators see the runner going away.
+        if request_value
+            .get("method")
+            .and_then(Value::as_str)
+            .is_some_and(|method| HEALTH_METHODS.contains(&method))
+        {
+            return stream::once(async move {
+                a2a::success_response(request_id, serde_json::json!(health::report(self).await))
+            })
+            .boxed_local();
+        }
+        let Some(in_flight) = self.shutdown_controller.track() else {
+            return stream::iter([a2a::error_response(
+                request_id,
+                response::SHUTTING_DOWN,
+                "Shutting down",
+                Some(Value::String("The runner takes no new requests".to_string())),
+            )])
+            .boxed_local();
+        };
+        // Counted as in flight until the response stream is dropped.
+        self.route_a2a_request(request_id, request_value)
+            .map(move |response| {
+                let _in_flight = &in_flight;
+                response
+            })
+            .boxed_local()
+    }
+
+    fn route_a2a_request(
+        &self,
+        request_id: Option<JSONRPCId>,
+        mut request_value: Value,
+    ) -> LocalBoxStream<'_, Value> {
+        if request_value
+            .get("method")
+            .and_then(Value::as_str)
+            .is_some_and(|method| RELOAD_METHODS.contains(&method))
+        {
+            let params = request_value.get("params").cloned().unwrap_or(Value::Null);
+            return stream::once(self.reload_config(request_id, params)).boxed_local();
+        }
+        if request_value
+            .get("method")
+            .and_then(Value::as_str)
+            .is_some_and(|method| SCHEMA_RELOAD_METHODS.contains(&method))
+        {
+            let params = request_value.get("params").cloned().unwrap_or(Value::Null);
+            return stream::once(async move {
+                match self.reload_schema(&params).await {
+                    Ok(reload) => a2a::success_response(request_id, serde_json::json!(reload)),
+                    Err(err) => map_a2a_error(request_id, err),
                 }
-            };
+            })
+            .boxed_local();
+        }
+        let (agent_name, prepared_request) = match self.prepare_a2a_request(&mut request_value) {
+            Ok(result) => result,
+            Err(err) => return stream::iter([map_a2a_error(request_id, err)]).boxed_local(),
+        };
 
-            let responses = agent
-                .handle_a2a(prepared_request)
-                .await
-                .unwrap_or_else(|err| vec![map_a2a_error(request_id, err)]);
-            for response in responses {
-                let serialized = serde_json::to_string(&response)
-                    .unwrap_or_else(|_| "{\"error\":\"serialization failed\"}".to_string());
-                stdout.write_all(serialized.as_bytes()).await?;
-                stdout.write_all(b"\n").await?;
-            }
-            stdout.flush().await?;
+        let Some(agent) = self.agents.get(&agent_name) else {
+            return stream::iter([a2a::error_response(
+                request_id,
+                -32601,
+                "Agent not found",
+                Some(Value::String(agent_name)),
+            )])
+            .boxed_local();
+        };
+
+        agent
+            .handle_a2a_stream(prepared_request)
+            .map(move |response| {
+                response.unwrap_or_else(|err| map_a2a_error(request_id.clone(), err))
+            })
+            .boxed_local()
+    }
+
+    /// Handle `admin.reloadConfig`: re-read `--config` and report the version now running.
+    ///
+    /// Only an operator may ask, with `params.operatorToken`.
+    async fn reload_config(&self, request_id: Option<JSONRPCId>, params: Value) -> Value {
+        if let Err(err) = self.operators.authenticate(operator_token(&params)) {
+            return map_a2a_error(request_id, err);
         }
+        let Some(reloader) = &self.config_reloader else {
+            return a2a::error_response(
+                request_id,
+                -32601,
+                "Config reload unavailable",
+                Some(Value::String("runner was started without --config".to_string())),
+            );
+        };
+        match reloader.reload("admin").await {
+            Ok(outcome) => a2a::success_response(request_id, outcome.to_json()),
+            Err(err) => map_a2a_error(request_id, err),
+        }
+    }
 
-        Ok(())
+    /// Handle `admin.reloadSchema`: reload an agent's BAML schema from the
+    /// `baml_src` directory it booted with, without rebooting it.
+    ///
+    /// Only an operator may ask, with `params.operatorToken`. `params.agent`
+    /// names the agent (optional with one agent loaded). A change is applied to
+    /// the agent's JS globals and recorded as a `SchemaChanged` provenance event.
+    async fn reload_schema(&self, params: &Value) -> Result<SchemaReload> {
+        self.operators.authenticate(operator_token(params))?;
+        let agent_name = match params.get("agent").and_then(Value::as_str) {
+            Some(agent_name) => agent_name.to_string(),
+            None if self.agents.len() == 1 => self.agents.keys().next().cloned().unwrap_or_default(),
+            None => {
+                return Err(BamlRtError::InvalidArgument(
+                    "admin.reloadSchema needs params.agent when several agents are loaded".to_string(),
+                ));
+            }
+        };
+        let booted = self.agents.get(&agent_name).ok_or_else(|| {
+            BamlRtError::InvalidArgument(format!("No agent named {agent_name} is loaded"))
+        })?;
+        let baml_src = booted.baml_src.to_str().ok_or_else(|| {
+            BamlRtError::InvalidArgument("BAML source path contains invalid UTF-8".to_string())
+        })?;
+
+        let reload = booted.agent.runtime().lock().await.reload_schema(baml_src)?;
+        if reload.is_unchanged() {
+            return Ok(reload);
+        }
+        booted.agent.bridge().lock().await.refresh_baml_functions(&reload).await?;
+        info!(
+            agent = agent_name,
+            digest = %reload.digest,
+            added = reload.added.len(),
+            removed = reload.removed.len(),
+            changed = reload.changed.len(),
+            "BAML schema reloaded"
+        );
+        if let Some(writer) = &self.provenance_writer {
+            let event = ProvEvent::schema_changed(
+                context::generate_context_id(),
+                booted.agent.agent_id().clone(),
+                reload.digest.clone(),
+                reload.previous_digest.clone(),
+                reload.added.clone(),
+                reload.removed.clone(),
+                reload.changed.clone(),
+            );
+            writer.add_event_with_logging(event, "schema change").await;
+        }
+        Ok(reload)
     }
 
     fn prepare_a2a_request(&self, request: &mut Value) -> Result<(String, Value)> {
@@ -501,6 +997,17 @@ impl AgentRunner {
     }
 }
 
+#[async_trait(?Send)]
+impl A2aRequestHandler for AgentRunner {
+    async fn handle_a2a(&self, request: Value) -> Result<Vec<Value>> {
+        Ok(self.route_a2a(request).await)
+    }
+
+    fn handle_a2a_stream(&self, request: Value) -> ChunkStream<'_> {
+        self.route_a2a_stream(request).map(Ok).boxed_local()
+    }
+}
+
 fn strip_stream_suffix(method: &str) -> (String, bool) {
     for suffix in ["/stream", ".stream", ":stream"] {
         if let Some(stripped) = method.strip_suffix(suffix) {
@@ -527,15 +1034,27 @@ fn is_a2a_method(method: &str) -> bool {
         || method.starts_with("agent/")
 }
 
+/// Create `path`, failing if it exists, readable only by the runner's user.
+fn create_private_dir(path: &Path) -> std::io::Result<()> {
+    let mut builder = std::fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(path)
+}
+
+/// `params.operatorToken` of a runner admin request.
+fn operator_token(params: &Value) -> Option<&str> {
+    params.get("operatorToken").and_then(Value::as_str)
+}
+
 fn map_a2a_error(id: Option<JSONRPCId>, err: BamlRtError) -> Value {
-    match err {
-        BamlRtError::InvalidArgument(message) => a2a::error_response(id, -32602, "Invalid params", Some(Value::String(message))),
-        BamlRtError::FunctionNotFound(message) => a2a::error_response(id, -32601, "Method not found", Some(Value::String(message))),
-        BamlRtError::QuickJs(message) => a2a::error_response(id, -32000, "QuickJS error", Some(Value::String(message))),
-        other => a2a::error_response(id, -32603, "Internal error", Some(Value::String(other.to_string()))),
-    }
+    warn!(code = %err.code(), error = %err, context = ?err.context_chain(), "Request failed");
+    response::JsonRpcResponseFormatter.format_error(id, &err)
 }
 
+/// Task updates buffered for WebSocket clients before slow ones start missing updates.
+const TASK_UPDATE_BUFFER: usize = 256;
+
 static MESSAGE_COUNTER: AtomicU64 = AtomicU64::new(1);
 static CONTEXT_COUNTER: AtomicU64 = AtomicU64::new(1);
 static STDIO_CONTEXT_ID: std::sync::OnceLock<ContextId> = std::sync::OnceLock::new();
@@ -561,6 +1080,55 @@ fn stdio_task_id() -> TaskId {
         .clone()
 }
 
+/// The request on one stdin line: `None` for a blank line, or the error
+/// response to send back when the line is too long.
+fn stdio_request(line: &str) -> Option<std::result::Result<Value, Value>> {
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+    if line.len() > DEFAULT_MAX_MESSAGE_BYTES {
+        return Some(Err(a2a::error_response(
+            None,
+            -32600,
+            "Invalid request",
+            Some(Value::String(format!("request exceeds {} bytes", DEFAULT_MAX_MESSAGE_BYTES))),
+        )));
+    }
+    Some(Ok(match serde_json::from_str::<Value>(line) {
+        Ok(value) if value.is_object() => value,
+        _ => wrap_plaintext_message(line),
+    }))
+}
+
+async fn write_stdio_response(stdout: &mut tokio::io::Stdout, response: &Value) -> Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    let serialized = serde_json::to_string(response)
+        .unwrap_or_else(|_| "{\"error\":\"serialization failed\"}".to_string());
+    stdout.write_all(serialized.as_bytes()).await?;
+    stdout.write_all(b"\n").await?;
+    stdout.flush().await?;
+    Ok(())
+}
+
+/// The task a request is about, which orders it after earlier requests for
+/// the same task.
+fn request_task_id(request: &Value) -> Option<String> {
+    let params = request.get("params")?;
+    let is_task_method = request
+        .get("method")
+        .and_then(Value::as_str)
+        .is_some_and(|method| method.contains("tasks"));
+    params
+        .get("message")
+        .and_then(|message| message.get("taskId"))
+        .or_else(|| params.get("taskId"))
+        .or_else(|| params.get("id").filter(|_| is_task_method))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
 fn wrap_plaintext_message(text: &str) -> Value {
     let message_id = A2aMessageId::outgoing(DerivedId::new(format!(
         "cli-msg-{}",
@@ -595,8 +1163,36 @@ fn wrap_plaintext_message(text: &str) -> Value {
 
 #[derive(Debug, Clone)]
 enum ProvenanceStoreKind {
-    Memory,
-    FalkorDb { url: String, graph: String },
+    Memory { snapshot: Option<SnapshotConfig>, redaction: Option<RedactionPolicy> },
+    FalkorDb {
+        url: String,
+        graph: String,
+        manage_indexes: bool,
+        slow_query_ms: Option<u64>,
+        settings: ProvenanceSettings,
+    },
+    Sqlite { path: PathBuf, strict_attributes: bool },
+    Jsonl(JsonlProvenanceConfig),
+}
+
+impl ProvenanceStoreKind {
+    fn name(&self) -> &'static str {
+        match self {
+            ProvenanceStoreKind::Memory { .. } => "memory",
+            ProvenanceStoreKind::FalkorDb { .. } => "falkordb",
+            ProvenanceStoreKind::Sqlite { .. } => "sqlite",
+            ProvenanceStoreKind::Jsonl(_) => "jsonl",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum ToolIndexKind {
+    /// Follow the provenance store: FalkorDB when configured, otherwise none.
+    Auto,
+    FalkorDb,
+    File(PathBuf),
+    None,
 }
 
 #[derive(Debug, Clone)]
@@ -604,13 +1200,58 @@ struct RunnerConfig {
     packages: Vec<PathBuf>,
     invoke: Option<(String, String, String)>,
     a2a_stdio: bool,
-    provenance_store: ProvenanceStoreKind,
+    /// Requests `--a2a-stdio` handles at once.
+    a2a_stdio_concurrency: usize,
+    a2a_http: Option<SocketAddr>,
+    a2a_ws: Option<SocketAddr>,
+    mcp_stdio: bool,
+    metrics_addr: Option<SocketAddr>,
+    /// Set by `--profile-tools-dir`.
+    #[cfg(feature = "profiling")]
+    tool_profiler: Option<ToolProfiler>,
+    /// Every event goes to each of these; one writer is built per store.
+    provenance_stores: Vec<ProvenanceStoreKind>,
+    provenance_failure_mode: FailureMode,
+    provenance_startup_attempts: u32,
+    provenance_health_interval: Duration,
+    tool_usage_interval: Duration,
+    tool_index: ToolIndexKind,
+    task_timeout: Option<Duration>,
+    /// Set by `--require-approval`: the tools whose calls wait for an operator.
+    require_approval: Vec<String>,
+    approval_timeout: Duration,
+    /// Set by `--operators`.
+    operators: OperatorCredentials,
+    /// Set by `--push-notifications`.
+    push_notifications: Option<WebhookConfig>,
+    max_concurrent_tasks: Option<usize>,
+    shutdown_timeout: Duration,
+    provenance: ProvenanceSettings,
+    capture_signal_duration: Duration,
+    config_path: Option<PathBuf>,
+    package_policy: VerifyPolicy,
+    /// Set by `--self-test`, with the `--self-test-invoke` calls to make.
+    self_test: Option<Vec<SmokeInvocation>>,
+    /// Set by `--replay-llm-calls`: the event log and what to replay from it.
+    replay_llm_calls: Option<(PathBuf, LlmReplayOptions)>,
+    /// `--replay-tenant`: only replay calls that tenant's contexts recorded.
+    replay_tenant: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
+enum ToolIndexChoice {
+    Auto,
+    Falkordb,
+    File,
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
 enum ProvenanceStoreChoice {
     Memory,
     Falkordb,
+    Sqlite,
+    Jsonl,
 }
 
 #[derive(Debug, Parser)]
@@ -629,9 +1270,63 @@ struct Cli {
     #[arg(long)]
     a2a_stdio: bool,
 
-    /// Provenance storage backend.
-    #[arg(long, value_enum, default_value_t = ProvenanceStoreChoice::Memory)]
-    provenance_store: ProvenanceStoreChoice,
+    /// Requests --a2a-stdio handles at once. Above 1, responses are interleaved
+    /// on stdout and matched by JSON-RPC id; requests for one task stay in order.
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 1,
+        requires = "a2a_stdio",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    a2a_stdio_concurrency: u32,
+
+    /// Serve A2A JSON-RPC over HTTP on this address (e.g. 127.0.0.1:8080).
+    #[arg(long, value_name = "ADDR", conflicts_with = "a2a_stdio")]
+    a2a_http: Option<SocketAddr>,
+
+    /// Serve A2A JSON-RPC over WebSocket on this address, pushing task updates to subscribers.
+    #[arg(long, value_name = "ADDR", conflicts_with_all = ["a2a_stdio", "a2a_http"])]
+    a2a_ws: Option<SocketAddr>,
+
+    /// Serve the agents' allowlisted tools to MCP clients over stdio.
+    #[arg(long, conflicts_with_all = ["invoke", "a2a_stdio", "a2a_http", "a2a_ws"])]
+    mcp_stdio: bool,
+
+    /// Serve Prometheus metrics at /metrics on this address (e.g. 127.0.0.1:9090).
+    #[arg(long, value_name = "ADDR")]
+    metrics_addr: Option<SocketAddr>,
+
+    /// Write a pprof CPU profile of each tool call into this directory.
+    #[cfg(feature = "profiling")]
+    #[arg(long, value_name = "DIR")]
+    profile_tools_dir: Option<PathBuf>,
+
+    /// Only profile this tool (repeatable); all tools are profiled by default.
+    #[cfg(feature = "profiling")]
+    #[arg(long, value_name = "TOOL", requires = "profile_tools_dir")]
+    profile_tool: Vec<String>,
+
+    /// Provenance storage backend. Name several, comma-separated, to write every
+    /// event to each of them, e.g. memory,falkordb.
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "memory")]
+    provenance_store: Vec<ProvenanceStoreChoice>,
+
+    /// With several provenance stores: fail-fast fails a write as soon as one
+    /// store fails it, best-effort only when every store does.
+    #[arg(long, value_name = "MODE", default_value_t = FailureMode::FailFast)]
+    provenance_failure_mode: FailureMode,
+
+    /// Provenance capture preset: dev, staging or prod. The flags below and
+    /// --provenance-set override individual keys of it.
+    #[arg(long, value_name = "PROFILE")]
+    provenance_profile: Option<ProvenanceProfile>,
+
+    /// Override one provenance profile key, e.g. redaction=payloads (repeatable).
+    /// Hashed values are keyed with BAML_REDACTION_KEY when it is set, and
+    /// with a random key per store otherwise.
+    #[arg(long, value_name = "KEY=VALUE")]
+    provenance_set: Vec<String>,
 
     /// FalkorDB connection URL (required when provenance store is falkordb).
     #[arg(long)]
@@ -640,9 +1335,222 @@ struct Cli {
     /// FalkorDB graph name (defaults to baml_prov).
     #[arg(long, default_value = "baml_prov")]
     falkordb_graph: String,
+
+    /// SQLite database file (required when provenance store is sqlite).
+    #[arg(long)]
+    provenance_sqlite_path: Option<PathBuf>,
+
+    /// Event log file (required when provenance store is jsonl).
+    #[arg(long)]
+    provenance_jsonl_path: Option<PathBuf>,
+
+    /// Rotate the JSONL event log at this many megabytes.
+    #[arg(long, default_value_t = 64)]
+    provenance_jsonl_max_mb: u64,
+
+    /// Also rotate the JSONL event log after this many hours.
+    #[arg(long)]
+    provenance_jsonl_max_age_hours: Option<u64>,
+
+    /// Rotated JSONL event log files to keep (default: all).
+    #[arg(long)]
+    provenance_jsonl_keep: Option<usize>,
+
+    /// Reject provenance events whose attributes violate the vocabulary schemas.
+    #[arg(long)]
+    strict_provenance_attributes: bool,
+
+    /// Persist the in-memory provenance store to this file and reload it on start.
+    #[arg(long)]
+    provenance_snapshot_path: Option<PathBuf>,
+
+    /// Seconds between in-memory provenance snapshots (profile default: 60).
+    #[arg(long)]
+    provenance_snapshot_interval_secs: Option<u64>,
+
+    /// Log FalkorDB provenance queries slower than this many milliseconds.
+    #[arg(long)]
+    provenance_slow_query_ms: Option<u64>,
+
+    /// Buffer FalkorDB provenance writes and flush them every this many milliseconds.
+    #[arg(long)]
+    provenance_flush_interval_ms: Option<u64>,
+
+    /// Buffered provenance events that trigger an immediate flush (profile default: 64).
+    #[arg(long)]
+    provenance_batch_size: Option<usize>,
+
+    /// Skip creating FalkorDB provenance indexes at startup.
+    #[arg(long)]
+    no_provenance_indexes: bool,
+
+    /// Health check attempts against the provenance store before giving up at startup.
+    #[arg(long, default_value_t = 5)]
+    provenance_startup_attempts: u32,
+
+    /// Seconds between background provenance store health checks.
+    #[arg(long, default_value_t = 30)]
+    provenance_health_interval_secs: u64,
+
+    /// Seconds between per-agent tool usage summaries written to the provenance store.
+    #[arg(long, default_value_t = 60)]
+    tool_usage_interval_secs: u64,
+
+    /// Where to index tool metadata for discovery.
+    #[arg(long, value_enum, default_value_t = ToolIndexChoice::Auto)]
+    tool_index: ToolIndexChoice,
+
+    /// JSON catalog path (required when tool index is file).
+    #[arg(long)]
+    tool_index_path: Option<PathBuf>,
+
+    /// Fail unfinished tasks idle for this many seconds, unless the agent manifest
+    /// sets its own `task_timeout_secs`.
+    #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    task_timeout_secs: Option<u64>,
+
+    /// Hold calls to this tool, or every tool of a bundle given as `bundle/*`,
+    /// until an operator approves them with admin.decideApproval (repeatable).
+    #[arg(long, value_name = "TOOL")]
+    require_approval: Vec<String>,
+
+    /// Seconds a held tool call waits for a decision before it is denied.
+    #[arg(long, value_name = "SECS", default_value_t = 300, requires = "require_approval")]
+    approval_timeout_secs: u64,
+
+    /// JSON object of operator name to the token that operator passes as
+    /// `operatorToken` to the admin methods: admin.listApprovals,
+    /// admin.decideApproval, admin.setCaptureDetail, admin.activeWork,
+    /// admin.reloadConfig and admin.reloadSchema. The operator a token names is
+    /// recorded as the approver.
+    #[arg(long, value_name = "PATH")]
+    operators: Option<PathBuf>,
+
+    /// POST task updates to the webhooks clients register with
+    /// tasks.pushNotificationConfig.set, signed with the HMAC key in
+    /// BAML_PUSH_SIGNING_SECRET when it is set.
+    #[arg(long)]
+    push_notifications: bool,
+
+    /// Tries per push notification, with exponential backoff, before it is dropped.
+    #[arg(long, default_value_t = 5, requires = "push_notifications")]
+    push_max_attempts: u32,
+
+    /// Accept webhooks on this host although it resolves to a loopback,
+    /// link-local or private address, which is refused otherwise (repeatable).
+    #[arg(long, value_name = "HOST", requires = "push_notifications")]
+    push_private_host: Vec<String>,
+
+    /// Run at most this many message.send executions per agent at once and
+    /// queue the rest, unless the agent manifest sets its own
+    /// `max_concurrent_tasks`.
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    max_concurrent_tasks: Option<u64>,
+
+    /// On Ctrl-C or SIGTERM, seconds to let in-flight requests finish before
+    /// unfinished tasks are canceled and the runner exits.
+    #[arg(long, value_name = "SECS", default_value_t = 30)]
+    shutdown_timeout_secs: u64,
+
+    /// Truncate prompts, tool args and results longer than this in provenance and logs
+    /// (profile default: 4096).
+    #[arg(long)]
+    max_payload_chars: Option<usize>,
+
+    /// Seconds of full payload capture after SIGUSR1 (SIGUSR2 ends it early).
+    #[arg(long, default_value_t = 300)]
+    capture_signal_secs: u64,
+
+    /// JSON file of rate limits, token budgets, payload capture and tool
+    /// allowlists, re-read on SIGHUP or an admin.reloadConfig request.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// JSON object of key id to hex Ed25519 public key that package signatures
+    /// are checked against.
+    #[arg(long, value_name = "PATH")]
+    trust_store: Option<PathBuf>,
+
+    /// Refuse agent packages that are not signed by a key in the trust store.
+    #[arg(long, requires = "trust_store")]
+    require_signed_packages: bool,
+
+    /// Boot every package, run its smoke invocations and the provenance and
+    /// tool checks, print a JSON report and exit non-zero if anything failed.
+    #[arg(long, conflicts_with_all = ["invoke", "a2a_stdio", "a2a_http", "a2a_ws", "mcp_stdio"])]
+    self_test: bool,
+
+    /// Smoke invocation for --self-test: <agent> <function> <json-args> (repeatable).
+    /// Agents without one call their manifest's health_function.
+    #[arg(
+        long,
+        num_args = 3,
+        action = ArgAction::Append,
+        value_names = ["AGENT", "FUNCTION", "JSON_ARGS"],
+        requires = "self_test"
+    )]
+    self_test_invoke: Vec<String>,
+
+    /// Re-run the LLM calls recorded in this provenance event log (a JSONL log,
+    /// rotated segments included) on the loaded agents, print which outputs
+    /// changed and exit non-zero if any did or failed.
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["invoke", "a2a_stdio", "a2a_http", "a2a_ws", "mcp_stdio", "self_test"]
+    )]
+    replay_llm_calls: Option<PathBuf>,
+
+    /// Client from the agents' BAML sources to replay against instead of the
+    /// recorded one.
+    #[arg(long, value_name = "CLIENT", requires = "replay_llm_calls")]
+    replay_client: Option<String>,
+
+    /// Only replay calls of this function (repeatable).
+    #[arg(long, value_name = "FUNCTION", requires = "replay_llm_calls")]
+    replay_function: Vec<String>,
+
+    /// Replay at most this many calls, oldest first.
+    #[arg(long, requires = "replay_llm_calls")]
+    replay_limit: Option<usize>,
+
+    /// Replay only the calls recorded in contexts this tenant owns, as the
+    /// tenant would see them through provenance access control.
+    #[arg(long, value_name = "TENANT", requires = "replay_llm_calls")]
+    replay_tenant: Option<String>,
 }
 
 impl Cli {
+    /// The selected profile, then `--provenance-set` overrides, then the dedicated flags.
+    fn provenance_settings(&self) -> anyhow::Result<ProvenanceSettings> {
+        let mut settings =
+            self.provenance_profile.map(|profile| profile.settings()).unwrap_or_default();
+        for assignment in &self.provenance_set {
+            settings = settings.with_assignment(assignment)?;
+        }
+        if self.strict_provenance_attributes {
+            settings.strict_attributes = true;
+        }
+        if let Some(secs) = self.provenance_snapshot_interval_secs {
+            settings.snapshot_interval = Duration::from_secs(secs.max(1));
+        }
+        if let Some(flush_interval_ms) = self.provenance_flush_interval_ms {
+            settings.flush_interval = Some(Duration::from_millis(flush_interval_ms.max(1)));
+        }
+        if let Some(batch_size) = self.provenance_batch_size {
+            settings.max_batch_size = batch_size.max(1);
+        }
+        if let Some(max_payload_chars) = self.max_payload_chars {
+            settings.max_payload_chars = max_payload_chars;
+        }
+        if let Ok(key) = std::env::var(REDACTION_KEY_ENV)
+            && !key.is_empty()
+        {
+            settings.redaction_key = Some(RedactionKey::new(key));
+        }
+        Ok(settings)
+    }
+
     fn into_config(self) -> anyhow::Result<RunnerConfig> {
         let invoke = self.invoke.map(|values| {
             (
@@ -652,63 +1560,283 @@ impl Cli {
             )
         });
 
-        let provenance_store = match self.provenance_store {
-            ProvenanceStoreChoice::Memory => ProvenanceStoreKind::Memory,
-            ProvenanceStoreChoice::Falkordb => {
-                let url = self.falkordb_url.ok_or_else(|| {
-                    anyhow::anyhow!("--falkordb-url is required for falkordb store")
-                })?;
-                ProvenanceStoreKind::FalkorDb {
-                    url,
-                    graph: self.falkordb_graph,
+        let self_test = if self.self_test {
+            let invocations = self
+                .self_test_invoke
+                .chunks(3)
+                .map(|call| {
+                    let args = serde_json::from_str(&call[2]).with_context(|| {
+                        format!("Invalid JSON arguments for --self-test-invoke {} {}", call[0], call[1])
+                    })?;
+                    Ok(SmokeInvocation { agent: call[0].clone(), function: call[1].clone(), args })
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            Some(invocations)
+        } else {
+            None
+        };
+
+        let replay_llm_calls = self.replay_llm_calls.clone().map(|path| {
+            let mut options = self
+                .replay_function
+                .iter()
+                .fold(LlmReplayOptions::default(), |options, function| options.with_function(function));
+            if let Some(client) = &self.replay_client {
+                options = options.with_client(client);
+            }
+            if let Some(limit) = self.replay_limit {
+                options = options.with_limit(limit);
+            }
+            (path, options)
+        });
+
+        let provenance = self.provenance_settings()?;
+        let mut provenance_stores = Vec::with_capacity(self.provenance_store.len());
+        for (index, choice) in self.provenance_store.iter().enumerate() {
+            if self.provenance_store[..index].contains(choice) {
+                anyhow::bail!("--provenance-store lists {choice:?} more than once");
+            }
+            provenance_stores.push(match choice {
+                ProvenanceStoreChoice::Memory => ProvenanceStoreKind::Memory {
+                    snapshot: self
+                        .provenance_snapshot_path
+                        .clone()
+                        .map(|path| SnapshotConfig::new(path, provenance.snapshot_interval)),
+                    redaction: provenance.redaction_policy(),
+                },
+                ProvenanceStoreChoice::Falkordb => {
+                    let url = self.falkordb_url.clone().ok_or_else(|| {
+                        anyhow::anyhow!("--falkordb-url is required for falkordb store")
+                    })?;
+                    ProvenanceStoreKind::FalkorDb {
+                        url,
+                        graph: self.falkordb_graph.clone(),
+                        manage_indexes: !self.no_provenance_indexes,
+                        slow_query_ms: self.provenance_slow_query_ms,
+                        settings: provenance.clone(),
+                    }
                 }
+                ProvenanceStoreChoice::Sqlite => ProvenanceStoreKind::Sqlite {
+                    path: self.provenance_sqlite_path.clone().ok_or_else(|| {
+                        anyhow::anyhow!("--provenance-sqlite-path is required for sqlite store")
+                    })?,
+                    strict_attributes: provenance.strict_attributes,
+                },
+                ProvenanceStoreChoice::Jsonl => {
+                    let path = self.provenance_jsonl_path.clone().ok_or_else(|| {
+                        anyhow::anyhow!("--provenance-jsonl-path is required for jsonl store")
+                    })?;
+                    ProvenanceStoreKind::Jsonl(
+                        JsonlProvenanceConfig::new(path)
+                            .with_max_bytes(Some(self.provenance_jsonl_max_mb.max(1) * 1024 * 1024))
+                            .with_max_age(
+                                self.provenance_jsonl_max_age_hours
+                                    .map(|hours| Duration::from_secs(hours.max(1) * 3600)),
+                            )
+                            .with_max_segments(self.provenance_jsonl_keep),
+                    )
+                }
+            });
+        }
+
+        let tool_index = match self.tool_index {
+            ToolIndexChoice::Auto => ToolIndexKind::Auto,
+            ToolIndexChoice::Falkordb => ToolIndexKind::FalkorDb,
+            ToolIndexChoice::File => ToolIndexKind::File(self.tool_index_path.ok_or_else(|| {
+                anyhow::anyhow!("--tool-index-path is required for file tool index")
+            })?),
+            ToolIndexChoice::None => ToolIndexKind::None,
+        };
+
+        let trust_store = match &self.trust_store {
+            Some(path) => {
+                let store = TrustStore::load(path)
+                    .with_context(|| format!("Failed to load trust store {}", path.display()))?;
+                info!(path = %path.display(), keys = store.key_count(), "Loaded package trust store");
+                Some(store)
+            }
+            None => None,
+        };
+
+        let operators = match &self.operators {
+            Some(path) => load_operators(path)
+                .with_context(|| format!("Failed to load operators {}", path.display()))?,
+            None => OperatorCredentials::new(),
+        };
+
+        #[cfg(feature = "profiling")]
+        let tool_profiler = match &self.profile_tools_dir {
+            Some(dir) => {
+                let profiler = ToolProfiler::new(dir)
+                    .with_context(|| format!("Failed to create profile directory {}", dir.display()))?;
+                Some(if self.profile_tool.is_empty() {
+                    profiler
+                } else {
+                    profiler.with_tools(&self.profile_tool)?
+                })
             }
+            None => None,
         };
 
+        let push_notifications = self.push_notifications.then(|| {
+            let config = self
+                .push_private_host
+                .iter()
+                .fold(WebhookConfig::default(), |config, host| config.with_private_host(host))
+                .with_max_attempts(self.push_max_attempts);
+            match std::env::var(PUSH_SIGNING_SECRET_ENV) {
+                Ok(secret) if !secret.is_empty() => config.with_signing_secret(secret),
+                _ => config,
+            }
+        });
         Ok(RunnerConfig {
             packages: self.packages,
             invoke,
             a2a_stdio: self.a2a_stdio,
-            provenance_store,
+            a2a_stdio_concurrency: self.a2a_stdio_concurrency as usize,
+            a2a_http: self.a2a_http,
+            a2a_ws: self.a2a_ws,
+            mcp_stdio: self.mcp_stdio,
+            metrics_addr: self.metrics_addr,
+            #[cfg(feature = "profiling")]
+            tool_profiler,
+            provenance_stores,
+            provenance_failure_mode: self.provenance_failure_mode,
+            provenance_startup_attempts: self.provenance_startup_attempts,
+            provenance_health_interval: Duration::from_secs(
+                self.provenance_health_interval_secs.max(1),
+            ),
+            tool_usage_interval: Duration::from_secs(self.tool_usage_interval_secs.max(1)),
+            tool_index,
+            task_timeout: self.task_timeout_secs.map(Duration::from_secs),
+            require_approval: self.require_approval,
+            approval_timeout: Duration::from_secs(self.approval_timeout_secs.max(1)),
+            operators,
+            push_notifications,
+            max_concurrent_tasks: self.max_concurrent_tasks.map(|limit| limit as usize),
+            shutdown_timeout: Duration::from_secs(self.shutdown_timeout_secs),
+            provenance,
+            capture_signal_duration: Duration::from_secs(self.capture_signal_secs.max(1)),
+            config_path: self.config,
+            package_policy: VerifyPolicy {
+                trust_store,
+                require_signed: self.require_signed_packages,
+            },
+            self_test,
+            replay_llm_calls,
+            replay_tenant: self.replay_tenant.clone(),
         })
     }
 }
 
-fn build_provenance_writer(
-    store: &ProvenanceStoreKind,
-) -> Option<Arc<dyn ProvenanceWriter>> {
+/// Read `--operators`: a JSON object of operator name to token.
+fn load_operators(path: &Path) -> anyhow::Result<OperatorCredentials> {
+    let tokens: HashMap<String, String> = serde_json::from_slice(&std::fs::read(path)?)?;
+    Ok(tokens
+        .into_iter()
+        .fold(OperatorCredentials::new(), |operators, (operator, token)| {
+            operators.with_operator(operator, token)
+        }))
+}
+
+fn falkordb_config(store: &ProvenanceStoreKind) -> Option<FalkorDbProvenanceConfig> {
     match store {
-        ProvenanceStoreKind::Memory => Some(Arc::new(InMemoryProvenanceStore::new())),
-        ProvenanceStoreKind::FalkorDb { url, graph } => {
-            let config = FalkorDbProvenanceConfig::new(url.clone(), graph.clone());
-            Some(Arc::new(FalkorDbProvenanceWriter::new(config)))
+        ProvenanceStoreKind::Memory { .. }
+        | ProvenanceStoreKind::Sqlite { .. }
+        | ProvenanceStoreKind::Jsonl(_) => None,
+        ProvenanceStoreKind::FalkorDb { url, graph, manage_indexes, slow_query_ms, settings } => {
+            let mut config = settings
+                .apply_to_falkordb(FalkorDbProvenanceConfig::new(url.clone(), graph.clone()));
+            if let Some(slow_query_ms) = slow_query_ms {
+                config = config.with_slow_query_threshold(Duration::from_millis(*slow_query_ms));
+            }
+            Some(if *manage_indexes { config } else { config.with_indexes(Vec::new()) })
         }
     }
 }
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    // Initialize tracing
-    tracing_setup::init_tracing();
-
-    info!("BAML Agent Runner starting");
-
-    // Parse command line arguments
-    let config = Cli::parse().into_config().context("Failed to parse arguments")?;
-    let provenance_writer = build_provenance_writer(&config.provenance_store);
-    let tool_index = match &config.provenance_store {
-        ProvenanceStoreKind::FalkorDb { url, graph } => {
-            Some(ToolIndexConfig::new(url.clone(), graph.clone()))
+/// One writer for all of `stores`, fanning out through a
+/// [`CompositeProvenanceWriter`] when there are several.
+async fn build_provenance_writer(
+    stores: &[ProvenanceStoreKind],
+    failure_mode: FailureMode,
+) -> anyhow::Result<(Option<Arc<dyn ProvenanceWriter>>, Option<ProvenanceSnapshotter>)> {
+    let mut writers = Vec::with_capacity(stores.len());
+    let mut snapshotter = None;
+    for store in stores {
+        let (writer, store_snapshotter) = build_store_writer(store).await?;
+        if let Some(writer) = writer {
+            writers.push((store.name(), writer));
         }
-        ProvenanceStoreKind::Memory => None,
+        snapshotter = snapshotter.or(store_snapshotter);
+    }
+    if writers.len() <= 1 {
+        return Ok((writers.pop().map(|(_, writer)| writer), snapshotter));
+    }
+    let composite = writers
+        .into_iter()
+        .fold(CompositeProvenanceWriter::new(failure_mode), |composite, (name, writer)| {
+            composite.with_writer(name, writer)
+        });
+    info!(
+        stores = ?composite.sink_names().collect::<Vec<_>>(),
+        failure_mode = %failure_mode,
+        "Writing provenance to several stores"
+    );
+    Ok((Some(Arc::new(composite)), snapshotter))
+}
+
+async fn build_store_writer(
+    store: &ProvenanceStoreKind,
+) -> anyhow::Result<(Option<Arc<dyn ProvenanceWriter>>, Option<ProvenanceSnapshotter>)> {
+    if let Some(config) = falkordb_config(store) {
+        return Ok((Some(Arc::new(FalkorDbProvenanceWriter::new(config))), None));
+    }
+    if let ProvenanceStoreKind::Sqlite { path, strict_attributes } = store {
+        let config =
+            SqliteProvenanceConfig::new(path.clone()).with_strict_attributes(*strict_attributes);
+        let writer = SqliteProvenanceWriter::open(config)
+            .with_context(|| format!("Failed to open provenance database {}", path.display()))?;
+        info!(path = %path.display(), "Opened SQLite provenance store");
+        return Ok((Some(Arc::new(writer)), None));
+    }
+    if let ProvenanceStoreKind::Jsonl(config) = store {
+        let writer = JsonlProvenanceWriter::open(config.clone()).with_context(|| {
+            format!("Failed to open provenance event log {}", config.path.display())
+        })?;
+        info!(path = %config.path.display(), "Opened JSONL provenance event log");
+        return Ok((Some(Arc::new(writer)), None));
+    }
+    let ProvenanceStoreKind::Memory { snapshot, redaction } = store else {
+        return Ok((None, None));
+    };
+    let Some(snapshot) = snapshot else {
+        let memory = match redaction {
+            Some(policy) => InMemoryProvenanceStore::new().with_redaction(policy.clone()),
+            None => InMemoryProvenanceStore::new(),
+        };
+        return Ok((Some(Arc::new(memory)), None));
     };
-    let mut runner = AgentRunner::new(provenance_writer, tool_index);
+    let memory = Arc::new(
+        InMemoryProvenanceStore::load_snapshot(&snapshot.path, redaction.clone())
+            .await
+            .with_context(|| format!("Failed to load provenance snapshot {}", snapshot.path.display()))?,
+    );
+    info!(
+        path = %snapshot.path.display(),
+        events = memory.len().await,
+        "Loaded provenance snapshot"
+    );
+    let snapshotter = ProvenanceSnapshotter::spawn(memory.clone(), snapshot.clone());
+    Ok((Some(memory), Some(snapshotter)))
+}
 
-    for package in &config.packages {
-        let package_path = Path::new(package);
+/// Load every package in `packages` into `runner`.
+async fn load_packages(runner: &mut AgentRunner, packages: &[PathBuf]) -> anyhow::Result<()> {
+    for package_path in packages {
+        let package_path = package_path.as_path();
         if !package_path.exists() {
-            eprintln!("Error: Agent package not found: {}", package_path.display());
-            std::process::exit(1);
+            anyhow::bail!("Agent package not found: {}", package_path.display());
         }
 
         match runner.load_agent(package_path).await {
@@ -717,28 +1845,77 @@ async fn main() -> anyhow::Result<()> {
             }
             Err(e) => {
                 error!(error = %e, package = %package_path.display(), "Failed to load agent package");
-                eprintln!("Error: Failed to load agent package {}: {}", package_path.display(), e);
-                std::process::exit(1);
+                anyhow::bail!("Failed to load agent package {}: {}", package_path.display(), e);
             }
         }
     }
+    Ok(())
+}
 
-    if let Some((agent_name, function_name, json_args)) = config.invoke {
-        let args_value: Value = serde_json::from_str(&json_args)
-            .context("Invalid JSON arguments")?;
-        let result = runner
-            .invoke(&agent_name, &function_name, args_value)
-            .await
-            .context("Function invocation failed")?;
-        println!("{}", serde_json::to_string_pretty(&result)?);
-        return Ok(());
+/// Run whatever `config` asks of the loaded agents: replay, a single
+/// invocation, or serving a transport until it closes or the runner drains.
+///
+/// Returns the shutdown reason with the outcome (`Ok(false)` when a replay
+/// failed), so `main` shuts the runner down and flushes provenance on every
+/// exit, errors included.
+async fn serve(
+    runner: &AgentRunner,
+    config: &RunnerConfig,
+    config_reloader: Option<&Arc<ConfigReloader>>,
+) -> (&'static str, anyhow::Result<bool>) {
+    if let Some((path, options)) = &config.replay_llm_calls {
+        let replayed = async {
+            let tenant = config.replay_tenant.as_deref();
+            let report = llm_replay::run(runner, path, options, tenant).await?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            Ok::<_, anyhow::Error>(report.passed())
+        };
+        return ("replay_complete", replayed.await);
+    }
+
+    let _reload_signals = match config_reloader {
+        Some(reloader) => {
+            if let Err(err) = reloader
+                .reload("startup")
+                .await
+                .with_context(|| format!("Invalid runner config {}", reloader.path().display()))
+            {
+                return ("startup_failed", Err(err));
+            }
+            spawn_reload_signal_handler(reloader.clone())
+        }
+        None => None,
+    };
+
+    if let Some((agent_name, function_name, json_args)) = &config.invoke {
+        let invoked = async {
+            let args_value: Value =
+                serde_json::from_str(json_args).context("Invalid JSON arguments")?;
+            let result = runner
+                .invoke(agent_name, function_name, args_value)
+                .await
+                .context("Function invocation failed")?;
+            println!("{}", serde_json::to_string_pretty(&result)?);
+            Ok::<_, anyhow::Error>(true)
+        };
+        return ("invoke_complete", invoked.await);
+    }
+
+    // MCP owns stdout, so serve it before printing anything there.
+    if config.mcp_stdio {
+        let server = match McpToolServer::new(runner).await {
+            Ok(server) => server,
+            Err(err) => return ("startup_failed", Err(err.into())),
+        };
+        info!(tools = server.tool_count(), "Serving tools over MCP stdio");
+        let served = server.serve_stdio().await.map(|()| true).map_err(Into::into);
+        return ("stdin_closed", served);
     }
 
     // If we get here, just loaded agents without invoking
     let agents = runner.list_agents();
     if agents.is_empty() {
-        eprintln!("Error: No agents loaded");
-        std::process::exit(1);
+        return ("runner_exit", Err(anyhow::anyhow!("No agents loaded")));
     }
 
     println!("✅ Loaded {} agent(s):", agents.len());
@@ -746,11 +1923,301 @@ async fn main() -> anyhow::Result<()> {
         println!("  - {}", agent_name);
     }
 
+    let _shutdown_signals = spawn_signal_handler(runner.shutdown_controller.clone());
+    let shutdown = runner.shutdown_controller.clone();
+
     if config.a2a_stdio {
-        runner.run_a2a_stdio().await?;
-        return Ok(());
+        let serving = runner.run_a2a_stdio(config.a2a_stdio_concurrency);
+        let served = shutdown.serve_until_drained(serving, config.shutdown_timeout).await;
+        let reason = if shutdown.is_draining() { "signal" } else { "stdin_closed" };
+        return (reason, served.transpose().map(|_| true).map_err(Into::into));
+    }
+
+    if let Some(addr) = config.a2a_http {
+        let served = async {
+            let server = A2aHttpServer::bind(addr)
+                .await
+                .with_context(|| format!("Failed to bind A2A HTTP transport to {}", addr))?;
+            println!("🌐 A2A HTTP transport listening on http://{}", server.local_addr()?);
+            let serving = server.serve(runner, shutdown.draining());
+            shutdown.serve_until_drained(serving, config.shutdown_timeout).await.transpose()?;
+            Ok::<_, anyhow::Error>(true)
+        };
+        return ("http_stopped", served.await);
+    }
+
+    if let Some(addr) = config.a2a_ws {
+        let served = async {
+            let server = A2aWebSocketServer::bind(addr)
+                .await
+                .with_context(|| format!("Failed to bind A2A WebSocket transport to {}", addr))?;
+            println!("🌐 A2A WebSocket transport listening on ws://{}", server.local_addr()?);
+            let serving =
+                server.serve(runner, runner.subscribe_task_updates(), shutdown.draining());
+            shutdown.serve_until_drained(serving, config.shutdown_timeout).await.transpose()?;
+            Ok::<_, anyhow::Error>(true)
+        };
+        return ("ws_stopped", served.await);
+    }
+
+    ("runner_exit", Ok(true))
+}
+
+/// Write the last tool usage summaries, then flush buffered provenance events
+/// and the final in-memory snapshot before exiting.
+async fn finish_provenance(
+    writer: Option<&dyn ProvenanceWriter>,
+    snapshotter: Option<ProvenanceSnapshotter>,
+    tool_usage: Option<ToolUsageReporter>,
+) {
+    if let Some(tool_usage) = tool_usage {
+        tool_usage.shutdown().await;
+    }
+    if let Some(writer) = writer
+        && let Err(err) = writer.flush().await
+    {
+        warn!(error = %err, "Failed to flush buffered provenance events");
     }
+    if let Some(snapshotter) = snapshotter
+        && let Err(err) = snapshotter.shutdown().await
+    {
+        warn!(error = %err, "Failed to write final provenance snapshot");
+    }
+}
+
+fn build_tool_indexer(
+    kind: &ToolIndexKind,
+    stores: &[ProvenanceStoreKind],
+) -> anyhow::Result<Arc<dyn ToolIndexer>> {
+    let falkordb = stores.iter().find_map(|store| match store {
+        ProvenanceStoreKind::FalkorDb { url, graph, .. } => {
+            Some(ToolIndexConfig::new(url.clone(), graph.clone()))
+        }
+        ProvenanceStoreKind::Memory { .. }
+        | ProvenanceStoreKind::Sqlite { .. }
+        | ProvenanceStoreKind::Jsonl(_) => None,
+    });
+    Ok(match (kind, falkordb) {
+        (ToolIndexKind::Auto | ToolIndexKind::FalkorDb, Some(config)) => {
+            Arc::new(FalkorDbToolIndexer::new(config))
+        }
+        (ToolIndexKind::FalkorDb, None) => {
+            anyhow::bail!("falkordb tool index requires --provenance-store falkordb")
+        }
+        (ToolIndexKind::File(path), _) => Arc::new(FileToolIndexer::new(path.clone())),
+        (ToolIndexKind::Auto | ToolIndexKind::None, _) => Arc::new(NoopToolIndexer),
+    })
+}
 
+/// Raise payload capture to full detail on SIGUSR1 and drop back on SIGUSR2,
+/// so operators can debug a running agent without redeploying it.
+#[cfg(unix)]
+fn spawn_capture_signal_handler(duration: Duration) -> Option<tokio::task::JoinHandle<()>> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let (mut raise, mut reset) =
+        match (signal(SignalKind::user_defined1()), signal(SignalKind::user_defined2())) {
+            (Ok(raise), Ok(reset)) => (raise, reset),
+            (Err(err), _) | (_, Err(err)) => {
+                warn!(error = %err, "Failed to install payload capture signal handlers");
+                return None;
+            }
+        };
+    Some(tokio::spawn(async move {
+        let capture = PayloadCapture::global();
+        loop {
+            tokio::select! {
+                Some(()) = raise.recv() => {
+                    capture.raise(None, duration);
+                }
+                Some(()) = reset.recv() => {
+                    capture.reset(None);
+                    info!("Payload capture reset to standard detail");
+                }
+                else => break,
+            }
+        }
+    }))
+}
+
+#[cfg(not(unix))]
+fn spawn_capture_signal_handler(_duration: Duration) -> Option<tokio::task::JoinHandle<()>> {
+    None
+}
+
+/// Re-read `--config` on SIGHUP. A config that fails validation is logged and
+/// the running version stays in place.
+#[cfg(unix)]
+fn spawn_reload_signal_handler(
+    reloader: Arc<ConfigReloader>,
+) -> Option<tokio::task::JoinHandle<()>> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(err) => {
+            warn!(error = %err, "Failed to install config reload signal handler");
+            return None;
+        }
+    };
+    Some(tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            if let Err(err) = reloader.reload("sighup").await {
+                warn!(
+                    error = %err,
+                    path = %reloader.path().display(),
+                    "Config reload rejected; keeping the running version"
+                );
+            }
+        }
+    }))
+}
+
+#[cfg(not(unix))]
+fn spawn_reload_signal_handler(
+    _reloader: Arc<ConfigReloader>,
+) -> Option<tokio::task::JoinHandle<()>> {
+    None
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    // Initialize tracing
+    let _tracing = tracing_setup::init_tracing();
+
+    info!("BAML Agent Runner starting");
+
+    // Parse command line arguments
+    let config = Cli::parse().into_config().context("Failed to parse arguments")?;
+    // Installed before anything records a metric, so every instrument exports.
+    let _metrics_server = match config.metrics_addr {
+        Some(addr) => Some(
+            PrometheusExporter::install()?
+                .serve(addr)
+                .await
+                .with_context(|| format!("Failed to serve metrics on {addr}"))?,
+        ),
+        None => None,
+    };
+    #[cfg(feature = "profiling")]
+    if let Some(profiler) = config.tool_profiler {
+        let profiler = ToolProfiler::install(profiler)?;
+        info!(dir = %profiler.output_dir().display(), "Profiling tool calls");
+    }
+    PayloadCapture::global().set_max_payload_chars(config.provenance.max_payload_chars);
+    let _capture_signals = spawn_capture_signal_handler(config.capture_signal_duration);
+    let (provenance_writer, snapshotter) =
+        build_provenance_writer(&config.provenance_stores, config.provenance_failure_mode).await?;
+    let provenance_health = match &provenance_writer {
+        Some(writer) => {
+            wait_until_healthy(
+                writer.as_ref(),
+                config.provenance_startup_attempts,
+                Duration::from_secs(1),
+            )
+            .await
+            .context("Provenance store is unreachable")?;
+            Some(ProvenanceHealthMonitor::spawn(
+                writer.clone(),
+                config.provenance_health_interval,
+            ))
+        }
+        None => None,
+    };
+    if let Some(falkordb) = config.provenance_stores.iter().find_map(falkordb_config) {
+        FalkorDbProvenanceWriter::new(falkordb)
+            .ensure_indexes()
+            .await
+            .context("Failed to create provenance graph indexes")?;
+    }
+    let tool_indexer = build_tool_indexer(&config.tool_index, &config.provenance_stores)?;
+    let config_reloader = match &config.config_path {
+        Some(path) => Some(Arc::new(
+            ConfigReloader::open(
+                path.clone(),
+                config.provenance.max_payload_chars,
+                provenance_writer.clone(),
+                provenance_writer.clone().map(|writer| {
+                    Arc::new(ProvenanceInterceptor::new(writer)) as Arc<dyn BudgetObserver>
+                }),
+            )
+            .with_context(|| format!("Failed to load runner config {}", path.display()))?,
+        )),
+        None => None,
+    };
+    let mut runner = AgentRunner::new(
+        provenance_writer.clone(),
+        tool_indexer,
+        config.task_timeout,
+        config_reloader.clone(),
+        config.package_policy.clone(),
+    )
+    .with_shutdown_timeout(config.shutdown_timeout);
+    if let Some(monitor) = provenance_health {
+        runner = runner.with_provenance_health(monitor);
+    }
+    if !config.require_approval.is_empty() {
+        info!(
+            tools = ?config.require_approval,
+            timeout_secs = config.approval_timeout.as_secs(),
+            "Tool calls held for operator approval"
+        );
+        if config.operators.is_empty() {
+            warn!("No --operators configured; held tool calls can only time out");
+        }
+        runner = runner.with_approvals(ApprovalSettings {
+            queue: PendingApprovalQueue::new(),
+            tools: config.require_approval.clone(),
+            timeout: config.approval_timeout,
+        });
+    }
+    runner = runner.with_operators(config.operators.clone());
+    if let Some(push_notifications) = &config.push_notifications {
+        info!(
+            signed = push_notifications.signing_secret.is_some(),
+            max_attempts = push_notifications.max_attempts,
+            "Task updates pushed to registered webhooks"
+        );
+        runner = runner.with_push_notifications(push_notifications.clone());
+    }
+    if let Some(limit) = config.max_concurrent_tasks {
+        runner = runner.with_max_concurrent_tasks(limit);
+    }
+    let tool_usage_reporter = match &provenance_writer {
+        Some(writer) => {
+            let tool_usage = ToolUsageAggregator::new();
+            runner = runner.with_tool_usage(tool_usage.clone());
+            Some(ToolUsageReporter::spawn(tool_usage, writer.clone(), config.tool_usage_interval))
+        }
+        None => None,
+    };
+
+    let (reason, outcome) = match &config.self_test {
+        Some(invocations) => {
+            let tested = async {
+                let report = self_test::run(
+                    &mut runner,
+                    &config.packages,
+                    invocations,
+                    config_reloader.as_deref(),
+                    provenance_writer.as_deref(),
+                )
+                .await;
+                println!("{}", serde_json::to_string_pretty(&report)?);
+                Ok::<_, anyhow::Error>(report.passed)
+            };
+            ("self_test_complete", tested.await)
+        }
+        None => match load_packages(&mut runner, &config.packages).await {
+            Ok(()) => serve(&runner, &config, config_reloader.as_ref()).await,
+            Err(err) => ("load_failed", Err(err)),
+        },
+    };
+    runner.shutdown(reason).await;
+    finish_provenance(provenance_writer.as_deref(), snapshotter, tool_usage_reporter).await;
+    if !outcome? {
+        std::process::exit(1);
+    }
     info!("Agent Runner completed successfully");
     Ok(())
 }
@@ -0,0 +1,282 @@
+//! `--mcp-stdio`: serve the loaded agents' tools over MCP.
+//!
+//! Lets MCP clients such as desktop assistants and IDEs call the tools the
+//! agents use, over newline-delimited JSON-RPC on stdin and stdout. Only tools
+//! an agent's manifest allows are listed or callable, whether they are host
+//! tools or registered by agent code. Two agents offering the same tool are
+//! refused at startup, since a client could not tell which one a call reaches.
+//!
+//! Each `tools/call` runs through the owning agent's runtime under a fresh
+//! message id, so the provenance interceptor records it like any other tool
+//! call. Tool failures come back as results with `isError` set, as MCP asks;
+//! JSON-RPC errors are reserved for malformed requests and unknown tools.
+
+use crate::AgentRunner;
+use baml_rt_a2a::{A2aAgent, a2a};
+use baml_rt_core::context;
+use baml_rt_core::ids::{AgentId, ExternalId};
+use baml_rt_core::{BamlRtError, MessageId, Result};
+use baml_rt_quickjs::BamlRuntimeManager;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::io::{self, AsyncBufReadExt, AsyncWriteExt};
+use tokio::sync::Mutex;
+
+/// Protocol revisions this server speaks, newest first.
+const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+struct ServedTool {
+    /// Name of the agent offering the tool.
+    agent: String,
+    agent_id: AgentId,
+    runtime: Arc<Mutex<BamlRuntimeManager>>,
+    /// The `tools/list` entry.
+    descriptor: Value,
+}
+
+/// The tools the loaded agents may call, by name.
+pub(crate) struct McpToolServer {
+    tools: BTreeMap<String, ServedTool>,
+}
+
+impl McpToolServer {
+    /// Fails when two agents allow a tool of the same name.
+    pub(crate) async fn new(runner: &AgentRunner) -> Result<Self> {
+        let agents = runner.agents.iter().map(|(name, booted)| {
+            (name.as_str(), &booted.agent, booted.manifest.tools.as_slice())
+        });
+        Self::for_agents(agents.collect()).await
+    }
+
+    /// Serve each agent's tools that its `allowed` list names.
+    async fn for_agents(mut agents: Vec<(&str, &A2aAgent, &[String])>) -> Result<Self> {
+        agents.sort_by_key(|(name, ..)| *name);
+        let mut tools: BTreeMap<String, ServedTool> = BTreeMap::new();
+        for (agent_name, agent, allowed) in agents {
+            let runtime = agent.runtime();
+            let exported = runtime.lock().await.export_tool_metadata().await;
+            for tool in exported {
+                let name = tool.name.to_string();
+                if !allowed.contains(&name) {
+                    continue;
+                }
+                if let Some(served) = tools.get(&name) {
+                    return Err(BamlRtError::InvalidArgument(format!(
+                        "Agents {} and {agent_name} both offer tool {name}; \
+                         MCP clients could not tell which one a call reaches",
+                        served.agent
+                    )));
+                }
+                let mut descriptor = json!({
+                    "name": name,
+                    "description": tool.description,
+                    "inputSchema": tool.input_schema,
+                });
+                // MCP only allows object output schemas.
+                if tool.output_schema.get("type") == Some(&json!("object")) {
+                    descriptor["outputSchema"] = tool.output_schema;
+                }
+                tools.insert(
+                    name,
+                    ServedTool {
+                        agent: agent_name.to_string(),
+                        agent_id: agent.agent_id().clone(),
+                        runtime: runtime.clone(),
+                        descriptor,
+                    },
+                );
+            }
+        }
+        Ok(Self { tools })
+    }
+
+    pub(crate) fn tool_count(&self) -> usize {
+        self.tools.len()
+    }
+
+    /// Answer JSON-RPC requests on stdin until it closes.
+    pub(crate) async fn serve_stdio(&self) -> Result<()> {
+        let mut lines = io::BufReader::new(io::stdin()).lines();
+        let mut stdout = io::stdout();
+        while let Some(line) = lines.next_line().await? {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let response = match serde_json::from_str::<Value>(line) {
+                Ok(request) => self.handle(request).await,
+                Err(err) => Some(a2a::error_response(
+                    None,
+                    -32700,
+                    "Parse error",
+                    Some(Value::String(err.to_string())),
+                )),
+            };
+            if let Some(response) = response {
+                stdout.write_all(response.to_string().as_bytes()).await?;
+                stdout.write_all(b"\n").await?;
+                stdout.flush().await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The response to one message; notifications get none.
+    async fn handle(&self, request: Value) -> Option<Value> {
+        let request_id = a2a::extract_jsonrpc_id(&request);
+        let method = request.get("method").and_then(Value::as_str);
+        if request.get("id").is_none() {
+            return None;
+        }
+        let params = request.get("params").cloned().unwrap_or_else(|| json!({}));
+        Some(match method {
+            Some("initialize") => a2a::success_response(request_id, initialize_result(&params)),
+            Some("ping") => a2a::success_response(request_id, json!({})),
+            Some("tools/list") => {
+                let tools: Vec<&Value> = self.tools.values().map(|tool| &tool.descriptor).collect();
+                a2a::success_response(request_id, json!({ "tools": tools }))
+            }
+            Some("tools/call") => {
+                let Some(name) = params.get("name").and_then(Value::as_str) else {
+                    return Some(a2a::error_response(
+                        request_id,
+                        -32602,
+                        "Invalid params",
+                        Some(Value::String("tools/call requires a tool name".to_string())),
+                    ));
+                };
+                let Some(tool) = self.tools.get(name) else {
+                    return Some(a2a::error_response(
+                        request_id,
+                        -32602,
+                        "Unknown tool",
+                        Some(Value::String(name.to_string())),
+                    ));
+                };
+                let arguments = params.get("arguments").cloned().unwrap_or_else(|| json!({}));
+                let structured = tool.descriptor.get("outputSchema").is_some();
+                a2a::success_response(request_id, call_result(tool.call(name, arguments).await, structured))
+            }
+            Some(other) => a2a::error_response(
+                request_id,
+                -32601,
+                "Method not found",
+                Some(Value::String(other.to_string())),
+            ),
+            None => a2a::error_response(request_id, -32600, "Invalid request", None),
+        })
+    }
+}
+
+impl ServedTool {
+    async fn call(&self, name: &str, arguments: Value) -> Result<Value> {
+        let message_id =
+            MessageId::from_external(ExternalId::new(format!("mcp-{}", uuid::Uuid::new_v4())));
+        let runtime = self.runtime.clone();
+        let name = name.to_string();
+        context::with_agent_id(self.agent_id.clone(), async move {
+            context::with_message_id(message_id, async move {
+                runtime.lock().await.execute_tool(&name, arguments).await
+            })
+            .await?
+        })
+        .await
+    }
+}
+
+fn initialize_result(params: &Value) -> Value {
+    let requested = params.get("protocolVersion").and_then(Value::as_str);
+    let version = requested
+        .filter(|version| SUPPORTED_PROTOCOL_VERSIONS.contains(version))
+        .unwrap_or(SUPPORTED_PROTOCOL_VERSIONS[0]);
+    json!({
+        "protocolVersion": version,
+        "capabilities": { "tools": { "listChanged": false } },
+        "serverInfo": { "name": "baml-agent-runner", "version": env!("CARGO_PKG_VERSION") }
+    })
+}
+
+fn call_result(result: Result<Value>, structured: bool) -> Value {
+    match result {
+        Ok(output) => {
+            let mut result = json!({
+                "content": [{ "type": "text", "text": output.to_string() }],
+                "isError": false,
+            });
+            if structured {
+                result["structuredContent"] = output;
+            }
+            result
+        }
+        Err(err) => json!({
+            "content": [{ "type": "text", "text": err.to_string() }],
+            "isError": true,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn agent_with_js_tools(names: &[&str]) -> A2aAgent {
+        let agent = A2aAgent::builder().build().await.expect("agent build");
+        for name in names {
+            agent
+                .register_js_tool(
+                    *name,
+                    "Adds two numbers",
+                    json!({"type": "object"}),
+                    r#"(args) => ({ sum: args.a + args.b })"#,
+                )
+                .await
+                .expect("register js tool");
+        }
+        agent
+    }
+
+    fn request(id: i64, method: &str, params: Value) -> Value {
+        json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params})
+    }
+
+    #[tokio::test]
+    async fn js_tools_outside_the_allowlist_are_neither_listed_nor_callable() {
+        let agent = agent_with_js_tools(&["js/add", "js/hidden"]).await;
+        let allowed = vec!["js/add".to_string()];
+        let server = McpToolServer::for_agents(vec![("calc", &agent, allowed.as_slice())])
+            .await
+            .expect("server");
+
+        let listed = server.handle(request(1, "tools/list", json!({}))).await.expect("response");
+        let names: Vec<&str> = listed["result"]["tools"]
+            .as_array()
+            .expect("tools")
+            .iter()
+            .filter_map(|tool| tool["name"].as_str())
+            .collect();
+        assert_eq!(names, vec!["js/add"]);
+
+        let arguments = json!({"a": 2, "b": 3});
+        let call = |name: &str| json!({"name": name, "arguments": arguments});
+        let allowed_call = server.handle(request(2, "tools/call", call("js/add"))).await;
+        assert_eq!(allowed_call.expect("response")["result"]["isError"], json!(false));
+        let hidden_call = server.handle(request(3, "tools/call", call("js/hidden"))).await;
+        assert_eq!(hidden_call.expect("response")["error"]["message"], json!("Unknown tool"));
+    }
+
+    #[tokio::test]
+    async fn a_tool_offered_by_two_agents_is_refused() {
+        let first = agent_with_js_tools(&["js/add"]).await;
+        let second = agent_with_js_tools(&["js/add"]).await;
+        let allowed = vec!["js/add".to_string()];
+        let agents = vec![
+            ("first", &first, allowed.as_slice()),
+            ("second", &second, allowed.as_slice()),
+        ];
+        let Err(err) = McpToolServer::for_agents(agents).await else {
+            panic!("a duplicate tool must be refused");
+        };
+        assert!(err.to_string().contains("first and second"), "{err}");
+    }
+}
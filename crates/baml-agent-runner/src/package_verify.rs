@@ -0,0 +1,235 @@
+//! Signature verification of agent packages at load time.
+//!
+//! A signed package's manifest has `signature_metadata` naming the algorithm
+//! and, for `ed25519`, the `key_id` of the signing key. What is signed is the
+//! [`package_digest`]: SHA-256 over every file in the archive in path order,
+//! plus the manifest itself with `signature` and `signature_metadata` removed,
+//! so neither code nor declared tools can change without breaking it.
+//!
+//! - `ed25519`: `signature` is the hex Ed25519 signature of the digest, checked
+//!   against the public key `key_id` names in the trust store.
+//! - `sha256`: `signature` is the hex digest itself. It catches corruption but
+//!   says nothing about who built the package, so it does not count as signed.
+//!
+//! Packages without `signature_metadata` are unsigned; their `signature` is only
+//! an identity. With `--require-signed-packages` the runner loads nothing that
+//! is not verified with a trusted ed25519 key.
+
+use baml_rt_core::manifest::AgentManifest;
+use baml_rt_core::package::package_digest;
+use baml_rt_core::{BamlRtError, Result};
+use ed25519_dalek::{Signature, VerifyingKey};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Public keys packages may be signed with, by key id.
+///
+/// Loaded from a JSON object mapping each key id to its hex-encoded 32-byte
+/// Ed25519 public key, e.g. `{"release-2025": "3d4017c3..."}`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TrustStore {
+    keys: HashMap<String, VerifyingKey>,
+}
+
+impl TrustStore {
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let raw = std::fs::read_to_string(path)?;
+        let entries: HashMap<String, String> = serde_json::from_str(&raw)?;
+        let mut store = Self::default();
+        for (key_id, key_hex) in entries {
+            let key = decode_fixed::<32>(&key_hex)
+                .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok())
+                .ok_or_else(|| {
+                    BamlRtError::Configuration(format!(
+                        "trust store {}: key '{key_id}' is not a hex Ed25519 public key",
+                        path.display()
+                    ))
+                })?;
+            store.keys.insert(key_id, key);
+        }
+        Ok(store)
+    }
+
+    fn key(&self, key_id: &str) -> Option<&VerifyingKey> {
+        self.keys.get(key_id)
+    }
+
+    pub(crate) fn key_count(&self) -> usize {
+        self.keys.len()
+    }
+}
+
+/// What the runner demands of the packages it loads.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct VerifyPolicy {
+    pub(crate) trust_store: Option<TrustStore>,
+    /// Refuse packages that are not verified with a trusted ed25519 key.
+    pub(crate) require_signed: bool,
+}
+
+/// How far a package's signature could be checked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Verification {
+    /// Signed by the trusted key `key_id`.
+    Signed { key_id: String },
+    /// Contents match the sha256 digest in the manifest.
+    DigestOnly,
+    /// Signed with `key_id`, but no trust store was configured to check it with.
+    Unverified { key_id: String },
+    /// No `signature_metadata`.
+    Unsigned,
+}
+
+/// Check the package extracted at `root` against `policy`.
+///
+/// A signature that is present but wrong is refused whatever the policy.
+pub(crate) fn verify_package(
+    root: &Path,
+    manifest: &AgentManifest,
+    policy: &VerifyPolicy,
+) -> Result<Verification> {
+    let refused = |reason: String| {
+        BamlRtError::InvalidArgument(format!(
+            "package '{}' failed signature verification: {reason}",
+            manifest.name
+        ))
+    };
+    let Some(metadata) = &manifest.signature_metadata else {
+        if policy.require_signed {
+            return Err(refused("package is unsigned".to_string()));
+        }
+        return Ok(Verification::Unsigned);
+    };
+    let signature = manifest.require_signature()?;
+    let digest = package_digest(root, manifest)?;
+    let digest_hex = hex::encode(digest);
+    if let Some(declared) = &metadata.digest
+        && !declared.eq_ignore_ascii_case(&digest_hex)
+    {
+        return Err(refused("contents do not match signature_metadata.digest".to_string()));
+    }
+
+    match metadata.algorithm.as_str() {
+        "sha256" => {
+            if !signature.eq_ignore_ascii_case(&digest_hex) {
+                return Err(refused("contents do not match the sha256 digest".to_string()));
+            }
+            if policy.require_signed {
+                return Err(refused("a sha256 digest is not a signature".to_string()));
+            }
+            Ok(Verification::DigestOnly)
+        }
+        "ed25519" => {
+            let key_id = metadata
+                .key_id
+                .clone()
+                .ok_or_else(|| refused("signature_metadata.key_id is missing".to_string()))?;
+            let Some(trust_store) = &policy.trust_store else {
+                if policy.require_signed {
+                    return Err(refused("no trust store is configured".to_string()));
+                }
+                return Ok(Verification::Unverified { key_id });
+            };
+            let key = trust_store
+                .key(&key_id)
+                .ok_or_else(|| refused(format!("key '{key_id}' is not in the trust store")))?;
+            let signature = decode_fixed::<64>(signature)
+                .map(|bytes| Signature::from_bytes(&bytes))
+                .ok_or_else(|| refused("signature is not a hex Ed25519 signature".to_string()))?;
+            key.verify_strict(&digest, &signature)
+                .map_err(|_| refused(format!("signature does not match key '{key_id}'")))?;
+            Ok(Verification::Signed { key_id })
+        }
+        other => Err(refused(format!("unsupported signature algorithm '{other}'"))),
+    }
+}
+
+fn decode_fixed<const N: usize>(hex_value: &str) -> Option<[u8; N]> {
+    hex::decode(hex_value.trim()).ok()?.try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use baml_rt_core::manifest::{MANIFEST_FILE, SignatureMetadata};
+    use ed25519_dalek::{Signer, SigningKey};
+
+    fn package(name: &str) -> (std::path::PathBuf, AgentManifest) {
+        let root = std::env::temp_dir()
+            .join(format!("package-verify-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("dist")).unwrap();
+        std::fs::write(root.join("dist/index.js"), "export const answer = 42;").unwrap();
+        let manifest = AgentManifest::from_value(serde_json::json!({
+            "manifest_version": 2,
+            "name": name,
+            "version": "1.0.0",
+            "tools": ["support/search"],
+        }))
+        .unwrap();
+        std::fs::write(root.join(MANIFEST_FILE), serde_json::to_string(&manifest).unwrap())
+            .unwrap();
+        (root, manifest)
+    }
+
+    fn sign(root: &Path, mut manifest: AgentManifest, key: &SigningKey) -> AgentManifest {
+        let digest = package_digest(root, &manifest).unwrap();
+        manifest.signature = Some(hex::encode(key.sign(&digest).to_bytes()));
+        manifest.signature_metadata = Some(SignatureMetadata {
+            algorithm: "ed25519".to_string(),
+            key_id: Some("release".to_string()),
+            digest: Some(hex::encode(digest)),
+        });
+        manifest
+    }
+
+    fn trusting(key: &SigningKey) -> VerifyPolicy {
+        VerifyPolicy {
+            trust_store: Some(TrustStore {
+                keys: HashMap::from([("release".to_string(), key.verifying_key())]),
+            }),
+            require_signed: true,
+        }
+    }
+
+    #[test]
+    fn trusted_signature_verifies_until_contents_change() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let (root, manifest) = package("signed");
+        let manifest = sign(&root, manifest, &key);
+
+        assert_eq!(
+            verify_package(&root, &manifest, &trusting(&key)).unwrap(),
+            Verification::Signed { key_id: "release".to_string() }
+        );
+
+        std::fs::write(root.join("dist/index.js"), "export const answer = 43;").unwrap();
+        assert!(verify_package(&root, &manifest, &trusting(&key)).is_err());
+
+        // Widening the tools breaks the signature too, even with contents restored.
+        std::fs::write(root.join("dist/index.js"), "export const answer = 42;").unwrap();
+        let mut widened = manifest.clone();
+        widened.tools.push("support/delete".to_string());
+        assert!(verify_package(&root, &widened, &trusting(&key)).is_err());
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn untrusted_keys_and_unsigned_packages_are_refused_when_required() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let other = SigningKey::from_bytes(&[9; 32]);
+        let (root, manifest) = package("untrusted");
+        let signed = sign(&root, manifest.clone(), &other);
+
+        assert!(verify_package(&root, &signed, &trusting(&key)).is_err());
+        assert!(verify_package(&root, &manifest, &trusting(&key)).is_err());
+
+        let lenient = VerifyPolicy::default();
+        assert_eq!(verify_package(&root, &manifest, &lenient).unwrap(), Verification::Unsigned);
+        assert_eq!(
+            verify_package(&root, &signed, &lenient).unwrap(),
+            Verification::Unverified { key_id: "release".to_string() }
+        );
+        std::fs::remove_dir_all(&root).ok();
+    }
+}
@@ -0,0 +1,224 @@
+//! `--self-test`: boot every package, exercise it, report and exit.
+//!
+//! Meant as a CI gate before deploying packages. Unlike a normal start, a
+//! package that fails to boot does not stop the run; every package gets a
+//! report, and the exit status says whether all of them passed.
+//!
+//! Per agent the self-test checks that it boots, that every tool in its
+//! manifest and allowlist is registered, and that a smoke invocation succeeds.
+//! The smoke invocations are the `--self-test-invoke` calls naming the agent,
+//! or else a call of the manifest's `health_function` with `{}`. Runner-wide it
+//! applies `--config` and writes a probe event through the provenance store.
+
+use crate::AgentRunner;
+use crate::dynamic_config::ConfigReloader;
+use baml_rt_core::context;
+use baml_rt_provenance::{ProvEvent, ProvenanceWriter};
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+/// A function to call on a booted agent, from `--self-test-invoke`.
+#[derive(Debug, Clone)]
+pub(crate) struct SmokeInvocation {
+    pub(crate) agent: String,
+    pub(crate) function: String,
+    pub(crate) args: Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum CheckStatus {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct Check {
+    name: String,
+    status: CheckStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+impl Check {
+    fn passed(name: impl Into<String>, detail: Option<String>) -> Self {
+        Self { name: name.into(), status: CheckStatus::Passed, detail }
+    }
+
+    fn failed(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self { name: name.into(), status: CheckStatus::Failed, detail: Some(detail.into()) }
+    }
+
+    fn skipped(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self { name: name.into(), status: CheckStatus::Skipped, detail: Some(detail.into()) }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct AgentReport {
+    package: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    agent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+    passed: bool,
+    checks: Vec<Check>,
+}
+
+/// What `--self-test` prints as JSON on stdout.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct SelfTestReport {
+    pub(crate) passed: bool,
+    agents: Vec<AgentReport>,
+    /// Checks not tied to one package.
+    runner: Vec<Check>,
+}
+
+fn all_passed(checks: &[Check]) -> bool {
+    checks.iter().all(|check| check.status != CheckStatus::Failed)
+}
+
+/// Load every package into `runner` and run the checks.
+pub(crate) async fn run(
+    runner: &mut AgentRunner,
+    packages: &[PathBuf],
+    invocations: &[SmokeInvocation],
+    config_reloader: Option<&ConfigReloader>,
+    provenance_writer: Option<&dyn ProvenanceWriter>,
+) -> SelfTestReport {
+    let mut agents = Vec::with_capacity(packages.len());
+    let mut booted = HashSet::new();
+    for package in packages {
+        let mut report = AgentReport {
+            package: package.clone(),
+            agent: None,
+            version: None,
+            passed: false,
+            checks: Vec::new(),
+        };
+        match runner.load_agent(package).await {
+            Ok(name) => {
+                report.checks.push(Check::passed("boot", None));
+                report.checks.extend(check_agent(runner, &name, invocations).await);
+                report.version =
+                    runner.agents.get(&name).map(|agent| agent.manifest.version.clone());
+                booted.insert(name.clone());
+                report.agent = Some(name);
+            }
+            Err(err) => report.checks.push(Check::failed("boot", err.to_string())),
+        }
+        report.passed = all_passed(&report.checks);
+        agents.push(report);
+    }
+
+    let mut checks = Vec::new();
+    for invocation in invocations.iter().filter(|call| !booted.contains(&call.agent)) {
+        checks.push(Check::failed(
+            format!("smoke:{}.{}", invocation.agent, invocation.function),
+            format!("agent '{}' is not loaded", invocation.agent),
+        ));
+    }
+    checks.push(match config_reloader {
+        Some(reloader) => match reloader.reload("self_test").await {
+            Ok(outcome) => Check::passed(
+                "config",
+                Some(format!("version {} ({})", outcome.version, outcome.digest)),
+            ),
+            Err(err) => Check::failed("config", err.to_string()),
+        },
+        None => Check::skipped("config", "runner was started without --config"),
+    });
+    checks.push(check_provenance(provenance_writer).await);
+
+    SelfTestReport {
+        passed: all_passed(&checks) && agents.iter().all(|agent| agent.passed),
+        agents,
+        runner: checks,
+    }
+}
+
+async fn check_agent(
+    runner: &AgentRunner,
+    name: &str,
+    invocations: &[SmokeInvocation],
+) -> Vec<Check> {
+    let Some(booted) = runner.agents.get(name) else {
+        return vec![Check::failed("boot", format!("agent '{name}' is not registered"))];
+    };
+    let mut checks = Vec::new();
+
+    {
+        let runtime = booted.agent.runtime();
+        let manager = runtime.lock().await;
+        let registered: HashSet<String> = manager.list_tools().await.into_iter().collect();
+        let missing: Vec<&str> = booted
+            .manifest
+            .tools
+            .iter()
+            .filter(|tool| !registered.contains(*tool))
+            .map(String::as_str)
+            .collect();
+        checks.push(if !missing.is_empty() {
+            Check::failed("tools", format!("manifest tools not registered: {}", missing.join(", ")))
+        } else if let Err(err) = manager.validate_tool_allowlist_registered().await {
+            Check::failed("tools", err.to_string())
+        } else {
+            let count = booted.manifest.tools.len();
+            Check::passed("tools", Some(format!("{count} manifest tools registered")))
+        });
+    }
+
+    let mut calls: Vec<(String, Value)> = invocations
+        .iter()
+        .filter(|call| call.agent == name)
+        .map(|call| (call.function.clone(), call.args.clone()))
+        .collect();
+    if calls.is_empty()
+        && let Some(function) = &booted.manifest.health_function
+    {
+        calls.push((function.clone(), json!({})));
+    }
+    if calls.is_empty() {
+        checks.push(Check::skipped(
+            "smoke",
+            "no --self-test-invoke for this agent and no health_function in its manifest",
+        ));
+    }
+    for (function, args) in calls {
+        let check_name = format!("smoke:{function}");
+        checks.push(match runner.invoke(name, &function, args).await {
+            Ok(_) => Check::passed(check_name, None),
+            Err(err) => Check::failed(check_name, err.to_string()),
+        });
+    }
+    checks
+}
+
+/// Write a probe event and flush it, so a store that accepts health checks but
+/// rejects writes still fails.
+async fn check_provenance(writer: Option<&dyn ProvenanceWriter>) -> Check {
+    let Some(writer) = writer else {
+        return Check::skipped("provenance", "no provenance store configured");
+    };
+    if let Err(err) = writer.health_check().await {
+        return Check::failed("provenance", format!("health check failed: {err}"));
+    }
+    let probe = ProvEvent::console_message_global(
+        context::generate_context_id(),
+        "info".to_string(),
+        "runner self-test".to_string(),
+    );
+    if let Err(err) = writer.add_event(probe).await {
+        return Check::failed("provenance", format!("probe event rejected: {err}"));
+    }
+    if let Err(err) = writer.flush().await {
+        return Check::failed("provenance", format!("flush failed: {err}"));
+    }
+    match writer.pending_events().await {
+        0 => Check::passed("provenance", None),
+        pending => Check::failed("provenance", format!("{pending} events still pending after flush")),
+    }
+}
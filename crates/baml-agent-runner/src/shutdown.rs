@@ -0,0 +1,184 @@
+//! Graceful shutdown: stop taking requests, let the running ones finish, stop.
+//!
+//! The first Ctrl-C or SIGTERM starts draining. New A2A requests are refused
+//! with a "shutting down" error and the transports stop accepting
+//! connections, while requests already running get up to the drain timeout to
+//! finish, along with the function calls and tool sessions they started. The
+//! runner then cancels the tasks its agents left unfinished, runs their
+//! `onShutdown` hooks and flushes provenance. A second signal stops the runner
+//! at once.
+
+use baml_rt_quickjs::WorkTracker;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::warn;
+
+/// How often [`ShutdownController::drain`] checks whether the work is done.
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+struct Inner {
+    draining: watch::Sender<bool>,
+    in_flight: AtomicUsize,
+}
+
+/// Tracks the requests in flight and whether the runner is draining
+///
+/// Clones share their state, so the signal handler can start draining what
+/// the transports track.
+#[derive(Clone)]
+pub(crate) struct ShutdownController {
+    inner: Arc<Inner>,
+}
+
+impl ShutdownController {
+    pub(crate) fn new() -> Self {
+        let (draining, _) = watch::channel(false);
+        Self { inner: Arc::new(Inner { draining, in_flight: AtomicUsize::new(0) }) }
+    }
+
+    /// Stop taking requests. Returns `false` if draining had already begun.
+    pub(crate) fn begin(&self) -> bool {
+        !self.inner.draining.send_replace(true)
+    }
+
+    pub(crate) fn is_draining(&self) -> bool {
+        *self.inner.draining.borrow()
+    }
+
+    /// Resolves once draining has begun.
+    pub(crate) async fn draining(&self) {
+        let mut draining = self.inner.draining.subscribe();
+        // The sender lives in `self`, so the channel cannot close while we wait.
+        let _ = draining.wait_for(|draining| *draining).await;
+    }
+
+    /// Count a request as in flight until the returned guard is dropped;
+    /// `None` once draining has begun, when the request should be refused.
+    pub(crate) fn track(&self) -> Option<InFlight> {
+        self.inner.in_flight.fetch_add(1, Ordering::SeqCst);
+        let guard = InFlight { inner: self.inner.clone() };
+        // Checked after counting, so `drain` never misses a request that got in.
+        (!self.is_draining()).then_some(guard)
+    }
+
+    pub(crate) fn in_flight(&self) -> usize {
+        self.inner.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Wait up to `timeout` for the tracked requests to finish and for every
+    /// runtime in `work` to go idle. Returns whether everything finished.
+    pub(crate) async fn drain(&self, work: &[WorkTracker], timeout: Duration) -> bool {
+        let settled = async {
+            while self.in_flight() > 0 || !work.iter().all(|work| work.snapshot().is_idle()) {
+                tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+            }
+        };
+        tokio::time::timeout(timeout, settled).await.is_ok()
+    }
+
+    /// Run `serving` until it ends, or until draining began `timeout` ago.
+    pub(crate) async fn serve_until_drained<F, T>(&self, serving: F, timeout: Duration) -> Option<T>
+    where
+        F: Future<Output = T>,
+    {
+        let deadline = async {
+            self.draining().await;
+            tokio::time::sleep(timeout).await;
+        };
+        tokio::select! {
+            served = serving => Some(served),
+            () = deadline => {
+                warn!(
+                    in_flight = self.in_flight(),
+                    timeout_secs = timeout.as_secs(),
+                    "Requests still running at the shutdown deadline; stopping anyway"
+                );
+                None
+            }
+        }
+    }
+}
+
+/// A request counted by [`ShutdownController::track`].
+pub(crate) struct InFlight {
+    inner: Arc<Inner>,
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.inner.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Begin draining `controller` on the first Ctrl-C or SIGTERM, and exit on the
+/// second.
+pub(crate) fn spawn_signal_handler(controller: ShutdownController) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        if controller.begin() {
+            warn!("Shutting down; finishing in-flight requests (signal again to stop now)");
+        }
+        shutdown_signal().await;
+        warn!("Second shutdown signal; stopping now");
+        std::process::exit(130);
+    })
+}
+
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+                return;
+            }
+            Err(err) => warn!(error = %err, "Failed to listen for SIGTERM"),
+        }
+    }
+    if let Err(err) = tokio::signal::ctrl_c().await {
+        warn!(error = %err, "Failed to listen for Ctrl-C");
+        std::future::pending::<()>().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn draining_refuses_new_requests_and_waits_for_running_ones() {
+        let controller = ShutdownController::new();
+        let running = controller.track().expect("accepted before draining");
+        assert!(controller.begin());
+        assert!(!controller.begin(), "already draining");
+        assert!(controller.track().is_none(), "refused while draining");
+        assert_eq!(controller.in_flight(), 1);
+
+        assert!(!controller.drain(&[], Duration::from_millis(100)).await, "still running");
+        let finishing = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            drop(running);
+        });
+        assert!(controller.drain(&[], Duration::from_secs(5)).await);
+        finishing.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn serving_is_cut_off_a_timeout_after_draining_begins() {
+        let controller = ShutdownController::new();
+        assert_eq!(controller.serve_until_drained(async { 7 }, Duration::ZERO).await, Some(7));
+
+        controller.begin();
+        let stuck = std::future::pending::<()>();
+        let served = controller.serve_until_drained(stuck, Duration::from_millis(20)).await;
+        assert_eq!(served, None);
+    }
+}
@@ -351,6 +351,281 @@ async fn test_e2e_agent_runner_invoke_function() {
     fs::remove_file(&package_path).ok();
 }
 
+#[tokio::test]
+async fn test_e2e_agent_runner_self_test_reports_every_package() {
+    let package_path = std::env::temp_dir().join("e2e-test-agent-self-test.tar.gz");
+    create_test_agent_package(&package_path)
+        .expect("Failed to create test agent package");
+    let missing_path = std::env::temp_dir().join("e2e-self-test-missing.tar.gz");
+
+    let output = agent_runner_command()
+        .arg(package_path.to_str().unwrap())
+        .arg(missing_path.to_str().unwrap())
+        .arg("--self-test")
+        .output()
+        .expect("Failed to execute binary");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let report: serde_json::Value = serde_json::from_str(&stdout)
+        .unwrap_or_else(|err| panic!("Self-test should print a JSON report ({err}): {stdout}"));
+
+    assert!(!output.status.success(), "A package that fails to boot fails the self-test");
+    assert_eq!(report["passed"], json!(false));
+    let agents = report["agents"].as_array().expect("agents array");
+    assert_eq!(agents.len(), 2);
+
+    assert_eq!(agents[0]["agent"], json!("test-agent"));
+    let checks = agents[0]["checks"].as_array().unwrap();
+    let status = |name: &str| {
+        checks
+            .iter()
+            .find(|check| check["name"] == json!(name))
+            .map(|check| check["status"].clone())
+    };
+    assert_eq!(status("boot"), Some(json!("passed")));
+    assert_eq!(status("smoke"), Some(json!("skipped")));
+    // The runner hosts no `test` bundle, so the manifest's test tool cannot resolve.
+    let tools = checks.iter().find(|check| check["name"] == json!("tools")).unwrap();
+    assert_eq!(tools["status"], json!("failed"));
+    assert!(tools["detail"].as_str().unwrap().contains("test/add_numbers"), "{tools}");
+
+    assert_eq!(agents[1]["passed"], json!(false));
+    assert_eq!(agents[1]["checks"][0]["name"], json!("boot"));
+    assert_eq!(agents[1]["checks"][0]["status"], json!("failed"));
+
+    let runner_checks = report["runner"].as_array().unwrap();
+    assert!(
+        runner_checks
+            .iter()
+            .any(|check| check["name"] == json!("provenance") && check["status"] == json!("passed")),
+        "In-memory provenance write path should pass: {report}"
+    );
+
+    fs::remove_file(&package_path).ok();
+}
+
+#[tokio::test]
+async fn test_e2e_agent_runner_serves_allowlisted_tools_over_mcp() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let package_path = std::env::temp_dir().join("e2e-test-agent-mcp.tar.gz");
+    create_test_agent_package(&package_path)
+        .expect("Failed to create test agent package");
+
+    let requests = [
+        json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {
+            "protocolVersion": "2025-06-18",
+            "capabilities": {},
+            "clientInfo": {"name": "runner-test", "version": "0.0.0"}
+        }}),
+        json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+        json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}),
+        json!({"jsonrpc": "2.0", "id": 3, "method": "tools/call", "params": {
+            "name": "support/calculate",
+            "arguments": {"expression": {"left": 2, "operation": "Add", "right": 3}}
+        }}),
+        json!({"jsonrpc": "2.0", "id": 4, "method": "tools/call", "params": {
+            "name": "support/undeclared", "arguments": {}
+        }}),
+    ];
+    let mut child = agent_runner_command()
+        .arg(package_path.to_str().unwrap())
+        .arg("--mcp-stdio")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to start binary");
+    {
+        let mut stdin = child.stdin.take().unwrap();
+        for request in &requests {
+            writeln!(stdin, "{request}").unwrap();
+        }
+    }
+    let output = child.wait_with_output().expect("Failed to wait for binary");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let responses: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|line| {
+            serde_json::from_str(line)
+                .unwrap_or_else(|err| panic!("stdout must hold only JSON-RPC ({err}): {line}"))
+        })
+        .collect();
+
+    assert_eq!(responses.len(), 4, "The notification gets no response: {stdout}");
+    assert_eq!(responses[0]["result"]["protocolVersion"], json!("2025-06-18"));
+
+    let names: Vec<&str> = responses[1]["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tool| tool["name"].as_str().unwrap())
+        .collect();
+    assert!(names.contains(&"support/calculate"), "{names:?}");
+
+    let call = &responses[2]["result"];
+    assert_eq!(call["isError"], json!(false), "{call}");
+    assert_eq!(call["structuredContent"]["result"], json!(5.0));
+
+    assert_eq!(responses[3]["error"]["code"], json!(-32602));
+
+    fs::remove_file(&package_path).ok();
+}
+
+#[tokio::test]
+async fn test_e2e_agent_runner_answers_concurrent_stdio_requests_by_id() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let package_path = std::env::temp_dir().join("e2e-test-agent-stdio-concurrent.tar.gz");
+    create_test_agent_package(&package_path)
+        .expect("Failed to create test agent package");
+
+    let requests = [
+        json!({"jsonrpc": "2.0", "id": "card-1", "method": "agent.getCard"}),
+        json!({"jsonrpc": "2.0", "id": "task-a", "method": "tasks.get", "params": {"id": "missing-task"}}),
+        json!({"jsonrpc": "2.0", "id": "card-2", "method": "agent.getCard"}),
+        json!({"jsonrpc": "2.0", "id": "task-b", "method": "tasks.get", "params": {"id": "missing-task"}}),
+    ];
+    let mut child = agent_runner_command()
+        .arg(package_path.to_str().unwrap())
+        .arg("--a2a-stdio")
+        .arg("--a2a-stdio-concurrency")
+        .arg("4")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to start binary");
+    {
+        let mut stdin = child.stdin.take().unwrap();
+        for request in &requests {
+            writeln!(stdin, "{request}").unwrap();
+        }
+    }
+    let output = child.wait_with_output().expect("Failed to wait for binary");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let ids: Vec<String> = stdout
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter_map(|response| response["id"].as_str().map(str::to_string))
+        .collect();
+
+    let mut answered = ids.clone();
+    answered.sort();
+    assert_eq!(answered, vec!["card-1", "card-2", "task-a", "task-b"], "{stdout}");
+    let position = |id: &str| ids.iter().position(|seen| seen == id).unwrap();
+    assert!(position("task-a") < position("task-b"), "same-task requests stay in order: {stdout}");
+
+    fs::remove_file(&package_path).ok();
+}
+
+/// Send `requests` to a runner serving `package_path` over stdio with `args`,
+/// returning its responses by id.
+fn stdio_responses(
+    package_path: &Path,
+    args: &[&str],
+    requests: &[serde_json::Value],
+) -> std::collections::HashMap<String, serde_json::Value> {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let mut child = agent_runner_command()
+        .arg(package_path.to_str().unwrap())
+        .arg("--a2a-stdio")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to start binary");
+    {
+        let mut stdin = child.stdin.take().unwrap();
+        for request in requests {
+            writeln!(stdin, "{request}").unwrap();
+        }
+    }
+    let output = child.wait_with_output().expect("Failed to wait for binary");
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter_map(|response| Some((response["id"].as_str()?.to_string(), response)))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_e2e_agent_runner_reload_config_requires_an_operator_token() {
+    let package_path = std::env::temp_dir().join("e2e-test-agent-reload-config.tar.gz");
+    create_test_agent_package(&package_path).expect("Failed to create test agent package");
+    let operators_path = std::env::temp_dir().join("e2e-test-reload-config-operators.json");
+    fs::write(&operators_path, json!({"ops": "ops-token"}).to_string()).unwrap();
+
+    let requests = [
+        json!({"jsonrpc": "2.0", "id": "anonymous", "method": "admin.reloadConfig"}),
+        json!({
+            "jsonrpc": "2.0",
+            "id": "guessed",
+            "method": "admin.reloadConfig",
+            "params": {"operatorToken": "guess"},
+        }),
+        json!({
+            "jsonrpc": "2.0",
+            "id": "operator",
+            "method": "admin.reloadConfig",
+            "params": {"operatorToken": "ops-token"},
+        }),
+    ];
+    let responses = stdio_responses(
+        &package_path,
+        &["--operators", operators_path.to_str().unwrap()],
+        &requests,
+    );
+
+    for id in ["anonymous", "guessed"] {
+        let details = &responses[id]["error"]["data"]["details"];
+        assert_eq!(details, &json!("A valid operator token is required"), "{responses:?}");
+    }
+    // Past authentication, the runner reports it has no --config to reload.
+    assert_eq!(responses["operator"]["error"]["message"], json!("Config reload unavailable"));
+
+    fs::remove_file(&package_path).ok();
+    fs::remove_file(&operators_path).ok();
+}
+
+#[tokio::test]
+async fn test_e2e_agent_runner_reload_schema_requires_an_operator_and_the_booted_source() {
+    let package_path = std::env::temp_dir().join("e2e-test-agent-reload-schema.tar.gz");
+    create_test_agent_package(&package_path).expect("Failed to create test agent package");
+    let operators_path = std::env::temp_dir().join("e2e-test-reload-schema-operators.json");
+    fs::write(&operators_path, json!({"ops": "ops-token"}).to_string()).unwrap();
+    let elsewhere = std::env::temp_dir().join("e2e-test-reload-schema-elsewhere");
+    fs::create_dir_all(&elsewhere).unwrap();
+
+    let reload = |id: &str, params: serde_json::Value| {
+        json!({"jsonrpc": "2.0", "id": id, "method": "admin.reloadSchema", "params": params})
+    };
+    let requests = [
+        reload("anonymous", json!({})),
+        // A path in the params is ignored: only the booted baml_src is reloaded.
+        reload("operator", json!({"operatorToken": "ops-token", "baml_src": elsewhere})),
+    ];
+    let responses = stdio_responses(
+        &package_path,
+        &["--operators", operators_path.to_str().unwrap()],
+        &requests,
+    );
+
+    let details = &responses["anonymous"]["error"]["data"]["details"];
+    assert_eq!(details, &json!("A valid operator token is required"), "{responses:?}");
+    let reloaded = &responses["operator"]["result"];
+    assert_eq!(reloaded["digest"], reloaded["previous_digest"], "{responses:?}");
+
+    fs::remove_file(&package_path).ok();
+    fs::remove_file(&operators_path).ok();
+    fs::remove_dir_all(&elsewhere).ok();
+}
+
 fn agent_runner_command() -> Command {
     let mut command = Command::new("cargo");
     command
@@ -0,0 +1,206 @@
+//! Two agents in one process: a coordinator that delegates to a specialist over
+//! the `a2a/session` tool, with both writing to one in-memory provenance store.
+//!
+//! The example drives a single `message.send` through the coordinator and then
+//! checks the provenance graph the conversation left behind:
+//!
+//! - both agents recorded their task, each under its own agent id;
+//! - the coordinator's delegation shows up as an `a2a/session` tool call that
+//!   starts before the specialist's task and completes successfully;
+//! - the specialist's work is filed under the coordinator's context, so one
+//!   context query returns the whole pipeline.
+//!
+//! Run with `cargo run -p baml-rt-a2a --example delegation_pipeline`. It exits
+//! non-zero if the graph does not have that shape.
+
+use baml_rt_a2a::a2a_types::{
+    A2aMessageId, JSONRPCId, JSONRPCRequest, Message, MessageRole, Part, ROLE_USER,
+    SendMessageRequest,
+};
+use baml_rt_a2a::{A2aAgent, A2aRequestHandler, A2aSessionBundle};
+use baml_rt_core::ids::{ContextId, ExternalId};
+use baml_rt_core::{BamlRtError, Result};
+use baml_rt_provenance::vocabulary::a2a_types;
+use baml_rt_provenance::{
+    InMemoryProvenanceStore, ProvEventData, ProvenanceQuery, ProvenanceReader,
+};
+use baml_rt_quickjs::BamlRuntimeManager;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const SPECIALIST_JS: &str = r#"
+    globalThis.handle_a2a_request = async function(request) {
+        const message = request?.params?.message ?? {};
+        const text = message.parts?.[0]?.text ?? "";
+        return {
+            task: {
+                id: "task-specialist",
+                contextId: message.contextId,
+                status: { state: "TASK_STATE_COMPLETED" },
+                history: [],
+                artifacts: [{ artifactId: "summary", parts: [{ text: `summary of ${text}` }] }]
+            }
+        };
+    };
+"#;
+
+const COORDINATOR_JS: &str = r#"
+    globalThis.handle_a2a_request = async function(request) {
+        const message = request?.params?.message ?? {};
+        const session = await openToolSession("a2a/session");
+        await session.send({
+            request: {
+                jsonrpc: "2.0",
+                id: "delegate-1",
+                method: "message.send",
+                params: {
+                    message: {
+                        messageId: "delegated-1",
+                        role: "ROLE_USER",
+                        contextId: message.contextId,
+                        parts: [{ text: message.parts?.[0]?.text ?? "" }]
+                    }
+                }
+            }
+        });
+        const step = await session.continue();
+        await session.finish();
+        const delegated = step?.output?.response?.result?.task;
+        return {
+            task: {
+                id: "task-coordinator",
+                contextId: message.contextId,
+                status: { state: "TASK_STATE_COMPLETED" },
+                history: [],
+                artifacts: delegated?.artifacts ?? []
+            }
+        };
+    };
+"#;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let store = Arc::new(InMemoryProvenanceStore::new());
+
+    let specialist = A2aAgent::builder()
+        .with_provenance_writer(store.clone())
+        .with_init_js(SPECIALIST_JS)
+        .build()
+        .await?;
+
+    // The coordinator reaches the specialist through its tool registry, exactly as
+    // it would reach a remote agent behind a transport.
+    let runtime = BamlRuntimeManager::new()?;
+    runtime
+        .tool_registry()
+        .lock()
+        .await
+        .register_bundle(A2aSessionBundle::new(Arc::new(specialist.clone())))?;
+    let coordinator = A2aAgent::builder()
+        .with_runtime_manager(runtime)
+        .with_provenance_writer(store.clone())
+        .with_init_js(COORDINATOR_JS)
+        .build()
+        .await?;
+
+    let context_id = ContextId::new(1, 1);
+    let responses =
+        coordinator.handle_a2a(send_request(&context_id, "the quarterly report")?).await?;
+    let task = responses
+        .first()
+        .and_then(|response| response.pointer("/result/task"))
+        .ok_or_else(|| shape_error("coordinator returned no task"))?;
+    println!("coordinator task: {}", serde_json::to_string_pretty(task)?);
+    check(
+        task.pointer("/artifacts/0/parts/0/text")
+            == Some(&json!("summary of the quarterly report")),
+        "coordinator task carries the specialist's artifact",
+    )?;
+
+    let events = store.events().await;
+    let created = |agent: &A2aAgent, task_id: &str| {
+        events.iter().position(|event| {
+            matches!(
+                event.data(),
+                ProvEventData::TaskCreated { task_id: id, agent_id }
+                    if id.as_str() == task_id && agent_id == agent.agent_id()
+            )
+        })
+    };
+    let delegation_started = events.iter().position(|event| {
+        matches!(event.data(), ProvEventData::ToolCallStarted { tool_name, .. } if tool_name == "a2a/session")
+    });
+    let delegation_succeeded = events.iter().any(|event| {
+        matches!(
+            event.data(),
+            ProvEventData::ToolCallCompleted { tool_name, success: true, .. } if tool_name == "a2a/session"
+        )
+    });
+    let coordinator_task = created(&coordinator, "task-coordinator");
+    let specialist_task = created(&specialist, "task-specialist");
+
+    check(coordinator_task.is_some(), "coordinator task is attributed to the coordinator")?;
+    check(specialist_task.is_some(), "specialist task is attributed to the specialist")?;
+    check(delegation_succeeded, "delegation completed successfully")?;
+    check(
+        matches!((delegation_started, specialist_task), (Some(started), Some(task)) if started < task),
+        "delegation starts before the specialist's task",
+    )?;
+
+    let nodes = store.query_nodes(&ProvenanceQuery::default().for_context(context_id)).await?;
+    let count = |prov_type: &str| {
+        nodes.iter().filter(|node| node.prov_type.as_deref() == Some(prov_type)).count()
+    };
+    println!(
+        "context graph: {} nodes, {} tasks, {} tool calls",
+        nodes.len(),
+        count(a2a_types::TASK),
+        count(a2a_types::TOOL_CALL)
+    );
+    check(count(a2a_types::TASK) == 2, "both tasks are filed under the shared context")?;
+    check(count(a2a_types::TOOL_CALL) >= 1, "the delegation tool call is in the context")?;
+
+    println!("provenance graph has the expected shape");
+    Ok(())
+}
+
+fn send_request(context_id: &ContextId, text: &str) -> Result<Value> {
+    let params = SendMessageRequest {
+        message: Message {
+            message_id: A2aMessageId::incoming(ExternalId::new("msg-1")),
+            role: MessageRole::String(ROLE_USER.to_string()),
+            parts: vec![Part { text: Some(text.to_string()), ..Part::default() }],
+            context_id: Some(context_id.clone()),
+            task_id: None,
+            reference_task_ids: Vec::new(),
+            extensions: Vec::new(),
+            metadata: None,
+            extra: HashMap::new(),
+        },
+        configuration: None,
+        metadata: None,
+        tenant: None,
+        extra: HashMap::new(),
+    };
+    let request = JSONRPCRequest {
+        jsonrpc: "2.0".to_string(),
+        method: "message.send".to_string(),
+        params: Some(serde_json::to_value(params)?),
+        id: Some(JSONRPCId::String("pipeline-1".to_string())),
+    };
+    Ok(serde_json::to_value(request)?)
+}
+
+fn check(condition: bool, what: &str) -> Result<()> {
+    if condition {
+        println!("ok: {what}");
+        Ok(())
+    } else {
+        Err(shape_error(what))
+    }
+}
+
+fn shape_error(what: &str) -> BamlRtError {
+    BamlRtError::InvalidArgument(format!("unexpected provenance graph: {what}"))
+}
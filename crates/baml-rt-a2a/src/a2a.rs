@@ -3,12 +3,20 @@
 //! This provides a thin adapter layer without adding external dependencies.
 
 use crate::a2a_types::{
-    JSONRPCError, JSONRPCErrorResponse, JSONRPCId, JSONRPCRequest, JSONRPCSuccessResponse,
-    ListTasksRequest, Message, SendMessageRequest,
+    ForkContextRequest, GetTaskPushNotificationConfigRequest, JSONRPCError, JSONRPCErrorResponse,
+    JSONRPCId, JSONRPCRequest, JSONRPCSuccessResponse, ListMessagesRequest, ListTasksRequest,
+    Message, SendMessageRequest, SetCaptureDetailRequest, SubmitFeedbackRequest,
+    TaskPushNotificationConfig,
 };
+use crate::chunk_stream::ChunkStream;
 use baml_rt_core::{BamlRtError, Result};
-use baml_rt_core::context;
+use baml_rt_core::context::{
+    self, DELEGATION_CHAIN_METADATA_KEY, PARENT_CONTEXT_ID_METADATA_KEY,
+};
 use baml_rt_core::ids::{ContextId, ExternalId, MessageId, TaskId};
+use baml_rt_core::trace_context::{TraceContext, TRACEPARENT_METADATA_KEY};
+use baml_rt_observability::spans;
+use baml_rt_provenance::CALLING_AGENT_ID_METADATA_KEY;
 use serde_json::{json, Map, Value};
 
 const JSONRPC_VERSION: &str = "2.0";
@@ -21,9 +29,44 @@ pub enum A2aMethod {
     TasksList,
     TasksCancel,
     TasksSubscribe,
+    TasksPollUpdates,
+    MessageFeedback,
+    ContextsFork,
+    MessageList,
+    ContextsList,
+    AgentCapabilities,
+    AgentGetCard,
+    AdminSetCaptureDetail,
+    AdminActiveWork,
+    AdminListApprovals,
+    AdminDecideApproval,
+    TasksSetPushNotificationConfig,
+    TasksGetPushNotificationConfig,
 }
 
 impl A2aMethod {
+    pub const ALL: [A2aMethod; 19] = [
+        A2aMethod::MessageSend,
+        A2aMethod::MessageSendStream,
+        A2aMethod::TasksGet,
+        A2aMethod::TasksList,
+        A2aMethod::TasksCancel,
+        A2aMethod::TasksSubscribe,
+        A2aMethod::TasksPollUpdates,
+        A2aMethod::MessageFeedback,
+        A2aMethod::ContextsFork,
+        A2aMethod::MessageList,
+        A2aMethod::ContextsList,
+        A2aMethod::AgentCapabilities,
+        A2aMethod::AgentGetCard,
+        A2aMethod::AdminSetCaptureDetail,
+        A2aMethod::AdminActiveWork,
+        A2aMethod::AdminListApprovals,
+        A2aMethod::AdminDecideApproval,
+        A2aMethod::TasksSetPushNotificationConfig,
+        A2aMethod::TasksGetPushNotificationConfig,
+    ];
+
     pub fn as_str(&self) -> &'static str {
         match self {
             A2aMethod::MessageSend => "message.send",
@@ -32,6 +75,19 @@ impl A2aMethod {
             A2aMethod::TasksList => "tasks.list",
             A2aMethod::TasksCancel => "tasks.cancel",
             A2aMethod::TasksSubscribe => "tasks.subscribe",
+            A2aMethod::TasksPollUpdates => "tasks.pollUpdates",
+            A2aMethod::MessageFeedback => "message.feedback",
+            A2aMethod::ContextsFork => "contexts.fork",
+            A2aMethod::MessageList => "message.list",
+            A2aMethod::ContextsList => "contexts.list",
+            A2aMethod::AgentCapabilities => "agent.capabilities",
+            A2aMethod::AgentGetCard => "agent.getCard",
+            A2aMethod::AdminSetCaptureDetail => "admin.setCaptureDetail",
+            A2aMethod::AdminActiveWork => "admin.activeWork",
+            A2aMethod::AdminListApprovals => "admin.listApprovals",
+            A2aMethod::AdminDecideApproval => "admin.decideApproval",
+            A2aMethod::TasksSetPushNotificationConfig => "tasks.pushNotificationConfig.set",
+            A2aMethod::TasksGetPushNotificationConfig => "tasks.pushNotificationConfig.get",
         }
     }
 }
@@ -47,6 +103,27 @@ impl std::str::FromStr for A2aMethod {
             "tasks.list" => Ok(A2aMethod::TasksList),
             "tasks.cancel" => Ok(A2aMethod::TasksCancel),
             "tasks.subscribe" => Ok(A2aMethod::TasksSubscribe),
+            "tasks.pollUpdates" | "tasks/pollUpdates" => Ok(A2aMethod::TasksPollUpdates),
+            "message.feedback" => Ok(A2aMethod::MessageFeedback),
+            "contexts.fork" => Ok(A2aMethod::ContextsFork),
+            "message.list" | "message/list" => Ok(A2aMethod::MessageList),
+            "contexts.list" | "contexts/list" => Ok(A2aMethod::ContextsList),
+            "agent.capabilities" | "agent/capabilities" => Ok(A2aMethod::AgentCapabilities),
+            "agent.getCard" | "agent/getCard" => Ok(A2aMethod::AgentGetCard),
+            "admin.setCaptureDetail" | "admin/setCaptureDetail" => {
+                Ok(A2aMethod::AdminSetCaptureDetail)
+            }
+            "admin.activeWork" | "admin/activeWork" => Ok(A2aMethod::AdminActiveWork),
+            "admin.listApprovals" | "admin/listApprovals" => Ok(A2aMethod::AdminListApprovals),
+            "admin.decideApproval" | "admin/decideApproval" => {
+                Ok(A2aMethod::AdminDecideApproval)
+            }
+            "tasks.pushNotificationConfig.set" | "tasks/pushNotificationConfig/set" => {
+                Ok(A2aMethod::TasksSetPushNotificationConfig)
+            }
+            "tasks.pushNotificationConfig.get" | "tasks/pushNotificationConfig/get" => {
+                Ok(A2aMethod::TasksGetPushNotificationConfig)
+            }
             _ => Err(BamlRtError::InvalidArgument(
                 "Unsupported A2A request method".to_string(),
             )),
@@ -63,6 +140,16 @@ pub struct A2aRequest {
     pub context_id: Option<ContextId>,
     pub message_id: Option<MessageId>,
     pub task_id: Option<TaskId>,
+    /// `tenant` from the request params; scopes the request to that tenant's tasks.
+    pub tenant: Option<String>,
+    /// W3C trace context from the message metadata (`traceparent`, `tracestate`).
+    pub trace_context: Option<TraceContext>,
+    /// `parent_context_id` from the message metadata: the context this
+    /// message's context is a child of.
+    pub parent_context_id: Option<ContextId>,
+    /// `delegation_chain` from the message metadata: the agents whose
+    /// delegations led to this message, outermost first.
+    pub delegation_chain: Vec<String>,
 }
 
 impl A2aRequest {
@@ -113,8 +200,12 @@ impl A2aRequest {
             A2aMethod::TasksGet
             | A2aMethod::TasksList
             | A2aMethod::TasksCancel
-            | A2aMethod::TasksSubscribe => {
-                if matches!(method, A2aMethod::TasksGet | A2aMethod::TasksCancel)
+            | A2aMethod::TasksSubscribe
+            | A2aMethod::TasksPollUpdates => {
+                if matches!(
+                    method,
+                    A2aMethod::TasksGet | A2aMethod::TasksCancel | A2aMethod::TasksPollUpdates
+                )
                     && let Some(id) = params_value.get("id").and_then(Value::as_str)
                 {
                     task_id = Some(TaskId::from_external(ExternalId::new(id)));
@@ -136,8 +227,65 @@ impl A2aRequest {
                     .unwrap_or(false)
                     && method == A2aMethod::TasksSubscribe
             }
+            A2aMethod::MessageFeedback => {
+                let params: SubmitFeedbackRequest =
+                    serde_json::from_value(params_value.clone()).map_err(BamlRtError::Json)?;
+                context_id = params.context_id;
+                message_id = params.message_id;
+                task_id = params.task_id;
+                false
+            }
+            A2aMethod::ContextsFork => {
+                let params: ForkContextRequest =
+                    serde_json::from_value(params_value.clone()).map_err(BamlRtError::Json)?;
+                context_id = Some(params.context_id);
+                message_id = Some(params.message_id);
+                false
+            }
+            A2aMethod::MessageList => {
+                let params: ListMessagesRequest =
+                    serde_json::from_value(params_value.clone()).map_err(BamlRtError::Json)?;
+                context_id = Some(params.context_id);
+                false
+            }
+            A2aMethod::ContextsList
+            | A2aMethod::AgentCapabilities
+            | A2aMethod::AgentGetCard
+            | A2aMethod::AdminActiveWork
+            | A2aMethod::AdminListApprovals
+            | A2aMethod::AdminDecideApproval => false,
+            A2aMethod::TasksSetPushNotificationConfig => {
+                let params: TaskPushNotificationConfig =
+                    serde_json::from_value(params_value.clone()).map_err(BamlRtError::Json)?;
+                task_id = Some(params.task_id);
+                false
+            }
+            A2aMethod::TasksGetPushNotificationConfig => {
+                let params: GetTaskPushNotificationConfigRequest =
+                    serde_json::from_value(params_value.clone()).map_err(BamlRtError::Json)?;
+                task_id = Some(params.id);
+                false
+            }
+            A2aMethod::AdminSetCaptureDetail => {
+                let params: SetCaptureDetailRequest =
+                    serde_json::from_value(params_value.clone()).map_err(BamlRtError::Json)?;
+                context_id = params.context_id;
+                false
+            }
         };
 
+        let tenant = params_value.get("tenant").and_then(Value::as_str).map(str::to_string);
+        let trace_context = ["/message/metadata", "/metadata"]
+            .iter()
+            .filter_map(|pointer| params_value.pointer(pointer).and_then(Value::as_object))
+            .find_map(TraceContext::from_metadata);
+        let (parent_context_id, delegation_chain) = match method {
+            A2aMethod::MessageSend | A2aMethod::MessageSendStream => (
+                parent_context_of(&params_value, context_id.as_ref())?,
+                delegation_chain_of(&params_value)?,
+            ),
+            _ => (None, Vec::new()),
+        };
         params_value = normalize_params(params_value);
         if let Value::Object(mut map) = params_value {
             map.remove("stream");
@@ -152,6 +300,10 @@ impl A2aRequest {
             context_id,
             message_id,
             task_id,
+            tenant,
+            trace_context,
+            parent_context_id,
+            delegation_chain,
         })
     }
 
@@ -160,10 +312,108 @@ impl A2aRequest {
     }
 }
 
-#[derive(Debug)]
+/// Put the current trace context into the message of an outbound
+/// `message.send` or `message.sendStream` request, so the receiving agent's
+/// spans join this trace (see [`spans::outgoing_trace_context`]).
+///
+/// A `traceparent` already in the message metadata is kept. Signed messages
+/// must be signed after this, since the signature covers the metadata.
+pub fn inject_trace_context(request: &mut Value) {
+    let Some(message) = request.pointer_mut("/params/message").filter(|message| message.is_object())
+    else {
+        return;
+    };
+    if message.pointer(&format!("/metadata/{TRACEPARENT_METADATA_KEY}")).is_some() {
+        return;
+    }
+    let Some(trace_context) = spans::outgoing_trace_context() else {
+        return;
+    };
+    let metadata = &mut message["metadata"];
+    if !metadata.is_object() {
+        *metadata = json!({});
+    }
+    if let Some(metadata) = metadata.as_object_mut() {
+        trace_context.insert_into(metadata);
+    }
+}
+
+/// Name the agent in the current scope as `calling_agent_id` in the metadata
+/// of an outgoing `message.send` or `message.sendStream` request, so the
+/// receiving agent's provenance records that it acted on the caller's behalf.
+///
+/// An existing `calling_agent_id` is kept, as is a request made outside any
+/// agent's scope.
+pub fn inject_calling_agent(request: &mut Value) {
+    let Some(message) = request.pointer_mut("/params/message").filter(|message| message.is_object())
+    else {
+        return;
+    };
+    if message.pointer(&format!("/metadata/{CALLING_AGENT_ID_METADATA_KEY}")).is_some() {
+        return;
+    }
+    let Some(agent_id) = context::current_agent_id() else {
+        return;
+    };
+    let metadata = &mut message["metadata"];
+    if !metadata.is_object() {
+        *metadata = json!({});
+    }
+    if let Some(metadata) = metadata.as_object_mut() {
+        metadata.insert(
+            CALLING_AGENT_ID_METADATA_KEY.to_string(),
+            Value::String(agent_id.as_str().to_string()),
+        );
+    }
+}
+
+/// Name the context in the current scope as `parent_context_id` in the
+/// metadata of an outgoing `message.send` or `message.sendStream` request, so
+/// the context the receiving agent handles it in becomes its child.
+///
+/// An existing `parent_context_id` is kept, as is a message sent into the
+/// current context itself or outside any scope.
+pub fn inject_parent_context(request: &mut Value) {
+    let Some(message) = request.pointer_mut("/params/message").filter(|message| message.is_object())
+    else {
+        return;
+    };
+    if message.pointer(&format!("/metadata/{PARENT_CONTEXT_ID_METADATA_KEY}")).is_some() {
+        return;
+    }
+    let Some(context_id) = context::current_context_id() else {
+        return;
+    };
+    if message.get("contextId").and_then(Value::as_str) == Some(context_id.as_str()) {
+        return;
+    }
+    let metadata = &mut message["metadata"];
+    if !metadata.is_object() {
+        *metadata = json!({});
+    }
+    if let Some(metadata) = metadata.as_object_mut() {
+        metadata.insert(
+            PARENT_CONTEXT_ID_METADATA_KEY.to_string(),
+            Value::String(context_id.into_string()),
+        );
+    }
+}
+
 pub enum A2aOutcome {
     Response(Value),
     Stream(Vec<Value>),
+    /// Stream chunks still being produced.
+    Chunks(ChunkStream<'static>),
+}
+
+impl std::fmt::Debug for A2aOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            A2aOutcome::Response(value) => f.debug_tuple("Response").field(value).finish(),
+            A2aOutcome::Stream(chunks) => f.debug_tuple("Stream").field(chunks).finish(),
+            A2aOutcome::Chunks(_) => f.write_str("Chunks(..)"),
+        }
+    }
 }
 
 pub fn success_response(id: Option<JSONRPCId>, result: Value) -> Value {
@@ -287,6 +537,43 @@ fn metadata_value_as_bool(
         .and_then(|value| value.as_bool())
 }
 
+/// `parent_context_id` from the message metadata, else the request metadata.
+/// One naming the message's own context is ignored.
+fn parent_context_of(params: &Value, context_id: Option<&ContextId>) -> Result<Option<ContextId>> {
+    let Some(raw) = ["/message/metadata", "/metadata"]
+        .iter()
+        .find_map(|pointer| params.pointer(pointer)?.get(PARENT_CONTEXT_ID_METADATA_KEY))
+    else {
+        return Ok(None);
+    };
+    let parent = raw
+        .as_str()
+        .filter(|raw| !raw.is_empty())
+        .and_then(|_| serde_json::from_value::<ContextId>(raw.clone()).ok())
+        .ok_or_else(|| {
+            BamlRtError::InvalidArgument(format!(
+                "Invalid {}: expected a context id, got {}",
+                PARENT_CONTEXT_ID_METADATA_KEY, raw
+            ))
+        })?;
+    Ok(Some(parent).filter(|parent| Some(parent) != context_id))
+}
+
+/// `delegation_chain` from the message metadata: a list of agent names.
+fn delegation_chain_of(params: &Value) -> Result<Vec<String>> {
+    let Some(raw) = params.pointer("/message/metadata").and_then(|metadata| {
+        metadata.get(DELEGATION_CHAIN_METADATA_KEY)
+    }) else {
+        return Ok(Vec::new());
+    };
+    serde_json::from_value(raw.clone()).map_err(|_| {
+        BamlRtError::InvalidArgument(format!(
+            "Invalid {}: expected a list of agent names, got {}",
+            DELEGATION_CHAIN_METADATA_KEY, raw
+        ))
+    })
+}
+
 fn augment_message_params(mut params_value: Value, message: &Message) -> Value {
     let message_text = message_text(message);
     if let Value::Object(ref mut map) = params_value
@@ -343,6 +630,7 @@ mod tests {
     use super::A2aRequest;
     use crate::a2a_types::{JSONRPCId, JSONRPCRequest, Message, MessageRole, Part, SendMessageRequest, ROLE_USER};
     use baml_rt_core::BamlRtError;
+    use baml_rt_core::ids::ContextId;
     use crate::{A2aAgent, A2aRequestHandler};
     use opentelemetry::global;
     use opentelemetry::trace::TracerProvider as _;
@@ -537,6 +825,130 @@ mod tests {
         assert_eq!(text, Some("hi Ada"));
     }
 
+    #[tokio::test]
+    async fn test_a2a_feedback_is_recorded() {
+        use crate::feedback::FeedbackQuery;
+        use baml_rt_core::ids::{ExternalId, MessageId};
+
+        let agent = setup_agent_with_js().await;
+        let request = json!({
+            "jsonrpc": "2.0",
+            "method": "message.feedback",
+            "params": { "messageId": "resp-1", "rating": 5, "comment": "spot on" },
+            "id": "corr-1-11"
+        });
+
+        let responses = agent.handle_a2a(request).await.expect("a2a handle");
+        let result = expect_success_result(responses);
+        assert_eq!(result.get("rating").and_then(Value::as_i64), Some(5));
+        assert!(result.get("feedbackId").and_then(Value::as_str).is_some());
+
+        let query = FeedbackQuery {
+            message_id: Some(MessageId::from_external(ExternalId::new("resp-1"))),
+            ..FeedbackQuery::default()
+        };
+        let stored = agent.feedback_store().list(&query).await;
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].comment.as_deref(), Some("spot on"));
+    }
+
+    #[tokio::test]
+    async fn test_a2a_feedback_requires_target() {
+        let agent = setup_agent_with_js().await;
+        let request = json!({
+            "jsonrpc": "2.0",
+            "method": "message.feedback",
+            "params": { "rating": 1 },
+            "id": "corr-1-12"
+        });
+
+        let responses = agent.handle_a2a(request).await.expect("a2a handle");
+        let response = responses.into_iter().next().expect("response");
+        assert!(response.get("error").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_a2a_capabilities_lists_supported_features() {
+        let agent = setup_agent_with_js().await;
+        for method in ["agent.capabilities", "agent/capabilities"] {
+            let request = json!({ "jsonrpc": "2.0", "method": method, "id": "corr-1-14" });
+            let result = expect_success_result(agent.handle_a2a(request).await.expect("a2a handle"));
+            assert_eq!(result["streaming"], json!(true));
+            assert_eq!(result["transports"], json!(["stdio", "http", "websocket"]));
+            assert_eq!(result["maxMessageBytes"], json!(2 * 1024 * 1024));
+            assert_eq!(result["extensions"]["pushNotifications"], json!(false));
+            assert_eq!(result["extensions"]["feedback"], json!(true));
+            let methods = result["methods"].as_array().expect("methods");
+            assert!(methods.contains(&json!("message.sendStream")));
+            assert!(methods.contains(&json!("agent.capabilities")));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_a2a_get_card_describes_the_manifest() {
+        use baml_rt_core::manifest::AgentManifest;
+
+        let manifest = AgentManifest::from_value(&json!({
+            "manifest_version": 2,
+            "name": "support-agent",
+            "version": "1.4.0",
+            "description": "Answers support questions",
+            "tools": ["support/calculate"],
+            "capabilities": ["streaming"]
+        }))
+        .expect("manifest");
+        let agent = A2aAgent::builder().with_manifest(manifest).build().await.expect("agent build");
+        for method in ["agent.getCard", "agent/getCard"] {
+            let request = json!({ "jsonrpc": "2.0", "method": method, "id": "corr-1-15" });
+            let result = expect_success_result(agent.handle_a2a(request).await.expect("a2a handle"));
+            assert_eq!(result["name"], json!("support-agent"));
+            assert_eq!(result["version"], json!("1.4.0"));
+            assert_eq!(result["description"], json!("Answers support questions"));
+            assert_eq!(result["tools"], json!(["support/calculate"]));
+            assert_eq!(result["features"], json!(["streaming"]));
+            assert_eq!(result["streaming"], json!(true));
+            let methods = result["methods"].as_array().expect("methods");
+            assert!(methods.contains(&json!("agent.getCard")));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_a2a_context_fork_copies_history_prefix() {
+        let agent = setup_agent_with_js().await;
+        let mut message = user_message("msg-fork", "Ada");
+        message.context_id = ContextId::parse_temporal("ctx-5-1");
+        let params = SendMessageRequest {
+            message,
+            configuration: None,
+            metadata: None,
+            tenant: None,
+            extra: HashMap::new(),
+        };
+        let send = json!({
+            "jsonrpc": "2.0",
+            "method": "message.send",
+            "params": serde_json::to_value(params).expect("serialize params"),
+            "id": "corr-1-13"
+        });
+        expect_success_result(agent.handle_a2a(send).await.expect("a2a handle"));
+
+        let fork = json!({
+            "jsonrpc": "2.0",
+            "method": "contexts.fork",
+            "params": { "contextId": "ctx-5-1", "messageId": "msg-fork", "newContextId": "ctx-5-2" },
+            "id": "corr-1-14"
+        });
+        let result = expect_success_result(agent.handle_a2a(fork).await.expect("a2a handle"));
+        assert_eq!(result.get("contextId").and_then(Value::as_str), Some("ctx-5-2"));
+        assert_eq!(result.get("parentContextId").and_then(Value::as_str), Some("ctx-5-1"));
+        let history = result.get("history").and_then(Value::as_array).expect("history");
+        assert_eq!(history.len(), 1);
+        assert_eq!(
+            history[0].get("contextId").and_then(Value::as_str),
+            Some("ctx-5-2")
+        );
+    }
+
     #[tokio::test]
     async fn test_a2a_request_span_structure() {
         let _otel = OtelTestFixture::new();
@@ -595,6 +1007,91 @@ mod tests {
         assert_eq!(attr_value(span, "correlation_id").as_deref(), Some("corr-1-20"));
     }
 
+    const REMOTE_TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+    fn traced_send_request(id: &str, traceparent: &str) -> Value {
+        let mut message = user_message(&format!("msg-{id}"), "Ada");
+        message.metadata = Some(HashMap::from([
+            ("traceparent".to_string(), json!(traceparent)),
+            ("tracestate".to_string(), json!("vendor=abc")),
+        ]));
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "message.send",
+            "params": { "message": message }
+        })
+    }
+
+    #[test]
+    fn test_a2a_request_reads_trace_context_from_message_metadata() {
+        let parsed = A2aRequest::from_value(traced_send_request("corr-1-60", REMOTE_TRACEPARENT))
+            .expect("parse request");
+        let trace_context = parsed.trace_context.expect("trace context");
+        assert_eq!(trace_context.trace_id(), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(trace_context.parent_id(), "00f067aa0ba902b7");
+        assert_eq!(trace_context.tracestate(), Some("vendor=abc"));
+        assert!(trace_context.sampled());
+
+        for invalid in [
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        ] {
+            let parsed = A2aRequest::from_value(traced_send_request("corr-1-61", invalid))
+                .expect("invalid trace context does not fail the request");
+            assert!(parsed.trace_context.is_none(), "{invalid}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_a2a_request_span_joins_incoming_trace() {
+        let _otel = OtelTestFixture::new();
+        let agent = setup_agent_with_js().await;
+
+        let _ = agent.handle_a2a(traced_send_request("corr-1-62", REMOTE_TRACEPARENT)).await;
+
+        let spans = _otel.spans();
+        let span = find_span(&spans, "baml_rt.a2a_request")
+            .expect("expected baml_rt.a2a_request span");
+        assert_eq!(
+            span.span_context.trace_id().to_string(),
+            "4bf92f3577b34da6a3ce929d0e0e4736"
+        );
+        assert_eq!(span.parent_span_id.to_string(), "00f067aa0ba902b7");
+    }
+
+    #[tokio::test]
+    async fn test_inject_trace_context_continues_current_span() {
+        use opentelemetry::trace::TraceContextExt;
+        use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+        let _otel = OtelTestFixture::new();
+        let span = tracing::info_span!("caller");
+        let _entered = span.enter();
+        let span_context = span.context().span().span_context().clone();
+
+        let mut request = json!({
+            "jsonrpc": "2.0",
+            "id": "corr-1-63",
+            "method": "message.send",
+            "params": { "message": user_message("msg-out", "Ada") }
+        });
+        super::inject_trace_context(&mut request);
+        let traceparent = request["params"]["message"]["metadata"]["traceparent"]
+            .as_str()
+            .expect("traceparent injected");
+        assert_eq!(
+            traceparent,
+            format!("00-{}-{}-01", span_context.trace_id(), span_context.span_id())
+        );
+
+        let mut traced = traced_send_request("corr-1-64", REMOTE_TRACEPARENT);
+        super::inject_trace_context(&mut traced);
+        assert_eq!(traced["params"]["message"]["metadata"]["traceparent"], json!(REMOTE_TRACEPARENT));
+    }
+
     #[tokio::test]
     async fn test_a2a_stream_suffix_dispatches_stream() {
         let agent = setup_agent_with_js().await;
@@ -799,6 +1296,77 @@ mod tests {
         assert!(any_final, "subscribe stream should include a final chunk");
     }
 
+    #[tokio::test]
+    async fn test_tasks_poll_updates_waits_for_new_updates() {
+        let agent = setup_agent_with_js().await;
+        let params = SendMessageRequest {
+            message: user_message("msg-task-poll", "task"),
+            configuration: None,
+            metadata: None,
+            tenant: None,
+            extra: HashMap::new(),
+        };
+        let create_request = json!({
+            "jsonrpc": "2.0",
+            "method": "message.send",
+            "params": serde_json::to_value(params).expect("serialize params"),
+            "id": "corr-1-30"
+        });
+        let create_responses = agent.handle_a2a(create_request).await.expect("create task");
+        let task_id = create_responses
+            .iter()
+            .find_map(|response| response["result"]["task"]["id"].as_str())
+            .expect("task id")
+            .to_string();
+        let poll = |id: &str, params: Value| {
+            json!({ "jsonrpc": "2.0", "method": "tasks/pollUpdates", "params": params, "id": id })
+        };
+
+        let result = expect_success_result(
+            agent
+                .handle_a2a(poll("corr-1-31", json!({ "id": task_id, "timeoutMs": 0 })))
+                .await
+                .expect("initial poll"),
+        );
+        let cursor = result["cursor"].as_u64().expect("cursor");
+
+        let cancel = json!({
+            "jsonrpc": "2.0",
+            "method": "tasks.cancel",
+            "params": { "id": task_id },
+            "id": "corr-1-32"
+        });
+        let (polled, cancelled) = tokio::join!(
+            agent.handle_a2a(poll(
+                "corr-1-33",
+                json!({ "id": task_id, "cursor": cursor, "timeoutMs": 5000 }),
+            )),
+            async {
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                agent.handle_a2a(cancel).await
+            }
+        );
+        cancelled.expect("cancel task");
+        let result = expect_success_result(polled.expect("long poll"));
+        let updates = result["updates"].as_array().expect("updates");
+        let last = updates.last().expect("update after cancel");
+        assert_eq!(last["statusUpdate"]["status"]["state"], json!("TASK_STATE_CANCELED"));
+        let next_cursor = result["cursor"].as_u64().expect("cursor");
+        assert_eq!(next_cursor, cursor + updates.len() as u64);
+
+        let result = expect_success_result(
+            agent
+                .handle_a2a(poll(
+                    "corr-1-34",
+                    json!({ "id": task_id, "cursor": next_cursor, "timeoutMs": 10 }),
+                ))
+                .await
+                .expect("empty poll"),
+        );
+        assert_eq!(result["updates"], json!([]));
+        assert_eq!(result["cursor"], json!(next_cursor));
+    }
+
     #[test]
     fn test_a2a_jsonrpc_version_validation() {
         let request = json!({
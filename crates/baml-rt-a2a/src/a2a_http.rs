@@ -0,0 +1,245 @@
+//! HTTP transport for [`A2aRequestHandler`].
+//!
+//! `POST /` takes one JSON-RPC request. Unary methods answer with the JSON-RPC
+//! response as `application/json`. `message.sendStream` (or any request sent with
+//! `Accept: text/event-stream`) answers with server-sent events, one `data:` event
+//! per stream chunk, each written as soon as the handler yields it.
+//!
+//! `GET /health` and `GET /ready` are for orchestrators' probes. Each sends the
+//! handler a [`HEALTH_METHOD`] request and answers with its result: 200 when the
+//! result's `healthy` (or `ready`) field is true, 503 otherwise.
+//!
+//! Handlers are `?Send`, so requests are not run on the HTTP tasks. They are
+//! forwarded to the future returned by [`A2aHttpServer::serve`], which runs them
+//! concurrently on the caller's task.
+
+use crate::a2a::{self, A2aMethod};
+use crate::a2a_transport::A2aRequestHandler;
+use crate::a2a_types::DEFAULT_MAX_MESSAGE_BYTES;
+use crate::response::{JsonRpcResponseFormatter, ResponseFormatter};
+use axum::body::Bytes;
+use axum::extract::{DefaultBodyLimit, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::sse::{Event, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use baml_rt_core::{BamlRtError, Result};
+use futures_util::stream::{self, FuturesUnordered, StreamExt};
+use serde_json::{json, Value};
+use std::future::Future;
+use std::net::SocketAddr;
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{info, warn};
+
+/// JSON-RPC method the health probes are answered from.
+pub const HEALTH_METHOD: &str = "runtime.health";
+
+/// Requests waiting to be picked up by the handler before new ones are refused.
+const DISPATCH_QUEUE: usize = 64;
+
+/// Stream chunks buffered for a slow client before the handler waits for it.
+const STREAM_BUFFER: usize = 16;
+
+struct Dispatch {
+    request: Value,
+    reply: Reply,
+}
+
+enum Reply {
+    /// Every response at once
+    Unary(oneshot::Sender<Vec<Value>>),
+    /// Each response as the handler yields it
+    Stream(mpsc::Sender<Value>),
+}
+
+/// A bound HTTP listener serving the A2A JSON-RPC API.
+pub struct A2aHttpServer {
+    listener: TcpListener,
+}
+
+impl A2aHttpServer {
+    /// Bind to `addr`. Use port 0 to pick a free port, then read it from [`Self::local_addr`].
+    pub async fn bind(addr: SocketAddr) -> Result<Self> {
+        Ok(Self { listener: TcpListener::bind(addr).await? })
+    }
+
+    pub fn from_listener(listener: TcpListener) -> Self {
+        Self { listener }
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Serve requests with `handler` until `shutdown` resolves, then finish in-flight requests.
+    pub async fn serve<H, F>(self, handler: &H, shutdown: F) -> Result<()>
+    where
+        H: A2aRequestHandler + ?Sized,
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let addr = self.local_addr()?;
+        let (dispatch_tx, mut dispatch_rx) = mpsc::channel::<Dispatch>(DISPATCH_QUEUE);
+        let app = Router::new()
+            .route("/", post(handle_post))
+            .route("/health", get(handle_health))
+            .route("/ready", get(handle_ready))
+            .layer(DefaultBodyLimit::max(DEFAULT_MAX_MESSAGE_BYTES))
+            .with_state(dispatch_tx);
+        let server = tokio::spawn(async move {
+            axum::serve(self.listener, app).with_graceful_shutdown(shutdown).await
+        });
+        info!(%addr, "A2A HTTP transport listening");
+
+        let mut in_flight = FuturesUnordered::new();
+        loop {
+            tokio::select! {
+                dispatch = dispatch_rx.recv() => match dispatch {
+                    Some(Dispatch { request, reply }) => in_flight.push(async move {
+                        match reply {
+                            Reply::Unary(reply) => {
+                                let responses = dispatch_request(handler, request).await;
+                                // The client may have disconnected; nothing to report to.
+                                let _ = reply.send(responses);
+                            }
+                            Reply::Stream(chunks) => stream_request(handler, request, chunks).await,
+                        }
+                    }),
+                    // The router (and its sender) is dropped once the server stops.
+                    None => break,
+                },
+                Some(()) = in_flight.next(), if !in_flight.is_empty() => {}
+            }
+        }
+        while in_flight.next().await.is_some() {}
+
+        server.await.map_err(std::io::Error::other)??;
+        Ok(())
+    }
+}
+
+async fn dispatch_request<H>(handler: &H, request: Value) -> Vec<Value>
+where
+    H: A2aRequestHandler + ?Sized,
+{
+    let request_id = a2a::extract_jsonrpc_id(&request);
+    match handler.handle_a2a(request).await {
+        Ok(responses) => responses,
+        Err(err) => vec![JsonRpcResponseFormatter.format_error(request_id, &err)],
+    }
+}
+
+/// Send each response to `request` to `chunks` as the handler yields it, ending
+/// with the error if the handler fails.
+async fn stream_request<H>(handler: &H, request: Value, chunks: mpsc::Sender<Value>)
+where
+    H: A2aRequestHandler + ?Sized,
+{
+    let request_id = a2a::extract_jsonrpc_id(&request);
+    let mut responses = handler.handle_a2a_stream(request);
+    while let Some(response) = responses.next().await {
+        let (response, failed) = match response {
+            Ok(response) => (response, false),
+            Err(err) => (JsonRpcResponseFormatter.format_error(request_id.clone(), &err), true),
+        };
+        // A client that disconnected gets nothing more; dropping the stream stops the work.
+        if chunks.send(response).await.is_err() || failed {
+            break;
+        }
+    }
+}
+
+async fn handle_post(
+    State(dispatch_tx): State<mpsc::Sender<Dispatch>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let request: Value = match serde_json::from_slice(&body) {
+        Ok(request) => request,
+        Err(err) => {
+            let error = BamlRtError::Json(err);
+            return Json(JsonRpcResponseFormatter.format_error(None, &error)).into_response();
+        }
+    };
+    let request_id = a2a::extract_jsonrpc_id(&request);
+    let shutting_down = || {
+        warn!("A2A HTTP request dropped during shutdown");
+        Json(a2a::error_response(request_id.clone(), -32603, "Server shutting down", None))
+            .into_response()
+    };
+
+    if wants_stream(&headers, &request) {
+        let (chunks_tx, mut chunks) = mpsc::channel(STREAM_BUFFER);
+        let dispatch = Dispatch { request, reply: Reply::Stream(chunks_tx) };
+        // Nothing at all arrives when the request is dropped unhandled.
+        let first = match dispatch_tx.send(dispatch).await {
+            Ok(()) => chunks.recv().await,
+            Err(_) => None,
+        };
+        let Some(first) = first else {
+            return shutting_down();
+        };
+        let rest = stream::unfold(chunks, |mut chunks| async move {
+            chunks.recv().await.map(|chunk| (chunk, chunks))
+        });
+        let events = stream::once(async { first })
+            .chain(rest)
+            .map(|response| Event::default().json_data(response));
+        return Sse::new(events).into_response();
+    }
+
+    let Some(responses) = dispatch(&dispatch_tx, request).await else {
+        return shutting_down();
+    };
+    match <[Value; 1]>::try_from(responses) {
+        Ok([response]) => Json(response).into_response(),
+        Err(responses) => Json(Value::Array(responses)).into_response(),
+    }
+}
+
+/// Hand `request` to the handler; `None` once the server is shutting down.
+async fn dispatch(dispatch_tx: &mpsc::Sender<Dispatch>, request: Value) -> Option<Vec<Value>> {
+    let (reply_tx, reply_rx) = oneshot::channel();
+    dispatch_tx.send(Dispatch { request, reply: Reply::Unary(reply_tx) }).await.ok()?;
+    reply_rx.await.ok()
+}
+
+async fn handle_health(State(dispatch_tx): State<mpsc::Sender<Dispatch>>) -> Response {
+    probe(&dispatch_tx, "healthy").await
+}
+
+async fn handle_ready(State(dispatch_tx): State<mpsc::Sender<Dispatch>>) -> Response {
+    probe(&dispatch_tx, "ready").await
+}
+
+/// Answer a probe with the handler's health report, 200 if its `field` is true.
+async fn probe(dispatch_tx: &mpsc::Sender<Dispatch>, field: &str) -> Response {
+    let request = json!({"jsonrpc": "2.0", "id": field, "method": HEALTH_METHOD});
+    let response =
+        dispatch(dispatch_tx, request).await.and_then(|responses| responses.into_iter().next());
+    let Some(response) = response else {
+        let error = a2a::error_response(None, -32603, "Server shutting down", None);
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(error)).into_response();
+    };
+    let passed = response
+        .get("result")
+        .and_then(|result| result.get(field))
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+    let status = if passed { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    let body = response.get("result").cloned().unwrap_or(response);
+    (status, Json(body)).into_response()
+}
+
+fn wants_stream(headers: &HeaderMap, request: &Value) -> bool {
+    let accepts_events = headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .any(|value| value.contains("text/event-stream"));
+    let method = request.get("method").and_then(Value::as_str).unwrap_or_default();
+    accepts_events
+        || method.ends_with(A2aMethod::MessageSendStream.as_str())
+        || ["/stream", ".stream", ":stream"].iter().any(|suffix| method.ends_with(suffix))
+}
@@ -1,16 +1,100 @@
 use crate::a2a_types::{
-    Artifact, ListTasksRequest, ListTasksResponse, Message, MessageRole, Task,
-    TaskArtifactUpdateEvent, TaskState, TaskStatus, TaskStatusUpdateEvent, ROLE_USER, TASK_STATE_CANCELED,
+    Artifact, ContextBranch, ContextSummary, ListContextsRequest, ListContextsResponse,
+    ListMessagesRequest, ListMessagesResponse, ListTasksRequest, ListTasksResponse, Message,
+    MessageRole, NumberOrString, StreamResponse, Task, TaskArtifactUpdateEvent, TaskState,
+    TaskStatus, TaskStatusUpdateEvent, ROLE_USER, TASK_STATE_CANCELED,
 };
+use crate::task_state::{self, IllegalTransition, TaskLifecycleState, TransitionPolicy};
 use async_trait::async_trait;
-use baml_rt_core::context;
-use baml_rt_core::ids::{AgentId, ContextId, TaskId};
+use baml_rt_core::context::{self, PARENT_CONTEXT_ID_METADATA_KEY};
+use baml_rt_core::ids::{AgentId, ContextId, MessageId, TaskId};
+use baml_rt_core::{BamlRtError, Result};
 use baml_rt_provenance::{ProvEvent, ProvenanceWriter};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::sync::Mutex;
+use tokio::time::Instant;
 use serde_json::Value;
 use std::sync::Arc;
 use std::collections::HashMap;
+use tracing::warn;
+
+/// Task and message metadata key naming the tenant an entry belongs to.
+pub const TENANT_METADATA_KEY: &str = "tenant";
+
+/// Page size of task, message and context listings that do not set one.
+const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Largest page a listing returns, whatever page size was asked for.
+pub const MAX_PAGE_SIZE: usize = 1000;
+
+/// The tenant `task` was stored for; `None` for tasks created outside any tenant.
+pub fn task_tenant(task: &Task) -> Option<&str> {
+    task.metadata
+        .as_ref()
+        .and_then(|metadata| metadata.get(TENANT_METADATA_KEY))
+        .and_then(Value::as_str)
+}
+
+/// The tenant `message` was stored for; `None` for messages sent outside any tenant.
+pub fn message_tenant(message: &Message) -> Option<&str> {
+    message
+        .metadata
+        .as_ref()
+        .and_then(|metadata| metadata.get(TENANT_METADATA_KEY))
+        .and_then(Value::as_str)
+}
+
+/// The context `message`'s context is a child of, as tagged when it was stored.
+pub fn message_parent_context(message: &Message) -> Option<ContextId> {
+    let parent = message.metadata.as_ref()?.get(PARENT_CONTEXT_ID_METADATA_KEY)?;
+    serde_json::from_value(parent.clone()).ok()
+}
+
+/// `message` tagged with the tenant and the parent context in scope, as the
+/// store keeps it. The parent is only tagged on messages of the scope's context.
+pub(crate) fn with_scope_metadata(message: &Message) -> Message {
+    let mut message = message.clone();
+    let Some(scope) = context::current_scope() else {
+        return message;
+    };
+    tag_tenant(&mut message.metadata, scope.tenant);
+    let in_scope_context = message.context_id.as_ref().is_none_or(|id| *id == scope.context_id);
+    if in_scope_context && let Some(parent) = scope.parent_context_id {
+        message
+            .metadata
+            .get_or_insert_with(HashMap::new)
+            .entry(PARENT_CONTEXT_ID_METADATA_KEY.to_string())
+            .or_insert(Value::String(parent.into_string()));
+    }
+    message
+}
+
+/// Tag `metadata` with `tenant`, replacing whatever tenant the sender put
+/// there: an entry belongs to the tenant it was written in.
+fn tag_tenant(metadata: &mut Option<HashMap<String, Value>>, tenant: Option<String>) {
+    match tenant {
+        Some(tenant) => {
+            metadata
+                .get_or_insert_with(HashMap::new)
+                .insert(TENANT_METADATA_KEY.to_string(), Value::String(tenant));
+        }
+        None => {
+            if let Some(metadata) = metadata {
+                metadata.remove(TENANT_METADATA_KEY);
+            }
+        }
+    }
+}
+
+/// A context belongs to the tenant its first message was sent in.
+fn context_tenant(history: &[Message]) -> Option<&str> {
+    history.first().and_then(message_tenant)
+}
+
+/// A context is the child of the context its first message names as parent.
+fn nested_parent(history: &[Message]) -> Option<ContextId> {
+    history.first().and_then(message_parent_context)
+}
 
 #[derive(Debug, Clone)]
 pub enum TaskUpdateEvent {
@@ -27,11 +111,34 @@ impl TaskUpdateEvent {
     }
 }
 
+impl From<TaskUpdateEvent> for StreamResponse {
+    fn from(update: TaskUpdateEvent) -> Self {
+        let (status_update, artifact_update) = match update {
+            TaskUpdateEvent::Status(status_update) => (Some(status_update), None),
+            TaskUpdateEvent::Artifact(artifact_update) => (None, Some(artifact_update)),
+        };
+        StreamResponse {
+            message: None,
+            task: None,
+            status_update,
+            artifact_update,
+            extra: HashMap::new(),
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct TaskStore {
     tasks: HashMap<String, Task>,
     order: Vec<String>,
     updates: HashMap<String, Vec<TaskUpdateEvent>>,
+    drained_updates: HashMap<String, u64>,
+    contexts: HashMap<String, Vec<Message>>,
+    /// Contexts in the order their first message arrived.
+    context_order: Vec<ContextId>,
+    branches: HashMap<String, ContextBranch>,
+    transition_policy: TransitionPolicy,
+    last_activity: HashMap<String, Instant>,
 }
 
 #[async_trait]
@@ -40,7 +147,14 @@ pub trait TaskRepository: Send + Sync {
     async fn get(&self, id: &str, history_length: Option<usize>) -> Option<Task>;
     async fn list(&self, request: &ListTasksRequest) -> ListTasksResponse;
     async fn cancel(&self, id: &str) -> Option<Task>;
-    async fn insert_message(&self, message: &Message);
+    /// Fails as not found when the message names another tenant's task or context.
+    async fn insert_message(&self, message: &Message) -> Result<()>;
+
+    /// Unfinished tasks with no activity for at least `idle`, oldest first. Stores that
+    /// do not track activity report none, so their tasks never time out.
+    async fn stale_tasks(&self, _idle: Duration) -> Vec<Task> {
+        Vec::new()
+    }
 }
 
 #[async_trait]
@@ -61,15 +175,48 @@ pub trait TaskEventRecorder: Send + Sync {
     ) -> Option<TaskUpdateEvent>;
 }
 
+/// Buffered updates for one task, as returned to a poll.
+#[derive(Debug, Clone, Default)]
+pub struct TaskUpdateBatch {
+    pub updates: Vec<TaskUpdateEvent>,
+    /// Position after the last update in the buffer; the next poll resumes here.
+    pub cursor: u64,
+}
+
 #[async_trait]
 pub trait TaskUpdateQueue: Send + Sync {
     async fn drain_updates(&self, task_id: &str) -> Vec<TaskUpdateEvent>;
+    /// Updates for `task_id` from `cursor` on, leaving them buffered.
+    async fn updates_since(&self, task_id: &str, cursor: u64) -> TaskUpdateBatch;
 }
 
+/// Per-context conversation history and branch lineage.
 #[async_trait]
-pub trait TaskStoreBackend: TaskRepository + TaskEventRecorder + TaskUpdateQueue {}
+pub trait ContextRepository: Send + Sync {
+    async fn context_history(&self, context_id: &str, history_length: Option<usize>) -> Vec<Message>;
+    /// Lineage of a forked context; `None` for contexts that were not forked.
+    async fn context_branch(&self, context_id: &str) -> Option<ContextBranch>;
+    async fn fork_context(
+        &self,
+        source: &ContextId,
+        at_message: &MessageId,
+        target: ContextId,
+    ) -> Result<ContextBranch>;
+    /// A page of the context's history; `None` when `request.tenant` has no such context.
+    async fn list_messages(&self, request: &ListMessagesRequest) -> Option<ListMessagesResponse>;
+    async fn list_contexts(&self, request: &ListContextsRequest) -> ListContextsResponse;
+}
+
+#[async_trait]
+pub trait TaskStoreBackend:
+    TaskRepository + TaskEventRecorder + TaskUpdateQueue + ContextRepository
+{
+}
 
-impl<T> TaskStoreBackend for T where T: TaskRepository + TaskEventRecorder + TaskUpdateQueue {}
+impl<T> TaskStoreBackend for T where
+    T: TaskRepository + TaskEventRecorder + TaskUpdateQueue + ContextRepository
+{
+}
 
 #[async_trait]
 impl TaskRepository for Mutex<TaskStore> {
@@ -93,9 +240,14 @@ impl TaskRepository for Mutex<TaskStore> {
         store.cancel(id)
     }
 
-    async fn insert_message(&self, message: &Message) {
+    async fn insert_message(&self, message: &Message) -> Result<()> {
         let mut store = self.lock().await;
-        store.insert_message(message);
+        store.insert_message(message)
+    }
+
+    async fn stale_tasks(&self, idle: Duration) -> Vec<Task> {
+        let store = self.lock().await;
+        store.stale_tasks(idle)
     }
 }
 
@@ -131,6 +283,44 @@ impl TaskUpdateQueue for Mutex<TaskStore> {
         let mut store = self.lock().await;
         store.drain_updates(task_id)
     }
+
+    async fn updates_since(&self, task_id: &str, cursor: u64) -> TaskUpdateBatch {
+        let store = self.lock().await;
+        store.updates_since(task_id, cursor)
+    }
+}
+
+#[async_trait]
+impl ContextRepository for Mutex<TaskStore> {
+    async fn context_history(&self, context_id: &str, history_length: Option<usize>) -> Vec<Message> {
+        let store = self.lock().await;
+        store.context_history(context_id, history_length)
+    }
+
+    async fn context_branch(&self, context_id: &str) -> Option<ContextBranch> {
+        let store = self.lock().await;
+        store.context_branch(context_id)
+    }
+
+    async fn fork_context(
+        &self,
+        source: &ContextId,
+        at_message: &MessageId,
+        target: ContextId,
+    ) -> Result<ContextBranch> {
+        let mut store = self.lock().await;
+        store.fork_context(source, at_message, target)
+    }
+
+    async fn list_messages(&self, request: &ListMessagesRequest) -> Option<ListMessagesResponse> {
+        let store = self.lock().await;
+        store.list_messages(request)
+    }
+
+    async fn list_contexts(&self, request: &ListContextsRequest) -> ListContextsResponse {
+        let store = self.lock().await;
+        store.list_contexts(request)
+    }
 }
 
 pub struct ProvenanceTaskStore {
@@ -148,11 +338,33 @@ impl ProvenanceTaskStore {
         }
     }
 
+    /// Start from `store` instead of an empty store, e.g. one restored from disk.
+    pub fn with_task_store(self, store: TaskStore) -> Self {
+        Self { inner: Mutex::new(store), ..self }
+    }
+
+    /// How illegal task state transitions are handled; defaults to [`TransitionPolicy::Reject`].
+    pub fn with_transition_policy(self, policy: TransitionPolicy) -> Self {
+        let inner = self.inner.into_inner().with_transition_policy(policy);
+        Self { inner: Mutex::new(inner), ..self }
+    }
+
     async fn record_event(&self, event: ProvEvent) {
         if let Some(writer) = &self.writer {
             writer.add_event_with_logging(event, "task store operation").await;
         }
     }
+
+    async fn record_illegal_transition(&self, illegal: IllegalTransition, context_id: Option<ContextId>) {
+        let event = ProvEvent::illegal_task_transition(
+            context_id.unwrap_or_else(context::current_or_new),
+            illegal.task_id,
+            illegal.from.as_str().to_string(),
+            illegal.to.as_str().to_string(),
+            illegal.rejected,
+        );
+        self.record_event(event).await;
+    }
 }
 
 fn now_millis() -> u64 {
@@ -176,13 +388,24 @@ impl TaskRepository for ProvenanceTaskStore {
             metadata.insert("agent_id".to_string(), Value::String(self.agent_id.as_str().to_string()));
             task.metadata = Some(metadata);
         }
-        
-        if let Some(task_id) = task.id.clone() {
-            let event = ProvEvent::task_created(context_id, task_id, self.agent_id.clone());
+
+        let (stored, illegal) = {
+            let mut store = self.inner.lock().await;
+            let illegal = task
+                .id
+                .as_ref()
+                .zip(task.status.as_ref())
+                .and_then(|(task_id, status)| store.check_transition(task_id, status));
+            (store.upsert(task)?, illegal)
+        };
+        if let Some(task_id) = stored.id.clone() {
+            let event = ProvEvent::task_created(context_id.clone(), task_id, self.agent_id.clone());
             self.record_event(event).await;
         }
-        let mut store = self.inner.lock().await;
-        store.upsert(task)
+        if let Some(illegal) = illegal {
+            self.record_illegal_transition(illegal, Some(context_id)).await;
+        }
+        Some(stored)
     }
 
     async fn get(&self, id: &str, history_length: Option<usize>) -> Option<Task> {
@@ -196,11 +419,24 @@ impl TaskRepository for ProvenanceTaskStore {
     }
 
     async fn cancel(&self, id: &str) -> Option<Task> {
-        let mut store = self.inner.lock().await;
-        store.cancel(id)
+        let (task, illegal) = {
+            let mut store = self.inner.lock().await;
+            let illegal = store
+                .get(id, Some(0))
+                .and_then(|task| task.id)
+                .and_then(|task_id| store.check_transition(&task_id, &canceled_status()));
+            (store.cancel(id), illegal)
+        };
+        if let Some(illegal) = illegal {
+            let context_id = task.as_ref().and_then(|task| task.context_id.clone());
+            self.record_illegal_transition(illegal, context_id).await;
+        }
+        task
     }
 
-    async fn insert_message(&self, message: &Message) {
+    async fn insert_message(&self, message: &Message) -> Result<()> {
+        self.inner.lock().await.check_message_tenant(message)?;
+        let message = &with_scope_metadata(message);
         let context_id = message
             .context_id
             .clone()
@@ -226,6 +462,11 @@ impl TaskRepository for ProvenanceTaskStore {
             let event = ProvEvent::task_created(context_id.clone(), task_id, self.agent_id.clone());
             self.record_event(event).await;
         }
+        let starts_context = !self.inner.lock().await.has_context(context_id.as_str());
+        if starts_context && let Some(parent) = message_parent_context(message) {
+            let event = ProvEvent::context_nested(context_id.clone(), parent);
+            self.record_event(event).await;
+        }
         let task_id_for_event = task_id.clone();
 
         let event = match (role.as_str(), task_id_for_event.clone()) {
@@ -267,7 +508,12 @@ impl TaskRepository for ProvenanceTaskStore {
         self.record_event(event).await;
 
         let mut store = self.inner.lock().await;
-        store.insert_message(message);
+        store.insert_message(message)
+    }
+
+    async fn stale_tasks(&self, idle: Duration) -> Vec<Task> {
+        let store = self.inner.lock().await;
+        store.stale_tasks(idle)
     }
 }
 
@@ -302,17 +548,35 @@ impl TaskEventRecorder for ProvenanceTaskStore {
         context_id: Option<ContextId>,
         status: TaskStatus,
     ) -> Option<TaskUpdateEvent> {
-        if let Some(task_id) = task_id.clone() {
+        let new_status = status_to_string(&status);
+        let (update, illegal, old_status) = {
+            let mut store = self.inner.lock().await;
+            let illegal = task_id
+                .as_ref()
+                .and_then(|task_id| store.check_transition(task_id, &status));
+            let old_status = task_id
+                .as_ref()
+                .and_then(|task_id| store.get(task_id.as_str(), Some(0)))
+                .and_then(|task| task.status)
+                .and_then(|status| status_to_string(&status));
+            let update = store.record_status_update(task_id.clone(), context_id.clone(), status);
+            (update, illegal, old_status)
+        };
+        if let Some(illegal) = illegal {
+            self.record_illegal_transition(illegal, context_id.clone()).await;
+        }
+        if update.is_some()
+            && let Some(task_id) = task_id
+        {
             let event = ProvEvent::task_status_changed(
-                context_id.clone().unwrap_or_else(context::current_or_new),
+                context_id.unwrap_or_else(context::current_or_new),
                 task_id,
-                None,
-                status_to_string(&status),
+                old_status,
+                new_status,
             );
             self.record_event(event).await;
         }
-        let mut store = self.inner.lock().await;
-        store.record_status_update(task_id, context_id, status)
+        update
     }
 
     async fn record_artifact_update(
@@ -344,6 +608,53 @@ impl TaskUpdateQueue for ProvenanceTaskStore {
         let mut store = self.inner.lock().await;
         store.drain_updates(task_id)
     }
+
+    async fn updates_since(&self, task_id: &str, cursor: u64) -> TaskUpdateBatch {
+        let store = self.inner.lock().await;
+        store.updates_since(task_id, cursor)
+    }
+}
+
+#[async_trait]
+impl ContextRepository for ProvenanceTaskStore {
+    async fn context_history(&self, context_id: &str, history_length: Option<usize>) -> Vec<Message> {
+        let store = self.inner.lock().await;
+        store.context_history(context_id, history_length)
+    }
+
+    async fn context_branch(&self, context_id: &str) -> Option<ContextBranch> {
+        let store = self.inner.lock().await;
+        store.context_branch(context_id)
+    }
+
+    async fn fork_context(
+        &self,
+        source: &ContextId,
+        at_message: &MessageId,
+        target: ContextId,
+    ) -> Result<ContextBranch> {
+        let branch = {
+            let mut store = self.inner.lock().await;
+            store.fork_context(source, at_message, target)?
+        };
+        let event = ProvEvent::context_forked(
+            branch.context_id.clone(),
+            branch.parent_context_id.clone(),
+            branch.forked_at_message_id.clone(),
+        );
+        self.record_event(event).await;
+        Ok(branch)
+    }
+
+    async fn list_messages(&self, request: &ListMessagesRequest) -> Option<ListMessagesResponse> {
+        let store = self.inner.lock().await;
+        store.list_messages(request)
+    }
+
+    async fn list_contexts(&self, request: &ListContextsRequest) -> ListContextsResponse {
+        let store = self.inner.lock().await;
+        store.list_contexts(request)
+    }
 }
 
 fn status_to_string(status: &TaskStatus) -> Option<String> {
@@ -361,13 +672,82 @@ impl TaskStore {
         Self::default()
     }
 
-    pub fn upsert(&mut self, task: Task) -> Option<Task> {
+    pub fn with_transition_policy(mut self, policy: TransitionPolicy) -> Self {
+        self.transition_policy = policy;
+        self
+    }
+
+    /// Rebuild a store from persisted tasks (in creation order), context messages
+    /// (in arrival order) and fork lineage.
+    ///
+    /// Restored tasks start a fresh idle period; buffered updates are not restored.
+    pub fn restore(
+        tasks: Vec<Task>,
+        context_messages: Vec<(String, Message)>,
+        branches: Vec<ContextBranch>,
+    ) -> Self {
+        let mut store = Self::new();
+        for task in tasks {
+            let Some(id) = task.id.as_ref().map(|id| id.as_str().to_string()) else {
+                continue;
+            };
+            if !store.tasks.contains_key(&id) {
+                store.order.push(id.clone());
+            }
+            store.tasks.insert(id.clone(), task);
+            store.touch(&id);
+        }
+        for (context_id, message) in context_messages {
+            if !store.contexts.contains_key(&context_id)
+                && let Some(id) =
+                    message.context_id.clone().or_else(|| ContextId::parse_temporal(&context_id))
+            {
+                store.context_order.push(id);
+            }
+            store.contexts.entry(context_id).or_default().push(message);
+        }
+        for branch in branches {
+            store.branches.insert(branch.context_id.as_str().to_string(), branch);
+        }
+        store
+    }
+
+    /// The state machine violation moving `task_id` to `status` would be, if any.
+    pub fn check_transition(&self, task_id: &TaskId, status: &TaskStatus) -> Option<IllegalTransition> {
+        let current = self.tasks.get(task_id.as_str()).and_then(|task| task.status.as_ref());
+        task_state::check_transition(task_id, current, status, self.transition_policy)
+    }
+
+    /// Log an illegal transition; `true` when it must not be applied.
+    fn refuse_transition(&self, task_id: &TaskId, status: &TaskStatus) -> bool {
+        let Some(illegal) = self.check_transition(task_id, status) else {
+            return false;
+        };
+        warn!(rejected = illegal.rejected, "Illegal task state transition: {}", illegal);
+        illegal.rejected
+    }
+
+    /// A rejected status change keeps the stored status; the rest of `task` is still stored.
+    ///
+    /// The task is tagged with the tenant in scope. Another tenant's task is left
+    /// alone and reported as missing, as reads report it.
+    pub fn upsert(&mut self, mut task: Task) -> Option<Task> {
         let id = task.id.clone()?;
+        if !self.tenant_owns_task(id.as_str()) {
+            return None;
+        }
+        tag_tenant(&mut task.metadata, context::current_tenant());
+        if let Some(status) = &task.status
+            && self.refuse_transition(&id, status)
+        {
+            task.status = self.tasks.get(id.as_str()).and_then(|stored| stored.status.clone());
+        }
         let id_str = id.as_str();
         if !self.tasks.contains_key(id_str) {
             self.order.push(id_str.to_string());
         }
         self.tasks.insert(id_str.to_string(), task.clone());
+        self.touch(id_str);
         Some(task)
     }
 
@@ -386,6 +766,9 @@ impl TaskStore {
             .filter_map(|id| self.tasks.get(id).cloned())
             .collect();
 
+        // Tenants only ever see their own tasks; untagged tasks belong to no tenant.
+        tasks.retain(|task| task_tenant(task) == request.tenant.as_deref());
+
         if let Some(context_id) = &request.context_id {
             tasks.retain(|task| task.context_id.as_ref().map(|id| id.as_str()) == Some(context_id.as_str()));
         }
@@ -407,53 +790,222 @@ impl TaskStore {
             }
         }
 
-        let total_size = tasks.len() as u64;
-        let page_size = request
-            .page_size
-            .as_ref()
-            .and_then(|value| value.as_usize())
-            .unwrap_or(50);
-        let start = request
-            .page_token
-            .as_ref()
-            .and_then(|value| value.parse::<usize>().ok())
-            .unwrap_or(0);
-        let end = usize::min(start + page_size, tasks.len());
-
-        let page_tasks = if start < tasks.len() {
-            tasks[start..end].to_vec()
-        } else {
-            Vec::new()
-        };
-
-        let next_page_token = if end < tasks.len() {
-            Some(end.to_string())
-        } else {
-            None
-        };
-
+        let page = Page::of(tasks, request.page_size.as_ref(), request.page_token.as_deref());
         ListTasksResponse {
-            tasks: page_tasks,
-            next_page_token,
-            total_size: Some(total_size),
-            page_size: Some(page_size as u64),
+            tasks: page.items,
+            next_page_token: page.next_page_token,
+            total_size: Some(page.total_size),
+            page_size: Some(page.page_size),
             extra: HashMap::new(),
         }
     }
 
+    /// Tasks already in a terminal state are returned unchanged under [`TransitionPolicy::Reject`].
     pub fn cancel(&mut self, id: &str) -> Option<Task> {
+        if let Some(task_id) = self.tasks.get(id)?.id.clone()
+            && self.refuse_transition(&task_id, &canceled_status())
+        {
+            return self.tasks.get(id).cloned();
+        }
         let task = self.tasks.get_mut(id)?;
         let status = task.status.get_or_insert_with(TaskStatus::default);
         status.state = Some(TaskState::String(TASK_STATE_CANCELED.to_string()));
         Some(task.clone())
     }
 
-    pub fn insert_message(&mut self, message: &Message) {
+    /// Messages are tagged with the tenant and parent context in scope, see
+    /// [`message_tenant`] and [`message_parent_context`].
+    pub fn insert_message(&mut self, message: &Message) -> Result<()> {
+        self.check_message_tenant(message)?;
+        let message = with_scope_metadata(message);
         if let Some(task_id) = &message.task_id
             && let Some(task) = self.tasks.get_mut(task_id.as_str())
         {
             task.history.push(message.clone());
+            self.touch(task_id.as_str());
+        }
+        if let Some(context_id) = message.context_id.clone().or_else(context::current_context_id) {
+            if !self.contexts.contains_key(context_id.as_str()) {
+                self.context_order.push(context_id.clone());
+            }
+            self.contexts.entry(context_id.as_str().to_string()).or_default().push(message);
+        }
+        Ok(())
+    }
+
+    /// Whether the tenant in scope may write task `id`; nobody owns a new task yet.
+    fn tenant_owns_task(&self, id: &str) -> bool {
+        let tenant = context::current_tenant();
+        self.tasks.get(id).is_none_or(|task| task_tenant(task) == tenant.as_deref())
+    }
+
+    /// Whether the tenant in scope may write context `id`; nobody owns a new context yet.
+    fn tenant_owns_context(&self, id: &str) -> bool {
+        let tenant = context::current_tenant();
+        self.contexts
+            .get(id)
+            .is_none_or(|history| context_tenant(history) == tenant.as_deref())
+    }
+
+    /// Refuse `message` as not found if it names another tenant's task or context.
+    pub fn check_message_tenant(&self, message: &Message) -> Result<()> {
+        if let Some(task_id) = &message.task_id
+            && !self.tenant_owns_task(task_id.as_str())
+        {
+            return Err(BamlRtError::InvalidArgument("Task not found".to_string()));
+        }
+        let context_id = message.context_id.clone().or_else(context::current_context_id);
+        if let Some(context_id) = context_id
+            && !self.tenant_owns_context(context_id.as_str())
+        {
+            return Err(BamlRtError::InvalidArgument("Context not found".to_string()));
+        }
+        Ok(())
+    }
+
+    pub fn context_history(&self, context_id: &str, history_length: Option<usize>) -> Vec<Message> {
+        let mut history = self.contexts.get(context_id).cloned().unwrap_or_default();
+        if let Some(limit) = history_length {
+            keep_recent(&mut history, limit);
         }
+        history
+    }
+
+    /// A page of the history of `request.context_id`, oldest first, after
+    /// `history_length` keeps only the most recent messages.
+    ///
+    /// Another tenant's context is reported as missing, like its tasks.
+    pub fn list_messages(&self, request: &ListMessagesRequest) -> Option<ListMessagesResponse> {
+        let history = self
+            .contexts
+            .get(request.context_id.as_str())
+            .filter(|history| context_tenant(history) == request.tenant.as_deref())?;
+        let mut messages = history.clone();
+        if let Some(limit) = request.history_length.as_ref().and_then(|value| value.as_usize()) {
+            keep_recent(&mut messages, limit);
+        }
+        let page = Page::of(messages, request.page_size.as_ref(), request.page_token.as_deref());
+        Some(ListMessagesResponse {
+            messages: page.items,
+            next_page_token: page.next_page_token,
+            total_size: Some(page.total_size),
+            page_size: Some(page.page_size),
+            extra: HashMap::new(),
+        })
+    }
+
+    /// The requesting tenant's contexts in the order they started, each with its
+    /// most recent `history_length` messages.
+    pub fn list_contexts(&self, request: &ListContextsRequest) -> ListContextsResponse {
+        let contexts: Vec<(&ContextId, &Vec<Message>)> = self
+            .context_order
+            .iter()
+            .filter_map(|context_id| Some((context_id, self.contexts.get(context_id.as_str())?)))
+            .filter(|(_, history)| context_tenant(history) == request.tenant.as_deref())
+            .collect();
+        let page = Page::of(contexts, request.page_size.as_ref(), request.page_token.as_deref());
+        let history_length = request.history_length.as_ref().and_then(|value| value.as_usize());
+        let contexts = page
+            .items
+            .into_iter()
+            .map(|(context_id, history)| {
+                let mut recent = history.clone();
+                if let Some(limit) = history_length {
+                    keep_recent(&mut recent, limit);
+                }
+                ContextSummary {
+                    context_id: context_id.clone(),
+                    message_count: history.len() as u64,
+                    parent_context_id: self.context_parent(context_id.as_str()),
+                    history: recent,
+                    extra: HashMap::new(),
+                }
+            })
+            .collect();
+        ListContextsResponse {
+            contexts,
+            next_page_token: page.next_page_token,
+            total_size: Some(page.total_size),
+            page_size: Some(page.page_size),
+            extra: HashMap::new(),
+        }
+    }
+
+    pub fn context_branch(&self, context_id: &str) -> Option<ContextBranch> {
+        self.branches.get(context_id).cloned()
+    }
+
+    pub fn has_context(&self, context_id: &str) -> bool {
+        self.contexts.contains_key(context_id)
+    }
+
+    /// The context `context_id` was forked from or started as a child of.
+    pub fn context_parent(&self, context_id: &str) -> Option<ContextId> {
+        match self.branches.get(context_id) {
+            Some(branch) => Some(branch.parent_context_id.clone()),
+            None => self.contexts.get(context_id).map(Vec::as_slice).and_then(nested_parent),
+        }
+    }
+
+    /// Copy `source` history up to and including `at_message` into a new `target` context.
+    ///
+    /// Copied messages are re-homed into the target context; their originating task is
+    /// kept as a reference so the branch does not mutate tasks of the parent context.
+    pub fn fork_context(
+        &mut self,
+        source: &ContextId,
+        at_message: &MessageId,
+        target: ContextId,
+    ) -> Result<ContextBranch> {
+        if self.contexts.contains_key(target.as_str()) || self.branches.contains_key(target.as_str()) {
+            return Err(BamlRtError::InvalidArgument(format!(
+                "Context {} already exists",
+                target
+            )));
+        }
+        let history = self
+            .contexts
+            .get(source.as_str())
+            .filter(|_| self.tenant_owns_context(source.as_str()))
+            .ok_or_else(|| BamlRtError::InvalidArgument(format!("Context {} not found", source)))?;
+        let position = history
+            .iter()
+            .position(|message| message.message_id.as_message_id() == at_message)
+            .ok_or_else(|| {
+                BamlRtError::InvalidArgument(format!(
+                    "Message {} not found in context {}",
+                    at_message, source
+                ))
+            })?;
+
+        let copied: Vec<Message> = history[..=position]
+            .iter()
+            .cloned()
+            .map(|mut message| {
+                message.context_id = Some(target.clone());
+                if let Some(task_id) = message.task_id.take()
+                    && !message.reference_task_ids.contains(&task_id)
+                {
+                    message.reference_task_ids.push(task_id);
+                }
+                message
+            })
+            .collect();
+
+        let branch = ContextBranch {
+            context_id: target.clone(),
+            parent_context_id: source.clone(),
+            forked_at_message_id: at_message.clone(),
+            history: copied.clone(),
+            extra: HashMap::new(),
+        };
+        self.context_order.push(target.clone());
+        self.contexts.insert(target.as_str().to_string(), copied);
+        self.branches.insert(
+            target.as_str().to_string(),
+            ContextBranch { history: Vec::new(), ..branch.clone() },
+        );
+        Ok(branch)
     }
 
     pub fn record_status_update(
@@ -463,7 +1015,14 @@ impl TaskStore {
         status: TaskStatus,
     ) -> Option<TaskUpdateEvent> {
         if let Some(task_id) = task_id {
+            if self.refuse_transition(&task_id, &status) {
+                return None;
+            }
             let task_id_str = task_id.as_str().to_string();
+            if let Some(task) = self.tasks.get_mut(&task_id_str) {
+                task.status = Some(status.clone());
+            }
+            self.touch(&task_id_str);
             let update = TaskStatusUpdateEvent {
                 context_id,
                 task_id: Some(task_id.clone()),
@@ -491,6 +1050,7 @@ impl TaskStore {
     ) -> Option<TaskUpdateEvent> {
         if let Some(task_id) = task_id {
             let task_id_str = task_id.as_str().to_string();
+            self.touch(&task_id_str);
             let update = TaskArtifactUpdateEvent {
                 context_id,
                 task_id: Some(task_id.clone()),
@@ -510,19 +1070,107 @@ impl TaskStore {
         None
     }
 
+    /// Unfinished tasks untouched for at least `idle`, oldest first. Tasks in a state
+    /// the lifecycle does not know are left alone.
+    pub fn stale_tasks(&self, idle: Duration) -> Vec<Task> {
+        let mut stale: Vec<(Instant, &Task)> = self
+            .order
+            .iter()
+            .filter_map(|id| Some((*self.last_activity.get(id)?, self.tasks.get(id)?)))
+            .filter(|(touched, _)| touched.elapsed() >= idle)
+            .filter(|(_, task)| {
+                task.status
+                    .as_ref()
+                    .and_then(TaskLifecycleState::of)
+                    .is_some_and(|state| !state.is_terminal())
+            })
+            .collect();
+        stale.sort_by_key(|(touched, _)| *touched);
+        stale.into_iter().map(|(_, task)| task.clone()).collect()
+    }
+
+    fn touch(&mut self, task_id: &str) {
+        if self.tasks.contains_key(task_id) {
+            self.last_activity.insert(task_id.to_string(), Instant::now());
+        }
+    }
+
     pub fn drain_updates(&mut self, task_id: &str) -> Vec<TaskUpdateEvent> {
-        self.updates.remove(task_id).unwrap_or_default()
+        let drained = self.updates.remove(task_id).unwrap_or_default();
+        if !drained.is_empty() {
+            *self.drained_updates.entry(task_id.to_string()).or_default() += drained.len() as u64;
+        }
+        drained
+    }
+
+    /// Cursors count every update ever buffered for the task, so they stay valid across
+    /// drains; updates a resubscribe already drained are simply skipped.
+    pub fn updates_since(&self, task_id: &str, cursor: u64) -> TaskUpdateBatch {
+        let drained = self.drained_updates.get(task_id).copied().unwrap_or(0);
+        let buffered = self.updates.get(task_id).map(Vec::as_slice).unwrap_or_default();
+        let skip = usize::try_from(cursor.saturating_sub(drained)).unwrap_or(usize::MAX);
+        TaskUpdateBatch {
+            updates: buffered.iter().skip(skip).cloned().collect(),
+            cursor: drained + buffered.len() as u64,
+        }
+    }
+}
+
+fn canceled_status() -> TaskStatus {
+    TaskStatus {
+        state: Some(TaskState::String(TASK_STATE_CANCELED.to_string())),
+        ..TaskStatus::default()
     }
 }
 
 fn truncate_history(task: &mut Task, limit: usize) {
-    if limit == 0 {
-        task.history.clear();
-        return;
+    keep_recent(&mut task.history, limit);
+}
+
+/// Drop all but the last `limit` messages.
+fn keep_recent(messages: &mut Vec<Message>, limit: usize) {
+    if messages.len() > limit {
+        messages.drain(..messages.len() - limit);
+    }
+}
+
+/// Refuse a `page_token` that no listing handed out: tokens are item offsets.
+pub fn validate_page_token(page_token: Option<&str>) -> Result<()> {
+    match page_token {
+        Some(token) if token.parse::<usize>().is_err() => Err(BamlRtError::InvalidArgument(
+            format!("Invalid pageToken '{}': expected a token from an earlier page", token),
+        )),
+        _ => Ok(()),
     }
-    if task.history.len() > limit {
-        let start = task.history.len() - limit;
-        task.history = task.history.split_off(start);
+}
+
+/// One page of a listing. `page_token` is the offset of the page's first item,
+/// checked by [`validate_page_token`] before the listing is made; the page size
+/// is clamped to `1..=MAX_PAGE_SIZE`.
+struct Page<T> {
+    items: Vec<T>,
+    next_page_token: Option<String>,
+    total_size: u64,
+    page_size: u64,
+}
+
+impl<T> Page<T> {
+    fn of(mut items: Vec<T>, page_size: Option<&NumberOrString>, page_token: Option<&str>) -> Self {
+        let total = items.len();
+        let page_size = page_size
+            .and_then(NumberOrString::as_usize)
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE);
+        let start =
+            page_token.and_then(|token| token.parse::<usize>().ok()).unwrap_or(0).min(total);
+        let end = usize::min(start.saturating_add(page_size), total);
+        items.truncate(end);
+        Self {
+            items: items.split_off(start),
+            next_page_token: (end < total).then(|| end.to_string()),
+            total_size: total as u64,
+            page_size: page_size as u64,
+        }
     }
 }
 
@@ -1,24 +1,43 @@
-//! A2A request handler interface for non-standard transports.
+//! A2A request handler interface for non-standard transports, and the WebSocket transport.
 
 use crate::a2a;
-use crate::a2a_types::SendMessageRequest;
+use crate::a2a_types::{
+    AgentCapabilities, AgentCard, ContextBranch, JSONRPCId, SendMessageRequest, StreamResponse,
+    Task, TaskState, TaskStatus, DEFAULT_MAX_MESSAGE_BYTES,
+};
 use crate::a2a_store::{
-    ProvenanceTaskStore, TaskEventRecorder, TaskRepository, TaskStoreBackend, TaskUpdateQueue,
+    ContextRepository, ProvenanceTaskStore, TaskEventRecorder, TaskRepository, TaskStoreBackend, TaskUpdateQueue,
     TaskUpdateEvent,
 };
 use crate::error_classifier::{A2aErrorClassifier, ErrorClassifier};
+use crate::chunk_stream::{channel_stream, ChunkStream};
 use crate::events::{BroadcastEventEmitter, EventEmitter};
-use crate::handlers::{DefaultTaskHandler, TaskHandler};
+use crate::extensions::{A2aExtension, ExtensionRegistry};
+use crate::feedback::{FeedbackRepository, ProvenanceFeedbackStore};
+use crate::lifecycle::LifecycleHooks;
+use crate::push_notifications::{PushNotificationRegistry, WebhookConfig, WebhookDispatcher};
+use crate::handlers::{
+    AdminHandler, ContextHandler, DefaultAdminHandler, DefaultContextHandler,
+    DefaultFeedbackHandler, DefaultTaskHandler, FeedbackHandler, TaskHandler,
+};
 use crate::request_router::{MethodBasedRouter, QuickJsInvoker, RequestRouter};
 use crate::result_deduplicator::{DeduplicatingPipeline, HashResultDeduplicator, ResultDeduplicator};
 use crate::result_pipeline::{A2aResultPipeline, ResultStoragePipeline};
+use crate::signing::{self, MessageSigner, MessageVerifier};
+use crate::sqlite_task_store::SqliteTaskStore;
 use crate::response::{JsonRpcResponseFormatter, ResponseFormatter};
 use crate::stream_normalizer::{A2aStreamNormalizer, StreamNormalizer};
+use crate::task_queue::{TaskQueue, TaskSlot};
+use crate::task_state::{TaskLifecycleState, TransitionPolicy};
+use crate::diagnostics::ProvenanceConsoleSink;
+use crate::task_timeout::{TaskTimeoutConfig, TaskTimeoutSweeper};
  
-use baml_rt_quickjs::{BamlRuntimeManager, QuickJSBridge, QuickJSConfig};
-use baml_rt_core::{BamlRtError, Result};
+use baml_rt_quickjs::{BamlRuntimeManager, ConsoleLevel, QuickJSBridge, QuickJSConfig};
+use baml_rt_core::{BamlRtError, ErrorContext, Result, ResultExt};
 use baml_rt_core::correlation;
 use baml_rt_core::context;
+use baml_rt_core::manifest::AgentManifest;
+use baml_rt_interceptor::{OperatorCredentials, PayloadCapture, PendingApprovalQueue};
 use baml_rt_observability::{metrics, spans};
 use baml_rt_tools::tools::ToolFunctionMetadata;
 use baml_rt_tools::{ToolHandler, ToolName, ToolSession, ToolTypeSpec};
@@ -26,10 +45,21 @@ use baml_rt_tools::tools::ToolSessionContext;
 use baml_rt_tools::{ToolFailure, ToolSessionError};
 use baml_rt_provenance::{InMemoryProvenanceStore, ProvenanceInterceptor, ProvenanceWriter};
 use async_trait::async_trait;
+use futures_util::stream::{self, FuturesUnordered};
+use futures_util::{Sink, SinkExt, StreamExt};
 use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::broadcast;
-use tokio::sync::Mutex;
+use tokio::sync::{mpsc, watch, Mutex};
+use tokio_tungstenite::tungstenite::protocol::WebSocketConfig;
+use tokio_tungstenite::tungstenite::{Message as WsMessage, Utf8Bytes};
+use tracing::{info, warn};
+use crate::delegation::{AgentDirectory, DelegateHandler};
 use crate::tools::A2aSessionBundle;
 
 /// Top-level agent type that owns runtime, JS bridge, and A2A comms.
@@ -39,11 +69,21 @@ pub struct A2aAgent {
     runtime: Arc<Mutex<BamlRuntimeManager>>,
     bridge: Arc<Mutex<QuickJSBridge>>,
     task_store: Arc<dyn TaskStoreBackend>,
+    feedback_store: Arc<dyn FeedbackRepository>,
     provenance_writer: Option<Arc<dyn ProvenanceWriter>>,
     response_formatter: Arc<dyn ResponseFormatter>,
     request_router: Arc<dyn RequestRouter>,
     error_classifier: Arc<dyn ErrorClassifier>,
     update_tx: broadcast::Sender<TaskUpdateEvent>,
+    capabilities: Arc<AgentCapabilities>,
+    card: Arc<AgentCard>,
+    extensions: Arc<ExtensionRegistry>,
+    message_verifier: Option<Arc<MessageVerifier>>,
+    task_queue: Option<TaskQueue>,
+    /// Stops when the last clone of the agent is dropped.
+    _task_timeout: Option<Arc<TaskTimeoutSweeper>>,
+    /// Stops when the last clone of the agent is dropped.
+    _push_dispatcher: Option<Arc<WebhookDispatcher>>,
 }
 
 impl A2aAgent {
@@ -69,16 +109,64 @@ impl A2aAgent {
         self.bridge.clone()
     }
 
+    /// JS lifecycle hooks (`onBoot`, `onTaskCreated`, `onShutdown`) for this agent.
+    pub fn lifecycle(&self) -> LifecycleHooks {
+        LifecycleHooks::new(self.agent_id.clone(), self.bridge.clone())
+    }
+
     /// Access the task store for this agent instance.
     pub fn task_store(&self) -> Arc<dyn TaskStoreBackend> {
         self.task_store.clone()
     }
 
+    /// Access the feedback submitted for this agent's messages and tasks.
+    pub fn feedback_store(&self) -> Arc<dyn FeedbackRepository> {
+        self.feedback_store.clone()
+    }
+
     /// Access the provenance writer, if configured.
     pub fn provenance_writer(&self) -> Option<Arc<dyn ProvenanceWriter>> {
         self.provenance_writer.clone()
     }
 
+    /// Branch `context_id` at `message_id` into a new context with the history up to that point.
+    pub async fn fork_context(
+        &self,
+        context_id: &baml_rt_core::ids::ContextId,
+        message_id: &baml_rt_core::ids::MessageId,
+    ) -> Result<ContextBranch> {
+        self.task_store
+            .fork_context(context_id, message_id, context::generate_context_id())
+            .await
+    }
+
+    /// What this agent reports from `agent.capabilities`.
+    pub fn capabilities(&self) -> &AgentCapabilities {
+        &self.capabilities
+    }
+
+    /// What this agent reports from `agent.getCard`.
+    pub fn card(&self) -> &AgentCard {
+        &self.card
+    }
+
+    /// The A2A extensions this agent negotiates.
+    pub fn extensions(&self) -> &ExtensionRegistry {
+        &self.extensions
+    }
+
+    /// The queue limiting this agent's concurrent task executions, if it has one.
+    pub fn task_queue(&self) -> Option<&TaskQueue> {
+        self.task_queue.as_ref()
+    }
+
+    /// Cancel the tasks this agent has not finished, for when it stops; see
+    /// [`crate::task_timeout::cancel_unfinished_tasks`].
+    pub async fn cancel_unfinished_tasks(&self) -> usize {
+        let emitter = BroadcastEventEmitter::new(self.update_tx.clone());
+        crate::task_timeout::cancel_unfinished_tasks(self.task_store.as_ref(), &emitter).await
+    }
+
     /// Subscribe to task update events for this agent instance.
     pub fn subscribe_task_updates(&self) -> broadcast::Receiver<TaskUpdateEvent> {
         self.update_tx.subscribe()
@@ -128,6 +216,7 @@ impl A2aAgent {
             tags: Vec::new(),
             secret_requirements: Vec::new(),
             is_host_tool: false,
+            timeout: None,
         };
 
         let handler: Arc<dyn ToolHandler> = Arc::new(JsToolHandler {
@@ -156,6 +245,24 @@ impl A2aAgent {
         registry.register_bundle(bundle)?;
         Ok(())
     }
+
+    /// Register `a2a/delegate`, letting this agent ask the other agents in
+    /// `directory` for help.
+    ///
+    /// The tool describes the agents in `directory` now; call this again after
+    /// agents are added to refresh that description. Agents added since can be
+    /// reached either way.
+    pub async fn register_delegate_tool(&self, directory: AgentDirectory) -> Result<()> {
+        let handler = DelegateHandler::new(self.card.name.clone(), directory);
+        let metadata = handler.metadata().clone();
+        let registry = {
+            let runtime = self.runtime.lock().await;
+            runtime.tool_registry()
+        };
+        let mut registry = registry.lock().await;
+        registry.replace_dynamic(metadata, Arc::new(handler))?;
+        Ok(())
+    }
 }
 
 /// Builder for configuring an A2A agent and its subcomponents.
@@ -166,9 +273,24 @@ pub struct A2aAgentBuilder {
     register_baml_functions: bool,
     init_js: Vec<String>,
     task_store: Option<Arc<dyn TaskStoreBackend>>,
+    task_store_path: Option<PathBuf>,
+    feedback_store: Option<Arc<dyn FeedbackRepository>>,
     provenance_writer: Option<Arc<dyn ProvenanceWriter>>,
     agent_id: Option<baml_rt_core::ids::AgentId>,
     register_a2a_session_tool: bool,
+    capabilities: AgentCapabilities,
+    manifest: Option<AgentManifest>,
+    extensions: Vec<Arc<dyn A2aExtension>>,
+    message_signer: Option<Arc<MessageSigner>>,
+    message_verifier: Option<Arc<MessageVerifier>>,
+    transition_policy: TransitionPolicy,
+    task_timeout: Option<TaskTimeoutConfig>,
+    console_provenance: Option<ConsoleLevel>,
+    approval_queue: Option<PendingApprovalQueue>,
+    operators: OperatorCredentials,
+    agent_directory: Option<AgentDirectory>,
+    push_notifications: Option<WebhookConfig>,
+    max_concurrent_tasks: Option<usize>,
 }
 
 impl Default for A2aAgentBuilder {
@@ -187,9 +309,24 @@ impl A2aAgentBuilder {
             register_baml_functions: true,
             init_js: Vec::new(),
             task_store: None,
+            task_store_path: None,
+            feedback_store: None,
             provenance_writer: None,
             agent_id: None, // Will be generated in build()
             register_a2a_session_tool: false,
+            capabilities: AgentCapabilities::default(),
+            manifest: None,
+            extensions: Vec::new(),
+            message_signer: None,
+            message_verifier: None,
+            transition_policy: TransitionPolicy::default(),
+            task_timeout: None,
+            console_provenance: None,
+            approval_queue: None,
+            operators: OperatorCredentials::new(),
+            agent_directory: None,
+            push_notifications: None,
+            max_concurrent_tasks: None,
         }
     }
 
@@ -235,12 +372,115 @@ impl A2aAgentBuilder {
         self
     }
 
+    /// Keep tasks in a SQLite file at `path` so `tasks/get` and `tasks/list` survive
+    /// restarts. Ignored when a custom task store backend is provided.
+    pub fn with_sqlite_task_store(mut self, path: impl Into<PathBuf>) -> Self {
+        self.task_store_path = Some(path.into());
+        self
+    }
+
+    /// How the default task store handles illegal task state transitions.
+    /// Ignored when a custom task store backend is provided.
+    pub fn with_task_transition_policy(mut self, policy: TransitionPolicy) -> Self {
+        self.transition_policy = policy;
+        self
+    }
+
+    /// Fail unfinished tasks that see no activity for `config.timeout`. Off by default.
+    pub fn with_task_timeout(mut self, config: TaskTimeoutConfig) -> Self {
+        self.task_timeout = Some(config);
+        self
+    }
+
+    /// Provide a custom feedback store.
+    pub fn with_feedback_store(mut self, feedback_store: Arc<dyn FeedbackRepository>) -> Self {
+        self.feedback_store = Some(feedback_store);
+        self
+    }
+
     /// Provide a custom provenance writer.
     pub fn with_provenance_writer(mut self, writer: Arc<dyn ProvenanceWriter>) -> Self {
         self.provenance_writer = Some(writer);
         self
     }
 
+    /// Override what `agent.capabilities` reports, e.g. when serving over fewer transports.
+    pub fn with_capabilities(mut self, capabilities: AgentCapabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Describe the agent in `agent.getCard` from its package manifest. Without one
+    /// the card is named by the agent id.
+    pub fn with_manifest(mut self, manifest: AgentManifest) -> Self {
+        self.manifest = Some(manifest);
+        self
+    }
+
+    /// Support an A2A extension: it is advertised on the agent card and
+    /// negotiated on every message that activates its URI. Registering two
+    /// handlers for one URI fails the build.
+    pub fn with_extension(mut self, extension: Arc<dyn A2aExtension>) -> Self {
+        self.extensions.push(extension);
+        self
+    }
+
+    /// Sign the messages the agent's handler returns.
+    pub fn with_message_signer(mut self, signer: Arc<MessageSigner>) -> Self {
+        self.message_signer = Some(signer);
+        self
+    }
+
+    /// Check the signatures of incoming messages against peer keys, recording
+    /// the outcome on each message.
+    pub fn with_message_verifier(mut self, verifier: Arc<MessageVerifier>) -> Self {
+        self.message_verifier = Some(verifier);
+        self
+    }
+
+    /// Record console lines the agent's JavaScript writes at `min_level` or above
+    /// in provenance, as diagnostics of the task or context that wrote them. They
+    /// are traced either way.
+    pub fn with_console_provenance(mut self, min_level: ConsoleLevel) -> Self {
+        self.console_provenance = Some(min_level);
+        self
+    }
+
+    /// Let operators list and decide the tool calls parked in `queue` through
+    /// `admin.listApprovals` and `admin.decideApproval`.
+    pub fn with_approval_queue(mut self, queue: PendingApprovalQueue) -> Self {
+        self.approval_queue = Some(queue);
+        self
+    }
+
+    /// The operators whose `operatorToken` the admin methods accept; with none,
+    /// every admin request that needs one is refused.
+    pub fn with_operators(mut self, operators: OperatorCredentials) -> Self {
+        self.operators = operators;
+        self
+    }
+
+    /// Register `a2a/delegate`, letting the agent ask the agents in `directory`
+    /// for help. The agent must declare the tool.
+    pub fn with_agent_directory(mut self, directory: AgentDirectory) -> Self {
+        self.agent_directory = Some(directory);
+        self
+    }
+
+    /// Serve `tasks.pushNotificationConfig.set` and POST task updates to the
+    /// webhooks it registers, as `config` says.
+    pub fn with_push_notifications(mut self, config: WebhookConfig) -> Self {
+        self.push_notifications = Some(config);
+        self
+    }
+
+    /// Run at most `limit` `message.send`/`message.sendStream` executions at
+    /// once and queue the rest; see [`crate::task_queue`]. Unlimited by default.
+    pub fn with_max_concurrent_tasks(mut self, limit: usize) -> Self {
+        self.max_concurrent_tasks = Some(limit);
+        self
+    }
+
     pub fn with_a2a_session_tool(mut self, enabled: bool) -> Self {
         self.register_a2a_session_tool = enabled;
         self
@@ -253,6 +493,10 @@ impl A2aAgentBuilder {
                 "A2aAgentBuilder requires a runtime handle when providing a bridge".to_string(),
             ));
         }
+        let mut extensions = ExtensionRegistry::new();
+        for extension in self.extensions {
+            extensions.register(extension)?;
+        }
 
         let runtime = match self.runtime {
             Some(runtime) => runtime,
@@ -274,6 +518,9 @@ impl A2aAgentBuilder {
             }
         };
 
+        if let Some(manifest) = &self.manifest {
+            bridge.lock().await.set_agent_name(manifest.name.clone());
+        }
         if self.register_baml_functions || !self.init_js.is_empty() {
             let mut bridge_guard = bridge.lock().await;
             if self.register_baml_functions {
@@ -288,23 +535,35 @@ impl A2aAgentBuilder {
 
         let (task_store, provenance_writer) = match (self.task_store, self.provenance_writer) {
             (Some(task_store), provenance_writer) => (task_store, provenance_writer),
-            (None, None) => {
+            (None, writer) => {
                 let writer: Arc<dyn ProvenanceWriter> =
-                    Arc::new(InMemoryProvenanceStore::new());
-                let store: Arc<dyn TaskStoreBackend> =
-                    Arc::new(ProvenanceTaskStore::new(Some(writer.clone()), agent_id.clone()));
-                (store, Some(writer))
-            }
-            (None, Some(writer)) => {
-                let store: Arc<dyn TaskStoreBackend> =
-                    Arc::new(ProvenanceTaskStore::new(Some(writer.clone()), agent_id.clone()));
+                    writer.unwrap_or_else(|| Arc::new(InMemoryProvenanceStore::new()));
+                let store: Arc<dyn TaskStoreBackend> = match &self.task_store_path {
+                    Some(path) => Arc::new(
+                        SqliteTaskStore::open(path.clone(), Some(writer.clone()), agent_id.clone())?
+                            .with_transition_policy(self.transition_policy),
+                    ),
+                    None => Arc::new(
+                        ProvenanceTaskStore::new(Some(writer.clone()), agent_id.clone())
+                            .with_transition_policy(self.transition_policy),
+                    ),
+                };
                 (store, Some(writer))
             }
         };
 
+        let feedback_store: Arc<dyn FeedbackRepository> = match self.feedback_store {
+            Some(feedback_store) => feedback_store,
+            None => Arc::new(ProvenanceFeedbackStore::new(provenance_writer.clone())),
+        };
+
         let emitter: Arc<dyn EventEmitter> = Arc::new(BroadcastEventEmitter::new(update_tx.clone()));
         let result_pipeline: Arc<dyn ResultStoragePipeline> =
-            Arc::new(A2aResultPipeline::new(task_store.clone(), emitter.clone()));
+            Arc::new(A2aResultPipeline::with_lifecycle_hooks(
+                task_store.clone(),
+                emitter.clone(),
+                LifecycleHooks::new(agent_id.clone(), bridge.clone()),
+            ));
         let deduplicator: Arc<dyn ResultDeduplicator> = Arc::new(HashResultDeduplicator::new());
         let result_pipeline: Arc<dyn ResultStoragePipeline> =
             Arc::new(DeduplicatingPipeline::new(result_pipeline, deduplicator));
@@ -313,24 +572,75 @@ impl A2aAgentBuilder {
         let repository: Arc<dyn TaskRepository> = task_store.clone();
         let recorder: Arc<dyn TaskEventRecorder> = task_store.clone();
         let update_queue: Arc<dyn TaskUpdateQueue> = task_store.clone();
-        let task_handler: Arc<dyn TaskHandler> = Arc::new(DefaultTaskHandler::new(
-            repository,
-            recorder,
-            update_queue,
-            bridge.clone(),
-            emitter.clone(),
+        let push_registry = self.push_notifications.as_ref().map(|config| {
+            PushNotificationRegistry::new().with_private_hosts(config.private_hosts.clone())
+        });
+        let task_handler: Arc<dyn TaskHandler> = Arc::new(
+            DefaultTaskHandler::new(
+                repository,
+                recorder,
+                update_queue,
+                bridge.clone(),
+                emitter.clone(),
+                update_tx.clone(),
+            )
+            .with_push_notifications(push_registry.clone()),
+        );
+        let feedback_handler: Arc<dyn FeedbackHandler> = Arc::new(DefaultFeedbackHandler::new(
+            feedback_store.clone(),
+            task_store.clone(),
         ));
+        let contexts: Arc<dyn ContextRepository> = task_store.clone();
+        let context_handler: Arc<dyn ContextHandler> =
+            Arc::new(DefaultContextHandler::new(contexts));
+        let work = runtime.lock().await.work_tracker();
+        let admin_handler: Arc<dyn AdminHandler> = Arc::new(
+            DefaultAdminHandler::new(PayloadCapture::global())
+                .with_work_tracker(work)
+                .with_provenance_writer(provenance_writer.clone())
+                .with_approval_queue(self.approval_queue)
+                .with_operators(self.operators),
+        );
         let js_invoker: Arc<dyn crate::request_router::JsInvoker> = Arc::new(QuickJsInvoker::new(
             bridge.clone(),
             stream_normalizer.clone(),
         ));
+        let mut card = match &self.manifest {
+            Some(manifest) => AgentCard::from_manifest(manifest, &self.capabilities),
+            None => AgentCard::anonymous(&agent_id, &self.capabilities),
+        };
+        card.extensions = extensions.declarations();
+        let card = Arc::new(card);
+        let mut capabilities = self.capabilities;
+        capabilities.extensions.push_notifications = push_registry.is_some();
+        let capabilities = Arc::new(capabilities);
         let request_router: Arc<dyn RequestRouter> = Arc::new(MethodBasedRouter::new(
             task_handler.clone(),
+            feedback_handler,
+            context_handler,
+            admin_handler,
             js_invoker,
             result_pipeline.clone(),
-        ));
+            capabilities.clone(),
+            card.clone(),
+        )
+        .with_message_signer(self.message_signer));
         let error_classifier: Arc<dyn ErrorClassifier> = Arc::new(A2aErrorClassifier);
+        let task_timeout = self.task_timeout.map(|config| {
+            Arc::new(TaskTimeoutSweeper::spawn(task_store.clone(), emitter.clone(), config))
+        });
+
+        let task_queue =
+            self.max_concurrent_tasks.map(|limit| TaskQueue::new(agent_id.as_str(), limit));
 
+        let push_dispatcher = self.push_notifications.zip(push_registry).map(|(config, registry)| {
+            Arc::new(WebhookDispatcher::spawn(update_tx.subscribe(), registry, config))
+        });
+
+        if let (Some(min_level), Some(writer)) = (self.console_provenance, provenance_writer.clone()) {
+            let sink = ProvenanceConsoleSink::new(writer).with_min_level(min_level);
+            bridge.lock().await.add_console_sink(Arc::new(sink));
+        }
         if let Some(writer) = provenance_writer.clone() {
             let runtime_guard = runtime.lock().await;
             runtime_guard.register_llm_interceptor(ProvenanceInterceptor::new(writer.clone())).await;
@@ -343,16 +653,27 @@ impl A2aAgentBuilder {
             runtime,
             bridge,
             task_store,
+            feedback_store,
             provenance_writer,
             response_formatter,
             request_router,
             error_classifier,
             update_tx,
+            capabilities,
+            card,
+            extensions: Arc::new(extensions),
+            message_verifier: self.message_verifier,
+            task_queue,
+            _task_timeout: task_timeout,
+            _push_dispatcher: push_dispatcher,
         };
 
         if self.register_a2a_session_tool {
             agent.register_a2a_session_tool().await?;
         }
+        if let Some(directory) = self.agent_directory {
+            agent.register_delegate_tool(directory).await?;
+        }
 
         {
             let runtime_guard = agent.runtime.lock().await;
@@ -371,17 +692,65 @@ impl A2aAgentBuilder {
 #[async_trait(?Send)]
 pub trait A2aRequestHandler: Send + Sync {
     async fn handle_a2a(&self, request: Value) -> Result<Vec<Value>>;
+
+    /// The responses to `request`, yielded as they become ready, so a transport
+    /// can write stream chunks while the handler is still producing them.
+    ///
+    /// Defaults to the responses of [`Self::handle_a2a`], all at once.
+    fn handle_a2a_stream<'a>(&'a self, request: Value) -> ChunkStream<'a> {
+        stream::once(self.handle_a2a(request))
+            .flat_map(|responses| {
+                stream::iter(match responses {
+                    Ok(responses) => responses.into_iter().map(Ok).collect(),
+                    Err(err) => vec![Err(err)],
+                })
+            })
+            .boxed_local()
+    }
 }
 
 #[async_trait(?Send)]
 impl A2aRequestHandler for A2aAgent {
     async fn handle_a2a(&self, request: Value) -> Result<Vec<Value>> {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        self.dispatch_a2a(request, &sender).await?;
+        drop(sender);
+        let mut responses = Vec::new();
+        while let Some(response) = receiver.recv().await {
+            responses.push(response);
+        }
+        Ok(responses)
+    }
+
+    fn handle_a2a_stream<'a>(&'a self, request: Value) -> ChunkStream<'a> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        channel_stream(
+            async move { self.dispatch_a2a(request, &sender).await },
+            receiver,
+        )
+    }
+}
+
+impl A2aAgent {
+    /// Handle one request, sending each response to `responses` as soon as it
+    /// is ready.
+    async fn dispatch_a2a(
+        &self,
+        request: Value,
+        responses: &mpsc::UnboundedSender<Value>,
+    ) -> Result<()> {
         let request_id = a2a::extract_jsonrpc_id(&request);
+        // Signatures cover the message as sent, before parsing fills in defaults.
+        let signed_message = self
+            .message_verifier
+            .as_ref()
+            .and_then(|_| request.pointer("/params/message").cloned());
         let parsed_request = match a2a::A2aRequest::from_value(request) {
             Ok(parsed) => parsed,
             Err(err) => {
                 let formatter = JsonRpcResponseFormatter;
-                return Ok(vec![formatter.format_error(request_id, &err)]);
+                let _ = responses.send(formatter.format_error(request_id, &err));
+                return Ok(());
             }
         };
         use baml_rt_core::ids::CorrelationId;
@@ -401,7 +770,13 @@ impl A2aRequestHandler for A2aAgent {
         } else {
             spans::a2a_request(parsed_request.method.as_str(), correlation_id.as_str())
         };
+        if let Some(trace_context) = &parsed_request.trace_context {
+            spans::set_remote_parent(&span, trace_context);
+        }
         let _guard = span.enter();
+        if let Some(trace_id) = spans::current_trace_id() {
+            tracing::debug!(correlation_id = correlation_id.as_str(), trace_id, "A2A request traced");
+        }
         let start = std::time::Instant::now();
         let method = parsed_request.method;
         let is_stream = parsed_request.is_stream;
@@ -410,37 +785,84 @@ impl A2aRequestHandler for A2aAgent {
             parsed_request.context_id.clone().unwrap_or_else(context::generate_context_id);
         let request_message_id = parsed_request.message_id.clone();
         let request_task_id = parsed_request.task_id.clone();
+        let request_tenant = parsed_request.tenant.clone();
+        let request_trace_context = parsed_request.trace_context.clone();
+        let request_parent_context = parsed_request.parent_context_id.clone();
+        let request_delegation_chain = parsed_request.delegation_chain.clone();
         let agent_id = self.agent_id.clone();
+        let response_id = request_id.clone();
+        // Chunks are drained inside the request's scope: the handler producing
+        // them still runs while they are read.
         let outcome = correlation::with_correlation_id(correlation_id, async move {
             let scope = context::RuntimeScope::new(
                 request_context_id,
                 agent_id,
                 request_message_id,
                 request_task_id,
-            );
+            )
+            .with_tenant(request_tenant)
+            .with_trace_context(request_trace_context)
+            .with_parent_context(request_parent_context)
+            .with_delegation_chain(request_delegation_chain);
             context::with_scope(scope, async move {
-                if matches!(
+                let mut parsed_request = parsed_request;
+                let mut activated = None;
+                let is_message = matches!(
                     parsed_request.method,
                     a2a::A2aMethod::MessageSend | a2a::A2aMethod::MessageSendStream
-                ) && let Ok(params) =
+                );
+                if is_message
+                    && let (Some(verifier), Some(signed_message)) = (&self.message_verifier, &signed_message)
+                {
+                    let status = verifier.check(signed_message)?;
+                    if let Some(message) = parsed_request.params.get_mut("message") {
+                        signing::record_status(message, &status);
+                    }
+                }
+                if is_message && let Ok(params) =
                     serde_json::from_value::<SendMessageRequest>(parsed_request.params.clone())
                 {
-                    self.task_store.insert_message(&params.message).await;
+                    let extensions = self.extensions.negotiate(&params.message)?;
+                    self.task_store.insert_message(&params.message).await?;
+                    if !extensions.is_empty() {
+                        extensions.apply_to_params(&mut parsed_request.params);
+                        activated = Some(extensions);
+                    }
+                }
+                // Held until the outcome is sent: streamed chunks are produced meanwhile.
+                let _slot = match &self.task_queue {
+                    Some(queue) if is_message => {
+                        Some(self.enter_queue(queue, parsed_request.task_id.as_ref()).await)
+                    }
+                    _ => None,
+                };
+                let mut outcome = self.request_router.route(&parsed_request).await?;
+                if let Some(extensions) = activated {
+                    outcome = extensions.annotate_outcome(outcome);
                 }
-                self.request_router.route(&parsed_request).await
+                self.send_outcome(response_id, outcome, responses).await
             })
             .await
         })
         .await;
 
+        let outcome = outcome.with_error_context(|| {
+            ErrorContext::operation(method.as_str()).with_agent(self.agent_id.as_str())
+        });
         let duration = start.elapsed();
         match &outcome {
-            Ok(a2a::A2aOutcome::Stream(chunks)) => {
+            Ok(Some(chunks)) => {
                 metrics::record_a2a_request(method.as_str(), "success", is_stream, duration);
-                metrics::record_a2a_stream_chunks(method.as_str(), chunks.len());
+                metrics::record_a2a_stream_chunks(method.as_str(), *chunks);
             }
-            Ok(_) => metrics::record_a2a_request(method.as_str(), "success", is_stream, duration),
+            Ok(None) => metrics::record_a2a_request(method.as_str(), "success", is_stream, duration),
             Err(err) => {
+                tracing::warn!(
+                    code = %err.code(),
+                    error = %err,
+                    context = ?err.context_chain(),
+                    "A2A request failed"
+                );
                 metrics::record_a2a_request(method.as_str(), "error", is_stream, duration);
                 metrics::record_a2a_error(
                     method.as_str(),
@@ -449,21 +871,309 @@ impl A2aRequestHandler for A2aAgent {
                 );
             }
         }
+        if let Err(err) = outcome {
+            let _ = responses.send(self.response_formatter.format_error(request_id, &err));
+        }
+        Ok(())
+    }
+
+    /// A slot in `queue` for a message naming `task_id`. A task that is new to
+    /// the store is recorded as submitted while the message waits.
+    async fn enter_queue(
+        &self,
+        queue: &TaskQueue,
+        task_id: Option<&baml_rt_core::ids::TaskId>,
+    ) -> TaskSlot {
+        if let Some(slot) = queue.try_start() {
+            return slot;
+        }
+        if let Some(task_id) = task_id
+            && self.task_store.get(task_id.as_str(), Some(0)).await.is_none()
+        {
+            self.record_submitted(task_id).await;
+        }
+        queue.start().await
+    }
+
+    async fn record_submitted(&self, task_id: &baml_rt_core::ids::TaskId) {
+        let context_id = context::current_context_id();
+        let status = TaskStatus {
+            state: Some(TaskState::String(TaskLifecycleState::Submitted.as_str().to_string())),
+            ..TaskStatus::default()
+        };
+        self.task_store
+            .upsert(Task {
+                id: Some(task_id.clone()),
+                context_id: context_id.clone(),
+                artifacts: Vec::new(),
+                history: Vec::new(),
+                status: Some(status.clone()),
+                metadata: None,
+                extra: HashMap::new(),
+            })
+            .await;
+        let update = self
+            .task_store
+            .record_status_update(Some(task_id.clone()), context_id, status)
+            .await;
+        if let Some(update) = update {
+            BroadcastEventEmitter::new(self.update_tx.clone()).emit(update).await;
+        }
+    }
 
-        let responses = match outcome {
-            Ok(a2a::A2aOutcome::Response(result)) => {
-                vec![self.response_formatter.format_success(request_id, result)]
+    /// Send the responses for `outcome`, returning the number of stream chunks
+    /// sent if it was a stream.
+    async fn send_outcome(
+        &self,
+        id: Option<JSONRPCId>,
+        outcome: a2a::A2aOutcome,
+        responses: &mpsc::UnboundedSender<Value>,
+    ) -> Result<Option<usize>> {
+        match outcome {
+            a2a::A2aOutcome::Response(result) => {
+                let _ = responses.send(self.response_formatter.format_success(id, result));
+                Ok(None)
             }
-            Ok(a2a::A2aOutcome::Stream(chunks)) => {
-                self.response_formatter.format_stream(request_id, chunks)
+            a2a::A2aOutcome::Stream(chunks) => {
+                let count = chunks.len();
+                for response in self.response_formatter.format_stream(id, chunks) {
+                    let _ = responses.send(response);
+                }
+                Ok(Some(count))
             }
-            Err(err) => vec![self.response_formatter.format_error(request_id, &err)],
-        };
+            a2a::A2aOutcome::Chunks(mut chunks) => {
+                // Each chunk waits for the next, so the last one can be marked final.
+                let mut pending: Option<Value> = None;
+                let mut index = 0;
+                loop {
+                    let next = match chunks.next().await {
+                        Some(Ok(chunk)) => Some(chunk),
+                        Some(Err(err)) => {
+                            if let Some(chunk) = pending.take() {
+                                let _ = responses.send(
+                                    self.response_formatter.format_stream_chunk(id.clone(), chunk, index, false),
+                                );
+                            }
+                            return Err(err);
+                        }
+                        None => None,
+                    };
+                    let is_final = next.is_none();
+                    if let Some(chunk) = std::mem::replace(&mut pending, next) {
+                        let _ = responses.send(
+                            self.response_formatter.format_stream_chunk(id.clone(), chunk, index, is_final),
+                        );
+                        index += 1;
+                    }
+                    if is_final {
+                        return Ok(Some(index));
+                    }
+                }
+            }
+        }
+    }
+}
 
-        Ok(responses)
+/// JSON-RPC notification method carrying a pushed [`TaskUpdateEvent`].
+pub const TASK_UPDATE_NOTIFICATION: &str = "tasks.update";
+
+/// A bound WebSocket listener serving the A2A JSON-RPC API.
+///
+/// Each text frame carries one JSON-RPC request, and every response is sent back as its
+/// own text frame as soon as it is ready, so clients match them by `id`. Once a
+/// connection has successfully called `tasks.subscribe` for a task, it is also pushed
+/// `tasks.update` notifications (a [`StreamResponse`] in `params`) for that task.
+///
+/// Like [`crate::A2aHttpServer`], every connection runs on the caller's task because
+/// handlers are `?Send`.
+pub struct A2aWebSocketServer {
+    listener: TcpListener,
+}
+
+struct WsReply {
+    responses: Vec<Value>,
+    subscribed: Option<String>,
+}
+
+impl A2aWebSocketServer {
+    /// Bind to `addr`. Use port 0 to pick a free port, then read it from [`Self::local_addr`].
+    pub async fn bind(addr: SocketAddr) -> Result<Self> {
+        Ok(Self { listener: TcpListener::bind(addr).await? })
+    }
+
+    pub fn from_listener(listener: TcpListener) -> Self {
+        Self { listener }
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Serve connections with `handler` until `shutdown` resolves.
+    ///
+    /// Each connection gets its own copy of `updates` (see
+    /// [`A2aAgent::subscribe_task_updates`]). On shutdown, connections stop reading,
+    /// finish their in-flight requests and are closed.
+    pub async fn serve<H, F>(
+        self,
+        handler: &H,
+        updates: broadcast::Receiver<TaskUpdateEvent>,
+        shutdown: F,
+    ) -> Result<()>
+    where
+        H: A2aRequestHandler + ?Sized,
+        F: Future<Output = ()>,
+    {
+        let addr = self.local_addr()?;
+        info!(%addr, "A2A WebSocket transport listening");
+        let (closing_tx, closing_rx) = watch::channel(false);
+        let mut connections = FuturesUnordered::new();
+        let mut shutdown = std::pin::pin!(shutdown);
+        loop {
+            tokio::select! {
+                accepted = self.listener.accept() => match accepted {
+                    Ok((stream, peer)) => {
+                        let updates = updates.resubscribe();
+                        let closing = closing_rx.clone();
+                        connections.push(async move {
+                            if let Err(err) = serve_ws_connection(handler, stream, updates, closing).await {
+                                warn!(%peer, error = %err, "A2A WebSocket connection failed");
+                            }
+                        });
+                    }
+                    Err(err) => warn!(error = %err, "Failed to accept A2A WebSocket connection"),
+                },
+                Some(()) = connections.next(), if !connections.is_empty() => {}
+                () = &mut shutdown => break,
+            }
+        }
+
+        let _ = closing_tx.send(true);
+        while connections.next().await.is_some() {}
+        Ok(())
+    }
+}
+
+async fn serve_ws_connection<H>(
+    handler: &H,
+    stream: TcpStream,
+    mut updates: broadcast::Receiver<TaskUpdateEvent>,
+    mut closing: watch::Receiver<bool>,
+) -> Result<()>
+where
+    H: A2aRequestHandler + ?Sized,
+{
+    let config = WebSocketConfig::default().max_message_size(Some(DEFAULT_MAX_MESSAGE_BYTES));
+    let socket = tokio_tungstenite::accept_async_with_config(stream, Some(config))
+        .await
+        .map_err(std::io::Error::other)?;
+    let (mut sink, mut frames) = socket.split();
+    let mut subscribed_tasks = HashSet::new();
+    let mut in_flight = FuturesUnordered::new();
+    let mut reading = true;
+    let mut updates_open = true;
+
+    while reading || !in_flight.is_empty() {
+        tokio::select! {
+            frame = frames.next(), if reading => match frame {
+                Some(Ok(WsMessage::Text(text))) => in_flight.push(handle_ws_request(handler, text)),
+                Some(Ok(WsMessage::Binary(_))) => {
+                    let error = BamlRtError::InvalidArgument(
+                        "A2A WebSocket requests must be sent as text frames".to_string(),
+                    );
+                    let response = JsonRpcResponseFormatter.format_error(None, &error);
+                    send_ws_json(&mut sink, &response).await?;
+                }
+                // Ping/pong is answered by tungstenite itself.
+                Some(Ok(WsMessage::Ping(_) | WsMessage::Pong(_) | WsMessage::Frame(_))) => {}
+                Some(Ok(WsMessage::Close(_))) | None => reading = false,
+                Some(Err(err)) => {
+                    warn!(error = %err, "A2A WebSocket read failed");
+                    reading = false;
+                }
+            },
+            Some(reply) = in_flight.next(), if !in_flight.is_empty() => {
+                subscribed_tasks.extend(reply.subscribed);
+                for response in &reply.responses {
+                    send_ws_json(&mut sink, response).await?;
+                }
+            },
+            update = updates.recv(), if updates_open => match update {
+                Ok(update) => {
+                    if update.task_id().is_some_and(|task_id| subscribed_tasks.contains(task_id)) {
+                        let notification = serde_json::json!({
+                            "jsonrpc": "2.0",
+                            "method": TASK_UPDATE_NOTIFICATION,
+                            "params": StreamResponse::from(update),
+                        });
+                        send_ws_json(&mut sink, &notification).await?;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!(skipped, "A2A WebSocket client fell behind on task updates");
+                }
+                Err(broadcast::error::RecvError::Closed) => updates_open = false,
+            },
+            _ = closing.changed(), if reading => reading = false,
+        }
+    }
+
+    // The peer may already be gone; there is nobody left to report a failed close to.
+    let _ = sink.close().await;
+    Ok(())
+}
+
+async fn handle_ws_request<H>(handler: &H, text: Utf8Bytes) -> WsReply
+where
+    H: A2aRequestHandler + ?Sized,
+{
+    let request: Value = match serde_json::from_str(text.as_str()) {
+        Ok(request) => request,
+        Err(err) => {
+            let error = BamlRtError::Json(err);
+            return WsReply {
+                responses: vec![JsonRpcResponseFormatter.format_error(None, &error)],
+                subscribed: None,
+            };
+        }
+    };
+    let request_id = a2a::extract_jsonrpc_id(&request);
+    let subscribe_to = subscribe_task_id(&request);
+    match handler.handle_a2a(request).await {
+        Ok(responses) => {
+            let succeeded = responses.iter().all(|response| response.get("error").is_none());
+            WsReply { subscribed: subscribe_to.filter(|_| succeeded), responses }
+        }
+        Err(err) => WsReply {
+            responses: vec![JsonRpcResponseFormatter.format_error(request_id, &err)],
+            subscribed: None,
+        },
     }
 }
 
+/// Task id of a `tasks.subscribe` request, including agent-prefixed method names.
+fn subscribe_task_id(request: &Value) -> Option<String> {
+    let method = request.get("method").and_then(Value::as_str)?;
+    let subscribe = a2a::A2aMethod::TasksSubscribe.as_str();
+    if !(method.ends_with(subscribe) || method.ends_with(&subscribe.replace('.', "/"))) {
+        return None;
+    }
+    request
+        .get("params")
+        .and_then(|params| params.get("id"))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
+async fn send_ws_json<S>(sink: &mut S, value: &Value) -> Result<()>
+where
+    S: Sink<WsMessage, Error = tokio_tungstenite::tungstenite::Error> + Unpin,
+{
+    sink.send(WsMessage::text(value.to_string()))
+        .await
+        .map_err(|err| BamlRtError::Io(std::io::Error::other(err)))
+}
+
 impl A2aAgent {
     // Result storage is handled by ResultStoragePipeline.
 }
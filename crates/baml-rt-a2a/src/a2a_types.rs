@@ -1,4 +1,6 @@
-use baml_rt_core::ids::{ArtifactId, ContextId, DerivedId, ExternalId, MessageId, TaskId};
+use baml_rt_core::ids::{AgentId, ArtifactId, ContextId, DerivedId, ExternalId, MessageId, TaskId};
+use baml_rt_core::manifest::AgentManifest;
+use baml_rt_interceptor::PendingApproval;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
@@ -7,6 +9,11 @@ pub const MESSAGE_KIND: &str = "message";
 pub const ROLE_USER: &str = "ROLE_USER";
 pub const ROLE_AGENT: &str = "ROLE_AGENT";
 pub const TASK_STATE_CANCELED: &str = "TASK_STATE_CANCELED";
+pub const TRANSPORT_STDIO: &str = "stdio";
+pub const TRANSPORT_HTTP: &str = "http";
+pub const TRANSPORT_WEBSOCKET: &str = "websocket";
+/// Largest JSON-RPC request, in bytes, the built-in transports accept.
+pub const DEFAULT_MAX_MESSAGE_BYTES: usize = 2 * 1024 * 1024;
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -56,7 +63,7 @@ pub enum MessageRole {
     Integer(i64),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum TaskState {
     String(String),
@@ -333,6 +340,226 @@ pub struct SubscribeToTaskRequest {
     pub extra: HashMap<String, Value>,
 }
 
+/// Params of `tasks.pollUpdates`, the long-poll alternative to `tasks.subscribe`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PollTaskUpdatesRequest {
+    pub id: TaskId,
+    /// `cursor` from the previous poll; omit to start at the oldest buffered update.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<u64>,
+    /// How long to wait for an update when none are buffered yet.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PollTaskUpdatesResponse {
+    #[serde(default)]
+    pub updates: Vec<StreamResponse>,
+    /// Pass back as `cursor` on the next poll.
+    pub cursor: u64,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForkContextRequest {
+    pub context_id: ContextId,
+    pub message_id: MessageId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_context_id: Option<ContextId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextBranch {
+    pub context_id: ContextId,
+    pub parent_context_id: ContextId,
+    pub forked_at_message_id: MessageId,
+    #[serde(default)]
+    pub history: Vec<Message>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListMessagesRequest {
+    pub context_id: ContextId,
+    /// Keep only this many of the most recent messages before paging.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub history_length: Option<NumberOrString>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_size: Option<NumberOrString>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListMessagesResponse {
+    /// Oldest first.
+    #[serde(default)]
+    pub messages: Vec<Message>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_page_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_size: Option<u64>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListContextsRequest {
+    /// Include this many of each context's most recent messages; all when unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub history_length: Option<NumberOrString>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_size: Option<NumberOrString>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextSummary {
+    pub context_id: ContextId,
+    pub message_count: u64,
+    /// The context this one was forked from or is a child of, if either.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_context_id: Option<ContextId>,
+    #[serde(default)]
+    pub history: Vec<Message>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListContextsResponse {
+    /// In the order the contexts were started.
+    #[serde(default)]
+    pub contexts: Vec<ContextSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_page_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_size: Option<u64>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmitFeedbackRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<MessageId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<TaskId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_id: Option<ContextId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rating: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub correction: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<HashMap<String, Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Feedback {
+    pub feedback_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_id: Option<ContextId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<MessageId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<TaskId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rating: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub correction: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<HashMap<String, Value>>,
+    pub created_at_ms: u64,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// Where to POST a task's updates.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PushNotificationConfig {
+    /// Setting a config with the same id again replaces it; one is assigned when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub url: String,
+    /// Sent back on every notification as `X-A2A-Notification-Token`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// Params and result of `tasks.pushNotificationConfig.set`, and result of
+/// `tasks.pushNotificationConfig.get`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskPushNotificationConfig {
+    pub task_id: TaskId,
+    pub push_notification_config: PushNotificationConfig,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// Params of `tasks.pushNotificationConfig.get`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetTaskPushNotificationConfigRequest {
+    pub id: TaskId,
+    /// Which of the task's configs; defaults to the first one set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub push_notification_config_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct TaskStatusUpdateEvent {
@@ -381,3 +608,197 @@ pub struct StreamResponse {
     #[serde(flatten)]
     pub extra: HashMap<String, Value>,
 }
+
+/// Params of `admin.setCaptureDetail`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetCaptureDetailRequest {
+    /// `full` to record whole payloads, `standard` to go back to truncated ones.
+    pub detail: String,
+    /// How long full capture lasts; defaults to five minutes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<u64>,
+    /// Limit full capture to this context instead of every context.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_id: Option<ContextId>,
+    /// Token of an operator allowed to change capture detail.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operator_token: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetCaptureDetailResponse {
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_id: Option<ContextId>,
+    /// Seconds until capture falls back to standard; absent when already standard.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_in_secs: Option<u64>,
+}
+
+/// Params of `admin.activeWork`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveWorkRequest {
+    /// Token of an operator allowed to see every tenant's work.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operator_token: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// Params of `admin.listApprovals`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListApprovalsRequest {
+    /// Token of an operator allowed to see waiting calls.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operator_token: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// Params of `admin.decideApproval`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecideApprovalRequest {
+    /// The `approvalId` `admin.listApprovals` reported.
+    pub approval_id: String,
+    pub approved: bool,
+    /// Token of the operator deciding, who is recorded in provenance as the
+    /// approver.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operator_token: Option<String>,
+    /// Why, for a denial; returned to the agent as the call's error.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// Result of `admin.listApprovals`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListApprovalsResponse {
+    pub approvals: Vec<PendingApproval>,
+}
+
+/// Result of `agent.capabilities`, so clients can feature-detect this runtime.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentCapabilities {
+    pub transports: Vec<String>,
+    pub streaming: bool,
+    pub max_message_bytes: usize,
+    pub methods: Vec<String>,
+    pub extensions: AgentExtensions,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentExtensions {
+    pub push_notifications: bool,
+    pub artifacts: bool,
+    pub feedback: bool,
+    pub context_fork: bool,
+}
+
+impl Default for AgentCapabilities {
+    fn default() -> Self {
+        Self {
+            transports: vec![
+                TRANSPORT_STDIO.to_string(),
+                TRANSPORT_HTTP.to_string(),
+                TRANSPORT_WEBSOCKET.to_string(),
+            ],
+            streaming: true,
+            max_message_bytes: DEFAULT_MAX_MESSAGE_BYTES,
+            methods: crate::a2a::A2aMethod::ALL
+                .iter()
+                .map(|method| method.as_str().to_string())
+                .collect(),
+            extensions: AgentExtensions {
+                push_notifications: false,
+                artifacts: true,
+                feedback: true,
+                context_fork: true,
+            },
+        }
+    }
+}
+
+/// Result of `agent.getCard`: what the agent is and how to talk to it, assembled
+/// from its package manifest and the capabilities it serves.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentCard {
+    pub name: String,
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Host tools the agent uses, as `bundle/tool` names.
+    pub tools: Vec<String>,
+    /// BAML functions the agent may invoke; absent when every function is allowed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub functions: Option<Vec<String>>,
+    /// Features the package declares in its manifest.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub features: Vec<String>,
+    pub methods: Vec<String>,
+    pub streaming: bool,
+    pub transports: Vec<String>,
+    /// A2A extensions the agent supports, activated per message by URI.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extensions: Vec<AgentExtensionDeclaration>,
+}
+
+/// An extension as advertised on the [`AgentCard`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentExtensionDeclaration {
+    pub uri: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Messages that do not activate a required extension are rejected.
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<Value>,
+}
+
+impl AgentCard {
+    pub fn from_manifest(manifest: &AgentManifest, capabilities: &AgentCapabilities) -> Self {
+        Self {
+            name: manifest.name.clone(),
+            version: manifest.version.clone(),
+            description: manifest.description.clone(),
+            tools: manifest.tools.clone(),
+            functions: manifest.functions.clone(),
+            features: manifest.capabilities.clone(),
+            methods: capabilities.methods.clone(),
+            streaming: capabilities.streaming,
+            transports: capabilities.transports.clone(),
+            extensions: Vec::new(),
+        }
+    }
+
+    /// Card for an agent built without a package manifest: it is named by its id
+    /// and versioned with the runtime.
+    pub fn anonymous(agent_id: &AgentId, capabilities: &AgentCapabilities) -> Self {
+        Self {
+            name: agent_id.as_str().to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            description: None,
+            tools: Vec::new(),
+            functions: None,
+            features: Vec::new(),
+            methods: capabilities.methods.clone(),
+            streaming: capabilities.streaming,
+            transports: capabilities.transports.clone(),
+            extensions: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! Streams fed by a future that produces items into a channel.
+//!
+//! Invocations hold locks and task-local scopes for as long as they run, so they
+//! cannot hand back a stream that outlives them. Instead they send what they
+//! produce into a channel, and [`channel_stream`] polls the invocation and the
+//! channel together, yielding each item as soon as it is sent.
+
+use baml_rt_core::Result;
+use futures_util::stream::{self, LocalBoxStream, StreamExt};
+use serde_json::Value;
+use std::future::Future;
+use std::task::Poll;
+use tokio::sync::mpsc;
+
+/// JSON values in the order they were produced, ended by an error if the
+/// producer failed.
+pub type ChunkStream<'a> = LocalBoxStream<'a, Result<Value>>;
+
+/// Yield what `producer` sends to `receiver`'s channel while it runs, then its
+/// error, if any.
+///
+/// The stream ends once the producer has finished and every sender is gone.
+pub(crate) fn channel_stream<'a, F>(
+    producer: F,
+    mut receiver: mpsc::UnboundedReceiver<Value>,
+) -> ChunkStream<'a>
+where
+    F: Future<Output = Result<()>> + 'a,
+{
+    let mut producer = Some(Box::pin(producer));
+    let mut failure = None;
+    stream::poll_fn(move |cx| {
+        if let Some(running) = producer.as_mut()
+            && let Poll::Ready(result) = running.as_mut().poll(cx)
+        {
+            producer = None;
+            failure = result.err();
+        }
+        match receiver.poll_recv(cx) {
+            Poll::Ready(Some(item)) => Poll::Ready(Some(Ok(item))),
+            // Senders can outlive a failed producer only briefly; wait for both.
+            Poll::Ready(None) if producer.is_some() => Poll::Pending,
+            Poll::Ready(None) => Poll::Ready(failure.take().map(Err)),
+            Poll::Pending => Poll::Pending,
+        }
+    })
+    .boxed_local()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use baml_rt_core::BamlRtError;
+
+    #[tokio::test]
+    async fn items_are_yielded_while_the_producer_runs() {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let (step_tx, mut step_rx) = mpsc::unbounded_channel::<()>();
+        let producer = async move {
+            sender.send(Value::from(1)).ok();
+            step_rx.recv().await;
+            sender.send(Value::from(2)).ok();
+            Err(BamlRtError::QuickJs("boom".to_string()))
+        };
+        let mut chunks = channel_stream(producer, receiver);
+
+        assert_eq!(chunks.next().await.unwrap().unwrap(), Value::from(1));
+        step_tx.send(()).unwrap();
+        assert_eq!(chunks.next().await.unwrap().unwrap(), Value::from(2));
+        assert!(chunks.next().await.unwrap().is_err());
+        assert!(chunks.next().await.is_none());
+    }
+}
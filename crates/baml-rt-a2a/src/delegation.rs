@@ -0,0 +1,382 @@
+//! The `a2a/delegate` tool: one agent asking another for help.
+//!
+//! An agent that declares `a2a/delegate` can send a message to any agent in
+//! its [`AgentDirectory`] and get the reply back as the tool's output. The
+//! tool's description and the `agent` parameter are generated from the cards
+//! of the agents in the directory, so a plan sees who it can delegate to and
+//! what each one does.
+//!
+//! The delegated `message.send` carries the caller's trace context and names
+//! the caller as `calling_agent_id`, so the receiving agent's provenance
+//! records that it acted on the caller's behalf. Unless the caller continues
+//! one of its own contexts, the delegated work runs in a child of the caller's
+//! context (`parent_context_id`).
+//!
+//! The request also carries the caller's tenant and the chain of agents whose
+//! delegations led to it (`delegation_chain`). A delegation back to an agent
+//! already in the chain, or one more than [`MAX_DELEGATION_DEPTH`] deep, is
+//! refused rather than sent, so agents cannot ask each other in circles.
+
+use crate::a2a::{inject_calling_agent, inject_parent_context, inject_trace_context};
+use crate::a2a_types::{AgentCard, ROLE_USER};
+use crate::A2aRequestHandler;
+use async_trait::async_trait;
+use baml_rt_core::context::{self, DELEGATION_CHAIN_METADATA_KEY};
+use baml_rt_core::Result;
+use baml_rt_tools::tools::{ToolFunctionMetadata, ToolSessionContext};
+use baml_rt_tools::{
+    json_schema_value, register_tool_metadata, ts_decl, ts_name, ToolFailure, ToolHandler,
+    ToolName, ToolSession, ToolSessionError, ToolStep, ToolTypeSpec,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use ts_rs::TS;
+
+/// Name of the delegation tool agents declare in their manifest.
+pub const DELEGATE_TOOL: &str = "a2a/delegate";
+
+/// Most delegations one request may pass through, counting the one being made.
+pub const MAX_DELEGATION_DEPTH: usize = 8;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, TS)]
+#[ts(export)]
+pub struct A2aDelegateInput {
+    /// The agent to ask, by the name on its agent card
+    pub agent: String,
+    /// What to ask it
+    pub message: String,
+    /// Continue the conversation an earlier delegation started
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, TS)]
+#[ts(export)]
+pub struct A2aDelegateOutput {
+    pub agent: String,
+    /// The text of the agent's reply, when it has any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// The agent's whole `message.send` result
+    #[ts(type = "any")]
+    pub response: Value,
+}
+
+#[derive(Clone)]
+struct DirectoryEntry {
+    card: AgentCard,
+    handler: Arc<dyn A2aRequestHandler>,
+}
+
+/// The agents that can be delegated to, by card name
+///
+/// Clones share their entries, so agents added after a delegate tool was
+/// registered can still be reached through it.
+#[derive(Clone, Default)]
+pub struct AgentDirectory {
+    agents: Arc<RwLock<BTreeMap<String, DirectoryEntry>>>,
+}
+
+impl AgentDirectory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make the agent `card` describes reachable through `handler`, replacing
+    /// any agent of the same name
+    pub fn insert(&self, card: AgentCard, handler: Arc<dyn A2aRequestHandler>) {
+        let name = card.name.clone();
+        self.entries_mut().insert(name, DirectoryEntry { card, handler });
+    }
+
+    pub fn remove(&self, name: &str) -> bool {
+        self.entries_mut().remove(name).is_some()
+    }
+
+    /// Cards of every agent in the directory, by name
+    pub fn cards(&self) -> Vec<AgentCard> {
+        self.entries().values().map(|entry| entry.card.clone()).collect()
+    }
+
+    fn handler(&self, name: &str) -> Option<Arc<dyn A2aRequestHandler>> {
+        self.entries().get(name).map(|entry| entry.handler.clone())
+    }
+
+    // A panic while the lock was held cannot leave the map half-updated, so a
+    // poisoned lock is still safe to use.
+    fn entries(&self) -> RwLockReadGuard<'_, BTreeMap<String, DirectoryEntry>> {
+        self.agents.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn entries_mut(&self) -> RwLockWriteGuard<'_, BTreeMap<String, DirectoryEntry>> {
+        self.agents.write().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Metadata of `a2a/delegate` describing the agents in `cards`
+pub fn delegate_metadata(cards: &[AgentCard]) -> ToolFunctionMetadata {
+    let parsed = ToolName::parse(DELEGATE_TOOL).expect("a2a tool name must be valid");
+    let class_name = ToolFunctionMetadata::derive_class_name(parsed.bundle(), parsed.local());
+
+    let mut description =
+        "Ask another agent to handle a request and return its reply.".to_string();
+    if cards.is_empty() {
+        description.push_str(" No other agents are available.");
+    } else {
+        description.push_str(" Available agents:");
+        for card in cards {
+            description.push_str(&format!("\n- {} (v{})", card.name, card.version));
+            if let Some(about) = &card.description {
+                description.push_str(&format!(": {}", about));
+            }
+        }
+    }
+
+    let mut input_schema = json_schema_value::<A2aDelegateInput>();
+    if !cards.is_empty()
+        && let Some(agent) = input_schema.pointer_mut("/properties/agent")
+    {
+        agent["enum"] = cards.iter().map(|card| Value::String(card.name.clone())).collect();
+    }
+
+    ToolFunctionMetadata {
+        name: parsed.clone(),
+        class_name,
+        description,
+        open_input_schema: json_schema_value::<()>(),
+        input_schema,
+        output_schema: json_schema_value::<A2aDelegateOutput>(),
+        open_input_type: ToolTypeSpec {
+            name: ts_name::<()>(),
+            ts_decl: ts_decl::<()>(),
+        },
+        input_type: ToolTypeSpec {
+            name: ts_name::<A2aDelegateInput>(),
+            ts_decl: ts_decl::<A2aDelegateInput>(),
+        },
+        output_type: ToolTypeSpec {
+            name: ts_name::<A2aDelegateOutput>(),
+            ts_decl: ts_decl::<A2aDelegateOutput>(),
+        },
+        tags: vec!["a2a".to_string(), "delegate".to_string()],
+        secret_requirements: Vec::new(),
+        // ALL Rust tools are host tools - they must be declared in manifest.json
+        is_host_tool: true,
+        timeout: None,
+    }
+}
+
+fn delegate_metadata_qualified() -> ToolFunctionMetadata {
+    delegate_metadata(&[])
+}
+
+register_tool_metadata!(delegate_metadata_qualified);
+
+/// Runs `a2a/delegate` for the agent named `caller`
+pub struct DelegateHandler {
+    caller: String,
+    directory: AgentDirectory,
+    metadata: ToolFunctionMetadata,
+}
+
+impl DelegateHandler {
+    /// Delegate from `caller` to the other agents in `directory`, described by
+    /// the cards it holds now
+    pub fn new(caller: impl Into<String>, directory: AgentDirectory) -> Self {
+        let caller = caller.into();
+        let cards: Vec<_> =
+            directory.cards().into_iter().filter(|card| card.name != caller).collect();
+        Self { metadata: delegate_metadata(&cards), caller, directory }
+    }
+}
+
+#[async_trait]
+impl ToolHandler for DelegateHandler {
+    fn metadata(&self) -> &ToolFunctionMetadata {
+        &self.metadata
+    }
+
+    async fn open_session(&self, ctx: ToolSessionContext) -> Result<Box<dyn ToolSession>> {
+        Ok(Box::new(DelegateSession {
+            ctx,
+            caller: self.caller.clone(),
+            directory: self.directory.clone(),
+            input: None,
+            completed: false,
+        }))
+    }
+}
+
+struct DelegateSession {
+    ctx: ToolSessionContext,
+    caller: String,
+    directory: AgentDirectory,
+    input: Option<A2aDelegateInput>,
+    completed: bool,
+}
+
+impl DelegateSession {
+    fn target(&self, agent: &str) -> std::result::Result<Arc<dyn A2aRequestHandler>, ToolFailure> {
+        if agent == self.caller {
+            return Err(ToolFailure::invalid_input(format!(
+                "Agent '{}' cannot delegate to itself",
+                agent
+            )));
+        }
+        let chain = self.chain();
+        if chain.iter().any(|name| name == agent) {
+            return Err(ToolFailure::invalid_input(format!(
+                "Delegation to '{}' would form a cycle: {} -> {}",
+                agent,
+                chain.join(" -> "),
+                agent
+            )));
+        }
+        if chain.len() > MAX_DELEGATION_DEPTH {
+            return Err(ToolFailure::invalid_input(format!(
+                "Delegation to '{}' exceeds the maximum depth of {}: {}",
+                agent,
+                MAX_DELEGATION_DEPTH,
+                chain.join(" -> ")
+            )));
+        }
+        self.directory.handler(agent).ok_or_else(|| {
+            let known: Vec<_> = self.directory.cards().into_iter().map(|card| card.name).collect();
+            ToolFailure::invalid_input(format!(
+                "Unknown agent '{}'; known agents: {}",
+                agent,
+                known.join(", ")
+            ))
+        })
+    }
+
+    /// The delegation chain in scope, ending with the caller
+    fn chain(&self) -> Vec<String> {
+        let mut chain = context::current_delegation_chain();
+        chain.push(self.caller.clone());
+        chain
+    }
+}
+
+#[async_trait]
+impl ToolSession for DelegateSession {
+    async fn send(&mut self, input: Value) -> std::result::Result<(), ToolSessionError> {
+        if self.input.is_some() {
+            return Err(ToolSessionError::Tool(ToolFailure::invalid_input(
+                "Delegation already has input",
+            )));
+        }
+        let input = serde_json::from_value(input).map_err(|e| {
+            ToolSessionError::Tool(ToolFailure::invalid_input(format!(
+                "Invalid delegation input: {}",
+                e
+            )))
+        })?;
+        self.input = Some(input);
+        Ok(())
+    }
+
+    async fn next(&mut self) -> std::result::Result<ToolStep, ToolSessionError> {
+        if self.completed {
+            return Ok(ToolStep::Done { output: None });
+        }
+        let input = self.input.take().ok_or_else(|| {
+            ToolSessionError::Tool(ToolFailure::invalid_input(format!(
+                "Delegation {} has no input",
+                self.ctx.session_id
+            )))
+        })?;
+        self.completed = true;
+        let target = match self.target(&input.agent) {
+            Ok(target) => target,
+            Err(error) => return Ok(ToolStep::Error { error }),
+        };
+
+        let request = delegation_request(&input, self.chain());
+        let handle = tokio::runtime::Handle::current();
+        let responses =
+            tokio::task::block_in_place(|| handle.block_on(target.handle_a2a(request)))
+                .map_err(ToolSessionError::Transport)?;
+        let response = responses.into_iter().next().unwrap_or(Value::Null);
+        if let Some(error) = response.get("error") {
+            let message = error.get("message").and_then(Value::as_str).unwrap_or("unknown error");
+            return Ok(ToolStep::Error {
+                error: ToolFailure::execution_failed(format!(
+                    "Agent '{}' failed: {}",
+                    input.agent, message
+                )),
+            });
+        }
+        let response = response.get("result").cloned().unwrap_or(Value::Null);
+        let output = A2aDelegateOutput { agent: input.agent, text: reply_text(&response), response };
+        let output = serde_json::to_value(output).map_err(|e| {
+            ToolSessionError::Tool(ToolFailure::execution_failed(format!(
+                "Invalid delegation output: {}",
+                e
+            )))
+        })?;
+        Ok(ToolStep::Done { output: Some(output) })
+    }
+
+    async fn finish(&mut self) -> std::result::Result<(), ToolSessionError> {
+        self.completed = true;
+        Ok(())
+    }
+
+    async fn abort(&mut self, _reason: Option<String>) -> std::result::Result<(), ToolSessionError> {
+        self.completed = true;
+        Ok(())
+    }
+}
+
+/// The `message.send` request asking for `input`, from the agent in scope
+/// at the end of `chain`
+fn delegation_request(input: &A2aDelegateInput, chain: Vec<String>) -> Value {
+    let message_id = uuid::Uuid::new_v4().to_string();
+    let mut message = json!({
+        "messageId": message_id,
+        "role": ROLE_USER,
+        "parts": [{ "text": input.message }],
+        "metadata": { DELEGATION_CHAIN_METADATA_KEY: chain },
+    });
+    if let Some(context_id) = &input.context_id {
+        message["contextId"] = Value::String(context_id.clone());
+    }
+    let mut request = json!({
+        "jsonrpc": "2.0",
+        "id": message_id,
+        "method": "message.send",
+        "params": { "message": message },
+    });
+    if let Some(tenant) = context::current_tenant() {
+        request["params"]["tenant"] = Value::String(tenant);
+    }
+    inject_trace_context(&mut request);
+    inject_calling_agent(&mut request);
+    inject_parent_context(&mut request);
+    request
+}
+
+/// Text parts of a `message.send` result: the reply message's, or else those
+/// of the task's artifacts
+fn reply_text(result: &Value) -> Option<String> {
+    let text_of = |parts: &Value| -> Vec<String> {
+        parts
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|part| part.get("text").and_then(Value::as_str))
+            .map(str::to_string)
+            .collect()
+    };
+    let mut texts = text_of(&result["message"]["parts"]);
+    if texts.is_empty() {
+        for artifact in result["task"]["artifacts"].as_array().into_iter().flatten() {
+            texts.extend(text_of(&artifact["parts"]));
+        }
+    }
+    (!texts.is_empty()).then(|| texts.join("\n"))
+}
@@ -0,0 +1,68 @@
+//! Console output of agent JavaScript recorded in provenance.
+//!
+//! [`ProvenanceConsoleSink`] turns each console line of a bridge into a
+//! `ConsoleMessage` event, stored as a diagnostic entity derived from the task
+//! or context that was running when the line was written. Lines written
+//! outside any invocation, e.g. while the agent's script loads, have no context
+//! to attach to and are only traced.
+
+use baml_rt_provenance::{ProvEvent, ProvenanceWriter};
+use baml_rt_quickjs::{ConsoleEntry, ConsoleLevel, ConsoleSink};
+use std::sync::Arc;
+use tokio::runtime::Handle;
+
+pub struct ProvenanceConsoleSink {
+    writer: Arc<dyn ProvenanceWriter>,
+    min_level: ConsoleLevel,
+    handle: Handle,
+}
+
+impl ProvenanceConsoleSink {
+    /// Record console lines with `writer` on the current Tokio runtime.
+    ///
+    /// # Panics
+    ///
+    /// Outside a Tokio runtime.
+    pub fn new(writer: Arc<dyn ProvenanceWriter>) -> Self {
+        Self { writer, min_level: ConsoleLevel::Debug, handle: Handle::current() }
+    }
+
+    /// Only record lines at `level` or above, e.g. `Warn` to keep just
+    /// warnings and errors.
+    pub fn with_min_level(mut self, level: ConsoleLevel) -> Self {
+        self.min_level = level;
+        self
+    }
+}
+
+fn severity(level: ConsoleLevel) -> u8 {
+    match level {
+        ConsoleLevel::Debug => 0,
+        ConsoleLevel::Log | ConsoleLevel::Info => 1,
+        ConsoleLevel::Warn => 2,
+        ConsoleLevel::Error => 3,
+    }
+}
+
+impl ConsoleSink for ProvenanceConsoleSink {
+    fn record(&self, entry: &ConsoleEntry) {
+        if severity(entry.level) < severity(self.min_level) {
+            return;
+        }
+        let Some(context_id) = entry.context_id.clone() else {
+            return;
+        };
+        let level = entry.level.as_str().to_string();
+        let message = entry.message.clone();
+        let event = match entry.task_id.clone() {
+            Some(task_id) => ProvEvent::console_message_task(context_id, task_id, level, message),
+            None => ProvEvent::console_message_global(context_id, level, message),
+        };
+        let writer = self.writer.clone();
+        // The sink is called on the QuickJS thread; write on the runtime instead
+        // of holding the script up.
+        self.handle.spawn(async move {
+            writer.add_event_with_logging(event, "console message").await;
+        });
+    }
+}
@@ -8,10 +8,18 @@ pub struct A2aErrorClassifier;
 
 impl ErrorClassifier for A2aErrorClassifier {
     fn classify(&self, error: &BamlRtError) -> &'static str {
-        match error {
+        match error.root() {
             BamlRtError::InvalidArgument(_) => "invalid_argument",
             BamlRtError::FunctionNotFound(_) => "function_not_found",
+            BamlRtError::ToolNotAllowed(_) => "tool_not_allowed",
+            BamlRtError::SessionStateViolation(_) => "session_state_violation",
+            BamlRtError::SchemaMismatch(_) => "schema_mismatch",
+            BamlRtError::Timeout(_) => "timeout",
+            BamlRtError::Budget(_) => "budget",
             BamlRtError::QuickJs(_) => "quickjs",
+            BamlRtError::JsRejection { .. } => "js_rejection",
+            BamlRtError::PromiseTimeout { .. } => "timeout",
+            BamlRtError::ResourceLimit { .. } => "resource_limit",
             BamlRtError::Json(_) => "json",
             BamlRtError::ToolExecution(_) => "tool_execution",
             _ => "internal",
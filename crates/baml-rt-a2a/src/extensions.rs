@@ -0,0 +1,218 @@
+//! A2A extensions: protocol additions an agent declares on its card and a
+//! client activates per message.
+//!
+//! A client activates an extension by listing its URI in `message.extensions`,
+//! and sends the extension's payload in `message.metadata` under the same URI.
+//! For every `message.send` and `message.sendStream` the agent:
+//!
+//! - rejects the message if it does not activate an extension declared required;
+//! - ignores URIs it has no handler for, as the A2A spec asks;
+//! - has each activated handler validate its payload, then hands what the
+//!   handlers contribute to the JS handler as `params.extensionContext[uri]`,
+//!   next to the activated URIs in `params.activatedExtensions`;
+//! - lists the activated URIs in the result's `activatedExtensions` (the first
+//!   chunk of a stream), with metadata the handlers contribute under
+//!   `metadata[uri]`.
+
+use crate::a2a::A2aOutcome;
+use crate::a2a_types::{AgentExtensionDeclaration, Message};
+use baml_rt_core::{BamlRtError, Result};
+use futures_util::StreamExt;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Handler for one extension URI, registered with
+/// [`A2aAgentBuilder::with_extension`](crate::A2aAgentBuilder::with_extension).
+///
+/// `payload` is the value the message carries under the extension's URI in its
+/// metadata, if any.
+pub trait A2aExtension: Send + Sync {
+    /// The URI clients activate the extension with.
+    fn uri(&self) -> &str;
+
+    fn description(&self) -> Option<&str> {
+        None
+    }
+
+    /// Whether every message must activate this extension.
+    fn required(&self) -> bool {
+        false
+    }
+
+    /// Extension-specific settings advertised on the agent card.
+    fn params(&self) -> Option<Value> {
+        None
+    }
+
+    /// Check the payload; an error rejects the message.
+    fn validate(&self, _message: &Message, _payload: Option<&Value>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Context for the JS handler, under `params.extensionContext[uri]`.
+    fn enrich(&self, _message: &Message, _payload: Option<&Value>) -> Result<Option<Value>> {
+        Ok(None)
+    }
+
+    /// Metadata for the response, under `metadata[uri]`.
+    fn response_metadata(&self, _message: &Message, _payload: Option<&Value>) -> Option<Value> {
+        None
+    }
+}
+
+/// The extensions an agent supports, by URI.
+#[derive(Clone, Default)]
+pub struct ExtensionRegistry {
+    extensions: BTreeMap<String, Arc<dyn A2aExtension>>,
+}
+
+impl ExtensionRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `extension`; a second handler for the same URI is an error.
+    pub fn register(&mut self, extension: Arc<dyn A2aExtension>) -> Result<()> {
+        let uri = extension.uri().to_string();
+        if self.extensions.contains_key(&uri) {
+            return Err(BamlRtError::InvalidArgument(format!(
+                "Extension '{uri}' is already registered"
+            )));
+        }
+        self.extensions.insert(uri, extension);
+        Ok(())
+    }
+
+    pub fn get(&self, uri: &str) -> Option<&Arc<dyn A2aExtension>> {
+        self.extensions.get(uri)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.extensions.is_empty()
+    }
+
+    /// What the agent card advertises, ordered by URI.
+    pub fn declarations(&self) -> Vec<AgentExtensionDeclaration> {
+        self.extensions
+            .values()
+            .map(|extension| AgentExtensionDeclaration {
+                uri: extension.uri().to_string(),
+                description: extension.description().map(str::to_string),
+                required: extension.required(),
+                params: extension.params(),
+            })
+            .collect()
+    }
+
+    /// Activate the extensions `message` asks for.
+    pub fn negotiate(&self, message: &Message) -> Result<ActivatedExtensions> {
+        let activates = |uri: &str| message.extensions.iter().any(|requested| requested == uri);
+        if let Some(missing) =
+            self.extensions.values().find(|extension| extension.required() && !activates(extension.uri()))
+        {
+            return Err(BamlRtError::InvalidArgument(format!(
+                "Extension '{}' is required but the message does not activate it",
+                missing.uri()
+            )));
+        }
+
+        let mut activated = ActivatedExtensions::default();
+        for uri in &message.extensions {
+            let Some(extension) = self.extensions.get(uri) else {
+                continue;
+            };
+            if activated.uris.contains(uri) {
+                continue;
+            }
+            let payload = message.metadata.as_ref().and_then(|metadata| metadata.get(uri));
+            extension.validate(message, payload)?;
+            if let Some(context) = extension.enrich(message, payload)? {
+                activated.context.insert(uri.clone(), context);
+            }
+            if let Some(metadata) = extension.response_metadata(message, payload) {
+                activated.metadata.insert(uri.clone(), metadata);
+            }
+            activated.uris.push(uri.clone());
+        }
+        Ok(activated)
+    }
+}
+
+/// The outcome of negotiating one message's extensions.
+#[derive(Debug, Clone, Default)]
+pub struct ActivatedExtensions {
+    uris: Vec<String>,
+    context: Map<String, Value>,
+    metadata: Map<String, Value>,
+}
+
+impl ActivatedExtensions {
+    /// Activated URIs, in the order the message listed them.
+    pub fn uris(&self) -> &[String] {
+        &self.uris
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.uris.is_empty()
+    }
+
+    /// Hand the activated URIs and handler context to the JS handler.
+    pub(crate) fn apply_to_params(&self, params: &mut Value) {
+        let Value::Object(params) = params else {
+            return;
+        };
+        params.insert("activatedExtensions".to_string(), self.uris_value());
+        if !self.context.is_empty() {
+            params.insert("extensionContext".to_string(), Value::Object(self.context.clone()));
+        }
+    }
+
+    /// Annotate the response, or the first chunk of a streamed one.
+    pub(crate) fn annotate_outcome(self, outcome: A2aOutcome) -> A2aOutcome {
+        match outcome {
+            A2aOutcome::Response(mut result) => {
+                self.annotate(&mut result);
+                A2aOutcome::Response(result)
+            }
+            A2aOutcome::Stream(mut chunks) => {
+                if let Some(first) = chunks.first_mut() {
+                    self.annotate(first);
+                }
+                A2aOutcome::Stream(chunks)
+            }
+            A2aOutcome::Chunks(chunks) => A2aOutcome::Chunks(
+                chunks
+                    .enumerate()
+                    .map(move |(index, chunk)| {
+                        let mut chunk = chunk?;
+                        if index == 0 {
+                            self.annotate(&mut chunk);
+                        }
+                        Ok(chunk)
+                    })
+                    .boxed_local(),
+            ),
+        }
+    }
+
+    fn annotate(&self, result: &mut Value) {
+        let Value::Object(result) = result else {
+            return;
+        };
+        result.insert("activatedExtensions".to_string(), self.uris_value());
+        if self.metadata.is_empty() {
+            return;
+        }
+        let metadata = result
+            .entry("metadata")
+            .or_insert_with(|| Value::Object(Map::new()));
+        if let Value::Object(metadata) = metadata {
+            metadata.extend(self.metadata.clone());
+        }
+    }
+
+    fn uris_value(&self) -> Value {
+        Value::Array(self.uris.iter().cloned().map(Value::String).collect())
+    }
+}
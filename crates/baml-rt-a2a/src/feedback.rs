@@ -0,0 +1,171 @@
+//! Feedback capture for rated messages and tasks.
+//!
+//! Clients submit ratings and corrections through `message.feedback`. Feedback is
+//! kept in a queryable store for evaluation/reporting and, when a provenance writer
+//! is configured, recorded as an entity derived from the rated message or task.
+
+use crate::a2a_types::{Feedback, SubmitFeedbackRequest};
+use async_trait::async_trait;
+use baml_rt_core::context;
+use baml_rt_core::ids::{ContextId, MessageId, TaskId};
+use baml_rt_core::{BamlRtError, Result};
+use baml_rt_provenance::{FeedbackTarget, ProvEvent, ProvenanceWriter};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// Filter for listing stored feedback. Unset fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct FeedbackQuery {
+    pub context_id: Option<ContextId>,
+    pub message_id: Option<MessageId>,
+    pub task_id: Option<TaskId>,
+}
+
+#[async_trait]
+pub trait FeedbackRepository: Send + Sync {
+    async fn record(&self, feedback: Feedback) -> Feedback;
+    async fn list(&self, query: &FeedbackQuery) -> Vec<Feedback>;
+}
+
+#[derive(Debug, Default)]
+pub struct FeedbackStore {
+    entries: Vec<Feedback>,
+}
+
+impl FeedbackStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, feedback: Feedback) -> Feedback {
+        self.entries.push(feedback.clone());
+        feedback
+    }
+
+    pub fn list(&self, query: &FeedbackQuery) -> Vec<Feedback> {
+        self.entries
+            .iter()
+            .filter(|feedback| matches_query(feedback, query))
+            .cloned()
+            .collect()
+    }
+}
+
+fn matches_query(feedback: &Feedback, query: &FeedbackQuery) -> bool {
+    fn matches<T: PartialEq>(expected: &Option<T>, actual: &Option<T>) -> bool {
+        expected.is_none() || expected == actual
+    }
+    matches(&query.context_id, &feedback.context_id)
+        && matches(&query.message_id, &feedback.message_id)
+        && matches(&query.task_id, &feedback.task_id)
+}
+
+#[async_trait]
+impl FeedbackRepository for Mutex<FeedbackStore> {
+    async fn record(&self, feedback: Feedback) -> Feedback {
+        let mut store = self.lock().await;
+        store.record(feedback)
+    }
+
+    async fn list(&self, query: &FeedbackQuery) -> Vec<Feedback> {
+        let store = self.lock().await;
+        store.list(query)
+    }
+}
+
+/// Feedback store that mirrors each submission into the provenance graph.
+pub struct ProvenanceFeedbackStore {
+    inner: Mutex<FeedbackStore>,
+    writer: Option<Arc<dyn ProvenanceWriter>>,
+}
+
+impl ProvenanceFeedbackStore {
+    pub fn new(writer: Option<Arc<dyn ProvenanceWriter>>) -> Self {
+        Self {
+            inner: Mutex::new(FeedbackStore::new()),
+            writer,
+        }
+    }
+}
+
+#[async_trait]
+impl FeedbackRepository for ProvenanceFeedbackStore {
+    async fn record(&self, feedback: Feedback) -> Feedback {
+        if let Some(writer) = &self.writer
+            && let Some(target) = feedback_target(&feedback)
+        {
+            let event = ProvEvent::feedback_submitted(
+                feedback.context_id.clone().unwrap_or_else(context::current_or_new),
+                feedback.feedback_id.clone(),
+                target,
+                feedback.rating,
+                feedback.correction.clone(),
+                feedback.comment.clone(),
+                feedback.metadata.as_ref().map(metadata_string_map),
+            );
+            writer.add_event_with_logging(event, "feedback submission").await;
+        }
+        let mut store = self.inner.lock().await;
+        store.record(feedback)
+    }
+
+    async fn list(&self, query: &FeedbackQuery) -> Vec<Feedback> {
+        let store = self.inner.lock().await;
+        store.list(query)
+    }
+}
+
+/// Messages are the primary feedback target; task feedback applies when no message is named.
+fn feedback_target(feedback: &Feedback) -> Option<FeedbackTarget> {
+    if let Some(message_id) = &feedback.message_id {
+        return Some(FeedbackTarget::Message { message_id: message_id.clone() });
+    }
+    feedback
+        .task_id
+        .as_ref()
+        .map(|task_id| FeedbackTarget::Task { task_id: task_id.clone() })
+}
+
+fn metadata_string_map(metadata: &HashMap<String, Value>) -> HashMap<String, String> {
+    metadata
+        .iter()
+        .filter_map(|(key, value)| value.as_str().map(|v| (key.clone(), v.to_string())))
+        .collect()
+}
+
+/// Validate a feedback submission and assign its id and timestamp.
+pub fn feedback_from_request(request: SubmitFeedbackRequest) -> Result<Feedback> {
+    if request.message_id.is_none() && request.task_id.is_none() {
+        return Err(BamlRtError::InvalidArgument(
+            "Feedback requires a messageId or taskId".to_string(),
+        ));
+    }
+    if request.rating.is_none() && request.correction.is_none() && request.comment.is_none() {
+        return Err(BamlRtError::InvalidArgument(
+            "Feedback requires a rating, correction, or comment".to_string(),
+        ));
+    }
+    Ok(Feedback {
+        feedback_id: Uuid::new_v4().to_string(),
+        context_id: request.context_id.or_else(context::current_context_id),
+        message_id: request.message_id,
+        task_id: request.task_id,
+        rating: request.rating,
+        correction: request.correction,
+        comment: request.comment,
+        metadata: request.metadata,
+        created_at_ms: now_millis(),
+        extra: HashMap::new(),
+    })
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
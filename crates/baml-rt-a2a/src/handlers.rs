@@ -1,16 +1,40 @@
 use crate::a2a;
-use crate::a2a_store::{TaskEventRecorder, TaskRepository, TaskUpdateQueue, TaskUpdateEvent};
+use crate::a2a_store::{
+    task_tenant, validate_page_token, ContextRepository, TaskEventRecorder, TaskRepository,
+    TaskUpdateEvent, TaskUpdateQueue,
+};
 use crate::a2a_types::{
-    CancelTaskRequest, GetTaskRequest, ListTasksRequest, ListTasksResponse, StreamResponse,
-    SubscribeToTaskRequest, TaskStatusUpdateEvent,
+    ActiveWorkRequest, CancelTaskRequest, DecideApprovalRequest, ForkContextRequest,
+    GetTaskPushNotificationConfigRequest, GetTaskRequest, ListApprovalsRequest,
+    ListApprovalsResponse, ListContextsRequest, ListMessagesRequest, ListTasksRequest, ListTasksResponse, PollTaskUpdatesRequest, PollTaskUpdatesResponse, SetCaptureDetailRequest,
+    SetCaptureDetailResponse, StreamResponse, SubmitFeedbackRequest, SubscribeToTaskRequest,
+    Task, TaskPushNotificationConfig, TaskStatusUpdateEvent,
 };
 use crate::events::EventEmitter;
+use crate::push_notifications::PushNotificationRegistry;
+use crate::task_state::TaskLifecycleState;
+use crate::feedback::{feedback_from_request, FeedbackRepository};
 use async_trait::async_trait;
+use baml_rt_core::context;
 use baml_rt_core::{BamlRtError, Result};
-use baml_rt_quickjs::QuickJSBridge;
+use baml_rt_interceptor::{
+    CaptureDetail, OperatorCredentials, PayloadCapture, PendingApprovalQueue,
+};
+use baml_rt_provenance::ProvenanceWriter;
+use baml_rt_quickjs::{QuickJSBridge, WorkTracker};
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::{broadcast, Mutex};
+
+/// How long `tasks.pollUpdates` waits when the request has no `timeoutMs`.
+pub const DEFAULT_POLL_WAIT: Duration = Duration::from_secs(10);
+/// Upper bound on any `tasks.pollUpdates` wait.
+pub const MAX_POLL_WAIT: Duration = Duration::from_secs(30);
+/// How long `admin.setCaptureDetail` raises capture when the request has no `durationSecs`.
+pub const DEFAULT_CAPTURE_DURATION: Duration = Duration::from_secs(300);
+/// Upper bound on any full-capture raise, so a forgotten toggle cannot stay on.
+pub const MAX_CAPTURE_DURATION: Duration = Duration::from_secs(3600);
 
 #[async_trait(?Send)]
 pub trait TaskHandler: Send + Sync {
@@ -22,6 +46,15 @@ pub trait TaskHandler: Send + Sync {
         request: SubscribeToTaskRequest,
         is_stream: bool,
     ) -> Result<a2a::A2aOutcome>;
+    async fn handle_poll_updates(&self, request: PollTaskUpdatesRequest) -> Result<a2a::A2aOutcome>;
+    async fn handle_set_push_config(
+        &self,
+        request: TaskPushNotificationConfig,
+    ) -> Result<a2a::A2aOutcome>;
+    async fn handle_get_push_config(
+        &self,
+        request: GetTaskPushNotificationConfigRequest,
+    ) -> Result<a2a::A2aOutcome>;
 }
 
 pub struct DefaultTaskHandler {
@@ -30,15 +63,19 @@ pub struct DefaultTaskHandler {
     update_queue: Arc<dyn TaskUpdateQueue>,
     bridge: Arc<Mutex<QuickJSBridge>>,
     emitter: Arc<dyn EventEmitter>,
+    updates: broadcast::Sender<TaskUpdateEvent>,
+    push_notifications: Option<PushNotificationRegistry>,
 }
 
 impl DefaultTaskHandler {
+    /// `updates` is the channel `emitter` publishes on; long polls wait on it.
     pub fn new(
         repository: Arc<dyn TaskRepository>,
         recorder: Arc<dyn TaskEventRecorder>,
         update_queue: Arc<dyn TaskUpdateQueue>,
         bridge: Arc<Mutex<QuickJSBridge>>,
         emitter: Arc<dyn EventEmitter>,
+        updates: broadcast::Sender<TaskUpdateEvent>,
     ) -> Self {
         Self {
             repository,
@@ -46,6 +83,60 @@ impl DefaultTaskHandler {
             update_queue,
             bridge,
             emitter,
+            updates,
+            push_notifications: None,
+        }
+    }
+
+    /// Keep the webhooks `tasks.pushNotificationConfig.set` registers in `registry`
+    pub fn with_push_notifications(mut self, registry: Option<PushNotificationRegistry>) -> Self {
+        self.push_notifications = registry;
+        self
+    }
+}
+
+impl DefaultTaskHandler {
+    /// Task `id` if it belongs to the requesting tenant.
+    ///
+    /// Another tenant's task is reported as missing, so task ids cannot be probed
+    /// across tenants.
+    async fn tenant_task(&self, id: &str, history_length: Option<usize>) -> Result<Task> {
+        tenant_task(self.repository.as_ref(), id, history_length).await
+    }
+
+    fn push_notifications(&self) -> Result<&PushNotificationRegistry> {
+        self.push_notifications.as_ref().ok_or_else(|| {
+            BamlRtError::InvalidArgument(
+                "Push notifications are not enabled for this agent".to_string(),
+            )
+        })
+    }
+}
+
+/// Task `id` from `repository` if it belongs to the requesting tenant; another
+/// tenant's task is reported as missing.
+async fn tenant_task(
+    repository: &dyn TaskRepository,
+    id: &str,
+    history_length: Option<usize>,
+) -> Result<Task> {
+    let tenant = context::current_tenant();
+    repository
+        .get(id, history_length)
+        .await
+        .filter(|task| task_tenant(task) == tenant.as_deref())
+        .ok_or_else(|| BamlRtError::InvalidArgument("Task not found".to_string()))
+}
+
+/// Wait for the next broadcast update for `task_id`; `false` once the channel closes.
+async fn next_update_for(updates: &mut broadcast::Receiver<TaskUpdateEvent>, task_id: &str) -> bool {
+    loop {
+        match updates.recv().await {
+            Ok(update) if update.task_id() == Some(task_id) => return true,
+            Ok(_) => {}
+            // Some of the missed updates may be for this task; re-read the buffer.
+            Err(broadcast::error::RecvError::Lagged(_)) => return true,
+            Err(broadcast::error::RecvError::Closed) => return false,
         }
     }
 }
@@ -54,28 +145,36 @@ impl DefaultTaskHandler {
 impl TaskHandler for DefaultTaskHandler {
     async fn handle_get(&self, request: GetTaskRequest) -> Result<a2a::A2aOutcome> {
         let history_length = request.history_length.and_then(|value| value.as_usize());
-        let task = self
-            .repository
-            .get(request.id.as_str(), history_length)
-            .await
-            .ok_or_else(|| BamlRtError::InvalidArgument("Task not found".to_string()))?;
+        let task = self.tenant_task(request.id.as_str(), history_length).await?;
         let value = serde_json::to_value(task).map_err(BamlRtError::Json)?;
         Ok(a2a::A2aOutcome::Response(value))
     }
 
-    async fn handle_list(&self, request: ListTasksRequest) -> Result<a2a::A2aOutcome> {
+    async fn handle_list(&self, mut request: ListTasksRequest) -> Result<a2a::A2aOutcome> {
+        // Listing follows the request scope, like every other task lookup.
+        request.tenant = context::current_tenant();
+        validate_page_token(request.page_token.as_deref())?;
         let response: ListTasksResponse = self.repository.list(&request).await;
         let value = serde_json::to_value(response).map_err(BamlRtError::Json)?;
         Ok(a2a::A2aOutcome::Response(value))
     }
 
     async fn handle_cancel(&self, request: CancelTaskRequest) -> Result<a2a::A2aOutcome> {
+        self.tenant_task(request.id.as_str(), Some(0)).await?;
         let task = {
             let task = self
                 .repository
                 .cancel(request.id.as_str())
                 .await
                 .ok_or_else(|| BamlRtError::InvalidArgument("Task not found".to_string()))?;
+            let state = task.status.as_ref().and_then(TaskLifecycleState::of);
+            if let Some(state) = state
+                && state != TaskLifecycleState::Canceled
+            {
+                return Err(BamlRtError::InvalidArgument(format!(
+                    "Task cannot be canceled in state {state}"
+                )));
+            }
             if let Some(status) = task.status.clone()
                 && let Some(event) = self
                     .recorder
@@ -106,11 +205,7 @@ impl TaskHandler for DefaultTaskHandler {
         request: SubscribeToTaskRequest,
         is_stream: bool,
     ) -> Result<a2a::A2aOutcome> {
-        let task = self
-            .repository
-            .get(request.id.as_str(), None)
-            .await
-            .ok_or_else(|| BamlRtError::InvalidArgument("Task not found".to_string()))?;
+        let task = self.tenant_task(request.id.as_str(), None).await?;
         let value = serde_json::to_value(&task).map_err(BamlRtError::Json)?;
 
         if is_stream {
@@ -132,22 +227,7 @@ impl TaskHandler for DefaultTaskHandler {
             responses.push(serde_json::to_value(response).map_err(BamlRtError::Json)?);
 
             for update in self.update_queue.drain_updates(request.id.as_str()).await {
-                let stream_response = match update {
-                    TaskUpdateEvent::Status(status_update) => StreamResponse {
-                        status_update: Some(status_update),
-                        message: None,
-                        task: None,
-                        artifact_update: None,
-                        extra: HashMap::new(),
-                    },
-                    TaskUpdateEvent::Artifact(artifact_update) => StreamResponse {
-                        artifact_update: Some(artifact_update),
-                        message: None,
-                        task: None,
-                        status_update: None,
-                        extra: HashMap::new(),
-                    },
-                };
+                let stream_response = StreamResponse::from(update);
                 responses.push(serde_json::to_value(stream_response).map_err(BamlRtError::Json)?);
             }
 
@@ -156,4 +236,311 @@ impl TaskHandler for DefaultTaskHandler {
             Ok(a2a::A2aOutcome::Response(value))
         }
     }
+
+    async fn handle_poll_updates(&self, request: PollTaskUpdatesRequest) -> Result<a2a::A2aOutcome> {
+        let task_id = request.id.as_str();
+        self.tenant_task(task_id, Some(0)).await?;
+        let cursor = request.cursor.unwrap_or(0);
+        let wait = request
+            .timeout_ms
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_POLL_WAIT)
+            .min(MAX_POLL_WAIT);
+        let deadline = tokio::time::Instant::now() + wait;
+
+        // Subscribe before reading the buffer so an update recorded in between still wakes us.
+        let mut wakeups = self.updates.subscribe();
+        let batch = loop {
+            let batch = self.update_queue.updates_since(task_id, cursor).await;
+            if !batch.updates.is_empty() {
+                break batch;
+            }
+            let woken = tokio::time::timeout_at(deadline, next_update_for(&mut wakeups, task_id)).await;
+            if !matches!(woken, Ok(true)) {
+                break batch;
+            }
+        };
+
+        let response = PollTaskUpdatesResponse {
+            updates: batch.updates.into_iter().map(StreamResponse::from).collect(),
+            cursor: batch.cursor,
+            extra: HashMap::new(),
+        };
+        let value = serde_json::to_value(response).map_err(BamlRtError::Json)?;
+        Ok(a2a::A2aOutcome::Response(value))
+    }
+
+    async fn handle_set_push_config(
+        &self,
+        mut request: TaskPushNotificationConfig,
+    ) -> Result<a2a::A2aOutcome> {
+        let registry = self.push_notifications()?;
+        self.tenant_task(request.task_id.as_str(), Some(0)).await?;
+        request.push_notification_config =
+            registry.set(request.task_id.as_str(), request.push_notification_config).await?;
+        let value = serde_json::to_value(request).map_err(BamlRtError::Json)?;
+        Ok(a2a::A2aOutcome::Response(value))
+    }
+
+    async fn handle_get_push_config(
+        &self,
+        request: GetTaskPushNotificationConfigRequest,
+    ) -> Result<a2a::A2aOutcome> {
+        let registry = self.push_notifications()?;
+        self.tenant_task(request.id.as_str(), Some(0)).await?;
+        let config = registry
+            .get(request.id.as_str())
+            .into_iter()
+            .find(|config| match &request.push_notification_config_id {
+                Some(id) => config.id.as_ref() == Some(id),
+                None => true,
+            })
+            .ok_or_else(|| {
+                BamlRtError::InvalidArgument(
+                    "Push notification config not found".to_string(),
+                )
+            })?;
+        let response = TaskPushNotificationConfig {
+            task_id: request.id,
+            push_notification_config: config,
+            tenant: request.tenant,
+            extra: HashMap::new(),
+        };
+        let value = serde_json::to_value(response).map_err(BamlRtError::Json)?;
+        Ok(a2a::A2aOutcome::Response(value))
+    }
+}
+
+#[async_trait(?Send)]
+pub trait FeedbackHandler: Send + Sync {
+    async fn handle_feedback(&self, request: SubmitFeedbackRequest) -> Result<a2a::A2aOutcome>;
+}
+
+pub struct DefaultFeedbackHandler {
+    repository: Arc<dyn FeedbackRepository>,
+    tasks: Arc<dyn TaskRepository>,
+}
+
+impl DefaultFeedbackHandler {
+    /// `tasks` is where task feedback targets are looked up for the requesting tenant.
+    pub fn new(repository: Arc<dyn FeedbackRepository>, tasks: Arc<dyn TaskRepository>) -> Self {
+        Self { repository, tasks }
+    }
+}
+
+#[async_trait(?Send)]
+impl FeedbackHandler for DefaultFeedbackHandler {
+    async fn handle_feedback(&self, request: SubmitFeedbackRequest) -> Result<a2a::A2aOutcome> {
+        let feedback = feedback_from_request(request)?;
+        if let Some(task_id) = &feedback.task_id {
+            tenant_task(self.tasks.as_ref(), task_id.as_str(), Some(0)).await?;
+        }
+        let feedback = self.repository.record(feedback).await;
+        let value = serde_json::to_value(feedback).map_err(BamlRtError::Json)?;
+        Ok(a2a::A2aOutcome::Response(value))
+    }
+}
+
+#[async_trait(?Send)]
+pub trait ContextHandler: Send + Sync {
+    async fn handle_fork(&self, request: ForkContextRequest) -> Result<a2a::A2aOutcome>;
+    /// A page of one context's message history, oldest first
+    async fn handle_list_messages(&self, request: ListMessagesRequest) -> Result<a2a::A2aOutcome>;
+    async fn handle_list_contexts(&self, request: ListContextsRequest) -> Result<a2a::A2aOutcome>;
+}
+
+pub struct DefaultContextHandler {
+    repository: Arc<dyn ContextRepository>,
+}
+
+impl DefaultContextHandler {
+    pub fn new(repository: Arc<dyn ContextRepository>) -> Self {
+        Self { repository }
+    }
+}
+
+#[async_trait(?Send)]
+impl ContextHandler for DefaultContextHandler {
+    async fn handle_fork(&self, request: ForkContextRequest) -> Result<a2a::A2aOutcome> {
+        let target = request.new_context_id.unwrap_or_else(context::generate_context_id);
+        let branch = self
+            .repository
+            .fork_context(&request.context_id, &request.message_id, target)
+            .await?;
+        let value = serde_json::to_value(branch).map_err(BamlRtError::Json)?;
+        Ok(a2a::A2aOutcome::Response(value))
+    }
+
+    async fn handle_list_messages(
+        &self,
+        mut request: ListMessagesRequest,
+    ) -> Result<a2a::A2aOutcome> {
+        request.tenant = context::current_tenant();
+        validate_page_token(request.page_token.as_deref())?;
+        let response = self
+            .repository
+            .list_messages(&request)
+            .await
+            .ok_or_else(|| BamlRtError::InvalidArgument("Context not found".to_string()))?;
+        let value = serde_json::to_value(response).map_err(BamlRtError::Json)?;
+        Ok(a2a::A2aOutcome::Response(value))
+    }
+
+    async fn handle_list_contexts(
+        &self,
+        mut request: ListContextsRequest,
+    ) -> Result<a2a::A2aOutcome> {
+        request.tenant = context::current_tenant();
+        validate_page_token(request.page_token.as_deref())?;
+        let response = self.repository.list_contexts(&request).await;
+        let value = serde_json::to_value(response).map_err(BamlRtError::Json)?;
+        Ok(a2a::A2aOutcome::Response(value))
+    }
+}
+
+#[async_trait(?Send)]
+pub trait AdminHandler: Send + Sync {
+    async fn handle_set_capture_detail(
+        &self,
+        request: SetCaptureDetailRequest,
+    ) -> Result<a2a::A2aOutcome>;
+
+    /// What the runtime has in progress, for diagnosing a hung agent
+    async fn handle_active_work(&self, request: ActiveWorkRequest) -> Result<a2a::A2aOutcome>;
+
+    /// Tool calls waiting for someone to approve them, oldest first
+    async fn handle_list_approvals(
+        &self,
+        request: ListApprovalsRequest,
+    ) -> Result<a2a::A2aOutcome>;
+
+    /// Approve or deny a waiting tool call as the operator the request's token
+    /// names
+    async fn handle_decide_approval(
+        &self,
+        request: DecideApprovalRequest,
+    ) -> Result<a2a::A2aOutcome>;
+}
+
+pub struct DefaultAdminHandler {
+    capture: Arc<PayloadCapture>,
+    work: Option<WorkTracker>,
+    provenance_writer: Option<Arc<dyn ProvenanceWriter>>,
+    approvals: Option<PendingApprovalQueue>,
+    operators: OperatorCredentials,
+}
+
+impl DefaultAdminHandler {
+    pub fn new(capture: Arc<PayloadCapture>) -> Self {
+        Self {
+            capture,
+            work: None,
+            provenance_writer: None,
+            approvals: None,
+            operators: OperatorCredentials::new(),
+        }
+    }
+
+    /// Report the work `work` tracks from `admin.activeWork`
+    pub fn with_work_tracker(mut self, work: WorkTracker) -> Self {
+        self.work = Some(work);
+        self
+    }
+
+    /// Report the events `writer` has queued from `admin.activeWork`
+    pub fn with_provenance_writer(mut self, writer: Option<Arc<dyn ProvenanceWriter>>) -> Self {
+        self.provenance_writer = writer;
+        self
+    }
+
+    /// List and decide the tool calls parked in `queue`
+    pub fn with_approval_queue(mut self, queue: Option<PendingApprovalQueue>) -> Self {
+        self.approvals = queue;
+        self
+    }
+
+    /// Accept approval, capture and active work requests only from `operators`
+    pub fn with_operators(mut self, operators: OperatorCredentials) -> Self {
+        self.operators = operators;
+        self
+    }
+
+    /// The approval queue, and the operator `token` authenticates
+    fn approvals(&self, token: Option<&str>) -> Result<(&PendingApprovalQueue, &str)> {
+        let approvals = self.approvals.as_ref().ok_or_else(|| {
+            BamlRtError::InvalidArgument("Tool approvals are not enabled for this agent".to_string())
+        })?;
+        Ok((approvals, self.operators.authenticate(token)?))
+    }
+}
+
+#[async_trait(?Send)]
+impl AdminHandler for DefaultAdminHandler {
+    async fn handle_set_capture_detail(
+        &self,
+        request: SetCaptureDetailRequest,
+    ) -> Result<a2a::A2aOutcome> {
+        self.operators.authenticate(request.operator_token.as_deref())?;
+        let detail: CaptureDetail = request.detail.parse()?;
+        let expires_in_secs = match detail {
+            CaptureDetail::Full => {
+                let duration = request
+                    .duration_secs
+                    .map(Duration::from_secs)
+                    .unwrap_or(DEFAULT_CAPTURE_DURATION)
+                    .min(MAX_CAPTURE_DURATION);
+                self.capture.raise(request.context_id.clone(), duration);
+                Some(duration.as_secs())
+            }
+            CaptureDetail::Standard => {
+                self.capture.reset(request.context_id.as_ref());
+                None
+            }
+        };
+        let response = SetCaptureDetailResponse {
+            detail: detail.as_str().to_string(),
+            context_id: request.context_id,
+            expires_in_secs,
+        };
+        let value = serde_json::to_value(response).map_err(BamlRtError::Json)?;
+        Ok(a2a::A2aOutcome::Response(value))
+    }
+
+    async fn handle_active_work(&self, request: ActiveWorkRequest) -> Result<a2a::A2aOutcome> {
+        // Work spans every tenant, so only operators may see it.
+        self.operators.authenticate(request.operator_token.as_deref())?;
+        let work = self.work.as_ref().ok_or_else(|| {
+            BamlRtError::InvalidArgument("Active work is not tracked by this agent".to_string())
+        })?;
+        let mut active = work.snapshot();
+        if let Some(writer) = &self.provenance_writer {
+            active = active.with_queued_provenance_events(writer.pending_events().await);
+        }
+        let value = serde_json::to_value(active).map_err(BamlRtError::Json)?;
+        Ok(a2a::A2aOutcome::Response(value))
+    }
+
+    async fn handle_list_approvals(
+        &self,
+        request: ListApprovalsRequest,
+    ) -> Result<a2a::A2aOutcome> {
+        let (approvals, _) = self.approvals(request.operator_token.as_deref())?;
+        let response = ListApprovalsResponse { approvals: approvals.list() };
+        let value = serde_json::to_value(response).map_err(BamlRtError::Json)?;
+        Ok(a2a::A2aOutcome::Response(value))
+    }
+
+    async fn handle_decide_approval(
+        &self,
+        request: DecideApprovalRequest,
+    ) -> Result<a2a::A2aOutcome> {
+        let (approvals, approver) = self.approvals(request.operator_token.as_deref())?;
+        let decided = if request.approved {
+            approvals.approve(&request.approval_id, approver)?
+        } else {
+            approvals.deny(&request.approval_id, approver, request.reason)?
+        };
+        let value = serde_json::to_value(decided).map_err(BamlRtError::Json)?;
+        Ok(a2a::A2aOutcome::Response(value))
+    }
 }
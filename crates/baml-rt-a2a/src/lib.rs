@@ -1,21 +1,50 @@
 //! A2A protocol support.
 
 pub mod a2a;
+pub mod a2a_http;
 pub mod a2a_store;
 pub mod a2a_transport;
 pub mod tools;
 pub mod a2a_types;
+pub mod chunk_stream;
+pub mod delegation;
+pub mod diagnostics;
 pub mod error_classifier;
 pub mod events;
+pub mod extensions;
+pub mod feedback;
 pub mod handlers;
+pub mod lifecycle;
+pub mod push_notifications;
 pub mod result_pipeline;
 pub mod result_extractor;
 pub mod result_processor;
 pub mod result_deduplicator;
 pub mod request_router;
 pub mod response;
+pub mod signing;
+pub mod sqlite_task_store;
 pub mod stream_normalizer;
+pub mod task_queue;
+pub mod task_state;
+pub mod task_timeout;
 
-pub use a2a::{A2aMethod, A2aOutcome, A2aRequest};
-pub use a2a_transport::{A2aAgent, A2aAgentBuilder, A2aRequestHandler};
+pub use a2a::{
+    inject_calling_agent, inject_parent_context, inject_trace_context, A2aMethod, A2aOutcome,
+    A2aRequest,
+};
+pub use a2a_http::{A2aHttpServer, HEALTH_METHOD};
+pub use a2a_transport::{A2aAgent, A2aAgentBuilder, A2aRequestHandler, A2aWebSocketServer};
+pub use delegation::{AgentDirectory, DELEGATE_TOOL, MAX_DELEGATION_DEPTH};
+pub use diagnostics::ProvenanceConsoleSink;
+pub use extensions::{A2aExtension, ActivatedExtensions, ExtensionRegistry};
+pub use lifecycle::LifecycleHooks;
+pub use push_notifications::{PushNotificationRegistry, WebhookConfig, WebhookDispatcher};
+pub use signing::{
+    MessageSigner, MessageVerifier, SignatureStatus, DEFAULT_MAX_SIGNATURE_AGE,
+};
+pub use sqlite_task_store::SqliteTaskStore;
+pub use task_queue::{TaskQueue, TaskSlot};
+pub use task_state::{TaskLifecycleState, TransitionPolicy};
+pub use task_timeout::{TaskTimeoutConfig, TaskTimeoutSweeper};
 pub use tools::A2aSessionBundle;
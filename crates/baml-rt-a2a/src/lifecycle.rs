@@ -0,0 +1,110 @@
+//! Agent lifecycle hooks.
+//!
+//! An agent entry point may define any of these functions on `globalThis`:
+//!
+//! ```js
+//! globalThis.onBoot = async ({ agentId, agentName, version }) => { ... };
+//! globalThis.onTaskCreated = async ({ agentId, taskId, contextId, task }) => { ... };
+//! globalThis.onShutdown = async ({ agentId, reason }) => { ... };
+//! ```
+//!
+//! Hooks are optional and may return promises. A hook that throws surfaces as an
+//! error to the caller: the runner aborts boot when `onBoot` fails, instead of
+//! logging and continuing as it does for top-level entry point code.
+
+use crate::a2a_types::Task;
+use baml_rt_core::ids::{AgentId, ContextId, TaskId};
+use baml_rt_core::{BamlRtError, Result};
+use baml_rt_quickjs::QuickJSBridge;
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+pub const ON_BOOT: &str = "onBoot";
+pub const ON_TASK_CREATED: &str = "onTaskCreated";
+pub const ON_SHUTDOWN: &str = "onShutdown";
+
+/// Payload for `onBoot`, called once after the entry point has been evaluated.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BootPayload {
+    pub agent_id: AgentId,
+    pub agent_name: String,
+    pub version: String,
+}
+
+/// Payload for `onTaskCreated`, called the first time a task id is stored.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskCreatedPayload {
+    pub agent_id: AgentId,
+    pub task_id: TaskId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_id: Option<ContextId>,
+    pub task: Task,
+}
+
+/// Payload for `onShutdown`, called before the runtime exits.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShutdownPayload {
+    pub agent_id: AgentId,
+    pub reason: String,
+}
+
+/// Invokes an agent's lifecycle hooks through its QuickJS bridge.
+#[derive(Clone)]
+pub struct LifecycleHooks {
+    agent_id: AgentId,
+    bridge: Arc<Mutex<QuickJSBridge>>,
+}
+
+impl LifecycleHooks {
+    pub fn new(agent_id: AgentId, bridge: Arc<Mutex<QuickJSBridge>>) -> Self {
+        Self { agent_id, bridge }
+    }
+
+    /// Returns `Ok(false)` when the agent does not define `onBoot`.
+    pub async fn on_boot(&self, agent_name: &str, version: &str) -> Result<bool> {
+        let payload = BootPayload {
+            agent_id: self.agent_id.clone(),
+            agent_name: agent_name.to_string(),
+            version: version.to_string(),
+        };
+        self.invoke(ON_BOOT, &payload).await
+    }
+
+    /// Returns `Ok(false)` when the task has no id or the agent does not define
+    /// `onTaskCreated`.
+    pub async fn on_task_created(&self, task: &Task) -> Result<bool> {
+        let Some(task_id) = task.id.clone() else {
+            return Ok(false);
+        };
+        let payload = TaskCreatedPayload {
+            agent_id: self.agent_id.clone(),
+            task_id,
+            context_id: task.context_id.clone(),
+            task: task.clone(),
+        };
+        self.invoke(ON_TASK_CREATED, &payload).await
+    }
+
+    /// Returns `Ok(false)` when the agent does not define `onShutdown`.
+    pub async fn on_shutdown(&self, reason: &str) -> Result<bool> {
+        let payload = ShutdownPayload {
+            agent_id: self.agent_id.clone(),
+            reason: reason.to_string(),
+        };
+        self.invoke(ON_SHUTDOWN, &payload).await
+    }
+
+    async fn invoke(&self, hook: &str, payload: &impl Serialize) -> Result<bool> {
+        let args = serde_json::to_value(payload).map_err(BamlRtError::Json)?;
+        let mut bridge = self.bridge.lock().await;
+        let result = bridge.invoke_optional_js_function(hook, args).await?;
+        if result.is_some() {
+            tracing::debug!(hook, agent_id = %self.agent_id, "Invoked lifecycle hook");
+        }
+        Ok(result.is_some())
+    }
+}
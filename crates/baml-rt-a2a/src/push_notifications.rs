@@ -0,0 +1,463 @@
+//! Webhook delivery of task updates (A2A push notifications).
+//!
+//! A client registers a [`PushNotificationConfig`] for a task with
+//! `tasks.pushNotificationConfig.set`. The [`WebhookDispatcher`] follows the
+//! agent's task update channel and POSTs each status and artifact update of
+//! that task to the registered URLs, as the same `StreamResponse` JSON that
+//! `tasks.subscribe` streams.
+//!
+//! Webhook URLs must be http or https, and by default may not resolve to
+//! loopback, link-local, private or other non-public addresses, so a client
+//! cannot make the agent POST to services only it can reach;
+//! [`WebhookConfig::with_private_host`] allows such a host. The address is
+//! checked when a config is set and again before each delivery, which then
+//! connects to the addresses it checked rather than resolving the host anew.
+//! Redirects are not followed.
+//!
+//! A notification carries `X-A2A-Notification-Token` when its config has a
+//! token. When the dispatcher has a signing secret it also carries
+//! `X-A2A-Timestamp`, the unix seconds it was sent at, and
+//! `X-A2A-Signature: sha256=<hex>`, the HMAC-SHA256 of `<timestamp>.<body>`;
+//! receivers check both with [`verify_signature`], which rejects stale
+//! timestamps so a captured notification cannot be replayed. Updates of one
+//! task are delivered in order. A failed
+//! delivery (connection error, 429 or 5xx) is retried with exponential backoff;
+//! any other response is final. A task's configs are dropped once its terminal
+//! status has been sent.
+
+use crate::a2a_store::TaskUpdateEvent;
+use crate::a2a_types::{PushNotificationConfig, StreamResponse};
+use crate::task_state::TaskLifecycleState;
+use baml_rt_core::{BamlRtError, Result};
+use hmac::{Hmac, Mac};
+use reqwest::{header, StatusCode};
+use sha2::Sha256;
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+pub const NOTIFICATION_TOKEN_HEADER: &str = "X-A2A-Notification-Token";
+pub const SIGNATURE_HEADER: &str = "X-A2A-Signature";
+pub const TIMESTAMP_HEADER: &str = "X-A2A-Timestamp";
+const SIGNATURE_PREFIX: &str = "sha256=";
+
+/// How far a notification's timestamp may be from the receiver's clock before
+/// [`verify_signature`] rejects it, unless the receiver chooses otherwise.
+pub const DEFAULT_SIGNATURE_TOLERANCE: Duration = Duration::from_secs(300);
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Push notification configs by task id
+///
+/// Clones share their configs, so the task handler setting them and the
+/// dispatcher reading them can each hold one.
+#[derive(Clone, Default)]
+pub struct PushNotificationRegistry {
+    configs: Arc<RwLock<HashMap<String, Vec<PushNotificationConfig>>>>,
+    /// Hosts allowed although they resolve to internal addresses
+    private_hosts: HashSet<String>,
+}
+
+impl PushNotificationRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accept webhooks on `hosts` although they resolve to loopback,
+    /// link-local or private addresses, as [`WebhookConfig::private_hosts`]
+    pub fn with_private_hosts(mut self, hosts: HashSet<String>) -> Self {
+        self.private_hosts = hosts;
+        self
+    }
+
+    /// Send the updates of `task_id` to `config.url`, replacing the task's
+    /// config with the same id. Returns the config as stored, with its id.
+    pub async fn set(
+        &self,
+        task_id: &str,
+        mut config: PushNotificationConfig,
+    ) -> Result<PushNotificationConfig> {
+        check_url(&config.url, &self.private_hosts).await?;
+        let id = config.id.get_or_insert_with(|| uuid::Uuid::new_v4().to_string()).clone();
+        let mut configs = self.configs.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let task_configs = configs.entry(task_id.to_string()).or_default();
+        task_configs.retain(|existing| existing.id.as_deref() != Some(id.as_str()));
+        task_configs.push(config.clone());
+        Ok(config)
+    }
+
+    /// The configs of `task_id`, in the order they were first set
+    pub fn get(&self, task_id: &str) -> Vec<PushNotificationConfig> {
+        let configs = self.configs.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+        configs.get(task_id).cloned().unwrap_or_default()
+    }
+
+    pub fn remove(&self, task_id: &str) -> Vec<PushNotificationConfig> {
+        let mut configs = self.configs.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        configs.remove(task_id).unwrap_or_default()
+    }
+}
+
+/// A webhook URL that passed [`check_url`]
+struct CheckedUrl {
+    host: String,
+    /// What the host resolved to when checked; empty when it is an IP or in
+    /// `private_hosts`.
+    resolved: Vec<SocketAddr>,
+}
+
+/// Parse `url`, refusing schemes other than http and https and, unless its
+/// host is in `private_hosts`, hosts resolving to internal addresses
+async fn check_url(url: &str, private_hosts: &HashSet<String>) -> Result<CheckedUrl> {
+    let invalid = |reason: String| {
+        BamlRtError::InvalidArgument(format!("Invalid push notification url '{}': {}", url, reason))
+    };
+    let parsed = reqwest::Url::parse(url).map_err(|e| invalid(e.to_string()))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(BamlRtError::InvalidArgument(format!(
+            "Push notification url must be http or https, got '{}'",
+            url
+        )));
+    }
+    let host = parsed.host_str().ok_or_else(|| invalid("no host".to_string()))?;
+    let host = host.trim_start_matches('[').trim_end_matches(']').to_string();
+    if private_hosts.contains(&host) {
+        return Ok(CheckedUrl { host, resolved: Vec::new() });
+    }
+    let (addresses, resolved): (Vec<IpAddr>, Vec<SocketAddr>) = match host.parse::<IpAddr>() {
+        Ok(address) => (vec![address], Vec::new()),
+        Err(_) => {
+            let port = parsed.port_or_known_default().unwrap_or(80);
+            let resolved: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), port))
+                .await
+                .map_err(|e| invalid(format!("{} does not resolve: {}", host, e)))?
+                .collect();
+            (resolved.iter().map(SocketAddr::ip).collect(), resolved)
+        }
+    };
+    if let Some(address) = addresses.into_iter().find(|address| is_internal(*address)) {
+        return Err(invalid(format!("{} is the internal address {}", host, address)));
+    }
+    Ok(CheckedUrl { host, resolved })
+}
+
+/// Loopback, link-local, private, shared (CGNAT), benchmarking, unspecified
+/// ("this network"), broadcast, multicast or NAT64
+fn is_internal(address: IpAddr) -> bool {
+    match address {
+        IpAddr::V4(v4) => {
+            let [first, second, ..] = v4.octets();
+            v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_broadcast()
+                || v4.is_multicast()
+                || first == 0
+                || (first == 100 && second & 0xc0 == 64)
+                || (first == 198 && second & 0xfe == 18)
+        }
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => is_internal(IpAddr::V4(v4)),
+            None => {
+                let [first, second, third, fourth, fifth, sixth, ..] = v6.segments();
+                v6.is_loopback()
+                    || v6.is_unspecified()
+                    || v6.is_unique_local()
+                    || v6.is_unicast_link_local()
+                    || v6.is_multicast()
+                    || [first, second, third, fourth, fifth, sixth] == [0x64, 0xff9b, 0, 0, 0, 0]
+            }
+        },
+    }
+}
+
+/// How notifications are sent
+#[derive(Clone)]
+pub struct WebhookConfig {
+    /// HMAC key for `X-A2A-Signature`; notifications are unsigned without one.
+    pub signing_secret: Option<Vec<u8>>,
+    /// Hosts webhooks may use although they resolve to loopback, link-local or
+    /// private addresses, as written in the URL (a name or an IP).
+    pub private_hosts: HashSet<String>,
+    /// Tries per notification, the first included.
+    pub max_attempts: u32,
+    /// Wait before the first retry; doubles with each further retry.
+    pub initial_backoff: Duration,
+    pub request_timeout: Duration,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            signing_secret: None,
+            private_hosts: HashSet::new(),
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(500),
+            request_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl WebhookConfig {
+    pub fn with_signing_secret(mut self, secret: impl Into<Vec<u8>>) -> Self {
+        self.signing_secret = Some(secret.into());
+        self
+    }
+
+    /// Allow webhooks on `host` although it resolves to an internal address
+    pub fn with_private_host(mut self, host: impl Into<String>) -> Self {
+        self.private_hosts.insert(host.into());
+        self
+    }
+
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    pub fn with_initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+}
+
+impl std::fmt::Debug for WebhookConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebhookConfig")
+            .field("signed", &self.signing_secret.is_some())
+            .field("private_hosts", &self.private_hosts)
+            .field("max_attempts", &self.max_attempts)
+            .field("initial_backoff", &self.initial_backoff)
+            .field("request_timeout", &self.request_timeout)
+            .finish()
+    }
+}
+
+fn signature_mac(secret: &[u8], timestamp: &str, body: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+/// `X-A2A-Signature` value for `body` sent at `timestamp`, the
+/// `X-A2A-Timestamp` value
+pub fn sign_payload(secret: &[u8], timestamp: &str, body: &[u8]) -> String {
+    let mac = signature_mac(secret, timestamp, body);
+    format!("{}{}", SIGNATURE_PREFIX, hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Whether `signature`, an `X-A2A-Signature` value, signs `body` sent at
+/// `timestamp` with `secret`, and `timestamp` is within `tolerance` of now
+pub fn verify_signature(
+    secret: &[u8],
+    timestamp: &str,
+    body: &[u8],
+    signature: &str,
+    tolerance: Duration,
+) -> bool {
+    let fresh = timestamp
+        .parse::<u64>()
+        .is_ok_and(|sent_at| unix_secs().abs_diff(sent_at) <= tolerance.as_secs());
+    let expected = signature.strip_prefix(SIGNATURE_PREFIX).and_then(|hex| hex::decode(hex).ok());
+    let Some(expected) = expected.filter(|_| fresh) else {
+        return false;
+    };
+    signature_mac(secret, timestamp, body).verify_slice(&expected).is_ok()
+}
+
+fn unix_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|since| since.as_secs()).unwrap_or_default()
+}
+
+/// One update of a task, for each of its configs
+struct Notification {
+    configs: Vec<PushNotificationConfig>,
+    body: Vec<u8>,
+}
+
+/// Background task POSTing task updates to their webhooks; aborted on drop.
+pub struct WebhookDispatcher {
+    handle: JoinHandle<()>,
+}
+
+impl WebhookDispatcher {
+    pub fn spawn(
+        mut updates: broadcast::Receiver<TaskUpdateEvent>,
+        registry: PushNotificationRegistry,
+        config: WebhookConfig,
+    ) -> Self {
+        let client = webhook_client(&config, None);
+        let config = Arc::new(config);
+        let handle = tokio::spawn(async move {
+            // One worker per task keeps its updates in order without a slow
+            // webhook holding up the others.
+            let mut workers: HashMap<String, mpsc::UnboundedSender<Notification>> = HashMap::new();
+            loop {
+                let update = match updates.recv().await {
+                    Ok(update) => update,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!(skipped, "Push notifications fell behind; updates were not sent");
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let Some(task_id) = update.task_id().map(str::to_string) else {
+                    continue;
+                };
+                let configs = registry.get(&task_id);
+                if configs.is_empty() {
+                    continue;
+                }
+                let terminal = is_terminal(&update);
+                let body = match serde_json::to_vec(&StreamResponse::from(update)) {
+                    Ok(body) => body,
+                    Err(error) => {
+                        warn!(
+                            task_id = task_id.as_str(),
+                            %error,
+                            "Task update could not be serialized"
+                        );
+                        continue;
+                    }
+                };
+                let worker = workers.entry(task_id.clone()).or_insert_with(|| {
+                    spawn_worker(client.clone(), config.clone(), task_id.clone())
+                });
+                let _ = worker.send(Notification { configs, body });
+                if terminal {
+                    registry.remove(&task_id);
+                    // The worker exits once it has sent what it was given.
+                    workers.remove(&task_id);
+                }
+            }
+        });
+        Self { handle }
+    }
+}
+
+impl Drop for WebhookDispatcher {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// A client that does not follow redirects, so a webhook cannot send its
+/// notifications on to an internal address, and that connects to `pinned`
+/// addresses for their host instead of resolving it
+fn webhook_client(config: &WebhookConfig, pinned: Option<&CheckedUrl>) -> reqwest::Client {
+    let mut builder = reqwest::Client::builder()
+        .timeout(config.request_timeout)
+        .redirect(reqwest::redirect::Policy::none());
+    if let Some(checked) = pinned {
+        builder = builder.resolve_to_addrs(&checked.host, &checked.resolved);
+    }
+    builder.build().unwrap_or_default()
+}
+
+fn is_terminal(update: &TaskUpdateEvent) -> bool {
+    match update {
+        TaskUpdateEvent::Status(event) => event
+            .status
+            .as_ref()
+            .and_then(TaskLifecycleState::of)
+            .is_some_and(TaskLifecycleState::is_terminal),
+        TaskUpdateEvent::Artifact(_) => false,
+    }
+}
+
+fn spawn_worker(
+    client: reqwest::Client,
+    config: Arc<WebhookConfig>,
+    task_id: String,
+) -> mpsc::UnboundedSender<Notification> {
+    let (sender, mut notifications) = mpsc::unbounded_channel::<Notification>();
+    tokio::spawn(async move {
+        while let Some(notification) = notifications.recv().await {
+            for push in &notification.configs {
+                deliver(&client, &config, &task_id, push, &notification.body).await;
+            }
+        }
+    });
+    sender
+}
+
+/// POST `body` to `push.url`, retrying failures
+async fn deliver(
+    client: &reqwest::Client,
+    config: &WebhookConfig,
+    task_id: &str,
+    push: &PushNotificationConfig,
+    body: &[u8],
+) {
+    // The host may resolve differently than when the config was set.
+    let checked = match check_url(&push.url, &config.private_hosts).await {
+        Ok(checked) => checked,
+        Err(error) => {
+            warn!(task_id, url = push.url.as_str(), %error, "Push notification not sent");
+            return;
+        }
+    };
+    // Connecting to what was checked leaves no second lookup to rebind.
+    let pinned;
+    let client = if checked.resolved.is_empty() {
+        client
+    } else {
+        pinned = webhook_client(config, Some(&checked));
+        &pinned
+    };
+    let mut backoff = config.initial_backoff;
+    for attempt in 1..=config.max_attempts {
+        let mut request = client
+            .post(&push.url)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body.to_vec());
+        if let Some(token) = &push.token {
+            request = request.header(NOTIFICATION_TOKEN_HEADER, token);
+        }
+        if let Some(secret) = config.signing_secret.as_deref() {
+            let timestamp = unix_secs().to_string();
+            request = request
+                .header(SIGNATURE_HEADER, sign_payload(secret, &timestamp, body))
+                .header(TIMESTAMP_HEADER, timestamp);
+        }
+        let retryable = match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                debug!(task_id, url = push.url.as_str(), attempt, "Push notification delivered");
+                return;
+            }
+            Ok(response) => {
+                let status = response.status();
+                warn!(
+                    task_id,
+                    url = push.url.as_str(),
+                    attempt,
+                    %status,
+                    "Push notification refused"
+                );
+                status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+            }
+            Err(error) => {
+                warn!(
+                    task_id,
+                    url = push.url.as_str(),
+                    attempt,
+                    %error,
+                    "Push notification failed"
+                );
+                true
+            }
+        };
+        if !retryable || attempt == config.max_attempts {
+            break;
+        }
+        tokio::time::sleep(backoff).await;
+        backoff = backoff.saturating_mul(2);
+    }
+    warn!(task_id, url = push.url.as_str(), "Push notification dropped");
+}
@@ -1,18 +1,23 @@
 use crate::a2a;
-use crate::handlers::TaskHandler;
+use crate::a2a_types::{AgentCapabilities, AgentCard};
+use crate::chunk_stream::{channel_stream, ChunkStream};
+use crate::handlers::{AdminHandler, ContextHandler, FeedbackHandler, TaskHandler};
 use crate::result_pipeline::ResultStoragePipeline;
+use crate::signing::MessageSigner;
 use crate::stream_normalizer::StreamNormalizer;
 use async_trait::async_trait;
 use baml_rt_core::{BamlRtError, Result};
 use baml_rt_quickjs::QuickJSBridge;
+use futures_util::StreamExt;
 use serde_json::Value;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{mpsc, Mutex};
 
 #[async_trait(?Send)]
 pub trait JsInvoker: Send + Sync {
     async fn invoke_handler(&self, request: &a2a::A2aRequest) -> Result<Value>;
-    async fn invoke_stream(&self, request: &a2a::A2aRequest) -> Result<Vec<Value>>;
+    /// The handler's chunks, yielded as it produces them.
+    fn invoke_stream(&self, request: &a2a::A2aRequest) -> ChunkStream<'static>;
 }
 
 pub struct QuickJsInvoker {
@@ -40,21 +45,20 @@ impl JsInvoker for QuickJsInvoker {
         bridge.invoke_js_function("handle_a2a_request", js_request).await
     }
 
-    async fn invoke_stream(&self, request: &a2a::A2aRequest) -> Result<Vec<Value>> {
-        let result = self.invoke_handler(request).await?;
-        match result {
-            Value::Array(values) => values
-                .into_iter()
-                .map(|value| self.stream_normalizer.normalize_chunk(value))
-                .collect::<Result<Vec<Value>>>(),
-            Value::Object(map) if map.get("error").is_some() => Err(BamlRtError::QuickJs(
-                map.get("error")
-                    .and_then(Value::as_str)
-                    .unwrap_or("unknown")
-                    .to_string(),
-            )),
-            other => Ok(vec![self.stream_normalizer.normalize_chunk(other)?]),
-        }
+    fn invoke_stream(&self, request: &a2a::A2aRequest) -> ChunkStream<'static> {
+        let js_request = a2a::request_to_js_value(request);
+        let bridge = self.bridge.clone();
+        let stream_normalizer = self.stream_normalizer.clone();
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let invocation = async move {
+            let mut bridge = bridge.lock().await;
+            bridge
+                .invoke_js_function_chunked("handle_a2a_request", js_request, sender)
+                .await
+        };
+        channel_stream(invocation, receiver)
+            .map(move |chunk| chunk.and_then(|value| stream_normalizer.normalize_chunk(value)))
+            .boxed_local()
     }
 }
 
@@ -65,22 +69,45 @@ pub trait RequestRouter: Send + Sync {
 
 pub struct MethodBasedRouter {
     task_handler: Arc<dyn TaskHandler>,
+    feedback_handler: Arc<dyn FeedbackHandler>,
+    context_handler: Arc<dyn ContextHandler>,
+    admin_handler: Arc<dyn AdminHandler>,
     js_invoker: Arc<dyn JsInvoker>,
     result_pipeline: Arc<dyn ResultStoragePipeline>,
+    capabilities: Arc<AgentCapabilities>,
+    card: Arc<AgentCard>,
+    message_signer: Option<Arc<MessageSigner>>,
 }
 
 impl MethodBasedRouter {
     pub fn new(
         task_handler: Arc<dyn TaskHandler>,
+        feedback_handler: Arc<dyn FeedbackHandler>,
+        context_handler: Arc<dyn ContextHandler>,
+        admin_handler: Arc<dyn AdminHandler>,
         js_invoker: Arc<dyn JsInvoker>,
         result_pipeline: Arc<dyn ResultStoragePipeline>,
+        capabilities: Arc<AgentCapabilities>,
+        card: Arc<AgentCard>,
     ) -> Self {
         Self {
             task_handler,
+            feedback_handler,
+            context_handler,
+            admin_handler,
             js_invoker,
             result_pipeline,
+            capabilities,
+            card,
+            message_signer: None,
         }
     }
+
+    /// Sign the message of every handler result before it is stored and sent.
+    pub fn with_message_signer(mut self, signer: Option<Arc<MessageSigner>>) -> Self {
+        self.message_signer = signer;
+        self
+    }
 }
 
 #[async_trait(?Send)]
@@ -107,15 +134,89 @@ impl RequestRouter for MethodBasedRouter {
                     serde_json::from_value(request.params.clone()).map_err(BamlRtError::Json)?;
                 self.task_handler.handle_subscribe(req, request.is_stream).await
             }
+            a2a::A2aMethod::TasksPollUpdates => {
+                let req =
+                    serde_json::from_value(request.params.clone()).map_err(BamlRtError::Json)?;
+                self.task_handler.handle_poll_updates(req).await
+            }
+            a2a::A2aMethod::TasksSetPushNotificationConfig => {
+                let req =
+                    serde_json::from_value(request.params.clone()).map_err(BamlRtError::Json)?;
+                self.task_handler.handle_set_push_config(req).await
+            }
+            a2a::A2aMethod::TasksGetPushNotificationConfig => {
+                let req =
+                    serde_json::from_value(request.params.clone()).map_err(BamlRtError::Json)?;
+                self.task_handler.handle_get_push_config(req).await
+            }
+            a2a::A2aMethod::MessageFeedback => {
+                let req =
+                    serde_json::from_value(request.params.clone()).map_err(BamlRtError::Json)?;
+                self.feedback_handler.handle_feedback(req).await
+            }
+            a2a::A2aMethod::ContextsFork => {
+                let req =
+                    serde_json::from_value(request.params.clone()).map_err(BamlRtError::Json)?;
+                self.context_handler.handle_fork(req).await
+            }
+            a2a::A2aMethod::MessageList => {
+                let req =
+                    serde_json::from_value(request.params.clone()).map_err(BamlRtError::Json)?;
+                self.context_handler.handle_list_messages(req).await
+            }
+            a2a::A2aMethod::ContextsList => {
+                let req =
+                    serde_json::from_value(request.params.clone()).map_err(BamlRtError::Json)?;
+                self.context_handler.handle_list_contexts(req).await
+            }
+            a2a::A2aMethod::AgentCapabilities => Ok(a2a::A2aOutcome::Response(
+                serde_json::to_value(self.capabilities.as_ref()).map_err(BamlRtError::Json)?,
+            )),
+            a2a::A2aMethod::AgentGetCard => Ok(a2a::A2aOutcome::Response(
+                serde_json::to_value(self.card.as_ref()).map_err(BamlRtError::Json)?,
+            )),
+            a2a::A2aMethod::AdminSetCaptureDetail => {
+                let req =
+                    serde_json::from_value(request.params.clone()).map_err(BamlRtError::Json)?;
+                self.admin_handler.handle_set_capture_detail(req).await
+            }
+            a2a::A2aMethod::AdminActiveWork => {
+                let req =
+                    serde_json::from_value(request.params.clone()).map_err(BamlRtError::Json)?;
+                self.admin_handler.handle_active_work(req).await
+            }
+            a2a::A2aMethod::AdminListApprovals => {
+                let req =
+                    serde_json::from_value(request.params.clone()).map_err(BamlRtError::Json)?;
+                self.admin_handler.handle_list_approvals(req).await
+            }
+            a2a::A2aMethod::AdminDecideApproval => {
+                let req =
+                    serde_json::from_value(request.params.clone()).map_err(BamlRtError::Json)?;
+                self.admin_handler.handle_decide_approval(req).await
+            }
             _ => {
                 if request.is_stream {
-                    let chunks = self.js_invoker.invoke_stream(request).await?;
-                    for chunk in &chunks {
-                        self.result_pipeline.store_result(chunk).await?;
-                    }
-                    Ok(a2a::A2aOutcome::Stream(chunks))
+                    let result_pipeline = self.result_pipeline.clone();
+                    let message_signer = self.message_signer.clone();
+                    let chunks = self.js_invoker.invoke_stream(request).then(move |chunk| {
+                        let result_pipeline = result_pipeline.clone();
+                        let message_signer = message_signer.clone();
+                        async move {
+                            let mut chunk = chunk?;
+                            if let Some(signer) = &message_signer {
+                                signer.sign_result(&mut chunk)?;
+                            }
+                            result_pipeline.store_result(&chunk).await?;
+                            Ok(chunk)
+                        }
+                    });
+                    Ok(a2a::A2aOutcome::Chunks(chunks.boxed_local()))
                 } else {
-                    let result = self.js_invoker.invoke_handler(request).await?;
+                    let mut result = self.js_invoker.invoke_handler(request).await?;
+                    if let Some(signer) = &self.message_signer {
+                        signer.sign_result(&mut result)?;
+                    }
                     self.result_pipeline.store_result(&result).await?;
                     Ok(a2a::A2aOutcome::Response(result))
                 }
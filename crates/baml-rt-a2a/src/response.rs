@@ -3,9 +3,25 @@ use crate::a2a_types::JSONRPCId;
 use baml_rt_core::BamlRtError;
 use serde_json::Value;
 
+/// JSON-RPC codes for runtime errors callers are expected to handle. They sit
+/// above the `-32001..=-32009` range the A2A specification reserves.
+pub const TOOL_NOT_ALLOWED: i64 = -32010;
+pub const SESSION_STATE_VIOLATION: i64 = -32011;
+pub const SCHEMA_MISMATCH: i64 = -32012;
+pub const TIMEOUT: i64 = -32013;
+pub const BUDGET_EXHAUSTED: i64 = -32014;
+pub const SHUTTING_DOWN: i64 = -32015;
+
 pub trait ResponseFormatter: Send + Sync {
     fn format_success(&self, id: Option<JSONRPCId>, result: Value) -> Value;
     fn format_stream(&self, id: Option<JSONRPCId>, chunks: Vec<Value>) -> Vec<Value>;
+    fn format_stream_chunk(
+        &self,
+        id: Option<JSONRPCId>,
+        chunk: Value,
+        index: usize,
+        is_final: bool,
+    ) -> Value;
     fn format_error(&self, id: Option<JSONRPCId>, error: &BamlRtError) -> Value;
 }
 
@@ -20,18 +36,32 @@ impl ResponseFormatter for JsonRpcResponseFormatter {
         let total = chunks.len();
         let mut responses = Vec::with_capacity(total);
         for (idx, chunk) in chunks.into_iter().enumerate() {
-            responses.push(a2a::stream_chunk_response(
-                id.clone(),
-                chunk,
-                idx,
-                idx + 1 == total,
-            ));
+            responses.push(self.format_stream_chunk(id.clone(), chunk, idx, idx + 1 == total));
         }
         responses
     }
 
+    fn format_stream_chunk(
+        &self,
+        id: Option<JSONRPCId>,
+        chunk: Value,
+        index: usize,
+        is_final: bool,
+    ) -> Value {
+        a2a::stream_chunk_response(id, chunk, index, is_final)
+    }
+
+    /// The error data also carries the stable `errorCode` and, when the error
+    /// picked up any, its `context` frames.
     fn format_error(&self, id: Option<JSONRPCId>, error: &BamlRtError) -> Value {
-        let (code, message, data) = map_jsonrpc_error(error);
+        let (code, message, mut data) = map_jsonrpc_error(error.root());
+        if let Some(Value::Object(data)) = &mut data {
+            data.insert("errorCode".to_string(), Value::from(error.code().as_str()));
+            let chain = error.context_chain();
+            if !chain.is_empty() {
+                data.insert("context".to_string(), serde_json::json!(chain));
+            }
+        }
         a2a::error_response(id, code, message, data)
     }
 }
@@ -46,6 +76,46 @@ fn map_jsonrpc_error(error: &BamlRtError) -> (i64, &'static str, Option<Value>)
                 "details": message,
             })),
         ),
+        BamlRtError::ToolNotAllowed(message) => (
+            TOOL_NOT_ALLOWED,
+            "Tool not allowed",
+            Some(serde_json::json!({
+                "error": error.to_string(),
+                "details": message,
+            })),
+        ),
+        BamlRtError::SessionStateViolation(message) => (
+            SESSION_STATE_VIOLATION,
+            "Session state violation",
+            Some(serde_json::json!({
+                "error": error.to_string(),
+                "details": message,
+            })),
+        ),
+        BamlRtError::SchemaMismatch(message) => (
+            SCHEMA_MISMATCH,
+            "Schema mismatch",
+            Some(serde_json::json!({
+                "error": error.to_string(),
+                "details": message,
+            })),
+        ),
+        BamlRtError::Timeout(message) => (
+            TIMEOUT,
+            "Timeout",
+            Some(serde_json::json!({
+                "error": error.to_string(),
+                "details": message,
+            })),
+        ),
+        BamlRtError::Budget(message) => (
+            BUDGET_EXHAUSTED,
+            "Budget exhausted",
+            Some(serde_json::json!({
+                "error": error.to_string(),
+                "details": message,
+            })),
+        ),
         BamlRtError::FunctionNotFound(name) => (
             -32601,
             "Method not found",
@@ -70,6 +140,33 @@ fn map_jsonrpc_error(error: &BamlRtError) -> (i64, &'static str, Option<Value>)
                 "context": context,
             })),
         ),
+        BamlRtError::JsRejection { function, name, message, stack } => (
+            -32603,
+            "Internal error",
+            Some(serde_json::json!({
+                "error": error.to_string(),
+                "function": function,
+                "name": name,
+                "message": message,
+                "stack": stack,
+            })),
+        ),
+        BamlRtError::PromiseTimeout { timeout_ms } => (
+            -32603,
+            "Internal error",
+            Some(serde_json::json!({
+                "error": error.to_string(),
+                "timeoutMs": timeout_ms,
+            })),
+        ),
+        BamlRtError::ResourceLimit { kind, .. } => (
+            -32603,
+            "Internal error",
+            Some(serde_json::json!({
+                "error": error.to_string(),
+                "limit": kind.as_str(),
+            })),
+        ),
         _ => (
             -32603,
             "Internal error",
@@ -1,10 +1,9 @@
 use crate::result_pipeline::ResultStoragePipeline;
 use async_trait::async_trait;
 use baml_rt_core::Result;
+use baml_rt_core::canonical;
 use serde_json::Value;
-use std::collections::hash_map::DefaultHasher;
 use std::collections::HashSet;
-use std::hash::{Hash, Hasher};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -15,7 +14,7 @@ pub trait ResultDeduplicator: Send + Sync {
 }
 
 pub struct HashResultDeduplicator {
-    seen: Mutex<HashSet<u64>>,
+    seen: Mutex<HashSet<canonical::Digest>>,
 }
 
 impl HashResultDeduplicator {
@@ -25,11 +24,8 @@ impl HashResultDeduplicator {
         }
     }
 
-    fn hash_value(value: &Value) -> u64 {
-        let mut hasher = DefaultHasher::new();
-        let serialized = serde_json::to_string(value).unwrap_or_default();
-        serialized.hash(&mut hasher);
-        hasher.finish()
+    fn hash_value(value: &Value) -> canonical::Digest {
+        canonical::digest(value)
     }
 }
 
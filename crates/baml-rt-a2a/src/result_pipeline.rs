@@ -1,5 +1,6 @@
 use crate::a2a_store::TaskStoreBackend;
 use crate::events::EventEmitter;
+use crate::lifecycle::LifecycleHooks;
 use crate::result_extractor::{A2aResultExtractor, ResultExtractor};
 use crate::result_processor::TaskProcessor;
 use baml_rt_core::Result;
@@ -18,9 +19,21 @@ pub struct A2aResultPipeline {
 
 impl A2aResultPipeline {
     pub fn new(task_store: Arc<dyn TaskStoreBackend>, emitter: Arc<dyn EventEmitter>) -> Self {
+        Self::with_processor(TaskProcessor::new(task_store, emitter))
+    }
+
+    /// Like [`A2aResultPipeline::new`], also running `onTaskCreated` for new tasks.
+    pub fn with_lifecycle_hooks(
+        task_store: Arc<dyn TaskStoreBackend>,
+        emitter: Arc<dyn EventEmitter>,
+        lifecycle: LifecycleHooks,
+    ) -> Self {
+        Self::with_processor(TaskProcessor::new(task_store, emitter).with_lifecycle_hooks(lifecycle))
+    }
+
+    fn with_processor(processor: TaskProcessor) -> Self {
         let extractor: Arc<dyn ResultExtractor> = Arc::new(A2aResultExtractor);
-        let processor = Arc::new(TaskProcessor::new(task_store, emitter));
-        Self { extractor, processor }
+        Self { extractor, processor: Arc::new(processor) }
     }
 }
 
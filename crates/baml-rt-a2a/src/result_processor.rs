@@ -4,17 +4,25 @@ use crate::a2a_types::{
     TaskStatusUpdateEvent,
 };
 use crate::events::EventEmitter;
+use crate::lifecycle::LifecycleHooks;
 use baml_rt_core::Result;
 use std::sync::Arc;
 
 pub struct TaskProcessor {
     task_store: Arc<dyn TaskStoreBackend>,
     emitter: Arc<dyn EventEmitter>,
+    lifecycle: Option<LifecycleHooks>,
 }
 
 impl TaskProcessor {
     pub fn new(task_store: Arc<dyn TaskStoreBackend>, emitter: Arc<dyn EventEmitter>) -> Self {
-        Self { task_store, emitter }
+        Self { task_store, emitter, lifecycle: None }
+    }
+
+    /// Call the agent's `onTaskCreated` hook for tasks not yet in the store.
+    pub fn with_lifecycle_hooks(mut self, lifecycle: LifecycleHooks) -> Self {
+        self.lifecycle = Some(lifecycle);
+        self
     }
 
     pub async fn process_stream_response(&self, stream: StreamResponse) -> Result<()> {
@@ -71,7 +79,24 @@ impl TaskProcessor {
             let context_id = task.context_id.clone();
             let task_id = task.id.clone();
             let artifacts = task.artifacts.clone();
-            self.task_store.upsert(task).await;
+            let created = match (&self.lifecycle, &task_id) {
+                (Some(_), Some(id)) => self.task_store.get(id.as_str(), Some(0)).await.is_none(),
+                _ => false,
+            };
+            let stored = self.task_store.upsert(task).await;
+            // The store keeps the previous status when it rejects a transition.
+            let status = status.filter(|status| {
+                stored
+                    .as_ref()
+                    .and_then(|stored| stored.status.as_ref())
+                    .is_some_and(|stored| stored.state == status.state)
+            });
+            if created
+                && let Some(lifecycle) = &self.lifecycle
+                && let Some(stored) = &stored
+            {
+                lifecycle.on_task_created(stored).await?;
+            }
             if let Some(status) = status
                 && let Some(event) = self
                     .task_store
@@ -108,7 +133,7 @@ impl TaskProcessor {
                 metadata.insert("agent_id".to_string(), agent_id_value);
                 msg.metadata = Some(metadata);
             }
-            self.task_store.insert_message(&msg).await;
+            self.task_store.insert_message(&msg).await?;
         }
         if let Some(update) = status_update
             && let Some(status) = update.status
@@ -0,0 +1,387 @@
+//! Ed25519 signing of A2A messages exchanged between agents.
+//!
+//! A signed message carries its signature in `metadata.signature`:
+//!
+//! ```json
+//! {
+//!   "algorithm": "ed25519", "keyId": "agent-a", "value": "<hex signature>",
+//!   "signedAt": 1700000000000, "nonce": "<uuid>"
+//! }
+//! ```
+//!
+//! What is signed is the [canonical](baml_rt_core::canonical) JSON of
+//! `{"message", "signedAt", "nonce"}`: the message as sent, without
+//! `metadata.signature` or the verification results below, and without
+//! `metadata` at all when nothing else is in it, next to the signing time (ms
+//! since the epoch) and a random nonce. Any change to the parts, ids, role,
+//! remaining metadata, time or nonce breaks the signature.
+//!
+//! Signatures made more than the verifier's maximum age away from its clock
+//! ([`DEFAULT_MAX_SIGNATURE_AGE`] unless configured) are invalid, and
+//! [`MessageVerifier::check`] refuses a nonce it has already accepted within
+//! that window, so a captured message cannot be replayed.
+//!
+//! An agent with a [`MessageSigner`] signs the `message` of every result its
+//! handler returns. An agent with a [`MessageVerifier`] checks the message of
+//! every `message.send` and `message.sendStream` against its peer keys before
+//! anything else sees it, and records the outcome in the message metadata as
+//! `signature_status` (and `signature_key_id`), so it lands on the message's
+//! provenance entity and the JS handler can read it. Values a sender puts
+//! under those keys are overwritten.
+//!
+//! As with package signatures, a signature that is present but wrong is refused
+//! whatever the policy; unsigned messages and unknown keys are refused only
+//! when the verifier requires verified messages.
+
+use crate::a2a_types::Message;
+use baml_rt_core::canonical::canonicalize;
+use baml_rt_core::{BamlRtError, Result};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const SIGNATURE_METADATA_KEY: &str = "signature";
+pub const SIGNATURE_STATUS_METADATA_KEY: &str = "signature_status";
+pub const SIGNATURE_KEY_ID_METADATA_KEY: &str = "signature_key_id";
+pub const SIGNATURE_ALGORITHM: &str = "ed25519";
+
+/// How far a signature's `signedAt` may be from the verifier's clock.
+pub const DEFAULT_MAX_SIGNATURE_AGE: Duration = Duration::from_secs(300);
+
+/// Signs outgoing messages with one agent key.
+pub struct MessageSigner {
+    key_id: String,
+    key: SigningKey,
+}
+
+impl std::fmt::Debug for MessageSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MessageSigner").field("key_id", &self.key_id).finish_non_exhaustive()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SigningKeyFile {
+    key_id: String,
+    secret_key: String,
+}
+
+impl MessageSigner {
+    pub fn new(key_id: impl Into<String>, key: SigningKey) -> Self {
+        Self { key_id: key_id.into(), key }
+    }
+
+    /// Signer for a hex-encoded 32-byte Ed25519 secret key.
+    pub fn from_hex(key_id: impl Into<String>, secret_hex: &str) -> Result<Self> {
+        let key_id = key_id.into();
+        let bytes = decode_fixed::<32>(secret_hex).ok_or_else(|| {
+            BamlRtError::Configuration(format!(
+                "signing key '{key_id}' is not a hex Ed25519 secret key"
+            ))
+        })?;
+        Ok(Self::new(key_id, SigningKey::from_bytes(&bytes)))
+    }
+
+    /// Load a signer from a JSON file of the form
+    /// `{"keyId": "agent-a", "secretKey": "<hex>"}`.
+    pub fn load(path: &Path) -> Result<Self> {
+        let file: SigningKeyFile = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        Self::from_hex(file.key_id, &file.secret_key)
+    }
+
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    /// The key peers verify this signer's messages with.
+    pub fn verifying_key(&self) -> VerifyingKey {
+        self.key.verifying_key()
+    }
+
+    /// Sign a message in its JSON form, replacing any earlier signature.
+    pub fn sign_value(&self, message: &mut Value) -> Result<()> {
+        if !message.is_object() {
+            return Err(BamlRtError::InvalidArgument("only message objects can be signed".to_string()));
+        }
+        let signature = self.signature_for(message);
+        let metadata = &mut message["metadata"];
+        if !metadata.is_object() {
+            *metadata = json!({});
+        }
+        metadata[SIGNATURE_METADATA_KEY] = signature;
+        Ok(())
+    }
+
+    pub fn sign(&self, message: &mut Message) -> Result<()> {
+        let signature = self.signature_for(&serde_json::to_value(&*message)?);
+        message
+            .metadata
+            .get_or_insert_with(HashMap::new)
+            .insert(SIGNATURE_METADATA_KEY.to_string(), signature);
+        Ok(())
+    }
+
+    fn signature_for(&self, message: &Value) -> Value {
+        let signed_at = now_ms();
+        let nonce = uuid::Uuid::new_v4().to_string();
+        let signature = self.key.sign(&signed_content(message, signed_at, &nonce));
+        json!({
+            "algorithm": SIGNATURE_ALGORITHM,
+            "keyId": self.key_id,
+            "value": hex::encode(signature.to_bytes()),
+            "signedAt": signed_at,
+            "nonce": nonce,
+        })
+    }
+
+    /// Sign the `message` of a handler result, if it has one.
+    pub(crate) fn sign_result(&self, result: &mut Value) -> Result<()> {
+        match result.get_mut("message") {
+            Some(message) if message.is_object() => self.sign_value(message),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// How an incoming message's signature checked out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureStatus {
+    /// Signed by the peer key `key_id`.
+    Verified { key_id: String },
+    /// No `metadata.signature`.
+    Unsigned,
+    /// Signed with a key that is not among the peer keys.
+    UnknownKey { key_id: String },
+    /// Malformed, or does not match the message.
+    Invalid { reason: String },
+}
+
+impl SignatureStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignatureStatus::Verified { .. } => "verified",
+            SignatureStatus::Unsigned => "unsigned",
+            SignatureStatus::UnknownKey { .. } => "unknown_key",
+            SignatureStatus::Invalid { .. } => "invalid",
+        }
+    }
+
+    pub fn key_id(&self) -> Option<&str> {
+        match self {
+            SignatureStatus::Verified { key_id } | SignatureStatus::UnknownKey { key_id } => Some(key_id),
+            SignatureStatus::Unsigned | SignatureStatus::Invalid { .. } => None,
+        }
+    }
+}
+
+/// Checks incoming messages against the public keys of trusted peers.
+///
+/// Clones share the nonces already accepted.
+#[derive(Debug, Clone)]
+pub struct MessageVerifier {
+    keys: HashMap<String, VerifyingKey>,
+    require_verified: bool,
+    max_age: Duration,
+    /// Nonces of accepted signatures, by key id and nonce, with their `signedAt`.
+    seen_nonces: Arc<Mutex<HashMap<(String, String), u64>>>,
+}
+
+impl Default for MessageVerifier {
+    fn default() -> Self {
+        Self {
+            keys: HashMap::new(),
+            require_verified: false,
+            max_age: DEFAULT_MAX_SIGNATURE_AGE,
+            seen_nonces: Arc::default(),
+        }
+    }
+}
+
+impl MessageVerifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_peer_key(mut self, key_id: impl Into<String>, key: VerifyingKey) -> Self {
+        self.keys.insert(key_id.into(), key);
+        self
+    }
+
+    /// Load peer keys from a JSON object mapping each key id to its hex-encoded
+    /// 32-byte Ed25519 public key, the format of the package trust store.
+    pub fn load_peer_keys(path: &Path) -> Result<Self> {
+        let entries: HashMap<String, String> = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        let mut verifier = Self::new();
+        for (key_id, key_hex) in entries {
+            let key = decode_fixed::<32>(&key_hex)
+                .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok())
+                .ok_or_else(|| {
+                    BamlRtError::Configuration(format!(
+                        "peer keys {}: key '{key_id}' is not a hex Ed25519 public key",
+                        path.display()
+                    ))
+                })?;
+            verifier.keys.insert(key_id, key);
+        }
+        Ok(verifier)
+    }
+
+    /// Refuse messages that are not signed by a peer key.
+    pub fn with_require_verified(mut self, require_verified: bool) -> Self {
+        self.require_verified = require_verified;
+        self
+    }
+
+    /// How far a signature's `signedAt` may be from this verifier's clock.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    pub fn key_count(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Check the signature of a message in its JSON form.
+    pub fn verify_value(&self, message: &Value) -> SignatureStatus {
+        let Some(signature) = message
+            .get("metadata")
+            .and_then(|metadata| metadata.get(SIGNATURE_METADATA_KEY))
+        else {
+            return SignatureStatus::Unsigned;
+        };
+        let invalid = |reason: &str| SignatureStatus::Invalid { reason: reason.to_string() };
+        if signature.get("algorithm").and_then(Value::as_str) != Some(SIGNATURE_ALGORITHM) {
+            return invalid("unsupported signature algorithm");
+        }
+        let Some(key_id) = signature.get("keyId").and_then(Value::as_str) else {
+            return invalid("signature keyId is missing");
+        };
+        let Some(key) = self.keys.get(key_id) else {
+            return SignatureStatus::UnknownKey { key_id: key_id.to_string() };
+        };
+        let Some(bytes) = signature.get("value").and_then(Value::as_str).and_then(decode_fixed::<64>) else {
+            return invalid("signature value is not a hex Ed25519 signature");
+        };
+        let Some(signed_at) = signature.get("signedAt").and_then(Value::as_u64) else {
+            return invalid("signature signedAt is missing");
+        };
+        let Some(nonce) = signature.get("nonce").and_then(Value::as_str) else {
+            return invalid("signature nonce is missing");
+        };
+        let content = signed_content(message, signed_at, nonce);
+        if key.verify_strict(&content, &Signature::from_bytes(&bytes)).is_err() {
+            return invalid("signature does not match the message");
+        }
+        if now_ms().abs_diff(signed_at) > self.max_age.as_millis() as u64 {
+            return invalid("signature is stale");
+        }
+        SignatureStatus::Verified { key_id: key_id.to_string() }
+    }
+
+    /// Remember the nonce of a verified `message`, refusing one already seen
+    /// within the maximum age.
+    fn accept_nonce(&self, message: &Value, key_id: &str) -> std::result::Result<(), String> {
+        let signature = &message["metadata"][SIGNATURE_METADATA_KEY];
+        let (Some(signed_at), Some(nonce)) = (
+            signature.get("signedAt").and_then(Value::as_u64),
+            signature.get("nonce").and_then(Value::as_str),
+        ) else {
+            return Err("signature nonce is missing".to_string());
+        };
+        let oldest = now_ms().saturating_sub(self.max_age.as_millis() as u64);
+        let mut seen = self.seen_nonces.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        seen.retain(|_, seen_at| *seen_at >= oldest);
+        match seen.entry((key_id.to_string(), nonce.to_string())) {
+            Entry::Occupied(_) => {
+                Err("signature nonce was already used".to_string())
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(signed_at);
+                Ok(())
+            }
+        }
+    }
+
+    /// Verify `message` and apply the policy, returning the status to record.
+    /// A verified signature whose nonce was already accepted is refused.
+    pub fn check(&self, message: &Value) -> Result<SignatureStatus> {
+        let status = self.verify_value(message);
+        let refused = |reason: String| {
+            BamlRtError::InvalidArgument(format!("message signature refused: {reason}"))
+        };
+        match &status {
+            SignatureStatus::Verified { key_id } => {
+                self.accept_nonce(message, key_id).map_err(refused)?;
+                Ok(status)
+            }
+            SignatureStatus::Invalid { reason } => Err(refused(reason.clone())),
+            SignatureStatus::Unsigned if self.require_verified => {
+                Err(refused("message is unsigned".to_string()))
+            }
+            SignatureStatus::UnknownKey { key_id } if self.require_verified => {
+                Err(refused(format!("key '{key_id}' is not a peer key")))
+            }
+            _ => Ok(status),
+        }
+    }
+}
+
+/// Write `status` into the metadata of a message in its JSON form.
+pub(crate) fn record_status(message: &mut Value, status: &SignatureStatus) {
+    if !message.is_object() {
+        return;
+    }
+    let metadata = &mut message["metadata"];
+    if !metadata.is_object() {
+        *metadata = json!({});
+    }
+    metadata[SIGNATURE_STATUS_METADATA_KEY] = Value::String(status.as_str().to_string());
+    if let Some(metadata) = metadata.as_object_mut() {
+        match status.key_id() {
+            Some(key_id) => {
+                metadata.insert(SIGNATURE_KEY_ID_METADATA_KEY.to_string(), Value::String(key_id.to_string()));
+            }
+            None => {
+                metadata.remove(SIGNATURE_KEY_ID_METADATA_KEY);
+            }
+        }
+    }
+}
+
+/// The bytes a message signature made at `signed_at` with `nonce` covers.
+fn signed_content(message: &Value, signed_at: u64, nonce: &str) -> Vec<u8> {
+    let mut content = message.clone();
+    if let Value::Object(object) = &mut content {
+        if let Some(Value::Object(metadata)) = object.get_mut("metadata") {
+            for key in [
+                SIGNATURE_METADATA_KEY,
+                SIGNATURE_STATUS_METADATA_KEY,
+                SIGNATURE_KEY_ID_METADATA_KEY,
+            ] {
+                metadata.remove(key);
+            }
+        }
+        if object
+            .get("metadata")
+            .is_some_and(|metadata| metadata.is_null() || metadata.as_object().is_some_and(|m| m.is_empty()))
+        {
+            object.remove("metadata");
+        }
+    }
+    canonicalize(&json!({"message": content, "signedAt": signed_at, "nonce": nonce})).into_bytes()
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_millis() as u64).unwrap_or(0)
+}
+
+fn decode_fixed<const N: usize>(hex_value: &str) -> Option<[u8; N]> {
+    hex::decode(hex_value.trim()).ok()?.try_into().ok()
+}
@@ -0,0 +1,318 @@
+//! SQLite-backed task store.
+//!
+//! Tasks, per-context message history and fork lineage are written through to a
+//! SQLite file, so `tasks/get`, `tasks/list` and context history survive a restart
+//! of the runner. Reads are served from the in-memory [`TaskStore`] the file is
+//! loaded into on open; provenance is recorded by the wrapped
+//! [`ProvenanceTaskStore`] exactly as for the in-memory backend.
+//!
+//! Buffered stream updates and idle timers are process-local and are not persisted.
+//! A failed write is logged and leaves the in-memory state authoritative until the
+//! next write of the same task succeeds.
+
+use crate::a2a_store::{
+    with_scope_metadata, ContextRepository, ProvenanceTaskStore, TaskEventRecorder,
+    TaskRepository, TaskStore, TaskUpdateBatch, TaskUpdateEvent, TaskUpdateQueue,
+};
+use crate::a2a_types::{
+    Artifact, ContextBranch, ListContextsRequest, ListContextsResponse, ListMessagesRequest,
+    ListMessagesResponse, ListTasksRequest, ListTasksResponse, Message, Task, TaskStatus,
+};
+use crate::task_state::TransitionPolicy;
+use async_trait::async_trait;
+use baml_rt_core::context;
+use baml_rt_core::ids::{AgentId, ContextId, MessageId, TaskId};
+use baml_rt_core::{BamlRtError, Result};
+use baml_rt_provenance::ProvenanceWriter;
+use rusqlite::{params, Connection};
+use serde::de::DeserializeOwned;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::warn;
+
+const SCHEMA: &str = r#"
+    CREATE TABLE IF NOT EXISTS tasks (
+        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+        id TEXT NOT NULL UNIQUE,
+        task TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS context_messages (
+        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+        context_id TEXT NOT NULL,
+        message TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS context_messages_context_id ON context_messages (context_id);
+    CREATE TABLE IF NOT EXISTS context_branches (
+        context_id TEXT PRIMARY KEY,
+        branch TEXT NOT NULL
+    );
+"#;
+
+pub struct SqliteTaskStore {
+    inner: ProvenanceTaskStore,
+    path: PathBuf,
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteTaskStore {
+    /// Open (or create) the task database at `path` and load the tasks it holds.
+    pub fn open(
+        path: impl Into<PathBuf>,
+        writer: Option<Arc<dyn ProvenanceWriter>>,
+        agent_id: AgentId,
+    ) -> Result<Self> {
+        let path = path.into();
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(&path).map_err(|err| storage_error(&path, err))?;
+        conn.pragma_update(None, "journal_mode", "WAL")
+            .map_err(|err| storage_error(&path, err))?;
+        conn.execute_batch(SCHEMA).map_err(|err| storage_error(&path, err))?;
+        let restored = load(&conn).map_err(|err| storage_error(&path, err))?;
+        tracing::debug!(path = %path.display(), "Opened SQLite task store");
+        Ok(Self {
+            inner: ProvenanceTaskStore::new(writer, agent_id).with_task_store(restored),
+            path,
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// How illegal task state transitions are handled; defaults to [`TransitionPolicy::Reject`].
+    pub fn with_transition_policy(self, policy: TransitionPolicy) -> Self {
+        Self { inner: self.inner.with_transition_policy(policy), ..self }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Write the current state of task `id`, if the store knows it.
+    async fn persist_task(&self, id: &str) {
+        let Some(task) = self.inner.get(id, None).await else {
+            return;
+        };
+        let Some(json) = to_json(&task) else {
+            return;
+        };
+        let id = id.to_string();
+        self.write("task", move |conn| {
+            conn.execute(
+                "INSERT INTO tasks (id, task) VALUES (?1, ?2)
+                 ON CONFLICT(id) DO UPDATE SET task = excluded.task",
+                params![id, json],
+            )
+            .map(|_| ())
+        })
+        .await;
+    }
+
+    async fn persist_messages(&self, context_id: &str, messages: &[Message]) {
+        let rows: Vec<String> = messages.iter().filter_map(to_json).collect();
+        let context_id = context_id.to_string();
+        self.write("context messages", move |conn| {
+            let tx = conn.transaction()?;
+            for message in rows {
+                tx.execute(
+                    "INSERT INTO context_messages (context_id, message) VALUES (?1, ?2)",
+                    params![context_id, message],
+                )?;
+            }
+            tx.commit()
+        })
+        .await;
+    }
+
+    async fn persist_branch(&self, branch: &ContextBranch) {
+        let lineage = ContextBranch { history: Vec::new(), ..branch.clone() };
+        let Some(json) = to_json(&lineage) else {
+            return;
+        };
+        let context_id = branch.context_id.as_str().to_string();
+        self.write("context branch", move |conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO context_branches (context_id, branch) VALUES (?1, ?2)",
+                params![context_id, json],
+            )
+            .map(|_| ())
+        })
+        .await;
+    }
+
+    /// Run `f` on the blocking pool, logging instead of failing the caller.
+    async fn write<F>(&self, what: &'static str, f: F)
+    where
+        F: FnOnce(&mut Connection) -> rusqlite::Result<()> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        let outcome = tokio::task::spawn_blocking(move || {
+            let mut conn = conn.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            f(&mut conn)
+        })
+        .await
+        .map_err(|err| err.to_string())
+        .and_then(|written| written.map_err(|err| err.to_string()));
+        if let Err(error) = outcome {
+            warn!(path = %self.path.display(), error = %error, "Failed to persist {}", what);
+        }
+    }
+}
+
+fn storage_error(path: &Path, err: rusqlite::Error) -> BamlRtError {
+    BamlRtError::Initialization(format!("Task store {}: {}", path.display(), err))
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Option<String> {
+    serde_json::to_string(value)
+        .map_err(|err| warn!(error = %err, "Failed to serialize task store row"))
+        .ok()
+}
+
+fn load(conn: &Connection) -> rusqlite::Result<TaskStore> {
+    let tasks = rows::<Task>(conn, "SELECT task, id FROM tasks ORDER BY seq")?
+        .into_iter()
+        .map(|(_, task)| task)
+        .collect();
+    let messages = rows::<Message>(conn, "SELECT message, context_id FROM context_messages ORDER BY seq")?;
+    let branches = rows::<ContextBranch>(conn, "SELECT branch, context_id FROM context_branches")?
+        .into_iter()
+        .map(|(_, branch)| branch)
+        .collect();
+    Ok(TaskStore::restore(tasks, messages, branches))
+}
+
+/// Decode the JSON in column 0 of each row, paired with the key in column 1.
+/// Rows that no longer decode are skipped with a warning rather than failing the open.
+fn rows<T: DeserializeOwned>(conn: &Connection, sql: &str) -> rusqlite::Result<Vec<(String, T)>> {
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(1)?, row.get::<_, String>(0)?)))?;
+    let mut decoded = Vec::new();
+    for row in rows {
+        let (key, json) = row?;
+        match serde_json::from_str(&json) {
+            Ok(value) => decoded.push((key, value)),
+            Err(err) => warn!(error = %err, "Skipping unreadable task store row"),
+        }
+    }
+    Ok(decoded)
+}
+
+#[async_trait]
+impl TaskRepository for SqliteTaskStore {
+    async fn upsert(&self, task: Task) -> Option<Task> {
+        let stored = self.inner.upsert(task).await;
+        if let Some(id) = stored.as_ref().and_then(|task| task.id.as_ref()) {
+            self.persist_task(id.as_str()).await;
+        }
+        stored
+    }
+
+    async fn get(&self, id: &str, history_length: Option<usize>) -> Option<Task> {
+        self.inner.get(id, history_length).await
+    }
+
+    async fn list(&self, request: &ListTasksRequest) -> ListTasksResponse {
+        self.inner.list(request).await
+    }
+
+    async fn cancel(&self, id: &str) -> Option<Task> {
+        let task = self.inner.cancel(id).await;
+        if task.is_some() {
+            self.persist_task(id).await;
+        }
+        task
+    }
+
+    async fn insert_message(&self, message: &Message) -> Result<()> {
+        self.inner.insert_message(message).await?;
+        if let Some(task_id) = &message.task_id {
+            self.persist_task(task_id.as_str()).await;
+        }
+        if let Some(context_id) = message.context_id.clone().or_else(context::current_context_id) {
+            // Persisted with its tenant and parent, as the in-memory store keeps it.
+            let message = with_scope_metadata(message);
+            self.persist_messages(context_id.as_str(), std::slice::from_ref(&message)).await;
+        }
+        Ok(())
+    }
+
+    async fn stale_tasks(&self, idle: Duration) -> Vec<Task> {
+        self.inner.stale_tasks(idle).await
+    }
+}
+
+#[async_trait]
+impl TaskEventRecorder for SqliteTaskStore {
+    async fn record_status_update(
+        &self,
+        task_id: Option<TaskId>,
+        context_id: Option<ContextId>,
+        status: TaskStatus,
+    ) -> Option<TaskUpdateEvent> {
+        let update = self.inner.record_status_update(task_id.clone(), context_id, status).await;
+        if update.is_some()
+            && let Some(task_id) = task_id
+        {
+            self.persist_task(task_id.as_str()).await;
+        }
+        update
+    }
+
+    async fn record_artifact_update(
+        &self,
+        task_id: Option<TaskId>,
+        context_id: Option<ContextId>,
+        artifact: Artifact,
+        append: Option<bool>,
+        last_chunk: Option<bool>,
+    ) -> Option<TaskUpdateEvent> {
+        self.inner
+            .record_artifact_update(task_id, context_id, artifact, append, last_chunk)
+            .await
+    }
+}
+
+#[async_trait]
+impl TaskUpdateQueue for SqliteTaskStore {
+    async fn drain_updates(&self, task_id: &str) -> Vec<TaskUpdateEvent> {
+        self.inner.drain_updates(task_id).await
+    }
+
+    async fn updates_since(&self, task_id: &str, cursor: u64) -> TaskUpdateBatch {
+        self.inner.updates_since(task_id, cursor).await
+    }
+}
+
+#[async_trait]
+impl ContextRepository for SqliteTaskStore {
+    async fn context_history(&self, context_id: &str, history_length: Option<usize>) -> Vec<Message> {
+        self.inner.context_history(context_id, history_length).await
+    }
+
+    async fn context_branch(&self, context_id: &str) -> Option<ContextBranch> {
+        self.inner.context_branch(context_id).await
+    }
+
+    async fn fork_context(
+        &self,
+        source: &ContextId,
+        at_message: &MessageId,
+        target: ContextId,
+    ) -> Result<ContextBranch> {
+        let branch = self.inner.fork_context(source, at_message, target).await?;
+        self.persist_messages(branch.context_id.as_str(), &branch.history).await;
+        self.persist_branch(&branch).await;
+        Ok(branch)
+    }
+
+    async fn list_messages(&self, request: &ListMessagesRequest) -> Option<ListMessagesResponse> {
+        self.inner.list_messages(request).await
+    }
+
+    async fn list_contexts(&self, request: &ListContextsRequest) -> ListContextsResponse {
+        self.inner.list_contexts(request).await
+    }
+}
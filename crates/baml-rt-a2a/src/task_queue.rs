@@ -0,0 +1,100 @@
+//! Backpressure for task executions.
+//!
+//! Each `message.send` or `message.sendStream` runs the agent's JS handler, and
+//! without a limit every request starts one at once. A [`TaskQueue`] caps how
+//! many run together; the others wait for a slot in the order they arrived.
+//!
+//! While a message waits, the task it names reads `TASK_STATE_SUBMITTED` if that
+//! task has not started yet, so `tasks.get` and subscribers can tell it is
+//! queued. `baml_rt.task_queue.depth` counts the executions waiting per agent
+//! and `baml_rt.task_queue.wait_ms` records how long each of them waited.
+
+use baml_rt_observability::metrics;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Caps how many task executions of one agent run at once
+///
+/// Clones share their slots.
+#[derive(Debug, Clone)]
+pub struct TaskQueue {
+    agent: Arc<str>,
+    slots: Arc<Semaphore>,
+    max_concurrent: usize,
+    waiting: Arc<AtomicUsize>,
+}
+
+/// The slot of a running task execution; freed when dropped.
+#[derive(Debug)]
+pub struct TaskSlot {
+    _permit: OwnedSemaphorePermit,
+}
+
+impl TaskQueue {
+    /// `agent` labels the queue's metrics. At least one execution runs at a time.
+    pub fn new(agent: impl Into<String>, max_concurrent: usize) -> Self {
+        let max_concurrent = max_concurrent.clamp(1, Semaphore::MAX_PERMITS);
+        Self {
+            agent: Arc::from(agent.into()),
+            slots: Arc::new(Semaphore::new(max_concurrent)),
+            max_concurrent,
+            waiting: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    pub fn max_concurrent(&self) -> usize {
+        self.max_concurrent
+    }
+
+    /// Executions holding a slot
+    pub fn running(&self) -> usize {
+        self.max_concurrent - self.slots.available_permits()
+    }
+
+    /// Executions waiting for a slot
+    pub fn waiting(&self) -> usize {
+        self.waiting.load(Ordering::SeqCst)
+    }
+
+    /// A slot if one is free now. Slots go to waiting executions first, so
+    /// this never overtakes them.
+    pub fn try_start(&self) -> Option<TaskSlot> {
+        let permit = self.slots.clone().try_acquire_owned().ok()?;
+        Some(TaskSlot { _permit: permit })
+    }
+
+    /// Wait for a slot. An execution dropped while waiting leaves the queue.
+    pub async fn start(&self) -> TaskSlot {
+        let _waiting = Waiting::enter(self);
+        let permit = self
+            .slots
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("the task queue never closes its semaphore");
+        TaskSlot { _permit: permit }
+    }
+}
+
+/// Counts an execution as queued for as long as it lives.
+struct Waiting<'a> {
+    queue: &'a TaskQueue,
+    since: Instant,
+}
+
+impl<'a> Waiting<'a> {
+    fn enter(queue: &'a TaskQueue) -> Self {
+        queue.waiting.fetch_add(1, Ordering::SeqCst);
+        metrics::record_task_queued(&queue.agent);
+        Self { queue, since: Instant::now() }
+    }
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.queue.waiting.fetch_sub(1, Ordering::SeqCst);
+        metrics::record_task_dequeued(&self.queue.agent, self.since.elapsed());
+    }
+}
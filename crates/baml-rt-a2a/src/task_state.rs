@@ -0,0 +1,200 @@
+//! The A2A task state machine.
+//!
+//! ```text
+//! submitted ──► working ──► completed | failed | canceled
+//!     │            ▲  │
+//!     │            │  ▼
+//!     └──────► input-required / auth-required ──► completed | failed | canceled
+//! ```
+//!
+//! A task may also go straight from `submitted` to any terminal state (including
+//! `rejected`), and re-reporting the current state is always allowed. Terminal states
+//! accept no further transitions. States this runtime does not recognise are left
+//! unchecked so custom agents keep working.
+
+use crate::a2a_types::{TaskState, TaskStatus};
+use baml_rt_core::ids::TaskId;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TaskLifecycleState {
+    Submitted,
+    Working,
+    InputRequired,
+    AuthRequired,
+    Completed,
+    Failed,
+    Canceled,
+    Rejected,
+}
+
+impl TaskLifecycleState {
+    /// Accepts `TASK_STATE_WORKING`, `working`, `input-required` and the A2A protobuf numbers.
+    pub fn parse(state: &TaskState) -> Option<Self> {
+        match state {
+            TaskState::String(value) => {
+                let name = value.strip_prefix("TASK_STATE_").unwrap_or(value);
+                match name.to_ascii_uppercase().replace('-', "_").as_str() {
+                    "SUBMITTED" => Some(Self::Submitted),
+                    "WORKING" => Some(Self::Working),
+                    "INPUT_REQUIRED" => Some(Self::InputRequired),
+                    "AUTH_REQUIRED" => Some(Self::AuthRequired),
+                    "COMPLETED" => Some(Self::Completed),
+                    "FAILED" => Some(Self::Failed),
+                    "CANCELED" | "CANCELLED" => Some(Self::Canceled),
+                    "REJECTED" => Some(Self::Rejected),
+                    _ => None,
+                }
+            }
+            TaskState::Integer(value) => match value {
+                1 => Some(Self::Submitted),
+                2 => Some(Self::Working),
+                3 => Some(Self::Completed),
+                4 => Some(Self::Failed),
+                5 => Some(Self::Canceled),
+                6 => Some(Self::InputRequired),
+                7 => Some(Self::Rejected),
+                8 => Some(Self::AuthRequired),
+                _ => None,
+            },
+        }
+    }
+
+    pub fn of(status: &TaskStatus) -> Option<Self> {
+        status.state.as_ref().and_then(Self::parse)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Submitted => "TASK_STATE_SUBMITTED",
+            Self::Working => "TASK_STATE_WORKING",
+            Self::InputRequired => "TASK_STATE_INPUT_REQUIRED",
+            Self::AuthRequired => "TASK_STATE_AUTH_REQUIRED",
+            Self::Completed => "TASK_STATE_COMPLETED",
+            Self::Failed => "TASK_STATE_FAILED",
+            Self::Canceled => crate::a2a_types::TASK_STATE_CANCELED,
+            Self::Rejected => "TASK_STATE_REJECTED",
+        }
+    }
+
+    pub fn is_terminal(self) -> bool {
+        matches!(self, Self::Completed | Self::Failed | Self::Canceled | Self::Rejected)
+    }
+
+    pub fn can_transition_to(self, next: Self) -> bool {
+        use TaskLifecycleState::*;
+        if self == next {
+            return true;
+        }
+        match self {
+            Submitted => next != Submitted,
+            Working => matches!(next, InputRequired | AuthRequired | Completed | Failed | Canceled),
+            InputRequired | AuthRequired => {
+                matches!(next, Working | Completed | Failed | Canceled)
+            }
+            Completed | Failed | Canceled | Rejected => false,
+        }
+    }
+}
+
+impl fmt::Display for TaskLifecycleState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// What the task store does with a transition the state machine does not allow.
+///
+/// Either way the attempt is logged and, for provenance-backed stores, recorded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransitionPolicy {
+    /// Keep the task in its current state.
+    #[default]
+    Reject,
+    /// Apply the transition anyway.
+    Flag,
+}
+
+/// A status change the state machine does not allow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IllegalTransition {
+    pub task_id: TaskId,
+    pub from: TaskLifecycleState,
+    pub to: TaskLifecycleState,
+    /// Whether the store refused the transition (see [`TransitionPolicy`]).
+    pub rejected: bool,
+}
+
+impl fmt::Display for IllegalTransition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "task {} cannot move from {} to {}", self.task_id, self.from, self.to)
+    }
+}
+
+/// Check moving from `current` to `next`; `None` when allowed or when either state is unknown.
+pub fn check_transition(
+    task_id: &TaskId,
+    current: Option<&TaskStatus>,
+    next: &TaskStatus,
+    policy: TransitionPolicy,
+) -> Option<IllegalTransition> {
+    let from = current.and_then(TaskLifecycleState::of)?;
+    let to = TaskLifecycleState::of(next)?;
+    (!from.can_transition_to(to)).then(|| IllegalTransition {
+        task_id: task_id.clone(),
+        from,
+        to,
+        rejected: policy == TransitionPolicy::Reject,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(state: &str) -> TaskStatus {
+        TaskStatus { state: Some(TaskState::String(state.to_string())), ..Default::default() }
+    }
+
+    #[test]
+    fn parses_prefixed_kebab_and_numeric_states() {
+        let parse = |value: &str| TaskLifecycleState::parse(&TaskState::String(value.to_string()));
+        assert_eq!(parse("TASK_STATE_INPUT_REQUIRED"), Some(TaskLifecycleState::InputRequired));
+        assert_eq!(parse("input-required"), Some(TaskLifecycleState::InputRequired));
+        assert_eq!(parse("cancelled"), Some(TaskLifecycleState::Canceled));
+        assert_eq!(parse("custom"), None);
+        assert_eq!(
+            TaskLifecycleState::parse(&TaskState::Integer(2)),
+            Some(TaskLifecycleState::Working)
+        );
+    }
+
+    #[test]
+    fn follows_the_a2a_lifecycle() {
+        use TaskLifecycleState::*;
+        assert!(Submitted.can_transition_to(Working));
+        assert!(Working.can_transition_to(InputRequired));
+        assert!(InputRequired.can_transition_to(Working));
+        assert!(Working.can_transition_to(Completed));
+        assert!(Completed.can_transition_to(Completed));
+        assert!(!Working.can_transition_to(Submitted));
+        assert!(!Completed.can_transition_to(Working));
+        assert!(!Canceled.can_transition_to(Completed));
+    }
+
+    #[test]
+    fn unknown_or_missing_states_are_not_checked() {
+        let id = TaskId::from_external(baml_rt_core::ids::ExternalId::new("task-1"));
+        let policy = TransitionPolicy::Reject;
+        assert!(check_transition(&id, None, &status("TASK_STATE_WORKING"), policy).is_none());
+        assert!(check_transition(&id, Some(&status("custom")), &status("working"), policy).is_none());
+
+        let illegal = check_transition(&id, Some(&status("completed")), &status("working"), policy)
+            .expect("illegal");
+        assert!(illegal.rejected);
+        assert_eq!(
+            illegal.to_string(),
+            "task task-1 cannot move from TASK_STATE_COMPLETED to TASK_STATE_WORKING"
+        );
+    }
+}
@@ -0,0 +1,185 @@
+//! Fail tasks that stop making progress.
+//!
+//! An agent that crashes mid-task leaves the task `working` forever. The sweeper
+//! periodically asks the task store for unfinished tasks with no activity for the
+//! configured timeout and moves them to `failed`. The change goes through the
+//! normal status update path, so subscribers receive a status update event and
+//! provenance-backed stores record the transition.
+//!
+//! [`cancel_unfinished_tasks`] takes the same path when an agent stops, so no
+//! task it was working on is left `working` after it is gone.
+
+use crate::a2a_store::{TaskStoreBackend, TaskUpdateEvent};
+use crate::a2a_types::{
+    A2aMessageId, Message, MessageRole, Part, TaskState, TaskStatus, ROLE_AGENT,
+};
+use crate::events::EventEmitter;
+use crate::task_state::TaskLifecycleState;
+use baml_rt_core::ids::{DerivedId, TaskId};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// `metadata.reason` on the status message of a task failed by the sweeper.
+pub const TASK_TIMEOUT_REASON: &str = "timeout";
+/// `metadata.reason` on the status message of a task canceled because its agent stopped.
+pub const TASK_SHUTDOWN_REASON: &str = "shutdown";
+
+const MIN_SWEEP_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskTimeoutConfig {
+    /// How long an unfinished task may go without activity.
+    pub timeout: Duration,
+    pub sweep_interval: Duration,
+}
+
+impl TaskTimeoutConfig {
+    /// Sweeps four times per timeout, between once a second and once a minute.
+    pub fn new(timeout: Duration) -> Self {
+        let sweep_interval = (timeout / 4).clamp(MIN_SWEEP_INTERVAL, MAX_SWEEP_INTERVAL);
+        Self { timeout, sweep_interval }
+    }
+
+    pub fn with_sweep_interval(mut self, interval: Duration) -> Self {
+        self.sweep_interval = interval;
+        self
+    }
+}
+
+/// Background task failing stale tasks; aborted on drop.
+pub struct TaskTimeoutSweeper {
+    handle: JoinHandle<()>,
+}
+
+impl TaskTimeoutSweeper {
+    pub fn spawn(
+        store: Arc<dyn TaskStoreBackend>,
+        emitter: Arc<dyn EventEmitter>,
+        config: TaskTimeoutConfig,
+    ) -> Self {
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(config.sweep_interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                fail_stale_tasks(store.as_ref(), emitter.as_ref(), config.timeout).await;
+            }
+        });
+        Self { handle }
+    }
+}
+
+impl Drop for TaskTimeoutSweeper {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Move every task idle for `timeout` to failed and emit its status update.
+/// Returns how many tasks were failed.
+pub async fn fail_stale_tasks(
+    store: &dyn TaskStoreBackend,
+    emitter: &dyn EventEmitter,
+    timeout: Duration,
+) -> usize {
+    let mut failed = 0;
+    for task in store.stale_tasks(timeout).await {
+        let Some(task_id) = task.id else {
+            continue;
+        };
+        let status = timed_out_status(&task_id, timeout);
+        let update = store
+            .record_status_update(Some(task_id.clone()), task.context_id.clone(), status)
+            .await;
+        match update {
+            Some(update @ TaskUpdateEvent::Status(_)) => {
+                info!(task_id = task_id.as_str(), timeout_secs = timeout.as_secs(), "Task timed out");
+                emitter.emit(update).await;
+                failed += 1;
+            }
+            // The task finished between the scan and the update.
+            _ => warn!(task_id = task_id.as_str(), "Stale task could not be failed"),
+        }
+    }
+    failed
+}
+
+/// Cancel every unfinished task in `store` and emit its status update, for an
+/// agent that is stopping. Returns how many tasks were canceled.
+pub async fn cancel_unfinished_tasks(
+    store: &dyn TaskStoreBackend,
+    emitter: &dyn EventEmitter,
+) -> usize {
+    let mut canceled = 0;
+    for task in store.stale_tasks(Duration::ZERO).await {
+        let Some(task_id) = task.id else {
+            continue;
+        };
+        let status = closing_status(
+            "task_shutdown",
+            &task_id,
+            TaskLifecycleState::Canceled,
+            "Task canceled because the agent shut down".to_string(),
+            TASK_SHUTDOWN_REASON,
+        );
+        let update = store
+            .record_status_update(Some(task_id.clone()), task.context_id.clone(), status)
+            .await;
+        match update {
+            Some(update @ TaskUpdateEvent::Status(_)) => {
+                info!(task_id = task_id.as_str(), "Task canceled at shutdown");
+                emitter.emit(update).await;
+                canceled += 1;
+            }
+            _ => warn!(task_id = task_id.as_str(), "Unfinished task could not be canceled"),
+        }
+    }
+    canceled
+}
+
+fn timed_out_status(task_id: &TaskId, timeout: Duration) -> TaskStatus {
+    closing_status(
+        "task_timeout",
+        task_id,
+        TaskLifecycleState::Failed,
+        format!("Task timed out after {}s without progress", timeout.as_secs()),
+        TASK_TIMEOUT_REASON,
+    )
+}
+
+/// Final `state` of a task the runtime closes, explaining why in `text`
+fn closing_status(
+    id_prefix: &'static str,
+    task_id: &TaskId,
+    state: TaskLifecycleState,
+    text: String,
+    reason: &str,
+) -> TaskStatus {
+    let message = Message {
+        message_id: A2aMessageId::outgoing(DerivedId::from_parts(id_prefix, [task_id.as_str()])),
+        role: MessageRole::String(ROLE_AGENT.to_string()),
+        parts: vec![Part {
+            text: Some(text),
+            ..Part::default()
+        }],
+        context_id: None,
+        task_id: Some(task_id.clone()),
+        reference_task_ids: Vec::new(),
+        extensions: Vec::new(),
+        metadata: Some(HashMap::from([(
+            "reason".to_string(),
+            Value::String(reason.to_string()),
+        )])),
+        extra: HashMap::new(),
+    };
+    TaskStatus {
+        state: Some(TaskState::String(state.as_str().to_string())),
+        message: Some(message),
+        ..TaskStatus::default()
+    }
+}
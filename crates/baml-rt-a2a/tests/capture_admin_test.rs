@@ -0,0 +1,65 @@
+use baml_rt_a2a::a2a::{A2aMethod, A2aOutcome, A2aRequest};
+use baml_rt_a2a::a2a_types::SetCaptureDetailRequest;
+use baml_rt_a2a::handlers::{AdminHandler, DefaultAdminHandler, MAX_CAPTURE_DURATION};
+use baml_rt_core::ids::ContextId;
+use baml_rt_interceptor::{CaptureDetail, OperatorCredentials, PayloadCapture};
+use serde_json::json;
+use std::sync::Arc;
+
+#[tokio::test]
+async fn set_capture_detail_raises_one_context_for_a_bounded_time() {
+    let request = A2aRequest::from_value(json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "admin.setCaptureDetail",
+        "params": {
+            "detail": "full",
+            "durationSecs": 86_400,
+            "contextId": "ctx-1-1",
+            "operatorToken": "secret",
+        },
+    }))
+    .expect("parse request");
+    assert_eq!(request.method, A2aMethod::AdminSetCaptureDetail);
+    assert_eq!(request.context_id, Some(ContextId::new(1, 1)));
+
+    let capture = Arc::new(PayloadCapture::new(8));
+    let handler = DefaultAdminHandler::new(capture.clone())
+        .with_operators(OperatorCredentials::new().with_operator("ops", "secret"));
+    let params: SetCaptureDetailRequest =
+        serde_json::from_value(request.params.clone()).expect("params");
+    let A2aOutcome::Response(response) =
+        handler.handle_set_capture_detail(params).await.expect("raise")
+    else {
+        panic!("expected a response");
+    };
+    assert_eq!(response["detail"], "full");
+    assert_eq!(response["expiresInSecs"], json!(MAX_CAPTURE_DURATION.as_secs()));
+    assert_eq!(capture.detail(&ContextId::new(1, 1)), CaptureDetail::Full);
+    assert_eq!(capture.detail(&ContextId::new(1, 2)), CaptureDetail::Standard);
+
+    let reset: SetCaptureDetailRequest = serde_json::from_value(
+        json!({"detail": "standard", "contextId": "ctx-1-1", "operatorToken": "secret"}),
+    )
+    .expect("params");
+    handler.handle_set_capture_detail(reset).await.expect("reset");
+    assert_eq!(capture.detail(&ContextId::new(1, 1)), CaptureDetail::Standard);
+
+    let unknown: SetCaptureDetailRequest =
+        serde_json::from_value(json!({"detail": "verbose", "operatorToken": "secret"}))
+            .expect("params");
+    assert!(handler.handle_set_capture_detail(unknown).await.is_err());
+}
+
+#[tokio::test]
+async fn set_capture_detail_requires_an_operator_token() {
+    let capture = Arc::new(PayloadCapture::new(8));
+    let handler = DefaultAdminHandler::new(capture.clone())
+        .with_operators(OperatorCredentials::new().with_operator("ops", "secret"));
+
+    for params in [json!({"detail": "full"}), json!({"detail": "full", "operatorToken": "guess"})] {
+        let request: SetCaptureDetailRequest = serde_json::from_value(params).expect("params");
+        assert!(handler.handle_set_capture_detail(request).await.is_err());
+    }
+    assert_eq!(capture.detail(&ContextId::new(1, 1)), CaptureDetail::Standard);
+}
@@ -0,0 +1,124 @@
+//! Child contexts: the parent pointer in scope, in A2A metadata and in provenance.
+
+use baml_rt_a2a::a2a::{inject_parent_context, A2aRequest};
+use baml_rt_a2a::a2a_store::{
+    message_parent_context, ContextRepository, ProvenanceTaskStore, TaskRepository,
+};
+use baml_rt_a2a::a2a_types::{A2aMessageId, ListContextsRequest, Message, MessageRole, Part, ROLE_USER};
+use baml_rt_core::context::{self, RuntimeScope, PARENT_CONTEXT_ID_METADATA_KEY};
+use baml_rt_core::ids::{AgentId, ContextId, ExternalId, UuidId};
+use baml_rt_provenance::{InMemoryProvenanceStore, ProvEventData};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+fn agent_id() -> AgentId {
+    AgentId::from_uuid(UuidId::new(uuid::Uuid::new_v4()))
+}
+
+fn message(id: &str, context_id: &ContextId) -> Message {
+    Message {
+        message_id: A2aMessageId::incoming(ExternalId::new(id)),
+        role: MessageRole::String(ROLE_USER.to_string()),
+        parts: vec![Part { text: Some(id.to_string()), ..Part::default() }],
+        context_id: Some(context_id.clone()),
+        task_id: None,
+        reference_task_ids: Vec::new(),
+        extensions: Vec::new(),
+        metadata: None,
+        extra: HashMap::new(),
+    }
+}
+
+fn send(message: Value) -> Value {
+    json!({"jsonrpc": "2.0", "id": 1, "method": "message.send", "params": {"message": message}})
+}
+
+#[test]
+fn requests_read_the_parent_from_message_metadata() {
+    let parent = ContextId::new(1, 1);
+    let child = ContextId::new(1, 2);
+    let request = A2aRequest::from_value(send(json!({
+        "messageId": "m1",
+        "role": "ROLE_USER",
+        "contextId": child,
+        "parts": [{"text": "hi"}],
+        "metadata": {PARENT_CONTEXT_ID_METADATA_KEY: parent},
+    })))
+    .expect("parse request");
+    assert_eq!(request.parent_context_id, Some(parent.clone()));
+
+    let own = A2aRequest::from_value(send(json!({
+        "messageId": "m2",
+        "role": "ROLE_USER",
+        "contextId": parent,
+        "parts": [{"text": "hi"}],
+        "metadata": {PARENT_CONTEXT_ID_METADATA_KEY: parent},
+    })))
+    .expect("parse request");
+    assert_eq!(own.parent_context_id, None, "a context is not its own child");
+
+    let invalid = A2aRequest::from_value(send(json!({
+        "messageId": "m3",
+        "role": "ROLE_USER",
+        "parts": [{"text": "hi"}],
+        "metadata": {PARENT_CONTEXT_ID_METADATA_KEY: 7},
+    })));
+    assert!(invalid.is_err());
+}
+
+#[tokio::test]
+async fn outgoing_messages_name_the_current_context_as_parent() {
+    let current = ContextId::new(3, 1);
+    let scope = RuntimeScope::new(current.clone(), agent_id(), None, None);
+    let (delegated, continued) = context::with_scope(scope, async {
+        let mut delegated = send(json!({"messageId": "m1", "parts": []}));
+        inject_parent_context(&mut delegated);
+        let mut continued = send(json!({"messageId": "m2", "contextId": current, "parts": []}));
+        inject_parent_context(&mut continued);
+        (delegated, continued)
+    })
+    .await;
+    let parent = format!("/params/message/metadata/{PARENT_CONTEXT_ID_METADATA_KEY}");
+    assert_eq!(delegated.pointer(&parent), Some(&json!(current)));
+    assert_eq!(continued.pointer(&parent), None, "same context, no parent");
+}
+
+#[tokio::test]
+async fn child_contexts_are_listed_and_recorded_once() {
+    let writer = Arc::new(InMemoryProvenanceStore::new());
+    let store = ProvenanceTaskStore::new(Some(writer.clone()), agent_id());
+    let parent = ContextId::new(4, 1);
+    let child = ContextId::new(4, 2);
+    let scope = RuntimeScope::new(parent.clone(), agent_id(), None, None);
+    store.insert_message(&message("root", &parent)).await.unwrap();
+    context::with_scope(scope, async {
+        context::with_child_context(child.clone(), async {
+            assert_eq!(context::current_parent_context_id(), Some(parent.clone()));
+            store.insert_message(&message("first", &child)).await.unwrap();
+            store.insert_message(&message("second", &child)).await.unwrap();
+        })
+        .await
+        .expect("child scope");
+    })
+    .await;
+
+    let listed = store.list_contexts(&ListContextsRequest::default()).await;
+    assert_eq!(listed.contexts[0].parent_context_id, None);
+    assert_eq!(listed.contexts[1].context_id, child);
+    assert_eq!(listed.contexts[1].parent_context_id, Some(parent.clone()));
+    assert_eq!(message_parent_context(&listed.contexts[1].history[0]), Some(parent.clone()));
+
+    let nested: Vec<_> = writer
+        .events()
+        .await
+        .into_iter()
+        .filter_map(|event| match event.data() {
+            ProvEventData::ContextNested { parent_context_id } => {
+                Some((event.context_id().clone(), parent_context_id.clone()))
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(nested, vec![(child, parent)]);
+}
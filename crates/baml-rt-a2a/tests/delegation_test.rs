@@ -0,0 +1,142 @@
+//! Agents asking other agents for help through `a2a/delegate`.
+
+use async_trait::async_trait;
+use baml_rt_a2a::a2a_types::{AgentCapabilities, AgentCard};
+use baml_rt_a2a::delegation::DelegateHandler;
+use baml_rt_a2a::{A2aRequestHandler, AgentDirectory, DELEGATE_TOOL, MAX_DELEGATION_DEPTH};
+use baml_rt_core::context::{self, RuntimeScope, DELEGATION_CHAIN_METADATA_KEY};
+use baml_rt_core::ids::{AgentId, ContextId, UuidId};
+use baml_rt_core::Result;
+use baml_rt_provenance::CALLING_AGENT_ID_METADATA_KEY;
+use baml_rt_tools::{ToolHandler, ToolRegistry};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+
+/// Answers every `message.send` with `reply`, keeping the requests it got.
+struct Responder {
+    reply: Value,
+    requests: Mutex<Vec<Value>>,
+}
+
+impl Responder {
+    fn new(reply: Value) -> Arc<Self> {
+        Arc::new(Self { reply, requests: Mutex::new(Vec::new()) })
+    }
+}
+
+#[async_trait(?Send)]
+impl A2aRequestHandler for Responder {
+    async fn handle_a2a(&self, request: Value) -> Result<Vec<Value>> {
+        let id = request["id"].clone();
+        self.requests.lock().unwrap().push(request);
+        Ok(vec![json!({"jsonrpc": "2.0", "id": id, "result": self.reply})])
+    }
+}
+
+fn card(name: &str, description: &str) -> AgentCard {
+    let agent_id = AgentId::from_uuid(UuidId::new(uuid::Uuid::new_v4()));
+    AgentCard {
+        name: name.to_string(),
+        description: Some(description.to_string()),
+        ..AgentCard::anonymous(&agent_id, &AgentCapabilities::default())
+    }
+}
+
+fn registry_for(caller: &str, directory: &AgentDirectory) -> ToolRegistry {
+    let handler = DelegateHandler::new(caller, directory.clone());
+    let mut registry = ToolRegistry::new();
+    registry.register_dynamic(handler.metadata().clone(), Arc::new(handler)).expect("register");
+    registry
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn delegations_reach_the_named_agent_on_the_callers_behalf() {
+    let directory = AgentDirectory::new();
+    let researcher =
+        Responder::new(json!({"message": {"role": "ROLE_AGENT", "parts": [{"text": "42"}]}}));
+    directory.insert(card("researcher", "Looks things up"), researcher.clone());
+    directory.insert(card("planner", "Plans the work"), Responder::new(json!({})));
+    let mut registry = registry_for("planner", &directory);
+
+    let metadata = registry.get_metadata(DELEGATE_TOOL).expect("delegate tool");
+    assert!(metadata.description.contains("researcher (v"), "{}", metadata.description);
+    assert!(metadata.description.contains("Looks things up"));
+    assert!(!metadata.description.contains("planner"), "agents do not delegate to themselves");
+    assert_eq!(metadata.input_schema["properties"]["agent"]["enum"], json!(["researcher"]));
+
+    let caller = AgentId::from_uuid(UuidId::new(uuid::Uuid::new_v4()));
+    let scope = RuntimeScope::new(ContextId::new(1, 1), caller.clone(), None, None)
+        .with_tenant(Some("acme".to_string()))
+        .with_delegation_chain(vec!["frontdesk".to_string()]);
+    let output = context::with_scope(
+        scope,
+        registry.execute(
+            DELEGATE_TOOL,
+            json!({"agent": "researcher", "message": "What is the answer?"}),
+        ),
+    )
+    .await
+    .expect("delegate");
+    assert_eq!(output["agent"], "researcher");
+    assert_eq!(output["text"], "42");
+
+    let requests = researcher.requests.lock().unwrap().clone();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0]["method"], "message.send");
+    let message = &requests[0]["params"]["message"];
+    assert_eq!(message["parts"][0]["text"], "What is the answer?");
+    assert_eq!(message["metadata"][CALLING_AGENT_ID_METADATA_KEY], caller.as_str());
+    let chain = &message["metadata"][DELEGATION_CHAIN_METADATA_KEY];
+    assert_eq!(chain, &json!(["frontdesk", "planner"]));
+    assert_eq!(requests[0]["params"]["tenant"], "acme");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn unknown_agents_and_the_caller_itself_are_refused() {
+    let directory = AgentDirectory::new();
+    directory.insert(card("planner", "Plans the work"), Responder::new(json!({})));
+    let mut registry = registry_for("planner", &directory);
+
+    let err = registry
+        .execute(DELEGATE_TOOL, json!({"agent": "nobody", "message": "hi"}))
+        .await
+        .expect_err("unknown agent");
+    assert!(err.to_string().contains("Unknown agent 'nobody'"), "{err}");
+
+    let err = registry
+        .execute(DELEGATE_TOOL, json!({"agent": "planner", "message": "hi"}))
+        .await
+        .expect_err("self delegation");
+    assert!(err.to_string().contains("cannot delegate to itself"), "{err}");
+}
+
+/// Delegate from `planner` to `researcher` with `chain` in scope.
+async fn delegate_along(registry: &mut ToolRegistry, chain: Vec<String>) -> Result<Value> {
+    let caller = AgentId::from_uuid(UuidId::new(uuid::Uuid::new_v4()));
+    let scope = RuntimeScope::new(ContextId::new(1, 1), caller, None, None)
+        .with_delegation_chain(chain);
+    let call = registry.execute(DELEGATE_TOOL, json!({"agent": "researcher", "message": "hi"}));
+    context::with_scope(scope, call).await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn delegations_in_circles_or_too_deep_are_refused() {
+    let directory = AgentDirectory::new();
+    let researcher = Responder::new(json!({}));
+    directory.insert(card("researcher", "Looks things up"), researcher.clone());
+    directory.insert(card("planner", "Plans the work"), Responder::new(json!({})));
+    let mut registry = registry_for("planner", &directory);
+
+    let err = delegate_along(&mut registry, vec!["researcher".to_string()])
+        .await
+        .expect_err("cycle");
+    assert!(err.to_string().contains("researcher -> planner -> researcher"), "{err}");
+
+    let deep = (0..MAX_DELEGATION_DEPTH).map(|hop| format!("agent-{hop}")).collect();
+    let err = delegate_along(&mut registry, deep).await.expect_err("too deep");
+    assert!(err.to_string().contains("maximum depth"), "{err}");
+
+    let shallow = (1..MAX_DELEGATION_DEPTH).map(|hop| format!("agent-{hop}")).collect();
+    delegate_along(&mut registry, shallow).await.expect("within the limit");
+    assert_eq!(researcher.requests.lock().unwrap().len(), 1);
+}
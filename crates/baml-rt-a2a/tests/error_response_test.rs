@@ -0,0 +1,31 @@
+use baml_rt_a2a::a2a_types::JSONRPCId;
+use baml_rt_a2a::response::{self, JsonRpcResponseFormatter, ResponseFormatter};
+use baml_rt_core::{BamlRtError, ErrorContext};
+use serde_json::json;
+
+#[test]
+fn refusals_map_to_their_own_jsonrpc_codes() {
+    let cases = [
+        (BamlRtError::ToolNotAllowed("fs/write".to_string()), response::TOOL_NOT_ALLOWED),
+        (BamlRtError::SessionStateViolation("next step before open".to_string()), response::SESSION_STATE_VIOLATION),
+        (BamlRtError::SchemaMismatch("Invalid input".to_string()), response::SCHEMA_MISMATCH),
+        (BamlRtError::Timeout("tool session step".to_string()), response::TIMEOUT),
+        (BamlRtError::Budget("rate limit exceeded".to_string()), response::BUDGET_EXHAUSTED),
+    ];
+    for (err, code) in cases {
+        let formatted = JsonRpcResponseFormatter.format_error(None, &err);
+        assert_eq!(formatted["error"]["code"], json!(code), "{formatted}");
+    }
+}
+
+#[test]
+fn context_frames_do_not_change_the_code() {
+    let err = BamlRtError::Budget("120 of 100 total_tokens".to_string())
+        .with_context(ErrorContext::operation("message.send").with_agent("agent-a"));
+    let formatted = JsonRpcResponseFormatter.format_error(Some(JSONRPCId::Integer(7)), &err);
+
+    assert_eq!(formatted["error"]["code"], json!(response::BUDGET_EXHAUSTED));
+    assert_eq!(formatted["error"]["data"]["errorCode"], json!("BUDGET"));
+    assert_eq!(formatted["error"]["data"]["details"], json!("120 of 100 total_tokens"));
+    assert_eq!(formatted["error"]["data"]["context"][0]["agent"], json!("agent-a"));
+}
@@ -0,0 +1,153 @@
+//! A2A extension negotiation through the agent
+
+use baml_rt_a2a::{A2aAgent, A2aExtension, A2aRequestHandler};
+use baml_rt_a2a::a2a_types::Message;
+use baml_rt_core::{BamlRtError, Result};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+const TRACE_URI: &str = "https://example.com/ext/trace/v1";
+const LOCALE_URI: &str = "https://example.com/ext/locale/v1";
+
+/// Requires a `traceId` in its payload, hands it to the handler, and echoes it
+/// in the response.
+struct TraceExtension;
+
+impl A2aExtension for TraceExtension {
+    fn uri(&self) -> &str {
+        TRACE_URI
+    }
+
+    fn description(&self) -> Option<&str> {
+        Some("Caller trace ids")
+    }
+
+    fn validate(&self, _message: &Message, payload: Option<&Value>) -> Result<()> {
+        match payload.and_then(|payload| payload.get("traceId")).and_then(Value::as_str) {
+            Some(_) => Ok(()),
+            None => Err(BamlRtError::InvalidArgument("trace extension needs a traceId".to_string())),
+        }
+    }
+
+    fn enrich(&self, _message: &Message, payload: Option<&Value>) -> Result<Option<Value>> {
+        Ok(payload.cloned())
+    }
+
+    fn response_metadata(&self, _message: &Message, payload: Option<&Value>) -> Option<Value> {
+        Some(json!({ "echo": payload.and_then(|payload| payload.get("traceId")) }))
+    }
+}
+
+struct LocaleExtension;
+
+impl A2aExtension for LocaleExtension {
+    fn uri(&self) -> &str {
+        LOCALE_URI
+    }
+
+    fn required(&self) -> bool {
+        true
+    }
+
+    fn params(&self) -> Option<Value> {
+        Some(json!({ "locales": ["en", "fr"] }))
+    }
+}
+
+fn echo_js_code() -> &'static str {
+    r#"
+    globalThis.handle_a2a_request = async function(request) {
+        const params = request.params;
+        return {
+            message: { messageId: "resp-1", role: "ROLE_AGENT", parts: [{ text: "ok" }] },
+            seen: {
+                activated: params.activatedExtensions || null,
+                context: params.extensionContext || null
+            }
+        };
+    };
+    "#
+}
+
+async fn agent() -> A2aAgent {
+    A2aAgent::builder()
+        .with_init_js(echo_js_code())
+        .with_extension(Arc::new(TraceExtension))
+        .with_extension(Arc::new(LocaleExtension))
+        .build()
+        .await
+        .expect("agent build")
+}
+
+fn send_request(id: &str, extensions: Value, metadata: Value) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": "message.send",
+        "params": {
+            "message": {
+                "messageId": format!("msg-{id}"),
+                "role": "ROLE_USER",
+                "parts": [{ "text": "hi" }],
+                "extensions": extensions,
+                "metadata": metadata
+            }
+        }
+    })
+}
+
+#[tokio::test]
+async fn card_declares_registered_extensions() {
+    let agent = agent().await;
+    let extensions = &agent.card().extensions;
+    assert_eq!(extensions.len(), 2);
+    assert_eq!(extensions[0].uri, LOCALE_URI);
+    assert!(extensions[0].required);
+    assert_eq!(extensions[0].params, Some(json!({ "locales": ["en", "fr"] })));
+    assert_eq!(extensions[1].uri, TRACE_URI);
+    assert_eq!(extensions[1].description.as_deref(), Some("Caller trace ids"));
+}
+
+#[tokio::test]
+async fn activated_extensions_reach_the_handler_and_the_response() {
+    let agent = agent().await;
+    let request = send_request(
+        "corr-1-1",
+        json!([LOCALE_URI, TRACE_URI, "https://example.com/ext/unknown"]),
+        json!({ TRACE_URI: { "traceId": "abc" } }),
+    );
+    let responses = agent.handle_a2a(request).await.expect("a2a handle");
+    let result = &responses[0]["result"];
+
+    assert_eq!(result["activatedExtensions"], json!([LOCALE_URI, TRACE_URI]));
+    assert_eq!(result["seen"]["activated"], json!([LOCALE_URI, TRACE_URI]));
+    assert_eq!(result["seen"]["context"], json!({ TRACE_URI: { "traceId": "abc" } }));
+    assert_eq!(result["metadata"][TRACE_URI], json!({ "echo": "abc" }));
+}
+
+#[tokio::test]
+async fn missing_required_and_invalid_payloads_are_rejected() {
+    let agent = agent().await;
+    let responses = agent
+        .handle_a2a(send_request("corr-1-2", json!([]), json!({})))
+        .await
+        .expect("a2a handle");
+    assert!(responses[0]["error"].to_string().contains(LOCALE_URI), "{}", responses[0]);
+
+    let responses = agent
+        .handle_a2a(send_request("corr-1-3", json!([LOCALE_URI, TRACE_URI]), json!({})))
+        .await
+        .expect("a2a handle");
+    assert!(responses[0]["error"].to_string().contains("traceId"), "{}", responses[0]);
+}
+
+#[tokio::test]
+async fn duplicate_extension_uris_fail_the_build() {
+    let built = A2aAgent::builder()
+        .with_init_js(echo_js_code())
+        .with_extension(Arc::new(TraceExtension))
+        .with_extension(Arc::new(TraceExtension))
+        .build()
+        .await;
+    assert!(built.is_err());
+}
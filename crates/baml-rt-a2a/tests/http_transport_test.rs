@@ -0,0 +1,208 @@
+use async_trait::async_trait;
+use baml_rt_a2a::chunk_stream::ChunkStream;
+use baml_rt_a2a::{A2aHttpServer, A2aRequestHandler, HEALTH_METHOD};
+use baml_rt_core::{BamlRtError, Result};
+use futures_util::stream::{self, StreamExt};
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::{oneshot, Notify};
+
+/// Echoes unary requests and splits `message.sendStream` into two chunks.
+struct EchoHandler;
+
+#[async_trait(?Send)]
+impl A2aRequestHandler for EchoHandler {
+    async fn handle_a2a(&self, request: Value) -> Result<Vec<Value>> {
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        match request.get("method").and_then(Value::as_str) {
+            Some("message.sendStream") => Ok(vec![
+                json!({"jsonrpc": "2.0", "id": id, "result": {"chunk": 0}}),
+                json!({"jsonrpc": "2.0", "id": id, "result": {"chunk": 1}}),
+            ]),
+            Some("fail") => Err(BamlRtError::InvalidArgument("bad request".to_string())),
+            _ => Ok(vec![json!({"jsonrpc": "2.0", "id": id, "result": request["params"]})]),
+        }
+    }
+}
+
+async fn post(addr: SocketAddr, body: &str) -> (String, String) {
+    let mut stream = TcpStream::connect(addr).await.expect("connect");
+    let request = format!(
+        "POST / HTTP/1.1\r\nHost: {addr}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(request.as_bytes()).await.expect("write request");
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.expect("read response");
+    let (head, body) = response.split_once("\r\n\r\n").expect("http response");
+    (head.to_string(), body.to_string())
+}
+
+fn json_body(body: &str) -> Value {
+    // Responses without a Content-Length arrive chunked; take the JSON line.
+    let line = body.lines().find(|line| line.starts_with('{')).expect("json body");
+    serde_json::from_str(line).expect("valid json")
+}
+
+#[tokio::test]
+async fn http_transport_serves_unary_and_streaming_requests() {
+    let server = A2aHttpServer::bind("127.0.0.1:0".parse().unwrap()).await.expect("bind");
+    let addr = server.local_addr().expect("local addr");
+    let (stop_tx, stop_rx) = oneshot::channel::<()>();
+    let handler = EchoHandler;
+
+    let client = async move {
+        let (head, body) =
+            post(addr, r#"{"jsonrpc":"2.0","id":1,"method":"message.send","params":{"ok":true}}"#).await;
+        assert!(head.contains("application/json"), "{head}");
+        assert_eq!(json_body(&body)["result"], json!({"ok": true}));
+
+        let (head, body) = post(addr, r#"{"jsonrpc":"2.0","id":2,"method":"message.sendStream"}"#).await;
+        assert!(head.contains("text/event-stream"), "{head}");
+        let chunks: Vec<Value> = body
+            .lines()
+            .filter_map(|line| line.strip_prefix("data: "))
+            .map(|data| serde_json::from_str(data).expect("sse json"))
+            .collect();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[1]["result"]["chunk"], json!(1));
+
+        let (_, body) = post(addr, r#"{"jsonrpc":"2.0","id":3,"method":"fail"}"#).await;
+        let error = json_body(&body);
+        assert_eq!(error["id"], json!(3));
+        assert_eq!(error["error"]["code"], json!(-32600));
+
+        let (_, body) = post(addr, "not json").await;
+        assert_eq!(json_body(&body)["error"]["code"], json!(-32700));
+
+        stop_tx.send(()).expect("stop server");
+    };
+    let serve = server.serve(&handler, async {
+        let _ = stop_rx.await;
+    });
+
+    let (served, ()) = tokio::join!(serve, client);
+    served.expect("server stops cleanly");
+}
+
+/// Healthy but not ready, like a runner that is draining.
+struct DrainingHandler;
+
+#[async_trait(?Send)]
+impl A2aRequestHandler for DrainingHandler {
+    async fn handle_a2a(&self, request: Value) -> Result<Vec<Value>> {
+        assert_eq!(request["method"], HEALTH_METHOD);
+        let result = json!({"healthy": true, "ready": false, "agents": []});
+        Ok(vec![json!({"jsonrpc": "2.0", "id": request["id"], "result": result})])
+    }
+}
+
+async fn get(addr: SocketAddr, path: &str) -> (String, String) {
+    let mut stream = TcpStream::connect(addr).await.expect("connect");
+    let request = format!("GET {path} HTTP/1.1\r\nHost: {addr}\r\nConnection: close\r\n\r\n");
+    stream.write_all(request.as_bytes()).await.expect("write request");
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.expect("read response");
+    let (head, body) = response.split_once("\r\n\r\n").expect("http response");
+    (head.to_string(), body.to_string())
+}
+
+#[tokio::test]
+async fn health_probes_answer_from_the_handlers_report() {
+    let server = A2aHttpServer::bind("127.0.0.1:0".parse().unwrap()).await.expect("bind");
+    let addr = server.local_addr().expect("local addr");
+    let (stop_tx, stop_rx) = oneshot::channel::<()>();
+    let handler = DrainingHandler;
+
+    let client = async move {
+        let (head, body) = get(addr, "/health").await;
+        assert!(head.starts_with("HTTP/1.1 200"), "{head}");
+        assert_eq!(json_body(&body)["healthy"], json!(true));
+
+        let (head, body) = get(addr, "/ready").await;
+        assert!(head.starts_with("HTTP/1.1 503"), "{head}");
+        assert_eq!(json_body(&body)["ready"], json!(false));
+
+        stop_tx.send(()).expect("stop server");
+    };
+    let serve = server.serve(&handler, async {
+        let _ = stop_rx.await;
+    });
+
+    let (served, ()) = tokio::join!(serve, client);
+    served.expect("server stops cleanly");
+}
+
+/// Streams one chunk, then waits for the client to have received it before
+/// streaming the next.
+struct PacedHandler {
+    received: Arc<Notify>,
+}
+
+#[async_trait(?Send)]
+impl A2aRequestHandler for PacedHandler {
+    async fn handle_a2a(&self, _request: Value) -> Result<Vec<Value>> {
+        unreachable!("streamed requests go through handle_a2a_stream")
+    }
+
+    fn handle_a2a_stream<'a>(&'a self, request: Value) -> ChunkStream<'a> {
+        let chunk = move |index: u64| {
+            json!({"jsonrpc": "2.0", "id": request["id"], "result": {"chunk": index}})
+        };
+        let first = chunk(0);
+        let received = self.received.clone();
+        stream::once(async { Ok(first) })
+            .chain(stream::once(async move {
+                received.notified().await;
+                Ok(chunk(1))
+            }))
+            .boxed_local()
+    }
+}
+
+#[tokio::test]
+async fn http_transport_writes_stream_chunks_as_they_are_produced() {
+    let server = A2aHttpServer::bind("127.0.0.1:0".parse().unwrap()).await.expect("bind");
+    let addr = server.local_addr().expect("local addr");
+    let (stop_tx, stop_rx) = oneshot::channel::<()>();
+    let received = Arc::new(Notify::new());
+    let handler = PacedHandler { received: received.clone() };
+
+    let client = async move {
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"message.sendStream"}"#;
+        let mut stream = TcpStream::connect(addr).await.expect("connect");
+        let request = format!(
+            "POST / HTTP/1.1\r\nHost: {addr}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        stream.write_all(request.as_bytes()).await.expect("write request");
+
+        let mut response = Vec::new();
+        let mut buffer = [0u8; 1024];
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !String::from_utf8_lossy(&response).contains(r#""chunk":0"#) {
+                let read = stream.read(&mut buffer).await.expect("read response");
+                assert!(read > 0, "the stream ended before its first chunk");
+                response.extend_from_slice(&buffer[..read]);
+            }
+        })
+        .await
+        .expect("the first chunk arrives before the handler produces the second");
+        received.notify_one();
+        stream.read_to_end(&mut response).await.expect("read response");
+        let response = String::from_utf8(response).unwrap();
+        assert!(response.contains(r#""chunk":1"#), "{response}");
+
+        stop_tx.send(()).expect("stop server");
+    };
+    let serve = server.serve(&handler, async {
+        let _ = stop_rx.await;
+    });
+
+    let (served, ()) = tokio::join!(serve, client);
+    served.expect("server stops cleanly");
+}
@@ -0,0 +1,86 @@
+//! Streamed A2A responses from handlers that produce chunks incrementally
+
+use baml_rt_a2a::{A2aAgent, A2aRequestHandler};
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+
+fn generator_js_code() -> &'static str {
+    r#"
+    const reply = (messageId, text) => ({
+        message: { messageId, role: "ROLE_AGENT", parts: [{ text }] }
+    });
+    globalThis.handle_a2a_request = async function*(request) {
+        const text = request.params.message.parts[0].text;
+        yield reply("resp-1", `hello ${text}`);
+        await Promise.resolve();
+        if (text === "fail") {
+            throw new Error("stream broke");
+        }
+        yield reply("resp-2", "done");
+    };
+    "#
+}
+
+fn stream_request(id: &str, text: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": "message.sendStream",
+        "params": {
+            "message": {
+                "messageId": format!("msg-{id}"),
+                "role": "ROLE_USER",
+                "parts": [{ "text": text }]
+            }
+        }
+    })
+}
+
+async fn agent() -> A2aAgent {
+    A2aAgent::builder()
+        .with_init_js(generator_js_code())
+        .build()
+        .await
+        .expect("agent build")
+}
+
+#[tokio::test]
+async fn async_generator_chunks_are_streamed_in_order() {
+    let agent = agent().await;
+    let responses: Vec<Value> = agent
+        .handle_a2a_stream(stream_request("corr-1-1", "Ada"))
+        .map(|response| response.expect("response"))
+        .collect()
+        .await;
+
+    assert_eq!(responses.len(), 2, "{responses:?}");
+    for (index, response) in responses.iter().enumerate() {
+        assert_eq!(response["id"], json!("corr-1-1"));
+        assert_eq!(response["result"]["index"], json!(index));
+        assert_eq!(response["result"]["final"], json!(index == 1));
+    }
+    assert_eq!(
+        responses[0]["result"]["chunk"]["message"]["parts"][0]["text"],
+        json!("hello Ada")
+    );
+
+    // The collected path answers with the same responses.
+    let collected = agent.handle_a2a(stream_request("corr-1-1", "Ada")).await.expect("a2a handle");
+    assert_eq!(collected.len(), 2);
+    assert_eq!(collected[1]["result"]["final"], json!(true));
+}
+
+#[tokio::test]
+async fn chunks_before_a_failure_are_sent_ahead_of_the_error() {
+    let agent = agent().await;
+    let responses: Vec<Value> = agent
+        .handle_a2a_stream(stream_request("corr-1-2", "fail"))
+        .map(|response| response.expect("response"))
+        .collect()
+        .await;
+
+    assert_eq!(responses.len(), 2, "{responses:?}");
+    assert_eq!(responses[0]["result"]["final"], json!(false));
+    assert!(responses[1].get("error").is_some(), "{}", responses[1]);
+    assert!(responses[1].to_string().contains("stream broke"), "{}", responses[1]);
+}
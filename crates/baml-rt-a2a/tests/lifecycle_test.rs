@@ -0,0 +1,119 @@
+use baml_rt::a2a_types::{JSONRPCId, JSONRPCRequest, Message, MessageRole, Part, SendMessageRequest};
+use baml_rt::baml::BamlRuntimeManager;
+use baml_rt::{A2aAgent, A2aRequestHandler};
+use baml_rt_a2a::a2a_types::A2aMessageId;
+use baml_rt_core::ids::{ContextId, ExternalId};
+use serde_json::Value;
+use std::collections::HashMap;
+
+fn hooks_js_code() -> String {
+    r#"
+    globalThis.__lifecycle = [];
+    globalThis.onBoot = async function(payload) {
+        globalThis.__lifecycle.push({ hook: "boot", name: payload.agentName, version: payload.version });
+    };
+    globalThis.onTaskCreated = async function(payload) {
+        globalThis.__lifecycle.push({ hook: "task", taskId: payload.taskId, state: payload.task.status.state });
+    };
+    globalThis.onShutdown = function(payload) {
+        globalThis.__lifecycle.push({ hook: "shutdown", reason: payload.reason });
+    };
+    globalThis.handle_a2a_request = async function(request) {
+        const message = request?.params?.message || {};
+        return {
+            task: {
+                id: `task-${message.messageId}`,
+                contextId: message.contextId,
+                status: { state: "TASK_STATE_WORKING" },
+                history: []
+            }
+        };
+    };
+    "#
+    .to_string()
+}
+
+async fn setup_agent(code: String) -> A2aAgent {
+    A2aAgent::builder()
+        .with_runtime_manager(BamlRuntimeManager::new().unwrap())
+        .with_init_js(code)
+        .build()
+        .await
+        .unwrap()
+}
+
+async fn send_message(agent: &A2aAgent, message_id: &str) {
+    let params = SendMessageRequest {
+        message: Message {
+            message_id: A2aMessageId::incoming(ExternalId::new(message_id)),
+            role: MessageRole::String("ROLE_USER".to_string()),
+            parts: vec![Part {
+                text: Some("hello".to_string()),
+                ..Part::default()
+            }],
+            context_id: Some(ContextId::new(1, 1)),
+            task_id: None,
+            reference_task_ids: Vec::new(),
+            extensions: Vec::new(),
+            metadata: None,
+            extra: HashMap::new(),
+        },
+        configuration: None,
+        metadata: None,
+        tenant: None,
+        extra: HashMap::new(),
+    };
+    let request = JSONRPCRequest {
+        jsonrpc: "2.0".to_string(),
+        method: "message.send".to_string(),
+        params: Some(serde_json::to_value(params).unwrap()),
+        id: Some(JSONRPCId::String(format!("corr-{message_id}"))),
+    };
+    agent
+        .handle_a2a(serde_json::to_value(request).unwrap())
+        .await
+        .unwrap();
+}
+
+async fn recorded_hooks(agent: &A2aAgent) -> Vec<Value> {
+    let result = agent
+        .evaluate_js("return JSON.stringify(globalThis.__lifecycle);")
+        .await
+        .unwrap();
+    result.as_array().cloned().unwrap_or_default()
+}
+
+#[tokio::test]
+async fn lifecycle_hooks_receive_typed_payloads() {
+    let agent = setup_agent(hooks_js_code()).await;
+    let lifecycle = agent.lifecycle();
+
+    assert!(lifecycle.on_boot("ritual-agent", "1.2.0").await.unwrap());
+    send_message(&agent, "vox-1").await;
+    // Re-sending the same task id updates the task without creating it again.
+    send_message(&agent, "vox-1").await;
+    assert!(lifecycle.on_shutdown("test_complete").await.unwrap());
+
+    let hooks = recorded_hooks(&agent).await;
+    assert_eq!(
+        hooks,
+        vec![
+            serde_json::json!({ "hook": "boot", "name": "ritual-agent", "version": "1.2.0" }),
+            serde_json::json!({ "hook": "task", "taskId": "task-vox-1", "state": "TASK_STATE_WORKING" }),
+            serde_json::json!({ "hook": "shutdown", "reason": "test_complete" }),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn missing_hooks_are_skipped_and_failing_hooks_error() {
+    let agent = setup_agent(
+        r#"globalThis.onBoot = function() { throw new Error("boot failed"); };"#.to_string(),
+    )
+    .await;
+    let lifecycle = agent.lifecycle();
+
+    let err = lifecycle.on_boot("ritual-agent", "1.2.0").await.unwrap_err();
+    assert!(err.to_string().contains("boot failed"), "unexpected error: {err}");
+    assert!(!lifecycle.on_shutdown("test_complete").await.unwrap());
+}
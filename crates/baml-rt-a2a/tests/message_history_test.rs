@@ -0,0 +1,139 @@
+//! Conversation history through `message.list` and `contexts.list`.
+
+use baml_rt_a2a::a2a::{A2aMethod, A2aRequest};
+use baml_rt_a2a::a2a_store::{
+    message_tenant, ContextRepository, TaskRepository, TaskStore, MAX_PAGE_SIZE,
+};
+use baml_rt_a2a::handlers::{ContextHandler, DefaultContextHandler};
+use baml_rt_a2a::a2a_types::{
+    A2aMessageId, ListContextsRequest, ListMessagesRequest, Message, MessageRole, Part, ROLE_USER,
+};
+use baml_rt_core::context::{self, RuntimeScope};
+use baml_rt_core::ids::{AgentId, ContextId, ExternalId, MessageId, UuidId};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+fn message(id: &str, context_id: &ContextId) -> Message {
+    Message {
+        message_id: A2aMessageId::incoming(ExternalId::new(id)),
+        role: MessageRole::String(ROLE_USER.to_string()),
+        parts: vec![Part { text: Some(id.to_string()), ..Part::default() }],
+        context_id: Some(context_id.clone()),
+        task_id: None,
+        reference_task_ids: Vec::new(),
+        extensions: Vec::new(),
+        metadata: None,
+        extra: HashMap::new(),
+    }
+}
+
+fn texts(messages: &[Message]) -> Vec<&str> {
+    messages.iter().filter_map(|message| message.parts[0].text.as_deref()).collect()
+}
+
+fn list_messages(context_id: &ContextId, mut params: Value) -> ListMessagesRequest {
+    params["contextId"] = json!(context_id);
+    serde_json::from_value(params).expect("list messages request")
+}
+
+#[tokio::test]
+async fn history_is_listed_per_context_and_paged() {
+    let store = Mutex::new(TaskStore::new());
+    let first = ContextId::new(1, 1);
+    let second = ContextId::new(1, 2);
+    for id in ["m1", "m2", "m3"] {
+        store.insert_message(&message(id, &first)).await.unwrap();
+    }
+    store.insert_message(&message("other", &second)).await.unwrap();
+
+    let page = store.list_messages(&list_messages(&first, json!({"pageSize": 2}))).await.unwrap();
+    assert_eq!(texts(&page.messages), vec!["m1", "m2"]);
+    assert_eq!(page.total_size, Some(3));
+    let token = page.next_page_token.expect("a second page");
+    let rest = list_messages(&first, json!({"pageSize": 2, "pageToken": token}));
+    let rest = store.list_messages(&rest).await.unwrap();
+    assert_eq!(texts(&rest.messages), vec!["m3"]);
+    assert!(rest.next_page_token.is_none());
+
+    let recent = list_messages(&first, json!({"historyLength": 2}));
+    let recent = store.list_messages(&recent).await.unwrap();
+    assert_eq!(texts(&recent.messages), vec!["m2", "m3"], "most recent messages, oldest first");
+
+    let at = MessageId::from_external(ExternalId::new("m2"));
+    let fork = store.fork_context(&first, &at, ContextId::new(1, 3)).await.expect("fork");
+    let request: ListContextsRequest =
+        serde_json::from_value(json!({"historyLength": "1"})).expect("list contexts request");
+    let listed = store.list_contexts(&request).await;
+    let ids: Vec<_> = listed.contexts.iter().map(|summary| summary.context_id.clone()).collect();
+    assert_eq!(ids, vec![first.clone(), second, fork.context_id.clone()], "in the order started");
+    assert_eq!(listed.contexts[0].message_count, 3);
+    assert_eq!(texts(&listed.contexts[0].history), vec!["m3"]);
+    assert_eq!(listed.contexts[2].parent_context_id.as_ref(), Some(&first));
+    assert_eq!(texts(&listed.contexts[2].history), vec!["m2"]);
+}
+
+#[tokio::test]
+async fn page_sizes_are_clamped_and_unknown_page_tokens_refused() {
+    let store = Arc::new(Mutex::new(TaskStore::new()));
+    let context_id = ContextId::new(1, 1);
+    for id in ["m1", "m2"] {
+        store.insert_message(&message(id, &context_id)).await.unwrap();
+    }
+
+    let empty = store.list_messages(&list_messages(&context_id, json!({"pageSize": 0}))).await;
+    let empty = empty.unwrap();
+    assert_eq!(texts(&empty.messages), vec!["m1"], "a page holds at least one item");
+    let huge = list_messages(&context_id, json!({"pageSize": MAX_PAGE_SIZE as u64 + 1}));
+    assert_eq!(store.list_messages(&huge).await.unwrap().page_size, Some(MAX_PAGE_SIZE as u64));
+
+    let handler = DefaultContextHandler::new(store);
+    let forged = list_messages(&context_id, json!({"pageToken": "../../etc"}));
+    let err = handler.handle_list_messages(forged).await.expect_err("forged page token");
+    assert!(err.to_string().contains("Invalid pageToken"), "{err}");
+}
+
+#[tokio::test]
+async fn tenants_only_see_their_own_contexts() {
+    let store = Mutex::new(TaskStore::new());
+    let shared = ContextId::new(2, 1);
+    let acme_context = ContextId::new(2, 2);
+    store.insert_message(&message("untenanted", &shared)).await.unwrap();
+    let agent = AgentId::from_uuid(UuidId::new(uuid::Uuid::new_v4()));
+    let scope = RuntimeScope::new(acme_context.clone(), agent, None, None)
+        .with_tenant(Some("acme".to_string()));
+    let insert = store.insert_message(&message("acme", &acme_context));
+    context::with_scope(scope, insert).await.unwrap();
+
+    let mut request = list_messages(&acme_context, json!({}));
+    assert!(store.list_messages(&request).await.is_none(), "reported as missing");
+    request.tenant = Some("acme".to_string());
+    let listed = store.list_messages(&request).await.expect("acme's context");
+    assert_eq!(message_tenant(&listed.messages[0]), Some("acme"));
+
+    let listed = store.list_contexts(&ListContextsRequest::default()).await;
+    assert_eq!(listed.contexts.len(), 1);
+    assert_eq!(listed.contexts[0].context_id, shared);
+}
+
+#[test]
+fn list_methods_parse_with_either_separator() {
+    let request = A2aRequest::from_value(json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "message/list",
+        "params": {"contextId": ContextId::new(1, 1), "historyLength": 10},
+    }))
+    .expect("parse message/list");
+    assert_eq!(request.method, A2aMethod::MessageList);
+    assert_eq!(request.context_id, Some(ContextId::new(1, 1)));
+
+    let request = A2aRequest::from_value(json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "contexts.list",
+    }))
+    .expect("parse contexts.list");
+    assert_eq!(request.method, A2aMethod::ContextsList);
+}
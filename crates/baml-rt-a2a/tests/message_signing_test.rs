@@ -0,0 +1,165 @@
+//! Signing outgoing A2A messages and verifying incoming ones
+
+use baml_rt_a2a::{A2aAgent, A2aRequestHandler, MessageSigner, MessageVerifier, SignatureStatus};
+use baml_rt_provenance::{InMemoryProvenanceStore, ProvEventData};
+use ed25519_dalek::SigningKey;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn signer(key_id: &str, seed: u8) -> MessageSigner {
+    MessageSigner::new(key_id, SigningKey::from_bytes(&[seed; 32]))
+}
+
+fn echo_js_code() -> &'static str {
+    r#"
+    globalThis.handle_a2a_request = async function(request) {
+        const metadata = request.params.message.metadata || {};
+        return {
+            message: {
+                messageId: "resp-1",
+                role: "ROLE_AGENT",
+                parts: [{ text: `status ${metadata.signature_status}` }]
+            }
+        };
+    };
+    "#
+}
+
+fn user_message(id: &str) -> Value {
+    json!({
+        "messageId": format!("msg-{id}"),
+        "role": "ROLE_USER",
+        "parts": [{ "text": "hello" }],
+        "metadata": { "topic": "greeting" }
+    })
+}
+
+fn send_request(id: &str, message: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": "message.send", "params": { "message": message } })
+}
+
+async fn verifying_agent(verifier: MessageVerifier, writer: Arc<InMemoryProvenanceStore>) -> A2aAgent {
+    A2aAgent::builder()
+        .with_init_js(echo_js_code())
+        .with_provenance_writer(writer)
+        .with_message_verifier(Arc::new(verifier))
+        .build()
+        .await
+        .expect("agent build")
+}
+
+fn reply_text(responses: &[Value]) -> &str {
+    responses[0]["result"]["message"]["parts"][0]["text"].as_str().unwrap_or_else(|| panic!("{}", responses[0]))
+}
+
+#[test]
+fn signatures_cover_the_message_content() {
+    let signer = signer("agent-a", 1);
+    let verifier = MessageVerifier::new().with_peer_key("agent-a", signer.verifying_key());
+
+    let mut message = user_message("1");
+    signer.sign_value(&mut message).unwrap();
+    assert_eq!(message["metadata"]["signature"]["keyId"], json!("agent-a"));
+    assert_eq!(verifier.verify_value(&message), SignatureStatus::Verified { key_id: "agent-a".to_string() });
+
+    let mut tampered = message.clone();
+    tampered["parts"][0]["text"] = json!("goodbye");
+    assert!(matches!(verifier.verify_value(&tampered), SignatureStatus::Invalid { .. }));
+
+    let other = signer_status(&signer("agent-b", 2), &verifier);
+    assert_eq!(other, SignatureStatus::UnknownKey { key_id: "agent-b".to_string() });
+    assert_eq!(verifier.verify_value(&user_message("2")), SignatureStatus::Unsigned);
+}
+
+#[test]
+fn stale_and_replayed_signatures_are_refused() {
+    let signer = signer("agent-a", 1);
+    let verifier = MessageVerifier::new().with_peer_key("agent-a", signer.verifying_key());
+    let mut message = user_message("1");
+    signer.sign_value(&mut message).unwrap();
+
+    let mut backdated = message.clone();
+    backdated["metadata"]["signature"]["signedAt"] = json!(0);
+    assert!(matches!(verifier.verify_value(&backdated), SignatureStatus::Invalid { .. }));
+
+    let strict = verifier.clone().with_max_age(Duration::ZERO);
+    std::thread::sleep(Duration::from_millis(5));
+    let stale = strict.verify_value(&message);
+    assert_eq!(stale, SignatureStatus::Invalid { reason: "signature is stale".to_string() });
+
+    assert!(verifier.check(&message).is_ok());
+    let replayed = verifier.check(&message).expect_err("replayed nonce");
+    assert!(replayed.to_string().contains("already used"), "{replayed}");
+}
+
+fn signer_status(signer: &MessageSigner, verifier: &MessageVerifier) -> SignatureStatus {
+    let mut message = user_message("3");
+    signer.sign_value(&mut message).unwrap();
+    verifier.verify_value(&message)
+}
+
+#[tokio::test]
+async fn verified_status_reaches_the_handler_and_provenance() {
+    let sender = signer("agent-a", 1);
+    let writer = Arc::new(InMemoryProvenanceStore::new());
+    let agent = verifying_agent(
+        MessageVerifier::new().with_peer_key("agent-a", sender.verifying_key()),
+        writer.clone(),
+    )
+    .await;
+
+    let mut message = user_message("4");
+    sender.sign_value(&mut message).unwrap();
+    let responses = agent.handle_a2a(send_request("corr-1-1", message)).await.expect("a2a handle");
+    assert_eq!(reply_text(&responses), "status verified");
+
+    let events = writer.events().await;
+    let metadata = events
+        .iter()
+        .find_map(|event| match event.data() {
+            ProvEventData::MessageReceived { metadata, .. } => metadata.clone(),
+            _ => None,
+        })
+        .expect("received message metadata");
+    assert_eq!(metadata.get("signature_status").map(String::as_str), Some("verified"));
+    assert_eq!(metadata.get("signature_key_id").map(String::as_str), Some("agent-a"));
+}
+
+#[tokio::test]
+async fn tampered_and_unsigned_messages_are_refused_by_policy() {
+    let sender = signer("agent-a", 1);
+    let verifier = MessageVerifier::new().with_peer_key("agent-a", sender.verifying_key());
+    let lenient = verifying_agent(verifier.clone(), Arc::new(InMemoryProvenanceStore::new())).await;
+    let strict = verifying_agent(verifier.with_require_verified(true), Arc::new(InMemoryProvenanceStore::new())).await;
+
+    // Spoofed status values are replaced by the real outcome.
+    let mut unsigned = user_message("5");
+    unsigned["metadata"]["signature_status"] = json!("verified");
+    let responses = lenient.handle_a2a(send_request("corr-1-2", unsigned.clone())).await.unwrap();
+    assert_eq!(reply_text(&responses), "status unsigned");
+    let responses = strict.handle_a2a(send_request("corr-1-3", unsigned)).await.unwrap();
+    assert!(responses[0]["error"].to_string().contains("unsigned"), "{}", responses[0]);
+
+    let mut tampered = user_message("6");
+    sender.sign_value(&mut tampered).unwrap();
+    tampered["metadata"]["topic"] = json!("other");
+    let responses = lenient.handle_a2a(send_request("corr-1-4", tampered)).await.unwrap();
+    assert!(responses[0]["error"].to_string().contains("does not match"), "{}", responses[0]);
+}
+
+#[tokio::test]
+async fn replies_are_signed_with_the_agent_key() {
+    let agent_key = Arc::new(signer("agent-b", 3));
+    let agent = A2aAgent::builder()
+        .with_init_js(echo_js_code())
+        .with_message_signer(agent_key.clone())
+        .build()
+        .await
+        .expect("agent build");
+
+    let responses = agent.handle_a2a(send_request("corr-1-5", user_message("7"))).await.unwrap();
+    let reply = &responses[0]["result"]["message"];
+    let verifier = MessageVerifier::new().with_peer_key("agent-b", agent_key.verifying_key());
+    assert_eq!(verifier.verify_value(reply), SignatureStatus::Verified { key_id: "agent-b".to_string() });
+}
@@ -0,0 +1,270 @@
+//! Task updates POSTed to the webhooks clients register.
+
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::Router;
+use baml_rt_a2a::a2a::{A2aMethod, A2aRequest};
+use baml_rt_a2a::a2a_store::TaskUpdateEvent;
+use baml_rt_a2a::a2a_types::{PushNotificationConfig, TaskState, TaskStatus, TaskStatusUpdateEvent};
+use baml_rt_a2a::push_notifications::{
+    sign_payload, verify_signature, DEFAULT_SIGNATURE_TOLERANCE, NOTIFICATION_TOKEN_HEADER,
+    SIGNATURE_HEADER, TIMESTAMP_HEADER,
+};
+use baml_rt_a2a::{PushNotificationRegistry, WebhookConfig, WebhookDispatcher};
+use baml_rt_core::ids::{ExternalId, TaskId};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::net::TcpListener;
+use tokio::sync::broadcast;
+
+/// Refuses the first notification with a 503, then keeps what it is sent.
+#[derive(Default)]
+struct Webhook {
+    attempts: AtomicUsize,
+    received: Mutex<Vec<(HeaderMap, Bytes)>>,
+}
+
+async fn receive(
+    State(webhook): State<Arc<Webhook>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> StatusCode {
+    if webhook.attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+        return StatusCode::SERVICE_UNAVAILABLE;
+    }
+    webhook.received.lock().unwrap().push((headers, body));
+    StatusCode::OK
+}
+
+async fn serve(webhook: Arc<Webhook>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("local addr");
+    let app = Router::new().route("/hook", post(receive)).with_state(webhook);
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    format!("http://{addr}/hook")
+}
+
+fn status_update(task_id: &str, state: &str) -> TaskUpdateEvent {
+    TaskUpdateEvent::Status(TaskStatusUpdateEvent {
+        task_id: Some(TaskId::from_external(ExternalId::new(task_id))),
+        status: Some(TaskStatus {
+            state: Some(TaskState::String(state.to_string())),
+            ..TaskStatus::default()
+        }),
+        ..TaskStatusUpdateEvent::default()
+    })
+}
+
+fn hook(url: &str, token: Option<&str>) -> PushNotificationConfig {
+    PushNotificationConfig {
+        id: None,
+        url: url.to_string(),
+        token: token.map(str::to_string),
+        extra: HashMap::new(),
+    }
+}
+
+#[tokio::test]
+async fn updates_are_signed_retried_and_delivered_in_order() {
+    let webhook = Arc::new(Webhook::default());
+    let url = serve(webhook.clone()).await;
+    let registry = PushNotificationRegistry::new()
+        .with_private_hosts(HashSet::from(["127.0.0.1".to_string()]));
+    let stored = registry.set("task-1", hook(&url, Some("client-token"))).await.expect("set");
+    assert!(stored.id.is_some(), "configs without an id get one");
+
+    let (updates, _) = broadcast::channel(16);
+    let _dispatcher = WebhookDispatcher::spawn(
+        updates.subscribe(),
+        registry.clone(),
+        WebhookConfig::default()
+            .with_private_host("127.0.0.1")
+            .with_signing_secret("s3cret")
+            .with_initial_backoff(Duration::from_millis(10)),
+    );
+    updates.send(status_update("task-1", "TASK_STATE_WORKING")).unwrap();
+    updates.send(status_update("task-2", "TASK_STATE_WORKING")).unwrap();
+    updates.send(status_update("task-1", "TASK_STATE_COMPLETED")).unwrap();
+
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while webhook.received.lock().unwrap().len() < 2 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("both task-1 updates delivered");
+
+    let received = webhook.received.lock().unwrap().clone();
+    assert_eq!(received.len(), 2, "task-2 has no webhook");
+    assert_eq!(webhook.attempts.load(Ordering::SeqCst), 3, "the refused update was retried");
+    let states: Vec<_> = received
+        .iter()
+        .map(|(_, body)| {
+            let body: Value = serde_json::from_slice(body).unwrap();
+            body["statusUpdate"]["status"]["state"].clone()
+        })
+        .collect();
+    assert_eq!(states, vec![json!("TASK_STATE_WORKING"), json!("TASK_STATE_COMPLETED")]);
+    let tolerance = DEFAULT_SIGNATURE_TOLERANCE;
+    for (headers, body) in &received {
+        assert_eq!(headers[NOTIFICATION_TOKEN_HEADER], "client-token");
+        let signature = headers[SIGNATURE_HEADER].to_str().unwrap();
+        let timestamp = headers[TIMESTAMP_HEADER].to_str().unwrap();
+        assert!(verify_signature(b"s3cret", timestamp, body, signature, tolerance), "{signature}");
+        assert!(!verify_signature(b"other", timestamp, body, signature, tolerance));
+        let replayed = verify_signature(b"s3cret", "0", body, signature, tolerance);
+        assert!(!replayed, "the timestamp is signed");
+    }
+    assert!(registry.get("task-1").is_empty(), "configs go once the task is done");
+}
+
+#[tokio::test]
+async fn configs_need_an_http_url_and_replace_by_id() {
+    let registry = PushNotificationRegistry::new();
+    assert!(registry.set("task-1", hook("ftp://93.184.216.34/hook", None)).await.is_err());
+    assert!(registry.set("task-1", hook("not a url", None)).await.is_err());
+
+    let mut config = hook("https://93.184.216.34/a", None);
+    config.id = Some("main".to_string());
+    registry.set("task-1", config.clone()).await.expect("set");
+    config.url = "https://93.184.216.34/b".to_string();
+    registry.set("task-1", config).await.expect("replace");
+    let configs = registry.get("task-1");
+    assert_eq!(configs.len(), 1);
+    assert_eq!(configs[0].url, "https://93.184.216.34/b");
+
+    let request = A2aRequest::from_value(json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "tasks/pushNotificationConfig/set",
+        "params": {
+            "taskId": "task-1",
+            "pushNotificationConfig": {"url": "https://example.com/a"},
+        },
+    }))
+    .expect("parse request");
+    assert_eq!(request.method, A2aMethod::TasksSetPushNotificationConfig);
+    assert_eq!(request.task_id.as_ref().map(|id| id.as_str()), Some("task-1"));
+}
+
+#[tokio::test]
+async fn webhooks_on_internal_addresses_are_refused_unless_allowed() {
+    let registry = PushNotificationRegistry::new();
+    for url in [
+        "http://127.0.0.1:8080/hook",
+        "http://localhost/hook",
+        "http://10.1.2.3/hook",
+        "http://192.168.0.10/hook",
+        "http://169.254.169.254/latest/meta-data",
+        "http://100.64.0.1/hook",
+        "http://[::1]/hook",
+        "http://[fd00::1]/hook",
+        "http://[::ffff:127.0.0.1]/hook",
+    ] {
+        let err = registry.set("task-1", hook(url, None)).await.expect_err(url);
+        assert!(err.to_string().contains("Invalid push notification url"), "{url}: {err}");
+    }
+
+    let allowed = PushNotificationRegistry::new()
+        .with_private_hosts(HashSet::from(["10.1.2.3".to_string(), "::1".to_string()]));
+    allowed.set("task-1", hook("http://10.1.2.3/hook", None)).await.expect("allowlisted");
+    allowed.set("task-1", hook("http://[::1]/hook", None)).await.expect("allowlisted");
+    assert!(allowed.set("task-1", hook("http://10.1.2.4/hook", None)).await.is_err());
+}
+
+async fn refused(url: &str) -> bool {
+    let registry = PushNotificationRegistry::new();
+    registry.set("task-1", hook(url, None)).await.is_err()
+}
+
+#[tokio::test]
+async fn this_network_addresses_are_refused() {
+    assert!(refused("http://0.0.0.0/hook").await);
+    assert!(refused("http://0.1.2.3/hook").await);
+    assert!(!refused("http://1.0.0.1/hook").await);
+}
+
+#[tokio::test]
+async fn multicast_addresses_are_refused() {
+    assert!(refused("http://224.0.0.1/hook").await);
+    assert!(refused("http://239.255.255.250/hook").await);
+    assert!(refused("http://[ff02::1]/hook").await);
+    assert!(refused("http://[::ffff:224.0.0.1]/hook").await);
+}
+
+#[tokio::test]
+async fn benchmarking_addresses_are_refused() {
+    assert!(refused("http://198.18.0.1/hook").await);
+    assert!(refused("http://198.19.255.255/hook").await);
+    assert!(!refused("http://198.20.0.1/hook").await);
+    assert!(!refused("http://198.17.255.255/hook").await);
+}
+
+#[tokio::test]
+async fn nat64_addresses_are_refused() {
+    assert!(refused("http://[64:ff9b::a9fe:a9fe]/hook").await);
+    assert!(refused("http://[64:ff9b::7f00:1]/hook").await);
+    assert!(!refused("http://[64:ff9b:1::a9fe:a9fe]/hook").await);
+}
+
+#[tokio::test]
+async fn redirects_are_not_followed() {
+    let target = Arc::new(Webhook::default());
+    let target_url = serve(target.clone()).await;
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("local addr");
+    let redirects = Arc::new(AtomicUsize::new(0));
+    let counted = redirects.clone();
+    let app = Router::new().route(
+        "/hook",
+        post(move || {
+            counted.fetch_add(1, Ordering::SeqCst);
+            let location = target_url.clone();
+            async move { (StatusCode::TEMPORARY_REDIRECT, [(header::LOCATION, location)]) }
+        }),
+    );
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    let registry = PushNotificationRegistry::new()
+        .with_private_hosts(HashSet::from(["127.0.0.1".to_string()]));
+    registry.set("task-1", hook(&format!("http://{addr}/hook"), None)).await.expect("set");
+    let (updates, _) = broadcast::channel(16);
+    let _dispatcher = WebhookDispatcher::spawn(
+        updates.subscribe(),
+        registry,
+        WebhookConfig::default().with_private_host("127.0.0.1"),
+    );
+    updates.send(status_update("task-1", "TASK_STATE_COMPLETED")).unwrap();
+
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while redirects.load(Ordering::SeqCst) == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("the webhook was called");
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(redirects.load(Ordering::SeqCst), 1, "a redirect is a final response");
+    assert_eq!(target.attempts.load(Ordering::SeqCst), 0, "the redirect was not followed");
+}
+
+#[test]
+fn stale_signatures_are_rejected() {
+    let body = br#"{"statusUpdate":{}}"#;
+    let sent_at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let fresh = sent_at.to_string();
+    let stale = (sent_at - 3600).to_string();
+    let tolerance = DEFAULT_SIGNATURE_TOLERANCE;
+
+    let signature = sign_payload(b"s3cret", &fresh, body);
+    assert!(verify_signature(b"s3cret", &fresh, body, &signature, tolerance));
+    let signature = sign_payload(b"s3cret", &stale, body);
+    assert!(!verify_signature(b"s3cret", &stale, body, &signature, tolerance));
+    assert!(verify_signature(b"s3cret", &stale, body, &signature, Duration::from_secs(7200)));
+    assert!(!verify_signature(b"s3cret", "soon", body, &signature, tolerance));
+}
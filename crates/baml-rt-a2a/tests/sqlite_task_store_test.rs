@@ -0,0 +1,109 @@
+use baml_rt_a2a::a2a_store::{ContextRepository, TaskEventRecorder, TaskRepository};
+use baml_rt_a2a::a2a_types::{
+    A2aMessageId, ListTasksRequest, Message, MessageRole, Part, Task, TaskState, TaskStatus, ROLE_USER,
+};
+use baml_rt_a2a::SqliteTaskStore;
+use baml_rt_core::ids::{AgentId, ContextId, ExternalId, MessageId, TaskId, UuidId};
+use serde_json::json;
+use std::collections::HashMap;
+
+fn status(state: &str) -> TaskStatus {
+    TaskStatus { state: Some(TaskState::String(state.to_string())), ..TaskStatus::default() }
+}
+
+fn task(id: &str) -> Task {
+    Task {
+        id: Some(TaskId::from_external(ExternalId::new(id))),
+        context_id: Some(ContextId::new(1, 1)),
+        artifacts: Vec::new(),
+        history: Vec::new(),
+        status: Some(status("TASK_STATE_WORKING")),
+        metadata: None,
+        extra: HashMap::new(),
+    }
+}
+
+fn user_message(message_id: &str, task_id: &str) -> Message {
+    Message {
+        message_id: A2aMessageId::incoming(ExternalId::new(message_id)),
+        role: MessageRole::String(ROLE_USER.to_string()),
+        parts: vec![Part { text: Some("hello".to_string()), ..Part::default() }],
+        context_id: Some(ContextId::new(1, 1)),
+        task_id: Some(TaskId::from_external(ExternalId::new(task_id))),
+        reference_task_ids: Vec::new(),
+        extensions: Vec::new(),
+        metadata: None,
+        extra: HashMap::new(),
+    }
+}
+
+fn agent_id() -> AgentId {
+    AgentId::from_uuid(UuidId::new(uuid::Uuid::new_v4()))
+}
+
+fn state_of(task: &Task) -> Option<&TaskState> {
+    task.status.as_ref().and_then(|status| status.state.as_ref())
+}
+
+#[tokio::test]
+async fn tasks_survive_reopening_the_store() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("tasks.db");
+
+    {
+        let store = SqliteTaskStore::open(&path, None, agent_id()).expect("open store");
+        store.upsert(task("task-1")).await.expect("task stored");
+        store.upsert(task("task-2")).await.expect("task stored");
+        store.insert_message(&user_message("msg-1", "task-1")).await.unwrap();
+        store
+            .record_status_update(
+                Some(TaskId::from_external(ExternalId::new("task-1"))),
+                Some(ContextId::new(1, 1)),
+                status("TASK_STATE_COMPLETED"),
+            )
+            .await
+            .expect("status recorded");
+    }
+
+    let reopened = SqliteTaskStore::open(&path, None, agent_id()).expect("reopen store");
+    let restored = reopened.get("task-1", None).await.expect("task-1 restored");
+    assert_eq!(state_of(&restored), Some(&TaskState::String("TASK_STATE_COMPLETED".to_string())));
+    assert_eq!(restored.history.len(), 1);
+
+    let request: ListTasksRequest = serde_json::from_value(json!({})).expect("list request");
+    let listed = reopened.list(&request).await;
+    let ids: Vec<_> = listed
+        .tasks
+        .iter()
+        .filter_map(|task| task.id.as_ref().map(|id| id.as_str().to_string()))
+        .collect();
+    assert_eq!(ids, vec!["task-1", "task-2"], "creation order is kept");
+
+    let history = reopened.context_history(ContextId::new(1, 1).as_str(), None).await;
+    assert_eq!(history.len(), 1);
+}
+
+#[tokio::test]
+async fn forked_contexts_survive_reopening_the_store() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("tasks.db");
+    let source = ContextId::new(1, 1);
+    let target = ContextId::new(1, 2);
+
+    {
+        let store = SqliteTaskStore::open(&path, None, agent_id()).expect("open store");
+        store.upsert(task("task-1")).await.expect("task stored");
+        store.insert_message(&user_message("msg-1", "task-1")).await.unwrap();
+        store.insert_message(&user_message("msg-2", "task-1")).await.unwrap();
+        store
+            .fork_context(&source, &MessageId::from_external(ExternalId::new("msg-1")), target.clone())
+            .await
+            .expect("fork");
+    }
+
+    let reopened = SqliteTaskStore::open(&path, None, agent_id()).expect("reopen store");
+    let branch = reopened.context_branch(target.as_str()).await.expect("branch restored");
+    assert_eq!(branch.parent_context_id, source);
+    assert_eq!(reopened.context_history(target.as_str(), None).await.len(), 1);
+    assert_eq!(reopened.context_history(source.as_str(), None).await.len(), 2);
+}
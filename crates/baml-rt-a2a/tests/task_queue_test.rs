@@ -0,0 +1,114 @@
+//! Per-agent limits on concurrent task executions.
+
+use baml_rt::baml::BamlRuntimeManager;
+use baml_rt::{A2aAgent, A2aRequestHandler};
+use baml_rt_a2a::a2a_store::TaskRepository;
+use baml_rt_a2a::{TaskLifecycleState, TaskQueue};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+const HANDLER_JS: &str = r#"
+globalThis.handle_a2a_request = async function(request) {
+    const message = request?.params?.message || {};
+    return {
+        task: {
+            id: message.taskId,
+            contextId: message.contextId,
+            status: { state: "TASK_STATE_WORKING" },
+            history: []
+        }
+    };
+};
+"#;
+
+fn send(task_id: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": task_id,
+        "method": "message.send",
+        "params": {
+            "message": {
+                "messageId": format!("msg-{task_id}"),
+                "role": "ROLE_USER",
+                "taskId": task_id,
+                "parts": [{"text": "hello"}],
+            },
+        },
+    })
+}
+
+async fn task_state(agent: &A2aAgent, task_id: &str) -> Option<TaskLifecycleState> {
+    let task = agent.task_store().get(task_id, Some(0)).await?;
+    task.status.as_ref().and_then(TaskLifecycleState::of)
+}
+
+#[tokio::test]
+async fn queued_messages_wait_as_submitted_tasks() {
+    let agent = A2aAgent::builder()
+        .with_runtime_manager(BamlRuntimeManager::new().unwrap())
+        .with_init_js(HANDLER_JS)
+        .with_max_concurrent_tasks(1)
+        .build()
+        .await
+        .unwrap();
+    let queue = agent.task_queue().expect("a limited agent has a queue").clone();
+    assert_eq!(queue.max_concurrent(), 1);
+
+    // Another execution holds the only slot.
+    let running = queue.try_start().expect("a free slot");
+    let queued = tokio::spawn({
+        let agent = agent.clone();
+        async move { agent.handle_a2a(send("task-queued")).await }
+    });
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while queue.waiting() == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("the message is queued");
+    assert_eq!(
+        task_state(&agent, "task-queued").await,
+        Some(TaskLifecycleState::Submitted)
+    );
+
+    drop(running);
+    let responses = queued.await.unwrap().expect("message handled");
+    assert!(responses[0].get("result").is_some(), "{responses:?}");
+    assert_eq!(task_state(&agent, "task-queued").await, Some(TaskLifecycleState::Working));
+    assert_eq!((queue.waiting(), queue.running()), (0, 0));
+}
+
+#[tokio::test]
+async fn slots_go_to_waiting_executions_in_order() {
+    let queue = TaskQueue::new("agent", 0);
+    assert_eq!(queue.max_concurrent(), 1, "at least one execution runs");
+
+    let first = queue.try_start().expect("a free slot");
+    assert!(queue.try_start().is_none());
+    let waiter = tokio::spawn({
+        let queue = queue.clone();
+        async move { queue.start().await }
+    });
+    while queue.waiting() == 0 {
+        tokio::task::yield_now().await;
+    }
+    drop(first);
+    let second = waiter.await.unwrap();
+    assert_eq!((queue.waiting(), queue.running()), (0, 1));
+    assert!(queue.try_start().is_none(), "the waiter took the freed slot");
+
+    // Dropping a waiting execution takes it out of the queue.
+    let abandoned = tokio::spawn({
+        let queue = queue.clone();
+        async move { queue.start().await }
+    });
+    while queue.waiting() == 0 {
+        tokio::task::yield_now().await;
+    }
+    abandoned.abort();
+    let _ = abandoned.await;
+    assert_eq!(queue.waiting(), 0);
+    drop(second);
+    assert_eq!(queue.running(), 0);
+}
@@ -0,0 +1,106 @@
+use baml_rt_a2a::a2a_store::{ProvenanceTaskStore, TaskEventRecorder, TaskRepository};
+use baml_rt_a2a::a2a_types::{Task, TaskState, TaskStatus};
+use baml_rt_a2a::TransitionPolicy;
+use baml_rt_core::ids::{AgentId, ContextId, ExternalId, TaskId, UuidId};
+use baml_rt_provenance::{InMemoryProvenanceStore, ProvEventData, ProvenanceWriter};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+fn status(state: &str) -> TaskStatus {
+    TaskStatus { state: Some(TaskState::String(state.to_string())), ..TaskStatus::default() }
+}
+
+fn task_id() -> TaskId {
+    TaskId::from_external(ExternalId::new("task-vox-1"))
+}
+
+fn context_id() -> Option<ContextId> {
+    Some(ContextId::new(1, 1))
+}
+
+async fn store_with(policy: TransitionPolicy) -> (ProvenanceTaskStore, Arc<InMemoryProvenanceStore>) {
+    let provenance = Arc::new(InMemoryProvenanceStore::new());
+    let writer: Arc<dyn ProvenanceWriter> = provenance.clone();
+    let agent_id = AgentId::from_uuid(UuidId::new(uuid::Uuid::new_v4()));
+    let store = ProvenanceTaskStore::new(Some(writer), agent_id).with_transition_policy(policy);
+    store
+        .upsert(Task {
+            id: Some(task_id()),
+            context_id: context_id(),
+            artifacts: Vec::new(),
+            history: Vec::new(),
+            status: Some(status("TASK_STATE_WORKING")),
+            metadata: None,
+            extra: HashMap::new(),
+        })
+        .await
+        .expect("task stored");
+    (store, provenance)
+}
+
+async fn illegal_transitions(provenance: &InMemoryProvenanceStore) -> Vec<(String, String, bool)> {
+    provenance
+        .events()
+        .await
+        .into_iter()
+        .filter_map(|event| match event.data() {
+            ProvEventData::IllegalTaskTransition { from_status, to_status, rejected, .. } => {
+                Some((from_status.clone(), to_status.clone(), *rejected))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn stored_state(task: Option<Task>) -> Option<TaskState> {
+    task.and_then(|task| task.status).and_then(|status| status.state)
+}
+
+#[tokio::test]
+async fn terminal_tasks_reject_further_transitions() {
+    let (store, provenance) = store_with(TransitionPolicy::Reject).await;
+
+    let completed = store
+        .record_status_update(Some(task_id()), context_id(), status("TASK_STATE_COMPLETED"))
+        .await;
+    assert!(completed.is_some());
+
+    let reopened = store
+        .record_status_update(Some(task_id()), context_id(), status("TASK_STATE_WORKING"))
+        .await;
+    assert!(reopened.is_none(), "completed tasks cannot go back to working");
+
+    let canceled = store.cancel(task_id().as_str()).await;
+    assert_eq!(stored_state(canceled), Some(TaskState::String("TASK_STATE_COMPLETED".to_string())));
+
+    assert_eq!(
+        illegal_transitions(&provenance).await,
+        vec![
+            ("TASK_STATE_COMPLETED".to_string(), "TASK_STATE_WORKING".to_string(), true),
+            ("TASK_STATE_COMPLETED".to_string(), "TASK_STATE_CANCELED".to_string(), true),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn flag_policy_applies_and_annotates_illegal_transitions() {
+    let (store, provenance) = store_with(TransitionPolicy::Flag).await;
+
+    store
+        .record_status_update(Some(task_id()), context_id(), status("TASK_STATE_FAILED"))
+        .await
+        .expect("failed");
+    let update = store
+        .record_status_update(Some(task_id()), context_id(), status("TASK_STATE_WORKING"))
+        .await;
+    assert!(update.is_some(), "flagged transitions are still applied");
+    assert_eq!(
+        stored_state(store.get(task_id().as_str(), None).await),
+        Some(TaskState::String("TASK_STATE_WORKING".to_string()))
+    );
+
+    assert_eq!(
+        illegal_transitions(&provenance).await,
+        vec![("TASK_STATE_FAILED".to_string(), "TASK_STATE_WORKING".to_string(), false)]
+    );
+}
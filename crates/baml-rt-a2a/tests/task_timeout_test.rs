@@ -0,0 +1,111 @@
+use baml_rt_a2a::a2a_store::{ProvenanceTaskStore, TaskRepository, TaskUpdateEvent};
+use baml_rt_a2a::a2a_types::{Task, TaskState, TaskStatus};
+use baml_rt_a2a::events::BroadcastEventEmitter;
+use baml_rt_a2a::task_timeout::{
+    cancel_unfinished_tasks, fail_stale_tasks, TASK_SHUTDOWN_REASON, TASK_TIMEOUT_REASON,
+};
+use baml_rt_core::ids::{AgentId, ContextId, ExternalId, TaskId, UuidId};
+use baml_rt_provenance::{InMemoryProvenanceStore, ProvEventData, ProvenanceWriter};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+fn task(id: &str, state: &str) -> Task {
+    Task {
+        id: Some(TaskId::from_external(ExternalId::new(id))),
+        context_id: Some(ContextId::new(1, 1)),
+        artifacts: Vec::new(),
+        history: Vec::new(),
+        status: Some(TaskStatus {
+            state: Some(TaskState::String(state.to_string())),
+            ..TaskStatus::default()
+        }),
+        metadata: None,
+        extra: HashMap::new(),
+    }
+}
+
+#[tokio::test]
+async fn idle_unfinished_tasks_are_failed_with_a_timeout_reason() {
+    let provenance = Arc::new(InMemoryProvenanceStore::new());
+    let writer: Arc<dyn ProvenanceWriter> = provenance.clone();
+    let agent_id = AgentId::from_uuid(UuidId::new(uuid::Uuid::new_v4()));
+    let store = ProvenanceTaskStore::new(Some(writer), agent_id);
+    let (update_tx, mut update_rx) = broadcast::channel(16);
+    let emitter = BroadcastEventEmitter::new(update_tx);
+
+    store.upsert(task("task-stuck", "TASK_STATE_WORKING")).await;
+    store.upsert(task("task-done", "TASK_STATE_COMPLETED")).await;
+    let timeout = Duration::from_millis(50);
+    tokio::time::sleep(timeout).await;
+    store.upsert(task("task-fresh", "TASK_STATE_WORKING")).await;
+
+    assert_eq!(fail_stale_tasks(&store, &emitter, timeout).await, 1);
+
+    let TaskUpdateEvent::Status(update) = update_rx.try_recv().expect("status update emitted") else {
+        panic!("expected a status update");
+    };
+    assert_eq!(update.task_id.as_ref().map(TaskId::as_str), Some("task-stuck"));
+    let status = update.status.expect("status");
+    assert_eq!(status.state, Some(TaskState::String("TASK_STATE_FAILED".to_string())));
+    let message = status.message.expect("timeout message");
+    assert_eq!(message.metadata.expect("metadata")["reason"], json!(TASK_TIMEOUT_REASON));
+    assert!(update_rx.try_recv().is_err(), "only the stale task times out");
+
+    let fresh = store.get("task-fresh", None).await.and_then(|task| task.status);
+    assert_eq!(
+        fresh.and_then(|status| status.state),
+        Some(TaskState::String("TASK_STATE_WORKING".to_string()))
+    );
+
+    let transitions: Vec<_> = provenance
+        .events()
+        .await
+        .into_iter()
+        .filter_map(|event| match event.data() {
+            ProvEventData::TaskStatusChanged { task_id, old_status, new_status } => {
+                Some((task_id.as_str().to_string(), old_status.clone(), new_status.clone()))
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        transitions,
+        vec![(
+            "task-stuck".to_string(),
+            Some("TASK_STATE_WORKING".to_string()),
+            Some("TASK_STATE_FAILED".to_string()),
+        )]
+    );
+
+    // task-stuck is now terminal, so only task-fresh is left to fail.
+    assert_eq!(fail_stale_tasks(&store, &emitter, Duration::ZERO).await, 1);
+}
+
+#[tokio::test]
+async fn shutdown_cancels_every_unfinished_task() {
+    let agent_id = AgentId::from_uuid(UuidId::new(uuid::Uuid::new_v4()));
+    let store = ProvenanceTaskStore::new(None, agent_id);
+    let (update_tx, mut update_rx) = broadcast::channel(16);
+    let emitter = BroadcastEventEmitter::new(update_tx);
+
+    store.upsert(task("task-working", "TASK_STATE_WORKING")).await;
+    store.upsert(task("task-waiting", "TASK_STATE_INPUT_REQUIRED")).await;
+    store.upsert(task("task-done", "TASK_STATE_COMPLETED")).await;
+
+    assert_eq!(cancel_unfinished_tasks(&store, &emitter).await, 2);
+
+    let mut canceled = Vec::new();
+    while let Ok(TaskUpdateEvent::Status(update)) = update_rx.try_recv() {
+        let status = update.status.expect("status");
+        assert_eq!(status.state, Some(TaskState::String("TASK_STATE_CANCELED".to_string())));
+        let metadata = status.message.and_then(|message| message.metadata).expect("metadata");
+        assert_eq!(metadata["reason"], json!(TASK_SHUTDOWN_REASON));
+        canceled.push(update.task_id.expect("task id").as_str().to_string());
+    }
+    canceled.sort();
+    assert_eq!(canceled, vec!["task-waiting".to_string(), "task-working".to_string()]);
+    assert_eq!(cancel_unfinished_tasks(&store, &emitter).await, 0, "nothing is left running");
+}
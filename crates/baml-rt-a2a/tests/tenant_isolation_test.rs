@@ -0,0 +1,231 @@
+use baml_rt::a2a_types::{
+    JSONRPCId, JSONRPCRequest, Message, MessageRole, Part, SendMessageRequest,
+};
+use baml_rt::baml::BamlRuntimeManager;
+use baml_rt::{A2aAgent, A2aRequestHandler};
+use baml_rt_a2a::a2a_store::{
+    task_tenant, ContextRepository, ProvenanceTaskStore, TaskRepository,
+};
+use baml_rt_a2a::a2a_types::{A2aMessageId, Task};
+use baml_rt_a2a::feedback::FeedbackQuery;
+use baml_rt_core::context::{self, RuntimeScope};
+use baml_rt_core::ids::{AgentId, ContextId, ExternalId, TaskId, UuidId};
+use baml_rt_provenance::{InMemoryProvenanceStore, ProvEventData};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const TASK_JS: &str = r#"
+    globalThis.handle_a2a_request = async function(request) {
+        const message = request?.params?.message || {};
+        return {
+            task: {
+                id: `task-${message.messageId}`,
+                contextId: message.contextId,
+                status: { state: "TASK_STATE_WORKING" },
+                history: []
+            }
+        };
+    };
+"#;
+
+async fn setup_agent(store: Arc<InMemoryProvenanceStore>) -> A2aAgent {
+    A2aAgent::builder()
+        .with_runtime_manager(BamlRuntimeManager::new().unwrap())
+        .with_provenance_writer(store)
+        .with_init_js(TASK_JS)
+        .build()
+        .await
+        .unwrap()
+}
+
+fn rpc(method: &str, params: Value) -> Value {
+    let request = JSONRPCRequest {
+        jsonrpc: "2.0".to_string(),
+        method: method.to_string(),
+        params: Some(params),
+        id: Some(JSONRPCId::String("corr-1-1".to_string())),
+    };
+    serde_json::to_value(request).unwrap()
+}
+
+fn message_send(message_id: &str, tenant: Option<&str>) -> SendMessageRequest {
+    SendMessageRequest {
+        message: Message {
+            message_id: A2aMessageId::incoming(ExternalId::new(message_id)),
+            role: MessageRole::String("ROLE_USER".to_string()),
+            parts: vec![Part { text: Some("hello".to_string()), ..Part::default() }],
+            context_id: None,
+            task_id: None,
+            reference_task_ids: Vec::new(),
+            extensions: Vec::new(),
+            metadata: None,
+            extra: HashMap::new(),
+        },
+        configuration: None,
+        metadata: None,
+        tenant: tenant.map(str::to_string),
+        extra: HashMap::new(),
+    }
+}
+
+async fn send(agent: &A2aAgent, params: SendMessageRequest) -> Value {
+    let request = rpc("message.send", serde_json::to_value(params).unwrap());
+    agent.handle_a2a(request).await.unwrap().remove(0)
+}
+
+async fn send_message(agent: &A2aAgent, message_id: &str, tenant: Option<&str>) {
+    let response = send(agent, message_send(message_id, tenant)).await;
+    assert!(response.get("error").is_none(), "send failed: {response}");
+}
+
+async fn listed_task_ids(agent: &A2aAgent, tenant: Option<&str>) -> Vec<String> {
+    let params = match tenant {
+        Some(tenant) => json!({ "tenant": tenant }),
+        None => json!({}),
+    };
+    let responses = agent.handle_a2a(rpc("tasks.list", params)).await.unwrap();
+    responses[0]["result"]["tasks"]
+        .as_array()
+        .expect("task list")
+        .iter()
+        .filter_map(|task| task["id"].as_str().map(str::to_string))
+        .collect()
+}
+
+#[tokio::test]
+async fn tenants_only_list_their_own_tasks() {
+    let agent = setup_agent(Arc::new(InMemoryProvenanceStore::new())).await;
+    send_message(&agent, "acme-1", Some("acme")).await;
+    send_message(&agent, "globex-1", Some("globex")).await;
+    send_message(&agent, "shared-1", None).await;
+
+    assert_eq!(listed_task_ids(&agent, Some("acme")).await, vec!["task-acme-1"]);
+    assert_eq!(listed_task_ids(&agent, Some("globex")).await, vec!["task-globex-1"]);
+    assert_eq!(listed_task_ids(&agent, None).await, vec!["task-shared-1"]);
+}
+
+#[tokio::test]
+async fn another_tenants_task_is_not_found() {
+    let agent = setup_agent(Arc::new(InMemoryProvenanceStore::new())).await;
+    send_message(&agent, "acme-1", Some("acme")).await;
+
+    let own = agent
+        .handle_a2a(rpc("tasks.get", json!({ "id": "task-acme-1", "tenant": "acme" })))
+        .await
+        .unwrap();
+    assert_eq!(own[0]["result"]["id"], json!("task-acme-1"));
+    assert_eq!(own[0]["result"]["metadata"]["tenant"], json!("acme"));
+
+    for params in
+        [json!({ "id": "task-acme-1", "tenant": "globex" }), json!({ "id": "task-acme-1" })]
+    {
+        let other = agent.handle_a2a(rpc("tasks.get", params)).await.unwrap();
+        assert!(other[0].get("error").is_some(), "task leaked across tenants: {}", other[0]);
+    }
+    let cancel = agent
+        .handle_a2a(rpc("tasks.cancel", json!({ "id": "task-acme-1", "tenant": "globex" })))
+        .await
+        .unwrap();
+    assert!(cancel[0].get("error").is_some(), "task canceled across tenants: {}", cancel[0]);
+}
+
+#[tokio::test]
+async fn feedback_cannot_target_another_tenants_task() {
+    let agent = setup_agent(Arc::new(InMemoryProvenanceStore::new())).await;
+    send_message(&agent, "acme-1", Some("acme")).await;
+    let feedback = |tenant: &str| {
+        rpc("message.feedback", json!({ "taskId": "task-acme-1", "rating": 1, "tenant": tenant }))
+    };
+
+    let own = agent.handle_a2a(feedback("acme")).await.unwrap();
+    assert!(own[0].get("error").is_none(), "feedback failed: {}", own[0]);
+    let other = agent.handle_a2a(feedback("globex")).await.unwrap();
+    assert!(other[0].get("error").is_some(), "feedback across tenants: {}", other[0]);
+    assert_eq!(agent.feedback_store().list(&FeedbackQuery::default()).await.len(), 1);
+}
+
+#[tokio::test]
+async fn provenance_events_carry_the_tenant() {
+    let store = Arc::new(InMemoryProvenanceStore::new());
+    let agent = setup_agent(store.clone()).await;
+    send_message(&agent, "acme-1", Some("acme")).await;
+
+    let events = store.events().await;
+    let created = events
+        .iter()
+        .find(|event| matches!(event.data(), ProvEventData::TaskCreated { .. }))
+        .expect("task created event");
+    assert_eq!(created.tenant(), Some("acme"));
+}
+
+#[tokio::test]
+async fn messages_cannot_write_to_another_tenants_task_or_context() {
+    let agent = setup_agent(Arc::new(InMemoryProvenanceStore::new())).await;
+    let acme_context = ContextId::new(2, 1);
+    let mut params = message_send("acme-1", Some("acme"));
+    params.message.context_id = Some(acme_context.clone());
+    assert!(send(&agent, params).await.get("error").is_none());
+    let store = agent.task_store();
+    let task_history = store.get("task-acme-1", None).await.expect("acme's task").history.len();
+    let context_history = store.context_history(acme_context.as_str(), None).await.len();
+
+    let mut into_task = message_send("globex-1", Some("globex"));
+    into_task.message.task_id = Some(TaskId::from_external(ExternalId::new("task-acme-1")));
+    let mut into_context = message_send("globex-2", Some("globex"));
+    into_context.message.context_id = Some(acme_context.clone());
+    for params in [into_task, into_context] {
+        let response = send(&agent, params).await;
+        assert!(response.get("error").is_some(), "wrote across tenants: {response}");
+    }
+
+    let task = store.get("task-acme-1", None).await.expect("acme's task");
+    assert_eq!(task_tenant(&task), Some("acme"));
+    assert_eq!(task.history.len(), task_history, "no foreign message appended");
+    let history = store.context_history(acme_context.as_str(), None).await;
+    assert_eq!(history.len(), context_history);
+}
+
+#[tokio::test]
+async fn upserts_and_forks_leave_another_tenants_entries_alone() {
+    let agent = AgentId::from_uuid(UuidId::new(uuid::Uuid::new_v4()));
+    let store = ProvenanceTaskStore::new(None, agent);
+    let context_id = ContextId::new(3, 1);
+    let task_id = TaskId::from_external(ExternalId::new("task-acme"));
+    let task = Task {
+        id: Some(task_id.clone()),
+        context_id: Some(context_id.clone()),
+        artifacts: Vec::new(),
+        history: Vec::new(),
+        status: None,
+        metadata: None,
+        extra: HashMap::new(),
+    };
+    let mut message = message_send("acme-1", None).message;
+    message.context_id = Some(context_id.clone());
+    let acme = tenant_scope(&context_id, "acme");
+    context::with_scope(acme, async {
+        store.upsert(task.clone()).await.expect("acme's task");
+        store.insert_message(&message).await.expect("acme's message");
+    })
+    .await;
+
+    let at = message.message_id.as_message_id().clone();
+    context::with_scope(tenant_scope(&context_id, "globex"), async {
+        let mut spoofed = task.clone();
+        spoofed.metadata = Some(HashMap::from([("tenant".to_string(), json!("acme"))]));
+        assert!(store.upsert(spoofed).await.is_none(), "another tenant's task is not found");
+        let fork = store.fork_context(&context_id, &at, ContextId::new(3, 2)).await;
+        assert!(fork.is_err(), "another tenant's context is not found");
+    })
+    .await;
+
+    let stored = store.get(task_id.as_str(), None).await.expect("task kept");
+    assert_eq!(task_tenant(&stored), Some("acme"));
+    assert!(store.context_branch(ContextId::new(3, 2).as_str()).await.is_none());
+}
+
+fn tenant_scope(context_id: &ContextId, tenant: &str) -> RuntimeScope {
+    let agent = AgentId::from_uuid(UuidId::new(uuid::Uuid::new_v4()));
+    RuntimeScope::new(context_id.clone(), agent, None, None).with_tenant(Some(tenant.to_string()))
+}
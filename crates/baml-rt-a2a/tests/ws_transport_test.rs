@@ -0,0 +1,84 @@
+use async_trait::async_trait;
+use baml_rt_a2a::a2a_store::TaskUpdateEvent;
+use baml_rt_a2a::a2a_types::TaskStatusUpdateEvent;
+use baml_rt_a2a::{A2aRequestHandler, A2aWebSocketServer};
+use baml_rt_core::ids::{ExternalId, TaskId};
+use baml_rt_core::Result;
+use futures_util::{SinkExt, Stream, StreamExt};
+use serde_json::{json, Value};
+use tokio::sync::{broadcast, oneshot};
+use tokio_tungstenite::tungstenite::Message;
+
+/// Echoes `params` back as the result of every request.
+struct EchoHandler;
+
+#[async_trait(?Send)]
+impl A2aRequestHandler for EchoHandler {
+    async fn handle_a2a(&self, request: Value) -> Result<Vec<Value>> {
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        Ok(vec![json!({"jsonrpc": "2.0", "id": id, "result": request["params"]})])
+    }
+}
+
+fn status_update(task_id: &str) -> TaskUpdateEvent {
+    TaskUpdateEvent::Status(TaskStatusUpdateEvent {
+        task_id: Some(TaskId::from_external(ExternalId::new(task_id))),
+        ..Default::default()
+    })
+}
+
+async fn next_json<S>(socket: &mut S) -> Value
+where
+    S: Stream<Item = tokio_tungstenite::tungstenite::Result<Message>> + Unpin,
+{
+    loop {
+        if let Message::Text(text) = socket.next().await.expect("frame").expect("read frame") {
+            return serde_json::from_str(text.as_str()).expect("json");
+        }
+    }
+}
+
+#[tokio::test]
+async fn websocket_transport_answers_requests_and_pushes_subscribed_updates() {
+    let server = A2aWebSocketServer::bind("127.0.0.1:0".parse().unwrap()).await.expect("bind");
+    let addr = server.local_addr().expect("local addr");
+    let (update_tx, update_rx) = broadcast::channel(16);
+    let (stop_tx, stop_rx) = oneshot::channel::<()>();
+    let handler = EchoHandler;
+
+    let client = async move {
+        let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{addr}"))
+            .await
+            .expect("connect");
+        socket
+            .send(Message::text(r#"{"jsonrpc":"2.0","id":1,"method":"message.send","params":{"ok":true}}"#))
+            .await
+            .expect("send");
+        assert_eq!(next_json(&mut socket).await["result"], json!({"ok": true}));
+
+        socket
+            .send(Message::text(r#"{"jsonrpc":"2.0","id":2,"method":"tasks.subscribe","params":{"id":"task-1"}}"#))
+            .await
+            .expect("send");
+        assert_eq!(next_json(&mut socket).await["id"], json!(2));
+
+        // Only the subscribed task is pushed.
+        update_tx.send(status_update("task-2")).expect("broadcast");
+        update_tx.send(status_update("task-1")).expect("broadcast");
+        let notification = next_json(&mut socket).await;
+        assert_eq!(notification["method"], json!("tasks.update"));
+        assert_eq!(notification["params"]["statusUpdate"]["taskId"], json!("task-1"));
+
+        socket.send(Message::text("not json")).await.expect("send");
+        assert_eq!(next_json(&mut socket).await["error"]["code"], json!(-32700));
+
+        socket.close(None).await.expect("close");
+        stop_tx.send(()).expect("stop server");
+    };
+    let serve = server.serve(&handler, update_rx, async {
+        let _ = stop_rx.await;
+    });
+
+    let (served, ()) = tokio::join!(serve, client);
+    served.expect("server stops cleanly");
+}
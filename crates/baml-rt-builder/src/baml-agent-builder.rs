@@ -9,10 +9,11 @@ use baml_rt_builder::builder::{
     AgentDir, PackagePath, FunctionName, BuildDir,
     BuilderService, StdFileSystem, OxcLinter,
     OxcTypeScriptCompiler, RuntimeTypeGenerator, StdPackager,
-    FileSystem, Linter,
+    FileSystem, Linter, PackageBuilder, PackageSigner,
 };
 use baml_rt_core::{BamlRtError, Result};
 use baml_rt_core::ids::AgentId;
+use baml_rt_core::manifest::AgentManifest;
 use baml_rt_observability::{spans, tracing_setup};
 use baml_rt_quickjs::{BamlRuntimeManager, QuickJSBridge};
 use clap::{Parser, Subcommand};
@@ -43,9 +44,12 @@ enum Commands {
     },
     
     /// Package an agent into a tar.gz file
+    ///
+    /// Builds the agent directory, or with --entry-point packages an agent
+    /// that is already compiled.
     Package {
         /// Agent directory (default: current directory)
-        #[arg(short, long, default_value = ".")]
+        #[arg(short, long, default_value = ".", conflicts_with = "entry_point")]
         agent_dir: PathBuf,
 
         /// Output file path
@@ -55,6 +59,38 @@ enum Commands {
         /// Skip linting
         #[arg(long)]
         skip_lint: bool,
+
+        /// Compiled JavaScript entry point of a prebuilt agent
+        #[arg(long, requires_all = ["name", "version"])]
+        entry_point: Option<PathBuf>,
+
+        /// BAML sources of a prebuilt agent
+        #[arg(long, default_value = "baml_src")]
+        baml_src: PathBuf,
+
+        /// Agent name for the generated manifest
+        #[arg(long, requires = "entry_point")]
+        name: Option<String>,
+
+        /// Agent version for the generated manifest
+        #[arg(long, requires = "entry_point")]
+        version: Option<String>,
+
+        /// Agent description for the generated manifest
+        #[arg(long, requires = "entry_point")]
+        description: Option<String>,
+
+        /// Tool the agent uses, as bundle/tool (repeatable)
+        #[arg(long = "tool", requires = "entry_point")]
+        tools: Vec<String>,
+
+        /// File holding the hex Ed25519 seed to sign the package with
+        #[arg(long, requires_all = ["entry_point", "key_id"])]
+        signing_key: Option<PathBuf>,
+
+        /// Trust store id of the signing key
+        #[arg(long, requires = "signing_key")]
+        key_id: Option<String>,
     },
     
     /// Run an agent package with stdin/stdout connectivity
@@ -75,7 +111,7 @@ enum Commands {
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_setup::init_tracing();
+    let _tracing = tracing_setup::init_tracing();
 
     let cli = Cli::parse();
 
@@ -88,9 +124,29 @@ async fn main() -> Result<()> {
             agent_dir,
             output,
             skip_lint,
+            entry_point,
+            baml_src,
+            name,
+            version,
+            description,
+            tools,
+            signing_key,
+            key_id,
         } => {
-            let agent_dir = AgentDir::new(agent_dir)?;
-            package_agent(&agent_dir, &output, !skip_lint).await?;
+            if let (Some(entry_point), Some(name), Some(version)) = (entry_point, name, version) {
+                let mut builder = PackageBuilder::new(name, version, baml_src, entry_point)
+                    .with_tools(tools);
+                if let Some(description) = description {
+                    builder = builder.with_description(description);
+                }
+                if let (Some(signing_key), Some(key_id)) = (signing_key, key_id) {
+                    builder = builder.with_signer(PackageSigner::from_key_file(key_id, &signing_key)?);
+                }
+                package_prebuilt_agent(&builder, &output)?;
+            } else {
+                let agent_dir = AgentDir::new(agent_dir)?;
+                package_agent(&agent_dir, &output, !skip_lint).await?;
+            }
         }
         Commands::Run {
             package,
@@ -154,6 +210,27 @@ async fn package_agent(
     Ok(())
 }
 
+fn package_prebuilt_agent(builder: &PackageBuilder, output: &std::path::Path) -> Result<()> {
+    println!("📦 Packaging prebuilt agent...");
+    println!("   Output: {}", output.display());
+
+    let manifest = builder.build(output)?;
+    let algorithm = manifest
+        .signature_metadata
+        .as_ref()
+        .map(|metadata| metadata.algorithm.as_str())
+        .unwrap_or("none");
+
+    println!(
+        "\n✅ Agent package built successfully: {} ({} {}, {} signature)",
+        output.display(),
+        manifest.name,
+        manifest.version,
+        algorithm
+    );
+    Ok(())
+}
+
 async fn run_agent(
     package_path: &PackagePath,
     function: Option<&FunctionName>,
@@ -290,20 +367,9 @@ async fn load_agent_package(package_path: &std::path::Path) -> Result<LoadedAgen
     let mut archive = tar::Archive::new(tar);
     archive.unpack(&extract_dir).map_err(BamlRtError::Io)?;
 
-    // Load manifest
-    let manifest_path = extract_dir.join("manifest.json");
-    let manifest_content = fs::read_to_string(&manifest_path).map_err(BamlRtError::Io)?;
-    let manifest_json: Value = serde_json::from_str(&manifest_content).map_err(BamlRtError::Json)?;
-
-    let name = manifest_json.get("name")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| BamlRtError::InvalidArgument("manifest.json missing 'name' field".to_string()))?
-        .to_string();
-
-    let entry_point = manifest_json.get("entry_point")
-        .and_then(|v| v.as_str())
-        .map(String::from)
-        .unwrap_or_else(|| "dist/index.js".to_string());
+    let manifest = AgentManifest::load(&extract_dir)?;
+    let name = manifest.name;
+    let entry_point = manifest.entry_point;
 
     // Load BAML schema
     let baml_src = extract_dir.join("baml_src");
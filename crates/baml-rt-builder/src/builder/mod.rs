@@ -12,6 +12,7 @@ pub mod ts_gen;
 pub mod baml_gen;
 pub mod schema_to_baml;
 pub mod packager;
+pub mod package_builder;
 pub mod service;
 
 pub use types::{AgentDir, PackagePath, FunctionName, BuildDir};
@@ -22,4 +23,5 @@ pub use filesystem::StdFileSystem;
 pub use linter::OxcLinter;
 pub use compiler::{OxcTypeScriptCompiler, RuntimeTypeGenerator};
 pub use packager::StdPackager;
+pub use package_builder::{PackageBuilder, PackageSigner};
 pub use service::BuilderService;
@@ -0,0 +1,246 @@
+//! Packaging prebuilt agents
+//!
+//! [`PackageBuilder`] assembles a package from parts that are already built: a
+//! `baml_src` directory, a compiled JavaScript entry point and the tools the
+//! agent uses. It writes `manifest.json` itself, signed over the package
+//! contents (see [`baml_rt_core::package::package_digest`]), so the result
+//! loads in a runner that verifies signatures.
+//!
+//! Declared tools must exist in the tool catalog; a typo fails the build rather
+//! than the agent's first tool call.
+
+use baml_rt_core::manifest::{AgentManifest, MANIFEST_FILE, SignatureMetadata};
+use baml_rt_core::package::package_digest;
+use baml_rt_core::{BamlRtError, Result};
+use baml_rt_tools::tool_catalog::{InventoryCatalog, ToolCatalog, resolve_manifest_tools_with_catalog};
+use crate::builder::filesystem::StdFileSystem;
+use crate::builder::traits::FileSystem;
+use crate::builder::types::BuildDir;
+use ed25519_dalek::{Signer, SigningKey};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use serde_json::{Value, json};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Ed25519 key a package is signed with, and the id runners know it by
+pub struct PackageSigner {
+    key_id: String,
+    key: SigningKey,
+}
+
+impl PackageSigner {
+    pub fn from_seed(key_id: impl Into<String>, seed: &[u8; 32]) -> Self {
+        Self { key_id: key_id.into(), key: SigningKey::from_bytes(seed) }
+    }
+
+    /// Read the key from a file holding the hex-encoded 32-byte secret seed.
+    pub fn from_key_file(key_id: impl Into<String>, path: &Path) -> Result<Self> {
+        let raw = fs::read_to_string(path).map_err(BamlRtError::Io)?;
+        let seed: [u8; 32] = hex::decode(raw.trim())
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| {
+                BamlRtError::InvalidArgument(format!(
+                    "signing key {} is not a hex Ed25519 seed",
+                    path.display()
+                ))
+            })?;
+        Ok(Self::from_seed(key_id, &seed))
+    }
+
+    /// Hex public key, as listed in a runner's trust store.
+    pub fn public_key_hex(&self) -> String {
+        hex::encode(self.key.verifying_key().to_bytes())
+    }
+}
+
+/// Builds an agent package from prebuilt parts
+///
+/// Without a [`PackageSigner`] the manifest's signature is the sha256 digest of
+/// the contents, which detects corruption but not who built the package.
+pub struct PackageBuilder {
+    name: String,
+    version: String,
+    description: Option<String>,
+    baml_src: PathBuf,
+    entry_point: PathBuf,
+    tools: Vec<String>,
+    signer: Option<PackageSigner>,
+}
+
+impl PackageBuilder {
+    /// A package named `name` at `version` from the BAML sources in `baml_src`
+    /// and the compiled JavaScript file `entry_point`.
+    pub fn new(
+        name: impl Into<String>,
+        version: impl Into<String>,
+        baml_src: impl Into<PathBuf>,
+        entry_point: impl Into<PathBuf>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            version: version.into(),
+            description: None,
+            baml_src: baml_src.into(),
+            entry_point: entry_point.into(),
+            tools: Vec::new(),
+            signer: None,
+        }
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Host tools the agent uses, as `bundle/tool` names
+    pub fn with_tools<I, S>(mut self, tools: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.tools.extend(tools.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn with_signer(mut self, signer: PackageSigner) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Write the package to `output`, checking tools against the registered
+    /// tool inventory. Returns the manifest it contains.
+    pub fn build(&self, output: &Path) -> Result<AgentManifest> {
+        self.build_with_catalog(&InventoryCatalog::new(), output)
+    }
+
+    /// Like [`build`](Self::build), checking tools against `catalog`.
+    pub fn build_with_catalog<C: ToolCatalog>(
+        &self,
+        catalog: &C,
+        output: &Path,
+    ) -> Result<AgentManifest> {
+        if !self.baml_src.is_dir() {
+            return Err(BamlRtError::InvalidArgument(format!(
+                "BAML source directory not found: {}",
+                self.baml_src.display()
+            )));
+        }
+        if !self.entry_point.is_file() {
+            return Err(BamlRtError::InvalidArgument(format!(
+                "Entry point not found: {}",
+                self.entry_point.display()
+            )));
+        }
+        resolve_manifest_tools_with_catalog(catalog, &self.tools)?;
+
+        let build_dir = BuildDir::new()?;
+        let staging = build_dir.join("package");
+        if staging.exists() {
+            fs::remove_dir_all(&staging).map_err(BamlRtError::Io)?;
+        }
+        let result = self.stage(&staging).and_then(|manifest| {
+            write_archive(&staging, output)?;
+            Ok(manifest)
+        });
+        let _ = fs::remove_dir_all(&staging);
+        result
+    }
+
+    /// Lay the package out in `staging` and write its signed manifest.
+    fn stage(&self, staging: &Path) -> Result<AgentManifest> {
+        let filesystem = StdFileSystem;
+        filesystem.copy_dir_all(&self.baml_src, &staging.join("baml_src"))?;
+        let file_name = self
+            .entry_point
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| {
+                BamlRtError::InvalidArgument(format!(
+                    "Entry point has no UTF-8 file name: {}",
+                    self.entry_point.display()
+                ))
+            })?;
+        let entry_point = format!("dist/{}", file_name);
+        fs::create_dir_all(staging.join("dist")).map_err(BamlRtError::Io)?;
+        fs::copy(&self.entry_point, staging.join(&entry_point)).map_err(BamlRtError::Io)?;
+
+        let mut manifest = json!({
+            "manifest_version": 2,
+            "name": self.name,
+            "version": self.version,
+            "entry_point": entry_point,
+            "tools": self.tools,
+        });
+        if let Some(description) = &self.description {
+            manifest["description"] = Value::String(description.clone());
+        }
+        let mut manifest = AgentManifest::from_value(&manifest)?;
+
+        let digest = package_digest(staging, &manifest)?;
+        let digest_hex = hex::encode(digest);
+        let (signature, metadata) = match &self.signer {
+            Some(signer) => (
+                hex::encode(signer.key.sign(&digest).to_bytes()),
+                SignatureMetadata {
+                    algorithm: "ed25519".to_string(),
+                    key_id: Some(signer.key_id.clone()),
+                    digest: Some(digest_hex),
+                },
+            ),
+            None => (
+                digest_hex.clone(),
+                SignatureMetadata {
+                    algorithm: "sha256".to_string(),
+                    key_id: None,
+                    digest: Some(digest_hex),
+                },
+            ),
+        };
+        manifest.signature = Some(signature);
+        manifest.signature_metadata = Some(metadata);
+
+        let content = serde_json::to_string_pretty(&manifest).map_err(BamlRtError::Json)?;
+        fs::write(staging.join(MANIFEST_FILE), content).map_err(BamlRtError::Io)?;
+        Ok(manifest)
+    }
+}
+
+/// tar.gz every file under `staging`, by its path relative to it.
+fn write_archive(staging: &Path, output: &Path) -> Result<()> {
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent).map_err(BamlRtError::Io)?;
+    }
+    let tar_gz = fs::File::create(output).map_err(BamlRtError::Io)?;
+    let mut tar = tar::Builder::new(GzEncoder::new(tar_gz, Compression::default()));
+
+    let mut files = Vec::new();
+    collect_files(staging, &mut files).map_err(BamlRtError::Io)?;
+    files.sort();
+    for file_path in files {
+        let relative_path = file_path.strip_prefix(staging).map_err(|_| {
+            BamlRtError::InvalidArgument(format!(
+                "File {} is not under directory {}",
+                file_path.display(),
+                staging.display()
+            ))
+        })?;
+        tar.append_path_with_name(&file_path, relative_path).map_err(BamlRtError::Io)?;
+    }
+
+    tar.into_inner().map_err(BamlRtError::Io)?.finish().map_err(BamlRtError::Io)?;
+    Ok(())
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
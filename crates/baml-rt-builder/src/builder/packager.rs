@@ -1,5 +1,6 @@
 //! Packager implementation for creating tar.gz agent packages
 
+use baml_rt_core::manifest::AgentManifest;
 use baml_rt_core::{BamlRtError, Result};
 use crate::builder::traits::{Packager, FileSystem};
 use crate::builder::types::{AgentDir, BuildDir};
@@ -38,6 +39,8 @@ impl<FS: FileSystem> Packager for StdPackager<FS> {
         if manifest_path.exists() {
             let content = fs::read_to_string(&manifest_path).map_err(BamlRtError::Io)?;
             let mut manifest: Value = serde_json::from_str(&content).map_err(BamlRtError::Json)?;
+            // Reject an invalid manifest here rather than when the runner loads the package.
+            AgentManifest::from_value(&manifest)?;
             if manifest.get("signature").and_then(|v| v.as_str()).is_none() {
                 manifest["signature"] = Value::String(Uuid::new_v4().to_string());
             }
@@ -1,31 +1,19 @@
+use baml_rt_core::manifest::{AgentManifest, MANIFEST_FILE};
 use baml_rt_core::{BamlRtError, Result};
 use genco::prelude::*;
 use genco::lang::js;
 use baml_rt_tools::tool_catalog::resolve_manifest_tools;
 use baml_rt_tools::ts_gen::render_tool_typescript;
-use std::fs;
 use std::path::Path;
 
 pub fn load_manifest_tools(baml_src: &Path) -> Result<Vec<String>> {
     let agent_dir = baml_src
         .parent()
         .ok_or_else(|| BamlRtError::InvalidArgument("baml_src has no parent directory".to_string()))?;
-    let manifest_path = agent_dir.join("manifest.json");
-    if !manifest_path.exists() {
+    if !agent_dir.join(MANIFEST_FILE).exists() {
         return Ok(Vec::new());
     }
-    let content = fs::read_to_string(&manifest_path).map_err(BamlRtError::Io)?;
-    let manifest_json: serde_json::Value = serde_json::from_str(&content).map_err(BamlRtError::Json)?;
-    let tools = manifest_json
-        .get("tools")
-        .and_then(|v| v.as_array())
-        .map(|arr| {
-            arr.iter()
-                .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                .collect()
-        })
-        .unwrap_or_default();
-    Ok(tools)
+    Ok(AgentManifest::load(agent_dir)?.tools)
 }
 
 pub fn render_ts_declarations(function_names: &[String], tool_names: &[String]) -> Result<String> {
@@ -215,3 +215,58 @@ async fn test_full_integration_package_load_execute() {
         }
     }
 }
+
+#[test]
+fn test_cli_package_prebuilt_agent() {
+    let harness = CliHarness::new();
+    let work_dir = TempDir::new().unwrap();
+    let entry_point = work_dir.path().join("index.js");
+    std::fs::write(&entry_point, "globalThis.answer = () => 42;").unwrap();
+    let output_path = work_dir.path().join("prebuilt.tar.gz");
+
+    let mut cmd = harness.builder_command();
+    cmd.arg("package")
+        .arg("--entry-point")
+        .arg(&entry_point)
+        .arg("--baml-src")
+        .arg(agent_fixture("tony-shrink").join("baml_src"))
+        .arg("--name")
+        .arg("tony-shrink")
+        .arg("--version")
+        .arg("1.0.0")
+        .arg("--tool")
+        .arg("support/calculate")
+        .arg("--output")
+        .arg(&output_path);
+
+    let output = cmd.output().expect("Failed to execute package command");
+    assert!(
+        output.status.success(),
+        "Packaging should succeed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let tar = flate2::read::GzDecoder::new(std::fs::File::open(&output_path).unwrap());
+    let extract_dir = TempDir::new().unwrap();
+    tar::Archive::new(tar).unpack(extract_dir.path()).unwrap();
+    let manifest = baml_rt_core::manifest::AgentManifest::load(extract_dir.path()).unwrap();
+    assert_eq!(manifest.entry_point, "dist/index.js");
+    assert!(manifest.signature.is_some());
+
+    let mut cmd = harness.builder_command();
+    cmd.arg("package")
+        .arg("--entry-point")
+        .arg(&entry_point)
+        .arg("--baml-src")
+        .arg(agent_fixture("tony-shrink").join("baml_src"))
+        .arg("--name")
+        .arg("tony-shrink")
+        .arg("--version")
+        .arg("1.0.0")
+        .arg("--tool")
+        .arg("support/no_such_tool")
+        .arg("--output")
+        .arg(work_dir.path().join("rejected.tar.gz"));
+    let output = cmd.output().expect("Failed to execute package command");
+    assert!(!output.status.success(), "Unknown tools should fail packaging");
+}
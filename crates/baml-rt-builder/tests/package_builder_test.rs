@@ -0,0 +1,86 @@
+//! Tests for packaging prebuilt agents with PackageBuilder
+
+use baml_rt_builder::builder::{PackageBuilder, PackageSigner};
+use baml_rt_core::manifest::AgentManifest;
+use baml_rt_core::package::package_digest;
+use ed25519_dalek::{Signature, VerifyingKey};
+use tempfile::TempDir;
+use test_support::common::agent_fixture;
+
+fn prebuilt_agent(dir: &TempDir) -> PackageBuilder {
+    let entry_point = dir.path().join("agent.js");
+    std::fs::write(&entry_point, "globalThis.answer = () => 42;").unwrap();
+    PackageBuilder::new(
+        "tony-shrink",
+        "1.2.0",
+        agent_fixture("tony-shrink").join("baml_src"),
+        entry_point,
+    )
+    .with_tools(["support/calculate"])
+}
+
+fn unpack(package: &std::path::Path) -> TempDir {
+    let extract_dir = TempDir::new().unwrap();
+    let tar = flate2::read::GzDecoder::new(std::fs::File::open(package).unwrap());
+    tar::Archive::new(tar).unpack(extract_dir.path()).unwrap();
+    extract_dir
+}
+
+#[test]
+fn test_prebuilt_package_carries_digest_of_its_contents() {
+    let dir = TempDir::new().unwrap();
+    let output = dir.path().join("out/agent.tar.gz");
+    let built = prebuilt_agent(&dir).build(&output).expect("Packaging should succeed");
+
+    let extracted = unpack(&output);
+    assert!(extracted.path().join("baml_src/tony_prompt.baml").exists());
+    assert!(extracted.path().join("dist/agent.js").exists());
+
+    let manifest = AgentManifest::load(extracted.path()).unwrap();
+    assert_eq!(manifest, built);
+    assert_eq!(manifest.entry_point, "dist/agent.js");
+    assert_eq!(manifest.tools, vec!["support/calculate".to_string()]);
+
+    let digest = hex::encode(package_digest(extracted.path(), &manifest).unwrap());
+    let metadata = manifest.signature_metadata.as_ref().unwrap();
+    assert_eq!(metadata.algorithm, "sha256");
+    assert_eq!(metadata.digest.as_deref(), Some(digest.as_str()));
+    assert_eq!(manifest.signature.as_deref(), Some(digest.as_str()));
+}
+
+#[test]
+fn test_prebuilt_package_is_signed_with_the_given_key() {
+    let dir = TempDir::new().unwrap();
+    let output = dir.path().join("agent.tar.gz");
+    let signer = PackageSigner::from_seed("release", &[7; 32]);
+    let public_key = signer.public_key_hex();
+    prebuilt_agent(&dir).with_signer(signer).build(&output).unwrap();
+
+    let extracted = unpack(&output);
+    let manifest = AgentManifest::load(extracted.path()).unwrap();
+    let metadata = manifest.signature_metadata.as_ref().unwrap();
+    assert_eq!(metadata.algorithm, "ed25519");
+    assert_eq!(metadata.key_id.as_deref(), Some("release"));
+
+    let key_bytes: [u8; 32] = hex::decode(public_key).unwrap().try_into().unwrap();
+    let signature: [u8; 64] =
+        hex::decode(manifest.signature.as_ref().unwrap()).unwrap().try_into().unwrap();
+    let digest = package_digest(extracted.path(), &manifest).unwrap();
+    VerifyingKey::from_bytes(&key_bytes)
+        .unwrap()
+        .verify_strict(&digest, &Signature::from_bytes(&signature))
+        .expect("Signature should cover the package digest");
+}
+
+#[test]
+fn test_prebuilt_package_rejects_unknown_tools() {
+    let dir = TempDir::new().unwrap();
+    let output = dir.path().join("agent.tar.gz");
+    let err = prebuilt_agent(&dir)
+        .with_tools(["support/no_such_tool"])
+        .build(&output)
+        .expect_err("Unknown tools should fail the build");
+
+    assert!(err.to_string().contains("support/no_such_tool"), "{err}");
+    assert!(!output.exists(), "No package should be written");
+}
@@ -0,0 +1,145 @@
+//! Canonical JSON encoding and digests.
+//!
+//! Any feature that hashes JSON (deduplication, caching, content addressing) must
+//! go through this module so that equal values always produce equal bytes:
+//! - object keys are sorted by their UTF-8 bytes,
+//! - integral floats are written as integers (`1.0` -> `1`, `-0.0` -> `0`),
+//! - strings use serde_json escaping,
+//! - whitespace is either absent or a fixed two-space indentation.
+
+use serde_json::{Number, Value};
+use sha2::{Digest as _, Sha256};
+use std::fmt::Write as _;
+
+/// Largest integer magnitude an `f64` represents exactly (2^53).
+const MAX_EXACT_F64_INT: f64 = 9_007_199_254_740_992.0;
+
+/// SHA-256 of a canonical encoding.
+pub type Digest = [u8; 32];
+
+/// Whitespace rules for canonical output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Whitespace {
+    /// No insignificant whitespace. Use this for digests.
+    #[default]
+    Compact,
+    /// Newlines and two-space indentation, for human-facing output.
+    Pretty,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CanonicalOptions {
+    pub whitespace: Whitespace,
+}
+
+/// Canonical compact encoding of `value`.
+pub fn canonicalize(value: &Value) -> String {
+    canonicalize_with(value, CanonicalOptions::default())
+}
+
+/// Canonical encoding of `value` with explicit options.
+pub fn canonicalize_with(value: &Value, options: CanonicalOptions) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value, options.whitespace, 0);
+    out
+}
+
+/// SHA-256 of the canonical compact encoding.
+///
+/// Unlike `DefaultHasher`, the result is stable across processes and toolchains,
+/// and wide enough that distinct values cannot realistically collide.
+pub fn digest(value: &Value) -> Digest {
+    Sha256::digest(canonicalize(value).as_bytes()).into()
+}
+
+/// [`digest`] of `Value::String(text)`, without copying `text` into a value.
+pub fn digest_str(text: &str) -> Digest {
+    let mut out = String::new();
+    write_string(&mut out, text);
+    Sha256::digest(out.as_bytes()).into()
+}
+
+/// Lowercase hex form of [`digest`], 64 characters.
+pub fn digest_hex(value: &Value) -> String {
+    digest(value).iter().fold(String::with_capacity(64), |mut out, byte| {
+        let _ = write!(out, "{byte:02x}");
+        out
+    })
+}
+
+fn write_value(out: &mut String, value: &Value, whitespace: Whitespace, depth: usize) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(flag) => out.push_str(if *flag { "true" } else { "false" }),
+        Value::Number(number) => write_number(out, number),
+        Value::String(text) => write_string(out, text),
+        Value::Array(items) => {
+            if items.is_empty() {
+                out.push_str("[]");
+                return;
+            }
+            out.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                write_break(out, whitespace, depth + 1);
+                write_value(out, item, whitespace, depth + 1);
+            }
+            write_break(out, whitespace, depth);
+            out.push(']');
+        }
+        Value::Object(map) => {
+            if map.is_empty() {
+                out.push_str("{}");
+                return;
+            }
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.as_bytes().cmp(b.as_bytes()));
+            out.push('{');
+            for (index, (key, item)) in entries.into_iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                write_break(out, whitespace, depth + 1);
+                write_string(out, key);
+                out.push(':');
+                if whitespace == Whitespace::Pretty {
+                    out.push(' ');
+                }
+                write_value(out, item, whitespace, depth + 1);
+            }
+            write_break(out, whitespace, depth);
+            out.push('}');
+        }
+    }
+}
+
+fn write_break(out: &mut String, whitespace: Whitespace, depth: usize) {
+    if whitespace == Whitespace::Pretty {
+        out.push('\n');
+        for _ in 0..depth {
+            out.push_str("  ");
+        }
+    }
+}
+
+fn write_string(out: &mut String, text: &str) {
+    // serde_json escaping is deterministic; serializing a &str cannot fail.
+    out.push_str(&serde_json::to_string(text).unwrap_or_default());
+}
+
+fn write_number(out: &mut String, number: &Number) {
+    if let Some(value) = number.as_i64() {
+        let _ = write!(out, "{value}");
+    } else if let Some(value) = number.as_u64() {
+        let _ = write!(out, "{value}");
+    } else if let Some(value) = number.as_f64() {
+        if value.fract() == 0.0 && value.abs() < MAX_EXACT_F64_INT {
+            // Covers -0.0 as well: `as i64` yields 0.
+            let _ = write!(out, "{}", value as i64);
+        } else {
+            let _ = write!(out, "{number}");
+        }
+    }
+}
@@ -2,18 +2,40 @@
 //!
 //! This module provides task-local context IDs so async boundaries
 //! can retain request context without requiring JS changes.
+//!
+//! A context may be the child of another, e.g. a sub-conversation or the work
+//! an agent delegates: its scope then carries the parent's id, which travels
+//! between agents as `parent_context_id` in A2A message metadata.
 
 use crate::ids::{AgentId, ContextId, MessageId, TaskId};
 use crate::error::{BamlRtError, Result};
+use crate::trace_context::TraceContext;
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// Message metadata key naming the context the message's context is a child of.
+pub const PARENT_CONTEXT_ID_METADATA_KEY: &str = "parent_context_id";
+
+/// Message metadata key listing the agents whose delegations led to the
+/// message, outermost first.
+pub const DELEGATION_CHAIN_METADATA_KEY: &str = "delegation_chain";
+
 #[derive(Debug, Clone)]
 pub struct RuntimeScope {
     pub context_id: ContextId,
     pub agent_id: AgentId,
     pub message_id: Option<MessageId>,
     pub task_id: Option<TaskId>,
+    /// Tenant the request is made on behalf of; `None` outside multi-tenant deployments.
+    pub tenant: Option<String>,
+    /// Trace context of the A2A request being handled, if the caller sent one.
+    pub trace_context: Option<TraceContext>,
+    /// The context `context_id` is a child of; `None` for top-level contexts.
+    pub parent_context_id: Option<ContextId>,
+    /// Agents, by card name, whose delegations led to this request, outermost
+    /// first; empty unless another agent delegated it.
+    pub delegation_chain: Vec<String>,
 }
 
 impl RuntimeScope {
@@ -23,7 +45,115 @@ impl RuntimeScope {
         message_id: Option<MessageId>,
         task_id: Option<TaskId>,
     ) -> Self {
-        Self { context_id, agent_id, message_id, task_id }
+        Self {
+            context_id,
+            agent_id,
+            message_id,
+            task_id,
+            tenant: None,
+            trace_context: None,
+            parent_context_id: None,
+            delegation_chain: Vec::new(),
+        }
+    }
+
+    pub fn with_tenant(mut self, tenant: Option<String>) -> Self {
+        self.tenant = tenant;
+        self
+    }
+
+    pub fn with_trace_context(mut self, trace_context: Option<TraceContext>) -> Self {
+        self.trace_context = trace_context;
+        self
+    }
+
+    pub fn with_parent_context(mut self, parent_context_id: Option<ContextId>) -> Self {
+        self.parent_context_id = parent_context_id;
+        self
+    }
+
+    pub fn with_delegation_chain(mut self, delegation_chain: Vec<String>) -> Self {
+        self.delegation_chain = delegation_chain;
+        self
+    }
+
+    /// Scope for `context_id` as a child of this scope's context. The agent,
+    /// tenant, trace and delegation chain carry over; message and task do not.
+    pub fn child(&self, context_id: ContextId) -> Self {
+        Self::new(context_id, self.agent_id.clone(), None, None)
+            .with_tenant(self.tenant.clone())
+            .with_trace_context(self.trace_context.clone())
+            .with_parent_context(Some(self.context_id.clone()))
+            .with_delegation_chain(self.delegation_chain.clone())
+    }
+}
+
+/// Scopes of the invocations in progress on one runtime, innermost last
+///
+/// Host callbacks run outside the task that started the invocation, so they
+/// cannot see its task-local scope; they read the innermost frame instead.
+/// Clones share their frames.
+#[derive(Debug, Clone, Default)]
+pub struct ScopeStack {
+    frames: Arc<Mutex<Vec<(u64, RuntimeScope)>>>,
+    next_frame: Arc<AtomicU64>,
+}
+
+impl ScopeStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Push `scope` until the returned frame is dropped
+    pub fn enter(&self, scope: RuntimeScope) -> ScopeFrame {
+        let id = self.next_frame.fetch_add(1, Ordering::Relaxed);
+        let depth = {
+            let mut frames = self.frames.lock().unwrap();
+            frames.push((id, scope));
+            frames.len()
+        };
+        ScopeFrame { stack: self.clone(), id, depth }
+    }
+
+    pub fn innermost(&self) -> Option<RuntimeScope> {
+        self.frames.lock().unwrap().last().map(|(_, scope)| scope.clone())
+    }
+
+    pub fn depth(&self) -> usize {
+        self.frames.lock().unwrap().len()
+    }
+
+    /// The task-local scope when there is one, otherwise the innermost frame
+    pub fn resolve(&self) -> Option<RuntimeScope> {
+        current_scope().or_else(|| self.innermost())
+    }
+}
+
+/// A scope pushed onto a [`ScopeStack`]; dropping it pops the scope
+///
+/// Frames may be dropped out of order when invocations overlap; each removes
+/// only its own scope.
+#[must_use = "the scope is popped as soon as the frame is dropped"]
+#[derive(Debug)]
+pub struct ScopeFrame {
+    stack: ScopeStack,
+    id: u64,
+    depth: usize,
+}
+
+impl ScopeFrame {
+    /// Frames on the stack when this one was pushed, itself included
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+}
+
+impl Drop for ScopeFrame {
+    fn drop(&mut self) {
+        let mut frames = self.stack.frames.lock().unwrap();
+        if let Some(index) = frames.iter().rposition(|(id, _)| *id == self.id) {
+            frames.remove(index);
+        }
     }
 }
 
@@ -62,6 +192,22 @@ pub fn current_task_id() -> Option<TaskId> {
     current_scope().and_then(|scope| scope.task_id)
 }
 
+pub fn current_tenant() -> Option<String> {
+    current_scope().and_then(|scope| scope.tenant)
+}
+
+pub fn current_trace_context() -> Option<TraceContext> {
+    current_scope().and_then(|scope| scope.trace_context)
+}
+
+pub fn current_parent_context_id() -> Option<ContextId> {
+    current_scope().and_then(|scope| scope.parent_context_id)
+}
+
+pub fn current_delegation_chain() -> Vec<String> {
+    current_scope().map(|scope| scope.delegation_chain).unwrap_or_default()
+}
+
 pub fn current_or_new() -> ContextId {
     current_context_id().unwrap_or_else(generate_context_id)
 }
@@ -79,7 +225,10 @@ where
 {
     let scope = current_scope()
         .map(|mut scope| {
-            scope.context_id = id.clone();
+            if scope.context_id != id {
+                scope.context_id = id.clone();
+                scope.parent_context_id = None;
+            }
             scope
         })
         .ok_or_else(|| {
@@ -90,6 +239,20 @@ where
     Ok(with_scope(scope, fut).await)
 }
 
+/// Run `fut` in a new context, `id`, that is a child of the current one.
+pub async fn with_child_context<F, T>(id: ContextId, fut: F) -> Result<T>
+where
+    F: std::future::Future<Output = T>,
+{
+    let scope = current_scope()
+        .ok_or_else(|| {
+            BamlRtError::InvalidArgument(
+                "RuntimeScope must exist with agent_id - cannot create scope without agent context".to_string()
+            )
+        })?;
+    Ok(with_scope(scope.child(id), fut).await)
+}
+
 pub async fn with_message_id<F, T>(id: MessageId, fut: F) -> Result<T>
 where
     F: std::future::Future<Output = T>,
@@ -100,7 +263,7 @@ where
                 "RuntimeScope must exist with agent_id - cannot create scope without agent context".to_string()
             )
         })?;
-    let scope = RuntimeScope::new(scope.context_id, scope.agent_id, Some(id), scope.task_id);
+    let scope = RuntimeScope { message_id: Some(id), ..scope };
     Ok(with_scope(scope, fut).await)
 }
 
@@ -114,10 +277,23 @@ where
                 "RuntimeScope must exist with agent_id - cannot create scope without agent context".to_string()
             )
         })?;
-    let scope = RuntimeScope::new(scope.context_id, scope.agent_id, scope.message_id, Some(id));
+    let scope = RuntimeScope { task_id: Some(id), ..scope };
     Ok(with_scope(scope, fut).await)
 }
 
+pub async fn with_tenant<F, T>(tenant: Option<String>, fut: F) -> Result<T>
+where
+    F: std::future::Future<Output = T>,
+{
+    let scope = current_scope()
+        .ok_or_else(|| {
+            BamlRtError::InvalidArgument(
+                "RuntimeScope must exist with agent_id - cannot create scope without agent context".to_string()
+            )
+        })?;
+    Ok(with_scope(scope.with_tenant(tenant), fut).await)
+}
+
 pub async fn with_agent_id<F, T>(id: AgentId, fut: F) -> T
 where
     F: std::future::Future<Output = T>,
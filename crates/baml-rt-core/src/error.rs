@@ -2,8 +2,16 @@
 //!
 //! Provides a comprehensive error hierarchy using `thiserror` for proper error handling
 //! and error chaining throughout the codebase.
+//!
+//! Every error has a stable [`ErrorCode`] for automated triage, and can carry
+//! [`ErrorContext`] frames (operation, agent, tool, function) added as it
+//! crosses layers. Context does not change the message, and [`BamlRtError::root`]
+//! gives the underlying error for matching on its variant.
 
 use anyhow::Error as AnyhowError;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fmt;
 use std::time::SystemTimeError;
 use thiserror::Error;
 
@@ -26,6 +34,23 @@ pub enum BamlRtError {
         source: Box<dyn std::error::Error + Send + Sync>,
     },
 
+    /// A JavaScript function threw or its returned promise rejected
+    #[error("JS function invocation error ({function}): {name}: {message}")]
+    JsRejection {
+        function: String,
+        name: String,
+        message: String,
+        stack: Option<String>,
+    },
+
+    /// A promise returned to the host did not settle in time
+    #[error("Promise did not resolve within {timeout_ms}ms")]
+    PromiseTimeout { timeout_ms: u64 },
+
+    /// JavaScript exceeded one of the limits set in `QuickJSConfig`
+    #[error("QuickJS {kind} limit exceeded: {detail}")]
+    ResourceLimit { kind: ResourceLimitKind, detail: String },
+
     /// Type conversion error between Rust and JavaScript types
     #[error("Type conversion error: {0}")]
     TypeConversion(String),
@@ -46,6 +71,27 @@ pub enum BamlRtError {
         source: Box<dyn std::error::Error + Send + Sync>,
     },
 
+    /// A tool call the agent's allowlist does not permit
+    #[error("Tool not allowed: {0}")]
+    ToolNotAllowed(String),
+
+    /// A tool session operation its session's state does not allow, such as
+    /// a send before the open or on a session that no longer exists
+    #[error("Tool session state violation: {0}")]
+    SessionStateViolation(String),
+
+    /// A value did not match the schema it was checked against
+    #[error("Schema mismatch: {0}")]
+    SchemaMismatch(String),
+
+    /// An operation did not finish before its deadline
+    #[error("Timed out: {0}")]
+    Timeout(String),
+
+    /// A token, cost or rate budget is spent
+    #[error("Budget exhausted: {0}")]
+    Budget(String),
+
     /// I/O error (file operations, etc.)
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
@@ -66,6 +112,14 @@ pub enum BamlRtError {
     #[error("Schema loading error: {0}")]
     SchemaLoading(String),
 
+    /// Agent package manifest failed schema validation
+    #[error(transparent)]
+    InvalidManifest(#[from] crate::manifest::ManifestError),
+
+    /// A BAML function's output did not match its declared type
+    #[error(transparent)]
+    OutputValidation(#[from] crate::types::OutputValidationError),
+
     /// Runtime configuration error
     #[error("Runtime configuration error: {0}")]
     Configuration(String),
@@ -107,6 +161,247 @@ pub enum BamlRtError {
     /// Tar header path error
     #[error("Failed to set tar header path")]
     TarHeaderPath(#[source] std::io::Error),
+
+    /// An error with the context it happened in
+    #[error("{inner}")]
+    WithContext {
+        context: ErrorContext,
+        inner: Box<BamlRtError>,
+    },
+}
+
+/// Stable, machine-readable identifier of an error's kind
+///
+/// The strings returned by [`ErrorCode::as_str`] are part of the public
+/// interface: JSON-RPC error data, logs and provenance carry them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    BamlRuntime,
+    QuickJs,
+    JsRejection,
+    PromiseTimeout,
+    ResourceLimit,
+    TypeConversion,
+    FunctionNotFound,
+    InvalidArgument,
+    ToolNotAllowed,
+    SessionStateViolation,
+    SchemaMismatch,
+    Timeout,
+    Budget,
+    Io,
+    Json,
+    ToolExecution,
+    ToolRegistration,
+    SchemaLoading,
+    InvalidManifest,
+    OutputValidation,
+    Configuration,
+    Initialization,
+    ExecutionFailed,
+    ParsedResultFailed,
+    RequestBuildFailed,
+    RuntimeLoadFailed,
+    SystemTime,
+    TarHeaderPath,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::BamlRuntime => "BAML_RUNTIME",
+            ErrorCode::QuickJs => "QUICKJS",
+            ErrorCode::JsRejection => "JS_REJECTION",
+            ErrorCode::PromiseTimeout => "PROMISE_TIMEOUT",
+            ErrorCode::ResourceLimit => "RESOURCE_LIMIT",
+            ErrorCode::TypeConversion => "TYPE_CONVERSION",
+            ErrorCode::FunctionNotFound => "FUNCTION_NOT_FOUND",
+            ErrorCode::InvalidArgument => "INVALID_ARGUMENT",
+            ErrorCode::ToolNotAllowed => "TOOL_NOT_ALLOWED",
+            ErrorCode::SessionStateViolation => "SESSION_STATE_VIOLATION",
+            ErrorCode::SchemaMismatch => "SCHEMA_MISMATCH",
+            ErrorCode::Timeout => "TIMEOUT",
+            ErrorCode::Budget => "BUDGET",
+            ErrorCode::Io => "IO",
+            ErrorCode::Json => "JSON",
+            ErrorCode::ToolExecution => "TOOL_EXECUTION",
+            ErrorCode::ToolRegistration => "TOOL_REGISTRATION",
+            ErrorCode::SchemaLoading => "SCHEMA_LOADING",
+            ErrorCode::InvalidManifest => "INVALID_MANIFEST",
+            ErrorCode::OutputValidation => "OUTPUT_VALIDATION",
+            ErrorCode::Configuration => "CONFIGURATION",
+            ErrorCode::Initialization => "INITIALIZATION",
+            ErrorCode::ExecutionFailed => "EXECUTION_FAILED",
+            ErrorCode::ParsedResultFailed => "PARSED_RESULT_FAILED",
+            ErrorCode::RequestBuildFailed => "REQUEST_BUILD_FAILED",
+            ErrorCode::RuntimeLoadFailed => "RUNTIME_LOAD_FAILED",
+            ErrorCode::SystemTime => "SYSTEM_TIME",
+            ErrorCode::TarHeaderPath => "TAR_HEADER_PATH",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Where an error happened; unset fields are omitted when serialized
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ErrorContext {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operation: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function: Option<String>,
+}
+
+impl ErrorContext {
+    pub fn operation(operation: impl Into<String>) -> Self {
+        Self { operation: Some(operation.into()), ..Self::default() }
+    }
+
+    pub fn with_agent(mut self, agent: impl Into<String>) -> Self {
+        self.agent = Some(agent.into());
+        self
+    }
+
+    pub fn with_tool(mut self, tool: impl Into<String>) -> Self {
+        self.tool = Some(tool.into());
+        self
+    }
+
+    pub fn with_function(mut self, function: impl Into<String>) -> Self {
+        self.function = Some(function.into());
+        self
+    }
+}
+
+impl BamlRtError {
+    /// Add a context frame; the message is unchanged.
+    pub fn with_context(self, context: ErrorContext) -> Self {
+        BamlRtError::WithContext { context, inner: Box::new(self) }
+    }
+
+    /// The error under any context frames
+    pub fn root(&self) -> &BamlRtError {
+        match self {
+            BamlRtError::WithContext { inner, .. } => inner.root(),
+            other => other,
+        }
+    }
+
+    /// The error under any context frames, by value
+    pub fn into_root(self) -> BamlRtError {
+        match self {
+            BamlRtError::WithContext { inner, .. } => inner.into_root(),
+            other => other,
+        }
+    }
+
+    /// Context frames, outermost first
+    pub fn context_chain(&self) -> Vec<&ErrorContext> {
+        let mut chain = Vec::new();
+        let mut error = self;
+        while let BamlRtError::WithContext { context, inner } = error {
+            chain.push(context);
+            error = inner;
+        }
+        chain
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            BamlRtError::WithContext { inner, .. } => inner.code(),
+            BamlRtError::BamlRuntime(_) => ErrorCode::BamlRuntime,
+            BamlRtError::QuickJs(_) | BamlRtError::QuickJsWithSource { .. } => ErrorCode::QuickJs,
+            BamlRtError::JsRejection { .. } => ErrorCode::JsRejection,
+            BamlRtError::PromiseTimeout { .. } => ErrorCode::PromiseTimeout,
+            BamlRtError::ResourceLimit { .. } => ErrorCode::ResourceLimit,
+            BamlRtError::TypeConversion(_) => ErrorCode::TypeConversion,
+            BamlRtError::FunctionNotFound(_) => ErrorCode::FunctionNotFound,
+            BamlRtError::InvalidArgument(_) | BamlRtError::InvalidArgumentWithSource { .. } => {
+                ErrorCode::InvalidArgument
+            }
+            BamlRtError::ToolNotAllowed(_) => ErrorCode::ToolNotAllowed,
+            BamlRtError::SessionStateViolation(_) => ErrorCode::SessionStateViolation,
+            BamlRtError::SchemaMismatch(_) => ErrorCode::SchemaMismatch,
+            BamlRtError::Timeout(_) => ErrorCode::Timeout,
+            BamlRtError::Budget(_) => ErrorCode::Budget,
+            BamlRtError::Io(_) => ErrorCode::Io,
+            BamlRtError::Json(_) => ErrorCode::Json,
+            BamlRtError::ToolExecution(_) => ErrorCode::ToolExecution,
+            BamlRtError::ToolRegistration(_) => ErrorCode::ToolRegistration,
+            BamlRtError::SchemaLoading(_) => ErrorCode::SchemaLoading,
+            BamlRtError::InvalidManifest(_) => ErrorCode::InvalidManifest,
+            BamlRtError::OutputValidation(_) => ErrorCode::OutputValidation,
+            BamlRtError::Configuration(_) => ErrorCode::Configuration,
+            BamlRtError::Initialization(_) => ErrorCode::Initialization,
+            BamlRtError::ExecutionFailed { .. } => ErrorCode::ExecutionFailed,
+            BamlRtError::ParsedResultFailed { .. } => ErrorCode::ParsedResultFailed,
+            BamlRtError::RequestBuildFailed(_) => ErrorCode::RequestBuildFailed,
+            BamlRtError::RuntimeLoadFailed { .. } => ErrorCode::RuntimeLoadFailed,
+            BamlRtError::SystemTime(_) => ErrorCode::SystemTime,
+            BamlRtError::TarHeaderPath(_) => ErrorCode::TarHeaderPath,
+        }
+    }
+
+    /// `{"code", "message", "context"}`, with `context` only when frames were added
+    pub fn to_value(&self) -> Value {
+        let mut value = json!({
+            "code": self.code().as_str(),
+            "message": self.to_string(),
+        });
+        let chain = self.context_chain();
+        if !chain.is_empty() {
+            value["context"] = json!(chain);
+        }
+        value
+    }
+}
+
+/// Attach [`ErrorContext`] to the error of a [`Result`]
+pub trait ResultExt<T> {
+    fn with_error_context<F>(self, context: F) -> Result<T>
+    where
+        F: FnOnce() -> ErrorContext;
+}
+
+impl<T> ResultExt<T> for Result<T> {
+    fn with_error_context<F>(self, context: F) -> Result<T>
+    where
+        F: FnOnce() -> ErrorContext,
+    {
+        self.map_err(|err| err.with_context(context()))
+    }
+}
+
+/// Which QuickJS limit a script exceeded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceLimitKind {
+    Memory,
+    Stack,
+    ExecutionTime,
+}
+
+impl ResourceLimitKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResourceLimitKind::Memory => "memory",
+            ResourceLimitKind::Stack => "stack",
+            ResourceLimitKind::ExecutionTime => "execution_time",
+        }
+    }
+}
+
+impl fmt::Display for ResourceLimitKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 /// Result type alias for convenience
@@ -1,10 +1,16 @@
 //! BAML runtime core types and shared utilities.
 
+pub mod canonical;
 pub mod correlation;
 pub mod context;
 pub mod error;
 pub mod ids;
+pub mod manifest;
+pub mod package;
+pub mod tokens;
+pub mod trace_context;
 pub mod types;
 
-pub use error::{BamlRtError, Result};
+pub use error::{BamlRtError, ErrorCode, ErrorContext, ResourceLimitKind, Result, ResultExt};
+pub use trace_context::TraceContext;
 pub use ids::{AgentId, ArtifactId, ContextId, CorrelationId, EventId, MessageId, TaskId};
@@ -0,0 +1,519 @@
+//! Agent package manifest (`manifest.json`) schema.
+//!
+//! Two schema versions are accepted:
+//! - **v1** (no `manifest_version` field, or `1`): `name`, `version`, `tools`,
+//!   plus optional `description`, `entry_point`, `runtime_version` and `signature`.
+//! - **v2** (`"manifest_version": 2`): everything in v1, plus `capabilities`, a
+//!   `functions` allowlist, a `config_schema` (JSON Schema for agent config),
+//!   `signature_metadata` describing how `signature` was produced, and
+//!   `required_bundles`, the host tool bundles the agent needs with optional
+//!   semver constraints (e.g. `support>=1.2`), `task_timeout_secs`, how long
+//!   an unfinished task may sit idle before the runner fails it,
+//!   `max_concurrent_tasks`, how many of its tasks may run at once,
+//!   `health_function`, a JS function the runner's self-test calls with `{}`,
+//!   and `policies`, authorization rules checked against every tool and LLM
+//!   call the agent makes.
+//!
+//! [`AgentManifest::from_value`] checks the whole document before deserializing
+//! and reports every problem at once, each located by a JSON pointer
+//! (RFC 6901), e.g. `/tools/2: expected a string`.
+
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+pub const DEFAULT_ENTRY_POINT: &str = "dist/index.js";
+/// Newest manifest schema version this runtime understands.
+pub const CURRENT_MANIFEST_VERSION: u32 = 2;
+
+/// Fields only valid with `"manifest_version": 2`.
+const V2_FIELDS: [&str; 9] = [
+    "capabilities",
+    "functions",
+    "config_schema",
+    "signature_metadata",
+    "required_bundles",
+    "task_timeout_secs",
+    "max_concurrent_tasks",
+    "health_function",
+    "policies",
+];
+
+/// A validated agent manifest of either schema version.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentManifest {
+    #[serde(default = "default_manifest_version")]
+    pub manifest_version: u32,
+    pub name: String,
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default = "default_entry_point")]
+    pub entry_point: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub runtime_version: Option<String>,
+    /// Package identity. Written by the packager when missing from the source manifest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// Host tools the agent uses, as `bundle/tool` names.
+    pub tools: Vec<String>,
+    /// v2: features the agent relies on (e.g. `streaming`, `push_notifications`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub capabilities: Vec<String>,
+    /// v2: BAML functions the agent may invoke. `None` allows every function.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub functions: Option<Vec<String>>,
+    /// v2: JSON Schema for the agent's configuration.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_schema: Option<Value>,
+    /// v2: how `signature` was computed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature_metadata: Option<SignatureMetadata>,
+    /// v2: tool bundles the runner must provide before the agent boots.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required_bundles: Vec<BundleRequirement>,
+    /// v2: seconds an unfinished task may go without activity before it is failed.
+    /// Overrides the runner's default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_timeout_secs: Option<u64>,
+    /// v2: how many `message.send` executions may run at once; the rest queue.
+    /// Overrides the runner's default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_tasks: Option<usize>,
+    /// v2: JS function that smoke-tests the agent, called with `{}` by the
+    /// runner's `--self-test`. It passes if the call does not fail.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health_function: Option<String>,
+    /// v2: authorization rules for the agent's tool and LLM calls, checked in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub policies: Vec<PolicyRule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignatureMetadata {
+    /// Signing or digest algorithm, e.g. `ed25519` or `sha256`.
+    pub algorithm: String,
+    /// Identifier of the key in the verifier's trust store.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+    /// Digest of the archive contents the signature covers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+}
+
+/// One authorization rule. The first rule whose `when` matches a call decides it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyRule {
+    pub id: String,
+    pub effect: PolicyEffect,
+    /// Calls the rule is checked against; `None` checks it against both kinds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub applies_to: Option<PolicyTarget>,
+    /// CEL expression over the call; the rule matches when it is `true`.
+    pub when: String,
+    /// Reason reported when the rule denies a call.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyEffect {
+    Allow,
+    Deny,
+}
+
+impl PolicyEffect {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PolicyEffect::Allow => "allow",
+            PolicyEffect::Deny => "deny",
+        }
+    }
+}
+
+/// The kind of call a policy rule is checked against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyTarget {
+    Tool,
+    Llm,
+}
+
+impl PolicyTarget {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PolicyTarget::Tool => "tool",
+            PolicyTarget::Llm => "llm",
+        }
+    }
+}
+
+/// A tool bundle an agent needs, written `<bundle>[<semver requirement>]`,
+/// e.g. `support`, `support>=1.2` or `support ^1.4, <1.9`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct BundleRequirement {
+    pub bundle: String,
+    /// `None` accepts any registered version.
+    pub version: Option<VersionReq>,
+}
+
+impl BundleRequirement {
+    pub fn matches(&self, version: &Version) -> bool {
+        self.version.as_ref().is_none_or(|req| req.matches(version))
+    }
+}
+
+impl FromStr for BundleRequirement {
+    type Err = String;
+
+    fn from_str(raw: &str) -> std::result::Result<Self, Self::Err> {
+        let raw = raw.trim();
+        let split = raw
+            .find(|ch: char| ch.is_whitespace() || "<>=^~*".contains(ch))
+            .unwrap_or(raw.len());
+        let (bundle, constraint) = raw.split_at(split);
+        if bundle.is_empty() {
+            return Err("missing bundle name".to_string());
+        }
+        if bundle.contains('/') {
+            return Err(format!("bundle name \"{}\" must not contain '/'", bundle));
+        }
+        let constraint = constraint.trim();
+        let version = if constraint.is_empty() {
+            None
+        } else {
+            Some(VersionReq::parse(constraint).map_err(|err| {
+                format!("invalid version constraint \"{}\": {}", constraint, err)
+            })?)
+        };
+        Ok(Self { bundle: bundle.to_string(), version })
+    }
+}
+
+impl TryFrom<String> for BundleRequirement {
+    type Error = String;
+
+    fn try_from(raw: String) -> std::result::Result<Self, Self::Error> {
+        raw.parse()
+    }
+}
+
+impl From<BundleRequirement> for String {
+    fn from(requirement: BundleRequirement) -> Self {
+        requirement.to_string()
+    }
+}
+
+impl fmt::Display for BundleRequirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.version {
+            Some(version) => write!(f, "{}{}", self.bundle, version),
+            None => write!(f, "{}", self.bundle),
+        }
+    }
+}
+
+fn default_manifest_version() -> u32 {
+    1
+}
+
+fn default_entry_point() -> String {
+    DEFAULT_ENTRY_POINT.to_string()
+}
+
+/// One validation failure, located by a JSON pointer into the manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestIssue {
+    pub pointer: String,
+    pub message: String,
+}
+
+impl fmt::Display for ManifestIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pointer = if self.pointer.is_empty() { "/" } else { &self.pointer };
+        write!(f, "{}: {}", pointer, self.message)
+    }
+}
+
+/// Every problem found in a manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestError {
+    pub issues: Vec<ManifestIssue>,
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid {}: ", MANIFEST_FILE)?;
+        for (index, issue) in self.issues.iter().enumerate() {
+            if index > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ManifestError {}
+
+impl AgentManifest {
+    /// Parse and validate manifest JSON text.
+    pub fn parse(content: &str) -> crate::Result<Self> {
+        let value: Value = serde_json::from_str(content)?;
+        Ok(Self::from_value(&value)?)
+    }
+
+    /// Read and validate `manifest.json` from an agent or extracted package directory.
+    pub fn load(dir: &Path) -> crate::Result<Self> {
+        let content = std::fs::read_to_string(dir.join(MANIFEST_FILE))?;
+        Self::parse(&content)
+    }
+
+    /// Validate `value` against the schema version it declares and deserialize it.
+    pub fn from_value(value: &Value) -> std::result::Result<Self, ManifestError> {
+        let issues = validate(value);
+        if !issues.is_empty() {
+            return Err(ManifestError { issues });
+        }
+        serde_json::from_value(value.clone()).map_err(|err| ManifestError {
+            issues: vec![ManifestIssue { pointer: String::new(), message: err.to_string() }],
+        })
+    }
+
+    /// The package signature, required before a package can be loaded.
+    pub fn require_signature(&self) -> std::result::Result<&str, ManifestError> {
+        self.signature.as_deref().ok_or_else(|| ManifestError {
+            issues: vec![ManifestIssue {
+                pointer: pointer(&["signature"]),
+                message: "required to load a package".to_string(),
+            }],
+        })
+    }
+
+    /// Whether the `functions` allowlist permits `function`.
+    pub fn allows_function(&self, function: &str) -> bool {
+        self.functions
+            .as_ref()
+            .is_none_or(|functions| functions.iter().any(|allowed| allowed == function))
+    }
+
+    pub fn has_capability(&self, capability: &str) -> bool {
+        self.capabilities.iter().any(|declared| declared == capability)
+    }
+}
+
+/// Check `value` against the manifest schema, returning every issue found.
+pub fn validate(value: &Value) -> Vec<ManifestIssue> {
+    let mut issues = Issues::default();
+    let Some(root) = value.as_object() else {
+        issues.push(&[], "expected a JSON object");
+        return issues.0;
+    };
+
+    let version = match root.get("manifest_version") {
+        None => 1,
+        Some(raw) => match raw.as_u64() {
+            Some(version @ 1..=2) => version as u32,
+            _ => {
+                issues.push(
+                    &["manifest_version"],
+                    format!("expected 1 or {}", CURRENT_MANIFEST_VERSION),
+                );
+                return issues.0;
+            }
+        },
+    };
+
+    issues.required_string(root, &["name"]);
+    issues.required_string(root, &["version"]);
+    issues.optional_string(root, "description");
+    issues.optional_string(root, "runtime_version");
+    issues.optional_string(root, "signature");
+    if let Some(entry_point) = issues.optional_string(root, "entry_point") {
+        let path = Path::new(entry_point);
+        if path.is_absolute()
+            || path.components().any(|part| matches!(part, std::path::Component::ParentDir))
+        {
+            issues.push(&["entry_point"], "must be a relative path inside the package");
+        }
+    }
+    match root.get("tools") {
+        None => issues.push(&["tools"], "required"),
+        Some(tools) => issues.string_set("tools", tools),
+    }
+
+    if version < 2 {
+        for field in V2_FIELDS {
+            if root.contains_key(field) {
+                issues.push(&[field], "requires \"manifest_version\": 2");
+            }
+        }
+        return issues.0;
+    }
+
+    if let Some(capabilities) = root.get("capabilities") {
+        issues.string_set("capabilities", capabilities);
+    }
+    if let Some(functions) = root.get("functions") {
+        issues.string_set("functions", functions);
+    }
+    if let Some(bundles) = root.get("required_bundles") {
+        issues.string_set("required_bundles", bundles);
+        for (index, raw) in bundles.as_array().into_iter().flatten().enumerate() {
+            if let Some(Err(message)) = raw
+                .as_str()
+                .filter(|raw| !raw.trim().is_empty())
+                .map(BundleRequirement::from_str)
+            {
+                issues.push(&["required_bundles", &index.to_string()], message);
+            }
+        }
+    }
+    if let Some(timeout) = root.get("task_timeout_secs")
+        && !timeout.as_u64().is_some_and(|secs| secs > 0)
+    {
+        issues.push(&["task_timeout_secs"], "expected a positive integer");
+    }
+    if let Some(limit) = root.get("max_concurrent_tasks")
+        && !limit.as_u64().is_some_and(|limit| limit > 0)
+    {
+        issues.push(&["max_concurrent_tasks"], "expected a positive integer");
+    }
+    if root.contains_key("health_function") {
+        issues.required_string(root, &["health_function"]);
+    }
+    if let Some(policies) = root.get("policies") {
+        issues.policies(policies);
+    }
+    if let Some(schema) = root.get("config_schema")
+        && !schema.is_object()
+        && !schema.is_boolean()
+    {
+        issues.push(&["config_schema"], "expected a JSON Schema object or boolean");
+    }
+    if let Some(metadata) = root.get("signature_metadata") {
+        match metadata.as_object() {
+            Some(metadata) => {
+                issues.required_string(metadata, &["signature_metadata", "algorithm"]);
+                for field in ["key_id", "digest"] {
+                    if metadata.get(field).is_some_and(|value| !value.is_string()) {
+                        issues.push(&["signature_metadata", field], "expected a string");
+                    }
+                }
+                if !root.contains_key("signature") {
+                    issues.push(&["signature"], "required when signature_metadata is present");
+                }
+            }
+            None => issues.push(&["signature_metadata"], "expected an object"),
+        }
+    }
+    issues.0
+}
+
+/// RFC 6901 pointer to `path`.
+fn pointer(path: &[&str]) -> String {
+    path.iter()
+        .map(|segment| format!("/{}", segment.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+#[derive(Default)]
+struct Issues(Vec<ManifestIssue>);
+
+impl Issues {
+    fn push(&mut self, path: &[&str], message: impl Into<String>) {
+        self.0.push(ManifestIssue { pointer: pointer(path), message: message.into() });
+    }
+
+    /// Require a non-empty string at `path`, whose last segment is a key of `object`.
+    fn required_string(&mut self, object: &Map<String, Value>, path: &[&str]) {
+        let field = path.last().copied().unwrap_or_default();
+        match object.get(field) {
+            None => self.push(path, "required"),
+            Some(Value::String(value)) if value.trim().is_empty() => {
+                self.push(path, "must not be empty")
+            }
+            Some(Value::String(_)) => {}
+            Some(_) => self.push(path, "expected a string"),
+        }
+    }
+
+    fn optional_string<'a>(&mut self, root: &'a Map<String, Value>, field: &str) -> Option<&'a str> {
+        match root.get(field) {
+            None => None,
+            Some(Value::String(value)) => Some(value),
+            Some(_) => {
+                self.push(&[field], "expected a string");
+                None
+            }
+        }
+    }
+
+    /// An array of policy rules with unique ids.
+    fn policies(&mut self, value: &Value) {
+        let Some(rules) = value.as_array() else {
+            self.push(&["policies"], "expected an array of policy rules");
+            return;
+        };
+        let mut seen = std::collections::HashSet::new();
+        for (index, rule) in rules.iter().enumerate() {
+            let index = index.to_string();
+            let Some(rule) = rule.as_object() else {
+                self.push(&["policies", &index], "expected an object");
+                continue;
+            };
+            self.required_string(rule, &["policies", &index, "id"]);
+            if let Some(id) = rule.get("id").and_then(Value::as_str)
+                && !id.trim().is_empty()
+                && !seen.insert(id)
+            {
+                self.push(&["policies", &index, "id"], format!("duplicate policy id \"{}\"", id));
+            }
+            match rule.get("effect") {
+                None => self.push(&["policies", &index, "effect"], "required"),
+                Some(effect) if !matches!(effect.as_str(), Some("allow" | "deny")) => {
+                    self.push(&["policies", &index, "effect"], "expected \"allow\" or \"deny\"")
+                }
+                Some(_) => {}
+            }
+            if rule
+                .get("applies_to")
+                .is_some_and(|target| !matches!(target.as_str(), Some("tool" | "llm")))
+            {
+                self.push(&["policies", &index, "applies_to"], "expected \"tool\" or \"llm\"");
+            }
+            self.required_string(rule, &["policies", &index, "when"]);
+            if rule.get("message").is_some_and(|message| !message.is_string()) {
+                self.push(&["policies", &index, "message"], "expected a string");
+            }
+        }
+    }
+
+    /// An array of unique, non-empty strings.
+    fn string_set(&mut self, field: &str, value: &Value) {
+        let Some(items) = value.as_array() else {
+            self.push(&[field], "expected an array of strings");
+            return;
+        };
+        let mut seen = std::collections::HashSet::new();
+        for (index, item) in items.iter().enumerate() {
+            let index = index.to_string();
+            match item.as_str() {
+                None => self.push(&[field, &index], "expected a string"),
+                Some(item) if item.trim().is_empty() => {
+                    self.push(&[field, &index], "must not be empty")
+                }
+                Some(item) if !seen.insert(item) => {
+                    self.push(&[field, &index], format!("duplicate entry \"{}\"", item))
+                }
+                Some(_) => {}
+            }
+        }
+    }
+}
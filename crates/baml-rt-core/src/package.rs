@@ -0,0 +1,68 @@
+//! The digest agent package signatures cover.
+//!
+//! Builders sign it and runners check it, so both must hash a package the same
+//! way: every regular file under the package root, in path order, plus the
+//! manifest without its own signature fields.
+
+use crate::canonical::canonicalize;
+use crate::manifest::{AgentManifest, MANIFEST_FILE};
+use crate::{BamlRtError, Result};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+/// SHA-256 over the package contents a signature covers.
+///
+/// Each file contributes its `/`-separated path and its bytes, both length
+/// prefixed, in path order. `manifest.json` contributes the canonical JSON of
+/// `manifest` without `signature` and `signature_metadata`, which cannot cover
+/// themselves.
+pub fn package_digest(root: &Path, manifest: &AgentManifest) -> Result<[u8; 32]> {
+    let mut manifest_value = serde_json::to_value(manifest)?;
+    if let Value::Object(fields) = &mut manifest_value {
+        fields.remove("signature");
+        fields.remove("signature_metadata");
+    }
+    let mut files = Vec::new();
+    collect_files(root, "", &mut files)?;
+    files.retain(|path| path != MANIFEST_FILE);
+    files.sort();
+
+    let mut hasher = Sha256::new();
+    hash_entry(&mut hasher, MANIFEST_FILE, canonicalize(&manifest_value).as_bytes());
+    for path in files {
+        hash_entry(&mut hasher, &path, &std::fs::read(root.join(&path))?);
+    }
+    Ok(hasher.finalize().into())
+}
+
+fn hash_entry(hasher: &mut Sha256, path: &str, contents: &[u8]) {
+    hasher.update((path.len() as u64).to_be_bytes());
+    hasher.update(path.as_bytes());
+    hasher.update((contents.len() as u64).to_be_bytes());
+    hasher.update(contents);
+}
+
+/// Paths of the regular files under `dir`, relative to the package root.
+fn collect_files(dir: &Path, prefix: &str, files: &mut Vec<String>) -> Result<()> {
+    for entry in std::fs::read_dir(dir.join(prefix))? {
+        let entry = entry?;
+        let name = entry.file_name().into_string().map_err(|name| {
+            BamlRtError::InvalidArgument(format!("package path {name:?} is not UTF-8"))
+        })?;
+        let path = if prefix.is_empty() { name } else { format!("{prefix}/{name}") };
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_files(dir, &path, files)?;
+        } else if file_type.is_file() {
+            files.push(path);
+        } else {
+            // A link could point outside the package and change without the
+            // signature noticing.
+            return Err(BamlRtError::InvalidArgument(format!(
+                "package entry '{path}' is not a regular file or directory"
+            )));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,119 @@
+//! Token counting shared by the runtime, agents and tools.
+//!
+//! A [`TokenizerRegistry`] maps model families to [`TokenEstimator`]s so every
+//! caller counts the same way: agents budget context windows through
+//! `host.tokens.count(text, model)`, tools and interceptors call
+//! [`TokenizerRegistry::count`], and streamed LLM calls estimate usage with the
+//! estimator for their model. Families without a registered tokenizer fall back
+//! to [`HeuristicTokenizer`].
+
+use std::sync::{Arc, RwLock};
+
+/// Counts the tokens a model would see in a piece of text.
+pub trait TokenEstimator: Send + Sync {
+    fn count_tokens(&self, text: &str) -> u64;
+}
+
+/// Approximates BPE tokenizers without a vocabulary.
+///
+/// Text is pre-tokenized the way GPT-style tokenizers split it (letter runs,
+/// digit runs, single punctuation characters; whitespace attaches to the next
+/// piece) and each piece is charged by length: one token per four letters or
+/// three digits, rounded up. Close enough for budgets and in-flight metrics;
+/// register a real tokenizer where exact counts matter.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeuristicTokenizer;
+
+impl HeuristicTokenizer {
+    const CHARS_PER_WORD_TOKEN: u64 = 4;
+    const DIGITS_PER_TOKEN: u64 = 3;
+}
+
+impl TokenEstimator for HeuristicTokenizer {
+    fn count_tokens(&self, text: &str) -> u64 {
+        let mut tokens = 0;
+        let mut chars = text.chars().peekable();
+        while let Some(ch) = chars.next() {
+            if ch.is_whitespace() {
+                continue;
+            }
+            let (per_token, same_run): (u64, fn(char) -> bool) = if ch.is_alphabetic() {
+                (Self::CHARS_PER_WORD_TOKEN, char::is_alphabetic)
+            } else if ch.is_numeric() {
+                (Self::DIGITS_PER_TOKEN, char::is_numeric)
+            } else {
+                tokens += 1;
+                continue;
+            };
+            let mut len = 1;
+            while chars.next_if(|next| same_run(*next)).is_some() {
+                len += 1;
+            }
+            tokens += len.div_ceil(per_token);
+        }
+        tokens
+    }
+}
+
+/// The family part of a model name: provider prefixes such as `openai/` are
+/// dropped and the rest is lowercased, e.g. `openai/GPT-4o-mini` -> `gpt-4o-mini`.
+pub fn model_family(model: &str) -> String {
+    model.rsplit('/').next().unwrap_or(model).trim().to_ascii_lowercase()
+}
+
+/// Tokenizers by model family.
+///
+/// Families are matched as prefixes of [`model_family`], longest first, so a
+/// tokenizer registered for `gpt-4o` wins over one for `gpt` on `gpt-4o-mini`.
+/// Registration goes through `&self` so a registry shared with running agents
+/// can be extended in place.
+pub struct TokenizerRegistry {
+    families: RwLock<Vec<(String, Arc<dyn TokenEstimator>)>>,
+    fallback: Arc<dyn TokenEstimator>,
+}
+
+impl Default for TokenizerRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TokenizerRegistry {
+    /// A registry that counts every model with [`HeuristicTokenizer`].
+    pub fn new() -> Self {
+        Self::with_fallback(Arc::new(HeuristicTokenizer))
+    }
+
+    /// A registry that counts unregistered families with `fallback`.
+    pub fn with_fallback(fallback: Arc<dyn TokenEstimator>) -> Self {
+        Self { families: RwLock::new(Vec::new()), fallback }
+    }
+
+    /// Count models of `family` with `estimator`, replacing any earlier registration.
+    pub fn register(&self, family: &str, estimator: Arc<dyn TokenEstimator>) {
+        let family = model_family(family);
+        let mut families = self.families.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        families.retain(|(registered, _)| *registered != family);
+        families.push((family, estimator));
+        families.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()));
+    }
+
+    /// The estimator for `model`; the fallback when `model` is `None` or unregistered.
+    pub fn estimator_for(&self, model: Option<&str>) -> Arc<dyn TokenEstimator> {
+        let Some(model) = model else {
+            return self.fallback.clone();
+        };
+        let family = model_family(model);
+        let families = self.families.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+        families
+            .iter()
+            .find(|(registered, _)| family.starts_with(registered.as_str()))
+            .map(|(_, estimator)| estimator.clone())
+            .unwrap_or_else(|| self.fallback.clone())
+    }
+
+    /// Tokens in `text` as `model` would count them.
+    pub fn count(&self, text: &str, model: Option<&str>) -> u64 {
+        self.estimator_for(model).count_tokens(text)
+    }
+}
@@ -0,0 +1,100 @@
+//! W3C Trace Context carried across A2A calls.
+//!
+//! A caller puts its `traceparent` (and optional `tracestate`) in the metadata
+//! of the message it sends; the receiving agent parents its spans on it and
+//! passes it on, so a chain of agents calling each other shows up as one
+//! distributed trace. See <https://www.w3.org/TR/trace-context/>.
+
+use serde_json::{Map, Value};
+
+pub const TRACEPARENT_METADATA_KEY: &str = "traceparent";
+pub const TRACESTATE_METADATA_KEY: &str = "tracestate";
+
+/// A validated `traceparent` with its `tracestate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceContext {
+    traceparent: String,
+    tracestate: Option<String>,
+}
+
+impl TraceContext {
+    /// Parse a `traceparent` header value.
+    ///
+    /// Returns `None` for malformed values and all-zero ids, which the spec
+    /// says to ignore rather than reject the request over.
+    pub fn parse(traceparent: &str, tracestate: Option<&str>) -> Option<Self> {
+        let traceparent = traceparent.trim();
+        let fields: Vec<&str> = traceparent.split('-').collect();
+        let [version, trace_id, parent_id, flags, rest @ ..] = fields.as_slice() else {
+            return None;
+        };
+        let valid = is_lower_hex(version, 2)
+            && *version != "ff"
+            // Version 00 has exactly four fields; later versions may append more.
+            && (rest.is_empty() || *version != "00")
+            && is_lower_hex(trace_id, 32)
+            && is_lower_hex(parent_id, 16)
+            && is_lower_hex(flags, 2)
+            && trace_id.bytes().any(|b| b != b'0')
+            && parent_id.bytes().any(|b| b != b'0');
+        valid.then(|| Self {
+            traceparent: traceparent.to_string(),
+            tracestate: tracestate
+                .map(str::trim)
+                .filter(|state| !state.is_empty())
+                .map(str::to_string),
+        })
+    }
+
+    /// The trace context in the `traceparent`/`tracestate` entries of `metadata`.
+    pub fn from_metadata(metadata: &Map<String, Value>) -> Option<Self> {
+        let traceparent = metadata.get(TRACEPARENT_METADATA_KEY)?.as_str()?;
+        let tracestate = metadata.get(TRACESTATE_METADATA_KEY).and_then(Value::as_str);
+        Self::parse(traceparent, tracestate)
+    }
+
+    pub fn traceparent(&self) -> &str {
+        &self.traceparent
+    }
+
+    pub fn tracestate(&self) -> Option<&str> {
+        self.tracestate.as_deref()
+    }
+
+    /// Hex id of the distributed trace.
+    pub fn trace_id(&self) -> &str {
+        &self.traceparent[3..35]
+    }
+
+    /// Hex id of the caller's span.
+    pub fn parent_id(&self) -> &str {
+        &self.traceparent[36..52]
+    }
+
+    pub fn sampled(&self) -> bool {
+        u8::from_str_radix(&self.traceparent[53..55], 16).is_ok_and(|flags| flags & 1 == 1)
+    }
+
+    /// Write this context into `metadata`, replacing any earlier one.
+    pub fn insert_into(&self, metadata: &mut Map<String, Value>) {
+        metadata.insert(
+            TRACEPARENT_METADATA_KEY.to_string(),
+            Value::String(self.traceparent.clone()),
+        );
+        match &self.tracestate {
+            Some(tracestate) => {
+                metadata.insert(
+                    TRACESTATE_METADATA_KEY.to_string(),
+                    Value::String(tracestate.clone()),
+                );
+            }
+            None => {
+                metadata.remove(TRACESTATE_METADATA_KEY);
+            }
+        }
+    }
+}
+
+fn is_lower_hex(field: &str, len: usize) -> bool {
+    field.len() == len && field.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
@@ -1,15 +1,48 @@
 //! Type definitions for BAML runtime integration
 
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::fmt;
 
 /// Represents a BAML function signature
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FunctionSignature {
     pub name: String,
-    pub input_types: Vec<BamlType>,
+    /// Parameters in declaration order.
+    pub parameters: Vec<ObjectField>,
     pub output_type: BamlType,
 }
 
+impl FunctionSignature {
+    /// Check invocation `args`, an object keyed by parameter name, against the
+    /// declared parameters.
+    pub fn check_args(&self, args: &Value) -> Vec<TypeMismatch> {
+        self.input_type().check(args)
+    }
+
+    /// JSON Schemas for the function's arguments object and its output.
+    pub fn schema(&self) -> FunctionSchema {
+        FunctionSchema {
+            name: self.name.clone(),
+            input_schema: self.input_type().json_schema(),
+            output_schema: self.output_type.json_schema(),
+        }
+    }
+
+    fn input_type(&self) -> BamlType {
+        BamlType::Object(self.parameters.clone())
+    }
+}
+
+/// A BAML function's arguments and output as JSON Schema (draft 2020-12), for
+/// callers that build forms or validate payloads before invoking it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionSchema {
+    pub name: String,
+    pub input_schema: Value,
+    pub output_schema: Value,
+}
+
 /// Represents a BAML type
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum BamlType {
@@ -17,11 +50,17 @@ pub enum BamlType {
     Int,
     Float,
     Bool,
+    Null,
     List(Box<BamlType>),
     Map(Box<BamlType>, Box<BamlType>), // key type, value type
     Object(Vec<ObjectField>),
     Optional(Box<BamlType>),
-    // TODO: Add more types as needed
+    /// One of the listed enum values.
+    Enum(Vec<String>),
+    /// Any of the member types.
+    Union(Vec<BamlType>),
+    /// Not known to the runtime; every value matches.
+    Any,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,3 +69,269 @@ pub struct ObjectField {
     pub ty: BamlType,
 }
 
+impl BamlType {
+    /// Check `value` against this type, returning every mismatch found.
+    ///
+    /// Object fields must be present unless their type accepts `null`; fields the
+    /// type does not declare are ignored. A union reports the mismatches of the
+    /// member that came closest to matching.
+    pub fn check(&self, value: &Value) -> Vec<TypeMismatch> {
+        let mut mismatches = Vec::new();
+        self.check_at("", value, &mut mismatches);
+        mismatches
+    }
+
+    /// This type as a JSON Schema. Object fields that do not accept `null` are
+    /// required; [`BamlType::Any`] is the schema every value matches.
+    pub fn json_schema(&self) -> Value {
+        match self {
+            BamlType::String => json!({"type": "string"}),
+            BamlType::Int => json!({"type": "integer"}),
+            BamlType::Float => json!({"type": "number"}),
+            BamlType::Bool => json!({"type": "boolean"}),
+            BamlType::Null => json!({"type": "null"}),
+            BamlType::List(item) => json!({"type": "array", "items": item.json_schema()}),
+            BamlType::Map(_, value) => {
+                json!({"type": "object", "additionalProperties": value.json_schema()})
+            }
+            BamlType::Object(fields) => {
+                let properties: Map<String, Value> = fields
+                    .iter()
+                    .map(|field| (field.name.clone(), field.ty.json_schema()))
+                    .collect();
+                let required: Vec<&str> = fields
+                    .iter()
+                    .filter(|field| !field.ty.accepts_null())
+                    .map(|field| field.name.as_str())
+                    .collect();
+                json!({"type": "object", "properties": properties, "required": required})
+            }
+            BamlType::Optional(inner) => {
+                json!({"anyOf": [inner.json_schema(), {"type": "null"}]})
+            }
+            BamlType::Enum(values) => json!({"type": "string", "enum": values}),
+            BamlType::Union(members) => {
+                let members: Vec<Value> = members.iter().map(BamlType::json_schema).collect();
+                json!({"anyOf": members})
+            }
+            BamlType::Any => json!({}),
+        }
+    }
+
+    pub fn accepts_null(&self) -> bool {
+        match self {
+            BamlType::Null | BamlType::Optional(_) | BamlType::Any => true,
+            BamlType::Union(members) => members.iter().any(BamlType::accepts_null),
+            _ => false,
+        }
+    }
+
+    fn check_at(&self, pointer: &str, value: &Value, mismatches: &mut Vec<TypeMismatch>) {
+        let matches = match (self, value) {
+            (BamlType::Any, _) => true,
+            (BamlType::String, Value::String(_)) => true,
+            (BamlType::Int, Value::Number(number)) => number.is_i64() || number.is_u64(),
+            (BamlType::Float, Value::Number(_)) => true,
+            (BamlType::Bool, Value::Bool(_)) => true,
+            (BamlType::Null, Value::Null) => true,
+            (BamlType::Optional(_), Value::Null) => true,
+            (BamlType::Optional(inner), _) => return inner.check_at(pointer, value, mismatches),
+            (BamlType::Enum(values), Value::String(variant)) => values.contains(variant),
+            (BamlType::List(item), Value::Array(items)) => {
+                for (index, element) in items.iter().enumerate() {
+                    item.check_at(&child(pointer, &index.to_string()), element, mismatches);
+                }
+                true
+            }
+            (BamlType::Map(_, entry), Value::Object(map)) => {
+                for (key, element) in map {
+                    entry.check_at(&child(pointer, key), element, mismatches);
+                }
+                true
+            }
+            (BamlType::Object(fields), Value::Object(map)) => {
+                for field in fields {
+                    let path = child(pointer, &field.name);
+                    match map.get(&field.name) {
+                        Some(element) => field.ty.check_at(&path, element, mismatches),
+                        None if field.ty.accepts_null() => {}
+                        None => mismatches.push(TypeMismatch {
+                            pointer: path,
+                            message: format!("missing required field of type {}", field.ty),
+                        }),
+                    }
+                }
+                true
+            }
+            (BamlType::Union(members), _) => {
+                let closest = members.iter().map(|member| member.check(value)).min_by_key(Vec::len);
+                match closest {
+                    Some(found) if found.is_empty() => true,
+                    // Nested mismatches are more useful than "expected A | B" when a
+                    // member matched at this level but not below it.
+                    Some(found) if found.iter().all(|mismatch| !mismatch.pointer.is_empty()) => {
+                        mismatches.extend(found.into_iter().map(|mismatch| TypeMismatch {
+                            pointer: format!("{}{}", pointer, mismatch.pointer),
+                            message: mismatch.message,
+                        }));
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            _ => false,
+        };
+        if !matches {
+            mismatches.push(TypeMismatch {
+                pointer: pointer.to_string(),
+                message: format!("expected {}, found {}", self, describe_value(value)),
+            });
+        }
+    }
+}
+
+impl fmt::Display for BamlType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BamlType::String => write!(f, "string"),
+            BamlType::Int => write!(f, "int"),
+            BamlType::Float => write!(f, "float"),
+            BamlType::Bool => write!(f, "bool"),
+            BamlType::Null => write!(f, "null"),
+            BamlType::List(item) => write!(f, "{}[]", item),
+            BamlType::Map(key, value) => write!(f, "map<{}, {}>", key, value),
+            BamlType::Object(fields) => {
+                write!(f, "{{ ")?;
+                for (index, field) in fields.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", field.name, field.ty)?;
+                }
+                write!(f, " }}")
+            }
+            BamlType::Optional(inner) => write!(f, "{}?", inner),
+            BamlType::Enum(values) => {
+                let quoted: Vec<String> =
+                    values.iter().map(|value| format!("\"{}\"", value)).collect();
+                write!(f, "{}", quoted.join(" | "))
+            }
+            BamlType::Union(members) => {
+                let members: Vec<String> = members.iter().map(ToString::to_string).collect();
+                write!(f, "{}", members.join(" | "))
+            }
+            BamlType::Any => write!(f, "any"),
+        }
+    }
+}
+
+/// One place where a value does not match its declared type, located by a JSON
+/// pointer into the value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeMismatch {
+    pub pointer: String,
+    pub message: String,
+}
+
+impl fmt::Display for TypeMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pointer = if self.pointer.is_empty() { "/" } else { &self.pointer };
+        write!(f, "{}: {}", pointer, self.message)
+    }
+}
+
+/// A BAML function returned a value that does not match its declared output type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputValidationError {
+    pub function: String,
+    pub value: Value,
+    pub mismatches: Vec<TypeMismatch>,
+}
+
+impl OutputValidationError {
+    /// The mismatches phrased as a follow-up prompt, for asking the model to
+    /// correct its answer.
+    pub fn feedback(&self) -> String {
+        let mut feedback =
+            String::from("Your previous answer did not match the required output type:\n");
+        for mismatch in &self.mismatches {
+            feedback.push_str(&format!("- {}\n", mismatch));
+        }
+        feedback.push_str("Answer again with output that matches the type exactly.");
+        feedback
+    }
+}
+
+impl fmt::Display for OutputValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "output of {} does not match its declared type: ", self.function)?;
+        for (index, mismatch) in self.mismatches.iter().enumerate() {
+            if index > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", mismatch)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for OutputValidationError {}
+
+/// How often to re-invoke a function whose output failed validation.
+///
+/// Each retry passes [`OutputValidationError::feedback`] in the argument named
+/// `feedback_argument`; a function sees it by declaring that parameter as an
+/// optional string and rendering it in its prompt. Functions that do not declare
+/// it are simply asked again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputRepair {
+    /// Attempts including the first call; `1` never retries.
+    pub max_attempts: u32,
+    pub feedback_argument: String,
+}
+
+impl OutputRepair {
+    pub const DEFAULT_FEEDBACK_ARGUMENT: &'static str = "validation_feedback";
+
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            feedback_argument: Self::DEFAULT_FEEDBACK_ARGUMENT.to_string(),
+        }
+    }
+
+    pub fn with_feedback_argument(mut self, name: impl Into<String>) -> Self {
+        self.feedback_argument = name.into();
+        self
+    }
+
+    /// Whether a call that failed validation on `attempt` (1-based) is retried.
+    pub fn should_retry(&self, attempt: u32) -> bool {
+        attempt < self.max_attempts
+    }
+
+    /// `args` with the feedback for `error` added under the feedback argument.
+    pub fn repair_args(&self, args: &Value, error: &OutputValidationError) -> Value {
+        let mut args = args.clone();
+        if let Value::Object(map) = &mut args {
+            map.insert(self.feedback_argument.clone(), Value::String(error.feedback()));
+        }
+        args
+    }
+}
+
+fn child(pointer: &str, segment: &str) -> String {
+    format!("{}/{}", pointer, segment.replace('~', "~0").replace('/', "~1"))
+}
+
+fn describe_value(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(number) if number.is_f64() => "float",
+        Value::Number(_) => "int",
+        Value::String(_) => "string",
+        Value::Array(_) => "list",
+        Value::Object(_) => "object",
+    }
+}
@@ -0,0 +1,90 @@
+use baml_rt_core::canonical::{
+    canonicalize, canonicalize_with, digest, digest_hex, CanonicalOptions, Whitespace,
+};
+use serde_json::{json, Value};
+
+#[test]
+fn scalars_encode_like_json() {
+    assert_eq!(canonicalize(&Value::Null), "null");
+    assert_eq!(canonicalize(&json!(true)), "true");
+    assert_eq!(canonicalize(&json!(false)), "false");
+    assert_eq!(canonicalize(&json!(42)), "42");
+    assert_eq!(canonicalize(&json!(-7)), "-7");
+    assert_eq!(canonicalize(&json!(u64::MAX)), "18446744073709551615");
+    assert_eq!(canonicalize(&json!("hi")), "\"hi\"");
+}
+
+#[test]
+fn object_keys_are_sorted_recursively() {
+    let value = json!({ "b": 1, "a": { "z": [ { "y": 1, "x": 2 } ], "c": null } });
+    assert_eq!(
+        canonicalize(&value),
+        r#"{"a":{"c":null,"z":[{"x":2,"y":1}]},"b":1}"#
+    );
+}
+
+#[test]
+fn keys_sort_by_bytes_not_locale() {
+    let value = json!({ "b": 0, "B": 0, "a": 0, "é": 0, "_": 0 });
+    assert_eq!(canonicalize(&value), r#"{"B":0,"_":0,"a":0,"b":0,"é":0}"#);
+}
+
+#[test]
+fn array_order_is_preserved() {
+    assert_eq!(canonicalize(&json!([3, 1, 2])), "[3,1,2]");
+}
+
+#[test]
+fn integral_floats_normalize_to_integers() {
+    assert_eq!(canonicalize(&json!(1.0)), "1");
+    assert_eq!(canonicalize(&json!(-2.0)), "-2");
+    assert_eq!(canonicalize(&json!(-0.0)), "0");
+    assert_eq!(canonicalize(&json!(1.5)), "1.5");
+    assert_eq!(digest(&json!({ "n": 1 })), digest(&json!({ "n": 1.0 })));
+}
+
+#[test]
+fn large_floats_keep_float_form() {
+    let encoded = canonicalize(&json!(1e300));
+    assert!(encoded.contains('e'), "unexpected encoding {encoded}");
+}
+
+#[test]
+fn strings_are_escaped() {
+    assert_eq!(canonicalize(&json!("a\"b\\c\n")), r#""a\"b\\c\n""#);
+    assert_eq!(canonicalize(&json!({ "k\"": 1 })), r#"{"k\"":1}"#);
+}
+
+#[test]
+fn empty_containers_have_no_whitespace() {
+    let pretty = CanonicalOptions { whitespace: Whitespace::Pretty };
+    assert_eq!(canonicalize_with(&json!({}), pretty), "{}");
+    assert_eq!(canonicalize_with(&json!([]), pretty), "[]");
+}
+
+#[test]
+fn pretty_output_uses_fixed_indentation() {
+    let pretty = CanonicalOptions { whitespace: Whitespace::Pretty };
+    let value = json!({ "b": [1, 2], "a": "x" });
+    assert_eq!(
+        canonicalize_with(&value, pretty),
+        "{\n  \"a\": \"x\",\n  \"b\": [\n    1,\n    2\n  ]\n}"
+    );
+}
+
+#[test]
+fn digest_ignores_key_insertion_order() {
+    let first: Value = serde_json::from_str(r#"{"a":1,"b":{"c":2,"d":3}}"#).expect("json");
+    let second: Value = serde_json::from_str(r#"{"b":{"d":3,"c":2},"a":1}"#).expect("json");
+    assert_eq!(digest(&first), digest(&second));
+    assert_ne!(digest(&first), digest(&json!({ "a": 2 })));
+}
+
+#[test]
+fn digest_is_stable_across_runs() {
+    // SHA-256 of "{}", pinned so accidental algorithm changes are caught.
+    assert_eq!(
+        digest_hex(&json!({})),
+        "44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a"
+    );
+}
@@ -0,0 +1,62 @@
+//! Stable error codes and context frames on `BamlRtError`
+
+use baml_rt_core::{BamlRtError, ErrorCode, ErrorContext, Result, ResultExt};
+use serde_json::json;
+
+fn failing_tool() -> Result<()> {
+    Err(BamlRtError::ToolExecution("disk full".to_string()))
+}
+
+#[test]
+fn context_frames_keep_the_message_and_code() {
+    let err = failing_tool()
+        .with_error_context(|| ErrorContext::operation("tool_call").with_tool("fs/write"))
+        .with_error_context(|| ErrorContext::operation("message.send").with_agent("agent-a"))
+        .unwrap_err();
+
+    assert_eq!(err.to_string(), "Tool execution error: disk full");
+    assert_eq!(err.code(), ErrorCode::ToolExecution);
+    assert!(matches!(err.root(), BamlRtError::ToolExecution(_)));
+    let chain = err.context_chain();
+    assert_eq!(chain[0].operation.as_deref(), Some("message.send"));
+    assert_eq!(chain[1].tool.as_deref(), Some("fs/write"));
+
+    assert_eq!(
+        err.to_value(),
+        json!({
+            "code": "TOOL_EXECUTION",
+            "message": "Tool execution error: disk full",
+            "context": [
+                { "operation": "message.send", "agent": "agent-a" },
+                { "operation": "tool_call", "tool": "fs/write" }
+            ]
+        })
+    );
+    assert!(matches!(err.into_root(), BamlRtError::ToolExecution(message) if message == "disk full"));
+}
+
+#[test]
+fn errors_without_context_have_no_context_key() {
+    let err = BamlRtError::InvalidArgumentWithSource {
+        message: "bad input".to_string(),
+        source: "nope".into(),
+    };
+    assert_eq!(err.code().as_str(), "INVALID_ARGUMENT");
+    assert_eq!(err.to_value(), json!({ "code": "INVALID_ARGUMENT", "message": "bad input" }));
+}
+
+#[test]
+fn refusals_have_their_own_codes() {
+    let errors = [
+        (BamlRtError::ToolNotAllowed("fs/write".to_string()), "TOOL_NOT_ALLOWED"),
+        (BamlRtError::SessionStateViolation("send before open".to_string()), "SESSION_STATE_VIOLATION"),
+        (BamlRtError::SchemaMismatch("missing field `path`".to_string()), "SCHEMA_MISMATCH"),
+        (BamlRtError::Timeout("tool session step".to_string()), "TIMEOUT"),
+        (BamlRtError::Budget("100 of 100 total_tokens".to_string()), "BUDGET"),
+    ];
+    for (err, code) in errors {
+        let err = err.with_context(ErrorContext::operation("tool_call"));
+        assert_eq!(err.code().as_str(), code);
+        assert_ne!(err.code(), ErrorCode::InvalidArgument);
+    }
+}
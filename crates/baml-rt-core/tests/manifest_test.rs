@@ -0,0 +1,190 @@
+use baml_rt_core::manifest::{
+    validate, AgentManifest, ManifestIssue, PolicyEffect, PolicyTarget, DEFAULT_ENTRY_POINT,
+};
+use baml_rt_core::BamlRtError;
+use serde_json::json;
+
+fn issue(pointer: &str, message: &str) -> ManifestIssue {
+    ManifestIssue { pointer: pointer.to_string(), message: message.to_string() }
+}
+
+#[test]
+fn v1_manifest_parses_with_defaults() {
+    let manifest = AgentManifest::from_value(&json!({
+        "version": "1.0.0",
+        "name": "voidship-rites",
+        "description": "fixture",
+        "runtime_version": "0.1.0",
+        "tools": ["support/calculate"]
+    }))
+    .expect("valid v1 manifest");
+
+    assert_eq!(manifest.manifest_version, 1);
+    assert_eq!(manifest.entry_point, DEFAULT_ENTRY_POINT);
+    assert_eq!(manifest.tools, vec!["support/calculate"]);
+    assert!(manifest.signature.is_none());
+    assert!(manifest.require_signature().is_err());
+    assert!(manifest.allows_function("AnyFunction"));
+}
+
+#[test]
+fn v2_manifest_parses_new_fields() {
+    let manifest = AgentManifest::from_value(&json!({
+        "manifest_version": 2,
+        "version": "2.0.0",
+        "name": "agent",
+        "signature": "abc123",
+        "tools": [],
+        "capabilities": ["streaming"],
+        "functions": ["Greet"],
+        "config_schema": {"type": "object"},
+        "signature_metadata": {"algorithm": "ed25519", "key_id": "release"},
+        "task_timeout_secs": 600,
+        "max_concurrent_tasks": 4,
+        "health_function": "healthCheck"
+    }))
+    .expect("valid v2 manifest");
+
+    assert_eq!(manifest.manifest_version, 2);
+    assert!(manifest.has_capability("streaming"));
+    assert!(manifest.allows_function("Greet"));
+    assert!(!manifest.allows_function("Other"));
+    assert_eq!(manifest.require_signature().unwrap(), "abc123");
+    assert_eq!(manifest.signature_metadata.unwrap().key_id.as_deref(), Some("release"));
+    assert_eq!(manifest.task_timeout_secs, Some(600));
+    assert_eq!(manifest.max_concurrent_tasks, Some(4));
+    assert_eq!(manifest.health_function.as_deref(), Some("healthCheck"));
+}
+
+#[test]
+fn validation_reports_every_issue_with_pointers() {
+    let issues = validate(&json!({
+        "version": 3,
+        "name": "",
+        "entry_point": "../escape.js",
+        "tools": ["support/calculate", 7, "support/calculate"],
+        "capabilities": ["streaming"]
+    }));
+
+    assert_eq!(
+        issues,
+        vec![
+            issue("/name", "must not be empty"),
+            issue("/version", "expected a string"),
+            issue("/entry_point", "must be a relative path inside the package"),
+            issue("/tools/1", "expected a string"),
+            issue("/tools/2", "duplicate entry \"support/calculate\""),
+            issue("/capabilities", "requires \"manifest_version\": 2"),
+        ]
+    );
+}
+
+#[test]
+fn v2_validation_checks_nested_fields() {
+    let issues = validate(&json!({
+        "manifest_version": 2,
+        "version": "1.0.0",
+        "name": "agent",
+        "tools": [],
+        "config_schema": "not a schema",
+        "signature_metadata": {"key_id": 1},
+        "task_timeout_secs": 0,
+        "max_concurrent_tasks": -1,
+        "health_function": ""
+    }));
+
+    assert_eq!(
+        issues,
+        vec![
+            issue("/task_timeout_secs", "expected a positive integer"),
+            issue("/max_concurrent_tasks", "expected a positive integer"),
+            issue("/health_function", "must not be empty"),
+            issue("/config_schema", "expected a JSON Schema object or boolean"),
+            issue("/signature_metadata/algorithm", "required"),
+            issue("/signature_metadata/key_id", "expected a string"),
+            issue("/signature", "required when signature_metadata is present"),
+        ]
+    );
+}
+
+#[test]
+fn unknown_manifest_version_is_rejected() {
+    let err = AgentManifest::parse(r#"{"manifest_version": 9, "name": "a", "version": "1", "tools": []}"#)
+        .expect_err("unsupported version");
+    assert!(matches!(err, BamlRtError::InvalidManifest(_)));
+    assert_eq!(err.to_string(), "invalid manifest.json: /manifest_version: expected 1 or 2");
+}
+
+#[test]
+fn required_bundles_parse_and_validate() {
+    let manifest = AgentManifest::from_value(&json!({
+        "manifest_version": 2,
+        "version": "1.0.0",
+        "name": "agent",
+        "tools": ["support/calculate"],
+        "required_bundles": ["support>=1.2", "a2a"]
+    }))
+    .expect("valid requirements");
+    let support = &manifest.required_bundles[0];
+    assert_eq!(support.bundle, "support");
+    assert!(support.matches(&semver::Version::new(1, 2, 0)));
+    assert!(!support.matches(&semver::Version::new(1, 1, 9)));
+    assert!(manifest.required_bundles[1].version.is_none());
+    assert_eq!(support.to_string(), "support>=1.2");
+
+    let issues = validate(&json!({
+        "manifest_version": 2,
+        "version": "1.0.0",
+        "name": "agent",
+        "tools": [],
+        "required_bundles": ["support>=one", ">=1.0"]
+    }));
+    assert_eq!(issues.len(), 2);
+    assert_eq!(issues[0].pointer, "/required_bundles/0");
+    assert!(issues[0].message.starts_with("invalid version constraint \">=one\""));
+    assert_eq!(issues[1], issue("/required_bundles/1", "missing bundle name"));
+}
+
+#[test]
+fn policies_parse_and_validate() {
+    let manifest = AgentManifest::from_value(&json!({
+        "manifest_version": 2,
+        "version": "1.0.0",
+        "name": "agent",
+        "tools": ["fs/write"],
+        "policies": [{
+            "id": "writes-stay-in-tmp",
+            "effect": "deny",
+            "applies_to": "tool",
+            "when": "call.name == 'fs/write' && !call.args.path.startsWith('/tmp/')",
+            "message": "fs/write is limited to /tmp"
+        }]
+    }))
+    .expect("valid policies");
+    let rule = &manifest.policies[0];
+    assert_eq!(rule.effect, PolicyEffect::Deny);
+    assert_eq!(rule.applies_to, Some(PolicyTarget::Tool));
+    assert_eq!(rule.message.as_deref(), Some("fs/write is limited to /tmp"));
+
+    let issues = validate(&json!({
+        "manifest_version": 2,
+        "version": "1.0.0",
+        "name": "agent",
+        "tools": [],
+        "policies": [
+            {"id": "a", "effect": "allow", "when": "true"},
+            {"id": "a", "effect": "permit", "applies_to": "agent", "when": ""},
+            "deny everything"
+        ]
+    }));
+    assert_eq!(
+        issues,
+        vec![
+            issue("/policies/1/id", "duplicate policy id \"a\""),
+            issue("/policies/1/effect", "expected \"allow\" or \"deny\""),
+            issue("/policies/1/applies_to", "expected \"tool\" or \"llm\""),
+            issue("/policies/1/when", "must not be empty"),
+            issue("/policies/2", "expected an object"),
+        ]
+    );
+}
@@ -0,0 +1,42 @@
+use baml_rt_core::tokens::{HeuristicTokenizer, TokenEstimator, TokenizerRegistry, model_family};
+use std::sync::Arc;
+
+struct FixedTokenizer(u64);
+
+impl TokenEstimator for FixedTokenizer {
+    fn count_tokens(&self, _text: &str) -> u64 {
+        self.0
+    }
+}
+
+#[test]
+fn heuristic_tokenizer_charges_runs_by_length() {
+    let tokenizer = HeuristicTokenizer;
+    assert_eq!(tokenizer.count_tokens(""), 0);
+    assert_eq!(tokenizer.count_tokens("   "), 0);
+    // "hello" -> 2, "," -> 1, "world" -> 2
+    assert_eq!(tokenizer.count_tokens("hello, world"), 5);
+    // "2026" -> 2, "-" -> 1, "10" -> 1
+    assert_eq!(tokenizer.count_tokens("2026-10"), 4);
+}
+
+#[test]
+fn model_family_drops_the_provider_prefix() {
+    assert_eq!(model_family("openai/GPT-4o-mini"), "gpt-4o-mini");
+    assert_eq!(model_family("claude-3-5-sonnet"), "claude-3-5-sonnet");
+}
+
+#[test]
+fn the_longest_registered_family_wins() {
+    let tokenizers = TokenizerRegistry::new();
+    tokenizers.register("gpt", Arc::new(FixedTokenizer(1)));
+    tokenizers.register("gpt-4o", Arc::new(FixedTokenizer(2)));
+
+    assert_eq!(tokenizers.count("hello", Some("openai/gpt-4o-mini")), 2);
+    assert_eq!(tokenizers.count("hello", Some("gpt-3.5-turbo")), 1);
+    assert_eq!(tokenizers.count("hello", Some("claude-3-5-sonnet")), 2, "heuristic fallback");
+    assert_eq!(tokenizers.count("hello", None), 2, "heuristic fallback");
+
+    tokenizers.register("GPT-4o", Arc::new(FixedTokenizer(3)));
+    assert_eq!(tokenizers.count("hello", Some("gpt-4o")), 3, "re-registering replaces");
+}
@@ -0,0 +1,155 @@
+use baml_rt_core::BamlRtError;
+use baml_rt_core::types::{
+    BamlType, FunctionSignature, ObjectField, OutputRepair, OutputValidationError, TypeMismatch,
+};
+use serde_json::json;
+
+fn field(name: &str, ty: BamlType) -> ObjectField {
+    ObjectField { name: name.to_string(), ty }
+}
+
+fn review() -> BamlType {
+    BamlType::Object(vec![
+        field("title", BamlType::String),
+        field("score", BamlType::Int),
+        field("tags", BamlType::List(Box::new(BamlType::String))),
+        field("note", BamlType::Optional(Box::new(BamlType::String))),
+        field("verdict", BamlType::Enum(vec!["Accept".to_string(), "Reject".to_string()])),
+    ])
+}
+
+fn mismatch(pointer: &str, message: &str) -> TypeMismatch {
+    TypeMismatch { pointer: pointer.to_string(), message: message.to_string() }
+}
+
+#[test]
+fn matching_values_have_no_mismatches() {
+    let value = json!({"title": "Good", "score": 4, "tags": ["a"], "verdict": "Accept"});
+    assert!(review().check(&value).is_empty());
+    assert!(BamlType::Any.check(&json!(null)).is_empty());
+    assert!(BamlType::Float.check(&json!(3)).is_empty());
+}
+
+#[test]
+fn every_mismatch_is_located_by_pointer() {
+    let value = json!({"score": 4.5, "tags": ["a", 2], "verdict": "Maybe"});
+    assert_eq!(
+        review().check(&value),
+        vec![
+            mismatch("/title", "missing required field of type string"),
+            mismatch("/score", "expected int, found float"),
+            mismatch("/tags/1", "expected string, found int"),
+            mismatch("/verdict", "expected \"Accept\" | \"Reject\", found string"),
+        ]
+    );
+}
+
+#[test]
+fn unions_report_the_closest_member() {
+    let answer = BamlType::Union(vec![
+        BamlType::Object(vec![
+            field("tool_name", BamlType::String),
+            field("query", BamlType::String),
+        ]),
+        BamlType::Object(vec![field("reply", BamlType::String)]),
+    ]);
+    assert!(answer.check(&json!({"reply": "hi"})).is_empty());
+    assert_eq!(
+        answer.check(&json!({"tool_name": "search", "query": 3, "reply": "hi"})),
+        Vec::<TypeMismatch>::new()
+    );
+    assert_eq!(
+        answer.check(&json!({"tool_name": "search", "query": 3})),
+        vec![mismatch("/query", "expected string, found int")]
+    );
+    assert_eq!(
+        answer.check(&json!("hi")),
+        vec![mismatch(
+            "",
+            "expected { tool_name: string, query: string } | { reply: string }, found string"
+        )]
+    );
+}
+
+#[test]
+fn output_validation_errors_carry_feedback_for_the_model() {
+    let error = OutputValidationError {
+        function: "Review".to_string(),
+        value: json!({"score": "high"}),
+        mismatches: vec![mismatch("/score", "expected int, found string")],
+    };
+    assert!(error.feedback().contains("- /score: expected int, found string"));
+
+    let error: BamlRtError = error.into();
+    assert_eq!(
+        error.to_string(),
+        "output of Review does not match its declared type: /score: expected int, found string"
+    );
+}
+
+#[test]
+fn output_repair_passes_feedback_to_bounded_retries() {
+    let repair = OutputRepair::new(2);
+    assert!(repair.should_retry(1));
+    assert!(!repair.should_retry(2));
+    assert!(!OutputRepair::new(0).should_retry(1));
+
+    let error = OutputValidationError {
+        function: "Review".to_string(),
+        value: json!({}),
+        mismatches: vec![mismatch("/title", "missing required field of type string")],
+    };
+    let args = repair.with_feedback_argument("hint").repair_args(&json!({"text": "x"}), &error);
+    assert_eq!(args["text"], json!("x"));
+    assert_eq!(args["hint"], json!(error.feedback()));
+}
+
+#[test]
+fn function_arguments_are_checked_against_parameters() {
+    let signature = FunctionSignature {
+        name: "Greet".to_string(),
+        parameters: vec![
+            field("name", BamlType::String),
+            field("tone", BamlType::Optional(Box::new(BamlType::String))),
+        ],
+        output_type: BamlType::String,
+    };
+    assert!(signature.check_args(&json!({"name": "Ada"})).is_empty());
+    assert!(signature.check_args(&json!({"name": "Ada", "extra": 1})).is_empty());
+    assert_eq!(
+        signature.check_args(&json!({"tone": 3})),
+        vec![
+            mismatch("/name", "missing required field of type string"),
+            mismatch("/tone", "expected string, found int"),
+        ]
+    );
+    assert_eq!(signature.check_args(&json!("Ada")).len(), 1);
+}
+
+#[test]
+fn types_export_as_json_schema() {
+    assert_eq!(
+        review().json_schema(),
+        json!({
+            "type": "object",
+            "properties": {
+                "title": {"type": "string"},
+                "score": {"type": "integer"},
+                "tags": {"type": "array", "items": {"type": "string"}},
+                "note": {"anyOf": [{"type": "string"}, {"type": "null"}]},
+                "verdict": {"type": "string", "enum": ["Accept", "Reject"]},
+            },
+            "required": ["title", "score", "tags", "verdict"],
+        })
+    );
+    assert_eq!(BamlType::Any.json_schema(), json!({}));
+
+    let schema = FunctionSignature {
+        name: "Review".to_string(),
+        parameters: vec![field("text", BamlType::String)],
+        output_type: review(),
+    }
+    .schema();
+    assert_eq!(schema.input_schema["required"], json!(["text"]));
+    assert_eq!(schema.output_schema, review().json_schema());
+}
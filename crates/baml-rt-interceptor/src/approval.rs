@@ -0,0 +1,353 @@
+//! Human approval of sensitive tool calls.
+//!
+//! [`ApprovalInterceptor`] parks each call to a tool that needs approval in a
+//! [`PendingApprovalQueue`] and waits. An operator lists what is waiting and
+//! approves or denies it, e.g. through the `admin.listApprovals` and
+//! `admin.decideApproval` A2A methods, identified by a token from
+//! [`OperatorCredentials`]; an approved call goes ahead, a denied one is
+//! blocked with the approver's reason. A call nobody decides within the
+//! timeout is blocked too.
+//!
+//! Approval runs in the authz stage, after transforms, so the operator sees
+//! and provenance records the arguments the call will actually run with.
+//!
+//! Every outcome, including timeouts, is reported to each [`ApprovalObserver`]
+//! with who decided and what they saw.
+
+use crate::interceptor::{InterceptorDecision, InterceptorStage, ToolCallContext, ToolInterceptor};
+use async_trait::async_trait;
+use baml_rt_core::context;
+use baml_rt_core::ids::ContextId;
+use baml_rt_core::{BamlRtError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::oneshot;
+
+/// How long a call waits for a decision unless configured otherwise.
+pub const DEFAULT_APPROVAL_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// A tool call waiting for someone to decide it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingApproval {
+    pub approval_id: String,
+    pub tool_name: String,
+    pub args: Value,
+    pub context_id: ContextId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_id: Option<String>,
+    pub requested_at_ms: u64,
+    /// When the call is blocked if still undecided
+    pub expires_at_ms: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApprovalVerdict {
+    Approved,
+    Denied,
+    TimedOut,
+}
+
+impl ApprovalVerdict {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApprovalVerdict::Approved => "approved",
+            ApprovalVerdict::Denied => "denied",
+            ApprovalVerdict::TimedOut => "timed_out",
+        }
+    }
+}
+
+/// How a parked call was settled
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApprovalOutcome {
+    pub request: PendingApproval,
+    pub verdict: ApprovalVerdict,
+    /// Who decided; `None` when the call timed out
+    pub approver: Option<String>,
+    pub reason: Option<String>,
+    pub waited_ms: u64,
+}
+
+/// Notified of every parked call once it is settled
+#[async_trait]
+pub trait ApprovalObserver: Send + Sync + 'static {
+    async fn on_approval_decided(&self, outcome: &ApprovalOutcome);
+}
+
+/// The operators allowed to list and decide parked calls, by token
+///
+/// Whoever a token names is recorded as the approver, so nobody can decide a
+/// call in someone else's name. With no operators every request is refused.
+#[derive(Clone, Default)]
+pub struct OperatorCredentials {
+    /// Token to operator name
+    operators: HashMap<String, String>,
+}
+
+impl OperatorCredentials {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Let `token` act as `operator`
+    pub fn with_operator(mut self, operator: impl Into<String>, token: impl Into<String>) -> Self {
+        self.operators.insert(token.into(), operator.into());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operators.is_empty()
+    }
+
+    /// The operator `token` belongs to
+    pub fn authenticate(&self, token: Option<&str>) -> Result<&str> {
+        token
+            .filter(|token| !token.is_empty())
+            .and_then(|token| self.operators.get(token))
+            .map(String::as_str)
+            .ok_or_else(|| {
+                BamlRtError::InvalidArgument("A valid operator token is required".to_string())
+            })
+    }
+}
+
+/// Lists the operators, never their tokens
+impl std::fmt::Debug for OperatorCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut operators: Vec<_> = self.operators.values().collect();
+        operators.sort();
+        f.debug_struct("OperatorCredentials").field("operators", &operators).finish()
+    }
+}
+
+struct Decision {
+    approved: bool,
+    approver: String,
+    reason: Option<String>,
+}
+
+struct Parked {
+    request: PendingApproval,
+    decide: oneshot::Sender<Decision>,
+}
+
+/// Tool calls waiting for approval
+///
+/// Clones share their queue, so the interceptors parking calls and the admin
+/// methods deciding them can each hold one.
+#[derive(Clone, Default)]
+pub struct PendingApprovalQueue {
+    pending: Arc<Mutex<HashMap<String, Parked>>>,
+}
+
+impl PendingApprovalQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Calls waiting for a decision, oldest first
+    pub fn list(&self) -> Vec<PendingApproval> {
+        let parked = self.pending.lock().unwrap();
+        let mut pending: Vec<_> = parked.values().map(|parked| parked.request.clone()).collect();
+        pending.sort_by(|a, b| {
+            (a.requested_at_ms, &a.approval_id).cmp(&(b.requested_at_ms, &b.approval_id))
+        });
+        pending
+    }
+
+    /// Let a parked call go ahead
+    pub fn approve(&self, approval_id: &str, approver: &str) -> Result<PendingApproval> {
+        let approver = approver.to_string();
+        self.decide(approval_id, Decision { approved: true, approver, reason: None })
+    }
+
+    /// Block a parked call
+    pub fn deny(
+        &self,
+        approval_id: &str,
+        approver: &str,
+        reason: Option<String>,
+    ) -> Result<PendingApproval> {
+        let approver = approver.to_string();
+        self.decide(approval_id, Decision { approved: false, approver, reason })
+    }
+
+    fn decide(&self, approval_id: &str, decision: Decision) -> Result<PendingApproval> {
+        let parked = self.pending.lock().unwrap().remove(approval_id).ok_or_else(|| {
+            BamlRtError::InvalidArgument(format!("No pending approval '{}'", approval_id))
+        })?;
+        // The waiting call may have been cancelled since; the decision is moot then.
+        let _ = parked.decide.send(decision);
+        Ok(parked.request)
+    }
+
+    /// Park `request` until it is decided or `timeout` elapses
+    async fn wait(&self, request: PendingApproval, timeout: Duration) -> ApprovalOutcome {
+        let (decide, decided) = oneshot::channel();
+        let approval_id = request.approval_id.clone();
+        self.pending
+            .lock()
+            .unwrap()
+            .insert(approval_id.clone(), Parked { request: request.clone(), decide });
+        // Unpark the call if its caller stops waiting before a decision.
+        let _unpark = Unpark { queue: self, approval_id: &approval_id };
+
+        let started = Instant::now();
+        let decision = tokio::time::timeout(timeout, decided).await;
+        let waited_ms = started.elapsed().as_millis() as u64;
+        match decision {
+            Ok(Ok(decision)) => ApprovalOutcome {
+                request,
+                verdict: if decision.approved {
+                    ApprovalVerdict::Approved
+                } else {
+                    ApprovalVerdict::Denied
+                },
+                approver: Some(decision.approver),
+                reason: decision.reason,
+                waited_ms,
+            },
+            Ok(Err(_)) | Err(_) => ApprovalOutcome {
+                request,
+                verdict: ApprovalVerdict::TimedOut,
+                approver: None,
+                reason: None,
+                waited_ms,
+            },
+        }
+    }
+}
+
+struct Unpark<'a> {
+    queue: &'a PendingApprovalQueue,
+    approval_id: &'a str,
+}
+
+impl Drop for Unpark<'_> {
+    fn drop(&mut self) {
+        self.queue.pending.lock().unwrap().remove(self.approval_id);
+    }
+}
+
+/// Holds calls to sensitive tools until someone approves them, as a
+/// [`ToolInterceptor`]
+///
+/// Runs last among authorization interceptors, so a call other checks refuse
+/// never waits for a person.
+pub struct ApprovalInterceptor {
+    queue: PendingApprovalQueue,
+    /// Tool names, or `bundle/*` for every tool of a bundle
+    tools: HashSet<String>,
+    timeout: Duration,
+    observers: Vec<Arc<dyn ApprovalObserver>>,
+}
+
+impl ApprovalInterceptor {
+    /// Require approval for `tools`, given as tool names or `bundle/*`
+    pub fn new(queue: PendingApprovalQueue, tools: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            queue,
+            tools: tools.into_iter().collect(),
+            timeout: DEFAULT_APPROVAL_TIMEOUT,
+            observers: Vec::new(),
+        }
+    }
+
+    /// Block calls still undecided after `timeout`
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Report each outcome to `observer`
+    pub fn with_observer(mut self, observer: Arc<dyn ApprovalObserver>) -> Self {
+        self.observers.push(observer);
+        self
+    }
+
+    pub fn requires_approval(&self, tool_name: &str) -> bool {
+        self.tools.contains(tool_name)
+            || tool_name
+                .split_once('/')
+                .is_some_and(|(bundle, _)| self.tools.contains(&format!("{}/*", bundle)))
+    }
+}
+
+#[async_trait]
+impl ToolInterceptor for ApprovalInterceptor {
+    fn stage(&self) -> InterceptorStage {
+        InterceptorStage::Authz
+    }
+
+    fn priority(&self) -> i32 {
+        i32::MAX
+    }
+
+    async fn intercept_tool_call(&self, context: &ToolCallContext) -> Result<InterceptorDecision> {
+        if !self.requires_approval(&context.tool_name) {
+            return Ok(InterceptorDecision::Allow);
+        }
+        let requested_at_ms = now_millis();
+        let request = PendingApproval {
+            approval_id: uuid::Uuid::new_v4().to_string(),
+            tool_name: context.tool_name.clone(),
+            args: context.args.clone(),
+            context_id: context.context_id.clone(),
+            agent_id: context::current_agent_id().map(|agent_id| agent_id.as_str().to_string()),
+            requested_at_ms,
+            expires_at_ms: requested_at_ms + self.timeout.as_millis() as u64,
+        };
+        tracing::info!(
+            approval_id = request.approval_id.as_str(),
+            tool = context.tool_name.as_str(),
+            "Tool call waiting for approval"
+        );
+
+        let outcome = self.queue.wait(request, self.timeout).await;
+        tracing::info!(
+            approval_id = outcome.request.approval_id.as_str(),
+            tool = context.tool_name.as_str(),
+            verdict = outcome.verdict.as_str(),
+            approver = outcome.approver.as_deref(),
+            waited_ms = outcome.waited_ms,
+            "Tool call approval settled"
+        );
+        for observer in &self.observers {
+            observer.on_approval_decided(&outcome).await;
+        }
+
+        Ok(match outcome.verdict {
+            ApprovalVerdict::Approved => InterceptorDecision::Allow,
+            ApprovalVerdict::Denied => InterceptorDecision::Block(format!(
+                "{} denied by {}{}",
+                context.tool_name,
+                outcome.approver.as_deref().unwrap_or_default(),
+                outcome.reason.as_deref().map(|reason| format!(": {}", reason)).unwrap_or_default()
+            )),
+            ApprovalVerdict::TimedOut => InterceptorDecision::Block(format!(
+                "{} was not approved within {}s",
+                context.tool_name,
+                self.timeout.as_secs()
+            )),
+        })
+    }
+
+    async fn on_tool_call_complete(
+        &self,
+        _context: &ToolCallContext,
+        _result: &Result<Value>,
+        _duration_ms: u64,
+    ) {
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
@@ -0,0 +1,317 @@
+//! Token budgets for LLM calls.
+//!
+//! [`UsageBudget`] adds up the token usage of completed LLM calls per task, per
+//! context and per agent, and blocks further calls in any scope whose budget is
+//! spent. A call is only refused before it starts, so the call that crosses a
+//! limit still completes; its completion trips the budget, and every
+//! [`BudgetObserver`] hears about it once per scope.
+//!
+//! Usage is read from the completion metadata: the reconciled usage streamed
+//! calls record under [`crate::USAGE_METADATA_KEY`], otherwise the provider's
+//! `usage` (`input_tokens`/`output_tokens`). Calls that report neither are not
+//! counted.
+
+use crate::interceptor::{InterceptorDecision, InterceptorStage, LLMCallContext, LLMInterceptor};
+use crate::usage::TokenUsage;
+use async_trait::async_trait;
+use baml_rt_core::Result;
+use baml_rt_core::context;
+use baml_rt_core::ids::{AgentId, ContextId, TaskId, UuidId};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// What a budget is kept for
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum BudgetScope {
+    Task(TaskId),
+    Context(ContextId),
+    Agent(AgentId),
+}
+
+impl BudgetScope {
+    /// `"task"`, `"context"` or `"agent"`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            BudgetScope::Task(_) => "task",
+            BudgetScope::Context(_) => "context",
+            BudgetScope::Agent(_) => "agent",
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        match self {
+            BudgetScope::Task(id) => id.as_str(),
+            BudgetScope::Context(id) => id.as_str(),
+            BudgetScope::Agent(id) => id.as_str(),
+        }
+    }
+}
+
+impl fmt::Display for BudgetScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} '{}'", self.kind(), self.id())
+    }
+}
+
+/// Which count of tokens a limit applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetDimension {
+    PromptTokens,
+    CompletionTokens,
+    TotalTokens,
+}
+
+impl BudgetDimension {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BudgetDimension::PromptTokens => "prompt_tokens",
+            BudgetDimension::CompletionTokens => "completion_tokens",
+            BudgetDimension::TotalTokens => "total_tokens",
+        }
+    }
+
+    fn of(&self, usage: &TokenUsage) -> u64 {
+        match self {
+            BudgetDimension::PromptTokens => usage.prompt_tokens,
+            BudgetDimension::CompletionTokens => usage.completion_tokens,
+            BudgetDimension::TotalTokens => usage.total_tokens,
+        }
+    }
+}
+
+/// Most tokens a scope may spend; unset counts are unlimited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BudgetLimit {
+    pub prompt_tokens: Option<u64>,
+    pub completion_tokens: Option<u64>,
+    pub total_tokens: Option<u64>,
+}
+
+impl BudgetLimit {
+    /// Limit prompt and completion tokens together
+    pub fn total_tokens(limit: u64) -> Self {
+        Self { total_tokens: Some(limit), ..Self::default() }
+    }
+
+    pub fn with_prompt_tokens(mut self, limit: u64) -> Self {
+        self.prompt_tokens = Some(limit);
+        self
+    }
+
+    pub fn with_completion_tokens(mut self, limit: u64) -> Self {
+        self.completion_tokens = Some(limit);
+        self
+    }
+
+    /// The first count `spent` has reached, with its limit
+    pub fn exhausted_by(&self, spent: &TokenUsage) -> Option<(BudgetDimension, u64)> {
+        [
+            (BudgetDimension::TotalTokens, self.total_tokens),
+            (BudgetDimension::PromptTokens, self.prompt_tokens),
+            (BudgetDimension::CompletionTokens, self.completion_tokens),
+        ]
+        .into_iter()
+        .find_map(|(dimension, limit)| {
+            limit.filter(|limit| dimension.of(spent) >= *limit).map(|limit| (dimension, limit))
+        })
+    }
+}
+
+/// A scope spent its budget
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BudgetExhausted {
+    pub scope: BudgetScope,
+    pub dimension: BudgetDimension,
+    pub limit: u64,
+    /// Everything the scope has spent, including the call that tripped it
+    pub spent: TokenUsage,
+}
+
+impl fmt::Display for BudgetExhausted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "usage budget exhausted for {}: {} of {} {}",
+            self.scope,
+            self.dimension.of(&self.spent),
+            self.limit,
+            self.dimension.as_str()
+        )
+    }
+}
+
+/// Notified when a completed call trips a budget
+#[async_trait]
+pub trait BudgetObserver: Send + Sync + 'static {
+    /// Called once per scope, with the call whose usage tripped it
+    async fn on_budget_exhausted(&self, context: &LLMCallContext, exhausted: &BudgetExhausted);
+}
+
+#[derive(Default)]
+struct Ledger {
+    spent: HashMap<BudgetScope, TokenUsage>,
+    tripped: HashSet<BudgetScope>,
+}
+
+/// Per-task, per-context and per-agent token budgets, enforced as an
+/// [`LLMInterceptor`]
+///
+/// Clones share their ledger.
+#[derive(Clone, Default)]
+pub struct UsageBudget {
+    task_limit: Option<BudgetLimit>,
+    context_limit: Option<BudgetLimit>,
+    agent_limit: Option<BudgetLimit>,
+    observers: Vec<Arc<dyn BudgetObserver>>,
+    ledger: Arc<Mutex<Ledger>>,
+}
+
+impl UsageBudget {
+    /// Create a budget with no limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit what each task may spend
+    pub fn with_task_limit(mut self, limit: BudgetLimit) -> Self {
+        self.task_limit = Some(limit);
+        self
+    }
+
+    /// Limit what each context may spend
+    pub fn with_context_limit(mut self, limit: BudgetLimit) -> Self {
+        self.context_limit = Some(limit);
+        self
+    }
+
+    /// Limit what each agent may spend
+    pub fn with_agent_limit(mut self, limit: BudgetLimit) -> Self {
+        self.agent_limit = Some(limit);
+        self
+    }
+
+    /// Notify `observer` when a budget trips
+    pub fn with_observer(mut self, observer: Arc<dyn BudgetObserver>) -> Self {
+        self.observers.push(observer);
+        self
+    }
+
+    /// Count spending in `other`'s ledger, so replacing `other` with this
+    /// budget changes limits without forgetting what has been spent
+    pub fn sharing_ledger_with(mut self, other: &Self) -> Self {
+        self.ledger = other.ledger.clone();
+        self
+    }
+
+    /// What `scope` has spent so far
+    pub fn spent(&self, scope: &BudgetScope) -> TokenUsage {
+        self.ledger.lock().unwrap().spent.get(scope).copied().unwrap_or_default()
+    }
+
+    /// Forget what `scope` has spent, lifting its block
+    pub fn reset(&self, scope: &BudgetScope) {
+        let mut ledger = self.ledger.lock().unwrap();
+        ledger.spent.remove(scope);
+        ledger.tripped.remove(scope);
+    }
+
+    fn limit(&self, scope: &BudgetScope) -> Option<BudgetLimit> {
+        match scope {
+            BudgetScope::Task(_) => self.task_limit,
+            BudgetScope::Context(_) => self.context_limit,
+            BudgetScope::Agent(_) => self.agent_limit,
+        }
+    }
+
+    /// The scopes a call counts against that have a limit
+    fn scopes(&self, context: &LLMCallContext) -> Vec<(BudgetScope, BudgetLimit)> {
+        let agent = context::current_agent_id().or_else(|| {
+            let raw = context.metadata.get("agent_id").and_then(Value::as_str)?;
+            UuidId::parse_str(raw).ok().map(AgentId::from_uuid)
+        });
+        context::current_task_id()
+            .map(BudgetScope::Task)
+            .into_iter()
+            .chain([BudgetScope::Context(context.context_id.clone())])
+            .chain(agent.map(BudgetScope::Agent))
+            .filter_map(|scope| self.limit(&scope).map(|limit| (scope, limit)))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl LLMInterceptor for UsageBudget {
+    fn stage(&self) -> InterceptorStage {
+        InterceptorStage::Authz
+    }
+
+    async fn intercept_llm_call(&self, context: &LLMCallContext) -> Result<InterceptorDecision> {
+        let ledger = self.ledger.lock().unwrap();
+        for (scope, limit) in self.scopes(context) {
+            let spent = ledger.spent.get(&scope).copied().unwrap_or_default();
+            if let Some((dimension, limit)) = limit.exhausted_by(&spent) {
+                let exhausted = BudgetExhausted { scope, dimension, limit, spent };
+                return Ok(InterceptorDecision::OverBudget(exhausted.to_string()));
+            }
+        }
+        Ok(InterceptorDecision::Allow)
+    }
+
+    async fn on_llm_call_complete(
+        &self,
+        context: &LLMCallContext,
+        _result: &Result<Value>,
+        _duration_ms: u64,
+    ) {
+        let Some(usage) = call_usage(&context.metadata) else {
+            return;
+        };
+        let tripped: Vec<BudgetExhausted> = {
+            let mut ledger = self.ledger.lock().unwrap();
+            let mut tripped = Vec::new();
+            for (scope, limit) in self.scopes(context) {
+                let spent = ledger.spent.entry(scope.clone()).or_default();
+                *spent = TokenUsage::new(
+                    spent.prompt_tokens.saturating_add(usage.prompt_tokens),
+                    spent.completion_tokens.saturating_add(usage.completion_tokens),
+                );
+                let spent = *spent;
+                if let Some((dimension, limit)) = limit.exhausted_by(&spent)
+                    && ledger.tripped.insert(scope.clone())
+                {
+                    tripped.push(BudgetExhausted { scope, dimension, limit, spent });
+                }
+            }
+            tripped
+        };
+        for exhausted in &tripped {
+            tracing::warn!(
+                scope = exhausted.scope.kind(),
+                scope_id = exhausted.scope.id(),
+                dimension = exhausted.dimension.as_str(),
+                limit = exhausted.limit,
+                "{}",
+                exhausted
+            );
+            for observer in &self.observers {
+                observer.on_budget_exhausted(context, exhausted).await;
+            }
+        }
+    }
+}
+
+/// Tokens a completed call used, as recorded in its metadata
+fn call_usage(metadata: &Value) -> Option<TokenUsage> {
+    if let Some(usage) = TokenUsage::from_metadata(metadata) {
+        return Some(usage);
+    }
+    let usage = metadata.get("usage")?;
+    let prompt_tokens = usage.get("input_tokens").and_then(Value::as_u64);
+    let completion_tokens = usage.get("output_tokens").and_then(Value::as_u64);
+    if prompt_tokens.is_none() && completion_tokens.is_none() {
+        return None;
+    }
+    Some(TokenUsage::new(prompt_tokens.unwrap_or(0), completion_tokens.unwrap_or(0)))
+}
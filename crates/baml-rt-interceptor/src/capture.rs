@@ -0,0 +1,176 @@
+//! Payload capture detail for interceptors
+//!
+//! Interceptors copy prompts, tool arguments and results into provenance and
+//! logs. At the standard level, payloads longer than the configured limit are
+//! replaced with a truncated preview. An operator can raise capture to full
+//! detail for a bounded time, for the whole process or for a single context, to
+//! debug a production agent without redeploying it.
+
+use baml_rt_core::ids::ContextId;
+use baml_rt_core::BamlRtError;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Serialized length above which standard capture truncates a payload
+pub const DEFAULT_MAX_PAYLOAD_CHARS: usize = 4096;
+
+static GLOBAL: OnceLock<Arc<PayloadCapture>> = OnceLock::new();
+
+/// How much of each payload interceptors record
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureDetail {
+    /// Payloads are truncated to the configured limit
+    Standard,
+
+    /// Payloads are recorded in full
+    Full,
+}
+
+impl CaptureDetail {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CaptureDetail::Standard => "standard",
+            CaptureDetail::Full => "full",
+        }
+    }
+}
+
+impl std::str::FromStr for CaptureDetail {
+    type Err = BamlRtError;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value {
+            "standard" => Ok(CaptureDetail::Standard),
+            "full" => Ok(CaptureDetail::Full),
+            other => Err(BamlRtError::InvalidArgument(format!(
+                "Unknown capture detail: {other}"
+            ))),
+        }
+    }
+}
+
+/// A temporary raise to full capture
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaptureOverride {
+    /// The context the raise applies to, or `None` for every context
+    pub context_id: Option<ContextId>,
+
+    /// When capture falls back to standard
+    pub expires_at: Instant,
+}
+
+/// Capture settings shared by the interceptors of a process
+pub struct PayloadCapture {
+    max_payload_chars: AtomicUsize,
+    overrides: Mutex<Vec<CaptureOverride>>,
+}
+
+impl PayloadCapture {
+    pub fn new(max_payload_chars: usize) -> Self {
+        Self {
+            max_payload_chars: AtomicUsize::new(max_payload_chars),
+            overrides: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// The process-wide settings used by the built-in interceptors
+    pub fn global() -> Arc<PayloadCapture> {
+        GLOBAL
+            .get_or_init(|| Arc::new(PayloadCapture::new(DEFAULT_MAX_PAYLOAD_CHARS)))
+            .clone()
+    }
+
+    pub fn max_payload_chars(&self) -> usize {
+        self.max_payload_chars.load(Ordering::Relaxed)
+    }
+
+    pub fn set_max_payload_chars(&self, max_payload_chars: usize) {
+        self.max_payload_chars.store(max_payload_chars, Ordering::Relaxed);
+    }
+
+    /// Capture full payloads for `duration`, for one context or for all of them
+    ///
+    /// Replaces any earlier raise for the same scope.
+    pub fn raise(&self, context_id: Option<ContextId>, duration: Duration) -> CaptureOverride {
+        let raised = CaptureOverride {
+            context_id,
+            expires_at: Instant::now() + duration,
+        };
+        let mut overrides = self.overrides.lock().unwrap();
+        overrides.retain(|existing| existing.context_id != raised.context_id);
+        overrides.push(raised.clone());
+        tracing::info!(
+            context_id = raised.context_id.as_ref().map(ContextId::as_str).unwrap_or("*"),
+            duration_secs = duration.as_secs(),
+            "Raised payload capture to full detail"
+        );
+        raised
+    }
+
+    /// Drop the raise for one context, or every raise when `context_id` is `None`
+    pub fn reset(&self, context_id: Option<&ContextId>) {
+        let mut overrides = self.overrides.lock().unwrap();
+        match context_id {
+            Some(context_id) => {
+                overrides.retain(|existing| existing.context_id.as_ref() != Some(context_id))
+            }
+            None => overrides.clear(),
+        }
+    }
+
+    /// Raises that have not expired yet
+    pub fn active(&self) -> Vec<CaptureOverride> {
+        let mut overrides = self.overrides.lock().unwrap();
+        let now = Instant::now();
+        overrides.retain(|existing| existing.expires_at > now);
+        overrides.clone()
+    }
+
+    /// The detail in effect for calls in `context_id`
+    pub fn detail(&self, context_id: &ContextId) -> CaptureDetail {
+        let raised = self.active().iter().any(|existing| {
+            existing
+                .context_id
+                .as_ref()
+                .is_none_or(|scoped| scoped == context_id)
+        });
+        if raised {
+            CaptureDetail::Full
+        } else {
+            CaptureDetail::Standard
+        }
+    }
+
+    /// The copy of `payload` to record for a call in `context_id`
+    ///
+    /// Under standard detail, payloads whose JSON is longer than the limit become
+    /// `{"truncated": true, "chars": <full length>, "preview": <leading chars>}`.
+    pub fn capture(&self, context_id: &ContextId, payload: &Value) -> Value {
+        if self.detail(context_id) == CaptureDetail::Full {
+            return payload.clone();
+        }
+        let max_chars = self.max_payload_chars();
+        let text = match payload {
+            Value::String(text) => text.clone(),
+            other => other.to_string(),
+        };
+        let chars = text.chars().count();
+        if chars <= max_chars {
+            return payload.clone();
+        }
+        json!({
+            "truncated": true,
+            "chars": chars,
+            "preview": text.chars().take(max_chars).collect::<String>(),
+        })
+    }
+}
+
+impl Default for PayloadCapture {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_PAYLOAD_CHARS)
+    }
+}
+
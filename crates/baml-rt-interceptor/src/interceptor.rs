@@ -2,15 +2,24 @@
 //!
 //! Provides a trait-based system for intercepting, logging, and potentially blocking
 //! LLM calls and tool executions for governance, tracing, and security purposes.
+//!
+//! Each interceptor runs in an [`InterceptorStage`]: pre-validate, transform,
+//! authz, then observe. Within a stage lower [`priority`](ToolInterceptor::priority)
+//! values run first, and interceptors of equal priority run in registration
+//! order. Transform-stage interceptors may answer [`InterceptorDecision::Modify`]
+//! to rewrite the call's arguments or prompt; later stages, authorization
+//! included, see the rewritten call, and the registry hands the final payload
+//! back to the executor.
 
 use baml_rt_core::{BamlRtError, Result};
 use baml_rt_core::ids::ContextId;
+use crate::usage::TokenUsage;
 use serde_json::Value;
 use std::sync::Arc;
 use async_trait::async_trait;
 
 /// Result of an interception decision
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum InterceptorDecision {
     /// Allow the call to proceed
     Allow,
@@ -18,6 +27,60 @@ pub enum InterceptorDecision {
     /// Block the call with this error message
     /// The error will be wrapped in a ToolExecution or BamlRuntime error
     Block(String),
+
+    /// Block the call because a budget or rate limit it counts against is
+    /// spent; reported as [`BamlRtError::Budget`] so callers can tell it from
+    /// a policy refusal and back off
+    OverBudget(String),
+
+    /// Allow the call with rewritten payloads; `None` leaves a payload as it was
+    ///
+    /// `args` replaces the arguments of a tool call, or of the BAML function
+    /// making an LLM call, and the executor runs with them. `prompt` replaces
+    /// the prompt of an LLM call as later interceptors and the runtime's reports
+    /// see it; BAML renders the prompt it sends from the function's template
+    /// and arguments, so rewrite `args` to change what the model receives.
+    ///
+    /// Only honored from [`InterceptorStage::Transform`] interceptors.
+    Modify {
+        args: Option<Value>,
+        prompt: Option<Value>,
+    },
+}
+
+/// Where in the pipeline an interceptor runs; stages run in declaration order
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum InterceptorStage {
+    /// Reject malformed calls before anything else sees them
+    PreValidate,
+
+    /// Rewrite the call's arguments or prompt
+    Transform,
+
+    /// Allow or refuse the call as transforms left it: policies, rate limits,
+    /// budgets, approvals
+    Authz,
+
+    /// Record the call as it will run: tracing, provenance, metrics
+    #[default]
+    Observe,
+}
+
+impl InterceptorStage {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            InterceptorStage::PreValidate => "pre-validate",
+            InterceptorStage::Authz => "authz",
+            InterceptorStage::Transform => "transform",
+            InterceptorStage::Observe => "observe",
+        }
+    }
+}
+
+impl std::fmt::Display for InterceptorStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 /// Context information about an LLM call
@@ -32,6 +95,9 @@ pub struct LLMCallContext {
     /// The function name that triggered this LLM call
     pub function_name: String,
 
+    /// The arguments the function was invoked with
+    pub args: Value,
+
     /// The active context ID for this call
     pub context_id: ContextId,
 
@@ -42,6 +108,25 @@ pub struct LLMCallContext {
     pub metadata: Value,
 }
 
+/// `metadata` key holding the id shared by every callback of one streamed call
+pub const STREAM_ID_METADATA_KEY: &str = "stream_id";
+
+/// One incremental result of a streamed LLM call
+#[derive(Debug, Clone)]
+pub struct LLMChunk {
+    /// Zero-based position of this chunk in the stream
+    pub index: u64,
+
+    /// Milliseconds since the call started
+    pub elapsed_ms: u64,
+
+    /// The partial result parsed so far
+    pub partial: Value,
+
+    /// Estimated token usage of the call up to and including this chunk
+    pub usage: TokenUsage,
+}
+
 /// Context information about a tool call
 #[derive(Debug, Clone)]
 pub struct ToolCallContext {
@@ -61,9 +146,65 @@ pub struct ToolCallContext {
     pub metadata: Value,
 }
 
+/// `metadata` key holding the retry progress of a tool call
+pub const RETRY_METADATA_KEY: &str = "retry";
+
+/// `metadata` key holding why a call failed, in the form of
+/// `BamlRtError::to_value`: `{"code", "message", "context"}`
+pub const ERROR_METADATA_KEY: &str = "error";
+
+/// `metadata` key holding the outcome of checking an LLM call's parsed output
+/// against its function's declared type: `{"valid": bool, "mismatches": [string]}`
+pub const OUTPUT_VALIDATION_METADATA_KEY: &str = "output_validation";
+
+/// `metadata` key holding the repair attempt an LLM call belongs to:
+/// `{"chain": string, "attempt": int, "max_attempts": int}`, shared by every
+/// attempt at one function invocation
+pub const OUTPUT_REPAIR_METADATA_KEY: &str = "output_repair";
+
+/// `metadata` key holding the arguments of the BAML function invocation, on the
+/// LLM call its output was parsed from, so the invocation can be replayed
+pub const FUNCTION_ARGS_METADATA_KEY: &str = "function_args";
+
+/// `metadata` key holding the BAML function's parsed output, on the LLM call it
+/// was parsed from
+pub const FUNCTION_OUTPUT_METADATA_KEY: &str = "function_output";
+
+/// A failed tool call attempt that is about to be retried
+#[derive(Debug, Clone)]
+pub struct ToolCallRetry {
+    /// The attempt that failed (1-based)
+    pub attempt: u32,
+
+    /// The most attempts the retry policy allows
+    pub max_attempts: u32,
+
+    /// The failure kind of the attempt (e.g. "Timeout")
+    pub failure_kind: String,
+
+    /// The error the attempt failed with
+    pub error: String,
+
+    /// How long the failed attempt ran in milliseconds
+    pub duration_ms: u64,
+
+    /// Wait before the next attempt in milliseconds
+    pub backoff_ms: u64,
+}
+
 /// Trait for intercepting LLM calls
 #[async_trait]
 pub trait LLMInterceptor: Send + Sync + 'static {
+    /// The stage this interceptor runs in
+    fn stage(&self) -> InterceptorStage {
+        InterceptorStage::Observe
+    }
+
+    /// Order within the stage; lower runs first
+    fn priority(&self) -> i32 {
+        0
+    }
+
     /// Intercept an LLM call before execution
     ///
     /// # Arguments
@@ -85,11 +226,36 @@ pub trait LLMInterceptor: Send + Sync + 'static {
         result: &Result<Value>,
         duration_ms: u64,
     );
+
+    /// Called for each chunk of a streamed LLM call
+    ///
+    /// Blocking aborts the stream; `on_llm_call_complete` still runs with an error.
+    ///
+    /// # Arguments
+    /// * `context` - The original call context
+    /// * `chunk` - The chunk just received
+    async fn on_llm_chunk(
+        &self,
+        _context: &LLMCallContext,
+        _chunk: &LLMChunk,
+    ) -> Result<InterceptorDecision> {
+        Ok(InterceptorDecision::Allow)
+    }
 }
 
 /// Trait for intercepting tool calls
 #[async_trait]
 pub trait ToolInterceptor: Send + Sync + 'static {
+    /// The stage this interceptor runs in
+    fn stage(&self) -> InterceptorStage {
+        InterceptorStage::Observe
+    }
+
+    /// Order within the stage; lower runs first
+    fn priority(&self) -> i32 {
+        0
+    }
+
     /// Intercept a tool call before execution
     ///
     /// # Arguments
@@ -111,17 +277,35 @@ pub trait ToolInterceptor: Send + Sync + 'static {
         result: &Result<Value>,
         duration_ms: u64,
     );
+
+    /// Called when an attempt of a tool call failed and will be retried
+    ///
+    /// `on_tool_call_complete` runs once, after the last attempt.
+    ///
+    /// # Arguments
+    /// * `context` - The original call context
+    /// * `retry` - The failed attempt and the backoff before the next one
+    async fn on_tool_call_retry(&self, _context: &ToolCallContext, _retry: &ToolCallRetry) {}
 }
 
 /// Pipeline for composing multiple interceptors
 ///
 /// This allows interceptors to be chained together in a pipeline pattern.
-/// Interceptors are executed in order, and if any interceptor blocks,
-/// subsequent interceptors are not called.
+/// A registry runs them by stage and priority, in the order they were added
+/// among equals, and if any interceptor blocks, subsequent interceptors are
+/// not called.
 pub struct InterceptorPipeline<I: ?Sized> {
     pub(crate) interceptors: Vec<Arc<I>>,
 }
 
+impl<I: ?Sized> Clone for InterceptorPipeline<I> {
+    fn clone(&self) -> Self {
+        Self {
+            interceptors: self.interceptors.clone(),
+        }
+    }
+}
+
 impl<I: ?Sized> InterceptorPipeline<I> {
     /// Create a new empty pipeline
     pub fn new() -> Self {
@@ -132,7 +316,7 @@ impl<I: ?Sized> InterceptorPipeline<I> {
 
     /// Add an interceptor to the pipeline
     ///
-    /// Interceptors are executed in the order they are added.
+    /// Among interceptors of the same stage and priority, those added first run first.
     pub fn with_interceptor(mut self, interceptor: Arc<I>) -> Self {
         self.interceptors.push(interceptor);
         self
@@ -169,6 +353,9 @@ impl<I: ?Sized> Default for InterceptorPipeline<I> {
 /// Registry for managing interceptors
 ///
 /// This registry manages pipelines of interceptors for both LLM and tool calls.
+/// Clones share the interceptors, so a caller can run a snapshot without
+/// holding whatever lock guards the registry.
+#[derive(Clone)]
 pub struct InterceptorRegistry {
     pub(crate) llm_pipeline: InterceptorPipeline<dyn LLMInterceptor>,
     pub(crate) tool_pipeline: InterceptorPipeline<dyn ToolInterceptor>,
@@ -188,30 +375,69 @@ impl InterceptorRegistry {
         llm_pipeline: InterceptorPipeline<dyn LLMInterceptor>,
         tool_pipeline: InterceptorPipeline<dyn ToolInterceptor>,
     ) -> Self {
-        Self {
+        let mut registry = Self {
             llm_pipeline,
             tool_pipeline,
-        }
+        };
+        registry.reorder();
+        registry
+    }
+
+    /// Register an LLM interceptor in `stage` at `priority`, overriding the
+    /// placement the interceptor declares
+    pub fn register_llm_interceptor_at<I: LLMInterceptor>(
+        &mut self,
+        interceptor: I,
+        stage: InterceptorStage,
+        priority: i32,
+    ) {
+        self.register_llm_interceptor(Placed { inner: interceptor, stage, priority });
+    }
+
+    /// Register a tool interceptor in `stage` at `priority`, overriding the
+    /// placement the interceptor declares
+    pub fn register_tool_interceptor_at<I: ToolInterceptor>(
+        &mut self,
+        interceptor: I,
+        stage: InterceptorStage,
+        priority: i32,
+    ) {
+        self.register_tool_interceptor(Placed { inner: interceptor, stage, priority });
+    }
+
+    /// Sort both pipelines into execution order. The sort is stable, so
+    /// registration order breaks ties.
+    fn reorder(&mut self) {
+        self.llm_pipeline
+            .interceptors
+            .sort_by_key(|interceptor| (interceptor.stage(), interceptor.priority()));
+        self.tool_pipeline
+            .interceptors
+            .sort_by_key(|interceptor| (interceptor.stage(), interceptor.priority()));
     }
 
     /// Register an LLM interceptor
     ///
-    /// Interceptors are called in registration order. If any interceptor
-    /// blocks the call, subsequent interceptors are not called.
+    /// Interceptors are called by stage and priority, then in registration
+    /// order. If any interceptor blocks the call, subsequent interceptors are
+    /// not called.
     pub fn register_llm_interceptor<I: LLMInterceptor>(&mut self, interceptor: I) {
         let pipeline = std::mem::take(&mut self.llm_pipeline);
         self.llm_pipeline =
             pipeline.with_interceptor(Arc::new(interceptor) as Arc<dyn LLMInterceptor>);
+        self.reorder();
     }
 
     /// Register a tool interceptor
     ///
-    /// Interceptors are called in registration order. If any interceptor
-    /// blocks the call, subsequent interceptors are not called.
+    /// Interceptors are called by stage and priority, then in registration
+    /// order. If any interceptor blocks the call, subsequent interceptors are
+    /// not called.
     pub fn register_tool_interceptor<I: ToolInterceptor>(&mut self, interceptor: I) {
         let pipeline = std::mem::take(&mut self.tool_pipeline);
         self.tool_pipeline =
             pipeline.with_interceptor(Arc::new(interceptor) as Arc<dyn ToolInterceptor>);
+        self.reorder();
     }
 
     /// Add an LLM interceptor pipeline
@@ -233,6 +459,7 @@ impl InterceptorRegistry {
         }
 
         self.llm_pipeline = merged;
+        self.reorder();
         self
     }
 
@@ -255,6 +482,7 @@ impl InterceptorRegistry {
         }
 
         self.tool_pipeline = merged;
+        self.reorder();
         self
     }
 
@@ -267,6 +495,7 @@ impl InterceptorRegistry {
         merged.interceptors.extend(existing.interceptors);
         merged.interceptors.extend(pipeline.interceptors);
         self.llm_pipeline = merged;
+        self.reorder();
     }
 
     /// Merge a tool interceptor pipeline into the registry.
@@ -278,22 +507,46 @@ impl InterceptorRegistry {
         merged.interceptors.extend(existing.interceptors);
         merged.interceptors.extend(pipeline.interceptors);
         self.tool_pipeline = merged;
+        self.reorder();
     }
 
     /// Execute LLM interceptors and return the final decision
     ///
-    /// Returns Ok(Allow) if all interceptors allow, or Err if any block
+    /// Returns Ok(Allow) if all interceptors allow, Ok(Modify) with whichever of
+    /// the arguments and prompt a transform changed, or Err if any block
     pub async fn intercept_llm_call(&self, context: &LLMCallContext) -> Result<InterceptorDecision> {
+        let mut rewritten: Option<LLMCallContext> = None;
         for interceptor in self.llm_pipeline.interceptors() {
-            match interceptor.intercept_llm_call(context).await {
+            let current = rewritten.as_ref().unwrap_or(context);
+            match interceptor.intercept_llm_call(current).await {
                 Ok(InterceptorDecision::Allow) => {
                     // Continue to next interceptor
                 }
+                Ok(InterceptorDecision::Modify { args, prompt }) => {
+                    if interceptor.stage() == InterceptorStage::Transform {
+                        let call = rewritten.get_or_insert_with(|| context.clone());
+                        if let Some(args) = args {
+                            call.args = args;
+                        }
+                        if let Some(prompt) = prompt {
+                            call.prompt = prompt;
+                        }
+                    } else {
+                        tracing::warn!(
+                            stage = %interceptor.stage(),
+                            function = context.function_name.as_str(),
+                            "Ignoring LLM call rewrite from an interceptor outside the transform stage"
+                        );
+                    }
+                }
                 Ok(InterceptorDecision::Block(msg)) => {
                     return Err(BamlRtError::BamlRuntime(format!(
                         "LLM call blocked by interceptor: {}", msg
                     )));
                 }
+                Ok(InterceptorDecision::OverBudget(msg)) => {
+                    return Err(BamlRtError::Budget(msg));
+                }
                 Err(e) => {
                     // Interceptor itself failed - log but continue?
                     tracing::warn!(error = ?e, "LLM interceptor failed");
@@ -301,23 +554,86 @@ impl InterceptorRegistry {
             }
         }
 
+        let Some(call) = rewritten else {
+            return Ok(InterceptorDecision::Allow);
+        };
+        let args = (call.args != context.args).then_some(call.args);
+        let prompt = (call.prompt != context.prompt).then_some(call.prompt);
+        Ok(if args.is_none() && prompt.is_none() {
+            InterceptorDecision::Allow
+        } else {
+            InterceptorDecision::Modify { args, prompt }
+        })
+    }
+
+    /// Execute LLM interceptors for one chunk of a streamed call
+    ///
+    /// Returns Ok(Allow) if all interceptors allow, or Err if any aborts the stream
+    pub async fn intercept_llm_chunk(
+        &self,
+        context: &LLMCallContext,
+        chunk: &LLMChunk,
+    ) -> Result<InterceptorDecision> {
+        for interceptor in self.llm_pipeline.interceptors() {
+            match interceptor.on_llm_chunk(context, chunk).await {
+                // A chunk has already been received; there is nothing left to rewrite.
+                Ok(InterceptorDecision::Allow | InterceptorDecision::Modify { .. }) => {}
+                Ok(InterceptorDecision::Block(msg)) => {
+                    return Err(BamlRtError::BamlRuntime(format!(
+                        "LLM stream aborted by interceptor: {}", msg
+                    )));
+                }
+                Ok(InterceptorDecision::OverBudget(msg)) => {
+                    return Err(BamlRtError::Budget(msg));
+                }
+                Err(e) => {
+                    tracing::warn!(error = ?e, "LLM chunk interceptor failed");
+                }
+            }
+        }
+
         Ok(InterceptorDecision::Allow)
     }
 
     /// Execute tool interceptors and return the final decision
     ///
-    /// Returns Ok(Allow) if all interceptors allow, or Err if any block
+    /// Returns Ok(Allow) if all interceptors allow, Ok(Modify) with the final
+    /// arguments if a transform rewrote them, or Err if any block
     pub async fn intercept_tool_call(&self, context: &ToolCallContext) -> Result<InterceptorDecision> {
+        let mut rewritten: Option<ToolCallContext> = None;
         for interceptor in self.tool_pipeline.interceptors() {
-            match interceptor.intercept_tool_call(context).await {
+            let current = rewritten.as_ref().unwrap_or(context);
+            match interceptor.intercept_tool_call(current).await {
                 Ok(InterceptorDecision::Allow) => {
                     // Continue to next interceptor
                 }
+                Ok(InterceptorDecision::Modify { args, prompt }) => {
+                    if interceptor.stage() != InterceptorStage::Transform {
+                        tracing::warn!(
+                            stage = %interceptor.stage(),
+                            tool = context.tool_name.as_str(),
+                            "Ignoring argument rewrite from an interceptor outside the transform stage"
+                        );
+                        continue;
+                    }
+                    if prompt.is_some() {
+                        tracing::warn!(
+                            tool = context.tool_name.as_str(),
+                            "Ignoring prompt rewrite of a tool call"
+                        );
+                    }
+                    if let Some(args) = args {
+                        rewritten.get_or_insert_with(|| context.clone()).args = args;
+                    }
+                }
                 Ok(InterceptorDecision::Block(msg)) => {
                     return Err(BamlRtError::ToolExecution(format!(
                         "Tool call blocked by interceptor: {}", msg
                     )));
                 }
+                Ok(InterceptorDecision::OverBudget(msg)) => {
+                    return Err(BamlRtError::Budget(msg));
+                }
                 Err(e) => {
                     // Interceptor itself failed - log but continue?
                     tracing::warn!("Tool interceptor failed: {}", e);
@@ -325,7 +641,10 @@ impl InterceptorRegistry {
             }
         }
 
-        Ok(InterceptorDecision::Allow)
+        Ok(match rewritten {
+            Some(call) => InterceptorDecision::Modify { args: Some(call.args), prompt: None },
+            None => InterceptorDecision::Allow,
+        })
     }
 
     /// Notify all LLM interceptors of a completed call
@@ -352,6 +671,13 @@ impl InterceptorRegistry {
         }
     }
 
+    /// Notify all tool interceptors of a failed attempt that will be retried
+    pub async fn notify_tool_call_retry(&self, context: &ToolCallContext, retry: &ToolCallRetry) {
+        for interceptor in self.tool_pipeline.interceptors() {
+            interceptor.on_tool_call_retry(context, retry).await;
+        }
+    }
+
     /// Get the LLM interceptor pipeline (for inspection)
     pub fn llm_pipeline(&self) -> &InterceptorPipeline<dyn LLMInterceptor> {
         &self.llm_pipeline
@@ -378,3 +704,70 @@ impl Default for InterceptorRegistry {
         Self::new()
     }
 }
+
+/// An interceptor registered at a placement other than the one it declares
+struct Placed<I> {
+    inner: I,
+    stage: InterceptorStage,
+    priority: i32,
+}
+
+#[async_trait]
+impl<I: LLMInterceptor> LLMInterceptor for Placed<I> {
+    fn stage(&self) -> InterceptorStage {
+        self.stage
+    }
+
+    fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    async fn intercept_llm_call(&self, context: &LLMCallContext) -> Result<InterceptorDecision> {
+        self.inner.intercept_llm_call(context).await
+    }
+
+    async fn on_llm_call_complete(
+        &self,
+        context: &LLMCallContext,
+        result: &Result<Value>,
+        duration_ms: u64,
+    ) {
+        self.inner.on_llm_call_complete(context, result, duration_ms).await
+    }
+
+    async fn on_llm_chunk(
+        &self,
+        context: &LLMCallContext,
+        chunk: &LLMChunk,
+    ) -> Result<InterceptorDecision> {
+        self.inner.on_llm_chunk(context, chunk).await
+    }
+}
+
+#[async_trait]
+impl<I: ToolInterceptor> ToolInterceptor for Placed<I> {
+    fn stage(&self) -> InterceptorStage {
+        self.stage
+    }
+
+    fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    async fn intercept_tool_call(&self, context: &ToolCallContext) -> Result<InterceptorDecision> {
+        self.inner.intercept_tool_call(context).await
+    }
+
+    async fn on_tool_call_complete(
+        &self,
+        context: &ToolCallContext,
+        result: &Result<Value>,
+        duration_ms: u64,
+    ) {
+        self.inner.on_tool_call_complete(context, result, duration_ms).await
+    }
+
+    async fn on_tool_call_retry(&self, context: &ToolCallContext, retry: &ToolCallRetry) {
+        self.inner.on_tool_call_retry(context, retry).await
+    }
+}
@@ -2,6 +2,8 @@
 //!
 //! This module provides pre-built interceptors for common use cases.
 
+pub mod rate_limit;
 pub mod tracing;
 
+pub use rate_limit::{retry_after, RateLimit, RateLimitInterceptor};
 pub use tracing::{TracingInterceptor, TracingLLMInterceptor, TracingToolInterceptor};
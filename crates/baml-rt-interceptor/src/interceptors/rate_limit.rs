@@ -0,0 +1,266 @@
+//! Rate limiting interceptor for LLM and tool calls
+//!
+//! [`RateLimitInterceptor`] keeps a token bucket per model, per tool and per
+//! agent. Every call takes one token from each bucket that applies to it; when
+//! any of them is empty the call is blocked with a message that ends in a
+//! retry-after hint, which [`retry_after`] parses back into a [`Duration`].
+//! A blocked call takes no tokens, so a caller that waits and retries is not
+//! penalised for the attempt that was refused.
+//!
+//! Agents are identified by `metadata.agent_id`; calls without one are only
+//! subject to model and tool limits.
+
+use crate::interceptor::{
+    InterceptorDecision, InterceptorStage, LLMCallContext, LLMInterceptor, ToolCallContext,
+    ToolInterceptor,
+};
+use async_trait::async_trait;
+use baml_rt_core::Result;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const RETRY_AFTER_PREFIX: &str = "retry after ";
+
+/// Steady rate and burst size of one token bucket
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    /// Most calls that can be made back to back
+    pub burst: u32,
+
+    /// Calls per second the bucket refills at
+    pub per_second: f64,
+}
+
+impl RateLimit {
+    pub fn per_second(calls: u32) -> Self {
+        Self { burst: calls.max(1), per_second: f64::from(calls) }
+    }
+
+    pub fn per_minute(calls: u32) -> Self {
+        Self { burst: calls.max(1), per_second: f64::from(calls) / 60.0 }
+    }
+
+    /// Allow up to `burst` calls back to back before the steady rate applies
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum BucketKey {
+    Model(String),
+    Tool(String),
+    Agent(String),
+}
+
+impl BucketKey {
+    fn describe(&self) -> String {
+        match self {
+            BucketKey::Model(model) => format!("model '{}'", model),
+            BucketKey::Tool(tool) => format!("tool '{}'", tool),
+            BucketKey::Agent(agent) => format!("agent '{}'", agent),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    limit: RateLimit,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(limit: RateLimit, now: Instant) -> Self {
+        Self { limit, tokens: f64::from(limit.burst), refilled_at: now }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refilled_at).as_secs_f64();
+        self.tokens =
+            (self.tokens + elapsed * self.limit.per_second).min(f64::from(self.limit.burst));
+        self.refilled_at = now;
+    }
+
+    /// How long until a token is available; zero when one is available now
+    fn wait(&self) -> Duration {
+        if self.tokens >= 1.0 {
+            return Duration::ZERO;
+        }
+        if self.limit.per_second <= 0.0 {
+            return Duration::MAX;
+        }
+        Duration::from_secs_f64((1.0 - self.tokens) / self.limit.per_second)
+    }
+}
+
+/// Token-bucket limits on LLM calls per model, tool calls per tool, and both
+/// per agent
+///
+/// Clones share their buckets, so registering one clone as the LLM interceptor
+/// and another as the tool interceptor counts both kinds of call against the
+/// same agent limit.
+///
+/// ```ignore
+/// let limiter = RateLimitInterceptor::new()
+///     .with_model_limit("gpt-4o", RateLimit::per_minute(60))
+///     .with_default_tool_limit(RateLimit::per_second(5))
+///     .with_agent_limit(RateLimit::per_minute(120).with_burst(10));
+/// registry.register_llm_interceptor(limiter.clone());
+/// registry.register_tool_interceptor(limiter);
+/// ```
+#[derive(Clone, Default)]
+pub struct RateLimitInterceptor {
+    model_limits: HashMap<String, RateLimit>,
+    default_model_limit: Option<RateLimit>,
+    tool_limits: HashMap<String, RateLimit>,
+    default_tool_limit: Option<RateLimit>,
+    agent_limit: Option<RateLimit>,
+    buckets: Arc<Mutex<HashMap<BucketKey, TokenBucket>>>,
+}
+
+impl RateLimitInterceptor {
+    /// Create an interceptor with no limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit LLM calls to `model`
+    pub fn with_model_limit(mut self, model: impl Into<String>, limit: RateLimit) -> Self {
+        self.model_limits.insert(model.into(), limit);
+        self
+    }
+
+    /// Limit LLM calls to each model without a limit of its own
+    pub fn with_default_model_limit(mut self, limit: RateLimit) -> Self {
+        self.default_model_limit = Some(limit);
+        self
+    }
+
+    /// Limit calls to `tool`
+    pub fn with_tool_limit(mut self, tool: impl Into<String>, limit: RateLimit) -> Self {
+        self.tool_limits.insert(tool.into(), limit);
+        self
+    }
+
+    /// Limit calls to each tool without a limit of its own
+    pub fn with_default_tool_limit(mut self, limit: RateLimit) -> Self {
+        self.default_tool_limit = Some(limit);
+        self
+    }
+
+    /// Limit the LLM and tool calls of each agent together
+    pub fn with_agent_limit(mut self, limit: RateLimit) -> Self {
+        self.agent_limit = Some(limit);
+        self
+    }
+
+    /// Keep drawing from `other`'s buckets, so replacing `other` with this
+    /// interceptor changes limits without refilling buckets that are empty
+    pub fn sharing_buckets_with(mut self, other: &Self) -> Self {
+        self.buckets = other.buckets.clone();
+        self
+    }
+
+    fn agent_bucket(&self, metadata: &Value) -> Option<(BucketKey, RateLimit)> {
+        let agent = metadata.get("agent_id").and_then(Value::as_str)?;
+        Some((BucketKey::Agent(agent.to_string()), self.agent_limit?))
+    }
+
+    /// Take a token from every bucket in `applicable`, or none if any is empty
+    fn acquire(&self, applicable: Vec<(BucketKey, RateLimit)>) -> InterceptorDecision {
+        if applicable.is_empty() {
+            return InterceptorDecision::Allow;
+        }
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        let mut exhausted: Option<(&BucketKey, Duration)> = None;
+        for (key, limit) in &applicable {
+            let bucket =
+                buckets.entry(key.clone()).or_insert_with(|| TokenBucket::new(*limit, now));
+            bucket.limit = *limit;
+            bucket.refill(now);
+            let wait = bucket.wait();
+            if !wait.is_zero() && exhausted.is_none_or(|(_, longest)| wait > longest) {
+                exhausted = Some((key, wait));
+            }
+        }
+        if let Some((key, wait)) = exhausted {
+            // Round up so that retrying after the hint always finds a token.
+            let millis = wait.as_millis() + u128::from(wait.subsec_nanos() % 1_000_000 != 0);
+            return InterceptorDecision::OverBudget(format!(
+                "rate limit exceeded for {}; {}{}ms",
+                key.describe(),
+                RETRY_AFTER_PREFIX,
+                millis
+            ));
+        }
+        for (key, _) in &applicable {
+            if let Some(bucket) = buckets.get_mut(key) {
+                bucket.tokens -= 1.0;
+            }
+        }
+        InterceptorDecision::Allow
+    }
+}
+
+/// The retry-after hint of a message [`RateLimitInterceptor`] blocked a call with
+pub fn retry_after(message: &str) -> Option<Duration> {
+    let (_, hint) = message.rsplit_once(RETRY_AFTER_PREFIX)?;
+    hint.strip_suffix("ms")?.parse().ok().map(Duration::from_millis)
+}
+
+#[async_trait]
+impl LLMInterceptor for RateLimitInterceptor {
+    fn stage(&self) -> InterceptorStage {
+        InterceptorStage::Authz
+    }
+
+    async fn intercept_llm_call(&self, context: &LLMCallContext) -> Result<InterceptorDecision> {
+        let model_limit =
+            self.model_limits.get(&context.model).copied().or(self.default_model_limit);
+        let applicable = model_limit
+            .map(|limit| (BucketKey::Model(context.model.clone()), limit))
+            .into_iter()
+            .chain(self.agent_bucket(&context.metadata))
+            .collect();
+        Ok(self.acquire(applicable))
+    }
+
+    async fn on_llm_call_complete(
+        &self,
+        _context: &LLMCallContext,
+        _result: &Result<Value>,
+        _duration_ms: u64,
+    ) {
+    }
+}
+
+#[async_trait]
+impl ToolInterceptor for RateLimitInterceptor {
+    fn stage(&self) -> InterceptorStage {
+        InterceptorStage::Authz
+    }
+
+    async fn intercept_tool_call(&self, context: &ToolCallContext) -> Result<InterceptorDecision> {
+        let tool_limit =
+            self.tool_limits.get(&context.tool_name).copied().or(self.default_tool_limit);
+        let applicable = tool_limit
+            .map(|limit| (BucketKey::Tool(context.tool_name.clone()), limit))
+            .into_iter()
+            .chain(self.agent_bucket(&context.metadata))
+            .collect();
+        Ok(self.acquire(applicable))
+    }
+
+    async fn on_tool_call_complete(
+        &self,
+        _context: &ToolCallContext,
+        _result: &Result<Value>,
+        _duration_ms: u64,
+    ) {
+    }
+}
@@ -5,14 +5,18 @@
 //!
 //! These spans are children of invoke_function/invoke_baml_function spans and inherit
 //! context automatically through OTEL span nesting - no need to repeat attributes.
+//!
+//! Logged payloads follow the process-wide [`PayloadCapture`] detail.
 
 use baml_rt_core::Result;
+use crate::capture::PayloadCapture;
 use crate::interceptor::{
-    InterceptorDecision, LLMCallContext, LLMInterceptor, ToolCallContext, ToolInterceptor,
+    InterceptorDecision, LLMCallContext, LLMChunk, LLMInterceptor, ToolCallContext,
+    ToolCallRetry, ToolInterceptor,
 };
 use async_trait::async_trait;
 use serde_json::Value;
-use tracing::{error, info, span, Level};
+use tracing::{debug, error, info, span, warn, Level};
 
 /// Tracing interceptor for LLM calls
 ///
@@ -47,8 +51,9 @@ impl LLMInterceptor for TracingLLMInterceptor {
         );
         let _guard = span.enter();
 
+        let capture = PayloadCapture::global();
         info!(
-            prompt = ?context.prompt,
+            prompt = ?capture.capture(&context.context_id, &context.prompt),
             metadata = ?context.metadata,
             "LLM call intercepted (pre-execution)"
         );
@@ -77,7 +82,7 @@ impl LLMInterceptor for TracingLLMInterceptor {
         match result {
             Ok(value) => {
                 info!(
-                    result = ?value,
+                    result = ?PayloadCapture::global().capture(&context.context_id, value),
                     success = true,
                     "LLM call completed"
                 );
@@ -91,6 +96,23 @@ impl LLMInterceptor for TracingLLMInterceptor {
             }
         }
     }
+
+    async fn on_llm_chunk(
+        &self,
+        context: &LLMCallContext,
+        chunk: &LLMChunk,
+    ) -> Result<InterceptorDecision> {
+        debug!(
+            function = %context.function_name,
+            context_id = %context.context_id,
+            chunk_index = chunk.index,
+            elapsed_ms = chunk.elapsed_ms,
+            estimated_tokens = chunk.usage.total_tokens,
+            "LLM stream chunk received"
+        );
+
+        Ok(InterceptorDecision::Allow)
+    }
 }
 
 /// Tracing interceptor for tool calls
@@ -126,8 +148,9 @@ impl ToolInterceptor for TracingToolInterceptor {
         let _guard = span.enter();
 
         // Use structured fields - no string interpolation in log messages
+        let capture = PayloadCapture::global();
         info!(
-            args = ?context.args,
+            args = ?capture.capture(&context.context_id, &context.args),
             metadata = ?context.metadata,
             "Tool call intercepted"
         );
@@ -155,7 +178,7 @@ impl ToolInterceptor for TracingToolInterceptor {
         match result {
             Ok(value) => {
                 info!(
-                    result = ?value,
+                    result = ?PayloadCapture::global().capture(&context.context_id, value),
                     success = true,
                     "Tool call completed"
                 );
@@ -169,6 +192,26 @@ impl ToolInterceptor for TracingToolInterceptor {
             }
         }
     }
+
+    async fn on_tool_call_retry(&self, context: &ToolCallContext, retry: &ToolCallRetry) {
+        let span = span!(
+            Level::DEBUG,
+            "baml_rt.tool_call_retry",
+            tool = %context.tool_name,
+            attempt = retry.attempt,
+            context_id = %context.context_id,
+        );
+        let _guard = span.enter();
+
+        warn!(
+            max_attempts = retry.max_attempts,
+            failure_kind = retry.failure_kind.as_str(),
+            error = retry.error.as_str(),
+            duration_ms = retry.duration_ms,
+            backoff_ms = retry.backoff_ms,
+            "Tool call attempt failed; retrying"
+        );
+    }
 }
 
 /// Combined tracing interceptor for both LLM and tool calls
@@ -211,6 +254,14 @@ impl LLMInterceptor for TracingInterceptor {
     ) {
         self.llm.on_llm_call_complete(context, result, duration_ms).await;
     }
+
+    async fn on_llm_chunk(
+        &self,
+        context: &LLMCallContext,
+        chunk: &LLMChunk,
+    ) -> Result<InterceptorDecision> {
+        self.llm.on_llm_chunk(context, chunk).await
+    }
 }
 
 // Delegate ToolInterceptor implementation
@@ -228,4 +279,8 @@ impl ToolInterceptor for TracingInterceptor {
     ) {
         self.tool.on_tool_call_complete(context, result, duration_ms).await;
     }
+
+    async fn on_tool_call_retry(&self, context: &ToolCallContext, retry: &ToolCallRetry) {
+        self.tool.on_tool_call_retry(context, retry).await;
+    }
 }
@@ -1,10 +1,36 @@
 //! Interceptor interfaces and implementations.
 
+pub mod approval;
+pub mod budget;
+pub mod capture;
 pub mod interceptor;
 pub mod interceptors;
+pub mod policy;
+pub mod reload;
+pub mod usage;
 
+pub use approval::{
+    ApprovalInterceptor, ApprovalObserver, ApprovalOutcome, ApprovalVerdict, OperatorCredentials,
+    PendingApproval, PendingApprovalQueue, DEFAULT_APPROVAL_TIMEOUT,
+};
+pub use budget::{
+    BudgetDimension, BudgetExhausted, BudgetLimit, BudgetObserver, BudgetScope, UsageBudget,
+};
+pub use capture::{CaptureDetail, CaptureOverride, PayloadCapture, DEFAULT_MAX_PAYLOAD_CHARS};
 pub use interceptor::{
-    InterceptorDecision, InterceptorPipeline, InterceptorRegistry, LLMCallContext, LLMInterceptor,
-    ToolCallContext, ToolInterceptor,
+    InterceptorDecision, InterceptorPipeline, InterceptorRegistry, InterceptorStage,
+    LLMCallContext, LLMChunk, LLMInterceptor, ToolCallContext, ToolCallRetry, ToolInterceptor,
+    ERROR_METADATA_KEY, FUNCTION_ARGS_METADATA_KEY, FUNCTION_OUTPUT_METADATA_KEY,
+    OUTPUT_REPAIR_METADATA_KEY, OUTPUT_VALIDATION_METADATA_KEY, RETRY_METADATA_KEY,
+    STREAM_ID_METADATA_KEY,
+};
+pub use interceptors::{
+    retry_after, RateLimit, RateLimitInterceptor, TracingInterceptor, TracingLLMInterceptor,
+    TracingToolInterceptor,
+};
+pub use policy::{PolicyDecision, PolicyInterceptor, PolicyObserver};
+pub use reload::Reloadable;
+pub use usage::{
+    HeuristicTokenizer, StreamUsageTracker, TokenEstimator, TokenUsage, UsageReport, UsageSource,
+    USAGE_METADATA_KEY,
 };
-pub use interceptors::{TracingInterceptor, TracingLLMInterceptor, TracingToolInterceptor};
@@ -0,0 +1,242 @@
+//! Declarative authorization of tool and LLM calls.
+//!
+//! [`PolicyInterceptor`] checks the [`PolicyRule`]s an agent's manifest declares
+//! against each call, in order. The first rule whose CEL `when` expression is
+//! true decides the call: `deny` blocks it with the rule's message, `allow` lets
+//! it through without consulting later rules. A call no rule matches is
+//! allowed. An expression that fails to evaluate, or evaluates to something
+//! other than a boolean, denies the call, so a mistake in a rule fails closed.
+//!
+//! Expressions see the call as `call`:
+//! - `call.kind`: `"tool"` or `"llm"`
+//! - `call.name`: the tool, or the BAML function making the LLM call
+//! - `call.args`: the call's arguments
+//! - `call.agent_id`: the calling agent, `""` when unknown
+//! - `call.context_id`
+//! - `call.model` and `call.client`: the LLM call's; `""` for tool calls
+//! - `call.metadata`
+//!
+//! e.g. `call.name == "fs/write" && !call.args.path.startsWith("/tmp/")`. Use
+//! `has(call.args.path)` before reading an argument that may be missing.
+//!
+//! Policies run in the authz stage, after transforms, so they judge the
+//! arguments the call will actually run with.
+//!
+//! Every decision a rule makes is reported to each [`PolicyObserver`].
+
+use crate::interceptor::{
+    InterceptorDecision, InterceptorStage, LLMCallContext, LLMInterceptor, ToolCallContext,
+    ToolInterceptor,
+};
+use async_trait::async_trait;
+use baml_rt_core::context;
+use baml_rt_core::ids::ContextId;
+use baml_rt_core::manifest::{PolicyEffect, PolicyRule, PolicyTarget};
+use baml_rt_core::{BamlRtError, Result};
+use cel_interpreter::Program;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// What a policy rule decided about one call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyDecision {
+    pub rule_id: String,
+    pub effect: PolicyEffect,
+    pub target: PolicyTarget,
+    /// The tool, or the BAML function making the LLM call
+    pub subject: String,
+    /// Why the call was denied; `None` when it was allowed
+    pub reason: Option<String>,
+}
+
+impl PolicyDecision {
+    pub fn allowed(&self) -> bool {
+        self.effect == PolicyEffect::Allow
+    }
+}
+
+/// Notified of every call a policy rule decided
+#[async_trait]
+pub trait PolicyObserver: Send + Sync + 'static {
+    async fn on_policy_decision(&self, context_id: &ContextId, decision: &PolicyDecision);
+}
+
+struct CompiledRule {
+    rule: PolicyRule,
+    program: Program,
+}
+
+impl CompiledRule {
+    fn applies_to(&self, target: PolicyTarget) -> bool {
+        self.rule.applies_to.is_none_or(|applies_to| applies_to == target)
+    }
+
+    fn matches(&self, call: &Value) -> std::result::Result<bool, String> {
+        let mut variables = cel_interpreter::Context::default();
+        variables.add_variable("call", call).map_err(|err| err.to_string())?;
+        match self.program.execute(&variables).map_err(|err| err.to_string())? {
+            cel_interpreter::Value::Bool(matched) => Ok(matched),
+            other => Err(format!("expected a boolean, got {:?}", other)),
+        }
+    }
+}
+
+/// Authorizes tool and LLM calls against declarative policy rules, as both an
+/// [`LLMInterceptor`] and a [`ToolInterceptor`]
+///
+/// Clones share their compiled rules.
+#[derive(Clone)]
+pub struct PolicyInterceptor {
+    rules: Arc<Vec<CompiledRule>>,
+    observers: Vec<Arc<dyn PolicyObserver>>,
+}
+
+impl PolicyInterceptor {
+    /// Compile `rules`, failing on the first whose expression does not parse
+    pub fn new(rules: impl IntoIterator<Item = PolicyRule>) -> Result<Self> {
+        let rules = rules
+            .into_iter()
+            .map(|rule| {
+                let program = Program::compile(&rule.when).map_err(|err| {
+                    BamlRtError::Configuration(format!(
+                        "policy '{}' does not compile: {}",
+                        rule.id, err
+                    ))
+                })?;
+                Ok(CompiledRule { rule, program })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { rules: Arc::new(rules), observers: Vec::new() })
+    }
+
+    /// Report each decision to `observer`
+    pub fn with_observer(mut self, observer: Arc<dyn PolicyObserver>) -> Self {
+        self.observers.push(observer);
+        self
+    }
+
+    pub fn rules(&self) -> impl Iterator<Item = &PolicyRule> {
+        self.rules.iter().map(|compiled| &compiled.rule)
+    }
+
+    /// The decision of the first rule `call` matches, if any
+    fn evaluate(&self, target: PolicyTarget, subject: &str, call: &Value) -> Option<PolicyDecision> {
+        let decision = |rule: &PolicyRule, effect, reason| PolicyDecision {
+            rule_id: rule.id.clone(),
+            effect,
+            target,
+            subject: subject.to_string(),
+            reason,
+        };
+        for compiled in self.rules.iter().filter(|compiled| compiled.applies_to(target)) {
+            let rule = &compiled.rule;
+            match compiled.matches(call) {
+                Ok(false) => {}
+                Ok(true) => {
+                    let reason = (rule.effect == PolicyEffect::Deny).then(|| {
+                        rule.message
+                            .clone()
+                            .unwrap_or_else(|| format!("denied by policy '{}'", rule.id))
+                    });
+                    return Some(decision(rule, rule.effect, reason));
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        policy = rule.id.as_str(),
+                        subject,
+                        error = err.as_str(),
+                        "Policy failed to evaluate; denying the call"
+                    );
+                    let reason = format!("policy '{}' failed to evaluate: {}", rule.id, err);
+                    return Some(decision(rule, PolicyEffect::Deny, Some(reason)));
+                }
+            }
+        }
+        None
+    }
+
+    async fn decide(
+        &self,
+        target: PolicyTarget,
+        subject: &str,
+        call: Value,
+        context_id: &ContextId,
+    ) -> Result<InterceptorDecision> {
+        let Some(decision) = self.evaluate(target, subject, &call) else {
+            return Ok(InterceptorDecision::Allow);
+        };
+        for observer in &self.observers {
+            observer.on_policy_decision(context_id, &decision).await;
+        }
+        Ok(match decision.reason {
+            Some(reason) => InterceptorDecision::Block(reason),
+            None => InterceptorDecision::Allow,
+        })
+    }
+}
+
+/// The calling agent, from the current scope or else `metadata.agent_id`
+fn agent_id(metadata: &Value) -> String {
+    context::current_agent_id()
+        .map(|agent_id| agent_id.as_str().to_string())
+        .or_else(|| metadata.get("agent_id").and_then(Value::as_str).map(str::to_string))
+        .unwrap_or_default()
+}
+
+#[async_trait]
+impl LLMInterceptor for PolicyInterceptor {
+    fn stage(&self) -> InterceptorStage {
+        InterceptorStage::Authz
+    }
+
+    async fn intercept_llm_call(&self, context: &LLMCallContext) -> Result<InterceptorDecision> {
+        let call = json!({
+            "kind": PolicyTarget::Llm.as_str(),
+            "name": context.function_name,
+            "args": context.args,
+            "agent_id": agent_id(&context.metadata),
+            "context_id": context.context_id.as_str(),
+            "model": context.model,
+            "client": context.client,
+            "metadata": context.metadata,
+        });
+        self.decide(PolicyTarget::Llm, &context.function_name, call, &context.context_id).await
+    }
+
+    async fn on_llm_call_complete(
+        &self,
+        _context: &LLMCallContext,
+        _result: &Result<Value>,
+        _duration_ms: u64,
+    ) {
+    }
+}
+
+#[async_trait]
+impl ToolInterceptor for PolicyInterceptor {
+    fn stage(&self) -> InterceptorStage {
+        InterceptorStage::Authz
+    }
+
+    async fn intercept_tool_call(&self, context: &ToolCallContext) -> Result<InterceptorDecision> {
+        let call = json!({
+            "kind": PolicyTarget::Tool.as_str(),
+            "name": context.tool_name,
+            "args": context.args,
+            "agent_id": agent_id(&context.metadata),
+            "context_id": context.context_id.as_str(),
+            "model": "",
+            "client": "",
+            "metadata": context.metadata,
+        });
+        self.decide(PolicyTarget::Tool, &context.tool_name, call, &context.context_id).await
+    }
+
+    async fn on_tool_call_complete(
+        &self,
+        _context: &ToolCallContext,
+        _result: &Result<Value>,
+        _duration_ms: u64,
+    ) {
+    }
+}
@@ -0,0 +1,112 @@
+//! Interceptors that can be replaced while registered.
+//!
+//! Registries keep their interceptors for the life of the runtime. Registering
+//! a [`Reloadable`] instead lets a new configuration take effect by calling
+//! [`Reloadable::replace`]; each hook runs against whichever interceptor is
+//! current when it is called.
+//!
+//! Interceptors that keep state, such as [`crate::RateLimitInterceptor`]'s
+//! buckets or [`crate::UsageBudget`]'s ledger, should be rebuilt sharing that
+//! state with the one they replace. Otherwise a reload resets every limit, and
+//! a call that started before it completes against a fresh ledger.
+
+use crate::interceptor::{
+    InterceptorDecision, InterceptorStage, LLMCallContext, LLMChunk, LLMInterceptor,
+    ToolCallContext, ToolCallRetry, ToolInterceptor,
+};
+use async_trait::async_trait;
+use baml_rt_core::Result;
+use serde_json::Value;
+use std::sync::{Arc, RwLock};
+
+/// A swappable interceptor
+///
+/// Clones share the slot, so a clone kept by whoever applies configuration
+/// replaces the interceptor every registered clone delegates to.
+pub struct Reloadable<T> {
+    current: Arc<RwLock<Arc<T>>>,
+}
+
+impl<T> Clone for Reloadable<T> {
+    fn clone(&self) -> Self {
+        Self { current: self.current.clone() }
+    }
+}
+
+impl<T> Reloadable<T> {
+    pub fn new(interceptor: T) -> Self {
+        Self { current: Arc::new(RwLock::new(Arc::new(interceptor))) }
+    }
+
+    /// The interceptor calls are delegated to
+    pub fn current(&self) -> Arc<T> {
+        self.current.read().unwrap().clone()
+    }
+
+    /// Delegate later calls to `interceptor`
+    pub fn replace(&self, interceptor: T) {
+        *self.current.write().unwrap() = Arc::new(interceptor);
+    }
+}
+
+#[async_trait]
+impl<T: LLMInterceptor> LLMInterceptor for Reloadable<T> {
+    /// Read once, when the registry orders its pipeline.
+    fn stage(&self) -> InterceptorStage {
+        self.current().stage()
+    }
+
+    fn priority(&self) -> i32 {
+        self.current().priority()
+    }
+
+    async fn intercept_llm_call(&self, context: &LLMCallContext) -> Result<InterceptorDecision> {
+        self.current().intercept_llm_call(context).await
+    }
+
+    async fn on_llm_call_complete(
+        &self,
+        context: &LLMCallContext,
+        result: &Result<Value>,
+        duration_ms: u64,
+    ) {
+        self.current().on_llm_call_complete(context, result, duration_ms).await
+    }
+
+    async fn on_llm_chunk(
+        &self,
+        context: &LLMCallContext,
+        chunk: &LLMChunk,
+    ) -> Result<InterceptorDecision> {
+        self.current().on_llm_chunk(context, chunk).await
+    }
+}
+
+#[async_trait]
+impl<T: ToolInterceptor> ToolInterceptor for Reloadable<T> {
+    /// Read once, when the registry orders its pipeline.
+    fn stage(&self) -> InterceptorStage {
+        self.current().stage()
+    }
+
+    fn priority(&self) -> i32 {
+        self.current().priority()
+    }
+
+    async fn intercept_tool_call(&self, context: &ToolCallContext) -> Result<InterceptorDecision> {
+        self.current().intercept_tool_call(context).await
+    }
+
+    async fn on_tool_call_complete(
+        &self,
+        context: &ToolCallContext,
+        result: &Result<Value>,
+        duration_ms: u64,
+    ) {
+        self.current().on_tool_call_complete(context, result, duration_ms).await
+    }
+
+    async fn on_tool_call_retry(&self, context: &ToolCallContext, retry: &ToolCallRetry) {
+        self.current().on_tool_call_retry(context, retry).await
+    }
+}
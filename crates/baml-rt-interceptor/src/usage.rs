@@ -0,0 +1,149 @@
+//! Token usage of LLM calls, including calls that are still streaming.
+//!
+//! Providers report usage once a call finishes, and for streamed calls often not
+//! at all. [`StreamUsageTracker`] estimates prompt tokens when a stream opens and
+//! completion tokens from every partial result, so interceptors see in-flight
+//! consumption on each [`crate::LLMChunk`]. When the stream ends the estimate is
+//! reconciled with whatever the provider reported: reported counts win, and the
+//! estimate is kept alongside them so its accuracy can be checked.
+//!
+//! Estimation goes through a [`TokenEstimator`], normally the one the runtime's
+//! [`baml_rt_core::tokens::TokenizerRegistry`] holds for the call's model.
+
+pub use baml_rt_core::tokens::{HeuristicTokenizer, TokenEstimator};
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+/// `metadata` key holding the reconciled usage of a completed streamed call
+pub const USAGE_METADATA_KEY: &str = "token_usage";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+}
+
+impl TokenUsage {
+    pub fn new(prompt_tokens: u64, completion_tokens: u64) -> Self {
+        Self {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens.saturating_add(completion_tokens),
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        json!({
+            "prompt_tokens": self.prompt_tokens,
+            "completion_tokens": self.completion_tokens,
+            "total_tokens": self.total_tokens,
+        })
+    }
+
+    /// Parse the shape written by [`TokenUsage::to_value`].
+    pub fn from_value(value: &Value) -> Option<Self> {
+        let prompt_tokens = value.get("prompt_tokens").and_then(Value::as_u64)?;
+        let completion_tokens = value.get("completion_tokens").and_then(Value::as_u64)?;
+        let total_tokens = value
+            .get("total_tokens")
+            .and_then(Value::as_u64)
+            .unwrap_or(prompt_tokens.saturating_add(completion_tokens));
+        Some(Self { prompt_tokens, completion_tokens, total_tokens })
+    }
+
+    /// Usage recorded under [`USAGE_METADATA_KEY`] in call metadata.
+    pub fn from_metadata(metadata: &Value) -> Option<Self> {
+        metadata.get(USAGE_METADATA_KEY).and_then(Self::from_value)
+    }
+}
+
+/// Where the final usage of a call came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsageSource {
+    /// Counted by the provider.
+    Reported,
+    /// Estimated by the runtime because the provider reported nothing.
+    Estimated,
+}
+
+impl UsageSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UsageSource::Reported => "reported",
+            UsageSource::Estimated => "estimated",
+        }
+    }
+}
+
+/// Final usage of a streamed call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UsageReport {
+    /// Reported usage when the provider sent it, otherwise the estimate.
+    pub usage: TokenUsage,
+    pub source: UsageSource,
+    /// What the runtime estimated while the call streamed.
+    pub estimated: TokenUsage,
+}
+
+impl UsageReport {
+    /// The shape stored under [`USAGE_METADATA_KEY`].
+    pub fn to_value(&self) -> Value {
+        let mut value = self.usage.to_value();
+        if let Value::Object(map) = &mut value {
+            map.insert("source".to_string(), json!(self.source.as_str()));
+            map.insert("estimated".to_string(), self.estimated.to_value());
+        }
+        value
+    }
+}
+
+/// Estimated usage of one streamed call.
+pub struct StreamUsageTracker {
+    estimator: Arc<dyn TokenEstimator>,
+    prompt_tokens: u64,
+    completion_tokens: u64,
+}
+
+impl StreamUsageTracker {
+    /// Start tracking a call, estimating the tokens in its rendered `prompt`.
+    pub fn new(estimator: Arc<dyn TokenEstimator>, prompt: &Value) -> Self {
+        let prompt_tokens = count_value_tokens(estimator.as_ref(), prompt);
+        Self { estimator, prompt_tokens, completion_tokens: 0 }
+    }
+
+    /// Account for a partial result and return the usage so far.
+    ///
+    /// Partials are cumulative, so the completion estimate is the size of the
+    /// latest partial; it never decreases, even if a parser reshapes the partial.
+    pub fn observe(&mut self, partial: &Value) -> TokenUsage {
+        let completion = count_value_tokens(self.estimator.as_ref(), partial);
+        self.completion_tokens = self.completion_tokens.max(completion);
+        self.estimated()
+    }
+
+    pub fn estimated(&self) -> TokenUsage {
+        TokenUsage::new(self.prompt_tokens, self.completion_tokens)
+    }
+
+    /// Final usage: `reported` when the provider sent it, otherwise the estimate.
+    pub fn reconcile(&self, reported: Option<TokenUsage>) -> UsageReport {
+        let estimated = self.estimated();
+        match reported {
+            Some(usage) => UsageReport { usage, source: UsageSource::Reported, estimated },
+            None => UsageReport { usage: estimated, source: UsageSource::Estimated, estimated },
+        }
+    }
+}
+
+/// Tokens in the text of `value`: string contents count, JSON syntax does not.
+fn count_value_tokens(estimator: &dyn TokenEstimator, value: &Value) -> u64 {
+    match value {
+        Value::String(text) => estimator.count_tokens(text),
+        Value::Array(items) => items.iter().map(|item| count_value_tokens(estimator, item)).sum(),
+        Value::Object(map) => map.values().map(|item| count_value_tokens(estimator, item)).sum(),
+        Value::Number(number) => estimator.count_tokens(&number.to_string()),
+        Value::Bool(_) => 1,
+        Value::Null => 0,
+    }
+}
@@ -0,0 +1,118 @@
+//! Tool calls held until someone approves them.
+
+use async_trait::async_trait;
+use baml_rt_core::ids::ContextId;
+use baml_rt_interceptor::{
+    ApprovalInterceptor, ApprovalObserver, ApprovalOutcome, ApprovalVerdict, InterceptorDecision,
+    PendingApprovalQueue, ToolCallContext, ToolInterceptor,
+};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+fn tool_call(tool: &str, args: Value) -> ToolCallContext {
+    ToolCallContext {
+        tool_name: tool.to_string(),
+        function_name: None,
+        args,
+        context_id: ContextId::new(1, 1),
+        metadata: json!({}),
+    }
+}
+
+#[derive(Default)]
+struct Recorder {
+    outcomes: Mutex<Vec<ApprovalOutcome>>,
+}
+
+#[async_trait]
+impl ApprovalObserver for Recorder {
+    async fn on_approval_decided(&self, outcome: &ApprovalOutcome) {
+        self.outcomes.lock().unwrap().push(outcome.clone());
+    }
+}
+
+/// Wait until `queue` holds a call, as an operator polling it would.
+async fn next_pending(queue: &PendingApprovalQueue) -> String {
+    loop {
+        if let Some(pending) = queue.list().into_iter().next() {
+            return pending.approval_id;
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+}
+
+#[tokio::test]
+async fn approved_calls_go_ahead_and_denied_ones_are_blocked() {
+    let queue = PendingApprovalQueue::new();
+    let recorder = Arc::new(Recorder::default());
+    let approvals = Arc::new(
+        ApprovalInterceptor::new(queue.clone(), ["payments/*".to_string()])
+            .with_observer(recorder.clone()),
+    );
+
+    let call = tokio::spawn({
+        let approvals = approvals.clone();
+        async move {
+            let call = tool_call("payments/refund", json!({"amount": 120}));
+            approvals.intercept_tool_call(&call).await
+        }
+    });
+    let approval_id = next_pending(&queue).await;
+    let pending = queue.list();
+    assert_eq!(pending[0].tool_name, "payments/refund");
+    assert_eq!(pending[0].args, json!({"amount": 120}));
+    queue.approve(&approval_id, "ops@example.com").expect("approve");
+    assert_eq!(call.await.unwrap().unwrap(), InterceptorDecision::Allow);
+    assert!(queue.list().is_empty());
+
+    let call = tokio::spawn({
+        let approvals = approvals.clone();
+        async move {
+            let call = tool_call("payments/refund", json!({"amount": 9000}));
+            approvals.intercept_tool_call(&call).await
+        }
+    });
+    let approval_id = next_pending(&queue).await;
+    queue
+        .deny(&approval_id, "ops@example.com", Some("over the refund limit".to_string()))
+        .expect("deny");
+    assert_eq!(
+        call.await.unwrap().unwrap(),
+        InterceptorDecision::Block(
+            "payments/refund denied by ops@example.com: over the refund limit".to_string()
+        )
+    );
+    assert!(queue.approve(&approval_id, "ops@example.com").is_err(), "already decided");
+
+    let outcomes = recorder.outcomes.lock().unwrap().clone();
+    assert_eq!(outcomes.len(), 2);
+    assert_eq!(outcomes[0].verdict, ApprovalVerdict::Approved);
+    assert_eq!(outcomes[0].approver.as_deref(), Some("ops@example.com"));
+    assert_eq!(outcomes[1].verdict, ApprovalVerdict::Denied);
+    assert_eq!(outcomes[1].reason.as_deref(), Some("over the refund limit"));
+}
+
+#[tokio::test]
+async fn undecided_calls_time_out_and_other_tools_pass() {
+    let queue = PendingApprovalQueue::new();
+    let recorder = Arc::new(Recorder::default());
+    let approvals = ApprovalInterceptor::new(queue.clone(), ["fs/delete".to_string()])
+        .with_timeout(Duration::from_millis(20))
+        .with_observer(recorder.clone());
+
+    let decision = approvals.intercept_tool_call(&tool_call("fs/read", json!({}))).await.unwrap();
+    assert_eq!(decision, InterceptorDecision::Allow);
+
+    let decision = approvals.intercept_tool_call(&tool_call("fs/delete", json!({}))).await.unwrap();
+    assert!(
+        matches!(&decision, InterceptorDecision::Block(reason) if reason.contains("not approved")),
+        "{decision:?}"
+    );
+    assert!(queue.list().is_empty(), "timed out calls leave the queue");
+
+    let outcomes = recorder.outcomes.lock().unwrap().clone();
+    assert_eq!(outcomes.len(), 1);
+    assert_eq!(outcomes[0].verdict, ApprovalVerdict::TimedOut);
+    assert_eq!(outcomes[0].approver, None);
+}
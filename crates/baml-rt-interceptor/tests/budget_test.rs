@@ -0,0 +1,104 @@
+//! Token budgets on LLM calls.
+
+use async_trait::async_trait;
+use baml_rt_core::ids::ContextId;
+use baml_rt_interceptor::{
+    BudgetDimension, BudgetExhausted, BudgetLimit, BudgetObserver, BudgetScope,
+    InterceptorDecision, LLMCallContext, LLMInterceptor, TokenUsage, UsageBudget,
+};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+
+fn llm_call(context_id: ContextId, metadata: Value) -> LLMCallContext {
+    LLMCallContext {
+        client: "client".to_string(),
+        model: "model".to_string(),
+        function_name: "Summarize".to_string(),
+        args: json!({}),
+        context_id,
+        prompt: json!({"messages": []}),
+        metadata,
+    }
+}
+
+fn used(prompt_tokens: u64, completion_tokens: u64) -> Value {
+    json!({"usage": {"input_tokens": prompt_tokens, "output_tokens": completion_tokens}})
+}
+
+#[derive(Default)]
+struct Recorder {
+    tripped: Mutex<Vec<BudgetExhausted>>,
+}
+
+#[async_trait]
+impl BudgetObserver for Recorder {
+    async fn on_budget_exhausted(&self, _context: &LLMCallContext, exhausted: &BudgetExhausted) {
+        self.tripped.lock().unwrap().push(exhausted.clone());
+    }
+}
+
+#[tokio::test]
+async fn calls_are_refused_once_a_context_spent_its_budget() {
+    let recorder = Arc::new(Recorder::default());
+    let budget = UsageBudget::new()
+        .with_context_limit(BudgetLimit::total_tokens(100))
+        .with_observer(recorder.clone());
+    let context_id = ContextId::new(1, 1);
+    let scope = BudgetScope::Context(context_id.clone());
+
+    let call = llm_call(context_id.clone(), used(40, 20));
+    for _ in 0..2 {
+        let decision = budget.intercept_llm_call(&call).await.expect("decision");
+        assert!(matches!(decision, InterceptorDecision::Allow));
+        budget.on_llm_call_complete(&call, &Ok(json!({})), 10).await;
+    }
+    assert_eq!(budget.spent(&scope), TokenUsage::new(80, 40));
+
+    match budget.intercept_llm_call(&call).await.expect("decision") {
+        InterceptorDecision::OverBudget(message) => {
+            assert!(message.contains("120 of 100 total_tokens"), "{message}");
+        }
+        other => panic!("expected the budget to block the call, got {other:?}"),
+    }
+
+    let tripped = recorder.tripped.lock().unwrap().clone();
+    assert_eq!(tripped.len(), 1);
+    assert_eq!(tripped[0].scope, scope);
+    assert_eq!(tripped[0].dimension, BudgetDimension::TotalTokens);
+    assert_eq!(tripped[0].limit, 100);
+
+    // Other contexts keep their own budget, and a reset lifts the block.
+    let other = llm_call(ContextId::new(1, 2), used(1, 1));
+    let decision = budget.intercept_llm_call(&other).await.expect("decision");
+    assert!(matches!(decision, InterceptorDecision::Allow));
+    budget.reset(&scope);
+    let decision = budget.intercept_llm_call(&call).await.expect("decision");
+    assert!(matches!(decision, InterceptorDecision::Allow));
+}
+
+#[tokio::test]
+async fn individual_token_counts_can_be_limited() {
+    let budget = UsageBudget::new()
+        .with_context_limit(BudgetLimit::default().with_completion_tokens(50));
+    let call = llm_call(ContextId::new(2, 1), used(1_000, 60));
+
+    budget.on_llm_call_complete(&call, &Ok(json!({})), 10).await;
+    match budget.intercept_llm_call(&call).await.expect("decision") {
+        InterceptorDecision::OverBudget(message) => {
+            assert!(message.contains("60 of 50 completion_tokens"), "{message}");
+        }
+        other => panic!("expected the budget to block the call, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn calls_without_reported_usage_are_not_counted() {
+    let budget = UsageBudget::new().with_context_limit(BudgetLimit::total_tokens(1));
+    let context_id = ContextId::new(3, 1);
+    let call = llm_call(context_id.clone(), json!({"usage": null}));
+
+    budget.on_llm_call_complete(&call, &Ok(json!({})), 10).await;
+    assert_eq!(budget.spent(&BudgetScope::Context(context_id)), TokenUsage::default());
+    let decision = budget.intercept_llm_call(&call).await.expect("decision");
+    assert!(matches!(decision, InterceptorDecision::Allow));
+}
@@ -0,0 +1,32 @@
+use baml_rt_core::ids::ContextId;
+use baml_rt_interceptor::capture::{CaptureDetail, PayloadCapture};
+use serde_json::json;
+use std::time::Duration;
+
+#[test]
+fn standard_capture_truncates_long_payloads() {
+    let capture = PayloadCapture::new(5);
+    let context_id = ContextId::new(1, 1);
+    assert_eq!(capture.capture(&context_id, &json!("short")), json!("short"));
+    assert_eq!(
+        capture.capture(&context_id, &json!("much longer")),
+        json!({"truncated": true, "chars": 11, "preview": "much "})
+    );
+}
+
+#[test]
+fn raised_capture_is_scoped_and_expires() {
+    let capture = PayloadCapture::new(1);
+    let raised = ContextId::new(1, 1);
+    let other = ContextId::new(1, 2);
+    capture.raise(Some(raised.clone()), Duration::from_secs(60));
+    assert_eq!(capture.detail(&raised), CaptureDetail::Full);
+    assert_eq!(capture.detail(&other), CaptureDetail::Standard);
+    assert_eq!(capture.capture(&raised, &json!("payload")), json!("payload"));
+
+    capture.raise(None, Duration::ZERO);
+    assert_eq!(capture.detail(&other), CaptureDetail::Standard);
+
+    capture.reset(None);
+    assert_eq!(capture.detail(&raised), CaptureDetail::Standard);
+}
@@ -1,7 +1,8 @@
 //! Integration and end-to-end tests for LLM interception.
 
 use baml_rt::{
-    interceptor::{LLMInterceptor, LLMCallContext, InterceptorDecision},
+    interceptor::{InterceptorRegistry, LLMInterceptor, LLMCallContext, LLMChunk, InterceptorDecision},
+    TokenUsage,
     error::Result,
 };
 use serde_json::Value;
@@ -577,3 +578,69 @@ async fn test_e2e_llm_interceptor_with_baml_execution() {
     
     tracing::info!("🎉 E2E LLM interceptor test completed successfully!");
 }
+
+/// Test interceptor that aborts streams after a fixed number of chunks
+struct ChunkLimit {
+    max_chunks: u64,
+}
+
+#[async_trait::async_trait]
+impl LLMInterceptor for ChunkLimit {
+    async fn intercept_llm_call(&self, _context: &LLMCallContext) -> Result<InterceptorDecision> {
+        Ok(InterceptorDecision::Allow)
+    }
+
+    async fn on_llm_call_complete(
+        &self,
+        _context: &LLMCallContext,
+        _result: &Result<Value>,
+        _duration_ms: u64,
+    ) {
+    }
+
+    async fn on_llm_chunk(
+        &self,
+        _context: &LLMCallContext,
+        chunk: &LLMChunk,
+    ) -> Result<InterceptorDecision> {
+        if chunk.index >= self.max_chunks {
+            return Ok(InterceptorDecision::Block("stream too long".to_string()));
+        }
+        Ok(InterceptorDecision::Allow)
+    }
+}
+
+#[tokio::test]
+async fn test_chunk_interceptor_aborts_long_streams() {
+    let mut registry = InterceptorRegistry::new();
+    // Interceptors without a chunk callback allow every chunk.
+    let (tracker, _calls) = PreExecutionTracker::new();
+    registry.register_llm_interceptor(tracker);
+    registry.register_llm_interceptor(ChunkLimit { max_chunks: 2 });
+
+    let context = LLMCallContext {
+        client: "client".to_string(),
+        model: "model".to_string(),
+        function_name: "SimpleGreeting".to_string(),
+        args: serde_json::json!({}),
+        context_id: context::current_or_new(),
+        prompt: serde_json::json!({}),
+        metadata: serde_json::json!({}),
+    };
+    let chunk = |index| LLMChunk {
+        index,
+        elapsed_ms: index * 10,
+        partial: serde_json::json!("Hel"),
+        usage: TokenUsage::default(),
+    };
+
+    assert!(matches!(
+        registry.intercept_llm_chunk(&context, &chunk(1)).await,
+        Ok(InterceptorDecision::Allow)
+    ));
+    let err = registry
+        .intercept_llm_chunk(&context, &chunk(2))
+        .await
+        .expect_err("third chunk aborts the stream");
+    assert!(err.to_string().contains("stream too long"));
+}
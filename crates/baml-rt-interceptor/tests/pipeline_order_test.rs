@@ -0,0 +1,219 @@
+//! Stage and priority ordering of interceptor pipelines, and argument and
+//! prompt rewrites by transform-stage interceptors.
+
+use async_trait::async_trait;
+use baml_rt_core::ids::ContextId;
+use baml_rt_core::Result;
+use baml_rt_interceptor::{
+    InterceptorDecision, InterceptorRegistry, InterceptorStage, LLMCallContext, LLMInterceptor,
+    ToolCallContext, ToolInterceptor,
+};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+
+type Log = Arc<Mutex<Vec<(&'static str, Value)>>>;
+
+/// Logs the call it sees and answers with a fixed decision.
+struct Step {
+    name: &'static str,
+    stage: InterceptorStage,
+    priority: i32,
+    decision: InterceptorDecision,
+    log: Log,
+}
+
+impl Step {
+    fn new(name: &'static str, stage: InterceptorStage, log: &Log) -> Self {
+        Self { name, stage, priority: 0, decision: InterceptorDecision::Allow, log: log.clone() }
+    }
+
+    fn at_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    fn deciding(mut self, decision: InterceptorDecision) -> Self {
+        self.decision = decision;
+        self
+    }
+}
+
+#[async_trait]
+impl ToolInterceptor for Step {
+    fn stage(&self) -> InterceptorStage {
+        self.stage
+    }
+
+    fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    async fn intercept_tool_call(&self, context: &ToolCallContext) -> Result<InterceptorDecision> {
+        self.log.lock().unwrap().push((self.name, context.args.clone()));
+        Ok(self.decision.clone())
+    }
+
+    async fn on_tool_call_complete(
+        &self,
+        _context: &ToolCallContext,
+        _result: &Result<Value>,
+        _duration_ms: u64,
+    ) {
+    }
+}
+
+#[async_trait]
+impl LLMInterceptor for Step {
+    fn stage(&self) -> InterceptorStage {
+        self.stage
+    }
+
+    fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    async fn intercept_llm_call(&self, context: &LLMCallContext) -> Result<InterceptorDecision> {
+        self.log
+            .lock()
+            .unwrap()
+            .push((self.name, json!({"args": context.args, "prompt": context.prompt})));
+        Ok(self.decision.clone())
+    }
+
+    async fn on_llm_call_complete(
+        &self,
+        _context: &LLMCallContext,
+        _result: &Result<Value>,
+        _duration_ms: u64,
+    ) {
+    }
+}
+
+fn modify_args(args: Value) -> InterceptorDecision {
+    InterceptorDecision::Modify { args: Some(args), prompt: None }
+}
+
+fn tool_call(args: Value) -> ToolCallContext {
+    ToolCallContext {
+        tool_name: "support/search".to_string(),
+        function_name: None,
+        args,
+        context_id: ContextId::new(1, 1),
+        metadata: json!({}),
+    }
+}
+
+fn llm_call(args: Value, prompt: Value) -> LLMCallContext {
+    LLMCallContext {
+        client: "client".to_string(),
+        model: "model".to_string(),
+        function_name: "Summarize".to_string(),
+        args,
+        context_id: ContextId::new(1, 1),
+        prompt,
+        metadata: json!({}),
+    }
+}
+
+fn order(log: &Log) -> Vec<&'static str> {
+    log.lock().unwrap().iter().map(|(name, _)| *name).collect()
+}
+
+#[tokio::test]
+async fn interceptors_run_by_stage_then_priority_then_registration() {
+    let log = Log::default();
+    let mut registry = InterceptorRegistry::new();
+    registry.register_tool_interceptor(Step::new("provenance", InterceptorStage::Observe, &log));
+    registry.register_tool_interceptor(Step::new("redact", InterceptorStage::Transform, &log));
+    registry.register_tool_interceptor(Step::new("rate-limit", InterceptorStage::Authz, &log));
+    registry.register_tool_interceptor(Step::new("policy", InterceptorStage::Authz, &log).at_priority(-10));
+    registry.register_tool_interceptor(Step::new("schema", InterceptorStage::PreValidate, &log));
+    registry.register_tool_interceptor(Step::new("metrics", InterceptorStage::Observe, &log));
+
+    registry.intercept_tool_call(&tool_call(json!({}))).await.expect("allowed");
+    assert_eq!(order(&log), ["schema", "redact", "policy", "rate-limit", "provenance", "metrics"]);
+}
+
+#[tokio::test]
+async fn registration_can_override_the_declared_placement() {
+    let log = Log::default();
+    let mut registry = InterceptorRegistry::new();
+    registry.register_tool_interceptor(Step::new("authz", InterceptorStage::Authz, &log));
+    registry.register_tool_interceptor_at(
+        Step::new("tracing", InterceptorStage::Observe, &log),
+        InterceptorStage::PreValidate,
+        0,
+    );
+
+    registry.intercept_tool_call(&tool_call(json!({}))).await.expect("allowed");
+    assert_eq!(order(&log), ["tracing", "authz"]);
+}
+
+#[tokio::test]
+async fn transformed_arguments_reach_later_stages_and_the_caller() {
+    let log = Log::default();
+    let mut registry = InterceptorRegistry::new();
+    registry.register_tool_interceptor(Step::new("observe", InterceptorStage::Observe, &log));
+    registry.register_tool_interceptor(
+        Step::new("redact", InterceptorStage::Transform, &log)
+            .deciding(modify_args(json!({"query": "[redacted]"}))),
+    );
+    registry.register_tool_interceptor(
+        Step::new("authz", InterceptorStage::Authz, &log)
+            .deciding(modify_args(json!({"query": "ignored"}))),
+    );
+
+    let decision = registry
+        .intercept_tool_call(&tool_call(json!({"query": "alice@example.com"})))
+        .await
+        .expect("allowed");
+    assert_eq!(decision, modify_args(json!({"query": "[redacted]"})));
+
+    let seen = log.lock().unwrap().clone();
+    assert_eq!(seen[0], ("redact", json!({"query": "alice@example.com"})));
+    assert_eq!(seen[1], ("authz", json!({"query": "[redacted]"})));
+    assert_eq!(seen[2], ("observe", json!({"query": "[redacted]"})));
+}
+
+#[tokio::test]
+async fn llm_rewrites_combine_and_report_only_what_changed() {
+    let log = Log::default();
+    let mut registry = InterceptorRegistry::new();
+    let system = json!({"messages": [{"role": "system", "content": "Be brief."}]});
+    registry.register_llm_interceptor(
+        Step::new("strip-pii", InterceptorStage::Transform, &log)
+            .deciding(modify_args(json!({"text": "[redacted]"}))),
+    );
+    registry.register_llm_interceptor(
+        Step::new("system-prompt", InterceptorStage::Transform, &log).at_priority(1).deciding(
+            InterceptorDecision::Modify { args: None, prompt: Some(system.clone()) },
+        ),
+    );
+    registry.register_llm_interceptor(Step::new("observe", InterceptorStage::Observe, &log));
+
+    let decision = registry
+        .intercept_llm_call(&llm_call(json!({"text": "call 555-0100"}), json!({"messages": []})))
+        .await
+        .expect("allowed");
+    assert_eq!(
+        decision,
+        InterceptorDecision::Modify {
+            args: Some(json!({"text": "[redacted]"})),
+            prompt: Some(system.clone()),
+        }
+    );
+    let seen = log.lock().unwrap().clone();
+    assert_eq!(seen[2], ("observe", json!({"args": {"text": "[redacted]"}, "prompt": system})));
+
+    let log = Log::default();
+    let mut registry = InterceptorRegistry::new();
+    registry.register_llm_interceptor(
+        Step::new("noop", InterceptorStage::Transform, &log)
+            .deciding(modify_args(json!({"text": "unchanged"}))),
+    );
+    let decision = registry
+        .intercept_llm_call(&llm_call(json!({"text": "unchanged"}), json!({})))
+        .await
+        .expect("allowed");
+    assert_eq!(decision, InterceptorDecision::Allow);
+}
@@ -0,0 +1,172 @@
+//! Declarative policy rules on tool and LLM calls.
+
+use async_trait::async_trait;
+use baml_rt_core::ids::ContextId;
+use baml_rt_core::manifest::{PolicyEffect, PolicyRule, PolicyTarget};
+use baml_rt_core::Result;
+use baml_rt_interceptor::{
+    InterceptorDecision, InterceptorRegistry, InterceptorStage, LLMCallContext, LLMInterceptor,
+    PolicyDecision, PolicyInterceptor, PolicyObserver, ToolCallContext, ToolInterceptor,
+};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+
+fn rules(rules: Value) -> Vec<PolicyRule> {
+    serde_json::from_value(rules).expect("policy rules")
+}
+
+fn tool_call(tool: &str, args: Value) -> ToolCallContext {
+    ToolCallContext {
+        tool_name: tool.to_string(),
+        function_name: None,
+        args,
+        context_id: ContextId::new(1, 1),
+        metadata: json!({}),
+    }
+}
+
+fn llm_call(model: &str) -> LLMCallContext {
+    LLMCallContext {
+        client: "client".to_string(),
+        model: model.to_string(),
+        function_name: "Summarize".to_string(),
+        args: json!({"text": "hello"}),
+        context_id: ContextId::new(1, 1),
+        prompt: json!({"messages": []}),
+        metadata: json!({}),
+    }
+}
+
+#[derive(Default)]
+struct Recorder {
+    decisions: Mutex<Vec<PolicyDecision>>,
+}
+
+#[async_trait]
+impl PolicyObserver for Recorder {
+    async fn on_policy_decision(&self, _context_id: &ContextId, decision: &PolicyDecision) {
+        self.decisions.lock().unwrap().push(decision.clone());
+    }
+}
+
+#[tokio::test]
+async fn first_matching_rule_decides_tool_calls() {
+    let recorder = Arc::new(Recorder::default());
+    let policy = PolicyInterceptor::new(rules(json!([
+        {
+            "id": "tmp-scratch",
+            "effect": "allow",
+            "applies_to": "tool",
+            "when": "call.name == 'fs/write' && call.args.path.startsWith('/tmp/scratch/')"
+        },
+        {
+            "id": "writes-stay-in-tmp",
+            "effect": "deny",
+            "when": "call.name == 'fs/write' && !call.args.path.startsWith('/tmp/')",
+            "message": "fs/write is limited to /tmp"
+        }
+    ])))
+    .expect("rules compile")
+    .with_observer(recorder.clone());
+
+    let denied = policy
+        .intercept_tool_call(&tool_call("fs/write", json!({"path": "/etc/passwd"})))
+        .await
+        .unwrap();
+    assert_eq!(denied, InterceptorDecision::Block("fs/write is limited to /tmp".to_string()));
+
+    let allowed = policy
+        .intercept_tool_call(&tool_call("fs/write", json!({"path": "/tmp/scratch/a"})))
+        .await
+        .unwrap();
+    assert_eq!(allowed, InterceptorDecision::Allow);
+
+    let unmatched = policy
+        .intercept_tool_call(&tool_call("fs/read", json!({"path": "/etc/passwd"})))
+        .await
+        .unwrap();
+    assert_eq!(unmatched, InterceptorDecision::Allow);
+
+    let decisions = recorder.decisions.lock().unwrap().clone();
+    assert_eq!(decisions.len(), 2, "calls no rule matched are not reported");
+    assert_eq!(decisions[0].rule_id, "writes-stay-in-tmp");
+    assert_eq!(decisions[0].effect, PolicyEffect::Deny);
+    assert_eq!(decisions[0].target, PolicyTarget::Tool);
+    assert_eq!(decisions[0].subject, "fs/write");
+    assert_eq!(decisions[1].rule_id, "tmp-scratch");
+    assert!(decisions[1].allowed());
+}
+
+#[tokio::test]
+async fn rules_apply_only_to_their_target_and_fail_closed() {
+    let policy = PolicyInterceptor::new(rules(json!([
+        {"id": "no-gpt3", "effect": "deny", "applies_to": "llm", "when": "call.model == 'gpt-3.5'"},
+        {"id": "broken", "effect": "deny", "applies_to": "tool", "when": "call.args.missing == 1"}
+    ])))
+    .expect("rules compile");
+
+    let decision = LLMInterceptor::intercept_llm_call(&policy, &llm_call("gpt-3.5")).await.unwrap();
+    assert_eq!(decision, InterceptorDecision::Block("denied by policy 'no-gpt3'".to_string()));
+    let decision = LLMInterceptor::intercept_llm_call(&policy, &llm_call("gpt-4o")).await.unwrap();
+    assert_eq!(decision, InterceptorDecision::Allow);
+
+    let decision = policy.intercept_tool_call(&tool_call("search", json!({}))).await.unwrap();
+    let InterceptorDecision::Block(reason) = decision else {
+        panic!("expected an evaluation failure to deny, got {decision:?}");
+    };
+    assert!(reason.starts_with("policy 'broken' failed to evaluate"), "{reason}");
+}
+
+/// Rewrites every tool call's `path` argument.
+struct RewritePath(&'static str);
+
+#[async_trait]
+impl ToolInterceptor for RewritePath {
+    fn stage(&self) -> InterceptorStage {
+        InterceptorStage::Transform
+    }
+
+    async fn intercept_tool_call(&self, context: &ToolCallContext) -> Result<InterceptorDecision> {
+        let mut args = context.args.clone();
+        args["path"] = json!(self.0);
+        Ok(InterceptorDecision::Modify { args: Some(args), prompt: None })
+    }
+
+    async fn on_tool_call_complete(
+        &self,
+        _context: &ToolCallContext,
+        _result: &Result<Value>,
+        _duration_ms: u64,
+    ) {
+    }
+}
+
+#[tokio::test]
+async fn rules_judge_the_arguments_transforms_leave() {
+    let policy = PolicyInterceptor::new(rules(json!([{
+        "id": "writes-stay-in-tmp",
+        "effect": "deny",
+        "when": "call.name == 'fs/write' && !call.args.path.startsWith('/tmp/')",
+        "message": "fs/write is limited to /tmp"
+    }])))
+    .expect("rules compile");
+    let mut registry = InterceptorRegistry::new();
+    registry.register_tool_interceptor(policy);
+    registry.register_tool_interceptor(RewritePath("/etc/passwd"));
+
+    let err = registry
+        .intercept_tool_call(&tool_call("fs/write", json!({"path": "/tmp/a"})))
+        .await
+        .expect_err("the rewritten path is denied");
+    assert!(err.to_string().contains("fs/write is limited to /tmp"), "{err}");
+}
+
+#[test]
+fn rules_that_do_not_compile_are_rejected() {
+    let err = PolicyInterceptor::new(rules(json!([
+        {"id": "typo", "effect": "deny", "when": "call.name == "}
+    ])))
+    .err()
+    .expect("invalid expression");
+    assert!(err.to_string().contains("policy 'typo' does not compile"), "{err}");
+}
@@ -0,0 +1,147 @@
+//! Token-bucket rate limits on LLM and tool calls.
+
+use baml_rt_core::ids::ContextId;
+use baml_rt_interceptor::{
+    retry_after, InterceptorDecision, LLMCallContext, LLMInterceptor, RateLimit,
+    RateLimitInterceptor, Reloadable, ToolCallContext, ToolInterceptor,
+};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+fn llm_call(model: &str, metadata: Value) -> LLMCallContext {
+    LLMCallContext {
+        client: "client".to_string(),
+        model: model.to_string(),
+        function_name: "Summarize".to_string(),
+        args: json!({}),
+        context_id: ContextId::new(1, 1),
+        prompt: json!({"messages": []}),
+        metadata,
+    }
+}
+
+fn tool_call(tool: &str, metadata: Value) -> ToolCallContext {
+    ToolCallContext {
+        tool_name: tool.to_string(),
+        function_name: None,
+        args: json!({}),
+        context_id: ContextId::new(1, 1),
+        metadata,
+    }
+}
+
+fn blocked(decision: InterceptorDecision) -> String {
+    match decision {
+        InterceptorDecision::OverBudget(message) => message,
+        other => panic!("expected the call to be blocked, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn calls_past_the_burst_are_blocked_with_a_retry_after_hint() {
+    let limiter = RateLimitInterceptor::new()
+        .with_model_limit("gpt-4o", RateLimit::per_minute(60).with_burst(2));
+    let call = llm_call("gpt-4o", json!({}));
+
+    for _ in 0..2 {
+        let decision = limiter.intercept_llm_call(&call).await.expect("decision");
+        assert!(matches!(decision, InterceptorDecision::Allow));
+    }
+    let message = blocked(limiter.intercept_llm_call(&call).await.expect("decision"));
+    assert!(message.contains("model 'gpt-4o'"), "{message}");
+    let wait = retry_after(&message).expect("retry-after hint");
+    assert!(wait > Duration::ZERO && wait <= Duration::from_secs(1), "{wait:?}");
+
+    // Other models are not limited.
+    let other = limiter.intercept_llm_call(&llm_call("gpt-4o-mini", json!({}))).await;
+    assert!(matches!(other.expect("decision"), InterceptorDecision::Allow));
+}
+
+#[tokio::test]
+async fn default_tool_limits_apply_to_each_tool_separately() {
+    let limiter = RateLimitInterceptor::new()
+        .with_default_tool_limit(RateLimit::per_minute(1))
+        .with_tool_limit("support/search", RateLimit::per_minute(2));
+
+    let first = limiter.intercept_tool_call(&tool_call("support/calculate", json!({}))).await;
+    assert!(matches!(first.expect("decision"), InterceptorDecision::Allow));
+    let message = blocked(
+        limiter.intercept_tool_call(&tool_call("support/calculate", json!({}))).await.unwrap(),
+    );
+    assert!(message.contains("tool 'support/calculate'"), "{message}");
+
+    for _ in 0..2 {
+        let decision = limiter.intercept_tool_call(&tool_call("support/search", json!({}))).await;
+        assert!(matches!(decision.expect("decision"), InterceptorDecision::Allow));
+    }
+}
+
+#[tokio::test]
+async fn agent_limits_count_llm_and_tool_calls_together() {
+    let limiter = RateLimitInterceptor::new().with_agent_limit(RateLimit::per_minute(2));
+    let tools = limiter.clone();
+    let agent = json!({"agent_id": "agent-1"});
+
+    let llm = limiter.intercept_llm_call(&llm_call("gpt-4o", agent.clone())).await;
+    assert!(matches!(llm.expect("decision"), InterceptorDecision::Allow));
+    let tool = tools.intercept_tool_call(&tool_call("support/search", agent.clone())).await;
+    assert!(matches!(tool.expect("decision"), InterceptorDecision::Allow));
+
+    let message =
+        blocked(limiter.intercept_llm_call(&llm_call("gpt-4o", agent)).await.expect("decision"));
+    assert!(message.contains("agent 'agent-1'"), "{message}");
+
+    // Another agent has its own bucket, and calls without an agent are not limited.
+    let other = limiter.intercept_llm_call(&llm_call("gpt-4o", json!({"agent_id": "agent-2"})));
+    assert!(matches!(other.await.expect("decision"), InterceptorDecision::Allow));
+    let anonymous = limiter.intercept_llm_call(&llm_call("gpt-4o", json!({}))).await;
+    assert!(matches!(anonymous.expect("decision"), InterceptorDecision::Allow));
+}
+
+#[tokio::test]
+async fn a_blocked_call_takes_no_tokens() {
+    let limiter = RateLimitInterceptor::new()
+        .with_agent_limit(RateLimit::per_minute(5))
+        .with_model_limit("gpt-4o", RateLimit::per_minute(1));
+    let agent = json!({"agent_id": "agent-1"});
+
+    let first = limiter.intercept_llm_call(&llm_call("gpt-4o", agent.clone())).await;
+    assert!(matches!(first.expect("decision"), InterceptorDecision::Allow));
+    for _ in 0..3 {
+        blocked(limiter.intercept_llm_call(&llm_call("gpt-4o", agent.clone())).await.unwrap());
+    }
+
+    // The agent bucket still holds four tokens despite the three refusals.
+    for _ in 0..4 {
+        let decision = limiter.intercept_tool_call(&tool_call("support/search", agent.clone()));
+        assert!(matches!(decision.await.expect("decision"), InterceptorDecision::Allow));
+    }
+    blocked(limiter.intercept_tool_call(&tool_call("support/search", agent)).await.unwrap());
+}
+
+#[tokio::test]
+async fn reloaded_limits_keep_draining_the_same_buckets() {
+    let limiter = Reloadable::new(
+        RateLimitInterceptor::new()
+            .with_model_limit("gpt-4o", RateLimit::per_minute(60).with_burst(2)),
+    );
+    let registered = limiter.clone();
+    let call = llm_call("gpt-4o", json!({}));
+    for _ in 0..2 {
+        let decision = registered.intercept_llm_call(&call).await.expect("decision");
+        assert!(matches!(decision, InterceptorDecision::Allow));
+    }
+
+    // A larger burst applies from here on, but does not refill the empty bucket.
+    limiter.replace(
+        RateLimitInterceptor::new()
+            .with_model_limit("gpt-4o", RateLimit::per_minute(60).with_burst(5))
+            .sharing_buckets_with(&limiter.current()),
+    );
+    blocked(registered.intercept_llm_call(&call).await.expect("decision"));
+
+    // Dropping the limit takes effect on the next call.
+    limiter.replace(RateLimitInterceptor::new().sharing_buckets_with(&limiter.current()));
+    let decision = registered.intercept_llm_call(&call).await.expect("decision");
+    assert!(matches!(decision, InterceptorDecision::Allow));
+}
@@ -0,0 +1,49 @@
+use baml_rt_interceptor::usage::{
+    HeuristicTokenizer, StreamUsageTracker, TokenUsage, USAGE_METADATA_KEY, UsageSource,
+};
+use serde_json::json;
+use std::sync::Arc;
+
+#[test]
+fn tracker_counts_the_prompt_and_never_shrinks_the_completion() {
+    let prompt = json!({"messages": [{"role": "user", "content": "summarize this"}]});
+    let mut tracker = StreamUsageTracker::new(Arc::new(HeuristicTokenizer), &prompt);
+    let prompt_tokens = tracker.estimated().prompt_tokens;
+    assert!(prompt_tokens > 0);
+
+    let first = tracker.observe(&json!({"summary": "The quarterly"}));
+    let second = tracker.observe(&json!({"summary": "The quarterly report shows growth"}));
+    assert!(second.completion_tokens > first.completion_tokens);
+    assert_eq!(second.total_tokens, prompt_tokens + second.completion_tokens);
+
+    // A reshaped, shorter partial keeps the high-water mark.
+    let third = tracker.observe(&json!({"summary": "The"}));
+    assert_eq!(third, second);
+}
+
+#[test]
+fn reported_usage_wins_and_keeps_the_estimate() {
+    let mut tracker = StreamUsageTracker::new(Arc::new(HeuristicTokenizer), &json!("hi"));
+    tracker.observe(&json!("hello there"));
+    let estimated = tracker.estimated();
+
+    let report = tracker.reconcile(Some(TokenUsage::new(9, 3)));
+    assert_eq!(report.source, UsageSource::Reported);
+    assert_eq!(report.usage, TokenUsage::new(9, 3));
+    assert_eq!(report.estimated, estimated);
+
+    let fallback = tracker.reconcile(None);
+    assert_eq!(fallback.source, UsageSource::Estimated);
+    assert_eq!(fallback.usage, estimated);
+}
+
+#[test]
+fn reports_round_trip_through_call_metadata() {
+    let tracker = StreamUsageTracker::new(Arc::new(HeuristicTokenizer), &json!("hi"));
+    let report = tracker.reconcile(Some(TokenUsage::new(9, 3)));
+    let metadata = json!({ USAGE_METADATA_KEY: report.to_value() });
+
+    assert_eq!(metadata[USAGE_METADATA_KEY]["source"], json!("reported"));
+    assert_eq!(TokenUsage::from_metadata(&metadata), Some(TokenUsage::new(9, 3)));
+    assert_eq!(TokenUsage::from_metadata(&json!({})), None);
+}
@@ -1,11 +1,13 @@
-//! Observability helpers (metrics, spans, tracing setup).
+//! Observability helpers (metrics, Prometheus export, spans, tracing setup).
 
 pub mod metrics;
+pub mod metrics_exporter;
 pub mod scope;
 pub mod spans;
 pub mod tracing_setup;
 
 pub use metrics::*;
+pub use metrics_exporter::*;
 pub use scope::*;
 pub use spans::*;
 pub use tracing_setup::*;
@@ -3,7 +3,7 @@
 //! Metrics are defined here to keep instrumentation orthogonal to business logic.
 
 use opentelemetry::{global, KeyValue};
-use opentelemetry::metrics::{Counter, Histogram};
+use opentelemetry::metrics::{Counter, Histogram, UpDownCounter};
 use std::sync::OnceLock;
 use std::time::Duration;
 
@@ -16,6 +16,15 @@ static A2A_STREAM_CHUNK_COUNTER: OnceLock<Counter<u64>> = OnceLock::new();
 static A2A_STREAM_CHUNK_HISTOGRAM: OnceLock<Histogram<f64>> = OnceLock::new();
 static TOOL_INVOCATION_COUNTER: OnceLock<Counter<u64>> = OnceLock::new();
 static TOOL_INVOCATION_HISTOGRAM: OnceLock<Histogram<f64>> = OnceLock::new();
+static PROVENANCE_QUERY_HISTOGRAM: OnceLock<Histogram<f64>> = OnceLock::new();
+static PROVENANCE_QUERY_BYTES_HISTOGRAM: OnceLock<Histogram<f64>> = OnceLock::new();
+static PROVENANCE_SLOW_QUERY_COUNTER: OnceLock<Counter<u64>> = OnceLock::new();
+static LLM_STREAM_TOKEN_COUNTER: OnceLock<Counter<u64>> = OnceLock::new();
+static LLM_TOKEN_COUNTER: OnceLock<Counter<u64>> = OnceLock::new();
+static LLM_CALL_HISTOGRAM: OnceLock<Histogram<f64>> = OnceLock::new();
+static PROVENANCE_WRITE_FAILURE_COUNTER: OnceLock<Counter<u64>> = OnceLock::new();
+static TASK_QUEUE_DEPTH: OnceLock<UpDownCounter<i64>> = OnceLock::new();
+static TASK_QUEUE_WAIT_HISTOGRAM: OnceLock<Histogram<f64>> = OnceLock::new();
 
 fn a2a_request_counter() -> &'static Counter<u64> {
     A2A_REQUEST_COUNTER.get_or_init(|| {
@@ -73,6 +82,78 @@ fn tool_invocation_histogram() -> &'static Histogram<f64> {
     })
 }
 
+fn provenance_query_histogram() -> &'static Histogram<f64> {
+    PROVENANCE_QUERY_HISTOGRAM.get_or_init(|| {
+        global::meter(METER_NAME)
+            .f64_histogram("baml_rt.provenance.query_duration_ms")
+            .init()
+    })
+}
+
+fn provenance_query_bytes_histogram() -> &'static Histogram<f64> {
+    PROVENANCE_QUERY_BYTES_HISTOGRAM.get_or_init(|| {
+        global::meter(METER_NAME)
+            .f64_histogram("baml_rt.provenance.query_bytes")
+            .init()
+    })
+}
+
+fn provenance_slow_query_counter() -> &'static Counter<u64> {
+    PROVENANCE_SLOW_QUERY_COUNTER.get_or_init(|| {
+        global::meter(METER_NAME)
+            .u64_counter("baml_rt.provenance.slow_query_total")
+            .init()
+    })
+}
+
+fn llm_stream_token_counter() -> &'static Counter<u64> {
+    LLM_STREAM_TOKEN_COUNTER.get_or_init(|| {
+        global::meter(METER_NAME)
+            .u64_counter("baml_rt.llm.stream.estimated_tokens_total")
+            .init()
+    })
+}
+
+fn llm_token_counter() -> &'static Counter<u64> {
+    LLM_TOKEN_COUNTER.get_or_init(|| {
+        global::meter(METER_NAME)
+            .u64_counter("baml_rt.llm.tokens_total")
+            .init()
+    })
+}
+
+fn llm_call_histogram() -> &'static Histogram<f64> {
+    LLM_CALL_HISTOGRAM.get_or_init(|| {
+        global::meter(METER_NAME)
+            .f64_histogram("baml_rt.llm.call_duration_ms")
+            .init()
+    })
+}
+
+fn provenance_write_failure_counter() -> &'static Counter<u64> {
+    PROVENANCE_WRITE_FAILURE_COUNTER.get_or_init(|| {
+        global::meter(METER_NAME)
+            .u64_counter("baml_rt.provenance.write_failure_total")
+            .init()
+    })
+}
+
+fn task_queue_depth() -> &'static UpDownCounter<i64> {
+    TASK_QUEUE_DEPTH.get_or_init(|| {
+        global::meter(METER_NAME)
+            .i64_up_down_counter("baml_rt.task_queue.depth")
+            .init()
+    })
+}
+
+fn task_queue_wait_histogram() -> &'static Histogram<f64> {
+    TASK_QUEUE_WAIT_HISTOGRAM.get_or_init(|| {
+        global::meter(METER_NAME)
+            .f64_histogram("baml_rt.task_queue.wait_ms")
+            .init()
+    })
+}
+
 /// Record completion of an A2A request.
 pub fn record_a2a_request(
     method: &str,
@@ -116,3 +197,67 @@ pub fn record_tool_invocation(tool_name: &str, result: &str, duration: Duration)
     tool_invocation_counter().add(1, attributes);
     tool_invocation_histogram().record(duration.as_millis() as f64, attributes);
 }
+
+/// Record execution of a generated provenance graph query.
+pub fn record_provenance_query(result: &str, query_bytes: usize, duration: Duration, slow: bool) {
+    let attributes = &[KeyValue::new("result", result.to_string())];
+    provenance_query_histogram().record(duration.as_millis() as f64, attributes);
+    provenance_query_bytes_histogram().record(query_bytes as f64, attributes);
+    if slow {
+        provenance_slow_query_counter().add(1, attributes);
+    }
+}
+
+/// Record completion tokens estimated while an LLM call streams.
+///
+/// Called with the increase since the previous chunk, so the counter tracks
+/// in-flight consumption before the provider reports anything.
+pub fn record_llm_stream_tokens(function: &str, completion_tokens: u64) {
+    let attributes = &[KeyValue::new("function", function.to_string())];
+    llm_stream_token_counter().add(completion_tokens, attributes);
+}
+
+/// Record the final token usage of an LLM call.
+///
+/// `source` is `reported` when the provider counted the tokens and `estimated`
+/// otherwise.
+pub fn record_llm_tokens(function: &str, prompt_tokens: u64, completion_tokens: u64, source: &str) {
+    for (kind, tokens) in [("prompt", prompt_tokens), ("completion", completion_tokens)] {
+        let attributes = &[
+            KeyValue::new("function", function.to_string()),
+            KeyValue::new("kind", kind),
+            KeyValue::new("source", source.to_string()),
+        ];
+        llm_token_counter().add(tokens, attributes);
+    }
+}
+
+/// Record the latency of one LLM call made by a BAML function.
+pub fn record_llm_call(function: &str, client: &str, is_stream: bool, duration: Duration) {
+    let attributes = &[
+        KeyValue::new("function", function.to_string()),
+        KeyValue::new("client", client.to_string()),
+        KeyValue::new("stream", is_stream.to_string()),
+    ];
+    llm_call_histogram().record(duration.as_millis() as f64, attributes);
+}
+
+/// Record a provenance event the store failed to write.
+///
+/// `operation` says what was being recorded, e.g. `tool call start`.
+pub fn record_provenance_write_failure(operation: &str) {
+    let attributes = &[KeyValue::new("operation", operation.to_string())];
+    provenance_write_failure_counter().add(1, attributes);
+}
+
+/// Record a task execution starting to wait for a free slot in an agent's queue.
+pub fn record_task_queued(agent: &str) {
+    task_queue_depth().add(1, &[KeyValue::new("agent", agent.to_string())]);
+}
+
+/// Record a queued task execution leaving the queue after waiting `wait`.
+pub fn record_task_dequeued(agent: &str, wait: Duration) {
+    let attributes = &[KeyValue::new("agent", agent.to_string())];
+    task_queue_depth().add(-1, attributes);
+    task_queue_wait_histogram().record(wait.as_millis() as f64, attributes);
+}
@@ -0,0 +1,118 @@
+//! Prometheus endpoint for the runtime metrics.
+//!
+//! [`PrometheusExporter::install`] makes a meter provider backed by a Prometheus
+//! registry the global OpenTelemetry provider, so everything recorded through
+//! [`crate::metrics`] (LLM call latency, tool invocations, A2A requests,
+//! provenance write failures, ...) can be scraped from it. Instruments bind to
+//! the provider that is global when they are first used, so install it before
+//! anything records a metric.
+//!
+//! [`PrometheusExporter::serve`] answers `GET /metrics` with the text exposition
+//! format. Metric names have their dots replaced, e.g.
+//! `baml_rt.a2a.request_total` is scraped as `baml_rt_a2a_request_total`.
+
+use axum::extract::State;
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use baml_rt_core::{BamlRtError, Result};
+use opentelemetry::global;
+use opentelemetry_sdk::metrics::SdkMeterProvider;
+use prometheus::{Encoder, Registry, TextEncoder};
+use std::net::SocketAddr;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+pub const METRICS_PATH: &str = "/metrics";
+
+/// Global meter provider exporting to a Prometheus registry.
+#[derive(Clone)]
+pub struct PrometheusExporter {
+    registry: Registry,
+    provider: SdkMeterProvider,
+}
+
+impl std::fmt::Debug for PrometheusExporter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PrometheusExporter").finish_non_exhaustive()
+    }
+}
+
+impl PrometheusExporter {
+    /// Create the exporter and make it the global meter provider.
+    pub fn install() -> Result<Self> {
+        let registry = Registry::new();
+        let exporter = opentelemetry_prometheus::exporter()
+            .with_registry(registry.clone())
+            .build()
+            .map_err(|err| {
+                BamlRtError::Configuration(format!("Failed to create Prometheus exporter: {err}"))
+            })?;
+        let provider = SdkMeterProvider::builder().with_reader(exporter).build();
+        global::set_meter_provider(provider.clone());
+        Ok(Self { registry, provider })
+    }
+
+    /// Current metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> Result<String> {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .map_err(std::io::Error::other)?;
+        String::from_utf8(buffer).map_err(|err| BamlRtError::Io(std::io::Error::other(err)))
+    }
+
+    /// Serve [`METRICS_PATH`] on `addr` until the returned server is dropped.
+    /// Use port 0 to pick a free port, then read it from [`MetricsServer::local_addr`].
+    pub async fn serve(&self, addr: SocketAddr) -> Result<MetricsServer> {
+        let listener = TcpListener::bind(addr).await?;
+        let local_addr = listener.local_addr()?;
+        let app = Router::new()
+            .route(METRICS_PATH, get(handle_metrics))
+            .with_state(self.clone());
+        let task = tokio::spawn(async move {
+            if let Err(err) = axum::serve(listener, app).await {
+                warn!(error = %err, "Prometheus metrics endpoint stopped");
+            }
+        });
+        info!(addr = %local_addr, "Prometheus metrics endpoint listening");
+        Ok(MetricsServer { local_addr, task })
+    }
+
+    /// Flush and stop the meter provider.
+    pub fn shutdown(&self) {
+        if let Err(err) = self.provider.shutdown() {
+            warn!(error = %err, "Failed to shut down the metrics provider");
+        }
+    }
+}
+
+/// A running metrics endpoint. Dropping it stops the endpoint.
+pub struct MetricsServer {
+    local_addr: SocketAddr,
+    task: JoinHandle<()>,
+}
+
+impl MetricsServer {
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+impl Drop for MetricsServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn handle_metrics(State(exporter): State<PrometheusExporter>) -> Response {
+    match exporter.render() {
+        Ok(body) => ([(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)], body).into_response(),
+        Err(err) => {
+            warn!(error = %err, "Failed to render metrics");
+            (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
+        }
+    }
+}
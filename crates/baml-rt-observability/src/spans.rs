@@ -10,6 +10,55 @@ use tracing::Span;
 use std::path::Path;
 use baml_rt_core::correlation::current_correlation_id;
 use crate::scope::scope_attributes;
+use baml_rt_core::context;
+use baml_rt_core::trace_context::{TraceContext, TRACEPARENT_METADATA_KEY, TRACESTATE_METADATA_KEY};
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry::trace::TraceContextExt;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use std::collections::HashMap;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Hex id of the OpenTelemetry trace the current span belongs to.
+///
+/// `None` unless spans are exported (see [`crate::tracing_setup`]).
+pub fn current_trace_id() -> Option<String> {
+    let context = Span::current().context();
+    let span = context.span();
+    let span_context = span.span_context();
+    span_context.is_valid().then(|| span_context.trace_id().to_string())
+}
+
+/// Make the caller's span, from an incoming A2A trace context, the parent of `span`.
+///
+/// Call before `span` is entered. Has no effect unless spans are exported.
+pub fn set_remote_parent(span: &Span, trace_context: &TraceContext) {
+    let mut carrier = HashMap::new();
+    carrier.insert(TRACEPARENT_METADATA_KEY.to_string(), trace_context.traceparent().to_string());
+    if let Some(tracestate) = trace_context.tracestate() {
+        carrier.insert(TRACESTATE_METADATA_KEY.to_string(), tracestate.to_string());
+    }
+    span.set_parent(TraceContextPropagator::new().extract(&carrier));
+}
+
+/// Trace context to send with an outbound A2A call.
+///
+/// This is the current span when spans are exported, so the callee's spans
+/// nest under the call. Otherwise the incoming trace context of the request
+/// being handled is passed on unchanged, which still keeps the agents that do
+/// export on one trace.
+pub fn outgoing_trace_context() -> Option<TraceContext> {
+    let context = Span::current().context();
+    if context.span().span_context().is_valid() {
+        let mut carrier = HashMap::new();
+        TraceContextPropagator::new().inject_context(&context, &mut carrier);
+        if let Some(trace_context) = carrier.get(TRACEPARENT_METADATA_KEY).and_then(|traceparent| {
+            TraceContext::parse(traceparent, carrier.get(TRACESTATE_METADATA_KEY).map(String::as_str))
+        }) {
+            return Some(trace_context);
+        }
+    }
+    context::current_trace_context()
+}
 
 // Builder operations
 
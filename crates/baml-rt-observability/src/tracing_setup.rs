@@ -1,4 +1,133 @@
 //! Standard tracing subscriber setup for CLI binaries.
+//!
+//! Spans can also be exported over OTLP (gRPC) to a collector such as Jaeger or
+//! Tempo. Export is off unless an endpoint is configured, either on a
+//! [`TracingConfig`] or through the standard OpenTelemetry variables:
+//!
+//! - `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT` or `OTEL_EXPORTER_OTLP_ENDPOINT`
+//! - `OTEL_SERVICE_NAME` (default [`DEFAULT_SERVICE_NAME`])
+//! - `OTEL_RESOURCE_ATTRIBUTES`, as `key=value,key=value`
+//!
+//! Span fields become span attributes, so the A2A root spans can be found by
+//! their `correlation_id`, and [`crate::spans::current_trace_id`] gives the
+//! trace of the request being handled.
+//!
+//! A2A calls carry W3C trace context in the message metadata (`traceparent`,
+//! `tracestate`): an agent's request span continues the caller's trace, so a
+//! chain of agents exports as one distributed trace.
+
+use baml_rt_core::{BamlRtError, Result};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::{global, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::trace::{Config, TracerProvider};
+use opentelemetry_sdk::{runtime, Resource};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+pub const DEFAULT_SERVICE_NAME: &str = "baml-rt";
+
+/// Where (and whether) spans are exported.
+#[derive(Debug, Clone, Default)]
+pub struct TracingConfig {
+    otlp_endpoint: Option<String>,
+    service_name: Option<String>,
+    resource_attributes: Vec<(String, String)>,
+}
+
+impl TracingConfig {
+    /// Settings from the standard OpenTelemetry environment variables.
+    pub fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.trim().is_empty());
+        let mut config = Self {
+            otlp_endpoint: var("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT")
+                .or_else(|| var("OTEL_EXPORTER_OTLP_ENDPOINT")),
+            service_name: var("OTEL_SERVICE_NAME"),
+            resource_attributes: Vec::new(),
+        };
+        if let Some(attributes) = var("OTEL_RESOURCE_ATTRIBUTES") {
+            for pair in attributes.split(',') {
+                if let Some((key, value)) = pair.split_once('=') {
+                    config = config.with_resource_attribute(key.trim(), value.trim());
+                }
+            }
+        }
+        config
+    }
+
+    /// Export spans to the OTLP gRPC collector at `endpoint`, e.g. `http://localhost:4317`.
+    pub fn with_otlp_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.otlp_endpoint = Some(endpoint.into());
+        self
+    }
+
+    pub fn with_service_name(mut self, service_name: impl Into<String>) -> Self {
+        self.service_name = Some(service_name.into());
+        self
+    }
+
+    pub fn with_resource_attribute(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.resource_attributes.push((key.into(), value.into()));
+        self
+    }
+
+    pub fn otlp_endpoint(&self) -> Option<&str> {
+        self.otlp_endpoint.as_deref()
+    }
+
+    pub fn service_name(&self) -> &str {
+        self.service_name.as_deref().unwrap_or(DEFAULT_SERVICE_NAME)
+    }
+
+    fn resource(&self) -> Resource {
+        let mut attributes = vec![KeyValue::new("service.name", self.service_name().to_string())];
+        attributes.extend(
+            self.resource_attributes
+                .iter()
+                .filter(|(key, _)| key != "service.name")
+                .map(|(key, value)| KeyValue::new(key.clone(), value.clone())),
+        );
+        Resource::new(attributes)
+    }
+
+    fn tracer_provider(&self) -> Result<Option<TracerProvider>> {
+        let Some(endpoint) = &self.otlp_endpoint else {
+            return Ok(None);
+        };
+        let exporter = opentelemetry_otlp::new_exporter().tonic().with_endpoint(endpoint.clone());
+        let provider = opentelemetry_otlp::new_pipeline()
+            .tracing()
+            .with_exporter(exporter)
+            .with_trace_config(Config::default().with_resource(self.resource()))
+            .install_batch(runtime::Tokio)
+            .map_err(|err| {
+                BamlRtError::Configuration(format!("Failed to start OTLP export to {endpoint}: {err}"))
+            })?;
+        global::set_tracer_provider(provider.clone());
+        Ok(Some(provider))
+    }
+}
+
+/// Flushes exported spans when dropped; keep it alive until the binary exits.
+#[must_use = "dropping the guard stops span export"]
+pub struct TracingGuard {
+    provider: Option<TracerProvider>,
+}
+
+impl Drop for TracingGuard {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take() {
+            for result in provider.force_flush() {
+                if let Err(err) = result {
+                    eprintln!("Failed to flush exported spans: {err}");
+                }
+            }
+            if let Err(err) = provider.shutdown() {
+                eprintln!("Failed to shut down span export: {err}");
+            }
+        }
+    }
+}
 
 /// Initialize a tracing subscriber with env-based filtering.
 ///
@@ -6,7 +135,28 @@
 /// - `baml_rt=info`
 /// - `quickjs_runtime::quickjsrealmadapter=warn`
 /// - `quickjs_runtime::typescript=warn`
-pub fn init_tracing() {
+///
+/// Logs go to stderr, leaving stdout to the binaries' stdio protocols and
+/// JSON output. Spans are exported over OTLP when the environment configures
+/// an endpoint; if the exporter cannot start, that is logged and only local
+/// logging is set up.
+pub fn init_tracing() -> TracingGuard {
+    let config = TracingConfig::from_env();
+    match init_tracing_with(&config) {
+        Ok(guard) => guard,
+        Err(err) => {
+            let guard = init_tracing_with(&TracingConfig::default())
+                .unwrap_or(TracingGuard { provider: None });
+            tracing::warn!(error = %err, "Span export disabled");
+            guard
+        }
+    }
+}
+
+/// [`init_tracing`] with explicit export settings.
+///
+/// Must be called from within a Tokio runtime when an OTLP endpoint is set.
+pub fn init_tracing_with(config: &TracingConfig) -> Result<TracingGuard> {
     let filter = tracing_subscriber::EnvFilter::from_default_env()
         .add_directive("baml_rt=info".parse().unwrap_or_default())
         .add_directive(
@@ -16,7 +166,15 @@ pub fn init_tracing() {
         )
         .add_directive("quickjs_runtime::typescript=warn".parse().unwrap_or_default());
 
-    tracing_subscriber::fmt()
-        .with_env_filter(filter)
+    let provider = config.tracer_provider()?;
+    let otel = provider.as_ref().map(|provider| {
+        tracing_opentelemetry::layer().with_tracer(provider.tracer(DEFAULT_SERVICE_NAME))
+    });
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+        .with(otel)
         .init();
+    Ok(TracingGuard { provider })
 }
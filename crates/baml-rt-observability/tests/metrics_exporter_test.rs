@@ -0,0 +1,40 @@
+//! Scraping runtime metrics from the Prometheus endpoint
+
+use baml_rt_observability::{metrics, PrometheusExporter};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+async fn scrape(addr: std::net::SocketAddr) -> String {
+    let mut stream = TcpStream::connect(addr).await.expect("connect");
+    stream
+        .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    response
+}
+
+#[tokio::test]
+async fn recorded_metrics_are_served_on_the_endpoint() {
+    let exporter = PrometheusExporter::install().expect("install exporter");
+    metrics::record_a2a_request("message.send", "success", false, Duration::from_millis(12));
+    metrics::record_tool_invocation("support/calculate", "success", Duration::from_millis(3));
+    metrics::record_llm_call("ExtractResume", "openai", false, Duration::from_millis(250));
+    metrics::record_provenance_write_failure("tool call start");
+
+    let server = exporter.serve("127.0.0.1:0".parse().unwrap()).await.expect("serve");
+    let response = scrape(server.local_addr()).await;
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    for name in [
+        "baml_rt_a2a_request_total",
+        "baml_rt_tool_invocation_total",
+        "baml_rt_llm_call_duration_ms_bucket",
+        "baml_rt_provenance_write_failure_total",
+    ] {
+        assert!(response.contains(name), "{name} missing from:\n{response}");
+    }
+    assert!(response.contains(r#"operation="tool call start""#), "{response}");
+    assert!(exporter.render().unwrap().contains("baml_rt_a2a_request_total"));
+}
@@ -0,0 +1,208 @@
+//! Per-principal access control for provenance reads.
+//!
+//! Every query endpoint reads through a [`ProvenanceReader`]. [`ScopedReader`]
+//! wraps one and limits a caller to the contexts its principal owns: queries
+//! naming another context are refused, and unscoped queries only return nodes
+//! whose `a2a:context_id` is in scope. Nodes without a context, such as agents,
+//! are only visible to principals with unrestricted access.
+//!
+//! The same scope limits the raw events a principal replays
+//! ([`ScopedReader::visible_events`]) and the archives it exports
+//! ([`crate::FalkorDbArchiver::export_for`]).
+
+use crate::error::{ProvenanceError, Result};
+use crate::events::ProvEvent;
+use crate::store::{ProvNodeRecord, ProvenanceQuery, ProvenanceReader};
+use crate::vocabulary::a2a;
+use async_trait::async_trait;
+use baml_rt_core::ids::ContextId;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+/// The contexts a principal may read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccessScope {
+    /// Every context, e.g. an operator.
+    All,
+    /// Only the listed contexts.
+    Contexts(HashSet<ContextId>),
+}
+
+impl AccessScope {
+    pub fn allows(&self, context_id: &str) -> bool {
+        match self {
+            AccessScope::All => true,
+            AccessScope::Contexts(contexts) => {
+                contexts.iter().any(|allowed| allowed.as_str() == context_id)
+            }
+        }
+    }
+
+    /// Whether a node with `attributes` is in scope. Nodes without a context
+    /// are only in scope for [`AccessScope::All`].
+    pub(crate) fn allows_attributes<'a>(
+        &self,
+        mut attributes: impl FnMut(&str) -> Option<&'a Value>,
+    ) -> bool {
+        match self {
+            AccessScope::All => true,
+            AccessScope::Contexts(_) => attributes(a2a::CONTEXT_ID)
+                .and_then(Value::as_str)
+                .is_some_and(|context_id| self.allows(context_id)),
+        }
+    }
+
+    fn allows_record(&self, record: &ProvNodeRecord) -> bool {
+        self.allows_attributes(|key| record.attributes.get(key))
+    }
+}
+
+/// Decides which contexts a principal may read.
+pub trait AccessResolver: Send + Sync {
+    fn scope_for(&self, principal: &str) -> AccessScope;
+}
+
+/// Context ownership kept in memory, granted as contexts are created.
+///
+/// Unknown principals get an empty scope.
+#[derive(Debug, Default)]
+pub struct ContextOwnership {
+    owned: RwLock<HashMap<String, HashSet<ContextId>>>,
+    unrestricted: RwLock<HashSet<String>>,
+}
+
+impl ContextOwnership {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn grant(&self, principal: impl Into<String>, context_id: ContextId) {
+        let mut owned = self.owned.write().expect("context ownership lock");
+        owned.entry(principal.into()).or_default().insert(context_id);
+    }
+
+    pub fn revoke(&self, principal: &str, context_id: &ContextId) {
+        let mut owned = self.owned.write().expect("context ownership lock");
+        if let Some(contexts) = owned.get_mut(principal) {
+            contexts.remove(context_id);
+        }
+    }
+
+    /// Let `principal` read every context.
+    pub fn grant_all(&self, principal: impl Into<String>) {
+        let mut unrestricted = self.unrestricted.write().expect("context ownership lock");
+        unrestricted.insert(principal.into());
+    }
+
+    /// Grant each event's tenant the context the event was recorded in.
+    /// Events without a tenant grant nothing.
+    pub fn grant_recorded_tenants(&self, events: &[ProvEvent]) {
+        for event in events {
+            if let Some(tenant) = event.tenant() {
+                self.grant(tenant, event.context_id().clone());
+            }
+        }
+    }
+}
+
+impl AccessResolver for ContextOwnership {
+    fn scope_for(&self, principal: &str) -> AccessScope {
+        if self.unrestricted.read().expect("context ownership lock").contains(principal) {
+            return AccessScope::All;
+        }
+        let owned = self.owned.read().expect("context ownership lock");
+        AccessScope::Contexts(owned.get(principal).cloned().unwrap_or_default())
+    }
+}
+
+/// Serves reads from `inner` to principals, each limited to its own scope.
+#[derive(Clone)]
+pub struct AccessControlledReader {
+    inner: Arc<dyn ProvenanceReader>,
+    resolver: Arc<dyn AccessResolver>,
+}
+
+impl AccessControlledReader {
+    pub fn new(inner: Arc<dyn ProvenanceReader>, resolver: Arc<dyn AccessResolver>) -> Self {
+        Self { inner, resolver }
+    }
+
+    /// A reader returning what `principal` may see.
+    ///
+    /// The scope is resolved on every query, so grants and revocations apply to
+    /// readers already handed out.
+    pub fn for_principal(&self, principal: impl Into<String>) -> ScopedReader {
+        ScopedReader {
+            inner: self.inner.clone(),
+            resolver: self.resolver.clone(),
+            principal: principal.into(),
+        }
+    }
+}
+
+/// A [`ProvenanceReader`] bound to one principal.
+#[derive(Clone)]
+pub struct ScopedReader {
+    inner: Arc<dyn ProvenanceReader>,
+    resolver: Arc<dyn AccessResolver>,
+    principal: String,
+}
+
+impl ScopedReader {
+    pub fn principal(&self) -> &str {
+        &self.principal
+    }
+
+    /// The contexts the principal may read now.
+    pub fn scope(&self) -> AccessScope {
+        self.resolver.scope_for(&self.principal)
+    }
+
+    /// The events of `events` recorded in a context the principal may read.
+    pub fn visible_events(&self, events: Vec<ProvEvent>) -> Vec<ProvEvent> {
+        let scope = self.scope();
+        events.into_iter().filter(|event| scope.allows(event.context_id().as_str())).collect()
+    }
+
+    /// `query` split into queries the backend can answer for `scope`: the query
+    /// itself when it names a context in scope, else one per context in scope.
+    /// A query naming a context out of scope is refused.
+    pub(crate) fn scoped_queries(
+        &self,
+        query: &ProvenanceQuery,
+        scope: &AccessScope,
+    ) -> Result<Vec<ProvenanceQuery>> {
+        let AccessScope::Contexts(contexts) = scope else {
+            return Ok(vec![query.clone()]);
+        };
+        match &query.context_id {
+            Some(context_id) if !contexts.contains(context_id) => {
+                Err(ProvenanceError::AccessDenied {
+                    principal: self.principal.clone(),
+                    resource: format!("context {}", context_id.as_str()),
+                })
+            }
+            Some(_) => Ok(vec![query.clone()]),
+            None => Ok(contexts
+                .iter()
+                .map(|context_id| query.clone().for_context(context_id.clone()))
+                .collect()),
+        }
+    }
+}
+
+#[async_trait]
+impl ProvenanceReader for ScopedReader {
+    async fn query_nodes(&self, query: &ProvenanceQuery) -> Result<Vec<ProvNodeRecord>> {
+        let scope = self.scope();
+        let scoped = match self.scoped_queries(query, &scope)?.as_slice() {
+            [] => return Ok(Vec::new()),
+            // Let the backend filter when only one context is in scope.
+            [scoped] => scoped.clone(),
+            _ => query.clone(),
+        };
+        let records = self.inner.query_nodes(&scoped).await?;
+        Ok(records.into_iter().filter(|record| scope.allows_record(record)).collect())
+    }
+}
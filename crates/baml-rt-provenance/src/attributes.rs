@@ -0,0 +1,265 @@
+//! Typed construction of PROV node attribute maps.
+//!
+//! Normalized nodes carry their data as `HashMap<String, Value>` keyed by
+//! [`crate::vocabulary::a2a`] terms. `AttrBuilder` exposes one setter per known
+//! term so callers cannot misspell a key or store a value under the wrong JSON type.
+
+use crate::events::{LlmUsage, ProvEvent};
+use crate::vocabulary::a2a;
+use baml_rt_interceptor::{
+    ERROR_METADATA_KEY, OUTPUT_REPAIR_METADATA_KEY, OUTPUT_VALIDATION_METADATA_KEY, RETRY_METADATA_KEY,
+};
+use baml_rt_core::ids::{AgentId, ContextId, EventId, MessageId, TaskId};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// LLM call `metadata` key holding the progress of a streamed call.
+pub(crate) const STREAM_METADATA_KEY: &str = "stream";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AttrBuilder {
+    attributes: HashMap<String, Value>,
+}
+
+impl AttrBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Context, event and (when task-scoped) task ids of `event`.
+    pub fn for_event(event: &ProvEvent) -> Self {
+        Self::new()
+            .context_id(event.context_id())
+            .event_id(event.id())
+            .maybe_task_id(event.task_id())
+            .maybe_tenant(event.tenant())
+    }
+
+    /// Context, task and timestamp of `event`, as carried by derived relations.
+    pub fn for_derived(event: &ProvEvent) -> Self {
+        Self::new()
+            .context_id(event.context_id())
+            .maybe_task_id(event.task_id())
+            .maybe_tenant(event.tenant())
+            .timestamp_ms(event.timestamp_ms())
+    }
+
+    pub fn context_id(self, context_id: &ContextId) -> Self {
+        self.string(a2a::CONTEXT_ID, context_id.as_str())
+    }
+
+    pub fn task_id(self, task_id: &TaskId) -> Self {
+        self.string(a2a::TASK_ID, task_id.as_str())
+    }
+
+    pub fn maybe_task_id(self, task_id: Option<&TaskId>) -> Self {
+        match task_id {
+            Some(task_id) => self.task_id(task_id),
+            None => self,
+        }
+    }
+
+    pub fn maybe_tenant(self, tenant: Option<&str>) -> Self {
+        match tenant {
+            Some(tenant) => self.string(a2a::TENANT, tenant),
+            None => self,
+        }
+    }
+
+    pub fn event_id(self, event_id: &EventId) -> Self {
+        self.string(a2a::EVENT_ID, event_id.as_str())
+    }
+
+    pub fn message_id(self, message_id: &MessageId) -> Self {
+        self.string(a2a::MESSAGE_ID, message_id.as_str())
+    }
+
+    pub fn role(self, role: &str) -> Self {
+        self.string(a2a::ROLE, role)
+    }
+
+    /// One of [`crate::vocabulary::message_directions`].
+    pub fn direction(self, direction: &str) -> Self {
+        self.string(a2a::DIRECTION, direction)
+    }
+
+    pub fn agent_id(self, agent_id: &AgentId) -> Self {
+        self.string(a2a::AGENT_ID, agent_id.as_str())
+    }
+
+    pub fn agent_type(self, agent_type: &str) -> Self {
+        self.string(a2a::AGENT_TYPE, agent_type)
+    }
+
+    pub fn agent_version(self, agent_version: &str) -> Self {
+        self.string(a2a::AGENT_VERSION, agent_version)
+    }
+
+    pub fn client(self, client: &str) -> Self {
+        self.string(a2a::CLIENT, client)
+    }
+
+    pub fn model(self, model: &str) -> Self {
+        self.string(a2a::MODEL, model)
+    }
+
+    pub fn function_name(self, function_name: &str) -> Self {
+        self.string(a2a::FUNCTION_NAME, function_name)
+    }
+
+    pub fn maybe_function_name(self, function_name: Option<&str>) -> Self {
+        match function_name {
+            Some(function_name) => self.function_name(function_name),
+            None => self,
+        }
+    }
+
+    pub fn tool_name(self, tool_name: &str) -> Self {
+        self.string(a2a::TOOL_NAME, tool_name)
+    }
+
+    pub fn prompt(self, prompt: &Value) -> Self {
+        self.attr(a2a::PROMPT, prompt.clone())
+    }
+
+    pub fn args(self, args: &Value) -> Self {
+        self.attr(a2a::ARGS, args.clone())
+    }
+
+    pub fn metadata(self, metadata: &Value) -> Self {
+        self.attr(a2a::METADATA, metadata.clone())
+    }
+
+    /// Token counts; `LlmUsage::Unknown` leaves the usage keys unset.
+    pub fn usage(self, usage: &LlmUsage) -> Self {
+        match usage {
+            LlmUsage::Known {
+                prompt_tokens,
+                completion_tokens,
+                total_tokens,
+            } => self
+                .attr(a2a::USAGE_PROMPT_TOKENS, *prompt_tokens)
+                .attr(a2a::USAGE_COMPLETION_TOKENS, *completion_tokens)
+                .attr(a2a::USAGE_TOTAL_TOKENS, *total_tokens),
+            LlmUsage::Unknown => self,
+        }
+    }
+
+    /// Chunk count and time to first token that [`crate::ProvenanceInterceptor`]
+    /// records under `metadata.stream` for streamed calls.
+    pub fn stream_progress(self, metadata: &Value) -> Self {
+        let Some(stream) = metadata.get(STREAM_METADATA_KEY) else {
+            return self;
+        };
+        let builder = match stream.get("chunk_count").and_then(Value::as_u64) {
+            Some(chunk_count) => self.attr(a2a::STREAM_CHUNK_COUNT, chunk_count),
+            None => self,
+        };
+        match stream.get("time_to_first_token_ms").and_then(Value::as_u64) {
+            Some(ttft_ms) => builder.attr(a2a::TIME_TO_FIRST_TOKEN_MS, ttft_ms),
+            None => builder,
+        }
+    }
+
+    /// Whether the parsed output matched its function's declared type, and the
+    /// mismatches when it did not, from `metadata.output_validation`.
+    pub fn output_validation(self, metadata: &Value) -> Self {
+        let Some(validation) = metadata.get(OUTPUT_VALIDATION_METADATA_KEY) else {
+            return self;
+        };
+        let builder = match validation.get("valid").and_then(Value::as_bool) {
+            Some(valid) => self.attr(a2a::OUTPUT_VALID, valid),
+            None => self,
+        };
+        match validation.get("mismatches") {
+            Some(mismatches @ Value::Array(items)) if !items.is_empty() => {
+                builder.attr(a2a::OUTPUT_MISMATCHES, mismatches.clone())
+            }
+            _ => builder,
+        }
+    }
+
+    /// Attempt number and attempt limit of an LLM call that is part of an output
+    /// repair, recorded under `metadata.output_repair`.
+    pub fn output_repair(self, metadata: &Value) -> Self {
+        let Some(repair) = metadata.get(OUTPUT_REPAIR_METADATA_KEY) else {
+            return self;
+        };
+        let mut builder = self;
+        for (field, key) in
+            [("attempt", a2a::RETRY_ATTEMPT), ("max_attempts", a2a::RETRY_MAX_ATTEMPTS)]
+        {
+            if let Some(value) = repair.get(field).and_then(Value::as_u64) {
+                builder = builder.attr(key, value);
+            }
+        }
+        builder
+    }
+
+    /// Attempt number, attempt limit and, for a failed attempt, the failure kind and
+    /// backoff that tool calls carry under `metadata.retry` once they were retried.
+    pub fn retry_progress(self, metadata: &Value) -> Self {
+        let Some(retry) = metadata.get(RETRY_METADATA_KEY) else {
+            return self;
+        };
+        let mut builder = self;
+        for (field, key) in [
+            ("attempt", a2a::RETRY_ATTEMPT),
+            ("max_attempts", a2a::RETRY_MAX_ATTEMPTS),
+            ("backoff_ms", a2a::RETRY_BACKOFF_MS),
+        ] {
+            if let Some(value) = retry.get(field).and_then(Value::as_u64) {
+                builder = builder.attr(key, value);
+            }
+        }
+        match retry.get("failure_kind").and_then(Value::as_str) {
+            Some(kind) => builder.attr(a2a::RETRY_FAILURE_KIND, kind),
+            None => builder,
+        }
+    }
+
+    /// Error code and context frames of a failed call, from `metadata.error`.
+    pub fn error_details(self, metadata: &Value) -> Self {
+        let Some(error) = metadata.get(ERROR_METADATA_KEY) else {
+            return self;
+        };
+        let builder = match error.get("code").and_then(Value::as_str) {
+            Some(code) => self.attr(a2a::ERROR_CODE, code),
+            None => self,
+        };
+        match error.get("context") {
+            Some(context @ Value::Array(_)) => builder.attr(a2a::ERROR_CONTEXT, context.clone()),
+            _ => builder,
+        }
+    }
+
+    pub fn duration_ms(self, duration_ms: u64) -> Self {
+        self.attr(a2a::DURATION_MS, duration_ms)
+    }
+
+    pub fn success(self, success: bool) -> Self {
+        self.attr(a2a::SUCCESS, success)
+    }
+
+    pub fn timestamp_ms(self, timestamp_ms: u64) -> Self {
+        self.attr(a2a::TIMESTAMP_MS, timestamp_ms)
+    }
+
+    pub fn archive_path(self, archive_path: &str) -> Self {
+        self.string(a2a::ARCHIVE_PATH, archive_path)
+    }
+
+    /// Escape hatch for keys without a typed setter.
+    pub fn attr(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.attributes.insert(key.to_string(), value.into());
+        self
+    }
+
+    pub fn build(self) -> HashMap<String, Value> {
+        self.attributes
+    }
+
+    fn string(self, key: &str, value: &str) -> Self {
+        self.attr(key, Value::String(value.to_string()))
+    }
+}
@@ -1,8 +1,9 @@
 use crate::{
     document::ProvDocument,
     types::{
-        Activity, Agent, Entity, ProvActivityId, ProvAgentId, ProvEntityId, ProvNodeRef,
-        QualifiedGeneration, Used, WasAssociatedWith, WasDerivedFrom, WasGeneratedBy,
+        ActedOnBehalfOf, Activity, Agent, Entity, ProvActivityId, ProvAgentId, ProvEntityId,
+        ProvNodeRef, QualifiedGeneration, Used, WasAssociatedWith, WasDerivedFrom, WasGeneratedBy,
+        WasInformedBy,
     },
 };
 use std::collections::HashMap;
@@ -175,6 +176,17 @@ impl ProvDocumentBuilder {
         WasDerivedFromBuilder::new(self, generated_entity.into(), used_entity.into())
     }
 
+    pub fn acted_on_behalf_of(self, delegate: impl Into<ProvAgentId>, responsible: impl Into<ProvAgentId>) -> ActedOnBehalfOfBuilder {
+        ActedOnBehalfOfBuilder::new(self, delegate.into(), responsible.into())
+    }
+
+    pub fn was_informed_by(mut self, informed: impl Into<ProvActivityId>, informant: impl Into<ProvActivityId>) -> Self {
+        let (informed, informant) = (informed.into(), informant.into());
+        let id = self.doc.relation_id("inf", informed.as_str(), informant.as_str());
+        self.doc.insert_was_informed_by(id, WasInformedBy { informed, informant });
+        self
+    }
+
     pub fn build(self) -> ProvDocument {
         self.doc
     }
@@ -198,7 +210,7 @@ impl UsedBuilder {
     }
 
     pub fn build(mut self) -> ProvDocumentBuilder {
-        let id = self.doc_builder.doc.blank_node_id("u");
+        let id = self.doc_builder.doc.relation_id("u", self.activity.as_str(), self.entity.as_str());
         let used = Used { activity: self.activity, entity: self.entity, role: self.role };
         self.doc_builder.doc.insert_used(id, used);
         self.doc_builder
@@ -237,7 +249,7 @@ impl WasAssociatedWithBuilder {
     }
 
     pub fn build(mut self) -> ProvDocumentBuilder {
-        let id = self.doc_builder.doc.blank_node_id("assoc");
+        let id = self.doc_builder.doc.relation_id("assoc", self.activity.as_str(), self.agent.as_str());
         let was_associated_with =
             WasAssociatedWith { activity: self.activity, agent: self.agent, role: self.role };
         self.doc_builder.doc.insert_was_associated_with(id, was_associated_with);
@@ -256,7 +268,7 @@ impl WasGeneratedByBuilder {
     }
 
     pub fn build(mut self) -> ProvDocumentBuilder {
-        let id = self.doc_builder.doc.blank_node_id("g");
+        let id = self.doc_builder.doc.relation_id("g", self.entity.id(), self.activity.as_str());
         let was_generated_by =
             WasGeneratedBy { entity: self.entity, activity: self.activity, time_ms: self.time_ms };
         self.doc_builder.doc.insert_was_generated_by(id, was_generated_by);
@@ -275,7 +287,7 @@ impl QualifiedGenerationBuilder {
     }
 
     pub fn build(mut self) -> ProvDocumentBuilder {
-        let id = self.doc_builder.doc.blank_node_id("gen");
+        let id = self.doc_builder.doc.relation_id("gen", self.entity.id(), self.activity.as_str());
         let qualified_generation = QualifiedGeneration {
             entity: self.entity,
             activity: self.activity,
@@ -320,7 +332,11 @@ impl WasDerivedFromBuilder {
     }
 
     pub fn build(mut self) -> ProvDocumentBuilder {
-        let id = self.doc_builder.doc.blank_node_id("d");
+        let id = self.doc_builder.doc.relation_id(
+            "d",
+            self.generated_entity.as_str(),
+            self.used_entity.as_str(),
+        );
         let was_derived_from = WasDerivedFrom {
             generated_entity: self.generated_entity,
             used_entity: self.used_entity,
@@ -332,6 +348,35 @@ impl WasDerivedFromBuilder {
     }
 }
 
+pub struct ActedOnBehalfOfBuilder {
+    doc_builder: ProvDocumentBuilder,
+    delegate: ProvAgentId,
+    responsible: ProvAgentId,
+    activity: Option<ProvActivityId>,
+}
+
+impl ActedOnBehalfOfBuilder {
+    fn new(doc_builder: ProvDocumentBuilder, delegate: ProvAgentId, responsible: ProvAgentId) -> Self {
+        Self { doc_builder, delegate, responsible, activity: None }
+    }
+
+    pub fn activity(mut self, activity: impl Into<ProvActivityId>) -> Self {
+        self.activity = Some(activity.into());
+        self
+    }
+
+    pub fn build(mut self) -> ProvDocumentBuilder {
+        let id = self.doc_builder.doc.relation_id("del", self.delegate.as_str(), self.responsible.as_str());
+        let acted_on_behalf_of = ActedOnBehalfOf {
+            delegate: self.delegate,
+            responsible: self.responsible,
+            activity: self.activity,
+        };
+        self.doc_builder.doc.insert_acted_on_behalf_of(id, acted_on_behalf_of);
+        self.doc_builder
+    }
+}
+
 impl Default for ProvDocumentBuilder {
     fn default() -> Self {
         Self::new()
@@ -0,0 +1,162 @@
+//! Fan-out of provenance events to several writers.
+//!
+//! A [`CompositeProvenanceWriter`] hands every event to each of its sinks in
+//! the order they were added, e.g. an in-memory store that tests inspect next
+//! to FalkorDB for persistence. What a failing sink does to the write is set by
+//! its [`FailureMode`].
+
+use crate::error::{ProvenanceError, Result};
+use crate::events::ProvEvent;
+use crate::store::ProvenanceWriter;
+use async_trait::async_trait;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// How a [`CompositeProvenanceWriter`] treats a sink that fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FailureMode {
+    /// Stop at the first failing sink and return its error. Sinks after it do
+    /// not see the event.
+    #[default]
+    FailFast,
+    /// Write to every sink, logging failures; fail only when every sink failed.
+    BestEffort,
+}
+
+impl FailureMode {
+    pub const ALL: [FailureMode; 2] = [FailureMode::FailFast, FailureMode::BestEffort];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FailureMode::FailFast => "fail-fast",
+            FailureMode::BestEffort => "best-effort",
+        }
+    }
+}
+
+impl fmt::Display for FailureMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for FailureMode {
+    type Err = ProvenanceError;
+
+    fn from_str(value: &str) -> Result<Self> {
+        Self::ALL.into_iter().find(|mode| mode.as_str() == value).ok_or_else(|| {
+            ProvenanceError::InvalidSetting {
+                key: "failure_mode".to_string(),
+                reason: format!("unknown failure mode '{value}', expected fail-fast or best-effort"),
+            }
+        })
+    }
+}
+
+/// Writes each event to every sink.
+#[derive(Clone, Default)]
+pub struct CompositeProvenanceWriter {
+    sinks: Vec<(String, Arc<dyn ProvenanceWriter>)>,
+    failure_mode: FailureMode,
+}
+
+impl fmt::Debug for CompositeProvenanceWriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompositeProvenanceWriter")
+            .field("sinks", &self.sink_names().collect::<Vec<_>>())
+            .field("failure_mode", &self.failure_mode)
+            .finish()
+    }
+}
+
+impl CompositeProvenanceWriter {
+    pub fn new(failure_mode: FailureMode) -> Self {
+        Self { sinks: Vec::new(), failure_mode }
+    }
+
+    /// Add a sink; `name` identifies it in logs and errors.
+    pub fn with_writer(mut self, name: impl Into<String>, writer: Arc<dyn ProvenanceWriter>) -> Self {
+        self.sinks.push((name.into(), writer));
+        self
+    }
+
+    pub fn failure_mode(&self) -> FailureMode {
+        self.failure_mode
+    }
+
+    pub fn sink_names(&self) -> impl Iterator<Item = &str> {
+        self.sinks.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.sinks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sinks.is_empty()
+    }
+
+    /// Run `operation` against every sink under the failure mode.
+    async fn fan_out<'a, F, Fut>(&'a self, operation: &str, mut call: F) -> Result<()>
+    where
+        F: FnMut(&'a dyn ProvenanceWriter) -> Fut,
+        Fut: std::future::Future<Output = Result<()>>,
+    {
+        let mut failures = Vec::new();
+        for (name, writer) in &self.sinks {
+            let Err(err) = call(writer.as_ref()).await else {
+                continue;
+            };
+            match self.failure_mode {
+                FailureMode::FailFast => {
+                    tracing::warn!(sink = %name, operation, error = ?err, "Provenance sink failed");
+                    return Err(err);
+                }
+                FailureMode::BestEffort => {
+                    tracing::warn!(
+                        sink = %name,
+                        operation,
+                        error = ?err,
+                        "Provenance sink failed; continuing with the others"
+                    );
+                    failures.push(format!("{name}: {err}"));
+                }
+            }
+        }
+        if !self.sinks.is_empty() && failures.len() == self.sinks.len() {
+            return Err(ProvenanceError::Storage(
+                format!("every provenance sink failed to {operation}: {}", failures.join("; ")).into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ProvenanceWriter for CompositeProvenanceWriter {
+    async fn add_event(&self, event: ProvEvent) -> Result<()> {
+        self.fan_out("add events", |writer| writer.add_event(event.clone())).await
+    }
+
+    async fn add_events(&self, events: Vec<ProvEvent>) -> Result<()> {
+        self.fan_out("add events", |writer| writer.add_events(events.clone())).await
+    }
+
+    /// Under [`FailureMode::BestEffort`] one reachable sink is enough.
+    async fn health_check(&self) -> Result<()> {
+        self.fan_out("pass health checks", |writer| writer.health_check()).await
+    }
+
+    async fn flush(&self) -> Result<()> {
+        self.fan_out("flush", |writer| writer.flush()).await
+    }
+
+    async fn pending_events(&self) -> usize {
+        let mut pending = 0;
+        for (_, writer) in &self.sinks {
+            pending += writer.pending_events().await;
+        }
+        pending
+    }
+}
@@ -0,0 +1,867 @@
+//! Cypher generation for normalized provenance documents.
+//!
+//! [`CypherBuilder`] turns PROV nodes and relations into idempotent `MERGE`
+//! clauses. Node and relationship labels come from a [`LabelStrategy`], so
+//! backends can choose between the semantic labels used by the FalkorDB writer
+//! ([`SemanticLabels`]) and the plain PROV base labels ([`BaseLabels`]).
+//!
+//! Edge clauses look up the labels of their endpoints, so nodes should be added
+//! before the relations that reference them; unknown endpoints fall back to the
+//! PROV base label. [`CypherBuilder::normalized`] adds a whole document in the
+//! right order.
+
+use crate::normalizer::{A2aDerivedRelation, NormalizedProv};
+use crate::store::ProvNodeKind;
+use crate::types::{
+    ActedOnBehalfOf, Activity, Agent, Entity, ProvActivityId, ProvAgentId, ProvEntityId,
+    ProvNodeRef, QualifiedGeneration, Used, WasAssociatedWith, WasDerivedFrom, WasGeneratedBy,
+    WasInformedBy,
+};
+use crate::vocabulary::{
+    a2a, a2a_relation_types, a2a_relations, a2a_roles, message_directions, node_labels, prov,
+    prov_relations, prov_roles, semantic_labels,
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Separator between clauses of a multi-clause query.
+///
+/// `WITH 1 AS _` starts a new variable scope, so every clause can reuse short
+/// variable names like `n`, `a`, `b` and `r`.
+pub const CLAUSE_SEPARATOR: &str = "\nWITH 1 AS _\n";
+
+/// Chooses graph labels for PROV nodes and relationships.
+pub trait LabelStrategy: Send + Sync {
+    /// Label for a node of `kind` with the given `prov:type`.
+    fn node_label(&self, kind: ProvNodeKind, prov_type: Option<&str>) -> String;
+
+    /// Relationship type for a PROV relation (`USED`, `WAS_GENERATED_BY`, ...)
+    /// between nodes with the given labels.
+    fn relation_label(
+        &self,
+        base: &str,
+        from_label: &str,
+        to_label: &str,
+        props: &HashMap<String, Value>,
+    ) -> String;
+
+    /// Relationship type for an A2A-derived relation.
+    fn derived_relation_label(
+        &self,
+        relation: &A2aDerivedRelation,
+        from_label: &str,
+        to_label: &str,
+        props: &HashMap<String, Value>,
+    ) -> String;
+}
+
+/// Labels nodes by the local part of their `prov:type` and maps relations to
+/// past-tense semantic names (`WAS_SPAWNED_BY`, `WAS_INVOKED_BY`, ...) using
+/// [`SEMANTIC_LABEL_RULES`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SemanticLabels;
+
+impl LabelStrategy for SemanticLabels {
+    fn node_label(&self, kind: ProvNodeKind, prov_type: Option<&str>) -> String {
+        label_from_prov_type(prov_type, kind.base_label())
+    }
+
+    fn relation_label(
+        &self,
+        base: &str,
+        from_label: &str,
+        to_label: &str,
+        props: &HashMap<String, Value>,
+    ) -> String {
+        semantic_label(base, from_label, to_label, props)
+    }
+
+    fn derived_relation_label(
+        &self,
+        relation: &A2aDerivedRelation,
+        from_label: &str,
+        to_label: &str,
+        props: &HashMap<String, Value>,
+    ) -> String {
+        semantic_label(relation.relation.as_str(), from_label, to_label, props)
+    }
+}
+
+/// Labels every node `ProvEntity`, `ProvActivity` or `ProvAgent` and keeps
+/// relation names as-is. The `prov:type` property still carries the A2A type.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BaseLabels;
+
+impl LabelStrategy for BaseLabels {
+    fn node_label(&self, kind: ProvNodeKind, _prov_type: Option<&str>) -> String {
+        kind.base_label().to_string()
+    }
+
+    fn relation_label(
+        &self,
+        base: &str,
+        _from_label: &str,
+        _to_label: &str,
+        _props: &HashMap<String, Value>,
+    ) -> String {
+        sanitize_label(base, base)
+    }
+
+    fn derived_relation_label(
+        &self,
+        relation: &A2aDerivedRelation,
+        _from_label: &str,
+        _to_label: &str,
+        _props: &HashMap<String, Value>,
+    ) -> String {
+        let base = relation.relation.as_str();
+        sanitize_label(base, base)
+    }
+}
+
+/// Accumulates `MERGE` clauses for PROV nodes and relations.
+pub struct CypherBuilder {
+    labels: Arc<dyn LabelStrategy>,
+    entity_labels: HashMap<String, String>,
+    activity_labels: HashMap<String, String>,
+    agent_labels: HashMap<String, String>,
+    clauses: Vec<String>,
+    /// For each clause, the node or relationship it upserts.
+    keys: Vec<String>,
+}
+
+impl Default for CypherBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CypherBuilder {
+    pub fn new() -> Self {
+        Self::with_label_strategy(Arc::new(SemanticLabels))
+    }
+
+    pub fn with_label_strategy(labels: Arc<dyn LabelStrategy>) -> Self {
+        Self {
+            labels,
+            entity_labels: HashMap::new(),
+            activity_labels: HashMap::new(),
+            agent_labels: HashMap::new(),
+            clauses: Vec::new(),
+            keys: Vec::new(),
+        }
+    }
+
+    /// Add every node and relation of `normalized`, each kind sorted by id so the
+    /// output is deterministic. Relation ids are derived from their endpoints
+    /// (see [`ProvDocument::relation_id`](crate::document::ProvDocument::relation_id)),
+    /// and A2A relations, which have none, are sorted by type and endpoints, so
+    /// normalizing the same event again builds the same query byte for byte.
+    pub fn normalized(&mut self, normalized: &NormalizedProv) -> &mut Self {
+        let document = &normalized.document;
+
+        let mut entities: Vec<_> = document.entities().collect();
+        entities.sort_by(|(a, _), (b, _)| a.cmp(b));
+        for (id, entity) in entities {
+            self.entity(id, entity);
+        }
+        let mut activities: Vec<_> = document.activities().collect();
+        activities.sort_by(|(a, _), (b, _)| a.cmp(b));
+        for (id, activity) in activities {
+            self.activity(id, activity);
+        }
+        for (id, prov_type) in &normalized.activity_types {
+            self.activity_type_hint(id, prov_type);
+        }
+        let mut agents: Vec<_> = document.agents().collect();
+        agents.sort_by(|(a, _), (b, _)| a.cmp(b));
+        for (id, agent) in agents {
+            self.agent(id, agent);
+        }
+        for (id, label) in &normalized.agent_labels {
+            self.agent_label_hint(id, label);
+        }
+
+        let mut used: Vec<_> = document.used().collect();
+        used.sort_by(|(a, _), (b, _)| a.cmp(b));
+        for (_, used) in used {
+            self.used(used);
+        }
+        let mut generated: Vec<_> = document.was_generated_by().collect();
+        generated.sort_by(|(a, _), (b, _)| a.cmp(b));
+        for (_, generated) in generated {
+            self.was_generated_by(generated);
+        }
+        let mut generations: Vec<_> = document.qualified_generation().collect();
+        generations.sort_by(|(a, _), (b, _)| a.cmp(b));
+        for (_, generation) in generations {
+            self.qualified_generation(generation);
+        }
+        let mut associations: Vec<_> = document.was_associated_with().collect();
+        associations.sort_by(|(a, _), (b, _)| a.cmp(b));
+        for (_, assoc) in associations {
+            self.was_associated_with(assoc);
+        }
+        let mut derivations: Vec<_> = document.was_derived_from().collect();
+        derivations.sort_by(|(a, _), (b, _)| a.cmp(b));
+        for (_, derived) in derivations {
+            self.was_derived_from(derived);
+        }
+        let mut delegations: Vec<_> = document.acted_on_behalf_of().collect();
+        delegations.sort_by(|(a, _), (b, _)| a.cmp(b));
+        for (_, delegation) in delegations {
+            self.acted_on_behalf_of(delegation);
+        }
+        let mut informed: Vec<_> = document.was_informed_by().collect();
+        informed.sort_by(|(a, _), (b, _)| a.cmp(b));
+        for (_, informed) in informed {
+            self.was_informed_by(informed);
+        }
+
+        let mut relations: Vec<_> = normalized.derived_relations.iter().collect();
+        relations.sort_by(|a, b| {
+            (a.relation.as_str(), a.from.id(), a.to.id())
+                .cmp(&(b.relation.as_str(), b.from.id(), b.to.id()))
+        });
+        for relation in relations {
+            self.derived_relation(relation);
+        }
+        self
+    }
+
+    pub fn entity(&mut self, id: &ProvEntityId, entity: &Entity) -> &mut Self {
+        let label = self.labels.node_label(ProvNodeKind::Entity, entity.prov_type.as_deref());
+        self.push_node(&label, id.as_str(), &entity_props(id, entity));
+        self.entity_labels.insert(id.as_str().to_string(), label);
+        self
+    }
+
+    pub fn activity(&mut self, id: &ProvActivityId, activity: &Activity) -> &mut Self {
+        let label = self.labels.node_label(ProvNodeKind::Activity, activity.prov_type.as_deref());
+        self.push_node(&label, id.as_str(), &activity_props(id, activity));
+        self.activity_labels.insert(id.as_str().to_string(), label);
+        self
+    }
+
+    /// Label, from its `prov:type`, for an activity referenced by relations
+    /// but not added as a node. Activities added through
+    /// [`CypherBuilder::activity`] keep their own label.
+    pub fn activity_type_hint(&mut self, id: &str, prov_type: &str) -> &mut Self {
+        let label = self.labels.node_label(ProvNodeKind::Activity, Some(prov_type));
+        self.activity_labels.entry(id.to_string()).or_insert(label);
+        self
+    }
+
+    pub fn agent(&mut self, id: &ProvAgentId, agent: &Agent) -> &mut Self {
+        let label = self.labels.node_label(ProvNodeKind::Agent, agent.prov_type.as_deref());
+        self.push_node(&label, id.as_str(), &agent_props(id, agent));
+        self.agent_labels.insert(id.as_str().to_string(), label);
+        self
+    }
+
+    /// Label for an agent referenced by relations but not added as a node.
+    /// Agents added through [`CypherBuilder::agent`] keep their own label.
+    pub fn agent_label_hint(&mut self, id: &str, label: &str) -> &mut Self {
+        self.agent_labels
+            .entry(id.to_string())
+            .or_insert_with(|| label.to_string());
+        self
+    }
+
+    pub fn used(&mut self, used: &Used) -> &mut Self {
+        let props = used_props(used);
+        let activity_label = self.activity_label(used.activity.as_str()).to_string();
+        let entity_label = self.entity_label(used.entity.as_str()).to_string();
+        self.edge(
+            prov_relations::USED,
+            (activity_label.as_str(), used.activity.as_str()),
+            (entity_label.as_str(), used.entity.as_str()),
+            &props,
+        )
+    }
+
+    pub fn was_generated_by(&mut self, generated: &WasGeneratedBy) -> &mut Self {
+        let props = was_generated_by_props(generated);
+        let entity_label = self.ref_label(&generated.entity).to_string();
+        let activity_label = self.activity_label(generated.activity.as_str()).to_string();
+        self.edge(
+            prov_relations::WAS_GENERATED_BY,
+            (entity_label.as_str(), generated.entity.id()),
+            (activity_label.as_str(), generated.activity.as_str()),
+            &props,
+        )
+    }
+
+    pub fn qualified_generation(&mut self, generation: &QualifiedGeneration) -> &mut Self {
+        let props = qualified_generation_props(generation);
+        let entity_label = self.ref_label(&generation.entity).to_string();
+        let activity_label = self.activity_label(generation.activity.as_str()).to_string();
+        self.edge(
+            prov_relations::QUALIFIED_GENERATION,
+            (entity_label.as_str(), generation.entity.id()),
+            (activity_label.as_str(), generation.activity.as_str()),
+            &props,
+        )
+    }
+
+    pub fn was_associated_with(&mut self, assoc: &WasAssociatedWith) -> &mut Self {
+        let props = was_associated_with_props(assoc);
+        let activity_label = self.activity_label(assoc.activity.as_str()).to_string();
+        let agent_label = self.agent_label(assoc.agent.as_str()).to_string();
+        self.edge(
+            prov_relations::WAS_ASSOCIATED_WITH,
+            (activity_label.as_str(), assoc.activity.as_str()),
+            (agent_label.as_str(), assoc.agent.as_str()),
+            &props,
+        )
+    }
+
+    pub fn was_derived_from(&mut self, derived: &WasDerivedFrom) -> &mut Self {
+        let props = was_derived_from_props(derived);
+        let generated_label = self.entity_label(derived.generated_entity.as_str()).to_string();
+        let used_label = self.entity_label(derived.used_entity.as_str()).to_string();
+        self.edge(
+            prov_relations::WAS_DERIVED_FROM,
+            (generated_label.as_str(), derived.generated_entity.as_str()),
+            (used_label.as_str(), derived.used_entity.as_str()),
+            &props,
+        )
+    }
+
+    pub fn acted_on_behalf_of(&mut self, delegation: &ActedOnBehalfOf) -> &mut Self {
+        let props = acted_on_behalf_of_props(delegation);
+        let delegate_label = self.agent_label(delegation.delegate.as_str()).to_string();
+        let responsible_label = self.agent_label(delegation.responsible.as_str()).to_string();
+        self.edge(
+            prov_relations::ACTED_ON_BEHALF_OF,
+            (delegate_label.as_str(), delegation.delegate.as_str()),
+            (responsible_label.as_str(), delegation.responsible.as_str()),
+            &props,
+        )
+    }
+
+    pub fn was_informed_by(&mut self, informed: &WasInformedBy) -> &mut Self {
+        let props = was_informed_by_props();
+        let informed_label = self.activity_label(informed.informed.as_str()).to_string();
+        let informant_label = self.activity_label(informed.informant.as_str()).to_string();
+        self.edge(
+            prov_relations::WAS_INFORMED_BY,
+            (informed_label.as_str(), informed.informed.as_str()),
+            (informant_label.as_str(), informed.informant.as_str()),
+            &props,
+        )
+    }
+
+    /// Add an A2A-derived relation edge between two PROV nodes.
+    pub fn derived_relation(&mut self, relation: &A2aDerivedRelation) -> &mut Self {
+        let props = relation_props(relation);
+        let from_label = self.ref_label(&relation.from).to_string();
+        let to_label = self.ref_label(&relation.to).to_string();
+        let rel_type = self
+            .labels
+            .derived_relation_label(relation, &from_label, &to_label, &props);
+        self.push_edge((&from_label, relation.from.id()), &rel_type, (&to_label, relation.to.id()), &props);
+        self
+    }
+
+    pub fn clauses(&self) -> &[String] {
+        &self.clauses
+    }
+
+    /// Each clause with a key naming the node or relationship it upserts, e.g.
+    /// `(ProvEntity task-1)`. Clauses with the same key write the same element.
+    pub fn keyed_clauses(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.keys.iter().map(String::as_str).zip(self.clauses.iter().map(String::as_str))
+    }
+
+    pub fn into_clauses(self) -> Vec<String> {
+        self.clauses
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clauses.is_empty()
+    }
+
+    /// All clauses joined into one query; empty when nothing was added.
+    pub fn build(&self) -> String {
+        self.clauses.join(CLAUSE_SEPARATOR)
+    }
+
+    fn edge(
+        &mut self,
+        base: &str,
+        (from_label, from_id): (&str, &str),
+        (to_label, to_id): (&str, &str),
+        props: &HashMap<String, Value>,
+    ) -> &mut Self {
+        let rel_type = self.labels.relation_label(base, from_label, to_label, props);
+        self.push_edge((from_label, from_id), &rel_type, (to_label, to_id), props);
+        self
+    }
+
+    fn push_node(&mut self, label: &str, id: &str, props: &HashMap<String, Value>) {
+        self.keys.push(format!("({label} {id})"));
+        self.clauses.push(merge_node(label, id, props));
+    }
+
+    fn push_edge(
+        &mut self,
+        (from_label, from_id): (&str, &str),
+        rel_type: &str,
+        (to_label, to_id): (&str, &str),
+        props: &HashMap<String, Value>,
+    ) {
+        self.keys
+            .push(format!("({from_label} {from_id})-[{rel_type}]->({to_label} {to_id})"));
+        self.clauses
+            .push(merge_edge(from_label, from_id, rel_type, to_label, to_id, props));
+    }
+
+    fn entity_label(&self, id: &str) -> &str {
+        self.entity_labels
+            .get(id)
+            .map(String::as_str)
+            .unwrap_or(ProvNodeKind::Entity.base_label())
+    }
+
+    fn activity_label(&self, id: &str) -> &str {
+        self.activity_labels
+            .get(id)
+            .map(String::as_str)
+            .unwrap_or(ProvNodeKind::Activity.base_label())
+    }
+
+    fn agent_label(&self, id: &str) -> &str {
+        self.agent_labels
+            .get(id)
+            .map(String::as_str)
+            .unwrap_or(ProvNodeKind::Agent.base_label())
+    }
+
+    fn ref_label(&self, reference: &ProvNodeRef) -> &str {
+        match reference {
+            ProvNodeRef::Entity(id) => self.entity_label(id.as_str()),
+            ProvNodeRef::Activity(id) => self.activity_label(id.as_str()),
+            ProvNodeRef::Agent(id) => self.agent_label(id.as_str()),
+        }
+    }
+}
+
+/// Convert a PROV Entity into a Cypher property map (including `name`).
+fn entity_props(id: &ProvEntityId, entity: &Entity) -> HashMap<String, Value> {
+    let mut props = entity.attributes.clone();
+    insert_type(&mut props, entity.prov_type.as_ref());
+    insert_base_type(&mut props, "ProvEntity");
+    insert_id_props(&mut props, id.as_str());
+    props
+}
+
+fn activity_props(id: &ProvActivityId, activity: &Activity) -> HashMap<String, Value> {
+    let mut props = activity.attributes.clone();
+    if let Some(start_time_ms) = activity.start_time_ms {
+        props.insert(prov::START_TIME.to_string(), Value::Number(start_time_ms.into()));
+    }
+    if let Some(end_time_ms) = activity.end_time_ms {
+        props.insert(prov::END_TIME.to_string(), Value::Number(end_time_ms.into()));
+    }
+    insert_type(&mut props, activity.prov_type.as_ref());
+    insert_base_type(&mut props, "ProvActivity");
+    insert_id_props(&mut props, id.as_str());
+    props
+}
+
+fn agent_props(id: &ProvAgentId, agent: &Agent) -> HashMap<String, Value> {
+    let mut props = agent.attributes.clone();
+    insert_type(&mut props, agent.prov_type.as_ref());
+    insert_base_type(&mut props, "ProvAgent");
+    insert_id_props(&mut props, id.as_str());
+    props
+}
+
+/// Relation properties for `USED`.
+pub(crate) fn used_props(used: &Used) -> HashMap<String, Value> {
+    let mut props = HashMap::new();
+    props.insert(prov::BASE_TYPE.to_string(), Value::String(prov_relations::USED.to_string()));
+    if let Some(role) = &used.role {
+        props.insert(prov::ROLE.to_string(), Value::String(role.clone()));
+    }
+    props
+}
+
+pub(crate) fn was_generated_by_props(generated: &WasGeneratedBy) -> HashMap<String, Value> {
+    let mut props = HashMap::new();
+    props.insert(
+        prov::BASE_TYPE.to_string(),
+        Value::String(prov_relations::WAS_GENERATED_BY.to_string()),
+    );
+    if let Some(time_ms) = generated.time_ms {
+        props.insert(prov::TIME.to_string(), Value::Number(time_ms.into()));
+    }
+    props
+}
+
+pub(crate) fn qualified_generation_props(generation: &QualifiedGeneration) -> HashMap<String, Value> {
+    let mut props = HashMap::new();
+    props.insert(
+        prov::BASE_TYPE.to_string(),
+        Value::String(prov_relations::QUALIFIED_GENERATION.to_string()),
+    );
+    if let Some(time_ms) = generation.time_ms {
+        props.insert(prov::TIME.to_string(), Value::Number(time_ms.into()));
+    }
+    props
+}
+
+pub(crate) fn was_associated_with_props(assoc: &WasAssociatedWith) -> HashMap<String, Value> {
+    let mut props = HashMap::new();
+    props.insert(
+        prov::BASE_TYPE.to_string(),
+        Value::String(prov_relations::WAS_ASSOCIATED_WITH.to_string()),
+    );
+    if let Some(role) = &assoc.role {
+        props.insert(prov::ROLE.to_string(), Value::String(role.clone()));
+    }
+    props
+}
+
+pub(crate) fn was_derived_from_props(derived: &WasDerivedFrom) -> HashMap<String, Value> {
+    let mut props = HashMap::new();
+    props.insert(
+        prov::BASE_TYPE.to_string(),
+        Value::String(prov_relations::WAS_DERIVED_FROM.to_string()),
+    );
+    if let Some(activity) = &derived.activity {
+        props.insert(prov::ACTIVITY.to_string(), Value::String(activity.to_string()));
+    }
+    if let Some(prov_type) = &derived.prov_type {
+        props.insert(prov::TYPE.to_string(), Value::String(prov_type.clone()));
+    }
+    props
+}
+
+pub(crate) fn acted_on_behalf_of_props(delegation: &ActedOnBehalfOf) -> HashMap<String, Value> {
+    let mut props = HashMap::new();
+    props.insert(
+        prov::BASE_TYPE.to_string(),
+        Value::String(prov_relations::ACTED_ON_BEHALF_OF.to_string()),
+    );
+    if let Some(activity) = &delegation.activity {
+        props.insert(prov::ACTIVITY.to_string(), Value::String(activity.to_string()));
+    }
+    props
+}
+
+pub(crate) fn was_informed_by_props() -> HashMap<String, Value> {
+    HashMap::from([(
+        prov::BASE_TYPE.to_string(),
+        Value::String(prov_relations::WAS_INFORMED_BY.to_string()),
+    )])
+}
+
+fn insert_base_type(props: &mut HashMap<String, Value>, base_type: &str) {
+    props.insert(prov::BASE_TYPE.to_string(), Value::String(base_type.to_string()));
+}
+
+pub(crate) fn relation_props(relation: &A2aDerivedRelation) -> HashMap<String, Value> {
+    let mut props = relation.attributes.clone();
+    // FalkorDB supports relationship properties; we persist event context on derived edges.
+    props.insert(
+        a2a::RELATION.to_string(),
+        Value::String(relation.relation.as_str().to_string()),
+    );
+    props.insert(a2a::FROM.to_string(), Value::String(relation.from.id().to_string()));
+    props.insert(a2a::TO.to_string(), Value::String(relation.to.id().to_string()));
+    props
+}
+
+fn insert_type(props: &mut HashMap<String, Value>, prov_type: Option<&String>) {
+    if let Some(prov_type) = prov_type {
+        props.insert(prov::TYPE.to_string(), Value::String(prov_type.clone()));
+    }
+}
+
+/// Local part of `prov_type` (`a2a:LlmCall` -> `LlmCall`) as a valid label.
+pub fn label_from_prov_type(prov_type: Option<&str>, fallback: &str) -> String {
+    let raw = prov_type
+        .and_then(|value| value.split(':').next_back())
+        .unwrap_or(fallback);
+    sanitize_label(raw, fallback)
+}
+
+/// Replace characters that are not valid in an unquoted label with `_`.
+pub fn sanitize_label(value: &str, fallback: &str) -> String {
+    let mut out = String::new();
+    for ch in value.chars() {
+        if ch.is_ascii_alphanumeric() || ch == '_' {
+            out.push(ch);
+        } else {
+            out.push('_');
+        }
+    }
+    if out.is_empty() {
+        return fallback.to_string();
+    }
+    let first = out.chars().next().unwrap_or('_');
+    if first.is_ascii_alphabetic() || first == '_' {
+        out
+    } else {
+        format!("L_{}", out)
+    }
+}
+
+/// Maps a PROV or A2A-derived relation to a semantic relationship type when
+/// its conditions hold. Unset conditions match anything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SemanticLabelRule {
+    /// Relation name the rule applies to (`USED`, `A2A_TASK_CALL`, ...).
+    pub relation: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_label: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_label: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub property: Option<PropertyMatch>,
+    /// Relationship type used when the rule matches.
+    pub label: &'static str,
+}
+
+/// A relation property that must hold a given string value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PropertyMatch {
+    pub key: &'static str,
+    pub value: &'static str,
+}
+
+impl SemanticLabelRule {
+    const fn new(relation: &'static str, label: &'static str) -> Self {
+        Self { relation, from_label: None, to_label: None, property: None, label }
+    }
+
+    const fn from(self, label: &'static str) -> Self {
+        Self { from_label: Some(label), ..self }
+    }
+
+    const fn to(self, label: &'static str) -> Self {
+        Self { to_label: Some(label), ..self }
+    }
+
+    const fn when(self, key: &'static str, value: &'static str) -> Self {
+        Self { property: Some(PropertyMatch { key, value }), ..self }
+    }
+
+    pub fn matches(
+        &self,
+        relation: &str,
+        from_label: &str,
+        to_label: &str,
+        props: &HashMap<String, Value>,
+    ) -> bool {
+        self.relation == relation
+            && self.from_label.is_none_or(|label| label == from_label)
+            && self.to_label.is_none_or(|label| label == to_label)
+            && self.property.is_none_or(|property| {
+                props.get(property.key).and_then(Value::as_str) == Some(property.value)
+            })
+    }
+}
+
+/// Rules used by [`SemanticLabels`], in priority order: the first rule that
+/// matches a relation names it, and relations no rule matches keep their name.
+pub const SEMANTIC_LABEL_RULES: &[SemanticLabelRule] = &[
+    SemanticLabelRule::new(prov_relations::USED, semantic_labels::WAS_SPAWNED_BY)
+        .when(prov::ROLE, a2a_roles::INPUT_MESSAGE)
+        .from(node_labels::TASK_EXECUTION),
+    SemanticLabelRule::new(prov_relations::USED, semantic_labels::WAS_RECEIVED_BY)
+        .when(prov::ROLE, a2a_roles::INPUT_MESSAGE)
+        .from(node_labels::MESSAGE_PROCESSING),
+    SemanticLabelRule::new(prov_relations::USED, semantic_labels::WAS_CONSUMED_BY)
+        .when(prov::ROLE, a2a_roles::INPUT_MESSAGE)
+        .from(node_labels::LLM_CALL),
+    SemanticLabelRule::new(prov_relations::USED, semantic_labels::WAS_CONSUMED_BY)
+        .when(prov::ROLE, a2a_roles::INPUT_MESSAGE)
+        .from(node_labels::TOOL_CALL),
+    SemanticLabelRule::new(prov_relations::USED, semantic_labels::WAS_USED_BY)
+        .when(prov::ROLE, a2a_roles::INPUT_MESSAGE),
+    SemanticLabelRule::new(prov_relations::USED, semantic_labels::WAS_UPDATED_BY)
+        .when(prov::ROLE, a2a_roles::TASK_STATE),
+    SemanticLabelRule::new(prov_relations::USED, semantic_labels::WAS_USED_BY)
+        .when(prov::ROLE, a2a_roles::PROMPT),
+    SemanticLabelRule::new(prov_relations::USED, semantic_labels::WAS_USED_BY)
+        .when(prov::ROLE, a2a_roles::ARGS),
+    SemanticLabelRule::new(prov_relations::USED, semantic_labels::WAS_BOOTSTRAPPED_BY)
+        .when(prov::ROLE, a2a_roles::ARCHIVE),
+    SemanticLabelRule::new(prov_relations::WAS_GENERATED_BY, semantic_labels::WAS_EMITTED_BY)
+        .from(node_labels::MESSAGE)
+        .to(node_labels::MESSAGE_PROCESSING),
+    SemanticLabelRule::new(prov_relations::WAS_GENERATED_BY, semantic_labels::WAS_GENERATED_BY)
+        .from(node_labels::ARTIFACT)
+        .to(node_labels::TASK_EXECUTION),
+    SemanticLabelRule::new(prov_relations::WAS_GENERATED_BY, semantic_labels::WAS_CREATED_BY)
+        .from(node_labels::TASK)
+        .to(node_labels::TASK_EXECUTION),
+    SemanticLabelRule::new(prov_relations::WAS_GENERATED_BY, semantic_labels::WAS_SPAWNED_BY)
+        .from(node_labels::AGENT_RUNTIME_INSTANCE)
+        .to(node_labels::AGENT_BOOT),
+    SemanticLabelRule::new(prov_relations::WAS_ASSOCIATED_WITH, semantic_labels::WAS_EXECUTED_BY)
+        .when(prov::ROLE, prov_roles::EXECUTING_AGENT),
+    SemanticLabelRule::new(prov_relations::WAS_ASSOCIATED_WITH, semantic_labels::WAS_INVOKED_BY)
+        .when(prov::ROLE, prov_roles::INVOKING_AGENT),
+    SemanticLabelRule::new(prov_relations::WAS_ASSOCIATED_WITH, semantic_labels::WAS_CALLED_BY)
+        .when(prov::ROLE, prov_roles::CALLING_AGENT),
+    SemanticLabelRule::new(prov_relations::WAS_DERIVED_FROM, semantic_labels::WAS_TRANSITIONED_FROM)
+        .when(prov::TYPE, a2a_relation_types::STATUS_TRANSITION),
+    SemanticLabelRule::new(prov_relations::WAS_DERIVED_FROM, semantic_labels::WAS_ATTEMPTED_ON)
+        .when(prov::TYPE, a2a_relation_types::ILLEGAL_TRANSITION),
+    SemanticLabelRule::new(prov_relations::WAS_DERIVED_FROM, semantic_labels::WAS_EXHAUSTED_BY)
+        .when(prov::TYPE, a2a_relation_types::BUDGET_EXHAUSTION),
+    SemanticLabelRule::new(prov_relations::WAS_DERIVED_FROM, semantic_labels::WAS_LOGGED_DURING)
+        .when(prov::TYPE, a2a_relation_types::DIAGNOSTIC),
+    SemanticLabelRule::new(prov_relations::WAS_DERIVED_FROM, semantic_labels::WAS_REVISED_FROM)
+        .when(prov::TYPE, a2a_relation_types::CONFIG_REVISION),
+    SemanticLabelRule::new(prov_relations::WAS_DERIVED_FROM, semantic_labels::WAS_REVISED_FROM)
+        .when(prov::TYPE, a2a_relation_types::SCHEMA_REVISION),
+    SemanticLabelRule::new(prov_relations::WAS_DERIVED_FROM, semantic_labels::WAS_DECIDED_DURING)
+        .when(prov::TYPE, a2a_relation_types::POLICY_DECISION),
+    SemanticLabelRule::new(prov_relations::WAS_DERIVED_FROM, semantic_labels::WAS_DECIDED_DURING)
+        .when(prov::TYPE, a2a_relation_types::APPROVAL_DECISION),
+    SemanticLabelRule::new(a2a_relations::TASK_CALL, semantic_labels::WAS_INVOKED_BY)
+        .to(node_labels::LLM_CALL),
+    SemanticLabelRule::new(a2a_relations::TASK_CALL, semantic_labels::WAS_EXECUTED_BY)
+        .to(node_labels::TOOL_CALL),
+    SemanticLabelRule::new(a2a_relations::MESSAGE_CALL, semantic_labels::WAS_INVOKED_BY)
+        .to(node_labels::LLM_CALL),
+    SemanticLabelRule::new(a2a_relations::MESSAGE_CALL, semantic_labels::WAS_EXECUTED_BY)
+        .to(node_labels::TOOL_CALL),
+    SemanticLabelRule::new(a2a_relations::TASK_MESSAGE, semantic_labels::WAS_SPAWNED_BY)
+        .when(a2a::DIRECTION, message_directions::RECEIVED),
+    SemanticLabelRule::new(a2a_relations::TASK_MESSAGE, semantic_labels::WAS_EMITTED_BY)
+        .when(a2a::DIRECTION, message_directions::SENT),
+    SemanticLabelRule::new(a2a_relations::TASK_MESSAGE, semantic_labels::WAS_RELATED_TO),
+    SemanticLabelRule::new(a2a_relations::TASK_ARTIFACT, semantic_labels::WAS_GENERATED_BY),
+    SemanticLabelRule::new(
+        a2a_relations::TASK_STATUS_TRANSITION,
+        semantic_labels::WAS_TRANSITIONED_TO,
+    ),
+    SemanticLabelRule::new(a2a_relations::CONTEXT_DERIVED_FROM, semantic_labels::WAS_BRANCHED_FROM),
+    SemanticLabelRule::new(a2a_relations::CONTEXT_CONTAINED_IN, semantic_labels::WAS_CONTAINED_IN),
+    SemanticLabelRule::new(a2a_relations::LLM_RETRY_OF, semantic_labels::RETRY_OF),
+];
+
+fn semantic_label(relation: &str, from_label: &str, to_label: &str, props: &HashMap<String, Value>) -> String {
+    let label = SEMANTIC_LABEL_RULES
+        .iter()
+        .find(|rule| rule.matches(relation, from_label, to_label, props))
+        .map_or(relation, |rule| rule.label);
+    sanitize_label(label, relation)
+}
+
+/// Insert stable identifiers used by upsert logic.
+fn insert_id_props(props: &mut HashMap<String, Value>, id: &str) {
+    props.insert("name".to_string(), Value::String(id.to_string()));
+}
+
+/// Create an idempotent node upsert.
+///
+/// `MERGE` will either match an existing node (same `name`) or create it.
+/// `SET n += {props}` then adds/updates properties without clearing others.
+fn merge_node(label: &str, id: &str, props: &HashMap<String, Value>) -> String {
+    let id_value = Value::String(id.to_string());
+    format!(
+        "MERGE (n:{label} {{name: {name}}}) SET n += {props}",
+        name = cypher_value(&id_value),
+        props = cypher_map(props)
+    )
+}
+
+/// Create an idempotent edge upsert between two nodes.
+///
+/// We `MERGE` both nodes (by `name`) and then `MERGE` the relationship.
+/// This avoids `MATCH` after an updating clause and keeps the clause atomic.
+fn merge_edge(
+    from_label: &str,
+    from_id: &str,
+    rel_type: &str,
+    to_label: &str,
+    to_id: &str,
+    props: &HashMap<String, Value>,
+) -> String {
+    let from_value = Value::String(from_id.to_string());
+    let to_value = Value::String(to_id.to_string());
+    let base = format!(
+        "MERGE (a:{from_label} {{name: {from_id}}}) MERGE (b:{to_label} {{name: {to_id}}}) MERGE (a)-[r:{rel_type}]->(b)",
+        from_id = cypher_value(&from_value),
+        to_id = cypher_value(&to_value)
+    );
+    if props.is_empty() {
+        base
+    } else {
+        format!("{base} SET r += {}", cypher_map(props))
+    }
+}
+
+/// Render a JSON map as a Cypher map literal with stable key ordering.
+fn cypher_map(map: &HashMap<String, Value>) -> String {
+    if map.is_empty() {
+        return "{}".to_string();
+    }
+    let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let mut parts = Vec::new();
+    for (key, value) in entries {
+        parts.push(format!("{}: {}", cypher_key(key), cypher_value(value)));
+    }
+    format!("{{{}}}", parts.join(", "))
+}
+
+pub(crate) fn cypher_key(key: &str) -> String {
+    if is_safe_identifier(key) {
+        key.to_string()
+    } else {
+        format!("`{}`", key.replace('`', "``"))
+    }
+}
+
+/// Determine if a key can be used without backticks in Cypher.
+fn is_safe_identifier(value: &str) -> bool {
+    let mut chars = value.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+pub(crate) fn cypher_value(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(value) => value.to_string(),
+        Value::Number(value) => value.to_string(),
+        Value::String(value) => serde_json::to_string(value).unwrap_or_else(|_| "\"\"".to_string()),
+        Value::Array(values) => {
+            if values.iter().all(is_primitive_value) {
+                let mut parts = Vec::new();
+                for value in values {
+                    parts.push(cypher_value(value));
+                }
+                format!("[{}]", parts.join(", "))
+            } else {
+                let json = serde_json::to_string(values).unwrap_or_else(|_| "[]".to_string());
+                json_string_literal(&json)
+            }
+        }
+        Value::Object(map) => {
+            let json = serde_json::to_string(map).unwrap_or_else(|_| "{}".to_string());
+            json_string_literal(&json)
+        }
+    }
+}
+
+fn is_primitive_value(value: &Value) -> bool {
+    matches!(value, Value::Null | Value::Bool(_) | Value::Number(_) | Value::String(_))
+}
+
+fn json_string_literal(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "\"\"".to_string())
+}
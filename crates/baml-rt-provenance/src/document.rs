@@ -1,8 +1,14 @@
+use crate::error::{ProvenanceError, Result};
 use crate::types::{
-    Activity, Agent, Entity, ProvActivityId, ProvAgentId, ProvEntityId, QualifiedGeneration, Used,
-    WasAssociatedWith, WasDerivedFrom, WasGeneratedBy,
+    ActedOnBehalfOf, Activity, Agent, Entity, ProvActivityId, ProvAgentId, ProvEntityId,
+    ProvNodeRef, QualifiedGeneration, Used, WasAssociatedWith, WasDerivedFrom, WasGeneratedBy,
+    WasInformedBy,
 };
-use std::collections::HashMap;
+use crate::vocabulary::{namespaces, prov};
+use serde_json::{json, Map, Value};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
 
 #[derive(Debug, Clone, Default)]
 pub struct ProvDocument {
@@ -14,7 +20,10 @@ pub struct ProvDocument {
     qualified_generation: HashMap<String, QualifiedGeneration>,
     was_associated_with: HashMap<String, WasAssociatedWith>,
     was_derived_from: HashMap<String, WasDerivedFrom>,
-    blank_node_counter: u64,
+    acted_on_behalf_of: HashMap<String, ActedOnBehalfOf>,
+    was_informed_by: HashMap<String, WasInformedBy>,
+    /// Mixed into every relation id, see [`ProvDocument::relation_id`].
+    relation_scope: String,
 }
 
 impl ProvDocument {
@@ -22,6 +31,13 @@ impl ProvDocument {
         Self::default()
     }
 
+    /// A document whose relation ids are derived from `scope`, typically the id
+    /// of the event it normalizes, so relations of different events never share
+    /// an id.
+    pub fn scoped(scope: impl Into<String>) -> Self {
+        Self { relation_scope: scope.into(), ..Self::default() }
+    }
+
     pub fn insert_entity(&mut self, id: ProvEntityId, entity: Entity) {
         self.entity.insert(id, entity);
     }
@@ -54,6 +70,14 @@ impl ProvDocument {
         self.was_derived_from.insert(id, rel);
     }
 
+    pub fn insert_acted_on_behalf_of(&mut self, id: String, rel: ActedOnBehalfOf) {
+        self.acted_on_behalf_of.insert(id, rel);
+    }
+
+    pub fn insert_was_informed_by(&mut self, id: String, rel: WasInformedBy) {
+        self.was_informed_by.insert(id, rel);
+    }
+
     pub fn entities(&self) -> impl Iterator<Item = (&ProvEntityId, &Entity)> {
         self.entity.iter()
     }
@@ -86,6 +110,14 @@ impl ProvDocument {
         self.was_derived_from.iter()
     }
 
+    pub fn acted_on_behalf_of(&self) -> impl Iterator<Item = (&String, &ActedOnBehalfOf)> {
+        self.acted_on_behalf_of.iter()
+    }
+
+    pub fn was_informed_by(&self) -> impl Iterator<Item = (&String, &WasInformedBy)> {
+        self.was_informed_by.iter()
+    }
+
     pub fn entity(&self, id: &ProvEntityId) -> Option<&Entity> {
         self.entity.get(id)
     }
@@ -98,8 +130,867 @@ impl ProvDocument {
         self.agent.get(id)
     }
 
-    pub fn blank_node_id(&mut self, prefix: &str) -> String {
-        self.blank_node_counter += 1;
-        format!("{}{}", prefix, self.blank_node_counter)
+    /// Attribute maps of every entity, activity and agent, for in-place rewrites
+    /// such as redaction.
+    pub fn node_attributes_mut(&mut self) -> impl Iterator<Item = &mut HashMap<String, Value>> {
+        let entities = self.entity.values_mut().map(|entity| &mut entity.attributes);
+        let activities = self.activity.values_mut().map(|activity| &mut activity.attributes);
+        let agents = self.agent.values_mut().map(|agent| &mut agent.attributes);
+        entities.chain(activities).chain(agents)
+    }
+
+    /// Blank id for a relation from `from` to `to`.
+    ///
+    /// A hash of the document's scope, `prefix` and both endpoints, so building
+    /// the same document twice yields the same ids whatever order relations are
+    /// added in. A further relation with the same endpoints gets a `-2`, `-3`, ...
+    /// suffix.
+    pub fn relation_id(&self, prefix: &str, from: &str, to: &str) -> String {
+        let hash = stable_hash(&[self.relation_scope.as_str(), prefix, from, to]);
+        let base = format!("{prefix}{hash:016x}");
+        if !self.has_relation_id(&base) {
+            return base;
+        }
+        (2..)
+            .map(|suffix| format!("{base}-{suffix}"))
+            .find(|candidate| !self.has_relation_id(candidate))
+            .expect("unbounded suffixes")
+    }
+
+    fn has_relation_id(&self, id: &str) -> bool {
+        self.used.contains_key(id)
+            || self.was_generated_by.contains_key(id)
+            || self.qualified_generation.contains_key(id)
+            || self.was_associated_with.contains_key(id)
+            || self.was_derived_from.contains_key(id)
+            || self.acted_on_behalf_of.contains_key(id)
+            || self.was_informed_by.contains_key(id)
+    }
+
+    /// Fold `other` into this document, e.g. to combine the per-event documents of a
+    /// context into one export.
+    ///
+    /// Nodes with the same id are merged the way the graph writers `MERGE` them: incoming
+    /// attributes overwrite, an existing `prov:type` is kept, and activity times widen to
+    /// the earliest start and latest end. Identical relations are kept once; a distinct
+    /// relation whose blank id is already taken is re-keyed with a numeric suffix.
+    pub fn merge(&mut self, other: ProvDocument) {
+        for (id, entity) in other.entity {
+            match self.entity.entry(id) {
+                Entry::Vacant(slot) => {
+                    slot.insert(entity);
+                }
+                Entry::Occupied(mut slot) => {
+                    let existing = slot.get_mut();
+                    existing.prov_type = existing.prov_type.take().or(entity.prov_type);
+                    existing.attributes.extend(entity.attributes);
+                }
+            }
+        }
+        for (id, activity) in other.activity {
+            match self.activity.entry(id) {
+                Entry::Vacant(slot) => {
+                    slot.insert(activity);
+                }
+                Entry::Occupied(mut slot) => {
+                    let existing = slot.get_mut();
+                    existing.start_time_ms = match (existing.start_time_ms, activity.start_time_ms) {
+                        (Some(left), Some(right)) => Some(left.min(right)),
+                        (left, right) => left.or(right),
+                    };
+                    existing.end_time_ms = existing.end_time_ms.max(activity.end_time_ms);
+                    existing.prov_type = existing.prov_type.take().or(activity.prov_type);
+                    existing.attributes.extend(activity.attributes);
+                }
+            }
+        }
+        for (id, agent) in other.agent {
+            match self.agent.entry(id) {
+                Entry::Vacant(slot) => {
+                    slot.insert(agent);
+                }
+                Entry::Occupied(mut slot) => {
+                    let existing = slot.get_mut();
+                    existing.prov_type = existing.prov_type.take().or(agent.prov_type);
+                    existing.attributes.extend(agent.attributes);
+                }
+            }
+        }
+        merge_relations(&mut self.used, other.used);
+        merge_relations(&mut self.was_generated_by, other.was_generated_by);
+        merge_relations(&mut self.qualified_generation, other.qualified_generation);
+        merge_relations(&mut self.was_associated_with, other.was_associated_with);
+        merge_relations(&mut self.was_derived_from, other.was_derived_from);
+        merge_relations(&mut self.acted_on_behalf_of, other.acted_on_behalf_of);
+        merge_relations(&mut self.was_informed_by, other.was_informed_by);
+    }
+
+    /// Serialize as W3C PROV-JSON.
+    ///
+    /// Node ids are exported as qualified names in the `baml` namespace, relation ids
+    /// as blank nodes, and times as `xsd:dateTime`. Qualified generations are folded
+    /// into `wasGeneratedBy`.
+    pub fn to_prov_json(&self) -> Value {
+        let mut document = Map::new();
+        let mut prefixes = Map::new();
+        prefixes.insert(namespaces::A2A_PREFIX.to_string(), Value::String(namespaces::A2A.to_string()));
+        prefixes.insert(namespaces::ID_PREFIX.to_string(), Value::String(namespaces::ID.to_string()));
+        document.insert("prefix".to_string(), Value::Object(prefixes));
+
+        let mut entities = Map::new();
+        for (id, entity) in &self.entity {
+            let attributes = node_attributes(entity.prov_type.as_deref(), &entity.attributes);
+            entities.insert(qualified_id(id.as_str()), Value::Object(json_attributes(attributes)));
+        }
+        let mut activities = Map::new();
+        for (id, activity) in &self.activity {
+            let mut attributes = Map::new();
+            if let Some(start) = activity.start_time_ms {
+                attributes.insert(prov::START_TIME.to_string(), Value::String(xsd_date_time(start)));
+            }
+            if let Some(end) = activity.end_time_ms {
+                attributes.insert(prov::END_TIME.to_string(), Value::String(xsd_date_time(end)));
+            }
+            attributes.extend(json_attributes(node_attributes(
+                activity.prov_type.as_deref(),
+                &activity.attributes,
+            )));
+            activities.insert(qualified_id(id.as_str()), Value::Object(attributes));
+        }
+        let mut agents = Map::new();
+        for (id, agent) in &self.agent {
+            let attributes = node_attributes(agent.prov_type.as_deref(), &agent.attributes);
+            agents.insert(qualified_id(id.as_str()), Value::Object(json_attributes(attributes)));
+        }
+
+        let mut used = Map::new();
+        for (id, rel) in &self.used {
+            let mut statement = Map::new();
+            statement.insert(prov::ACTIVITY.to_string(), Value::String(qualified_id(rel.activity.as_str())));
+            statement.insert("prov:entity".to_string(), Value::String(qualified_id(rel.entity.as_str())));
+            if let Some(role) = &rel.role {
+                statement.insert(prov::ROLE.to_string(), Value::String(role.clone()));
+            }
+            used.insert(blank_id(id), Value::Object(statement));
+        }
+        let mut generated = Map::new();
+        for (id, entity, activity, time_ms) in self.generations() {
+            let mut statement = Map::new();
+            statement.insert("prov:entity".to_string(), Value::String(qualified_id(entity)));
+            statement.insert(prov::ACTIVITY.to_string(), Value::String(qualified_id(activity)));
+            if let Some(time_ms) = time_ms {
+                statement.insert(prov::TIME.to_string(), Value::String(xsd_date_time(time_ms)));
+            }
+            generated.insert(blank_id(id), Value::Object(statement));
+        }
+        let mut associated = Map::new();
+        for (id, rel) in &self.was_associated_with {
+            let mut statement = Map::new();
+            statement.insert(prov::ACTIVITY.to_string(), Value::String(qualified_id(rel.activity.as_str())));
+            statement.insert("prov:agent".to_string(), Value::String(qualified_id(rel.agent.as_str())));
+            if let Some(role) = &rel.role {
+                statement.insert(prov::ROLE.to_string(), Value::String(role.clone()));
+            }
+            associated.insert(blank_id(id), Value::Object(statement));
+        }
+        let mut derived = Map::new();
+        for (id, rel) in &self.was_derived_from {
+            let mut statement = Map::new();
+            statement.insert(
+                "prov:generatedEntity".to_string(),
+                Value::String(qualified_id(rel.generated_entity.as_str())),
+            );
+            statement.insert(
+                "prov:usedEntity".to_string(),
+                Value::String(qualified_id(rel.used_entity.as_str())),
+            );
+            if let Some(activity) = &rel.activity {
+                statement.insert(prov::ACTIVITY.to_string(), Value::String(qualified_id(activity.as_str())));
+            }
+            if let Some(prov_type) = &rel.prov_type {
+                statement.insert(prov::TYPE.to_string(), Literal::for_type(prov_type).to_prov_json());
+            }
+            derived.insert(blank_id(id), Value::Object(statement));
+        }
+        let mut delegations = Map::new();
+        for (id, rel) in &self.acted_on_behalf_of {
+            let mut statement = Map::new();
+            statement.insert("prov:delegate".to_string(), Value::String(qualified_id(rel.delegate.as_str())));
+            statement.insert(
+                "prov:responsible".to_string(),
+                Value::String(qualified_id(rel.responsible.as_str())),
+            );
+            if let Some(activity) = &rel.activity {
+                statement.insert(prov::ACTIVITY.to_string(), Value::String(qualified_id(activity.as_str())));
+            }
+            delegations.insert(blank_id(id), Value::Object(statement));
+        }
+        let mut informed = Map::new();
+        for (id, rel) in &self.was_informed_by {
+            let mut statement = Map::new();
+            statement.insert("prov:informed".to_string(), Value::String(qualified_id(rel.informed.as_str())));
+            statement.insert("prov:informant".to_string(), Value::String(qualified_id(rel.informant.as_str())));
+            informed.insert(blank_id(id), Value::Object(statement));
+        }
+
+        for (section, statements) in [
+            ("entity", entities),
+            ("activity", activities),
+            ("agent", agents),
+            ("used", used),
+            ("wasGeneratedBy", generated),
+            ("wasAssociatedWith", associated),
+            ("wasDerivedFrom", derived),
+            ("actedOnBehalfOf", delegations),
+            ("wasInformedBy", informed),
+        ] {
+            if !statements.is_empty() {
+                document.insert(section.to_string(), Value::Object(statements));
+            }
+        }
+        Value::Object(document)
+    }
+
+    /// Parse a document written by [`Self::to_prov_json`].
+    ///
+    /// Typed literals come back as JSON numbers and booleans, qualified names as their
+    /// text, and activity times as epoch milliseconds. Attributes that were exported as
+    /// JSON text stay text, and qualified generations come back as `wasGeneratedBy`.
+    pub fn from_prov_json(value: &Value) -> Result<ProvDocument> {
+        let invalid = |reason: String| ProvenanceError::InvalidDocument(reason);
+        let document = value
+            .as_object()
+            .ok_or_else(|| invalid("document is not a JSON object".to_string()))?;
+        let section = |name: &str| -> Result<Vec<(String, &Map<String, Value>)>> {
+            let Some(statements) = document.get(name) else {
+                return Ok(Vec::new());
+            };
+            let statements = statements
+                .as_object()
+                .ok_or_else(|| invalid(format!("section {name} is not an object")))?;
+            statements
+                .iter()
+                .map(|(id, statement)| {
+                    statement
+                        .as_object()
+                        .map(|statement| (id.clone(), statement))
+                        .ok_or_else(|| invalid(format!("statement {id} in {name} is not an object")))
+                })
+                .collect()
+        };
+        let reference = |statement: &Map<String, Value>, key: &str| -> Result<String> {
+            statement
+                .get(key)
+                .and_then(Value::as_str)
+                .ok_or_else(|| invalid(format!("statement is missing {key}")))
+                .and_then(unqualified_id)
+        };
+
+        let mut doc = ProvDocument::new();
+        for (id, statement) in section("entity")? {
+            let (prov_type, attributes) = parse_node_attributes(statement)?;
+            doc.insert_entity(ProvEntityId::from_stored(unqualified_id(&id)?), Entity { prov_type, attributes });
+        }
+        for (id, statement) in section("activity")? {
+            let time = |key: &str| -> Result<Option<u64>> {
+                statement
+                    .get(key)
+                    .map(|value| {
+                        value
+                            .as_str()
+                            .and_then(parse_xsd_date_time)
+                            .ok_or_else(|| invalid(format!("invalid {key} on {id}")))
+                    })
+                    .transpose()
+            };
+            let start_time_ms = time(prov::START_TIME)?;
+            let end_time_ms = time(prov::END_TIME)?;
+            let mut rest = statement.clone();
+            rest.remove(prov::START_TIME);
+            rest.remove(prov::END_TIME);
+            let (prov_type, attributes) = parse_node_attributes(&rest)?;
+            doc.insert_activity(
+                ProvActivityId::from_stored(unqualified_id(&id)?),
+                Activity { start_time_ms, end_time_ms, prov_type, attributes },
+            );
+        }
+        for (id, statement) in section("agent")? {
+            let (prov_type, attributes) = parse_node_attributes(statement)?;
+            doc.insert_agent(ProvAgentId::from_stored(unqualified_id(&id)?), Agent { prov_type, attributes });
+        }
+
+        let role = |statement: &Map<String, Value>| {
+            statement.get(prov::ROLE).and_then(Value::as_str).map(str::to_string)
+        };
+        for (id, statement) in section("used")? {
+            let used = Used {
+                activity: ProvActivityId::from_stored(reference(statement, prov::ACTIVITY)?),
+                entity: ProvEntityId::from_stored(reference(statement, "prov:entity")?),
+                role: role(statement),
+            };
+            doc.insert_used(relation_id(&id), used);
+        }
+        for (id, statement) in section("wasGeneratedBy")? {
+            let entity = reference(statement, "prov:entity")?;
+            let entity = if doc.agent.contains_key(&ProvAgentId::from_stored(entity.clone())) {
+                ProvNodeRef::Agent(ProvAgentId::from_stored(entity))
+            } else {
+                ProvNodeRef::Entity(ProvEntityId::from_stored(entity))
+            };
+            let time_ms = match statement.get(prov::TIME) {
+                Some(time) => Some(
+                    time.as_str()
+                        .and_then(parse_xsd_date_time)
+                        .ok_or_else(|| invalid(format!("invalid {} on {id}", prov::TIME)))?,
+                ),
+                None => None,
+            };
+            let generated = WasGeneratedBy {
+                entity,
+                activity: ProvActivityId::from_stored(reference(statement, prov::ACTIVITY)?),
+                time_ms,
+            };
+            doc.insert_was_generated_by(relation_id(&id), generated);
+        }
+        for (id, statement) in section("wasAssociatedWith")? {
+            let assoc = WasAssociatedWith {
+                activity: ProvActivityId::from_stored(reference(statement, prov::ACTIVITY)?),
+                agent: ProvAgentId::from_stored(reference(statement, "prov:agent")?),
+                role: role(statement),
+            };
+            doc.insert_was_associated_with(relation_id(&id), assoc);
+        }
+        for (id, statement) in section("wasDerivedFrom")? {
+            let activity = match statement.get(prov::ACTIVITY) {
+                Some(_) => Some(ProvActivityId::from_stored(reference(statement, prov::ACTIVITY)?)),
+                None => None,
+            };
+            let derived = WasDerivedFrom {
+                generated_entity: ProvEntityId::from_stored(reference(statement, "prov:generatedEntity")?),
+                used_entity: ProvEntityId::from_stored(reference(statement, "prov:usedEntity")?),
+                activity,
+                prov_type: statement.get(prov::TYPE).map(literal_text),
+            };
+            doc.insert_was_derived_from(relation_id(&id), derived);
+        }
+        for (id, statement) in section("actedOnBehalfOf")? {
+            let activity = match statement.get(prov::ACTIVITY) {
+                Some(_) => Some(ProvActivityId::from_stored(reference(statement, prov::ACTIVITY)?)),
+                None => None,
+            };
+            let delegation = ActedOnBehalfOf {
+                delegate: ProvAgentId::from_stored(reference(statement, "prov:delegate")?),
+                responsible: ProvAgentId::from_stored(reference(statement, "prov:responsible")?),
+                activity,
+            };
+            doc.insert_acted_on_behalf_of(relation_id(&id), delegation);
+        }
+        for (id, statement) in section("wasInformedBy")? {
+            let informed = WasInformedBy {
+                informed: ProvActivityId::from_stored(reference(statement, "prov:informed")?),
+                informant: ProvActivityId::from_stored(reference(statement, "prov:informant")?),
+            };
+            doc.insert_was_informed_by(relation_id(&id), informed);
+        }
+        Ok(doc)
+    }
+
+    /// Serialize as W3C PROV-N. Statements are sorted so equal documents render identically.
+    pub fn to_prov_n(&self) -> String {
+        let sections: Vec<Vec<String>> = vec![
+            self.entity
+                .iter()
+                .map(|(id, entity)| {
+                    let attributes = node_attributes(entity.prov_type.as_deref(), &entity.attributes);
+                    format!("entity({}{})", qualified_id(id.as_str()), prov_n_attributes(&attributes))
+                })
+                .collect(),
+            self.activity
+                .iter()
+                .map(|(id, activity)| {
+                    let attributes = node_attributes(activity.prov_type.as_deref(), &activity.attributes);
+                    format!(
+                        "activity({}, {}, {}{})",
+                        qualified_id(id.as_str()),
+                        prov_n_time(activity.start_time_ms),
+                        prov_n_time(activity.end_time_ms),
+                        prov_n_attributes(&attributes)
+                    )
+                })
+                .collect(),
+            self.agent
+                .iter()
+                .map(|(id, agent)| {
+                    let attributes = node_attributes(agent.prov_type.as_deref(), &agent.attributes);
+                    format!("agent({}{})", qualified_id(id.as_str()), prov_n_attributes(&attributes))
+                })
+                .collect(),
+            self.used
+                .values()
+                .map(|rel| {
+                    format!(
+                        "used({}, {}, -{})",
+                        qualified_id(rel.activity.as_str()),
+                        qualified_id(rel.entity.as_str()),
+                        prov_n_attributes(&role_attribute(rel.role.as_deref()))
+                    )
+                })
+                .collect(),
+            self.generations()
+                .map(|(_, entity, activity, time_ms)| {
+                    format!(
+                        "wasGeneratedBy({}, {}, {})",
+                        qualified_id(entity),
+                        qualified_id(activity),
+                        prov_n_time(time_ms)
+                    )
+                })
+                .collect(),
+            self.was_associated_with
+                .values()
+                .map(|rel| {
+                    format!(
+                        "wasAssociatedWith({}, {}, -{})",
+                        qualified_id(rel.activity.as_str()),
+                        qualified_id(rel.agent.as_str()),
+                        prov_n_attributes(&role_attribute(rel.role.as_deref()))
+                    )
+                })
+                .collect(),
+            self.was_derived_from
+                .values()
+                .map(|rel| {
+                    let attributes: Vec<_> = rel
+                        .prov_type
+                        .iter()
+                        .map(|prov_type| (prov::TYPE.to_string(), Literal::for_type(prov_type)))
+                        .collect();
+                    format!(
+                        "wasDerivedFrom({}, {}, {}, -, -{})",
+                        qualified_id(rel.generated_entity.as_str()),
+                        qualified_id(rel.used_entity.as_str()),
+                        rel.activity
+                            .as_ref()
+                            .map(|activity| qualified_id(activity.as_str()))
+                            .unwrap_or_else(|| "-".to_string()),
+                        prov_n_attributes(&attributes)
+                    )
+                })
+                .collect(),
+            self.acted_on_behalf_of
+                .values()
+                .map(|rel| {
+                    format!(
+                        "actedOnBehalfOf({}, {}, {})",
+                        qualified_id(rel.delegate.as_str()),
+                        qualified_id(rel.responsible.as_str()),
+                        rel.activity
+                            .as_ref()
+                            .map(|activity| qualified_id(activity.as_str()))
+                            .unwrap_or_else(|| "-".to_string()),
+                    )
+                })
+                .collect(),
+            self.was_informed_by
+                .values()
+                .map(|rel| {
+                    format!(
+                        "wasInformedBy({}, {})",
+                        qualified_id(rel.informed.as_str()),
+                        qualified_id(rel.informant.as_str())
+                    )
+                })
+                .collect(),
+        ];
+
+        let mut out = String::from("document\n");
+        for (prefix, iri) in [
+            (namespaces::A2A_PREFIX, namespaces::A2A),
+            (namespaces::ID_PREFIX, namespaces::ID),
+        ] {
+            out.push_str(&format!("  prefix {} <{}>\n", prefix, iri));
+        }
+        for mut statements in sections {
+            statements.sort();
+            statements.dedup();
+            for statement in statements {
+                out.push_str("  ");
+                out.push_str(&statement);
+                out.push('\n');
+            }
+        }
+        out.push_str("endDocument\n");
+        out
+    }
+
+    /// `wasGeneratedBy` and qualified generations as `(id, entity, activity, time)`,
+    /// skipping qualified generations that repeat a plain one.
+    fn generations(&self) -> impl Iterator<Item = (&str, &str, &str, Option<u64>)> {
+        let plain: HashSet<_> = self
+            .was_generated_by
+            .values()
+            .map(|rel| (rel.entity.id(), rel.activity.as_str(), rel.time_ms))
+            .collect();
+        let qualified = self
+            .qualified_generation
+            .iter()
+            .map(|(id, rel)| (id.as_str(), rel.entity.id(), rel.activity.as_str(), rel.time_ms))
+            .filter(move |(_, entity, activity, time_ms)| {
+                !plain.contains(&(*entity, *activity, *time_ms))
+            });
+        self.was_generated_by
+            .iter()
+            .map(|(id, rel)| (id.as_str(), rel.entity.id(), rel.activity.as_str(), rel.time_ms))
+            .chain(qualified)
+    }
+}
+
+fn merge_relations<R: Clone + Eq + Hash>(target: &mut HashMap<String, R>, incoming: HashMap<String, R>) {
+    let mut seen: HashSet<R> = target.values().cloned().collect();
+    let mut incoming: Vec<_> = incoming.into_iter().collect();
+    incoming.sort_by(|left, right| left.0.cmp(&right.0));
+    for (id, rel) in incoming {
+        if !seen.insert(rel.clone()) {
+            continue;
+        }
+        let id = if target.contains_key(&id) {
+            (2..)
+                .map(|suffix| format!("{id}-{suffix}"))
+                .find(|candidate| !target.contains_key(candidate))
+                .expect("unbounded suffixes")
+        } else {
+            id
+        };
+        target.insert(id, rel);
+    }
+}
+
+/// 64-bit FNV-1a over `parts`, each terminated by a byte UTF-8 never contains.
+///
+/// Spelled out rather than taken from `std::hash`, whose output may change
+/// between Rust releases; relation ids end up in stored graphs and exports.
+fn stable_hash(parts: &[&str]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    let mut hash = OFFSET_BASIS;
+    for part in parts {
+        for byte in part.bytes().chain(std::iter::once(0xff)) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(PRIME);
+        }
+    }
+    hash
+}
+
+/// An attribute value as PROV-JSON and PROV-N understand it.
+enum Literal {
+    String(String),
+    QualifiedName(String),
+    Typed { lexical: String, datatype: &'static str },
+}
+
+impl Literal {
+    /// `prov:type` values are qualified names when they use a declared prefix.
+    fn for_type(value: &str) -> Self {
+        let declared = value.split_once(':').is_some_and(|(prefix, local)| {
+            is_declared_prefix(prefix)
+                && !local.is_empty()
+                && local.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-')
+        });
+        if declared {
+            Literal::QualifiedName(value.to_string())
+        } else {
+            Literal::String(value.to_string())
+        }
+    }
+
+    fn from_json(key: &str, value: &Value) -> Option<Self> {
+        match value {
+            Value::Null => None,
+            Value::String(text) if key == prov::TYPE => Some(Self::for_type(text)),
+            Value::String(text) => Some(Literal::String(text.clone())),
+            Value::Bool(flag) => Some(Literal::Typed { lexical: flag.to_string(), datatype: "xsd:boolean" }),
+            Value::Number(number) => Some(Literal::Typed {
+                lexical: number.to_string(),
+                datatype: if number.is_f64() { "xsd:double" } else { "xsd:long" },
+            }),
+            // PROV has no structured values; nested JSON travels as its text.
+            Value::Array(_) | Value::Object(_) => Some(Literal::String(value.to_string())),
+        }
+    }
+
+    fn to_prov_json(&self) -> Value {
+        match self {
+            Literal::String(text) => Value::String(text.clone()),
+            Literal::QualifiedName(name) => json!({"$": name, "type": "prov:QUALIFIED_NAME"}),
+            Literal::Typed { lexical, datatype } => json!({"$": lexical, "type": datatype}),
+        }
+    }
+
+    fn to_prov_n(&self) -> String {
+        match self {
+            Literal::String(text) => prov_n_string(text),
+            Literal::QualifiedName(name) => format!("'{}'", name),
+            Literal::Typed { lexical, datatype } => format!("{} %% {}", prov_n_string(lexical), datatype),
+        }
     }
 }
+
+fn is_declared_prefix(prefix: &str) -> bool {
+    [
+        namespaces::PROV_PREFIX,
+        namespaces::XSD_PREFIX,
+        namespaces::A2A_PREFIX,
+        namespaces::ID_PREFIX,
+    ]
+    .contains(&prefix)
+}
+
+/// Sorted attribute literals, `prov:type` first. The internal `prov:base_type` is dropped.
+fn node_attributes(prov_type: Option<&str>, attributes: &HashMap<String, Value>) -> Vec<(String, Literal)> {
+    let mut literals: Vec<_> = attributes
+        .iter()
+        .filter(|(key, _)| key.as_str() != prov::BASE_TYPE)
+        .filter(|(key, _)| prov_type.is_none() || key.as_str() != prov::TYPE)
+        .filter_map(|(key, value)| Literal::from_json(key, value).map(|literal| (attribute_name(key), literal)))
+        .collect();
+    literals.sort_by(|left, right| left.0.cmp(&right.0));
+    if let Some(prov_type) = prov_type {
+        literals.insert(0, (prov::TYPE.to_string(), Literal::for_type(prov_type)));
+    }
+    literals
+}
+
+fn role_attribute(role: Option<&str>) -> Vec<(String, Literal)> {
+    role.map(|role| (prov::ROLE.to_string(), Literal::String(role.to_string())))
+        .into_iter()
+        .collect()
+}
+
+fn attribute_name(key: &str) -> String {
+    match key.split_once(':') {
+        Some((prefix, _)) if is_declared_prefix(prefix) => key.to_string(),
+        _ => qualified_id(key),
+    }
+}
+
+fn json_attributes(attributes: Vec<(String, Literal)>) -> Map<String, Value> {
+    attributes
+        .into_iter()
+        .map(|(key, literal)| (key, literal.to_prov_json()))
+        .collect()
+}
+
+fn prov_n_attributes(attributes: &[(String, Literal)]) -> String {
+    if attributes.is_empty() {
+        return String::new();
+    }
+    let rendered: Vec<_> = attributes
+        .iter()
+        .map(|(key, literal)| format!("{}={}", key, literal.to_prov_n()))
+        .collect();
+    format!(", [{}]", rendered.join(", "))
+}
+
+fn prov_n_time(time_ms: Option<u64>) -> String {
+    time_ms.map(xsd_date_time).unwrap_or_else(|| "-".to_string())
+}
+
+fn prov_n_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            ch => out.push(ch),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn blank_id(id: &str) -> String {
+    format!("_:{}", id)
+}
+
+fn relation_id(id: &str) -> String {
+    id.strip_prefix("_:").unwrap_or(id).to_string()
+}
+
+/// `prov:type` and the remaining attributes of an exported node.
+fn parse_node_attributes(
+    statement: &Map<String, Value>,
+) -> Result<(Option<String>, HashMap<String, Value>)> {
+    let mut prov_type = None;
+    let mut attributes = HashMap::new();
+    for (key, value) in statement {
+        if key == prov::TYPE {
+            prov_type = Some(literal_text(value));
+            continue;
+        }
+        let key = match key.strip_prefix(&format!("{}:", namespaces::ID_PREFIX)) {
+            Some(_) => unqualified_id(key)?,
+            None => key.clone(),
+        };
+        attributes.insert(key, literal_value(value));
+    }
+    Ok((prov_type, attributes))
+}
+
+/// The lexical form of a plain or typed literal.
+fn literal_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Object(typed) => match typed.get("$") {
+            Some(Value::String(lexical)) => lexical.clone(),
+            Some(other) => other.to_string(),
+            None => value.to_string(),
+        },
+        other => other.to_string(),
+    }
+}
+
+/// A literal as the JSON value it was exported from.
+fn literal_value(value: &Value) -> Value {
+    let Value::Object(typed) = value else {
+        return value.clone();
+    };
+    let lexical = literal_text(value);
+    let parsed = match typed.get("type").and_then(Value::as_str) {
+        Some("xsd:long" | "xsd:int" | "xsd:integer") => lexical
+            .parse::<i64>()
+            .map(Value::from)
+            .or_else(|_| lexical.parse::<u64>().map(Value::from))
+            .ok(),
+        Some("xsd:double") => lexical
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(Value::Number),
+        Some("xsd:boolean") => lexical.parse::<bool>().ok().map(Value::Bool),
+        _ => None,
+    };
+    parsed.unwrap_or(Value::String(lexical))
+}
+
+/// Inverse of [`qualified_id`].
+fn unqualified_id(name: &str) -> Result<String> {
+    let prefix = format!("{}:", namespaces::ID_PREFIX);
+    let local = name.strip_prefix(&prefix).ok_or_else(|| {
+        ProvenanceError::InvalidDocument(format!("{name} is not in the {} namespace", namespaces::ID_PREFIX))
+    })?;
+    let mut bytes = Vec::with_capacity(local.len());
+    let mut chars = local.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => {
+                let escaped = chars.next().ok_or_else(|| {
+                    ProvenanceError::InvalidDocument(format!("{name} ends with an escape"))
+                })?;
+                let mut buf = [0u8; 4];
+                bytes.extend_from_slice(escaped.encode_utf8(&mut buf).as_bytes());
+            }
+            '%' => {
+                let hex: String = chars.by_ref().take(2).collect();
+                let byte = u8::from_str_radix(&hex, 16).map_err(|_| {
+                    ProvenanceError::InvalidDocument(format!("{name} has an invalid percent escape"))
+                })?;
+                bytes.push(byte);
+            }
+            ch => {
+                let mut buf = [0u8; 4];
+                bytes.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+            }
+        }
+    }
+    String::from_utf8(bytes)
+        .map_err(|_| ProvenanceError::InvalidDocument(format!("{name} is not valid UTF-8")))
+}
+
+/// `id` as a qualified name in the `baml` namespace, escaping characters that are
+/// not allowed in a PROV-N local name.
+fn qualified_id(id: &str) -> String {
+    let mut out = format!("{}:", namespaces::ID_PREFIX);
+    for (index, ch) in id.char_indices() {
+        let edge = index == 0 || index + ch.len_utf8() == id.len();
+        match ch {
+            ch if ch.is_ascii_alphanumeric() || ch == '_' || ch == ':' => out.push(ch),
+            '-' if index > 0 => out.push(ch),
+            '.' if !edge => out.push(ch),
+            '~' | '.' | '-' | '!' | '$' | '&' | '\'' | '(' | ')' | '*' | '+' | ',' | ';' | '='
+            | '/' | '?' | '#' | '@' | '%' => {
+                out.push('\\');
+                out.push(ch);
+            }
+            ch => {
+                let mut buf = [0u8; 4];
+                for byte in ch.encode_utf8(&mut buf).bytes() {
+                    out.push_str(&format!("%{:02X}", byte));
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Epoch milliseconds as an `xsd:dateTime` in UTC.
+fn xsd_date_time(time_ms: u64) -> String {
+    let seconds = time_ms / 1000;
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    let seconds_of_day = seconds % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60,
+        time_ms % 1000
+    )
+}
+
+/// Epoch milliseconds from an `xsd:dateTime` written by [`xsd_date_time`].
+fn parse_xsd_date_time(value: &str) -> Option<u64> {
+    let value = value.strip_suffix('Z')?;
+    let (date, time) = value.split_once('T')?;
+    let mut date = date.splitn(3, '-').map(str::parse::<i64>);
+    let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+    let (time, millis) = match time.split_once('.') {
+        Some((time, fraction)) => (time, format!("{fraction:0<3}")[..3].parse::<u64>().ok()?),
+        None => (time, 0),
+    };
+    let mut time = time.splitn(3, ':').map(str::parse::<u64>);
+    let (hours, minutes, seconds) = (time.next()?.ok()?, time.next()?.ok()?, time.next()?.ok()?);
+    let days = u64::try_from(days_from_civil(year, month as u32, day as u32)).ok()?;
+    Some(((days * 86_400 + hours * 3600 + minutes * 60 + seconds) * 1000) + millis)
+}
+
+/// Day count since 1970-01-01 for a Gregorian date (Howard Hinnant's algorithm).
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = i64::from(month);
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Gregorian date for a day count since 1970-01-01 (Howard Hinnant's algorithm).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
@@ -10,6 +10,16 @@ pub enum ProvenanceError {
     MissingField { event_id: String, field: String },
     #[error("invalid provenance mapping: {relation} ({from_label} -> {to_label})")]
     InvalidMapping { relation: String, from_label: String, to_label: String },
+    #[error("invalid attribute {key} on {node_id}: {reason}")]
+    InvalidAttribute { node_id: String, key: String, reason: String },
+    #[error("{principal} may not read {resource}")]
+    AccessDenied { principal: String, resource: String },
+    #[error("invalid PROV document: {0}")]
+    InvalidDocument(String),
+    #[error("invalid redaction rule {rule}: {reason}")]
+    InvalidRedactionRule { rule: String, reason: String },
+    #[error("invalid provenance setting {key}: {reason}")]
+    InvalidSetting { key: String, reason: String },
     #[error("missing required label for {kind} {node_id}")]
     MissingLabel { node_id: String, kind: String },
 }
@@ -1,3 +1,4 @@
+use baml_rt_core::context;
 use baml_rt_core::ids::{AgentId, ArtifactId, ContextId, EventId, MessageId, TaskId};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -7,7 +8,7 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 static EVENT_COUNTER: AtomicU64 = AtomicU64::new(1);
 
-fn now_millis() -> u64 {
+pub(crate) fn now_millis() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .map(|d| d.as_millis() as u64)
@@ -19,6 +20,21 @@ fn next_event_id() -> EventId {
     EventId::from_counter(id)
 }
 
+/// Advance the event counter past an id restored from storage so that events
+/// created afterwards do not reuse it.
+pub(crate) fn observe_event_id(id: &EventId) {
+    if let Some(counter) = event_sequence(id) {
+        EVENT_COUNTER.fetch_max(counter.saturating_add(1), Ordering::Relaxed);
+    }
+}
+
+/// The counter an event id was issued from, for ids minted by this process.
+pub(crate) fn event_sequence(id: &EventId) -> Option<u64> {
+    id.as_str()
+        .strip_prefix("prov-")
+        .and_then(|counter| counter.parse::<u64>().ok())
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 #[serde(transparent)]
 pub struct AgentType(String);
@@ -59,6 +75,12 @@ pub enum CallScope {
     Task { task_id: TaskId },
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum FeedbackTarget {
+    Message { message_id: MessageId },
+    Task { task_id: TaskId },
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ProvEventData {
     LlmCallStarted {
@@ -111,6 +133,14 @@ pub enum ProvEventData {
         old_status: Option<String>,
         new_status: Option<String>,
     },
+    /// A status change the task state machine does not allow.
+    IllegalTaskTransition {
+        task_id: TaskId,
+        from_status: String,
+        to_status: String,
+        /// Whether the store refused the change or applied it anyway.
+        rejected: bool,
+    },
     TaskArtifactGenerated {
         task_id: TaskId,
         artifact_id: Option<ArtifactId>,
@@ -128,6 +158,131 @@ pub enum ProvEventData {
         content: Vec<String>,
         metadata: Option<HashMap<String, String>>,
     },
+    ContextForked {
+        parent_context_id: ContextId,
+        forked_at_message_id: MessageId,
+    },
+    ContextNested {
+        parent_context_id: ContextId,
+    },
+    FeedbackSubmitted {
+        feedback_id: String,
+        target: FeedbackTarget,
+        rating: Option<i64>,
+        correction: Option<String>,
+        comment: Option<String>,
+        metadata: Option<HashMap<String, String>>,
+    },
+    /// A usage budget was spent; further LLM calls in its scope are refused.
+    BudgetExhausted {
+        /// `"task"`, `"context"` or `"agent"`
+        scope: String,
+        scope_id: String,
+        /// The count that reached its limit, e.g. `"total_tokens"`.
+        dimension: String,
+        limit: u64,
+        /// Everything the scope spent, including the call that tripped the budget.
+        spent: LlmUsage,
+    },
+    /// A line the agent's JavaScript wrote to the console.
+    ConsoleMessage {
+        /// `"debug"`, `"log"`, `"info"`, `"warn"` or `"error"`
+        level: String,
+        message: String,
+    },
+    /// The runner applied a new version of its reloadable configuration.
+    ConfigChanged {
+        /// Increments with every applied change, starting at 1 for the config
+        /// read at startup.
+        version: u64,
+        /// Digest of the applied configuration.
+        digest: String,
+        /// Digest of the configuration it replaced; `None` at startup.
+        previous_digest: Option<String>,
+        /// Top-level keys whose values changed, e.g. `"rate_limits"`.
+        changed: Vec<String>,
+        /// What triggered the reload, e.g. `"startup"`, `"sighup"` or `"admin"`.
+        trigger: String,
+    },
+    /// An agent's BAML schema was reloaded from a changed `baml_src`.
+    SchemaChanged {
+        agent_id: AgentId,
+        /// Digest of the `.baml` sources now loaded.
+        digest: String,
+        /// Digest of the sources they replaced.
+        previous_digest: Option<String>,
+        /// Functions by name.
+        added: Vec<String>,
+        removed: Vec<String>,
+        /// Functions whose parameters or output type changed.
+        changed: Vec<String>,
+    },
+    /// Running totals of one agent's calls to one tool, written periodically
+    /// by [`ToolUsageReporter`](crate::tool_usage::ToolUsageReporter).
+    ToolUsageSummarized {
+        agent_id: AgentId,
+        tool_name: String,
+        invocations: u64,
+        failures: u64,
+        /// Latency percentiles over the most recent calls.
+        p50_ms: u64,
+        p95_ms: u64,
+        p99_ms: u64,
+    },
+    /// A policy rule decided a tool or LLM call.
+    PolicyEvaluated {
+        policy_id: String,
+        /// `"allow"` or `"deny"`
+        effect: String,
+        /// `"tool"` or `"llm"`
+        target: String,
+        /// The tool, or the BAML function making the LLM call.
+        subject: String,
+        /// Why the call was denied.
+        reason: Option<String>,
+    },
+    /// A tool call held for approval was approved, denied, or timed out.
+    ApprovalDecided {
+        approval_id: String,
+        tool_name: String,
+        /// The arguments the approver was shown.
+        args: Value,
+        /// `"approved"`, `"denied"` or `"timed_out"`
+        verdict: String,
+        /// Who decided; absent when the call timed out.
+        approver: Option<String>,
+        reason: Option<String>,
+        waited_ms: u64,
+    },
+}
+
+impl ProvEventData {
+    /// The variant name, as it appears in serialized events.
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            ProvEventData::LlmCallStarted { .. } => "LlmCallStarted",
+            ProvEventData::LlmCallCompleted { .. } => "LlmCallCompleted",
+            ProvEventData::ToolCallStarted { .. } => "ToolCallStarted",
+            ProvEventData::ToolCallCompleted { .. } => "ToolCallCompleted",
+            ProvEventData::AgentBooted { .. } => "AgentBooted",
+            ProvEventData::TaskCreated { .. } => "TaskCreated",
+            ProvEventData::TaskStatusChanged { .. } => "TaskStatusChanged",
+            ProvEventData::IllegalTaskTransition { .. } => "IllegalTaskTransition",
+            ProvEventData::TaskArtifactGenerated { .. } => "TaskArtifactGenerated",
+            ProvEventData::MessageReceived { .. } => "MessageReceived",
+            ProvEventData::MessageSent { .. } => "MessageSent",
+            ProvEventData::ContextForked { .. } => "ContextForked",
+            ProvEventData::ContextNested { .. } => "ContextNested",
+            ProvEventData::FeedbackSubmitted { .. } => "FeedbackSubmitted",
+            ProvEventData::BudgetExhausted { .. } => "BudgetExhausted",
+            ProvEventData::ConsoleMessage { .. } => "ConsoleMessage",
+            ProvEventData::ConfigChanged { .. } => "ConfigChanged",
+            ProvEventData::SchemaChanged { .. } => "SchemaChanged",
+            ProvEventData::ToolUsageSummarized { .. } => "ToolUsageSummarized",
+            ProvEventData::PolicyEvaluated { .. } => "PolicyEvaluated",
+            ProvEventData::ApprovalDecided { .. } => "ApprovalDecided",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -136,6 +291,9 @@ pub struct TaskScopedEvent {
     pub context_id: ContextId,
     pub task_id: TaskId,
     pub timestamp_ms: u64,
+    /// Tenant the event was recorded for; absent outside multi-tenant deployments.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
     pub data: ProvEventData,
 }
 
@@ -144,6 +302,9 @@ pub struct GlobalEvent {
     pub id: EventId,
     pub context_id: ContextId,
     pub timestamp_ms: u64,
+    /// Tenant the event was recorded for; absent outside multi-tenant deployments.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
     pub data: ProvEventData,
 }
 
@@ -175,6 +336,13 @@ impl ProvEvent {
         }
     }
 
+    pub fn tenant(&self) -> Option<&str> {
+        match self {
+            ProvEvent::Task(event) => event.tenant.as_deref(),
+            ProvEvent::Global(event) => event.tenant.as_deref(),
+        }
+    }
+
     pub fn timestamp_ms(&self) -> u64 {
         match self {
             ProvEvent::Task(event) => event.timestamp_ms,
@@ -189,6 +357,13 @@ impl ProvEvent {
         }
     }
 
+    pub fn data_mut(&mut self) -> &mut ProvEventData {
+        match self {
+            ProvEvent::Task(event) => &mut event.data,
+            ProvEvent::Global(event) => &mut event.data,
+        }
+    }
+
     pub fn llm_call_started_global(
         context_id: ContextId,
         message_id: MessageId,
@@ -200,6 +375,7 @@ impl ProvEvent {
     ) -> Self {
         ProvEvent::Global(GlobalEvent {
             id: next_event_id(),
+            tenant: context::current_tenant(),
             context_id,
             timestamp_ms: now_millis(),
             data: ProvEventData::LlmCallStarted {
@@ -224,6 +400,7 @@ impl ProvEvent {
     ) -> Self {
         ProvEvent::Task(TaskScopedEvent {
             id: next_event_id(),
+            tenant: context::current_tenant(),
             context_id,
             task_id: task_id.clone(),
             timestamp_ms: now_millis(),
@@ -253,6 +430,7 @@ impl ProvEvent {
     ) -> Self {
         ProvEvent::Global(GlobalEvent {
             id: next_event_id(),
+            tenant: context::current_tenant(),
             context_id,
             timestamp_ms: now_millis(),
             data: ProvEventData::LlmCallCompleted {
@@ -284,6 +462,7 @@ impl ProvEvent {
     ) -> Self {
         ProvEvent::Task(TaskScopedEvent {
             id: next_event_id(),
+            tenant: context::current_tenant(),
             context_id,
             task_id: task_id.clone(),
             timestamp_ms: now_millis(),
@@ -311,6 +490,7 @@ impl ProvEvent {
     ) -> Self {
         ProvEvent::Global(GlobalEvent {
             id: next_event_id(),
+            tenant: context::current_tenant(),
             context_id,
             timestamp_ms: now_millis(),
             data: ProvEventData::ToolCallStarted {
@@ -333,6 +513,7 @@ impl ProvEvent {
     ) -> Self {
         ProvEvent::Task(TaskScopedEvent {
             id: next_event_id(),
+            tenant: context::current_tenant(),
             context_id,
             task_id: task_id.clone(),
             timestamp_ms: now_millis(),
@@ -359,6 +540,7 @@ impl ProvEvent {
     ) -> Self {
         ProvEvent::Global(GlobalEvent {
             id: next_event_id(),
+            tenant: context::current_tenant(),
             context_id,
             timestamp_ms: now_millis(),
             data: ProvEventData::ToolCallCompleted {
@@ -386,6 +568,7 @@ impl ProvEvent {
     ) -> Self {
         ProvEvent::Task(TaskScopedEvent {
             id: next_event_id(),
+            tenant: context::current_tenant(),
             context_id,
             task_id: task_id.clone(),
             timestamp_ms: now_millis(),
@@ -410,6 +593,7 @@ impl ProvEvent {
     ) -> Self {
         ProvEvent::Global(GlobalEvent {
             id: next_event_id(),
+            tenant: context::current_tenant(),
             context_id,
             timestamp_ms: now_millis(),
             data: ProvEventData::AgentBooted {
@@ -424,6 +608,7 @@ impl ProvEvent {
     pub fn task_created(context_id: ContextId, task_id: TaskId, agent_id: AgentId) -> Self {
         ProvEvent::Task(TaskScopedEvent {
             id: next_event_id(),
+            tenant: context::current_tenant(),
             context_id,
             task_id: task_id.clone(),
             timestamp_ms: now_millis(),
@@ -439,6 +624,7 @@ impl ProvEvent {
     ) -> Self {
         ProvEvent::Task(TaskScopedEvent {
             id: next_event_id(),
+            tenant: context::current_tenant(),
             context_id,
             task_id: task_id.clone(),
             timestamp_ms: now_millis(),
@@ -446,6 +632,23 @@ impl ProvEvent {
         })
     }
 
+    pub fn illegal_task_transition(
+        context_id: ContextId,
+        task_id: TaskId,
+        from_status: String,
+        to_status: String,
+        rejected: bool,
+    ) -> Self {
+        ProvEvent::Task(TaskScopedEvent {
+            id: next_event_id(),
+            tenant: context::current_tenant(),
+            context_id,
+            task_id: task_id.clone(),
+            timestamp_ms: now_millis(),
+            data: ProvEventData::IllegalTaskTransition { task_id, from_status, to_status, rejected },
+        })
+    }
+
     pub fn task_artifact_generated(
         context_id: ContextId,
         task_id: TaskId,
@@ -454,6 +657,7 @@ impl ProvEvent {
     ) -> Self {
         ProvEvent::Task(TaskScopedEvent {
             id: next_event_id(),
+            tenant: context::current_tenant(),
             context_id,
             task_id: task_id.clone(),
             timestamp_ms: now_millis(),
@@ -472,6 +676,7 @@ impl ProvEvent {
     ) -> Self {
         ProvEvent::Task(TaskScopedEvent {
             id: next_event_id(),
+            tenant: context::current_tenant(),
             context_id,
             task_id,
             timestamp_ms,
@@ -489,6 +694,7 @@ impl ProvEvent {
     ) -> Self {
         ProvEvent::Global(GlobalEvent {
             id: next_event_id(),
+            tenant: context::current_tenant(),
             context_id,
             timestamp_ms,
             data: ProvEventData::MessageReceived { id, role, content, metadata },
@@ -506,6 +712,7 @@ impl ProvEvent {
     ) -> Self {
         ProvEvent::Task(TaskScopedEvent {
             id: next_event_id(),
+            tenant: context::current_tenant(),
             context_id,
             task_id,
             timestamp_ms,
@@ -523,9 +730,300 @@ impl ProvEvent {
     ) -> Self {
         ProvEvent::Global(GlobalEvent {
             id: next_event_id(),
+            tenant: context::current_tenant(),
             context_id,
             timestamp_ms,
             data: ProvEventData::MessageSent { id, role, content, metadata },
         })
     }
+
+    /// Feedback on a task is task-scoped; feedback on a message is global.
+    pub fn feedback_submitted(
+        context_id: ContextId,
+        feedback_id: String,
+        target: FeedbackTarget,
+        rating: Option<i64>,
+        correction: Option<String>,
+        comment: Option<String>,
+        metadata: Option<HashMap<String, String>>,
+    ) -> Self {
+        let data = ProvEventData::FeedbackSubmitted {
+            feedback_id,
+            target: target.clone(),
+            rating,
+            correction,
+            comment,
+            metadata,
+        };
+        match target {
+            FeedbackTarget::Task { task_id } => ProvEvent::Task(TaskScopedEvent {
+                id: next_event_id(),
+                tenant: context::current_tenant(),
+                context_id,
+                task_id,
+                timestamp_ms: now_millis(),
+                data,
+            }),
+            FeedbackTarget::Message { .. } => ProvEvent::Global(GlobalEvent {
+                id: next_event_id(),
+                tenant: context::current_tenant(),
+                context_id,
+                timestamp_ms: now_millis(),
+                data,
+            }),
+        }
+    }
+
+    /// The event belongs to the new branch; the parent is carried in the payload.
+    pub fn context_forked(
+        context_id: ContextId,
+        parent_context_id: ContextId,
+        forked_at_message_id: MessageId,
+    ) -> Self {
+        ProvEvent::Global(GlobalEvent {
+            id: next_event_id(),
+            tenant: context::current_tenant(),
+            context_id,
+            timestamp_ms: now_millis(),
+            data: ProvEventData::ContextForked { parent_context_id, forked_at_message_id },
+        })
+    }
+
+    /// A child context started: the event belongs to the child, the parent is
+    /// carried in the payload.
+    pub fn context_nested(context_id: ContextId, parent_context_id: ContextId) -> Self {
+        ProvEvent::Global(GlobalEvent {
+            id: next_event_id(),
+            tenant: context::current_tenant(),
+            context_id,
+            timestamp_ms: now_millis(),
+            data: ProvEventData::ContextNested { parent_context_id },
+        })
+    }
+
+    pub fn budget_exhausted_task(
+        context_id: ContextId,
+        task_id: TaskId,
+        scope: String,
+        scope_id: String,
+        dimension: String,
+        limit: u64,
+        spent: LlmUsage,
+    ) -> Self {
+        ProvEvent::Task(TaskScopedEvent {
+            id: next_event_id(),
+            tenant: context::current_tenant(),
+            context_id,
+            task_id,
+            timestamp_ms: now_millis(),
+            data: ProvEventData::BudgetExhausted { scope, scope_id, dimension, limit, spent },
+        })
+    }
+
+    pub fn budget_exhausted_global(
+        context_id: ContextId,
+        scope: String,
+        scope_id: String,
+        dimension: String,
+        limit: u64,
+        spent: LlmUsage,
+    ) -> Self {
+        ProvEvent::Global(GlobalEvent {
+            id: next_event_id(),
+            tenant: context::current_tenant(),
+            context_id,
+            timestamp_ms: now_millis(),
+            data: ProvEventData::BudgetExhausted { scope, scope_id, dimension, limit, spent },
+        })
+    }
+
+    pub fn policy_evaluated_task(
+        context_id: ContextId,
+        task_id: TaskId,
+        policy_id: String,
+        effect: String,
+        target: String,
+        subject: String,
+        reason: Option<String>,
+    ) -> Self {
+        ProvEvent::Task(TaskScopedEvent {
+            id: next_event_id(),
+            tenant: context::current_tenant(),
+            context_id,
+            task_id,
+            timestamp_ms: now_millis(),
+            data: ProvEventData::PolicyEvaluated { policy_id, effect, target, subject, reason },
+        })
+    }
+
+    pub fn policy_evaluated_global(
+        context_id: ContextId,
+        policy_id: String,
+        effect: String,
+        target: String,
+        subject: String,
+        reason: Option<String>,
+    ) -> Self {
+        ProvEvent::Global(GlobalEvent {
+            id: next_event_id(),
+            tenant: context::current_tenant(),
+            context_id,
+            timestamp_ms: now_millis(),
+            data: ProvEventData::PolicyEvaluated { policy_id, effect, target, subject, reason },
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn approval_decided_task(
+        context_id: ContextId,
+        task_id: TaskId,
+        approval_id: String,
+        tool_name: String,
+        args: Value,
+        verdict: String,
+        approver: Option<String>,
+        reason: Option<String>,
+        waited_ms: u64,
+    ) -> Self {
+        ProvEvent::Task(TaskScopedEvent {
+            id: next_event_id(),
+            tenant: context::current_tenant(),
+            context_id,
+            task_id,
+            timestamp_ms: now_millis(),
+            data: ProvEventData::ApprovalDecided {
+                approval_id,
+                tool_name,
+                args,
+                verdict,
+                approver,
+                reason,
+                waited_ms,
+            },
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn approval_decided_global(
+        context_id: ContextId,
+        approval_id: String,
+        tool_name: String,
+        args: Value,
+        verdict: String,
+        approver: Option<String>,
+        reason: Option<String>,
+        waited_ms: u64,
+    ) -> Self {
+        ProvEvent::Global(GlobalEvent {
+            id: next_event_id(),
+            tenant: context::current_tenant(),
+            context_id,
+            timestamp_ms: now_millis(),
+            data: ProvEventData::ApprovalDecided {
+                approval_id,
+                tool_name,
+                args,
+                verdict,
+                approver,
+                reason,
+                waited_ms,
+            },
+        })
+    }
+
+    pub fn console_message_task(
+        context_id: ContextId,
+        task_id: TaskId,
+        level: String,
+        message: String,
+    ) -> Self {
+        ProvEvent::Task(TaskScopedEvent {
+            id: next_event_id(),
+            tenant: context::current_tenant(),
+            context_id,
+            task_id,
+            timestamp_ms: now_millis(),
+            data: ProvEventData::ConsoleMessage { level, message },
+        })
+    }
+
+    pub fn console_message_global(context_id: ContextId, level: String, message: String) -> Self {
+        ProvEvent::Global(GlobalEvent {
+            id: next_event_id(),
+            tenant: context::current_tenant(),
+            context_id,
+            timestamp_ms: now_millis(),
+            data: ProvEventData::ConsoleMessage { level, message },
+        })
+    }
+
+    pub fn config_changed(
+        context_id: ContextId,
+        version: u64,
+        digest: String,
+        previous_digest: Option<String>,
+        changed: Vec<String>,
+        trigger: String,
+    ) -> Self {
+        ProvEvent::Global(GlobalEvent {
+            id: next_event_id(),
+            tenant: context::current_tenant(),
+            context_id,
+            timestamp_ms: now_millis(),
+            data: ProvEventData::ConfigChanged { version, digest, previous_digest, changed, trigger },
+        })
+    }
+
+    pub fn schema_changed(
+        context_id: ContextId,
+        agent_id: AgentId,
+        digest: String,
+        previous_digest: Option<String>,
+        added: Vec<String>,
+        removed: Vec<String>,
+        changed: Vec<String>,
+    ) -> Self {
+        ProvEvent::Global(GlobalEvent {
+            id: next_event_id(),
+            tenant: context::current_tenant(),
+            context_id,
+            timestamp_ms: now_millis(),
+            data: ProvEventData::SchemaChanged {
+                agent_id,
+                digest,
+                previous_digest,
+                added,
+                removed,
+                changed,
+            },
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn tool_usage_summarized(
+        context_id: ContextId,
+        agent_id: AgentId,
+        tool_name: String,
+        invocations: u64,
+        failures: u64,
+        p50_ms: u64,
+        p95_ms: u64,
+        p99_ms: u64,
+    ) -> Self {
+        ProvEvent::Global(GlobalEvent {
+            id: next_event_id(),
+            tenant: context::current_tenant(),
+            context_id,
+            timestamp_ms: now_millis(),
+            data: ProvEventData::ToolUsageSummarized {
+                agent_id,
+                tool_name,
+                invocations,
+                failures,
+                p50_ms,
+                p95_ms,
+                p99_ms,
+            },
+        })
+    }
 }
@@ -0,0 +1,402 @@
+//! Archival of the FalkorDB provenance graph as PROV-JSON.
+//!
+//! [`FalkorDbArchiver::export`] walks the nodes a [`ProvenanceQuery`] selects (by
+//! context, task, agent or time range) and every edge touching them, and rebuilds
+//! a W3C PROV-JSON document. Nodes outside the scope that those edges reach, such
+//! as agents, are included so the document stands on its own. A2A-derived edges
+//! have no PROV equivalent and are kept next to the document in the
+//! [`ProvArchive`].
+//!
+//! [`FalkorDbArchiver::export_for`] exports on a principal's behalf: only its
+//! contexts, and only edges whose ends are both in them.
+//!
+//! Archives are plain JSON, ready to upload to object storage.
+//! [`FalkorDbArchiver::import_verified`] writes one back and re-exports it to check
+//! that every archived statement is in the graph again, so a graph can be truncated
+//! once its archive is stored.
+
+use crate::cypher::{CypherBuilder, LabelStrategy, SemanticLabels, CLAUSE_SEPARATOR};
+use crate::access::{AccessScope, ScopedReader};
+use crate::document::ProvDocument;
+use crate::error::{ProvenanceError, Result};
+use crate::events::now_millis;
+use crate::falkordb_store::{node_match, FalkorDbProvenanceConfig};
+use crate::normalizer::{A2aDerivedRelation, A2aRelationType, NormalizedProv};
+use crate::store::{ProvNodeKind, ProvenanceQuery};
+use crate::types::{
+    ActedOnBehalfOf, Activity, Agent, Entity, ProvActivityId, ProvAgentId, ProvEntityId,
+    ProvNodeRef, QualifiedGeneration, Used, WasAssociatedWith, WasDerivedFrom, WasGeneratedBy,
+    WasInformedBy,
+};
+use crate::vocabulary::{a2a, prov, prov_relations};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use text_to_cypher::core::execute_cypher_query;
+
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// Clauses per write when importing, so large archives are not sent as one query.
+const IMPORT_BATCH_CLAUSES: usize = 256;
+
+/// An exported slice of the provenance graph.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProvArchive {
+    pub version: u32,
+    pub exported_at_ms: u64,
+    /// W3C PROV-JSON.
+    pub document: Value,
+    #[serde(default)]
+    pub derived_relations: Vec<ArchivedRelation>,
+}
+
+/// An A2A-derived edge, e.g. a task's link to its messages.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchivedRelation {
+    pub relation: String,
+    pub from: ProvNodeRef,
+    pub to: ProvNodeRef,
+    #[serde(default)]
+    pub attributes: HashMap<String, Value>,
+}
+
+impl ProvArchive {
+    pub fn prov_document(&self) -> Result<ProvDocument> {
+        ProvDocument::from_prov_json(&self.document)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        serde_json::to_vec(self).map_err(|err| ProvenanceError::Storage(Box::new(err)))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let archive: ProvArchive =
+            serde_json::from_slice(bytes).map_err(|err| ProvenanceError::Storage(Box::new(err)))?;
+        if archive.version != ARCHIVE_VERSION {
+            return Err(ProvenanceError::InvalidDocument(format!(
+                "unsupported archive version {}",
+                archive.version
+            )));
+        }
+        Ok(archive)
+    }
+
+    /// Every statement as one line: PROV-N for the document, then one line per
+    /// derived relation. Relation ids are left out, so equal content compares equal.
+    pub fn statements(&self) -> Result<BTreeSet<String>> {
+        let mut statements: BTreeSet<String> = self
+            .prov_document()?
+            .to_prov_n()
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && *line != "document" && *line != "endDocument")
+            .map(str::to_string)
+            .collect();
+        for relation in &self.derived_relations {
+            statements.insert(format!("{}({}, {})", relation.relation, relation.from.id(), relation.to.id()));
+        }
+        Ok(statements)
+    }
+}
+
+/// Exports and re-imports slices of a FalkorDB provenance graph.
+#[derive(Clone)]
+pub struct FalkorDbArchiver {
+    config: FalkorDbProvenanceConfig,
+    labels: Arc<dyn LabelStrategy>,
+}
+
+impl FalkorDbArchiver {
+    pub fn new(config: FalkorDbProvenanceConfig) -> Self {
+        Self { config, labels: Arc::new(SemanticLabels) }
+    }
+
+    /// Labels to write imported nodes and edges with; must match the writer's.
+    pub fn with_label_strategy(mut self, labels: Arc<dyn LabelStrategy>) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    pub async fn export(&self, query: &ProvenanceQuery) -> Result<ProvArchive> {
+        self.export_slices(std::slice::from_ref(query), AccessScope::All).await
+    }
+
+    /// Export what `query` selects of the contexts `reader`'s principal may
+    /// read. A query naming another context is refused.
+    pub async fn export_for(
+        &self,
+        reader: &ScopedReader,
+        query: &ProvenanceQuery,
+    ) -> Result<ProvArchive> {
+        let scope = reader.scope();
+        let queries = reader.scoped_queries(query, &scope)?;
+        self.export_slices(&queries, scope).await
+    }
+
+    async fn export_slices(
+        &self,
+        queries: &[ProvenanceQuery],
+        scope: AccessScope,
+    ) -> Result<ProvArchive> {
+        let mut graph = GraphSlice { scope, ..GraphSlice::default() };
+        for query in queries {
+            for row in json_rows(&self.run(&export_node_query(query)).await?) {
+                graph.node(&row);
+            }
+            for row in json_rows(&self.run(&export_edge_query(query)).await?) {
+                graph.edge(&row);
+            }
+        }
+        let (document, derived_relations) = graph.finish();
+        Ok(ProvArchive {
+            version: ARCHIVE_VERSION,
+            exported_at_ms: now_millis(),
+            document: document.to_prov_json(),
+            derived_relations,
+        })
+    }
+
+    /// Write `archive` into the graph. Nodes and edges are merged, so importing into
+    /// a graph that still holds some of them is safe.
+    pub async fn import(&self, archive: &ProvArchive) -> Result<()> {
+        let derived_relations = archive
+            .derived_relations
+            .iter()
+            .map(|relation| {
+                let kind = A2aRelationType::parse(&relation.relation).ok_or_else(|| {
+                    ProvenanceError::InvalidDocument(format!(
+                        "unknown derived relation {}",
+                        relation.relation
+                    ))
+                })?;
+                Ok(A2aDerivedRelation {
+                    relation: kind,
+                    from: relation.from.clone(),
+                    to: relation.to.clone(),
+                    attributes: relation.attributes.clone(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let normalized = NormalizedProv {
+            document: archive.prov_document()?,
+            derived_relations,
+            agent_labels: HashMap::new(),
+            activity_types: HashMap::new(),
+        };
+        let mut builder = CypherBuilder::with_label_strategy(self.labels.clone());
+        builder.normalized(&normalized);
+        for batch in builder.clauses().chunks(IMPORT_BATCH_CLAUSES) {
+            self.run(&batch.join(CLAUSE_SEPARATOR)).await?;
+        }
+        Ok(())
+    }
+
+    /// [`Self::import`], then export `query` again and fail unless every archived
+    /// statement is back in the graph.
+    pub async fn import_verified(&self, archive: &ProvArchive, query: &ProvenanceQuery) -> Result<()> {
+        self.import(archive).await?;
+        let present = self.export(query).await?.statements()?;
+        let missing: Vec<String> = archive.statements()?.difference(&present).cloned().collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        Err(ProvenanceError::InvalidDocument(format!(
+            "{} archived statements missing after import, e.g. {}",
+            missing.len(),
+            missing[0]
+        )))
+    }
+
+    async fn run(&self, query: &str) -> Result<String> {
+        Ok(execute_cypher_query(query, &self.config.graph, &self.config.connection, true).await?)
+    }
+}
+
+fn export_node_query(query: &ProvenanceQuery) -> String {
+    format!("{} RETURN toJSON(properties(n))", node_match(query))
+}
+
+/// Edges with at least one endpoint in scope, each endpoint with all its properties.
+fn export_edge_query(query: &ProvenanceQuery) -> String {
+    format!(
+        "{} MATCH (n)-[r]-() RETURN toJSON({{from: properties(startNode(r)), props: properties(r), \
+         to: properties(endNode(r))}})",
+        node_match(query)
+    )
+}
+
+/// JSON objects in query output, one per row. Rows may come back quoted.
+fn json_rows(raw: &str) -> Vec<Map<String, Value>> {
+    raw.lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let value = match serde_json::from_str::<Value>(line) {
+                Ok(Value::String(inner)) => serde_json::from_str(&inner).ok()?,
+                Ok(value) => value,
+                Err(_) => {
+                    let start = line.find('{')?;
+                    let end = line.rfind('}')?;
+                    serde_json::from_str(line.get(start..=end)?).ok()?
+                }
+            };
+            match value {
+                Value::Object(row) => Some(row),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Nodes and edges read from the graph, turned back into a document.
+struct GraphSlice {
+    document: ProvDocument,
+    derived: Vec<ArchivedRelation>,
+    seen_edges: BTreeSet<String>,
+    /// Edges reaching a node out of scope are left out.
+    scope: AccessScope,
+}
+
+impl Default for GraphSlice {
+    fn default() -> Self {
+        Self {
+            document: ProvDocument::default(),
+            derived: Vec::new(),
+            seen_edges: BTreeSet::new(),
+            scope: AccessScope::All,
+        }
+    }
+}
+
+impl GraphSlice {
+    fn node(&mut self, props: &Map<String, Value>) -> Option<ProvNodeRef> {
+        let id = props.get("name")?.as_str()?.to_string();
+        let kind = ProvNodeKind::from_base_label(props.get(prov::BASE_TYPE)?.as_str()?)?;
+        let prov_type = props.get(prov::TYPE).and_then(Value::as_str).map(str::to_string);
+        let mut attributes: HashMap<String, Value> = props
+            .iter()
+            .filter(|(key, _)| !["name", prov::BASE_TYPE, prov::TYPE].contains(&key.as_str()))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        Some(match kind {
+            ProvNodeKind::Entity => {
+                let id = ProvEntityId::from_stored(id);
+                self.document.insert_entity(id.clone(), Entity { prov_type, attributes });
+                ProvNodeRef::Entity(id)
+            }
+            ProvNodeKind::Activity => {
+                let start_time_ms = attributes.remove(prov::START_TIME).and_then(|time| time.as_u64());
+                let end_time_ms = attributes.remove(prov::END_TIME).and_then(|time| time.as_u64());
+                let id = ProvActivityId::from_stored(id);
+                let activity = Activity { start_time_ms, end_time_ms, prov_type, attributes };
+                self.document.insert_activity(id.clone(), activity);
+                ProvNodeRef::Activity(id)
+            }
+            ProvNodeKind::Agent => {
+                let id = ProvAgentId::from_stored(id);
+                self.document.insert_agent(id.clone(), Agent { prov_type, attributes });
+                ProvNodeRef::Agent(id)
+            }
+        })
+    }
+
+    fn edge(&mut self, row: &Map<String, Value>) {
+        // Edges are matched from both ends when both are in scope.
+        if !self.seen_edges.insert(Value::Object(row.clone()).to_string()) {
+            return;
+        }
+        let endpoint = |key: &str| row.get(key).and_then(Value::as_object);
+        let (Some(from), Some(to), Some(props)) = (endpoint("from"), endpoint("to"), endpoint("props"))
+        else {
+            return;
+        };
+        if ![from, to].iter().all(|node| self.scope.allows_attributes(|key| node.get(key))) {
+            return;
+        }
+        let (Some(from), Some(to)) = (self.node(from), self.node(to)) else {
+            return;
+        };
+        let text = |key: &str| props.get(key).and_then(Value::as_str).map(str::to_string);
+        let time_ms = props.get(prov::TIME).and_then(Value::as_u64);
+        let doc = &mut self.document;
+        match (text(prov::BASE_TYPE).as_deref(), &from, &to) {
+            (Some(prov_relations::USED), ProvNodeRef::Activity(activity), ProvNodeRef::Entity(entity)) => {
+                let id = doc.relation_id("u", activity.as_str(), entity.as_str());
+                let used = Used { activity: activity.clone(), entity: entity.clone(), role: text(prov::ROLE) };
+                doc.insert_used(id, used);
+            }
+            (Some(prov_relations::WAS_GENERATED_BY), entity, ProvNodeRef::Activity(activity)) => {
+                let id = doc.relation_id("g", entity.id(), activity.as_str());
+                let generated = WasGeneratedBy { entity: entity.clone(), activity: activity.clone(), time_ms };
+                doc.insert_was_generated_by(id, generated);
+            }
+            (Some(prov_relations::QUALIFIED_GENERATION), entity, ProvNodeRef::Activity(activity)) => {
+                let id = doc.relation_id("gen", entity.id(), activity.as_str());
+                let generation =
+                    QualifiedGeneration { entity: entity.clone(), activity: activity.clone(), time_ms };
+                doc.insert_qualified_generation(id, generation);
+            }
+            (
+                Some(prov_relations::WAS_ASSOCIATED_WITH),
+                ProvNodeRef::Activity(activity),
+                ProvNodeRef::Agent(agent),
+            ) => {
+                let id = doc.relation_id("assoc", activity.as_str(), agent.as_str());
+                let assoc =
+                    WasAssociatedWith { activity: activity.clone(), agent: agent.clone(), role: text(prov::ROLE) };
+                doc.insert_was_associated_with(id, assoc);
+            }
+            (Some(prov_relations::WAS_DERIVED_FROM), ProvNodeRef::Entity(generated), ProvNodeRef::Entity(used)) => {
+                let id = doc.relation_id("d", generated.as_str(), used.as_str());
+                let derived = WasDerivedFrom {
+                    generated_entity: generated.clone(),
+                    used_entity: used.clone(),
+                    activity: text(prov::ACTIVITY).map(ProvActivityId::from_stored),
+                    prov_type: text(prov::TYPE),
+                };
+                doc.insert_was_derived_from(id, derived);
+            }
+            (
+                Some(prov_relations::ACTED_ON_BEHALF_OF),
+                ProvNodeRef::Agent(delegate),
+                ProvNodeRef::Agent(responsible),
+            ) => {
+                let id = doc.relation_id("del", delegate.as_str(), responsible.as_str());
+                let delegation = ActedOnBehalfOf {
+                    delegate: delegate.clone(),
+                    responsible: responsible.clone(),
+                    activity: text(prov::ACTIVITY).map(ProvActivityId::from_stored),
+                };
+                doc.insert_acted_on_behalf_of(id, delegation);
+            }
+            (
+                Some(prov_relations::WAS_INFORMED_BY),
+                ProvNodeRef::Activity(informed),
+                ProvNodeRef::Activity(informant),
+            ) => {
+                let id = doc.relation_id("inf", informed.as_str(), informant.as_str());
+                let was_informed_by = WasInformedBy { informed: informed.clone(), informant: informant.clone() };
+                doc.insert_was_informed_by(id, was_informed_by);
+            }
+            _ => {
+                let Some(relation) = text(a2a::RELATION) else {
+                    tracing::debug!(edge = ?props, "Skipping unrecognized edge in provenance export");
+                    return;
+                };
+                let attributes = props
+                    .iter()
+                    .filter(|(key, _)| ![a2a::RELATION, a2a::FROM, a2a::TO].contains(&key.as_str()))
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect();
+                self.derived.push(ArchivedRelation { relation, from, to, attributes });
+            }
+        }
+    }
+
+    fn finish(mut self) -> (ProvDocument, Vec<ArchivedRelation>) {
+        self.derived.sort_by(|a, b| {
+            (a.relation.as_str(), a.from.id(), a.to.id()).cmp(&(b.relation.as_str(), b.from.id(), b.to.id()))
+        });
+        (self.document, self.derived)
+    }
+}
@@ -0,0 +1,162 @@
+//! Index management for the FalkorDB provenance graph.
+//!
+//! Every write `MERGE`s nodes by `name`, and graph queries filter by context and
+//! task ids; without indexes both degrade to label scans as the graph grows.
+//! Index creation is idempotent: "already indexed" errors are treated as success.
+
+use crate::error::Result;
+use crate::falkordb_store::FalkorDbProvenanceConfig;
+use crate::vocabulary::{a2a, node_labels};
+use text_to_cypher::core::execute_cypher_query;
+
+/// Merge key written on every node by the FalkorDB writer.
+const NAME_PROPERTY: &str = "name";
+
+/// A single-property range index on a node label.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GraphIndex {
+    pub label: String,
+    pub property: String,
+}
+
+impl GraphIndex {
+    pub fn new(label: impl Into<String>, property: impl Into<String>) -> Self {
+        Self { label: label.into(), property: property.into() }
+    }
+
+    fn create_query(&self) -> String {
+        format!(
+            "CREATE INDEX FOR (n:{}) ON (n.`{}`)",
+            self.label, self.property
+        )
+    }
+}
+
+/// Indexes the writer creates when none are configured explicitly.
+pub fn default_indexes() -> Vec<GraphIndex> {
+    let all_labels = [
+        node_labels::LLM_CALL,
+        node_labels::TOOL_CALL,
+        node_labels::AGENT_BOOT,
+        node_labels::TASK_EXECUTION,
+        node_labels::MESSAGE_PROCESSING,
+        node_labels::LLM_PROMPT,
+        node_labels::TOOL_ARGS,
+        node_labels::AGENT_ARCHIVE,
+        node_labels::AGENT_RUNTIME_INSTANCE,
+        node_labels::TASK,
+        node_labels::TASK_STATE,
+        node_labels::MESSAGE,
+        node_labels::ARTIFACT,
+        node_labels::FEEDBACK,
+        node_labels::CONTEXT,
+        node_labels::BUDGET_EXHAUSTION,
+        node_labels::DIAGNOSTIC,
+        node_labels::RUNTIME_CONFIG,
+        node_labels::BAML_SCHEMA,
+        node_labels::TOOL_USAGE,
+        node_labels::POLICY_DECISION,
+        node_labels::APPROVAL_DECISION,
+    ];
+    let context_scoped = [
+        node_labels::LLM_CALL,
+        node_labels::TOOL_CALL,
+        node_labels::TASK,
+        node_labels::TASK_EXECUTION,
+        node_labels::MESSAGE,
+        node_labels::MESSAGE_PROCESSING,
+        node_labels::CONTEXT,
+    ];
+    let task_scoped = [
+        node_labels::TASK,
+        node_labels::TASK_EXECUTION,
+        node_labels::TASK_STATE,
+    ];
+
+    let mut indexes: Vec<GraphIndex> = all_labels
+        .iter()
+        .map(|label| GraphIndex::new(*label, NAME_PROPERTY))
+        .collect();
+    indexes.extend(context_scoped.iter().map(|label| GraphIndex::new(*label, a2a::CONTEXT_ID)));
+    indexes.extend(task_scoped.iter().map(|label| GraphIndex::new(*label, a2a::TASK_ID)));
+    indexes
+}
+
+/// Create every index in `config.indexes`, skipping ones that already exist.
+pub async fn ensure_indexes(config: &FalkorDbProvenanceConfig) -> Result<()> {
+    for index in &config.indexes {
+        match execute_cypher_query(&index.create_query(), &config.graph, &config.connection, false)
+            .await
+        {
+            Ok(_) => {
+                tracing::debug!(label = %index.label, property = %index.property, "Created provenance index");
+            }
+            Err(err) if is_already_indexed(&err.to_string()) => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(())
+}
+
+/// Report the indexes currently defined on the provenance graph.
+pub async fn list_indexes(config: &FalkorDbProvenanceConfig) -> Result<Vec<GraphIndex>> {
+    let raw = execute_cypher_query(
+        "CALL db.indexes() YIELD label, properties UNWIND properties AS property RETURN label, property",
+        &config.graph,
+        &config.connection,
+        true,
+    )
+    .await?;
+    Ok(parse_index_rows(&raw))
+}
+
+fn is_already_indexed(message: &str) -> bool {
+    let message = message.to_lowercase();
+    message.contains("already indexed") || message.contains("already exists")
+}
+
+/// Parse `label, property` rows from raw query output, one row per line.
+fn parse_index_rows(raw: &str) -> Vec<GraphIndex> {
+    raw.lines()
+        .filter_map(|line| {
+            let mut fields = line
+                .split([',', '\t', '|'])
+                .map(|field| field.trim().trim_matches('"'))
+                .filter(|field| !field.is_empty());
+            let label = fields.next()?;
+            let property = fields.next()?;
+            Some(GraphIndex::new(label, property))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_query_quotes_namespaced_properties() {
+        let index = GraphIndex::new(node_labels::TASK, a2a::TASK_ID);
+        assert_eq!(index.create_query(), "CREATE INDEX FOR (n:A2ATask) ON (n.`a2a:task_id`)");
+    }
+
+    #[test]
+    fn default_indexes_cover_merge_key_for_every_label() {
+        let indexes = default_indexes();
+        assert!(indexes.contains(&GraphIndex::new(node_labels::MESSAGE, NAME_PROPERTY)));
+        assert!(indexes.contains(&GraphIndex::new(node_labels::MESSAGE, a2a::CONTEXT_ID)));
+        assert!(indexes.contains(&GraphIndex::new(node_labels::TASK_STATE, a2a::TASK_ID)));
+    }
+
+    #[test]
+    fn parse_index_rows_skips_blank_lines() {
+        let rows = parse_index_rows("A2ATask, name\n\n\"Message\", a2a:context_id\n");
+        assert_eq!(
+            rows,
+            vec![
+                GraphIndex::new("A2ATask", "name"),
+                GraphIndex::new("Message", "a2a:context_id"),
+            ]
+        );
+    }
+}
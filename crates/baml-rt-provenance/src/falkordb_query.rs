@@ -0,0 +1,374 @@
+//! Typed queries over the FalkorDB provenance graph.
+//!
+//! Each helper builds its Cypher from the vocabulary and parses the rows into
+//! plain Rust types. Nodes are matched on their `prov:type` and
+//! `prov:base_type` properties rather than their labels, so the queries work
+//! with any [`LabelStrategy`](crate::cypher::LabelStrategy) the writer used.
+
+use crate::cypher::{cypher_key, cypher_value};
+use crate::error::Result;
+use crate::falkordb_store::{node_query, parse_node_row, FalkorDbProvenanceConfig};
+use crate::store::{sort_records, ProvNodeRecord, ProvenanceQuery};
+use crate::tool_usage::ToolUsageSummary;
+use crate::vocabulary::{a2a, a2a_types, base_types, prov};
+use baml_rt_core::ids::{AgentId, TaskId};
+use serde_json::Value;
+use text_to_cypher::core::execute_cypher_query;
+
+/// Everything recorded for one task: its nodes in time order and the
+/// relationships touching them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskLineage {
+    pub task_id: TaskId,
+    pub nodes: Vec<ProvNodeRecord>,
+    pub edges: Vec<LineageEdge>,
+}
+
+/// A relationship between two nodes, by node id and relationship type.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LineageEdge {
+    pub from: String,
+    pub relation: String,
+    pub to: String,
+}
+
+/// One completed LLM call. Fields the call did not report are `None`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LlmCallSummary {
+    pub id: String,
+    pub client: Option<String>,
+    pub model: Option<String>,
+    pub function_name: Option<String>,
+    pub prompt_tokens: Option<u64>,
+    pub completion_tokens: Option<u64>,
+    pub total_tokens: Option<u64>,
+    pub duration_ms: Option<u64>,
+    pub success: Option<bool>,
+    pub time_ms: Option<u64>,
+}
+
+/// Totals over the tasks attributed to one agent.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AgentActivitySummary {
+    pub tasks: usize,
+    pub llm_calls: usize,
+    pub tool_calls: usize,
+    pub total_tokens: u64,
+    pub first_activity_ms: Option<u64>,
+    pub last_activity_ms: Option<u64>,
+}
+
+/// Read-side helpers for a graph written by
+/// [`FalkorDbProvenanceWriter`](crate::FalkorDbProvenanceWriter).
+#[derive(Debug, Clone)]
+pub struct FalkorDbProvenanceQueries {
+    config: FalkorDbProvenanceConfig,
+}
+
+impl FalkorDbProvenanceQueries {
+    pub fn new(config: FalkorDbProvenanceConfig) -> Self {
+        Self { config }
+    }
+
+    pub async fn task_lineage(&self, task_id: &TaskId) -> Result<TaskLineage> {
+        let node_rows = self
+            .run(&node_query(&ProvenanceQuery::default().for_task(task_id.clone())))
+            .await?;
+        let mut nodes: Vec<ProvNodeRecord> = node_rows.lines().filter_map(parse_node_row).collect();
+        sort_records(&mut nodes);
+
+        let edge_rows = self.run(&edge_query(task_id)).await?;
+        let mut edges: Vec<LineageEdge> = edge_rows.lines().filter_map(parse_edge_row).collect();
+        edges.sort();
+        edges.dedup();
+        Ok(TaskLineage { task_id: task_id.clone(), nodes, edges })
+    }
+
+    /// LLM calls made on behalf of `task_id`, oldest first.
+    pub async fn llm_calls_for_task(&self, task_id: &TaskId) -> Result<Vec<LlmCallSummary>> {
+        let raw = self.run(&llm_call_query(task_id)).await?;
+        let mut calls: Vec<LlmCallSummary> = raw.lines().filter_map(parse_llm_call_row).collect();
+        calls.sort_by(|a, b| {
+            let time = |call: &LlmCallSummary| call.time_ms.unwrap_or(u64::MAX);
+            time(a).cmp(&time(b)).then_with(|| a.id.cmp(&b.id))
+        });
+        Ok(calls)
+    }
+
+    /// Tasks are attributed by their `a2a:agent_id`; calls by the task they ran under.
+    pub async fn agent_activity_summary(&self, agent_id: &AgentId) -> Result<AgentActivitySummary> {
+        let raw = self.run(&agent_task_query(agent_id)).await?;
+        let task_ids: Vec<String> = raw.lines().filter_map(parse_task_row).collect();
+        if task_ids.is_empty() {
+            return Ok(AgentActivitySummary::default());
+        }
+        let raw = self.run(&task_activity_query(&task_ids)).await?;
+        let mut summary = AgentActivitySummary { tasks: task_ids.len(), ..Default::default() };
+        for (prov_type, tokens, time_ms) in raw.lines().filter_map(parse_activity_row) {
+            match prov_type.as_str() {
+                a2a_types::LLM_CALL => summary.llm_calls += 1,
+                a2a_types::TOOL_CALL => summary.tool_calls += 1,
+                _ => {}
+            }
+            summary.total_tokens += tokens.unwrap_or(0);
+            if let Some(time_ms) = time_ms {
+                summary.first_activity_ms =
+                    Some(summary.first_activity_ms.map_or(time_ms, |first| first.min(time_ms)));
+                summary.last_activity_ms =
+                    Some(summary.last_activity_ms.map_or(time_ms, |last| last.max(time_ms)));
+            }
+        }
+        Ok(summary)
+    }
+
+    /// The latest usage summary of every tool `agent_id` has called, most
+    /// used first, read from the `ToolUsage` nodes rather than the calls.
+    pub async fn tool_usage(&self, agent_id: &AgentId) -> Result<Vec<ToolUsageSummary>> {
+        let raw = self.run(&tool_usage_query(agent_id)).await?;
+        let mut usage: Vec<ToolUsageSummary> =
+            raw.lines().filter_map(|line| parse_tool_usage_row(line, agent_id)).collect();
+        usage.sort_by(|a, b| b.invocations.cmp(&a.invocations).then_with(|| a.tool_name.cmp(&b.tool_name)));
+        Ok(usage)
+    }
+
+    async fn run(&self, query: &str) -> Result<String> {
+        Ok(execute_cypher_query(query, &self.config.graph, &self.config.connection, true).await?)
+    }
+}
+
+fn property(alias: &str, key: &str) -> String {
+    format!("{alias}.{}", cypher_key(key))
+}
+
+fn string_literal(value: &str) -> String {
+    cypher_value(&Value::String(value.to_string()))
+}
+
+/// `coalesce` of the times a node may carry, matching the node reader.
+fn node_time(alias: &str) -> String {
+    format!(
+        "coalesce({}, {}, {})",
+        property(alias, prov::START_TIME),
+        property(alias, prov::END_TIME),
+        property(alias, a2a::TASK_STATE_TIME)
+    )
+}
+
+fn edge_query(task_id: &TaskId) -> String {
+    let task = string_literal(task_id.as_str());
+    format!(
+        "MATCH (a)-[r]->(b) WHERE {from} = {task} OR {to} = {task} RETURN a.name, type(r), b.name",
+        from = property("a", a2a::TASK_ID),
+        to = property("b", a2a::TASK_ID),
+    )
+}
+
+const LLM_CALL_COLUMNS: [&str; 8] = [
+    a2a::CLIENT,
+    a2a::MODEL,
+    a2a::FUNCTION_NAME,
+    a2a::USAGE_PROMPT_TOKENS,
+    a2a::USAGE_COMPLETION_TOKENS,
+    a2a::USAGE_TOTAL_TOKENS,
+    a2a::DURATION_MS,
+    a2a::SUCCESS,
+];
+
+fn llm_call_query(task_id: &TaskId) -> String {
+    let columns: Vec<String> = LLM_CALL_COLUMNS.iter().map(|key| property("a", key)).collect();
+    format!(
+        "MATCH (a) WHERE {prov_type} = {llm_call} AND {task} = {task_id} \
+         RETURN a.name, {prov_type}, {columns}, {time}",
+        prov_type = property("a", prov::TYPE),
+        llm_call = string_literal(a2a_types::LLM_CALL),
+        task = property("a", a2a::TASK_ID),
+        task_id = string_literal(task_id.as_str()),
+        columns = columns.join(", "),
+        time = node_time("a"),
+    )
+}
+
+fn agent_task_query(agent_id: &AgentId) -> String {
+    format!(
+        "MATCH (t) WHERE {prov_type} = {task_type} AND {agent} = {agent_id} RETURN {prov_type}, {task}",
+        prov_type = property("t", prov::TYPE),
+        task_type = string_literal(a2a_types::TASK),
+        agent = property("t", a2a::AGENT_ID),
+        agent_id = string_literal(agent_id.as_str()),
+        task = property("t", a2a::TASK_ID),
+    )
+}
+
+fn task_activity_query(task_ids: &[String]) -> String {
+    let ids = Value::Array(task_ids.iter().cloned().map(Value::String).collect());
+    format!(
+        "MATCH (a) WHERE {base_type} = {activity} AND {task} IN {ids} \
+         RETURN {base_type}, {prov_type}, {tokens}, {time}",
+        base_type = property("a", prov::BASE_TYPE),
+        activity = string_literal(base_types::ACTIVITY),
+        task = property("a", a2a::TASK_ID),
+        ids = cypher_value(&ids),
+        prov_type = property("a", prov::TYPE),
+        tokens = property("a", a2a::USAGE_TOTAL_TOKENS),
+        time = node_time("a"),
+    )
+}
+
+const TOOL_USAGE_COLUMNS: [&str; 6] = [
+    a2a::TOOL_NAME,
+    a2a::TOOL_INVOCATIONS,
+    a2a::TOOL_FAILURES,
+    a2a::TOOL_LATENCY_P50_MS,
+    a2a::TOOL_LATENCY_P95_MS,
+    a2a::TOOL_LATENCY_P99_MS,
+];
+
+fn tool_usage_query(agent_id: &AgentId) -> String {
+    let columns: Vec<String> = TOOL_USAGE_COLUMNS.iter().map(|key| property("u", key)).collect();
+    format!(
+        "MATCH (u) WHERE {prov_type} = {tool_usage} AND {agent} = {agent_id} \
+         RETURN {prov_type}, {columns}",
+        prov_type = property("u", prov::TYPE),
+        tool_usage = string_literal(a2a_types::TOOL_USAGE),
+        agent = property("u", a2a::AGENT_ID),
+        agent_id = string_literal(agent_id.as_str()),
+        columns = columns.join(", "),
+    )
+}
+
+/// Split a raw result row into trimmed fields, with `null` and blanks as `None`.
+pub(crate) fn row_fields(line: &str) -> Vec<Option<&str>> {
+    line.split([',', '\t', '|'])
+        .map(|field| field.trim().trim_matches('"'))
+        .map(|field| (!field.is_empty() && field != "null").then_some(field))
+        .collect()
+}
+
+pub(crate) fn field<'a>(fields: &[Option<&'a str>], index: usize) -> Option<&'a str> {
+    fields.get(index).copied().flatten()
+}
+
+fn number(fields: &[Option<&str>], index: usize) -> Option<u64> {
+    field(fields, index).and_then(|value| value.parse::<f64>().ok()).map(|value| value as u64)
+}
+
+fn parse_edge_row(line: &str) -> Option<LineageEdge> {
+    let fields = row_fields(line);
+    // Header rows echo the returned expressions.
+    let relation = field(&fields, 1).filter(|relation| !relation.starts_with("type("))?;
+    Some(LineageEdge {
+        from: field(&fields, 0)?.to_string(),
+        relation: relation.to_string(),
+        to: field(&fields, 2)?.to_string(),
+    })
+}
+
+fn parse_llm_call_row(line: &str) -> Option<LlmCallSummary> {
+    let fields = row_fields(line);
+    if field(&fields, 1) != Some(a2a_types::LLM_CALL) {
+        return None;
+    }
+    let text = |index: usize| field(&fields, index).map(str::to_string);
+    Some(LlmCallSummary {
+        id: field(&fields, 0)?.to_string(),
+        client: text(2),
+        model: text(3),
+        function_name: text(4),
+        prompt_tokens: number(&fields, 5),
+        completion_tokens: number(&fields, 6),
+        total_tokens: number(&fields, 7),
+        duration_ms: number(&fields, 8),
+        success: field(&fields, 9).and_then(|value| value.parse().ok()),
+        time_ms: number(&fields, 10),
+    })
+}
+
+fn parse_task_row(line: &str) -> Option<String> {
+    let fields = row_fields(line);
+    if field(&fields, 0) != Some(a2a_types::TASK) {
+        return None;
+    }
+    field(&fields, 1).map(str::to_string)
+}
+
+fn parse_tool_usage_row(line: &str, agent_id: &AgentId) -> Option<ToolUsageSummary> {
+    let fields = row_fields(line);
+    if field(&fields, 0) != Some(a2a_types::TOOL_USAGE) {
+        return None;
+    }
+    Some(ToolUsageSummary {
+        agent_id: agent_id.clone(),
+        tool_name: field(&fields, 1)?.to_string(),
+        invocations: number(&fields, 2).unwrap_or(0),
+        failures: number(&fields, 3).unwrap_or(0),
+        p50_ms: number(&fields, 4).unwrap_or(0),
+        p95_ms: number(&fields, 5).unwrap_or(0),
+        p99_ms: number(&fields, 6).unwrap_or(0),
+    })
+}
+
+fn parse_activity_row(line: &str) -> Option<(String, Option<u64>, Option<u64>)> {
+    let fields = row_fields(line);
+    if field(&fields, 0) != Some(base_types::ACTIVITY) {
+        return None;
+    }
+    Some((field(&fields, 1)?.to_string(), number(&fields, 2), number(&fields, 3)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use baml_rt_core::ids::ExternalId;
+
+    fn task_id() -> TaskId {
+        TaskId::from_external(ExternalId::new("task-1"))
+    }
+
+    #[test]
+    fn queries_match_on_vocabulary_properties() {
+        assert_eq!(
+            llm_call_query(&task_id()),
+            "MATCH (a) WHERE a.`prov:type` = \"a2a:LlmCall\" AND a.`a2a:task_id` = \"task-1\" \
+             RETURN a.name, a.`prov:type`, a.`a2a:client`, a.`a2a:model`, a.`a2a:function_name`, \
+             a.`a2a:usage_prompt_tokens`, a.`a2a:usage_completion_tokens`, a.`a2a:usage_total_tokens`, \
+             a.`a2a:duration_ms`, a.`a2a:success`, \
+             coalesce(a.`prov:startTime`, a.`prov:endTime`, a.`a2a:task_state_time`)"
+        );
+        assert!(task_activity_query(&["task-1".to_string()]).contains(r#"a.`a2a:task_id` IN ["task-1"]"#));
+    }
+
+    #[test]
+    fn rows_parse_into_typed_records_and_skip_headers() {
+        let raw = "a.name, a.`prov:type`, a.`a2a:client`\n\
+                   llm_call:prov-7, a2a:LlmCall, openai, gpt-4o, Greet, 12, 30, 42, 850, true, 1700\n";
+        let calls: Vec<_> = raw.lines().filter_map(parse_llm_call_row).collect();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].id, "llm_call:prov-7");
+        assert_eq!(calls[0].model.as_deref(), Some("gpt-4o"));
+        assert_eq!(calls[0].total_tokens, Some(42));
+        assert_eq!(calls[0].success, Some(true));
+        assert_eq!(calls[0].time_ms, Some(1700));
+
+        assert_eq!(parse_edge_row("a.name, type(r), b.name"), None);
+        assert_eq!(
+            parse_edge_row("llm_call:prov-7 | WAS_CALLED_BY | task_exec:task-1"),
+            Some(LineageEdge {
+                from: "llm_call:prov-7".to_string(),
+                relation: "WAS_CALLED_BY".to_string(),
+                to: "task_exec:task-1".to_string(),
+            })
+        );
+        let agent_id = AgentId::from_uuid(
+            baml_rt_core::ids::UuidId::parse_str("00000000-0000-0000-0000-000000000001").unwrap(),
+        );
+        let usage = parse_tool_usage_row("a2a:ToolUsage | web/search | 40 | 2 | 120 | 480 | 900", &agent_id)
+            .expect("usage row");
+        assert_eq!((usage.tool_name.as_str(), usage.invocations, usage.failures), ("web/search", 40, 2));
+        assert_eq!(usage.p95_ms, 480);
+        assert_eq!(parse_tool_usage_row("u.`prov:type`, u.`a2a:tool_name`", &agent_id), None);
+        assert_eq!(
+            parse_activity_row("ProvActivity, a2a:ToolCall, null, 1800"),
+            Some(("a2a:ToolCall".to_string(), None, Some(1800)))
+        );
+    }
+}
@@ -0,0 +1,318 @@
+//! Retention for the FalkorDB provenance graph.
+//!
+//! Nothing the writer merges is ever removed, so a long-running deployment
+//! grows its graph without bound. [`FalkorDbProvenanceWriter::prune`] deletes
+//! the nodes recorded before a cutoff; [`PruneRequest`] narrows that to the
+//! tasks that reached a terminal state before it, archives what is about to go
+//! with [`FalkorDbArchiver`], or only counts it (dry run).
+//!
+//! A [`RetentionPolicy`] set with
+//! [`FalkorDbProvenanceConfig::with_retention`] runs the same prune in the
+//! background every `interval`, with the cutoff `max_age` before now.
+//!
+//! Nodes are selected by time the way reads select them: the first of
+//! `prov:startTime`, `prov:endTime` and `a2a:task_state_time` they carry.
+//! Untimed nodes are never selected. Entities whose every relationship led to a
+//! pruned node are swept afterwards; entities that never had one, such as tool
+//! usage summaries, are kept, and so are agents.
+//!
+//! [`FalkorDbProvenanceWriter::prune`]: crate::FalkorDbProvenanceWriter::prune
+
+use crate::cypher::{cypher_key, cypher_value};
+use crate::error::{ProvenanceError, Result};
+use crate::falkordb_archive::{FalkorDbArchiver, ProvArchive};
+use crate::falkordb_query::{field, row_fields};
+use crate::falkordb_store::{node_match, FalkorDbProvenanceConfig};
+use crate::normalizer_state::is_terminal;
+use crate::store::ProvenanceQuery;
+use crate::vocabulary::{a2a, base_types, prov};
+use baml_rt_core::ids::{ExternalId, TaskId};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use text_to_cypher::core::execute_cypher_query;
+
+const DEFAULT_RETENTION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Which nodes recorded before the cutoff a prune may remove.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RetentionScope {
+    /// Every timed node.
+    #[default]
+    AllNodes,
+    /// Every node of each task whose last state is terminal and was reached
+    /// before the cutoff; running tasks are kept however old.
+    TerminalTasks,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PruneRequest {
+    /// Epoch milliseconds; only what was recorded strictly before is pruned.
+    pub before_ms: u64,
+    pub scope: RetentionScope,
+    /// Count what would be pruned without deleting it.
+    pub dry_run: bool,
+    /// Export what is about to be pruned first, into [`PruneReport::archives`].
+    pub archive: bool,
+}
+
+impl PruneRequest {
+    pub fn before(before_ms: u64) -> Self {
+        Self { before_ms, scope: RetentionScope::default(), dry_run: false, archive: false }
+    }
+
+    pub fn with_scope(mut self, scope: RetentionScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    pub fn with_archive(mut self, archive: bool) -> Self {
+        self.archive = archive;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PruneReport {
+    pub before_ms: u64,
+    pub dry_run: bool,
+    /// Nodes deleted, or that would be in a dry run.
+    pub nodes: usize,
+    /// Tasks pruned under [`RetentionScope::TerminalTasks`].
+    pub tasks: Vec<TaskId>,
+    /// Entities deleted because the prune left them without relationships, or
+    /// that would be in a dry run.
+    pub orphaned_entities: usize,
+    /// One archive for [`RetentionScope::AllNodes`], one per task for
+    /// [`RetentionScope::TerminalTasks`].
+    pub archives: Vec<ProvArchive>,
+}
+
+/// Background pruning for a [`FalkorDbProvenanceWriter`](crate::FalkorDbProvenanceWriter).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Prune what was recorded longer ago than this.
+    pub max_age: Duration,
+    /// How often to prune.
+    pub interval: Duration,
+    pub scope: RetentionScope,
+    /// Log what would be pruned instead of pruning it.
+    pub dry_run: bool,
+    /// Write an archive of everything pruned here first, as
+    /// `provenance-<cutoff>-<n>.json`.
+    pub archive_dir: Option<PathBuf>,
+}
+
+impl RetentionPolicy {
+    pub fn new(max_age: Duration) -> Self {
+        Self {
+            max_age,
+            interval: DEFAULT_RETENTION_INTERVAL,
+            scope: RetentionScope::TerminalTasks,
+            dry_run: false,
+            archive_dir: None,
+        }
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn with_scope(mut self, scope: RetentionScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    pub fn with_archive_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.archive_dir = Some(dir.into());
+        self
+    }
+
+    /// The prune one run of this policy performs at `now_ms`.
+    pub fn request(&self, now_ms: u64) -> PruneRequest {
+        PruneRequest::before(now_ms.saturating_sub(self.max_age.as_millis() as u64))
+            .with_scope(self.scope)
+            .with_dry_run(self.dry_run)
+            .with_archive(self.archive_dir.is_some())
+    }
+}
+
+pub(crate) async fn prune(config: &FalkorDbProvenanceConfig, request: &PruneRequest) -> Result<PruneReport> {
+    let mut report =
+        PruneReport { before_ms: request.before_ms, dry_run: request.dry_run, ..PruneReport::default() };
+    let Some(until_ms) = request.before_ms.checked_sub(1) else {
+        return Ok(report);
+    };
+    let (selection, exports) = match request.scope {
+        RetentionScope::AllNodes => {
+            let query = ProvenanceQuery::default().between(None, Some(until_ms));
+            (node_match(&query), vec![query])
+        }
+        RetentionScope::TerminalTasks => {
+            report.tasks = terminal_tasks(config, request.before_ms).await?;
+            if report.tasks.is_empty() {
+                return Ok(report);
+            }
+            let exports = report
+                .tasks
+                .iter()
+                .map(|task_id| ProvenanceQuery::default().for_task(task_id.clone()))
+                .collect();
+            (task_node_match(&report.tasks), exports)
+        }
+    };
+
+    report.nodes = count(&run(config, &format!("{selection} RETURN count(n)")).await?);
+    if request.archive && report.nodes > 0 {
+        let archiver = FalkorDbArchiver::new(config.clone());
+        for query in &exports {
+            report.archives.push(archiver.export(query).await?);
+        }
+    }
+    if report.nodes == 0 {
+        return Ok(report);
+    }
+    let orphans = node_ids(&run(config, &orphaned_entity_query(&selection)).await?);
+    if request.dry_run {
+        report.orphaned_entities = orphans.len();
+        return Ok(report);
+    }
+    run(config, &format!("{selection} DETACH DELETE n")).await?;
+    if !orphans.is_empty() {
+        // Only those still unlinked: something may have been merged meanwhile.
+        let sweep = orphan_sweep_match(&orphans);
+        report.orphaned_entities = count(&run(config, &format!("{sweep} RETURN count(n)")).await?);
+        if report.orphaned_entities > 0 {
+            run(config, &format!("{sweep} DELETE n")).await?;
+        }
+    }
+    Ok(report)
+}
+
+/// Run `policy` once at `now_ms`, writing its archives before anything is deleted.
+pub(crate) async fn apply_policy(
+    config: &FalkorDbProvenanceConfig,
+    policy: &RetentionPolicy,
+    now_ms: u64,
+) -> Result<PruneReport> {
+    let request = policy.request(now_ms);
+    if let Some(dir) = &policy.archive_dir {
+        // Archive in a dry run first, so a failed write stops the prune.
+        let mut preview = prune(config, &request.clone().with_dry_run(true)).await?;
+        let archives = std::mem::take(&mut preview.archives);
+        write_archives(dir, request.before_ms, archives).await?;
+        if policy.dry_run {
+            return Ok(preview);
+        }
+        return prune(config, &request.with_archive(false)).await;
+    }
+    prune(config, &request).await
+}
+
+async fn write_archives(dir: &std::path::Path, before_ms: u64, archives: Vec<ProvArchive>) -> Result<()> {
+    let io_error = |err: std::io::Error| ProvenanceError::Storage(Box::new(err));
+    tokio::fs::create_dir_all(dir).await.map_err(io_error)?;
+    for (index, archive) in archives.iter().enumerate() {
+        let path = dir.join(format!("provenance-{before_ms}-{index}.json"));
+        tokio::fs::write(&path, archive.to_bytes()?).await.map_err(io_error)?;
+    }
+    Ok(())
+}
+
+/// Tasks whose latest state is terminal and older than `before_ms`.
+async fn terminal_tasks(config: &FalkorDbProvenanceConfig, before_ms: u64) -> Result<Vec<TaskId>> {
+    let raw = run(config, &task_state_query()).await?;
+    let mut latest: HashMap<String, (u64, String)> = HashMap::new();
+    for line in raw.lines() {
+        let fields = row_fields(line);
+        let (Some(task_id), Some(state), Some(time_ms)) = (
+            field(&fields, 0),
+            field(&fields, 1),
+            field(&fields, 2).and_then(|value| value.parse::<f64>().ok()).map(|value| value as u64),
+        ) else {
+            continue;
+        };
+        let entry = latest.entry(task_id.to_string()).or_insert((time_ms, state.to_string()));
+        if time_ms > entry.0 {
+            *entry = (time_ms, state.to_string());
+        }
+    }
+    let mut tasks: Vec<TaskId> = latest
+        .into_iter()
+        .filter(|(_, (time_ms, state))| *time_ms < before_ms && is_terminal(state))
+        .map(|(task_id, _)| TaskId::from_external(ExternalId::new(task_id)))
+        .collect();
+    tasks.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+    Ok(tasks)
+}
+
+fn task_state_query() -> String {
+    format!(
+        "MATCH (s) WHERE s.{state} IS NOT NULL AND s.{task} IS NOT NULL RETURN s.{task}, s.{state}, s.{time}",
+        state = cypher_key(a2a::TASK_STATE),
+        task = cypher_key(a2a::TASK_ID),
+        time = cypher_key(a2a::TASK_STATE_TIME),
+    )
+}
+
+fn task_node_match(tasks: &[TaskId]) -> String {
+    let ids = Value::Array(tasks.iter().map(|task_id| Value::String(task_id.as_str().to_string())).collect());
+    format!("MATCH (n) WHERE n.{} IN {}", cypher_key(a2a::TASK_ID), cypher_value(&ids))
+}
+
+/// Ids of the entities outside `selection` whose every neighbour is in it, i.e.
+/// the entities deleting `selection` leaves without relationships.
+fn orphaned_entity_query(selection: &str) -> String {
+    format!(
+        "{selection} WITH collect(id(n)) AS pruned \
+         UNWIND pruned AS pruned_id MATCH (n)--(m) WHERE id(n) = pruned_id \
+         AND m.{base_type} = {entity} AND NOT id(m) IN pruned \
+         WITH DISTINCT m, pruned MATCH (m)--(linked) \
+         WITH m, pruned, collect(id(linked)) AS links \
+         WHERE all(link IN links WHERE link IN pruned) RETURN id(m)",
+        base_type = cypher_key(prov::BASE_TYPE),
+        entity = cypher_value(&Value::String(base_types::ENTITY.to_string())),
+    )
+}
+
+fn orphan_sweep_match(ids: &[u64]) -> String {
+    let ids = Value::Array(ids.iter().map(|id| Value::from(*id)).collect());
+    format!(
+        "MATCH (n) WHERE id(n) IN {} AND n.{} = {} AND NOT (n)--()",
+        cypher_value(&ids),
+        cypher_key(prov::BASE_TYPE),
+        cypher_value(&Value::String(base_types::ENTITY.to_string()))
+    )
+}
+
+/// The node ids in a `RETURN id(m)` result, skipping the header row.
+fn node_ids(raw: &str) -> Vec<u64> {
+    raw.lines()
+        .filter_map(|line| field(&row_fields(line), 0)?.parse::<f64>().ok())
+        .map(|value| value as u64)
+        .collect()
+}
+
+/// The number in a `RETURN count(n)` result, skipping the header row.
+fn count(raw: &str) -> usize {
+    raw.lines()
+        .filter_map(|line| field(&row_fields(line), 0)?.parse::<f64>().ok())
+        .last()
+        .map_or(0, |value| value as usize)
+}
+
+async fn run(config: &FalkorDbProvenanceConfig, query: &str) -> Result<String> {
+    Ok(execute_cypher_query(query, &config.graph, &config.connection, true).await?)
+}
@@ -6,29 +6,45 @@
 //! Key design points:
 //! - We use `MERGE` for idempotent upserts by `name`.
 //! - Each event is written as a single Cypher query (multiple clauses joined
-//!   with `WITH 1 AS _`) to reduce round-trips.
-//! - `WITH 1 AS _` resets the variable scope between clauses so we can reuse
-//!   short variable names like `n`, `a`, `b`, and `r`.
-use crate::error::Result;
-use crate::normalizer::{
-    validate_event, A2aDerivedRelation, DefaultProvNormalizer, NormalizedProv, ProvNormalizer,
-};
-use crate::store::ProvenanceWriter;
-use crate::types::{
-    Activity, Agent, Entity, ProvActivityId, ProvAgentId, ProvEntityId, QualifiedGeneration, Used,
-    WasAssociatedWith, WasDerivedFrom, WasGeneratedBy,
+//!   with `WITH 1 AS _`) to reduce round-trips. In buffered mode the clauses of
+//!   several events are coalesced into one query, flushed by a background task
+//!   every `flush_interval` or as soon as `max_batch_size` events are pending.
+//! - `add_events` normalizes its events into one document
+//!   ([`ProvNormalizer::normalize_batch`]), so nodes they share are written once.
+//! - Query text comes from [`CypherBuilder`]; labels follow the writer's
+//!   [`LabelStrategy`].
+//! - Clauses identical to the last one this writer wrote for the same node or
+//!   relationship are left out (see [`crate::falkordb_write_cache`]), so nodes
+//!   every event re-ensures are written once rather than per event.
+//! - Old nodes are removed by [`FalkorDbProvenanceWriter::prune`], or in the
+//!   background under a [`RetentionPolicy`] (see [`crate::falkordb_retention`]).
+use crate::cypher::{
+    cypher_key, cypher_value, CypherBuilder, LabelStrategy, SemanticLabels, CLAUSE_SEPARATOR,
 };
-use crate::vocabulary::{
-    a2a, a2a_relation_types, a2a_roles, message_directions, prov, prov_relations, prov_roles,
-    semantic_labels,
+use crate::error::Result;
+use crate::events::now_millis;
+use crate::falkordb_indexes::{default_indexes, ensure_indexes, list_indexes, GraphIndex};
+use crate::falkordb_query::FalkorDbProvenanceQueries;
+use crate::falkordb_retention::{apply_policy, prune, PruneReport, PruneRequest, RetentionPolicy};
+use crate::falkordb_write_cache::{ClauseId, WriteCache};
+use crate::normalizer::{validate_event, DefaultProvNormalizer, NormalizedProv, ProvNormalizer};
+use crate::redaction::RedactionPolicy;
+use crate::schema::validate_document;
+use crate::store::{
+    sort_records, ProvNodeKind, ProvNodeRecord, ProvenanceQuery, ProvenanceReader, ProvenanceWriter,
 };
+use crate::vocabulary::{a2a, prov};
 use async_trait::async_trait;
 use serde_json::Value;
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
 use text_to_cypher::core::execute_cypher_query;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
 
-const CLAUSE_SEPARATOR: &str = "\nWITH 1 AS _\n";
+const DEFAULT_SLOW_QUERY_LOG_CHARS: usize = 2048;
+const DEFAULT_MAX_BATCH_SIZE: usize = 64;
+const DEFAULT_WRITE_CACHE_CAPACITY: usize = 4096;
 
 #[derive(Debug, Clone)]
 pub struct FalkorDbProvenanceConfig {
@@ -36,628 +52,543 @@ pub struct FalkorDbProvenanceConfig {
     pub connection: String,
     /// Graph name to store provenance in.
     pub graph: String,
+    /// Reject events whose normalized attributes violate the vocabulary schemas.
+    /// Debug builds always validate. Attributes are checked before redaction.
+    pub strict_attributes: bool,
+    /// Indexes created by [`FalkorDbProvenanceWriter::ensure_indexes`]. Empty disables index management.
+    pub indexes: Vec<GraphIndex>,
+    /// Log queries slower than this at warn level, with their text truncated to
+    /// `slow_query_log_chars`. `None` disables slow-query logging.
+    pub slow_query_threshold: Option<Duration>,
+    pub slow_query_log_chars: usize,
+    /// Buffer events and write them in batches at this interval. `None` writes
+    /// each event as it arrives.
+    pub flush_interval: Option<Duration>,
+    /// Pending events that trigger an immediate flush in buffered mode.
+    pub max_batch_size: usize,
+    /// Applied by the default normalizer before anything is written.
+    pub redaction: Option<Arc<RedactionPolicy>>,
+    /// Nodes and relationships whose last written clause is remembered, so an
+    /// unchanged clause is not sent again. Zero writes every clause.
+    pub write_cache_capacity: usize,
+    /// Prune the graph in the background. `None` keeps everything.
+    pub retention: Option<RetentionPolicy>,
 }
 
 impl FalkorDbProvenanceConfig {
     pub fn new(connection: impl Into<String>, graph: impl Into<String>) -> Self {
-        Self { connection: connection.into(), graph: graph.into() }
+        Self {
+            connection: connection.into(),
+            graph: graph.into(),
+            strict_attributes: false,
+            indexes: default_indexes(),
+            slow_query_threshold: None,
+            slow_query_log_chars: DEFAULT_SLOW_QUERY_LOG_CHARS,
+            flush_interval: None,
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
+            redaction: None,
+            write_cache_capacity: DEFAULT_WRITE_CACHE_CAPACITY,
+            retention: None,
+        }
     }
-}
-
-#[derive(Clone)]
-pub struct FalkorDbProvenanceWriter {
-    config: FalkorDbProvenanceConfig,
-    normalizer: Arc<dyn ProvNormalizer>,
-}
 
-impl FalkorDbProvenanceWriter {
-    pub fn new(config: FalkorDbProvenanceConfig) -> Self {
-        Self { config, normalizer: Arc::new(DefaultProvNormalizer::default()) }
+    pub fn with_strict_attributes(mut self, strict: bool) -> Self {
+        self.strict_attributes = strict;
+        self
     }
 
-    pub fn with_normalizer(
-        config: FalkorDbProvenanceConfig,
-        normalizer: Arc<dyn ProvNormalizer>,
-    ) -> Self {
-        Self { config, normalizer }
+    fn validates(&self) -> bool {
+        self.strict_attributes || cfg!(debug_assertions)
     }
 
-    /// Build a single Cypher query by joining multiple MERGE clauses.
-    ///
-    /// The `WITH 1 AS _` separator ensures each clause is a new scope so
-    /// variable names can be reused without collisions.
-    fn build_query(normalized: &NormalizedProv) -> String {
-        let mut clauses = Vec::new();
-
-        let mut entity_entries: Vec<(&ProvEntityId, &Entity)> =
-            normalized.document.entities().collect();
-        entity_entries.sort_by(|(a, _), (b, _)| a.cmp(b));
-        let mut entity_labels = HashMap::new();
-        for (id, entity) in &entity_entries {
-            let label = label_from_prov_type(entity.prov_type.as_deref(), "ProvEntity");
-            entity_labels.insert(id.as_str().to_string(), label);
-        }
-        for (id, entity) in entity_entries {
-            let label = entity_labels
-                .get(id.as_str())
-                .map(|value| value.as_str())
-                .unwrap_or("ProvEntity");
-            let props = entity_props(id, entity);
-            clauses.push(merge_node(label, id.as_str(), &props));
-        }
-
-        let mut activity_entries: Vec<(&ProvActivityId, &Activity)> =
-            normalized.document.activities().collect();
-        activity_entries.sort_by(|(a, _), (b, _)| a.cmp(b));
-        let mut activity_labels = HashMap::new();
-        for (id, activity) in &activity_entries {
-            let label = label_from_prov_type(activity.prov_type.as_deref(), "ProvActivity");
-            activity_labels.insert(id.as_str().to_string(), label);
-        }
-        for (id, activity) in activity_entries {
-            let label = activity_labels
-                .get(id.as_str())
-                .map(|value| value.as_str())
-                .unwrap_or("ProvActivity");
-            let props = activity_props(id, activity);
-            clauses.push(merge_node(label, id.as_str(), &props));
-        }
-
-        let mut agent_entries: Vec<(&ProvAgentId, &Agent)> =
-            normalized.document.agents().collect();
-        agent_entries.sort_by(|(a, _), (b, _)| a.cmp(b));
-        let mut agent_labels = HashMap::new();
-        for (id, agent) in &agent_entries {
-            let label = label_from_prov_type(agent.prov_type.as_deref(), "ProvAgent");
-            agent_labels.insert(id.as_str().to_string(), label);
-        }
-        for (id, label) in &normalized.agent_labels {
-            agent_labels.entry(id.clone()).or_insert_with(|| label.clone());
-        }
-        for (id, agent) in agent_entries {
-            let label = agent_labels
-                .get(id.as_str())
-                .map(|value| value.as_str())
-                .unwrap_or("ProvAgent");
-            let props = agent_props(id, agent);
-            clauses.push(merge_node(label, id.as_str(), &props));
-        }
+    pub fn with_indexes(mut self, indexes: Vec<GraphIndex>) -> Self {
+        self.indexes = indexes;
+        self
+    }
 
-        let mut used_entries: Vec<(&String, &Used)> = normalized.document.used().collect();
-        used_entries.sort_by(|(a, _), (b, _)| a.cmp(b));
-        for (_, used) in used_entries {
-            let props = used_props(used);
-            let activity_label = label_for_activity(&activity_labels, used.activity.as_str());
-            let entity_label = label_for_entity(&entity_labels, used.entity.as_str());
-            let rel_type = relation_label("USED", activity_label, entity_label, &props);
-            clauses.push(merge_edge(
-                activity_label,
-                used.activity.as_str(),
-                &rel_type,
-                entity_label,
-                used.entity.as_str(),
-                &props,
-            ));
-        }
-        let mut generated_entries: Vec<(&String, &WasGeneratedBy)> =
-            normalized.document.was_generated_by().collect();
-        generated_entries.sort_by(|(a, _), (b, _)| a.cmp(b));
-        for (_, generated) in generated_entries {
-            let props = was_generated_by_props(generated);
-            let entity_label =
-                label_for_ref(generated.entity.clone(), &entity_labels, &activity_labels, &agent_labels);
-            let activity_label = label_for_activity(&activity_labels, generated.activity.as_str());
-            let rel_type = relation_label("WAS_GENERATED_BY", entity_label, activity_label, &props);
-            clauses.push(merge_edge(
-                entity_label,
-                generated.entity.id(),
-                &rel_type,
-                activity_label,
-                generated.activity.as_str(),
-                &props,
-            ));
-        }
-        let mut qualified_gen_entries: Vec<(&String, &QualifiedGeneration)> =
-            normalized.document.qualified_generation().collect();
-        qualified_gen_entries.sort_by(|(a, _), (b, _)| a.cmp(b));
-        for (_, generation) in qualified_gen_entries {
-            let props = qualified_generation_props(generation);
-            let entity_label =
-                label_for_ref(generation.entity.clone(), &entity_labels, &activity_labels, &agent_labels);
-            let activity_label = label_for_activity(&activity_labels, generation.activity.as_str());
-            let rel_type = relation_label(
-                prov_relations::QUALIFIED_GENERATION,
-                entity_label,
-                activity_label,
-                &props,
-            );
-            clauses.push(merge_edge(
-                entity_label,
-                generation.entity.id(),
-                &rel_type,
-                activity_label,
-                generation.activity.as_str(),
-                &props,
-            ));
-        }
-        let mut assoc_entries: Vec<(&String, &WasAssociatedWith)> =
-            normalized.document.was_associated_with().collect();
-        assoc_entries.sort_by(|(a, _), (b, _)| a.cmp(b));
-        for (_, assoc) in assoc_entries {
-            let props = was_associated_with_props(assoc);
-            let activity_label = label_for_activity(&activity_labels, assoc.activity.as_str());
-            let agent_label = label_for_agent(&agent_labels, assoc.agent.as_str());
-            let rel_type = relation_label("WAS_ASSOCIATED_WITH", activity_label, agent_label, &props);
-            clauses.push(merge_edge(
-                activity_label,
-                assoc.activity.as_str(),
-                &rel_type,
-                agent_label,
-                assoc.agent.as_str(),
-                &props,
-            ));
-        }
-        let mut derived_entries: Vec<(&String, &WasDerivedFrom)> =
-            normalized.document.was_derived_from().collect();
-        derived_entries.sort_by(|(a, _), (b, _)| a.cmp(b));
-        for (_, derived) in derived_entries {
-            let props = was_derived_from_props(derived);
-            let generated_label = label_for_entity(&entity_labels, derived.generated_entity.as_str());
-            let used_label = label_for_entity(&entity_labels, derived.used_entity.as_str());
-            let rel_type = relation_label("WAS_DERIVED_FROM", generated_label, used_label, &props);
-            clauses.push(merge_edge(
-                generated_label,
-                derived.generated_entity.as_str(),
-                &rel_type,
-                used_label,
-                derived.used_entity.as_str(),
-                &props,
-            ));
-        }
+    pub fn with_slow_query_threshold(mut self, threshold: Duration) -> Self {
+        self.slow_query_threshold = Some(threshold);
+        self
+    }
 
-        for relation in &normalized.derived_relations {
-            clauses.push(merge_derived_relation(
-                relation,
-                &entity_labels,
-                &activity_labels,
-                &agent_labels,
-            ));
-        }
+    pub fn with_redaction(mut self, policy: RedactionPolicy) -> Self {
+        self.redaction = Some(Arc::new(policy));
+        self
+    }
 
-        if clauses.is_empty() {
-            return String::new();
-        }
+    pub fn with_buffering(mut self, flush_interval: Duration, max_batch_size: usize) -> Self {
+        self.flush_interval = Some(flush_interval);
+        self.max_batch_size = max_batch_size.max(1);
+        self
+    }
 
-        clauses.join(CLAUSE_SEPARATOR)
+    pub fn with_write_cache_capacity(mut self, capacity: usize) -> Self {
+        self.write_cache_capacity = capacity;
+        self
     }
-}
 
-#[async_trait]
-impl ProvenanceWriter for FalkorDbProvenanceWriter {
-    async fn add_event(&self, event: crate::events::ProvEvent) -> Result<()> {
-        validate_event(&event)?;
-        let normalized = self.normalizer.normalize(&event)?;
-        let query = Self::build_query(&normalized);
-        if query.is_empty() {
-            return Ok(());
-        }
-        execute_cypher_query(&query, &self.config.graph, &self.config.connection, false)
-            .await
-            ?;
-        Ok(())
+    pub fn with_retention(mut self, policy: RetentionPolicy) -> Self {
+        self.retention = Some(policy);
+        self
     }
 }
 
-/// Build an A2A-derived relation edge between two PROV nodes.
-fn merge_derived_relation(
-    relation: &A2aDerivedRelation,
-    entity_labels: &HashMap<String, String>,
-    activity_labels: &HashMap<String, String>,
-    agent_labels: &HashMap<String, String>,
-) -> String {
-    let props = relation_props(relation);
-    let from_label = label_for_ref(relation.from.clone(), entity_labels, activity_labels, agent_labels);
-    let to_label = label_for_ref(relation.to.clone(), entity_labels, activity_labels, agent_labels);
-    let rel_type = derived_relation_label(relation, from_label, to_label, &props);
-    merge_edge(
-        from_label,
-        relation.from.id(),
-        &rel_type,
-        to_label,
-        relation.to.id(),
-        &props,
-    )
+/// Clauses generated for one event or `add_events` batch, waiting for the
+/// next flush.
+struct PendingEvent {
+    /// The event id, or `first..last` for a batch.
+    event_id: String,
+    event_count: usize,
+    clauses: Vec<String>,
+    /// Recorded as written when buffered; forgotten if the batch fails.
+    written: Vec<ClauseId>,
 }
 
-/// Convert a PROV Entity into a Cypher property map (including `name`).
-fn entity_props(id: &ProvEntityId, entity: &Entity) -> HashMap<String, Value> {
-    let mut props = entity.attributes.clone();
-    insert_type(&mut props, entity.prov_type.as_ref());
-    insert_base_type(&mut props, "ProvEntity");
-    insert_id_props(&mut props, id.as_str());
-    props
+type EventBuffer = Mutex<Vec<PendingEvent>>;
+type SharedWriteCache = Arc<std::sync::Mutex<WriteCache>>;
+
+/// Background flush or retention loop; aborted when the last writer clone is
+/// dropped.
+struct BackgroundTask {
+    handle: JoinHandle<()>,
 }
 
-fn activity_props(id: &ProvActivityId, activity: &Activity) -> HashMap<String, Value> {
-    let mut props = activity.attributes.clone();
-    if let Some(start_time_ms) = activity.start_time_ms {
-        props.insert(prov::START_TIME.to_string(), Value::Number(start_time_ms.into()));
+impl Drop for BackgroundTask {
+    fn drop(&mut self) {
+        self.handle.abort();
     }
-    if let Some(end_time_ms) = activity.end_time_ms {
-        props.insert(prov::END_TIME.to_string(), Value::Number(end_time_ms.into()));
-    }
-    insert_type(&mut props, activity.prov_type.as_ref());
-    insert_base_type(&mut props, "ProvActivity");
-    insert_id_props(&mut props, id.as_str());
-    props
 }
 
-fn agent_props(id: &ProvAgentId, agent: &Agent) -> HashMap<String, Value> {
-    let mut props = agent.attributes.clone();
-    insert_type(&mut props, agent.prov_type.as_ref());
-    insert_base_type(&mut props, "ProvAgent");
-    insert_id_props(&mut props, id.as_str());
-    props
+#[derive(Clone)]
+pub struct FalkorDbProvenanceWriter {
+    config: FalkorDbProvenanceConfig,
+    normalizer: Arc<dyn ProvNormalizer>,
+    labels: Arc<dyn LabelStrategy>,
+    buffer: Arc<EventBuffer>,
+    flusher: Arc<OnceLock<BackgroundTask>>,
+    retention: Arc<OnceLock<BackgroundTask>>,
+    written: SharedWriteCache,
 }
 
-/// Relation properties for `USED`.
-fn used_props(used: &Used) -> HashMap<String, Value> {
-    let mut props = HashMap::new();
-    props.insert(prov::BASE_TYPE.to_string(), Value::String(prov_relations::USED.to_string()));
-    if let Some(role) = &used.role {
-        props.insert(prov::ROLE.to_string(), Value::String(role.clone()));
+impl FalkorDbProvenanceWriter {
+    pub fn new(config: FalkorDbProvenanceConfig) -> Self {
+        let normalizer = DefaultProvNormalizer::default()
+            .with_validation(config.validates())
+            .with_redaction(config.redaction.clone());
+        Self::with_normalizer(config, Arc::new(normalizer))
     }
-    props
-}
 
-fn was_generated_by_props(generated: &WasGeneratedBy) -> HashMap<String, Value> {
-    let mut props = HashMap::new();
-    props.insert(
-        prov::BASE_TYPE.to_string(),
-        Value::String(prov_relations::WAS_GENERATED_BY.to_string()),
-    );
-    if let Some(time_ms) = generated.time_ms {
-        props.insert(prov::TIME.to_string(), Value::Number(time_ms.into()));
+    pub fn with_normalizer(
+        config: FalkorDbProvenanceConfig,
+        normalizer: Arc<dyn ProvNormalizer>,
+    ) -> Self {
+        let written = Arc::new(std::sync::Mutex::new(WriteCache::new(config.write_cache_capacity)));
+        Self {
+            config,
+            normalizer,
+            labels: Arc::new(SemanticLabels),
+            buffer: Arc::new(Mutex::new(Vec::new())),
+            flusher: Arc::new(OnceLock::new()),
+            retention: Arc::new(OnceLock::new()),
+            written,
+        }
     }
-    props
-}
 
-fn qualified_generation_props(generation: &QualifiedGeneration) -> HashMap<String, Value> {
-    let mut props = HashMap::new();
-    props.insert(
-        prov::BASE_TYPE.to_string(),
-        Value::String(prov_relations::QUALIFIED_GENERATION.to_string()),
-    );
-    if let Some(time_ms) = generation.time_ms {
-        props.insert(prov::TIME.to_string(), Value::Number(time_ms.into()));
+    /// Replace the default [`SemanticLabels`] used for nodes and relationships.
+    pub fn with_label_strategy(mut self, labels: Arc<dyn LabelStrategy>) -> Self {
+        self.labels = labels;
+        self
     }
-    props
-}
 
-fn was_associated_with_props(assoc: &WasAssociatedWith) -> HashMap<String, Value> {
-    let mut props = HashMap::new();
-    props.insert(
-        prov::BASE_TYPE.to_string(),
-        Value::String(prov_relations::WAS_ASSOCIATED_WITH.to_string()),
-    );
-    if let Some(role) = &assoc.role {
-        props.insert(prov::ROLE.to_string(), Value::String(role.clone()));
+    /// Idempotently create the configured graph indexes. Call once at startup.
+    pub async fn ensure_indexes(&self) -> Result<()> {
+        ensure_indexes(&self.config).await
     }
-    props
-}
 
-fn was_derived_from_props(derived: &WasDerivedFrom) -> HashMap<String, Value> {
-    let mut props = HashMap::new();
-    props.insert(
-        prov::BASE_TYPE.to_string(),
-        Value::String(prov_relations::WAS_DERIVED_FROM.to_string()),
-    );
-    if let Some(activity) = &derived.activity {
-        props.insert(prov::ACTIVITY.to_string(), Value::String(activity.to_string()));
+    /// Indexes currently defined on the provenance graph.
+    pub async fn list_indexes(&self) -> Result<Vec<GraphIndex>> {
+        list_indexes(&self.config).await
     }
-    if let Some(prov_type) = &derived.prov_type {
-        props.insert(prov::TYPE.to_string(), Value::String(prov_type.clone()));
+
+    /// Typed read queries against the same graph and connection.
+    pub fn queries(&self) -> FalkorDbProvenanceQueries {
+        FalkorDbProvenanceQueries::new(self.config.clone())
     }
-    props
-}
 
-fn insert_base_type(props: &mut HashMap<String, Value>, base_type: &str) {
-    props.insert(prov::BASE_TYPE.to_string(), Value::String(base_type.to_string()));
-}
+    /// Events buffered and not yet written. Always zero when buffering is off.
+    pub async fn pending_events(&self) -> usize {
+        self.buffer.lock().await.iter().map(|pending| pending.event_count).sum()
+    }
 
-fn relation_props(relation: &A2aDerivedRelation) -> HashMap<String, Value> {
-    let mut props = relation.attributes.clone();
-    // FalkorDB supports relationship properties; we persist event context on derived edges.
-    props.insert(
-        a2a::RELATION.to_string(),
-        Value::String(relation.relation.as_str().to_string()),
-    );
-    props.insert(a2a::FROM.to_string(), Value::String(relation.from.id().to_string()));
-    props.insert(a2a::TO.to_string(), Value::String(relation.to.id().to_string()));
-    props
-}
+    /// Forget what was written, so every clause is sent again. Call after
+    /// anything else deletes nodes from the graph.
+    pub fn clear_write_cache(&self) {
+        self.written.lock().unwrap().clear();
+    }
 
-fn insert_type(props: &mut HashMap<String, Value>, prov_type: Option<&String>) {
-    if let Some(prov_type) = prov_type {
-        props.insert(prov::TYPE.to_string(), Value::String(prov_type.clone()));
+    /// Delete every timed node recorded before `before_ms` (epoch
+    /// milliseconds). Buffered events are flushed first.
+    pub async fn prune(&self, before_ms: u64) -> Result<PruneReport> {
+        self.prune_with(&PruneRequest::before(before_ms)).await
     }
-}
 
-fn label_from_prov_type(prov_type: Option<&str>, fallback: &str) -> String {
-    let raw = prov_type
-        .and_then(|value| value.split(':').next_back())
-        .unwrap_or(fallback);
-    sanitize_label(raw, fallback)
-}
+    /// Prune, count or archive as `request` says. Buffered events are flushed
+    /// first, and the write cache is cleared after anything is deleted.
+    pub async fn prune_with(&self, request: &PruneRequest) -> Result<PruneReport> {
+        self.flush().await?;
+        let report = prune(&self.config, request).await?;
+        if !report.dry_run && report.nodes > 0 {
+            self.clear_write_cache();
+        }
+        Ok(report)
+    }
 
-fn sanitize_label(value: &str, fallback: &str) -> String {
-    let mut out = String::new();
-    for ch in value.chars() {
-        if ch.is_ascii_alphanumeric() || ch == '_' {
-            out.push(ch);
-        } else {
-            out.push('_');
+    /// The clauses of `builder` not already written, and their ids.
+    fn unwritten(&self, builder: &CypherBuilder) -> (Vec<String>, Vec<ClauseId>) {
+        let mut cache = self.written.lock().unwrap();
+        let mut clauses = Vec::new();
+        let mut ids = Vec::new();
+        for (key, clause) in builder.keyed_clauses() {
+            let id = ClauseId::of(key, clause);
+            if cache.is_persisted(id) || ids.contains(&id) {
+                continue;
+            }
+            clauses.push(clause.to_string());
+            ids.push(id);
         }
+        (clauses, ids)
     }
-    if out.is_empty() {
-        return fallback.to_string();
+
+    /// Start the background flush loop on first use, so the writer can be
+    /// constructed outside a Tokio runtime.
+    fn ensure_flusher(&self, interval: Duration) {
+        self.flusher.get_or_init(|| {
+            let config = self.config.clone();
+            let buffer = self.buffer.clone();
+            let written = self.written.clone();
+            let handle = tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(interval);
+                ticker.tick().await;
+                loop {
+                    ticker.tick().await;
+                    if let Err(err) = flush_buffer(&config, &buffer, &written).await {
+                        tracing::error!(error = %err, "Failed to flush buffered provenance events");
+                    }
+                }
+            });
+            BackgroundTask { handle }
+        });
     }
-    let first = out.chars().next().unwrap_or('_');
-    if first.is_ascii_alphabetic() || first == '_' {
-        out
-    } else {
-        format!("L_{}", out)
+
+    /// Start the retention loop on first use, like the flush loop.
+    fn ensure_retention(&self) {
+        let Some(policy) = self.config.retention.clone() else {
+            return;
+        };
+        self.retention.get_or_init(|| {
+            let config = self.config.clone();
+            let buffer = self.buffer.clone();
+            let written = self.written.clone();
+            let handle = tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(policy.interval);
+                loop {
+                    ticker.tick().await;
+                    if let Err(err) = flush_buffer(&config, &buffer, &written).await {
+                        tracing::warn!(error = %err, "Failed to flush provenance events before pruning");
+                    }
+                    match apply_policy(&config, &policy, now_millis()).await {
+                        Ok(report) => {
+                            if !report.dry_run && report.nodes > 0 {
+                                written.lock().unwrap().clear();
+                            }
+                            tracing::info!(
+                                before_ms = report.before_ms,
+                                dry_run = report.dry_run,
+                                nodes = report.nodes,
+                                tasks = report.tasks.len(),
+                                orphaned_entities = report.orphaned_entities,
+                                "Applied provenance retention policy"
+                            );
+                        }
+                        Err(err) => tracing::error!(error = %err, "Failed to prune provenance graph"),
+                    }
+                }
+            });
+            BackgroundTask { handle }
+        });
     }
 }
 
-fn relation_label(base: &str, from_label: &str, to_label: &str, props: &HashMap<String, Value>) -> String {
-    let semantic = match base {
-        prov_relations::USED => semantic_used(from_label, to_label, props),
-        prov_relations::WAS_GENERATED_BY => semantic_generated_by(from_label, to_label),
-        prov_relations::WAS_ASSOCIATED_WITH => semantic_associated_with(props),
-        prov_relations::WAS_DERIVED_FROM => semantic_derived_from(props),
-        _ => None,
+/// Write every buffered event in a single query. A failed batch is dropped and
+/// logged rather than retried, matching the unbuffered path's per-event errors.
+async fn flush_buffer(
+    config: &FalkorDbProvenanceConfig,
+    buffer: &EventBuffer,
+    written: &SharedWriteCache,
+) -> Result<()> {
+    let batch = std::mem::take(&mut *buffer.lock().await);
+    let (Some(first), Some(last)) = (batch.first(), batch.last()) else {
+        return Ok(());
     };
-    let label = semantic.unwrap_or(base);
-    sanitize_label(label, base)
-}
-
-fn semantic_used(from_label: &str, _to_label: &str, props: &HashMap<String, Value>) -> Option<&'static str> {
-    let role = props.get(prov::ROLE).and_then(Value::as_str);
-    match role {
-        Some(a2a_roles::INPUT_MESSAGE) => Some(match from_label {
-            "A2ATaskExecution" => semantic_labels::WAS_SPAWNED_BY,
-            "A2AMessageProcessing" => semantic_labels::WAS_RECEIVED_BY,
-            "LlmCall" => semantic_labels::WAS_CONSUMED_BY,
-            "ToolCall" => semantic_labels::WAS_CONSUMED_BY,
-            _ => semantic_labels::WAS_USED_BY,
-        }),
-        Some(a2a_roles::TASK_STATE) => Some(semantic_labels::WAS_UPDATED_BY),
-        Some(a2a_roles::PROMPT) => Some(semantic_labels::WAS_USED_BY),
-        Some(a2a_roles::ARGS) => Some(semantic_labels::WAS_USED_BY),
-        Some(a2a_roles::ARCHIVE) => Some(semantic_labels::WAS_BOOTSTRAPPED_BY),
-        _ => None,
+    let label = format!("{}..{}", first.event_id, last.event_id);
+    let events: usize = batch.iter().map(|pending| pending.event_count).sum();
+    let query = batch
+        .iter()
+        .flat_map(|pending| pending.clauses.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(CLAUSE_SEPARATOR);
+    let result = execute_traced(config, &query, &label).await;
+    if let Err(err) = &result {
+        let mut cache = written.lock().unwrap();
+        for pending in &batch {
+            cache.forget(&pending.written);
+        }
+        tracing::warn!(events, error = %err, "Dropped provenance batch after failed write");
     }
-}
-
-fn semantic_associated_with(props: &HashMap<String, Value>) -> Option<&'static str> {
-    let role = props.get(prov::ROLE).and_then(Value::as_str);
-    match role {
-        Some(role) if role == prov_roles::EXECUTING_AGENT => Some(semantic_labels::WAS_EXECUTED_BY),
-        Some(role) if role == prov_roles::INVOKING_AGENT => Some(semantic_labels::WAS_INVOKED_BY),
-        Some(role) if role == prov_roles::CALLING_AGENT => Some(semantic_labels::WAS_CALLED_BY),
-        _ => None,
+    result
+}
+
+/// Execute a generated query, recording its size and latency and logging it
+/// when it exceeds the slow-query threshold.
+async fn execute_traced(config: &FalkorDbProvenanceConfig, query: &str, event_id: &str) -> Result<()> {
+    let clause_count = query.matches(CLAUSE_SEPARATOR).count() + 1;
+    let started = Instant::now();
+    let result = execute_cypher_query(query, &config.graph, &config.connection, false).await;
+    let elapsed = started.elapsed();
+    let slow = config
+        .slow_query_threshold
+        .is_some_and(|threshold| elapsed >= threshold);
+
+    baml_rt_observability::metrics::record_provenance_query(
+        if result.is_ok() { "ok" } else { "error" },
+        query.len(),
+        elapsed,
+        slow,
+    );
+    tracing::debug!(
+        event_id,
+        query_bytes = query.len(),
+        clause_count,
+        duration_ms = elapsed.as_millis() as u64,
+        "Executed provenance query"
+    );
+    if slow {
+        tracing::warn!(
+            event_id,
+            query_bytes = query.len(),
+            clause_count,
+            duration_ms = elapsed.as_millis() as u64,
+            query = truncate_query(query, config.slow_query_log_chars),
+            "Slow provenance query"
+        );
     }
+    result?;
+    Ok(())
 }
 
-
-#[derive(Debug, Clone, Copy)]
-enum GeneratedByPair {
-    MessageProcessing,
-    ArtifactTaskExecution,
-    TaskTaskExecution,
-    AgentRuntimeInstanceBoot,
-}
-
-impl GeneratedByPair {
-    fn from_labels(from_label: &str, to_label: &str) -> Option<Self> {
-        match (from_label, to_label) {
-            ("A2AMessage", "A2AMessageProcessing") => Some(Self::MessageProcessing),
-            ("Artifact", "A2ATaskExecution") => Some(Self::ArtifactTaskExecution),
-            ("A2ATask", "A2ATaskExecution") => Some(Self::TaskTaskExecution),
-            ("AgentRuntimeInstance", "AgentBoot") => Some(Self::AgentRuntimeInstanceBoot),
-            _ => None,
+impl FalkorDbProvenanceWriter {
+    /// Write (or buffer) the clauses for `normalized`, the document of
+    /// `event_count` events labelled `event_id` in logs.
+    async fn write_normalized(
+        &self,
+        normalized: &NormalizedProv,
+        event_id: String,
+        event_count: usize,
+    ) -> Result<()> {
+        // A redacted document was validated by the normalizer, before redaction.
+        if self.config.validates() && self.config.redaction.is_none() {
+            validate_document(&normalized.document)?;
+        }
+        self.ensure_retention();
+        let mut builder = CypherBuilder::with_label_strategy(self.labels.clone());
+        builder.normalized(normalized);
+        let (clauses, written) = self.unwritten(&builder);
+        if clauses.len() < builder.clauses().len() {
+            tracing::trace!(
+                event_id,
+                skipped = builder.clauses().len() - clauses.len(),
+                "Skipped unchanged provenance clauses"
+            );
         }
+        if clauses.is_empty() {
+            return Ok(());
+        }
+        let Some(flush_interval) = self.config.flush_interval else {
+            let query = clauses.join(CLAUSE_SEPARATOR);
+            execute_traced(&self.config, &query, &event_id).await?;
+            let mut cache = self.written.lock().unwrap();
+            for id in written {
+                cache.record(id);
+            }
+            return Ok(());
+        };
+
+        self.ensure_flusher(flush_interval);
+        // Recorded now so later events in the same batch skip these clauses too.
+        {
+            let mut cache = self.written.lock().unwrap();
+            for id in &written {
+                cache.record(*id);
+            }
+        }
+        let pending = {
+            let mut buffer = self.buffer.lock().await;
+            buffer.push(PendingEvent { event_id, event_count, clauses, written });
+            buffer.iter().map(|pending| pending.event_count).sum::<usize>()
+        };
+        if pending >= self.config.max_batch_size {
+            self.flush().await?;
+        }
+        Ok(())
     }
 }
 
-fn semantic_generated_by(from_label: &str, to_label: &str) -> Option<&'static str> {
-    let pair = GeneratedByPair::from_labels(from_label, to_label)?;
-    let label = match pair {
-        GeneratedByPair::MessageProcessing => semantic_labels::WAS_EMITTED_BY,
-        GeneratedByPair::ArtifactTaskExecution => semantic_labels::WAS_GENERATED_BY,
-        GeneratedByPair::TaskTaskExecution => semantic_labels::WAS_CREATED_BY,
-        GeneratedByPair::AgentRuntimeInstanceBoot => semantic_labels::WAS_SPAWNED_BY,
-    };
-    Some(label)
-}
-
-fn semantic_derived_from(props: &HashMap<String, Value>) -> Option<&'static str> {
-    let prov_type = props.get(prov::TYPE).and_then(Value::as_str);
-    match prov_type {
-        Some(a2a_relation_types::STATUS_TRANSITION) => Some(semantic_labels::WAS_TRANSITIONED_FROM),
-        _ => None,
+#[async_trait]
+impl ProvenanceWriter for FalkorDbProvenanceWriter {
+    async fn add_event(&self, event: crate::events::ProvEvent) -> Result<()> {
+        validate_event(&event)?;
+        let normalized = self.normalizer.normalize(&event)?;
+        self.write_normalized(&normalized, event.id().as_str().to_string(), 1).await
     }
-}
 
-fn derived_relation_label(
-    relation: &A2aDerivedRelation,
-    _from_label: &str,
-    to_label: &str,
-    props: &HashMap<String, Value>,
-) -> String {
-    let semantic = match relation.relation.as_str() {
-        "A2A_TASK_CALL" => match to_label {
-            "LlmCall" => Some(semantic_labels::WAS_INVOKED_BY),
-            "ToolCall" => Some(semantic_labels::WAS_EXECUTED_BY),
-            _ => None,
-        },
-        "A2A_MESSAGE_CALL" => match to_label {
-            "LlmCall" => Some(semantic_labels::WAS_INVOKED_BY),
-            "ToolCall" => Some(semantic_labels::WAS_EXECUTED_BY),
-            _ => None,
-        },
-        "A2A_TASK_MESSAGE" => match props.get(a2a::DIRECTION).and_then(Value::as_str) {
-            Some(message_directions::RECEIVED) => Some(semantic_labels::WAS_SPAWNED_BY),
-            Some(message_directions::SENT) => Some(semantic_labels::WAS_EMITTED_BY),
-            _ => Some(semantic_labels::WAS_RELATED_TO),
-        },
-        "A2A_TASK_ARTIFACT" => Some(semantic_labels::WAS_GENERATED_BY),
-        "A2A_TASK_STATUS_TRANSITION" => Some(semantic_labels::WAS_TRANSITIONED_TO),
-        _ => None,
-    };
-    let label = semantic.unwrap_or(relation.relation.as_str());
-    sanitize_label(label, relation.relation.as_str())
-}
+    /// One document and one query (or buffered entry) for the whole batch.
+    async fn add_events(&self, events: Vec<crate::events::ProvEvent>) -> Result<()> {
+        let (Some(first), Some(last)) = (events.first(), events.last()) else {
+            return Ok(());
+        };
+        for event in &events {
+            validate_event(event)?;
+        }
+        let label = if events.len() == 1 {
+            first.id().as_str().to_string()
+        } else {
+            format!("{}..{}", first.id().as_str(), last.id().as_str())
+        };
+        let normalized = self.normalizer.normalize_batch(&events)?;
+        self.write_normalized(&normalized, label, events.len()).await
+    }
 
-fn label_for_entity<'a>(labels: &'a HashMap<String, String>, id: &str) -> &'a str {
-    labels.get(id).map(|value| value.as_str()).unwrap_or("ProvEntity")
-}
+    async fn flush(&self) -> Result<()> {
+        flush_buffer(&self.config, &self.buffer, &self.written).await
+    }
 
-fn label_for_activity<'a>(labels: &'a HashMap<String, String>, id: &str) -> &'a str {
-    labels.get(id).map(|value| value.as_str()).unwrap_or("ProvActivity")
-}
+    async fn pending_events(&self) -> usize {
+        FalkorDbProvenanceWriter::pending_events(self).await
+    }
 
-fn label_for_agent<'a>(labels: &'a HashMap<String, String>, id: &str) -> &'a str {
-    labels.get(id).map(|value| value.as_str()).unwrap_or("ProvAgent")
+    async fn health_check(&self) -> Result<()> {
+        execute_cypher_query("RETURN 1", &self.config.graph, &self.config.connection, false)
+            .await?;
+        Ok(())
+    }
 }
 
-fn label_for_ref<'a>(
-    reference: crate::types::ProvNodeRef,
-    entity_labels: &'a HashMap<String, String>,
-    activity_labels: &'a HashMap<String, String>,
-    agent_labels: &'a HashMap<String, String>,
-) -> &'a str {
-    match reference {
-        crate::types::ProvNodeRef::Entity(id) => label_for_entity(entity_labels, id.as_str()),
-        crate::types::ProvNodeRef::Activity(id) => label_for_activity(activity_labels, id.as_str()),
-        // Agent nodes can appear in derived relations when needed.
-        // If no agent label exists, fall back to ProvAgent.
-        // (Currently unused, but kept for completeness.)
-        // Note: derived relations today only point to entities/activities.
-        //
-        // This branch allows future agent-derived relations without schema changes.
-        // It does not emit new nodes by itself.
-        // - If agent_labels has no entry, it returns "ProvAgent".
-        crate::types::ProvNodeRef::Agent(id) => label_for_agent(agent_labels, id.as_str()),
+/// Reads return each node's id, type, time and scope attributes
+/// (`a2a:context_id`, `a2a:task_id`, `a2a:agent_id`).
+#[async_trait]
+impl ProvenanceReader for FalkorDbProvenanceWriter {
+    async fn query_nodes(&self, query: &ProvenanceQuery) -> Result<Vec<ProvNodeRecord>> {
+        let raw = execute_cypher_query(
+            &node_query(query),
+            &self.config.graph,
+            &self.config.connection,
+            true,
+        )
+        .await?;
+        let mut records: Vec<ProvNodeRecord> = raw.lines().filter_map(parse_node_row).collect();
+        sort_records(&mut records);
+        Ok(records)
     }
 }
 
-/// Insert stable identifiers used by upsert logic.
-fn insert_id_props(props: &mut HashMap<String, Value>, id: &str) {
-    props.insert("name".to_string(), Value::String(id.to_string()));
-}
+const NODE_QUERY_COLUMNS: [&str; 3] = [a2a::CONTEXT_ID, a2a::TASK_ID, a2a::AGENT_ID];
 
-/// Create an idempotent node upsert.
-///
-/// `MERGE` will either match an existing node (same `name`) or create it.
-/// `SET n += {props}` then adds/updates properties without clearing others.
-fn merge_node(label: &str, id: &str, props: &HashMap<String, Value>) -> String {
-    let id_value = Value::String(id.to_string());
+/// Cypher for [`ProvenanceReader::query_nodes`], one row per node.
+pub(crate) fn node_query(query: &ProvenanceQuery) -> String {
+    let columns: Vec<String> = NODE_QUERY_COLUMNS
+        .iter()
+        .map(|key| format!("n.{}", cypher_key(key)))
+        .collect();
     format!(
-        "MERGE (n:{label} {{name: {name}}}) SET n += {props}",
-        name = cypher_value(&id_value),
-        props = cypher_map(props)
+        "{matched} RETURN n.name, n.{base_type}, n.{prov_type}, t, {columns}",
+        matched = node_match(query),
+        base_type = cypher_key(prov::BASE_TYPE),
+        prov_type = cypher_key(prov::TYPE),
+        columns = columns.join(", "),
     )
 }
 
-/// Create an idempotent edge upsert between two nodes.
-///
-/// We `MERGE` both nodes (by `name`) and then `MERGE` the relationship.
-/// This avoids `MATCH` after an updating clause and keeps the clause atomic.
-fn merge_edge(
-    from_label: &str,
-    from_id: &str,
-    rel_type: &str,
-    to_label: &str,
-    to_id: &str,
-    props: &HashMap<String, Value>,
-) -> String {
-    let from_value = Value::String(from_id.to_string());
-    let to_value = Value::String(to_id.to_string());
-    let base = format!(
-        "MERGE (a:{from_label} {{name: {from_id}}}) MERGE (b:{to_label} {{name: {to_id}}}) MERGE (a)-[r:{rel_type}]->(b)",
-        from_id = cypher_value(&from_value),
-        to_id = cypher_value(&to_value)
-    );
-    if props.is_empty() {
-        base
-    } else {
-        format!("{base} SET r += {}", cypher_map(props))
+/// `MATCH` binding `n` to the nodes `query` selects and `t` to their time.
+pub(crate) fn node_match(query: &ProvenanceQuery) -> String {
+    let mut conditions = Vec::new();
+    let filters = [
+        (a2a::TASK_ID, query.task_id.as_ref().map(|id| id.as_str())),
+        (a2a::CONTEXT_ID, query.context_id.as_ref().map(|id| id.as_str())),
+        (a2a::AGENT_ID, query.agent_id.as_ref().map(|id| id.as_str())),
+    ];
+    for (key, value) in filters {
+        if let Some(value) = value {
+            conditions.push(format!(
+                "n.{} = {}",
+                cypher_key(key),
+                cypher_value(&Value::String(value.to_string()))
+            ));
+        }
     }
-}
-
-/// Render a JSON map as a Cypher map literal with stable key ordering.
-fn cypher_map(map: &HashMap<String, Value>) -> String {
-    if map.is_empty() {
-        return "{}".to_string();
+    if let Some(since) = query.since_ms {
+        conditions.push(format!("t >= {since}"));
     }
-    let mut entries: Vec<(&String, &Value)> = map.iter().collect();
-    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
-    let mut parts = Vec::new();
-    for (key, value) in entries {
-        parts.push(format!("{}: {}", cypher_key(key), cypher_value(value)));
+    if let Some(until) = query.until_ms {
+        conditions.push(format!("t <= {until}"));
     }
-    format!("{{{}}}", parts.join(", "))
-}
-
-fn cypher_key(key: &str) -> String {
-    if is_safe_identifier(key) {
-        key.to_string()
+    let where_clause = if conditions.is_empty() {
+        String::new()
     } else {
-        format!("`{}`", key.replace('`', "``"))
-    }
-}
-
-/// Determine if a key can be used without backticks in Cypher.
-fn is_safe_identifier(value: &str) -> bool {
-    let mut chars = value.chars();
-    match chars.next() {
-        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
-        _ => return false,
-    }
-    chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        format!(" WHERE {}", conditions.join(" AND "))
+    };
+    format!(
+        "MATCH (n) WITH n, coalesce(n.{start}, n.{end}, n.{state_time}) AS t{where_clause}",
+        start = cypher_key(prov::START_TIME),
+        end = cypher_key(prov::END_TIME),
+        state_time = cypher_key(a2a::TASK_STATE_TIME),
+    )
 }
 
-fn cypher_value(value: &Value) -> String {
-    match value {
-        Value::Null => "null".to_string(),
-        Value::Bool(value) => value.to_string(),
-        Value::Number(value) => value.to_string(),
-        Value::String(value) => serde_json::to_string(value).unwrap_or_else(|_| "\"\"".to_string()),
-        Value::Array(values) => {
-            if values.iter().all(is_primitive_value) {
-                let mut parts = Vec::new();
-                for value in values {
-                    parts.push(cypher_value(value));
-                }
-                format!("[{}]", parts.join(", "))
-            } else {
-                let json = serde_json::to_string(values).unwrap_or_else(|_| "[]".to_string());
-                json_string_literal(&json)
-            }
-        }
-        Value::Object(map) => {
-            let json = serde_json::to_string(map).unwrap_or_else(|_| "{}".to_string());
-            json_string_literal(&json)
-        }
+/// Parse one `name, base_type, prov_type, time, scope...` row.
+pub(crate) fn parse_node_row(line: &str) -> Option<ProvNodeRecord> {
+    let fields: Vec<&str> = line
+        .split([',', '\t', '|'])
+        .map(|field| field.trim().trim_matches('"'))
+        .collect();
+    let present = |index: usize| {
+        fields
+            .get(index)
+            .copied()
+            .filter(|field| !field.is_empty() && *field != "null")
+    };
+    let id = present(0)?;
+    let base_type = present(1)?;
+    let kind = ProvNodeKind::from_base_label(base_type)?;
+    let attributes = NODE_QUERY_COLUMNS
+        .iter()
+        .enumerate()
+        .filter_map(|(offset, key)| {
+            present(4 + offset).map(|value| (key.to_string(), Value::String(value.to_string())))
+        })
+        .collect();
+    Some(ProvNodeRecord {
+        id: id.to_string(),
+        kind,
+        prov_type: present(2).map(str::to_string),
+        time_ms: present(3).and_then(|value| value.parse().ok()),
+        attributes,
+    })
+}
+
+/// Truncate `query` to at most `max_chars` characters for logging.
+fn truncate_query(query: &str, max_chars: usize) -> String {
+    match query.char_indices().nth(max_chars) {
+        Some((cut, _)) => format!("{}... ({} bytes total)", &query[..cut], query.len()),
+        None => query.to_string(),
     }
 }
-
-fn is_primitive_value(value: &Value) -> bool {
-    matches!(value, Value::Null | Value::Bool(_) | Value::Number(_) | Value::String(_))
-}
-
-fn json_string_literal(value: &str) -> String {
-    serde_json::to_string(value).unwrap_or_else(|_| "\"\"".to_string())
-}
@@ -0,0 +1,149 @@
+//! What a FalkorDB writer already persisted, so unchanged clauses can be skipped.
+//!
+//! Most events re-ensure nodes that earlier events wrote with the same
+//! properties: the task entity, the runner's agent, the calling activity. The
+//! cache maps each node or relationship a clause upserts (its
+//! [`CypherBuilder`](crate::cypher::CypherBuilder) key) to a digest of the last
+//! clause written for it; a clause whose digest matches is left out of the
+//! query. A changed property changes the digest, so the clause is written again.
+//!
+//! The cache only knows what this writer wrote. Anything that deletes nodes
+//! behind its back should call
+//! [`FalkorDbProvenanceWriter::clear_write_cache`](crate::FalkorDbProvenanceWriter::clear_write_cache).
+
+use baml_rt_core::canonical::{self, Digest};
+use std::collections::{BTreeMap, HashMap};
+
+/// A clause, identified by what it upserts and a digest of its text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ClauseId {
+    key: Digest,
+    digest: Digest,
+}
+
+impl ClauseId {
+    pub(crate) fn of(key: &str, clause: &str) -> Self {
+        // Canonical digests are SHA-256, so two different clauses cannot
+        // realistically collide and be skipped.
+        Self { key: canonical::digest_str(key), digest: canonical::digest_str(clause) }
+    }
+}
+
+/// Bounded LRU of the last clause digest written per key.
+#[derive(Debug)]
+pub(crate) struct WriteCache {
+    capacity: usize,
+    tick: u64,
+    /// Key to (digest, last use).
+    entries: HashMap<Digest, (Digest, u64)>,
+    /// Last use to key, oldest first.
+    recency: BTreeMap<u64, Digest>,
+}
+
+impl WriteCache {
+    /// A cache of `capacity` keys; zero disables it.
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self { capacity, tick: 0, entries: HashMap::new(), recency: BTreeMap::new() }
+    }
+
+    /// Whether exactly this clause was the last one written for its key.
+    pub(crate) fn is_persisted(&mut self, id: ClauseId) -> bool {
+        match self.entries.get(&id.key) {
+            Some(&(digest, _)) if digest == id.digest => {
+                self.touch(id.key, digest);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub(crate) fn record(&mut self, id: ClauseId) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.touch(id.key, id.digest);
+        while self.entries.len() > self.capacity {
+            let Some((_, oldest)) = self.recency.pop_first() else { break };
+            self.entries.remove(&oldest);
+        }
+    }
+
+    /// Drop clauses whose write failed, so they are sent again.
+    pub(crate) fn forget(&mut self, ids: &[ClauseId]) {
+        for id in ids {
+            if let Some(&(digest, used)) = self.entries.get(&id.key)
+                && digest == id.digest
+            {
+                self.entries.remove(&id.key);
+                self.recency.remove(&used);
+            }
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn touch(&mut self, key: Digest, digest: Digest) {
+        self.tick += 1;
+        if let Some((_, used)) = self.entries.insert(key, (digest, self.tick)) {
+            self.recency.remove(&used);
+        }
+        self.recency.insert(self.tick, key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changed_clause_for_a_key_is_not_persisted() {
+        let mut cache = WriteCache::new(8);
+        let first = ClauseId::of("(Task task-1)", "MERGE ... {state: \"working\"}");
+        let second = ClauseId::of("(Task task-1)", "MERGE ... {state: \"completed\"}");
+        cache.record(first);
+        assert!(cache.is_persisted(first));
+        assert!(!cache.is_persisted(second));
+
+        // Reverting to an earlier value is a change too.
+        cache.record(second);
+        assert!(!cache.is_persisted(first));
+    }
+
+    #[test]
+    fn least_recently_used_keys_are_evicted() {
+        let mut cache = WriteCache::new(2);
+        let a = ClauseId::of("a", "a");
+        let b = ClauseId::of("b", "b");
+        let c = ClauseId::of("c", "c");
+        cache.record(a);
+        cache.record(b);
+        assert!(cache.is_persisted(a));
+        cache.record(c);
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.is_persisted(a));
+        assert!(!cache.is_persisted(b));
+        assert!(cache.is_persisted(c));
+    }
+
+    #[test]
+    fn forgotten_and_disabled_entries_are_not_persisted() {
+        let mut cache = WriteCache::new(4);
+        let a = ClauseId::of("a", "a");
+        cache.record(a);
+        cache.forget(&[a]);
+        assert!(!cache.is_persisted(a));
+        assert_eq!(cache.len(), 0);
+
+        let mut disabled = WriteCache::new(0);
+        disabled.record(a);
+        assert!(!disabled.is_persisted(a));
+    }
+}
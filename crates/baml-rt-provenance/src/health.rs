@@ -0,0 +1,118 @@
+//! Provenance backend health checks.
+//!
+//! Writers are constructed lazily, so an unreachable backend would otherwise only
+//! show up when the first event fails to write. [`wait_until_healthy`] lets the
+//! runner fail fast at startup, and [`ProvenanceHealthMonitor`] keeps probing in the
+//! background so readiness reporting reflects the backend's current state.
+
+use crate::error::Result;
+use crate::store::ProvenanceWriter;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HealthStatus {
+    /// No check has completed yet.
+    Unknown,
+    Healthy,
+    /// The failed check's error with every underlying cause, `: `-separated.
+    Unhealthy(String),
+}
+
+impl HealthStatus {
+    pub fn is_healthy(&self) -> bool {
+        matches!(self, HealthStatus::Healthy)
+    }
+}
+
+/// Run `writer.health_check()` up to `attempts` times, sleeping `delay` between
+/// failures. Returns the last error if every attempt fails.
+pub async fn wait_until_healthy(
+    writer: &dyn ProvenanceWriter,
+    attempts: u32,
+    delay: Duration,
+) -> Result<()> {
+    let attempts = attempts.max(1);
+    let mut attempt = 1;
+    loop {
+        match writer.health_check().await {
+            Ok(()) => return Ok(()),
+            Err(err) if attempt >= attempts => return Err(err),
+            Err(err) => {
+                tracing::warn!(
+                    error = %error_chain(&err),
+                    attempt,
+                    attempts,
+                    "Provenance store not ready; retrying"
+                );
+                attempt += 1;
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+/// Periodic background health checks for a provenance writer.
+///
+/// The probe task is aborted when the monitor is dropped.
+pub struct ProvenanceHealthMonitor {
+    status: Arc<RwLock<HealthStatus>>,
+    handle: JoinHandle<()>,
+}
+
+impl ProvenanceHealthMonitor {
+    pub fn spawn(writer: Arc<dyn ProvenanceWriter>, interval: Duration) -> Self {
+        let status = Arc::new(RwLock::new(HealthStatus::Unknown));
+        let task_status = status.clone();
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let next = match writer.health_check().await {
+                    Ok(()) => HealthStatus::Healthy,
+                    Err(err) => HealthStatus::Unhealthy(error_chain(&err)),
+                };
+                let mut current = task_status.write().await;
+                if *current != next {
+                    match &next {
+                        HealthStatus::Unhealthy(reason) => {
+                            tracing::warn!(reason = %reason, "Provenance store became unhealthy");
+                        }
+                        _ => tracing::info!("Provenance store is healthy"),
+                    }
+                    *current = next;
+                }
+            }
+        });
+        Self { status, handle }
+    }
+
+    pub async fn status(&self) -> HealthStatus {
+        self.status.read().await.clone()
+    }
+
+    /// Readiness signal: true once the most recent check succeeded.
+    pub async fn is_ready(&self) -> bool {
+        self.status().await.is_healthy()
+    }
+}
+
+impl Drop for ProvenanceHealthMonitor {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// `err` followed by each of its sources, so wrapped backend errors keep their cause.
+fn error_chain(err: &dyn std::error::Error) -> String {
+    let mut chain = err.to_string();
+    let mut source = err.source();
+    while let Some(cause) = source {
+        chain.push_str(": ");
+        chain.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    chain
+}
@@ -1,5 +1,5 @@
 use crate::vocabulary::a2a_types;
-use baml_rt_core::ids::{AgentId, ArtifactId, EventId, MessageId, TaskId};
+use baml_rt_core::ids::{AgentId, ArtifactId, ContextId, EventId, MessageId, TaskId};
 use baml_rt_id::{
     ConstantConstructible, ConstantId, DerivedConstructible, DerivedId, ProvActivitySemantics,
     ProvAgentSemantics, ProvConstantAgentSemantics, ProvConstantIdTemplate,
@@ -443,3 +443,228 @@ impl ProvDerivedIdTemplate for MessageProcessingActivityId {
         DerivedId::new(format!("message_processing:{}", input.message_id.as_str()))
     }
 }
+
+/// Entity representing client feedback on a message or task.
+pub struct FeedbackEntityId;
+impl DerivedConstructible for FeedbackEntityId {}
+impl ProvIdSemantics for FeedbackEntityId {
+    const KIND: ProvKind = ProvKind::Entity;
+}
+impl ProvEntitySemantics for FeedbackEntityId {}
+impl ProvDerivedEntitySemantics for FeedbackEntityId {}
+impl ProvVocabularyType for FeedbackEntityId {
+    const VOCAB_TYPE: &'static str = a2a_types::FEEDBACK;
+}
+
+pub struct FeedbackEntityInput<'a> {
+    pub feedback_id: &'a str,
+}
+
+impl ProvDerivedIdTemplate for FeedbackEntityId {
+    type Input<'a> = FeedbackEntityInput<'a>;
+
+    fn build<'a>(input: Self::Input<'a>) -> DerivedId {
+        DerivedId::from_parts("feedback", [input.feedback_id])
+    }
+}
+
+/// Entity representing a conversation context (one branch of a conversation).
+pub struct ContextEntityId;
+impl DerivedConstructible for ContextEntityId {}
+impl ProvIdSemantics for ContextEntityId {
+    const KIND: ProvKind = ProvKind::Entity;
+}
+impl ProvEntitySemantics for ContextEntityId {}
+impl ProvDerivedEntitySemantics for ContextEntityId {}
+impl ProvVocabularyType for ContextEntityId {
+    const VOCAB_TYPE: &'static str = a2a_types::CONTEXT;
+}
+
+pub struct ContextEntityInput<'a> {
+    pub context_id: &'a ContextId,
+}
+
+impl ProvDerivedIdTemplate for ContextEntityId {
+    type Input<'a> = ContextEntityInput<'a>;
+
+    fn build<'a>(input: Self::Input<'a>) -> DerivedId {
+        DerivedId::from_parts("context", [input.context_id.as_str()])
+    }
+}
+
+/// Entity recording that a usage budget was spent.
+pub struct BudgetExhaustionEntityId;
+impl DerivedConstructible for BudgetExhaustionEntityId {}
+impl ProvIdSemantics for BudgetExhaustionEntityId {
+    const KIND: ProvKind = ProvKind::Entity;
+}
+impl ProvEntitySemantics for BudgetExhaustionEntityId {}
+impl ProvDerivedEntitySemantics for BudgetExhaustionEntityId {}
+impl ProvVocabularyType for BudgetExhaustionEntityId {
+    const VOCAB_TYPE: &'static str = a2a_types::BUDGET_EXHAUSTION;
+}
+
+pub struct BudgetExhaustionEntityInput<'a> {
+    pub event_id: &'a EventId,
+}
+
+impl ProvDerivedIdTemplate for BudgetExhaustionEntityId {
+    type Input<'a> = BudgetExhaustionEntityInput<'a>;
+
+    fn build<'a>(input: Self::Input<'a>) -> DerivedId {
+        DerivedId::from_parts("budget_exhaustion", [input.event_id.as_str()])
+    }
+}
+
+/// Entity recording one console line written by agent JavaScript.
+pub struct DiagnosticEntityId;
+impl DerivedConstructible for DiagnosticEntityId {}
+impl ProvIdSemantics for DiagnosticEntityId {
+    const KIND: ProvKind = ProvKind::Entity;
+}
+impl ProvEntitySemantics for DiagnosticEntityId {}
+impl ProvDerivedEntitySemantics for DiagnosticEntityId {}
+impl ProvVocabularyType for DiagnosticEntityId {
+    const VOCAB_TYPE: &'static str = a2a_types::DIAGNOSTIC;
+}
+
+pub struct DiagnosticEntityInput<'a> {
+    pub event_id: &'a EventId,
+}
+
+impl ProvDerivedIdTemplate for DiagnosticEntityId {
+    type Input<'a> = DiagnosticEntityInput<'a>;
+
+    fn build<'a>(input: Self::Input<'a>) -> DerivedId {
+        DerivedId::from_parts("diagnostic", [input.event_id.as_str()])
+    }
+}
+
+/// Entity recording one applied version of the runner's reloadable config.
+///
+/// Keyed by content digest, so reverting to an earlier config revisits its node.
+pub struct RuntimeConfigEntityId;
+impl DerivedConstructible for RuntimeConfigEntityId {}
+impl ProvIdSemantics for RuntimeConfigEntityId {
+    const KIND: ProvKind = ProvKind::Entity;
+}
+impl ProvEntitySemantics for RuntimeConfigEntityId {}
+impl ProvDerivedEntitySemantics for RuntimeConfigEntityId {}
+impl ProvVocabularyType for RuntimeConfigEntityId {
+    const VOCAB_TYPE: &'static str = a2a_types::RUNTIME_CONFIG;
+}
+
+pub struct RuntimeConfigEntityInput<'a> {
+    pub digest: &'a str,
+}
+
+impl ProvDerivedIdTemplate for RuntimeConfigEntityId {
+    type Input<'a> = RuntimeConfigEntityInput<'a>;
+
+    fn build<'a>(input: Self::Input<'a>) -> DerivedId {
+        DerivedId::from_parts("runtime_config", [input.digest])
+    }
+}
+
+/// Entity recording one loaded version of an agent's BAML sources.
+///
+/// Keyed by agent and source digest, so reloading unchanged sources revisits
+/// the same node.
+pub struct BamlSchemaEntityId;
+impl DerivedConstructible for BamlSchemaEntityId {}
+impl ProvIdSemantics for BamlSchemaEntityId {
+    const KIND: ProvKind = ProvKind::Entity;
+}
+impl ProvEntitySemantics for BamlSchemaEntityId {}
+impl ProvDerivedEntitySemantics for BamlSchemaEntityId {}
+impl ProvVocabularyType for BamlSchemaEntityId {
+    const VOCAB_TYPE: &'static str = a2a_types::BAML_SCHEMA;
+}
+
+pub struct BamlSchemaEntityInput<'a> {
+    pub agent_id: &'a AgentId,
+    pub digest: &'a str,
+}
+
+impl ProvDerivedIdTemplate for BamlSchemaEntityId {
+    type Input<'a> = BamlSchemaEntityInput<'a>;
+
+    fn build<'a>(input: Self::Input<'a>) -> DerivedId {
+        DerivedId::from_parts("baml_schema", [input.agent_id.as_str(), input.digest])
+    }
+}
+
+/// Entity summarizing one agent's use of one tool.
+///
+/// Keyed by agent and tool, so each periodic summary overwrites the last.
+pub struct ToolUsageEntityId;
+impl DerivedConstructible for ToolUsageEntityId {}
+impl ProvIdSemantics for ToolUsageEntityId {
+    const KIND: ProvKind = ProvKind::Entity;
+}
+impl ProvEntitySemantics for ToolUsageEntityId {}
+impl ProvDerivedEntitySemantics for ToolUsageEntityId {}
+impl ProvVocabularyType for ToolUsageEntityId {
+    const VOCAB_TYPE: &'static str = a2a_types::TOOL_USAGE;
+}
+
+pub struct ToolUsageEntityInput<'a> {
+    pub agent_id: &'a AgentId,
+    pub tool_name: &'a str,
+}
+
+impl ProvDerivedIdTemplate for ToolUsageEntityId {
+    type Input<'a> = ToolUsageEntityInput<'a>;
+
+    fn build<'a>(input: Self::Input<'a>) -> DerivedId {
+        DerivedId::from_parts("tool_usage", [input.agent_id.as_str(), input.tool_name])
+    }
+}
+
+/// Entity recording that a policy rule allowed or denied a call.
+pub struct PolicyDecisionEntityId;
+impl DerivedConstructible for PolicyDecisionEntityId {}
+impl ProvIdSemantics for PolicyDecisionEntityId {
+    const KIND: ProvKind = ProvKind::Entity;
+}
+impl ProvEntitySemantics for PolicyDecisionEntityId {}
+impl ProvDerivedEntitySemantics for PolicyDecisionEntityId {}
+impl ProvVocabularyType for PolicyDecisionEntityId {
+    const VOCAB_TYPE: &'static str = a2a_types::POLICY_DECISION;
+}
+
+pub struct PolicyDecisionEntityInput<'a> {
+    pub event_id: &'a EventId,
+}
+
+impl ProvDerivedIdTemplate for PolicyDecisionEntityId {
+    type Input<'a> = PolicyDecisionEntityInput<'a>;
+
+    fn build<'a>(input: Self::Input<'a>) -> DerivedId {
+        DerivedId::from_parts("policy_decision", [input.event_id.as_str()])
+    }
+}
+
+/// Entity recording who approved or denied a tool call held for approval.
+pub struct ApprovalDecisionEntityId;
+impl DerivedConstructible for ApprovalDecisionEntityId {}
+impl ProvIdSemantics for ApprovalDecisionEntityId {
+    const KIND: ProvKind = ProvKind::Entity;
+}
+impl ProvEntitySemantics for ApprovalDecisionEntityId {}
+impl ProvDerivedEntitySemantics for ApprovalDecisionEntityId {}
+impl ProvVocabularyType for ApprovalDecisionEntityId {
+    const VOCAB_TYPE: &'static str = a2a_types::APPROVAL_DECISION;
+}
+
+pub struct ApprovalDecisionEntityInput<'a> {
+    pub approval_id: &'a str,
+}
+
+impl ProvDerivedIdTemplate for ApprovalDecisionEntityId {
+    type Input<'a> = ApprovalDecisionEntityInput<'a>;
+
+    fn build<'a>(input: Self::Input<'a>) -> DerivedId {
+        DerivedId::from_parts("approval_decision", [input.approval_id])
+    }
+}
@@ -1,22 +1,126 @@
+use crate::attributes::STREAM_METADATA_KEY;
 use crate::events::ProvEvent;
 use crate::store::ProvenanceWriter;
 use async_trait::async_trait;
 use baml_rt_interceptor::{
-    InterceptorDecision, LLMCallContext, LLMInterceptor, ToolCallContext, ToolInterceptor,
+    ApprovalObserver, ApprovalOutcome, BudgetExhausted, BudgetObserver, InterceptorDecision, LLMCallContext, LLMChunk, LLMInterceptor, PayloadCapture,
+    PolicyDecision, PolicyObserver, TokenUsage, ToolCallContext, ToolCallRetry, ToolInterceptor, ERROR_METADATA_KEY,
+    FUNCTION_ARGS_METADATA_KEY, FUNCTION_OUTPUT_METADATA_KEY, OUTPUT_REPAIR_METADATA_KEY,
+    OUTPUT_VALIDATION_METADATA_KEY, RETRY_METADATA_KEY, STREAM_ID_METADATA_KEY,
 };
 use baml_rt_core::{BamlRtError, Result};
 use baml_rt_core::context;
-use baml_rt_core::ids::{ExternalId, MessageId};
-use serde_json::Value;
-use std::sync::Arc;
+use baml_rt_core::ids::{ContextId, EventId, ExternalId, MessageId};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 pub struct ProvenanceInterceptor {
     writer: Arc<dyn ProvenanceWriter>,
+    /// Decides how much of each prompt and argument payload is recorded.
+    capture: Arc<PayloadCapture>,
+    /// Progress of in-flight streamed calls, keyed by stream id.
+    streams: Mutex<HashMap<String, StreamProgress>>,
+    /// Latest attempt of each unfinished output repair, keyed by repair chain.
+    repairs: Mutex<HashMap<String, RepairAttempt>>,
+}
+
+/// A completed attempt whose output the next call in its repair chain retries.
+#[derive(Debug, Clone)]
+struct RepairAttempt {
+    event_id: EventId,
+    client: String,
+    model: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct StreamProgress {
+    chunk_count: u64,
+    time_to_first_token_ms: u64,
 }
 
 impl ProvenanceInterceptor {
     pub fn new(writer: Arc<dyn ProvenanceWriter>) -> Self {
-        Self { writer }
+        Self {
+            writer,
+            capture: PayloadCapture::global(),
+            streams: Mutex::new(HashMap::new()),
+            repairs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Use `capture` instead of the process-wide capture settings.
+    pub fn with_capture(mut self, capture: Arc<PayloadCapture>) -> Self {
+        self.capture = capture;
+        self
+    }
+
+    /// Completion metadata with the progress of the call's stream, if it streamed,
+    /// and the function's arguments and output captured like the prompt.
+    fn completion_metadata(&self, context: &LLMCallContext) -> Value {
+        let metadata = &context.metadata;
+        let progress = stream_id_from_metadata(metadata)
+            .and_then(|stream_id| self.streams.lock().unwrap().remove(stream_id));
+        let mut metadata = metadata.clone();
+        if let Value::Object(map) = &mut metadata {
+            for key in [FUNCTION_ARGS_METADATA_KEY, FUNCTION_OUTPUT_METADATA_KEY] {
+                if let Some(payload) = map.get_mut(key) {
+                    *payload = self.capture.capture(&context.context_id, payload);
+                }
+            }
+        }
+        if let (Some(progress), Value::Object(map)) = (progress, &mut metadata) {
+            map.insert(
+                STREAM_METADATA_KEY.to_string(),
+                json!({
+                    "chunk_count": progress.chunk_count,
+                    "time_to_first_token_ms": progress.time_to_first_token_ms,
+                }),
+            );
+        }
+        let previous = repair_chain(&metadata)
+            .and_then(|chain| self.repairs.lock().unwrap().get(chain).cloned());
+        if let Some(Value::Object(repair)) = metadata.get_mut(OUTPUT_REPAIR_METADATA_KEY)
+            && let Some(previous) = previous
+        {
+            repair.insert(
+                "retry_of".to_string(),
+                json!({
+                    "event_id": previous.event_id.as_str(),
+                    "client": previous.client,
+                    "model": previous.model,
+                }),
+            );
+        }
+        metadata
+    }
+
+    /// Remember the completed call as the attempt the next retry in its repair
+    /// chain repairs, or forget the chain once it ended.
+    fn track_repair(&self, context: &LLMCallContext, event_id: &EventId) {
+        let metadata = &context.metadata;
+        let Some(chain) = repair_chain(metadata) else {
+            return;
+        };
+        let repair = &metadata[OUTPUT_REPAIR_METADATA_KEY];
+        let valid = metadata
+            .pointer(&format!("/{}/valid", OUTPUT_VALIDATION_METADATA_KEY))
+            .and_then(Value::as_bool)
+            .unwrap_or(true);
+        let last = repair["attempt"].as_u64() >= repair["max_attempts"].as_u64();
+        let mut repairs = self.repairs.lock().unwrap();
+        if valid || last {
+            repairs.remove(chain);
+        } else {
+            repairs.insert(
+                chain.to_string(),
+                RepairAttempt {
+                    event_id: event_id.clone(),
+                    client: context.client.clone(),
+                    model: context.model.clone(),
+                },
+            );
+        }
     }
 }
 
@@ -37,7 +141,7 @@ impl LLMInterceptor for ProvenanceInterceptor {
                 context.client.clone(),
                 context.model.clone(),
                 context.function_name.clone(),
-                context.prompt.clone(),
+                self.capture.capture(&context.context_id, &context.prompt),
                 context.metadata.clone(),
             )
         } else {
@@ -55,7 +159,7 @@ impl LLMInterceptor for ProvenanceInterceptor {
                 context.client.clone(),
                 context.model.clone(),
                 context.function_name.clone(),
-                context.prompt.clone(),
+                self.capture.capture(&context.context_id, &context.prompt),
                 context.metadata.clone(),
             )
         };
@@ -70,6 +174,8 @@ impl LLMInterceptor for ProvenanceInterceptor {
         duration_ms: u64,
     ) {
         let success = result.is_ok();
+        let metadata = with_error(self.completion_metadata(context), result);
+        let usage = llm_usage(&context.metadata);
         let task_id = context::current_task_id();
         let message_id = message_id_from_metadata(&context.metadata);
         if task_id.is_none() && message_id.is_none() {
@@ -83,9 +189,9 @@ impl LLMInterceptor for ProvenanceInterceptor {
                 context.client.clone(),
                 context.model.clone(),
                 context.function_name.clone(),
-                context.prompt.clone(),
-                context.metadata.clone(),
-                crate::events::LlmUsage::Unknown,
+                self.capture.capture(&context.context_id, &context.prompt),
+                metadata,
+                usage,
                 duration_ms,
                 success,
             )
@@ -103,15 +209,32 @@ impl LLMInterceptor for ProvenanceInterceptor {
                 context.client.clone(),
                 context.model.clone(),
                 context.function_name.clone(),
-                context.prompt.clone(),
-                context.metadata.clone(),
-                crate::events::LlmUsage::Unknown,
+                self.capture.capture(&context.context_id, &context.prompt),
+                metadata,
+                usage,
                 duration_ms,
                 success,
             )
         };
+        self.track_repair(context, event.id());
         self.writer.add_event_with_logging(event, "LLM call completion").await;
     }
+
+    async fn on_llm_chunk(
+        &self,
+        context: &LLMCallContext,
+        chunk: &LLMChunk,
+    ) -> Result<InterceptorDecision> {
+        if let Some(stream_id) = stream_id_from_metadata(&context.metadata) {
+            let mut streams = self.streams.lock().unwrap();
+            let progress = streams.entry(stream_id.to_string()).or_insert(StreamProgress {
+                chunk_count: 0,
+                time_to_first_token_ms: chunk.elapsed_ms,
+            });
+            progress.chunk_count += 1;
+        }
+        Ok(InterceptorDecision::Allow)
+    }
 }
 
 #[async_trait]
@@ -130,7 +253,7 @@ impl ToolInterceptor for ProvenanceInterceptor {
                 task_id,
                 context.tool_name.clone(),
                 context.function_name.clone(),
-                context.args.clone(),
+                self.capture.capture(&context.context_id, &context.args),
                 context.metadata.clone(),
             )
         } else {
@@ -147,7 +270,7 @@ impl ToolInterceptor for ProvenanceInterceptor {
                 message_id,
                 context.tool_name.clone(),
                 context.function_name.clone(),
-                context.args.clone(),
+                self.capture.capture(&context.context_id, &context.args),
                 context.metadata.clone(),
             )
         };
@@ -161,12 +284,159 @@ impl ToolInterceptor for ProvenanceInterceptor {
         result: &Result<Value>,
         duration_ms: u64,
     ) {
-        let success = result.is_ok();
+        let metadata = with_error(context.metadata.clone(), result);
+        let Some(event) = self.tool_completion_event(context, metadata, duration_ms, result.is_ok())
+        else {
+            return;
+        };
+        self.writer.add_event_with_logging(event, "tool call completion").await;
+    }
+
+    /// Each failed attempt is recorded as an unsuccessful completion whose
+    /// `metadata.retry` names the attempt, its failure kind and the backoff.
+    async fn on_tool_call_retry(&self, context: &ToolCallContext, retry: &ToolCallRetry) {
+        let mut metadata = context.metadata.clone();
+        if let Value::Object(map) = &mut metadata {
+            map.insert(
+                RETRY_METADATA_KEY.to_string(),
+                json!({
+                    "attempt": retry.attempt,
+                    "max_attempts": retry.max_attempts,
+                    "failure_kind": retry.failure_kind,
+                    "error": retry.error,
+                    "backoff_ms": retry.backoff_ms,
+                }),
+            );
+        }
+        let Some(event) = self.tool_completion_event(context, metadata, retry.duration_ms, false)
+        else {
+            return;
+        };
+        self.writer.add_event_with_logging(event, "tool call retry").await;
+    }
+}
+
+/// Records a tripped [`baml_rt_interceptor::UsageBudget`] as a `BudgetExhausted` event.
+#[async_trait]
+impl BudgetObserver for ProvenanceInterceptor {
+    async fn on_budget_exhausted(&self, context: &LLMCallContext, exhausted: &BudgetExhausted) {
+        let spent = crate::events::LlmUsage::Known {
+            prompt_tokens: exhausted.spent.prompt_tokens,
+            completion_tokens: exhausted.spent.completion_tokens,
+            total_tokens: exhausted.spent.total_tokens,
+        };
+        let scope = exhausted.scope.kind().to_string();
+        let scope_id = exhausted.scope.id().to_string();
+        let dimension = exhausted.dimension.as_str().to_string();
+        let event = match context::current_task_id() {
+            Some(task_id) => ProvEvent::budget_exhausted_task(
+                context.context_id.clone(),
+                task_id,
+                scope,
+                scope_id,
+                dimension,
+                exhausted.limit,
+                spent,
+            ),
+            None => ProvEvent::budget_exhausted_global(
+                context.context_id.clone(),
+                scope,
+                scope_id,
+                dimension,
+                exhausted.limit,
+                spent,
+            ),
+        };
+        self.writer.add_event_with_logging(event, "budget exhaustion").await;
+    }
+}
+
+/// Records each decision of a [`baml_rt_interceptor::PolicyInterceptor`] rule
+/// as a `PolicyEvaluated` event.
+#[async_trait]
+impl PolicyObserver for ProvenanceInterceptor {
+    async fn on_policy_decision(&self, context_id: &ContextId, decision: &PolicyDecision) {
+        let policy_id = decision.rule_id.clone();
+        let effect = decision.effect.as_str().to_string();
+        let target = decision.target.as_str().to_string();
+        let subject = decision.subject.clone();
+        let reason = decision.reason.clone();
+        let event = match context::current_task_id() {
+            Some(task_id) => ProvEvent::policy_evaluated_task(
+                context_id.clone(),
+                task_id,
+                policy_id,
+                effect,
+                target,
+                subject,
+                reason,
+            ),
+            None => ProvEvent::policy_evaluated_global(
+                context_id.clone(),
+                policy_id,
+                effect,
+                target,
+                subject,
+                reason,
+            ),
+        };
+        self.writer.add_event_with_logging(event, "policy decision").await;
+    }
+}
+
+/// Records how each call a [`baml_rt_interceptor::ApprovalInterceptor`] held
+/// was settled, and by whom, as an `ApprovalDecided` event.
+#[async_trait]
+impl ApprovalObserver for ProvenanceInterceptor {
+    async fn on_approval_decided(&self, outcome: &ApprovalOutcome) {
+        let request = &outcome.request;
+        let context_id = request.context_id.clone();
+        let approval_id = request.approval_id.clone();
+        let tool_name = request.tool_name.clone();
+        let args = self.capture.capture(&context_id, &request.args);
+        let verdict = outcome.verdict.as_str().to_string();
+        let approver = outcome.approver.clone();
+        let reason = outcome.reason.clone();
+        let event = match context::current_task_id() {
+            Some(task_id) => ProvEvent::approval_decided_task(
+                context_id,
+                task_id,
+                approval_id,
+                tool_name,
+                args,
+                verdict,
+                approver,
+                reason,
+                outcome.waited_ms,
+            ),
+            None => ProvEvent::approval_decided_global(
+                context_id,
+                approval_id,
+                tool_name,
+                args,
+                verdict,
+                approver,
+                reason,
+                outcome.waited_ms,
+            ),
+        };
+        self.writer.add_event_with_logging(event, "approval decision").await;
+    }
+}
+
+impl ProvenanceInterceptor {
+    fn tool_completion_event(
+        &self,
+        context: &ToolCallContext,
+        metadata: Value,
+        duration_ms: u64,
+        success: bool,
+    ) -> Option<ProvEvent> {
         let task_id = context::current_task_id();
         let message_id = message_id_from_metadata(&context.metadata);
         if task_id.is_none() && message_id.is_none() {
             tracing::error!("Tool call completion missing metadata.message_id");
-            return;
+            return None;
         }
         let event = if let Some(task_id) = task_id {
             ProvEvent::tool_call_completed_task(
@@ -174,8 +444,8 @@ impl ToolInterceptor for ProvenanceInterceptor {
                 task_id,
                 context.tool_name.clone(),
                 context.function_name.clone(),
-                context.args.clone(),
-                context.metadata.clone(),
+                self.capture.capture(&context.context_id, &context.args),
+                metadata,
                 duration_ms,
                 success,
             )
@@ -184,7 +454,7 @@ impl ToolInterceptor for ProvenanceInterceptor {
                 Some(message_id) => message_id,
                 None => {
                     tracing::error!("Tool call completion missing metadata.message_id");
-                    return;
+                    return None;
                 }
             };
             ProvEvent::tool_call_completed_global(
@@ -192,16 +462,44 @@ impl ToolInterceptor for ProvenanceInterceptor {
                 message_id,
                 context.tool_name.clone(),
                 context.function_name.clone(),
-                context.args.clone(),
-                context.metadata.clone(),
+                self.capture.capture(&context.context_id, &context.args),
+                metadata,
                 duration_ms,
                 success,
             )
         };
-        self.writer.add_event_with_logging(event, "tool call completion").await;
+        Some(event)
+    }
+}
+
+/// Record why a failed call failed under `metadata.error`.
+fn with_error(mut metadata: Value, result: &Result<Value>) -> Value {
+    if let (Err(err), Value::Object(map)) = (result, &mut metadata) {
+        map.insert(ERROR_METADATA_KEY.to_string(), err.to_value());
+    }
+    metadata
+}
+
+fn stream_id_from_metadata(metadata: &Value) -> Option<&str> {
+    metadata.get(STREAM_ID_METADATA_KEY).and_then(|value| value.as_str())
+}
+
+/// Usage the runtime attached to a completed call, reported or estimated
+fn llm_usage(metadata: &Value) -> crate::events::LlmUsage {
+    match TokenUsage::from_metadata(metadata) {
+        Some(usage) => crate::events::LlmUsage::Known {
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            total_tokens: usage.total_tokens,
+        },
+        None => crate::events::LlmUsage::Unknown,
     }
 }
 
+fn repair_chain(metadata: &Value) -> Option<&str> {
+    metadata.get(OUTPUT_REPAIR_METADATA_KEY)?.get("chain")?.as_str()
+}
+
 fn message_id_from_metadata(metadata: &Value) -> Option<MessageId> {
     metadata
         .get("message_id")
@@ -0,0 +1,307 @@
+//! Append-only JSON lines event log.
+//!
+//! [`JsonlProvenanceWriter`] appends every event, as written, to a log file
+//! with one JSON object per line. Nothing is normalized or indexed, so writes
+//! are cheap; the log is an audit trail to load into a graph later with
+//! [`crate::replay::replay_jsonl_log`].
+//!
+//! The active file is rotated when it would grow past `max_bytes` or has been
+//! open for `max_age`. Rotated segments sit next to it with the rotation time
+//! appended, oldest first by name:
+//!
+//! ```text
+//! events.jsonl                  active
+//! events.jsonl.1760612345678    rotated
+//! ```
+//!
+//! Writes run on Tokio's blocking pool; each line is handed to the OS before
+//! `add_event` returns, and with `sync` set it is also fsynced.
+
+use crate::error::{ProvenanceError, Result};
+use crate::events::{observe_event_id, ProvEvent};
+use crate::normalizer::validate_event;
+use crate::store::ProvenanceWriter;
+use async_trait::async_trait;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Size at which the active file is rotated unless configured otherwise.
+pub const DEFAULT_MAX_BYTES: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct JsonlProvenanceConfig {
+    /// Active log file; created with its parent directories if missing.
+    pub path: PathBuf,
+    /// Rotate before the active file grows past this many bytes. A single
+    /// event larger than this still gets a file of its own.
+    pub max_bytes: Option<u64>,
+    /// Rotate once the active file has been open this long.
+    pub max_age: Option<Duration>,
+    /// Rotated segments to keep; older ones are deleted. `None` keeps all.
+    pub max_segments: Option<usize>,
+    /// fsync after every write.
+    pub sync: bool,
+}
+
+impl JsonlProvenanceConfig {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            max_bytes: Some(DEFAULT_MAX_BYTES),
+            max_age: None,
+            max_segments: None,
+            sync: false,
+        }
+    }
+
+    pub fn with_max_bytes(mut self, max_bytes: Option<u64>) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    pub fn with_max_age(mut self, max_age: Option<Duration>) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    pub fn with_max_segments(mut self, max_segments: Option<usize>) -> Self {
+        self.max_segments = max_segments;
+        self
+    }
+
+    pub fn with_sync(mut self, sync: bool) -> Self {
+        self.sync = sync;
+        self
+    }
+}
+
+struct ActiveLog {
+    file: File,
+    len: u64,
+    opened_at: SystemTime,
+}
+
+#[derive(Clone)]
+pub struct JsonlProvenanceWriter {
+    config: JsonlProvenanceConfig,
+    active: Arc<Mutex<ActiveLog>>,
+}
+
+impl std::fmt::Debug for JsonlProvenanceWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JsonlProvenanceWriter").field("config", &self.config).finish_non_exhaustive()
+    }
+}
+
+impl JsonlProvenanceWriter {
+    /// Open the log for appending, creating it if needed.
+    ///
+    /// The newest file is scanned so new event ids continue past the ones
+    /// already logged, as loading a snapshot does for the in-memory store.
+    pub fn open(config: JsonlProvenanceConfig) -> Result<Self> {
+        if let Some(parent) = config.path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent).map_err(io_error)?;
+        }
+        let newest = log_segments(&config.path)?
+            .into_iter()
+            .rev()
+            .find(|segment| std::fs::metadata(segment).is_ok_and(|metadata| metadata.len() > 0));
+        if let Some(newest) = newest {
+            observe_logged_event_ids(&newest)?;
+        }
+        let active = open_active(&config.path)?;
+        tracing::debug!(path = %config.path.display(), bytes = active.len, "Opened JSONL provenance log");
+        Ok(Self { config, active: Arc::new(Mutex::new(active)) })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.config.path
+    }
+
+    /// Serialize `events` and append them, rotating first where needed.
+    async fn append(&self, events: &[ProvEvent]) -> Result<()> {
+        let mut lines = Vec::with_capacity(events.len());
+        for event in events {
+            validate_event(event)?;
+            let mut line = serde_json::to_vec(event).map_err(|err| ProvenanceError::Storage(Box::new(err)))?;
+            line.push(b'\n');
+            lines.push(line);
+        }
+        let config = self.config.clone();
+        let active = self.active.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut active = active.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            for line in &lines {
+                if needs_rotation(&config, &active, line.len() as u64) {
+                    rotate(&config, &mut active)?;
+                }
+                active.file.write_all(line).map_err(io_error)?;
+                active.len += line.len() as u64;
+            }
+            if config.sync {
+                active.file.sync_data().map_err(io_error)?;
+            }
+            Ok(())
+        })
+        .await
+        .map_err(|err| ProvenanceError::Storage(Box::new(err)))?
+    }
+}
+
+#[async_trait]
+impl ProvenanceWriter for JsonlProvenanceWriter {
+    async fn add_event(&self, event: ProvEvent) -> Result<()> {
+        self.append(std::slice::from_ref(&event)).await
+    }
+
+    /// The batch is appended under one lock, so its lines stay together.
+    async fn add_events(&self, events: Vec<ProvEvent>) -> Result<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        self.append(&events).await
+    }
+
+    async fn health_check(&self) -> Result<()> {
+        let path = self.config.path.clone();
+        tokio::task::spawn_blocking(move || {
+            let metadata = std::fs::metadata(&path).map_err(io_error)?;
+            if metadata.permissions().readonly() {
+                return Err(ProvenanceError::Storage(
+                    format!("provenance log {} is read-only", path.display()).into(),
+                ));
+            }
+            Ok(())
+        })
+        .await
+        .map_err(|err| ProvenanceError::Storage(Box::new(err)))?
+    }
+
+    async fn flush(&self) -> Result<()> {
+        let active = self.active.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut active = active.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            active.file.flush().map_err(io_error)?;
+            active.file.sync_data().map_err(io_error)
+        })
+        .await
+        .map_err(|err| ProvenanceError::Storage(Box::new(err)))?
+    }
+}
+
+/// Every file of the log at `path`: rotated segments oldest first, then the
+/// active file if it exists.
+pub fn log_segments(path: &Path) -> Result<Vec<PathBuf>> {
+    let mut segments = rotated_segments(path)?
+        .into_iter()
+        .map(|(_, segment)| segment)
+        .collect::<Vec<_>>();
+    if path.exists() {
+        segments.push(path.to_path_buf());
+    }
+    Ok(segments)
+}
+
+/// Rotated segments of the log at `path` with their rotation times, oldest first.
+fn rotated_segments(path: &Path) -> Result<Vec<(u128, PathBuf)>> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name().and_then(|name| name.to_str()))
+    else {
+        return Ok(Vec::new());
+    };
+    let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(io_error(err)),
+    };
+    let prefix = format!("{name}.");
+    let mut segments = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(io_error)?;
+        let file_name = entry.file_name();
+        let Some(rotated_at) = file_name
+            .to_str()
+            .and_then(|file_name| file_name.strip_prefix(&prefix))
+            .and_then(|suffix| suffix.parse::<u128>().ok())
+        else {
+            continue;
+        };
+        segments.push((rotated_at, entry.path()));
+    }
+    segments.sort();
+    Ok(segments)
+}
+
+fn open_active(path: &Path) -> Result<ActiveLog> {
+    let file = OpenOptions::new().create(true).append(true).open(path).map_err(io_error)?;
+    let metadata = file.metadata().map_err(io_error)?;
+    let opened_at = if metadata.len() == 0 {
+        SystemTime::now()
+    } else {
+        metadata.created().or_else(|_| metadata.modified()).unwrap_or_else(|_| SystemTime::now())
+    };
+    Ok(ActiveLog { file, len: metadata.len(), opened_at })
+}
+
+fn needs_rotation(config: &JsonlProvenanceConfig, active: &ActiveLog, incoming: u64) -> bool {
+    if active.len == 0 {
+        return false;
+    }
+    let too_big = config.max_bytes.is_some_and(|max_bytes| active.len + incoming > max_bytes);
+    let too_old = config.max_age.is_some_and(|max_age| {
+        active.opened_at.elapsed().is_ok_and(|age| age >= max_age)
+    });
+    too_big || too_old
+}
+
+/// Move the active file aside and start a new one, then prune old segments.
+fn rotate(config: &JsonlProvenanceConfig, active: &mut ActiveLog) -> Result<()> {
+    active.file.flush().map_err(io_error)?;
+    let mut rotated_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis())
+        .unwrap_or_default();
+    let segment = loop {
+        let mut name = config.path.as_os_str().to_owned();
+        name.push(format!(".{rotated_at}"));
+        let segment = PathBuf::from(name);
+        if !segment.exists() {
+            break segment;
+        }
+        rotated_at += 1;
+    };
+    std::fs::rename(&config.path, &segment).map_err(io_error)?;
+    *active = open_active(&config.path)?;
+    tracing::debug!(segment = %segment.display(), "Rotated JSONL provenance log");
+
+    if let Some(max_segments) = config.max_segments {
+        let segments = rotated_segments(&config.path)?;
+        let excess = segments.len().saturating_sub(max_segments);
+        for (_, old) in segments.into_iter().take(excess) {
+            if let Err(err) = std::fs::remove_file(&old) {
+                tracing::warn!(error = %err, segment = %old.display(), "Failed to delete old provenance log segment");
+            }
+        }
+    }
+    Ok(())
+}
+
+fn observe_logged_event_ids(path: &Path) -> Result<()> {
+    let file = File::open(path).map_err(io_error)?;
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(io_error)?;
+        if let Ok(event) = serde_json::from_str::<ProvEvent>(&line) {
+            observe_event_id(event.id());
+        }
+    }
+    Ok(())
+}
+
+fn io_error(err: std::io::Error) -> ProvenanceError {
+    ProvenanceError::Storage(Box::new(err))
+}
@@ -1,33 +1,109 @@
 //! Provenance capture and storage.
 //!
 //! This crate provides event types and interceptors for provenance recording,
-//! along with a pluggable storage interface and an in-memory implementation.
+//! along with a pluggable storage interface, in-memory, SQLite and FalkorDB
+//! implementations, an append-only JSON lines event log, fan-out to several
+//! of them at once, replay of recorded events into a fresh store and of
+//! recorded LLM calls against the current prompts, redaction of payloads
+//! both before they are stored and per reader role, per-principal scoping of
+//! reads, archival of FalkorDB graphs as PROV-JSON and pruning them under a
+//! retention policy, named capture profiles for dev, staging and prod, per-agent
+//! tool usage rollups, and a machine-readable description of the vocabulary
+//! for UI builders.
 
 pub mod error;
 pub mod events;
 pub mod types;
 pub mod document;
+mod traversal;
 pub mod builders;
+pub mod attributes;
 pub mod store;
+pub mod composite;
+pub mod jsonl_store;
+pub mod health;
+pub mod snapshot;
+pub mod replay;
+pub mod llm_replay;
+pub mod redaction;
+pub mod profile;
+pub mod access;
 pub mod interceptors;
 pub mod normalizer;
+pub mod normalizer_state;
+pub mod schema;
+pub mod ontology;
+pub mod cypher;
 pub mod falkordb_store;
+pub mod falkordb_indexes;
+pub mod falkordb_query;
+pub mod falkordb_archive;
+pub mod falkordb_retention;
+mod falkordb_write_cache;
+pub mod sqlite_schema;
+pub mod sqlite_store;
 pub mod tool_index;
+pub mod tool_usage;
 pub mod vocabulary;
 pub mod id_semantics;
 
+pub use attributes::AttrBuilder;
 pub use error::ProvenanceError;
 pub use events::{
-    AgentType, CallScope, GlobalEvent, LlmUsage, ProvEvent, ProvEventData, TaskScopedEvent,
+    AgentType, CallScope, FeedbackTarget, GlobalEvent, LlmUsage, ProvEvent, ProvEventData,
+    TaskScopedEvent,
+};
+pub use store::{
+    InMemoryProvenanceStore, ProvNodeKind, ProvNodeRecord, ProvenanceQuery, ProvenanceReader,
+    ProvenanceWriter,
+};
+pub use composite::{CompositeProvenanceWriter, FailureMode};
+pub use health::{wait_until_healthy, HealthStatus, ProvenanceHealthMonitor};
+pub use snapshot::{ProvenanceSnapshotter, SnapshotConfig};
+pub use replay::{
+    decode_event_log, replay_events, replay_file, replay_jsonl_log, replay_store, ReplayOptions,
+    ReplayReport, SkippedEvent,
+};
+pub use llm_replay::{
+    recorded_llm_calls, replay_llm_calls, LlmReplayOptions, LlmReplayOutcome, LlmReplayReport,
+    LlmReplayTarget, RecordedLlmCall, SkippedLlmCall,
+};
+pub use redaction::{
+    AttributeRedactor, ReadPolicy, RedactingReader, RedactionAction, RedactionPolicy,
+    RedactionKey, RedactionRule, Redactor, RoleReader, HASH_PREFIX, PAYLOAD_ATTRIBUTES,
+    PRIVILEGED_ROLE, REDACTED, REDACTION_KEY_ENV,
+};
+pub use profile::{ProfileRedaction, ProvenanceProfile, ProvenanceSettings};
+pub use access::{
+    AccessControlledReader, AccessResolver, AccessScope, ContextOwnership, ScopedReader,
 };
-pub use store::{InMemoryProvenanceStore, ProvenanceWriter};
 pub use interceptors::ProvenanceInterceptor;
+pub use normalizer_state::ContextRetention;
 pub use normalizer::{
-    normalize_event, validate_event, A2aDerivedRelation, A2aRelationType, DefaultProvNormalizer,
-    NormalizedProv, ProvNormalizer,
+    normalize_event, normalize_event_redacted, normalize_events, validate_event, A2aDerivedRelation, A2aRelationType, DefaultProvNormalizer,
+    NormalizedProv, ProvNormalizer, CALLING_AGENT_ID_METADATA_KEY,
 };
+pub use cypher::{
+    BaseLabels, CypherBuilder, LabelStrategy, PropertyMatch, SemanticLabelRule, SemanticLabels,
+    SEMANTIC_LABEL_RULES,
+};
+pub use ontology::{ontology, Ontology, ONTOLOGY_VERSION};
 pub use falkordb_store::{FalkorDbProvenanceConfig, FalkorDbProvenanceWriter};
-pub use tool_index::{ToolIndexConfig, index_tools};
+pub use falkordb_indexes::GraphIndex;
+pub use falkordb_archive::{ArchivedRelation, FalkorDbArchiver, ProvArchive, ARCHIVE_VERSION};
+pub use falkordb_retention::{PruneReport, PruneRequest, RetentionPolicy, RetentionScope};
+pub use falkordb_query::{
+    AgentActivitySummary, FalkorDbProvenanceQueries, LineageEdge, LlmCallSummary, TaskLineage,
+};
+pub use jsonl_store::{JsonlProvenanceConfig, JsonlProvenanceWriter};
+pub use sqlite_store::{SqliteProvenanceConfig, SqliteProvenanceWriter};
+pub use tool_index::{
+    FalkorDbToolIndexer, FileToolIndexer, IndexedTool, NoopToolIndexer, ToolIndexConfig,
+    ToolIndexDrift, ToolIndexSource, ToolIndexer, detect_drift, index_tools, metadata_digest,
+};
+pub use tool_usage::{
+    ToolUsageAggregator, ToolUsageReporter, ToolUsageSummary, LATENCY_WINDOW,
+};
 pub use types::{
     ProvActivityId, ProvAgentId, ProvEntityId, ProvNodeRef,
 };
\ No newline at end of file
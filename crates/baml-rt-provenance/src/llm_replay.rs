@@ -0,0 +1,240 @@
+//! Re-invoke recorded LLM calls and compare their outputs.
+//!
+//! The LLM call a BAML function's output was parsed from records the
+//! function's arguments and output in its completion metadata (see
+//! [`FUNCTION_ARGS_METADATA_KEY`]). [`recorded_llm_calls`] pairs those
+//! completions with the `LlmCallStarted` events before them, and
+//! [`replay_llm_calls`] invokes each function again through an
+//! [`LlmReplayTarget`], optionally against another client, and compares the new
+//! output with the recorded one. Run it after a prompt change to see which
+//! recorded calls now answer differently.
+//!
+//! Only calls recorded under full payload capture can be replayed; truncated
+//! arguments are skipped.
+
+use crate::events::{event_sequence, CallScope, ProvEvent, ProvEventData};
+use async_trait::async_trait;
+use baml_rt_core::ids::{ContextId, EventId};
+use baml_rt_interceptor::{FUNCTION_ARGS_METADATA_KEY, FUNCTION_OUTPUT_METADATA_KEY};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// One recorded BAML function invocation, as its LLM call events saw it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecordedLlmCall {
+    /// The `LlmCallStarted` event, when one was recorded before the completion.
+    pub started: Option<EventId>,
+    pub completed: EventId,
+    pub context_id: ContextId,
+    pub scope: CallScope,
+    pub client: String,
+    pub model: String,
+    pub function_name: String,
+    /// The request sent to the model, from the started event if there is one.
+    pub prompt: Value,
+    pub args: Value,
+    pub output: Option<Value>,
+    pub success: bool,
+    pub timestamp_ms: u64,
+}
+
+/// Invokes BAML functions for [`replay_llm_calls`].
+#[async_trait]
+pub trait LlmReplayTarget: Send + Sync {
+    /// Invoke `function_name` with `args`, against `client` instead of the
+    /// function's own client when given.
+    async fn invoke(
+        &self,
+        function_name: &str,
+        args: Value,
+        client: Option<&str>,
+    ) -> baml_rt_core::Result<Value>;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LlmReplayOptions {
+    /// Client to replay against instead of the recorded one.
+    pub client: Option<String>,
+    /// Only replay these functions; empty replays all.
+    pub functions: HashSet<String>,
+    /// Replay at most this many calls, oldest first.
+    pub limit: Option<usize>,
+}
+
+impl LlmReplayOptions {
+    pub fn with_client(mut self, client: impl Into<String>) -> Self {
+        self.client = Some(client.into());
+        self
+    }
+
+    pub fn with_function(mut self, function_name: impl Into<String>) -> Self {
+        self.functions.insert(function_name.into());
+        self
+    }
+
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LlmReplayOutcome {
+    pub call: RecordedLlmCall,
+    /// The client replayed against, or `None` for the function's own.
+    pub client: Option<String>,
+    pub output: Option<Value>,
+    pub error: Option<String>,
+    /// Whether the new output equals the recorded one; `None` when either is
+    /// missing.
+    pub matches: Option<bool>,
+}
+
+/// A recorded call that could not be replayed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SkippedLlmCall {
+    pub event_id: EventId,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LlmReplayReport {
+    pub outcomes: Vec<LlmReplayOutcome>,
+    pub skipped: Vec<SkippedLlmCall>,
+}
+
+impl LlmReplayReport {
+    /// Replays that failed or whose output changed.
+    pub fn regressions(&self) -> impl Iterator<Item = &LlmReplayOutcome> {
+        self.outcomes
+            .iter()
+            .filter(|outcome| outcome.error.is_some() || outcome.matches == Some(false))
+    }
+
+    pub fn passed(&self) -> bool {
+        self.regressions().next().is_none()
+    }
+}
+
+/// Every replayable call in `events`, oldest first, with the calls that cannot
+/// be replayed.
+///
+/// A completion is paired with the earliest unpaired `LlmCallStarted` issued
+/// before it for the same function in the same context and scope.
+pub fn recorded_llm_calls(events: &[ProvEvent]) -> (Vec<RecordedLlmCall>, Vec<SkippedLlmCall>) {
+    let mut events: Vec<&ProvEvent> = events.iter().collect();
+    events.sort_by_key(|event| event_sequence(event.id()).unwrap_or(u64::MAX));
+
+    let mut started: Vec<(&ProvEvent, bool)> = Vec::new();
+    let mut calls = Vec::new();
+    let mut skipped = Vec::new();
+    for event in events {
+        match event.data() {
+            ProvEventData::LlmCallStarted { .. } => started.push((event, false)),
+            ProvEventData::LlmCallCompleted {
+                scope,
+                client,
+                model,
+                function_name,
+                prompt,
+                metadata,
+                success,
+                ..
+            } => {
+                let start = started.iter_mut().find(|(candidate, paired)| {
+                    !paired
+                        && candidate.context_id() == event.context_id()
+                        && matches!(
+                            candidate.data(),
+                            ProvEventData::LlmCallStarted { scope: s, function_name: f, .. }
+                                if s == scope && f == function_name
+                        )
+                });
+                let start = start.map(|(candidate, paired)| {
+                    *paired = true;
+                    *candidate
+                });
+                let Some(args) = metadata.get(FUNCTION_ARGS_METADATA_KEY) else {
+                    // Not the call the function's output came from.
+                    continue;
+                };
+                if is_truncated(args) {
+                    skipped.push(SkippedLlmCall {
+                        event_id: event.id().clone(),
+                        reason: "arguments were truncated when recorded".to_string(),
+                    });
+                    continue;
+                }
+                let prompt = match start.map(ProvEvent::data) {
+                    Some(ProvEventData::LlmCallStarted { prompt, .. }) => prompt,
+                    _ => prompt,
+                };
+                calls.push(RecordedLlmCall {
+                    started: start.map(|start| start.id().clone()),
+                    completed: event.id().clone(),
+                    context_id: event.context_id().clone(),
+                    scope: scope.clone(),
+                    client: client.clone(),
+                    model: model.clone(),
+                    function_name: function_name.clone(),
+                    prompt: prompt.clone(),
+                    args: args.clone(),
+                    output: metadata
+                        .get(FUNCTION_OUTPUT_METADATA_KEY)
+                        .filter(|output| !is_truncated(output))
+                        .cloned(),
+                    success: *success,
+                    timestamp_ms: event.timestamp_ms(),
+                });
+            }
+            _ => {}
+        }
+    }
+    (calls, skipped)
+}
+
+/// Invoke every replayable call in `events` again through `target`, one at a
+/// time and oldest first.
+pub async fn replay_llm_calls(
+    events: &[ProvEvent],
+    target: &dyn LlmReplayTarget,
+    options: &LlmReplayOptions,
+) -> LlmReplayReport {
+    let (calls, skipped) = recorded_llm_calls(events);
+    let mut calls: Vec<RecordedLlmCall> = calls
+        .into_iter()
+        .filter(|call| options.functions.is_empty() || options.functions.contains(&call.function_name))
+        .collect();
+    calls.truncate(options.limit.unwrap_or(usize::MAX));
+
+    let mut report = LlmReplayReport { outcomes: Vec::with_capacity(calls.len()), skipped };
+    for call in calls {
+        let client = options.client.clone();
+        let result = target.invoke(&call.function_name, call.args.clone(), client.as_deref()).await;
+        let (output, error) = match result {
+            Ok(output) => (Some(output), None),
+            Err(err) => {
+                tracing::warn!(
+                    error = %err,
+                    event_id = call.completed.as_str(),
+                    function = call.function_name.as_str(),
+                    "Replayed LLM call failed"
+                );
+                (None, Some(err.to_string()))
+            }
+        };
+        let matches = match (&output, &call.output) {
+            (Some(output), Some(recorded)) => Some(output == recorded),
+            _ => None,
+        };
+        report.outcomes.push(LlmReplayOutcome { call, client, output, error, matches });
+    }
+    report
+}
+
+/// Whether `payload` is the placeholder payload capture leaves for a payload
+/// over its limit.
+fn is_truncated(payload: &Value) -> bool {
+    payload.get("truncated").and_then(Value::as_bool) == Some(true) && payload.get("preview").is_some()
+}
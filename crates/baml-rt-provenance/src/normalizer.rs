@@ -1,11 +1,18 @@
+use crate::attributes::AttrBuilder;
 use crate::document::ProvDocument;
 use crate::error::{ProvenanceError, Result};
-use crate::events::{CallScope, ProvEvent, ProvEventData};
+use crate::events::{CallScope, FeedbackTarget, ProvEvent, ProvEventData};
 use crate::id_semantics::{
     AgentBootActivityId, AgentBootActivityInput, AgentRuntimeInstanceId,
     AgentRuntimeInstanceInput, ArchiveEntityId, ArchiveEntityInput, ArtifactByEventEntityId,
     ArtifactByEventEntityInput, ArtifactByIdEntityId, ArtifactByIdEntityInput,
-    ArtifactByTypeEntityId, ArtifactByTypeEntityInput, ArtifactIdentity, LlmCallActivityId,
+    ArtifactByTypeEntityId, ArtifactByTypeEntityInput, ArtifactIdentity,
+    BudgetExhaustionEntityId, BudgetExhaustionEntityInput, ContextEntityId, DiagnosticEntityId,
+    DiagnosticEntityInput, RuntimeConfigEntityId, RuntimeConfigEntityInput, BamlSchemaEntityId,
+    BamlSchemaEntityInput, ToolUsageEntityId, ToolUsageEntityInput, PolicyDecisionEntityId,
+    PolicyDecisionEntityInput, ApprovalDecisionEntityId, ApprovalDecisionEntityInput,
+    ContextEntityInput, FeedbackEntityId,
+    FeedbackEntityInput, LlmCallActivityId,
     LlmCallActivityInput, LlmPromptEntityId, LlmPromptEntityInput, MessageEntityId,
     MessageEntityInput, MessageProcessingActivityId, MessageProcessingActivityInput,
     RunnerRuntimeInstanceId, TaskEntityId, TaskEntityInput, TaskExecutionActivityId,
@@ -13,9 +20,15 @@ use crate::id_semantics::{
     TaskStatePrevEntityInput, ToolArgsEntityId, ToolArgsEntityInput, ToolCallActivityId,
     ToolCallActivityInput,
 };
+use crate::normalizer_state::{
+    Backfill, ContextRetention, KnownAgents, NormalizerState, CallActivity,
+};
+use crate::redaction::RedactionPolicy;
+use crate::schema::validate_document;
 use crate::types::{
-    Activity, Agent, Entity, ProvActivityId, ProvAgentId, ProvEntityId, ProvNodeRef,
-    QualifiedGeneration, Used, WasAssociatedWith, WasDerivedFrom, WasGeneratedBy,
+    ActedOnBehalfOf, Activity, Agent, Entity, ProvActivityId, ProvAgentId, ProvEntityId,
+    ProvNodeRef, QualifiedGeneration, Used, WasAssociatedWith, WasDerivedFrom, WasGeneratedBy,
+    WasInformedBy,
 };
 use crate::vocabulary::{
     a2a, a2a_relation_types, a2a_relations, a2a_roles, agent_types, message_directions,
@@ -24,14 +37,56 @@ use crate::vocabulary::{
 use baml_rt_core::ids::{
     AgentId, ArtifactId, ContextId, EventId, MessageId, TaskId, UuidId, ProvVocabularyType,
 };
+use baml_rt_interceptor::OUTPUT_REPAIR_METADATA_KEY;
 use serde_json::Value;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 #[derive(Debug, Clone)]
 pub struct NormalizedProv {
     pub document: ProvDocument,
     pub derived_relations: Vec<A2aDerivedRelation>,
     pub agent_labels: HashMap<String, String>,
+    /// `prov:type` of activities that relations refer to but that an earlier
+    /// event added, keyed by activity id.
+    pub activity_types: HashMap<String, String>,
+}
+
+impl NormalizedProv {
+    fn empty() -> Self {
+        Self {
+            document: ProvDocument::new(),
+            derived_relations: Vec::new(),
+            agent_labels: HashMap::new(),
+            activity_types: HashMap::new(),
+        }
+    }
+
+    /// Fold a later event's result into this one.
+    ///
+    /// Documents merge as in [`ProvDocument::merge`]. An A2A relation between
+    /// nodes already related the same way is kept once, with the incoming
+    /// attributes added; the first label hint for an agent wins.
+    pub fn merge(&mut self, other: NormalizedProv) {
+        self.document.merge(other.document);
+        for relation in other.derived_relations {
+            let existing = self.derived_relations.iter_mut().find(|known| {
+                known.relation.as_str() == relation.relation.as_str()
+                    && known.from == relation.from
+                    && known.to == relation.to
+            });
+            match existing {
+                Some(known) => known.attributes.extend(relation.attributes),
+                None => self.derived_relations.push(relation),
+            }
+        }
+        for (id, label) in other.agent_labels {
+            self.agent_labels.entry(id).or_insert(label);
+        }
+        for (id, prov_type) in other.activity_types {
+            self.activity_types.entry(id).or_insert(prov_type);
+        }
+    }
 }
 
 fn parse_agent_id(event: &ProvEvent, raw: &str) -> Result<AgentId> {
@@ -45,17 +100,95 @@ fn parse_agent_id(event: &ProvEvent, raw: &str) -> Result<AgentId> {
 
 pub trait ProvNormalizer: Send + Sync {
     fn normalize(&self, event: &ProvEvent) -> Result<NormalizedProv>;
+
+    /// Normalize `events`, in order, into one document, so a node several of
+    /// them touch is built once. The default merges [`normalize`](Self::normalize)
+    /// results; see [`NormalizedProv::merge`].
+    fn normalize_batch(&self, events: &[ProvEvent]) -> Result<NormalizedProv> {
+        let mut batch = NormalizedProv::empty();
+        for event in events {
+            batch.merge(self.normalize(event)?);
+        }
+        Ok(batch)
+    }
 }
 
+/// Normalizes events with what earlier ones established: booted agents, per
+/// tenant, and which agent runs each task, per context. See
+/// [`crate::normalizer_state`].
 #[derive(Debug, Default)]
 pub struct DefaultProvNormalizer {
-    agent_registry: std::sync::Mutex<std::collections::HashSet<String>>,
+    state: std::sync::Mutex<NormalizerState>,
+    redaction: Option<Arc<RedactionPolicy>>,
+    validate: bool,
+}
+
+impl DefaultProvNormalizer {
+    /// Apply `policy` to the node attributes of every normalized event.
+    pub fn with_redaction(mut self, policy: Option<Arc<RedactionPolicy>>) -> Self {
+        self.redaction = policy;
+        self
+    }
+
+    /// Check each document against the attribute schemas before redaction, which
+    /// may leave placeholders where the schemas expect arrays or required keys.
+    pub fn with_validation(mut self, validate: bool) -> Self {
+        self.validate = validate;
+        self
+    }
+
+    /// Bound the per-context state kept between events.
+    pub fn with_context_retention(self, retention: ContextRetention) -> Self {
+        Self { state: std::sync::Mutex::new(NormalizerState::new(retention)), ..self }
+    }
+
+    /// Contexts state is currently kept for.
+    pub fn tracked_contexts(&self) -> usize {
+        self.state.lock().expect("normalizer state lock").tracked_contexts()
+    }
+
+    pub fn is_tracking(&self, context_id: &ContextId) -> bool {
+        self.state.lock().expect("normalizer state lock").is_tracked(context_id)
+    }
+
+    fn normalize_with(&self, state: &mut NormalizerState, event: &ProvEvent) -> Result<NormalizedProv> {
+        let mut normalized = normalize_event_with(event, &state.known_agents(event))?;
+        if let Some(call) = call_activity(event, &normalized.document) {
+            if let Some(informant) = state.sequence_call(event, &call) {
+                insert_was_informed_by(&mut normalized, &call, informant);
+            }
+            if !has_executing_agent(&normalized.document, &call) {
+                state.defer(event, call);
+            }
+        }
+        if let Some(backfill) = state.record(event) {
+            backfill_executing_agent(&mut normalized, backfill);
+        }
+        if self.validate {
+            validate_document(&normalized.document)?;
+        }
+        if let Some(policy) = &self.redaction {
+            redact_document(&mut normalized, event, policy);
+        }
+        Ok(normalized)
+    }
 }
 
 impl ProvNormalizer for DefaultProvNormalizer {
     fn normalize(&self, event: &ProvEvent) -> Result<NormalizedProv> {
-        let mut registry = self.agent_registry.lock().expect("agent registry lock");
-        normalize_event_with_registry(event, &mut registry)
+        let mut state = self.state.lock().expect("normalizer state lock");
+        self.normalize_with(&mut state, event)
+    }
+
+    /// Holds the state for the whole batch, so agents booted and tasks created
+    /// early in it are known to the events after them.
+    fn normalize_batch(&self, events: &[ProvEvent]) -> Result<NormalizedProv> {
+        let mut state = self.state.lock().expect("normalizer state lock");
+        let mut batch = NormalizedProv::empty();
+        for event in events {
+            batch.merge(self.normalize_with(&mut state, event)?);
+        }
+        Ok(batch)
     }
 }
 
@@ -74,6 +207,9 @@ pub enum A2aRelationType {
     TaskCall,
     TaskStatusTransition,
     MessageCall,
+    ContextDerivedFrom,
+    ContextContainedIn,
+    LlmRetryOf,
 }
 
 impl A2aRelationType {
@@ -84,8 +220,26 @@ impl A2aRelationType {
             A2aRelationType::TaskCall => a2a_relations::TASK_CALL,
             A2aRelationType::TaskStatusTransition => a2a_relations::TASK_STATUS_TRANSITION,
             A2aRelationType::MessageCall => a2a_relations::MESSAGE_CALL,
+            A2aRelationType::ContextDerivedFrom => a2a_relations::CONTEXT_DERIVED_FROM,
+            A2aRelationType::ContextContainedIn => a2a_relations::CONTEXT_CONTAINED_IN,
+            A2aRelationType::LlmRetryOf => a2a_relations::LLM_RETRY_OF,
         }
     }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        [
+            A2aRelationType::TaskHasMessage,
+            A2aRelationType::TaskHasArtifact,
+            A2aRelationType::TaskCall,
+            A2aRelationType::TaskStatusTransition,
+            A2aRelationType::MessageCall,
+            A2aRelationType::ContextDerivedFrom,
+            A2aRelationType::ContextContainedIn,
+            A2aRelationType::LlmRetryOf,
+        ]
+        .into_iter()
+        .find(|relation| relation.as_str() == value)
+    }
 }
 
 fn prov_type<S: ProvVocabularyType>() -> String {
@@ -93,14 +247,35 @@ fn prov_type<S: ProvVocabularyType>() -> String {
 }
 
 pub fn normalize_event(event: &ProvEvent) -> Result<NormalizedProv> {
-    normalize_event_with_registry(event, &mut std::collections::HashSet::new())
+    normalize_event_with(event, &KnownAgents::default())
 }
 
-fn normalize_event_with_registry(
-    event: &ProvEvent,
-    agent_registry: &mut std::collections::HashSet<String>,
-) -> Result<NormalizedProv> {
-    let mut doc = ProvDocument::new();
+/// Normalize `events` into one document, resolving agents booted by earlier
+/// events in the batch for the later ones. See [`ProvNormalizer::normalize_batch`].
+pub fn normalize_events(events: &[ProvEvent]) -> Result<NormalizedProv> {
+    DefaultProvNormalizer::default().normalize_batch(events)
+}
+
+/// [`normalize_event`] followed by `policy`, so redacted values never leave the
+/// normalizer.
+pub fn normalize_event_redacted(event: &ProvEvent, policy: &RedactionPolicy) -> Result<NormalizedProv> {
+    let mut normalized = normalize_event(event)?;
+    redact_document(&mut normalized, event, policy);
+    Ok(normalized)
+}
+
+fn redact_document(normalized: &mut NormalizedProv, event: &ProvEvent, policy: &RedactionPolicy) {
+    let event_type = event.data().event_type();
+    for attributes in normalized.document.node_attributes_mut() {
+        policy.apply(event_type, attributes);
+    }
+    for relation in &mut normalized.derived_relations {
+        policy.apply(event_type, &mut relation.attributes);
+    }
+}
+
+fn normalize_event_with(event: &ProvEvent, agents: &KnownAgents<'_>) -> Result<NormalizedProv> {
+    let mut doc = ProvDocument::scoped(event.id().as_str());
     let mut derived_relations = Vec::new();
     let mut agent_labels = HashMap::new();
 
@@ -114,11 +289,12 @@ fn normalize_event_with_registry(
             metadata,
         } => {
             let activity_id = llm_activity_id(event.id());
-            let mut attrs = base_attrs(event);
-            attrs.insert(a2a::CLIENT.to_string(), Value::String(client.clone()));
-            attrs.insert(a2a::MODEL.to_string(), Value::String(model.clone()));
-            attrs.insert(a2a::FUNCTION_NAME.to_string(), Value::String(function_name.clone()));
-            attrs.insert(a2a::METADATA.to_string(), metadata.clone());
+            let attrs = AttrBuilder::for_event(event)
+                .client(client)
+                .model(model)
+                .function_name(function_name)
+                .metadata(metadata)
+                .build();
             let start_time_ms = Some(event.timestamp_ms());
 
             doc.insert_activity(
@@ -132,8 +308,7 @@ fn normalize_event_with_registry(
             );
 
             let prompt_id = llm_prompt_entity_id(event.id());
-            let mut prompt_attrs = base_attrs(event);
-            prompt_attrs.insert(a2a::PROMPT.to_string(), prompt.clone());
+            let prompt_attrs = AttrBuilder::for_event(event).prompt(prompt).build();
             doc.insert_entity(
                 prompt_id.clone(),
                 Entity { prov_type: Some(prov_type::<LlmPromptEntityId>()), attributes: prompt_attrs },
@@ -146,14 +321,16 @@ fn normalize_event_with_registry(
                     &activity_id,
                     message_id,
                     &mut derived_relations,
-                );
+                    agents,
+                    &mut agent_labels,
+                )?;
             }
             attach_task_call_context(
                 &mut doc,
                 event,
                 &activity_id,
                 &mut derived_relations,
-                agent_registry,
+                agents,
                 &mut agent_labels,
             )?;
         }
@@ -169,37 +346,19 @@ fn normalize_event_with_registry(
             success,
         } => {
             let activity_id = llm_activity_id(event.id());
-            let mut attrs = base_attrs(event);
-            attrs.insert(a2a::CLIENT.to_string(), Value::String(client.clone()));
-            attrs.insert(a2a::MODEL.to_string(), Value::String(model.clone()));
-            attrs.insert(a2a::FUNCTION_NAME.to_string(), Value::String(function_name.clone()));
-            attrs.insert(a2a::METADATA.to_string(), metadata.clone());
-            match usage {
-                crate::events::LlmUsage::Known {
-                    prompt_tokens,
-                    completion_tokens,
-                    total_tokens,
-                } => {
-                    attrs.insert(
-                        a2a::USAGE_PROMPT_TOKENS.to_string(),
-                        Value::Number((*prompt_tokens).into()),
-                    );
-                    attrs.insert(
-                        a2a::USAGE_COMPLETION_TOKENS.to_string(),
-                        Value::Number((*completion_tokens).into()),
-                    );
-                    attrs.insert(
-                        a2a::USAGE_TOTAL_TOKENS.to_string(),
-                        Value::Number((*total_tokens).into()),
-                    );
-                }
-                crate::events::LlmUsage::Unknown => {}
-            }
-            attrs.insert(
-                a2a::DURATION_MS.to_string(),
-                Value::Number((*duration_ms).into()),
-            );
-            attrs.insert(a2a::SUCCESS.to_string(), Value::Bool(*success));
+            let attrs = AttrBuilder::for_event(event)
+                .client(client)
+                .model(model)
+                .function_name(function_name)
+                .metadata(metadata)
+                .stream_progress(metadata)
+                .output_validation(metadata)
+                .output_repair(metadata)
+                .error_details(metadata)
+                .usage(usage)
+                .duration_ms(*duration_ms)
+                .success(*success)
+                .build();
 
             doc.insert_activity(
                 activity_id.clone(),
@@ -210,10 +369,17 @@ fn normalize_event_with_registry(
                     attributes: attrs,
                 },
             );
+            if let Some(retried) = retried_llm_call(&mut doc, metadata, function_name) {
+                derived_relations.push(A2aDerivedRelation {
+                    relation: A2aRelationType::LlmRetryOf,
+                    from: ProvNodeRef::Activity(activity_id.clone()),
+                    to: ProvNodeRef::Activity(retried),
+                    attributes: derived_attrs(event),
+                });
+            }
 
             let prompt_id = llm_prompt_entity_id(event.id());
-            let mut prompt_attrs = base_attrs(event);
-            prompt_attrs.insert(a2a::PROMPT.to_string(), prompt.clone());
+            let prompt_attrs = AttrBuilder::for_event(event).prompt(prompt).build();
             doc.insert_entity(
                 prompt_id.clone(),
                 Entity { prov_type: Some(prov_type::<LlmPromptEntityId>()), attributes: prompt_attrs },
@@ -231,14 +397,16 @@ fn normalize_event_with_registry(
                     &activity_id,
                     message_id,
                     &mut derived_relations,
-                );
+                    agents,
+                    &mut agent_labels,
+                )?;
             }
             attach_task_call_context(
                 &mut doc,
                 event,
                 &activity_id,
                 &mut derived_relations,
-                agent_registry,
+                agents,
                 &mut agent_labels,
             )?;
         }
@@ -250,12 +418,11 @@ fn normalize_event_with_registry(
             metadata,
         } => {
             let activity_id = tool_activity_id(event.id());
-            let mut attrs = base_attrs(event);
-            attrs.insert(a2a::TOOL_NAME.to_string(), Value::String(tool_name.clone()));
-            if let Some(function_name) = function_name {
-                attrs.insert(a2a::FUNCTION_NAME.to_string(), Value::String(function_name.clone()));
-            }
-            attrs.insert(a2a::METADATA.to_string(), metadata.clone());
+            let attrs = AttrBuilder::for_event(event)
+                .tool_name(tool_name)
+                .maybe_function_name(function_name.as_deref())
+                .metadata(metadata)
+                .build();
             let start_time_ms = Some(event.timestamp_ms());
 
             doc.insert_activity(
@@ -269,8 +436,7 @@ fn normalize_event_with_registry(
             );
 
             let args_id = tool_args_entity_id(event.id());
-            let mut args_attrs = base_attrs(event);
-            args_attrs.insert(a2a::ARGS.to_string(), args.clone());
+            let args_attrs = AttrBuilder::for_event(event).args(args).build();
             doc.insert_entity(
                 args_id.clone(),
                 Entity { prov_type: Some(prov_type::<ToolArgsEntityId>()), attributes: args_attrs },
@@ -283,14 +449,16 @@ fn normalize_event_with_registry(
                     &activity_id,
                     message_id,
                     &mut derived_relations,
-                );
+                    agents,
+                    &mut agent_labels,
+                )?;
             }
             attach_task_call_context(
                 &mut doc,
                 event,
                 &activity_id,
                 &mut derived_relations,
-                agent_registry,
+                agents,
                 &mut agent_labels,
             )?;
         }
@@ -304,14 +472,15 @@ fn normalize_event_with_registry(
             success,
         } => {
             let activity_id = tool_activity_id(event.id());
-            let mut attrs = base_attrs(event);
-            attrs.insert(a2a::TOOL_NAME.to_string(), Value::String(tool_name.clone()));
-            if let Some(function_name) = function_name {
-                attrs.insert(a2a::FUNCTION_NAME.to_string(), Value::String(function_name.clone()));
-            }
-            attrs.insert(a2a::METADATA.to_string(), metadata.clone());
-            attrs.insert(a2a::DURATION_MS.to_string(), Value::Number((*duration_ms).into()));
-            attrs.insert(a2a::SUCCESS.to_string(), Value::Bool(*success));
+            let attrs = AttrBuilder::for_event(event)
+                .tool_name(tool_name)
+                .maybe_function_name(function_name.as_deref())
+                .metadata(metadata)
+                .retry_progress(metadata)
+                .error_details(metadata)
+                .duration_ms(*duration_ms)
+                .success(*success)
+                .build();
 
             doc.insert_activity(
                 activity_id.clone(),
@@ -324,8 +493,7 @@ fn normalize_event_with_registry(
             );
 
             let args_id = tool_args_entity_id(event.id());
-            let mut args_attrs = base_attrs(event);
-            args_attrs.insert(a2a::ARGS.to_string(), args.clone());
+            let args_attrs = AttrBuilder::for_event(event).args(args).build();
             doc.insert_entity(
                 args_id.clone(),
                 Entity { prov_type: Some(prov_type::<ToolArgsEntityId>()), attributes: args_attrs },
@@ -343,14 +511,16 @@ fn normalize_event_with_registry(
                     &activity_id,
                     message_id,
                     &mut derived_relations,
-                );
+                    agents,
+                    &mut agent_labels,
+                )?;
             }
             attach_task_call_context(
                 &mut doc,
                 event,
                 &activity_id,
                 &mut derived_relations,
-                agent_registry,
+                agents,
                 &mut agent_labels,
             )?;
         }
@@ -360,11 +530,9 @@ fn normalize_event_with_registry(
             agent_version,
             archive_path,
         } => {
-            agent_registry.insert(agent_id.as_str().to_string());
             // Create AgentArchive entity
             let archive_entity_id = archive_entity_id(archive_path);
-            let mut archive_attrs = base_attrs(event);
-            archive_attrs.insert(a2a::ARCHIVE_PATH.to_string(), Value::String(archive_path.clone()));
+            let archive_attrs = AttrBuilder::for_event(event).archive_path(archive_path).build();
             doc.insert_entity(
                 archive_entity_id.clone(),
                 Entity {
@@ -375,10 +543,11 @@ fn normalize_event_with_registry(
 
             // Create AgentBoot activity
             let boot_activity_id = boot_activity_id(agent_id);
-            let mut boot_attrs = base_attrs(event);
-            boot_attrs.insert(a2a::AGENT_ID.to_string(), Value::String(agent_id.as_str().to_string()));
-            boot_attrs.insert(a2a::AGENT_TYPE.to_string(), Value::String(agent_type.as_str().to_string()));
-            boot_attrs.insert(a2a::AGENT_VERSION.to_string(), Value::String(agent_version.clone()));
+            let boot_attrs = AttrBuilder::for_event(event)
+                .agent_id(agent_id)
+                .agent_type(agent_type.as_str())
+                .agent_version(agent_version)
+                .build();
             doc.insert_activity(
                 boot_activity_id.clone(),
                 Activity {
@@ -394,10 +563,11 @@ fn normalize_event_with_registry(
 
             // Create AgentRuntimeInstance agent
             let instance_agent_id = agent_runtime_instance_id(agent_id);
-            let mut instance_attrs = base_attrs(event);
-            instance_attrs.insert(a2a::AGENT_ID.to_string(), Value::String(agent_id.as_str().to_string()));
-            instance_attrs.insert(a2a::AGENT_TYPE.to_string(), Value::String(agent_type.as_str().to_string()));
-            instance_attrs.insert(a2a::AGENT_VERSION.to_string(), Value::String(agent_version.clone()));
+            let instance_attrs = AttrBuilder::for_event(event)
+                .agent_id(agent_id)
+                .agent_type(agent_type.as_str())
+                .agent_version(agent_version)
+                .build();
             doc.insert_agent(
                 instance_agent_id.clone(),
                 Agent {
@@ -461,7 +631,7 @@ fn normalize_event_with_registry(
                 Some(event.timestamp_ms()),
                 None,
                 agent_type.as_deref(),
-                agent_registry,
+                agents,
                 &mut agent_labels,
             )?;
             insert_was_generated_by(
@@ -471,7 +641,7 @@ fn normalize_event_with_registry(
                 Some(event.timestamp_ms()),
             );
 
-            let agent_instance_id = get_agent_runtime_instance(&doc, agent_id, agent_registry, &mut agent_labels)?;
+            let agent_instance_id = get_agent_runtime_instance(&doc, agent_id, agents, &mut agent_labels)?;
             insert_was_associated_with(
                 &mut doc,
                 task_execution.clone(),
@@ -498,7 +668,7 @@ fn normalize_event_with_registry(
                 None,
                 is_terminal.then_some(event.timestamp_ms()),
                 None,
-                agent_registry,
+                agents,
                 &mut agent_labels,
             )?;
             let status_id = task_state_entity_id(task_id, event.timestamp_ms());
@@ -562,6 +732,32 @@ fn normalize_event_with_registry(
                 });
             }
         }
+        ProvEventData::IllegalTaskTransition { task_id, from_status, to_status, rejected } => {
+            // Recorded as an attempted state hanging off the task rather than a step in the
+            // execution, so the task's state history stays a chain of states it really held.
+            let task_entity = ensure_task_entity(&mut doc, task_id, event.context_id(), None);
+            let status_id = task_state_entity_id(task_id, event.timestamp_ms());
+            let mut status_attrs = base_attrs(event);
+            status_attrs.insert(
+                a2a::TASK_STATE_TIME.to_string(),
+                Value::Number(event.timestamp_ms().into()),
+            );
+            status_attrs.insert(a2a::TASK_STATE.to_string(), Value::String(to_status.clone()));
+            status_attrs.insert(a2a::OLD_STATUS.to_string(), Value::String(from_status.clone()));
+            status_attrs.insert(a2a::ILLEGAL_TRANSITION.to_string(), Value::Bool(true));
+            status_attrs.insert(a2a::TRANSITION_REJECTED.to_string(), Value::Bool(*rejected));
+            doc.insert_entity(
+                status_id.clone(),
+                Entity { prov_type: Some(prov_type::<TaskStateEntityId>()), attributes: status_attrs },
+            );
+            insert_was_derived_from(
+                &mut doc,
+                status_id,
+                task_entity,
+                None,
+                Some(a2a_relation_types::ILLEGAL_TRANSITION.to_string()),
+            );
+        }
         ProvEventData::TaskArtifactGenerated { task_id, artifact_id, artifact_type } => {
             let task_entity = ensure_task_entity(&mut doc, task_id, event.context_id(), None);
             let task_execution = ensure_task_execution_activity(
@@ -571,7 +767,7 @@ fn normalize_event_with_registry(
                 None,
                 None,
                 None,
-                agent_registry,
+                agents,
                 &mut agent_labels,
             )?;
             let artifact_id_str =
@@ -640,10 +836,11 @@ fn normalize_event_with_registry(
             );
 
             let processing_id = message_processing_activity_id(id);
-            let mut processing_attrs = base_attrs(event);
-            processing_attrs.insert(a2a::MESSAGE_ID.to_string(), Value::String(id.as_str().to_string()));
-            processing_attrs.insert(a2a::DIRECTION.to_string(), Value::String(direction.to_string()));
-            processing_attrs.insert(a2a::ROLE.to_string(), Value::String(role.clone()));
+            let processing_attrs = AttrBuilder::for_event(event)
+                .message_id(id)
+                .direction(direction)
+                .role(role)
+                .build();
             doc.insert_activity(
                 processing_id.clone(),
                 Activity {
@@ -671,13 +868,27 @@ fn normalize_event_with_registry(
                 });
             };
             
-            let executing_agent_id = get_agent_runtime_instance(&doc, &agent_id, agent_registry, &mut agent_labels)?;
+            let executing_agent_id = get_agent_runtime_instance(&doc, &agent_id, agents, &mut agent_labels)?;
             insert_was_associated_with(
                 &mut doc,
                 processing_id.clone(),
-                executing_agent_id,
+                executing_agent_id.clone(),
                 Some(prov_roles::EXECUTING_AGENT.to_string()),
             );
+            // A message sent by another agent's A2A call: the receiving agent
+            // handles it for the caller
+            insert_delegation(
+                &mut doc,
+                event,
+                executing_agent_id,
+                &processing_id,
+                metadata
+                    .as_ref()
+                    .and_then(|metadata| metadata.get(CALLING_AGENT_ID_METADATA_KEY))
+                    .map(String::as_str),
+                agents,
+                &mut agent_labels,
+            )?;
 
             let invoking_agent_id = runner_runtime_instance_id();
             ensure_runner_runtime_instance(&mut doc);
@@ -734,7 +945,7 @@ fn normalize_event_with_registry(
                     None,
                     None,
                     None,
-                    agent_registry,
+                    agents,
                     &mut agent_labels,
                 )?;
                 if matches!(event.data(), ProvEventData::MessageReceived { .. }) {
@@ -755,9 +966,364 @@ fn normalize_event_with_registry(
                 });
             }
         }
+        ProvEventData::ContextForked { parent_context_id, forked_at_message_id } => {
+            let branch = ensure_context_entity(&mut doc, event.context_id());
+            let parent = ensure_context_entity(&mut doc, parent_context_id);
+            if let Some(entity) = doc.entity(&branch) {
+                let mut attrs = entity.attributes.clone();
+                attrs.insert(
+                    a2a::PARENT_CONTEXT_ID.to_string(),
+                    Value::String(parent_context_id.as_str().to_string()),
+                );
+                attrs.insert(
+                    a2a::FORKED_AT_MESSAGE_ID.to_string(),
+                    Value::String(forked_at_message_id.as_str().to_string()),
+                );
+                let prov_type = entity.prov_type.clone();
+                doc.insert_entity(branch.clone(), Entity { prov_type, attributes: attrs });
+            }
+            let mut attrs = derived_attrs(event);
+            attrs.insert(
+                a2a::FORKED_AT_MESSAGE_ID.to_string(),
+                Value::String(forked_at_message_id.as_str().to_string()),
+            );
+            derived_relations.push(A2aDerivedRelation {
+                relation: A2aRelationType::ContextDerivedFrom,
+                from: ProvNodeRef::Entity(branch),
+                to: ProvNodeRef::Entity(parent),
+                attributes: attrs,
+            });
+        }
+        ProvEventData::ContextNested { parent_context_id } => {
+            let child = ensure_context_entity(&mut doc, event.context_id());
+            let parent = ensure_context_entity(&mut doc, parent_context_id);
+            if let Some(entity) = doc.entity(&child) {
+                let mut attrs = entity.attributes.clone();
+                attrs.insert(
+                    a2a::PARENT_CONTEXT_ID.to_string(),
+                    Value::String(parent_context_id.as_str().to_string()),
+                );
+                let prov_type = entity.prov_type.clone();
+                doc.insert_entity(child.clone(), Entity { prov_type, attributes: attrs });
+            }
+            derived_relations.push(A2aDerivedRelation {
+                relation: A2aRelationType::ContextContainedIn,
+                from: ProvNodeRef::Entity(child),
+                to: ProvNodeRef::Entity(parent),
+                attributes: derived_attrs(event),
+            });
+        }
+        ProvEventData::FeedbackSubmitted {
+            feedback_id,
+            target,
+            rating,
+            correction,
+            comment,
+            metadata,
+        } => {
+            let feedback_entity = feedback_entity_id(feedback_id);
+            let mut feedback_attrs = base_attrs(event);
+            feedback_attrs.insert(a2a::FEEDBACK_ID.to_string(), Value::String(feedback_id.clone()));
+            if let Some(rating) = rating {
+                feedback_attrs.insert(a2a::RATING.to_string(), Value::Number((*rating).into()));
+            }
+            if let Some(correction) = correction {
+                feedback_attrs.insert(a2a::CORRECTION.to_string(), Value::String(correction.clone()));
+            }
+            if let Some(comment) = comment {
+                feedback_attrs.insert(a2a::COMMENT.to_string(), Value::String(comment.clone()));
+            }
+            if let Some(metadata) = metadata {
+                feedback_attrs.insert(a2a::METADATA.to_string(), map_string_map(metadata));
+            }
+            let rated_entity = match target {
+                FeedbackTarget::Message { message_id } => {
+                    feedback_attrs.insert(
+                        a2a::MESSAGE_ID.to_string(),
+                        Value::String(message_id.as_str().to_string()),
+                    );
+                    // Stub the rated message so the edge MERGE resolves to the typed node;
+                    // omit event attributes so the original message event is not overwritten.
+                    let rated = message_entity_id(message_id);
+                    let mut rated_attrs = HashMap::new();
+                    rated_attrs.insert(
+                        a2a::MESSAGE_ID.to_string(),
+                        Value::String(message_id.as_str().to_string()),
+                    );
+                    doc.insert_entity(
+                        rated.clone(),
+                        Entity { prov_type: Some(prov_type::<MessageEntityId>()), attributes: rated_attrs },
+                    );
+                    rated
+                }
+                FeedbackTarget::Task { task_id } => {
+                    ensure_task_entity(&mut doc, task_id, event.context_id(), None)
+                }
+            };
+            doc.insert_entity(
+                feedback_entity.clone(),
+                Entity { prov_type: Some(prov_type::<FeedbackEntityId>()), attributes: feedback_attrs },
+            );
+            insert_was_derived_from(
+                &mut doc,
+                feedback_entity,
+                rated_entity,
+                None,
+                Some(a2a_relation_types::FEEDBACK.to_string()),
+            );
+        }
+        ProvEventData::BudgetExhausted { scope, scope_id, dimension, limit, spent } => {
+            let exhaustion_id = budget_exhaustion_entity_id(event.id());
+            let attrs = AttrBuilder::for_event(event)
+                .attr(a2a::BUDGET_SCOPE, scope.as_str())
+                .attr(a2a::BUDGET_SCOPE_ID, scope_id.as_str())
+                .attr(a2a::BUDGET_DIMENSION, dimension.as_str())
+                .attr(a2a::BUDGET_LIMIT, *limit)
+                .usage(spent)
+                .build();
+            doc.insert_entity(
+                exhaustion_id.clone(),
+                Entity {
+                    prov_type: Some(prov_type::<BudgetExhaustionEntityId>()),
+                    attributes: attrs,
+                },
+            );
+            // Hang the exhaustion off the task or conversation that was running when the
+            // budget tripped, whichever scope the budget itself was kept for.
+            let exhausted_by = match event.task_id() {
+                Some(task_id) => ensure_task_entity(&mut doc, task_id, event.context_id(), None),
+                None => ensure_context_entity(&mut doc, event.context_id()),
+            };
+            insert_was_derived_from(
+                &mut doc,
+                exhaustion_id,
+                exhausted_by,
+                None,
+                Some(a2a_relation_types::BUDGET_EXHAUSTION.to_string()),
+            );
+        }
+        ProvEventData::ConsoleMessage { level, message } => {
+            let diagnostic_id = diagnostic_entity_id(event.id());
+            let attrs = AttrBuilder::for_event(event)
+                .attr(a2a::LOG_LEVEL, level.as_str())
+                .attr(a2a::LOG_MESSAGE, message.as_str())
+                .build();
+            doc.insert_entity(
+                diagnostic_id.clone(),
+                Entity { prov_type: Some(prov_type::<DiagnosticEntityId>()), attributes: attrs },
+            );
+            let logged_during = match event.task_id() {
+                Some(task_id) => ensure_task_entity(&mut doc, task_id, event.context_id(), None),
+                None => ensure_context_entity(&mut doc, event.context_id()),
+            };
+            insert_was_derived_from(
+                &mut doc,
+                diagnostic_id,
+                logged_during,
+                None,
+                Some(a2a_relation_types::DIAGNOSTIC.to_string()),
+            );
+        }
+        ProvEventData::ConfigChanged { version, digest, previous_digest, changed, trigger } => {
+            let config_id = runtime_config_entity_id(digest);
+            let attrs = AttrBuilder::for_event(event)
+                .attr(a2a::CONFIG_VERSION, *version)
+                .attr(a2a::CONFIG_DIGEST, digest.as_str())
+                .attr(a2a::CONFIG_CHANGED, changed.clone())
+                .attr(a2a::CONFIG_TRIGGER, trigger.as_str())
+                .build();
+            doc.insert_entity(
+                config_id.clone(),
+                Entity { prov_type: Some(prov_type::<RuntimeConfigEntityId>()), attributes: attrs },
+            );
+            if let Some(previous_digest) = previous_digest {
+                insert_was_derived_from(
+                    &mut doc,
+                    config_id,
+                    runtime_config_entity_id(previous_digest),
+                    None,
+                    Some(a2a_relation_types::CONFIG_REVISION.to_string()),
+                );
+            }
+        }
+        ProvEventData::SchemaChanged { agent_id, digest, previous_digest, added, removed, changed } => {
+            let schema_id = baml_schema_entity_id(agent_id, digest);
+            let attrs = AttrBuilder::for_event(event)
+                .attr(a2a::AGENT_ID, agent_id.as_str())
+                .attr(a2a::SCHEMA_DIGEST, digest.as_str())
+                .attr(a2a::SCHEMA_ADDED, added.clone())
+                .attr(a2a::SCHEMA_REMOVED, removed.clone())
+                .attr(a2a::SCHEMA_CHANGED, changed.clone())
+                .build();
+            doc.insert_entity(
+                schema_id.clone(),
+                Entity { prov_type: Some(prov_type::<BamlSchemaEntityId>()), attributes: attrs },
+            );
+            if let Some(previous_digest) = previous_digest {
+                insert_was_derived_from(
+                    &mut doc,
+                    schema_id,
+                    baml_schema_entity_id(agent_id, previous_digest),
+                    None,
+                    Some(a2a_relation_types::SCHEMA_REVISION.to_string()),
+                );
+            }
+        }
+        ProvEventData::ToolUsageSummarized {
+            agent_id,
+            tool_name,
+            invocations,
+            failures,
+            p50_ms,
+            p95_ms,
+            p99_ms,
+        } => {
+            let failure_rate =
+                if *invocations == 0 { 0.0 } else { *failures as f64 / *invocations as f64 };
+            let attrs = AttrBuilder::for_event(event)
+                .agent_id(agent_id)
+                .tool_name(tool_name)
+                .timestamp_ms(event.timestamp_ms())
+                .attr(a2a::TOOL_INVOCATIONS, *invocations)
+                .attr(a2a::TOOL_FAILURES, *failures)
+                .attr(a2a::TOOL_FAILURE_RATE, failure_rate)
+                .attr(a2a::TOOL_LATENCY_P50_MS, *p50_ms)
+                .attr(a2a::TOOL_LATENCY_P95_MS, *p95_ms)
+                .attr(a2a::TOOL_LATENCY_P99_MS, *p99_ms)
+                .build();
+            doc.insert_entity(
+                tool_usage_entity_id(agent_id, tool_name),
+                Entity { prov_type: Some(prov_type::<ToolUsageEntityId>()), attributes: attrs },
+            );
+        }
+        ProvEventData::PolicyEvaluated { policy_id, effect, target, subject, reason } => {
+            let decision_id = policy_decision_entity_id(event.id());
+            let mut attrs = AttrBuilder::for_event(event)
+                .attr(a2a::POLICY_ID, policy_id.as_str())
+                .attr(a2a::POLICY_EFFECT, effect.as_str())
+                .attr(a2a::POLICY_TARGET, target.as_str())
+                .attr(a2a::POLICY_SUBJECT, subject.as_str());
+            if let Some(reason) = reason {
+                attrs = attrs.attr(a2a::POLICY_REASON, reason.as_str());
+            }
+            doc.insert_entity(
+                decision_id.clone(),
+                Entity {
+                    prov_type: Some(prov_type::<PolicyDecisionEntityId>()),
+                    attributes: attrs.build(),
+                },
+            );
+            let decided_during = match event.task_id() {
+                Some(task_id) => ensure_task_entity(&mut doc, task_id, event.context_id(), None),
+                None => ensure_context_entity(&mut doc, event.context_id()),
+            };
+            insert_was_derived_from(
+                &mut doc,
+                decision_id,
+                decided_during,
+                None,
+                Some(a2a_relation_types::POLICY_DECISION.to_string()),
+            );
+        }
+        ProvEventData::ApprovalDecided {
+            approval_id,
+            tool_name,
+            args,
+            verdict,
+            approver,
+            reason,
+            waited_ms,
+        } => {
+            let decision_id = approval_decision_entity_id(approval_id);
+            let mut attrs = AttrBuilder::for_event(event)
+                .attr(a2a::APPROVAL_ID, approval_id.as_str())
+                .tool_name(tool_name)
+                .args(args)
+                .attr(a2a::APPROVAL_VERDICT, verdict.as_str())
+                .attr(a2a::APPROVAL_WAITED_MS, *waited_ms);
+            if let Some(approver) = approver {
+                attrs = attrs.attr(a2a::APPROVER, approver.as_str());
+            }
+            if let Some(reason) = reason {
+                attrs = attrs.attr(a2a::APPROVAL_REASON, reason.as_str());
+            }
+            doc.insert_entity(
+                decision_id.clone(),
+                Entity {
+                    prov_type: Some(prov_type::<ApprovalDecisionEntityId>()),
+                    attributes: attrs.build(),
+                },
+            );
+            let decided_during = match event.task_id() {
+                Some(task_id) => ensure_task_entity(&mut doc, task_id, event.context_id(), None),
+                None => ensure_context_entity(&mut doc, event.context_id()),
+            };
+            insert_was_derived_from(
+                &mut doc,
+                decision_id,
+                decided_during,
+                None,
+                Some(a2a_relation_types::APPROVAL_DECISION.to_string()),
+            );
+        }
     }
 
-    Ok(NormalizedProv { document: doc, derived_relations, agent_labels })
+    Ok(NormalizedProv { document: doc, derived_relations, agent_labels, activity_types: HashMap::new() })
+}
+
+/// The call activity `event` added, if it is a call event.
+fn call_activity(event: &ProvEvent, doc: &ProvDocument) -> Option<CallActivity> {
+    let activity = match event.data() {
+        ProvEventData::LlmCallStarted { .. } | ProvEventData::LlmCallCompleted { .. } => {
+            llm_activity_id(event.id())
+        }
+        ProvEventData::ToolCallStarted { .. } | ProvEventData::ToolCallCompleted { .. } => {
+            tool_activity_id(event.id())
+        }
+        _ => return None,
+    };
+    let prov_type = doc.activity(&activity).and_then(|activity| activity.prov_type.clone());
+    Some(CallActivity { activity, prov_type })
+}
+
+fn has_executing_agent(doc: &ProvDocument, call: &CallActivity) -> bool {
+    doc.was_associated_with().any(|(_, assoc)| {
+        assoc.activity == call.activity && assoc.role.as_deref() == Some(prov_roles::EXECUTING_AGENT)
+    })
+}
+
+/// Link `call` to the call completed before it in its task, which was added
+/// to an earlier document.
+fn insert_was_informed_by(normalized: &mut NormalizedProv, call: &CallActivity, informant: CallActivity) {
+    if let Some(prov_type) = informant.prov_type {
+        normalized.activity_types.entry(informant.activity.as_str().to_string()).or_insert(prov_type);
+    }
+    let doc = &mut normalized.document;
+    let id = doc.relation_id("inf", call.activity.as_str(), informant.activity.as_str());
+    doc.insert_was_informed_by(id, WasInformedBy { informed: call.activity.clone(), informant: informant.activity });
+}
+
+/// Associate calls normalized before their agent was known with it, in the
+/// document of the event that bound them.
+fn backfill_executing_agent(normalized: &mut NormalizedProv, backfill: Backfill) {
+    let agent = agent_runtime_instance_id(&backfill.agent_id);
+    if normalized.document.agent(&agent).is_none() {
+        normalized
+            .agent_labels
+            .entry(agent.as_str().to_string())
+            .or_insert_with(|| "AgentRuntimeInstance".to_string());
+    }
+    for call in backfill.calls {
+        if let Some(prov_type) = call.prov_type {
+            normalized.activity_types.entry(call.activity.as_str().to_string()).or_insert(prov_type);
+        }
+        insert_was_associated_with(
+            &mut normalized.document,
+            call.activity,
+            agent.clone(),
+            Some(prov_roles::EXECUTING_AGENT.to_string()),
+        );
+    }
 }
 
 pub fn validate_event(event: &ProvEvent) -> Result<()> {
@@ -770,6 +1336,14 @@ pub fn validate_event(event: &ProvEvent) -> Result<()> {
         | ProvEventData::ToolCallCompleted { scope, .. } => {
             validate_call_scope(event, scope, "tool call")?;
         }
+        ProvEventData::FeedbackSubmitted { rating, correction, comment, .. } => {
+            if rating.is_none() && correction.is_none() && comment.is_none() {
+                return Err(ProvenanceError::InvalidEvent {
+                    event_id: event.id().as_str().to_string(),
+                    reason: "feedback requires a rating, correction, or comment".to_string(),
+                });
+            }
+        }
         _ => {}
     }
     Ok(())
@@ -805,35 +1379,11 @@ fn validate_call_scope(
 }
 
 fn base_attrs(event: &ProvEvent) -> HashMap<String, Value> {
-    let mut attrs = HashMap::new();
-    attrs.insert(
-        a2a::CONTEXT_ID.to_string(),
-        Value::String(event.context_id().as_str().to_string()),
-    );
-    attrs.insert(
-        a2a::EVENT_ID.to_string(),
-        Value::String(event.id().as_str().to_string()),
-    );
-    if let Some(task_id) = event.task_id() {
-        attrs.insert(a2a::TASK_ID.to_string(), Value::String(task_id.as_str().to_string()));
-    }
-    attrs
+    AttrBuilder::for_event(event).build()
 }
 
 fn derived_attrs(event: &ProvEvent) -> HashMap<String, Value> {
-    let mut attrs = HashMap::new();
-    attrs.insert(
-        a2a::CONTEXT_ID.to_string(),
-        Value::String(event.context_id().as_str().to_string()),
-    );
-    if let Some(task_id) = event.task_id() {
-        attrs.insert(a2a::TASK_ID.to_string(), Value::String(task_id.as_str().to_string()));
-    }
-    attrs.insert(
-        a2a::TIMESTAMP_MS.to_string(),
-        Value::Number(event.timestamp_ms().into()),
-    );
-    attrs
+    AttrBuilder::for_derived(event).build()
 }
 
 fn ensure_task_entity(
@@ -863,6 +1413,23 @@ fn ensure_task_entity(
     id
 }
 
+fn ensure_context_entity(doc: &mut ProvDocument, context_id: &ContextId) -> ProvEntityId {
+    let id = context_entity_id(context_id);
+    let mut attrs = doc
+        .entity(&id)
+        .map(|entity| entity.attributes.clone())
+        .unwrap_or_default();
+    attrs.insert(
+        a2a::CONTEXT_ID.to_string(),
+        Value::String(context_id.as_str().to_string()),
+    );
+    doc.insert_entity(
+        id.clone(),
+        Entity { prov_type: Some(prov_type::<ContextEntityId>()), attributes: attrs },
+    );
+    id
+}
+
 #[allow(clippy::too_many_arguments)]
 fn ensure_task_execution_activity(
     doc: &mut ProvDocument,
@@ -871,7 +1438,7 @@ fn ensure_task_execution_activity(
     start_time_ms: Option<u64>,
     end_time_ms: Option<u64>,
     _agent_type: Option<&str>,
-    agent_registry: &std::collections::HashSet<String>,
+    agents: &KnownAgents<'_>,
     agent_labels: &mut HashMap<String, String>,
 ) -> Result<ProvActivityId> {
     let id = task_execution_activity_id(task_id);
@@ -888,8 +1455,9 @@ fn ensure_task_execution_activity(
         a2a::CONTEXT_ID.to_string(),
         Value::String(context_id.as_str().to_string()),
     );
-    // Extract agent_id from task entity - optional, may not be set yet if TaskCreated hasn't been processed
-    let agent_id = task_agent_id(doc, task_id);
+    // Extract agent_id from task entity, falling back to the context's binding;
+    // neither is set when TaskCreated hasn't been processed yet
+    let agent_id = task_agent_id(doc, task_id).or_else(|| agents.task_agent(task_id));
     
     // Look up agent_type from runtime instance agent for display purposes
     if let Some(ref agent_id) = agent_id {
@@ -914,12 +1482,12 @@ fn ensure_task_execution_activity(
         },
     );
     // Associate with agent if available - if not, association will be added when TaskCreated is processed
-    associate_task_execution_agents(doc, &id, agent_id.as_ref(), agent_registry, agent_labels)?;
+    associate_task_execution_agents(doc, &id, agent_id.as_ref(), agents, agent_labels)?;
     Ok(id)
 }
 
 fn insert_used(doc: &mut ProvDocument, activity: ProvActivityId, entity: ProvEntityId, role: Option<String>) {
-    let id = doc.blank_node_id("u");
+    let id = doc.relation_id("u", activity.as_str(), entity.as_str());
     doc.insert_used(id, Used { activity, entity, role });
 }
 
@@ -929,7 +1497,7 @@ fn insert_was_generated_by(
     activity: ProvActivityId,
     time_ms: Option<u64>,
 ) {
-    let id = doc.blank_node_id("g");
+    let id = doc.relation_id("g", entity.id(), activity.as_str());
     doc.insert_was_generated_by(id, WasGeneratedBy { entity, activity, time_ms });
 }
 
@@ -939,7 +1507,7 @@ fn insert_qualified_generation(
     activity: ProvActivityId,
     time_ms: Option<u64>,
 ) {
-    let id = doc.blank_node_id("gen");
+    let id = doc.relation_id("gen", entity.id(), activity.as_str());
     doc.insert_qualified_generation(id, QualifiedGeneration { entity, activity, time_ms });
 }
 
@@ -949,10 +1517,67 @@ fn insert_was_associated_with(
     agent: ProvAgentId,
     role: Option<String>,
 ) {
-    let id = doc.blank_node_id("assoc");
+    let id = doc.relation_id("assoc", activity.as_str(), agent.as_str());
     doc.insert_was_associated_with(id, WasAssociatedWith { activity, agent, role });
 }
 
+/// Metadata key naming the agent whose A2A call led to this message or call.
+pub const CALLING_AGENT_ID_METADATA_KEY: &str = "calling_agent_id";
+
+/// Record that `delegate` ran `activity` for the agent in `calling_agent_id`.
+///
+/// The calling agent has to be known here, booted in this tenant or seen in
+/// this context; a caller outside the runner has no node to point at and the
+/// delegation is left out.
+fn insert_delegation(
+    doc: &mut ProvDocument,
+    event: &ProvEvent,
+    delegate: ProvAgentId,
+    activity: &ProvActivityId,
+    calling_agent_id: Option<&str>,
+    agents: &KnownAgents<'_>,
+    agent_labels: &mut HashMap<String, String>,
+) -> Result<()> {
+    let Some(raw) = calling_agent_id else {
+        return Ok(());
+    };
+    let calling_agent_id = parse_agent_id(event, raw)?;
+    let responsible = match get_agent_runtime_instance(doc, &calling_agent_id, agents, agent_labels) {
+        Ok(responsible) => responsible,
+        Err(ProvenanceError::MissingLabel { .. }) => {
+            tracing::debug!(
+                event_id = event.id().as_str(),
+                calling_agent_id = raw,
+                "Calling agent is not known to the runner; skipping delegation"
+            );
+            return Ok(());
+        }
+        Err(err) => return Err(err),
+    };
+    if responsible == delegate {
+        return Ok(());
+    }
+    let id = doc.relation_id("del", delegate.as_str(), responsible.as_str());
+    doc.insert_acted_on_behalf_of(
+        id,
+        ActedOnBehalfOf { delegate, responsible, activity: Some(activity.clone()) },
+    );
+    Ok(())
+}
+
+/// `calling_agent_id` from an LLM or tool call's metadata.
+fn call_calling_agent_id(event: &ProvEvent) -> Option<&str> {
+    match event.data() {
+        ProvEventData::LlmCallStarted { metadata, .. }
+        | ProvEventData::LlmCallCompleted { metadata, .. }
+        | ProvEventData::ToolCallStarted { metadata, .. }
+        | ProvEventData::ToolCallCompleted { metadata, .. } => {
+            metadata.get(CALLING_AGENT_ID_METADATA_KEY).and_then(Value::as_str)
+        }
+        _ => None,
+    }
+}
+
 fn insert_was_derived_from(
     doc: &mut ProvDocument,
     generated_entity: ProvEntityId,
@@ -960,7 +1585,7 @@ fn insert_was_derived_from(
     activity: Option<ProvActivityId>,
     prov_type: Option<String>,
 ) {
-    let id = doc.blank_node_id("d");
+    let id = doc.relation_id("d", generated_entity.as_str(), used_entity.as_str());
     doc.insert_was_derived_from(id, WasDerivedFrom { generated_entity, used_entity, activity, prov_type });
 }
 
@@ -1017,13 +1642,13 @@ fn runner_runtime_instance_id() -> ProvAgentId {
 fn get_agent_runtime_instance(
     doc: &ProvDocument,
     agent_id: &AgentId,
-    agent_registry: &std::collections::HashSet<String>,
+    agents: &KnownAgents<'_>,
     agent_labels: &mut HashMap<String, String>,
 ) -> Result<ProvAgentId> {
     let instance_id = agent_runtime_instance_id(agent_id);
     if doc.agent(&instance_id).is_some() {
         Ok(instance_id)
-    } else if agent_registry.contains(agent_id.as_str()) {
+    } else if agents.contains(agent_id) {
         agent_labels
             .entry(instance_id.as_str().to_string())
             .or_insert_with(|| "AgentRuntimeInstance".to_string());
@@ -1063,6 +1688,50 @@ fn message_processing_activity_id(message_id: &MessageId) -> ProvActivityId {
     })
 }
 
+/// Budget exhaustion entity id: derived from the `EventId` that recorded it.
+fn budget_exhaustion_entity_id(event_id: &EventId) -> ProvEntityId {
+    ProvEntityId::derived::<BudgetExhaustionEntityId>(BudgetExhaustionEntityInput { event_id })
+}
+
+/// Diagnostic entity id: derived from the `EventId` of the console line.
+fn diagnostic_entity_id(event_id: &EventId) -> ProvEntityId {
+    ProvEntityId::derived::<DiagnosticEntityId>(DiagnosticEntityInput { event_id })
+}
+
+/// Runtime config entity id: derived from the digest of the applied config.
+fn runtime_config_entity_id(digest: &str) -> ProvEntityId {
+    ProvEntityId::derived::<RuntimeConfigEntityId>(RuntimeConfigEntityInput { digest })
+}
+
+/// BAML schema entity id: derived from the agent and the digest of its sources.
+fn baml_schema_entity_id(agent_id: &AgentId, digest: &str) -> ProvEntityId {
+    ProvEntityId::derived::<BamlSchemaEntityId>(BamlSchemaEntityInput { agent_id, digest })
+}
+
+/// Tool usage entity id: derived from the agent and the tool it called.
+fn tool_usage_entity_id(agent_id: &AgentId, tool_name: &str) -> ProvEntityId {
+    ProvEntityId::derived::<ToolUsageEntityId>(ToolUsageEntityInput { agent_id, tool_name })
+}
+
+fn policy_decision_entity_id(event_id: &EventId) -> ProvEntityId {
+    ProvEntityId::derived::<PolicyDecisionEntityId>(PolicyDecisionEntityInput { event_id })
+}
+
+/// Approval decision entity id: one per call held for approval.
+fn approval_decision_entity_id(approval_id: &str) -> ProvEntityId {
+    ProvEntityId::derived::<ApprovalDecisionEntityId>(ApprovalDecisionEntityInput { approval_id })
+}
+
+/// Context entity id: derived from `ContextId`, one node per conversation branch.
+fn context_entity_id(context_id: &ContextId) -> ProvEntityId {
+    ProvEntityId::derived::<ContextEntityId>(ContextEntityInput { context_id })
+}
+
+/// Feedback entity id: derived from the client-visible feedback id.
+fn feedback_entity_id(feedback_id: &str) -> ProvEntityId {
+    ProvEntityId::derived::<FeedbackEntityId>(FeedbackEntityInput { feedback_id })
+}
+
 fn ensure_message_processing_activity(
     doc: &mut ProvDocument,
     context_id: &ContextId,
@@ -1093,13 +1762,44 @@ fn ensure_message_processing_activity(
     id
 }
 
+/// Stub the attempt an output-repair retry re-ran, as recorded under
+/// `metadata.output_repair.retry_of`, so the retry edge resolves to the typed node.
+/// Only the attributes the attempt's own event also wrote are set.
+fn retried_llm_call(
+    doc: &mut ProvDocument,
+    metadata: &Value,
+    function_name: &str,
+) -> Option<ProvActivityId> {
+    let retry_of = metadata.get(OUTPUT_REPAIR_METADATA_KEY)?.get("retry_of")?;
+    let event_id: EventId = serde_json::from_value(retry_of.get("event_id")?.clone()).ok()?;
+    let client = retry_of.get("client")?.as_str()?;
+    let model = retry_of.get("model")?.as_str()?;
+    let id = llm_activity_id(&event_id);
+    let mut attrs = HashMap::new();
+    attrs.insert(a2a::CLIENT.to_string(), Value::String(client.to_string()));
+    attrs.insert(a2a::MODEL.to_string(), Value::String(model.to_string()));
+    attrs.insert(a2a::FUNCTION_NAME.to_string(), Value::String(function_name.to_string()));
+    doc.insert_activity(
+        id.clone(),
+        Activity {
+            start_time_ms: None,
+            end_time_ms: None,
+            prov_type: Some(prov_type::<LlmCallActivityId>()),
+            attributes: attrs,
+        },
+    );
+    Some(id)
+}
+
 fn attach_message_context(
     doc: &mut ProvDocument,
     event: &ProvEvent,
     activity_id: &ProvActivityId,
     message_id: &MessageId,
     derived_relations: &mut Vec<A2aDerivedRelation>,
-) {
+    agents: &KnownAgents<'_>,
+    agent_labels: &mut HashMap<String, String>,
+) -> Result<()> {
     let message_entity_id = message_entity_id(message_id);
     let mut message_attrs = base_attrs(event);
     message_attrs.insert(
@@ -1126,6 +1826,27 @@ fn attach_message_context(
         to: ProvNodeRef::Activity(activity_id.clone()),
         attributes: derived_attrs(event),
     });
+    // The agent that handled the message executes its calls; until its
+    // MessageReceived arrives the call stays unbound
+    if let Some(agent_id) = agents.message_agent(message_id) {
+        let executing_agent_id = get_agent_runtime_instance(doc, &agent_id, agents, agent_labels)?;
+        insert_was_associated_with(
+            doc,
+            activity_id.clone(),
+            executing_agent_id.clone(),
+            Some(prov_roles::EXECUTING_AGENT.to_string()),
+        );
+        insert_delegation(
+            doc,
+            event,
+            executing_agent_id,
+            activity_id,
+            call_calling_agent_id(event),
+            agents,
+            agent_labels,
+        )?;
+    }
+    Ok(())
 }
 
 fn attach_task_call_context(
@@ -1133,7 +1854,7 @@ fn attach_task_call_context(
     event: &ProvEvent,
     activity_id: &ProvActivityId,
     derived_relations: &mut Vec<A2aDerivedRelation>,
-    agent_registry: &std::collections::HashSet<String>,
+    agents: &KnownAgents<'_>,
     agent_labels: &mut HashMap<String, String>,
 ) -> Result<()> {
     let Some(task_id) = event.task_id() else {
@@ -1181,19 +1902,30 @@ fn attach_task_call_context(
         None,
         None,
         None,
-        agent_registry,
+        agents,
         agent_labels,
     )?;
     // Associate call with agent - use agent_id from metadata if available, otherwise try task entity
-    associate_call_with_agent(
+    let executing_agent_id = associate_call_with_agent(
         doc,
         event.context_id(),
         task_id,
         activity_id,
         agent_id_from_metadata.as_ref(),
-        agent_registry,
+        agents,
         agent_labels,
     )?;
+    if let Some(executing_agent_id) = executing_agent_id {
+        insert_delegation(
+            doc,
+            event,
+            executing_agent_id,
+            activity_id,
+            call_calling_agent_id(event),
+            agents,
+            agent_labels,
+        )?;
+    }
     derived_relations.push(A2aDerivedRelation {
         relation: A2aRelationType::TaskCall,
         from: ProvNodeRef::Activity(task_execution),
@@ -1217,14 +1949,14 @@ fn associate_task_execution_agents(
     doc: &mut ProvDocument,
     task_execution: &ProvActivityId,
     agent_id: Option<&AgentId>,
-    agent_registry: &std::collections::HashSet<String>,
+    agents: &KnownAgents<'_>,
     agent_labels: &mut HashMap<String, String>,
 ) -> Result<()> {
     let Some(agent_id) = agent_id else {
         return Ok(());
     };
 
-    let executing_agent_id = get_agent_runtime_instance(doc, agent_id, agent_registry, agent_labels)?;
+    let executing_agent_id = get_agent_runtime_instance(doc, agent_id, agents, agent_labels)?;
     insert_was_associated_with(
         doc,
         task_execution.clone(),
@@ -1249,32 +1981,36 @@ fn associate_call_with_agent(
     task_id: &TaskId,
     activity_id: &ProvActivityId,
     agent_id_from_metadata: Option<&AgentId>,
-    agent_registry: &std::collections::HashSet<String>,
+    agents: &KnownAgents<'_>,
     agent_labels: &mut HashMap<String, String>,
-) -> Result<()> {
-    // Try to get agent_id from metadata first, then from task entity
-    let agent_id = agent_id_from_metadata.cloned().or_else(|| {
-        let task_entity = task_entity_id(task_id);
-        doc.entity(&task_entity)
-            .and_then(|entity| entity.attributes.get(a2a::AGENT_ID))
-            .and_then(|v| v.as_str())
-            .and_then(|raw| UuidId::parse_str(raw).ok())
-            .map(AgentId::from_uuid)
-    });
+) -> Result<Option<ProvAgentId>> {
+    // Try to get agent_id from metadata first, then from task entity, then
+    // from the task's binding in the context
+    let agent_id = agent_id_from_metadata
+        .cloned()
+        .or_else(|| {
+            let task_entity = task_entity_id(task_id);
+            doc.entity(&task_entity)
+                .and_then(|entity| entity.attributes.get(a2a::AGENT_ID))
+                .and_then(|v| v.as_str())
+                .and_then(|raw| UuidId::parse_str(raw).ok())
+                .map(AgentId::from_uuid)
+        })
+        .or_else(|| agents.task_agent(task_id));
     
     // If agent_id is available, associate the call with the agent
     // If not, the association will be added when TaskCreated is processed
-    if let Some(agent_id) = agent_id {
-        let executing_agent_id =
-            get_agent_runtime_instance(doc, &agent_id, agent_registry, agent_labels)?;
-        insert_was_associated_with(
-            doc,
-            activity_id.clone(),
-            executing_agent_id,
-            Some(prov_roles::EXECUTING_AGENT.to_string()),
-        );
-    }
-    Ok(())
+    let Some(agent_id) = agent_id else {
+        return Ok(None);
+    };
+    let executing_agent_id = get_agent_runtime_instance(doc, &agent_id, agents, agent_labels)?;
+    insert_was_associated_with(
+        doc,
+        activity_id.clone(),
+        executing_agent_id.clone(),
+        Some(prov_roles::EXECUTING_AGENT.to_string()),
+    );
+    Ok(Some(executing_agent_id))
 }
 
 
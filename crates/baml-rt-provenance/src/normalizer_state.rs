@@ -0,0 +1,308 @@
+//! What [`DefaultProvNormalizer`](crate::normalizer::DefaultProvNormalizer)
+//! remembers between events.
+//!
+//! Each event is normalized into its own document, so facts established by
+//! earlier events have to be carried over: which agents were booted, and which
+//! agent runs each task or handles each message. Booted agents are kept per
+//! tenant, so an agent booted for one tenant never resolves for another; agents
+//! booted without a tenant (the runner's own) resolve everywhere. Bindings are
+//! kept per [`ContextId`].
+//!
+//! A call can be normalized before its binding arrives, e.g. an LLM call in a
+//! task whose `TaskCreated` is still in flight. Such a call is remembered as
+//! unbound, and the event that brings the binding back-fills its
+//! executing-agent association.
+//!
+//! The last call completed in each task is remembered too, so the next call
+//! started in it can be linked to it with `wasInformedBy`.
+//!
+//! A context is completed once every task created in it has reached a terminal
+//! state. Completed contexts stay around for late events (feedback on a
+//! finished task, say) until more than [`ContextRetention::max_completed`]
+//! have piled up, oldest first; past [`ContextRetention::max_contexts`] the
+//! least recently used context is dropped whatever its state.
+
+use crate::events::{CallScope, ProvEvent, ProvEventData};
+use crate::types::ProvActivityId;
+use baml_rt_core::ids::{AgentId, ContextId, MessageId, TaskId, UuidId};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// How many contexts the normalizer keeps state for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContextRetention {
+    /// Contexts tracked at most, completed or not.
+    pub max_contexts: usize,
+    /// Completed contexts kept after completion.
+    pub max_completed: usize,
+}
+
+impl Default for ContextRetention {
+    fn default() -> Self {
+        Self { max_contexts: 10_000, max_completed: 1_000 }
+    }
+}
+
+/// A call activity added to an earlier event's document.
+#[derive(Debug, Clone)]
+pub(crate) struct CallActivity {
+    pub(crate) activity: ProvActivityId,
+    pub(crate) prov_type: Option<String>,
+}
+
+/// Calls that just learned their executing agent.
+#[derive(Debug)]
+pub(crate) struct Backfill {
+    pub(crate) agent_id: AgentId,
+    pub(crate) calls: Vec<CallActivity>,
+}
+
+#[derive(Debug, Default)]
+struct ContextState {
+    agents: HashSet<AgentId>,
+    task_agents: HashMap<TaskId, AgentId>,
+    message_agents: HashMap<MessageId, AgentId>,
+    unbound_task_calls: HashMap<TaskId, Vec<CallActivity>>,
+    unbound_message_calls: HashMap<MessageId, Vec<CallActivity>>,
+    last_completed_calls: HashMap<TaskId, CallActivity>,
+    open_tasks: HashSet<TaskId>,
+    /// Completion sequence number while the context is completed.
+    completed: Option<u64>,
+    last_used: u64,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct NormalizerState {
+    retention: ContextRetention,
+    booted: HashMap<Option<String>, HashSet<AgentId>>,
+    contexts: HashMap<ContextId, ContextState>,
+    /// Completed contexts in completion order; entries whose sequence no
+    /// longer matches the context's are stale and skipped.
+    completion_order: VecDeque<(ContextId, u64)>,
+    completed_count: usize,
+    clock: u64,
+}
+
+impl NormalizerState {
+    pub(crate) fn new(retention: ContextRetention) -> Self {
+        Self { retention, ..Self::default() }
+    }
+
+    pub(crate) fn tracked_contexts(&self) -> usize {
+        self.contexts.len()
+    }
+
+    pub(crate) fn is_tracked(&self, context_id: &ContextId) -> bool {
+        self.contexts.contains_key(context_id)
+    }
+
+    /// Agents and task bindings visible to `event`.
+    pub(crate) fn known_agents(&self, event: &ProvEvent) -> KnownAgents<'_> {
+        KnownAgents {
+            shared: self.booted.get(&None),
+            tenant: event.tenant().and_then(|tenant| self.booted.get(&Some(tenant.to_string()))),
+            context: self.contexts.get(event.context_id()),
+        }
+    }
+
+    /// Sequence `call`, made by `event`, within its task. A started call gets
+    /// the call completed last in the task, if any; a completed call becomes
+    /// the one the next started call gets.
+    pub(crate) fn sequence_call(&mut self, event: &ProvEvent, call: &CallActivity) -> Option<CallActivity> {
+        let (scope, completed) = match event.data() {
+            ProvEventData::LlmCallStarted { scope, .. } | ProvEventData::ToolCallStarted { scope, .. } => {
+                (scope, false)
+            }
+            ProvEventData::LlmCallCompleted { scope, .. }
+            | ProvEventData::ToolCallCompleted { scope, .. } => (scope, true),
+            _ => return None,
+        };
+        let CallScope::Task { task_id } = scope else {
+            return None;
+        };
+        let now = self.clock;
+        let context = self.context_mut(event.context_id(), now);
+        if completed {
+            context.last_completed_calls.insert(task_id.clone(), call.clone());
+            None
+        } else {
+            context.last_completed_calls.get(task_id).cloned()
+        }
+    }
+
+    /// Remember that `call`, made by `event`, has no executing agent yet.
+    pub(crate) fn defer(&mut self, event: &ProvEvent, call: CallActivity) {
+        let scope = match event.data() {
+            ProvEventData::LlmCallStarted { scope, .. }
+            | ProvEventData::LlmCallCompleted { scope, .. }
+            | ProvEventData::ToolCallStarted { scope, .. }
+            | ProvEventData::ToolCallCompleted { scope, .. } => scope,
+            _ => return,
+        };
+        let now = self.clock;
+        let context = self.context_mut(event.context_id(), now);
+        match scope {
+            CallScope::Task { task_id } => {
+                context.unbound_task_calls.entry(task_id.clone()).or_default().push(call)
+            }
+            CallScope::Message { message_id } => {
+                context.unbound_message_calls.entry(message_id.clone()).or_default().push(call)
+            }
+        }
+    }
+
+    /// Record what `event` establishes, once it has been normalized. Returns
+    /// the unbound calls it binds to an agent.
+    pub(crate) fn record(&mut self, event: &ProvEvent) -> Option<Backfill> {
+        self.clock += 1;
+        let now = self.clock;
+        let context_id = event.context_id();
+        let mut backfill = None;
+        match event.data() {
+            ProvEventData::AgentBooted { agent_id, .. } => {
+                self.booted
+                    .entry(event.tenant().map(str::to_string))
+                    .or_default()
+                    .insert(agent_id.clone());
+                self.context_mut(context_id, now).agents.insert(agent_id.clone());
+            }
+            ProvEventData::TaskCreated { task_id, agent_id } => {
+                let reopened = {
+                    let context = self.context_mut(context_id, now);
+                    context.agents.insert(agent_id.clone());
+                    context.task_agents.insert(task_id.clone(), agent_id.clone());
+                    context.open_tasks.insert(task_id.clone());
+                    let calls = context.unbound_task_calls.remove(task_id).unwrap_or_default();
+                    backfill = Backfill::of(agent_id, calls);
+                    context.completed.take().is_some()
+                };
+                if reopened {
+                    self.completed_count -= 1;
+                }
+            }
+            ProvEventData::TaskStatusChanged { task_id, new_status: Some(status), .. }
+                if is_terminal(status) =>
+            {
+                let Some(context) = self.contexts.get_mut(context_id) else {
+                    return None;
+                };
+                context.last_used = now;
+                if context.open_tasks.remove(task_id)
+                    && context.open_tasks.is_empty()
+                    && context.completed.is_none()
+                {
+                    context.completed = Some(now);
+                    self.completion_order.push_back((context_id.clone(), now));
+                    self.completed_count += 1;
+                }
+            }
+            ProvEventData::MessageReceived { id, metadata, .. }
+            | ProvEventData::MessageSent { id, metadata, .. } => {
+                let agent_id = metadata
+                    .as_ref()
+                    .and_then(|metadata| metadata.get("agent_id"))
+                    .and_then(|raw| UuidId::parse_str(raw).ok())
+                    .map(AgentId::from_uuid);
+                let context = self.context_mut(context_id, now);
+                if let Some(agent_id) = agent_id {
+                    context.agents.insert(agent_id.clone());
+                    context.message_agents.insert(id.clone(), agent_id.clone());
+                    let mut calls = context.unbound_message_calls.remove(id).unwrap_or_default();
+                    // A message in a task binds the task too, unless TaskCreated got there first.
+                    if let Some(task_id) = event.task_id()
+                        && !context.task_agents.contains_key(task_id)
+                    {
+                        context.task_agents.insert(task_id.clone(), agent_id.clone());
+                        calls.extend(context.unbound_task_calls.remove(task_id).unwrap_or_default());
+                    }
+                    backfill = Backfill::of(&agent_id, calls);
+                }
+            }
+            _ => {
+                if let Some(context) = self.contexts.get_mut(context_id) {
+                    context.last_used = now;
+                }
+            }
+        }
+        self.evict();
+        backfill
+    }
+
+    fn context_mut(&mut self, context_id: &ContextId, now: u64) -> &mut ContextState {
+        let context = self.contexts.entry(context_id.clone()).or_default();
+        context.last_used = now;
+        context
+    }
+
+    fn evict(&mut self) {
+        while self.completed_count > self.retention.max_completed {
+            let Some((context_id, sequence)) = self.completion_order.pop_front() else {
+                break;
+            };
+            if self.contexts.get(&context_id).is_some_and(|context| context.completed == Some(sequence)) {
+                self.remove(&context_id);
+            }
+        }
+        while self.contexts.len() > self.retention.max_contexts {
+            let Some(oldest) = self
+                .contexts
+                .iter()
+                .min_by_key(|(_, context)| context.last_used)
+                .map(|(context_id, _)| context_id.clone())
+            else {
+                break;
+            };
+            self.remove(&oldest);
+        }
+    }
+
+    fn remove(&mut self, context_id: &ContextId) {
+        if let Some(context) = self.contexts.remove(context_id) {
+            if context.completed.is_some() {
+                self.completed_count -= 1;
+            }
+            tracing::trace!(context_id = context_id.as_str(), "Dropped normalizer state for context");
+        }
+    }
+}
+
+/// `completed`, `TASK_STATE_COMPLETED` and the other terminal states in either spelling.
+pub(crate) fn is_terminal(status: &str) -> bool {
+    let status = status.to_ascii_lowercase();
+    matches!(
+        status.strip_prefix("task_state_").unwrap_or(&status),
+        "completed" | "failed" | "cancelled" | "canceled" | "rejected"
+    )
+}
+
+/// The agent knowledge one event is normalized with.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct KnownAgents<'a> {
+    shared: Option<&'a HashSet<AgentId>>,
+    tenant: Option<&'a HashSet<AgentId>>,
+    context: Option<&'a ContextState>,
+}
+
+impl KnownAgents<'_> {
+    pub(crate) fn contains(&self, agent_id: &AgentId) -> bool {
+        [self.shared, self.tenant, self.context.map(|context| &context.agents)]
+            .into_iter()
+            .flatten()
+            .any(|agents| agents.contains(agent_id))
+    }
+
+    /// The agent bound to `task_id` by an earlier `TaskCreated` in the context.
+    pub(crate) fn task_agent(&self, task_id: &TaskId) -> Option<AgentId> {
+        self.context.and_then(|context| context.task_agents.get(task_id)).cloned()
+    }
+
+    /// The agent that received or sent `message_id` earlier in the context.
+    pub(crate) fn message_agent(&self, message_id: &MessageId) -> Option<AgentId> {
+        self.context.and_then(|context| context.message_agents.get(message_id)).cloned()
+    }
+}
+
+impl Backfill {
+    fn of(agent_id: &AgentId, calls: Vec<CallActivity>) -> Option<Self> {
+        (!calls.is_empty()).then(|| Backfill { agent_id: agent_id.clone(), calls })
+    }
+}
@@ -0,0 +1,175 @@
+//! Machine-readable description of the provenance vocabulary.
+//!
+//! [`ontology`] assembles node types, attribute keys, relation names and the
+//! semantic label rules straight from [`crate::vocabulary`], [`crate::schema`]
+//! and [`crate::cypher`], so tools that render or query the graph (UI builders,
+//! query editors) can read the vocabulary instead of copying it.
+
+use crate::cypher::{SEMANTIC_LABEL_RULES, SemanticLabelRule, label_from_prov_type};
+use crate::schema::{AttrSpec, COMMON, SCHEMAS};
+use crate::vocabulary::{
+    a2a_relation_types, a2a_relations, a2a_roles, namespaces, prov_relations, prov_roles,
+};
+use serde::Serialize;
+use serde_json::Value;
+
+/// Bumped whenever the shape of [`Ontology`] changes.
+pub const ONTOLOGY_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Ontology {
+    pub version: u32,
+    pub namespaces: Vec<NamespaceDescription>,
+    pub node_types: Vec<NodeTypeDescription>,
+    /// Scope attributes any node may carry, in addition to its own.
+    pub common_attributes: Vec<AttributeDescription>,
+    pub relations: Vec<RelationDescription>,
+    /// Values of `prov:type` on relations.
+    pub relation_types: Vec<&'static str>,
+    /// Values of `prov:role` on `USED` and `WAS_ASSOCIATED_WITH` relations.
+    pub roles: Vec<&'static str>,
+    /// Evaluated in order; the first matching rule names the relationship.
+    pub semantic_label_rules: Vec<SemanticLabelRule>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NamespaceDescription {
+    pub prefix: &'static str,
+    pub iri: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeTypeDescription {
+    pub prov_type: &'static str,
+    /// `ProvEntity`, `ProvActivity` or `ProvAgent`.
+    pub base_type: &'static str,
+    /// Graph label under [`crate::SemanticLabels`].
+    pub label: String,
+    pub attributes: Vec<AttributeDescription>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AttributeDescription {
+    pub key: &'static str,
+    pub kind: &'static str,
+    pub required: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RelationFamily {
+    /// W3C PROV relation.
+    Prov,
+    /// Relation derived by the normalizer from A2A scope.
+    A2a,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RelationDescription {
+    pub name: &'static str,
+    pub family: RelationFamily,
+    /// Semantic relationship types the rules can give this relation.
+    pub semantic_labels: Vec<&'static str>,
+}
+
+impl Ontology {
+    pub fn to_json(&self) -> Value {
+        serde_json::to_value(self).unwrap_or(Value::Null)
+    }
+
+    pub fn node_type(&self, prov_type: &str) -> Option<&NodeTypeDescription> {
+        self.node_types.iter().find(|node| node.prov_type == prov_type)
+    }
+
+    pub fn relation(&self, name: &str) -> Option<&RelationDescription> {
+        self.relations.iter().find(|relation| relation.name == name)
+    }
+}
+
+/// Describe the vocabulary as it is compiled into this crate.
+pub fn ontology() -> Ontology {
+    let prov = [
+        prov_relations::USED,
+        prov_relations::WAS_GENERATED_BY,
+        prov_relations::QUALIFIED_GENERATION,
+        prov_relations::WAS_ASSOCIATED_WITH,
+        prov_relations::WAS_DERIVED_FROM,
+        prov_relations::ACTED_ON_BEHALF_OF,
+        prov_relations::WAS_INFORMED_BY,
+    ];
+    let derived = [
+        a2a_relations::TASK_MESSAGE,
+        a2a_relations::TASK_ARTIFACT,
+        a2a_relations::TASK_CALL,
+        a2a_relations::TASK_STATUS_TRANSITION,
+        a2a_relations::MESSAGE_CALL,
+        a2a_relations::CONTEXT_DERIVED_FROM,
+        a2a_relations::CONTEXT_CONTAINED_IN,
+        a2a_relations::LLM_RETRY_OF,
+    ];
+    let relations = prov
+        .into_iter()
+        .map(|name| relation(name, RelationFamily::Prov))
+        .chain(derived.into_iter().map(|name| relation(name, RelationFamily::A2a)))
+        .collect();
+
+    Ontology {
+        version: ONTOLOGY_VERSION,
+        namespaces: vec![
+            NamespaceDescription { prefix: namespaces::PROV_PREFIX, iri: namespaces::PROV },
+            NamespaceDescription { prefix: namespaces::XSD_PREFIX, iri: namespaces::XSD },
+            NamespaceDescription { prefix: namespaces::A2A_PREFIX, iri: namespaces::A2A },
+            NamespaceDescription { prefix: namespaces::ID_PREFIX, iri: namespaces::ID },
+        ],
+        node_types: SCHEMAS
+            .iter()
+            .map(|schema| {
+                let base_type = schema.kind.base_label();
+                NodeTypeDescription {
+                    prov_type: schema.prov_type,
+                    base_type,
+                    label: label_from_prov_type(Some(schema.prov_type), base_type),
+                    attributes: schema.attributes.iter().map(attribute).collect(),
+                }
+            })
+            .collect(),
+        common_attributes: COMMON.iter().map(attribute).collect(),
+        relations,
+        relation_types: vec![
+            a2a_relation_types::STATUS_TRANSITION,
+            a2a_relation_types::ILLEGAL_TRANSITION,
+            a2a_relation_types::FEEDBACK,
+            a2a_relation_types::BUDGET_EXHAUSTION,
+            a2a_relation_types::DIAGNOSTIC,
+            a2a_relation_types::CONFIG_REVISION,
+            a2a_relation_types::SCHEMA_REVISION,
+            a2a_relation_types::POLICY_DECISION,
+            a2a_relation_types::APPROVAL_DECISION,
+        ],
+        roles: vec![
+            a2a_roles::PROMPT,
+            a2a_roles::ARGS,
+            a2a_roles::ARCHIVE,
+            a2a_roles::INPUT_MESSAGE,
+            a2a_roles::TASK_STATE,
+            prov_roles::EXECUTING_AGENT,
+            prov_roles::INVOKING_AGENT,
+            prov_roles::CALLING_AGENT,
+        ],
+        semantic_label_rules: SEMANTIC_LABEL_RULES.to_vec(),
+    }
+}
+
+fn attribute(spec: &AttrSpec) -> AttributeDescription {
+    AttributeDescription { key: spec.key, kind: spec.kind.as_str(), required: spec.required }
+}
+
+fn relation(name: &'static str, family: RelationFamily) -> RelationDescription {
+    let mut semantic_labels = Vec::new();
+    for rule in SEMANTIC_LABEL_RULES.iter().filter(|rule| rule.relation == name) {
+        if !semantic_labels.contains(&rule.label) {
+            semantic_labels.push(rule.label);
+        }
+    }
+    RelationDescription { name, family, semantic_labels }
+}
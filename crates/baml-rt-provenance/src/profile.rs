@@ -0,0 +1,247 @@
+//! Named presets for provenance capture.
+//!
+//! A [`ProvenanceProfile`] picks payload detail, write-time redaction, attribute
+//! validation, batching and snapshot cadence together, so environments differ by
+//! one name rather than by a dozen hand-copied flags. Individual keys can still
+//! be overridden on top of a profile with [`ProvenanceSettings::with_override`].
+//!
+//! | key                      | dev     | staging | prod    |
+//! |--------------------------|---------|---------|---------|
+//! | `max_payload_chars`      | 65536   | 4096    | 1024    |
+//! | `redaction`              | off     | secrets | secrets |
+//! | `strict_attributes`      | true    | true    | false   |
+//! | `flush_interval_ms`      | off     | 250     | 1000    |
+//! | `batch_size`             | 64      | 64      | 256     |
+//! | `snapshot_interval_secs` | 10      | 60      | 300     |
+
+use crate::error::{ProvenanceError, Result};
+use crate::falkordb_store::FalkorDbProvenanceConfig;
+use crate::redaction::{RedactionAction, RedactionKey, RedactionPolicy, RedactionRule};
+use baml_rt_interceptor::DEFAULT_MAX_PAYLOAD_CHARS;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Bearer tokens and `sk-` style API keys.
+const SECRET_PATTERN: &str = r"(?i)\bbearer\s+[a-z0-9._~+/-]+=*|\bsk-[a-z0-9_-]{16,}";
+
+/// A named set of [`ProvenanceSettings`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProvenanceProfile {
+    /// Large payloads, nothing redacted, every event written as it arrives.
+    Dev,
+    /// Realistic payload limits and secret masking with light batching.
+    Staging,
+    /// Small payloads, secret masking and batched writes.
+    Prod,
+}
+
+impl ProvenanceProfile {
+    pub const ALL: [ProvenanceProfile; 3] =
+        [ProvenanceProfile::Dev, ProvenanceProfile::Staging, ProvenanceProfile::Prod];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProvenanceProfile::Dev => "dev",
+            ProvenanceProfile::Staging => "staging",
+            ProvenanceProfile::Prod => "prod",
+        }
+    }
+
+    pub fn settings(&self) -> ProvenanceSettings {
+        let base = ProvenanceSettings::default();
+        match self {
+            ProvenanceProfile::Dev => ProvenanceSettings {
+                max_payload_chars: 65_536,
+                strict_attributes: true,
+                snapshot_interval: Duration::from_secs(10),
+                ..base
+            },
+            ProvenanceProfile::Staging => ProvenanceSettings {
+                redaction: ProfileRedaction::Secrets,
+                strict_attributes: true,
+                flush_interval: Some(Duration::from_millis(250)),
+                ..base
+            },
+            ProvenanceProfile::Prod => ProvenanceSettings {
+                max_payload_chars: 1024,
+                redaction: ProfileRedaction::Secrets,
+                flush_interval: Some(Duration::from_secs(1)),
+                max_batch_size: 256,
+                snapshot_interval: Duration::from_secs(300),
+                ..base
+            },
+        }
+    }
+}
+
+impl fmt::Display for ProvenanceProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ProvenanceProfile {
+    type Err = ProvenanceError;
+
+    fn from_str(value: &str) -> Result<Self> {
+        Self::ALL.into_iter().find(|profile| profile.as_str() == value).ok_or_else(|| {
+            ProvenanceError::InvalidSetting {
+                key: "profile".to_string(),
+                reason: format!("unknown profile '{value}', expected dev, staging or prod"),
+            }
+        })
+    }
+}
+
+/// Redaction applied before events are stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileRedaction {
+    Off,
+    /// Mask bearer tokens and API keys found in payloads.
+    Secrets,
+    /// Hash every payload attribute.
+    Payloads,
+}
+
+impl ProfileRedaction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProfileRedaction::Off => "off",
+            ProfileRedaction::Secrets => "secrets",
+            ProfileRedaction::Payloads => "payloads",
+        }
+    }
+
+    /// The policy to configure on the store; `None` when nothing is redacted.
+    pub fn policy(&self) -> Option<RedactionPolicy> {
+        let rule = match self {
+            ProfileRedaction::Off => return None,
+            ProfileRedaction::Secrets => {
+                RedactionRule::pattern(Vec::<String>::new(), SECRET_PATTERN, RedactionAction::Mask)
+                    .expect("secret pattern is valid")
+            }
+            ProfileRedaction::Payloads => {
+                RedactionRule::allow(Vec::<String>::new(), RedactionAction::Hash)
+            }
+        };
+        Some(RedactionPolicy::new().with_rule(rule))
+    }
+}
+
+impl FromStr for ProfileRedaction {
+    type Err = ProvenanceError;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "off" => Ok(ProfileRedaction::Off),
+            "secrets" => Ok(ProfileRedaction::Secrets),
+            "payloads" => Ok(ProfileRedaction::Payloads),
+            other => Err(ProvenanceError::InvalidSetting {
+                key: "redaction".to_string(),
+                reason: format!("unknown redaction '{other}', expected off, secrets or payloads"),
+            }),
+        }
+    }
+}
+
+/// Provenance capture knobs, usually taken from a [`ProvenanceProfile`].
+///
+/// The default matches what the runtime does with no profile selected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProvenanceSettings {
+    /// Payloads longer than this are truncated in provenance and logs.
+    pub max_payload_chars: usize,
+    pub redaction: ProfileRedaction,
+    /// Key redacted values are hashed with; a random one per policy when unset.
+    pub redaction_key: Option<RedactionKey>,
+    /// Reject events whose attributes violate the vocabulary schemas.
+    pub strict_attributes: bool,
+    /// Buffer writes and flush them at this interval; `None` writes each event
+    /// as it arrives.
+    pub flush_interval: Option<Duration>,
+    pub max_batch_size: usize,
+    /// How often the in-memory store is snapshotted, when snapshots are enabled.
+    pub snapshot_interval: Duration,
+}
+
+impl Default for ProvenanceSettings {
+    fn default() -> Self {
+        Self {
+            max_payload_chars: DEFAULT_MAX_PAYLOAD_CHARS,
+            redaction: ProfileRedaction::Off,
+            redaction_key: None,
+            strict_attributes: false,
+            flush_interval: None,
+            max_batch_size: 64,
+            snapshot_interval: Duration::from_secs(60),
+        }
+    }
+}
+
+impl ProvenanceSettings {
+    /// Override one key, named as in the module table.
+    ///
+    /// `flush_interval_ms` accepts `off` to write unbuffered.
+    pub fn with_override(mut self, key: &str, value: &str) -> Result<Self> {
+        let invalid = |reason: String| ProvenanceError::InvalidSetting {
+            key: key.to_string(),
+            reason,
+        };
+        let number = |value: &str| {
+            value.parse::<u64>().map_err(|_| invalid(format!("expected a number, got '{value}'")))
+        };
+        match key {
+            "max_payload_chars" => self.max_payload_chars = number(value)? as usize,
+            "redaction" => self.redaction = value.parse()?,
+            "strict_attributes" => {
+                self.strict_attributes = value
+                    .parse()
+                    .map_err(|_| invalid(format!("expected true or false, got '{value}'")))?
+            }
+            "flush_interval_ms" => {
+                self.flush_interval = match value {
+                    "off" => None,
+                    value => Some(Duration::from_millis(number(value)?.max(1))),
+                }
+            }
+            "batch_size" => self.max_batch_size = (number(value)? as usize).max(1),
+            "snapshot_interval_secs" => {
+                self.snapshot_interval = Duration::from_secs(number(value)?.max(1))
+            }
+            _ => return Err(invalid("unknown provenance setting".to_string())),
+        }
+        Ok(self)
+    }
+
+    /// Override one key given as `key=value`.
+    pub fn with_assignment(self, assignment: &str) -> Result<Self> {
+        let (key, value) = assignment.split_once('=').ok_or_else(|| {
+            ProvenanceError::InvalidSetting {
+                key: assignment.to_string(),
+                reason: "expected key=value".to_string(),
+            }
+        })?;
+        self.with_override(key.trim(), value.trim())
+    }
+
+    pub fn redaction_policy(&self) -> Option<RedactionPolicy> {
+        let policy = self.redaction.policy()?;
+        Some(match &self.redaction_key {
+            Some(key) => policy.with_key(key.clone()),
+            None => policy,
+        })
+    }
+
+    /// Apply validation, batching and redaction to a FalkorDB writer config.
+    pub fn apply_to_falkordb(&self, config: FalkorDbProvenanceConfig) -> FalkorDbProvenanceConfig {
+        let mut config = config.with_strict_attributes(self.strict_attributes);
+        if let Some(flush_interval) = self.flush_interval {
+            config = config.with_buffering(flush_interval, self.max_batch_size);
+        }
+        match self.redaction_policy() {
+            Some(policy) => config.with_redaction(policy),
+            None => config,
+        }
+    }
+}
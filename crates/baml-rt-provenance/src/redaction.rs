@@ -0,0 +1,666 @@
+//! Redaction of provenance payloads.
+//!
+//! Two places can strip sensitive values:
+//!
+//! - A [`RedactionPolicy`] runs while events are normalized, so what it removes
+//!   never reaches the graph, or on the events themselves before a store keeps
+//!   them. Rules can drop, mask or hash whole attributes, fields at a path
+//!   inside an attribute, or regex matches in string values, and can be
+//!   limited to particular event types.
+//! - A store keeps whatever the writer recorded. [`RedactingReader`] sits in
+//!   front of any [`ProvenanceReader`] and applies a [`Redactor`] chosen by the
+//!   caller's role to every record it returns, so one store can serve operators
+//!   who need full payloads alongside consumers who must not see them.
+
+use crate::error::{ProvenanceError, Result};
+use crate::events::{ProvEvent, ProvEventData};
+use crate::store::{ProvNodeRecord, ProvenanceQuery, ProvenanceReader};
+use crate::vocabulary::a2a;
+use async_trait::async_trait;
+use baml_rt_core::canonical::canonicalize;
+use hmac::{Hmac, Mac};
+use regex::Regex;
+use serde_json::Value;
+use sha2::Sha256;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use uuid::Uuid;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Replacement for redacted attribute values.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Prefix of the digest stored by [`RedactionAction::Hash`].
+pub const HASH_PREFIX: &str = "hmac-sha256:";
+
+/// Env var holding the [`RedactionKey`] the runner hashes redacted values with.
+pub const REDACTION_KEY_ENV: &str = "BAML_REDACTION_KEY";
+
+/// Bytes of the seal that marks a digest as produced by a given key.
+const SEAL_LEN: usize = 8;
+
+/// Key of the HMAC-SHA256 behind [`RedactionAction::Hash`].
+///
+/// Without the key, a hashed value cannot be confirmed by guessing it. Digests
+/// only match between policies holding the same key, so configure one for
+/// digests that must correlate across stores or restarts.
+#[derive(Clone, PartialEq, Eq)]
+pub struct RedactionKey(Arc<[u8]>);
+
+impl RedactionKey {
+    pub fn new(key: impl AsRef<[u8]>) -> Self {
+        Self(Arc::from(key.as_ref()))
+    }
+
+    /// A fresh key no other policy shares.
+    pub fn random() -> Self {
+        let bytes: Vec<u8> =
+            [Uuid::new_v4(), Uuid::new_v4()].iter().flat_map(|id| *id.as_bytes()).collect();
+        Self::new(bytes)
+    }
+
+    fn mac(&self, domain: &[u8]) -> HmacSha256 {
+        let mut mac =
+            HmacSha256::new_from_slice(&self.0).expect("HMAC accepts keys of any length");
+        mac.update(domain);
+        mac
+    }
+
+    /// [`HASH_PREFIX`], the keyed digest of `value`, and a seal over that digest
+    /// so [`Self::produced`] can recognize it later.
+    fn hash(&self, value: &Value) -> String {
+        let mut mac = self.mac(b"value\0");
+        mac.update(canonicalize(value).as_bytes());
+        let digest = mac.finalize().into_bytes();
+        let mut seal = self.mac(b"seal\0");
+        seal.update(&digest);
+        let seal = seal.finalize().into_bytes();
+        format!("{HASH_PREFIX}{}.{}", hex::encode(digest), hex::encode(&seal[..SEAL_LEN]))
+    }
+
+    /// Whether `text` is a digest [`Self::hash`] produced with this key.
+    fn produced(&self, text: &str) -> bool {
+        let Some((digest, seal)) =
+            text.strip_prefix(HASH_PREFIX).and_then(|rest| rest.split_once('.'))
+        else {
+            return false;
+        };
+        let (Ok(digest), Ok(seal)) = (hex::decode(digest), hex::decode(seal)) else {
+            return false;
+        };
+        let mut mac = self.mac(b"seal\0");
+        mac.update(&digest);
+        seal.len() == SEAL_LEN && mac.verify_truncated_left(&seal).is_ok()
+    }
+}
+
+impl Default for RedactionKey {
+    fn default() -> Self {
+        Self::random()
+    }
+}
+
+/// Never prints the key.
+impl std::fmt::Debug for RedactionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("RedactionKey(..)")
+    }
+}
+
+/// Role whose reads are returned unredacted by [`ReadPolicy::default`].
+pub const PRIVILEGED_ROLE: &str = "privileged";
+
+/// Rewrites a record before it reaches a reader.
+pub trait Redactor: Send + Sync {
+    fn redact(&self, record: &mut ProvNodeRecord);
+}
+
+/// Attributes that carry caller-supplied content rather than identifiers.
+pub const PAYLOAD_ATTRIBUTES: [&str; 7] = [
+    a2a::PROMPT,
+    a2a::ARGS,
+    a2a::CONTENT,
+    a2a::METADATA,
+    a2a::CORRECTION,
+    a2a::COMMENT,
+    a2a::LOG_MESSAGE,
+];
+
+/// Replaces the listed attributes with [`REDACTED`].
+#[derive(Debug, Clone, Default)]
+pub struct AttributeRedactor {
+    attributes: HashSet<String>,
+}
+
+impl AttributeRedactor {
+    pub fn new<I, S>(attributes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self { attributes: attributes.into_iter().map(Into::into).collect() }
+    }
+
+    /// Prompts, tool args, message content, metadata and feedback text.
+    pub fn payloads() -> Self {
+        Self::new(PAYLOAD_ATTRIBUTES.iter().copied())
+    }
+}
+
+impl Redactor for AttributeRedactor {
+    fn redact(&self, record: &mut ProvNodeRecord) {
+        for (key, value) in record.attributes.iter_mut() {
+            if self.attributes.contains(key) {
+                *value = Value::String(REDACTED.to_string());
+            }
+        }
+    }
+}
+
+/// Which redactor applies to each caller role.
+///
+/// Roles without an entry get the default redactor, so a new or misspelled role
+/// never sees more than a restricted one.
+#[derive(Clone)]
+pub struct ReadPolicy {
+    roles: HashMap<String, Option<Arc<dyn Redactor>>>,
+    default: Arc<dyn Redactor>,
+}
+
+impl ReadPolicy {
+    /// Every role gets `default` until configured otherwise.
+    pub fn new(default: Arc<dyn Redactor>) -> Self {
+        Self { roles: HashMap::new(), default }
+    }
+
+    /// Give `role` its own redactor, or `None` to return its reads unredacted.
+    pub fn with_role(
+        mut self,
+        role: impl Into<String>,
+        redactor: Option<Arc<dyn Redactor>>,
+    ) -> Self {
+        self.roles.insert(role.into(), redactor);
+        self
+    }
+
+    pub fn redactor_for(&self, role: &str) -> Option<Arc<dyn Redactor>> {
+        match self.roles.get(role) {
+            Some(redactor) => redactor.clone(),
+            None => Some(self.default.clone()),
+        }
+    }
+}
+
+impl Default for ReadPolicy {
+    /// [`PRIVILEGED_ROLE`] reads everything; other roles get
+    /// [`AttributeRedactor::payloads`].
+    fn default() -> Self {
+        Self::new(Arc::new(AttributeRedactor::payloads())).with_role(PRIVILEGED_ROLE, None)
+    }
+}
+
+/// Serves reads from `inner` through a [`ReadPolicy`].
+#[derive(Clone)]
+pub struct RedactingReader {
+    inner: Arc<dyn ProvenanceReader>,
+    policy: Arc<ReadPolicy>,
+}
+
+impl RedactingReader {
+    pub fn new(inner: Arc<dyn ProvenanceReader>, policy: ReadPolicy) -> Self {
+        Self { inner, policy: Arc::new(policy) }
+    }
+
+    /// A reader returning what `role` may see.
+    pub fn for_role(&self, role: &str) -> RoleReader {
+        RoleReader { inner: self.inner.clone(), redactor: self.policy.redactor_for(role) }
+    }
+}
+
+/// A [`ProvenanceReader`] bound to one caller role.
+#[derive(Clone)]
+pub struct RoleReader {
+    inner: Arc<dyn ProvenanceReader>,
+    redactor: Option<Arc<dyn Redactor>>,
+}
+
+#[async_trait]
+impl ProvenanceReader for RoleReader {
+    async fn query_nodes(&self, query: &ProvenanceQuery) -> Result<Vec<ProvNodeRecord>> {
+        let mut records = self.inner.query_nodes(query).await?;
+        if let Some(redactor) = &self.redactor {
+            records.iter_mut().for_each(|record| redactor.redact(record));
+        }
+        Ok(records)
+    }
+}
+
+/// What a [`RedactionRule`] does to the values it selects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedactionAction {
+    /// Drop the attribute or field; regex matches are deleted from the string.
+    Remove,
+    /// Replace the value with [`REDACTED`].
+    Mask,
+    /// Replace the value with [`HASH_PREFIX`] and an HMAC of it under the
+    /// policy's [`RedactionKey`], so equal values can still be correlated without
+    /// being stored. Digests the same key produced are kept, so redacting twice
+    /// changes nothing; any other value is hashed, whatever it looks like.
+    Hash,
+}
+
+impl RedactionAction {
+    fn replacement(&self, value: &Value, key: &RedactionKey) -> Option<Value> {
+        match self {
+            RedactionAction::Remove => None,
+            RedactionAction::Mask => Some(Value::String(REDACTED.to_string())),
+            RedactionAction::Hash => match value {
+                Value::String(text) if key.produced(text) => Some(value.clone()),
+                _ => Some(Value::String(key.hash(value))),
+            },
+        }
+    }
+}
+
+/// One step of a field path, see [`RedactionRule::path`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PathSegment {
+    Key(String),
+    Index(usize),
+    Any,
+}
+
+fn parse_path(path: &str) -> Result<Vec<PathSegment>> {
+    let invalid = |reason: &str| ProvenanceError::InvalidRedactionRule {
+        rule: path.to_string(),
+        reason: reason.to_string(),
+    };
+    let trimmed = path.strip_prefix('$').unwrap_or(path);
+    let trimmed = trimmed.strip_prefix('.').unwrap_or(trimmed);
+    let mut segments = Vec::new();
+    for part in trimmed.split('.') {
+        let (key, mut rest) = match part.find('[') {
+            Some(open) => part.split_at(open),
+            None => (part, ""),
+        };
+        if !key.is_empty() {
+            segments.push(if key == "*" {
+                PathSegment::Any
+            } else {
+                PathSegment::Key(key.to_string())
+            });
+        } else if rest.is_empty() {
+            return Err(invalid("empty path segment"));
+        }
+        while !rest.is_empty() {
+            let close = rest.find(']').ok_or_else(|| invalid("unclosed '['"))?;
+            let index = &rest[1..close];
+            segments.push(match index {
+                "*" => PathSegment::Any,
+                index => PathSegment::Index(
+                    index.parse().map_err(|_| invalid("index must be a number or '*'"))?,
+                ),
+            });
+            rest = &rest[close + 1..];
+            if !rest.is_empty() && !rest.starts_with('[') {
+                return Err(invalid("unexpected text after ']'"));
+            }
+        }
+    }
+    Ok(segments)
+}
+
+fn redact_path(
+    value: &mut Value,
+    path: &[PathSegment],
+    action: RedactionAction,
+    key: &RedactionKey,
+) {
+    let Some((segment, rest)) = path.split_first() else {
+        return;
+    };
+    match (segment, value) {
+        (PathSegment::Key(key), Value::Object(map)) => {
+            if rest.is_empty() {
+                if let Some(found) = map.get(key) {
+                    match action.replacement(found, key) {
+                        Some(replacement) => {
+                            map.insert(key.clone(), replacement);
+                        }
+                        None => {
+                            map.remove(key);
+                        }
+                    }
+                }
+            } else if let Some(child) = map.get_mut(key) {
+                redact_path(child, rest, action, key);
+            }
+        }
+        (PathSegment::Index(index), Value::Array(items)) => {
+            if rest.is_empty() {
+                if *index < items.len() {
+                    match action.replacement(&items[*index], key) {
+                        Some(replacement) => items[*index] = replacement,
+                        None => {
+                            items.remove(*index);
+                        }
+                    }
+                }
+            } else if let Some(child) = items.get_mut(*index) {
+                redact_path(child, rest, action, key);
+            }
+        }
+        (PathSegment::Any, Value::Object(map)) => {
+            if rest.is_empty() {
+                replace_all(map.values_mut(), action, key);
+                if action == RedactionAction::Remove {
+                    map.clear();
+                }
+            } else {
+                map.values_mut().for_each(|child| redact_path(child, rest, action, key));
+            }
+        }
+        (PathSegment::Any, Value::Array(items)) => {
+            if rest.is_empty() {
+                replace_all(items.iter_mut(), action, key);
+                if action == RedactionAction::Remove {
+                    items.clear();
+                }
+            } else {
+                items.iter_mut().for_each(|child| redact_path(child, rest, action, key));
+            }
+        }
+        _ => {}
+    }
+}
+
+fn replace_all<'a>(
+    values: impl Iterator<Item = &'a mut Value>,
+    action: RedactionAction,
+    key: &RedactionKey,
+) {
+    for value in values {
+        if let Some(replacement) = action.replacement(value, key) {
+            *value = replacement;
+        }
+    }
+}
+
+fn redact_matches(value: &mut Value, pattern: &Regex, action: RedactionAction, key: &RedactionKey) {
+    match value {
+        Value::String(text) => {
+            let replaced = pattern.replace_all(text, |captures: &regex::Captures| {
+                let matched = Value::String(captures[0].to_string());
+                match action.replacement(&matched, key) {
+                    Some(Value::String(replacement)) => replacement,
+                    _ => String::new(),
+                }
+            });
+            if let std::borrow::Cow::Owned(replaced) = replaced {
+                *text = replaced;
+            }
+        }
+        Value::Array(items) => {
+            items.iter_mut().for_each(|item| redact_matches(item, pattern, action, key))
+        }
+        Value::Object(map) => {
+            map.values_mut().for_each(|item| redact_matches(item, pattern, action, key))
+        }
+        _ => {}
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Selector {
+    Deny(HashSet<String>),
+    Allow(HashSet<String>),
+    Path { attribute: String, path: Vec<PathSegment> },
+    Pattern { attributes: HashSet<String>, pattern: Regex },
+}
+
+/// One redaction step of a [`RedactionPolicy`].
+#[derive(Debug, Clone)]
+pub struct RedactionRule {
+    selector: Selector,
+    action: RedactionAction,
+    event_types: HashSet<String>,
+}
+
+impl RedactionRule {
+    fn new(selector: Selector, action: RedactionAction) -> Self {
+        Self { selector, action, event_types: HashSet::new() }
+    }
+
+    /// Applies `action` to the listed attributes.
+    pub fn deny<I, S>(attributes: I, action: RedactionAction) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::new(Selector::Deny(attributes.into_iter().map(Into::into).collect()), action)
+    }
+
+    /// Applies `action` to every [`PAYLOAD_ATTRIBUTES`] entry not listed.
+    ///
+    /// Identifiers, times and other structural attributes are never touched, so
+    /// the graph still links up.
+    pub fn allow<I, S>(attributes: I, action: RedactionAction) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::new(Selector::Allow(attributes.into_iter().map(Into::into).collect()), action)
+    }
+
+    /// Applies `action` to the fields `path` selects inside `attribute`.
+    ///
+    /// Paths are dotted keys with `[n]` or `[*]` for array items and `*` for any
+    /// key, optionally prefixed with `$.`, e.g. `$.messages[*].content`.
+    pub fn path(attribute: impl Into<String>, path: &str, action: RedactionAction) -> Result<Self> {
+        Ok(Self::new(
+            Selector::Path { attribute: attribute.into(), path: parse_path(path)? },
+            action,
+        ))
+    }
+
+    /// Applies `action` to each match of `pattern` in string values of the listed
+    /// attributes, or of [`PAYLOAD_ATTRIBUTES`] when none are listed.
+    pub fn pattern<I, S>(attributes: I, pattern: &str, action: RedactionAction) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let pattern = Regex::new(pattern).map_err(|err| ProvenanceError::InvalidRedactionRule {
+            rule: pattern.to_string(),
+            reason: err.to_string(),
+        })?;
+        let mut attributes: HashSet<String> = attributes.into_iter().map(Into::into).collect();
+        if attributes.is_empty() {
+            attributes = PAYLOAD_ATTRIBUTES.iter().map(|key| key.to_string()).collect();
+        }
+        Ok(Self::new(Selector::Pattern { attributes, pattern }, action))
+    }
+
+    /// Limits the rule to events of the given types, named after their
+    /// [`ProvEventData`](crate::events::ProvEventData) variant, e.g. `"LlmCallStarted"`.
+    pub fn for_events<I, S>(mut self, event_types: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.event_types = event_types.into_iter().map(Into::into).collect();
+        self
+    }
+
+    fn applies_to(&self, event_type: Option<&str>) -> bool {
+        match event_type {
+            Some(event_type) => {
+                self.event_types.is_empty() || self.event_types.contains(event_type)
+            }
+            None => self.event_types.is_empty(),
+        }
+    }
+
+    fn apply(&self, attributes: &mut HashMap<String, Value>, hash_key: &RedactionKey) {
+        let action = self.action;
+        let replace = |attributes: &mut HashMap<String, Value>, key: &str| {
+            if let Some(value) = attributes.get(key) {
+                match action.replacement(value, hash_key) {
+                    Some(replacement) => {
+                        attributes.insert(key.to_string(), replacement);
+                    }
+                    None => {
+                        attributes.remove(key);
+                    }
+                }
+            }
+        };
+        match &self.selector {
+            Selector::Deny(denied) => denied.iter().for_each(|key| replace(attributes, key)),
+            Selector::Allow(allowed) => PAYLOAD_ATTRIBUTES
+                .iter()
+                .filter(|key| !allowed.contains(**key))
+                .for_each(|key| replace(attributes, key)),
+            Selector::Path { attribute, path } => {
+                if let Some(value) = attributes.get_mut(attribute) {
+                    redact_path(value, path, action, hash_key);
+                }
+            }
+            Selector::Pattern { attributes: keys, pattern } => {
+                for key in keys {
+                    if let Some(value) = attributes.get_mut(key) {
+                        redact_matches(value, pattern, action, hash_key);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Redaction applied to node attributes while events are normalized.
+///
+/// Rules run in the order they were added. Configure it on
+/// [`DefaultProvNormalizer::with_redaction`](crate::normalizer::DefaultProvNormalizer::with_redaction),
+/// [`FalkorDbProvenanceConfig::with_redaction`](crate::falkordb_store::FalkorDbProvenanceConfig::with_redaction)
+/// or [`InMemoryProvenanceStore::with_redaction`](crate::store::InMemoryProvenanceStore::with_redaction).
+///
+/// Each policy hashes with a random [`RedactionKey`] unless given one.
+#[derive(Debug, Clone, Default)]
+pub struct RedactionPolicy {
+    rules: Vec<RedactionRule>,
+    key: RedactionKey,
+}
+
+impl RedactionPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_rule(mut self, rule: RedactionRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Hash with `key`, so digests match those of other policies holding it.
+    pub fn with_key(mut self, key: RedactionKey) -> Self {
+        self.key = key;
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Redact the attributes of a node produced by an event of `event_type`.
+    pub fn apply(&self, event_type: &str, attributes: &mut HashMap<String, Value>) {
+        self.rules
+            .iter()
+            .filter(|rule| rule.applies_to(Some(event_type)))
+            .for_each(|rule| rule.apply(attributes, &self.key));
+    }
+
+    /// Redact the payload fields of `event` itself, each as the attribute it
+    /// normalizes into, e.g. `args` as [`a2a::ARGS`].
+    ///
+    /// Message and feedback metadata are maps of strings: a rule that replaces
+    /// such a map whole drops it.
+    pub fn redact_event(&self, event: &mut ProvEvent) {
+        let event_type = event.data().event_type();
+        let redact = |key: &str, value: Value| {
+            let mut attributes = HashMap::from([(key.to_string(), value)]);
+            self.apply(event_type, &mut attributes);
+            attributes.remove(key)
+        };
+        let redact_value = |key: &str, value: &mut Value| {
+            *value = redact(key, value.take()).unwrap_or(Value::Null);
+        };
+        let redact_text = |key: &str, text: &mut Option<String>| {
+            *text = text.take().and_then(|text| redact(key, Value::String(text))).map(into_text);
+        };
+        let redact_map = |metadata: &mut Option<HashMap<String, String>>| {
+            *metadata = metadata.take().and_then(|metadata| {
+                let entries = metadata.into_iter().map(|(key, value)| (key, Value::String(value)));
+                match redact(a2a::METADATA, Value::Object(entries.collect()))? {
+                    Value::Object(map) => {
+                        Some(map.into_iter().map(|(key, value)| (key, into_text(value))).collect())
+                    }
+                    _ => None,
+                }
+            });
+        };
+        match event.data_mut() {
+            ProvEventData::LlmCallStarted { prompt, metadata, .. }
+            | ProvEventData::LlmCallCompleted { prompt, metadata, .. } => {
+                redact_value(a2a::PROMPT, prompt);
+                redact_value(a2a::METADATA, metadata);
+            }
+            ProvEventData::ToolCallStarted { args, metadata, .. }
+            | ProvEventData::ToolCallCompleted { args, metadata, .. } => {
+                redact_value(a2a::ARGS, args);
+                redact_value(a2a::METADATA, metadata);
+            }
+            ProvEventData::ApprovalDecided { args, .. } => redact_value(a2a::ARGS, args),
+            ProvEventData::MessageReceived { content, metadata, .. }
+            | ProvEventData::MessageSent { content, metadata, .. } => {
+                let lines = Value::Array(content.drain(..).map(Value::String).collect());
+                *content = match redact(a2a::CONTENT, lines) {
+                    Some(Value::Array(lines)) => lines.into_iter().map(into_text).collect(),
+                    Some(value) => vec![into_text(value)],
+                    None => Vec::new(),
+                };
+                redact_map(metadata);
+            }
+            ProvEventData::FeedbackSubmitted { correction, comment, metadata, .. } => {
+                redact_text(a2a::CORRECTION, correction);
+                redact_text(a2a::COMMENT, comment);
+                redact_map(metadata);
+            }
+            ProvEventData::ConsoleMessage { message, .. } => {
+                let mut text = Some(std::mem::take(message));
+                redact_text(a2a::LOG_MESSAGE, &mut text);
+                *message = text.unwrap_or_default();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A redacted value written back into a string field.
+fn into_text(value: Value) -> String {
+    match value {
+        Value::String(text) => text,
+        other => other.to_string(),
+    }
+}
+
+impl Redactor for RedactionPolicy {
+    /// Records no longer say which event produced them, so only rules without
+    /// an event-type filter apply on read.
+    fn redact(&self, record: &mut ProvNodeRecord) {
+        self.rules
+            .iter()
+            .filter(|rule| rule.applies_to(None))
+            .for_each(|rule| rule.apply(&mut record.attributes, &self.key));
+    }
+}
@@ -0,0 +1,133 @@
+//! Re-run recorded provenance events through a writer.
+//!
+//! Writers normalize each event as it arrives, so replaying the raw event history
+//! into a fresh writer rebuilds its graph with the current normalizer, schema and
+//! vocabulary. This is how a FalkorDB graph is migrated after a vocabulary change
+//! without re-running the agents that produced it.
+//!
+//! Events come from an [`InMemoryProvenanceStore`] or from an event log on disk:
+//! a store snapshot, a JSON array of events, or JSON lines with one event per line,
+//! including a rotated [`crate::jsonl_store`] log.
+
+use crate::error::{ProvenanceError, Result};
+use crate::events::{event_sequence, observe_event_id, ProvEvent};
+use crate::jsonl_store::log_segments;
+use crate::store::{decode_snapshot, InMemoryProvenanceStore, ProvenanceWriter};
+use baml_rt_core::ids::EventId;
+use serde_json::Value;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReplayOptions {
+    /// Stop at the first event the target rejects instead of skipping it.
+    pub fail_fast: bool,
+}
+
+/// An event the target writer refused during replay.
+#[derive(Debug, Clone)]
+pub struct SkippedEvent {
+    pub event_id: EventId,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ReplayReport {
+    pub replayed: usize,
+    pub skipped: Vec<SkippedEvent>,
+}
+
+/// Replay every event held by `store` into `target`.
+pub async fn replay_store(
+    store: &InMemoryProvenanceStore,
+    target: &dyn ProvenanceWriter,
+    options: ReplayOptions,
+) -> Result<ReplayReport> {
+    replay_events(store.events().await, target, options).await
+}
+
+/// Replay the event log at `path` into `target`.
+pub async fn replay_file(
+    path: &Path,
+    target: &dyn ProvenanceWriter,
+    options: ReplayOptions,
+) -> Result<ReplayReport> {
+    let bytes = tokio::fs::read(path)
+        .await
+        .map_err(|err| ProvenanceError::Storage(Box::new(err)))?;
+    replay_events(decode_event_log(&bytes)?, target, options).await
+}
+
+/// Replay every segment of the JSONL log at `path`, rotated ones included,
+/// into `target`.
+pub async fn replay_jsonl_log(
+    path: &Path,
+    target: &dyn ProvenanceWriter,
+    options: ReplayOptions,
+) -> Result<ReplayReport> {
+    let mut events = Vec::new();
+    for segment in log_segments(path)? {
+        let bytes = tokio::fs::read(&segment)
+            .await
+            .map_err(|err| ProvenanceError::Storage(Box::new(err)))?;
+        events.extend(decode_event_log(&bytes)?);
+    }
+    replay_events(events, target, options).await
+}
+
+/// Write `events` to `target` in the order they were issued, then flush it.
+///
+/// Events whose ids were not issued by this runtime keep their relative order
+/// and are replayed last.
+pub async fn replay_events(
+    mut events: Vec<ProvEvent>,
+    target: &dyn ProvenanceWriter,
+    options: ReplayOptions,
+) -> Result<ReplayReport> {
+    events.sort_by_key(|event| event_sequence(event.id()).unwrap_or(u64::MAX));
+    let mut report = ReplayReport::default();
+    for event in events {
+        let event_id = event.id().clone();
+        match target.add_event(event).await {
+            Ok(()) => report.replayed += 1,
+            Err(err) if options.fail_fast => return Err(err),
+            Err(err) => {
+                tracing::warn!(error = %err, event_id = event_id.as_str(), "Skipping event during provenance replay");
+                report.skipped.push(SkippedEvent { event_id, error: err.to_string() });
+            }
+        }
+    }
+    target.flush().await?;
+    Ok(report)
+}
+
+/// Parse an event log in any of the supported formats.
+///
+/// Event ids are reserved so events recorded afterwards never reuse them.
+pub fn decode_event_log(bytes: &[u8]) -> Result<Vec<ProvEvent>> {
+    let events: Vec<ProvEvent> = match serde_json::from_slice::<Value>(bytes) {
+        Ok(Value::Object(object)) if object.contains_key("events") => {
+            return decode_snapshot(bytes);
+        }
+        Ok(Value::Array(_)) => {
+            serde_json::from_slice(bytes).map_err(|err| ProvenanceError::Storage(Box::new(err)))?
+        }
+        _ => decode_json_lines(bytes)?,
+    };
+    for event in &events {
+        observe_event_id(event.id());
+    }
+    Ok(events)
+}
+
+fn decode_json_lines(bytes: &[u8]) -> Result<Vec<ProvEvent>> {
+    let text = std::str::from_utf8(bytes).map_err(|err| ProvenanceError::Storage(Box::new(err)))?;
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).map_err(|err| {
+                ProvenanceError::Storage(format!("event log line {}: {err}", index + 1).into())
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,366 @@
+//! Attribute schemas per PROV vocabulary type.
+//!
+//! Each `a2a:*` node type declares the attribute keys it must carry and the JSON
+//! type of every key it may carry. Writers run [`validate_document`] on the
+//! normalized document before persisting it (always in debug builds, otherwise
+//! when strict attributes are enabled), so a misspelled or mistyped attribute is
+//! rejected at write time rather than silently missing from later graph queries.
+//!
+//! Keys outside the schema are allowed; only known keys are type-checked.
+
+use crate::document::ProvDocument;
+use crate::error::{ProvenanceError, Result};
+use crate::store::ProvNodeKind;
+use crate::vocabulary::{a2a, a2a_types};
+use serde_json::Value;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttrKind {
+    String,
+    Integer,
+    Bool,
+    Array,
+    /// Integer or floating point.
+    Number,
+    /// Any JSON value (prompts, args, free-form metadata).
+    Any,
+}
+
+impl AttrKind {
+    fn matches(self, value: &Value) -> bool {
+        match self {
+            AttrKind::String => value.is_string(),
+            AttrKind::Integer => value.is_u64() || value.is_i64(),
+            AttrKind::Bool => value.is_boolean(),
+            AttrKind::Array => value.is_array(),
+            AttrKind::Number => value.is_number(),
+            AttrKind::Any => true,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AttrKind::String => "string",
+            AttrKind::Integer => "integer",
+            AttrKind::Bool => "boolean",
+            AttrKind::Array => "array",
+            AttrKind::Number => "number",
+            AttrKind::Any => "any",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AttrSpec {
+    pub key: &'static str,
+    pub kind: AttrKind,
+    pub required: bool,
+}
+
+const fn required(key: &'static str, kind: AttrKind) -> AttrSpec {
+    AttrSpec { key, kind, required: true }
+}
+
+const fn optional(key: &'static str, kind: AttrKind) -> AttrSpec {
+    AttrSpec { key, kind, required: false }
+}
+
+/// Scope attributes any node may carry.
+pub const COMMON: &[AttrSpec] = &[
+    optional(a2a::CONTEXT_ID, AttrKind::String),
+    optional(a2a::TASK_ID, AttrKind::String),
+    optional(a2a::EVENT_ID, AttrKind::String),
+    optional(a2a::TIMESTAMP_MS, AttrKind::Integer),
+    optional(a2a::TENANT, AttrKind::String),
+];
+
+#[derive(Debug, Clone, Copy)]
+pub struct NodeSchema {
+    pub prov_type: &'static str,
+    pub kind: ProvNodeKind,
+    pub attributes: &'static [AttrSpec],
+}
+
+/// Every registered node schema.
+pub const SCHEMAS: &[NodeSchema] = &[
+    NodeSchema {
+        prov_type: a2a_types::LLM_CALL,
+        kind: ProvNodeKind::Activity,
+        attributes: &[
+            required(a2a::CLIENT, AttrKind::String),
+            required(a2a::MODEL, AttrKind::String),
+            required(a2a::FUNCTION_NAME, AttrKind::String),
+            optional(a2a::METADATA, AttrKind::Any),
+            optional(a2a::USAGE_PROMPT_TOKENS, AttrKind::Integer),
+            optional(a2a::USAGE_COMPLETION_TOKENS, AttrKind::Integer),
+            optional(a2a::USAGE_TOTAL_TOKENS, AttrKind::Integer),
+            optional(a2a::DURATION_MS, AttrKind::Integer),
+            optional(a2a::SUCCESS, AttrKind::Bool),
+            optional(a2a::STREAM_CHUNK_COUNT, AttrKind::Integer),
+            optional(a2a::TIME_TO_FIRST_TOKEN_MS, AttrKind::Integer),
+            optional(a2a::OUTPUT_VALID, AttrKind::Bool),
+            optional(a2a::OUTPUT_MISMATCHES, AttrKind::Array),
+            optional(a2a::RETRY_ATTEMPT, AttrKind::Integer),
+            optional(a2a::RETRY_MAX_ATTEMPTS, AttrKind::Integer),
+            optional(a2a::ERROR_CODE, AttrKind::String),
+            optional(a2a::ERROR_CONTEXT, AttrKind::Array),
+        ],
+    },
+    NodeSchema {
+        prov_type: a2a_types::TOOL_CALL,
+        kind: ProvNodeKind::Activity,
+        attributes: &[
+            required(a2a::TOOL_NAME, AttrKind::String),
+            optional(a2a::FUNCTION_NAME, AttrKind::String),
+            optional(a2a::METADATA, AttrKind::Any),
+            optional(a2a::DURATION_MS, AttrKind::Integer),
+            optional(a2a::SUCCESS, AttrKind::Bool),
+            optional(a2a::RETRY_ATTEMPT, AttrKind::Integer),
+            optional(a2a::RETRY_MAX_ATTEMPTS, AttrKind::Integer),
+            optional(a2a::RETRY_FAILURE_KIND, AttrKind::String),
+            optional(a2a::RETRY_BACKOFF_MS, AttrKind::Integer),
+            optional(a2a::ERROR_CODE, AttrKind::String),
+            optional(a2a::ERROR_CONTEXT, AttrKind::Array),
+        ],
+    },
+    NodeSchema {
+        prov_type: a2a_types::LLM_PROMPT,
+        kind: ProvNodeKind::Entity,
+        attributes: &[required(a2a::PROMPT, AttrKind::Any)],
+    },
+    NodeSchema {
+        prov_type: a2a_types::TOOL_ARGS,
+        kind: ProvNodeKind::Entity,
+        attributes: &[required(a2a::ARGS, AttrKind::Any)],
+    },
+    NodeSchema {
+        prov_type: a2a_types::AGENT_BOOT,
+        kind: ProvNodeKind::Activity,
+        attributes: &[
+            required(a2a::AGENT_ID, AttrKind::String),
+            required(a2a::AGENT_TYPE, AttrKind::String),
+            required(a2a::AGENT_VERSION, AttrKind::String),
+        ],
+    },
+    NodeSchema {
+        prov_type: a2a_types::AGENT_ARCHIVE,
+        kind: ProvNodeKind::Entity,
+        attributes: &[required(a2a::ARCHIVE_PATH, AttrKind::String)],
+    },
+    NodeSchema {
+        // Shared by agent instances and the runner instance, which has no agent_id.
+        prov_type: a2a_types::AGENT_RUNTIME_INSTANCE,
+        kind: ProvNodeKind::Agent,
+        attributes: &[
+            required(a2a::AGENT_TYPE, AttrKind::String),
+            optional(a2a::AGENT_ID, AttrKind::String),
+            optional(a2a::AGENT_VERSION, AttrKind::String),
+        ],
+    },
+    NodeSchema {
+        prov_type: a2a_types::TASK,
+        kind: ProvNodeKind::Entity,
+        attributes: &[
+            required(a2a::TASK_ID, AttrKind::String),
+            optional(a2a::AGENT_ID, AttrKind::String),
+        ],
+    },
+    NodeSchema {
+        prov_type: a2a_types::TASK_EXECUTION,
+        kind: ProvNodeKind::Activity,
+        attributes: &[
+            required(a2a::TASK_ID, AttrKind::String),
+            optional(a2a::AGENT_TYPE, AttrKind::String),
+        ],
+    },
+    NodeSchema {
+        prov_type: a2a_types::TASK_STATE,
+        kind: ProvNodeKind::Entity,
+        attributes: &[
+            required(a2a::TASK_STATE_TIME, AttrKind::Integer),
+            optional(a2a::TASK_STATE, AttrKind::String),
+            optional(a2a::OLD_STATUS, AttrKind::String),
+            optional(a2a::IS_PREVIOUS, AttrKind::Bool),
+            optional(a2a::ILLEGAL_TRANSITION, AttrKind::Bool),
+            optional(a2a::TRANSITION_REJECTED, AttrKind::Bool),
+        ],
+    },
+    NodeSchema {
+        prov_type: a2a_types::MESSAGE,
+        kind: ProvNodeKind::Entity,
+        attributes: &[
+            optional(a2a::MESSAGE_ID, AttrKind::String),
+            optional(a2a::ROLE, AttrKind::String),
+            optional(a2a::CONTENT, AttrKind::Array),
+            optional(a2a::DIRECTION, AttrKind::String),
+            optional(a2a::METADATA, AttrKind::Any),
+        ],
+    },
+    NodeSchema {
+        prov_type: a2a_types::MESSAGE_PROCESSING,
+        kind: ProvNodeKind::Activity,
+        attributes: &[
+            required(a2a::MESSAGE_ID, AttrKind::String),
+            optional(a2a::ROLE, AttrKind::String),
+            optional(a2a::DIRECTION, AttrKind::String),
+        ],
+    },
+    NodeSchema {
+        prov_type: a2a_types::ARTIFACT,
+        kind: ProvNodeKind::Entity,
+        attributes: &[
+            optional(a2a::ARTIFACT_ID, AttrKind::String),
+            optional(a2a::ARTIFACT_TYPE, AttrKind::String),
+        ],
+    },
+    NodeSchema {
+        prov_type: a2a_types::FEEDBACK,
+        kind: ProvNodeKind::Entity,
+        attributes: &[
+            required(a2a::FEEDBACK_ID, AttrKind::String),
+            optional(a2a::MESSAGE_ID, AttrKind::String),
+            optional(a2a::RATING, AttrKind::Integer),
+            optional(a2a::CORRECTION, AttrKind::String),
+            optional(a2a::COMMENT, AttrKind::String),
+            optional(a2a::METADATA, AttrKind::Any),
+        ],
+    },
+    NodeSchema {
+        prov_type: a2a_types::BUDGET_EXHAUSTION,
+        kind: ProvNodeKind::Entity,
+        attributes: &[
+            required(a2a::BUDGET_SCOPE, AttrKind::String),
+            required(a2a::BUDGET_SCOPE_ID, AttrKind::String),
+            required(a2a::BUDGET_DIMENSION, AttrKind::String),
+            required(a2a::BUDGET_LIMIT, AttrKind::Integer),
+            optional(a2a::USAGE_PROMPT_TOKENS, AttrKind::Integer),
+            optional(a2a::USAGE_COMPLETION_TOKENS, AttrKind::Integer),
+            optional(a2a::USAGE_TOTAL_TOKENS, AttrKind::Integer),
+        ],
+    },
+    NodeSchema {
+        prov_type: a2a_types::DIAGNOSTIC,
+        kind: ProvNodeKind::Entity,
+        attributes: &[
+            required(a2a::LOG_LEVEL, AttrKind::String),
+            required(a2a::LOG_MESSAGE, AttrKind::String),
+        ],
+    },
+    NodeSchema {
+        prov_type: a2a_types::RUNTIME_CONFIG,
+        kind: ProvNodeKind::Entity,
+        attributes: &[
+            required(a2a::CONFIG_VERSION, AttrKind::Integer),
+            required(a2a::CONFIG_DIGEST, AttrKind::String),
+            required(a2a::CONFIG_CHANGED, AttrKind::Array),
+            required(a2a::CONFIG_TRIGGER, AttrKind::String),
+        ],
+    },
+    NodeSchema {
+        prov_type: a2a_types::BAML_SCHEMA,
+        kind: ProvNodeKind::Entity,
+        attributes: &[
+            required(a2a::AGENT_ID, AttrKind::String),
+            required(a2a::SCHEMA_DIGEST, AttrKind::String),
+            required(a2a::SCHEMA_ADDED, AttrKind::Array),
+            required(a2a::SCHEMA_REMOVED, AttrKind::Array),
+            required(a2a::SCHEMA_CHANGED, AttrKind::Array),
+        ],
+    },
+    NodeSchema {
+        prov_type: a2a_types::TOOL_USAGE,
+        kind: ProvNodeKind::Entity,
+        attributes: &[
+            required(a2a::AGENT_ID, AttrKind::String),
+            required(a2a::TOOL_NAME, AttrKind::String),
+            required(a2a::TOOL_INVOCATIONS, AttrKind::Integer),
+            required(a2a::TOOL_FAILURES, AttrKind::Integer),
+            required(a2a::TOOL_FAILURE_RATE, AttrKind::Number),
+            required(a2a::TOOL_LATENCY_P50_MS, AttrKind::Integer),
+            required(a2a::TOOL_LATENCY_P95_MS, AttrKind::Integer),
+            required(a2a::TOOL_LATENCY_P99_MS, AttrKind::Integer),
+        ],
+    },
+    NodeSchema {
+        prov_type: a2a_types::POLICY_DECISION,
+        kind: ProvNodeKind::Entity,
+        attributes: &[
+            required(a2a::POLICY_ID, AttrKind::String),
+            required(a2a::POLICY_EFFECT, AttrKind::String),
+            required(a2a::POLICY_TARGET, AttrKind::String),
+            required(a2a::POLICY_SUBJECT, AttrKind::String),
+            optional(a2a::POLICY_REASON, AttrKind::String),
+        ],
+    },
+    NodeSchema {
+        prov_type: a2a_types::APPROVAL_DECISION,
+        kind: ProvNodeKind::Entity,
+        attributes: &[
+            required(a2a::APPROVAL_ID, AttrKind::String),
+            required(a2a::TOOL_NAME, AttrKind::String),
+            required(a2a::ARGS, AttrKind::Any),
+            required(a2a::APPROVAL_VERDICT, AttrKind::String),
+            optional(a2a::APPROVER, AttrKind::String),
+            optional(a2a::APPROVAL_REASON, AttrKind::String),
+            required(a2a::APPROVAL_WAITED_MS, AttrKind::Integer),
+        ],
+    },
+    NodeSchema {
+        prov_type: a2a_types::CONTEXT,
+        kind: ProvNodeKind::Entity,
+        attributes: &[required(a2a::CONTEXT_ID, AttrKind::String)],
+    },
+];
+
+/// Schema registered for `prov_type`, if any.
+pub fn schema_for(prov_type: &str) -> Option<&'static NodeSchema> {
+    SCHEMAS.iter().find(|schema| schema.prov_type == prov_type)
+}
+
+/// Check one node's attributes against the schema for its `prov_type`.
+///
+/// Nodes without a `prov_type`, or with a type that has no schema, pass.
+pub fn validate_attributes(
+    node_id: &str,
+    prov_type: Option<&str>,
+    attributes: &HashMap<String, Value>,
+) -> Result<()> {
+    let Some(schema) = prov_type.and_then(schema_for) else {
+        return Ok(());
+    };
+    for spec in COMMON.iter().chain(schema.attributes) {
+        match attributes.get(spec.key) {
+            None if spec.required => {
+                return Err(invalid(node_id, spec.key, format!("required by {}", schema.prov_type)));
+            }
+            Some(value) if !spec.kind.matches(value) => {
+                return Err(invalid(node_id, spec.key, format!("expected {}", spec.kind.as_str())));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Validate every entity, activity and agent in `doc`.
+pub fn validate_document(doc: &ProvDocument) -> Result<()> {
+    for (id, entity) in doc.entities() {
+        validate_attributes(id.as_str(), entity.prov_type.as_deref(), &entity.attributes)?;
+    }
+    for (id, activity) in doc.activities() {
+        validate_attributes(id.as_str(), activity.prov_type.as_deref(), &activity.attributes)?;
+    }
+    for (id, agent) in doc.agents() {
+        validate_attributes(id.as_str(), agent.prov_type.as_deref(), &agent.attributes)?;
+    }
+    Ok(())
+}
+
+fn invalid(node_id: &str, key: &str, reason: String) -> ProvenanceError {
+    ProvenanceError::InvalidAttribute {
+        node_id: node_id.to_string(),
+        key: key.to_string(),
+        reason,
+    }
+}
@@ -0,0 +1,71 @@
+//! Periodic disk snapshots for the in-memory provenance store.
+//!
+//! Gives the memory backend restart durability without a database: the runner
+//! loads the last snapshot at startup and [`ProvenanceSnapshotter`] rewrites it on
+//! an interval whenever new events have arrived.
+
+use crate::store::InMemoryProvenanceStore;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+#[derive(Debug, Clone)]
+pub struct SnapshotConfig {
+    pub path: PathBuf,
+    pub interval: Duration,
+}
+
+impl SnapshotConfig {
+    pub fn new(path: impl Into<PathBuf>, interval: Duration) -> Self {
+        Self { path: path.into(), interval }
+    }
+}
+
+/// Background task that snapshots a store; aborted on drop.
+pub struct ProvenanceSnapshotter {
+    store: Arc<InMemoryProvenanceStore>,
+    config: SnapshotConfig,
+    handle: JoinHandle<()>,
+}
+
+impl ProvenanceSnapshotter {
+    pub fn spawn(store: Arc<InMemoryProvenanceStore>, config: SnapshotConfig) -> Self {
+        let task_store = store.clone();
+        let task_config = config.clone();
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(task_config.interval);
+            // Skip the immediate first tick; the store was just loaded from this file.
+            ticker.tick().await;
+            let mut saved_len = task_store.len().await;
+            loop {
+                ticker.tick().await;
+                let len = task_store.len().await;
+                if len == saved_len {
+                    continue;
+                }
+                match task_store.save_snapshot(&task_config.path).await {
+                    Ok(()) => saved_len = len,
+                    Err(err) => tracing::warn!(
+                        error = %err,
+                        path = %task_config.path.display(),
+                        "Failed to write provenance snapshot"
+                    ),
+                }
+            }
+        });
+        Self { store, config, handle }
+    }
+
+    /// Stop the background task and write a final snapshot.
+    pub async fn shutdown(self) -> crate::error::Result<()> {
+        self.handle.abort();
+        self.store.save_snapshot(&self.config.path).await
+    }
+}
+
+impl Drop for ProvenanceSnapshotter {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
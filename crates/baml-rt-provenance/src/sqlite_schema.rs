@@ -0,0 +1,110 @@
+//! Schema and migrations for the SQLite provenance store.
+//!
+//! The schema version lives in `PRAGMA user_version`. Each entry in
+//! [`MIGRATIONS`] upgrades the database by one version and runs in its own
+//! transaction, so a file written by an older build is upgraded in place on open.
+//! Migrations are append-only: never edit one that has shipped.
+//!
+//! Tables:
+//! - `events`: every accepted `ProvEvent` as JSON, in arrival order.
+//! - `nodes`: the merged PROV document, one row per entity, activity or agent.
+//!   Later events merge their attributes into existing rows, like `MERGE ... SET +=`
+//!   in the FalkorDB writer. Scope ids are copied into columns for filtering.
+//! - `relations`: PROV and A2A-derived relations, unique per
+//!   `(relation, from_id, to_id)`.
+
+use crate::error::{ProvenanceError, Result};
+use rusqlite::Connection;
+
+const MIGRATIONS: &[&str] = &[
+    // 1: initial schema
+    r#"
+    CREATE TABLE events (
+        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+        event_id TEXT NOT NULL UNIQUE,
+        event TEXT NOT NULL
+    );
+
+    CREATE TABLE nodes (
+        id TEXT PRIMARY KEY,
+        kind TEXT NOT NULL,
+        prov_type TEXT,
+        time_ms INTEGER,
+        context_id TEXT,
+        task_id TEXT,
+        agent_id TEXT,
+        attributes TEXT NOT NULL
+    );
+    CREATE INDEX nodes_context_id ON nodes (context_id);
+    CREATE INDEX nodes_task_id ON nodes (task_id);
+    CREATE INDEX nodes_agent_id ON nodes (agent_id);
+    CREATE INDEX nodes_time_ms ON nodes (time_ms);
+
+    CREATE TABLE relations (
+        relation TEXT NOT NULL,
+        from_id TEXT NOT NULL,
+        to_id TEXT NOT NULL,
+        attributes TEXT NOT NULL,
+        PRIMARY KEY (relation, from_id, to_id)
+    );
+    CREATE INDEX relations_to_id ON relations (to_id);
+    "#,
+];
+
+/// Schema version a fully migrated database reports.
+pub const SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// Version recorded in the database; 0 for a new file.
+pub fn schema_version(conn: &Connection) -> Result<u32> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(storage_error)
+}
+
+/// Apply every migration newer than the database's current version.
+///
+/// Fails without touching the file if it was written by a newer schema.
+pub fn migrate(conn: &mut Connection) -> Result<()> {
+    let current = schema_version(conn)?;
+    if current > SCHEMA_VERSION {
+        return Err(ProvenanceError::Storage(
+            format!(
+                "provenance database schema version {current} is newer than supported version {SCHEMA_VERSION}"
+            )
+            .into(),
+        ));
+    }
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(current as usize) {
+        let version = index as u32 + 1;
+        let tx = conn.transaction().map_err(storage_error)?;
+        tx.execute_batch(migration).map_err(storage_error)?;
+        tx.pragma_update(None, "user_version", version).map_err(storage_error)?;
+        tx.commit().map_err(storage_error)?;
+        tracing::debug!(version, "Applied provenance SQLite migration");
+    }
+    Ok(())
+}
+
+pub(crate) fn storage_error(err: rusqlite::Error) -> ProvenanceError {
+    ProvenanceError::Storage(Box::new(err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrate_is_idempotent() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), 0);
+        migrate(&mut conn).unwrap();
+        migrate(&mut conn).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn migrate_rejects_newer_schema() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION + 1).unwrap();
+        assert!(migrate(&mut conn).is_err());
+    }
+}
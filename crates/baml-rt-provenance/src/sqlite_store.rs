@@ -0,0 +1,451 @@
+//! SQLite-backed provenance writer.
+//!
+//! Durable provenance for deployments without FalkorDB. Each event is stored
+//! verbatim and its normalized PROV document is merged into `nodes` and
+//! `relations` tables (see [`crate::sqlite_schema`]) in the same transaction,
+//! so the file always holds a consistent document for the events it contains.
+//! `add_events` stores a whole batch in one transaction, normalizing the events
+//! it has not seen before into one document.
+//!
+//! rusqlite is synchronous; every statement runs on Tokio's blocking pool
+//! behind a single connection.
+use crate::cypher::{
+    acted_on_behalf_of_props, qualified_generation_props, relation_props, used_props,
+    was_associated_with_props, was_derived_from_props, was_generated_by_props,
+    was_informed_by_props,
+};
+use crate::error::{ProvenanceError, Result};
+use crate::events::{observe_event_id, ProvEvent};
+use crate::normalizer::{validate_event, DefaultProvNormalizer, NormalizedProv, ProvNormalizer};
+use crate::schema::validate_document;
+use crate::sqlite_schema::{migrate, storage_error};
+use crate::store::{
+    document_records, sort_records, ProvNodeKind, ProvNodeRecord, ProvenanceQuery,
+    ProvenanceReader, ProvenanceWriter,
+};
+use crate::vocabulary::{a2a, prov_relations};
+use async_trait::async_trait;
+use baml_rt_core::ids::EventId;
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone)]
+pub struct SqliteProvenanceConfig {
+    /// Database file; created with its parent directories if missing.
+    pub path: PathBuf,
+    /// Reject events whose normalized attributes violate the vocabulary schemas.
+    /// Debug builds always validate.
+    pub strict_attributes: bool,
+}
+
+impl SqliteProvenanceConfig {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into(), strict_attributes: false }
+    }
+
+    pub fn with_strict_attributes(mut self, strict: bool) -> Self {
+        self.strict_attributes = strict;
+        self
+    }
+}
+
+/// A relation row: `(relation, from_id, to_id, attributes)`.
+type RelationRow = (String, String, String, HashMap<String, Value>);
+
+#[derive(Clone)]
+pub struct SqliteProvenanceWriter {
+    config: SqliteProvenanceConfig,
+    normalizer: Arc<dyn ProvNormalizer>,
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteProvenanceWriter {
+    /// Open (or create) the database and bring its schema up to date.
+    pub fn open(config: SqliteProvenanceConfig) -> Result<Self> {
+        Self::with_normalizer(config, Arc::new(DefaultProvNormalizer::default()))
+    }
+
+    pub fn with_normalizer(
+        config: SqliteProvenanceConfig,
+        normalizer: Arc<dyn ProvNormalizer>,
+    ) -> Result<Self> {
+        if let Some(parent) = config.path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent).map_err(|err| ProvenanceError::Storage(Box::new(err)))?;
+        }
+        let mut conn = Connection::open(&config.path).map_err(storage_error)?;
+        conn.pragma_update(None, "journal_mode", "WAL").map_err(storage_error)?;
+        migrate(&mut conn)?;
+        observe_stored_event_ids(&conn)?;
+        tracing::debug!(path = %config.path.display(), "Opened SQLite provenance store");
+        Ok(Self { config, normalizer, conn: Arc::new(Mutex::new(conn)) })
+    }
+
+    /// Every stored event, in the order it was written.
+    pub async fn events(&self) -> Result<Vec<ProvEvent>> {
+        self.with_connection(|conn| {
+            let mut stmt = conn
+                .prepare("SELECT event FROM events ORDER BY seq")
+                .map_err(storage_error)?;
+            let rows = stmt
+                .query_map([], |row| row.get::<_, String>(0))
+                .map_err(storage_error)?;
+            rows.map(|row| {
+                let json = row.map_err(storage_error)?;
+                serde_json::from_str(&json).map_err(|err| ProvenanceError::Storage(Box::new(err)))
+            })
+            .collect()
+        })
+        .await
+    }
+
+    /// Run `f` against the connection on the blocking pool.
+    async fn with_connection<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = conn.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            f(&mut conn)
+        })
+        .await
+        .map_err(|err| ProvenanceError::Storage(Box::new(err)))?
+    }
+}
+
+/// Advance the event counter past ids already in the file, as snapshot loading
+/// does for the in-memory store.
+fn observe_stored_event_ids(conn: &Connection) -> Result<()> {
+    let mut stmt = conn.prepare("SELECT event_id FROM events").map_err(storage_error)?;
+    let ids = stmt.query_map([], |row| row.get::<_, String>(0)).map_err(storage_error)?;
+    for id in ids {
+        let id = id.map_err(storage_error)?;
+        if let Ok(id) = serde_json::from_value::<EventId>(Value::String(id)) {
+            observe_event_id(&id);
+        }
+    }
+    Ok(())
+}
+
+fn relation_rows(normalized: &NormalizedProv) -> Vec<RelationRow> {
+    let document = &normalized.document;
+    let mut rows: Vec<RelationRow> = Vec::new();
+    for (_, used) in document.used() {
+        rows.push((
+            prov_relations::USED.to_string(),
+            used.activity.as_str().to_string(),
+            used.entity.as_str().to_string(),
+            used_props(used),
+        ));
+    }
+    for (_, generated) in document.was_generated_by() {
+        rows.push((
+            prov_relations::WAS_GENERATED_BY.to_string(),
+            generated.entity.id().to_string(),
+            generated.activity.as_str().to_string(),
+            was_generated_by_props(generated),
+        ));
+    }
+    for (_, generation) in document.qualified_generation() {
+        rows.push((
+            prov_relations::QUALIFIED_GENERATION.to_string(),
+            generation.entity.id().to_string(),
+            generation.activity.as_str().to_string(),
+            qualified_generation_props(generation),
+        ));
+    }
+    for (_, assoc) in document.was_associated_with() {
+        rows.push((
+            prov_relations::WAS_ASSOCIATED_WITH.to_string(),
+            assoc.activity.as_str().to_string(),
+            assoc.agent.as_str().to_string(),
+            was_associated_with_props(assoc),
+        ));
+    }
+    for (_, derived) in document.was_derived_from() {
+        rows.push((
+            prov_relations::WAS_DERIVED_FROM.to_string(),
+            derived.generated_entity.as_str().to_string(),
+            derived.used_entity.as_str().to_string(),
+            was_derived_from_props(derived),
+        ));
+    }
+    for (_, delegation) in document.acted_on_behalf_of() {
+        rows.push((
+            prov_relations::ACTED_ON_BEHALF_OF.to_string(),
+            delegation.delegate.as_str().to_string(),
+            delegation.responsible.as_str().to_string(),
+            acted_on_behalf_of_props(delegation),
+        ));
+    }
+    for (_, informed) in document.was_informed_by() {
+        rows.push((
+            prov_relations::WAS_INFORMED_BY.to_string(),
+            informed.informed.as_str().to_string(),
+            informed.informant.as_str().to_string(),
+            was_informed_by_props(),
+        ));
+    }
+    for relation in &normalized.derived_relations {
+        rows.push((
+            relation.relation.as_str().to_string(),
+            relation.from.id().to_string(),
+            relation.to.id().to_string(),
+            relation_props(relation),
+        ));
+    }
+    rows
+}
+
+fn to_json(value: &impl serde::Serialize) -> Result<String> {
+    serde_json::to_string(value).map_err(|err| ProvenanceError::Storage(Box::new(err)))
+}
+
+fn kind_from_str(value: &str) -> Option<ProvNodeKind> {
+    [ProvNodeKind::Entity, ProvNodeKind::Activity, ProvNodeKind::Agent]
+        .into_iter()
+        .find(|kind| kind.base_label() == value)
+}
+
+/// Store one event row. Returns false if the event id was already stored.
+fn insert_event(tx: &Transaction<'_>, event_id: &str, event_json: &str) -> Result<bool> {
+    let inserted = tx
+        .execute(
+            "INSERT OR IGNORE INTO events (event_id, event) VALUES (?1, ?2)",
+            params![event_id, event_json],
+        )
+        .map_err(storage_error)?;
+    Ok(inserted > 0)
+}
+
+/// Merge a normalized document into the `nodes` and `relations` tables.
+fn merge_document(
+    tx: &Transaction<'_>,
+    nodes: &[ProvNodeRecord],
+    relations: &[RelationRow],
+) -> Result<()> {
+    for node in nodes {
+        let existing: Option<String> = tx
+            .query_row("SELECT attributes FROM nodes WHERE id = ?1", [&node.id], |row| row.get(0))
+            .optional()
+            .map_err(storage_error)?;
+        let mut attributes: HashMap<String, Value> = match existing {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|err| ProvenanceError::Storage(Box::new(err)))?,
+            None => HashMap::new(),
+        };
+        attributes.extend(node.attributes.clone());
+        let scope = |key: &str| attributes.get(key).and_then(Value::as_str).map(str::to_string);
+        let (context_id, task_id, agent_id) =
+            (scope(a2a::CONTEXT_ID), scope(a2a::TASK_ID), scope(a2a::AGENT_ID));
+        tx.execute(
+            "INSERT INTO nodes (id, kind, prov_type, time_ms, context_id, task_id, agent_id, attributes)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT (id) DO UPDATE SET
+                 prov_type = coalesce(nodes.prov_type, excluded.prov_type),
+                 time_ms = coalesce(nodes.time_ms, excluded.time_ms),
+                 context_id = excluded.context_id,
+                 task_id = excluded.task_id,
+                 agent_id = excluded.agent_id,
+                 attributes = excluded.attributes",
+            params![
+                node.id,
+                node.kind.base_label(),
+                node.prov_type,
+                node.time_ms.map(|time| time as i64),
+                context_id,
+                task_id,
+                agent_id,
+                to_json(&attributes)?,
+            ],
+        )
+        .map_err(storage_error)?;
+    }
+
+    for (relation, from_id, to_id, attributes) in relations {
+        tx.execute(
+            "INSERT INTO relations (relation, from_id, to_id, attributes) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (relation, from_id, to_id) DO UPDATE SET
+                 attributes = json_patch(relations.attributes, excluded.attributes)",
+            params![relation, from_id, to_id, to_json(attributes)?],
+        )
+        .map_err(storage_error)?;
+    }
+    Ok(())
+}
+
+/// Store one event and merge its document. Returns false if the event id was
+/// already stored, in which case nothing else is written.
+fn write_event(
+    tx: &Transaction<'_>,
+    event_id: &str,
+    event_json: &str,
+    nodes: &[ProvNodeRecord],
+    relations: &[RelationRow],
+) -> Result<bool> {
+    if !insert_event(tx, event_id, event_json)? {
+        return Ok(false);
+    }
+    merge_document(tx, nodes, relations)?;
+    Ok(true)
+}
+
+#[async_trait]
+impl ProvenanceWriter for SqliteProvenanceWriter {
+    async fn add_event(&self, event: ProvEvent) -> Result<()> {
+        validate_event(&event)?;
+        let normalized = self.normalizer.normalize(&event)?;
+        if self.config.strict_attributes || cfg!(debug_assertions) {
+            validate_document(&normalized.document)?;
+        }
+        let event_id = event.id().as_str().to_string();
+        let event_json = to_json(&event)?;
+        let nodes = document_records(&normalized.document);
+        let relations = relation_rows(&normalized);
+
+        let written = self
+            .with_connection({
+                let event_id = event_id.clone();
+                move |conn| {
+                    let tx = conn.transaction().map_err(storage_error)?;
+                    let written = write_event(&tx, &event_id, &event_json, &nodes, &relations)?;
+                    tx.commit().map_err(storage_error)?;
+                    Ok(written)
+                }
+            })
+            .await?;
+        if !written {
+            tracing::debug!(event_id, "Skipped provenance event already stored");
+        }
+        Ok(())
+    }
+
+    /// One transaction for the batch. Events already stored are skipped before
+    /// normalizing, so the rest are merged as one document.
+    async fn add_events(&self, events: Vec<ProvEvent>) -> Result<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let mut rows = Vec::with_capacity(events.len());
+        for event in &events {
+            validate_event(event)?;
+            rows.push((event.id().as_str().to_string(), to_json(event)?));
+        }
+        let normalizer = self.normalizer.clone();
+        let validate = self.config.strict_attributes || cfg!(debug_assertions);
+        let total = events.len();
+
+        let fresh = self
+            .with_connection(move |conn| {
+                let tx = conn.transaction().map_err(storage_error)?;
+                let mut fresh = Vec::with_capacity(events.len());
+                for (event, (event_id, event_json)) in events.into_iter().zip(&rows) {
+                    if insert_event(&tx, event_id, event_json)? {
+                        fresh.push(event);
+                    }
+                }
+                if !fresh.is_empty() {
+                    let normalized = normalizer.normalize_batch(&fresh)?;
+                    if validate {
+                        validate_document(&normalized.document)?;
+                    }
+                    merge_document(
+                        &tx,
+                        &document_records(&normalized.document),
+                        &relation_rows(&normalized),
+                    )?;
+                }
+                tx.commit().map_err(storage_error)?;
+                Ok(fresh.len())
+            })
+            .await?;
+        if fresh < total {
+            tracing::debug!(skipped = total - fresh, "Skipped provenance events already stored");
+        }
+        Ok(())
+    }
+
+    async fn health_check(&self) -> Result<()> {
+        self.with_connection(|conn| {
+            conn.query_row("SELECT 1", [], |_| Ok(())).map_err(storage_error)
+        })
+        .await
+    }
+}
+
+/// Reads return each node's full merged attributes.
+#[async_trait]
+impl ProvenanceReader for SqliteProvenanceWriter {
+    async fn query_nodes(&self, query: &ProvenanceQuery) -> Result<Vec<ProvNodeRecord>> {
+        let query = query.clone();
+        let mut records = self
+            .with_connection(move |conn| {
+                let mut conditions = Vec::new();
+                let mut values: Vec<rusqlite::types::Value> = Vec::new();
+                let filters = [
+                    ("task_id", query.task_id.as_ref().map(|id| id.as_str())),
+                    ("context_id", query.context_id.as_ref().map(|id| id.as_str())),
+                    ("agent_id", query.agent_id.as_ref().map(|id| id.as_str())),
+                ];
+                for (column, value) in filters {
+                    if let Some(value) = value {
+                        values.push(value.to_string().into());
+                        conditions.push(format!("{column} = ?{}", values.len()));
+                    }
+                }
+                if let Some(since) = query.since_ms {
+                    values.push(i64::try_from(since).unwrap_or(i64::MAX).into());
+                    conditions.push(format!("time_ms >= ?{}", values.len()));
+                }
+                if let Some(until) = query.until_ms {
+                    values.push(i64::try_from(until).unwrap_or(i64::MAX).into());
+                    conditions.push(format!("time_ms <= ?{}", values.len()));
+                }
+                let where_clause = if conditions.is_empty() {
+                    String::new()
+                } else {
+                    format!(" WHERE {}", conditions.join(" AND "))
+                };
+                let sql = format!(
+                    "SELECT id, kind, prov_type, time_ms, attributes FROM nodes{where_clause}"
+                );
+                let mut stmt = conn.prepare(&sql).map_err(storage_error)?;
+                let rows = stmt
+                    .query_map(rusqlite::params_from_iter(values), |row| {
+                        Ok((
+                            row.get::<_, String>(0)?,
+                            row.get::<_, String>(1)?,
+                            row.get::<_, Option<String>>(2)?,
+                            row.get::<_, Option<i64>>(3)?,
+                            row.get::<_, String>(4)?,
+                        ))
+                    })
+                    .map_err(storage_error)?;
+                let mut records = Vec::new();
+                for row in rows {
+                    let (id, kind, prov_type, time_ms, attributes) = row.map_err(storage_error)?;
+                    let Some(kind) = kind_from_str(&kind) else {
+                        continue;
+                    };
+                    records.push(ProvNodeRecord {
+                        id,
+                        kind,
+                        prov_type,
+                        time_ms: time_ms.map(|time| time as u64),
+                        attributes: serde_json::from_str(&attributes)
+                            .map_err(|err| ProvenanceError::Storage(Box::new(err)))?,
+                    });
+                }
+                Ok(records)
+            })
+            .await?;
+        sort_records(&mut records);
+        Ok(records)
+    }
+}
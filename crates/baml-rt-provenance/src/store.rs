@@ -1,9 +1,42 @@
-use crate::error::Result;
-use crate::events::ProvEvent;
-use crate::normalizer::validate_event;
+use crate::document::ProvDocument;
+use crate::error::{ProvenanceError, Result};
+use crate::events::{observe_event_id, ProvEvent};
+use crate::normalizer::{validate_event, DefaultProvNormalizer, ProvNormalizer};
+use crate::redaction::RedactionPolicy;
+use crate::vocabulary::a2a;
 use async_trait::async_trait;
+use baml_rt_core::ids::{AgentId, ContextId, TaskId};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::Arc;
 use tokio::sync::RwLock;
 
+const SNAPSHOT_VERSION: u32 = 1;
+
+/// On-disk form of an [`InMemoryProvenanceStore`].
+#[derive(Debug, Serialize, Deserialize)]
+struct Snapshot {
+    version: u32,
+    events: Vec<ProvEvent>,
+}
+
+/// Parse snapshot bytes and reserve their event ids so new events never reuse them.
+pub(crate) fn decode_snapshot(bytes: &[u8]) -> Result<Vec<ProvEvent>> {
+    let snapshot: Snapshot =
+        serde_json::from_slice(bytes).map_err(|err| ProvenanceError::Storage(Box::new(err)))?;
+    if snapshot.version != SNAPSHOT_VERSION {
+        return Err(ProvenanceError::Storage(
+            format!("unsupported provenance snapshot version {}", snapshot.version).into(),
+        ));
+    }
+    for event in &snapshot.events {
+        observe_event_id(event.id());
+    }
+    Ok(snapshot.events)
+}
+
 #[async_trait]
 pub trait ProvenanceWriter: Send + Sync {
     async fn add_event(&self, event: ProvEvent) -> Result<()>;
@@ -15,26 +48,244 @@ pub trait ProvenanceWriter: Send + Sync {
         Ok(())
     }
 
+    /// Verify the backing store is reachable. In-process stores are always healthy.
+    async fn health_check(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Persist any events the writer has buffered. Call before shutdown; writers
+    /// that write through are a no-op.
+    async fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Events accepted but not yet persisted. Writers that write through have none.
+    async fn pending_events(&self) -> usize {
+        0
+    }
+
     async fn add_event_with_logging(&self, event: ProvEvent, context: &str) {
         if let Err(e) = self.add_event(event).await {
             tracing::warn!(error = ?e, context = context, "Failed to record provenance event");
+            baml_rt_observability::metrics::record_provenance_write_failure(context);
         }
     }
 }
 
  
 
+/// Filter for [`ProvenanceReader::query_nodes`]. Unset fields match everything.
+///
+/// Id filters match the node's `a2a:task_id`, `a2a:context_id` and `a2a:agent_id`
+/// attributes. The time range applies to [`ProvNodeRecord::time_ms`]; nodes
+/// without a time are excluded once either bound is set.
+#[derive(Debug, Clone, Default)]
+pub struct ProvenanceQuery {
+    pub task_id: Option<TaskId>,
+    pub context_id: Option<ContextId>,
+    pub agent_id: Option<AgentId>,
+    pub since_ms: Option<u64>,
+    pub until_ms: Option<u64>,
+}
+
+impl ProvenanceQuery {
+    pub fn for_task(mut self, task_id: TaskId) -> Self {
+        self.task_id = Some(task_id);
+        self
+    }
+
+    pub fn for_context(mut self, context_id: ContextId) -> Self {
+        self.context_id = Some(context_id);
+        self
+    }
+
+    pub fn for_agent(mut self, agent_id: AgentId) -> Self {
+        self.agent_id = Some(agent_id);
+        self
+    }
+
+    /// Inclusive time bounds in epoch milliseconds.
+    pub fn between(mut self, since_ms: Option<u64>, until_ms: Option<u64>) -> Self {
+        self.since_ms = since_ms;
+        self.until_ms = until_ms;
+        self
+    }
+
+    fn has_time_range(&self) -> bool {
+        self.since_ms.is_some() || self.until_ms.is_some()
+    }
+
+    pub fn matches(&self, record: &ProvNodeRecord) -> bool {
+        let attr_matches = |key: &str, expected: Option<&str>| match expected {
+            Some(expected) => record.attributes.get(key).and_then(Value::as_str) == Some(expected),
+            None => true,
+        };
+        if !attr_matches(a2a::TASK_ID, self.task_id.as_ref().map(TaskId::as_str))
+            || !attr_matches(a2a::CONTEXT_ID, self.context_id.as_ref().map(ContextId::as_str))
+            || !attr_matches(a2a::AGENT_ID, self.agent_id.as_ref().map(AgentId::as_str))
+        {
+            return false;
+        }
+        if !self.has_time_range() {
+            return true;
+        }
+        match record.time_ms {
+            Some(time_ms) => {
+                self.since_ms.is_none_or(|since| time_ms >= since)
+                    && self.until_ms.is_none_or(|until| time_ms <= until)
+            }
+            None => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProvNodeKind {
+    Entity,
+    Activity,
+    Agent,
+}
+
+impl ProvNodeKind {
+    /// Label and `prov:base_type` of nodes of this kind.
+    pub fn base_label(self) -> &'static str {
+        match self {
+            ProvNodeKind::Entity => "ProvEntity",
+            ProvNodeKind::Activity => "ProvActivity",
+            ProvNodeKind::Agent => "ProvAgent",
+        }
+    }
+
+    pub fn from_base_label(label: &str) -> Option<Self> {
+        [ProvNodeKind::Entity, ProvNodeKind::Activity, ProvNodeKind::Agent]
+            .into_iter()
+            .find(|kind| kind.base_label() == label)
+    }
+}
+
+/// A PROV node as returned by a [`ProvenanceReader`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProvNodeRecord {
+    pub id: String,
+    pub kind: ProvNodeKind,
+    pub prov_type: Option<String>,
+    /// Activity start (or end) time, or the state time of task state entities.
+    pub time_ms: Option<u64>,
+    pub attributes: HashMap<String, Value>,
+}
+
+/// Read access to stored provenance, independent of the backend.
+#[async_trait]
+pub trait ProvenanceReader: Send + Sync {
+    /// Nodes matching `query`, ordered by time (untimed nodes last) and then id.
+    async fn query_nodes(&self, query: &ProvenanceQuery) -> Result<Vec<ProvNodeRecord>>;
+
+    /// Every node recorded for a task.
+    async fn task_history(&self, task_id: &TaskId) -> Result<Vec<ProvNodeRecord>> {
+        self.query_nodes(&ProvenanceQuery::default().for_task(task_id.clone())).await
+    }
+}
+
+/// One record per node in `document`, before any merging across events.
+pub(crate) fn document_records(document: &ProvDocument) -> Vec<ProvNodeRecord> {
+    let entities = document.entities().map(|(id, entity)| ProvNodeRecord {
+        id: id.as_str().to_string(),
+        kind: ProvNodeKind::Entity,
+        prov_type: entity.prov_type.clone(),
+        time_ms: entity.attributes.get(a2a::TASK_STATE_TIME).and_then(Value::as_u64),
+        attributes: entity.attributes.clone(),
+    });
+    let activities = document.activities().map(|(id, activity)| ProvNodeRecord {
+        id: id.as_str().to_string(),
+        kind: ProvNodeKind::Activity,
+        prov_type: activity.prov_type.clone(),
+        time_ms: activity.start_time_ms.or(activity.end_time_ms),
+        attributes: activity.attributes.clone(),
+    });
+    let agents = document.agents().map(|(id, agent)| ProvNodeRecord {
+        id: id.as_str().to_string(),
+        kind: ProvNodeKind::Agent,
+        prov_type: agent.prov_type.clone(),
+        time_ms: None,
+        attributes: agent.attributes.clone(),
+    });
+    entities.chain(activities).chain(agents).collect()
+}
+
+pub(crate) fn sort_records(records: &mut [ProvNodeRecord]) {
+    records.sort_by(|a, b| {
+        let time = |record: &ProvNodeRecord| record.time_ms.unwrap_or(u64::MAX);
+        time(a).cmp(&time(b)).then_with(|| a.id.cmp(&b.id))
+    });
+}
+
 pub struct InMemoryProvenanceStore {
     events: RwLock<Vec<ProvEvent>>,
+    redaction: Option<Arc<RedactionPolicy>>,
 }
 
 impl InMemoryProvenanceStore {
     pub fn new() -> Self {
         Self {
             events: RwLock::new(Vec::new()),
+            redaction: None,
         }
     }
 
+    /// Redact events with `policy` as they are added, see
+    /// [`RedactionPolicy::redact_event`], so neither queries, [`Self::events`]
+    /// nor snapshots hold what it removes.
+    pub fn with_redaction(mut self, policy: RedactionPolicy) -> Self {
+        self.redaction = Some(Arc::new(policy));
+        self
+    }
+
+    /// Restore a store from a snapshot written by [`Self::save_snapshot`],
+    /// redacting with `redaction` what it restores and what is added later.
+    /// A missing file yields an empty store.
+    pub async fn load_snapshot(path: &Path, redaction: Option<RedactionPolicy>) -> Result<Self> {
+        let store = Self { redaction: redaction.map(Arc::new), ..Self::new() };
+        let bytes = match tokio::fs::read(path).await {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(store),
+            Err(err) => return Err(ProvenanceError::Storage(Box::new(err))),
+        };
+        let mut events = decode_snapshot(&bytes)?;
+        if let Some(policy) = &store.redaction {
+            events.iter_mut().for_each(|event| policy.redact_event(event));
+        }
+        Ok(Self { events: RwLock::new(events), ..store })
+    }
+
+    /// Write all events to `path`, replacing it atomically.
+    pub async fn save_snapshot(&self, path: &Path) -> Result<()> {
+        let snapshot = Snapshot { version: SNAPSHOT_VERSION, events: self.events().await };
+        let bytes =
+            serde_json::to_vec(&snapshot).map_err(|err| ProvenanceError::Storage(Box::new(err)))?;
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|err| ProvenanceError::Storage(Box::new(err)))?;
+        }
+        let tmp = path.with_extension("tmp");
+        tokio::fs::write(&tmp, bytes)
+            .await
+            .map_err(|err| ProvenanceError::Storage(Box::new(err)))?;
+        tokio::fs::rename(&tmp, path)
+            .await
+            .map_err(|err| ProvenanceError::Storage(Box::new(err)))
+    }
+
+    pub async fn len(&self) -> usize {
+        self.events.read().await.len()
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.events.read().await.is_empty()
+    }
+
     pub async fn events(&self) -> Vec<ProvEvent> {
         let events = self.events.read().await;
         let mut cloned = events.clone();
@@ -51,11 +302,49 @@ impl Default for InMemoryProvenanceStore {
 
 #[async_trait]
 impl ProvenanceWriter for InMemoryProvenanceStore {
-    async fn add_event(&self, event: ProvEvent) -> Result<()> {
+    async fn add_event(&self, mut event: ProvEvent) -> Result<()> {
         validate_event(&event)?;
+        if let Some(policy) = &self.redaction {
+            policy.redact_event(&mut event);
+        }
         let mut events = self.events.write().await;
         events.push(event);
         Ok(())
     }
 }
 
+
+#[async_trait]
+impl ProvenanceReader for InMemoryProvenanceStore {
+    async fn query_nodes(&self, query: &ProvenanceQuery) -> Result<Vec<ProvNodeRecord>> {
+        // Replay through one normalizer so agent registrations carry across events.
+        let normalizer = DefaultProvNormalizer::default();
+        let mut nodes: BTreeMap<String, ProvNodeRecord> = BTreeMap::new();
+        for event in self.events().await {
+            let normalized = match normalizer.normalize(&event) {
+                Ok(normalized) => normalized,
+                Err(err) => {
+                    tracing::debug!(error = %err, event_id = event.id().as_str(), "Skipping event in provenance query");
+                    continue;
+                }
+            };
+            for record in document_records(&normalized.document) {
+                match nodes.get_mut(&record.id) {
+                    // Later events extend a node the way MERGE ... SET += does in the graph.
+                    Some(existing) => {
+                        existing.attributes.extend(record.attributes);
+                        existing.time_ms = existing.time_ms.or(record.time_ms);
+                        existing.prov_type = existing.prov_type.take().or(record.prov_type);
+                    }
+                    None => {
+                        nodes.insert(record.id.clone(), record);
+                    }
+                }
+            }
+        }
+        let mut records: Vec<ProvNodeRecord> =
+            nodes.into_values().filter(|record| query.matches(record)).collect();
+        sort_records(&mut records);
+        Ok(records)
+    }
+}
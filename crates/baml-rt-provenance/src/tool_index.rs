@@ -1,8 +1,23 @@
-//! Tool metadata indexing for FalkorDB.
+//! Tool metadata indexing.
+//!
+//! Booted agents publish their tool metadata through a [`ToolIndexer`]. The
+//! FalkorDB indexer upserts `ToolFunction` nodes with a fulltext index; the file
+//! indexer keeps a JSON catalog for deployments without a graph store. Both key
+//! entries by package and tool name, so packages that ship a tool of the same
+//! name each keep their own entry.
+//!
+//! Entries are stamped with the owning package and its signature. Re-indexing a
+//! package removes entries left over from its previous versions, and
+//! [`detect_drift`] compares the live registry against what is indexed.
 
-use crate::error::Result;
+use crate::error::{ProvenanceError, Result};
+use async_trait::async_trait;
+use baml_rt_core::canonical;
 use baml_rt_tools::ToolFunctionMetadataExport;
+use serde::{Deserialize, Serialize};
 use serde_json;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
 use text_to_cypher::core::execute_cypher_query;
 
 const TOOL_LABEL: &str = "ToolFunction";
@@ -22,12 +37,265 @@ impl ToolIndexConfig {
     }
 }
 
-pub async fn index_tools(config: &ToolIndexConfig, tools: &[ToolFunctionMetadataExport]) -> Result<()> {
+/// Package that owns a set of indexed tools.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolIndexSource {
+    /// Agent package name.
+    pub package: String,
+    /// Package signature; changes whenever the package is rebuilt.
+    pub version: String,
+}
+
+impl ToolIndexSource {
+    pub fn new(package: impl Into<String>, version: impl Into<String>) -> Self {
+        Self { package: package.into(), version: version.into() }
+    }
+}
+
+/// Index entry as recorded by a backend.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedTool {
+    pub name: String,
+    pub package: String,
+    pub version: String,
+    /// [`metadata_digest`] of the metadata at indexing time.
+    pub digest: String,
+}
+
+/// Difference between a package's live tools and its index entries.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolIndexDrift {
+    /// Live tools with no index entry.
+    pub missing: Vec<String>,
+    /// Index entries for tools the package no longer registers, or from another version.
+    pub stale: Vec<String>,
+    /// Tools whose indexed metadata differs from the live metadata.
+    pub changed: Vec<String>,
+}
+
+impl ToolIndexDrift {
+    pub fn is_clean(&self) -> bool {
+        self.missing.is_empty() && self.stale.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Stable digest of a tool's exported metadata.
+pub fn metadata_digest(tool: &ToolFunctionMetadataExport) -> String {
+    canonical::digest_hex(&serde_json::to_value(tool).unwrap_or_default())
+}
+
+/// Compare `live` tools for `source` with the entries a backend reports for that package.
+pub fn detect_drift(
+    source: &ToolIndexSource,
+    live: &[ToolFunctionMetadataExport],
+    indexed: &[IndexedTool],
+) -> ToolIndexDrift {
+    let indexed_by_name: HashMap<&str, &IndexedTool> =
+        indexed.iter().map(|entry| (entry.name.as_str(), entry)).collect();
+    let live_names: HashMap<String, &ToolFunctionMetadataExport> =
+        live.iter().map(|tool| (tool.name.to_string(), tool)).collect();
+
+    let mut drift = ToolIndexDrift::default();
+    for (name, tool) in &live_names {
+        match indexed_by_name.get(name.as_str()) {
+            None => drift.missing.push(name.clone()),
+            Some(entry) if entry.digest != metadata_digest(tool) => drift.changed.push(name.clone()),
+            Some(_) => {}
+        }
+    }
+    for entry in indexed {
+        if entry.version != source.version || !live_names.contains_key(&entry.name) {
+            drift.stale.push(entry.name.clone());
+        }
+    }
+    drift.missing.sort();
+    drift.stale.sort();
+    drift.changed.sort();
+    drift
+}
+
+#[async_trait]
+pub trait ToolIndexer: Send + Sync {
+    /// Upsert `tools` for `source` and drop entries from the package's other versions.
+    async fn index(&self, source: &ToolIndexSource, tools: &[ToolFunctionMetadataExport]) -> Result<()>;
+
+    /// Entries currently indexed for `package`.
+    async fn indexed(&self, package: &str) -> Result<Vec<IndexedTool>>;
+}
+
+/// Indexes tools as `ToolFunction` nodes in a FalkorDB graph.
+pub struct FalkorDbToolIndexer {
+    config: ToolIndexConfig,
+}
+
+impl FalkorDbToolIndexer {
+    pub fn new(config: ToolIndexConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl ToolIndexer for FalkorDbToolIndexer {
+    async fn index(&self, source: &ToolIndexSource, tools: &[ToolFunctionMetadataExport]) -> Result<()> {
+        index_tools(&self.config, source, tools).await
+    }
+
+    async fn indexed(&self, package: &str) -> Result<Vec<IndexedTool>> {
+        let query = format!(
+            "MATCH (t:{label} {{package: \"{package}\"}}) RETURN t.name, t.version, t.metadata_digest",
+            label = TOOL_LABEL,
+            package = escape_cypher(package),
+        );
+        let raw = execute_cypher_query(&query, &self.config.graph, &self.config.connection, true).await?;
+        Ok(raw
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line
+                    .split([',', '\t', '|'])
+                    .map(|field| field.trim().trim_matches('"'))
+                    .filter(|field| !field.is_empty());
+                Some(IndexedTool {
+                    name: fields.next()?.to_string(),
+                    package: package.to_string(),
+                    version: fields.next()?.to_string(),
+                    digest: fields.next().unwrap_or_default().to_string(),
+                })
+            })
+            .collect())
+    }
+}
+
+/// Discards tool metadata.
+#[derive(Debug, Default)]
+pub struct NoopToolIndexer;
+
+#[async_trait]
+impl ToolIndexer for NoopToolIndexer {
+    async fn index(&self, _source: &ToolIndexSource, _tools: &[ToolFunctionMetadataExport]) -> Result<()> {
+        Ok(())
+    }
+
+    async fn indexed(&self, _package: &str) -> Result<Vec<IndexedTool>> {
+        Ok(Vec::new())
+    }
+}
+
+/// Catalog record written by [`FileToolIndexer`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileCatalogEntry {
+    #[serde(flatten)]
+    pub entry: IndexedTool,
+    pub metadata: ToolFunctionMetadataExport,
+}
+
+/// Maintains a JSON catalog of tool metadata keyed by package and tool name
+/// (`package:tool`).
+///
+/// Entries owned by other packages are kept; a tool indexed again by the same
+/// package replaces its previous entry.
+pub struct FileToolIndexer {
+    path: PathBuf,
+    lock: tokio::sync::Mutex<()>,
+}
+
+impl FileToolIndexer {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into(), lock: tokio::sync::Mutex::new(()) }
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    /// The catalog, re-keyed from its entries so catalogs written under
+    /// another key scheme are read the same way.
+    async fn read_catalog(&self) -> Result<BTreeMap<String, FileCatalogEntry>> {
+        let catalog: BTreeMap<String, FileCatalogEntry> = match tokio::fs::read(&self.path).await {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(storage_error)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => return Err(storage_error(err)),
+        };
+        Ok(catalog
+            .into_values()
+            .map(|record| (catalog_key(&record.entry.package, &record.entry.name), record))
+            .collect())
+    }
+}
+
+#[async_trait]
+impl ToolIndexer for FileToolIndexer {
+    async fn index(&self, source: &ToolIndexSource, tools: &[ToolFunctionMetadataExport]) -> Result<()> {
+        let _guard = self.lock.lock().await;
+        let mut catalog = self.read_catalog().await?;
+        catalog.retain(|_, record| {
+            record.entry.package != source.package || record.entry.version == source.version
+        });
+        for tool in tools {
+            let name = tool.name.to_string();
+            let entry = IndexedTool {
+                name: name.clone(),
+                package: source.package.clone(),
+                version: source.version.clone(),
+                digest: metadata_digest(tool),
+            };
+            catalog.insert(
+                catalog_key(&source.package, &name),
+                FileCatalogEntry { entry, metadata: tool.clone() },
+            );
+        }
+        if let Some(parent) = self.path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            tokio::fs::create_dir_all(parent).await.map_err(storage_error)?;
+        }
+        let bytes = serde_json::to_vec_pretty(&catalog).map_err(storage_error)?;
+        tokio::fs::write(&self.path, bytes).await.map_err(storage_error)
+    }
+
+    async fn indexed(&self, package: &str) -> Result<Vec<IndexedTool>> {
+        let _guard = self.lock.lock().await;
+        Ok(self
+            .read_catalog()
+            .await?
+            .into_values()
+            .map(|record| record.entry)
+            .filter(|entry| entry.package == package)
+            .collect())
+    }
+}
+
+fn catalog_key(package: &str, name: &str) -> String {
+    format!("{package}:{name}")
+}
+
+fn storage_error(err: impl std::error::Error + Send + Sync + 'static) -> ProvenanceError {
+    ProvenanceError::Storage(Box::new(err))
+}
+
+pub async fn index_tools(
+    config: &ToolIndexConfig,
+    source: &ToolIndexSource,
+    tools: &[ToolFunctionMetadataExport],
+) -> Result<()> {
     ensure_fulltext_index(config).await?;
     for tool in tools {
-        upsert_tool(config, tool).await?;
+        upsert_tool(config, source, tool).await?;
     }
-    Ok(())
+    remove_other_versions(config, source).await
+}
+
+/// Delete `ToolFunction` nodes left behind by earlier versions of the package.
+async fn remove_other_versions(config: &ToolIndexConfig, source: &ToolIndexSource) -> Result<()> {
+    let query = format!(
+        "MATCH (t:{label} {{package: \"{package}\"}}) WHERE t.version <> \"{version}\" DELETE t",
+        label = TOOL_LABEL,
+        package = escape_cypher(&source.package),
+        version = escape_cypher(&source.version),
+    );
+    execute_cypher_query(&query, &config.graph, &config.connection, false)
+        .await
+        .map(|_| ())
+        .map_err(Into::into)
 }
 
 async fn ensure_fulltext_index(config: &ToolIndexConfig) -> Result<()> {
@@ -48,7 +316,11 @@ async fn ensure_fulltext_index(config: &ToolIndexConfig) -> Result<()> {
     }
 }
 
-async fn upsert_tool(config: &ToolIndexConfig, tool: &ToolFunctionMetadataExport) -> Result<()> {
+async fn upsert_tool(
+    config: &ToolIndexConfig,
+    source: &ToolIndexSource,
+    tool: &ToolFunctionMetadataExport,
+) -> Result<()> {
     let name = tool.name.to_string();
     let description = tool.description.as_str();
     let tags = tool.tags.join(" ");
@@ -59,9 +331,10 @@ async fn upsert_tool(config: &ToolIndexConfig, tool: &ToolFunctionMetadataExport
     let output_schema = tool.output_schema.to_string();
     let secret_requirements = serde_json::to_string(&tool.secret_requirements).unwrap_or_default();
     let is_host_tool = tool.is_host_tool;
+    let digest = metadata_digest(tool);
 
     let query = format!(
-        "MERGE (t:{label} {{name: \"{name}\"}})\n\
+        "MERGE (t:{label} {{package: \"{package}\", name: \"{name}\"}})\n\
          SET t.description = \"{description}\",\n\
              t.tags = \"{tags}\",\n\
              t.bundle = \"{bundle}\",\n\
@@ -70,7 +343,9 @@ async fn upsert_tool(config: &ToolIndexConfig, tool: &ToolFunctionMetadataExport
              t.input_schema = \"{input_schema}\",\n\
              t.output_schema = \"{output_schema}\",\n\
              t.secret_requirements = \"{secret_requirements}\",\n\
-             t.is_host_tool = {is_host_tool}",
+             t.is_host_tool = {is_host_tool},\n\
+             t.version = \"{version}\",\n\
+             t.metadata_digest = \"{digest}\"",
         label = TOOL_LABEL,
         name = escape_cypher(&name),
         description = escape_cypher(description),
@@ -81,7 +356,9 @@ async fn upsert_tool(config: &ToolIndexConfig, tool: &ToolFunctionMetadataExport
         input_schema = escape_cypher(&input_schema),
         output_schema = escape_cypher(&output_schema),
         secret_requirements = escape_cypher(&secret_requirements),
-        is_host_tool = if is_host_tool { "true" } else { "false" }
+        is_host_tool = if is_host_tool { "true" } else { "false" },
+        package = escape_cypher(&source.package),
+        version = escape_cypher(&source.version),
     );
 
     execute_cypher_query(&query, &config.graph, &config.connection, false)
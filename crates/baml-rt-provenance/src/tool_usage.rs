@@ -0,0 +1,254 @@
+//! Per-agent rollups of tool calls.
+//!
+//! Every tool call is recorded as its own `ToolCall` node, so asking the graph
+//! which tools an agent actually uses means scanning all of them.
+//! [`ToolUsageAggregator`] is a [`ToolInterceptor`] that keeps invocation and
+//! failure counts and recent latencies per agent and tool in memory, and
+//! [`ToolUsageReporter`] periodically writes the pairs that changed as
+//! `ToolUsageSummarized` events. Each normalizes into one `ToolUsage` node per
+//! agent and tool, overwritten by the next summary.
+//!
+//! Counts cover the life of the process; latency percentiles are taken over
+//! the last [`LATENCY_WINDOW`] calls of each pair, so they follow recent
+//! behaviour. Calls made outside an agent scope are not counted.
+
+use crate::events::ProvEvent;
+use crate::store::ProvenanceWriter;
+use async_trait::async_trait;
+use baml_rt_core::context;
+use baml_rt_core::ids::AgentId;
+use baml_rt_core::Result;
+use baml_rt_interceptor::{InterceptorDecision, ToolCallContext, ToolInterceptor};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// Calls per agent and tool whose latencies the percentiles are taken over.
+pub const LATENCY_WINDOW: usize = 1024;
+
+/// One agent's use of one tool.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolUsageSummary {
+    pub agent_id: AgentId,
+    pub tool_name: String,
+    pub invocations: u64,
+    pub failures: u64,
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub p99_ms: u64,
+}
+
+impl ToolUsageSummary {
+    /// Failed calls as a fraction of all calls; zero before the first call.
+    pub fn failure_rate(&self) -> f64 {
+        if self.invocations == 0 {
+            0.0
+        } else {
+            self.failures as f64 / self.invocations as f64
+        }
+    }
+
+    fn into_event(self) -> ProvEvent {
+        ProvEvent::tool_usage_summarized(
+            context::generate_context_id(),
+            self.agent_id,
+            self.tool_name,
+            self.invocations,
+            self.failures,
+            self.p50_ms,
+            self.p95_ms,
+            self.p99_ms,
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+struct ToolStats {
+    invocations: u64,
+    failures: u64,
+    latencies: VecDeque<u64>,
+    /// Whether calls arrived since the last summary was written.
+    changed: bool,
+}
+
+impl ToolStats {
+    fn record(&mut self, duration_ms: u64, success: bool) {
+        self.invocations += 1;
+        if !success {
+            self.failures += 1;
+        }
+        if self.latencies.len() == LATENCY_WINDOW {
+            self.latencies.pop_front();
+        }
+        self.latencies.push_back(duration_ms);
+        self.changed = true;
+    }
+
+    fn summary(&self, agent_id: &AgentId, tool_name: &str) -> ToolUsageSummary {
+        let mut sorted: Vec<u64> = self.latencies.iter().copied().collect();
+        sorted.sort_unstable();
+        ToolUsageSummary {
+            agent_id: agent_id.clone(),
+            tool_name: tool_name.to_string(),
+            invocations: self.invocations,
+            failures: self.failures,
+            p50_ms: percentile(&sorted, 50),
+            p95_ms: percentile(&sorted, 95),
+            p99_ms: percentile(&sorted, 99),
+        }
+    }
+}
+
+/// Nearest-rank percentile of `sorted`; zero when empty.
+fn percentile(sorted: &[u64], percent: usize) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (percent * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+/// Counts the tool calls of each agent. Clones share their counts, so one
+/// aggregator can be registered with every agent's tool interceptors and
+/// handed to a [`ToolUsageReporter`].
+#[derive(Debug, Clone, Default)]
+pub struct ToolUsageAggregator {
+    stats: Arc<Mutex<HashMap<(AgentId, String), ToolStats>>>,
+}
+
+impl ToolUsageAggregator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, agent_id: &AgentId, tool_name: &str, duration_ms: u64, success: bool) {
+        self.stats
+            .lock()
+            .unwrap()
+            .entry((agent_id.clone(), tool_name.to_string()))
+            .or_default()
+            .record(duration_ms, success);
+    }
+
+    /// Current totals of every agent and tool seen, by agent then tool.
+    pub fn snapshot(&self) -> Vec<ToolUsageSummary> {
+        let mut summaries: Vec<_> = self
+            .stats
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|((agent_id, tool_name), stats)| stats.summary(agent_id, tool_name))
+            .collect();
+        sort_summaries(&mut summaries);
+        summaries
+    }
+
+    /// Write a summary for every agent and tool called since the last flush.
+    /// Returns how many were written; a pair whose write failed is retried by
+    /// the next flush.
+    pub async fn flush(&self, writer: &dyn ProvenanceWriter) -> usize {
+        let mut written = 0;
+        for summary in self.take_changed() {
+            let key = (summary.agent_id.clone(), summary.tool_name.clone());
+            match writer.add_event(summary.into_event()).await {
+                Ok(()) => written += 1,
+                Err(err) => {
+                    tracing::warn!(
+                        error = ?err,
+                        agent_id = key.0.as_str(),
+                        tool = key.1.as_str(),
+                        "Failed to record tool usage summary"
+                    );
+                    baml_rt_observability::metrics::record_provenance_write_failure(
+                        "tool usage summary",
+                    );
+                    if let Some(stats) = self.stats.lock().unwrap().get_mut(&key) {
+                        stats.changed = true;
+                    }
+                }
+            }
+        }
+        written
+    }
+
+    fn take_changed(&self) -> Vec<ToolUsageSummary> {
+        let mut summaries: Vec<_> = self
+            .stats
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .filter(|(_, stats)| stats.changed)
+            .map(|((agent_id, tool_name), stats)| {
+                stats.changed = false;
+                stats.summary(agent_id, tool_name)
+            })
+            .collect();
+        sort_summaries(&mut summaries);
+        summaries
+    }
+}
+
+fn sort_summaries(summaries: &mut [ToolUsageSummary]) {
+    summaries.sort_by(|a, b| (&a.agent_id, &a.tool_name).cmp(&(&b.agent_id, &b.tool_name)));
+}
+
+#[async_trait]
+impl ToolInterceptor for ToolUsageAggregator {
+    async fn intercept_tool_call(&self, _context: &ToolCallContext) -> Result<InterceptorDecision> {
+        Ok(InterceptorDecision::Allow)
+    }
+
+    async fn on_tool_call_complete(
+        &self,
+        context: &ToolCallContext,
+        result: &Result<Value>,
+        duration_ms: u64,
+    ) {
+        let Some(agent_id) = context::current_agent_id() else {
+            return;
+        };
+        self.record(&agent_id, &context.tool_name, duration_ms, result.is_ok());
+    }
+}
+
+/// Background task that flushes an aggregator on an interval; aborted on drop.
+pub struct ToolUsageReporter {
+    aggregator: ToolUsageAggregator,
+    writer: Arc<dyn ProvenanceWriter>,
+    handle: JoinHandle<()>,
+}
+
+impl ToolUsageReporter {
+    pub fn spawn(
+        aggregator: ToolUsageAggregator,
+        writer: Arc<dyn ProvenanceWriter>,
+        interval: Duration,
+    ) -> Self {
+        let task_aggregator = aggregator.clone();
+        let task_writer = writer.clone();
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            // Skip the immediate first tick; nothing has been counted yet.
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                task_aggregator.flush(task_writer.as_ref()).await;
+            }
+        });
+        Self { aggregator, writer, handle }
+    }
+
+    /// Stop the task and write whatever changed since its last flush.
+    pub async fn shutdown(self) -> usize {
+        self.handle.abort();
+        self.aggregator.flush(self.writer.as_ref()).await
+    }
+}
+
+impl Drop for ToolUsageReporter {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
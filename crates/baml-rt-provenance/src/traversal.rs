@@ -0,0 +1,178 @@
+//! Typed traversal of a [`ProvDocument`]'s relations.
+//!
+//! Answers the questions analysis code keeps asking of a document (what
+//! generated this entity, what did this activity use, who was it associated
+//! with, what informed it, whom did an agent act for, what was this derived
+//! from) without each caller scanning relation maps. Relations are held in
+//! hash maps, so every method that returns several references sorts them by
+//! id.
+
+use crate::document::ProvDocument;
+use crate::types::{ProvActivityId, ProvAgentId, ProvEntityId, ProvNodeRef, WasAssociatedWith};
+use std::collections::{BTreeSet, VecDeque};
+
+impl ProvDocument {
+    /// The node stored under `id`, looked up among entities, then activities,
+    /// then agents.
+    pub fn node_ref(&self, id: &str) -> Option<ProvNodeRef> {
+        let entity = ProvEntityId::from_stored(id);
+        if self.entity(&entity).is_some() {
+            return Some(ProvNodeRef::Entity(entity));
+        }
+        let activity = ProvActivityId::from_stored(id);
+        if self.activity(&activity).is_some() {
+            return Some(ProvNodeRef::Activity(activity));
+        }
+        let agent = ProvAgentId::from_stored(id);
+        self.agent(&agent).is_some().then_some(ProvNodeRef::Agent(agent))
+    }
+
+    /// The activity that generated `node`, through `wasGeneratedBy` or a
+    /// qualified generation. The earliest generation wins if there are several.
+    pub fn generated_by(&self, node: &ProvNodeRef) -> Option<&ProvActivityId> {
+        let plain = self
+            .was_generated_by()
+            .filter(|(_, rel)| &rel.entity == node)
+            .map(|(_, rel)| (rel.time_ms, &rel.activity));
+        let qualified = self
+            .qualified_generation()
+            .filter(|(_, rel)| &rel.entity == node)
+            .map(|(_, rel)| (rel.time_ms, &rel.activity));
+        plain
+            .chain(qualified)
+            .min_by(|(left_time, left), (right_time, right)| {
+                // Untimed generations sort after timed ones.
+                let time = |time: &Option<u64>| time.unwrap_or(u64::MAX);
+                time(left_time).cmp(&time(right_time)).then_with(|| left.cmp(right))
+            })
+            .map(|(_, activity)| activity)
+    }
+
+    /// Everything `activity` generated.
+    pub fn generated(&self, activity: &ProvActivityId) -> Vec<&ProvNodeRef> {
+        let plain = self
+            .was_generated_by()
+            .filter(|(_, rel)| &rel.activity == activity)
+            .map(|(_, rel)| &rel.entity);
+        let qualified = self
+            .qualified_generation()
+            .filter(|(_, rel)| &rel.activity == activity)
+            .map(|(_, rel)| &rel.entity);
+        sorted_unique(plain.chain(qualified))
+    }
+
+    /// Entities `activity` used, in any role.
+    pub fn used_entities(&self, activity: &ProvActivityId) -> Vec<&ProvEntityId> {
+        sorted_unique(
+            self.used().filter(|(_, rel)| &rel.activity == activity).map(|(_, rel)| &rel.entity),
+        )
+    }
+
+    /// Entities `activity` used in `role`.
+    pub fn used_entities_in_role(&self, activity: &ProvActivityId, role: &str) -> Vec<&ProvEntityId> {
+        sorted_unique(
+            self.used()
+                .filter(|(_, rel)| &rel.activity == activity && rel.role.as_deref() == Some(role))
+                .map(|(_, rel)| &rel.entity),
+        )
+    }
+
+    /// Activities that used `entity`.
+    pub fn users_of(&self, entity: &ProvEntityId) -> Vec<&ProvActivityId> {
+        sorted_unique(
+            self.used().filter(|(_, rel)| &rel.entity == entity).map(|(_, rel)| &rel.activity),
+        )
+    }
+
+    /// Agents associated with `activity`, with the role of each association.
+    pub fn associated_agents(&self, activity: &ProvActivityId) -> Vec<(&ProvAgentId, Option<&str>)> {
+        let mut agents: Vec<_> = self
+            .was_associated_with()
+            .filter(|(_, rel)| &rel.activity == activity)
+            .map(|(_, rel)| (&rel.agent, rel.role.as_deref()))
+            .collect();
+        agents.sort();
+        agents.dedup();
+        agents
+    }
+
+    /// Activities `agent` was associated with.
+    pub fn activities_of(&self, agent: &ProvAgentId) -> Vec<&ProvActivityId> {
+        sorted_unique(
+            self.was_associated_with()
+                .filter(|(_, rel)| &rel.agent == agent)
+                .map(|(_, rel)| &rel.activity),
+        )
+    }
+
+    /// Activities that informed `activity`: for a call, the calls before it
+    /// in its task.
+    pub fn informants_of(&self, activity: &ProvActivityId) -> Vec<&ProvActivityId> {
+        sorted_unique(
+            self.was_informed_by()
+                .filter(|(_, rel)| &rel.informed == activity)
+                .map(|(_, rel)| &rel.informant),
+        )
+    }
+
+    /// Agents `agent` acted on behalf of.
+    pub fn responsible_agents(&self, agent: &ProvAgentId) -> Vec<&ProvAgentId> {
+        sorted_unique(
+            self.acted_on_behalf_of()
+                .filter(|(_, rel)| &rel.delegate == agent)
+                .map(|(_, rel)| &rel.responsible),
+        )
+    }
+
+    /// Associations in `role`, ordered by activity then agent.
+    pub fn associations_in_role(&self, role: &str) -> Vec<&WasAssociatedWith> {
+        let mut associations: Vec<_> = self
+            .was_associated_with()
+            .map(|(_, rel)| rel)
+            .filter(|rel| rel.role.as_deref() == Some(role))
+            .collect();
+        associations.sort_by(|left, right| {
+            (&left.activity, &left.agent).cmp(&(&right.activity, &right.agent))
+        });
+        associations
+    }
+
+    /// Entities `entity` was directly derived from.
+    pub fn derived_from(&self, entity: &ProvEntityId) -> Vec<&ProvEntityId> {
+        sorted_unique(
+            self.was_derived_from()
+                .filter(|(_, rel)| &rel.generated_entity == entity)
+                .map(|(_, rel)| &rel.used_entity),
+        )
+    }
+
+    /// Entities directly derived from `entity`.
+    pub fn derivations_of(&self, entity: &ProvEntityId) -> Vec<&ProvEntityId> {
+        sorted_unique(
+            self.was_derived_from()
+                .filter(|(_, rel)| &rel.used_entity == entity)
+                .map(|(_, rel)| &rel.generated_entity),
+        )
+    }
+
+    /// Every entity `entity` was derived from, directly or transitively,
+    /// nearest first. Cycles are followed once.
+    pub fn derivation_ancestors(&self, entity: &ProvEntityId) -> Vec<&ProvEntityId> {
+        let mut seen = BTreeSet::from([entity]);
+        let mut queue = VecDeque::from([entity]);
+        let mut ancestors = Vec::new();
+        while let Some(current) = queue.pop_front() {
+            for source in self.derived_from(current) {
+                if seen.insert(source) {
+                    ancestors.push(source);
+                    queue.push_back(source);
+                }
+            }
+        }
+        ancestors
+    }
+}
+
+fn sorted_unique<'a, T: Ord + ?Sized>(items: impl Iterator<Item = &'a T>) -> Vec<&'a T> {
+    items.collect::<BTreeSet<_>>().into_iter().collect()
+}
@@ -30,6 +30,11 @@ macro_rules! define_prov_id_type {
                 Self(S::build().as_str().to_string())
             }
 
+            /// An id read back from storage or an export, trusted as already valid.
+            pub(crate) fn from_stored(id: impl Into<String>) -> Self {
+                Self(id.into())
+            }
+
             pub fn as_str(&self) -> &str {
                 &self.0
             }
@@ -105,7 +110,7 @@ pub struct Agent {
     pub attributes: HashMap<String, serde_json::Value>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum ProvNodeRef {
     Entity(ProvEntityId),
     Activity(ProvActivityId),
@@ -128,6 +133,27 @@ impl ProvNodeRef {
             ProvNodeRef::Agent(id) => id.as_str(),
         }
     }
+
+    pub fn as_entity(&self) -> Option<&ProvEntityId> {
+        match self {
+            ProvNodeRef::Entity(id) => Some(id),
+            _ => None,
+        }
+    }
+
+    pub fn as_activity(&self) -> Option<&ProvActivityId> {
+        match self {
+            ProvNodeRef::Activity(id) => Some(id),
+            _ => None,
+        }
+    }
+
+    pub fn as_agent(&self) -> Option<&ProvAgentId> {
+        match self {
+            ProvNodeRef::Agent(id) => Some(id),
+            _ => None,
+        }
+    }
 }
 
 impl From<ProvEntityId> for ProvNodeRef {
@@ -136,13 +162,19 @@ impl From<ProvEntityId> for ProvNodeRef {
     }
 }
 
+impl From<ProvActivityId> for ProvNodeRef {
+    fn from(value: ProvActivityId) -> Self {
+        ProvNodeRef::Activity(value)
+    }
+}
+
 impl From<ProvAgentId> for ProvNodeRef {
     fn from(value: ProvAgentId) -> Self {
         ProvNodeRef::Agent(value)
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct Used {
     #[serde(rename = "prov:activity")]
     pub activity: ProvActivityId,
@@ -152,7 +184,7 @@ pub struct Used {
     pub role: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct WasAssociatedWith {
     #[serde(rename = "prov:activity")]
     pub activity: ProvActivityId,
@@ -162,7 +194,7 @@ pub struct WasAssociatedWith {
     pub role: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct WasGeneratedBy {
     #[serde(rename = "prov:entity")]
     pub entity: ProvNodeRef,
@@ -172,7 +204,7 @@ pub struct WasGeneratedBy {
     pub time_ms: Option<u64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct QualifiedGeneration {
     #[serde(rename = "prov:entity")]
     pub entity: ProvNodeRef,
@@ -181,7 +213,7 @@ pub struct QualifiedGeneration {
     #[serde(rename = "prov:time", skip_serializing_if = "Option::is_none")]
     pub time_ms: Option<u64>,
 }
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct WasDerivedFrom {
     #[serde(rename = "prov:generatedEntity")]
     pub generated_entity: ProvEntityId,
@@ -192,3 +224,25 @@ pub struct WasDerivedFrom {
     #[serde(rename = "prov:type", skip_serializing_if = "Option::is_none")]
     pub prov_type: Option<String>,
 }
+
+/// `informed` used something `informant` produced: a call started after an
+/// earlier call in the same task had finished.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct WasInformedBy {
+    #[serde(rename = "prov:informed")]
+    pub informed: ProvActivityId,
+    #[serde(rename = "prov:informant")]
+    pub informant: ProvActivityId,
+}
+
+/// `delegate` acted for `responsible`, in `activity` if given: an agent
+/// handling work another agent asked for over A2A.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct ActedOnBehalfOf {
+    #[serde(rename = "prov:delegate")]
+    pub delegate: ProvAgentId,
+    #[serde(rename = "prov:responsible")]
+    pub responsible: ProvAgentId,
+    #[serde(rename = "prov:activity", skip_serializing_if = "Option::is_none")]
+    pub activity: Option<ProvActivityId>,
+}
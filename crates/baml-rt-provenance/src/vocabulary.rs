@@ -17,6 +17,19 @@ pub mod prov {
     pub const BASE_TYPE: &str = "prov:base_type";
 }
 
+// Namespaces declared when exporting PROV-JSON / PROV-N
+pub mod namespaces {
+    pub const PROV_PREFIX: &str = "prov";
+    pub const PROV: &str = "http://www.w3.org/ns/prov#";
+    pub const XSD_PREFIX: &str = "xsd";
+    pub const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+    pub const A2A_PREFIX: &str = "a2a";
+    pub const A2A: &str = "urn:baml-rt:a2a#";
+    // Node and relation identifiers are exported as `baml:<id>`.
+    pub const ID_PREFIX: &str = "baml";
+    pub const ID: &str = "urn:baml-rt:id:";
+}
+
 // A2A-specific attributes
 pub mod a2a {
     // Agent attributes
@@ -30,6 +43,8 @@ pub mod a2a {
     pub const TASK_STATE_TIME: &str = "a2a:task_state_time";
     pub const OLD_STATUS: &str = "a2a:old_status";
     pub const IS_PREVIOUS: &str = "a2a:is_previous";
+    pub const ILLEGAL_TRANSITION: &str = "a2a:illegal_transition";
+    pub const TRANSITION_REJECTED: &str = "a2a:transition_rejected";
     
     // Message attributes
     pub const MESSAGE_ID: &str = "a2a:message_id";
@@ -52,19 +67,86 @@ pub mod a2a {
     pub const USAGE_TOTAL_TOKENS: &str = "a2a:usage_total_tokens";
     pub const DURATION_MS: &str = "a2a:duration_ms";
     pub const SUCCESS: &str = "a2a:success";
+    pub const STREAM_CHUNK_COUNT: &str = "a2a:stream_chunk_count";
+    pub const TIME_TO_FIRST_TOKEN_MS: &str = "a2a:time_to_first_token_ms";
+    pub const OUTPUT_VALID: &str = "a2a:output_valid";
+    pub const OUTPUT_MISMATCHES: &str = "a2a:output_mismatches";
     
     // Tool call attributes
     pub const TOOL_NAME: &str = "a2a:tool_name";
     pub const ARGS: &str = "a2a:args";
+    pub const RETRY_ATTEMPT: &str = "a2a:retry_attempt";
+    pub const RETRY_MAX_ATTEMPTS: &str = "a2a:retry_max_attempts";
+    pub const RETRY_FAILURE_KIND: &str = "a2a:retry_failure_kind";
+    pub const RETRY_BACKOFF_MS: &str = "a2a:retry_backoff_ms";
+
+    // Failure attributes
+    pub const ERROR_CODE: &str = "a2a:error_code";
+    pub const ERROR_CONTEXT: &str = "a2a:error_context";
     
     // Archive attributes
     pub const ARCHIVE_PATH: &str = "a2a:archive_path";
     pub const ARTIFACT_ID: &str = "a2a:artifact_id";
     pub const ARTIFACT_TYPE: &str = "a2a:artifact_type";
     
+    // Feedback attributes
+    pub const FEEDBACK_ID: &str = "a2a:feedback_id";
+    pub const RATING: &str = "a2a:rating";
+    pub const CORRECTION: &str = "a2a:correction";
+    pub const COMMENT: &str = "a2a:comment";
+    
+    // Budget attributes
+    pub const BUDGET_SCOPE: &str = "a2a:budget_scope";
+    pub const BUDGET_SCOPE_ID: &str = "a2a:budget_scope_id";
+    pub const BUDGET_DIMENSION: &str = "a2a:budget_dimension";
+    pub const BUDGET_LIMIT: &str = "a2a:budget_limit";
+
+    // Diagnostic attributes
+    pub const LOG_LEVEL: &str = "a2a:log_level";
+    pub const LOG_MESSAGE: &str = "a2a:log_message";
+
+    // Runtime config attributes
+    pub const CONFIG_VERSION: &str = "a2a:config_version";
+    pub const CONFIG_DIGEST: &str = "a2a:config_digest";
+    pub const CONFIG_CHANGED: &str = "a2a:config_changed";
+    pub const CONFIG_TRIGGER: &str = "a2a:config_trigger";
+
+    // BAML schema attributes
+    pub const SCHEMA_DIGEST: &str = "a2a:schema_digest";
+    pub const SCHEMA_ADDED: &str = "a2a:schema_added";
+    pub const SCHEMA_REMOVED: &str = "a2a:schema_removed";
+    pub const SCHEMA_CHANGED: &str = "a2a:schema_changed";
+
+    // Tool usage summary attributes
+    pub const TOOL_INVOCATIONS: &str = "a2a:tool_invocations";
+    pub const TOOL_FAILURES: &str = "a2a:tool_failures";
+    pub const TOOL_FAILURE_RATE: &str = "a2a:tool_failure_rate";
+    pub const TOOL_LATENCY_P50_MS: &str = "a2a:tool_latency_p50_ms";
+    pub const TOOL_LATENCY_P95_MS: &str = "a2a:tool_latency_p95_ms";
+    pub const TOOL_LATENCY_P99_MS: &str = "a2a:tool_latency_p99_ms";
+
+    // Policy decision attributes
+    pub const POLICY_ID: &str = "a2a:policy_id";
+    pub const POLICY_EFFECT: &str = "a2a:policy_effect";
+    pub const POLICY_TARGET: &str = "a2a:policy_target";
+    pub const POLICY_SUBJECT: &str = "a2a:policy_subject";
+    pub const POLICY_REASON: &str = "a2a:policy_reason";
+
+    // Approval decision attributes
+    pub const APPROVAL_ID: &str = "a2a:approval_id";
+    pub const APPROVAL_VERDICT: &str = "a2a:approval_verdict";
+    pub const APPROVER: &str = "a2a:approver";
+    pub const APPROVAL_REASON: &str = "a2a:approval_reason";
+    pub const APPROVAL_WAITED_MS: &str = "a2a:approval_waited_ms";
+
     // Context attributes
     pub const CONTEXT_ID: &str = "a2a:context_id";
+    pub const PARENT_CONTEXT_ID: &str = "a2a:parent_context_id";
+    pub const FORKED_AT_MESSAGE_ID: &str = "a2a:forked_at_message_id";
     pub const TIMESTAMP_MS: &str = "a2a:timestamp_ms";
+
+    // Tenant attributes
+    pub const TENANT: &str = "a2a:tenant";
 }
 
 // PROV types
@@ -88,6 +170,8 @@ pub mod prov_relations {
     pub const QUALIFIED_GENERATION: &str = "QUALIFIED_GENERATION";
     pub const WAS_ASSOCIATED_WITH: &str = "WAS_ASSOCIATED_WITH";
     pub const WAS_DERIVED_FROM: &str = "WAS_DERIVED_FROM";
+    pub const ACTED_ON_BEHALF_OF: &str = "ACTED_ON_BEHALF_OF";
+    pub const WAS_INFORMED_BY: &str = "WAS_INFORMED_BY";
 }
 
 // A2A-specific PROV types
@@ -108,12 +192,29 @@ pub mod a2a_types {
     pub const TASK_STATE: &str = "a2a:A2ATaskState";
     pub const MESSAGE: &str = "a2a:Message";
     pub const ARTIFACT: &str = "a2a:Artifact";
+    pub const FEEDBACK: &str = "a2a:Feedback";
+    pub const CONTEXT: &str = "a2a:A2AContext";
+    pub const BUDGET_EXHAUSTION: &str = "a2a:BudgetExhaustion";
+    pub const DIAGNOSTIC: &str = "a2a:Diagnostic";
+    pub const RUNTIME_CONFIG: &str = "a2a:RuntimeConfig";
+    pub const BAML_SCHEMA: &str = "a2a:BamlSchema";
+    pub const TOOL_USAGE: &str = "a2a:ToolUsage";
+    pub const POLICY_DECISION: &str = "a2a:PolicyDecision";
+    pub const APPROVAL_DECISION: &str = "a2a:ApprovalDecision";
     
 }
 
 // A2A relation types (used in prov:type on relations)
 pub mod a2a_relation_types {
     pub const STATUS_TRANSITION: &str = "a2a:status_transition";
+    pub const ILLEGAL_TRANSITION: &str = "a2a:illegal_transition";
+    pub const FEEDBACK: &str = "a2a:feedback";
+    pub const BUDGET_EXHAUSTION: &str = "a2a:budget_exhaustion";
+    pub const DIAGNOSTIC: &str = "a2a:diagnostic";
+    pub const CONFIG_REVISION: &str = "a2a:config_revision";
+    pub const SCHEMA_REVISION: &str = "a2a:schema_revision";
+    pub const POLICY_DECISION: &str = "a2a:policy_decision";
+    pub const APPROVAL_DECISION: &str = "a2a:approval_decision";
 }
 
 // Semantic relation labels (past tense, passive voice)
@@ -133,6 +234,14 @@ pub mod semantic_labels {
     pub const WAS_TRANSITIONED_FROM: &str = "WAS_TRANSITIONED_FROM";
     pub const WAS_TRANSITIONED_TO: &str = "WAS_TRANSITIONED_TO";
     pub const WAS_RELATED_TO: &str = "WAS_RELATED_TO";
+    pub const WAS_BRANCHED_FROM: &str = "WAS_BRANCHED_FROM";
+    pub const WAS_CONTAINED_IN: &str = "WAS_CONTAINED_IN";
+    pub const WAS_ATTEMPTED_ON: &str = "WAS_ATTEMPTED_ON";
+    pub const RETRY_OF: &str = "RETRY_OF";
+    pub const WAS_EXHAUSTED_BY: &str = "WAS_EXHAUSTED_BY";
+    pub const WAS_LOGGED_DURING: &str = "WAS_LOGGED_DURING";
+    pub const WAS_REVISED_FROM: &str = "WAS_REVISED_FROM";
+    pub const WAS_DECIDED_DURING: &str = "WAS_DECIDED_DURING";
 }
 
 // PROV roles
@@ -170,6 +279,9 @@ pub mod a2a_relations {
     pub const TASK_CALL: &str = "A2A_TASK_CALL";
     pub const TASK_STATUS_TRANSITION: &str = "A2A_TASK_STATUS_TRANSITION";
     pub const MESSAGE_CALL: &str = "A2A_MESSAGE_CALL";
+    pub const CONTEXT_DERIVED_FROM: &str = "A2A_CONTEXT_DERIVED_FROM";
+    pub const CONTEXT_CONTAINED_IN: &str = "A2A_CONTEXT_CONTAINED_IN";
+    pub const LLM_RETRY_OF: &str = "A2A_LLM_RETRY_OF";
 }
 
 // Derived node labels (sanitized `prov:type` suffixes)
@@ -187,4 +299,13 @@ pub mod node_labels {
     pub const TASK_STATE: &str = "A2ATaskState";
     pub const MESSAGE: &str = "A2AMessage";
     pub const ARTIFACT: &str = "Artifact";
+    pub const FEEDBACK: &str = "Feedback";
+    pub const CONTEXT: &str = "A2AContext";
+    pub const BUDGET_EXHAUSTION: &str = "BudgetExhaustion";
+    pub const DIAGNOSTIC: &str = "Diagnostic";
+    pub const RUNTIME_CONFIG: &str = "RuntimeConfig";
+    pub const BAML_SCHEMA: &str = "BamlSchema";
+    pub const TOOL_USAGE: &str = "ToolUsage";
+    pub const POLICY_DECISION: &str = "PolicyDecision";
+    pub const APPROVAL_DECISION: &str = "ApprovalDecision";
 }
@@ -0,0 +1,107 @@
+use baml_rt_core::ids::{ContextId, ExternalId, MessageId};
+use baml_rt_provenance::vocabulary::a2a;
+use baml_rt_provenance::{
+    AccessControlledReader, ContextOwnership, InMemoryProvenanceStore, ProvEvent, ProvenanceError,
+    ProvenanceQuery, ProvenanceReader, ProvenanceWriter,
+};
+use serde_json::{Value, json};
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+fn tool_call(context_id: ContextId, message: &str) -> ProvEvent {
+    ProvEvent::tool_call_started_global(
+        context_id,
+        MessageId::from_external(ExternalId::new(message)),
+        "tool".to_string(),
+        None,
+        json!({"secret": message}),
+        json!({"message_id": message}),
+    )
+}
+
+async fn contexts_seen_by(
+    reader: &dyn ProvenanceReader,
+    query: &ProvenanceQuery,
+) -> BTreeSet<String> {
+    reader
+        .query_nodes(query)
+        .await
+        .expect("query")
+        .into_iter()
+        .filter_map(|record| {
+            record.attributes.get(a2a::CONTEXT_ID).and_then(Value::as_str).map(str::to_string)
+        })
+        .collect()
+}
+
+async fn tenant_store() -> (Arc<InMemoryProvenanceStore>, ContextId, ContextId) {
+    let store = Arc::new(InMemoryProvenanceStore::new());
+    let (alice_ctx, bob_ctx) = (ContextId::new(1, 1), ContextId::new(2, 2));
+    store.add_event(tool_call(alice_ctx.clone(), "msg-a")).await.expect("add event");
+    store.add_event(tool_call(bob_ctx.clone(), "msg-b")).await.expect("add event");
+    (store, alice_ctx, bob_ctx)
+}
+
+#[tokio::test]
+async fn principals_only_see_their_own_contexts() {
+    let (store, alice_ctx, bob_ctx) = tenant_store().await;
+    let ownership = Arc::new(ContextOwnership::new());
+    ownership.grant("alice", alice_ctx.clone());
+    ownership.grant("bob", bob_ctx.clone());
+    let reader = AccessControlledReader::new(store, ownership.clone());
+
+    let all = ProvenanceQuery::default();
+    let alice = reader.for_principal("alice");
+    assert_eq!(
+        contexts_seen_by(&alice, &all).await,
+        BTreeSet::from([alice_ctx.as_str().to_string()])
+    );
+    assert!(contexts_seen_by(&reader.for_principal("mallory"), &all).await.is_empty());
+
+    let denied = alice.query_nodes(&ProvenanceQuery::default().for_context(bob_ctx.clone())).await;
+    assert!(matches!(denied, Err(ProvenanceError::AccessDenied { .. })));
+
+    // Grants apply to readers already handed out.
+    ownership.grant("alice", bob_ctx.clone());
+    assert_eq!(contexts_seen_by(&alice, &all).await.len(), 2);
+    ownership.revoke("alice", &bob_ctx);
+    assert_eq!(contexts_seen_by(&alice, &all).await.len(), 1);
+}
+
+#[tokio::test]
+async fn unrestricted_principals_see_every_context() {
+    let (store, _, _) = tenant_store().await;
+    let ownership = Arc::new(ContextOwnership::new());
+    ownership.grant_all("operator");
+    let reader = AccessControlledReader::new(store, ownership);
+
+    let seen =
+        contexts_seen_by(&reader.for_principal("operator"), &ProvenanceQuery::default()).await;
+    assert_eq!(seen.len(), 2);
+}
+
+fn tenant_event(context_id: ContextId, message: &str, tenant: &str) -> ProvEvent {
+    let mut event = tool_call(context_id, message);
+    if let ProvEvent::Global(global) = &mut event {
+        global.tenant = Some(tenant.to_string());
+    }
+    event
+}
+
+#[tokio::test]
+async fn replays_only_see_events_from_the_tenants_recorded_contexts() {
+    let (acme_ctx, globex_ctx) = (ContextId::new(1, 1), ContextId::new(2, 2));
+    let events = vec![
+        tenant_event(acme_ctx.clone(), "msg-a", "acme"),
+        tenant_event(globex_ctx.clone(), "msg-b", "globex"),
+        tool_call(ContextId::new(3, 3), "msg-c"),
+    ];
+    let ownership = Arc::new(ContextOwnership::new());
+    ownership.grant_recorded_tenants(&events);
+    let reader = AccessControlledReader::new(Arc::new(InMemoryProvenanceStore::new()), ownership);
+
+    let visible = reader.for_principal("acme").visible_events(events.clone());
+    assert_eq!(visible.len(), 1);
+    assert_eq!(visible[0].context_id(), &acme_ctx);
+    assert!(reader.for_principal("initech").visible_events(events).is_empty());
+}
@@ -0,0 +1,91 @@
+use async_trait::async_trait;
+use baml_rt_core::ids::{ContextId, ExternalId, MessageId};
+use baml_rt_provenance::error::Result as ProvResult;
+use baml_rt_provenance::{
+    CompositeProvenanceWriter, FailureMode, InMemoryProvenanceStore, ProvEvent, ProvenanceError,
+    ProvenanceWriter,
+};
+use serde_json::json;
+use std::sync::Arc;
+
+struct BrokenWriter;
+
+#[async_trait]
+impl ProvenanceWriter for BrokenWriter {
+    async fn add_event(&self, _event: ProvEvent) -> ProvResult<()> {
+        Err(ProvenanceError::Storage("connection refused".into()))
+    }
+
+    async fn health_check(&self) -> ProvResult<()> {
+        Err(ProvenanceError::Storage("connection refused".into()))
+    }
+}
+
+fn tool_event(message: &str) -> ProvEvent {
+    ProvEvent::tool_call_started_global(
+        ContextId::new(1, 1),
+        MessageId::from_external(ExternalId::new(message)),
+        "tool".to_string(),
+        None,
+        json!({"input": "value"}),
+        json!({"message_id": message}),
+    )
+}
+
+#[tokio::test]
+async fn events_reach_every_sink() {
+    let first = Arc::new(InMemoryProvenanceStore::new());
+    let second = Arc::new(InMemoryProvenanceStore::new());
+    let composite = CompositeProvenanceWriter::new(FailureMode::FailFast)
+        .with_writer("first", first.clone())
+        .with_writer("second", second.clone());
+
+    composite.add_event(tool_event("msg-1")).await.expect("add event");
+    composite
+        .add_events(vec![tool_event("msg-2"), tool_event("msg-3")])
+        .await
+        .expect("add events");
+
+    assert_eq!(first.events().await.len(), 3);
+    let ids = |events: Vec<ProvEvent>| events.iter().map(|event| event.id().clone()).collect::<Vec<_>>();
+    assert_eq!(ids(second.events().await), ids(first.events().await));
+    assert_eq!(composite.sink_names().collect::<Vec<_>>(), ["first", "second"]);
+}
+
+#[tokio::test]
+async fn fail_fast_stops_at_the_failing_sink() {
+    let after = Arc::new(InMemoryProvenanceStore::new());
+    let composite = CompositeProvenanceWriter::new(FailureMode::FailFast)
+        .with_writer("broken", Arc::new(BrokenWriter))
+        .with_writer("memory", after.clone());
+
+    assert!(composite.add_event(tool_event("msg-1")).await.is_err());
+    assert!(composite.health_check().await.is_err());
+    assert!(after.events().await.is_empty());
+}
+
+#[tokio::test]
+async fn best_effort_fails_only_when_every_sink_fails() {
+    let memory = Arc::new(InMemoryProvenanceStore::new());
+    let composite = CompositeProvenanceWriter::new(FailureMode::BestEffort)
+        .with_writer("broken", Arc::new(BrokenWriter))
+        .with_writer("memory", memory.clone());
+
+    composite.add_event(tool_event("msg-1")).await.expect("one sink succeeded");
+    composite.health_check().await.expect("one sink is healthy");
+    assert_eq!(memory.events().await.len(), 1);
+
+    let all_broken = CompositeProvenanceWriter::new(FailureMode::BestEffort)
+        .with_writer("a", Arc::new(BrokenWriter))
+        .with_writer("b", Arc::new(BrokenWriter));
+    let err = all_broken.add_event(tool_event("msg-2")).await.expect_err("no sink succeeded");
+    assert!(format!("{err:?}").contains("a: "), "{err:?}");
+}
+
+#[test]
+fn failure_modes_parse_from_their_names() {
+    for mode in FailureMode::ALL {
+        assert_eq!(mode.as_str().parse::<FailureMode>().unwrap(), mode);
+    }
+    assert!("sometimes".parse::<FailureMode>().is_err());
+}
@@ -0,0 +1,224 @@
+use baml_rt_provenance::cypher::CLAUSE_SEPARATOR;
+use baml_rt_provenance::types::{
+    ActedOnBehalfOf, Activity, Entity, Used, WasAssociatedWith, WasInformedBy,
+};
+use baml_rt_provenance::{
+    normalize_event, BaseLabels, CypherBuilder, ProvActivityId, ProvAgentId, ProvEntityId,
+    ProvEvent,
+};
+use baml_rt_core::ids::{ContextId, ExternalId, MessageId};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+fn entity_id(id: &str) -> ProvEntityId {
+    serde_json::from_value(json!(id)).expect("entity id")
+}
+
+fn activity_id(id: &str) -> ProvActivityId {
+    serde_json::from_value(json!(id)).expect("activity id")
+}
+
+fn agent_id(id: &str) -> ProvAgentId {
+    serde_json::from_value(json!(id)).expect("agent id")
+}
+
+fn tool_call() -> Activity {
+    Activity {
+        start_time_ms: Some(10),
+        end_time_ms: None,
+        prov_type: Some("a2a:ToolCall".to_string()),
+        attributes: HashMap::new(),
+    }
+}
+
+fn tool_args() -> Entity {
+    Entity { prov_type: Some("a2a:ToolArgs".to_string()), attributes: HashMap::new() }
+}
+
+fn args_used() -> Used {
+    Used {
+        activity: activity_id("act-1"),
+        entity: entity_id("ent-args"),
+        role: Some("a2a:args".to_string()),
+    }
+}
+
+#[test]
+fn entity_clause_golden() {
+    let mut builder = CypherBuilder::new();
+    builder.entity(
+        &entity_id("ent-1"),
+        &Entity {
+            prov_type: Some("a2a:A2ATask".to_string()),
+            attributes: HashMap::from([("a2a:task_id".to_string(), json!("task-1"))]),
+        },
+    );
+    assert_eq!(
+        builder.build(),
+        "MERGE (n:A2ATask {name: \"ent-1\"}) SET n += {`a2a:task_id`: \"task-1\", name: \"ent-1\", \
+         `prov:base_type`: \"ProvEntity\", `prov:type`: \"a2a:A2ATask\"}"
+    );
+}
+
+#[test]
+fn used_edge_golden_with_semantic_labels() {
+    let mut builder = CypherBuilder::new();
+    builder
+        .activity(&activity_id("act-1"), &tool_call())
+        .entity(&entity_id("ent-args"), &tool_args())
+        .used(&args_used());
+
+    let expected = [
+        "MERGE (n:ToolCall {name: \"act-1\"}) SET n += {name: \"act-1\", \
+         `prov:base_type`: \"ProvActivity\", `prov:startTime`: 10, `prov:type`: \"a2a:ToolCall\"}",
+        "MERGE (n:ToolArgs {name: \"ent-args\"}) SET n += {name: \"ent-args\", \
+         `prov:base_type`: \"ProvEntity\", `prov:type`: \"a2a:ToolArgs\"}",
+        "MERGE (a:ToolCall {name: \"act-1\"}) MERGE (b:ToolArgs {name: \"ent-args\"}) \
+         MERGE (a)-[r:WAS_USED_BY]->(b) SET r += {`prov:base_type`: \"USED\", `prov:role`: \"a2a:args\"}",
+    ];
+    assert_eq!(builder.clauses(), expected);
+    assert_eq!(builder.build(), expected.join(CLAUSE_SEPARATOR));
+}
+
+#[test]
+fn used_edge_golden_with_base_labels() {
+    let mut builder = CypherBuilder::with_label_strategy(Arc::new(BaseLabels));
+    builder
+        .activity(&activity_id("act-1"), &tool_call())
+        .entity(&entity_id("ent-args"), &tool_args())
+        .used(&args_used());
+
+    assert!(builder.clauses()[0].starts_with("MERGE (n:ProvActivity {name: \"act-1\"})"));
+    assert_eq!(
+        builder.clauses()[2],
+        "MERGE (a:ProvActivity {name: \"act-1\"}) MERGE (b:ProvEntity {name: \"ent-args\"}) \
+         MERGE (a)-[r:USED]->(b) SET r += {`prov:base_type`: \"USED\", `prov:role`: \"a2a:args\"}"
+    );
+}
+
+#[test]
+fn clause_keys_name_the_upserted_element_not_its_properties() {
+    let mut builder = CypherBuilder::new();
+    let mut finished = tool_call();
+    finished.end_time_ms = Some(20);
+    builder
+        .activity(&activity_id("act-1"), &tool_call())
+        .activity(&activity_id("act-1"), &finished)
+        .entity(&entity_id("ent-args"), &tool_args())
+        .used(&args_used());
+
+    let keyed: Vec<(&str, &str)> = builder.keyed_clauses().collect();
+    let keys: Vec<&str> = keyed.iter().map(|(key, _)| *key).collect();
+    assert_eq!(
+        keys,
+        [
+            "(ToolCall act-1)",
+            "(ToolCall act-1)",
+            "(ToolArgs ent-args)",
+            "(ToolCall act-1)-[WAS_USED_BY]->(ToolArgs ent-args)",
+        ]
+    );
+    assert_ne!(keyed[0].1, keyed[1].1);
+}
+
+#[test]
+fn edges_to_unknown_nodes_use_hints_or_base_labels() {
+    let mut builder = CypherBuilder::new();
+    builder.agent_label_hint("agent-1", "Runner").was_associated_with(&WasAssociatedWith {
+        activity: activity_id("act-1"),
+        agent: agent_id("agent-1"),
+        role: Some("executing_agent".to_string()),
+    });
+    assert_eq!(
+        builder.build(),
+        "MERGE (a:ProvActivity {name: \"act-1\"}) MERGE (b:Runner {name: \"agent-1\"}) \
+         MERGE (a)-[r:WAS_EXECUTED_BY]->(b) SET r += {`prov:base_type`: \"WAS_ASSOCIATED_WITH\", \
+         `prov:role`: \"executing_agent\"}"
+    );
+}
+
+#[test]
+fn delegations_become_acted_on_behalf_of_edges() {
+    let mut builder = CypherBuilder::new();
+    builder.acted_on_behalf_of(&ActedOnBehalfOf {
+        delegate: agent_id("agent-2"),
+        responsible: agent_id("agent-1"),
+        activity: Some(activity_id("act-1")),
+    });
+    assert_eq!(
+        builder.build(),
+        "MERGE (a:ProvAgent {name: \"agent-2\"}) MERGE (b:ProvAgent {name: \"agent-1\"}) \
+         MERGE (a)-[r:ACTED_ON_BEHALF_OF]->(b) SET r += {`prov:activity`: \"act-1\", \
+         `prov:base_type`: \"ACTED_ON_BEHALF_OF\"}"
+    );
+}
+
+#[test]
+fn informed_calls_become_was_informed_by_edges() {
+    let mut builder = CypherBuilder::new();
+    builder.activity_type_hint("act-1", "baml:LlmCall");
+    builder.was_informed_by(&WasInformedBy {
+        informed: activity_id("act-2"),
+        informant: activity_id("act-1"),
+    });
+    let query = builder.build();
+    assert!(query.contains("MERGE (a)-[r:WAS_INFORMED_BY]->(b)"), "{query}");
+    assert!(query.contains("{name: \"act-2\"}"), "{query}");
+}
+
+#[test]
+fn normalized_documents_build_deterministically() {
+    assert!(CypherBuilder::new().build().is_empty());
+
+    let event = ProvEvent::tool_call_started_global(
+        ContextId::new(1, 1),
+        MessageId::from_external(ExternalId::new("msg-1")),
+        "tool".to_string(),
+        None,
+        json!({"input": "value"}),
+        json!({}),
+    );
+    let normalized = normalize_event(&event).expect("normalize");
+    let first = CypherBuilder::new().normalized(&normalized).build();
+    let second = CypherBuilder::new().normalized(&normalized).build();
+    assert!(!first.is_empty());
+    assert_eq!(first, second);
+}
+
+#[test]
+fn renormalizing_an_event_is_byte_identical() {
+    let event = ProvEvent::tool_call_started_global(
+        ContextId::new(1, 1),
+        MessageId::from_external(ExternalId::new("msg-1")),
+        "tool".to_string(),
+        None,
+        json!({"input": "value"}),
+        json!({}),
+    );
+    let first = normalize_event(&event).expect("normalize");
+    let second = normalize_event(&event).expect("normalize");
+
+    assert_eq!(
+        CypherBuilder::new().normalized(&first).build(),
+        CypherBuilder::new().normalized(&second).build()
+    );
+    assert_eq!(first.document.to_prov_json(), second.document.to_prov_json());
+
+    let mut used_ids: Vec<_> = first.document.used().map(|(id, _)| id.clone()).collect();
+    used_ids.sort();
+    assert!(!used_ids.is_empty());
+    assert!(used_ids.iter().all(|id| id.starts_with('u') && id.len() == 17), "{used_ids:?}");
+
+    // Relations of another event do not reuse these ids.
+    let other = ProvEvent::tool_call_started_global(
+        ContextId::new(1, 1),
+        MessageId::from_external(ExternalId::new("msg-1")),
+        "tool".to_string(),
+        None,
+        json!({"input": "value"}),
+        json!({}),
+    );
+    let other = normalize_event(&other).expect("normalize");
+    assert!(other.document.used().all(|(id, _)| !used_ids.contains(id)));
+}
@@ -1,14 +1,23 @@
 use baml_rt_core::ids::{AgentId, ArtifactId, ContextId, EventId, ExternalId, MessageId, TaskId, UuidId};
 use baml_rt_provenance::{
+    AccessControlledReader,
     AgentType,
     CallScope,
+    ContextOwnership,
+    FalkorDbArchiver,
     FalkorDbProvenanceConfig,
     FalkorDbProvenanceWriter,
     GlobalEvent,
     LlmUsage,
     ProvEvent,
+    ProvArchive,
     ProvEventData,
+    ProvenanceError,
+    ProvenanceProfile,
+    ProvenanceQuery,
     ProvenanceWriter,
+    PruneRequest,
+    RetentionScope,
     TaskScopedEvent,
 };
 use insta::assert_json_snapshot;
@@ -17,6 +26,7 @@ use testcontainers::core::ContainerPort;
 use testcontainers::runners::AsyncRunner;
 use testcontainers::GenericImage;
 use text_to_cypher::core::execute_cypher_query;
+use std::sync::Arc;
 use tokio::time::{sleep, Duration};
 
 async fn start_falkordb() -> (testcontainers::ContainerAsync<GenericImage>, String) {
@@ -75,6 +85,7 @@ async fn falkordb_writer_persists_task_and_artifact() {
 
     let agent_booted = ProvEvent::Global(GlobalEvent {
         id: EventId::from_counter(0),
+        tenant: None,
         context_id: context_id.clone(),
         timestamp_ms: 1_700_000_000_000,
         data: ProvEventData::AgentBooted {
@@ -87,6 +98,7 @@ async fn falkordb_writer_persists_task_and_artifact() {
 
     let task_created = ProvEvent::Task(TaskScopedEvent {
         id: EventId::from_counter(1),
+        tenant: None,
         context_id: context_id.clone(),
         task_id: task_id.clone(),
         timestamp_ms: 1_700_000_000_000,
@@ -97,6 +109,7 @@ async fn falkordb_writer_persists_task_and_artifact() {
     });
     let task_artifact_generated = ProvEvent::Task(TaskScopedEvent {
         id: EventId::from_counter(2),
+        tenant: None,
         context_id: context_id.clone(),
         task_id: task_id.clone(),
         timestamp_ms: 1_700_000_000_100,
@@ -168,6 +181,7 @@ async fn falkordb_writer_persists_large_document() {
 
     let agent_booted = ProvEvent::Global(GlobalEvent {
         id: EventId::from_counter(2),
+        tenant: None,
         context_id: context_id.clone(),
         timestamp_ms: 1_700_000_000_900,
         data: ProvEventData::AgentBooted {
@@ -180,6 +194,7 @@ async fn falkordb_writer_persists_large_document() {
 
     let message_received = ProvEvent::Task(TaskScopedEvent {
         id: EventId::from_counter(3),
+        tenant: None,
         context_id: context_id.clone(),
         task_id: task_id.clone(),
         timestamp_ms: 1_700_000_001_000,
@@ -195,6 +210,7 @@ async fn falkordb_writer_persists_large_document() {
     });
     let message_sent = ProvEvent::Task(TaskScopedEvent {
         id: EventId::from_counter(4),
+        tenant: None,
         context_id: context_id.clone(),
         task_id: task_id.clone(),
         timestamp_ms: 1_700_000_001_500,
@@ -209,6 +225,7 @@ async fn falkordb_writer_persists_large_document() {
     });
     let task_status_changed = ProvEvent::Task(TaskScopedEvent {
         id: EventId::from_counter(5),
+        tenant: None,
         context_id: context_id.clone(),
         task_id: task_id.clone(),
         timestamp_ms: 1_700_000_001_250,
@@ -221,6 +238,7 @@ async fn falkordb_writer_persists_large_document() {
 
     let llm_call_started = ProvEvent::Task(TaskScopedEvent {
         id: EventId::from_counter(6),
+        tenant: None,
         context_id: context_id.clone(),
         task_id: task_id.clone(),
         timestamp_ms: 1_700_000_001_600,
@@ -241,6 +259,7 @@ async fn falkordb_writer_persists_large_document() {
     });
     let llm_call_completed = ProvEvent::Task(TaskScopedEvent {
         id: EventId::from_counter(7),
+        tenant: None,
         context_id: context_id.clone(),
         task_id: task_id.clone(),
         timestamp_ms: 1_700_000_001_700,
@@ -268,6 +287,7 @@ async fn falkordb_writer_persists_large_document() {
     });
     let tool_call_started = ProvEvent::Task(TaskScopedEvent {
         id: EventId::from_counter(8),
+        tenant: None,
         context_id: context_id.clone(),
         task_id: task_id.clone(),
         timestamp_ms: 1_700_000_001_800,
@@ -284,6 +304,7 @@ async fn falkordb_writer_persists_large_document() {
     });
     let tool_call_completed = ProvEvent::Task(TaskScopedEvent {
         id: EventId::from_counter(9),
+        tenant: None,
         context_id: context_id.clone(),
         task_id: task_id.clone(),
         timestamp_ms: 1_700_000_001_900,
@@ -303,6 +324,7 @@ async fn falkordb_writer_persists_large_document() {
 
     let task_created = ProvEvent::Task(TaskScopedEvent {
         id: EventId::from_counter(10),
+        tenant: None,
         context_id: context_id.clone(),
         task_id: task_id.clone(),
         timestamp_ms: 1_700_000_000_050,
@@ -313,6 +335,7 @@ async fn falkordb_writer_persists_large_document() {
     });
     let task_artifact_generated = ProvEvent::Task(TaskScopedEvent {
         id: EventId::from_counter(11),
+        tenant: None,
         context_id: context_id.clone(),
         task_id: task_id.clone(),
         timestamp_ms: 1_700_000_001_950,
@@ -394,6 +417,7 @@ async fn falkordb_writer_persists_send_message_calls_without_task() {
 
     let agent_booted = ProvEvent::Global(GlobalEvent {
         id: EventId::from_counter(11),
+        tenant: None,
         context_id: context_id.clone(),
         timestamp_ms: 1_700_000_001_900,
         data: ProvEventData::AgentBooted {
@@ -406,6 +430,7 @@ async fn falkordb_writer_persists_send_message_calls_without_task() {
 
     let message_received = ProvEvent::Global(GlobalEvent {
         id: EventId::from_counter(12),
+        tenant: None,
         context_id: context_id.clone(),
         timestamp_ms: 1_700_000_002_000,
         data: ProvEventData::MessageReceived {
@@ -420,6 +445,7 @@ async fn falkordb_writer_persists_send_message_calls_without_task() {
     });
     let message_sent = ProvEvent::Global(GlobalEvent {
         id: EventId::from_counter(13),
+        tenant: None,
         context_id: context_id.clone(),
         timestamp_ms: 1_700_000_002_200,
         data: ProvEventData::MessageSent {
@@ -434,6 +460,7 @@ async fn falkordb_writer_persists_send_message_calls_without_task() {
     });
     let llm_call_started = ProvEvent::Global(GlobalEvent {
         id: EventId::from_counter(14),
+        tenant: None,
         context_id: context_id.clone(),
         timestamp_ms: 1_700_000_002_050,
         data: ProvEventData::LlmCallStarted {
@@ -453,6 +480,7 @@ async fn falkordb_writer_persists_send_message_calls_without_task() {
     });
     let llm_call_completed = ProvEvent::Global(GlobalEvent {
         id: EventId::from_counter(15),
+        tenant: None,
         context_id: context_id.clone(),
         timestamp_ms: 1_700_000_002_120,
         data: ProvEventData::LlmCallCompleted {
@@ -482,6 +510,7 @@ async fn falkordb_writer_persists_send_message_calls_without_task() {
     });
     let tool_call_started = ProvEvent::Global(GlobalEvent {
         id: EventId::from_counter(16),
+        tenant: None,
         context_id: context_id.clone(),
         timestamp_ms: 1_700_000_002_060,
         data: ProvEventData::ToolCallStarted {
@@ -496,6 +525,7 @@ async fn falkordb_writer_persists_send_message_calls_without_task() {
     });
     let tool_call_completed = ProvEvent::Global(GlobalEvent {
         id: EventId::from_counter(17),
+        tenant: None,
         context_id: context_id.clone(),
         timestamp_ms: 1_700_000_002_110,
         data: ProvEventData::ToolCallCompleted {
@@ -582,6 +612,307 @@ async fn falkordb_writer_persists_send_message_calls_without_task() {
     );
 }
 
+#[tokio::test]
+async fn falkordb_buffered_writer_flushes_batches() {
+    let (_container, connection) = start_falkordb().await;
+    let graph = "baml_prov_buffered_test";
+    wait_for_falkordb(&connection, graph).await;
+
+    let writer = FalkorDbProvenanceWriter::new(
+        FalkorDbProvenanceConfig::new(connection.clone(), graph)
+            .with_buffering(Duration::from_secs(3600), 3),
+    );
+    let tool_event = |message_id: &str| {
+        ProvEvent::tool_call_started_global(
+            ContextId::new(1, 1),
+            MessageId::from_external(ExternalId::new(message_id)),
+            "buffered-tool".to_string(),
+            None,
+            json!({}),
+            json!({}),
+        )
+    };
+    let tool_names = || {
+        let connection = connection.clone();
+        async move {
+            execute_cypher_query(
+                "MATCH (n:ToolCall) RETURN n.`a2a:tool_name`",
+                graph,
+                &connection,
+                true,
+            )
+            .await
+            .expect("query tool calls")
+        }
+    };
+
+    writer.add_event(tool_event("msg-1")).await.expect("buffer first event");
+    writer.add_event(tool_event("msg-2")).await.expect("buffer second event");
+    assert_eq!(writer.pending_events().await, 2);
+    assert!(!tool_names().await.contains("buffered-tool"), "events written before flush");
+
+    writer.flush().await.expect("flush");
+    assert_eq!(writer.pending_events().await, 0);
+    assert!(tool_names().await.contains("buffered-tool"));
+
+    for message_id in ["msg-3", "msg-4", "msg-5"] {
+        writer.add_event(tool_event(message_id)).await.expect("buffer event");
+    }
+    assert_eq!(writer.pending_events().await, 0, "max batch size triggers a flush");
+}
+
+#[tokio::test]
+async fn falkordb_archive_reimports_into_an_empty_graph() {
+    let (_container, connection) = start_falkordb().await;
+    let graph = "baml_prov_archive_test";
+    wait_for_falkordb(&connection, graph).await;
+
+    let writer = FalkorDbProvenanceWriter::new(FalkorDbProvenanceConfig::new(
+        connection.clone(),
+        graph,
+    ));
+    let context_id = ContextId::new(1, 1);
+    let task_id = TaskId::from_external(ExternalId::new("task-1"));
+    let agent_id = AgentId::from_uuid(UuidId::parse_str("00000000-0000-0000-0000-000000000010").unwrap());
+    writer
+        .add_event(ProvEvent::Task(TaskScopedEvent {
+            id: EventId::from_counter(0),
+            tenant: None,
+            context_id: context_id.clone(),
+            task_id: task_id.clone(),
+            timestamp_ms: 1_700_000_000_000,
+            data: ProvEventData::TaskCreated { task_id: task_id.clone(), agent_id },
+        }))
+        .await
+        .expect("write task_created");
+    writer
+        .add_event(ProvEvent::Task(TaskScopedEvent {
+            id: EventId::from_counter(1),
+            tenant: None,
+            context_id: context_id.clone(),
+            task_id: task_id.clone(),
+            timestamp_ms: 1_700_000_000_100,
+            data: ProvEventData::TaskArtifactGenerated {
+                task_id: task_id.clone(),
+                artifact_id: Some(ArtifactId::from_external(ExternalId::new("artifact-1"))),
+                artifact_type: Some("result".to_string()),
+            },
+        }))
+        .await
+        .expect("write task_artifact_generated");
+
+    let query = ProvenanceQuery::default().for_context(context_id);
+    let archive = FalkorDbArchiver::new(FalkorDbProvenanceConfig::new(connection.clone(), graph))
+        .export(&query)
+        .await
+        .expect("export");
+    assert!(archive.document["entity"].get("baml:task:task-1").is_some());
+    assert!(!archive.statements().expect("statements").is_empty());
+
+    let stored = ProvArchive::from_bytes(&archive.to_bytes().expect("serialize")).expect("parse");
+    assert_eq!(stored, archive);
+
+    let restored = "baml_prov_archive_restored";
+    FalkorDbArchiver::new(FalkorDbProvenanceConfig::new(connection.clone(), restored))
+        .import_verified(&stored, &query)
+        .await
+        .expect("verified import");
+
+    let edge_count = execute_cypher_query(
+        "MATCH (:A2ATask {name: \"task:task-1\"})-[:WAS_GENERATED_BY]->(:Artifact) RETURN COUNT(*)",
+        restored,
+        &connection,
+        true,
+    )
+    .await
+    .expect("query restored edge count");
+    assert_eq!(edge_count.trim(), "1");
+}
+
+#[tokio::test]
+async fn falkordb_archive_exports_only_the_principals_contexts() {
+    let (_container, connection) = start_falkordb().await;
+    let graph = "baml_prov_scoped_archive_test";
+    wait_for_falkordb(&connection, graph).await;
+
+    let config = FalkorDbProvenanceConfig::new(connection.clone(), graph);
+    let writer = FalkorDbProvenanceWriter::new(config.clone());
+    let agent_id = AgentId::from_uuid(UuidId::parse_str("00000000-0000-0000-0000-000000000011").unwrap());
+    let contexts = [ContextId::new(1, 1), ContextId::new(2, 2)];
+    for (counter, (context_id, task)) in contexts.iter().zip(["task-a", "task-b"]).enumerate() {
+        let task_id = TaskId::from_external(ExternalId::new(task));
+        writer
+            .add_event(ProvEvent::Task(TaskScopedEvent {
+                id: EventId::from_counter(counter as u64),
+                tenant: None,
+                context_id: context_id.clone(),
+                task_id: task_id.clone(),
+                timestamp_ms: 1_700_000_000_000,
+                data: ProvEventData::TaskCreated { task_id, agent_id: agent_id.clone() },
+            }))
+            .await
+            .expect("write task_created");
+    }
+
+    let ownership = Arc::new(ContextOwnership::new());
+    ownership.grant("alice", contexts[0].clone());
+    let reader = AccessControlledReader::new(Arc::new(writer), ownership).for_principal("alice");
+    let archiver = FalkorDbArchiver::new(config);
+
+    let archive = archiver.export_for(&reader, &ProvenanceQuery::default()).await.expect("export");
+    assert!(archive.document["entity"].get("baml:task:task-a").is_some());
+    assert!(archive.document["entity"].get("baml:task:task-b").is_none());
+
+    let denied = archiver
+        .export_for(&reader, &ProvenanceQuery::default().for_context(contexts[1].clone()))
+        .await;
+    assert!(matches!(denied, Err(ProvenanceError::AccessDenied { .. })));
+}
+
+#[tokio::test]
+async fn falkordb_staging_profile_writes_redacted_messages() {
+    let (_container, connection) = start_falkordb().await;
+    let graph = "baml_prov_staging_redaction_test";
+    wait_for_falkordb(&connection, graph).await;
+
+    let settings = ProvenanceProfile::Staging.settings();
+    assert!(settings.strict_attributes);
+    let writer = FalkorDbProvenanceWriter::new(
+        settings.apply_to_falkordb(FalkorDbProvenanceConfig::new(connection.clone(), graph)),
+    );
+    let secret = "sk-live0123456789abcdef";
+    let message = ProvEvent::Global(GlobalEvent {
+        id: EventId::from_counter(30),
+        tenant: None,
+        context_id: ContextId::new(5, 1),
+        timestamp_ms: 1_700_000_003_000,
+        data: ProvEventData::MessageReceived {
+            id: MessageId::from_external(ExternalId::new("msg-secret")),
+            role: "user".to_string(),
+            content: vec![format!("my key is {secret}")],
+            metadata: None,
+        },
+    });
+    writer.add_event(message).await.expect("redacted message passes validation");
+    writer.flush().await.expect("flush");
+
+    let stored = execute_cypher_query(
+        "MATCH (n) WHERE n.`a2a:content` IS NOT NULL RETURN n.`a2a:content`",
+        graph,
+        &connection,
+        true,
+    )
+    .await
+    .expect("query message content");
+    assert!(stored.contains("my key is"), "message stored: {stored}");
+    assert!(!stored.contains(secret), "secret redacted: {stored}");
+}
+
+#[tokio::test]
+async fn falkordb_prune_removes_finished_tasks_before_the_cutoff() {
+    let (_container, connection) = start_falkordb().await;
+    let graph = "baml_prov_retention_test";
+    wait_for_falkordb(&connection, graph).await;
+
+    let writer = FalkorDbProvenanceWriter::new(FalkorDbProvenanceConfig::new(
+        connection.clone(),
+        graph,
+    ));
+    let context_id = ContextId::new(1, 1);
+    let agent_id = AgentId::from_uuid(UuidId::parse_str("00000000-0000-0000-0000-000000000010").unwrap());
+    let task_event = |task: &str, counter: u64, timestamp_ms: u64, data: ProvEventData| {
+        ProvEvent::Task(TaskScopedEvent {
+            id: EventId::from_counter(counter),
+            tenant: None,
+            context_id: context_id.clone(),
+            task_id: TaskId::from_external(ExternalId::new(task)),
+            timestamp_ms,
+            data,
+        })
+    };
+    let finished = TaskId::from_external(ExternalId::new("task-finished"));
+    let running = TaskId::from_external(ExternalId::new("task-running"));
+    writer
+        .add_events(vec![
+            task_event(
+                "task-finished",
+                0,
+                1_700_000_000_000,
+                ProvEventData::TaskCreated { task_id: finished.clone(), agent_id: agent_id.clone() },
+            ),
+            task_event(
+                "task-finished",
+                1,
+                1_700_000_000_100,
+                ProvEventData::TaskStatusChanged {
+                    task_id: finished.clone(),
+                    old_status: Some("TASK_STATE_WORKING".to_string()),
+                    new_status: Some("TASK_STATE_COMPLETED".to_string()),
+                },
+            ),
+            task_event(
+                "task-running",
+                2,
+                1_700_000_000_000,
+                ProvEventData::TaskCreated { task_id: running.clone(), agent_id },
+            ),
+        ])
+        .await
+        .expect("write tasks");
+    // Entities without relationships by design, like tool usage summaries.
+    execute_cypher_query(
+        "CREATE (n:ProvEntity {name: \"standalone\", `prov:base_type`: \"ProvEntity\"})",
+        graph,
+        &connection,
+        true,
+    )
+    .await
+    .expect("create unlinked entity");
+    let nodes_of = |task_id: &TaskId| {
+        let connection = connection.clone();
+        let query = format!("MATCH (n) WHERE n.`a2a:task_id` = \"{}\" RETURN count(n)", task_id.as_str());
+        async move {
+            let raw = execute_cypher_query(&query, graph, &connection, true).await.expect("count nodes");
+            raw.trim().parse::<usize>().expect("count")
+        }
+    };
+
+    let request = PruneRequest::before(1_700_000_001_000).with_scope(RetentionScope::TerminalTasks);
+    let preview = writer
+        .prune_with(&request.clone().with_dry_run(true).with_archive(true))
+        .await
+        .expect("dry run");
+    assert_eq!(preview.tasks, vec![finished.clone()]);
+    assert!(preview.nodes > 0);
+    assert_eq!(preview.archives.len(), 1);
+    assert!(preview.archives[0].document["entity"].get("baml:task:task-finished").is_some());
+    assert_eq!(nodes_of(&finished).await, preview.nodes, "a dry run deletes nothing");
+
+    let report = writer.prune_with(&request).await.expect("prune finished tasks");
+    assert_eq!(report.nodes, preview.nodes);
+    assert_eq!(report.orphaned_entities, preview.orphaned_entities, "a dry run counts orphans");
+    assert_eq!(nodes_of(&finished).await, 0);
+    assert!(nodes_of(&running).await > 0, "running tasks are kept");
+
+    let report = writer.prune(1_700_000_001_000).await.expect("prune everything old");
+    assert!(report.nodes > 0);
+    assert!(report.tasks.is_empty());
+    let again = writer
+        .prune_with(&PruneRequest::before(1_700_000_001_000).with_dry_run(true))
+        .await
+        .expect("dry run after prune");
+    assert_eq!(again.nodes, 0, "nothing timed before the cutoff is left");
+    let standalone = execute_cypher_query(
+        "MATCH (n) WHERE n.name = \"standalone\" RETURN count(n)",
+        graph,
+        &connection,
+        true,
+    )
+    .await
+    .expect("count unlinked entity");
+    assert_eq!(standalone.trim(), "1", "entities a prune did not orphan are kept");
+}
+
 fn graph_snapshot_json(raw: &str) -> Value {
     parse_graph_snapshot(raw)
         .map(normalize_value)
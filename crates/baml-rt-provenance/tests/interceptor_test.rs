@@ -0,0 +1,347 @@
+use baml_rt_core::ids::ContextId;
+use baml_rt_core::manifest::{PolicyEffect, PolicyTarget};
+use baml_rt_interceptor::{
+    ApprovalObserver, ApprovalOutcome, ApprovalVerdict, BudgetDimension, BudgetExhausted,
+    BudgetObserver, BudgetScope, LLMCallContext, LLMChunk, LLMInterceptor, PendingApproval,
+    PolicyDecision, PolicyObserver, TokenUsage, ToolCallContext, ToolCallRetry, ToolInterceptor,
+};
+use baml_rt_provenance::vocabulary::{a2a, a2a_types};
+use baml_rt_provenance::{
+    normalize_event, A2aRelationType, InMemoryProvenanceStore, ProvEventData, ProvNodeRef,
+    ProvenanceInterceptor, ProvenanceWriter,
+};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+fn llm_context(metadata: Value) -> LLMCallContext {
+    LLMCallContext {
+        client: "client".to_string(),
+        model: "model".to_string(),
+        function_name: "Summarize".to_string(),
+        args: json!({}),
+        context_id: ContextId::new(1, 1),
+        prompt: json!({"messages": []}),
+        metadata,
+    }
+}
+
+#[tokio::test]
+async fn streamed_llm_call_records_chunk_count_and_time_to_first_token() {
+    let store = Arc::new(InMemoryProvenanceStore::new());
+    let writer: Arc<dyn ProvenanceWriter> = store.clone();
+    let interceptor = ProvenanceInterceptor::new(writer);
+    let context = llm_context(json!({"message_id": "msg-1", "stream_id": "stream-1"}));
+
+    interceptor.intercept_llm_call(&context).await.expect("start");
+    for (index, elapsed_ms) in [(0, 40), (1, 55), (2, 90)] {
+        let chunk = LLMChunk {
+            index,
+            elapsed_ms,
+            partial: json!({"text": "partial"}),
+            usage: TokenUsage::new(4, index + 1),
+        };
+        interceptor.on_llm_chunk(&context, &chunk).await.expect("chunk");
+    }
+    interceptor.on_llm_call_complete(&context, &Ok(json!({"text": "done"})), 120).await;
+
+    let events = store.events().await;
+    let completed = events
+        .iter()
+        .find(|event| matches!(event.data(), ProvEventData::LlmCallCompleted { .. }))
+        .expect("completion event");
+    let ProvEventData::LlmCallCompleted { metadata, .. } = completed.data() else {
+        unreachable!();
+    };
+    assert_eq!(metadata["stream"], json!({"chunk_count": 3, "time_to_first_token_ms": 40}));
+
+    let normalized = normalize_event(completed).expect("normalize");
+    let (_, activity) = normalized
+        .document
+        .activities()
+        .find(|(_, activity)| activity.attributes.contains_key(a2a::DURATION_MS))
+        .expect("llm activity");
+    assert_eq!(activity.attributes.get(a2a::STREAM_CHUNK_COUNT), Some(&json!(3)));
+    assert_eq!(activity.attributes.get(a2a::TIME_TO_FIRST_TOKEN_MS), Some(&json!(40)));
+}
+
+#[tokio::test]
+async fn streamed_llm_call_records_reconciled_token_usage() {
+    let store = Arc::new(InMemoryProvenanceStore::new());
+    let writer: Arc<dyn ProvenanceWriter> = store.clone();
+    let interceptor = ProvenanceInterceptor::new(writer);
+    let context = llm_context(json!({
+        "message_id": "msg-1",
+        "stream_id": "stream-1",
+        "token_usage": {
+            "prompt_tokens": 12,
+            "completion_tokens": 30,
+            "total_tokens": 42,
+            "source": "reported",
+            "estimated": {"prompt_tokens": 10, "completion_tokens": 28, "total_tokens": 38}
+        }
+    }));
+
+    interceptor.intercept_llm_call(&context).await.expect("start");
+    interceptor.on_llm_call_complete(&context, &Ok(json!({"text": "done"})), 120).await;
+
+    let events = store.events().await;
+    let completed = events
+        .iter()
+        .find(|event| matches!(event.data(), ProvEventData::LlmCallCompleted { .. }))
+        .expect("completion event");
+    let normalized = normalize_event(completed).expect("normalize");
+    let (_, activity) = normalized
+        .document
+        .activities()
+        .find(|(_, activity)| activity.attributes.contains_key(a2a::DURATION_MS))
+        .expect("llm activity");
+    assert_eq!(activity.attributes.get(a2a::USAGE_PROMPT_TOKENS), Some(&json!(12)));
+    assert_eq!(activity.attributes.get(a2a::USAGE_COMPLETION_TOKENS), Some(&json!(30)));
+    assert_eq!(activity.attributes.get(a2a::USAGE_TOTAL_TOKENS), Some(&json!(42)));
+}
+
+#[tokio::test]
+async fn invalid_llm_output_is_annotated_on_the_call() {
+    let store = Arc::new(InMemoryProvenanceStore::new());
+    let writer: Arc<dyn ProvenanceWriter> = store.clone();
+    let interceptor = ProvenanceInterceptor::new(writer);
+    let context = llm_context(json!({
+        "message_id": "msg-1",
+        "output_validation": {"valid": false, "mismatches": ["/score: expected int, found string"]}
+    }));
+
+    interceptor.on_llm_call_complete(&context, &Ok(json!({})), 10).await;
+
+    let events = store.events().await;
+    let normalized = normalize_event(&events[0]).expect("normalize");
+    let (_, activity) = normalized
+        .document
+        .activities()
+        .find(|(_, activity)| activity.attributes.contains_key(a2a::DURATION_MS))
+        .expect("llm activity");
+    assert_eq!(activity.attributes.get(a2a::OUTPUT_VALID), Some(&json!(false)));
+    assert_eq!(
+        activity.attributes.get(a2a::OUTPUT_MISMATCHES),
+        Some(&json!(["/score: expected int, found string"]))
+    );
+}
+
+#[tokio::test]
+async fn output_repair_retries_link_to_the_attempt_they_retry() {
+    let store = Arc::new(InMemoryProvenanceStore::new());
+    let writer: Arc<dyn ProvenanceWriter> = store.clone();
+    let interceptor = ProvenanceInterceptor::new(writer);
+    let first = llm_context(json!({
+        "message_id": "msg-1",
+        "output_validation": {"valid": false, "mismatches": ["/score: expected int, found string"]},
+        "output_repair": {"chain": "repair-1", "attempt": 1, "max_attempts": 2}
+    }));
+    let second = llm_context(json!({
+        "message_id": "msg-1",
+        "output_validation": {"valid": true},
+        "output_repair": {"chain": "repair-1", "attempt": 2, "max_attempts": 2}
+    }));
+
+    interceptor.on_llm_call_complete(&first, &Ok(json!({})), 10).await;
+    interceptor.on_llm_call_complete(&second, &Ok(json!({})), 10).await;
+
+    let events = store.events().await;
+    let ProvEventData::LlmCallCompleted { metadata, .. } = events[1].data() else {
+        panic!("expected a completion event");
+    };
+    assert_eq!(metadata["output_repair"]["retry_of"]["event_id"], json!(events[0].id().as_str()));
+
+    let normalized = normalize_event(&events[1]).expect("normalize");
+    let retry = normalized
+        .derived_relations
+        .iter()
+        .find(|relation| matches!(relation.relation, A2aRelationType::LlmRetryOf))
+        .expect("retry relation");
+    let ProvNodeRef::Activity(retried) = &retry.to else {
+        panic!("retry should point at an activity");
+    };
+    let stub = normalized.document.activity(retried).expect("retried attempt");
+    assert_eq!(stub.attributes.get(a2a::MODEL), Some(&json!("model")));
+    assert!(!stub.attributes.contains_key(a2a::DURATION_MS));
+    let (_, activity) = normalized
+        .document
+        .activities()
+        .find(|(_, activity)| activity.attributes.contains_key(a2a::DURATION_MS))
+        .expect("llm activity");
+    assert_eq!(activity.attributes.get(a2a::RETRY_ATTEMPT), Some(&json!(2)));
+    assert_eq!(activity.attributes.get(a2a::RETRY_MAX_ATTEMPTS), Some(&json!(2)));
+
+    // The chain ended with a valid answer, so a new call reusing it starts fresh.
+    interceptor.on_llm_call_complete(&first, &Ok(json!({})), 10).await;
+    let events = store.events().await;
+    let ProvEventData::LlmCallCompleted { metadata, .. } = events[2].data() else {
+        panic!("expected a completion event");
+    };
+    assert!(metadata["output_repair"].get("retry_of").is_none());
+}
+
+#[tokio::test]
+async fn unstreamed_llm_call_has_no_stream_progress() {
+    let store = Arc::new(InMemoryProvenanceStore::new());
+    let writer: Arc<dyn ProvenanceWriter> = store.clone();
+    let interceptor = ProvenanceInterceptor::new(writer);
+    let context = llm_context(json!({"message_id": "msg-1"}));
+
+    interceptor.on_llm_call_complete(&context, &Ok(json!({})), 10).await;
+
+    let events = store.events().await;
+    let ProvEventData::LlmCallCompleted { metadata, .. } = events[0].data() else {
+        panic!("expected a completion event");
+    };
+    assert!(metadata.get("stream").is_none());
+}
+
+#[tokio::test]
+async fn tool_retries_are_recorded_as_failed_attempts() {
+    let store = Arc::new(InMemoryProvenanceStore::new());
+    let writer: Arc<dyn ProvenanceWriter> = store.clone();
+    let interceptor = ProvenanceInterceptor::new(writer);
+    let context = ToolCallContext {
+        tool_name: "support/calculate".to_string(),
+        function_name: None,
+        args: json!({}),
+        context_id: ContextId::new(1, 1),
+        metadata: json!({"message_id": "msg-1"}),
+    };
+    let retry = ToolCallRetry {
+        attempt: 1,
+        max_attempts: 3,
+        failure_kind: "Timeout".to_string(),
+        error: "timed out".to_string(),
+        duration_ms: 50,
+        backoff_ms: 100,
+    };
+
+    interceptor.on_tool_call_retry(&context, &retry).await;
+
+    let events = store.events().await;
+    let ProvEventData::ToolCallCompleted { success, duration_ms, .. } = events[0].data() else {
+        panic!("expected a completion event");
+    };
+    assert!(!success);
+    assert_eq!(*duration_ms, 50);
+
+    let normalized = normalize_event(&events[0]).expect("normalize");
+    let (_, activity) = normalized
+        .document
+        .activities()
+        .find(|(_, activity)| activity.attributes.contains_key(a2a::TOOL_NAME))
+        .expect("tool activity");
+    assert_eq!(activity.attributes.get(a2a::RETRY_ATTEMPT), Some(&json!(1)));
+    assert_eq!(activity.attributes.get(a2a::RETRY_MAX_ATTEMPTS), Some(&json!(3)));
+    assert_eq!(activity.attributes.get(a2a::RETRY_FAILURE_KIND), Some(&json!("Timeout")));
+    assert_eq!(activity.attributes.get(a2a::RETRY_BACKOFF_MS), Some(&json!(100)));
+}
+
+#[tokio::test]
+async fn exhausted_budgets_are_recorded_against_the_context() {
+    let store = Arc::new(InMemoryProvenanceStore::new());
+    let writer: Arc<dyn ProvenanceWriter> = store.clone();
+    let interceptor = ProvenanceInterceptor::new(writer);
+    let context = llm_context(json!({"message_id": "msg-1"}));
+    let exhausted = BudgetExhausted {
+        scope: BudgetScope::Context(context.context_id.clone()),
+        dimension: BudgetDimension::TotalTokens,
+        limit: 100,
+        spent: TokenUsage::new(80, 40),
+    };
+
+    interceptor.on_budget_exhausted(&context, &exhausted).await;
+
+    let events = store.events().await;
+    assert_eq!(events[0].data().event_type(), "BudgetExhausted");
+    let normalized = normalize_event(&events[0]).expect("normalize");
+    let (_, entity) = normalized
+        .document
+        .entities()
+        .find(|(_, entity)| entity.prov_type.as_deref() == Some(a2a_types::BUDGET_EXHAUSTION))
+        .expect("budget exhaustion entity");
+    assert_eq!(entity.attributes.get(a2a::BUDGET_SCOPE), Some(&json!("context")));
+    assert_eq!(entity.attributes.get(a2a::BUDGET_DIMENSION), Some(&json!("total_tokens")));
+    assert_eq!(entity.attributes.get(a2a::BUDGET_LIMIT), Some(&json!(100)));
+    assert_eq!(entity.attributes.get(a2a::USAGE_TOTAL_TOKENS), Some(&json!(120)));
+    baml_rt_provenance::schema::validate_document(&normalized.document).expect("valid document");
+}
+
+#[tokio::test]
+async fn policy_decisions_are_recorded_against_the_context() {
+    let store = Arc::new(InMemoryProvenanceStore::new());
+    let writer: Arc<dyn ProvenanceWriter> = store.clone();
+    let interceptor = ProvenanceInterceptor::new(writer);
+    let decision = PolicyDecision {
+        rule_id: "writes-stay-in-tmp".to_string(),
+        effect: PolicyEffect::Deny,
+        target: PolicyTarget::Tool,
+        subject: "fs/write".to_string(),
+        reason: Some("fs/write is limited to /tmp".to_string()),
+    };
+
+    interceptor.on_policy_decision(&ContextId::new(1, 1), &decision).await;
+
+    let events = store.events().await;
+    assert_eq!(events[0].data().event_type(), "PolicyEvaluated");
+    let normalized = normalize_event(&events[0]).expect("normalize");
+    let (_, entity) = normalized
+        .document
+        .entities()
+        .find(|(_, entity)| entity.prov_type.as_deref() == Some(a2a_types::POLICY_DECISION))
+        .expect("policy decision entity");
+    assert_eq!(entity.attributes.get(a2a::POLICY_ID), Some(&json!("writes-stay-in-tmp")));
+    assert_eq!(entity.attributes.get(a2a::POLICY_EFFECT), Some(&json!("deny")));
+    assert_eq!(entity.attributes.get(a2a::POLICY_TARGET), Some(&json!("tool")));
+    assert_eq!(entity.attributes.get(a2a::POLICY_SUBJECT), Some(&json!("fs/write")));
+    assert_eq!(
+        entity.attributes.get(a2a::POLICY_REASON),
+        Some(&json!("fs/write is limited to /tmp"))
+    );
+    baml_rt_provenance::schema::validate_document(&normalized.document).expect("valid document");
+}
+
+#[tokio::test]
+async fn approval_decisions_record_who_decided_what() {
+    let store = Arc::new(InMemoryProvenanceStore::new());
+    let writer: Arc<dyn ProvenanceWriter> = store.clone();
+    let interceptor = ProvenanceInterceptor::new(writer);
+    let outcome = ApprovalOutcome {
+        request: PendingApproval {
+            approval_id: "approval-1".to_string(),
+            tool_name: "payments/refund".to_string(),
+            args: json!({"amount": 120}),
+            context_id: ContextId::new(1, 1),
+            agent_id: None,
+            requested_at_ms: 1_000,
+            expires_at_ms: 301_000,
+        },
+        verdict: ApprovalVerdict::Denied,
+        approver: Some("ops@example.com".to_string()),
+        reason: Some("over the refund limit".to_string()),
+        waited_ms: 4_200,
+    };
+
+    interceptor.on_approval_decided(&outcome).await;
+
+    let events = store.events().await;
+    assert_eq!(events[0].data().event_type(), "ApprovalDecided");
+    let normalized = normalize_event(&events[0]).expect("normalize");
+    let (_, entity) = normalized
+        .document
+        .entities()
+        .find(|(_, entity)| entity.prov_type.as_deref() == Some(a2a_types::APPROVAL_DECISION))
+        .expect("approval decision entity");
+    assert_eq!(entity.attributes.get(a2a::APPROVAL_ID), Some(&json!("approval-1")));
+    assert_eq!(entity.attributes.get(a2a::TOOL_NAME), Some(&json!("payments/refund")));
+    assert_eq!(entity.attributes.get(a2a::ARGS), Some(&json!({"amount": 120})));
+    assert_eq!(entity.attributes.get(a2a::APPROVAL_VERDICT), Some(&json!("denied")));
+    assert_eq!(entity.attributes.get(a2a::APPROVER), Some(&json!("ops@example.com")));
+    assert_eq!(
+        entity.attributes.get(a2a::APPROVAL_REASON),
+        Some(&json!("over the refund limit"))
+    );
+    assert_eq!(entity.attributes.get(a2a::APPROVAL_WAITED_MS), Some(&json!(4_200)));
+    baml_rt_provenance::schema::validate_document(&normalized.document).expect("valid document");
+}
@@ -0,0 +1,103 @@
+use baml_rt_core::ids::{ContextId, ExternalId, MessageId};
+use baml_rt_provenance::jsonl_store::log_segments;
+use baml_rt_provenance::{
+    replay_jsonl_log, InMemoryProvenanceStore, JsonlProvenanceConfig, JsonlProvenanceWriter,
+    ProvEvent, ProvenanceWriter, ReplayOptions,
+};
+use serde_json::json;
+use std::time::Duration;
+
+fn tool_event(message: &str) -> ProvEvent {
+    ProvEvent::tool_call_started_global(
+        ContextId::new(1, 1),
+        MessageId::from_external(ExternalId::new(message)),
+        "tool".to_string(),
+        None,
+        json!({"input": "value"}),
+        json!({"message_id": message}),
+    )
+}
+
+fn line_count(path: &std::path::Path) -> usize {
+    std::fs::read_to_string(path).unwrap().lines().count()
+}
+
+#[tokio::test]
+async fn events_are_appended_one_per_line() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("logs").join("events.jsonl");
+    let writer = JsonlProvenanceWriter::open(JsonlProvenanceConfig::new(&path)).unwrap();
+
+    writer.add_event(tool_event("msg-1")).await.unwrap();
+    writer.add_events(vec![tool_event("msg-2"), tool_event("msg-3")]).await.unwrap();
+    writer.flush().await.unwrap();
+    writer.health_check().await.unwrap();
+
+    let text = std::fs::read_to_string(&path).unwrap();
+    let events: Vec<ProvEvent> =
+        text.lines().map(|line| serde_json::from_str(line).expect("event line")).collect();
+    assert_eq!(events.len(), 3);
+    assert!(text.contains("msg-2"));
+
+    // Reopening appends after what is already there.
+    let reopened = JsonlProvenanceWriter::open(JsonlProvenanceConfig::new(&path)).unwrap();
+    reopened.add_event(tool_event("msg-4")).await.unwrap();
+    assert_eq!(line_count(&path), 4);
+}
+
+#[tokio::test]
+async fn the_log_rotates_by_size_and_prunes_old_segments() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("events.jsonl");
+    let line_len = serde_json::to_vec(&tool_event("msg-0")).unwrap().len() as u64 + 1;
+    let config = JsonlProvenanceConfig::new(&path)
+        .with_max_bytes(Some(line_len * 2 + line_len / 2))
+        .with_max_segments(Some(2));
+    let writer = JsonlProvenanceWriter::open(config).unwrap();
+
+    for index in 1..=7 {
+        writer.add_event(tool_event(&format!("msg-{index}"))).await.unwrap();
+    }
+
+    let segments = log_segments(&path).unwrap();
+    assert_eq!(segments.len(), 3, "two rotated segments and the active file: {segments:?}");
+    assert_eq!(segments.last(), Some(&path));
+    assert_eq!(line_count(&path), 1);
+    assert!(segments[..2].iter().all(|segment| line_count(segment) == 2));
+}
+
+#[tokio::test]
+async fn the_log_rotates_by_age() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("events.jsonl");
+    let config = JsonlProvenanceConfig::new(&path)
+        .with_max_bytes(None)
+        .with_max_age(Some(Duration::from_millis(20)));
+    let writer = JsonlProvenanceWriter::open(config).unwrap();
+
+    writer.add_event(tool_event("msg-1")).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(40)).await;
+    writer.add_event(tool_event("msg-2")).await.unwrap();
+
+    assert_eq!(log_segments(&path).unwrap().len(), 2);
+    assert_eq!(line_count(&path), 1);
+}
+
+#[tokio::test]
+async fn a_rotated_log_replays_into_a_store() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("events.jsonl");
+    let line_len = serde_json::to_vec(&tool_event("msg-0")).unwrap().len() as u64 + 1;
+    let writer =
+        JsonlProvenanceWriter::open(JsonlProvenanceConfig::new(&path).with_max_bytes(Some(line_len)))
+            .unwrap();
+    for index in 1..=4 {
+        writer.add_event(tool_event(&format!("msg-{index}"))).await.unwrap();
+    }
+
+    let store = InMemoryProvenanceStore::new();
+    let report = replay_jsonl_log(&path, &store, ReplayOptions::default()).await.unwrap();
+    assert_eq!(report.replayed, 4);
+    assert!(report.skipped.is_empty());
+    assert_eq!(store.len().await, 4);
+}
@@ -0,0 +1,107 @@
+use async_trait::async_trait;
+use baml_rt_core::ids::{ContextId, ExternalId, TaskId};
+use baml_rt_core::BamlRtError;
+use baml_rt_interceptor::{FUNCTION_ARGS_METADATA_KEY, FUNCTION_OUTPUT_METADATA_KEY};
+use baml_rt_provenance::{
+    recorded_llm_calls, replay_llm_calls, LlmReplayOptions, LlmReplayTarget, LlmUsage, ProvEvent,
+};
+use serde_json::{json, Value};
+use std::sync::Mutex;
+
+/// Answers `Summarize` by upper-casing its text and fails everything else.
+#[derive(Default)]
+struct UppercaseTarget {
+    clients: Mutex<Vec<Option<String>>>,
+}
+
+#[async_trait]
+impl LlmReplayTarget for UppercaseTarget {
+    async fn invoke(&self, function_name: &str, args: Value, client: Option<&str>) -> baml_rt_core::Result<Value> {
+        self.clients.lock().unwrap().push(client.map(str::to_string));
+        if function_name != "Summarize" {
+            return Err(BamlRtError::FunctionNotFound(function_name.to_string()));
+        }
+        Ok(json!(args["text"].as_str().unwrap_or_default().to_uppercase()))
+    }
+}
+
+fn recorded(args: Value, output: Value) -> Value {
+    json!({FUNCTION_ARGS_METADATA_KEY: args, FUNCTION_OUTPUT_METADATA_KEY: output})
+}
+
+fn call_events(function_name: &str, metadata: Value) -> Vec<ProvEvent> {
+    let context_id = ContextId::new(1, 1);
+    let task_id = TaskId::from_external(ExternalId::new("task-1"));
+    let started = ProvEvent::llm_call_started_task(
+        context_id.clone(),
+        task_id.clone(),
+        "openai".to_string(),
+        "gpt-4o".to_string(),
+        function_name.to_string(),
+        json!({"messages": [{"role": "user", "content": "prompt"}]}),
+        json!({}),
+    );
+    let completed = ProvEvent::llm_call_completed_task(
+        context_id,
+        task_id,
+        "openai".to_string(),
+        "gpt-4o".to_string(),
+        function_name.to_string(),
+        json!({"request": "as sent"}),
+        metadata,
+        LlmUsage::Unknown,
+        10,
+        true,
+    );
+    vec![started, completed]
+}
+
+#[test]
+fn completions_pair_with_the_call_that_started_them() {
+    let events = call_events("Summarize", recorded(json!({"text": "hi"}), json!("HI")));
+    let (calls, skipped) = recorded_llm_calls(&events);
+    assert!(skipped.is_empty());
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].started.as_ref(), Some(events[0].id()));
+    assert_eq!(calls[0].completed, *events[1].id());
+    assert_eq!(calls[0].prompt["messages"][0]["content"], json!("prompt"), "prompt comes from the start");
+    assert_eq!(calls[0].output, Some(json!("HI")));
+}
+
+#[test]
+fn calls_without_arguments_or_with_truncated_ones_are_not_replayable() {
+    let mut events = call_events("Summarize", recorded(json!({"text": "hi"}), json!("HI")));
+    // A call the output was not parsed from carries no arguments.
+    events.extend(call_events("Summarize", json!({})));
+    events.extend(call_events(
+        "Summarize",
+        recorded(json!({"truncated": true, "chars": 9000, "preview": "{\"text\""}), json!("X")),
+    ));
+
+    let (calls, skipped) = recorded_llm_calls(&events);
+    assert_eq!(calls.len(), 1);
+    assert_eq!(skipped.len(), 1);
+    assert!(skipped[0].reason.contains("truncated"));
+}
+
+#[tokio::test]
+async fn replay_compares_new_outputs_with_the_recorded_ones() {
+    let mut events = call_events("Summarize", recorded(json!({"text": "same"}), json!("SAME")));
+    events.extend(call_events("Summarize", recorded(json!({"text": "changed"}), json!("old answer"))));
+    events.extend(call_events("Classify", recorded(json!({"text": "x"}), json!("label"))));
+    let target = UppercaseTarget::default();
+
+    let report = replay_llm_calls(&events, &target, &LlmReplayOptions::default().with_client("Claude")).await;
+    assert_eq!(report.outcomes.len(), 3);
+    assert_eq!(report.outcomes[0].matches, Some(true));
+    assert_eq!(report.outcomes[1].matches, Some(false));
+    assert!(report.outcomes[2].error.is_some());
+    assert_eq!(report.regressions().count(), 2);
+    assert!(!report.passed());
+    assert!(target.clients.lock().unwrap().iter().all(|client| client.as_deref() == Some("Claude")));
+
+    let only = LlmReplayOptions::default().with_function("Summarize").with_limit(1);
+    let report = replay_llm_calls(&events, &target, &only).await;
+    assert_eq!(report.outcomes.len(), 1);
+    assert!(report.passed());
+}
@@ -0,0 +1,348 @@
+use baml_rt_core::ids::{AgentId, ContextId, EventId, ExternalId, MessageId, TaskId, UuidId};
+use baml_rt_provenance::vocabulary::{a2a_types, prov_roles};
+use baml_rt_provenance::{
+    AgentType, ContextRetention, DefaultProvNormalizer, GlobalEvent, ProvEvent, ProvEventData,
+    NormalizedProv, ProvNormalizer, ProvenanceError, TaskScopedEvent, CALLING_AGENT_ID_METADATA_KEY,
+};
+use serde_json::json;
+use std::collections::HashMap;
+
+fn agent(raw: &str) -> AgentId {
+    AgentId::from_uuid(UuidId::parse_str(raw).unwrap())
+}
+
+fn agent_booted(tenant: Option<&str>, context_id: &ContextId, agent_id: &AgentId) -> ProvEvent {
+    ProvEvent::Global(GlobalEvent {
+        id: EventId::from_counter(0),
+        tenant: tenant.map(str::to_string),
+        context_id: context_id.clone(),
+        timestamp_ms: 1_700_000_000_000,
+        data: ProvEventData::AgentBooted {
+            agent_id: agent_id.clone(),
+            agent_type: AgentType::new("tony").expect("agent_type"),
+            agent_version: "1.0.0".to_string(),
+            archive_path: "tony@1.0.0".to_string(),
+        },
+    })
+}
+
+fn task_event(tenant: Option<&str>, context_id: &ContextId, task_id: &TaskId, data: ProvEventData) -> ProvEvent {
+    ProvEvent::Task(TaskScopedEvent {
+        id: EventId::from_counter(1),
+        tenant: tenant.map(str::to_string),
+        context_id: context_id.clone(),
+        task_id: task_id.clone(),
+        timestamp_ms: 1_700_000_000_001,
+        data,
+    })
+}
+
+fn task_created(tenant: Option<&str>, context_id: &ContextId, task_id: &TaskId, agent_id: &AgentId) -> ProvEvent {
+    task_event(
+        tenant,
+        context_id,
+        task_id,
+        ProvEventData::TaskCreated { task_id: task_id.clone(), agent_id: agent_id.clone() },
+    )
+}
+
+fn task_completed(context_id: &ContextId, task_id: &TaskId) -> ProvEvent {
+    task_event(
+        None,
+        context_id,
+        task_id,
+        ProvEventData::TaskStatusChanged {
+            task_id: task_id.clone(),
+            old_status: Some("TASK_STATE_WORKING".to_string()),
+            new_status: Some("TASK_STATE_COMPLETED".to_string()),
+        },
+    )
+}
+
+#[test]
+fn agents_booted_for_one_tenant_do_not_resolve_for_another() {
+    let normalizer = DefaultProvNormalizer::default();
+    let agent_id = agent("00000000-0000-0000-0000-000000000010");
+    let task_id = TaskId::from_external(ExternalId::new("task-1"));
+
+    normalizer
+        .normalize(&agent_booted(Some("alice"), &ContextId::new(1, 1), &agent_id))
+        .expect("boot");
+    normalizer
+        .normalize(&task_created(Some("alice"), &ContextId::new(2, 1), &task_id, &agent_id))
+        .expect("same tenant resolves the agent");
+
+    let err = normalizer
+        .normalize(&task_created(Some("bob"), &ContextId::new(3, 1), &task_id, &agent_id))
+        .expect_err("other tenant does not");
+    assert!(matches!(err, ProvenanceError::MissingLabel { .. }), "{err:?}");
+
+    // Agents booted without a tenant belong to the runner and resolve everywhere.
+    let runner_agent = agent("00000000-0000-0000-0000-000000000020");
+    normalizer.normalize(&agent_booted(None, &ContextId::new(1, 1), &runner_agent)).expect("boot");
+    normalizer
+        .normalize(&task_created(Some("bob"), &ContextId::new(4, 1), &task_id, &runner_agent))
+        .expect("shared agent resolves");
+}
+
+#[test]
+fn later_events_on_a_task_are_associated_with_its_agent() {
+    let normalizer = DefaultProvNormalizer::default();
+    let context_id = ContextId::new(1, 1);
+    let agent_id = agent("00000000-0000-0000-0000-000000000010");
+    let task_id = TaskId::from_external(ExternalId::new("task-1"));
+
+    normalizer.normalize(&agent_booted(None, &context_id, &agent_id)).expect("boot");
+    normalizer.normalize(&task_created(None, &context_id, &task_id, &agent_id)).expect("task");
+
+    let normalized = normalizer.normalize(&task_completed(&context_id, &task_id)).expect("status");
+    let associations: Vec<_> = normalized.document.was_associated_with().collect();
+    assert!(
+        associations
+            .iter()
+            .any(|(_, relation)| relation.role.as_deref() == Some(prov_roles::EXECUTING_AGENT)),
+        "{associations:?}"
+    );
+}
+
+#[test]
+fn completed_contexts_are_evicted_past_the_retention_limit() {
+    let normalizer = DefaultProvNormalizer::default()
+        .with_context_retention(ContextRetention { max_contexts: 10, max_completed: 1 });
+    let agent_id = agent("00000000-0000-0000-0000-000000000010");
+    normalizer.normalize(&agent_booted(None, &ContextId::new(9, 1), &agent_id)).expect("boot");
+
+    let contexts: Vec<_> = (1..=3).map(|index| ContextId::new(index, 1)).collect();
+    let task_id = TaskId::from_external(ExternalId::new("task-1"));
+    for context_id in &contexts {
+        normalizer.normalize(&task_created(None, context_id, &task_id, &agent_id)).expect("task");
+    }
+    assert_eq!(normalizer.tracked_contexts(), 4);
+
+    normalizer.normalize(&task_completed(&contexts[0], &task_id)).expect("complete");
+    assert!(normalizer.is_tracking(&contexts[0]), "one completed context is kept");
+    normalizer.normalize(&task_completed(&contexts[1], &task_id)).expect("complete");
+    assert!(!normalizer.is_tracking(&contexts[0]), "the oldest completed context is evicted");
+    assert!(normalizer.is_tracking(&contexts[1]));
+    assert!(normalizer.is_tracking(&contexts[2]), "open contexts are kept");
+}
+
+#[test]
+fn the_least_recently_used_context_is_dropped_past_the_context_limit() {
+    let normalizer = DefaultProvNormalizer::default()
+        .with_context_retention(ContextRetention { max_contexts: 2, max_completed: 2 });
+    let agent_id = agent("00000000-0000-0000-0000-000000000010");
+    let task_id = TaskId::from_external(ExternalId::new("task-1"));
+    normalizer.normalize(&agent_booted(None, &ContextId::new(1, 1), &agent_id)).expect("boot");
+
+    for index in 2..=3 {
+        normalizer
+            .normalize(&task_created(None, &ContextId::new(index, 1), &task_id, &agent_id))
+            .expect("task");
+    }
+    assert_eq!(normalizer.tracked_contexts(), 2);
+    assert!(!normalizer.is_tracking(&ContextId::new(1, 1)));
+    // The boot itself is not forgotten with its context.
+    normalizer
+        .normalize(&task_created(None, &ContextId::new(4, 1), &task_id, &agent_id))
+        .expect("agent still resolves");
+}
+
+fn executing_agent_of(normalized: &NormalizedProv, activity: &str) -> bool {
+    normalized.document.was_associated_with().any(|(_, relation)| {
+        relation.activity.as_str() == activity
+            && relation.role.as_deref() == Some(prov_roles::EXECUTING_AGENT)
+    })
+}
+
+fn llm_activity(normalized: &NormalizedProv) -> String {
+    normalized
+        .document
+        .activities()
+        .find(|(_, activity)| activity.prov_type.as_deref() == Some(a2a_types::LLM_CALL))
+        .map(|(id, _)| id.as_str().to_string())
+        .expect("llm call activity")
+}
+
+#[test]
+fn calls_made_before_task_created_are_backfilled_by_it() {
+    let normalizer = DefaultProvNormalizer::default();
+    let context_id = ContextId::new(1, 1);
+    let agent_id = agent("00000000-0000-0000-0000-000000000010");
+    let task_id = TaskId::from_external(ExternalId::new("task-1"));
+    normalizer.normalize(&agent_booted(None, &context_id, &agent_id)).expect("boot");
+
+    let call = ProvEvent::llm_call_started_task(
+        context_id.clone(),
+        task_id.clone(),
+        "openai".to_string(),
+        "gpt-4o".to_string(),
+        "Summarize".to_string(),
+        json!({"text": "hi"}),
+        json!({}),
+    );
+    let early = normalizer.normalize(&call).expect("llm call");
+    let activity = llm_activity(&early);
+    assert!(!executing_agent_of(&early, &activity), "no agent is known for the task yet");
+
+    let created = normalizer.normalize(&task_created(None, &context_id, &task_id, &agent_id)).expect("task");
+    assert!(executing_agent_of(&created, &activity), "TaskCreated back-fills the association");
+    assert!(created.activity_types.contains_key(&activity));
+
+    // Only once: a later binding event has nothing left to back-fill.
+    let again = normalizer.normalize(&task_completed(&context_id, &task_id)).expect("status");
+    assert!(!executing_agent_of(&again, &activity));
+}
+
+#[test]
+fn message_calls_are_associated_with_the_agent_that_handled_the_message() {
+    let normalizer = DefaultProvNormalizer::default();
+    let context_id = ContextId::new(1, 1);
+    let agent_id = agent("00000000-0000-0000-0000-000000000010");
+    let message_id = MessageId::from_external(ExternalId::new("msg-1"));
+    normalizer.normalize(&agent_booted(None, &context_id, &agent_id)).expect("boot");
+
+    let call = || {
+        ProvEvent::llm_call_started_global(
+            context_id.clone(),
+            message_id.clone(),
+            "openai".to_string(),
+            "gpt-4o".to_string(),
+            "Summarize".to_string(),
+            json!({"text": "hi"}),
+            json!({}),
+        )
+    };
+    let early = normalizer.normalize(&call()).expect("early call");
+    let early_activity = llm_activity(&early);
+    assert!(!executing_agent_of(&early, &early_activity));
+
+    let received = ProvEvent::message_received_global(
+        context_id.clone(),
+        message_id.clone(),
+        "user".to_string(),
+        vec!["hi".to_string()],
+        Some(HashMap::from([("agent_id".to_string(), agent_id.as_str().to_string())])),
+        1_700_000_000_000,
+    );
+    let received = normalizer.normalize(&received).expect("message");
+    assert!(executing_agent_of(&received, &early_activity), "the message back-fills the early call");
+
+    let late = normalizer.normalize(&call()).expect("late call");
+    assert!(executing_agent_of(&late, &llm_activity(&late)), "later calls resolve directly");
+}
+
+#[test]
+fn a_message_from_another_agent_records_the_delegation() {
+    let normalizer = DefaultProvNormalizer::default();
+    let context_id = ContextId::new(1, 1);
+    let caller = agent("00000000-0000-0000-0000-000000000010");
+    let callee = agent("00000000-0000-0000-0000-000000000020");
+    normalizer.normalize(&agent_booted(None, &context_id, &caller)).expect("boot caller");
+    normalizer.normalize(&agent_booted(None, &context_id, &callee)).expect("boot callee");
+
+    let received = ProvEvent::message_received_global(
+        ContextId::new(2, 1),
+        MessageId::from_external(ExternalId::new("msg-1")),
+        "user".to_string(),
+        vec!["hi".to_string()],
+        Some(HashMap::from([
+            ("agent_id".to_string(), callee.as_str().to_string()),
+            (CALLING_AGENT_ID_METADATA_KEY.to_string(), caller.as_str().to_string()),
+        ])),
+        1_700_000_000_000,
+    );
+    let normalized = normalizer.normalize(&received).expect("message");
+    let delegations: Vec<_> = normalized.document.acted_on_behalf_of().map(|(_, rel)| rel).collect();
+    assert_eq!(delegations.len(), 1);
+    let responsible = normalized.document.responsible_agents(&delegations[0].delegate);
+    assert_eq!(responsible, [&delegations[0].responsible]);
+    assert!(delegations[0].activity.is_some());
+
+    // A caller the runner never booted has no node to point at.
+    let stranger = ProvEvent::message_received_global(
+        ContextId::new(2, 1),
+        MessageId::from_external(ExternalId::new("msg-2")),
+        "user".to_string(),
+        vec!["hi".to_string()],
+        Some(HashMap::from([
+            ("agent_id".to_string(), callee.as_str().to_string()),
+            (
+                CALLING_AGENT_ID_METADATA_KEY.to_string(),
+                "00000000-0000-0000-0000-000000000099".to_string(),
+            ),
+        ])),
+        1_700_000_000_000,
+    );
+    let normalized = normalizer.normalize(&stranger).expect("message");
+    assert_eq!(normalized.document.acted_on_behalf_of().count(), 0);
+}
+
+#[test]
+fn a_call_made_for_another_agent_records_the_delegation() {
+    let normalizer = DefaultProvNormalizer::default();
+    let context_id = ContextId::new(1, 1);
+    let caller = agent("00000000-0000-0000-0000-000000000010");
+    let callee = agent("00000000-0000-0000-0000-000000000020");
+    let task_id = TaskId::from_external(ExternalId::new("task-1"));
+    normalizer.normalize(&agent_booted(None, &context_id, &caller)).expect("boot caller");
+    normalizer.normalize(&agent_booted(None, &context_id, &callee)).expect("boot callee");
+    normalizer.normalize(&task_created(None, &context_id, &task_id, &callee)).expect("task");
+
+    let call = ProvEvent::llm_call_started_task(
+        context_id.clone(),
+        task_id.clone(),
+        "openai".to_string(),
+        "gpt-4o".to_string(),
+        "Summarize".to_string(),
+        json!({"text": "hi"}),
+        json!({ CALLING_AGENT_ID_METADATA_KEY: caller.as_str() }),
+    );
+    let normalized = normalizer.normalize(&call).expect("llm call");
+    let activity = llm_activity(&normalized);
+    let delegation = normalized.document.acted_on_behalf_of().map(|(_, rel)| rel).next().expect("delegation");
+    assert_eq!(delegation.activity.as_ref().map(|id| id.as_str()), Some(activity.as_str()));
+}
+
+#[test]
+fn a_call_started_after_another_completed_in_its_task_is_informed_by_it() {
+    let normalizer = DefaultProvNormalizer::default();
+    let context_id = ContextId::new(1, 1);
+    let agent_id = agent("00000000-0000-0000-0000-000000000010");
+    let task_id = TaskId::from_external(ExternalId::new("task-1"));
+    normalizer.normalize(&agent_booted(None, &context_id, &agent_id)).expect("boot");
+    normalizer.normalize(&task_created(None, &context_id, &task_id, &agent_id)).expect("task");
+
+    let tool_call = |completed: bool| {
+        let (tool, args) = ("search".to_string(), json!({"q": "rust"}));
+        if completed {
+            ProvEvent::tool_call_completed_task(context_id.clone(), task_id.clone(), tool, None, args, json!({}), 5, true)
+        } else {
+            ProvEvent::tool_call_started_task(context_id.clone(), task_id.clone(), tool, None, args, json!({}))
+        }
+    };
+    let first = normalizer.normalize(&tool_call(false)).expect("first started");
+    assert_eq!(first.document.was_informed_by().count(), 0, "nothing came before it");
+    let completed = normalizer.normalize(&tool_call(true)).expect("first completed");
+    assert_eq!(completed.document.was_informed_by().count(), 0);
+    let completed_activity = completed.document.activities().next().map(|(id, _)| id.clone()).expect("activity");
+
+    let next = normalizer.normalize(&tool_call(false)).expect("next started");
+    let (_, informed) = next.document.was_informed_by().next().expect("wasInformedBy");
+    assert_eq!(informed.informant, completed_activity);
+    assert!(next.document.activity(&informed.informed).is_some());
+    assert!(next.activity_types.contains_key(completed_activity.as_str()));
+
+    // Calls in another task are not ordered after it.
+    let other_task = TaskId::from_external(ExternalId::new("task-2"));
+    let other = ProvEvent::tool_call_started_task(
+        context_id.clone(),
+        other_task,
+        "search".to_string(),
+        None,
+        json!({}),
+        json!({}),
+    );
+    let other = normalizer.normalize(&other).expect("other task");
+    assert_eq!(other.document.was_informed_by().count(), 0);
+}
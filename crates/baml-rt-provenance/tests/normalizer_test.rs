@@ -1,5 +1,10 @@
-use baml_rt_core::ids::{ContextId, ExternalId, TaskId};
-use baml_rt_provenance::{normalize_event, A2aRelationType, ProvEvent};
+use baml_rt_core::ids::{AgentId, ContextId, ExternalId, MessageId, TaskId, UuidId};
+use baml_rt_provenance::schema::{validate_attributes, validate_document};
+use baml_rt_provenance::vocabulary::{a2a, a2a_types};
+use baml_rt_provenance::{
+    normalize_event, normalize_events, validate_event, A2aRelationType, AttrBuilder,
+    FeedbackTarget, LlmUsage, ProvEvent,
+};
 
 #[test]
 fn normalize_status_change_includes_derived_relation() {
@@ -16,3 +21,243 @@ fn normalize_status_change_includes_derived_relation() {
         .iter()
         .any(|rel| matches!(rel.relation, A2aRelationType::TaskStatusTransition)));
 }
+
+#[test]
+fn normalize_feedback_derives_from_rated_message() {
+    let message_id = MessageId::from_external(ExternalId::new("msg-1"));
+    let event = ProvEvent::feedback_submitted(
+        ContextId::new(1, 1),
+        "fb-1".to_string(),
+        FeedbackTarget::Message { message_id },
+        Some(4),
+        None,
+        Some("helpful".to_string()),
+        None,
+    );
+    validate_event(&event).expect("valid feedback");
+    let normalized = normalize_event(&event).expect("normalize event");
+    let derived: Vec<_> = normalized.document.was_derived_from().collect();
+    assert_eq!(derived.len(), 1);
+    let (_, relation) = derived[0];
+    assert_eq!(relation.generated_entity.as_str(), "feedback:fb-1");
+    assert_eq!(relation.used_entity.as_str(), "message:msg-1");
+}
+
+#[test]
+fn validate_feedback_requires_content() {
+    let event = ProvEvent::feedback_submitted(
+        ContextId::new(1, 1),
+        "fb-2".to_string(),
+        FeedbackTarget::Task { task_id: TaskId::from_external(ExternalId::new("task-1")) },
+        None,
+        None,
+        None,
+        None,
+    );
+    assert!(validate_event(&event).is_err());
+}
+
+#[test]
+fn normalize_console_message_derives_diagnostic_from_task() {
+    let event = ProvEvent::console_message_task(
+        ContextId::new(1, 1),
+        TaskId::from_external(ExternalId::new("task-1")),
+        "warn".to_string(),
+        "retrying search".to_string(),
+    );
+    let normalized = normalize_event(&event).expect("normalize event");
+    validate_document(&normalized.document).expect("schema-valid document");
+    let derived: Vec<_> = normalized.document.was_derived_from().collect();
+    assert_eq!(derived.len(), 1);
+    let (_, relation) = derived[0];
+    assert!(relation.generated_entity.as_str().starts_with("diagnostic:"));
+    assert_eq!(relation.used_entity.as_str(), "task:task-1");
+    let (_, diagnostic) = normalized
+        .document
+        .entities()
+        .find(|(_, entity)| entity.prov_type.as_deref() == Some(a2a_types::DIAGNOSTIC))
+        .expect("diagnostic entity");
+    assert_eq!(diagnostic.attributes[a2a::LOG_LEVEL], "warn");
+    assert_eq!(diagnostic.attributes[a2a::LOG_MESSAGE], "retrying search");
+}
+
+#[test]
+fn normalize_config_change_revises_previous_config() {
+    let event = ProvEvent::config_changed(
+        ContextId::new(1, 1),
+        2,
+        "bbbb".to_string(),
+        Some("aaaa".to_string()),
+        vec!["rate_limits".to_string()],
+        "sighup".to_string(),
+    );
+    let normalized = normalize_event(&event).expect("normalize event");
+    validate_document(&normalized.document).expect("schema-valid document");
+    let derived: Vec<_> = normalized.document.was_derived_from().collect();
+    assert_eq!(derived.len(), 1);
+    let (_, relation) = derived[0];
+    assert_eq!(relation.generated_entity.as_str(), "runtime_config:bbbb");
+    assert_eq!(relation.used_entity.as_str(), "runtime_config:aaaa");
+    let (_, config) = normalized
+        .document
+        .entities()
+        .find(|(_, entity)| entity.prov_type.as_deref() == Some(a2a_types::RUNTIME_CONFIG))
+        .expect("config entity");
+    assert_eq!(config.attributes[a2a::CONFIG_VERSION], 2);
+    assert_eq!(config.attributes[a2a::CONFIG_CHANGED], serde_json::json!(["rate_limits"]));
+    assert_eq!(config.attributes[a2a::CONFIG_TRIGGER], "sighup");
+}
+
+#[test]
+fn normalize_schema_change_revises_previous_schema() {
+    let agent_id = AgentId::from_uuid(UuidId::parse_str("00000000-0000-0000-0000-000000000042").unwrap());
+    let event = ProvEvent::schema_changed(
+        ContextId::new(1, 1),
+        agent_id.clone(),
+        "bbbb".to_string(),
+        Some("aaaa".to_string()),
+        vec!["Summarize".to_string()],
+        Vec::new(),
+        vec!["Greet".to_string()],
+    );
+    let normalized = normalize_event(&event).expect("normalize event");
+    validate_document(&normalized.document).expect("schema-valid document");
+    let derived: Vec<_> = normalized.document.was_derived_from().collect();
+    assert_eq!(derived.len(), 1);
+    let (_, relation) = derived[0];
+    assert_eq!(relation.generated_entity.as_str(), format!("baml_schema:{}:bbbb", agent_id.as_str()));
+    assert_eq!(relation.used_entity.as_str(), format!("baml_schema:{}:aaaa", agent_id.as_str()));
+    let (_, schema) = normalized
+        .document
+        .entities()
+        .find(|(_, entity)| entity.prov_type.as_deref() == Some(a2a_types::BAML_SCHEMA))
+        .expect("schema entity");
+    assert_eq!(schema.attributes[a2a::SCHEMA_ADDED], serde_json::json!(["Summarize"]));
+    assert_eq!(schema.attributes[a2a::SCHEMA_CHANGED], serde_json::json!(["Greet"]));
+}
+
+#[test]
+fn normalize_context_fork_links_branch_to_parent() {
+    let event = ProvEvent::context_forked(
+        ContextId::new(2, 1),
+        ContextId::new(1, 1),
+        MessageId::from_external(ExternalId::new("msg-3")),
+    );
+    let normalized = normalize_event(&event).expect("normalize event");
+    let relation = normalized
+        .derived_relations
+        .iter()
+        .find(|rel| matches!(rel.relation, A2aRelationType::ContextDerivedFrom))
+        .expect("context lineage relation");
+    assert_eq!(relation.from.id(), "context:ctx-2-1");
+    assert_eq!(relation.to.id(), "context:ctx-1-1");
+}
+
+#[test]
+fn normalize_nested_context_is_contained_in_its_parent() {
+    let event = ProvEvent::context_nested(ContextId::new(2, 1), ContextId::new(1, 1));
+    let normalized = normalize_event(&event).expect("normalize event");
+    let relation = normalized
+        .derived_relations
+        .iter()
+        .find(|rel| matches!(rel.relation, A2aRelationType::ContextContainedIn))
+        .expect("context containment relation");
+    assert_eq!(relation.from.id(), "context:ctx-2-1");
+    assert_eq!(relation.to.id(), "context:ctx-1-1");
+    let (_, child) = normalized
+        .document
+        .entities()
+        .find(|(id, _)| id.as_str() == "context:ctx-2-1")
+        .expect("child context entity");
+    assert_eq!(child.attributes[a2a::PARENT_CONTEXT_ID], "ctx-1-1");
+}
+
+#[test]
+fn attr_builder_uses_vocabulary_keys() {
+    let event = ProvEvent::task_status_changed(
+        ContextId::new(1, 1),
+        TaskId::from_external(ExternalId::new("task-1")),
+        None,
+        Some("TASK_STATE_WORKING".to_string()),
+    );
+    let attrs = AttrBuilder::for_event(&event)
+        .model("gpt-4o")
+        .usage(&LlmUsage::Known { prompt_tokens: 3, completion_tokens: 4, total_tokens: 7 })
+        .duration_ms(12)
+        .build();
+    assert_eq!(attrs[a2a::CONTEXT_ID], "ctx-1-1");
+    assert_eq!(attrs[a2a::TASK_ID], "task-1");
+    assert_eq!(attrs[a2a::MODEL], "gpt-4o");
+    assert_eq!(attrs[a2a::USAGE_TOTAL_TOKENS], 7);
+    assert_eq!(attrs[a2a::DURATION_MS], 12);
+
+    let unknown = AttrBuilder::new().usage(&LlmUsage::Unknown).build();
+    assert!(unknown.is_empty());
+}
+
+#[test]
+fn normalize_events_shares_nodes_across_the_batch() {
+    let context_id = ContextId::new(1, 1);
+    let task_id = TaskId::from_external(ExternalId::new("task-1"));
+    let events = vec![
+        ProvEvent::task_status_changed(
+            context_id.clone(),
+            task_id.clone(),
+            Some("TASK_STATE_PENDING".to_string()),
+            Some("TASK_STATE_WORKING".to_string()),
+        ),
+        ProvEvent::task_status_changed(
+            context_id,
+            task_id,
+            Some("TASK_STATE_WORKING".to_string()),
+            Some("TASK_STATE_COMPLETED".to_string()),
+        ),
+    ];
+    let batch = normalize_events(&events).expect("normalize batch");
+
+    let mut expected: Vec<String> = Vec::new();
+    for event in &events {
+        let single = normalize_event(event).expect("normalize event");
+        expected.extend(single.document.entities().map(|(id, _)| id.as_str().to_string()));
+    }
+    expected.sort();
+    expected.dedup();
+    let mut entities: Vec<String> =
+        batch.document.entities().map(|(id, _)| id.as_str().to_string()).collect();
+    entities.sort();
+    assert_eq!(entities, expected);
+    assert!(!batch.derived_relations.is_empty());
+    validate_document(&batch.document).expect("schema-valid batch document");
+}
+
+#[test]
+fn normalized_documents_satisfy_attribute_schemas() {
+    let event = ProvEvent::task_status_changed(
+        ContextId::new(1, 1),
+        TaskId::from_external(ExternalId::new("task-1")),
+        Some("TASK_STATE_PENDING".to_string()),
+        Some("TASK_STATE_WORKING".to_string()),
+    );
+    let normalized = normalize_event(&event).expect("normalize event");
+    validate_document(&normalized.document).expect("schema-valid document");
+}
+
+#[test]
+fn attribute_schema_rejects_missing_and_mistyped_keys() {
+    let missing_model = AttrBuilder::new().client("openai").function_name("Summarize").build();
+    let err = validate_attributes("llm:1", Some(a2a_types::LLM_CALL), &missing_model)
+        .expect_err("model is required");
+    assert!(err.to_string().contains(a2a::MODEL));
+
+    let mistyped = AttrBuilder::new()
+        .client("openai")
+        .model("gpt-4o")
+        .function_name("Summarize")
+        .attr(a2a::DURATION_MS, "12")
+        .build();
+    let err = validate_attributes("llm:1", Some(a2a_types::LLM_CALL), &mistyped)
+        .expect_err("duration must be an integer");
+    assert!(err.to_string().contains("expected integer"));
+
+    assert!(validate_attributes("x", Some("a2a:Unregistered"), &mistyped).is_ok());
+}
@@ -0,0 +1,84 @@
+use baml_rt_provenance::schema::SCHEMAS;
+use baml_rt_provenance::vocabulary::{
+    a2a, a2a_relations, a2a_types, prov_relations, semantic_labels,
+};
+use baml_rt_provenance::{LabelStrategy, SEMANTIC_LABEL_RULES, SemanticLabels, ontology};
+use serde_json::json;
+use std::collections::HashMap;
+
+#[test]
+fn every_schema_type_is_described() {
+    let ontology = ontology();
+    assert_eq!(ontology.node_types.len(), SCHEMAS.len());
+
+    let tool_call = ontology.node_type(a2a_types::TOOL_CALL).expect("tool call");
+    assert_eq!(tool_call.base_type, "ProvActivity");
+    assert_eq!(tool_call.label, "ToolCall");
+    let tool_name = tool_call
+        .attributes
+        .iter()
+        .find(|attribute| attribute.key == a2a::TOOL_NAME)
+        .expect("tool name attribute");
+    assert!(tool_name.required);
+    assert_eq!(tool_name.kind, "string");
+
+    let instance = ontology.node_type(a2a_types::AGENT_RUNTIME_INSTANCE).expect("instance");
+    assert_eq!(instance.base_type, "ProvAgent");
+}
+
+#[test]
+fn relations_list_the_labels_their_rules_produce() {
+    let ontology = ontology();
+    let task_message = ontology.relation(a2a_relations::TASK_MESSAGE).expect("task message");
+    assert_eq!(
+        task_message.semantic_labels,
+        vec![
+            semantic_labels::WAS_SPAWNED_BY,
+            semantic_labels::WAS_EMITTED_BY,
+            semantic_labels::WAS_RELATED_TO
+        ]
+    );
+    let qualified = ontology.relation(prov_relations::QUALIFIED_GENERATION).expect("qualified");
+    assert!(qualified.semantic_labels.is_empty());
+
+    for rule in SEMANTIC_LABEL_RULES {
+        assert!(
+            ontology.relation(rule.relation).is_some(),
+            "rule for unknown relation {}",
+            rule.relation
+        );
+    }
+}
+
+#[test]
+fn rules_are_the_ones_semantic_labels_apply() {
+    let props = HashMap::from([("prov:role".to_string(), json!("input_message"))]);
+    let label =
+        SemanticLabels.relation_label(prov_relations::USED, "A2ATaskExecution", "Message", &props);
+    assert_eq!(label, semantic_labels::WAS_SPAWNED_BY);
+
+    let rule = SEMANTIC_LABEL_RULES
+        .iter()
+        .find(|rule| rule.matches(prov_relations::USED, "A2ATaskExecution", "Message", &props))
+        .expect("matching rule");
+    assert_eq!(rule.label, label);
+}
+
+#[test]
+fn ontology_serializes_rules_with_only_their_conditions() {
+    let json = ontology().to_json();
+    assert_eq!(json["version"], json!(baml_rt_provenance::ONTOLOGY_VERSION));
+    let rules = json["semantic_label_rules"].as_array().expect("rules");
+    assert_eq!(
+        rules[0],
+        json!({
+            "relation": "USED",
+            "from_label": "A2ATaskExecution",
+            "property": {"key": "prov:role", "value": "input_message"},
+            "label": "WAS_SPAWNED_BY"
+        })
+    );
+    assert!(
+        json["namespaces"].as_array().expect("namespaces").iter().any(|ns| ns["prefix"] == "a2a")
+    );
+}
@@ -0,0 +1,80 @@
+use baml_rt_core::ids::{ContextId, ExternalId, MessageId};
+use baml_rt_provenance::vocabulary::a2a;
+use baml_rt_provenance::{
+    FalkorDbProvenanceConfig, ProfileRedaction, ProvEvent, ProvenanceError, ProvenanceProfile,
+    ProvenanceSettings, REDACTED, normalize_event_redacted,
+};
+use serde_json::json;
+use std::time::Duration;
+
+#[test]
+fn profiles_parse_by_name_and_differ_in_capture() {
+    for profile in ProvenanceProfile::ALL {
+        assert_eq!(profile.as_str().parse::<ProvenanceProfile>().unwrap(), profile);
+    }
+    assert!(matches!(
+        "production".parse::<ProvenanceProfile>(),
+        Err(ProvenanceError::InvalidSetting { .. })
+    ));
+
+    let dev = ProvenanceProfile::Dev.settings();
+    let prod = ProvenanceProfile::Prod.settings();
+    assert_eq!(dev.redaction, ProfileRedaction::Off);
+    assert_eq!(dev.flush_interval, None);
+    assert_eq!(prod.redaction, ProfileRedaction::Secrets);
+    assert_eq!(prod.flush_interval, Some(Duration::from_secs(1)));
+    assert!(prod.max_payload_chars < dev.max_payload_chars);
+}
+
+#[test]
+fn overrides_replace_single_keys_of_a_profile() {
+    let settings = ProvenanceProfile::Prod
+        .settings()
+        .with_assignment("redaction=payloads")
+        .unwrap()
+        .with_override("flush_interval_ms", "off")
+        .unwrap()
+        .with_assignment("batch_size = 16")
+        .unwrap();
+    assert_eq!(settings.redaction, ProfileRedaction::Payloads);
+    assert_eq!(settings.flush_interval, None);
+    assert_eq!(settings.max_batch_size, 16);
+    assert_eq!(settings.max_payload_chars, ProvenanceProfile::Prod.settings().max_payload_chars);
+
+    let settings = ProvenanceSettings::default();
+    assert!(settings.clone().with_assignment("retention").is_err());
+    assert!(settings.clone().with_override("batch_size", "many").is_err());
+    assert!(settings.with_override("verbosity", "high").is_err());
+}
+
+#[test]
+fn profile_redaction_masks_secrets_before_storage() {
+    let event = ProvEvent::tool_call_started_global(
+        ContextId::new(1, 1),
+        MessageId::from_external(ExternalId::new("msg-1")),
+        "tool".to_string(),
+        None,
+        json!({"header": "Bearer abc.def-123", "query": "weather"}),
+        json!({"message_id": "msg-1"}),
+    );
+    let policy = ProvenanceProfile::Staging.settings().redaction_policy().expect("policy");
+    let normalized = normalize_event_redacted(&event, &policy).expect("normalize");
+    let args = normalized
+        .document
+        .entities()
+        .find_map(|(_, entity)| entity.attributes.get(a2a::ARGS).cloned())
+        .expect("args");
+    assert_eq!(args, json!({"header": REDACTED, "query": "weather"}));
+    assert!(ProvenanceProfile::Dev.settings().redaction_policy().is_none());
+}
+
+#[test]
+fn profile_settings_apply_to_falkordb_config() {
+    let config = ProvenanceProfile::Prod
+        .settings()
+        .apply_to_falkordb(FalkorDbProvenanceConfig::new("falkor://127.0.0.1:6379", "prov"));
+    assert_eq!(config.flush_interval, Some(Duration::from_secs(1)));
+    assert_eq!(config.max_batch_size, 256);
+    assert!(config.redaction.is_some());
+    assert!(!config.strict_attributes);
+}
@@ -0,0 +1,126 @@
+use baml_rt_core::ids::{ContextId, ExternalId, MessageId};
+use baml_rt_provenance::builders::ProvDocumentBuilder;
+use baml_rt_provenance::document::ProvDocument;
+use baml_rt_provenance::{normalize_event, FeedbackTarget, ProvActivityId, ProvAgentId, ProvEvent};
+use serde_json::json;
+
+fn feedback_document(feedback_id: &str, message_id: &str, rating: Option<i64>) -> ProvDocument {
+    let event = ProvEvent::feedback_submitted(
+        ContextId::new(1, 1),
+        feedback_id.to_string(),
+        FeedbackTarget::Message { message_id: MessageId::from_external(ExternalId::new(message_id)) },
+        rating,
+        None,
+        Some("helpful".to_string()),
+        None,
+    );
+    normalize_event(&event).expect("normalize event").document
+}
+
+#[test]
+fn merge_combines_event_documents_without_losing_relations() {
+    let first = feedback_document("fb-1", "msg-1", Some(4));
+    let second = feedback_document("fb-2", "msg-1", None);
+
+    let mut merged = first.clone();
+    merged.merge(second.clone());
+    assert_eq!(merged.was_derived_from().count(), 2);
+    assert_eq!(
+        merged.entities().count(),
+        first.entities().count() + second.entities().count() - 1,
+        "the rated message is merged"
+    );
+
+    // Merging the same statements again is a no-op.
+    let before = merged.to_prov_n();
+    merged.merge(first);
+    assert_eq!(merged.to_prov_n(), before);
+}
+
+#[test]
+fn prov_n_is_independent_of_merge_order() {
+    let first = feedback_document("fb-1", "msg-1", Some(4));
+    let second = feedback_document("fb-2", "msg-1", None);
+
+    let mut forward = first.clone();
+    forward.merge(second.clone());
+    let mut backward = second;
+    backward.merge(first);
+
+    let rendered = forward.to_prov_n();
+    assert_eq!(rendered, backward.to_prov_n());
+    assert!(rendered.starts_with("document\n  prefix a2a <urn:baml-rt:a2a#>\n"));
+    assert!(rendered.ends_with("endDocument\n"));
+    assert!(rendered.contains("wasDerivedFrom(baml:feedback:fb-1, baml:message:msg-1, "));
+    assert!(rendered.contains("prov:type='a2a:Feedback'"));
+    assert!(rendered.contains("a2a:rating=\"4\" %% xsd:long"));
+}
+
+#[test]
+fn prov_json_uses_qualified_names_and_typed_literals() {
+    let exported = feedback_document("fb-1", "msg-1", Some(4)).to_prov_json();
+
+    assert_eq!(exported["prefix"]["baml"], json!("urn:baml-rt:id:"));
+    let feedback = &exported["entity"]["baml:feedback:fb-1"];
+    assert_eq!(feedback["prov:type"], json!({"$": "a2a:Feedback", "type": "prov:QUALIFIED_NAME"}));
+    assert_eq!(feedback["a2a:rating"], json!({"$": "4", "type": "xsd:long"}));
+    assert!(feedback.get("prov:base_type").is_none());
+
+    let derived = exported["wasDerivedFrom"].as_object().expect("wasDerivedFrom section");
+    let (id, relation) = derived.iter().next().expect("one derivation");
+    assert!(id.starts_with("_:"));
+    assert_eq!(relation["prov:generatedEntity"], json!("baml:feedback:fb-1"));
+    assert_eq!(relation["prov:usedEntity"], json!("baml:message:msg-1"));
+}
+
+#[test]
+fn prov_json_round_trips_to_the_same_statements() {
+    let mut document = feedback_document("fb-1", "msg-1", Some(4));
+    document.merge(feedback_document("fb-2", "msg-1", None));
+
+    let exported = document.to_prov_json();
+    let imported = ProvDocument::from_prov_json(&exported).expect("parse PROV-JSON");
+    assert_eq!(imported.to_prov_n(), document.to_prov_n());
+}
+
+#[test]
+fn delegations_round_trip_through_prov_json() {
+    let agent = |id: &str| serde_json::from_value::<ProvAgentId>(json!(id)).expect("agent id");
+    let activity = serde_json::from_value::<ProvActivityId>(json!("act-1")).expect("activity id");
+    let document = ProvDocumentBuilder::new()
+        .acted_on_behalf_of(agent("agent-2"), agent("agent-1"))
+        .activity(activity)
+        .build()
+        .build();
+
+    let exported = document.to_prov_json();
+    let delegation = exported["actedOnBehalfOf"].as_object().expect("delegations").values().next().unwrap();
+    assert_eq!(delegation["prov:delegate"], json!("baml:agent-2"));
+    assert_eq!(delegation["prov:responsible"], json!("baml:agent-1"));
+    assert_eq!(delegation["prov:activity"], json!("baml:act-1"));
+    assert!(document.to_prov_n().contains("actedOnBehalfOf(baml:agent-2, baml:agent-1, baml:act-1)"));
+
+    let imported = ProvDocument::from_prov_json(&exported).expect("parse PROV-JSON");
+    assert_eq!(imported.to_prov_n(), document.to_prov_n());
+}
+
+#[test]
+fn informed_activities_round_trip_through_prov_json() {
+    let activity = |id: &str| serde_json::from_value::<ProvActivityId>(json!(id)).expect("activity id");
+    let document = ProvDocumentBuilder::new().was_informed_by(activity("act-2"), activity("act-1")).build();
+
+    let exported = document.to_prov_json();
+    let informed = exported["wasInformedBy"].as_object().expect("wasInformedBy").values().next().unwrap();
+    assert_eq!(informed["prov:informed"], json!("baml:act-2"));
+    assert_eq!(informed["prov:informant"], json!("baml:act-1"));
+    assert!(document.to_prov_n().contains("wasInformedBy(baml:act-2, baml:act-1)"));
+
+    let imported = ProvDocument::from_prov_json(&exported).expect("parse PROV-JSON");
+    assert_eq!(imported.to_prov_n(), document.to_prov_n());
+}
+
+#[test]
+fn prov_json_import_rejects_relations_without_endpoints() {
+    let broken = json!({"wasDerivedFrom": {"_:d1": {"prov:generatedEntity": "baml:feedback:fb-1"}}});
+    assert!(ProvDocument::from_prov_json(&broken).is_err());
+}
@@ -0,0 +1,221 @@
+use baml_rt_core::ids::{ContextId, ExternalId, MessageId};
+use baml_rt_provenance::vocabulary::a2a;
+use baml_rt_provenance::{
+    AttributeRedactor, HASH_PREFIX, InMemoryProvenanceStore, PRIVILEGED_ROLE, ProvEvent,
+    ProvEventData, ProvenanceError, ProvenanceQuery, ProvenanceReader, ProvenanceWriter, REDACTED,
+    ReadPolicy, RedactingReader, RedactionAction, RedactionKey, RedactionPolicy, RedactionRule,
+    normalize_event_redacted,
+};
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+async fn args_seen_by(reader: &dyn ProvenanceReader) -> Vec<Value> {
+    reader
+        .query_nodes(&ProvenanceQuery::default())
+        .await
+        .expect("query")
+        .into_iter()
+        .filter_map(|record| record.attributes.get(a2a::ARGS).cloned())
+        .collect()
+}
+
+fn tool_call(args: Value) -> ProvEvent {
+    ProvEvent::tool_call_started_global(
+        ContextId::new(1, 1),
+        MessageId::from_external(ExternalId::new("msg-1")),
+        "tool".to_string(),
+        None,
+        args,
+        json!({"message_id": "msg-1"}),
+    )
+}
+
+fn normalized_args(event: &ProvEvent, policy: &RedactionPolicy) -> Option<Value> {
+    let normalized = normalize_event_redacted(event, policy).expect("normalize");
+    normalized.document.entities().find_map(|(_, entity)| entity.attributes.get(a2a::ARGS).cloned())
+}
+
+#[tokio::test]
+async fn readers_get_payloads_redacted_by_role() {
+    let store = Arc::new(InMemoryProvenanceStore::new());
+    store.add_event(tool_call(json!({"email": "someone@example.com"}))).await.expect("add event");
+
+    let policy = ReadPolicy::default()
+        .with_role("auditor", Some(Arc::new(AttributeRedactor::new([a2a::METADATA]))));
+    let reader = RedactingReader::new(store.clone(), policy);
+
+    let full = json!({"email": "someone@example.com"});
+    assert_eq!(args_seen_by(&reader.for_role(PRIVILEGED_ROLE)).await, vec![full.clone()]);
+    assert_eq!(args_seen_by(&reader.for_role("auditor")).await, vec![full.clone()]);
+    assert_eq!(args_seen_by(&reader.for_role("restricted")).await, vec![json!(REDACTED)]);
+    assert_eq!(args_seen_by(&reader.for_role("unknown")).await, vec![json!(REDACTED)]);
+
+    // The store itself is untouched.
+    assert_eq!(args_seen_by(store.as_ref()).await, vec![full]);
+}
+
+#[test]
+fn path_rules_mask_hash_and_remove_fields() {
+    let event = tool_call(json!({
+        "user": {"email": "someone@example.com", "name": "Someone"},
+        "messages": [{"role": "user", "content": "hi"}, {"role": "user", "content": "bye"}],
+        "token": "secret",
+    }));
+    let policy = RedactionPolicy::new()
+        .with_rule(RedactionRule::path(a2a::ARGS, "$.user.email", RedactionAction::Hash).unwrap())
+        .with_rule(
+            RedactionRule::path(a2a::ARGS, "messages[*].content", RedactionAction::Mask).unwrap(),
+        )
+        .with_rule(RedactionRule::path(a2a::ARGS, "token", RedactionAction::Remove).unwrap());
+
+    let args = normalized_args(&event, &policy).expect("args");
+    let email = args["user"]["email"].as_str().expect("hashed email");
+    assert!(email.starts_with(HASH_PREFIX));
+    assert_eq!(args["user"]["name"], json!("Someone"));
+    assert_eq!(args["messages"][0]["content"], json!(REDACTED));
+    assert_eq!(args["messages"][1]["content"], json!(REDACTED));
+    assert!(args.get("token").is_none());
+
+    // Equal values hash equally, so they can still be correlated.
+    let again = normalized_args(&event, &policy).expect("args");
+    assert_eq!(again["user"]["email"], json!(email));
+}
+
+#[test]
+fn hashes_are_keyed_and_only_the_policys_own_digests_are_kept() {
+    let forged = format!("{HASH_PREFIX}{}", "0".repeat(64));
+    let event = tool_call(json!({"email": "someone@example.com", "forged": forged}));
+    let policy = |key: &str| {
+        RedactionPolicy::new()
+            .with_key(RedactionKey::new(key))
+            .with_rule(RedactionRule::path(a2a::ARGS, "email", RedactionAction::Hash).unwrap())
+            .with_rule(RedactionRule::path(a2a::ARGS, "forged", RedactionAction::Hash).unwrap())
+    };
+
+    let args = normalized_args(&event, &policy("key-1")).expect("args");
+    let email = args["email"].as_str().expect("hashed email");
+    assert!(email.starts_with(HASH_PREFIX));
+    assert!(args["forged"].as_str().is_some_and(|digest| digest.starts_with(HASH_PREFIX)));
+    assert_ne!(args["forged"], json!(forged), "a value shaped like a digest is still hashed");
+    assert_eq!(normalized_args(&event, &policy("key-1")).expect("args")["email"], json!(email));
+    assert_ne!(normalized_args(&event, &policy("key-2")).expect("args")["email"], json!(email));
+
+    // Redacting again, e.g. when a snapshot is restored, keeps the digests.
+    let tool_args = |event: &ProvEvent| match event.data() {
+        ProvEventData::ToolCallStarted { args, .. } => args.clone(),
+        other => panic!("unexpected event {other:?}"),
+    };
+    let mut redacted = event.clone();
+    policy("key-1").redact_event(&mut redacted);
+    let once = tool_args(&redacted);
+    policy("key-1").redact_event(&mut redacted);
+    assert_eq!(tool_args(&redacted), once);
+    assert_eq!(once["email"], json!(email));
+}
+
+#[test]
+fn pattern_rules_rewrite_matches_in_strings() {
+    let event = tool_call(json!({"note": "mail someone@example.com or other@example.org"}));
+    let policy = RedactionPolicy::new().with_rule(
+        RedactionRule::pattern(
+            Vec::<String>::new(),
+            r"[\w.+-]+@[\w-]+\.[\w.]+",
+            RedactionAction::Mask,
+        )
+        .unwrap(),
+    );
+
+    let args = normalized_args(&event, &policy).expect("args");
+    assert_eq!(args["note"], json!(format!("mail {REDACTED} or {REDACTED}")));
+}
+
+#[test]
+fn allow_rules_redact_every_other_payload_attribute() {
+    let event = tool_call(json!({"email": "someone@example.com"}));
+    let policy = RedactionPolicy::new()
+        .with_rule(RedactionRule::allow([a2a::METADATA], RedactionAction::Remove));
+
+    let normalized = normalize_event_redacted(&event, &policy).expect("normalize");
+    let attributes: Vec<_> = normalized
+        .document
+        .entities()
+        .flat_map(|(_, entity)| entity.attributes.keys().cloned())
+        .collect();
+    assert!(!attributes.iter().any(|key| key == a2a::ARGS));
+    assert!(attributes.iter().any(|key| key == a2a::EVENT_ID));
+}
+
+#[test]
+fn rules_scoped_to_other_event_types_are_skipped() {
+    let event = tool_call(json!({"email": "someone@example.com"}));
+    let policy = RedactionPolicy::new().with_rule(
+        RedactionRule::deny([a2a::ARGS], RedactionAction::Mask).for_events(["LlmCallStarted"]),
+    );
+    assert_eq!(normalized_args(&event, &policy), Some(json!({"email": "someone@example.com"})));
+
+    let policy = RedactionPolicy::new().with_rule(
+        RedactionRule::deny([a2a::ARGS], RedactionAction::Mask).for_events(["ToolCallStarted"]),
+    );
+    assert_eq!(normalized_args(&event, &policy), Some(json!(REDACTED)));
+}
+
+#[test]
+fn invalid_rules_are_rejected() {
+    assert!(matches!(
+        RedactionRule::path(a2a::ARGS, "messages[x]", RedactionAction::Mask),
+        Err(ProvenanceError::InvalidRedactionRule { .. })
+    ));
+    assert!(matches!(
+        RedactionRule::pattern([a2a::ARGS], "(", RedactionAction::Mask),
+        Err(ProvenanceError::InvalidRedactionRule { .. })
+    ));
+}
+
+#[tokio::test]
+async fn in_memory_store_applies_redaction_to_queries() {
+    let policy = RedactionPolicy::new()
+        .with_rule(RedactionRule::path(a2a::ARGS, "email", RedactionAction::Mask).unwrap());
+    let store = InMemoryProvenanceStore::new().with_redaction(policy);
+    store
+        .add_event(tool_call(json!({"email": "someone@example.com", "id": 7})))
+        .await
+        .expect("add event");
+
+    assert_eq!(args_seen_by(&store).await, vec![json!({"email": REDACTED, "id": 7})]);
+}
+
+#[tokio::test]
+async fn in_memory_store_redacts_recorded_events_and_snapshots() {
+    let policy = || {
+        RedactionPolicy::new()
+            .with_rule(RedactionRule::path(a2a::ARGS, "email", RedactionAction::Mask).unwrap())
+    };
+    let path = std::env::temp_dir()
+        .join(format!("baml-prov-redacted-{}", uuid::Uuid::new_v4()))
+        .join("prov.json");
+    let store = InMemoryProvenanceStore::new().with_redaction(policy());
+    store
+        .add_event(tool_call(json!({"email": "someone@example.com", "id": 7})))
+        .await
+        .expect("add event");
+
+    let recorded = serde_json::to_string(&store.events().await).unwrap();
+    assert!(!recorded.contains("someone@example.com"), "{recorded}");
+    store.save_snapshot(&path).await.expect("save snapshot");
+    let saved = std::fs::read_to_string(&path).unwrap();
+    assert!(!saved.contains("someone@example.com"), "{saved}");
+
+    let restored =
+        InMemoryProvenanceStore::load_snapshot(&path, Some(policy())).await.expect("load");
+    restored
+        .add_event(tool_call(json!({"email": "other@example.com", "id": 8})))
+        .await
+        .expect("add event");
+    let mut args = args_seen_by(&restored).await;
+    args.sort_by_key(|args| args["id"].as_i64());
+    assert_eq!(
+        args,
+        vec![json!({"email": REDACTED, "id": 7}), json!({"email": REDACTED, "id": 8})]
+    );
+    let _ = std::fs::remove_dir_all(path.parent().unwrap());
+}
@@ -0,0 +1,117 @@
+use async_trait::async_trait;
+use baml_rt_core::ids::{ContextId, ExternalId, MessageId};
+use baml_rt_provenance::error::Result as ProvResult;
+use baml_rt_provenance::{
+    decode_event_log, replay_file, replay_store, InMemoryProvenanceStore, ProvEvent,
+    ProvenanceError, ProvenanceWriter, ReplayOptions,
+};
+use serde_json::json;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Records the ids it receives and refuses events for one message.
+#[derive(Default)]
+struct RecordingWriter {
+    ids: Mutex<Vec<String>>,
+    reject_message: Option<String>,
+    flushed: AtomicBool,
+}
+
+#[async_trait]
+impl ProvenanceWriter for RecordingWriter {
+    async fn add_event(&self, event: ProvEvent) -> ProvResult<()> {
+        let payload = serde_json::to_string(&event).expect("serialize event");
+        if let Some(message) = &self.reject_message
+            && payload.contains(message.as_str())
+        {
+            return Err(ProvenanceError::InvalidEvent {
+                event_id: event.id().as_str().to_string(),
+                reason: "rejected by test writer".to_string(),
+            });
+        }
+        self.ids.lock().unwrap().push(event.id().as_str().to_string());
+        Ok(())
+    }
+
+    async fn flush(&self) -> ProvResult<()> {
+        self.flushed.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+fn tool_event(message: &str) -> ProvEvent {
+    ProvEvent::tool_call_started_global(
+        ContextId::new(1, 1),
+        MessageId::from_external(ExternalId::new(message)),
+        "tool".to_string(),
+        None,
+        json!({}),
+        json!({}),
+    )
+}
+
+#[tokio::test]
+async fn replays_store_events_in_issue_order() {
+    let store = InMemoryProvenanceStore::new();
+    let mut issued = Vec::new();
+    // Enough events that string order and issue order differ.
+    for index in 0..12 {
+        let event = tool_event(&format!("msg-{index}"));
+        issued.push(event.id().as_str().to_string());
+        store.add_event(event).await.expect("add event");
+    }
+
+    let target = RecordingWriter::default();
+    let report = replay_store(&store, &target, ReplayOptions::default()).await.expect("replay");
+
+    assert_eq!(report.replayed, 12);
+    assert!(report.skipped.is_empty());
+    assert_eq!(*target.ids.lock().unwrap(), issued);
+    assert!(target.flushed.load(Ordering::SeqCst));
+}
+
+#[tokio::test]
+async fn replays_json_lines_log_and_reports_rejected_events() {
+    let events = [tool_event("msg-keep"), tool_event("msg-bad"), tool_event("msg-also")];
+    let log: String = events
+        .iter()
+        .map(|event| serde_json::to_string(event).expect("serialize") + "\n")
+        .collect();
+    let path = std::env::temp_dir().join(format!("baml-prov-replay-{}.jsonl", uuid::Uuid::new_v4()));
+    std::fs::write(&path, &log).expect("write log");
+
+    let target = RecordingWriter { reject_message: Some("msg-bad".to_string()), ..Default::default() };
+    let report = replay_file(&path, &target, ReplayOptions::default()).await.expect("replay");
+    assert_eq!(report.replayed, 2);
+    assert_eq!(report.skipped.len(), 1);
+    assert_eq!(&report.skipped[0].event_id, events[1].id());
+
+    let strict = RecordingWriter { reject_message: Some("msg-bad".to_string()), ..Default::default() };
+    let err = replay_file(&path, &strict, ReplayOptions { fail_fast: true })
+        .await
+        .expect_err("fail fast");
+    assert!(matches!(err, ProvenanceError::InvalidEvent { .. }));
+    assert!(!strict.flushed.load(Ordering::SeqCst));
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn event_log_formats_decode_to_the_same_events() {
+    let events = vec![tool_event("msg-1"), tool_event("msg-2")];
+    let array = serde_json::to_vec(&events).expect("array");
+    let snapshot = serde_json::to_vec(&json!({"version": 1, "events": events})).expect("snapshot");
+
+    let ids = |bytes: &[u8]| -> Vec<String> {
+        decode_event_log(bytes)
+            .expect("decode")
+            .iter()
+            .map(|event| event.id().as_str().to_string())
+            .collect()
+    };
+    assert_eq!(ids(&array), ids(&snapshot));
+    assert_eq!(ids(&array).len(), 2);
+
+    let err = decode_event_log(b"{\"not\": \"an event\"}\n").expect_err("bad line");
+    assert!(err.to_string().contains("provenance storage error"));
+}
@@ -0,0 +1,91 @@
+use baml_rt_provenance::sqlite_schema::{schema_version, SCHEMA_VERSION};
+use baml_rt_provenance::{
+    InMemoryProvenanceStore, ProvEvent, ProvenanceQuery, ProvenanceReader, ProvenanceWriter,
+    SqliteProvenanceConfig, SqliteProvenanceWriter,
+};
+use baml_rt_core::ids::{ContextId, ExternalId, MessageId, TaskId};
+use serde_json::json;
+
+fn sample_events(task_id: &TaskId) -> Vec<ProvEvent> {
+    vec![
+        ProvEvent::tool_call_started_task(
+            ContextId::new(1, 1),
+            task_id.clone(),
+            "tool".to_string(),
+            None,
+            json!({"input": "value"}),
+            json!({}),
+        ),
+        ProvEvent::tool_call_started_global(
+            ContextId::new(1, 1),
+            MessageId::from_external(ExternalId::new("msg-1")),
+            "tool".to_string(),
+            None,
+            json!({}),
+            json!({}),
+        ),
+    ]
+}
+
+fn node_ids(records: &[baml_rt_provenance::ProvNodeRecord]) -> Vec<String> {
+    records.iter().map(|record| record.id.clone()).collect()
+}
+
+#[tokio::test]
+async fn sqlite_writer_persists_events_across_reopen() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("prov/provenance.db");
+    let task_id = TaskId::from_external(ExternalId::new("task-1"));
+    let events = sample_events(&task_id);
+
+    let writer = SqliteProvenanceWriter::open(SqliteProvenanceConfig::new(&path)).expect("open");
+    writer.add_events(events.clone()).await.expect("add events");
+    writer.health_check().await.expect("healthy");
+    drop(writer);
+
+    let reopened = SqliteProvenanceWriter::open(SqliteProvenanceConfig::new(&path)).expect("reopen");
+    let stored = reopened.events().await.expect("events");
+    let ids = |events: &[ProvEvent]| events.iter().map(|event| event.id().clone()).collect::<Vec<_>>();
+    assert_eq!(ids(&stored), ids(&events));
+
+    let conn = rusqlite::Connection::open(&path).expect("raw connection");
+    assert_eq!(schema_version(&conn).expect("schema version"), SCHEMA_VERSION);
+}
+
+#[tokio::test]
+async fn sqlite_reader_matches_in_memory_reader() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let task_id = TaskId::from_external(ExternalId::new("task-1"));
+    let events = sample_events(&task_id);
+
+    let sqlite = SqliteProvenanceWriter::open(SqliteProvenanceConfig::new(
+        dir.path().join("provenance.db"),
+    ))
+    .expect("open");
+    let memory = InMemoryProvenanceStore::new();
+    for event in events {
+        sqlite.add_event(event.clone()).await.expect("sqlite add");
+        // A repeated event id is ignored rather than merged twice.
+        sqlite.add_event(event.clone()).await.expect("sqlite re-add");
+        memory.add_event(event).await.expect("memory add");
+    }
+    assert_eq!(sqlite.events().await.expect("events").len(), 2);
+
+    let queries = [
+        ProvenanceQuery::default(),
+        ProvenanceQuery::default().for_task(task_id.clone()),
+        ProvenanceQuery::default().for_context(ContextId::new(1, 1)),
+        ProvenanceQuery::default().for_task(task_id).between(Some(u64::MAX - 1), None),
+    ];
+    for query in &queries {
+        let from_sqlite = sqlite.query_nodes(query).await.expect("sqlite query");
+        let from_memory = memory.query_nodes(query).await.expect("memory query");
+        assert_eq!(node_ids(&from_sqlite), node_ids(&from_memory), "{query:?}");
+    }
+
+    let history = sqlite
+        .task_history(&TaskId::from_external(ExternalId::new("task-1")))
+        .await
+        .expect("task history");
+    assert!(history.iter().any(|node| node.attributes.contains_key("a2a:tool_name")));
+}
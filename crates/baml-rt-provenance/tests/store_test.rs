@@ -1,7 +1,16 @@
-use baml_rt_provenance::{normalize_event, InMemoryProvenanceStore, ProvEvent, ProvenanceWriter};
-use baml_rt_core::ids::{ContextId, ExternalId, MessageId};
+use async_trait::async_trait;
+use baml_rt_provenance::error::Result as ProvResult;
+use baml_rt_provenance::{
+    normalize_event, wait_until_healthy, HealthStatus, InMemoryProvenanceStore, ProvEvent,
+    ProvNodeKind, ProvenanceError, ProvenanceHealthMonitor, ProvenanceQuery, ProvenanceReader,
+    ProvenanceWriter,
+};
+use baml_rt_core::ids::{ContextId, ExternalId, MessageId, TaskId};
 use serde_json::{json, Value};
 use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 
 #[tokio::test]
 async fn test_in_memory_store_adds_events() {
@@ -214,3 +223,150 @@ fn sort_value(value: Value) -> Value {
         other => other,
     }
 }
+
+/// Writer whose health check fails until it has been probed `failures` times.
+struct FlakyWriter {
+    failures: u32,
+    probes: AtomicU32,
+}
+
+#[async_trait]
+impl ProvenanceWriter for FlakyWriter {
+    async fn add_event(&self, _event: ProvEvent) -> ProvResult<()> {
+        Ok(())
+    }
+
+    async fn health_check(&self) -> ProvResult<()> {
+        let probe = self.probes.fetch_add(1, Ordering::SeqCst);
+        if probe < self.failures {
+            return Err(ProvenanceError::Storage("connection refused".into()));
+        }
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_wait_until_healthy_retries_then_gives_up() {
+    let writer = FlakyWriter { failures: 2, probes: AtomicU32::new(0) };
+    wait_until_healthy(&writer, 3, Duration::from_millis(1))
+        .await
+        .expect("third probe succeeds");
+
+    let writer = FlakyWriter { failures: 5, probes: AtomicU32::new(0) };
+    let err = wait_until_healthy(&writer, 2, Duration::from_millis(1))
+        .await
+        .expect_err("store never becomes healthy");
+    assert!(matches!(err, ProvenanceError::Storage(_)));
+    assert_eq!(writer.probes.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_health_monitor_reports_readiness() {
+    let monitor = ProvenanceHealthMonitor::spawn(
+        Arc::new(InMemoryProvenanceStore::new()),
+        Duration::from_millis(5),
+    );
+    for _ in 0..100 {
+        if monitor.is_ready().await {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    assert_eq!(monitor.status().await, HealthStatus::Healthy);
+}
+
+#[tokio::test]
+async fn test_health_monitor_reports_the_underlying_cause() {
+    let writer = Arc::new(FlakyWriter { failures: u32::MAX, probes: AtomicU32::new(0) });
+    let monitor = ProvenanceHealthMonitor::spawn(writer, Duration::from_millis(5));
+    for _ in 0..100 {
+        if monitor.status().await != HealthStatus::Unknown {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    assert_eq!(
+        monitor.status().await,
+        HealthStatus::Unhealthy("provenance storage error: connection refused".to_string())
+    );
+}
+
+#[tokio::test]
+async fn test_in_memory_snapshot_round_trip() {
+    let dir = std::env::temp_dir().join(format!("baml-prov-snapshot-{}", uuid::Uuid::new_v4()));
+    let path = dir.join("prov.json");
+
+    let empty = InMemoryProvenanceStore::load_snapshot(&path, None).await.expect("missing file is empty");
+    assert!(empty.is_empty().await);
+
+    let store = InMemoryProvenanceStore::new();
+    let event = ProvEvent::tool_call_started_global(
+        ContextId::new(1, 1),
+        MessageId::from_external(ExternalId::new("msg-1")),
+        "tool".to_string(),
+        None,
+        json!({"input": "value"}),
+        json!({}),
+    );
+    let saved_id = event.id().clone();
+    store.add_event(event).await.expect("add event");
+    store.save_snapshot(&path).await.expect("save snapshot");
+
+    let restored = InMemoryProvenanceStore::load_snapshot(&path, None).await.expect("load snapshot");
+    let events = restored.events().await;
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].id(), &saved_id);
+
+    let next = ProvEvent::tool_call_started_global(
+        ContextId::new(1, 1),
+        MessageId::from_external(ExternalId::new("msg-2")),
+        "tool".to_string(),
+        None,
+        json!({}),
+        json!({}),
+    );
+    assert_ne!(next.id(), &saved_id, "restored ids must not be reissued");
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[tokio::test]
+async fn test_in_memory_reader_filters_by_task_and_time() {
+    let store = InMemoryProvenanceStore::new();
+    let task_id = TaskId::from_external(ExternalId::new("task-1"));
+    store
+        .add_event(ProvEvent::tool_call_started_task(
+            ContextId::new(1, 1),
+            task_id.clone(),
+            "tool".to_string(),
+            None,
+            json!({}),
+            json!({}),
+        ))
+        .await
+        .expect("add task event");
+    store
+        .add_event(ProvEvent::tool_call_started_global(
+            ContextId::new(1, 1),
+            MessageId::from_external(ExternalId::new("msg-1")),
+            "tool".to_string(),
+            None,
+            json!({}),
+            json!({}),
+        ))
+        .await
+        .expect("add global event");
+
+    let history = store.task_history(&task_id).await.expect("task history");
+    assert!(!history.is_empty());
+    assert!(history.iter().all(|node| {
+        node.attributes.get("a2a:task_id").and_then(Value::as_str) == Some(task_id.as_str())
+    }));
+    assert!(history.iter().any(|node| node.kind == ProvNodeKind::Activity));
+
+    let all = store.query_nodes(&ProvenanceQuery::default()).await.expect("all nodes");
+    assert!(all.len() > history.len());
+
+    let future = ProvenanceQuery::default().for_task(task_id).between(Some(u64::MAX - 1), None);
+    assert!(store.query_nodes(&future).await.expect("future range").is_empty());
+}
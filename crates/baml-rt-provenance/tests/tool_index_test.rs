@@ -1,6 +1,9 @@
-use baml_rt_provenance::{ToolIndexConfig, index_tools};
+use baml_rt_provenance::{
+    detect_drift, metadata_digest, FileToolIndexer, IndexedTool, ToolIndexConfig, ToolIndexSource,
+    ToolIndexer, index_tools,
+};
 use baml_rt_tools::{ToolFunctionMetadataExport, ToolName, ToolSecretRequirement, ToolTypeSpec};
-use serde_json::json;
+use serde_json::{json, Value};
 use testcontainers::core::ContainerPort;
 use testcontainers::runners::AsyncRunner;
 use testcontainers::GenericImage;
@@ -77,7 +80,8 @@ async fn tool_index_creates_nodes_and_fulltext() {
     }];
 
     let config = ToolIndexConfig::new(connection.clone(), graph);
-    index_tools(&config, &tools).await.expect("index tools");
+    let source = ToolIndexSource::new("support-agent", "sig-1");
+    index_tools(&config, &source, &tools).await.expect("index tools");
 
     let node_count = execute_cypher_query(
         "MATCH (t:ToolFunction {name: \"support/get_weather\"}) RETURN COUNT(t)",
@@ -111,3 +115,102 @@ async fn tool_index_creates_nodes_and_fulltext() {
         "expected fulltext search to find tool node, got: {search_count}"
     );
 }
+
+fn catalog_tool(name: &str, description: &str) -> ToolFunctionMetadataExport {
+    let type_spec = |name: &str| ToolTypeSpec { name: name.to_string(), ts_decl: None };
+    ToolFunctionMetadataExport {
+        name: ToolName::parse(name).expect("valid tool name"),
+        class_name: "CatalogTool".to_string(),
+        description: description.to_string(),
+        open_input_schema: json!({ "type": "object" }),
+        input_schema: json!({ "type": "object" }),
+        output_schema: json!({ "type": "object" }),
+        open_input_type: type_spec("CatalogOpenInput"),
+        input_type: type_spec("CatalogInput"),
+        output_type: type_spec("CatalogOutput"),
+        tags: vec![],
+        secret_requirements: vec![],
+        is_host_tool: false,
+    }
+}
+
+#[tokio::test]
+async fn file_tool_indexer_replaces_previous_package_version() {
+    let dir = std::env::temp_dir().join(format!("baml-tool-index-{}", uuid::Uuid::new_v4()));
+    let indexer = FileToolIndexer::new(dir.join("tools.json"));
+    let v1 = ToolIndexSource::new("support-agent", "sig-1");
+    let v2 = ToolIndexSource::new("support-agent", "sig-2");
+    let other = ToolIndexSource::new("math-agent", "sig-9");
+
+    indexer
+        .index(&v1, &[catalog_tool("support/get_weather", "v1"), catalog_tool("support/legacy", "old")])
+        .await
+        .expect("index v1");
+    indexer
+        .index(&other, &[catalog_tool("math/calculate", "math")])
+        .await
+        .expect("index other package");
+    indexer
+        .index(&v2, &[catalog_tool("support/get_weather", "v2")])
+        .await
+        .expect("index v2");
+
+    let indexed = indexer.indexed("support-agent").await.expect("list support entries");
+    assert_eq!(indexed.len(), 1);
+    assert_eq!(indexed[0].name, "support/get_weather");
+    assert_eq!(indexed[0].version, "sig-2");
+    assert_eq!(indexer.indexed("math-agent").await.expect("list math entries").len(), 1);
+
+    let catalog: Value =
+        serde_json::from_slice(&std::fs::read(indexer.path()).expect("read catalog")).expect("json");
+    assert_eq!(catalog["support-agent:support/get_weather"]["metadata"]["description"], "v2");
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[tokio::test]
+async fn file_tool_indexer_keeps_same_named_tools_of_different_packages() {
+    let dir = std::env::temp_dir().join(format!("baml-tool-index-{}", uuid::Uuid::new_v4()));
+    let indexer = FileToolIndexer::new(dir.join("tools.json"));
+    let support = ToolIndexSource::new("support-agent", "sig-1");
+    let billing = ToolIndexSource::new("billing-agent", "sig-1");
+
+    indexer.index(&support, &[catalog_tool("shared/lookup", "support")]).await.expect("index");
+    indexer.index(&billing, &[catalog_tool("shared/lookup", "billing")]).await.expect("index");
+    indexer.index(&support, &[catalog_tool("shared/lookup", "support")]).await.expect("reindex");
+
+    let support_entries = indexer.indexed("support-agent").await.expect("list support entries");
+    let billing_entries = indexer.indexed("billing-agent").await.expect("list billing entries");
+    assert_eq!(support_entries.len(), 1);
+    assert_eq!(billing_entries.len(), 1);
+    assert_ne!(support_entries[0].digest, billing_entries[0].digest);
+    assert!(detect_drift(&billing, &[catalog_tool("shared/lookup", "billing")], &billing_entries)
+        .is_clean());
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn detect_drift_reports_missing_stale_and_changed_tools() {
+    let source = ToolIndexSource::new("support-agent", "sig-2");
+    let weather = catalog_tool("support/get_weather", "v2");
+    let live = vec![weather.clone(), catalog_tool("support/new_tool", "new")];
+    let entry = |name: &str, version: &str, digest: String| IndexedTool {
+        name: name.to_string(),
+        package: "support-agent".to_string(),
+        version: version.to_string(),
+        digest,
+    };
+    let indexed = vec![
+        entry("support/get_weather", "sig-2", metadata_digest(&catalog_tool("support/get_weather", "v1"))),
+        entry("support/removed", "sig-1", "0".repeat(16)),
+    ];
+
+    let drift = detect_drift(&source, &live, &indexed);
+    assert_eq!(drift.missing, vec!["support/new_tool".to_string()]);
+    assert_eq!(drift.stale, vec!["support/removed".to_string()]);
+    assert_eq!(drift.changed, vec!["support/get_weather".to_string()]);
+
+    let indexed = vec![entry("support/get_weather", "sig-2", metadata_digest(&weather))];
+    assert!(detect_drift(&source, &[weather], &indexed).is_clean());
+}
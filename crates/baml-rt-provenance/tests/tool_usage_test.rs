@@ -0,0 +1,90 @@
+use baml_rt_core::context;
+use baml_rt_core::ids::{AgentId, ContextId, UuidId};
+use baml_rt_core::BamlRtError;
+use baml_rt_interceptor::{ToolCallContext, ToolInterceptor};
+use baml_rt_provenance::schema::validate_document;
+use baml_rt_provenance::vocabulary::{a2a, a2a_types};
+use baml_rt_provenance::{
+    normalize_event, InMemoryProvenanceStore, ProvEventData, ProvenanceWriter, ToolUsageAggregator,
+};
+use serde_json::json;
+use std::sync::Arc;
+
+fn agent(n: u32) -> AgentId {
+    AgentId::from_uuid(UuidId::parse_str(&format!("00000000-0000-0000-0000-{n:012}")).unwrap())
+}
+
+fn tool_call(tool_name: &str) -> ToolCallContext {
+    ToolCallContext {
+        tool_name: tool_name.to_string(),
+        function_name: None,
+        args: json!({}),
+        context_id: ContextId::new(1, 1),
+        metadata: json!({"message_id": "msg-1"}),
+    }
+}
+
+#[tokio::test]
+async fn tool_calls_are_counted_per_agent_and_tool() {
+    let aggregator = ToolUsageAggregator::new();
+    let search = tool_call("web/search");
+    context::with_agent_id(agent(1), async {
+        for duration_ms in 1..=100 {
+            let result = if duration_ms % 10 == 0 {
+                Err(BamlRtError::ToolExecution("timeout".to_string()))
+            } else {
+                Ok(json!({}))
+            };
+            aggregator.on_tool_call_complete(&search, &result, duration_ms).await;
+        }
+    })
+    .await;
+    context::with_agent_id(agent(2), async {
+        aggregator.on_tool_call_complete(&search, &Ok(json!({})), 7).await;
+    })
+    .await;
+    // Outside an agent scope nothing is counted.
+    aggregator.on_tool_call_complete(&search, &Ok(json!({})), 7).await;
+
+    let usage = aggregator.snapshot();
+    assert_eq!(usage.len(), 2);
+    assert_eq!(usage[0].agent_id, agent(1));
+    assert_eq!(usage[0].invocations, 100);
+    assert_eq!(usage[0].failures, 10);
+    assert_eq!(usage[0].failure_rate(), 0.1);
+    assert_eq!((usage[0].p50_ms, usage[0].p95_ms, usage[0].p99_ms), (50, 95, 99));
+    assert_eq!(usage[1].invocations, 1);
+    assert_eq!(usage[1].p99_ms, 7);
+}
+
+#[tokio::test]
+async fn flush_writes_only_pairs_that_changed() {
+    let store = Arc::new(InMemoryProvenanceStore::new());
+    let writer: Arc<dyn ProvenanceWriter> = store.clone();
+    let aggregator = ToolUsageAggregator::new();
+    aggregator.record(&agent(1), "web/search", 20, true);
+    aggregator.record(&agent(1), "math/sum", 3, false);
+
+    assert_eq!(aggregator.flush(writer.as_ref()).await, 2);
+    assert_eq!(aggregator.flush(writer.as_ref()).await, 0);
+    aggregator.record(&agent(1), "math/sum", 5, true);
+    assert_eq!(aggregator.flush(writer.as_ref()).await, 1);
+
+    let events = store.events().await;
+    let last = events.last().expect("summary event");
+    let ProvEventData::ToolUsageSummarized { tool_name, invocations, failures, .. } = last.data() else {
+        panic!("expected a tool usage summary, got {:?}", last.data());
+    };
+    assert_eq!((tool_name.as_str(), *invocations, *failures), ("math/sum", 2, 1));
+
+    let normalized = normalize_event(last).expect("normalize");
+    validate_document(&normalized.document).expect("schema-valid document");
+    let (id, usage) = normalized
+        .document
+        .entities()
+        .find(|(_, entity)| entity.prov_type.as_deref() == Some(a2a_types::TOOL_USAGE))
+        .expect("tool usage entity");
+    assert_eq!(id.as_str(), format!("tool_usage:{}:math/sum", agent(1).as_str()));
+    assert_eq!(usage.attributes[a2a::TOOL_FAILURE_RATE], json!(0.5));
+    assert_eq!(usage.attributes[a2a::TOOL_LATENCY_P99_MS], json!(5));
+}
@@ -0,0 +1,114 @@
+use baml_rt_provenance::{ProvDocument, ProvNodeRef};
+use serde_json::json;
+
+/// A summary derived from a draft, which was derived from a prompt that (to
+/// close a cycle) is recorded as derived from the summary. The draft was
+/// generated twice, by the draft call first.
+fn document() -> ProvDocument {
+    ProvDocument::from_prov_json(&json!({
+        "entity": {
+            "baml:prompt:1": {},
+            "baml:draft:1": {},
+            "baml:summary:1": {},
+            "baml:config:1": {}
+        },
+        "activity": {
+            "baml:call:draft": {},
+            "baml:call:summarize": {}
+        },
+        "agent": {
+            "baml:agent:runner": {},
+            "baml:agent:reviewer": {}
+        },
+        "used": {
+            "_:u1": { "prov:activity": "baml:call:summarize", "prov:entity": "baml:draft:1", "prov:role": "input" },
+            "_:u2": { "prov:activity": "baml:call:summarize", "prov:entity": "baml:config:1", "prov:role": "config" },
+            "_:u3": { "prov:activity": "baml:call:draft", "prov:entity": "baml:prompt:1", "prov:role": "input" }
+        },
+        "wasGeneratedBy": {
+            "_:g1": { "prov:entity": "baml:summary:1", "prov:activity": "baml:call:summarize" },
+            "_:g2": { "prov:entity": "baml:draft:1", "prov:activity": "baml:call:draft", "prov:time": "2024-01-01T00:00:01.000Z" },
+            "_:g3": { "prov:entity": "baml:draft:1", "prov:activity": "baml:call:summarize", "prov:time": "2024-01-01T00:00:05.000Z" }
+        },
+        "wasAssociatedWith": {
+            "_:a1": { "prov:activity": "baml:call:summarize", "prov:agent": "baml:agent:runner", "prov:role": "executor" },
+            "_:a2": { "prov:activity": "baml:call:summarize", "prov:agent": "baml:agent:reviewer", "prov:role": "reviewer" },
+            "_:a3": { "prov:activity": "baml:call:draft", "prov:agent": "baml:agent:runner", "prov:role": "executor" }
+        },
+        "wasDerivedFrom": {
+            "_:d1": { "prov:generatedEntity": "baml:summary:1", "prov:usedEntity": "baml:draft:1" },
+            "_:d2": { "prov:generatedEntity": "baml:draft:1", "prov:usedEntity": "baml:prompt:1" },
+            "_:d3": { "prov:generatedEntity": "baml:prompt:1", "prov:usedEntity": "baml:summary:1" }
+        }
+    }))
+    .expect("parse PROV-JSON")
+}
+
+fn ids<T: AsRef<str>>(refs: Vec<&T>) -> Vec<&str> {
+    refs.into_iter().map(|id| id.as_ref()).collect()
+}
+
+#[test]
+fn node_refs_resolve_to_their_kind() {
+    let document = document();
+    let summary = document.node_ref("summary:1").expect("entity");
+    assert!(summary.as_entity().is_some());
+    let call = document.node_ref("call:summarize").expect("activity");
+    assert_eq!(call.as_activity().map(|id| id.as_str()), Some("call:summarize"));
+    assert!(matches!(document.node_ref("agent:runner"), Some(ProvNodeRef::Agent(_))));
+    assert!(document.node_ref("missing").is_none());
+}
+
+#[test]
+fn generation_and_usage_are_traversed_both_ways() {
+    let document = document();
+    let summary = document.node_ref("summary:1").unwrap();
+    let draft = document.node_ref("draft:1").unwrap();
+    let call = document.node_ref("call:summarize").unwrap();
+    let call = call.as_activity().unwrap();
+
+    assert_eq!(document.generated_by(&summary).map(|id| id.as_str()), Some("call:summarize"));
+    // The earliest of several generations wins.
+    assert_eq!(document.generated_by(&draft).map(|id| id.as_str()), Some("call:draft"));
+    let generated: Vec<&str> = document.generated(call).into_iter().map(ProvNodeRef::id).collect();
+    assert_eq!(generated, vec!["draft:1", "summary:1"]);
+
+    assert_eq!(ids(document.used_entities(call)), vec!["config:1", "draft:1"]);
+    assert_eq!(ids(document.used_entities_in_role(call, "input")), vec!["draft:1"]);
+    let draft = draft.as_entity().unwrap();
+    assert_eq!(ids(document.users_of(draft)), vec!["call:summarize"]);
+}
+
+#[test]
+fn associations_are_listed_with_roles() {
+    let document = document();
+    let call = document.node_ref("call:summarize").unwrap();
+    let agents: Vec<(&str, Option<&str>)> = document
+        .associated_agents(call.as_activity().unwrap())
+        .into_iter()
+        .map(|(agent, role)| (agent.as_str(), role))
+        .collect();
+    assert_eq!(agents, vec![("agent:reviewer", Some("reviewer")), ("agent:runner", Some("executor"))]);
+
+    let runner = document.node_ref("agent:runner").unwrap();
+    assert_eq!(
+        ids(document.activities_of(runner.as_agent().unwrap())),
+        vec!["call:draft", "call:summarize"]
+    );
+    let executors: Vec<&str> = document
+        .associations_in_role("executor")
+        .into_iter()
+        .map(|association| association.activity.as_str())
+        .collect();
+    assert_eq!(executors, vec!["call:draft", "call:summarize"]);
+}
+
+#[test]
+fn derivation_ancestors_are_nearest_first_and_stop_at_cycles() {
+    let document = document();
+    let summary = document.node_ref("summary:1").unwrap();
+    let summary = summary.as_entity().unwrap();
+    assert_eq!(ids(document.derived_from(summary)), vec!["draft:1"]);
+    assert_eq!(ids(document.derivations_of(summary)), vec!["prompt:1"]);
+    assert_eq!(ids(document.derivation_ancestors(summary)), vec!["draft:1", "prompt:1"]);
+}
@@ -0,0 +1,219 @@
+//! Introspection of the work a runtime has in progress
+//!
+//! [`WorkTracker`] records BAML functions while they execute, tool sessions
+//! while they are open and LLM calls while they are in flight. Its snapshot,
+//! [`ActiveWork`], shows what a hung agent is stuck on. Taking one never waits
+//! on the runtime manager's lock, which a running BAML call holds, so it can be
+//! read from the outside while the agent is wedged.
+
+use baml_rt_core::context;
+use baml_rt_core::ids::{ContextId, TaskId};
+use baml_rt_interceptor::LLMCallContext;
+use baml_rt_tools::ToolSessionId;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// A BAML function that is executing
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveFunction {
+    pub function_name: String,
+    pub streaming: bool,
+    pub context_id: Option<ContextId>,
+    pub task_id: Option<TaskId>,
+    pub age_ms: u64,
+}
+
+/// A tool session that has been opened and not yet finished
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenToolSession {
+    pub session_id: String,
+    pub tool_name: String,
+    pub context_id: Option<ContextId>,
+    pub task_id: Option<TaskId>,
+    pub age_ms: u64,
+}
+
+/// An LLM call that has been allowed and not yet completed
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InFlightLlmCall {
+    pub function_name: String,
+    pub client: String,
+    pub model: String,
+    pub context_id: Option<ContextId>,
+    pub task_id: Option<TaskId>,
+    pub age_ms: u64,
+}
+
+/// Everything a runtime has in progress, oldest first in each list
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveWork {
+    pub functions: Vec<ActiveFunction>,
+    pub tool_sessions: Vec<OpenToolSession>,
+    pub llm_calls: Vec<InFlightLlmCall>,
+    /// Provenance events accepted but not yet written; `None` when no writer is known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queued_provenance_events: Option<usize>,
+}
+
+impl ActiveWork {
+    pub fn with_queued_provenance_events(mut self, queued: usize) -> Self {
+        self.queued_provenance_events = Some(queued);
+        self
+    }
+
+    /// Nothing is executing, open or in flight
+    pub fn is_idle(&self) -> bool {
+        self.functions.is_empty() && self.tool_sessions.is_empty() && self.llm_calls.is_empty()
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Work {
+    Function { function_name: String, streaming: bool },
+    LlmCall { function_name: String, client: String, model: String },
+}
+
+#[derive(Debug, Clone)]
+struct Started<T> {
+    work: T,
+    context_id: Option<ContextId>,
+    task_id: Option<TaskId>,
+    at: Instant,
+}
+
+impl<T> Started<T> {
+    fn now(work: T, context_id: Option<ContextId>) -> Self {
+        Self {
+            work,
+            context_id: context_id.or_else(context::current_context_id),
+            task_id: context::current_task_id(),
+            at: Instant::now(),
+        }
+    }
+
+    fn age_ms(&self, now: Instant) -> u64 {
+        now.saturating_duration_since(self.at).as_millis() as u64
+    }
+}
+
+#[derive(Debug, Default)]
+struct Ledger {
+    next_id: u64,
+    running: HashMap<u64, Started<Work>>,
+    tool_sessions: HashMap<ToolSessionId, Started<String>>,
+}
+
+/// Records the work a runtime has in progress
+///
+/// Clones share their records.
+#[derive(Debug, Clone, Default)]
+pub struct WorkTracker {
+    ledger: Arc<Mutex<Ledger>>,
+}
+
+impl WorkTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a BAML function as executing until the guard is dropped
+    pub fn begin_function(&self, function_name: &str, streaming: bool) -> WorkGuard {
+        let work = Work::Function { function_name: function_name.to_string(), streaming };
+        self.begin(Started::now(work, None))
+    }
+
+    /// Record an LLM call as in flight until the guard is dropped
+    pub fn begin_llm_call(&self, call: &LLMCallContext) -> WorkGuard {
+        let work = Work::LlmCall {
+            function_name: call.function_name.clone(),
+            client: call.client.clone(),
+            model: call.model.clone(),
+        };
+        self.begin(Started::now(work, Some(call.context_id.clone())))
+    }
+
+    pub fn open_tool_session(&self, session_id: &ToolSessionId, tool_name: &str) {
+        let started = Started::now(tool_name.to_string(), None);
+        self.ledger.lock().unwrap().tool_sessions.insert(session_id.clone(), started);
+    }
+
+    pub fn close_tool_session(&self, session_id: &ToolSessionId) {
+        self.ledger.lock().unwrap().tool_sessions.remove(session_id);
+    }
+
+    pub fn snapshot(&self) -> ActiveWork {
+        let now = Instant::now();
+        let ledger = self.ledger.lock().unwrap();
+        let mut running: Vec<&Started<Work>> = ledger.running.values().collect();
+        running.sort_by_key(|started| started.at);
+        let mut active = ActiveWork::default();
+        for started in running {
+            let context_id = started.context_id.clone();
+            let task_id = started.task_id.clone();
+            let age_ms = started.age_ms(now);
+            match &started.work {
+                Work::Function { function_name, streaming } => {
+                    active.functions.push(ActiveFunction {
+                        function_name: function_name.clone(),
+                        streaming: *streaming,
+                        context_id,
+                        task_id,
+                        age_ms,
+                    })
+                }
+                Work::LlmCall { function_name, client, model } => {
+                    active.llm_calls.push(InFlightLlmCall {
+                        function_name: function_name.clone(),
+                        client: client.clone(),
+                        model: model.clone(),
+                        context_id,
+                        task_id,
+                        age_ms,
+                    })
+                }
+            }
+        }
+        let mut sessions: Vec<(&ToolSessionId, &Started<String>)> =
+            ledger.tool_sessions.iter().collect();
+        sessions.sort_by_key(|(_, started)| started.at);
+        active.tool_sessions = sessions
+            .into_iter()
+            .map(|(session_id, started)| OpenToolSession {
+                session_id: session_id.as_str().to_string(),
+                tool_name: started.work.clone(),
+                context_id: started.context_id.clone(),
+                task_id: started.task_id.clone(),
+                age_ms: started.age_ms(now),
+            })
+            .collect();
+        active
+    }
+
+    fn begin(&self, started: Started<Work>) -> WorkGuard {
+        let mut ledger = self.ledger.lock().unwrap();
+        let id = ledger.next_id;
+        ledger.next_id += 1;
+        ledger.running.insert(id, started);
+        WorkGuard { tracker: self.clone(), id }
+    }
+}
+
+/// Keeps a function or LLM call listed as active; dropping it ends the record
+#[must_use = "the work is no longer listed once the guard is dropped"]
+#[derive(Debug)]
+pub struct WorkGuard {
+    tracker: WorkTracker,
+    id: u64,
+}
+
+impl Drop for WorkGuard {
+    fn drop(&mut self) {
+        self.tracker.ledger.lock().unwrap().running.remove(&self.id);
+    }
+}
@@ -1,19 +1,28 @@
 //! BAML runtime wrapper and function execution
 
+use crate::active_work::{ActiveWork, WorkTracker};
 use crate::baml_execution::BamlExecutor;
-use baml_rt_core::{BamlRtError, Result};
-use baml_rt_core::types::FunctionSignature;
-use baml_rt_tools::{ToolRegistry as ConcreteToolRegistry, ToolFunctionMetadataExport, ToolSessionId, ToolStep};
+use crate::baml_stream::LLMStreamMonitor;
+use baml_rt_core::{BamlRtError, ErrorContext, Result, ResultExt};
+use baml_rt_core::types::{BamlType, FunctionSchema, FunctionSignature, OutputRepair};
+use baml_rt_tools::{
+    RetryPolicy, ToolRegistry as ConcreteToolRegistry, ToolFunctionMetadataExport, ToolSessionId,
+    ToolStep,
+};
 use crate::traits::{BamlFunctionExecutor, SchemaLoader};
-use baml_rt_interceptor::{InterceptorRegistry, ToolCallContext};
+use baml_rt_interceptor::{InterceptorRegistry, ToolCallContext, ToolCallRetry, RETRY_METADATA_KEY};
+use baml_rt_core::tokens::TokenizerRegistry;
 use baml_rt_core::correlation::current_correlation_id;
 use baml_rt_core::context;
-use baml_rt_observability::metrics;
+use baml_rt_observability::{metrics, spans};
 use async_trait::async_trait;
 use serde_json::Value;
+use serde_json_path::JsonPath;
 use std::collections::{HashMap, HashSet};
+use std::future::Future;
 use std::path::Path;
 use std::fs;
+use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Instant;
 use tokio::sync::Mutex as TokioMutex;
@@ -30,6 +39,34 @@ pub struct BamlRuntimeManager {
     interceptor_registry: Arc<TokioMutex<InterceptorRegistry>>,
     tool_session_scopes: Arc<TokioMutex<HashMap<ToolSessionId, ToolSessionScope>>>,
     tool_session_states: Arc<TokioMutex<HashMap<ToolSessionId, ToolCallSessionState>>>,
+    tool_retry_policy: Option<RetryPolicy>,
+    tokenizers: Arc<TokenizerRegistry>,
+    output_validation: bool,
+    output_repair: Option<OutputRepair>,
+    work: WorkTracker,
+    /// Digest of the `.baml` sources loaded, for [`Self::reload_schema`].
+    schema_digest: Option<String>,
+}
+
+/// What [`BamlRuntimeManager::reload_schema`] changed. Function lists are
+/// sorted by name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct SchemaReload {
+    /// Digest of the `.baml` sources now loaded.
+    pub digest: String,
+    /// Digest of the sources replaced; `None` when no schema was loaded.
+    pub previous_digest: Option<String>,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Functions whose parameters or output type changed.
+    pub changed: Vec<String>,
+}
+
+impl SchemaReload {
+    /// Whether the sources were unchanged, so nothing was reloaded.
+    pub fn is_unchanged(&self) -> bool {
+        self.previous_digest.as_deref() == Some(self.digest.as_str())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -49,14 +86,7 @@ impl BamlRuntimeManager {
     pub fn new() -> Result<Self> {
         tracing::info!("Initializing BAML runtime manager");
 
-        Ok(Self {
-            function_registry: HashMap::new(),
-            executor: None,
-            tool_registry: Arc::new(TokioMutex::new(ConcreteToolRegistry::new())),
-            interceptor_registry: Arc::new(TokioMutex::new(InterceptorRegistry::new())),
-            tool_session_scopes: Arc::new(TokioMutex::new(HashMap::new())),
-            tool_session_states: Arc::new(TokioMutex::new(HashMap::new())),
-        })
+        Ok(Self::default())
     }
 
     /// Check if a schema is loaded
@@ -73,53 +103,110 @@ impl BamlRuntimeManager {
     pub fn load_schema(&mut self, schema_path: &str) -> Result<()> {
         tracing::info!(schema_path = schema_path, "Loading BAML IL");
 
-        use std::path::Path;
+        let schema = self.read_schema(schema_path)?;
+        self.function_registry.extend(schema.functions);
+        self.executor = Some(schema.executor);
+        self.schema_digest = Some(schema.digest);
 
-        // Find project root
-        let schema_path_obj = Path::new(schema_path);
-        let project_root = if schema_path_obj.is_file() {
-            schema_path_obj.parent()
-                .and_then(|p| p.parent())
-        } else if schema_path_obj.file_name() == Some(std::ffi::OsStr::new("baml_src")) {
-            schema_path_obj.parent()
-        } else {
-            Some(schema_path_obj)
+        tracing::info!(
+            function_count = self.function_registry.len(),
+            "Loaded BAML IL"
+        );
+
+        Ok(())
+    }
+
+    /// Load a changed `baml_src` in place of the current schema
+    ///
+    /// Nothing is reloaded when the `.baml` sources are unchanged. Otherwise the
+    /// new sources are compiled in full before anything is replaced, so a
+    /// broken edit leaves the running schema in place; then the executor and
+    /// every function signature are swapped together. Output types declared
+    /// with [`Self::set_function_output_type`] are dropped for changed
+    /// functions, which now carry the types read from the new IR. Calls
+    /// already running finish on the schema they started with.
+    pub fn reload_schema(&mut self, schema_path: &str) -> Result<SchemaReload> {
+        let digest = baml_src_digest(&resolve_baml_src(schema_path)?)?;
+        if self.schema_digest.as_deref() == Some(digest.as_str()) {
+            return Ok(SchemaReload {
+                previous_digest: Some(digest.clone()),
+                digest,
+                ..SchemaReload::default()
+            });
         }
-        .ok_or_else(|| BamlRtError::InvalidArgument("Invalid schema path".to_string()))?;
 
-        let baml_src_dir = project_root.join("baml_src");
-        if !baml_src_dir.exists() {
-            return Err(BamlRtError::BamlRuntime(
-                "baml_src directory not found".to_string()
-            ));
+        tracing::info!(schema_path = schema_path, "Reloading BAML IL");
+        let schema = self.read_schema(schema_path)?;
+        let mut reload = SchemaReload {
+            digest: schema.digest.clone(),
+            previous_digest: self.schema_digest.clone(),
+            ..SchemaReload::default()
+        };
+        for (name, signature) in &schema.functions {
+            match self.function_registry.get(name) {
+                None => reload.added.push(name.clone()),
+                Some(current) if !same_signature(current, signature) => reload.changed.push(name.clone()),
+                Some(_) => {}
+            }
         }
+        reload.removed = self
+            .function_registry
+            .keys()
+            .filter(|name| !schema.functions.contains_key(*name))
+            .cloned()
+            .collect();
+        reload.added.sort();
+        reload.removed.sort();
+        reload.changed.sort();
+
+        // Unchanged functions keep any output type declared for them.
+        let mut functions = schema.functions;
+        for (name, signature) in functions.iter_mut() {
+            if let Some(current) = self.function_registry.get(name)
+                && !reload.changed.contains(name)
+            {
+                signature.output_type = current.output_type.clone();
+            }
+        }
+        self.function_registry = functions;
+        self.executor = Some(schema.executor);
+        self.schema_digest = Some(schema.digest);
+
+        tracing::info!(
+            digest = %reload.digest,
+            added = ?reload.added,
+            removed = ?reload.removed,
+            changed = ?reload.changed,
+            "Reloaded BAML IL"
+        );
+        Ok(reload)
+    }
+
+    /// Compile the schema at `schema_path` and read its function signatures.
+    fn read_schema(&self, schema_path: &str) -> Result<LoadedSchema> {
+        let baml_src_dir = resolve_baml_src(schema_path)?;
+        let digest = baml_src_digest(&baml_src_dir)?;
 
         // Load BAML IL into executor (pass tool registry)
         let tool_registry_clone = self.tool_registry.clone();
-        let executor = BamlExecutor::load_il(&baml_src_dir, tool_registry_clone)?;
-
-        // Discover functions from the BAML runtime
-        let function_names = executor.list_functions();
-        for func_name in function_names {
-            // Register function signature
-            self.function_registry.insert(
-                func_name.clone(),
+        let executor = BamlExecutor::load_il(&baml_src_dir, tool_registry_clone)?
+            .with_work_tracker(self.work.clone());
+
+        // Discover functions and their signatures from the BAML runtime
+        let mut functions = HashMap::new();
+        for func_name in executor.list_functions() {
+            let signature = executor.function_signature(&func_name).unwrap_or_else(|| {
+                tracing::warn!(function = func_name.as_str(), "No signature in BAML IR; arguments are not validated");
                 FunctionSignature {
                     name: func_name.clone(),
-                    input_types: vec![],
-                    output_type: baml_rt_core::types::BamlType::String,
-                },
-            );
+                    parameters: vec![],
+                    output_type: BamlType::Any,
+                }
+            });
+            functions.insert(func_name, signature);
         }
 
-        self.executor = Some(executor);
-
-        tracing::info!(
-            function_count = self.function_registry.len(),
-            "Loaded BAML IL"
-        );
-
-        Ok(())
+        Ok(LoadedSchema { executor, functions, digest })
     }
 
     /// Get the signature of a function by name
@@ -127,6 +214,72 @@ impl BamlRuntimeManager {
         self.function_registry.get(name)
     }
 
+    /// JSON Schemas for the arguments and output of every loaded BAML
+    /// function, sorted by name
+    pub fn export_function_schemas(&self) -> Vec<FunctionSchema> {
+        let mut schemas: Vec<FunctionSchema> =
+            self.function_registry.values().map(FunctionSignature::schema).collect();
+        schemas.sort_by(|a, b| a.name.cmp(&b.name));
+        schemas
+    }
+
+    /// Check `args` against the parameters `function_name` declares, so a
+    /// malformed invocation is rejected before it reaches the model
+    ///
+    /// Every parameter must be present with a matching value unless its type
+    /// accepts `null`; arguments the function does not declare are ignored.
+    pub fn validate_args(&self, function_name: &str, args: &Value) -> Result<()> {
+        let signature = self
+            .function_registry
+            .get(function_name)
+            .ok_or_else(|| BamlRtError::FunctionNotFound(function_name.to_string()))?;
+        let mismatches = signature.check_args(args);
+        if mismatches.is_empty() {
+            return Ok(());
+        }
+        let mismatches: Vec<String> = mismatches.iter().map(ToString::to_string).collect();
+        Err(BamlRtError::SchemaMismatch(format!(
+            "arguments of {} do not match its parameters: {}",
+            function_name,
+            mismatches.join("; ")
+        )))
+    }
+
+    /// Declare the type a function returns, for strict output validation
+    pub fn set_function_output_type(&mut self, name: &str, output_type: BamlType) -> Result<()> {
+        let signature = self
+            .function_registry
+            .get_mut(name)
+            .ok_or_else(|| BamlRtError::FunctionNotFound(name.to_string()))?;
+        signature.output_type = output_type;
+        Ok(())
+    }
+
+    /// Check the final value of `invoke_function` against the function's output type
+    ///
+    /// Off by default. When on, a value that does not match fails the call with
+    /// `BamlRtError::OutputValidation`, whose `feedback()` can be sent back to the
+    /// model, instead of being returned or dispatched to a tool. The LLM call is
+    /// annotated under `OUTPUT_VALIDATION_METADATA_KEY` either way. Functions
+    /// whose output type is `BamlType::Any` are not checked.
+    pub fn set_output_validation(&mut self, enabled: bool) {
+        self.output_validation = enabled;
+    }
+
+    pub fn output_validation(&self) -> bool {
+        self.output_validation
+    }
+
+    /// Re-invoke functions whose output fails validation, passing the mismatches
+    /// back to the model; `None` fails on the first invalid output
+    ///
+    /// Every attempt is its own LLM call, annotated under
+    /// `OUTPUT_REPAIR_METADATA_KEY` so provenance can link each retry to the
+    /// attempt it repairs. Has no effect unless output validation is on.
+    pub fn set_output_repair(&mut self, repair: Option<OutputRepair>) {
+        self.output_repair = repair;
+    }
+
     /// Execute a BAML function with the given arguments
     ///
     /// This is the main entry point for executing BAML functions.
@@ -135,6 +288,19 @@ impl BamlRuntimeManager {
         &self,
         function_name: &str,
         args: serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        self.invoke_function_with_client(function_name, args, None).await
+    }
+
+    /// [`Self::invoke_function`] against `client`, a client defined in the BAML
+    /// source, instead of the one the function declares; `None` keeps it
+    ///
+    /// Used to re-run a recorded call against another model.
+    pub async fn invoke_function_with_client(
+        &self,
+        function_name: &str,
+        args: serde_json::Value,
+        client: Option<&str>,
     ) -> Result<serde_json::Value> {
         let correlation_id = current_correlation_id();
         if let Some(correlation_id) = correlation_id.as_ref().map(|id| id.as_str()) {
@@ -153,10 +319,15 @@ impl BamlRuntimeManager {
         }
 
         // Verify function exists
-        let _signature = self
+        let signature = self
             .function_registry
             .get(function_name)
             .ok_or_else(|| BamlRtError::FunctionNotFound(function_name.to_string()))?;
+        self.validate_args(function_name, &args)?;
+        let output_type = Some(&signature.output_type).filter(|output_type| {
+            self.output_validation && !matches!(output_type, BamlType::Any)
+        });
+        let _work = self.work.begin_function(function_name, false);
 
         // Execute the BAML function using the executor
         let executor = self.executor.as_ref()
@@ -164,34 +335,87 @@ impl BamlRuntimeManager {
 
         // Pass tool registry and interceptor registry to executor
         let interceptor_registry = Some(self.interceptor_registry.clone());
-        executor.execute_function(function_name, args, interceptor_registry).await
+        let Some(repair) = self.output_repair.as_ref().filter(|_| output_type.is_some()) else {
+            return executor
+                .execute_function(function_name, args, interceptor_registry, output_type, None, client)
+                .await;
+        };
+
+        // Retry invalid outputs with the mismatches fed back, up to the repair limit
+        let chain = uuid::Uuid::new_v4().to_string();
+        let mut args = args;
+        let mut attempt = 1;
+        loop {
+            let annotation = serde_json::json!({
+                "chain": chain,
+                "attempt": attempt,
+                "max_attempts": repair.max_attempts,
+            });
+            let result = executor
+                .execute_function(
+                    function_name,
+                    args.clone(),
+                    interceptor_registry.clone(),
+                    output_type,
+                    Some(&annotation),
+                    client,
+                )
+                .await;
+            match result {
+                Err(BamlRtError::OutputValidation(error)) if repair.should_retry(attempt) => {
+                    tracing::warn!(
+                        function = function_name,
+                        attempt,
+                        max_attempts = repair.max_attempts,
+                        mismatches = error.mismatches.len(),
+                        "Retrying BAML function with output validation feedback"
+                    );
+                    args = repair.repair_args(&args, &error);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
     }
 
     /// Invoke a BAML function with streaming support
     ///
-    /// Returns a stream that yields incremental results as the function executes.
-    pub fn invoke_function_stream(
+    /// Returns a stream that yields incremental results as the function executes,
+    /// with the monitor that routes those results through the LLM interceptors.
+    pub async fn invoke_function_stream(
         &self,
         function_name: &str,
         args: serde_json::Value,
-    ) -> Result<baml_runtime::FunctionResultStream> {
+    ) -> Result<(baml_runtime::FunctionResultStream, Option<LLMStreamMonitor>)> {
         tracing::debug!(
             function = function_name,
             args = ?args,
             "Invoking BAML function with streaming"
         );
 
-        // Verify function exists
-        let _signature = self
-            .function_registry
-            .get(function_name)
-            .ok_or_else(|| BamlRtError::FunctionNotFound(function_name.to_string()))?;
+        // Verify function exists and its arguments match
+        self.validate_args(function_name, &args)?;
 
         // Execute the BAML function using the executor
         let executor = self.executor.as_ref()
             .ok_or_else(|| BamlRtError::BamlRuntime("BAML runtime not loaded".to_string()))?;
 
-        executor.execute_function_stream(function_name, args)
+        let interceptor_registry = Some(self.interceptor_registry.clone());
+        let (stream, monitor) = executor
+            .execute_function_stream(function_name, args, interceptor_registry)
+            .await?;
+        Ok((stream, monitor.map(|monitor| monitor.with_tokenizers(&self.tokenizers))))
+    }
+
+    /// Functions executing, tool sessions open and LLM calls in flight
+    pub fn active_work(&self) -> ActiveWork {
+        self.work.snapshot()
+    }
+
+    /// The tracker behind [`Self::active_work`], for reading it without this
+    /// manager's lock
+    pub fn work_tracker(&self) -> WorkTracker {
+        self.work.clone()
     }
 
     /// List all available BAML functions
@@ -269,11 +493,29 @@ impl BamlRuntimeManager {
         registry.register(tool)
     }
 
+    /// Retry policy for `execute_tool` calls to tools without a policy in the registry
+    pub fn set_tool_retry_policy(&mut self, policy: Option<RetryPolicy>) {
+        self.tool_retry_policy = policy;
+    }
+
+    /// Tokenizers by model family
+    ///
+    /// Streamed LLM calls estimate usage with the tokenizer for their model, and
+    /// agents count through `host.tokens.count`. Register model-specific
+    /// tokenizers on the returned registry; unregistered families use
+    /// `HeuristicTokenizer`.
+    pub fn tokenizers(&self) -> Arc<TokenizerRegistry> {
+        self.tokenizers.clone()
+    }
+
     /// Execute a tool function by name
     ///
-    /// This will call tool interceptors before and after execution.
+    /// This will call tool interceptors before and after execution. Attempts that
+    /// fail with a retryable kind are retried under the tool's retry policy, with
+    /// `on_tool_call_retry` sent to the interceptors before each backoff; the
+    /// registry is not held while waiting.
     pub async fn execute_tool(&self, name: &str, args: Value) -> Result<Value> {
-        use baml_rt_interceptor::ToolCallContext;
+        use baml_rt_interceptor::{InterceptorDecision, ToolCallContext};
         use std::time::Instant;
 
         let start = Instant::now();
@@ -288,30 +530,84 @@ impl BamlRuntimeManager {
         if let Some(message_id) = context::current_message_id() {
             metadata_map.insert("message_id".to_string(), Value::String(message_id.as_str().to_string()));
         }
+        if let Some(trace_context) = spans::outgoing_trace_context() {
+            trace_context.insert_into(&mut metadata_map);
+        }
         let metadata = Value::Object(metadata_map);
 
         // Build context for interceptors
-        let context = ToolCallContext {
+        let mut context = ToolCallContext {
             tool_name: name.to_string(),
             function_name: None, // Could be enhanced to track which function called this tool
-            args: args.clone(),
+            args,
             metadata,
             context_id: context::current_or_new(),
         };
 
-        // Run interceptors before execution
-        let interceptor_registry = self.interceptor_registry.lock().await;
-        let _decision = interceptor_registry.intercept_tool_call(&context).await?;
-        drop(interceptor_registry);
+        // Run interceptors before execution, on a snapshot: one may park the
+        // call for approval, which must not stall every other call.
+        let interceptors = self.interceptor_registry.lock().await.clone();
+        let decision = interceptors.intercept_tool_call(&context).await?;
 
-        // Handle interceptor decision
-        // If we get here, the decision is Allow (blocking would have returned Err)
-        let final_args = args;
+        // Blocking would have returned Err; a transform may have rewritten the
+        // arguments, and completion is reported with the ones the tool ran on.
+        if let InterceptorDecision::Modify { args: Some(args), .. } = decision {
+            context.args = args;
+        }
+        let final_args = context.args.clone();
+
+        let policy = self
+            .tool_registry
+            .lock()
+            .await
+            .retry_policy(name)
+            .or_else(|| self.tool_retry_policy.clone())
+            .unwrap_or_else(RetryPolicy::never);
+
+        // Execute the tool, retrying failed attempts the policy allows
+        let mut attempt = 1;
+        let result = loop {
+            let attempt_start = Instant::now();
+            let mut registry = self.tool_registry.lock().await;
+            let outcome = registry.try_execute(name, final_args.clone(), None).await;
+            drop(registry);
 
-        // Execute the tool
-        let mut registry = self.tool_registry.lock().await;
-        let result = registry.execute(name, final_args).await;
-        drop(registry);
+            let failure = match outcome {
+                Ok(Ok(output)) => break Ok(output),
+                Ok(Err(failure)) => failure,
+                Err(err) => break Err(err),
+            };
+            if !policy.should_retry(&failure, attempt) {
+                break Err(failure.into_error());
+            }
+
+            let backoff = policy.backoff(attempt);
+            let retry = ToolCallRetry {
+                attempt,
+                max_attempts: policy.max_attempts(),
+                failure_kind: failure.kind.as_str().to_string(),
+                error: failure.message,
+                duration_ms: attempt_start.elapsed().as_millis() as u64,
+                backoff_ms: backoff.as_millis() as u64,
+            };
+            let interceptor_registry = self.interceptor_registry.lock().await;
+            interceptor_registry.notify_tool_call_retry(&context, &retry).await;
+            drop(interceptor_registry);
+
+            tokio::time::sleep(backoff).await;
+            attempt += 1;
+        };
+
+        if attempt > 1
+            && let Value::Object(map) = &mut context.metadata
+        {
+            map.insert(
+                RETRY_METADATA_KEY.to_string(),
+                serde_json::json!({"attempt": attempt, "max_attempts": policy.max_attempts()}),
+            );
+        }
+
+        let result = result.with_error_context(|| ErrorContext::operation("tool_call").with_tool(name));
 
         // Calculate duration
         let duration = start.elapsed();
@@ -344,6 +640,7 @@ impl BamlRuntimeManager {
         let mut registry = self.tool_registry.lock().await;
         let session_id = registry.open_session(tool_name).await?;
         drop(registry);
+        self.work.open_tool_session(&session_id, tool_name);
         let scope = context::current_scope();
         let mut scopes = self.tool_session_scopes.lock().await;
         scopes.insert(
@@ -364,7 +661,7 @@ impl BamlRuntimeManager {
             scopes.get(session_id).cloned()
         };
         let session_scope = session_scope.ok_or_else(|| {
-            BamlRtError::InvalidArgument(format!(
+            BamlRtError::SessionStateViolation(format!(
                 "Unknown tool session {}",
                 session_id.as_str()
             ))
@@ -386,9 +683,12 @@ impl BamlRuntimeManager {
                     Value::String(message_id.as_str().to_string()),
                 );
             }
+            if let Some(trace_context) = spans::outgoing_trace_context() {
+                trace_context.insert_into(&mut metadata_map);
+            }
             let metadata = Value::Object(metadata_map);
 
-            let context = ToolCallContext {
+            let mut context = ToolCallContext {
                 tool_name: session_scope.tool_name.clone(),
                 function_name: None,
                 args: input.clone(),
@@ -396,10 +696,32 @@ impl BamlRuntimeManager {
                 context_id: context::current_or_new(),
             };
 
-            let interceptor_registry = self.interceptor_registry.lock().await;
-            let _decision: InterceptorDecision =
-                interceptor_registry.intercept_tool_call(&context).await?;
-            drop(interceptor_registry);
+            let interceptors = self.interceptor_registry.lock().await.clone();
+            let decision = match interceptors.intercept_tool_call(&context).await {
+                Ok(decision) => decision,
+                Err(err) => {
+                    // The tool never sees this input and its session cannot
+                    // continue without it, so end the session here.
+                    let mut registry = self.tool_registry.lock().await;
+                    if let Err(abort_err) =
+                        registry.session_abort(session_id, Some(err.to_string())).await
+                    {
+                        tracing::warn!(
+                            session_id = session_id.as_str(),
+                            error = %abort_err,
+                            "Failed to abort tool session after a blocked send"
+                        );
+                    }
+                    drop(registry);
+                    self.tool_session_scopes.lock().await.remove(session_id);
+                    self.work.close_tool_session(session_id);
+                    return Err(err);
+                }
+            };
+            // The session receives, and completion reports, the rewritten input.
+            if let InterceptorDecision::Modify { args: Some(args), .. } = decision {
+                context.args = args;
+            }
 
             {
                 let mut states = self.tool_session_states.lock().await;
@@ -413,7 +735,7 @@ impl BamlRuntimeManager {
             }
 
             let registry = self.tool_registry.lock().await;
-            let result = registry.session_send(session_id, input).await;
+            let result = registry.session_send(session_id, context.args.clone()).await;
             drop(registry);
 
             if result.is_err() {
@@ -431,6 +753,7 @@ impl BamlRuntimeManager {
                 states.remove(session_id);
                 let mut scopes = self.tool_session_scopes.lock().await;
                 scopes.remove(session_id);
+                self.work.close_tool_session(session_id);
             }
 
             result
@@ -456,12 +779,7 @@ impl BamlRuntimeManager {
 
             let completion = match &result {
                 Ok(ToolStep::Done { output }) => Some(Ok(output.clone().unwrap_or(Value::Null))),
-                Ok(ToolStep::Error { error }) => {
-                    Some(Err(BamlRtError::InvalidArgument(format!(
-                        "Tool failure ({:?}): {}",
-                        error.kind, error.message
-                    ))))
-                }
+                Ok(ToolStep::Error { error }) => Some(Err(error.clone().into_error())),
                 Err(err) => Some(Err(BamlRtError::InvalidArgument(err.to_string()))),
                 _ => None,
             };
@@ -473,6 +791,7 @@ impl BamlRuntimeManager {
                 } {
                     let mut scopes = self.tool_session_scopes.lock().await;
                     scopes.remove(session_id);
+                    self.work.close_tool_session(session_id);
                     let duration_ms = state.start.elapsed().as_millis() as u64;
                     let interceptor_registry = self.interceptor_registry.lock().await;
                     interceptor_registry
@@ -509,6 +828,7 @@ impl BamlRuntimeManager {
             let mut registry = self.tool_registry.lock().await;
             let result = registry.session_finish(session_id).await;
             drop(registry);
+            self.work.close_tool_session(session_id);
 
             if let Some(state) = {
                 let mut states = self.tool_session_states.lock().await;
@@ -555,6 +875,7 @@ impl BamlRuntimeManager {
             let mut registry = self.tool_registry.lock().await;
             let result = registry.session_abort(session_id, reason.clone()).await;
             drop(registry);
+            self.work.close_tool_session(session_id);
 
             if let Some(state) = {
                 let mut states = self.tool_session_states.lock().await;
@@ -617,11 +938,33 @@ impl BamlRuntimeManager {
         registry.write_typescript_declarations(path)
     }
 
+    /// Write TypeScript declarations for the BAML functions the QuickJS bridge
+    /// injects, typed from their signatures
+    pub fn write_baml_typescript(&self, path: &Path) -> Result<()> {
+        let mut functions: Vec<FunctionSignature> = self.function_registry.values().cloned().collect();
+        functions.sort_by(|a, b| a.name.cmp(&b.name));
+        let content = crate::ts_gen::render_baml_typescript(&functions)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(BamlRtError::Io)?;
+        }
+        fs::write(path, content).map_err(BamlRtError::Io)?;
+        Ok(())
+    }
+
     pub async fn validate_tool_allowlist_registered(&self) -> Result<()> {
         let registry = self.tool_registry.lock().await;
         registry.validate_allowlist_registered()
     }
 
+    /// Fail unless the registered tool bundles satisfy a manifest's `required_bundles`.
+    pub async fn check_bundle_requirements(
+        &self,
+        requirements: &[baml_rt_core::manifest::BundleRequirement],
+    ) -> Result<()> {
+        let registry = self.tool_registry.lock().await;
+        registry.check_bundle_requirements(requirements)
+    }
+
     /// Execute a tool from a BAML result
     ///
     /// BAML returns either:
@@ -697,12 +1040,7 @@ impl BamlRuntimeManager {
         &self,
         steps: &[ToolSessionPlanStep],
     ) -> Result<String> {
-        let input = steps.iter().find_map(|step| {
-            step.initial_input
-                .as_ref()
-                .or_else(|| step.input.as_ref())
-        });
-        let input = input.ok_or_else(|| {
+        let input = first_plan_input(steps).ok_or_else(|| {
             BamlRtError::InvalidArgument(
                 "ToolSessionPlan must include initial_input or input to bind a tool".to_string(),
             )
@@ -725,11 +1063,11 @@ impl BamlRuntimeManager {
             .collect::<Vec<_>>();
         match matches.len() {
             1 => Ok(matches.pop().unwrap()),
-            0 => Err(BamlRtError::InvalidArgument(format!(
+            0 => Err(BamlRtError::SchemaMismatch(format!(
                 "No tool input schema matched input: {}",
                 input
             ))),
-            _ => Err(BamlRtError::InvalidArgument(format!(
+            _ => Err(BamlRtError::SchemaMismatch(format!(
                 "Multiple tools matched input schema: {}",
                 matches.join(", ")
             ))),
@@ -745,7 +1083,7 @@ impl BamlRuntimeManager {
         let first_non_open = steps.iter().position(|s| s.op != "open");
         if let Some(pos) = first_non_open {
             if steps[pos].op == "send" {
-                return Err(BamlRtError::InvalidArgument(format!(
+                return Err(BamlRtError::SessionStateViolation(format!(
                     "FSM violation: plan has '{}' step at position {} before any 'open' step. FSM requires Open before Send.",
                     steps[pos].op, pos
                 )));
@@ -755,121 +1093,144 @@ impl BamlRuntimeManager {
             return Err(BamlRtError::InvalidArgument("ToolSessionPlan must have at least one step".to_string()));
         }
 
-        let mut session_id: Option<ToolSessionId> = None;
-        let mut last_output: Option<Value> = None;
-        let mut streaming_outputs: Vec<Value> = Vec::new();
-
-        for step in steps {
-            match step.op.as_str() {
-                "open" => {
-                    if session_id.is_some() {
-                        return Err(BamlRtError::InvalidArgument(
-                            "Tool session already open".to_string(),
-                        ));
+        let mut run = PlanRun::default();
+        self.run_plan_steps(&tool_name, &steps, &mut run).await?;
+
+        // If session is still open and no explicit Next was called, call Next to get result
+        if let Some(session) = run.session_id.take() {
+            self.drain_plan_session(&session, &mut run).await?;
+        }
+
+        let PlanRun { last_output, mut streaming_outputs, .. } = run;
+        if !streaming_outputs.is_empty() {
+            if let Some(done) = last_output {
+                streaming_outputs.push(done);
+            }
+            return Ok(Value::Array(streaming_outputs));
+        }
+
+        Ok(last_output.unwrap_or(Value::Null))
+    }
+
+    /// Run `steps` against `run`; `branch` and `repeat_until` recurse into
+    /// their nested steps, so this returns a boxed future.
+    fn run_plan_steps<'a>(
+        &'a self,
+        tool_name: &'a str,
+        steps: &'a [ToolSessionPlanStep],
+        run: &'a mut PlanRun,
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
+        Box::pin(async move {
+            for step in steps {
+                match step.op.as_str() {
+                    "open" => {
+                        if run.session_id.is_some() {
+                            return Err(BamlRtError::SessionStateViolation(
+                                "Tool session already open".to_string(),
+                            ));
+                        }
+                        let session = self.open_tool_session(tool_name).await?;
+                        run.session_id = Some(session.clone());
+                        // If Open step has initial_input, automatically Send it
+                        if let Some(initial_input) = step.initial_input.clone() {
+                            let normalized = normalize_plan_input(initial_input)?;
+                            self.tool_session_send(&session, normalized).await?;
+                        }
                     }
-                    let session = self.open_tool_session(&tool_name).await?;
-                    session_id = Some(session.clone());
-                    // If Open step has initial_input, automatically Send it
-                    if let Some(initial_input) = step.initial_input {
-                        let normalized = normalize_plan_input(initial_input)?;
-                        self.tool_session_send(&session, normalized).await?;
+                    "send" => {
+                        let session = run.session_id.as_ref().ok_or_else(|| {
+                            BamlRtError::SessionStateViolation("send step before open: FSM requires Open before Send".to_string())
+                        })?;
+                        // Send steps must use 'input' field, not 'initial_input'
+                        // Treat null/None as missing input
+                        let input = step.input
+                            .clone()
+                            .filter(|v| !v.is_null())
+                            .ok_or_else(|| {
+                                if step.initial_input.is_some() {
+                                    BamlRtError::InvalidArgument("send step must use 'input' field, not 'initial_input' (initial_input is only for Open steps)".to_string())
+                                } else {
+                                    BamlRtError::InvalidArgument("send step missing input (input is null or missing)".to_string())
+                                }
+                            })?;
+                        let normalized = normalize_plan_input(input)?;
+                        self.tool_session_send(session, normalized).await?;
                     }
-                }
-                "send" => {
-                    let session = session_id.as_ref().ok_or_else(|| {
-                        BamlRtError::InvalidArgument("send step before open: FSM requires Open before Send".to_string())
-                    })?;
-                    // Send steps must use 'input' field, not 'initial_input'
-                    // Treat null/None as missing input
-                    let input = step.input
-                        .filter(|v| !v.is_null())
-                        .ok_or_else(|| {
-                            if step.initial_input.is_some() {
-                                BamlRtError::InvalidArgument("send step must use 'input' field, not 'initial_input' (initial_input is only for Open steps)".to_string())
-                            } else {
-                                BamlRtError::InvalidArgument("send step missing input (input is null or missing)".to_string())
-                            }
+                    "next" => {
+                        let session = run.session_id.take().ok_or_else(|| {
+                            BamlRtError::SessionStateViolation("next step before open".to_string())
                         })?;
-                    let normalized = normalize_plan_input(input)?;
-                    self.tool_session_send(session, normalized).await?;
-                }
-                "next" => {
-                    let session = session_id.as_ref().ok_or_else(|| {
-                        BamlRtError::InvalidArgument("next step before open".to_string())
-                    })?;
-                    loop {
-                        match self.tool_session_next(session).await? {
-                            ToolStep::Streaming { output } => {
-                                streaming_outputs.push(output);
-                            }
-                            ToolStep::Done { output } => {
-                                last_output = output;
-                                self.tool_session_finish(session).await?;
-                                session_id = None;
-                                break;
-                            }
-                            ToolStep::Error { error } => {
-                                self.tool_session_abort(session, Some(error.message.clone())).await?;
-                                return Err(BamlRtError::InvalidArgument(format!(
-                                    "Tool failure ({:?}): {}",
-                                    error.kind, error.message
-                                )));
-                            }
-                        }
+                        self.drain_plan_session(&session, run).await?;
                     }
-                }
-                "finish" => {
-                    if let Some(session) = session_id.as_ref() {
-                        self.tool_session_finish(session).await?;
-                        session_id = None;
+                    "finish" => {
+                        if let Some(session) = run.session_id.take() {
+                            self.tool_session_finish(&session).await?;
+                        }
                     }
-                }
-                "abort" => {
-                    if let Some(session) = session_id.as_ref() {
-                        self.tool_session_abort(session, step.reason).await?;
-                        session_id = None;
+                    "abort" => {
+                        if let Some(session) = run.session_id.take() {
+                            self.tool_session_abort(&session, step.reason.clone()).await?;
+                        }
                     }
-                }
-                other => {
-                    return Err(BamlRtError::InvalidArgument(format!(
-                        "Unknown tool session op '{}'",
-                        other
-                    )));
-                }
-            }
-        }
-
-        // If session is still open and no explicit Next was called, call Next to get result
-        if let Some(session) = session_id.as_ref() {
-            loop {
-                match self.tool_session_next(session).await? {
-                    ToolStep::Streaming { output } => {
-                        streaming_outputs.push(output);
+                    "branch" => {
+                        let taken = if step.condition_holds(run.previous.as_ref()) {
+                            &step.body
+                        } else {
+                            &step.otherwise
+                        };
+                        self.run_plan_steps(tool_name, taken, run).await?;
                     }
-                    ToolStep::Done { output } => {
-                        last_output = output;
-                        self.tool_session_finish(session).await?;
-                        break;
+                    "repeat_until" => {
+                        let max_iterations = step.max_iterations.unwrap_or(DEFAULT_PLAN_MAX_ITERATIONS);
+                        let mut iterations = 0;
+                        loop {
+                            if iterations == max_iterations {
+                                return Err(BamlRtError::ToolExecution(format!(
+                                    "repeat_until condition still false after {} iterations",
+                                    max_iterations
+                                )));
+                            }
+                            self.run_plan_steps(tool_name, &step.body, run).await?;
+                            iterations += 1;
+                            if step.condition_holds(run.previous.as_ref()) {
+                                break;
+                            }
+                        }
                     }
-                    ToolStep::Error { error } => {
-                        self.tool_session_abort(session, Some(error.message.clone())).await?;
+                    other => {
                         return Err(BamlRtError::InvalidArgument(format!(
-                            "Tool failure ({:?}): {}",
-                            error.kind, error.message
+                            "Unknown tool session op '{}'",
+                            other
                         )));
                     }
                 }
             }
-        }
+            Ok(())
+        })
+    }
 
-        if !streaming_outputs.is_empty() {
-            if let Some(done) = last_output {
-                streaming_outputs.push(done);
+    /// Step `session` until it is done, then finish it.
+    async fn drain_plan_session(&self, session: &ToolSessionId, run: &mut PlanRun) -> Result<()> {
+        loop {
+            match self.tool_session_next(session).await? {
+                ToolStep::Streaming { output } => {
+                    run.previous = Some(output.clone());
+                    run.streaming_outputs.push(output);
+                }
+                ToolStep::Done { output } => {
+                    if output.is_some() {
+                        run.previous = output.clone();
+                    }
+                    run.last_output = output;
+                    self.tool_session_finish(session).await?;
+                    return Ok(());
+                }
+                ToolStep::Error { error } => {
+                    self.tool_session_abort(session, Some(error.message.clone())).await?;
+                    return Err(error.into_error());
+                }
             }
-            return Ok(Value::Array(streaming_outputs));
         }
-
-        Ok(last_output.unwrap_or(Value::Null))
     }
 }
 
@@ -904,8 +1265,70 @@ impl Default for BamlRuntimeManager {
             interceptor_registry: Arc::new(TokioMutex::new(InterceptorRegistry::new())),
             tool_session_scopes: Arc::new(TokioMutex::new(HashMap::new())),
             tool_session_states: Arc::new(TokioMutex::new(HashMap::new())),
+            tool_retry_policy: None,
+            tokenizers: Arc::new(TokenizerRegistry::new()),
+            output_validation: false,
+            output_repair: None,
+            work: WorkTracker::new(),
+            schema_digest: None,
+        }
+    }
+}
+
+/// A compiled schema, before it replaces the manager's.
+struct LoadedSchema {
+    executor: BamlExecutor,
+    functions: HashMap<String, FunctionSignature>,
+    digest: String,
+}
+
+/// The `baml_src` directory `schema_path` names: the directory itself, a file
+/// in it, or the project directory holding it.
+fn resolve_baml_src(schema_path: &str) -> Result<std::path::PathBuf> {
+    // Find project root
+    let schema_path_obj = Path::new(schema_path);
+    let project_root = if schema_path_obj.is_file() {
+        schema_path_obj.parent()
+            .and_then(|p| p.parent())
+    } else if schema_path_obj.file_name() == Some(std::ffi::OsStr::new("baml_src")) {
+        schema_path_obj.parent()
+    } else {
+        Some(schema_path_obj)
+    }
+    .ok_or_else(|| BamlRtError::InvalidArgument("Invalid schema path".to_string()))?;
+
+    let baml_src_dir = project_root.join("baml_src");
+    if !baml_src_dir.exists() {
+        return Err(BamlRtError::BamlRuntime(
+            "baml_src directory not found".to_string()
+        ));
+    }
+    Ok(baml_src_dir)
+}
+
+/// Digest of every `.baml` file under `baml_src_dir`, by relative path and contents.
+fn baml_src_digest(baml_src_dir: &Path) -> Result<String> {
+    fn collect(dir: &Path, root: &Path, sources: &mut serde_json::Map<String, Value>) -> Result<()> {
+        for entry in fs::read_dir(dir).map_err(BamlRtError::Io)? {
+            let path = entry.map_err(BamlRtError::Io)?.path();
+            if path.is_dir() {
+                collect(&path, root, sources)?;
+            } else if path.extension().and_then(|extension| extension.to_str()) == Some("baml") {
+                let relative = path.strip_prefix(root).unwrap_or(&path).to_string_lossy().into_owned();
+                let contents = fs::read_to_string(&path).map_err(BamlRtError::Io)?;
+                sources.insert(relative, Value::String(contents));
+            }
         }
+        Ok(())
     }
+
+    let mut sources = serde_json::Map::new();
+    collect(baml_src_dir, baml_src_dir, &mut sources)?;
+    Ok(baml_rt_core::canonical::digest_hex(&Value::Object(sources)))
+}
+
+fn same_signature(a: &FunctionSignature, b: &FunctionSignature) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
 }
 
 #[derive(Debug, Clone)]
@@ -990,14 +1413,66 @@ fn input_matches_schema(input: &Value, schema: &Value) -> bool {
     true
 }
 
+/// How many times a `repeat_until` step runs its steps when the plan does not say.
+const DEFAULT_PLAN_MAX_ITERATIONS: u32 = 10;
+
 #[derive(Debug, Clone)]
 struct ToolSessionPlanStep {
     op: String,
     initial_input: Option<Value>, // For Open step - initial input when opening session
     input: Option<Value>,         // For Send step - subsequent inputs
     reason: Option<String>,
+    /// `when` for a branch step, `until` for a repeat_until step
+    condition: Option<JsonPath>,
+    /// `then` for a branch step, `steps` for a repeat_until step
+    body: Vec<ToolSessionPlanStep>,
+    /// `else` for a branch step
+    otherwise: Vec<ToolSessionPlanStep>,
+    max_iterations: Option<u32>,
 }
 
+impl ToolSessionPlanStep {
+    /// Whether the step's condition holds for the previous step's output.
+    ///
+    /// The JSONPath query runs against a one-element array holding the
+    /// output, so a filter such as `$[?@.status == 'done']` can test the
+    /// output itself, and `$[0].items[*]` reaches into it. The condition holds
+    /// when the query selects at least one node that is not `null` or `false`;
+    /// it never holds before any step has produced output.
+    fn condition_holds(&self, previous: Option<&Value>) -> bool {
+        let (Some(condition), Some(previous)) = (self.condition.as_ref(), previous) else {
+            return false;
+        };
+        let root = Value::Array(vec![previous.clone()]);
+        condition
+            .query(&root)
+            .all()
+            .into_iter()
+            .any(|node| !matches!(node, Value::Null | Value::Bool(false)))
+    }
+}
+
+/// Where a tool session plan is while it runs.
+#[derive(Debug, Default)]
+struct PlanRun {
+    session_id: Option<ToolSessionId>,
+    last_output: Option<Value>,
+    streaming_outputs: Vec<Value>,
+    /// Output of the latest step that produced one, which `branch` and
+    /// `repeat_until` conditions are evaluated against.
+    previous: Option<Value>,
+}
+
+/// The first input in `steps`, looking inside `branch` and `repeat_until` steps.
+fn first_plan_input(steps: &[ToolSessionPlanStep]) -> Option<&Value> {
+    steps.iter().find_map(|step| {
+        step.initial_input
+            .as_ref()
+            .or(step.input.as_ref())
+            .or_else(|| first_plan_input(&step.body))
+            .or_else(|| first_plan_input(&step.otherwise))
+    })
+}
 
 fn extract_tool_session_plan(result: &Value) -> Result<Option<Vec<ToolSessionPlanStep>>> {
     let obj = match result.as_object() {
@@ -1012,6 +1487,10 @@ fn extract_tool_session_plan(result: &Value) -> Result<Option<Vec<ToolSessionPla
         BamlRtError::InvalidArgument("ToolSessionPlan.steps must be an array".to_string())
     })?;
 
+    parse_plan_steps(steps_array).map(Some)
+}
+
+fn parse_plan_steps(steps_array: &[Value]) -> Result<Vec<ToolSessionPlanStep>> {
     let mut steps = Vec::new();
     for step_value in steps_array {
         let step_obj = step_value.as_object().ok_or_else(|| {
@@ -1067,15 +1546,74 @@ fn extract_tool_session_plan(result: &Value) -> Result<Option<Vec<ToolSessionPla
         };
         
         let reason = step_obj.get("reason").and_then(|v| v.as_str()).map(|s| s.to_string());
+
+        // Control-flow steps: a condition over the previous output and nested steps
+        let (condition, body, otherwise) = match op.as_str() {
+            "branch" => (
+                Some(parse_plan_condition(step_obj, "when")?),
+                parse_nested_plan_steps(step_obj, "then")?,
+                parse_nested_plan_steps(step_obj, "else")?,
+            ),
+            "repeat_until" => {
+                let body = parse_nested_plan_steps(step_obj, "steps")?;
+                if body.is_empty() {
+                    return Err(BamlRtError::InvalidArgument(
+                        "repeat_until step must have at least one step".to_string(),
+                    ));
+                }
+                (Some(parse_plan_condition(step_obj, "until")?), body, Vec::new())
+            }
+            _ => (None, Vec::new(), Vec::new()),
+        };
+        let max_iterations = match step_obj.get("max_iterations") {
+            None | Some(Value::Null) => None,
+            Some(value) => match value.as_u64().and_then(|n| u32::try_from(n).ok()) {
+                Some(n) if n > 0 => Some(n),
+                _ => {
+                    return Err(BamlRtError::InvalidArgument(format!(
+                        "max_iterations must be a positive integer, got {}",
+                        value
+                    )));
+                }
+            },
+        };
+
         steps.push(ToolSessionPlanStep {
             op,
             initial_input,
             input,
             reason,
+            condition,
+            body,
+            otherwise,
+            max_iterations,
         });
     }
 
-    Ok(Some(steps))
+    Ok(steps)
+}
+
+fn parse_plan_condition(step_obj: &serde_json::Map<String, Value>, field: &str) -> Result<JsonPath> {
+    let raw = step_obj.get(field).and_then(|v| v.as_str()).ok_or_else(|| {
+        BamlRtError::InvalidArgument(format!("ToolSessionPlan step missing '{}' JSONPath", field))
+    })?;
+    JsonPath::parse(raw).map_err(|e| {
+        BamlRtError::InvalidArgument(format!("Invalid JSONPath in '{}': {}", field, e))
+    })
+}
+
+fn parse_nested_plan_steps(
+    step_obj: &serde_json::Map<String, Value>,
+    field: &str,
+) -> Result<Vec<ToolSessionPlanStep>> {
+    match step_obj.get(field) {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(Value::Array(nested)) => parse_plan_steps(nested),
+        Some(_) => Err(BamlRtError::InvalidArgument(format!(
+            "ToolSessionPlan step '{}' must be an array of steps",
+            field
+        ))),
+    }
 }
 
 fn normalize_plan_input(value: Value) -> Result<Value> {
@@ -11,9 +11,11 @@
 use baml_rt_core::Result;
 use baml_rt_core::context;
 use baml_rt_interceptor::{InterceptorRegistry, LLMCallContext};
+use baml_rt_observability::metrics;
 use baml_runtime::tracingv2::storage::storage::Collector;
-use serde_json::json;
+use serde_json::{json, Map, Value};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
 
 /// BAML collector wrapper that tracks LLM calls via trace events
@@ -56,7 +58,10 @@ impl BamlLLMCollector {
     /// This should be called after function execution to process collected trace events.
     ///
     /// Note: This uses the last function log tracked by the collector.
-    pub async fn process_trace_events(&self) -> Result<()> {
+    ///
+    /// `annotations` are added to the metadata of the selected call, the one whose
+    /// response the function's output was parsed from.
+    pub async fn process_trace_events(&self, annotations: &Map<String, Value>) -> Result<()> {
 
         // Get the last function log tracked by this collector
         // The collector tracks function IDs as they're executed when passed to call_function
@@ -76,10 +81,19 @@ impl BamlLLMCollector {
         for call_kind in llm_calls {
             // Extract context from the LLM call
             if let Some(llm_call) = call_kind.as_request() {
-                let context = self.extract_context_from_llm_call(llm_call);
+                let mut context = self.extract_context_from_llm_call(llm_call);
+                if let (true, Value::Object(metadata)) = (llm_call.selected, &mut context.metadata) {
+                    metadata.extend(annotations.clone());
+                }
 
                 // Extract duration from timing
                 let duration_ms = llm_call.timing.duration_ms.unwrap_or(0) as u64;
+                metrics::record_llm_call(
+                    &context.function_name,
+                    &context.client,
+                    false,
+                    Duration::from_millis(duration_ms),
+                );
 
                 // Notify interceptors (post-execution notification)
                 let registry = self.interceptor_registry.lock().await;
@@ -112,6 +126,7 @@ impl BamlLLMCollector {
             client,
             model,
             function_name: self.function_name.clone(),
+            args: json!({}),
             context_id: context::current_or_new(),
             prompt,
             metadata: json!({
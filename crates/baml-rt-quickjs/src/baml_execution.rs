@@ -5,13 +5,20 @@
 
 use baml_rt_core::{BamlRtError, Result};
 use baml_rt_core::context;
+use baml_rt_core::types::{BamlType, FunctionSignature, OutputValidationError};
 use baml_rt_tools::ToolRegistry;
-use baml_rt_interceptor::{InterceptorDecision, InterceptorRegistry};
+use baml_rt_interceptor::{
+    InterceptorDecision, InterceptorRegistry, FUNCTION_ARGS_METADATA_KEY,
+    FUNCTION_OUTPUT_METADATA_KEY, OUTPUT_REPAIR_METADATA_KEY, OUTPUT_VALIDATION_METADATA_KEY,
+};
+use crate::active_work::WorkTracker;
 use crate::baml_collector::BamlLLMCollector;
-use crate::baml_pre_execution::intercept_llm_call_pre_execution;
-use baml_runtime::{BamlRuntime, FunctionResultStream, RuntimeContextManager};
+use crate::baml_pre_execution::{build_llm_call_context, intercept_llm_call_pre_execution};
+use crate::baml_stream::LLMStreamMonitor;
+use baml_runtime::client_registry::ClientRegistry;
+use baml_runtime::{BamlRuntime, FunctionResultStream, InternalRuntimeInterface, RuntimeContextManager};
 use baml_types::BamlValue;
-use serde_json::Value;
+use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
@@ -22,6 +29,7 @@ use tokio::sync::Mutex;
 pub struct BamlExecutor {
     runtime: Arc<BamlRuntime>,
     tool_registry: Arc<Mutex<ToolRegistry>>,
+    work: WorkTracker,
 }
 
 impl BamlExecutor {
@@ -62,15 +70,35 @@ impl BamlExecutor {
         Ok(Self {
             runtime: Arc::new(runtime),
             tool_registry,
+            work: WorkTracker::new(),
         })
     }
 
+    /// List the LLM calls this executor makes as in flight on `work`
+    pub fn with_work_tracker(mut self, work: WorkTracker) -> Self {
+        self.work = work;
+        self
+    }
+
     /// Execute a BAML function using the compiled IL
+    ///
+    /// When `output_type` is given the parsed result is checked against it before
+    /// it is returned or dispatched as a tool call; a mismatch fails the call with
+    /// `BamlRtError::OutputValidation` and is recorded on the LLM call. `repair`
+    /// identifies the attempt when the call is a retry of an invalid output.
+    /// `client` names a client defined in the BAML source to call instead of
+    /// the function's own.
+    ///
+    /// The arguments and parsed output are recorded on the selected LLM call, so
+    /// the invocation can be replayed from provenance.
     pub async fn execute_function(
         &self,
         function_name: &str,
-        args: Value,
+        mut args: Value,
         interceptor_registry: Option<Arc<Mutex<InterceptorRegistry>>>,
+        output_type: Option<&BamlType>,
+        repair: Option<&Value>,
+        client: Option<&str>,
     ) -> Result<Value> {
         tracing::debug!(
             function = function_name,
@@ -79,7 +107,7 @@ impl BamlExecutor {
         );
 
         // Convert JSON args to BamlValue map
-        let params = self.json_to_baml_map(&args)?;
+        let mut params = self.json_to_baml_map(&args)?;
 
         // Call the function
         // Load environment variables for API keys
@@ -94,6 +122,11 @@ impl BamlExecutor {
         }
         let tags = None;
         let cancel_tripwire = baml_runtime::TripWire::new(None);
+        let client_registry = client.map(|client| {
+            let mut registry = ClientRegistry::new();
+            registry.set_primary(client.to_string());
+            registry
+        });
 
         // Track execution start time for LLM interceptor callbacks
         let _start_time = Instant::now();
@@ -108,31 +141,52 @@ impl BamlExecutor {
 
         // Pre-execution interception: intercept LLM calls before they're sent
         let ctx_manager = self.create_ctx_manager_for_current_scope()?;
-        if let Some(ref registry) = interceptor_registry {
-            match intercept_llm_call_pre_execution(
+        let in_flight = match interceptor_registry {
+            Some(ref registry) => match intercept_llm_call_pre_execution(
                 &self.runtime,
                 function_name,
+                &args,
                 &params,
                 &ctx_manager,
+                client_registry.as_ref(),
                 registry,
                 env_vars.clone(),
                 false, // stream = false for regular calls
             ).await {
-                Ok(InterceptorDecision::Allow) => {
+                Ok((InterceptorDecision::Allow, context)) => {
                     // Allow the call to proceed
+                    Some(self.work.begin_llm_call(&context))
+                }
+                Ok((InterceptorDecision::Modify { args: rewritten, prompt }, mut context)) => {
+                    // Rewritten arguments are what the function runs, and is recorded,
+                    // with. BAML still renders the prompt it sends from the template,
+                    // so a rewritten prompt only reaches reports of the call.
+                    if let Some(rewritten) = rewritten {
+                        params = self.json_to_baml_map(&rewritten)?;
+                        context.args = rewritten.clone();
+                        args = rewritten;
+                    }
+                    if let Some(prompt) = prompt {
+                        context.prompt = prompt;
+                    }
+                    Some(self.work.begin_llm_call(&context))
                 }
-                Ok(InterceptorDecision::Block(msg)) => {
+                Ok((InterceptorDecision::Block(msg), _)) => {
                     // Block the call - return error
                     return Err(BamlRtError::BamlRuntime(format!(
                         "LLM call blocked by interceptor: {}", msg
                     )));
                 }
+                Ok((InterceptorDecision::OverBudget(msg), _)) => {
+                    return Err(BamlRtError::Budget(msg));
+                }
                 Err(e) => {
                     // Interceptor error - return it
                     return Err(e);
                 }
-            }
-        }
+            },
+            None => None,
+        };
 
         // Wire up the collector to track function execution
         // Note: We track the function call by passing the collector, but we also need
@@ -148,12 +202,13 @@ impl BamlExecutor {
             &params,
             &ctx_manager,
             None, // type_builder
-            None, // client_registry
+            client_registry.as_ref(),
             collectors, // collectors - now wired up to track execution
             env_vars,
             tags,
             cancel_tripwire,
         ).await;
+        drop(in_flight);
 
         let function_result = result
             .map_err(|e| BamlRtError::ExecutionFailed { source: e })?;
@@ -173,16 +228,49 @@ impl BamlExecutor {
         let json_value = serde_json::to_value(parsed.serialize_partial())
             .map_err(BamlRtError::Json)?;
 
+        let mismatches = output_type.map(|output_type| output_type.check(&json_value));
+        let mut annotations = serde_json::Map::new();
+        if let Some(mismatches) = &mismatches {
+            annotations.insert(
+                OUTPUT_VALIDATION_METADATA_KEY.to_string(),
+                json!({
+                    "valid": mismatches.is_empty(),
+                    "mismatches": mismatches.iter().map(ToString::to_string).collect::<Vec<_>>(),
+                }),
+            );
+        }
+        if let Some(repair) = repair {
+            annotations.insert(OUTPUT_REPAIR_METADATA_KEY.to_string(), repair.clone());
+        }
+        annotations.insert(FUNCTION_ARGS_METADATA_KEY.to_string(), args.clone());
+        annotations.insert(FUNCTION_OUTPUT_METADATA_KEY.to_string(), json_value.clone());
+
         // Process trace events to notify LLM interceptors of completion
         // This extracts LLM call information from BAML's trace events
         if let Some(ref collector) = collector {
             // Process trace events to extract LLM call context and notify interceptors
             // The collector tracks the function call via the collector we passed to call_function
-            if let Err(e) = collector.process_trace_events().await {
+            if let Err(e) = collector.process_trace_events(&annotations).await {
                 tracing::warn!(error = ?e, "Failed to process trace events for LLM interception");
             }
         }
 
+        if let Some(mismatches) = mismatches
+            && !mismatches.is_empty()
+        {
+            tracing::warn!(
+                function = function_name,
+                mismatches = mismatches.len(),
+                "BAML function output does not match its declared type"
+            );
+            return Err(OutputValidationError {
+                function: function_name.to_string(),
+                value: json_value,
+                mismatches,
+            }
+            .into());
+        }
+
         if let Some(tool_result) =
             maybe_execute_tool_from_result(&self.tool_registry, &json_value).await?
         {
@@ -194,12 +282,14 @@ impl BamlExecutor {
 
     /// Execute a BAML function with streaming support
     ///
-    /// Returns a stream of incremental results as the function executes.
-    pub fn execute_function_stream(
+    /// Returns a stream of incremental results as the function executes, and a
+    /// monitor to pass each result to when an interceptor registry is provided.
+    pub async fn execute_function_stream(
         &self,
         function_name: &str,
         args: Value,
-    ) -> Result<FunctionResultStream> {
+        interceptor_registry: Option<Arc<Mutex<InterceptorRegistry>>>,
+    ) -> Result<(FunctionResultStream, Option<LLMStreamMonitor>)> {
         tracing::debug!(
             function = function_name,
             args = ?args,
@@ -224,6 +314,29 @@ impl BamlExecutor {
         let tags = None;
         let cancel_tripwire = baml_runtime::TripWire::new(None);
 
+        // Pre-execution interception; blocked calls never open the stream
+        let monitor = match interceptor_registry {
+            Some(registry) => {
+                let context = build_llm_call_context(
+                    &self.runtime,
+                    function_name,
+                    &args,
+                    &params,
+                    &ctx_manager,
+                    None,
+                    env_vars.clone(),
+                    true, // stream = true for streaming calls
+                ).await?;
+                let in_flight = self.work.begin_llm_call(&context);
+                Some(LLMStreamMonitor::start(registry, context).await?.tracked_by(in_flight))
+            }
+            None => None,
+        };
+        let params = match &monitor {
+            Some(monitor) if monitor.args() != &args => self.json_to_baml_map(monitor.args())?,
+            _ => params,
+        };
+
         let stream = self.runtime.stream_function(
             function_name.to_string(),
             &params,
@@ -237,7 +350,7 @@ impl BamlExecutor {
         )
         .map_err(|e| BamlRtError::BamlRuntime(format!("Failed to create stream: {}", e)))?;
 
-        Ok(stream)
+        Ok((stream, monitor))
     }
 
     /// Create a context manager tied to the current runtime scope.
@@ -267,6 +380,11 @@ impl BamlExecutor {
         self.runtime.function_names().map(|s| s.to_string()).collect()
     }
 
+    /// The parameter and return types `function_name` declares, from the BAML IR
+    pub fn function_signature(&self, function_name: &str) -> Option<FunctionSignature> {
+        crate::ir_types::function_signature(self.runtime.ir(), function_name)
+    }
+
     /// Convert JSON Value to BamlMap<String, BamlValue>
     fn json_to_baml_map(&self, value: &Value) -> Result<baml_types::BamlMap<String, BamlValue>> {
         let obj = value.as_object()
@@ -5,7 +5,9 @@
 
 use baml_rt_core::{BamlRtError, Result};
 use baml_rt_core::context;
+use baml_rt_observability::spans;
 use baml_rt_interceptor::{InterceptorDecision, InterceptorRegistry, LLMCallContext};
+use baml_runtime::client_registry::ClientRegistry;
 use baml_runtime::RuntimeContextManager;
 use baml_types::{BamlMap, BamlValue};
 use serde_json::{json, Value};
@@ -20,6 +22,7 @@ use tokio::sync::Mutex;
 pub fn extract_context_from_http_request(
     http_request: &baml_types::tracing::events::HTTPRequest,
     function_name: &str,
+    args: &Value,
 ) -> LLMCallContext {
     // Extract client and model from client_details
     // HTTPRequest has fields: id, url, method, body, client_details (Arc<ClientDetails>)
@@ -53,38 +56,43 @@ pub fn extract_context_from_http_request(
     if let Some(message_id) = context::current_message_id() {
         metadata_map.insert("message_id".to_string(), Value::String(message_id.as_str().to_string()));
     }
+    if let Some(trace_context) = spans::outgoing_trace_context() {
+        trace_context.insert_into(&mut metadata_map);
+    }
 
     LLMCallContext {
         client,
         model,
         function_name: function_name.to_string(),
+        args: args.clone(),
         context_id: context::current_or_new(),
         prompt,
         metadata: Value::Object(metadata_map),
     }
 }
 
-/// Intercept an LLM call before execution using build_request
+/// Build the context for the LLM call a function is about to make
 ///
-/// This builds the HTTP request, extracts context, runs interceptors,
-/// and returns the decision. If blocked, returns an error.
-pub async fn intercept_llm_call_pre_execution(
+/// This builds the HTTP request without sending it and extracts the call details.
+/// `client_registry` must be the one the function will be called with, and
+/// `params` the conversion of `args`.
+#[allow(clippy::too_many_arguments)]
+pub async fn build_llm_call_context(
     runtime: &baml_runtime::BamlRuntime,
     function_name: &str,
+    args: &Value,
     params: &BamlMap<String, BamlValue>,
     ctx_manager: &RuntimeContextManager,
-    interceptor_registry: &Arc<Mutex<InterceptorRegistry>>,
+    client_registry: Option<&ClientRegistry>,
     env_vars: HashMap<String, String>,
     stream: bool,
-) -> Result<InterceptorDecision> {
-    // Build the HTTP request to get LLM call details
-    // This doesn't actually send the request, just builds it
+) -> Result<LLMCallContext> {
     let http_request_result = runtime.build_request(
         function_name.to_string(),
         params,
         ctx_manager,
         None, // type_builder
-        None, // client_registry
+        client_registry,
         env_vars,
         stream,
     ).await;
@@ -93,7 +101,7 @@ pub async fn intercept_llm_call_pre_execution(
         .map_err(|e| BamlRtError::RequestBuildFailed(e.to_string()))?;
 
     // Extract LLM call context from the HTTP request
-    let context = extract_context_from_http_request(&http_request, function_name);
+    let context = extract_context_from_http_request(&http_request, function_name, args);
 
     tracing::debug!(
         client = context.client,
@@ -102,11 +110,40 @@ pub async fn intercept_llm_call_pre_execution(
         "Pre-execution interception: extracted LLM call context"
     );
 
+    Ok(context)
+}
+
+/// Intercept an LLM call before execution using build_request
+///
+/// This builds the HTTP request, extracts context, runs interceptors,
+/// and returns the decision with the context it was made on.
+#[allow(clippy::too_many_arguments)]
+pub async fn intercept_llm_call_pre_execution(
+    runtime: &baml_runtime::BamlRuntime,
+    function_name: &str,
+    args: &Value,
+    params: &BamlMap<String, BamlValue>,
+    ctx_manager: &RuntimeContextManager,
+    client_registry: Option<&ClientRegistry>,
+    interceptor_registry: &Arc<Mutex<InterceptorRegistry>>,
+    env_vars: HashMap<String, String>,
+    stream: bool,
+) -> Result<(InterceptorDecision, LLMCallContext)> {
+    let context = build_llm_call_context(
+        runtime,
+        function_name,
+        args,
+        params,
+        ctx_manager,
+        client_registry,
+        env_vars,
+        stream,
+    ).await?;
+
     // Run interceptors
     let registry = interceptor_registry.lock().await;
     let decision = registry.intercept_llm_call(&context).await?;
     drop(registry);
 
-    // Return the decision
-    Ok(decision)
+    Ok((decision, context))
 }
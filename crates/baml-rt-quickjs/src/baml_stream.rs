@@ -0,0 +1,169 @@
+//! LLM interception for streamed BAML calls
+//!
+//! A streamed call is intercepted once before it starts, like a regular call, and
+//! then once per chunk through `LLMInterceptor::on_llm_chunk`. An interceptor that
+//! blocks a chunk aborts the stream. Completion is reported when the stream ends,
+//! whether it finished, failed or was aborted. A rewrite from a transform
+//! interceptor is applied to the monitored call before the stream is opened.
+//!
+//! Token usage is estimated as chunks arrive and carried on every `LLMChunk`.
+//! When the stream ends the estimate is reconciled with the usage the provider
+//! reported, if any, and stored under `USAGE_METADATA_KEY` in the completion
+//! context.
+
+use crate::active_work::WorkGuard;
+use baml_rt_core::Result;
+use baml_rt_core::tokens::TokenizerRegistry;
+use baml_rt_interceptor::{
+    HeuristicTokenizer, InterceptorDecision, InterceptorRegistry, LLMCallContext, LLMChunk,
+    StreamUsageTracker, TokenEstimator, TokenUsage, STREAM_ID_METADATA_KEY, USAGE_METADATA_KEY,
+};
+use baml_rt_observability::metrics;
+use baml_runtime::internal::llm_client::LLMResponse;
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::Mutex;
+
+/// Tracks one streamed LLM call and routes its chunks through the interceptors
+pub struct LLMStreamMonitor {
+    registry: Arc<Mutex<InterceptorRegistry>>,
+    context: LLMCallContext,
+    started: Instant,
+    chunk_count: u64,
+    usage: StreamUsageTracker,
+    in_flight: Option<WorkGuard>,
+}
+
+impl LLMStreamMonitor {
+    /// Run pre-execution interception for a streamed call
+    ///
+    /// The context is tagged with a stream id so interceptors can correlate
+    /// chunks with the start and completion of the call. Returns an error if
+    /// any interceptor blocks the call; rewritten arguments or prompt replace
+    /// the context's own, and the call should be streamed with [`Self::args`].
+    pub async fn start(
+        registry: Arc<Mutex<InterceptorRegistry>>,
+        mut context: LLMCallContext,
+    ) -> Result<Self> {
+        if let Value::Object(metadata) = &mut context.metadata {
+            metadata.insert(
+                STREAM_ID_METADATA_KEY.to_string(),
+                Value::String(uuid::Uuid::new_v4().to_string()),
+            );
+        }
+        let decision = registry.lock().await.intercept_llm_call(&context).await?;
+        if let InterceptorDecision::Modify { args, prompt } = decision {
+            if let Some(args) = args {
+                context.args = args;
+            }
+            if let Some(prompt) = prompt {
+                context.prompt = prompt;
+            }
+        }
+        let usage = StreamUsageTracker::new(Arc::new(HeuristicTokenizer), &context.prompt);
+        Ok(Self {
+            registry,
+            context,
+            started: Instant::now(),
+            chunk_count: 0,
+            usage,
+            in_flight: None,
+        })
+    }
+
+    /// The arguments the function should be streamed with
+    pub fn args(&self) -> &Value {
+        &self.context.args
+    }
+
+    /// List the call as in flight until the stream finishes
+    pub fn tracked_by(mut self, in_flight: WorkGuard) -> Self {
+        self.in_flight = Some(in_flight);
+        self
+    }
+
+    /// Estimate token usage with `estimator` instead of the default heuristic
+    pub fn with_token_estimator(mut self, estimator: Arc<dyn TokenEstimator>) -> Self {
+        self.usage = StreamUsageTracker::new(estimator, &self.context.prompt);
+        self
+    }
+
+    /// Estimate token usage with the tokenizer `tokenizers` holds for the call's model
+    pub fn with_tokenizers(self, tokenizers: &TokenizerRegistry) -> Self {
+        let estimator = tokenizers.estimator_for(Some(&self.context.model));
+        self.with_token_estimator(estimator)
+    }
+
+    /// Pass a chunk to the interceptors
+    ///
+    /// Returns an error if an interceptor aborts the stream.
+    pub async fn on_chunk(&mut self, partial: &Value) -> Result<()> {
+        let before = self.usage.estimated().completion_tokens;
+        let usage = self.usage.observe(partial);
+        metrics::record_llm_stream_tokens(
+            &self.context.function_name,
+            usage.completion_tokens - before,
+        );
+        let chunk = LLMChunk {
+            index: self.chunk_count,
+            elapsed_ms: self.started.elapsed().as_millis() as u64,
+            partial: partial.clone(),
+            usage,
+        };
+        self.chunk_count += 1;
+        self.registry
+            .lock()
+            .await
+            .intercept_llm_chunk(&self.context, &chunk)
+            .await?;
+        Ok(())
+    }
+
+    /// Number of chunks seen so far
+    pub fn chunk_count(&self) -> u64 {
+        self.chunk_count
+    }
+
+    /// Token usage estimated so far
+    pub fn estimated_usage(&self) -> TokenUsage {
+        self.usage.estimated()
+    }
+
+    /// Notify interceptors that the stream ended
+    ///
+    /// `reported` is the usage the provider sent with the final response; the
+    /// estimate stands in for it when the provider sent none.
+    pub async fn finish(mut self, result: &Result<Value>, reported: Option<TokenUsage>) {
+        let report = self.usage.reconcile(reported);
+        metrics::record_llm_tokens(
+            &self.context.function_name,
+            report.usage.prompt_tokens,
+            report.usage.completion_tokens,
+            report.source.as_str(),
+        );
+        if let Value::Object(metadata) = &mut self.context.metadata {
+            metadata.insert(USAGE_METADATA_KEY.to_string(), report.to_value());
+        }
+        let duration = self.started.elapsed();
+        metrics::record_llm_call(&self.context.function_name, &self.context.client, true, duration);
+        let duration_ms = duration.as_millis() as u64;
+        self.registry
+            .lock()
+            .await
+            .notify_llm_call_complete(&self.context, result, duration_ms)
+            .await;
+    }
+}
+
+/// Usage reported by the provider in the final response of a stream
+pub fn reported_usage(result: &baml_runtime::FunctionResult) -> Option<TokenUsage> {
+    match result.llm_response() {
+        LLMResponse::Success(response) => {
+            let prompt_tokens = response.metadata.prompt_tokens?;
+            let completion_tokens = response.metadata.output_tokens?;
+            Some(TokenUsage::new(prompt_tokens, completion_tokens))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,139 @@
+//! Console output from agent JavaScript
+//!
+//! The sandbox's `console.log`, `info`, `warn`, `error` and `debug` hand each
+//! line to the bridge, which emits it as a `tracing` event on the
+//! `baml_rt::console` target, tagged with the agent and with the context and
+//! task of the invocation that wrote it. [`ConsoleSink`]s registered on the
+//! bridge receive every line as well, e.g. to record it in provenance.
+
+use baml_rt_core::ids::{AgentId, ContextId, TaskId};
+use std::sync::{Arc, RwLock};
+
+/// Target of the `tracing` events console lines are emitted as
+pub const CONSOLE_TARGET: &str = "baml_rt::console";
+
+/// Which console method wrote a line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsoleLevel {
+    Debug,
+    Log,
+    Info,
+    Warn,
+    Error,
+}
+
+impl ConsoleLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConsoleLevel::Debug => "debug",
+            ConsoleLevel::Log => "log",
+            ConsoleLevel::Info => "info",
+            ConsoleLevel::Warn => "warn",
+            ConsoleLevel::Error => "error",
+        }
+    }
+
+    /// The console method named `method`; unknown methods log at `Log`
+    pub fn from_method(method: &str) -> Self {
+        match method {
+            "debug" => ConsoleLevel::Debug,
+            "info" => ConsoleLevel::Info,
+            "warn" => ConsoleLevel::Warn,
+            "error" => ConsoleLevel::Error,
+            _ => ConsoleLevel::Log,
+        }
+    }
+}
+
+/// One line written to the console
+#[derive(Debug, Clone)]
+pub struct ConsoleEntry {
+    pub level: ConsoleLevel,
+    /// The arguments, formatted and joined by spaces
+    pub message: String,
+    pub agent_id: AgentId,
+    pub agent_name: Option<String>,
+    /// Scope of the invocation that wrote the line; `None` at load time
+    pub context_id: Option<ContextId>,
+    pub task_id: Option<TaskId>,
+}
+
+impl ConsoleEntry {
+    /// Emit the line as a `tracing` event at the matching level
+    pub fn trace(&self) {
+        let agent_name = self.agent_name.as_deref().unwrap_or("");
+        let context_id = self.context_id.as_ref().map(ContextId::as_str).unwrap_or("");
+        let task_id = self.task_id.as_ref().map(TaskId::as_str).unwrap_or("");
+        macro_rules! emit {
+            ($level:ident) => {
+                tracing::$level!(
+                    target: CONSOLE_TARGET,
+                    agent_id = %self.agent_id,
+                    agent_name,
+                    context_id,
+                    task_id,
+                    console = self.level.as_str(),
+                    "{}",
+                    self.message
+                )
+            };
+        }
+        match self.level {
+            ConsoleLevel::Debug => emit!(debug),
+            ConsoleLevel::Log | ConsoleLevel::Info => emit!(info),
+            ConsoleLevel::Warn => emit!(warn),
+            ConsoleLevel::Error => emit!(error),
+        }
+    }
+}
+
+/// Receives the console lines of a bridge
+///
+/// Called on the QuickJS thread while the script waits, so implementations
+/// should hand slow work off rather than block.
+pub trait ConsoleSink: Send + Sync + 'static {
+    fn record(&self, entry: &ConsoleEntry);
+}
+
+/// Where a bridge's console lines go
+///
+/// Clones share their settings, so the host callback registered when the
+/// bridge is built sees sinks and names added later.
+#[derive(Clone, Default)]
+pub(crate) struct ConsoleRouter {
+    agent_name: Arc<RwLock<Option<String>>>,
+    sinks: Arc<RwLock<Vec<Arc<dyn ConsoleSink>>>>,
+}
+
+impl ConsoleRouter {
+    pub(crate) fn set_agent_name(&self, name: Option<String>) {
+        *self.agent_name.write().unwrap() = name;
+    }
+
+    pub(crate) fn add_sink(&self, sink: Arc<dyn ConsoleSink>) {
+        self.sinks.write().unwrap().push(sink);
+    }
+
+    pub(crate) fn route(
+        &self,
+        level: ConsoleLevel,
+        message: String,
+        agent_id: AgentId,
+        context_id: Option<ContextId>,
+        task_id: Option<TaskId>,
+    ) {
+        let entry = ConsoleEntry {
+            level,
+            message,
+            agent_id,
+            agent_name: self.agent_name.read().unwrap().clone(),
+            context_id,
+            task_id,
+        };
+        entry.trace();
+        let sinks = self.sinks.read().unwrap().clone();
+        for sink in sinks {
+            sink.record(&entry);
+        }
+    }
+}
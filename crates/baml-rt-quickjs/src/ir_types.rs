@@ -0,0 +1,77 @@
+//! Function signatures read from the BAML IR.
+//!
+//! BAML types map onto [`BamlType`] one for one, with two exceptions: a
+//! literal is treated as its primitive type (a string literal becomes a
+//! one-value enum), and types the runtime cannot check (media, type aliases,
+//! a class that refers back to itself) become [`BamlType::Any`].
+
+use baml_rt_core::types::{BamlType, FunctionSignature, ObjectField};
+use baml_types::{FieldType, LiteralValue, TypeValue};
+use internal_baml_core::ir::{IRHelper, IntermediateRepr};
+
+/// The parameters and return type `function_name` declares in `ir`.
+pub(crate) fn function_signature(ir: &IntermediateRepr, function_name: &str) -> Option<FunctionSignature> {
+    let function = ir.find_function(function_name).ok()?;
+    let mut classes = Vec::new();
+    let parameters = function
+        .inputs()
+        .iter()
+        .map(|(name, ty)| ObjectField {
+            name: name.clone(),
+            ty: baml_type(ir, ty, &mut classes),
+        })
+        .collect();
+    Some(FunctionSignature {
+        name: function_name.to_string(),
+        parameters,
+        output_type: baml_type(ir, function.output(), &mut classes),
+    })
+}
+
+/// `ty` as a [`BamlType`]; `classes` holds the classes being expanded, so a
+/// recursive class stops at its first self-reference.
+fn baml_type(ir: &IntermediateRepr, ty: &FieldType, classes: &mut Vec<String>) -> BamlType {
+    match ty {
+        FieldType::Primitive(TypeValue::String) => BamlType::String,
+        FieldType::Primitive(TypeValue::Int) => BamlType::Int,
+        FieldType::Primitive(TypeValue::Float) => BamlType::Float,
+        FieldType::Primitive(TypeValue::Bool) => BamlType::Bool,
+        FieldType::Primitive(TypeValue::Null) => BamlType::Null,
+        FieldType::Literal(LiteralValue::String(value)) => BamlType::Enum(vec![value.clone()]),
+        FieldType::Literal(LiteralValue::Int(_)) => BamlType::Int,
+        FieldType::Literal(LiteralValue::Bool(_)) => BamlType::Bool,
+        FieldType::Enum(name) => match ir.find_enum(name) {
+            Ok(walker) => BamlType::Enum(walker.walk_values().map(|value| value.name().to_string()).collect()),
+            Err(_) => BamlType::Any,
+        },
+        FieldType::Class(name) => {
+            if classes.contains(name) {
+                return BamlType::Any;
+            }
+            let Ok(walker) = ir.find_class(name) else {
+                return BamlType::Any;
+            };
+            classes.push(name.clone());
+            let fields = walker
+                .walk_fields()
+                .map(|field| ObjectField {
+                    name: field.name().to_string(),
+                    ty: baml_type(ir, field.r#type(), classes),
+                })
+                .collect();
+            classes.pop();
+            BamlType::Object(fields)
+        }
+        FieldType::List(item) => BamlType::List(Box::new(baml_type(ir, item, classes))),
+        FieldType::Map(key, value) => BamlType::Map(
+            Box::new(baml_type(ir, key, classes)),
+            Box::new(baml_type(ir, value, classes)),
+        ),
+        FieldType::Optional(inner) => BamlType::Optional(Box::new(baml_type(ir, inner, classes))),
+        FieldType::Union(members) => {
+            BamlType::Union(members.iter().map(|member| baml_type(ir, member, classes)).collect())
+        }
+        FieldType::WithMetadata { base, .. } => baml_type(ir, base, classes),
+        _ => BamlType::Any,
+    }
+}
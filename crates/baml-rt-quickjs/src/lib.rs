@@ -1,17 +1,24 @@
 //! BAML runtime with QuickJS integration.
 
+pub mod active_work;
 pub mod baml;
 pub mod baml_collector;
 pub mod baml_execution;
 pub mod baml_pre_execution;
+pub mod baml_stream;
+pub mod console;
 pub mod context;
+mod ir_types;
 pub mod js_value_converter;
 pub mod quickjs_bridge;
 pub mod runtime;
 pub mod traits;
+pub mod ts_gen;
 
-pub use baml::BamlRuntimeManager;
-pub use quickjs_bridge::QuickJSBridge;
+pub use active_work::{ActiveWork, WorkTracker};
+pub use baml::{BamlRuntimeManager, SchemaReload};
+pub use console::{ConsoleEntry, ConsoleLevel, ConsoleSink};
+pub use quickjs_bridge::{HeapStats, QuickJSBridge};
 pub use runtime::{QuickJSConfig, Runtime, RuntimeBuilder, RuntimeConfig};
 pub use context::{BamlContext, ContextMetadata};
 pub use traits::{BamlFunctionExecutor, BamlGateway, JsRuntimeHost, SchemaLoader, ToolRegistryTrait};
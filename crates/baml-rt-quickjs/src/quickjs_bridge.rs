@@ -3,27 +3,107 @@
 //! This module maps BAML function calls (executed in Rust) to QuickJS,
 //! allowing JavaScript code to invoke BAML functions.
 
-use crate::baml::BamlRuntimeManager;
-use baml_rt_core::{BamlRtError, Result};
+use crate::baml::{BamlRuntimeManager, SchemaReload};
+use crate::console::{ConsoleLevel, ConsoleRouter, ConsoleSink};
+use baml_rt_core::{BamlRtError, ResourceLimitKind, Result};
 use crate::js_value_converter::value_to_js_value_facade;
 use baml_rt_core::correlation;
 use baml_rt_core::context;
-use baml_rt_core::ids::{ContextId, ExternalId, MessageId, TaskId};
+use baml_rt_core::context::{RuntimeScope, ScopeStack};
+use baml_rt_core::ids::{AgentId, ContextId, ExternalId, MessageId, TaskId};
+use baml_rt_core::tokens::TokenizerRegistry;
 use baml_rt_tools::{ToolSessionId, ToolStep};
 use quickjs_runtime::builder::QuickJsRuntimeBuilder;
 use quickjs_runtime::facades::QuickJsRuntimeFacade;
+use quickjs_runtime::jsutils::JsError;
 use quickjs_runtime::jsutils::Script;
 use quickjs_runtime::quickjsrealmadapter::QuickJsRealmAdapter;
 use quickjs_runtime::values::JsValueFacade;
-use serde_json::{json, Value};
 use serde::Serialize;
+use serde_json::{json, Value};
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+use tracing::Instrument;
+
+/// Where `__baml_emit_chunk` sends the chunks of the chunked invocation in
+/// progress, if any.
+type ChunkSink = Arc<std::sync::Mutex<Option<mpsc::UnboundedSender<Value>>>>;
+
+/// Scope for a host call made from JS
+///
+/// Ids JS passed explicitly win; the rest are inherited from the innermost
+/// invocation in progress, unless JS named a different context.
+fn host_call_scope(
+    scopes: &ScopeStack,
+    agent_id: &AgentId,
+    context_id: Option<ContextId>,
+    message_id: Option<MessageId>,
+    task_id: Option<TaskId>,
+) -> RuntimeScope {
+    let enclosing = scopes.resolve();
+    let tenant = enclosing.as_ref().and_then(|scope| scope.tenant.clone());
+    let trace_context = enclosing.as_ref().and_then(|scope| scope.trace_context.clone());
+    let delegation_chain =
+        enclosing.as_ref().map(|scope| scope.delegation_chain.clone()).unwrap_or_default();
+    let inherited = enclosing
+        .filter(|scope| context_id.as_ref().is_none_or(|id| *id == scope.context_id));
+    let context_id = context_id
+        .or_else(|| inherited.as_ref().map(|scope| scope.context_id.clone()))
+        .unwrap_or_else(context::current_or_new);
+    let message_id =
+        message_id.or_else(|| inherited.as_ref().and_then(|scope| scope.message_id.clone()));
+    let task_id = task_id.or_else(|| inherited.as_ref().and_then(|scope| scope.task_id.clone()));
+    let parent_context_id = inherited.and_then(|scope| scope.parent_context_id);
+    RuntimeScope::new(context_id, agent_id.clone(), message_id, task_id)
+        .with_tenant(tenant)
+        .with_trace_context(trace_context)
+        .with_parent_context(parent_context_id)
+        .with_delegation_chain(delegation_chain)
+}
+
+/// The ids of `scope` as the JS object `__baml_in_scope` takes
+fn js_scope_frame(scope: Option<&RuntimeScope>) -> Result<String> {
+    let frame = json!({
+        "context_id": scope.map(|scope| scope.context_id.clone()),
+        "message_id": scope.and_then(|scope| scope.message_id.clone()),
+        "task_id": scope.and_then(|scope| scope.task_id.clone()),
+    });
+    serde_json::to_string(&frame).map_err(BamlRtError::Json)
+}
+
+/// The limit a QuickJS error message reports running out of, if any
+fn resource_limit_kind(message: &str) -> Option<ResourceLimitKind> {
+    match message {
+        "out of memory" => Some(ResourceLimitKind::Memory),
+        "stack overflow" | "Maximum call stack size exceeded" => Some(ResourceLimitKind::Stack),
+        _ => None,
+    }
+}
 
-/// Helper function to serialize an ID to a JSON string for JavaScript prelude code.
-fn serialize_id(id: &impl Serialize) -> Result<String> {
-    serde_json::to_string(id).map_err(BamlRtError::Json)
+/// Map an `{ error, rejection? }` result from a JS function call to an error,
+/// keeping the thrown value's name, message and stack when JS reported them.
+fn invocation_error(function_name: &str, result: &serde_json::Map<String, Value>) -> BamlRtError {
+    let field = |value: &Value, key: &str| value.get(key).and_then(Value::as_str).map(str::to_string);
+    if let Some(message) = result.get("rejection").and_then(|rejection| field(rejection, "message"))
+        && let Some(kind) = resource_limit_kind(&message)
+    {
+        return BamlRtError::ResourceLimit { kind, detail: format!("{}: {}", function_name, message) };
+    }
+    match result.get("rejection") {
+        Some(rejection) => BamlRtError::JsRejection {
+            function: function_name.to_string(),
+            name: field(rejection, "name").unwrap_or_else(|| "Error".to_string()),
+            message: field(rejection, "message").unwrap_or_default(),
+            stack: field(rejection, "stack"),
+        },
+        None => BamlRtError::QuickJs(format!(
+            "JS function invocation error ({}): {}",
+            function_name,
+            result.get("error").and_then(Value::as_str).unwrap_or("unknown")
+        )),
+    }
 }
 
 fn tool_step_to_value(step: ToolStep) -> Value {
@@ -41,6 +121,97 @@ fn tool_step_to_value(step: ToolStep) -> Value {
     }
 }
 
+/// Interrupts JS that runs past `QuickJSConfig::max_execution_time`
+///
+/// The deadline is armed only while the bridge runs JS itself, evaluating code or
+/// draining promise jobs, so time spent waiting on host futures never counts.
+#[derive(Clone, Default)]
+struct ExecutionWatchdog {
+    limit: Option<Duration>,
+    state: Arc<std::sync::Mutex<WatchdogState>>,
+}
+
+#[derive(Default)]
+struct WatchdogState {
+    deadline: Option<std::time::Instant>,
+    tripped: bool,
+}
+
+impl ExecutionWatchdog {
+    fn new(limit: Option<Duration>) -> Self {
+        Self { limit, ..Self::default() }
+    }
+
+    /// Start the clock; it stops when the returned guard is dropped
+    fn arm(&self) -> WatchdogArm {
+        if let Some(limit) = self.limit {
+            self.state.lock().unwrap().deadline = Some(std::time::Instant::now() + limit);
+        }
+        WatchdogArm { watchdog: self.clone() }
+    }
+
+    /// Called by QuickJS while JS runs; `true` interrupts it
+    fn should_interrupt(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match state.deadline {
+            Some(deadline) if std::time::Instant::now() >= deadline => {
+                state.tripped = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether JS was interrupted since the last call
+    fn take_tripped(&self) -> bool {
+        std::mem::take(&mut self.state.lock().unwrap().tripped)
+    }
+
+    fn exceeded(&self) -> BamlRtError {
+        BamlRtError::ResourceLimit {
+            kind: ResourceLimitKind::ExecutionTime,
+            detail: format!(
+                "script ran longer than {}ms",
+                self.limit.unwrap_or_default().as_millis()
+            ),
+        }
+    }
+
+    /// The limit error behind a failed script, if a limit caused it
+    fn limit_error(&self, error: &JsError) -> Option<BamlRtError> {
+        if self.take_tripped() {
+            return Some(self.exceeded());
+        }
+        resource_limit_kind(error.get_message()).map(|kind| BamlRtError::ResourceLimit {
+            kind,
+            detail: error.get_message().to_string(),
+        })
+    }
+}
+
+struct WatchdogArm {
+    watchdog: ExecutionWatchdog,
+}
+
+impl Drop for WatchdogArm {
+    fn drop(&mut self) {
+        self.watchdog.state.lock().unwrap().deadline = None;
+    }
+}
+
+/// Heap usage of a QuickJS runtime, in bytes and live object counts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct HeapStats {
+    /// Bytes allocated by the runtime
+    pub malloc_size: i64,
+    /// The runtime's memory limit; -1 when unlimited
+    pub malloc_limit: i64,
+    /// Bytes in use by JS values and the engine's own structures
+    pub memory_used_size: i64,
+    pub object_count: i64,
+    pub function_count: i64,
+}
+
 /// Bridge between QuickJS JavaScript runtime and BAML functions
 /// 
 /// BAML functions execute in Rust. This bridge exposes them to QuickJS
@@ -50,6 +221,12 @@ pub struct QuickJSBridge {
     baml_manager: Arc<Mutex<BamlRuntimeManager>>,
     js_tools: HashSet<String>, // Track JavaScript-only tools
     agent_id: baml_rt_core::ids::AgentId, // REQUIRED - agent_id is never optional
+    scopes: ScopeStack, // Scopes of the invocations in progress, for host callbacks
+    promise_timeout: Duration,
+    watchdog: ExecutionWatchdog,
+    console: ConsoleRouter,
+    chunk_sink: ChunkSink,
+    next_eval_id: u64,
 }
 
 impl QuickJSBridge {
@@ -81,6 +258,8 @@ impl QuickJSBridge {
             max_stack_size = ?config.max_stack_size,
             gc_threshold = ?config.gc_threshold,
             gc_interval = ?config.gc_interval,
+            promise_timeout = ?config.promise_timeout,
+            max_execution_time = ?config.max_execution_time,
             "Initializing QuickJS bridge with configuration"
         );
 
@@ -102,6 +281,12 @@ impl QuickJSBridge {
         if let Some(interval) = config.gc_interval {
             builder = builder.gc_interval(interval);
         }
+
+        let watchdog = ExecutionWatchdog::new(config.max_execution_time);
+        if config.max_execution_time.is_some() {
+            let watchdog = watchdog.clone();
+            builder = builder.set_interrupt_handler(move |_rt| watchdog.should_interrupt());
+        }
         
         let runtime = builder.build();
 
@@ -111,55 +296,116 @@ impl QuickJSBridge {
             baml_manager,
             js_tools: HashSet::new(),
             agent_id,
+            scopes: ScopeStack::new(),
+            promise_timeout: config
+                .promise_timeout
+                .unwrap_or(crate::runtime::DEFAULT_PROMISE_TIMEOUT),
+            watchdog,
+            console: ConsoleRouter::default(),
+            chunk_sink: ChunkSink::default(),
+            next_eval_id: 0,
         };
 
         // Initialize sandbox - remove dangerous globals and implement safe console
         bridge.initialize_sandbox().await?;
+        bridge.register_chunk_emitter().await?;
+        let tokenizers = bridge.baml_manager.lock().await.tokenizers();
+        bridge.register_token_helpers(tokenizers).await?;
 
         Ok(bridge)
     }
 
+    /// Name the agent in console output
+    pub fn set_agent_name(&self, name: impl Into<String>) {
+        self.console.set_agent_name(Some(name.into()));
+    }
+
+    /// Also hand every console line to `sink`
+    pub fn add_console_sink(&self, sink: Arc<dyn ConsoleSink>) {
+        self.console.add_sink(sink);
+    }
+
+    /// Current heap usage, read on the JS event loop
+    pub fn heap_stats(&self) -> HeapStats {
+        let usage = self.runtime.exe_rt_task_in_event_loop(|rt| rt.memory_usage());
+        HeapStats {
+            malloc_size: usage.malloc_size,
+            malloc_limit: usage.malloc_limit,
+            memory_used_size: usage.memory_used_size,
+            object_count: usage.obj_count,
+            function_count: usage.js_func_count,
+        }
+    }
+
     /// Initialize the sandbox environment
     /// 
     /// This removes dangerous globals and modules, and implements a safe console API.
-    /// Only console.log is available - no filesystem, network, or other I/O access.
+    /// Console methods are the only output - no filesystem, network, or other I/O access.
     async fn initialize_sandbox(&mut self) -> Result<()> {
         tracing::info!("Initializing QuickJS sandbox environment");
 
+        let console = self.console.clone();
+        let agent_id = self.agent_id.clone();
+        let scopes = self.scopes.clone();
+        self.runtime.set_function(
+            &[],
+            "__console_emit",
+            move |_realm: &QuickJsRealmAdapter, args: Vec<JsValueFacade>| -> std::result::Result<JsValueFacade, quickjs_runtime::jsutils::JsError> {
+                let text = |index: usize| {
+                    args.get(index).filter(|value| value.is_string()).map(|value| value.get_str().to_string())
+                };
+                let level = ConsoleLevel::from_method(text(0).as_deref().unwrap_or("log"));
+                let scope = scopes.resolve();
+                console.route(
+                    level,
+                    text(1).unwrap_or_default(),
+                    agent_id.clone(),
+                    scope.as_ref().map(|scope| scope.context_id.clone()),
+                    scope.and_then(|scope| scope.task_id),
+                );
+                Ok(value_to_js_value_facade(Value::Null))
+            },
+        ).map_err(|e| BamlRtError::QuickJsWithSource {
+            context: "Failed to register console bridge".to_string(),
+            source: Box::new(e),
+        })?;
+
         // Initialize safe console and ensure dangerous globals aren't available
-        // QuickJS by default doesn't expose require, fetch, etc., but we ensure console.log works safely
+        // QuickJS by default doesn't expose require, fetch, etc.; console output is
+        // routed to the host, which logs it through tracing.
         let sandbox_code = r#"
             (function() {
-                // Implement safe console object - only log methods, no I/O
-                // QuickJS handles console output through its runtime, preventing direct system I/O
-                globalThis.console = {
-                    log: function() {
-                        // console.log output goes to QuickJS runtime logs
-                        // No filesystem or network access
-                        var args = arguments;
-                        for (var i = 0; i < args.length; i++) {
-                            var arg = args[i];
-                            if (typeof arg === 'object') {
-                                try {
-                                    JSON.stringify(arg);
-                                } catch (e) {
-                                    String(arg);
-                                }
-                            }
+                function format(value) {
+                    if (typeof value === 'string') {
+                        return value;
+                    }
+                    if (value instanceof Error) {
+                        return value.stack ? value.name + ': ' + value.message + '\n' + value.stack : String(value);
+                    }
+                    if (typeof value === 'object' && value !== null) {
+                        try {
+                            return JSON.stringify(value);
+                        } catch (e) {
+                            return String(value);
                         }
-                    },
-                    info: function() {
-                        globalThis.console.log.apply(globalThis.console, arguments);
-                    },
-                    warn: function() {
-                        globalThis.console.log.apply(globalThis.console, arguments);
-                    },
-                    error: function() {
-                        globalThis.console.log.apply(globalThis.console, arguments);
-                    },
-                    debug: function() {
-                        globalThis.console.log.apply(globalThis.console, arguments);
                     }
+                    return String(value);
+                }
+                function method(level) {
+                    return function() {
+                        var parts = [];
+                        for (var i = 0; i < arguments.length; i++) {
+                            parts.push(format(arguments[i]));
+                        }
+                        __console_emit(level, parts.join(' '));
+                    };
+                }
+                globalThis.console = {
+                    log: method('log'),
+                    info: method('info'),
+                    warn: method('warn'),
+                    error: method('error'),
+                    debug: method('debug')
                 };
             })();
         "#;
@@ -177,6 +423,50 @@ impl QuickJSBridge {
         Ok(())
     }
 
+    /// Expose the runtime's tokenizers as `host.tokens.count(text, model)`
+    ///
+    /// Counting is synchronous and returns a number. Non-string `text` is counted
+    /// as its JSON; `model` may be omitted to use the default tokenizer.
+    async fn register_token_helpers(&mut self, tokenizers: Arc<TokenizerRegistry>) -> Result<()> {
+        self.runtime.set_function(
+            &[],
+            "__tokens_count",
+            move |_realm: &QuickJsRealmAdapter, args: Vec<JsValueFacade>| -> std::result::Result<JsValueFacade, quickjs_runtime::jsutils::JsError> {
+                let text = match args.first() {
+                    Some(text) if text.is_string() => text.get_str().to_string(),
+                    _ => return Err(quickjs_runtime::jsutils::JsError::new_str("First argument must be a string (text)")),
+                };
+                let model = args.get(1).filter(|model| model.is_string()).map(|model| model.get_str().to_string());
+                let count = tokenizers.count(&text, model.as_deref());
+                Ok(value_to_js_value_facade(json!(count)))
+            },
+        ).map_err(|e| BamlRtError::QuickJsWithSource {
+            context: "Failed to register token count function".to_string(),
+            source: Box::new(e),
+        })?;
+
+        let host_code = r#"
+            globalThis.host = globalThis.host || {};
+            globalThis.host.tokens = {
+                count: function(text, model) {
+                    const value = typeof text === 'string' ? text : JSON.stringify(text ?? '');
+                    return __tokens_count(value, typeof model === 'string' ? model : null);
+                }
+            };
+        "#;
+        let script = Script::new("register_host_tokens.js", host_code);
+        self.runtime
+            .eval(None, script)
+            .await
+            .map_err(|e| BamlRtError::QuickJsWithSource {
+                context: "Failed to register host.tokens".to_string(),
+                source: Box::new(e),
+            })?;
+
+        tracing::debug!("Registered host.tokens.count");
+        Ok(())
+    }
+
     /// Register all BAML functions with the QuickJS context
     /// 
     /// This maps Rust BAML functions to JavaScript callables.
@@ -204,6 +494,32 @@ impl QuickJSBridge {
         Ok(())
     }
 
+    /// Bring the injected BAML functions in line with a reloaded schema:
+    /// register the added functions and remove the removed ones. Changed
+    /// functions need nothing, as their wrappers call through by name.
+    pub async fn refresh_baml_functions(&mut self, reload: &SchemaReload) -> Result<()> {
+        for function_name in &reload.added {
+            self.register_single_function(function_name).await?;
+            self.register_single_stream_function(function_name).await?;
+        }
+        for function_name in &reload.removed {
+            let js_code = format!(
+                "delete globalThis[{name}]; delete globalThis[{name} + \"Stream\"];",
+                name = Value::String(function_name.clone())
+            );
+            let script = Script::new("unregister_function.js", &js_code);
+            self.runtime
+                .eval(None, script)
+                .await
+                .map_err(|e| BamlRtError::QuickJsWithSource {
+                    context: "Failed to unregister function".to_string(),
+                    source: Box::new(e),
+                })?;
+            tracing::debug!(function = function_name.as_str(), "Removed function from QuickJS");
+        }
+        Ok(())
+    }
+
     /// Register all tool functions with QuickJS
     async fn register_tool_functions(&mut self) -> Result<()> {
         tracing::info!("Registering tool functions with QuickJS");
@@ -256,6 +572,7 @@ impl QuickJSBridge {
     async fn register_tool_invoke_helper(&mut self) -> Result<()> {
         let manager_clone = self.baml_manager.clone();
         let agent_id = self.agent_id.clone(); // REQUIRED - capture agent_id from bridge
+        let scopes = self.scopes.clone();
 
         // Register __tool_invoke for Rust tools (low-level helper)
         self.runtime.set_function(
@@ -308,9 +625,8 @@ impl QuickJSBridge {
                 let tool_name_clone = tool_name.clone();
                 let manager_for_promise = manager_clone.clone();
                 let correlation_id = correlation::current_or_new();
-                let context_id = context_id_arg.unwrap_or_else(context::current_or_new);
                 // agent_id is REQUIRED and captured from bridge - never optional
-                let scope = context::RuntimeScope::new(context_id, agent_id.clone(), message_id_arg, task_id_arg);
+                let scope = host_call_scope(&scopes, &agent_id, context_id_arg, message_id_arg, task_id_arg);
 
                 Ok(JsValueFacade::new_promise::<JsValueFacade, _, ()>(async move {
                     correlation::with_correlation_id(correlation_id, async move {
@@ -342,6 +658,7 @@ impl QuickJSBridge {
         // Register __tool_from_baml_result for executing tools based on BAML union output.
         let manager_clone = self.baml_manager.clone();
         let agent_id = self.agent_id.clone(); // REQUIRED - capture agent_id from bridge
+        let scopes = self.scopes.clone();
         self.runtime.set_function(
             &[],
             "__tool_from_baml_result",
@@ -362,16 +679,13 @@ impl QuickJSBridge {
 
                 let manager_for_promise = manager_clone.clone();
                 let correlation_id = correlation::current_or_new();
-                let context_id = args
-                    .get(1)
-                    .and_then(|value| {
-                        if value.is_string() {
-                            ContextId::parse_temporal(value.get_str())
-                        } else {
-                            None
-                        }
-                    })
-                    .unwrap_or_else(context::current_or_new);
+                let context_id = args.get(1).and_then(|value| {
+                    if value.is_string() {
+                        ContextId::parse_temporal(value.get_str())
+                    } else {
+                        None
+                    }
+                });
                 let message_id = args.get(2).and_then(|value| {
                     if value.is_string() {
                         Some(MessageId::from_external(ExternalId::new(value.get_str())))
@@ -387,7 +701,7 @@ impl QuickJSBridge {
                     }
                 });
                 // agent_id is REQUIRED and captured from bridge - never optional
-                let scope = context::RuntimeScope::new(context_id, agent_id.clone(), message_id, task_id);
+                let scope = host_call_scope(&scopes, &agent_id, context_id, message_id, task_id);
 
                 Ok(JsValueFacade::new_promise::<JsValueFacade, _, ()>(async move {
                     correlation::with_correlation_id(correlation_id, async move {
@@ -442,6 +756,7 @@ impl QuickJSBridge {
     async fn register_tool_session_helpers(&mut self) -> Result<()> {
         let manager_clone = self.baml_manager.clone();
         let agent_id = self.agent_id.clone();
+        let scopes = self.scopes.clone();
 
         self.runtime.set_function(
             &[],
@@ -482,8 +797,7 @@ impl QuickJSBridge {
 
                 let manager_for_promise = manager_clone.clone();
                 let correlation_id = correlation::current_or_new();
-                let context_id = context_id_arg.unwrap_or_else(context::current_or_new);
-                let scope = context::RuntimeScope::new(context_id, agent_id.clone(), message_id_arg, task_id_arg);
+                let scope = host_call_scope(&scopes, &agent_id, context_id_arg, message_id_arg, task_id_arg);
 
                 Ok(JsValueFacade::new_promise::<JsValueFacade, _, ()>(async move {
                     correlation::with_correlation_id(correlation_id, async move {
@@ -688,6 +1002,7 @@ impl QuickJSBridge {
     async fn register_baml_invoke_helper(&mut self) -> Result<()> {
         let manager_clone = self.baml_manager.clone();
         let agent_id = self.agent_id.clone(); // REQUIRED - capture agent_id from bridge
+        let scopes = self.scopes.clone();
         
         // Register a native Rust function that JavaScript can call
         // This function will handle the async BAML execution using promises
@@ -745,9 +1060,8 @@ impl QuickJSBridge {
                         None
                     }
                 });
-                let context_id = context_id_arg.unwrap_or_else(context::current_or_new);
                 // agent_id is REQUIRED and captured from bridge - never optional
-                let scope = context::RuntimeScope::new(context_id, agent_id.clone(), message_id_arg, task_id_arg);
+                let scope = host_call_scope(&scopes, &agent_id, context_id_arg, message_id_arg, task_id_arg);
 
                 // Create a promise that will execute the BAML call asynchronously
                 let func_name_clone = func_name.clone();
@@ -797,16 +1111,103 @@ impl QuickJSBridge {
         // Register a helper that synchronously extracts promise results
         // This will be used by evaluate() to handle promises
         let js_code = r#"
+            // Describe a thrown value without throwing again, whatever was thrown
+            globalThis.__describeError = function(e) {
+                try {
+                    if (e instanceof Error) {
+                        return { name: String(e.name), message: String(e.message), stack: e.stack ? String(e.stack) : null };
+                    }
+                    return { name: 'Error', message: String(e), stack: null };
+                } catch (_) {
+                    return { name: 'Error', message: 'unprintable rejection', stack: null };
+                }
+            };
+
+            globalThis.__rejectionJson = function(e) {
+                const rejection = __describeError(e);
+                const text = e instanceof Error ? rejection.name + ': ' + rejection.message : rejection.message;
+                return JSON.stringify({ error: text, rejection: rejection });
+            };
+
             globalThis.__awaitAndStringify = async function(promise) {
                 try {
                     const result = await promise;
                     // Return the result directly, not wrapped in success notification
-                    return JSON.stringify(result);
+                    const json = JSON.stringify(result);
+                    return json === undefined ? 'null' : json;
                 } catch (e) {
-                    return JSON.stringify({ error: e.toString() });
+                    return __rejectionJson(e);
                 }
             };
-            
+
+            // Scopes of the invocations in progress, innermost last. The __baml_*
+            // scope globals mirror the innermost one, so code resuming after an
+            // await sees its own invocation's ids again once nested ones settle.
+            globalThis.__baml_scope_frames = [];
+            globalThis.__baml_apply_scope = function() {
+                const frames = globalThis.__baml_scope_frames;
+                const scope = frames.length > 0 ? frames[frames.length - 1] : {};
+                const ids = {
+                    __baml_context_id: scope.context_id,
+                    __baml_message_id: scope.message_id,
+                    __baml_task_id: scope.task_id
+                };
+                for (const key in ids) {
+                    if (ids[key] === undefined || ids[key] === null) {
+                        delete globalThis[key];
+                    } else {
+                        globalThis[key] = ids[key];
+                    }
+                }
+            };
+
+            // Run `run` with `scope` as the innermost scope until its result settles.
+            // Frames are removed by identity, so overlapping invocations may settle
+            // in any order.
+            globalThis.__baml_in_scope = function(scope, run) {
+                const frame = Object.assign({}, scope);
+                const frames = globalThis.__baml_scope_frames;
+                frames.push(frame);
+                __baml_apply_scope();
+                const exit = function() {
+                    const index = frames.lastIndexOf(frame);
+                    if (index !== -1) {
+                        frames.splice(index, 1);
+                    }
+                    __baml_apply_scope();
+                };
+                let result;
+                try {
+                    result = run();
+                } catch (e) {
+                    exit();
+                    throw e;
+                }
+                return Promise.resolve(result).finally(exit);
+            };
+
+            // Settled results of promises returned to evaluate(), keyed by eval id.
+            // Ids whose caller timed out are abandoned so a late settlement is dropped
+            // instead of being picked up by a later call.
+            globalThis.__eval_results = new Map();
+            globalThis.__eval_abandoned = new Set();
+            globalThis.__settleEval = async function(id, promise) {
+                let out;
+                try {
+                    const result = await promise;
+                    out = typeof result === 'string' ? result : JSON.stringify(result);
+                    if (out === undefined) {
+                        out = 'null';
+                    }
+                } catch (e) {
+                    out = __rejectionJson(e);
+                }
+                if (globalThis.__eval_abandoned.delete(id)) {
+                    return;
+                }
+                globalThis.__eval_results.set(id, out);
+            };
+
             // Helper to synchronously check if a value is a promise
             globalThis.__isPromise = function(value) {
                 return value && typeof value.then === 'function';
@@ -822,7 +1223,7 @@ impl QuickJSBridge {
                 source: Box::new(e),
             })?;
         
-        tracing::debug!("Registered __awaitAndStringify helper function");
+        tracing::debug!("Registered __awaitAndStringify and __baml_in_scope helper functions");
         Ok(())
     }
 
@@ -932,10 +1333,42 @@ impl QuickJSBridge {
         self.js_tools.contains(name)
     }
 
+    /// Register `__baml_emit_chunk`, which hands one JSON chunk to the sink of
+    /// [`Self::invoke_js_function_chunked`]
+    async fn register_chunk_emitter(&mut self) -> Result<()> {
+        let sink = self.chunk_sink.clone();
+        self.runtime.set_function(
+            &[],
+            "__baml_emit_chunk",
+            move |_realm: &QuickJsRealmAdapter, args: Vec<JsValueFacade>| -> std::result::Result<JsValueFacade, quickjs_runtime::jsutils::JsError> {
+                let chunk_json = match args.first() {
+                    Some(value) if value.is_string() => value.get_str().to_string(),
+                    _ => return Err(quickjs_runtime::jsutils::JsError::new_str("Expected 1 argument: chunk JSON string")),
+                };
+                let chunk: Value = serde_json::from_str(&chunk_json)
+                    .map_err(|e| quickjs_runtime::jsutils::JsError::new_str(&format!("Failed to parse chunk JSON: {}", e)))?;
+                let sink = sink.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                match sink.as_ref() {
+                    // A closed receiver means the consumer went away; the rest is dropped.
+                    Some(sender) => {
+                        let _ = sender.send(chunk);
+                        Ok(value_to_js_value_facade(Value::Null))
+                    }
+                    None => Err(quickjs_runtime::jsutils::JsError::new_str("No chunked invocation in progress")),
+                }
+            },
+        ).map_err(|e| BamlRtError::QuickJsWithSource {
+            context: "Failed to register __baml_emit_chunk".to_string(),
+            source: Box::new(e),
+        })?;
+        Ok(())
+    }
+
     /// Register a helper function for streaming BAML function execution
     async fn register_baml_stream_helper(&mut self) -> Result<()> {
         let manager_clone = self.baml_manager.clone();
         let agent_id = self.agent_id.clone(); // REQUIRED - capture agent_id from bridge
+        let scopes = self.scopes.clone();
         
         // Register a native Rust function that JavaScript can call for streaming
         self.runtime.set_function(
@@ -991,9 +1424,8 @@ impl QuickJSBridge {
                         None
                     }
                 });
-                let context_id = context_id_arg.unwrap_or_else(context::current_or_new);
                 // agent_id is REQUIRED and captured from bridge - never optional
-                let scope = context::RuntimeScope::new(context_id, agent_id.clone(), message_id_arg, task_id_arg);
+                let scope = host_call_scope(&scopes, &agent_id, context_id_arg, message_id_arg, task_id_arg);
 
                 // Create a promise that will execute the streaming BAML call
                 let manager_for_stream = manager_clone.clone();
@@ -1032,7 +1464,10 @@ impl QuickJSBridge {
 
                                 // Create the stream
                                 let manager = manager_for_stream.lock().await;
-                                let stream_result = manager.invoke_function_stream(&func_name_stream, args_json_stream);
+                                let _work = manager.work_tracker().begin_function(&func_name_stream, true);
+                                let stream_result = manager
+                                    .invoke_function_stream(&func_name_stream, args_json_stream)
+                                    .await;
                                 
                                 let executor_ref = match manager.executor.as_ref() {
                                     Some(exec) => exec,
@@ -1062,7 +1497,7 @@ impl QuickJSBridge {
                                 };
                                 
                                 // Create the stream
-                                let mut stream = match stream_result {
+                                let (mut stream, mut monitor) = match stream_result {
                                     Ok(s) => s,
                                     Err(e) => {
                                         drop(manager); // Release lock
@@ -1078,46 +1513,90 @@ impl QuickJSBridge {
                                 // because ctx_manager is a reference. For now, we'll collect all results
                                 // in the callback and then drop the lock.
                                 let env_vars = HashMap::new();
-                                let (final_result, _call_id) = {
-                                    stream.run(
-                                        None::<fn()>, // on_tick
-                                        Some(|result: baml_runtime::FunctionResult| {
-                                            // Extract incremental result and send it
-                                            // parsed() returns Option<Result<ResponseBamlValue, Error>>
-                                            if let Some(Ok(parsed)) = result.parsed()
-                                                && let Ok(parsed_value) =
-                                                    serde_json::to_value(parsed.serialize_partial())
-                                                && let Err(e) = tx.try_send(parsed_value)
-                                            {
-                                                tracing::warn!(error = ?e, "Stream channel try_send failed");
-                                            }
-                                        }),
-                                        &ctx_manager,
-                                        None, // type_builder
-                                        None, // client_registry
-                                        env_vars,
-                                    ).await
-                                };
-                                drop(manager); // Release lock after stream completes
-
-                                // Send final result
-                                match final_result {
-                                    Ok(result) => {
+                                // The runtime's chunk callback is synchronous, so chunks are
+                                // queued and handed to the interceptors while the stream runs.
+                                let (chunk_tx, mut chunk_rx) = mpsc::unbounded_channel::<serde_json::Value>();
+                                let run = stream.run(
+                                    None::<fn()>, // on_tick
+                                    Some(move |result: baml_runtime::FunctionResult| {
                                         // parsed() returns Option<Result<ResponseBamlValue, Error>>
                                         if let Some(Ok(parsed)) = result.parsed()
-                                            && let Ok(final_value) =
+                                            && let Ok(parsed_value) =
                                                 serde_json::to_value(parsed.serialize_partial())
-                                            && let Err(e) = tx.send(final_value).await
                                         {
-                                            tracing::warn!(error = ?e, "Stream channel send failed");
+                                            let _ = chunk_tx.send(parsed_value);
                                         }
-                                    }
-                                    Err(e) => {
-                                        let error_value = serde_json::json!({"error": format!("{}", e)});
-                                        if let Err(e) = tx.send(error_value).await {
-                                            tracing::warn!(error = ?e, "Stream channel send failed");
+                                    }),
+                                    &ctx_manager,
+                                    None, // type_builder
+                                    None, // client_registry
+                                    env_vars,
+                                );
+                                tokio::pin!(run);
+                                let mut aborted = None;
+                                let mut finished = None;
+                                loop {
+                                    let chunk = if finished.is_none() {
+                                        tokio::select! {
+                                            (final_result, _call_id) = &mut run => {
+                                                finished = Some(final_result);
+                                                continue;
+                                            }
+                                            Some(chunk) = chunk_rx.recv() => chunk,
+                                        }
+                                    } else {
+                                        // Chunks queued before the stream ended still count.
+                                        match chunk_rx.try_recv() {
+                                            Ok(chunk) => chunk,
+                                            Err(_) => break,
                                         }
+                                    };
+                                    if let Some(monitor) = monitor.as_mut()
+                                        && let Err(e) = monitor.on_chunk(&chunk).await
+                                    {
+                                        // Dropping the run future below cancels the LLM request.
+                                        aborted = Some(e);
+                                        break;
                                     }
+                                    if let Err(e) = tx.try_send(chunk) {
+                                        tracing::warn!(error = ?e, "Stream channel try_send failed");
+                                    }
+                                }
+                                drop(run);
+                                drop(manager); // Release lock after stream completes
+
+                                let reported_usage = match &finished {
+                                    Some(Ok(result)) => crate::baml_stream::reported_usage(result),
+                                    _ => None,
+                                };
+
+                                let outcome: Result<Option<Value>> = match (aborted, finished) {
+                                    (Some(e), _) => Err(e),
+                                    // parsed() returns Option<Result<ResponseBamlValue, Error>>
+                                    (None, Some(Ok(result))) => Ok(match result.parsed() {
+                                        Some(Ok(parsed)) => serde_json::to_value(parsed.serialize_partial()).ok(),
+                                        _ => None,
+                                    }),
+                                    (None, Some(Err(e))) => Err(BamlRtError::BamlRuntime(e.to_string())),
+                                    (None, None) => Ok(None),
+                                };
+                                if let Some(monitor) = monitor {
+                                    let result = match &outcome {
+                                        Ok(value) => Ok(value.clone().unwrap_or(Value::Null)),
+                                        Err(e) => Err(BamlRtError::BamlRuntime(e.to_string())),
+                                    };
+                                    monitor.finish(&result, reported_usage).await;
+                                }
+
+                                // Send final result
+                                let final_value = match outcome {
+                                    Ok(value) => value,
+                                    Err(e) => Some(serde_json::json!({"error": format!("{}", e)})),
+                                };
+                                if let Some(final_value) = final_value
+                                    && let Err(e) = tx.send(final_value).await
+                                {
+                                    tracing::warn!(error = ?e, "Stream channel send failed");
                                 }
                                 }).await;
                             })
@@ -1234,39 +1713,67 @@ impl QuickJSBridge {
     /// Execute JavaScript code in the QuickJS context
     /// 
     /// The code should return a JSON string or a promise that resolves to a JSON string.
-    /// If code returns a promise, we wait for it to resolve.
+    ///
+    /// Semantics for promise-returning code:
+    /// - The code runs exactly once; a returned promise is tracked under a fresh eval id
+    ///   and awaited for at most the configured promise timeout
+    ///   ([`BamlRtError::PromiseTimeout`]). A promise that settles after its caller timed
+    ///   out is discarded rather than leaking into a later call.
+    /// - A rejection resolves to `{ error, rejection: { name, message, stack } }` instead
+    ///   of leaving the call pending.
+    /// - The job queue is drained before the code runs and again after it settles, so
+    ///   microtasks queued by one call never run in the middle of the next.
     pub async fn evaluate(&mut self, code: &str) -> Result<Value> {
         tracing::trace!(code = code, "Executing JavaScript code");
-        
-        // First, try executing the code directly (for synchronous code like assignments)
-        // This handles agent initialization code that just assigns to globalThis
+        self.drain_pending_jobs();
+        self.watchdog.take_tripped();
+
         // If code already has a return statement (like in an IIFE), execute as-is
-        // Otherwise, wrap it in an IIFE
+        // Otherwise, wrap it in an IIFE (preserves side effects for assignments)
         let code_trimmed = code.trim();
         let is_arrow_iife = code_trimmed.starts_with("(()") || code_trimmed.starts_with("(async ()");
         let already_wrapped = code_trimmed.starts_with("(function()")
             || code_trimmed.starts_with("(async function()")
             || is_arrow_iife;
-        
         let direct_code = if already_wrapped {
-            // Code is already wrapped in an IIFE - execute directly
             code.to_string()
         } else {
-            // Code needs wrapping - wrap in IIFE (preserves side effects for assignments)
             format!("(function() {{ {} }})()", code)
         };
-        let direct_script = Script::new("eval_direct.js", &direct_code);
-        let direct_result = self.runtime.eval(None, direct_script).await;
-        if let Err(e) = direct_result {
-            let message = e.to_string();
-            return Err(BamlRtError::QuickJsWithSource {
-                context: format!("Failed to execute JavaScript: {}", message),
-                source: Box::new(e),
-            });
-        }
-        
-        // If direct execution succeeds and returns a non-promise, we're done
-        let js_result = direct_result.expect("direct_result validated as Ok");
+
+        // Run the code through an indirect eval so its completion value can be inspected
+        // in JS: a promise is handed to __settleEval here instead of re-running the code.
+        let eval_id = self.next_eval_id;
+        self.next_eval_id += 1;
+        let tracked_code = format!(
+            r#"
+            (function() {{
+                const value = (0, eval)({});
+                if (__isPromise(value)) {{
+                    __settleEval({}, value);
+                }}
+                return value;
+            }})()
+            "#,
+            serde_json::to_string(&direct_code).map_err(BamlRtError::Json)?,
+            eval_id
+        );
+        let script = Script::new("eval_direct.js", &tracked_code);
+        let armed = self.watchdog.arm();
+        let js_result = self.runtime.eval(None, script).await;
+        drop(armed);
+        let js_result = js_result
+            .map_err(|e| {
+                if let Some(limit) = self.watchdog.limit_error(&e) {
+                    return limit;
+                }
+                let message = e.to_string();
+                BamlRtError::QuickJsWithSource {
+                    context: format!("Failed to execute JavaScript: {}", message),
+                    source: Box::new(e),
+                }
+            })?;
+
         if js_result.is_string() {
             // Got a string result - try parsing as JSON
             let json_str = js_result.get_str();
@@ -1276,129 +1783,96 @@ impl QuickJSBridge {
             // Not JSON - return the string wrapped in a result object
             return Ok(serde_json::json!({ "result": json_str }));
         }
-        // Not a string - might be undefined/null from assignment code
-        // Check if it's a promise
         let debug_str = format!("{:?}", js_result);
         if !debug_str.contains("Promise") && !debug_str.contains("JsPromise") {
             // Not a promise, code executed successfully (side effects happened)
             // Return empty object to indicate success without a value
             return Ok(serde_json::json!({}));
         }
-        
-        // Code returned a promise - need to await it and store result
-        // The code is already wrapped in (function() { ... })(), so execute it directly
-        // It returns a promise (from __awaitAndStringify), so we await it
-        let wrapped_code = format!(
+
+        let result = self.await_settled(eval_id).await;
+        self.drain_pending_jobs();
+        result
+    }
+
+    /// Poll for the settled result of the promise tracked under `eval_id`.
+    async fn await_settled(&mut self, eval_id: u64) -> Result<Value> {
+        let poll_span = tracing::trace_span!("baml_rt.poll_promise_resolution", eval_id);
+        let _poll_guard = poll_span.enter();
+        let deadline = tokio::time::Instant::now() + self.promise_timeout;
+        let check_code = format!(
             r#"
-            (async function() {{
-                try {{
-                    // Execute the code (it's already an IIFE) which returns a promise
-                    const codePromise = {};
-                    const result = await codePromise;
-                    // result is the JSON string from __awaitAndStringify
-                    globalThis.__eval_result = typeof result === 'string' ? result : JSON.stringify(result);
-                }} catch (error) {{
-                    globalThis.__eval_result = JSON.stringify({{ error: error.toString() }});
+            (function() {{
+                const out = globalThis.__eval_results.get({0});
+                if (out === undefined) {{
+                    return null;
                 }}
+                globalThis.__eval_results.delete({0});
+                return out;
             }})()
             "#,
-            code
+            eval_id
         );
-        
-        let script = Script::new("eval.js", &wrapped_code);
-        
-        // Execute the code - this will set __eval_result when the promise resolves
-        let js_result = self.runtime
-            .eval(None, script)
-            .await
-            .map_err(|e| {
-                let message = e.to_string();
-                BamlRtError::QuickJsWithSource {
-                    context: format!("Failed to execute JavaScript: {}", message),
+        let mut attempts: u64 = 0;
+
+        loop {
+            let check_script = Script::new("check_result.js", &check_code);
+            let check_result = self.runtime
+                .eval(None, check_script)
+                .await
+                .map_err(|e| BamlRtError::QuickJsWithSource {
+                    context: "Failed to check result".to_string(),
                     source: Box::new(e),
-                }
-            })?;
+                })?;
 
-        // Check if result is a string (synchronous code returned immediately)
-        if js_result.is_string() {
-            let json_str = js_result.get_str();
-            serde_json::from_str(json_str)
-                .map_err(BamlRtError::Json)
-        } else {
-            // Result is a promise - we need to wait for it to resolve
-            // The async IIFE will set globalThis.__eval_result when done
-            let debug_str = format!("{:?}", js_result);
-            
-            // Check if it's a promise
-            if debug_str.contains("Promise") || debug_str.contains("JsPromise") {
-                // Wait for the promise to resolve by running pending jobs in a loop
-                // and checking if __eval_result has been set
-                let poll_span = tracing::trace_span!("baml_rt.poll_promise_resolution");
-                let _poll_guard = poll_span.enter();
-                let mut attempts = 0;
-                const MAX_ATTEMPTS: u32 = 60000;
-                
-                loop {
-                    // Check if result is available (trace level - happens many times per resolution)
-                    let check_code = r#"
-                        (function() {
-                            if (typeof globalThis.__eval_result !== 'undefined') {
-                                return globalThis.__eval_result;
-                            }
-                            return null;
-                        })()
-                    "#;
-                    let check_script = Script::new("check_result.js", check_code);
-                    let check_result = self.runtime
-                        .eval(None, check_script)
-                        .await
-                        .map_err(|e| BamlRtError::QuickJsWithSource {
-                            context: "Failed to check result".to_string(),
-                            source: Box::new(e),
-                        })?;
-                    
-                    if check_result.is_string() {
-                        let result_str = check_result.get_str();
-                        // Clean up the global
-                        if let Err(e) = self.runtime.eval(None, Script::new("cleanup.js", "delete globalThis.__eval_result")).await {
-                            tracing::warn!(error = ?e, "Failed to clean up eval result");
-                        }
-                        tracing::trace!(attempts = attempts, "Promise resolved");
-                        return serde_json::from_str(result_str).map_err(BamlRtError::Json);
-                    }
-                    
-                    // Run pending jobs - this is how quickjs_runtime processes promises
-                    // The runtime automatically polls Rust futures backing promises
-                    self.runtime.exe_rt_task_in_event_loop(|rt| {
-                        rt.run_pending_jobs_if_any();
-                    });
-                    
-                    // Yield to Tokio to allow futures to progress
-                    tokio::task::yield_now().await;
-                    
-                    // Small delay to allow promise resolution
-                    tokio::time::sleep(tokio::time::Duration::from_millis(1)).await;
-                    
-                    attempts += 1;
-                    if attempts >= MAX_ATTEMPTS {
-                        // Clean up the global
-                        if let Err(e) = self.runtime.eval(None, Script::new("cleanup.js", "delete globalThis.__eval_result")).await {
-                            tracing::warn!(error = ?e, "Failed to clean up eval result");
-                        }
-                        return Err(BamlRtError::QuickJs(format!(
-                            "Promise did not resolve after {} attempts ({}ms)",
-                            MAX_ATTEMPTS,
-                            MAX_ATTEMPTS
-                        )));
-                    }
-                }
+            if check_result.is_string() {
+                tracing::trace!(attempts = attempts, "Promise resolved");
+                return serde_json::from_str(check_result.get_str()).map_err(BamlRtError::Json);
+            }
+
+            let failure = if self.watchdog.take_tripped() {
+                tracing::warn!(eval_id, "Promise job interrupted after exceeding the execution time limit");
+                Some(self.watchdog.exceeded())
+            } else if tokio::time::Instant::now() >= deadline {
+                let timeout_ms = self.promise_timeout.as_millis() as u64;
+                tracing::warn!(eval_id, timeout_ms, "Promise did not settle before timeout");
+                Some(BamlRtError::PromiseTimeout { timeout_ms })
             } else {
-                // Not a promise, wrap in success object
-                Ok(serde_json::json!({ "success": true, "result": debug_str }))
+                None
+            };
+            if let Some(failure) = failure {
+                let abandon = format!(
+                    "globalThis.__eval_results.delete({0}) || globalThis.__eval_abandoned.add({0});",
+                    eval_id
+                );
+                if let Err(e) = self.runtime.eval(None, Script::new("abandon.js", &abandon)).await {
+                    tracing::warn!(error = ?e, "Failed to abandon pending eval result");
+                }
+                return Err(failure);
             }
+
+            // Run pending jobs - this is how quickjs_runtime processes promises
+            // The runtime automatically polls Rust futures backing promises
+            self.drain_pending_jobs();
+
+            // Yield to Tokio to allow futures to progress
+            tokio::task::yield_now().await;
+
+            // Small delay to allow promise resolution
+            tokio::time::sleep(Duration::from_millis(1)).await;
+            attempts += 1;
         }
     }
 
+    /// Run every job currently queued in the QuickJS runtime (promise reactions and
+    /// other microtasks).
+    fn drain_pending_jobs(&self) {
+        let _armed = self.watchdog.arm();
+        self.runtime.exe_rt_task_in_event_loop(|rt| {
+            rt.run_pending_jobs_if_any();
+        });
+    }
+
     /// Invoke a BAML function by name.
     ///
     /// This is a helper method that generates and executes JavaScript code to:
@@ -1415,55 +1889,24 @@ impl QuickJSBridge {
     pub async fn invoke_function(&mut self, function_name: &str, args: Value) -> Result<Value> {
         let args_json = serde_json::to_string(&args)
             .map_err(BamlRtError::Json)?;
-        let context_prelude = match context::current_context_id() {
-            Some(id) => format!(
-                "globalThis.__baml_context_id = {};",
-                serialize_id(&id)?
-            ),
-            None => "delete globalThis.__baml_context_id;".to_string(),
-        };
-        let message_prelude = match context::current_message_id() {
-            Some(id) => format!(
-                "globalThis.__baml_message_id = {};",
-                serialize_id(&id)?
-            ),
-            None => "delete globalThis.__baml_message_id;".to_string(),
-        };
-        let task_prelude = match context::current_task_id() {
-            Some(id) => format!(
-                "globalThis.__baml_task_id = {};",
-                serialize_id(&id)?
-            ),
-            None => "delete globalThis.__baml_task_id;".to_string(),
-        };
-        let scope_prelude = format!("{context_prelude}\n{message_prelude}\n{task_prelude}");
-        
+
         // Generate JavaScript code that invokes the BAML runtime only (no JS fallback)
-        let js_code = format!(
-            r#"
+        self.evaluate_invocation(|scope_frame| {
+            format!(
+                r#"
             (function() {{
                 try {{
-                    {}
                     const args = {};
-                    const promise = __baml_invoke("{}", JSON.stringify(args), globalThis.__baml_context_id, globalThis.__baml_message_id, globalThis.__baml_task_id);
-                    return __awaitAndStringify(promise);
+                    return __awaitAndStringify(__baml_in_scope({}, () => __baml_invoke("{}", JSON.stringify(args), globalThis.__baml_context_id, globalThis.__baml_message_id, globalThis.__baml_task_id)));
                 }} catch (error) {{
                     return JSON.stringify({{ error: error.message || String(error) }});
                 }}
             }})()
             "#,
-            scope_prelude, args_json, function_name
-        );
-
-        if correlation::current_correlation_id().is_some() {
-            self.evaluate(&js_code).await
-        } else {
-            let correlation_id = correlation::generate_correlation_id();
-            correlation::with_correlation_id(correlation_id, async {
-                self.evaluate(&js_code).await
-            })
-            .await
-        }
+                args_json, scope_frame, function_name
+            )
+        })
+        .await
     }
 
     /// Invoke a JavaScript tool by name.
@@ -1472,191 +1915,161 @@ impl QuickJSBridge {
     pub async fn invoke_js_tool(&mut self, tool_name: &str, args: Value) -> Result<Value> {
         let args_json = serde_json::to_string(&args)
             .map_err(BamlRtError::Json)?;
-        let context_prelude = match context::current_context_id() {
-            Some(id) => format!(
-                "globalThis.__baml_context_id = {};",
-                serialize_id(&id)?
-            ),
-            None => "delete globalThis.__baml_context_id;".to_string(),
-        };
-        let message_prelude = match context::current_message_id() {
-            Some(id) => format!(
-                "globalThis.__baml_message_id = {};",
-                serialize_id(&id)?
-            ),
-            None => "delete globalThis.__baml_message_id;".to_string(),
-        };
-        let task_prelude = match context::current_task_id() {
-            Some(id) => format!(
-                "globalThis.__baml_task_id = {};",
-                serialize_id(&id)?
-            ),
-            None => "delete globalThis.__baml_task_id;".to_string(),
-        };
-        let scope_prelude = format!("{context_prelude}\n{message_prelude}\n{task_prelude}");
 
-        let js_code = format!(
-            r#"
+        self.evaluate_invocation(|scope_frame| {
+            format!(
+                r#"
             (function() {{
                 try {{
-                    {}
                     const args = {};
                     const func = globalThis.__js_tools && globalThis.__js_tools["{}"];
                     if (func === undefined || typeof func !== 'function') {{
                         return JSON.stringify({{ error: "JS tool not found" }});
                     }}
-                    return __awaitAndStringify(func(args));
+                    return __awaitAndStringify(__baml_in_scope({}, () => func(args)));
                 }} catch (error) {{
                     return JSON.stringify({{ error: error.message || String(error) }});
                 }}
             }})()
             "#,
-            scope_prelude, args_json, tool_name
-        );
-
-        if correlation::current_correlation_id().is_some() {
-            self.evaluate(&js_code).await
-        } else {
-            let correlation_id = correlation::generate_correlation_id();
-            correlation::with_correlation_id(correlation_id, async {
-                self.evaluate(&js_code).await
-            })
-            .await
-        }
+                args_json, tool_name, scope_frame
+            )
+        })
+        .await
     }
 
     pub async fn invoke_js_function(&mut self, function_name: &str, args: Value) -> Result<Value> {
         let args_json = serde_json::to_string(&args).map_err(BamlRtError::Json)?;
-        let context_prelude = match context::current_context_id() {
-            Some(id) => format!(
-                "globalThis.__baml_context_id = {};",
-                serialize_id(&id)?
-            ),
-            None => "delete globalThis.__baml_context_id;".to_string(),
-        };
-        let message_prelude = match context::current_message_id() {
-            Some(id) => format!(
-                "globalThis.__baml_message_id = {};",
-                serialize_id(&id)?
-            ),
-            None => "delete globalThis.__baml_message_id;".to_string(),
-        };
-        let task_prelude = match context::current_task_id() {
-            Some(id) => format!(
-                "globalThis.__baml_task_id = {};",
-                serialize_id(&id)?
-            ),
-            None => "delete globalThis.__baml_task_id;".to_string(),
-        };
-        let scope_prelude = format!("{context_prelude}\n{message_prelude}\n{task_prelude}");
 
-        let js_code = format!(
-            r#"
+        let result = self
+            .evaluate_invocation(|scope_frame| {
+                format!(
+                    r#"
             (function() {{
                 try {{
-                    {}
                     const args = {};
                     const func = globalThis["{}"];
                     if (func === undefined || typeof func !== 'function') {{
                         return JSON.stringify({{ error: "JS function not found: {}" }});
                     }}
-                    return __awaitAndStringify(func(args));
+                    return __awaitAndStringify(__baml_in_scope({}, () => func(args)));
                 }} catch (error) {{
-                    return JSON.stringify({{ error: error.message || String(error) }});
+                    return __rejectionJson(error);
                 }}
             }})()
             "#,
-            scope_prelude, args_json, function_name, function_name
-        );
-
-        let result = if correlation::current_correlation_id().is_some() {
-            self.evaluate(&js_code).await?
-        } else {
-            let correlation_id = correlation::generate_correlation_id();
-            correlation::with_correlation_id(correlation_id, async {
-                self.evaluate(&js_code).await
+                    args_json, function_name, function_name, scope_frame
+                )
             })
-            .await?
-        };
+            .await?;
 
         match &result {
-            Value::Object(map) if map.get("error").is_some() => Err(BamlRtError::QuickJs(format!(
-                "JS function invocation error ({}): {}",
-                function_name,
-                map.get("error").and_then(Value::as_str).unwrap_or("unknown")
-            ))),
+            Value::Object(map) if map.get("error").is_some() => {
+                Err(invocation_error(function_name, map))
+            }
             _ => Ok(result),
         }
     }
 
+    /// Invoke a JavaScript function, sending its result to `sink` chunk by chunk
+    /// as JS produces it
+    ///
+    /// An async iterable result, such as that of an `async function*`, is
+    /// drained one chunk at a time; an array is sent element by element; any
+    /// other result is sent as a single chunk. A result object carrying `error`
+    /// fails the call instead. `sink` is released when the call returns, which
+    /// ends the receiver's stream.
+    pub async fn invoke_js_function_chunked(
+        &mut self,
+        function_name: &str,
+        args: Value,
+        sink: mpsc::UnboundedSender<Value>,
+    ) -> Result<()> {
+        let args_json = serde_json::to_string(&args).map_err(BamlRtError::Json)?;
+        *self.chunk_sink.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(sink);
+
+        let result = self
+            .evaluate_invocation(|scope_frame| {
+                format!(
+                    r#"
+            (function() {{
+                try {{
+                    const args = {};
+                    const func = globalThis["{}"];
+                    if (func === undefined || typeof func !== 'function') {{
+                        return JSON.stringify({{ error: "JS function not found: {}" }});
+                    }}
+                    const emit = (chunk) => __baml_emit_chunk(JSON.stringify(chunk === undefined ? null : chunk));
+                    return __awaitAndStringify(__baml_in_scope({}, async () => {{
+                        const result = await func(args);
+                        if (result && typeof result[Symbol.asyncIterator] === 'function') {{
+                            for await (const chunk of result) {{
+                                emit(chunk);
+                            }}
+                            return null;
+                        }}
+                        if (result && typeof result === 'object' && !Array.isArray(result) && result.error !== undefined) {{
+                            return result;
+                        }}
+                        for (const chunk of Array.isArray(result) ? result : [result]) {{
+                            emit(chunk);
+                        }}
+                        return null;
+                    }}));
+                }} catch (error) {{
+                    return __rejectionJson(error);
+                }}
+            }})()
+            "#,
+                    args_json, function_name, function_name, scope_frame
+                )
+            })
+            .await;
+        self.chunk_sink.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take();
+
+        match &result? {
+            Value::Object(map) if map.get("error").is_some() => {
+                Err(invocation_error(function_name, map))
+            }
+            _ => Ok(()),
+        }
+    }
+
     pub async fn invoke_optional_js_function(
         &mut self,
         function_name: &str,
         args: Value,
     ) -> Result<Option<Value>> {
         let args_json = serde_json::to_string(&args).map_err(BamlRtError::Json)?;
-        let context_prelude = match context::current_context_id() {
-            Some(id) => format!(
-                "globalThis.__baml_context_id = {};",
-                serialize_id(&id)?
-            ),
-            None => "delete globalThis.__baml_context_id;".to_string(),
-        };
-        let message_prelude = match context::current_message_id() {
-            Some(id) => format!(
-                "globalThis.__baml_message_id = {};",
-                serialize_id(&id)?
-            ),
-            None => "delete globalThis.__baml_message_id;".to_string(),
-        };
-        let task_prelude = match context::current_task_id() {
-            Some(id) => format!(
-                "globalThis.__baml_task_id = {};",
-                serialize_id(&id)?
-            ),
-            None => "delete globalThis.__baml_task_id;".to_string(),
-        };
-        let scope_prelude = format!("{context_prelude}\n{message_prelude}\n{task_prelude}");
 
-        let js_code = format!(
-            r#"
+        let result = self
+            .evaluate_invocation(|scope_frame| {
+                format!(
+                    r#"
             (function() {{
                 try {{
-                    {}
                     const args = {};
                     const func = globalThis["{}"];
                     if (func === undefined || typeof func !== 'function') {{
                         return JSON.stringify({{ __absent: true }});
                     }}
-                    return __awaitAndStringify(func(args));
+                    return __awaitAndStringify(__baml_in_scope({}, () => func(args)));
                 }} catch (error) {{
-                    return JSON.stringify({{ error: error.message || String(error) }});
+                    return __rejectionJson(error);
                 }}
             }})()
             "#,
-            scope_prelude, args_json, function_name
-        );
-
-        let result = if correlation::current_correlation_id().is_some() {
-            self.evaluate(&js_code).await?
-        } else {
-            let correlation_id = correlation::generate_correlation_id();
-            correlation::with_correlation_id(correlation_id, async {
-                self.evaluate(&js_code).await
+                    args_json, function_name, scope_frame
+                )
             })
-            .await?
-        };
+            .await?;
 
         if let Value::Object(map) = &result {
             if map.get("__absent").and_then(Value::as_bool).unwrap_or(false) {
                 return Ok(None);
             }
-            if let Some(error) = map.get("error").and_then(Value::as_str) {
-                return Err(BamlRtError::QuickJs(format!(
-                    "JS function invocation error ({}): {}",
-                    function_name,
-                    error
-                )));
+            if map.get("error").is_some() {
+                return Err(invocation_error(function_name, map));
             }
         }
 
@@ -1672,38 +2085,30 @@ impl QuickJSBridge {
             .map_err(BamlRtError::Json)?;
         let stream_function = format!("{}Stream", function_name);
 
-        let js_code = format!(
-            r#"
+        let result = self
+            .evaluate_invocation(|scope_frame| {
+                format!(
+                    r#"
             (function() {{
                 try {{
                     const args = {};
-                    let promise;
                     const streamFunc = globalThis["{}"];
-                    if (streamFunc !== undefined && typeof streamFunc === 'function') {{
-                        promise = streamFunc(args);
-                    }} else {{
-                        promise = __baml_stream("{}", JSON.stringify(args), globalThis.__baml_context_id, globalThis.__baml_message_id, globalThis.__baml_task_id);
-                    }}
+                    const promise = __baml_in_scope({}, () => {{
+                        if (streamFunc !== undefined && typeof streamFunc === 'function') {{
+                            return streamFunc(args);
+                        }}
+                        return __baml_stream("{}", JSON.stringify(args), globalThis.__baml_context_id, globalThis.__baml_message_id, globalThis.__baml_task_id);
+                    }});
                     return __awaitAndStringify(promise);
                 }} catch (error) {{
                     return JSON.stringify({{ error: error.message || String(error) }});
                 }}
             }})()
             "#,
-            args_json,
-            stream_function,
-            function_name
-        );
-
-        let result = if correlation::current_correlation_id().is_some() {
-            self.evaluate(&js_code).await?
-        } else {
-            let correlation_id = correlation::generate_correlation_id();
-            correlation::with_correlation_id(correlation_id, async {
-                self.evaluate(&js_code).await
+                    args_json, stream_function, scope_frame, function_name
+                )
             })
-            .await?
-        };
+            .await?;
         match result {
             Value::Array(values) => Ok(values),
             Value::Object(map) if map.get("error").is_some() => Err(BamlRtError::QuickJs(format!(
@@ -1714,4 +2119,37 @@ impl QuickJSBridge {
         }
     }
 
+    /// Evaluate the code of an invocation inside its scope
+    ///
+    /// The scope is the caller's task-local one, or else that of the invocation
+    /// this one is nested in. `js_code` is given the scope's ids as a JS object
+    /// literal to pass to `__baml_in_scope`, which keeps the `__baml_*` scope
+    /// globals pointing at the innermost invocation while it runs. The scope is
+    /// also pushed onto the bridge's stack so host callbacks made without ids
+    /// inherit it.
+    async fn evaluate_invocation(&mut self, js_code: impl FnOnce(&str) -> String) -> Result<Value> {
+        let scope = self.scopes.resolve();
+        let js_code = js_code(&js_scope_frame(scope.as_ref())?);
+        let context_id = scope.as_ref().map(|scope| scope.context_id.to_string());
+        let frame = scope.map(|scope| self.scopes.enter(scope));
+        let span = tracing::debug_span!(
+            "baml_rt.invocation",
+            depth = frame.as_ref().map_or(0, |frame| frame.depth()),
+            context_id = context_id.as_deref(),
+        );
+
+        let result = if correlation::current_correlation_id().is_some() {
+            self.evaluate(&js_code).instrument(span).await
+        } else {
+            let correlation_id = correlation::generate_correlation_id();
+            correlation::with_correlation_id(correlation_id, async {
+                self.evaluate(&js_code).await
+            })
+            .instrument(span)
+            .await
+        };
+        drop(frame);
+        result
+    }
+
 }
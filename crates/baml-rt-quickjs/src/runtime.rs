@@ -11,15 +11,20 @@ use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::Mutex;
 
+/// Default time the host waits for a JavaScript promise to settle.
+pub const DEFAULT_PROMISE_TIMEOUT: Duration = Duration::from_secs(60);
+
 /// Configuration for QuickJS runtime options
 /// 
 /// These options map directly to the available options in `quickjs_runtime::builder::QuickJsRuntimeBuilder`.
 #[derive(Debug, Clone, Default)]
 pub struct QuickJSConfig {
-    /// Maximum memory limit in bytes (None = no limit)
+    /// Maximum JS heap size in bytes; allocating past it fails the script with
+    /// `BamlRtError::ResourceLimit` (None = no limit)
     pub memory_limit: Option<u64>,
     
-    /// Maximum stack size in bytes (None = default)
+    /// Maximum stack size in bytes; recursing past it fails the script with
+    /// `BamlRtError::ResourceLimit` (None = default)
     pub max_stack_size: Option<u64>,
     
     /// Number of allocations before garbage collection runs (None = default)
@@ -27,6 +32,15 @@ pub struct QuickJSConfig {
     
     /// Garbage collection interval - triggers a full GC every set interval (None = disabled)
     pub gc_interval: Option<Duration>,
+
+    /// How long the host waits for a promise returned to it, e.g. from
+    /// `handle_a2a_request`, to settle (None = `DEFAULT_PROMISE_TIMEOUT`)
+    pub promise_timeout: Option<Duration>,
+
+    /// Longest JS may run without yielding to the host before it is interrupted
+    /// with `BamlRtError::ResourceLimit` (None = no limit). Time spent waiting on
+    /// BAML calls, tools and other host work does not count.
+    pub max_execution_time: Option<Duration>,
 }
 
 impl QuickJSConfig {
@@ -60,6 +74,18 @@ impl QuickJSConfig {
         self.gc_interval = interval;
         self
     }
+
+    /// Set how long to wait for a returned promise before failing the call
+    pub fn with_promise_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.promise_timeout = timeout;
+        self
+    }
+
+    /// Set how long JS may run without yielding before it is interrupted
+    pub fn with_max_execution_time(mut self, limit: Option<Duration>) -> Self {
+        self.max_execution_time = limit;
+        self
+    }
 }
 
 /// Configuration for the BAML runtime environment
@@ -0,0 +1,101 @@
+//! TypeScript declarations for the BAML functions the QuickJS bridge injects.
+//!
+//! Each function `F` is declared with an `FArgs` interface for its arguments
+//! object and an `FOutput` alias for what it returns, next to the `FStream`
+//! variant, which resolves to the partial results streamed before the final
+//! one. A function whose output is a tool call or tool session plan resolves to
+//! the tool's output in JS rather than to its declared output.
+
+use baml_rt_core::types::{BamlType, FunctionSignature, ObjectField};
+use baml_rt_core::{BamlRtError, Result};
+use genco::lang::js;
+use genco::prelude::*;
+
+pub fn render_baml_typescript(functions: &[FunctionSignature]) -> Result<String> {
+    let mut tokens: js::Tokens = quote!(
+        // TypeScript declarations for BAML functions
+        // This file is auto-generated - do not edit manually
+    );
+    tokens.line();
+
+    let partial = "export type BamlPartial<T> = T extends object ? { [K in keyof T]?: BamlPartial<T[K]> } : T;";
+    quote_in!(tokens => $(partial));
+    tokens.line();
+
+    for function in functions {
+        let name = &function.name;
+        let args = format!("export interface {}Args {}", name, object_type(&function.parameters));
+        let output = format!("export type {}Output = {};", name, ts_type(&function.output_type));
+        let call = format!(
+            "declare function {name}(args: {name}Args): Promise<{name}Output>;"
+        );
+        let stream = format!(
+            "declare function {name}Stream(args: {name}Args): Promise<BamlPartial<{name}Output>[]>;"
+        );
+        for line in [args, output, call, stream] {
+            quote_in!(tokens => $(line));
+            tokens.push();
+        }
+        tokens.line();
+    }
+
+    tokens
+        .to_file_string()
+        .map_err(|e| BamlRtError::InvalidArgument(format!("TypeScript render error: {}", e)))
+}
+
+fn ts_type(ty: &BamlType) -> String {
+    match ty {
+        BamlType::String => "string".to_string(),
+        BamlType::Int | BamlType::Float => "number".to_string(),
+        BamlType::Bool => "boolean".to_string(),
+        BamlType::Null => "null".to_string(),
+        BamlType::List(item) => match item.as_ref() {
+            BamlType::Optional(_) | BamlType::Union(_) | BamlType::Enum(_) => {
+                format!("({})[]", ts_type(item))
+            }
+            _ => format!("{}[]", ts_type(item)),
+        },
+        BamlType::Map(_, value) => format!("Record<string, {}>", ts_type(value)),
+        BamlType::Object(fields) => object_type(fields),
+        BamlType::Optional(inner) => format!("{} | null", ts_type(inner)),
+        BamlType::Enum(values) if values.is_empty() => "never".to_string(),
+        BamlType::Enum(values) => values
+            .iter()
+            .map(|value| serde_json::Value::String(value.clone()).to_string())
+            .collect::<Vec<_>>()
+            .join(" | "),
+        BamlType::Union(members) => {
+            members.iter().map(ts_type).collect::<Vec<_>>().join(" | ")
+        }
+        BamlType::Any => "unknown".to_string(),
+    }
+}
+
+/// An object type literal; fields that accept `null` may also be left out.
+fn object_type(fields: &[ObjectField]) -> String {
+    if fields.is_empty() {
+        return "{}".to_string();
+    }
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|field| {
+            let optional = if field.ty.accepts_null() { "?" } else { "" };
+            format!("{}{}: {};", property_name(&field.name), optional, ts_type(&field.ty))
+        })
+        .collect();
+    format!("{{ {} }}", fields.join(" "))
+}
+
+fn property_name(name: &str) -> String {
+    let mut chars = name.chars();
+    let identifier = chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_' || first == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if identifier {
+        name.to_string()
+    } else {
+        serde_json::Value::String(name.to_string()).to_string()
+    }
+}
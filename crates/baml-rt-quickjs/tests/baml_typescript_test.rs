@@ -0,0 +1,42 @@
+//! TypeScript declarations for BAML functions.
+
+use baml_rt_quickjs::ts_gen::render_baml_typescript;
+use baml_rt_core::types::{BamlType, FunctionSignature, ObjectField};
+
+fn field(name: &str, ty: BamlType) -> ObjectField {
+    ObjectField { name: name.to_string(), ty }
+}
+
+#[test]
+fn functions_are_declared_with_typed_args_and_outputs() {
+    let review = FunctionSignature {
+        name: "ReviewText".to_string(),
+        parameters: vec![
+            field("text", BamlType::String),
+            field("hint", BamlType::Optional(Box::new(BamlType::String))),
+        ],
+        output_type: BamlType::Object(vec![
+            field("score", BamlType::Int),
+            field("tags", BamlType::List(Box::new(BamlType::String))),
+            field(
+                "verdict",
+                BamlType::Enum(vec!["Accept".to_string(), "Reject".to_string()]),
+            ),
+            field("extra", BamlType::Map(Box::new(BamlType::String), Box::new(BamlType::Any))),
+        ]),
+    };
+    let ts = render_baml_typescript(&[review]).expect("render");
+
+    assert!(ts.contains("export interface ReviewTextArgs { text: string; hint?: string | null; }"), "{ts}");
+    assert!(
+        ts.contains(
+            r#"export type ReviewTextOutput = { score: number; tags: string[]; verdict: "Accept" | "Reject"; extra: Record<string, unknown>; };"#
+        ),
+        "{ts}"
+    );
+    assert!(ts.contains("declare function ReviewText(args: ReviewTextArgs): Promise<ReviewTextOutput>;"), "{ts}");
+    assert!(
+        ts.contains("declare function ReviewTextStream(args: ReviewTextArgs): Promise<BamlPartial<ReviewTextOutput>[]>;"),
+        "{ts}"
+    );
+}
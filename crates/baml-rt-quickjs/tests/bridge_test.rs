@@ -2,6 +2,8 @@
 
 use baml_rt::baml::BamlRuntimeManager;
 use baml_rt::quickjs_bridge::QuickJSBridge;
+use baml_rt::{QuickJSConfig, ResourceLimitKind};
+use baml_rt::console::{ConsoleEntry, ConsoleLevel, ConsoleSink};
 use baml_rt_core::context::{self, RuntimeScope};
 use baml_rt_core::ids::{AgentId, ContextId, ExternalId, MessageId, TaskId, UuidId};
 use baml_rt_tools::BamlTool;
@@ -11,6 +13,7 @@ use serde::{Deserialize, Serialize};
 use schemars::JsonSchema;
 use ts_rs::TS;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
 use tokio::task::LocalSet;
 
@@ -56,6 +59,204 @@ async fn test_quickjs_evaluate_json() {
     assert!(result.is_ok(), "Should be able to execute JavaScript and get JSON");
 }
 
+#[tokio::test]
+async fn test_quickjs_rejected_handler_surfaces_structured_error() {
+    let baml_manager = Arc::new(Mutex::new(BamlRuntimeManager::new().unwrap()));
+    let agent_id =
+        AgentId::from_uuid(UuidId::parse_str("00000000-0000-0000-0000-000000000013").unwrap());
+    let mut bridge = QuickJSBridge::new(baml_manager, agent_id).await.unwrap();
+    bridge
+        .evaluate(
+            r#"
+            globalThis.handle_a2a_request = async () => {
+                await Promise.resolve();
+                throw new TypeError("bad request");
+            };
+            "#,
+        )
+        .await
+        .unwrap();
+
+    let err = bridge
+        .invoke_js_function("handle_a2a_request", json!({}))
+        .await
+        .expect_err("rejection should fail the call");
+    match err {
+        baml_rt::BamlRtError::JsRejection { function, name, message, stack } => {
+            assert_eq!(function, "handle_a2a_request");
+            assert_eq!(name, "TypeError");
+            assert_eq!(message, "bad request");
+            assert!(stack.is_some());
+        }
+        other => panic!("expected JsRejection, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_quickjs_pending_promise_times_out_without_leaking() {
+    let baml_manager = Arc::new(Mutex::new(BamlRuntimeManager::new().unwrap()));
+    let agent_id =
+        AgentId::from_uuid(UuidId::parse_str("00000000-0000-0000-0000-000000000014").unwrap());
+    let config = QuickJSConfig::new().with_promise_timeout(Some(Duration::from_millis(50)));
+    let mut bridge = QuickJSBridge::new_with_config(baml_manager, agent_id, config)
+        .await
+        .unwrap();
+    bridge
+        .evaluate(
+            r#"
+            globalThis.calls = 0;
+            globalThis.release = null;
+            globalThis.hang = () => {
+                globalThis.calls += 1;
+                return new Promise((resolve) => { globalThis.release = resolve; });
+            };
+            globalThis.answer = async () => ({ answer: 42 });
+            "#,
+        )
+        .await
+        .unwrap();
+
+    let err = bridge
+        .invoke_js_function("hang", json!({}))
+        .await
+        .expect_err("never-settling promise should time out");
+    assert!(matches!(err, baml_rt::BamlRtError::PromiseTimeout { timeout_ms: 50 }));
+
+    // Settle the abandoned promise; its result must not be returned by the next call.
+    bridge.evaluate("globalThis.release({ stale: true })").await.unwrap();
+    let result = bridge.invoke_js_function("answer", json!({})).await.unwrap();
+    assert_eq!(result, json!({ "answer": 42 }));
+
+    // The timed-out handler ran once, not once per poll or retry.
+    let calls = bridge.evaluate("return JSON.stringify(globalThis.calls)").await.unwrap();
+    assert_eq!(calls, json!(1));
+}
+
+#[tokio::test]
+async fn test_quickjs_runaway_script_hits_execution_time_limit() {
+    let baml_manager = Arc::new(Mutex::new(BamlRuntimeManager::new().unwrap()));
+    let agent_id =
+        AgentId::from_uuid(UuidId::parse_str("00000000-0000-0000-0000-000000000016").unwrap());
+    let config = QuickJSConfig::new().with_max_execution_time(Some(Duration::from_millis(50)));
+    let mut bridge = QuickJSBridge::new_with_config(baml_manager, agent_id, config)
+        .await
+        .unwrap();
+    bridge.register_baml_functions().await.expect("register helpers");
+
+    let err = bridge.evaluate("while (true) {}").await.expect_err("loop should be interrupted");
+    assert!(matches!(
+        err,
+        baml_rt::BamlRtError::ResourceLimit { kind: ResourceLimitKind::ExecutionTime, .. }
+    ));
+
+    bridge
+        .evaluate(
+            r#"
+            globalThis.spin = async () => {
+                await Promise.resolve();
+                while (true) {}
+            };
+            globalThis.answer = async () => ({ answer: 42 });
+            "#,
+        )
+        .await
+        .unwrap();
+    let err = bridge
+        .invoke_js_function("spin", json!({}))
+        .await
+        .expect_err("loop in a promise job should be interrupted");
+    assert!(matches!(
+        err,
+        baml_rt::BamlRtError::ResourceLimit { kind: ResourceLimitKind::ExecutionTime, .. }
+    ));
+
+    // The bridge stays usable once the runaway script is stopped.
+    let result = bridge.invoke_js_function("answer", json!({})).await.unwrap();
+    assert_eq!(result, json!({ "answer": 42 }));
+}
+
+#[tokio::test]
+async fn test_quickjs_unbounded_recursion_hits_stack_limit() {
+    let baml_manager = Arc::new(Mutex::new(BamlRuntimeManager::new().unwrap()));
+    let agent_id =
+        AgentId::from_uuid(UuidId::parse_str("00000000-0000-0000-0000-000000000017").unwrap());
+    let config = QuickJSConfig::new().with_max_stack_size(Some(256 * 1024));
+    let mut bridge = QuickJSBridge::new_with_config(baml_manager, agent_id, config)
+        .await
+        .unwrap();
+    bridge.register_baml_functions().await.expect("register helpers");
+    bridge
+        .evaluate("globalThis.recurse = async function(args) { const r = (n) => r(n + 1) + 1; return r(0); };")
+        .await
+        .unwrap();
+
+    let err = bridge
+        .invoke_js_function("recurse", json!({}))
+        .await
+        .expect_err("recursion should overflow the stack");
+    assert!(matches!(
+        err,
+        baml_rt::BamlRtError::ResourceLimit { kind: ResourceLimitKind::Stack, .. }
+    ));
+}
+
+#[derive(Default)]
+struct CollectingSink {
+    entries: std::sync::Mutex<Vec<ConsoleEntry>>,
+}
+
+impl ConsoleSink for CollectingSink {
+    fn record(&self, entry: &ConsoleEntry) {
+        self.entries.lock().unwrap().push(entry.clone());
+    }
+}
+
+#[tokio::test]
+async fn test_quickjs_console_lines_carry_agent_and_scope() {
+    let baml_manager = Arc::new(Mutex::new(BamlRuntimeManager::new().unwrap()));
+    let agent_id =
+        AgentId::from_uuid(UuidId::parse_str("00000000-0000-0000-0000-000000000018").unwrap());
+    let mut bridge = QuickJSBridge::new(baml_manager, agent_id.clone()).await.unwrap();
+    bridge.register_baml_functions().await.expect("register helpers");
+    let sink = Arc::new(CollectingSink::default());
+    bridge.set_agent_name("support-agent");
+    bridge.add_console_sink(sink.clone());
+
+    bridge.evaluate("console.log('loaded');").await.unwrap();
+    bridge
+        .evaluate(
+            r#"
+            globalThis.noisy = async () => {
+                console.warn("retrying", { attempt: 2 });
+                console.error(new Error("gave up"));
+                return {};
+            };
+            "#,
+        )
+        .await
+        .unwrap();
+    let context_id = ContextId::new(4, 1);
+    let task_id = TaskId::from_external(ExternalId::new("task-console"));
+    let scope = RuntimeScope::new(context_id.clone(), agent_id.clone(), None, Some(task_id.clone()));
+    context::with_scope(scope, bridge.invoke_js_function("noisy", json!({})))
+        .await
+        .unwrap();
+
+    let entries = sink.entries.lock().unwrap().clone();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0].level, ConsoleLevel::Log);
+    assert_eq!(entries[0].message, "loaded");
+    assert_eq!(entries[0].context_id, None);
+    assert_eq!(entries[1].level, ConsoleLevel::Warn);
+    assert_eq!(entries[1].message, r#"retrying {"attempt":2}"#);
+    assert_eq!(entries[1].context_id, Some(context_id));
+    assert_eq!(entries[1].task_id, Some(task_id));
+    assert_eq!(entries[1].agent_id, agent_id);
+    assert_eq!(entries[1].agent_name.as_deref(), Some("support-agent"));
+    assert_eq!(entries[2].level, ConsoleLevel::Error);
+    assert!(entries[2].message.contains("gave up"));
+}
+
 #[tokio::test(flavor = "current_thread")]
 async fn test_quickjs_concurrent_scope_propagation() {
     let mut manager = BamlRuntimeManager::new().unwrap();
@@ -230,6 +431,83 @@ async fn test_quickjs_concurrent_stream_scope_propagation() {
     }
 }
 
+#[tokio::test(flavor = "current_thread")]
+async fn test_quickjs_nested_invocation_scope_is_restored() {
+    let manager = Arc::new(Mutex::new(BamlRuntimeManager::new().unwrap()));
+    let agent_id =
+        AgentId::from_uuid(UuidId::parse_str("00000000-0000-0000-0000-000000000015").unwrap());
+    let mut bridge = QuickJSBridge::new(manager, agent_id.clone()).await.unwrap();
+    bridge.register_baml_functions().await.expect("register helpers");
+
+    bridge
+        .evaluate(
+            r#"
+            globalThis.js_nested_scope = async function() {
+                const probe = async function() {
+                    const results = await __baml_stream(
+                        "scope_probe",
+                        JSON.stringify({ __scope_probe: true }),
+                        globalThis.__baml_context_id,
+                        globalThis.__baml_message_id,
+                        globalThis.__baml_task_id
+                    );
+                    return results[0];
+                };
+                const before = await probe();
+                const inner = await __baml_in_scope(
+                    { context_id: globalThis.__baml_context_id, message_id: "msg-inner", task_id: "task-inner" },
+                    probe
+                );
+                const after = await probe();
+                const inherited = (await __baml_stream("scope_probe", JSON.stringify({ __scope_probe: true })))[0];
+                return { before, inner, after, inherited };
+            };
+            "#,
+        )
+        .await
+        .expect("register nested scope function");
+
+    let context_id = ContextId::new(3, 1);
+    let message_id = MessageId::from_external(ExternalId::new("msg-outer"));
+    let task_id = TaskId::from_external(ExternalId::new("task-outer"));
+    let scope = RuntimeScope::new(
+        context_id.clone(),
+        agent_id,
+        Some(message_id.clone()),
+        Some(task_id.clone()),
+    );
+    let result = context::with_scope(scope, bridge.invoke_js_function("js_nested_scope", json!({})))
+        .await
+        .expect("invoke nested scope function");
+
+    let ids = |probe: &str| {
+        let probe = &result[probe];
+        (
+            probe["context_id"].as_str().map(str::to_string),
+            probe["message_id"].as_str().map(str::to_string),
+            probe["task_id"].as_str().map(str::to_string),
+        )
+    };
+    let outer = (
+        Some(context_id.as_str().to_string()),
+        Some(message_id.as_str().to_string()),
+        Some(task_id.as_str().to_string()),
+    );
+    assert_eq!(ids("before"), outer);
+    assert_eq!(
+        ids("inner"),
+        (Some(context_id.as_str().to_string()), Some("msg-inner".into()), Some("task-inner".into()))
+    );
+    assert_eq!(ids("after"), outer, "outer scope should be restored after the nested call");
+    assert_eq!(ids("inherited"), outer, "host calls without ids should inherit the invocation scope");
+
+    let globals = bridge
+        .evaluate("return JSON.stringify({ cleared: globalThis.__baml_context_id === undefined });")
+        .await
+        .expect("read scope globals");
+    assert_eq!(globals["cleared"], json!(true), "scope globals should be cleared once settled");
+}
+
 #[derive(Debug)]
 struct ScopeEchoTool;
 
@@ -269,3 +547,35 @@ impl BamlTool for ScopeEchoTool {
         })
     }
 }
+
+struct FixedTokenizer(u64);
+
+impl baml_rt_core::tokens::TokenEstimator for FixedTokenizer {
+    fn count_tokens(&self, _text: &str) -> u64 {
+        self.0
+    }
+}
+
+#[tokio::test]
+async fn test_host_tokens_count_uses_the_model_tokenizer() {
+    let manager = BamlRuntimeManager::new().unwrap();
+    manager.tokenizers().register("gpt-4o", Arc::new(FixedTokenizer(100)));
+    let baml_manager = Arc::new(Mutex::new(manager));
+    let agent_id =
+        AgentId::from_uuid(UuidId::parse_str("00000000-0000-0000-0000-000000000013").unwrap());
+    let mut bridge = QuickJSBridge::new(baml_manager, agent_id).await.unwrap();
+
+    let counts = bridge
+        .evaluate(
+            r#"({
+                fallback: host.tokens.count("hello, world"),
+                model: host.tokens.count("hello, world", "openai/gpt-4o-mini"),
+                json: host.tokens.count({ greeting: "hi" })
+            })"#,
+        )
+        .await
+        .unwrap();
+    assert_eq!(counts["fallback"], json!(5));
+    assert_eq!(counts["model"], json!(100));
+    assert!(counts["json"].as_u64().unwrap() > 0);
+}
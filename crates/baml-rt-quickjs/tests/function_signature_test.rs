@@ -0,0 +1,103 @@
+//! Function signatures read from the BAML IR, and argument validation.
+
+use baml_rt_core::BamlRtError;
+use baml_rt_core::types::BamlType;
+use serde_json::json;
+use test_support::common::{agent_fixture, setup_baml_runtime_manager};
+
+#[test]
+fn signatures_carry_declared_parameter_and_return_types() {
+    let manager = setup_baml_runtime_manager(agent_fixture("voidship-rites").to_str().unwrap());
+
+    let greeting = manager.get_function_signature("VoidshipGreeting").expect("signature");
+    assert_eq!(greeting.parameters.len(), 1);
+    assert_eq!(greeting.parameters[0].name, "name");
+    assert!(matches!(greeting.parameters[0].ty, BamlType::String));
+    assert!(matches!(greeting.output_type, BamlType::String));
+
+    let plan = manager.get_function_signature("ChooseRiteTool").expect("signature");
+    let BamlType::Object(fields) = &plan.output_type else {
+        panic!("expected a class output, got {}", plan.output_type);
+    };
+    assert_eq!(fields[0].name, "steps");
+    assert!(matches!(fields[0].ty, BamlType::List(_)));
+}
+
+#[tokio::test]
+async fn malformed_arguments_are_rejected_before_the_call() {
+    let manager = setup_baml_runtime_manager(agent_fixture("voidship-rites").to_str().unwrap());
+
+    manager.validate_args("VoidshipGreeting", &json!({"name": "Ada"})).expect("valid");
+    let err = manager
+        .invoke_function("VoidshipGreeting", json!({"name": 7}))
+        .await
+        .expect_err("wrong type");
+    assert!(matches!(err, BamlRtError::SchemaMismatch(_)), "{err}");
+    assert!(err.to_string().contains("/name: expected string, found int"), "{err}");
+
+    let err = manager.validate_args("Missing", &json!({})).expect_err("unknown");
+    assert!(matches!(err, BamlRtError::FunctionNotFound(_)));
+}
+
+#[test]
+fn function_schemas_are_exported_for_every_function() {
+    let manager = setup_baml_runtime_manager(agent_fixture("voidship-rites").to_str().unwrap());
+
+    let schemas = manager.export_function_schemas();
+    let names: Vec<&str> = schemas.iter().map(|schema| schema.name.as_str()).collect();
+    assert!(names.windows(2).all(|pair| pair[0] <= pair[1]), "{names:?}");
+
+    let greeting = schemas.iter().find(|schema| schema.name == "VoidshipGreeting").expect("schema");
+    assert_eq!(
+        greeting.input_schema,
+        json!({
+            "type": "object",
+            "properties": {"name": {"type": "string"}},
+            "required": ["name"],
+        })
+    );
+    assert_eq!(greeting.output_schema, json!({"type": "string"}));
+}
+
+/// A copy of the voidship-rites `baml_src` that a test can edit.
+fn scratch_baml_src() -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("baml-reload-{}", uuid::Uuid::new_v4()));
+    let baml_src = dir.join("baml_src");
+    std::fs::create_dir_all(&baml_src).unwrap();
+    for entry in std::fs::read_dir(agent_fixture("voidship-rites").join("baml_src")).unwrap() {
+        let path = entry.unwrap().path();
+        std::fs::copy(&path, baml_src.join(path.file_name().unwrap())).unwrap();
+    }
+    baml_src
+}
+
+#[test]
+fn reloading_a_changed_schema_swaps_its_functions() {
+    let baml_src = scratch_baml_src();
+    let path = baml_src.to_str().unwrap().to_string();
+    let mut manager = setup_baml_runtime_manager(&path);
+
+    let unchanged = manager.reload_schema(&path).expect("reload");
+    assert!(unchanged.is_unchanged());
+    assert!(unchanged.added.is_empty() && unchanged.removed.is_empty());
+
+    let prompt = baml_src.join("voidship_prompt.baml");
+    let source = std::fs::read_to_string(&prompt).unwrap().replace(
+        "function VoidshipGreeting(name: string) -> string {",
+        "function VoidshipFarewell(name: string, rank: int) -> string {",
+    );
+    std::fs::write(&prompt, source).unwrap();
+    let reload = manager.reload_schema(&path).expect("reload");
+    assert_eq!(reload.added, vec!["VoidshipFarewell".to_string()]);
+    assert_eq!(reload.removed, vec!["VoidshipGreeting".to_string()]);
+    assert_eq!(reload.previous_digest, Some(unchanged.digest));
+    assert!(manager.get_function_signature("VoidshipGreeting").is_none());
+    assert_eq!(manager.get_function_signature("VoidshipFarewell").unwrap().parameters.len(), 2);
+
+    // A broken edit is refused and leaves the running schema in place.
+    std::fs::write(&prompt, "function Broken(").unwrap();
+    manager.reload_schema(&path).expect_err("does not compile");
+    assert!(manager.get_function_signature("VoidshipFarewell").is_some());
+
+    std::fs::remove_dir_all(baml_src.parent().unwrap()).ok();
+}
@@ -0,0 +1,99 @@
+//! Control flow in tool session plans: `branch` and `repeat_until` steps.
+
+use async_trait::async_trait;
+use baml_rt::baml::BamlRuntimeManager;
+use baml_rt_tools::BamlTool;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicI64, Ordering};
+use ts_rs::TS;
+
+/// Adds to a total shared by every call.
+#[derive(Debug, Default)]
+struct CounterTool {
+    total: Arc<AtomicI64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, TS)]
+#[ts(export)]
+struct CounterInput {
+    add: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, TS)]
+#[ts(export)]
+struct CounterOutput {
+    total: i64,
+}
+
+#[async_trait]
+impl BamlTool for CounterTool {
+    const NAME: &'static str = "test/counter";
+    type OpenInput = ();
+    type Input = CounterInput;
+    type Output = CounterOutput;
+
+    fn description(&self) -> &'static str {
+        "Adds to a running total."
+    }
+
+    async fn execute(&self, args: Self::Input) -> baml_rt::Result<Self::Output> {
+        let total = self.total.fetch_add(args.add, Ordering::SeqCst) + args.add;
+        Ok(CounterOutput { total })
+    }
+}
+
+async fn manager() -> BamlRuntimeManager {
+    let mut manager = BamlRuntimeManager::new().unwrap();
+    manager.register_tool(CounterTool::default()).await.expect("register tool");
+    manager
+}
+
+fn add(n: i64) -> serde_json::Value {
+    json!([{"op": "open", "initial_input": {"add": n}}, {"op": "next"}])
+}
+
+#[tokio::test]
+async fn repeat_until_runs_its_steps_until_the_condition_holds() {
+    let manager = manager().await;
+    let plan = json!({"steps": [
+        {"op": "repeat_until", "until": "$[?@.total >= 6]", "steps": add(2)},
+    ]});
+    let output = manager.execute_tool_from_baml_result_or_value(plan).await.expect("plan");
+    assert_eq!(output, json!({"total": 6}));
+}
+
+#[tokio::test]
+async fn branch_follows_the_previous_output() {
+    let manager = manager().await;
+    let mut steps = add(1).as_array().unwrap().clone();
+    steps.push(json!({
+        "op": "branch",
+        "when": "$[?@.total > 5]",
+        "then": add(100),
+        "else": add(10),
+    }));
+    let output = manager
+        .execute_tool_from_baml_result_or_value(json!({"steps": steps}))
+        .await
+        .expect("plan");
+    assert_eq!(output, json!({"total": 11}));
+}
+
+#[tokio::test]
+async fn repeat_until_gives_up_after_max_iterations() {
+    let manager = manager().await;
+    let plan = json!({"steps": [
+        {"op": "repeat_until", "until": "$[?@.total < 0]", "steps": add(1), "max_iterations": 3},
+    ]});
+    let err = manager.execute_tool_from_baml_result_or_value(plan).await.expect_err("never holds");
+    assert!(err.to_string().contains("after 3 iterations"), "{err}");
+
+    let plan = json!({"steps": [
+        {"op": "repeat_until", "until": "$[?", "steps": add(1)},
+    ]});
+    let err = manager.execute_tool_from_baml_result_or_value(plan).await.expect_err("bad path");
+    assert!(err.to_string().contains("Invalid JSONPath"), "{err}");
+}
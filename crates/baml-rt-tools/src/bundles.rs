@@ -7,6 +7,9 @@ use crate::tools::BundleName;
 use baml_rt_core::Result;
 use serde_json::Value;
 
+/// Version reported by bundles that do not declare one.
+pub const DEFAULT_BUNDLE_VERSION: &str = "1.0.0";
+
 /// Trait for tool bundle types
 ///
 /// Each bundle (e.g., "support") should be represented
@@ -35,6 +38,11 @@ pub trait BundleType: Send + Sync + 'static {
         None
     }
 
+    /// Semver version, checked against manifest `required_bundles` constraints
+    fn version() -> &'static str {
+        DEFAULT_BUNDLE_VERSION
+    }
+
     /// Get the BundleName for this bundle type
     fn bundle_name() -> Result<BundleName> {
         BundleName::new(Self::NAME)
@@ -1,6 +1,13 @@
 //! Tool registry and mapping utilities.
 
 pub mod bundles;
+pub mod mcp;
+#[cfg(feature = "profiling")]
+pub mod profiling;
+pub mod retry;
+pub mod sandbox;
+mod schema_check;
+pub mod session_store;
 pub mod tool_fsm;
 pub mod tool_schema;
 pub mod tools;
@@ -8,8 +15,17 @@ pub mod ts_gen;
 pub mod tool_catalog;
 pub mod support;
 
-pub use bundles::{BundleType, Support};
-pub use tool_fsm::{ToolFailure, ToolFailureKind, ToolSession, ToolSessionError, ToolSessionId, ToolStep};
+pub use bundles::{BundleType, Support, DEFAULT_BUNDLE_VERSION};
+pub use mcp::{McpToolBundle, McpTransport};
+#[cfg(feature = "profiling")]
+pub use profiling::ToolProfiler;
+pub use retry::{RetryAttempt, RetryPolicy};
+pub use sandbox::{FsBundle, FsSandbox};
+pub use session_store::{FileSessionStore, InMemorySessionStore, SessionCheckpoint, SessionStore};
+pub use tool_fsm::{
+    CancellationToken, ToolFailure, ToolFailureKind, ToolSession, ToolSessionError, ToolSessionId,
+    ToolStep,
+};
 pub use tool_schema::{json_schema_value, ts_decl, ts_name, ToolType};
 pub use tool_catalog::{ToolCatalog, InventoryCatalog};
 pub use tools::{
@@ -0,0 +1,521 @@
+//! Tools served by MCP (Model Context Protocol) servers.
+//!
+//! [`McpToolBundle::connect`] runs the MCP handshake with a server, lists its
+//! tools and turns each into a [`ToolHandler`] whose calls are forwarded as
+//! `tools/call` requests. Registering the bundle makes them `bundle/tool` host
+//! tools like any other, so they still have to be declared in the manifest.
+//!
+//! Each tool's `inputSchema` becomes its input schema. A tool that declares an
+//! `outputSchema` returns its `structuredContent`; any other tool returns
+//! `{"content": [...]}` with the content blocks as the server sent them. A
+//! result with `isError` set fails the call with the text of its content.
+//!
+//! Requests on one connection are sent one at a time, so concurrent calls to
+//! the same server queue behind each other.
+
+use crate::bundles::DEFAULT_BUNDLE_VERSION;
+use crate::tools::{
+    BundleName, LocalToolName, OneShotSession, ToolBundle, ToolBundleMetadata,
+    ToolFunctionMetadata, ToolHandler, ToolName, ToolSessionContext, ToolTypeSpec,
+};
+use crate::{json_schema_value, ts_decl, ts_name, ToolSession};
+use async_trait::async_trait;
+use baml_rt_core::{BamlRtError, Result};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, Command};
+use tokio::sync::Mutex;
+
+/// Protocol revision sent in `initialize`.
+pub const MCP_PROTOCOL_VERSION: &str = "2025-06-18";
+
+/// Output schema of tools that declare no `outputSchema`.
+fn content_output_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "content": { "type": "array", "items": { "type": "object" } }
+        },
+        "required": ["content"]
+    })
+}
+
+/// How to reach an MCP server
+#[derive(Debug, Clone)]
+pub enum McpTransport {
+    /// Spawn `command` and speak newline-delimited JSON-RPC over its stdin and
+    /// stdout. The process is killed when the bundle is dropped.
+    Stdio {
+        command: String,
+        args: Vec<String>,
+        env: Vec<(String, String)>,
+    },
+    /// POST JSON-RPC to `url` (the streamable HTTP transport). Responses may be
+    /// plain JSON or an event stream.
+    Http {
+        url: String,
+        headers: Vec<(String, String)>,
+    },
+}
+
+impl McpTransport {
+    pub fn stdio<I, S>(command: impl Into<String>, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        McpTransport::Stdio {
+            command: command.into(),
+            args: args.into_iter().map(Into::into).collect(),
+            env: Vec::new(),
+        }
+    }
+
+    pub fn http(url: impl Into<String>) -> Self {
+        McpTransport::Http { url: url.into(), headers: Vec::new() }
+    }
+
+    /// Set an environment variable for a stdio server, or a request header
+    /// (e.g. `Authorization`) for an HTTP one.
+    pub fn with_env_or_header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        match &mut self {
+            McpTransport::Stdio { env, .. } => env.push((key.into(), value.into())),
+            McpTransport::Http { headers, .. } => headers.push((key.into(), value.into())),
+        }
+        self
+    }
+}
+
+type BoxedReader = Box<dyn AsyncRead + Send + Unpin>;
+type BoxedWriter = Box<dyn AsyncWrite + Send + Unpin>;
+
+enum Connection {
+    Stream {
+        writer: BoxedWriter,
+        lines: Lines<BufReader<BoxedReader>>,
+        /// Kept so the server lives (and dies) with the connection.
+        _child: Option<Child>,
+    },
+    Http {
+        client: reqwest::Client,
+        url: String,
+        headers: Vec<(String, String)>,
+        session_id: Option<String>,
+    },
+}
+
+fn protocol_error(message: impl Into<String>) -> BamlRtError {
+    BamlRtError::ToolExecution(format!("MCP: {}", message.into()))
+}
+
+impl Connection {
+    async fn send_line(writer: &mut BoxedWriter, message: &Value) -> Result<()> {
+        let mut line = serde_json::to_vec(message).map_err(BamlRtError::Json)?;
+        line.push(b'\n');
+        writer.write_all(&line).await.map_err(BamlRtError::Io)?;
+        writer.flush().await.map_err(BamlRtError::Io)
+    }
+
+    /// Send `message` and, when it carries an id, return the response to it.
+    async fn exchange(&mut self, message: &Value) -> Result<Option<Value>> {
+        let id = message.get("id").cloned();
+        match self {
+            Connection::Stream { writer, lines, .. } => {
+                Self::send_line(writer, message).await?;
+                let Some(id) = id else { return Ok(None) };
+                loop {
+                    let line = lines
+                        .next_line()
+                        .await
+                        .map_err(BamlRtError::Io)?
+                        .ok_or_else(|| protocol_error("server closed the connection"))?;
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    let incoming: Value = serde_json::from_str(&line)
+                        .map_err(|err| protocol_error(format!("invalid message from server: {err}")))?;
+                    if let Some(reply) = reply_to_server_request(&incoming) {
+                        Self::send_line(writer, &reply).await?;
+                        continue;
+                    }
+                    // Anything else that is not our response is a notification or
+                    // the late answer to a call whose caller gave up.
+                    if incoming.get("id") == Some(&id) {
+                        return Ok(Some(incoming));
+                    }
+                }
+            }
+            Connection::Http { client, url, headers, session_id } => {
+                let mut request = client
+                    .post(url.as_str())
+                    .header("Content-Type", "application/json")
+                    .header("Accept", "application/json, text/event-stream")
+                    .body(serde_json::to_vec(message).map_err(BamlRtError::Json)?);
+                if let Some(session) = session_id.as_deref() {
+                    request = request
+                        .header("Mcp-Session-Id", session)
+                        .header("MCP-Protocol-Version", MCP_PROTOCOL_VERSION);
+                }
+                for (name, value) in headers.iter() {
+                    request = request.header(name.as_str(), value.as_str());
+                }
+                let response = request
+                    .send()
+                    .await
+                    .map_err(|err| protocol_error(format!("request to {url} failed: {err}")))?;
+                let status = response.status();
+                if !status.is_success() {
+                    return Err(protocol_error(format!("{url} answered {status}")));
+                }
+                if let Some(session) = response
+                    .headers()
+                    .get("Mcp-Session-Id")
+                    .and_then(|value| value.to_str().ok())
+                {
+                    *session_id = Some(session.to_string());
+                }
+                let Some(id) = id else { return Ok(None) };
+                let is_stream = response
+                    .headers()
+                    .get("Content-Type")
+                    .and_then(|value| value.to_str().ok())
+                    .is_some_and(|value| value.starts_with("text/event-stream"));
+                let body = response
+                    .text()
+                    .await
+                    .map_err(|err| protocol_error(format!("reading response from {url}: {err}")))?;
+                if !is_stream {
+                    let reply = serde_json::from_str(&body)
+                        .map_err(|err| protocol_error(format!("invalid response: {err}")))?;
+                    return Ok(Some(reply));
+                }
+                event_stream_messages(&body)
+                    .into_iter()
+                    .find(|incoming| incoming.get("id") == Some(&id) && incoming.get("method").is_none())
+                    .map(Some)
+                    .ok_or_else(|| protocol_error("event stream ended without a response"))
+            }
+        }
+    }
+}
+
+/// The answer to a request the server sent us, if `message` is one. Servers may
+/// `ping` at any time; this client offers no other capabilities.
+fn reply_to_server_request(message: &Value) -> Option<Value> {
+    let method = message.get("method")?.as_str()?;
+    let id = message.get("id")?.clone();
+    Some(if method == "ping" {
+        json!({ "jsonrpc": "2.0", "id": id, "result": {} })
+    } else {
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": -32601, "message": format!("method not supported: {method}") }
+        })
+    })
+}
+
+/// JSON messages in the `data` fields of a `text/event-stream` body.
+fn event_stream_messages(body: &str) -> Vec<Value> {
+    body.replace("\r\n", "\n")
+        .split("\n\n")
+        .filter_map(|event| {
+            let data: Vec<&str> = event
+                .lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+                .map(|data| data.strip_prefix(' ').unwrap_or(data))
+                .collect();
+            if data.is_empty() {
+                return None;
+            }
+            serde_json::from_str(&data.join("\n")).ok()
+        })
+        .collect()
+}
+
+/// A JSON-RPC session with one MCP server
+struct McpClient {
+    connection: Mutex<Connection>,
+    next_id: AtomicU64,
+}
+
+impl McpClient {
+    async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        let reply = self
+            .connection
+            .lock()
+            .await
+            .exchange(&message)
+            .await?
+            .ok_or_else(|| protocol_error(format!("no response to {method}")))?;
+        if let Some(error) = reply.get("error") {
+            return Err(protocol_error(format!(
+                "{method} failed ({}): {}",
+                error.get("code").and_then(Value::as_i64).unwrap_or_default(),
+                error.get("message").and_then(Value::as_str).unwrap_or("unknown error")
+            )));
+        }
+        reply
+            .get("result")
+            .cloned()
+            .ok_or_else(|| protocol_error(format!("response to {method} has no result")))
+    }
+
+    async fn notify(&self, method: &str) -> Result<()> {
+        let message = json!({ "jsonrpc": "2.0", "method": method });
+        self.connection.lock().await.exchange(&message).await.map(|_| ())
+    }
+
+    async fn call_tool(&self, name: &str, arguments: Value, structured: bool) -> Result<Value> {
+        let result = self
+            .request("tools/call", json!({ "name": name, "arguments": arguments }))
+            .await?;
+        let content = result.get("content").cloned().unwrap_or_else(|| json!([]));
+        if result.get("isError").and_then(Value::as_bool).unwrap_or(false) {
+            return Err(BamlRtError::ToolExecution(format!(
+                "MCP tool '{name}' failed: {}",
+                content_text(&content)
+            )));
+        }
+        if !structured {
+            return Ok(json!({ "content": content }));
+        }
+        result.get("structuredContent").cloned().ok_or_else(|| {
+            protocol_error(format!("tool '{name}' declares an outputSchema but returned no structuredContent"))
+        })
+    }
+}
+
+/// The text blocks of a tool result, joined.
+fn content_text(content: &Value) -> String {
+    let text: Vec<&str> = content
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|block| block.get("text").and_then(Value::as_str))
+        .collect();
+    if text.is_empty() { content.to_string() } else { text.join("\n") }
+}
+
+/// The tools of one MCP server, as a [`ToolBundle`]
+pub struct McpToolBundle {
+    metadata: ToolBundleMetadata,
+    handlers: Vec<Arc<dyn ToolHandler>>,
+}
+
+impl McpToolBundle {
+    /// Connect to the server, run the handshake and list its tools, which are
+    /// registered as `bundle/<tool name>`.
+    pub async fn connect(bundle: &str, transport: McpTransport) -> Result<Self> {
+        let connection = match transport {
+            McpTransport::Stdio { command, args, env } => {
+                let mut child = Command::new(&command)
+                    .args(&args)
+                    .envs(env)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::inherit())
+                    .kill_on_drop(true)
+                    .spawn()
+                    .map_err(BamlRtError::Io)?;
+                let stdin = child.stdin.take().ok_or_else(|| protocol_error("no stdin"))?;
+                let stdout = child.stdout.take().ok_or_else(|| protocol_error("no stdout"))?;
+                Connection::Stream {
+                    writer: Box::new(stdin),
+                    lines: BufReader::new(Box::new(stdout) as BoxedReader).lines(),
+                    _child: Some(child),
+                }
+            }
+            McpTransport::Http { url, headers } => Connection::Http {
+                client: reqwest::Client::new(),
+                url,
+                headers,
+                session_id: None,
+            },
+        };
+        Self::handshake(bundle, connection).await
+    }
+
+    /// Like [`connect`](Self::connect), over a server's output and input
+    /// streams, e.g. one running in the same process.
+    pub async fn connect_streams<R, W>(bundle: &str, reader: R, writer: W) -> Result<Self>
+    where
+        R: AsyncRead + Send + Unpin + 'static,
+        W: AsyncWrite + Send + Unpin + 'static,
+    {
+        let connection = Connection::Stream {
+            writer: Box::new(writer),
+            lines: BufReader::new(Box::new(reader) as BoxedReader).lines(),
+            _child: None,
+        };
+        Self::handshake(bundle, connection).await
+    }
+
+    async fn handshake(bundle: &str, connection: Connection) -> Result<Self> {
+        let bundle_name = BundleName::new(bundle)?;
+        let client = Arc::new(McpClient {
+            connection: Mutex::new(connection),
+            next_id: AtomicU64::new(1),
+        });
+        let init = client
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": MCP_PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": { "name": "baml-rt", "version": env!("CARGO_PKG_VERSION") }
+                }),
+            )
+            .await?;
+        client.notify("notifications/initialized").await?;
+
+        let server_name = init
+            .pointer("/serverInfo/name")
+            .and_then(Value::as_str)
+            .unwrap_or(bundle);
+        let version = init
+            .pointer("/serverInfo/version")
+            .and_then(Value::as_str)
+            .filter(|version| semver::Version::parse(version).is_ok())
+            .unwrap_or(DEFAULT_BUNDLE_VERSION);
+        let description = init
+            .get("instructions")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("Tools from the MCP server '{server_name}'"));
+
+        let mut handlers: Vec<Arc<dyn ToolHandler>> = Vec::new();
+        let mut seen = HashSet::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let params = match &cursor {
+                Some(cursor) => json!({ "cursor": cursor }),
+                None => json!({}),
+            };
+            let page = client.request("tools/list", params).await?;
+            for tool in page.get("tools").and_then(Value::as_array).into_iter().flatten() {
+                let handler = McpToolHandler::new(&bundle_name, tool, client.clone())?;
+                if !seen.insert(handler.metadata.name.clone()) {
+                    return Err(protocol_error(format!(
+                        "server lists tool '{}' more than once",
+                        handler.metadata.name
+                    )));
+                }
+                handlers.push(Arc::new(handler));
+            }
+            cursor = page.get("nextCursor").and_then(Value::as_str).map(str::to_string);
+            if cursor.is_none() {
+                break;
+            }
+        }
+        tracing::info!(bundle, server = server_name, tools = handlers.len(), "Connected to MCP server");
+
+        Ok(Self {
+            metadata: ToolBundleMetadata {
+                name: bundle_name,
+                description,
+                config_schema: None,
+                secret_requirements: Vec::new(),
+                version: version.to_string(),
+            },
+            handlers,
+        })
+    }
+}
+
+impl ToolBundle for McpToolBundle {
+    fn metadata(&self) -> ToolBundleMetadata {
+        self.metadata.clone()
+    }
+
+    fn functions(&self) -> Vec<Arc<dyn ToolHandler>> {
+        self.handlers.clone()
+    }
+}
+
+struct McpToolHandler {
+    metadata: ToolFunctionMetadata,
+    /// Name the server knows the tool by.
+    remote_name: String,
+    structured: bool,
+    client: Arc<McpClient>,
+}
+
+impl McpToolHandler {
+    fn new(bundle: &BundleName, tool: &Value, client: Arc<McpClient>) -> Result<Self> {
+        let remote_name = tool
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or_else(|| protocol_error("tools/list entry without a name"))?
+            .to_string();
+        // Tool names are `bundle/tool`, so a '/' in the server's name cannot stay.
+        let local = LocalToolName::new(remote_name.replace('/', "_"))?;
+        let name = ToolName::qualified(bundle.clone(), local);
+        let class_name: String = ToolFunctionMetadata::derive_class_name(name.bundle(), name.local())
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+            .collect();
+        let input_schema = tool
+            .get("inputSchema")
+            .cloned()
+            .unwrap_or_else(|| json!({ "type": "object" }));
+        let output_schema = tool.get("outputSchema").cloned();
+        let structured = output_schema.is_some();
+        let dynamic_type = |suffix: &str| {
+            let type_name = format!("{class_name}{suffix}");
+            ToolTypeSpec {
+                ts_decl: Some(format!("export type {type_name} = Record<string, unknown>;")),
+                name: type_name,
+            }
+        };
+
+        Ok(Self {
+            metadata: ToolFunctionMetadata {
+                description: tool
+                    .get("description")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+                open_input_schema: json_schema_value::<()>(),
+                input_schema,
+                output_schema: output_schema.unwrap_or_else(content_output_schema),
+                open_input_type: ToolTypeSpec { name: ts_name::<()>(), ts_decl: ts_decl::<()>() },
+                input_type: dynamic_type("Input"),
+                output_type: dynamic_type("Output"),
+                tags: vec!["mcp".to_string(), bundle.as_str().to_string()],
+                secret_requirements: Vec::new(),
+                is_host_tool: true,
+                timeout: None,
+                class_name,
+                name,
+            },
+            remote_name,
+            structured,
+            client,
+        })
+    }
+}
+
+#[async_trait]
+impl ToolHandler for McpToolHandler {
+    fn metadata(&self) -> &ToolFunctionMetadata {
+        &self.metadata
+    }
+
+    async fn open_session(&self, ctx: ToolSessionContext) -> Result<Box<dyn ToolSession>> {
+        let client = self.client.clone();
+        let remote_name = self.remote_name.clone();
+        let structured = self.structured;
+        Ok(Box::new(OneShotSession::new(ctx, move |input| {
+            let client = client.clone();
+            let remote_name = remote_name.clone();
+            Box::pin(async move { client.call_tool(&remote_name, input, structured).await })
+        })))
+    }
+}
@@ -0,0 +1,161 @@
+//! Per-tool CPU profiling (feature `profiling`).
+//!
+//! With a [`ToolProfiler`] installed, every tool session the registry opens is
+//! sampled from open to finish or abort, and the samples are written as a
+//! pprof profile, one file per session:
+//!
+//! ```text
+//! <output_dir>/<bundle>.<tool>-<unix millis>-<session id>.pb
+//! ```
+//!
+//! The files open in `go tool pprof` (including its flame graph view) and
+//! speedscope. Sampling is process-wide and only one session is sampled at a
+//! time: sessions opened while another is being profiled are skipped, and a
+//! profile includes whatever else the process ran during the session, so it is
+//! sharpest for heavy tools run on their own (parsers, local models).
+
+use crate::tools::ToolName;
+use crate::tool_fsm::ToolSessionId;
+use baml_rt_core::{BamlRtError, Result};
+use pprof::protos::Message;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Samples per second taken while a tool runs.
+pub const DEFAULT_FREQUENCY_HZ: i32 = 997;
+
+/// Frames from these libraries are dropped from the sampled stacks.
+const BLOCKLIST: &[&str] = &["libc", "libgcc", "pthread", "vdso"];
+
+static GLOBAL: OnceLock<ToolProfiler> = OnceLock::new();
+
+/// Writes a pprof profile for each profiled tool session.
+#[derive(Debug, Clone)]
+pub struct ToolProfiler {
+    output_dir: PathBuf,
+    frequency_hz: i32,
+    tools: Option<HashSet<ToolName>>,
+}
+
+impl ToolProfiler {
+    /// Profile every tool into `output_dir`, creating it if needed.
+    pub fn new(output_dir: impl Into<PathBuf>) -> Result<Self> {
+        let output_dir = output_dir.into();
+        std::fs::create_dir_all(&output_dir)?;
+        Ok(Self {
+            output_dir,
+            frequency_hz: DEFAULT_FREQUENCY_HZ,
+            tools: None,
+        })
+    }
+
+    pub fn with_frequency_hz(mut self, frequency_hz: i32) -> Result<Self> {
+        if frequency_hz <= 0 {
+            return Err(BamlRtError::InvalidArgument(format!(
+                "Profiling frequency must be positive, got {frequency_hz}"
+            )));
+        }
+        self.frequency_hz = frequency_hz;
+        Ok(self)
+    }
+
+    /// Only profile these tools.
+    pub fn with_tools<I, S>(mut self, tools: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let tools = tools
+            .into_iter()
+            .map(|name| ToolName::parse(name.as_ref()))
+            .collect::<Result<HashSet<_>>>()?;
+        self.tools = Some(tools);
+        Ok(self)
+    }
+
+    pub fn output_dir(&self) -> &Path {
+        &self.output_dir
+    }
+
+    /// Make `profiler` the one tool registries in this process use.
+    ///
+    /// Fails if one is already installed.
+    pub fn install(profiler: ToolProfiler) -> Result<&'static ToolProfiler> {
+        GLOBAL.set(profiler).map_err(|_| {
+            BamlRtError::Configuration("A tool profiler is already installed".to_string())
+        })?;
+        Ok(GLOBAL.get().expect("tool profiler was just installed"))
+    }
+
+    /// The installed profiler, if any.
+    pub fn global() -> Option<&'static ToolProfiler> {
+        GLOBAL.get()
+    }
+
+    /// Start sampling a session of `tool`.
+    ///
+    /// Returns `None` when the tool is filtered out or another session is
+    /// already being sampled.
+    pub fn start(&self, tool: &ToolName, session_id: &ToolSessionId) -> Option<ToolProfile> {
+        if self.tools.as_ref().is_some_and(|tools| !tools.contains(tool)) {
+            return None;
+        }
+        let guard = pprof::ProfilerGuardBuilder::default()
+            .frequency(self.frequency_hz)
+            .blocklist(BLOCKLIST)
+            .build();
+        match guard {
+            Ok(guard) => Some(ToolProfile {
+                guard,
+                path: self.output_dir.join(profile_file_name(tool, session_id)),
+                tool: tool.clone(),
+                started: Instant::now(),
+            }),
+            Err(err) => {
+                tracing::debug!(tool = %tool, error = %err, "Tool session not profiled");
+                None
+            }
+        }
+    }
+}
+
+/// Sampling of one tool session; [`ToolProfile::finish`] writes it out.
+pub struct ToolProfile {
+    guard: pprof::ProfilerGuard<'static>,
+    path: PathBuf,
+    tool: ToolName,
+    started: Instant,
+}
+
+impl ToolProfile {
+    /// Stop sampling and write the pprof profile, returning its path.
+    pub fn finish(self) -> Result<PathBuf> {
+        let wall = self.started.elapsed();
+        let report = self.guard.report().build().map_err(profiling_error)?;
+        let samples: isize = report.data.values().sum();
+        let profile = report.pprof().map_err(profiling_error)?;
+        std::fs::write(&self.path, profile.encode_to_vec())?;
+        tracing::info!(
+            tool = %self.tool,
+            wall_ms = wall.as_millis() as u64,
+            samples,
+            path = %self.path.display(),
+            "Wrote tool profile"
+        );
+        Ok(self.path)
+    }
+}
+
+fn profile_file_name(tool: &ToolName, session_id: &ToolSessionId) -> String {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis())
+        .unwrap_or_default();
+    format!("{}-{millis}-{session_id}.pb", tool.to_string().replace('/', "."))
+}
+
+fn profiling_error(err: pprof::Error) -> BamlRtError {
+    BamlRtError::ToolExecution(format!("Tool profiling failed: {err}"))
+}
@@ -0,0 +1,100 @@
+//! Retry policy for tool execution.
+//!
+//! A [`RetryPolicy`] re-runs a one-shot tool call whose step fails with one of its
+//! retryable [`ToolFailureKind`]s, waiting an exponentially growing backoff between
+//! attempts. Each attempt opens a fresh session, so a timed-out or cancelled
+//! attempt never leaks into the next one.
+
+use crate::tool_fsm::{ToolFailure, ToolFailureKind};
+use std::time::Duration;
+
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(10);
+const DEFAULT_MULTIPLIER: f64 = 2.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+    retry_on: Vec<ToolFailureKind>,
+}
+
+impl RetryPolicy {
+    /// Up to `max_attempts` calls in total, retrying timeouts and rate limits.
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+            multiplier: DEFAULT_MULTIPLIER,
+            retry_on: vec![ToolFailureKind::Timeout, ToolFailureKind::RateLimited],
+        }
+    }
+
+    /// A single attempt; failures are returned as they are.
+    pub fn never() -> Self {
+        Self::new(1)
+    }
+
+    /// Wait `initial` before the first retry, growing by the multiplier up to `max`.
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max.max(initial);
+        self
+    }
+
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    /// Replace the failure kinds that are retried.
+    pub fn retry_on(mut self, kinds: impl IntoIterator<Item = ToolFailureKind>) -> Self {
+        self.retry_on = kinds.into_iter().collect();
+        self
+    }
+
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    pub fn retryable_kinds(&self) -> &[ToolFailureKind] {
+        &self.retry_on
+    }
+
+    /// Whether a call that failed on `attempt` (1-based) should run again.
+    pub fn should_retry(&self, failure: &ToolFailure, attempt: u32) -> bool {
+        attempt < self.max_attempts && self.retry_on.contains(&failure.kind)
+    }
+
+    /// Backoff before the attempt after `attempt` (1-based).
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let scaled = self.initial_backoff.as_secs_f64() * self.multiplier.powi(exponent);
+        if !scaled.is_finite() || scaled >= self.max_backoff.as_secs_f64() {
+            return self.max_backoff;
+        }
+        Duration::from_secs_f64(scaled)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(3)
+    }
+}
+
+/// A failed attempt that is about to be retried.
+#[derive(Debug, Clone)]
+pub struct RetryAttempt {
+    /// The attempt that failed (1-based)
+    pub attempt: u32,
+    pub max_attempts: u32,
+    pub failure: ToolFailure,
+    /// How long the failed attempt ran
+    pub duration: Duration,
+    /// Wait before the next attempt
+    pub backoff: Duration,
+}
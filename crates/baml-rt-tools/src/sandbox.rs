@@ -0,0 +1,424 @@
+//! Per-agent filesystem sandbox and the `fs` tool bundle confined to it.
+//!
+//! Each booted agent gets an [`FsSandbox`] rooted in a directory derived from
+//! the directory its package was extracted to. Paths the agent passes to the
+//! `fs/read`, `fs/write` and `fs/list` tools are relative to that root: absolute
+//! paths, `..` that climbs above the root, and symlinks leading out of it are
+//! rejected, so a packaged agent can persist files without reaching the rest of
+//! the host filesystem.
+//!
+//! The tools are host tools, so an agent still has to declare the ones it uses
+//! in its manifest; [`FsBundle::declared_in`] keeps only those.
+
+use crate::bundles::DEFAULT_BUNDLE_VERSION;
+use crate::register_tool_metadata;
+use crate::tools::{
+    BundleName, OneShotSession, ToolBundle, ToolBundleMetadata, ToolFunctionMetadata,
+    ToolHandler, ToolName, ToolSessionContext,
+};
+use crate::{json_schema_value, ts_decl, ts_name, ToolSession, ToolTypeSpec};
+use async_trait::async_trait;
+use baml_rt_core::{BamlRtError, Result};
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::future::Future;
+use std::path::{Component, Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use ts_rs::TS;
+
+/// Directory under an agent's extract dir that becomes its sandbox root.
+pub const SANDBOX_DIR: &str = "workdir";
+
+/// Largest file `fs/read` returns or `fs/write` leaves behind, unless overridden.
+pub const DEFAULT_MAX_FILE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// A directory an agent's file tools are confined to.
+#[derive(Debug, Clone)]
+pub struct FsSandbox {
+    /// Canonical, so resolved paths can be checked by prefix.
+    root: PathBuf,
+    max_file_bytes: u64,
+}
+
+impl FsSandbox {
+    /// Sandbox rooted at `root`, which is created if missing.
+    pub fn new(root: impl AsRef<Path>) -> Result<Self> {
+        let root = root.as_ref();
+        std::fs::create_dir_all(root).map_err(BamlRtError::Io)?;
+        Ok(Self {
+            root: root.canonicalize().map_err(BamlRtError::Io)?,
+            max_file_bytes: DEFAULT_MAX_FILE_BYTES,
+        })
+    }
+
+    /// Sandbox for the agent whose package was extracted to `extract_dir`.
+    pub fn for_extract_dir(extract_dir: impl AsRef<Path>) -> Result<Self> {
+        Self::new(extract_dir.as_ref().join(SANDBOX_DIR))
+    }
+
+    pub fn with_max_file_bytes(mut self, max_file_bytes: u64) -> Self {
+        self.max_file_bytes = max_file_bytes;
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn max_file_bytes(&self) -> u64 {
+        self.max_file_bytes
+    }
+
+    /// The host path for `path`, relative to the root.
+    ///
+    /// The path may not exist yet; the part of it that does must not lead out of
+    /// the root through a symlink. Fails if the root itself has been removed.
+    pub fn resolve(&self, path: &str) -> Result<PathBuf> {
+        let resolved = self.root.join(normalize(path)?);
+        let mut existing = resolved.as_path();
+        while existing.symlink_metadata().is_err() {
+            match existing.parent() {
+                Some(parent) if parent != existing && existing != self.root => existing = parent,
+                _ => {
+                    return Err(BamlRtError::Io(std::io::Error::new(
+                        std::io::ErrorKind::NotFound,
+                        format!("sandbox root {} no longer exists", self.root.display()),
+                    )));
+                }
+            }
+        }
+        // Canonicalizing follows symlinks; a dangling one fails here.
+        let canonical = existing.canonicalize().map_err(|_| escapes(path))?;
+        if !canonical.starts_with(&self.root) {
+            return Err(escapes(path));
+        }
+        Ok(resolved)
+    }
+
+    pub async fn read(&self, path: &str) -> Result<String> {
+        let resolved = self.resolve(path)?;
+        let size = tokio::fs::metadata(&resolved).await.map_err(BamlRtError::Io)?.len();
+        self.check_size(path, size)?;
+        let bytes = tokio::fs::read(&resolved).await.map_err(BamlRtError::Io)?;
+        String::from_utf8(bytes)
+            .map_err(|_| BamlRtError::InvalidArgument(format!("'{path}' is not UTF-8 text")))
+    }
+
+    /// Write `content` to `path`, creating parent directories; returns the
+    /// file's size afterwards.
+    pub async fn write(&self, path: &str, content: &str, append: bool) -> Result<u64> {
+        let resolved = self.resolve(path)?;
+        if resolved == self.root {
+            return Err(BamlRtError::InvalidArgument("Cannot write to the sandbox root".to_string()));
+        }
+        let existing = match tokio::fs::metadata(&resolved).await {
+            Ok(metadata) if append => metadata.len(),
+            _ => 0,
+        };
+        self.check_size(path, existing + content.len() as u64)?;
+        if let Some(parent) = resolved.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(BamlRtError::Io)?;
+        }
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(&resolved)
+            .await
+            .map_err(BamlRtError::Io)?;
+        tokio::io::AsyncWriteExt::write_all(&mut file, content.as_bytes())
+            .await
+            .map_err(BamlRtError::Io)?;
+        Ok(existing + content.len() as u64)
+    }
+
+    /// Entries of the directory at `path`, sorted by name.
+    pub async fn list(&self, path: &str) -> Result<Vec<FsEntry>> {
+        let resolved = self.resolve(path)?;
+        let mut reader = tokio::fs::read_dir(&resolved).await.map_err(BamlRtError::Io)?;
+        let mut entries = Vec::new();
+        while let Some(entry) = reader.next_entry().await.map_err(BamlRtError::Io)? {
+            // A symlink is listed as itself, never as what it points to.
+            let metadata =
+                tokio::fs::symlink_metadata(entry.path()).await.map_err(BamlRtError::Io)?;
+            let kind = if metadata.is_dir() {
+                FsEntryKind::Dir
+            } else if metadata.is_file() {
+                FsEntryKind::File
+            } else {
+                FsEntryKind::Other
+            };
+            entries.push(FsEntry {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                kind,
+                bytes: metadata.is_file().then_some(metadata.len()),
+            });
+        }
+        entries.sort_by(|left, right| left.name.cmp(&right.name));
+        Ok(entries)
+    }
+
+    fn check_size(&self, path: &str, size: u64) -> Result<()> {
+        if size > self.max_file_bytes {
+            return Err(BamlRtError::InvalidArgument(format!(
+                "'{path}' would be {size} bytes, over the sandbox limit of {}",
+                self.max_file_bytes
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// `path` as a relative path with no `.` or `..` left in it.
+fn normalize(path: &str) -> Result<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in Path::new(path).components() {
+        match component {
+            Component::Normal(part) => normalized.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    return Err(escapes(path));
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => {
+                return Err(BamlRtError::InvalidArgument(format!(
+                    "Sandbox paths must be relative: '{path}'"
+                )));
+            }
+        }
+    }
+    Ok(normalized)
+}
+
+fn escapes(path: &str) -> BamlRtError {
+    BamlRtError::InvalidArgument(format!("Path '{path}' leads outside the sandbox"))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, TS)]
+#[ts(export)]
+pub struct FsReadInput {
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, TS)]
+#[ts(export)]
+pub struct FsReadOutput {
+    pub path: String,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, TS)]
+#[ts(export)]
+pub struct FsWriteInput {
+    pub path: String,
+    pub content: String,
+    /// Append to the file instead of replacing it
+    #[serde(default)]
+    pub append: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, TS)]
+#[ts(export)]
+pub struct FsWriteOutput {
+    pub path: String,
+    /// Size of the file after the write
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, TS)]
+#[ts(export)]
+pub struct FsListInput {
+    /// Directory to list; the sandbox root when absent
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, TS)]
+#[ts(export)]
+pub struct FsListOutput {
+    pub path: String,
+    pub entries: Vec<FsEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
+#[ts(export)]
+pub struct FsEntry {
+    pub name: String,
+    pub kind: FsEntryKind,
+    /// Size of files; absent for directories
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum FsEntryKind {
+    File,
+    Dir,
+    Other,
+}
+
+pub const FS_BUNDLE: &str = "fs";
+pub const FS_READ: &str = "fs/read";
+pub const FS_WRITE: &str = "fs/write";
+pub const FS_LIST: &str = "fs/list";
+
+fn fs_metadata<I: TS + JsonSchema, O: TS + JsonSchema>(
+    name: &str,
+    description: &str,
+) -> ToolFunctionMetadata {
+    let name = ToolName::parse(name).expect("fs tool names must be valid");
+    let class_name = ToolFunctionMetadata::derive_class_name(name.bundle(), name.local());
+    ToolFunctionMetadata {
+        name: name.clone(),
+        class_name,
+        description: description.to_string(),
+        open_input_schema: json_schema_value::<()>(),
+        input_schema: json_schema_value::<I>(),
+        output_schema: json_schema_value::<O>(),
+        open_input_type: ToolTypeSpec { name: ts_name::<()>(), ts_decl: ts_decl::<()>() },
+        input_type: ToolTypeSpec { name: ts_name::<I>(), ts_decl: ts_decl::<I>() },
+        output_type: ToolTypeSpec { name: ts_name::<O>(), ts_decl: ts_decl::<O>() },
+        tags: vec![FS_BUNDLE.to_string(), name.local().as_str().to_string()],
+        secret_requirements: Vec::new(),
+        is_host_tool: true,
+        timeout: None,
+    }
+}
+
+pub fn fs_read_metadata() -> ToolFunctionMetadata {
+    fs_metadata::<FsReadInput, FsReadOutput>(
+        FS_READ,
+        "Reads a UTF-8 text file from the agent's sandboxed working directory.",
+    )
+}
+
+pub fn fs_write_metadata() -> ToolFunctionMetadata {
+    fs_metadata::<FsWriteInput, FsWriteOutput>(
+        FS_WRITE,
+        "Writes or appends UTF-8 text to a file in the agent's sandboxed working directory, creating parent directories.",
+    )
+}
+
+pub fn fs_list_metadata() -> ToolFunctionMetadata {
+    fs_metadata::<FsListInput, FsListOutput>(
+        FS_LIST,
+        "Lists a directory in the agent's sandboxed working directory.",
+    )
+}
+
+register_tool_metadata!(fs_read_metadata);
+register_tool_metadata!(fs_write_metadata);
+register_tool_metadata!(fs_list_metadata);
+
+/// The `fs` bundle: file tools confined to one [`FsSandbox`].
+pub struct FsBundle {
+    sandbox: Arc<FsSandbox>,
+    tools: Vec<ToolFunctionMetadata>,
+}
+
+impl FsBundle {
+    /// Every `fs` tool.
+    pub fn new(sandbox: FsSandbox) -> Self {
+        Self {
+            sandbox: Arc::new(sandbox),
+            tools: vec![fs_read_metadata(), fs_write_metadata(), fs_list_metadata()],
+        }
+    }
+
+    /// The `fs` tools named in `tools` (manifest tool names), or `None` when
+    /// it names none.
+    pub fn declared_in(sandbox: FsSandbox, tools: &[String]) -> Option<Self> {
+        let mut bundle = Self::new(sandbox);
+        bundle
+            .tools
+            .retain(|metadata| tools.iter().any(|tool| *tool == metadata.name.to_string()));
+        (!bundle.tools.is_empty()).then_some(bundle)
+    }
+
+    pub fn sandbox(&self) -> &FsSandbox {
+        &self.sandbox
+    }
+}
+
+impl ToolBundle for FsBundle {
+    fn metadata(&self) -> ToolBundleMetadata {
+        ToolBundleMetadata {
+            name: BundleName::new(FS_BUNDLE).expect("fs bundle name must be valid"),
+            description: "File tools confined to the agent's working directory".to_string(),
+            config_schema: None,
+            secret_requirements: Vec::new(),
+            version: DEFAULT_BUNDLE_VERSION.to_string(),
+        }
+    }
+
+    fn functions(&self) -> Vec<Arc<dyn ToolHandler>> {
+        self.tools
+            .iter()
+            .map(|metadata| {
+                Arc::new(FsToolHandler { metadata: metadata.clone(), sandbox: self.sandbox.clone() })
+                    as Arc<dyn ToolHandler>
+            })
+            .collect()
+    }
+}
+
+struct FsToolHandler {
+    metadata: ToolFunctionMetadata,
+    sandbox: Arc<FsSandbox>,
+}
+
+type ToolFuture = Pin<Box<dyn Future<Output = Result<Value>> + Send>>;
+
+fn parse_input<I: DeserializeOwned>(input: Value) -> Result<I> {
+    serde_json::from_value(input)
+        .map_err(|err| BamlRtError::InvalidArgument(format!("Invalid input: {err}")))
+}
+
+fn to_output<O: Serialize>(output: O) -> Result<Value> {
+    serde_json::to_value(output)
+        .map_err(|err| BamlRtError::InvalidArgument(format!("Invalid output: {err}")))
+}
+
+async fn call(sandbox: Arc<FsSandbox>, tool: String, input: Value) -> Result<Value> {
+    match tool.as_str() {
+        FS_READ => {
+            let input: FsReadInput = parse_input(input)?;
+            let content = sandbox.read(&input.path).await?;
+            to_output(FsReadOutput { path: input.path, content })
+        }
+        FS_WRITE => {
+            let input: FsWriteInput = parse_input(input)?;
+            let bytes = sandbox.write(&input.path, &input.content, input.append).await?;
+            to_output(FsWriteOutput { path: input.path, bytes })
+        }
+        FS_LIST => {
+            let input: FsListInput = parse_input(input)?;
+            let path = input.path.unwrap_or_else(|| ".".to_string());
+            let entries = sandbox.list(&path).await?;
+            to_output(FsListOutput { path, entries })
+        }
+        other => Err(BamlRtError::ToolExecution(format!("Unknown fs tool '{other}'"))),
+    }
+}
+
+#[async_trait]
+impl ToolHandler for FsToolHandler {
+    fn metadata(&self) -> &ToolFunctionMetadata {
+        &self.metadata
+    }
+
+    async fn open_session(&self, ctx: ToolSessionContext) -> Result<Box<dyn ToolSession>> {
+        let sandbox = self.sandbox.clone();
+        let tool = self.metadata.name.to_string();
+        Ok(Box::new(OneShotSession::new(ctx, move |input| -> ToolFuture {
+            Box::pin(call(sandbox.clone(), tool.clone(), input))
+        })))
+    }
+}
@@ -0,0 +1,79 @@
+//! JSON Schema checks on tool input and output.
+//!
+//! Schemas come from [`ToolFunctionMetadata`] and are compiled once, when the
+//! tool is registered. A `null` schema means the tool declared none, and a
+//! schema that does not compile is logged and skipped rather than rejecting the
+//! tool, so validation never makes a registration fail.
+
+use crate::tool_fsm::ToolFailure;
+use crate::tools::ToolFunctionMetadata;
+use jsonschema::Validator;
+use serde_json::Value;
+
+/// Compiled input and output schemas of one tool.
+pub(crate) struct ToolSchemas {
+    input: Option<Validator>,
+    output: Option<Validator>,
+}
+
+impl ToolSchemas {
+    pub(crate) fn compile(metadata: &ToolFunctionMetadata) -> Self {
+        Self {
+            input: compile(metadata, "input", &metadata.input_schema),
+            output: compile(metadata, "output", &metadata.output_schema),
+        }
+    }
+
+    pub(crate) fn check_input(&self, tool: &str, input: &Value) -> Result<(), ToolFailure> {
+        check(self.input.as_ref(), tool, "input", input)
+    }
+
+    pub(crate) fn check_output(&self, tool: &str, output: &Value) -> Result<(), ToolFailure> {
+        check(self.output.as_ref(), tool, "output", output)
+    }
+}
+
+fn compile(metadata: &ToolFunctionMetadata, which: &str, schema: &Value) -> Option<Validator> {
+    if schema.is_null() {
+        return None;
+    }
+    match jsonschema::validator_for(schema) {
+        Ok(validator) => Some(validator),
+        Err(err) => {
+            tracing::warn!(
+                tool = %metadata.name,
+                schema = which,
+                error = %err,
+                "Tool schema does not compile; skipping validation"
+            );
+            None
+        }
+    }
+}
+
+fn check(
+    validator: Option<&Validator>,
+    tool: &str,
+    which: &str,
+    instance: &Value,
+) -> Result<(), ToolFailure> {
+    let Some(validator) = validator else {
+        return Ok(());
+    };
+    let violations: Vec<String> = validator
+        .iter_errors(instance)
+        .map(|error| {
+            let path = error.instance_path.to_string();
+            if path.is_empty() { error.to_string() } else { format!("{}: {}", path, error) }
+        })
+        .collect();
+    if violations.is_empty() {
+        return Ok(());
+    }
+    Err(ToolFailure::schema_violation(format!(
+        "{} of tool '{}' does not match its schema: {}",
+        which,
+        tool,
+        violations.join("; ")
+    )))
+}
@@ -0,0 +1,162 @@
+//! Checkpoints of open tool sessions, so a restarted agent can resume them.
+//!
+//! A session whose [`ToolSession::snapshot`](crate::ToolSession::snapshot)
+//! returns state is checkpointed by the [`ToolRegistry`](crate::ToolRegistry)
+//! after every accepted input and every streamed step, and its checkpoint is
+//! dropped once the session finishes or is aborted. After a restart,
+//! [`ToolRegistry::resume_session`](crate::ToolRegistry::resume_session) opens
+//! the tool again under the same [`ToolSessionId`] and hands the last
+//! checkpoint to [`ToolSession::restore`](crate::ToolSession::restore).
+
+use crate::tool_fsm::ToolSessionId;
+use crate::tools::ToolName;
+use async_trait::async_trait;
+use baml_rt_core::{BamlRtError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The state of one open session at its last checkpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionCheckpoint {
+    pub session_id: String,
+    pub tool: ToolName,
+    /// What the session's `snapshot` returned.
+    pub state: Value,
+    /// The step timeout the session was opened with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    pub saved_at_ms: u64,
+}
+
+impl SessionCheckpoint {
+    pub fn session_id(&self) -> Result<ToolSessionId> {
+        ToolSessionId::new(self.session_id.clone())
+    }
+}
+
+pub(crate) fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Where the registry keeps session checkpoints.
+#[async_trait]
+pub trait SessionStore: Send + Sync {
+    /// Save `checkpoint`, replacing any earlier one for its session.
+    async fn save(&self, checkpoint: SessionCheckpoint) -> Result<()>;
+    async fn load(&self, session_id: &ToolSessionId) -> Result<Option<SessionCheckpoint>>;
+    async fn remove(&self, session_id: &ToolSessionId) -> Result<()>;
+    /// Every saved checkpoint, oldest first.
+    async fn list(&self) -> Result<Vec<SessionCheckpoint>>;
+}
+
+/// Keeps checkpoints for the life of the process; for tests and for resuming
+/// sessions across registries in one process.
+#[derive(Debug, Default)]
+pub struct InMemorySessionStore {
+    checkpoints: Mutex<HashMap<String, SessionCheckpoint>>,
+}
+
+impl InMemorySessionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl SessionStore for InMemorySessionStore {
+    async fn save(&self, checkpoint: SessionCheckpoint) -> Result<()> {
+        self.checkpoints.lock().unwrap().insert(checkpoint.session_id.clone(), checkpoint);
+        Ok(())
+    }
+
+    async fn load(&self, session_id: &ToolSessionId) -> Result<Option<SessionCheckpoint>> {
+        Ok(self.checkpoints.lock().unwrap().get(session_id.as_str()).cloned())
+    }
+
+    async fn remove(&self, session_id: &ToolSessionId) -> Result<()> {
+        self.checkpoints.lock().unwrap().remove(session_id.as_str());
+        Ok(())
+    }
+
+    async fn list(&self) -> Result<Vec<SessionCheckpoint>> {
+        let mut checkpoints: Vec<_> = self.checkpoints.lock().unwrap().values().cloned().collect();
+        checkpoints.sort_by_key(|checkpoint| checkpoint.saved_at_ms);
+        Ok(checkpoints)
+    }
+}
+
+/// One JSON file per session in a directory, written through a temporary
+/// file so a crash mid-write leaves the previous checkpoint in place.
+#[derive(Debug, Clone)]
+pub struct FileSessionStore {
+    dir: PathBuf,
+}
+
+impl FileSessionStore {
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, session_id: &str) -> PathBuf {
+        self.dir.join(format!("{session_id}.json"))
+    }
+}
+
+#[async_trait]
+impl SessionStore for FileSessionStore {
+    async fn save(&self, checkpoint: SessionCheckpoint) -> Result<()> {
+        let path = self.path(&checkpoint.session_id);
+        let partial = path.with_extension("json.tmp");
+        tokio::fs::write(&partial, serde_json::to_vec(&checkpoint)?).await?;
+        tokio::fs::rename(&partial, &path).await?;
+        Ok(())
+    }
+
+    async fn load(&self, session_id: &ToolSessionId) -> Result<Option<SessionCheckpoint>> {
+        match tokio::fs::read(self.path(session_id.as_str())).await {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(BamlRtError::Io(err)),
+        }
+    }
+
+    async fn remove(&self, session_id: &ToolSessionId) -> Result<()> {
+        match tokio::fs::remove_file(self.path(session_id.as_str())).await {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(BamlRtError::Io(err)),
+            _ => Ok(()),
+        }
+    }
+
+    async fn list(&self) -> Result<Vec<SessionCheckpoint>> {
+        let mut checkpoints = Vec::new();
+        let mut entries = tokio::fs::read_dir(&self.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+                continue;
+            }
+            let bytes = tokio::fs::read(&path).await?;
+            match serde_json::from_slice::<SessionCheckpoint>(&bytes) {
+                Ok(checkpoint) => checkpoints.push(checkpoint),
+                Err(err) => {
+                    tracing::warn!(path = %path.display(), error = %err, "Skipping unreadable tool session checkpoint");
+                }
+            }
+        }
+        checkpoints.sort_by_key(|checkpoint| checkpoint.saved_at_ms);
+        Ok(checkpoints)
+    }
+}
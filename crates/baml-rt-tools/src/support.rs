@@ -67,6 +67,7 @@ pub fn support_calculate_metadata() -> ToolFunctionMetadata {
         secret_requirements: Vec::new(),
         // ALL Rust tools are host tools - they must be declared in manifest.json
         is_host_tool: true,
+        timeout: None,
     }
 }
 
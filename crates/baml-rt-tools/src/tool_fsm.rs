@@ -4,6 +4,9 @@ use baml_rt_core::BamlRtError;
 use async_trait::async_trait;
 use serde_json::Value;
 use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::Notify;
 use uuid::Uuid;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -36,9 +39,27 @@ pub enum ToolFailureKind {
     NotAuthorized,
     RateLimited,
     Cancelled,
+    Timeout,
+    /// Input or output did not match the tool's JSON Schema.
+    SchemaViolation,
     Unknown,
 }
 
+impl ToolFailureKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ToolFailureKind::InvalidInput => "InvalidInput",
+            ToolFailureKind::ExecutionFailed => "ExecutionFailed",
+            ToolFailureKind::NotAuthorized => "NotAuthorized",
+            ToolFailureKind::RateLimited => "RateLimited",
+            ToolFailureKind::Cancelled => "Cancelled",
+            ToolFailureKind::Timeout => "Timeout",
+            ToolFailureKind::SchemaViolation => "SchemaViolation",
+            ToolFailureKind::Unknown => "Unknown",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ToolFailure {
     pub kind: ToolFailureKind,
@@ -63,14 +84,59 @@ impl ToolFailure {
         }
     }
 
+    pub fn cancelled(message: impl Into<String>) -> Self {
+        Self {
+            kind: ToolFailureKind::Cancelled,
+            message: message.into(),
+            retryable: false,
+        }
+    }
+
+    pub fn timeout(message: impl Into<String>) -> Self {
+        Self {
+            kind: ToolFailureKind::Timeout,
+            message: message.into(),
+            retryable: true,
+        }
+    }
+
+    pub fn schema_violation(message: impl Into<String>) -> Self {
+        Self {
+            kind: ToolFailureKind::SchemaViolation,
+            message: message.into(),
+            retryable: false,
+        }
+    }
+
+    /// The runtime error reported to callers when the failure ends a call
+    pub fn into_error(self) -> BamlRtError {
+        let message = format!("Tool failure ({:?}): {}", self.kind, self.message);
+        match self.kind {
+            ToolFailureKind::Timeout => BamlRtError::Timeout(message),
+            ToolFailureKind::SchemaViolation => BamlRtError::SchemaMismatch(message),
+            ToolFailureKind::NotAuthorized => BamlRtError::ToolNotAllowed(message),
+            ToolFailureKind::RateLimited => BamlRtError::Budget(message),
+            ToolFailureKind::ExecutionFailed => BamlRtError::ToolExecution(message),
+            ToolFailureKind::InvalidInput | ToolFailureKind::Cancelled | ToolFailureKind::Unknown => {
+                BamlRtError::InvalidArgument(message)
+            }
+        }
+    }
+
     pub fn from_error(error: &BamlRtError) -> Self {
-        let kind = match error {
+        let kind = match error.root() {
             BamlRtError::InvalidArgument(_) | BamlRtError::InvalidArgumentWithSource { .. } => {
                 ToolFailureKind::InvalidInput
             }
-            BamlRtError::QuickJs(_) | BamlRtError::QuickJsWithSource { .. } => {
-                ToolFailureKind::ExecutionFailed
-            }
+            BamlRtError::SchemaMismatch(_) => ToolFailureKind::SchemaViolation,
+            BamlRtError::ToolNotAllowed(_) => ToolFailureKind::NotAuthorized,
+            BamlRtError::Budget(_) => ToolFailureKind::RateLimited,
+            BamlRtError::Timeout(_) => ToolFailureKind::Timeout,
+            BamlRtError::QuickJs(_)
+            | BamlRtError::QuickJsWithSource { .. }
+            | BamlRtError::JsRejection { .. }
+            | BamlRtError::PromiseTimeout { .. }
+            | BamlRtError::ResourceLimit { .. } => ToolFailureKind::ExecutionFailed,
             BamlRtError::ToolExecution(_) => ToolFailureKind::ExecutionFailed,
             _ => ToolFailureKind::Unknown,
         };
@@ -82,6 +148,49 @@ impl ToolFailure {
     }
 }
 
+/// Signals a tool session to stop.
+///
+/// Clones share the same state, so whoever holds a clone can cancel the session
+/// while another task is waiting on it.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<CancellationState>,
+}
+
+#[derive(Debug, Default)]
+struct CancellationState {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        self.inner.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once [`Self::cancel`] has been called.
+    pub async fn cancelled(&self) {
+        loop {
+            let notified = self.inner.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum ToolSessionError {
     Transport(BamlRtError),
@@ -107,4 +216,18 @@ pub trait ToolSession: Send + Sync {
     async fn next(&mut self) -> std::result::Result<ToolStep, ToolSessionError>;
     async fn finish(&mut self) -> std::result::Result<(), ToolSessionError>;
     async fn abort(&mut self, reason: Option<String>) -> std::result::Result<(), ToolSessionError>;
+
+    /// State to resume the session from after a restart, or `None` if it
+    /// cannot be resumed. Taken after each accepted input and streamed step.
+    async fn snapshot(&self) -> std::result::Result<Option<Value>, ToolSessionError> {
+        Ok(None)
+    }
+
+    /// Continue from a state [`Self::snapshot`] returned, on a session just
+    /// opened under the same id.
+    async fn restore(&mut self, _state: Value) -> std::result::Result<(), ToolSessionError> {
+        Err(ToolSessionError::Transport(BamlRtError::SessionStateViolation(
+            "Tool session does not support restore".to_string(),
+        )))
+    }
 }
@@ -5,8 +5,14 @@
 
 use baml_rt_core::{BamlRtError, Result};
 use baml_rt_core::ids::UuidId;
+use baml_rt_core::manifest::BundleRequirement;
 use crate::bundles::BundleType;
-use crate::tool_fsm::{ToolFailure, ToolSessionError, ToolSession, ToolSessionId, ToolStep};
+use crate::retry::{RetryAttempt, RetryPolicy};
+use crate::schema_check::ToolSchemas;
+use crate::session_store::{now_ms, SessionCheckpoint, SessionStore};
+use crate::tool_fsm::{
+    CancellationToken, ToolFailure, ToolSessionError, ToolSession, ToolSessionId, ToolStep,
+};
 use crate::tool_schema::{json_schema_value, ts_decl, ts_name, ToolType};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
@@ -18,6 +24,7 @@ use std::future::Future;
 use std::marker::PhantomData;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
@@ -126,6 +133,13 @@ pub trait BamlTool: Send + Sync + 'static {
         json_schema_value::<Self::Output>()
     }
 
+    /// Longest a call may run before it fails with `ToolFailureKind::Timeout`
+    ///
+    /// `None` uses the registry default.
+    fn timeout(&self) -> Option<std::time::Duration> {
+        None
+    }
+
     /// Execute the tool with the given arguments
     ///
     /// # Arguments
@@ -367,6 +381,9 @@ pub struct ToolFunctionMetadata {
     pub secret_requirements: Vec<ToolSecretRequirement>,
     /// Whether this tool is a host tool (manifest allowlist applies)
     pub is_host_tool: bool,
+    /// Longest a single step may run before the session fails with
+    /// `ToolFailureKind::Timeout`; falls back to the registry default
+    pub timeout: Option<Duration>,
 }
 
 impl ToolFunctionMetadata {
@@ -396,6 +413,8 @@ pub struct ToolFunctionMetadataExport {
     pub tags: Vec<String>,
     pub secret_requirements: Vec<ToolSecretRequirement>,
     pub is_host_tool: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
 }
 
 impl From<&ToolFunctionMetadata> for ToolFunctionMetadataExport {
@@ -413,6 +432,7 @@ impl From<&ToolFunctionMetadata> for ToolFunctionMetadataExport {
             tags: metadata.tags.clone(),
             secret_requirements: metadata.secret_requirements.clone(),
             is_host_tool: metadata.is_host_tool,
+            timeout_ms: metadata.timeout.map(|timeout| timeout.as_millis() as u64),
         }
     }
 }
@@ -423,6 +443,12 @@ pub struct ToolBundleMetadata {
     pub description: String,
     pub config_schema: Option<Value>,
     pub secret_requirements: Vec<ToolSecretRequirement>,
+    #[serde(default = "default_bundle_version")]
+    pub version: String,
+}
+
+fn default_bundle_version() -> String {
+    crate::bundles::DEFAULT_BUNDLE_VERSION.to_string()
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -434,6 +460,9 @@ pub enum ToolCapability {
 pub struct ToolSessionContext {
     pub session_id: ToolSessionId,
     pub tool_name: ToolName,
+    /// Cancelled when the session times out or is aborted from outside; long-running
+    /// sessions should stop work once it fires
+    pub cancellation: CancellationToken,
 }
 
 #[async_trait]
@@ -453,20 +482,46 @@ pub trait ToolBundle: Send + Sync {
 /// Registry for dynamically registered tool functions
 pub struct ToolRegistry {
     tools: HashMap<ToolName, (ToolFunctionMetadata, Arc<dyn ToolHandler>)>,
+    schemas: HashMap<ToolName, ToolSchemas>,
+    schema_validation: bool,
     bundles: HashMap<BundleName, ToolBundleMetadata>,
     allowlist: Option<HashSet<ToolName>>,
-    sessions: HashMap<ToolSessionId, Arc<Mutex<Box<dyn ToolSession>>>>,
+    sessions: HashMap<ToolSessionId, OpenSession>,
+    default_timeout: Option<Duration>,
+    retry_policies: HashMap<ToolName, RetryPolicy>,
+    default_retry_policy: Option<RetryPolicy>,
+    session_store: Option<Arc<dyn SessionStore>>,
+}
+
+/// A session opened through the registry
+struct OpenSession {
+    tool: ToolName,
+    session: Arc<Mutex<Box<dyn ToolSession>>>,
+    cancellation: CancellationToken,
+    timeout: Option<Duration>,
+    #[cfg(feature = "profiling")]
+    profile: Option<crate::profiling::ToolProfile>,
+}
+
+impl OpenSession {
+    /// Write the session's profile, if it was sampled.
+    #[cfg(feature = "profiling")]
+    fn write_profile(&mut self) {
+        if let Some(profile) = self.profile.take()
+            && let Err(err) = profile.finish()
+        {
+            tracing::warn!(tool = %self.tool, error = %err, "Failed to write tool profile");
+        }
+    }
+
+    #[cfg(not(feature = "profiling"))]
+    fn write_profile(&mut self) {}
 }
 
 fn map_session_error(error: ToolSessionError) -> BamlRtError {
     match error {
         ToolSessionError::Transport(err) => err,
-        ToolSessionError::Tool(failure) => {
-            BamlRtError::InvalidArgument(format!(
-                "Tool failure ({:?}): {}",
-                failure.kind, failure.message
-            ))
-        }
+        ToolSessionError::Tool(failure) => failure.into_error(),
     }
 }
 
@@ -659,7 +714,7 @@ impl<T: BamlTool> ToolHandler for ToolWrapper<T> {
             let tool = tool.clone();
             Box::pin(async move {
                 let parsed: T::Input = serde_json::from_value(input).map_err(|err| {
-                    BamlRtError::InvalidArgument(format!("Invalid input: {}", err))
+                    BamlRtError::SchemaMismatch(format!("Invalid input: {}", err))
                 })?;
                 let output = tool.execute(parsed).await?;
                 serde_json::to_value(output)
@@ -674,12 +729,69 @@ impl ToolRegistry {
     pub fn new() -> Self {
         Self {
             tools: HashMap::new(),
+            schemas: HashMap::new(),
+            schema_validation: true,
             bundles: HashMap::new(),
             allowlist: None,
             sessions: HashMap::new(),
+            default_timeout: None,
+            retry_policies: HashMap::new(),
+            default_retry_policy: None,
+            session_store: None,
         }
     }
 
+    /// Timeout for tools whose metadata does not set one; `None` waits forever
+    pub fn set_default_timeout(&mut self, timeout: Option<Duration>) {
+        self.default_timeout = timeout;
+    }
+
+    pub fn default_timeout(&self) -> Option<Duration> {
+        self.default_timeout
+    }
+
+    /// Retry policy for one tool, taking precedence over the registry default
+    pub fn set_retry_policy(&mut self, name: &str, policy: RetryPolicy) -> Result<()> {
+        self.retry_policies.insert(ToolName::parse(name)?, policy);
+        Ok(())
+    }
+
+    /// Checkpoint sessions that support it into `store`, so they can be
+    /// resumed with [`Self::resume_session`] after a restart
+    pub fn set_session_store(&mut self, store: Arc<dyn SessionStore>) {
+        self.session_store = Some(store);
+    }
+
+    pub fn session_store(&self) -> Option<&Arc<dyn SessionStore>> {
+        self.session_store.as_ref()
+    }
+
+    /// Retry policy for tools without their own; `None` runs every call once
+    pub fn set_default_retry_policy(&mut self, policy: Option<RetryPolicy>) {
+        self.default_retry_policy = policy;
+    }
+
+    /// The policy `execute` applies to `name`, if any
+    pub fn retry_policy(&self, name: &str) -> Option<RetryPolicy> {
+        let parsed = ToolName::parse(name).ok()?;
+        self.retry_policies
+            .get(&parsed)
+            .or(self.default_retry_policy.as_ref())
+            .cloned()
+    }
+
+    /// Check tool input and output against their JSON Schemas; on by default
+    ///
+    /// Input that does not match is rejected before it reaches the tool, and output
+    /// that does not match turns the step into a `ToolFailureKind::SchemaViolation`.
+    pub fn set_schema_validation(&mut self, enabled: bool) {
+        self.schema_validation = enabled;
+    }
+
+    pub fn schema_validation(&self) -> bool {
+        self.schema_validation
+    }
+
     pub fn set_allowlist(&mut self, allowlist: HashSet<ToolName>) {
         self.allowlist = Some(allowlist);
     }
@@ -757,11 +869,20 @@ impl ToolRegistry {
             )));
         }
 
+        self.bundles.entry(expected_bundle.clone()).or_insert_with(|| ToolBundleMetadata {
+            name: expected_bundle,
+            description: T::Bundle::description().to_string(),
+            config_schema: T::Bundle::config_schema(),
+            secret_requirements: Vec::new(),
+            version: T::Bundle::version().to_string(),
+        });
+
         let description_str = tool.description().to_string();
         let open_input_schema = tool.open_input_schema();
         let input_schema = tool.input_schema();
         let output_schema = tool.output_schema();
         let class_name = T::class_name();
+        let timeout = tool.timeout();
         let metadata = ToolFunctionMetadata {
             name: name.clone(),
             class_name: class_name.clone(),
@@ -785,6 +906,7 @@ impl ToolRegistry {
             secret_requirements: Vec::new(),
             // ALL Rust tools are host tools - they must be declared in manifest.json
             is_host_tool: true,
+            timeout,
         };
 
         let tool_handler: Arc<dyn ToolHandler> = Arc::new(ToolWrapper {
@@ -792,7 +914,7 @@ impl ToolRegistry {
             metadata,
         });
 
-        self.tools.insert(name.clone(), (tool_handler.metadata().clone(), tool_handler));
+        self.insert_tool(tool_handler.metadata().clone(), tool_handler);
 
         tracing::info!(
             tool = %name,
@@ -824,9 +946,20 @@ impl ToolRegistry {
             "Registered dynamic tool function"
         );
 
-        self.tools
-            .insert(metadata.name.clone(), (metadata, handler));
+        self.insert_tool(metadata, handler);
+
+        Ok(())
+    }
 
+    /// Register a tool with dynamic metadata, replacing one already registered
+    /// under the same name, e.g. when what the metadata describes has changed.
+    pub fn replace_dynamic(
+        &mut self,
+        metadata: ToolFunctionMetadata,
+        handler: Arc<dyn ToolHandler>,
+    ) -> Result<()> {
+        self.ensure_allowed(&metadata.name, metadata.is_host_tool)?;
+        self.insert_tool(metadata, handler);
         Ok(())
     }
 
@@ -853,12 +986,55 @@ impl ToolRegistry {
                     metadata.name
                 )));
             }
-            self.tools.insert(metadata.name.clone(), (metadata, handler.clone()));
+            self.insert_tool(metadata, handler.clone());
         }
         self.bundles.insert(bundle_meta.name.clone(), bundle_meta);
         Ok(())
     }
 
+    fn insert_tool(&mut self, metadata: ToolFunctionMetadata, handler: Arc<dyn ToolHandler>) {
+        self.schemas.insert(metadata.name.clone(), ToolSchemas::compile(&metadata));
+        self.tools.insert(metadata.name.clone(), (metadata, handler));
+    }
+
+    /// Metadata for every registered bundle, including bundles of `BamlTool`s.
+    pub fn bundles(&self) -> impl Iterator<Item = &ToolBundleMetadata> {
+        self.bundles.values()
+    }
+
+    /// Check that each requirement names a registered bundle whose version satisfies
+    /// its constraint. Every unmet requirement is listed in the error.
+    pub fn check_bundle_requirements(&self, requirements: &[BundleRequirement]) -> Result<()> {
+        let mut unmet = Vec::new();
+        for requirement in requirements {
+            let registered = BundleName::new(requirement.bundle.as_str())
+                .ok()
+                .and_then(|name| self.bundles.get(&name));
+            let Some(registered) = registered else {
+                unmet.push(format!("{}: bundle is not registered", requirement));
+                continue;
+            };
+            match semver::Version::parse(&registered.version) {
+                Ok(version) if requirement.matches(&version) => {}
+                Ok(version) => unmet.push(format!(
+                    "{}: registered version {} does not satisfy the constraint",
+                    requirement, version
+                )),
+                Err(err) => unmet.push(format!(
+                    "{}: registered version '{}' is not valid semver ({})",
+                    requirement, registered.version, err
+                )),
+            }
+        }
+        if unmet.is_empty() {
+            return Ok(());
+        }
+        Err(BamlRtError::ToolRegistration(format!(
+            "unsatisfied tool bundle requirements:\n  - {}",
+            unmet.join("\n  - ")
+        )))
+    }
+
     /// Get tool metadata by name
     pub fn get_metadata(&self, name: &str) -> Option<&ToolFunctionMetadata> {
         ToolName::parse(name)
@@ -949,53 +1125,315 @@ impl ToolRegistry {
 
     /// Open a tool session and return its session id.
     pub async fn open_session(&mut self, name: &str) -> Result<ToolSessionId> {
+        self.open_session_with_timeout(name, None).await
+    }
+
+    /// Open a tool session whose steps time out after `timeout`, overriding the
+    /// tool and registry defaults when set.
+    pub async fn open_session_with_timeout(
+        &mut self,
+        name: &str,
+        timeout: Option<Duration>,
+    ) -> Result<ToolSessionId> {
+        let session_id = ToolSessionId::new(UuidId::new(Uuid::new_v4()).to_string())?;
+        self.start_session(name, session_id.clone(), timeout, None).await?;
+        Ok(session_id)
+    }
+
+    /// Reopen a session from its last checkpoint in the session store, under
+    /// the same id, e.g. after the agent restarted mid-session.
+    ///
+    /// Fails with `SessionStateViolation` when there is no store, no
+    /// checkpoint for the session, or the session is already open here.
+    pub async fn resume_session(&mut self, session_id: &ToolSessionId) -> Result<()> {
+        if self.sessions.contains_key(session_id) {
+            return Err(BamlRtError::SessionStateViolation(format!(
+                "Tool session {} is already open",
+                session_id
+            )));
+        }
+        let store = self.session_store.clone().ok_or_else(|| {
+            BamlRtError::SessionStateViolation("No session store to resume from".to_string())
+        })?;
+        let checkpoint = store.load(session_id).await?.ok_or_else(|| {
+            BamlRtError::SessionStateViolation(format!("No checkpoint for tool session {}", session_id))
+        })?;
+        let timeout = checkpoint.timeout_ms.map(Duration::from_millis);
+        self.start_session(&checkpoint.tool.to_string(), session_id.clone(), timeout, Some(checkpoint.state))
+            .await?;
+        tracing::info!(session_id = %session_id, tool = %checkpoint.tool, "Resumed tool session from checkpoint");
+        Ok(())
+    }
+
+    /// Checkpoints left by sessions that never finished, oldest first.
+    pub async fn checkpointed_sessions(&self) -> Result<Vec<SessionCheckpoint>> {
+        match &self.session_store {
+            Some(store) => store.list().await,
+            None => Ok(Vec::new()),
+        }
+    }
+
+    async fn start_session(
+        &mut self,
+        name: &str,
+        session_id: ToolSessionId,
+        timeout: Option<Duration>,
+        restore: Option<Value>,
+    ) -> Result<()> {
         let parsed = ToolName::parse(name)?;
         let (metadata, handler) = self.tools.get(&parsed)
             .ok_or_else(|| BamlRtError::FunctionNotFound(format!("Tool '{}' not found", parsed)))?;
         self.ensure_allowed(&parsed, metadata.is_host_tool)?;
 
-        let session_id = ToolSessionId::new(UuidId::new(Uuid::new_v4()).to_string())?;
+        let cancellation = CancellationToken::new();
         let ctx = ToolSessionContext {
             session_id: session_id.clone(),
             tool_name: metadata.name.clone(),
+            cancellation: cancellation.clone(),
         };
-        let session = handler.open_session(ctx).await?;
-        self.sessions.insert(session_id.clone(), Arc::new(Mutex::new(session)));
-        Ok(session_id)
+        let timeout = timeout.or(metadata.timeout).or(self.default_timeout);
+        let mut session = handler.open_session(ctx).await?;
+        if let Some(state) = restore {
+            session.restore(state).await.map_err(map_session_error)?;
+        }
+        #[cfg(feature = "profiling")]
+        let profile = crate::profiling::ToolProfiler::global()
+            .and_then(|profiler| profiler.start(&parsed, &session_id));
+        self.sessions.insert(
+            session_id.clone(),
+            OpenSession {
+                tool: parsed,
+                session: Arc::new(Mutex::new(session)),
+                cancellation,
+                timeout,
+                #[cfg(feature = "profiling")]
+                profile,
+            },
+        );
+        Ok(())
+    }
+
+    fn open(&self, session_id: &ToolSessionId) -> Result<&OpenSession> {
+        self.sessions.get(session_id)
+            .ok_or_else(|| BamlRtError::SessionStateViolation(format!("Unknown session {}", session_id)))
+    }
+
+    /// Token that cancels the session when fired
+    ///
+    /// Hold on to it to abort a session from outside without taking the registry lock.
+    pub fn cancellation_token(&self, session_id: &ToolSessionId) -> Option<CancellationToken> {
+        self.sessions.get(session_id).map(|open| open.cancellation.clone())
+    }
+
+    /// Cancel a session; a pending or later `session_next` reports `ToolFailureKind::Cancelled`.
+    pub fn cancel_session(&self, session_id: &ToolSessionId) -> Result<()> {
+        self.open(session_id)?.cancellation.cancel();
+        Ok(())
     }
 
+    /// Send input to a session; input that violates the tool's input schema is
+    /// rejected with `ToolFailureKind::SchemaViolation` without reaching the tool.
     pub async fn session_send(&self, session_id: &ToolSessionId, input: Value) -> Result<()> {
-        let session = self.sessions.get(session_id)
-            .ok_or_else(|| BamlRtError::InvalidArgument(format!("Unknown session {}", session_id)))?;
-        let mut guard = session.lock().await;
+        let open = self.open(session_id)?;
+        self.check_input(&open.tool, &input).map_err(ToolFailure::into_error)?;
+        Self::send(open, input).await?;
+        self.checkpoint(session_id, open).await;
+        Ok(())
+    }
+
+    /// Save the session's snapshot, if it has one and there is a store. A
+    /// failed checkpoint is logged; the session carries on without it.
+    async fn checkpoint(&self, session_id: &ToolSessionId, open: &OpenSession) {
+        let Some(store) = &self.session_store else {
+            return;
+        };
+        let state = match open.session.lock().await.snapshot().await {
+            Ok(Some(state)) => state,
+            Ok(None) => return,
+            Err(err) => {
+                tracing::warn!(session_id = %session_id, error = %map_session_error(err), "Tool session snapshot failed");
+                return;
+            }
+        };
+        let checkpoint = SessionCheckpoint {
+            session_id: session_id.as_str().to_string(),
+            tool: open.tool.clone(),
+            state,
+            timeout_ms: open.timeout.map(|timeout| timeout.as_millis() as u64),
+            saved_at_ms: now_ms(),
+        };
+        if let Err(err) = store.save(checkpoint).await {
+            tracing::warn!(session_id = %session_id, error = %err, "Failed to checkpoint tool session");
+        }
+    }
+
+    async fn forget_checkpoint(&self, session_id: &ToolSessionId) {
+        if let Some(store) = &self.session_store
+            && let Err(err) = store.remove(session_id).await
+        {
+            tracing::warn!(session_id = %session_id, error = %err, "Failed to remove tool session checkpoint");
+        }
+    }
+
+    async fn send(open: &OpenSession, input: Value) -> Result<()> {
+        let mut guard = open.session.lock().await;
         guard.send(input).await.map_err(map_session_error)
     }
 
+    fn check_input(&self, tool: &ToolName, input: &Value) -> std::result::Result<(), ToolFailure> {
+        match self.schemas.get(tool) {
+            Some(schemas) if self.schema_validation => {
+                schemas.check_input(&tool.to_string(), input)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Replace a step whose output violates the tool's output schema with a
+    /// `ToolFailureKind::SchemaViolation` error.
+    fn check_output(&self, tool: &ToolName, step: ToolStep) -> ToolStep {
+        let Some(schemas) = self.schemas.get(tool).filter(|_| self.schema_validation) else {
+            return step;
+        };
+        let output = match &step {
+            ToolStep::Streaming { output } | ToolStep::Done { output: Some(output) } => output,
+            _ => return step,
+        };
+        match schemas.check_output(&tool.to_string(), output) {
+            Ok(()) => step,
+            Err(error) => ToolStep::Error { error },
+        }
+    }
+
+    /// Advance a session by one step.
+    ///
+    /// A step that outlives the session timeout, or a session cancelled through its
+    /// token, yields `ToolStep::Error` with `ToolFailureKind::Timeout` or
+    /// `ToolFailureKind::Cancelled`. The in-flight step is dropped and the token fired.
+    /// Output that violates the tool's output schema yields `ToolFailureKind::SchemaViolation`.
     pub async fn session_next(&self, session_id: &ToolSessionId) -> Result<ToolStep> {
-        let session = self.sessions.get(session_id)
-            .ok_or_else(|| BamlRtError::InvalidArgument(format!("Unknown session {}", session_id)))?;
-        let mut guard = session.lock().await;
-        guard.next().await.map_err(map_session_error)
+        let open = self.open(session_id)?;
+        let cancellation = open.cancellation.clone();
+        if cancellation.is_cancelled() {
+            return Ok(ToolStep::Error {
+                error: ToolFailure::cancelled(format!("Tool session {} was cancelled", session_id)),
+            });
+        }
+        let mut guard = open.session.lock().await;
+        let step = guard.next();
+        let deadline = async {
+            match open.timeout {
+                Some(timeout) => tokio::time::sleep(timeout).await,
+                None => std::future::pending::<()>().await,
+            }
+        };
+        let step = tokio::select! {
+            step = step => step.map_err(map_session_error)?,
+            _ = cancellation.cancelled() => ToolStep::Error {
+                error: ToolFailure::cancelled(format!("Tool session {} was cancelled", session_id)),
+            },
+            _ = deadline => {
+                cancellation.cancel();
+                let timeout = open.timeout.unwrap_or_default();
+                tracing::warn!(
+                    session_id = %session_id,
+                    timeout_ms = timeout.as_millis() as u64,
+                    "Tool session step timed out"
+                );
+                ToolStep::Error {
+                    error: ToolFailure::timeout(format!(
+                        "Tool session {} timed out after {}ms",
+                        session_id,
+                        timeout.as_millis()
+                    )),
+                }
+            }
+        };
+        drop(guard);
+        let step = self.check_output(&open.tool, step);
+        if matches!(step, ToolStep::Streaming { .. }) {
+            self.checkpoint(session_id, open).await;
+        }
+        Ok(step)
     }
 
     pub async fn session_finish(&mut self, session_id: &ToolSessionId) -> Result<()> {
-        if let Some(session) = self.sessions.remove(session_id) {
-            let mut guard = session.lock().await;
-            guard.finish().await.map_err(map_session_error)?;
+        if let Some(mut open) = self.sessions.remove(session_id) {
+            let finished = open.session.lock().await.finish().await;
+            open.write_profile();
+            self.forget_checkpoint(session_id).await;
+            finished.map_err(map_session_error)?;
         }
         Ok(())
     }
 
     pub async fn session_abort(&mut self, session_id: &ToolSessionId, reason: Option<String>) -> Result<()> {
-        if let Some(session) = self.sessions.remove(session_id) {
-            let mut guard = session.lock().await;
-            guard.abort(reason).await.map_err(map_session_error)?;
+        if let Some(mut open) = self.sessions.remove(session_id) {
+            open.cancellation.cancel();
+            let aborted = open.session.lock().await.abort(reason).await;
+            open.write_profile();
+            self.forget_checkpoint(session_id).await;
+            aborted.map_err(map_session_error)?;
         }
         Ok(())
     }
 
     /// Execute a tool function by name (single-shot convenience).
     pub async fn execute(&mut self, name: &str, args: Value) -> Result<Value> {
+        self.execute_with_timeout(name, args, None).await
+    }
+
+    /// Execute a tool function, failing with `ToolFailureKind::Timeout` if it runs
+    /// longer than `timeout` (or the tool / registry default when `None`).
+    ///
+    /// Failed attempts are retried according to the tool's retry policy.
+    pub async fn execute_with_timeout(
+        &mut self,
+        name: &str,
+        args: Value,
+        timeout: Option<Duration>,
+    ) -> Result<Value> {
+        let policy = self.retry_policy(name).unwrap_or_else(RetryPolicy::never);
+        let mut attempt = 1;
+        loop {
+            let started = std::time::Instant::now();
+            let failure = match self.try_execute(name, args.clone(), timeout).await? {
+                Ok(output) => return Ok(output),
+                Err(failure) => failure,
+            };
+            if !policy.should_retry(&failure, attempt) {
+                return Err(failure.into_error());
+            }
+            let retry = RetryAttempt {
+                attempt,
+                max_attempts: policy.max_attempts(),
+                failure,
+                duration: started.elapsed(),
+                backoff: policy.backoff(attempt),
+            };
+            tracing::warn!(
+                tool = name,
+                attempt = retry.attempt,
+                max_attempts = retry.max_attempts,
+                failure_kind = retry.failure.kind.as_str(),
+                backoff_ms = retry.backoff.as_millis() as u64,
+                "Retrying tool call"
+            );
+            tokio::time::sleep(retry.backoff).await;
+            attempt += 1;
+        }
+    }
+
+    /// Run a one-shot tool call once, without retries
+    ///
+    /// Returns `Ok(Err(failure))` when the tool step fails, so callers can decide
+    /// whether to retry; lookup, allowlist and transport errors are returned as `Err`.
+    pub async fn try_execute(
+        &mut self,
+        name: &str,
+        args: Value,
+        timeout: Option<Duration>,
+    ) -> Result<std::result::Result<Value, ToolFailure>> {
         tracing::debug!(
             tool = name,
             args = ?args,
@@ -1005,27 +1443,31 @@ impl ToolRegistry {
         let (_, handler) = self.tools.get(&parsed)
             .ok_or_else(|| BamlRtError::FunctionNotFound(format!("Tool '{}' not found", parsed)))?;
         if handler.capability() != ToolCapability::OneShot {
-            return Err(BamlRtError::InvalidArgument(format!(
+            return Err(BamlRtError::SessionStateViolation(format!(
                 "Tool '{}' requires a streaming session; use open_session",
                 parsed
             )));
         }
 
-        let session_id = self.open_session(&parsed.to_string()).await?;
-        self.session_send(&session_id, args).await?;
+        if let Err(failure) = self.check_input(&parsed, &args) {
+            return Ok(Err(failure));
+        }
+
+        let session_id = self.open_session_with_timeout(&parsed.to_string(), timeout).await?;
+        Self::send(self.open(&session_id)?, args).await?;
         loop {
             match self.session_next(&session_id).await? {
                 ToolStep::Streaming { output } => {
                     self.session_finish(&session_id).await?;
-                    return Ok(output);
+                    return Ok(Ok(output));
                 }
                 ToolStep::Done { output } => {
                     self.session_finish(&session_id).await?;
-                    return Ok(output.unwrap_or(Value::Null));
+                    return Ok(Ok(output.unwrap_or(Value::Null)));
                 }
                 ToolStep::Error { error } => {
                     self.session_abort(&session_id, Some(error.message.clone())).await?;
-                    return Err(map_session_error(ToolSessionError::Tool(error)));
+                    return Ok(Err(error));
                 }
             }
         }
@@ -1035,7 +1477,7 @@ impl ToolRegistry {
         if is_host_tool {
             if let Some(allowlist) = &self.allowlist {
                 if !allowlist.contains(name) {
-                    return Err(BamlRtError::InvalidArgument(format!(
+                    return Err(BamlRtError::ToolNotAllowed(format!(
                         "Tool '{}' is not declared in the manifest allowlist",
                         name
                     )));
@@ -1094,6 +1536,7 @@ where
             secret_requirements: Vec::new(),
             // ALL Rust tools are host tools - they must be declared in manifest.json
             is_host_tool: true,
+            timeout: None,
         };
         Self {
             metadata,
@@ -1121,7 +1564,7 @@ where
                 Ok(value) => value,
                 Err(err) => {
                     return Box::pin(async move {
-                        Err(BamlRtError::InvalidArgument(format!(
+                        Err(BamlRtError::SchemaMismatch(format!(
                             "Invalid input: {}",
                             err
                         )))
@@ -1138,7 +1581,7 @@ where
     }
 }
 
-struct OneShotSession<F>
+pub(crate) struct OneShotSession<F>
 where
     F: Fn(Value) -> Pin<Box<dyn Future<Output = Result<Value>> + Send>> + Send + Sync + 'static,
 {
@@ -1152,7 +1595,7 @@ impl<F> OneShotSession<F>
 where
     F: Fn(Value) -> Pin<Box<dyn Future<Output = Result<Value>> + Send>> + Send + Sync + 'static,
 {
-    fn new(ctx: ToolSessionContext, handler: F) -> Self {
+    pub(crate) fn new(ctx: ToolSessionContext, handler: F) -> Self {
         Self {
             ctx,
             handler: Arc::new(handler),
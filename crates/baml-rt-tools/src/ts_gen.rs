@@ -17,6 +17,8 @@ pub fn render_tool_typescript(tools: &[ToolFunctionMetadata]) -> Result<String>
             | "NotAuthorized"
             | "RateLimited"
             | "Cancelled"
+            | "Timeout"
+            | "SchemaViolation"
             | "Unknown";
 
         export interface ToolFailure {
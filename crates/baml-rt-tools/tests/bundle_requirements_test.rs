@@ -0,0 +1,51 @@
+use baml_rt_core::manifest::BundleRequirement;
+use baml_rt_tools::tools::{BundleName, ToolBundle, ToolBundleMetadata, ToolHandler, ToolRegistry};
+use std::sync::Arc;
+
+struct VersionedBundle(&'static str, &'static str);
+
+impl ToolBundle for VersionedBundle {
+    fn metadata(&self) -> ToolBundleMetadata {
+        ToolBundleMetadata {
+            name: BundleName::new(self.0).expect("bundle name"),
+            description: "test bundle".to_string(),
+            config_schema: None,
+            secret_requirements: Vec::new(),
+            version: self.1.to_string(),
+        }
+    }
+
+    fn functions(&self) -> Vec<Arc<dyn ToolHandler>> {
+        Vec::new()
+    }
+}
+
+fn requirements(raw: &[&str]) -> Vec<BundleRequirement> {
+    raw.iter().map(|raw| raw.parse().expect("valid requirement")).collect()
+}
+
+#[test]
+fn satisfied_bundle_requirements_pass() {
+    let mut registry = ToolRegistry::new();
+    registry.register_bundle(VersionedBundle("support", "1.3.0")).expect("register");
+
+    registry
+        .check_bundle_requirements(&requirements(&["support", "support>=1.2", "support ^1.3, <2"]))
+        .expect("requirements satisfied");
+}
+
+#[test]
+fn unmet_bundle_requirements_are_all_reported() {
+    let mut registry = ToolRegistry::new();
+    registry.register_bundle(VersionedBundle("support", "1.1.4")).expect("register");
+
+    let err = registry
+        .check_bundle_requirements(&requirements(&["support>=1.2", "weather"]))
+        .expect_err("requirements unmet");
+    let report = err.to_string();
+    assert!(
+        report.contains("support>=1.2: registered version 1.1.4 does not satisfy the constraint"),
+        "{report}"
+    );
+    assert!(report.contains("weather: bundle is not registered"), "{report}");
+}
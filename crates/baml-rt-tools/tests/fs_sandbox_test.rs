@@ -0,0 +1,90 @@
+//! Tests for the per-agent filesystem sandbox and the `fs` bundle
+
+use baml_rt_tools::{FsBundle, FsSandbox, ToolBundle, ToolRegistry};
+use serde_json::json;
+
+fn sandbox(dir: &tempfile::TempDir) -> FsSandbox {
+    FsSandbox::for_extract_dir(dir.path()).expect("sandbox")
+}
+
+#[tokio::test]
+async fn files_round_trip_through_the_registered_tools() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut registry = ToolRegistry::new();
+    registry.register_bundle(FsBundle::new(sandbox(&dir))).expect("register");
+
+    let written = registry
+        .execute("fs/write", json!({ "path": "notes/today.txt", "content": "hello" }))
+        .await
+        .unwrap();
+    assert_eq!(written, json!({ "path": "notes/today.txt", "bytes": 5 }));
+    registry
+        .execute("fs/write", json!({ "path": "notes/today.txt", "content": " world", "append": true }))
+        .await
+        .unwrap();
+
+    let read = registry.execute("fs/read", json!({ "path": "./notes/today.txt" })).await.unwrap();
+    assert_eq!(read["content"], json!("hello world"));
+
+    let listed = registry.execute("fs/list", json!({})).await.unwrap();
+    assert_eq!(listed["entries"], json!([{ "name": "notes", "kind": "dir" }]));
+    let listed = registry.execute("fs/list", json!({ "path": "notes" })).await.unwrap();
+    assert_eq!(listed["entries"], json!([{ "name": "today.txt", "kind": "file", "bytes": 11 }]));
+
+    // Files land under the sandbox root, not the extract dir itself.
+    assert!(dir.path().join("workdir/notes/today.txt").is_file());
+}
+
+#[tokio::test]
+async fn paths_outside_the_root_are_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("manifest.json"), "{}").unwrap();
+    let sandbox = sandbox(&dir);
+
+    for path in ["../manifest.json", "/etc/passwd", "notes/../../manifest.json"] {
+        let err = sandbox.read(path).await.expect_err(path);
+        assert!(err.to_string().contains(path), "{err}");
+    }
+    assert!(sandbox.write("a/../b.txt", "ok", false).await.is_ok());
+}
+
+#[test]
+fn a_removed_root_fails_instead_of_looping() {
+    let dir = tempfile::tempdir().unwrap();
+    let sandbox = sandbox(&dir);
+    std::fs::remove_dir_all(sandbox.root()).unwrap();
+
+    let err = sandbox.resolve("notes/today.txt").expect_err("root is gone");
+    assert!(err.to_string().contains("no longer exists"), "{err}");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn symlinks_leading_out_of_the_root_are_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let outside = tempfile::tempdir().unwrap();
+    let sandbox = sandbox(&dir);
+    std::os::unix::fs::symlink(outside.path(), sandbox.root().join("escape")).unwrap();
+
+    assert!(sandbox.write("escape/loot.txt", "x", false).await.is_err());
+    assert!(!outside.path().join("loot.txt").exists());
+
+    // Listed as the link itself, not the directory it points to.
+    let listed = serde_json::to_value(sandbox.list(".").await.unwrap()).unwrap();
+    assert_eq!(listed, json!([{ "name": "escape", "kind": "other" }]));
+}
+
+#[tokio::test]
+async fn size_limit_and_declared_tools_are_enforced() {
+    let dir = tempfile::tempdir().unwrap();
+    let sandbox = sandbox(&dir).with_max_file_bytes(4);
+    assert!(sandbox.write("big.txt", "12345", false).await.is_err());
+    sandbox.write("small.txt", "123", false).await.unwrap();
+    assert!(sandbox.write("small.txt", "45", true).await.is_err());
+
+    let declared = FsBundle::declared_in(sandbox.clone(), &["fs/read".to_string(), "support/calculate".to_string()])
+        .expect("fs/read is declared");
+    let names: Vec<String> = declared.functions().iter().map(|tool| tool.metadata().name.to_string()).collect();
+    assert_eq!(names, vec!["fs/read"]);
+    assert!(FsBundle::declared_in(sandbox, &["support/calculate".to_string()]).is_none());
+}
@@ -0,0 +1,116 @@
+//! Tests for McpToolBundle against an in-process MCP server
+
+use baml_rt_tools::{McpToolBundle, ToolBundle, ToolRegistry};
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, DuplexStream};
+
+/// Answers the handshake, lists two tools over two pages and serves calls.
+async fn fake_server(stream: DuplexStream) {
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let request: Value = serde_json::from_str(&line).expect("client sends JSON");
+        let Some(id) = request.get("id").cloned() else {
+            continue;
+        };
+        let params = request.get("params").cloned().unwrap_or_default();
+        let result = match request["method"].as_str().unwrap() {
+            "initialize" => json!({
+                "protocolVersion": "2025-06-18",
+                "capabilities": { "tools": {} },
+                "serverInfo": { "name": "fake", "version": "2.1.0" }
+            }),
+            "tools/list" if params.get("cursor").is_none() => json!({
+                "tools": [{
+                    "name": "echo",
+                    "description": "Echoes its text",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": { "text": { "type": "string" } },
+                        "required": ["text"]
+                    }
+                }],
+                "nextCursor": "page-2"
+            }),
+            "tools/list" => json!({
+                "tools": [{
+                    "name": "add",
+                    "description": "Adds two numbers",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": { "a": { "type": "number" }, "b": { "type": "number" } },
+                        "required": ["a", "b"]
+                    },
+                    "outputSchema": {
+                        "type": "object",
+                        "properties": { "sum": { "type": "number" } },
+                        "required": ["sum"]
+                    }
+                }]
+            }),
+            "tools/call" => {
+                let args = &params["arguments"];
+                match params["name"].as_str().unwrap() {
+                    "echo" if args["text"] == "fail" => json!({
+                        "content": [{ "type": "text", "text": "echo refused" }],
+                        "isError": true
+                    }),
+                    "echo" => json!({ "content": [{ "type": "text", "text": args["text"] }] }),
+                    _ => {
+                        let sum = args["a"].as_f64().unwrap() + args["b"].as_f64().unwrap();
+                        json!({
+                            "content": [{ "type": "text", "text": sum.to_string() }],
+                            "structuredContent": { "sum": sum }
+                        })
+                    }
+                }
+            }
+            other => panic!("unexpected method {other}"),
+        };
+        let mut response = serde_json::to_vec(&json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+            .unwrap();
+        response.push(b'\n');
+        writer.write_all(&response).await.unwrap();
+    }
+}
+
+async fn connect() -> McpToolBundle {
+    let (client, server) = tokio::io::duplex(64 * 1024);
+    tokio::spawn(fake_server(server));
+    let (reader, writer) = tokio::io::split(client);
+    McpToolBundle::connect_streams("fake", reader, writer).await.expect("connect")
+}
+
+#[tokio::test]
+async fn discovers_tools_across_pages() {
+    let bundle = connect().await;
+    let metadata = bundle.metadata();
+    assert_eq!(metadata.name.as_str(), "fake");
+    assert_eq!(metadata.version, "2.1.0");
+
+    let mut names: Vec<String> = bundle
+        .functions()
+        .iter()
+        .map(|handler| handler.metadata().name.to_string())
+        .collect();
+    names.sort();
+    assert_eq!(names, vec!["fake/add", "fake/echo"]);
+}
+
+#[tokio::test]
+async fn registered_tools_forward_calls_to_the_server() {
+    let mut registry = ToolRegistry::new();
+    registry.register_bundle(connect().await).expect("register");
+
+    let echoed = registry.execute("fake/echo", json!({ "text": "hi" })).await.unwrap();
+    assert_eq!(echoed, json!({ "content": [{ "type": "text", "text": "hi" }] }));
+
+    let sum = registry.execute("fake/add", json!({ "a": 2, "b": 3 })).await.unwrap();
+    assert_eq!(sum, json!({ "sum": 5.0 }));
+
+    let err = registry
+        .execute("fake/echo", json!({ "text": "fail" }))
+        .await
+        .expect_err("isError results fail the call");
+    assert!(err.to_string().contains("echo refused"), "{err}");
+}
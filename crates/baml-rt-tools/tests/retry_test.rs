@@ -0,0 +1,103 @@
+//! Retrying failed tool attempts with backoff.
+
+use baml_rt_core::{BamlRtError, Result};
+use baml_rt_tools::support::{CalculatorInput, CalculatorOutput};
+use baml_rt_tools::tools::TypedToolFunction;
+use baml_rt_tools::{RetryPolicy, ToolFailure, ToolFailureKind, ToolHandler, ToolRegistry};
+use serde_json::json;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+type Handler =
+    fn(CalculatorInput) -> Pin<Box<dyn Future<Output = Result<CalculatorOutput>> + Send>>;
+
+static SLOW_THEN_FAST_CALLS: AtomicU32 = AtomicU32::new(0);
+static INVALID_CALLS: AtomicU32 = AtomicU32::new(0);
+
+fn output() -> CalculatorOutput {
+    CalculatorOutput { expression: "1 + 2".to_string(), result: 3.0, formatted: "3".to_string() }
+}
+
+/// Hangs on the first call so it times out, then answers immediately.
+fn slow_then_fast(
+    _input: CalculatorInput,
+) -> Pin<Box<dyn Future<Output = Result<CalculatorOutput>> + Send>> {
+    Box::pin(async move {
+        if SLOW_THEN_FAST_CALLS.fetch_add(1, Ordering::SeqCst) == 0 {
+            tokio::time::sleep(Duration::from_secs(30)).await;
+        }
+        Ok(output())
+    })
+}
+
+fn always_invalid(
+    _input: CalculatorInput,
+) -> Pin<Box<dyn Future<Output = Result<CalculatorOutput>> + Send>> {
+    Box::pin(async move {
+        INVALID_CALLS.fetch_add(1, Ordering::SeqCst);
+        Err(BamlRtError::InvalidArgument("bad expression".to_string()))
+    })
+}
+
+fn registry_with(name: &str, handler: Handler, timeout: Option<Duration>) -> ToolRegistry {
+    let tool: TypedToolFunction<CalculatorInput, CalculatorOutput, Handler> =
+        TypedToolFunction::new(name, "Flaky calculator", handler);
+    let mut metadata = tool.metadata().clone();
+    metadata.timeout = timeout;
+    let mut registry = ToolRegistry::new();
+    registry.register_dynamic(metadata, Arc::new(tool)).expect("register tool");
+    registry
+}
+
+fn args() -> serde_json::Value {
+    json!({"expression": {"left": 1, "operation": "Add", "right": 2}})
+}
+
+#[tokio::test]
+async fn timed_out_attempt_is_retried() {
+    let mut registry =
+        registry_with("support/flaky", slow_then_fast as Handler, Some(Duration::from_millis(20)));
+    registry
+        .set_retry_policy(
+            "support/flaky",
+            RetryPolicy::new(3).with_backoff(Duration::from_millis(1), Duration::from_millis(1)),
+        )
+        .expect("set policy");
+
+    let result = registry.execute("support/flaky", args()).await.expect("retried call");
+    assert_eq!(result["result"], json!(3.0));
+    assert_eq!(SLOW_THEN_FAST_CALLS.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn non_retryable_failure_runs_once() {
+    let mut registry = registry_with("support/invalid", always_invalid as Handler, None);
+    registry.set_default_retry_policy(Some(
+        RetryPolicy::new(5).with_backoff(Duration::from_millis(1), Duration::from_millis(1)),
+    ));
+
+    let err = registry.execute("support/invalid", args()).await.expect_err("invalid input");
+    assert!(err.to_string().contains("InvalidInput"), "{err}");
+    assert_eq!(INVALID_CALLS.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn backoff_grows_exponentially_up_to_the_cap() {
+    let policy = RetryPolicy::new(10)
+        .with_backoff(Duration::from_millis(100), Duration::from_millis(500))
+        .with_multiplier(2.0);
+    let backoffs: Vec<_> = (1..=5).map(|attempt| policy.backoff(attempt).as_millis()).collect();
+    assert_eq!(backoffs, vec![100, 200, 400, 500, 500]);
+}
+
+#[test]
+fn policy_stops_at_max_attempts_and_honours_kinds() {
+    let policy = RetryPolicy::new(2).retry_on([ToolFailureKind::ExecutionFailed]);
+    let failed = ToolFailure::execution_failed("boom");
+    assert!(policy.should_retry(&failed, 1));
+    assert!(!policy.should_retry(&failed, 2));
+    assert!(!policy.should_retry(&ToolFailure::timeout("slow"), 1));
+}
@@ -0,0 +1,98 @@
+//! Tool input and output checked against their JSON Schemas.
+
+use baml_rt_core::Result;
+use baml_rt_tools::support::{CalculatorInput, CalculatorOutput};
+use baml_rt_tools::tools::TypedToolFunction;
+use baml_rt_tools::{ToolFailureKind, ToolHandler, ToolRegistry, ToolStep};
+use serde_json::{Value, json};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+type Handler =
+    fn(CalculatorInput) -> Pin<Box<dyn Future<Output = Result<CalculatorOutput>> + Send>>;
+
+static CALLS: AtomicU32 = AtomicU32::new(0);
+
+fn calculate(
+    _input: CalculatorInput,
+) -> Pin<Box<dyn Future<Output = Result<CalculatorOutput>> + Send>> {
+    Box::pin(async move {
+        CALLS.fetch_add(1, Ordering::SeqCst);
+        Ok(CalculatorOutput {
+            expression: "1 + 2".to_string(),
+            result: 3.0,
+            formatted: "3".to_string(),
+        })
+    })
+}
+
+fn registry_with(name: &str, output_schema: Option<Value>) -> ToolRegistry {
+    let tool: TypedToolFunction<CalculatorInput, CalculatorOutput, Handler> =
+        TypedToolFunction::new(name, "Calculator", calculate as Handler);
+    let mut metadata = tool.metadata().clone();
+    if let Some(schema) = output_schema {
+        metadata.output_schema = schema;
+    }
+    let mut registry = ToolRegistry::new();
+    registry.register_dynamic(metadata, Arc::new(tool)).expect("register tool");
+    registry
+}
+
+fn args() -> Value {
+    json!({"expression": {"left": 1, "operation": "Add", "right": 2}})
+}
+
+#[tokio::test]
+async fn input_that_violates_the_schema_never_reaches_the_tool() {
+    let mut registry = registry_with("support/checked_input", None);
+    let before = CALLS.load(Ordering::SeqCst);
+
+    let failure = registry
+        .try_execute("support/checked_input", json!({"expression": "1 + 2"}), None)
+        .await
+        .expect("lookup")
+        .expect_err("schema violation");
+    assert_eq!(failure.kind, ToolFailureKind::SchemaViolation);
+    assert!(failure.message.contains("/expression"), "{}", failure.message);
+    assert!(!failure.retryable);
+    assert_eq!(CALLS.load(Ordering::SeqCst), before);
+
+    let session_id = registry.open_session("support/checked_input").await.expect("open");
+    let err = registry
+        .session_send(&session_id, json!({"expression": 3}))
+        .await
+        .expect_err("schema violation");
+    assert!(err.to_string().contains("SchemaViolation"), "{err}");
+}
+
+#[tokio::test]
+async fn output_that_violates_the_schema_fails_the_step() {
+    let schema = json!({"type": "object", "required": ["answer"]});
+    let mut registry = registry_with("support/checked_output", Some(schema));
+
+    let session_id = registry.open_session("support/checked_output").await.expect("open");
+    registry.session_send(&session_id, args()).await.expect("send");
+    match registry.session_next(&session_id).await.expect("step") {
+        ToolStep::Error { error } => {
+            assert_eq!(error.kind, ToolFailureKind::SchemaViolation);
+            assert!(error.message.contains("answer"), "{}", error.message);
+        }
+        _ => panic!("expected a schema violation"),
+    }
+
+    let err = registry.execute("support/checked_output", args()).await.expect_err("violation");
+    assert!(err.to_string().contains("SchemaViolation"), "{err}");
+}
+
+#[tokio::test]
+async fn validation_can_be_turned_off() {
+    let schema = json!({"type": "object", "required": ["answer"]});
+    let mut registry = registry_with("support/unchecked", Some(schema));
+    registry.set_schema_validation(false);
+    assert!(!registry.schema_validation());
+
+    let output = registry.execute("support/unchecked", args()).await.expect("unchecked call");
+    assert_eq!(output["result"], json!(3.0));
+}
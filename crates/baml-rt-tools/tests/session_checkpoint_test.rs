@@ -0,0 +1,144 @@
+//! Checkpointing streaming tool sessions and resuming them in a new registry.
+
+use async_trait::async_trait;
+use baml_rt_core::{BamlRtError, Result};
+use baml_rt_tools::support::{CalculatorInput, CalculatorOutput};
+use baml_rt_tools::tool_fsm::ToolSessionError;
+use baml_rt_tools::tools::{ToolFunctionMetadata, ToolSessionContext, TypedToolFunction};
+use baml_rt_tools::{
+    FileSessionStore, InMemorySessionStore, SessionStore, ToolCapability, ToolHandler,
+    ToolRegistry, ToolSession, ToolStep,
+};
+use serde_json::{json, Value};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// Streams a running total of the numbers sent to it.
+struct Summer {
+    metadata: ToolFunctionMetadata,
+}
+
+struct SummerSession {
+    total: i64,
+    pending: Vec<i64>,
+}
+
+#[async_trait]
+impl ToolSession for SummerSession {
+    async fn send(&mut self, input: Value) -> std::result::Result<(), ToolSessionError> {
+        self.pending.push(input["add"].as_i64().unwrap_or_default());
+        Ok(())
+    }
+
+    async fn next(&mut self) -> std::result::Result<ToolStep, ToolSessionError> {
+        if self.pending.is_empty() {
+            return Ok(ToolStep::Done { output: Some(json!({"total": self.total})) });
+        }
+        self.total += self.pending.remove(0);
+        Ok(ToolStep::Streaming { output: json!({"total": self.total}) })
+    }
+
+    async fn finish(&mut self) -> std::result::Result<(), ToolSessionError> {
+        Ok(())
+    }
+
+    async fn abort(&mut self, _reason: Option<String>) -> std::result::Result<(), ToolSessionError> {
+        Ok(())
+    }
+
+    async fn snapshot(&self) -> std::result::Result<Option<Value>, ToolSessionError> {
+        Ok(Some(json!({"total": self.total, "pending": self.pending})))
+    }
+
+    async fn restore(&mut self, state: Value) -> std::result::Result<(), ToolSessionError> {
+        self.total = state["total"].as_i64().unwrap_or_default();
+        self.pending = serde_json::from_value(state["pending"].clone()).unwrap_or_default();
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ToolHandler for Summer {
+    fn metadata(&self) -> &ToolFunctionMetadata {
+        &self.metadata
+    }
+
+    fn capability(&self) -> ToolCapability {
+        ToolCapability::Streaming
+    }
+
+    async fn open_session(&self, _ctx: ToolSessionContext) -> Result<Box<dyn ToolSession>> {
+        Ok(Box::new(SummerSession { total: 0, pending: Vec::new() }))
+    }
+}
+
+type Handler =
+    fn(CalculatorInput) -> Pin<Box<dyn Future<Output = Result<CalculatorOutput>> + Send>>;
+
+fn never_called(_: CalculatorInput) -> Pin<Box<dyn Future<Output = Result<CalculatorOutput>> + Send>> {
+    Box::pin(async {
+        Err::<CalculatorOutput, _>(BamlRtError::ToolExecution("only the metadata is used".to_string()))
+    })
+}
+
+fn registry(store: Arc<dyn SessionStore>) -> ToolRegistry {
+    let template: TypedToolFunction<CalculatorInput, CalculatorOutput, Handler> =
+        TypedToolFunction::new("math/sum", "Running total", never_called as Handler);
+    let metadata = template.metadata().clone();
+    let mut registry = ToolRegistry::new();
+    registry.set_schema_validation(false);
+    registry.set_session_store(store);
+    registry
+        .register_dynamic(metadata.clone(), Arc::new(Summer { metadata }))
+        .expect("register tool");
+    registry
+}
+
+fn streamed_total(step: ToolStep) -> i64 {
+    match step {
+        ToolStep::Streaming { output } => output["total"].as_i64().expect("total"),
+        other => panic!("expected a streamed step, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn a_session_resumes_from_its_last_checkpoint() {
+    let dir = tempfile::tempdir().unwrap();
+    let store: Arc<dyn SessionStore> = Arc::new(FileSessionStore::open(dir.path()).unwrap());
+
+    let mut first = registry(store.clone());
+    let session_id = first.open_session("math/sum").await.expect("open");
+    for add in [2, 3, 4] {
+        first.session_send(&session_id, json!({"add": add})).await.expect("send");
+    }
+    assert_eq!(streamed_total(first.session_next(&session_id).await.expect("next")), 2);
+    drop(first);
+
+    let mut second = registry(store.clone());
+    let checkpoints = second.checkpointed_sessions().await.expect("list");
+    assert_eq!(checkpoints.len(), 1);
+    assert_eq!(checkpoints[0].session_id().unwrap(), session_id);
+    assert_eq!(checkpoints[0].tool.to_string(), "math/sum");
+
+    second.resume_session(&session_id).await.expect("resume");
+    assert_eq!(streamed_total(second.session_next(&session_id).await.expect("next")), 5);
+    assert_eq!(streamed_total(second.session_next(&session_id).await.expect("next")), 9);
+
+    second.session_finish(&session_id).await.expect("finish");
+    assert!(store.load(&session_id).await.expect("load").is_none());
+}
+
+#[tokio::test]
+async fn sessions_without_a_checkpoint_cannot_be_resumed() {
+    let store: Arc<dyn SessionStore> = Arc::new(InMemorySessionStore::new());
+    let mut registry = registry(store);
+    let session_id = registry.open_session("math/sum").await.expect("open");
+
+    let err = registry.resume_session(&session_id).await.expect_err("already open");
+    assert!(err.to_string().contains("already open"), "{err}");
+
+    registry.session_abort(&session_id, None).await.expect("abort");
+    let err = registry.resume_session(&session_id).await.expect_err("no checkpoint");
+    assert!(err.to_string().contains("No checkpoint"), "{err}");
+}
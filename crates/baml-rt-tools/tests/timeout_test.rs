@@ -0,0 +1,88 @@
+//! Tool execution timeouts and external cancellation.
+
+use baml_rt_core::Result;
+use baml_rt_tools::support::{CalculatorInput, CalculatorOutput};
+use baml_rt_tools::tools::TypedToolFunction;
+use baml_rt_tools::{ToolFailureKind, ToolHandler, ToolRegistry, ToolStep};
+use serde_json::json;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+type Handler =
+    fn(CalculatorInput) -> Pin<Box<dyn Future<Output = Result<CalculatorOutput>> + Send>>;
+
+fn slow_calculate(
+    input: CalculatorInput,
+) -> Pin<Box<dyn Future<Output = Result<CalculatorOutput>> + Send>> {
+    Box::pin(async move {
+        tokio::time::sleep(Duration::from_secs(30)).await;
+        Ok(CalculatorOutput {
+            expression: format!("{:?}", input.expression),
+            result: 0.0,
+            formatted: String::new(),
+        })
+    })
+}
+
+fn registry_with_slow_tool(timeout: Option<Duration>) -> ToolRegistry {
+    let tool: TypedToolFunction<CalculatorInput, CalculatorOutput, Handler> =
+        TypedToolFunction::new("support/slow", "Never finishes in time", slow_calculate as Handler);
+    let mut metadata = tool.metadata().clone();
+    metadata.timeout = timeout;
+    let mut registry = ToolRegistry::new();
+    registry.register_dynamic(metadata, Arc::new(tool)).expect("register tool");
+    registry
+}
+
+fn args() -> serde_json::Value {
+    json!({"expression": {"left": 1, "operation": "Add", "right": 2}})
+}
+
+#[tokio::test]
+async fn per_tool_timeout_fails_the_call() {
+    let mut registry = registry_with_slow_tool(Some(Duration::from_millis(50)));
+    let err = registry.execute("support/slow", args()).await.expect_err("timeout");
+    assert!(err.to_string().contains("Timeout"), "{err}");
+}
+
+#[tokio::test]
+async fn per_call_timeout_overrides_registry_default() {
+    let mut registry = registry_with_slow_tool(None);
+    registry.set_default_timeout(Some(Duration::from_secs(60)));
+
+    let session_id = registry
+        .open_session_with_timeout("support/slow", Some(Duration::from_millis(10)))
+        .await
+        .expect("open");
+    let token = registry.cancellation_token(&session_id).expect("token");
+    registry.session_send(&session_id, args()).await.expect("send");
+    match registry.session_next(&session_id).await.expect("next") {
+        ToolStep::Error { error } => {
+            assert_eq!(error.kind, ToolFailureKind::Timeout);
+            assert!(error.retryable);
+        }
+        other => panic!("expected a timeout, got {other:?}"),
+    }
+    assert!(token.is_cancelled());
+}
+
+#[tokio::test]
+async fn sessions_can_be_cancelled_externally() {
+    let mut registry = registry_with_slow_tool(None);
+    let session_id = registry.open_session("support/slow").await.expect("open");
+    registry.session_send(&session_id, args()).await.expect("send");
+
+    let token = registry.cancellation_token(&session_id).expect("token");
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        token.cancel();
+    });
+
+    match registry.session_next(&session_id).await.expect("next") {
+        ToolStep::Error { error } => assert_eq!(error.kind, ToolFailureKind::Cancelled),
+        other => panic!("expected cancellation, got {other:?}"),
+    }
+    registry.session_abort(&session_id, None).await.expect("abort");
+}
@@ -0,0 +1,70 @@
+//! Per-tool pprof profiles (feature `profiling`).
+#![cfg(feature = "profiling")]
+
+use baml_rt_core::Result;
+use baml_rt_tools::support::{CalculatorInput, CalculatorOutput};
+use baml_rt_tools::tools::TypedToolFunction;
+use baml_rt_tools::{ToolHandler, ToolProfiler, ToolRegistry};
+use serde_json::json;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+type Handler =
+    fn(CalculatorInput) -> Pin<Box<dyn Future<Output = Result<CalculatorOutput>> + Send>>;
+
+fn busy_calculate(
+    input: CalculatorInput,
+) -> Pin<Box<dyn Future<Output = Result<CalculatorOutput>> + Send>> {
+    Box::pin(async move {
+        let started = Instant::now();
+        let mut total = 0u64;
+        while started.elapsed() < Duration::from_millis(200) {
+            total = std::hint::black_box(total.wrapping_mul(31).wrapping_add(7));
+        }
+        Ok(CalculatorOutput {
+            expression: format!("{:?}", input.expression),
+            result: total as f64,
+            formatted: String::new(),
+        })
+    })
+}
+
+fn register(registry: &mut ToolRegistry, name: &str) {
+    let tool: TypedToolFunction<CalculatorInput, CalculatorOutput, Handler> =
+        TypedToolFunction::new(name, "Burns CPU", busy_calculate as Handler);
+    registry.register_dynamic(tool.metadata().clone(), Arc::new(tool)).expect("register tool");
+}
+
+fn profiles(dir: &std::path::Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn selected_tools_write_a_profile_per_call() {
+    let dir = tempfile::tempdir().unwrap();
+    let profiler = ToolProfiler::new(dir.path())
+        .unwrap()
+        .with_tools(["support/busy"])
+        .unwrap();
+    ToolProfiler::install(profiler).expect("install profiler");
+
+    let mut registry = ToolRegistry::new();
+    register(&mut registry, "support/busy");
+    register(&mut registry, "support/other");
+    let args = json!({"expression": {"left": 1, "operation": "Add", "right": 2}});
+    registry.execute("support/busy", args.clone()).await.expect("busy call");
+    registry.execute("support/other", args).await.expect("other call");
+
+    let written = profiles(dir.path());
+    assert_eq!(written.len(), 1, "{written:?}");
+    assert!(written[0].starts_with("support.busy-") && written[0].ends_with(".pb"), "{written:?}");
+    assert!(std::fs::metadata(dir.path().join(&written[0])).unwrap().len() > 0);
+    assert!(ToolProfiler::install(ToolProfiler::new(dir.path()).unwrap()).is_err());
+}
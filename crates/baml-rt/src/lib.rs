@@ -2,12 +2,18 @@
 //!
 //! This crate re-exports functionality from the workspace sub-crates.
 
-pub use baml_rt_core::{BamlRtError, Result};
+pub use baml_rt_core::{BamlRtError, ResourceLimitKind, Result};
 pub use baml_rt_core::correlation::{current_correlation_id, generate_correlation_id};
 pub use baml_rt_core::context::{current_context_id, generate_context_id};
 pub mod error {
     pub use baml_rt_core::error::*;
 }
+pub mod tokens {
+    pub use baml_rt_core::tokens::*;
+}
+pub mod types {
+    pub use baml_rt_core::types::*;
+}
 
 #[cfg(feature = "tools")]
 pub mod tools {
@@ -41,6 +47,10 @@ pub mod baml_pre_execution {
     pub use baml_rt_quickjs::baml_pre_execution::*;
 }
 #[cfg(feature = "quickjs")]
+pub mod console {
+    pub use baml_rt_quickjs::console::*;
+}
+#[cfg(feature = "quickjs")]
 pub mod quickjs_bridge {
     pub use baml_rt_quickjs::quickjs_bridge::*;
 }
@@ -74,9 +84,25 @@ pub mod a2a_types {
     pub use baml_rt_a2a::a2a_types::*;
 }
 #[cfg(feature = "a2a")]
+pub mod a2a_http {
+    pub use baml_rt_a2a::a2a_http::*;
+}
+#[cfg(feature = "a2a")]
 pub mod a2a_transport {
     pub use baml_rt_a2a::a2a_transport::*;
 }
+#[cfg(feature = "a2a")]
+pub mod lifecycle {
+    pub use baml_rt_a2a::lifecycle::*;
+}
+#[cfg(feature = "a2a")]
+pub mod task_state {
+    pub use baml_rt_a2a::task_state::*;
+}
+#[cfg(feature = "a2a")]
+pub mod task_timeout {
+    pub use baml_rt_a2a::task_timeout::*;
+}
 
 #[cfg(feature = "builder")]
 pub mod builder {
@@ -102,14 +128,19 @@ pub use baml_rt_quickjs::{QuickJSBridge, Runtime, RuntimeBuilder, RuntimeConfig,
 pub use baml_rt_quickjs::{BamlRuntimeManager, BamlContext, ContextMetadata};
 #[cfg(feature = "interceptor")]
 pub use baml_rt_interceptor::{
-    InterceptorRegistry, InterceptorDecision, LLMInterceptor, ToolInterceptor,
-    LLMCallContext, ToolCallContext,
+    InterceptorRegistry, InterceptorDecision, InterceptorStage, LLMInterceptor, ToolInterceptor,
+    LLMCallContext, LLMChunk, ToolCallContext, CaptureDetail, PayloadCapture,
+    TokenEstimator, TokenUsage,
 };
 #[cfg(feature = "interceptor")]
 pub use baml_rt_interceptor::{
-    TracingInterceptor, TracingLLMInterceptor, TracingToolInterceptor,
+    ApprovalInterceptor, ApprovalObserver, BudgetLimit, BudgetObserver, PendingApprovalQueue,
+    PolicyInterceptor, PolicyObserver, RateLimit, RateLimitInterceptor, Reloadable,
+    TracingInterceptor, TracingLLMInterceptor, TracingToolInterceptor, UsageBudget,
 };
 #[cfg(feature = "a2a")]
 pub use baml_rt_a2a::{A2aMethod, A2aOutcome, A2aRequest};
 #[cfg(feature = "a2a")]
-pub use baml_rt_a2a::{A2aAgent, A2aAgentBuilder, A2aRequestHandler};
+pub use baml_rt_a2a::{
+    A2aAgent, A2aAgentBuilder, A2aHttpServer, A2aRequestHandler, A2aWebSocketServer, LifecycleHooks,
+};
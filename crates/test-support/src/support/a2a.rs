@@ -1,6 +1,7 @@
 //! In-memory A2A test client.
 
 use baml_rt::{A2aRequestHandler, Result};
+use baml_rt::a2a::{inject_calling_agent, inject_parent_context, inject_trace_context};
 use baml_rt::tools::BamlTool;
 use baml_rt_tools::bundles::Support;
 use async_trait::async_trait;
@@ -21,7 +22,12 @@ impl A2aInMemoryClient {
         Self { target }
     }
 
-    pub async fn send(&self, request: Value) -> Result<Vec<Value>> {
+    /// Send `request`, carrying the caller's trace context, agent and context
+    /// like a real client would.
+    pub async fn send(&self, mut request: Value) -> Result<Vec<Value>> {
+        inject_trace_context(&mut request);
+        inject_calling_agent(&mut request);
+        inject_parent_context(&mut request);
         self.target.handle_a2a(request).await
     }
 }